@@ -1,9 +1,10 @@
 //! Cryptographic signature keys storage API
 
+use namada_core::types::account::DEFAULT_KEY_WEIGHT;
 use namada_core::types::address::Address;
 use namada_core::types::key::common;
-use namada_core::types::storage;
-use namada_storage::{Result, StorageRead, StorageWrite};
+use namada_core::types::{storage, token};
+use namada_storage::{Error, Result, StorageRead, StorageWrite};
 
 use super::*;
 
@@ -77,8 +78,70 @@ where
     S: StorageRead,
 {
     let public_keys = public_keys(storage, owner)?;
+    let weights = weights(storage, owner)?;
 
-    Ok(AccountPublicKeysMap::from_iter(public_keys))
+    Ok(AccountPublicKeysMap::from_iter(public_keys).with_weights(weights))
+}
+
+/// Get the weights of the public keys associated with an account, in the
+/// order of the public keys. Empty if the keys don't have explicit weights.
+pub fn weights<S>(storage: &S, owner: &Address) -> Result<Vec<u8>>
+where
+    S: StorageRead,
+{
+    weights_handle(owner)
+        .iter(storage)?
+        .map(|data| data.map(|(_index, weight)| weight))
+        .collect()
+}
+
+/// Set the weights of the public keys associated with an account, in the
+/// order of the public keys. Empty weights give every key the default weight.
+pub fn set_weights<S>(
+    storage: &mut S,
+    owner: &Address,
+    weights: &[u8],
+) -> Result<()>
+where
+    S: StorageWrite + StorageRead,
+{
+    let total_weights = weights_handle(owner).len(storage)?;
+    for index in 0..total_weights as u8 {
+        weights_handle(owner).remove(storage, &index)?;
+    }
+    for (index, weight) in weights.iter().enumerate() {
+        weights_handle(owner).insert(storage, index as u8, *weight)?;
+    }
+    Ok(())
+}
+
+/// Check that the given weights can be assigned to the given number of public
+/// keys with the given signature threshold, which must be reachable by the
+/// sum of the weights
+pub fn validate_weights(
+    num_public_keys: usize,
+    weights: &[u8],
+    threshold: u8,
+) -> Result<()> {
+    if !weights.is_empty() && weights.len() != num_public_keys {
+        return Err(Error::new_const(
+            "The number of weights must match the number of public keys",
+        ));
+    }
+    if weights.contains(&0) {
+        return Err(Error::new_const("The weight of a public key cannot be 0"));
+    }
+    let total_weight: u64 = if weights.is_empty() {
+        num_public_keys as u64 * u64::from(DEFAULT_KEY_WEIGHT)
+    } else {
+        weights.iter().copied().map(u64::from).sum()
+    };
+    if u64::from(threshold) > total_weight {
+        return Err(Error::new_const(
+            "The threshold cannot exceed the total weight of the public keys",
+        ));
+    }
+    Ok(())
 }
 
 /// Check if a user account exists in storage
@@ -121,3 +184,133 @@ where
     }
     Ok(())
 }
+
+/// Get the policy associated with an account, if any
+pub fn policy<S>(storage: &S, owner: &Address) -> Result<Option<AccountPolicy>>
+where
+    S: StorageRead,
+{
+    storage.read(&policy_key(owner))
+}
+
+/// Set the policy of an account. An empty policy removes any existing one.
+pub fn set_policy<S>(
+    storage: &mut S,
+    owner: &Address,
+    policy: &AccountPolicy,
+) -> Result<()>
+where
+    S: StorageWrite + StorageRead,
+{
+    let policy_key = policy_key(owner);
+    if policy.is_empty() {
+        storage.delete(&policy_key)
+    } else {
+        storage.write(&policy_key, policy)
+    }
+}
+
+/// The number of seconds in a day of spending limits
+pub const SECONDS_PER_DAY: i64 = 86_400;
+
+/// Get the index of the day of the current block since the UNIX epoch
+pub fn current_day<S>(storage: &S) -> Result<u64>
+where
+    S: StorageRead,
+{
+    let height = storage.get_block_height()?;
+    let header = storage.get_block_header(height)?.ok_or_else(|| {
+        Error::new_const("The header of the current block is not available")
+    })?;
+    Ok((header.time.0.timestamp() / SECONDS_PER_DAY).max(0) as u64)
+}
+
+/// Get the amount of a token debited from an account during the given day
+pub fn spent_on_day<S>(
+    storage: &S,
+    owner: &Address,
+    token: &Address,
+    day: u64,
+) -> Result<token::Amount>
+where
+    S: StorageRead,
+{
+    let spending: Option<DailySpending> =
+        storage.read(&daily_spending_key(owner, token))?;
+    Ok(spending
+        .filter(|spending| spending.day == day)
+        .map(|spending| spending.amount)
+        .unwrap_or_default())
+}
+
+/// Record a debit of a token from an account against the account's daily
+/// spending limit. This is a no-op if the account's policy has no spending
+/// limit for the token.
+pub fn record_spending<S>(
+    storage: &mut S,
+    owner: &Address,
+    token: &Address,
+    amount: token::Amount,
+) -> Result<()>
+where
+    S: StorageWrite + StorageRead,
+{
+    let has_limit = policy(storage, owner)?
+        .map(|policy| policy.spending_limits.contains_key(token))
+        .unwrap_or_default();
+    if !has_limit {
+        return Ok(());
+    }
+    let day = current_day(storage)?;
+    let amount = spent_on_day(storage, owner, token, day)?
+        .checked_add(amount)
+        .ok_or_else(|| Error::new_const("Daily spending overflow"))?;
+    storage.write(
+        &daily_spending_key(owner, token),
+        DailySpending { day, amount },
+    )
+}
+
+/// Get the number of epochs that an update of an account's VP is delayed by
+pub fn vp_update_timelock<S>(storage: &S, owner: &Address) -> Result<u64>
+where
+    S: StorageRead,
+{
+    Ok(storage
+        .read(&vp_update_timelock_key(owner))?
+        .unwrap_or_default())
+}
+
+/// Set the number of epochs that an update of an account's VP is delayed by
+pub fn set_vp_update_timelock<S>(
+    storage: &mut S,
+    owner: &Address,
+    timelock: u64,
+) -> Result<()>
+where
+    S: StorageWrite + StorageRead,
+{
+    storage.write(&vp_update_timelock_key(owner), timelock)
+}
+
+/// Get the pending update of an account's VP, if any
+pub fn pending_vp_update<S>(
+    storage: &S,
+    owner: &Address,
+) -> Result<Option<PendingVpUpdate>>
+where
+    S: StorageRead,
+{
+    storage.read(&pending_vp_key(owner))
+}
+
+/// Cancel the pending update of an account's VP, if any
+pub fn cancel_pending_vp_update<S>(
+    storage: &mut S,
+    owner: &Address,
+) -> Result<()>
+where
+    S: StorageWrite + StorageRead,
+{
+    storage.delete(&pending_vp_key(owner))
+}
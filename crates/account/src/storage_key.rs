@@ -11,6 +11,11 @@ struct Keys {
     public_keys: &'static str,
     threshold: &'static str,
     protocol_public_keys: &'static str,
+    weights: &'static str,
+    policy: &'static str,
+    daily_spending: &'static str,
+    vp_update_timelock: &'static str,
+    pending_vp: &'static str,
 }
 
 /// Obtain a storage key for user's public key.
@@ -46,6 +51,41 @@ pub fn is_pks_key(key: &storage::Key) -> Option<&Address> {
     }
 }
 
+/// Obtain a storage key prefix for the weights of user's public keys.
+pub fn weights_key_prefix(owner: &Address) -> storage::Key {
+    storage::Key {
+        segments: vec![
+            DbKeySeg::AddressSeg(owner.to_owned()),
+            DbKeySeg::StringSeg(Keys::VALUES.weights.to_string()),
+        ],
+    }
+}
+
+/// LazyMap handler for the weights of the user's public keys, by the index of
+/// the public key
+pub fn weights_handle(owner: &Address) -> LazyMap<u8, u8> {
+    LazyMap::open(weights_key_prefix(owner))
+}
+
+/// Check if the given storage key is a weight of a public key. If it is,
+/// returns the owner.
+pub fn is_weights_key(key: &storage::Key) -> Option<&Address> {
+    match &key.segments[..] {
+        [
+            DbKeySeg::AddressSeg(owner),
+            DbKeySeg::StringSeg(prefix),
+            DbKeySeg::StringSeg(data),
+            DbKeySeg::StringSeg(index),
+        ] if prefix.as_str() == Keys::VALUES.weights
+            && data.as_str() == lazy_map::DATA_SUBKEY
+            && index.parse::<u8>().is_ok() =>
+        {
+            Some(owner)
+        }
+        _ => None,
+    }
+}
+
 /// Check if the given storage key is a threshol key.
 pub fn is_threshold_key(key: &storage::Key) -> Option<&Address> {
     match &key.segments[..] {
@@ -89,3 +129,103 @@ pub fn is_protocol_pk_key(key: &storage::Key) -> Option<&Address> {
         _ => None,
     }
 }
+
+/// Obtain the storage key for a user's policy
+pub fn policy_key(owner: &Address) -> storage::Key {
+    storage::Key {
+        segments: vec![
+            DbKeySeg::AddressSeg(owner.to_owned()),
+            DbKeySeg::StringSeg(Keys::VALUES.policy.to_string()),
+        ],
+    }
+}
+
+/// Check if the given storage key is a policy key. If it is, returns the
+/// owner.
+pub fn is_policy_key(key: &storage::Key) -> Option<&Address> {
+    match &key.segments[..] {
+        [DbKeySeg::AddressSeg(owner), DbKeySeg::StringSeg(prefix)]
+            if prefix.as_str() == Keys::VALUES.policy =>
+        {
+            Some(owner)
+        }
+        _ => None,
+    }
+}
+
+/// Obtain the storage key for the amount of a token debited from a user's
+/// account during the current day
+pub fn daily_spending_key(owner: &Address, token: &Address) -> storage::Key {
+    storage::Key {
+        segments: vec![
+            DbKeySeg::AddressSeg(owner.to_owned()),
+            DbKeySeg::StringSeg(Keys::VALUES.daily_spending.to_string()),
+            DbKeySeg::AddressSeg(token.to_owned()),
+        ],
+    }
+}
+
+/// Check if the given storage key is a daily spending key. If it is, returns
+/// the owner and the token.
+pub fn is_daily_spending_key(
+    key: &storage::Key,
+) -> Option<(&Address, &Address)> {
+    match &key.segments[..] {
+        [DbKeySeg::AddressSeg(owner), DbKeySeg::StringSeg(prefix), token]
+            if prefix.as_str() == Keys::VALUES.daily_spending =>
+        {
+            match token {
+                DbKeySeg::AddressSeg(token) => Some((owner, token)),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Obtain the storage key for the number of epochs that an update of a user's
+/// VP is delayed by
+pub fn vp_update_timelock_key(owner: &Address) -> storage::Key {
+    storage::Key {
+        segments: vec![
+            DbKeySeg::AddressSeg(owner.to_owned()),
+            DbKeySeg::StringSeg(Keys::VALUES.vp_update_timelock.to_string()),
+        ],
+    }
+}
+
+/// Check if the given storage key is a VP update timelock key. If it is,
+/// returns the owner.
+pub fn is_vp_update_timelock_key(key: &storage::Key) -> Option<&Address> {
+    match &key.segments[..] {
+        [DbKeySeg::AddressSeg(owner), DbKeySeg::StringSeg(prefix)]
+            if prefix.as_str() == Keys::VALUES.vp_update_timelock =>
+        {
+            Some(owner)
+        }
+        _ => None,
+    }
+}
+
+/// Obtain the storage key for a pending update of a user's VP
+pub fn pending_vp_key(owner: &Address) -> storage::Key {
+    storage::Key {
+        segments: vec![
+            DbKeySeg::AddressSeg(owner.to_owned()),
+            DbKeySeg::StringSeg(Keys::VALUES.pending_vp.to_string()),
+        ],
+    }
+}
+
+/// Check if the given storage key is a pending VP update key. If it is,
+/// returns the owner.
+pub fn is_pending_vp_key(key: &storage::Key) -> Option<&Address> {
+    match &key.segments[..] {
+        [DbKeySeg::AddressSeg(owner), DbKeySeg::StringSeg(prefix)]
+            if prefix.as_str() == Keys::VALUES.pending_vp =>
+        {
+            Some(owner)
+        }
+        _ => None,
+    }
+}
@@ -1,7 +1,11 @@
+use std::collections::BTreeMap;
+
 use namada_core::borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
 use namada_core::types::address::Address;
 use namada_core::types::hash::Hash;
 use namada_core::types::key::common;
+use namada_core::types::storage::{BlockHeight, Epoch};
+use namada_core::types::token;
 use serde::{Deserialize, Serialize};
 
 /// A tx data type to initialize a new established account
@@ -20,9 +24,13 @@ pub struct InitAccount {
     /// for signature verification of transactions for the newly created
     /// account.
     pub public_keys: Vec<common::PublicKey>,
+    /// The weights of the public keys, in the same order as the keys. When
+    /// empty, every key has the default weight of 1.
+    pub weights: Vec<u8>,
     /// The VP code hash
     pub vp_code_hash: Hash,
-    /// The account signature threshold
+    /// The account signature threshold, i.e. the minimum total weight of the
+    /// keys that signed a tx
     pub threshold: u8,
 }
 
@@ -48,6 +56,158 @@ pub struct UpdateAccount {
     pub public_keys: Vec<common::PublicKey>,
     /// The account signature threshold
     pub threshold: Option<u8>,
+    /// The new number of epochs that an update of the account's VP is delayed
+    /// by. The timelock can only be increased.
+    pub vp_update_timelock: Option<u64>,
+    /// Whether to cancel a pending update of the account's VP
+    pub cancel_pending_vp_update: bool,
+}
+
+/// An update of an account's VP that is delayed by the account's VP update
+/// timelock
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Serialize,
+    Deserialize,
+)]
+pub struct PendingVpUpdate {
+    /// The new VP code hash
+    pub vp_code_hash: Hash,
+    /// The epoch from which the new VP is in effect
+    pub activation_epoch: Epoch,
+}
+
+impl PendingVpUpdate {
+    /// Check if the update is in effect in the given epoch
+    pub fn is_active(&self, epoch: Epoch) -> bool {
+        self.activation_epoch <= epoch
+    }
+}
+
+/// A tx data type to rotate the weighted public keys of an account
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Serialize,
+    Deserialize,
+)]
+pub struct RotateAccountKeys {
+    /// An address of the account
+    pub addr: Address,
+    /// The new public keys of the account
+    pub public_keys: Vec<common::PublicKey>,
+    /// The weights of the new public keys, in the same order as the keys.
+    /// When empty, every key has the default weight of 1.
+    pub weights: Vec<u8>,
+    /// The new account signature threshold, i.e. the minimum total weight of
+    /// the keys that signed a tx
+    pub threshold: u8,
+}
+
+/// A secondary key delegated by an account to sign only specific txs until
+/// an expiry height
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Serialize,
+    Deserialize,
+)]
+pub struct SessionKey {
+    /// The delegated public key
+    pub public_key: common::PublicKey,
+    /// The hashes of the tx codes that the key is allowed to sign
+    pub allowed_code_hashes: Vec<Hash>,
+    /// The last block height at which the key can be used
+    pub expiry: BlockHeight,
+}
+
+impl SessionKey {
+    /// Check if the key is allowed to sign a tx with the given code hash at
+    /// the given block height
+    pub fn is_allowed(&self, code_hash: &Hash, height: BlockHeight) -> bool {
+        height <= self.expiry && self.allowed_code_hashes.contains(code_hash)
+    }
+}
+
+/// Optional restrictions and delegations enforced by the user VP of an
+/// account
+#[derive(
+    Debug,
+    Clone,
+    Default,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Serialize,
+    Deserialize,
+)]
+pub struct AccountPolicy {
+    /// The maximum amount of each token that can be debited from the account
+    /// per day
+    pub spending_limits: BTreeMap<Address, token::Amount>,
+    /// The session keys delegated by the account
+    pub session_keys: Vec<SessionKey>,
+}
+
+impl AccountPolicy {
+    /// Check if the policy doesn't impose any restriction or delegation
+    pub fn is_empty(&self) -> bool {
+        self.spending_limits.is_empty() && self.session_keys.is_empty()
+    }
+}
+
+/// The amount of a token debited from an account during a day, tracked
+/// against the account's daily spending limit
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Serialize,
+    Deserialize,
+)]
+pub struct DailySpending {
+    /// The index of the day since the UNIX epoch
+    pub day: u64,
+    /// The amount debited during the day
+    pub amount: token::Amount,
+}
+
+/// A tx data type to update an account's policy
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Serialize,
+    Deserialize,
+)]
+pub struct UpdateAccountPolicy {
+    /// An address of the account
+    pub addr: Address,
+    /// The new policy of the account. An empty policy removes any existing
+    /// one.
+    pub policy: AccountPolicy,
 }
 
 #[cfg(any(test, feature = "testing"))]
@@ -56,8 +216,8 @@ pub mod tests {
     use namada_core::types::address::testing::arb_non_internal_address;
     use namada_core::types::hash::testing::arb_hash;
     use namada_core::types::key::testing::arb_common_pk;
-    use proptest::prelude::Just;
-    use proptest::{collection, option, prop_compose};
+    use proptest::prelude::{any, Just};
+    use proptest::{collection, option, prop_compose, prop_oneof};
 
     use super::*;
 
@@ -66,12 +226,24 @@ pub mod tests {
         pub fn arb_init_account()(
             public_keys in collection::vec(arb_common_pk(), 0..10),
         )(
-            threshold in 0..=public_keys.len() as u8,
+            weights in prop_oneof![
+                Just(vec![]),
+                collection::vec(1..=3_u8, public_keys.len()),
+            ],
+            public_keys in Just(public_keys),
+        )(
+            threshold in 0..=if weights.is_empty() {
+                public_keys.len() as u8
+            } else {
+                weights.iter().sum()
+            },
+            weights in Just(weights),
             public_keys in Just(public_keys),
             vp_code_hash in arb_hash(),
         ) -> InitAccount {
             InitAccount {
                 public_keys,
+                weights,
                 vp_code_hash,
                 threshold,
             }
@@ -87,12 +259,16 @@ pub mod tests {
             vp_code_hash in option::of(arb_hash()),
             threshold in option::of(0..=public_keys.len() as u8),
             public_keys in Just(public_keys),
+            vp_update_timelock in option::of(0..10_u64),
+            cancel_pending_vp_update in any::<bool>(),
         ) -> UpdateAccount {
             UpdateAccount {
                 addr,
                 vp_code_hash,
                 public_keys,
                 threshold,
+                vp_update_timelock,
+                cancel_pending_vp_update,
             }
         }
     }
@@ -53,4 +53,14 @@ fn main() {
 
     // Tell Cargo that if the given file changes, to rerun this build script.
     println!("cargo:rerun-if-changed={}", PROTO_SRC);
+
+    // The generated code is written into `OUT_DIR` and included with
+    // `tonic::include_proto!`, so it's always compiled from the current proto
+    // files
+    tonic_build::configure()
+        .build_client(false)
+        .message_attribute(".query", "#[derive(serde::Serialize)]")
+        .protoc_arg("--experimental_allow_proto3_optional")
+        .compile(&[format!("{}/query.proto", PROTO_SRC)], &[PROTO_SRC])
+        .unwrap();
 }
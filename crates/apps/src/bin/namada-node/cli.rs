@@ -33,9 +33,25 @@ pub fn main() -> Result<()> {
                 let chain_ctx = ctx.take_chain_or_exit();
                 ledger::dump_db(chain_ctx.config.ledger, args);
             }
-            cmds::Ledger::RollBack(_) => {
+            cmds::Ledger::ExportStateDiff(cmds::LedgerExportStateDiff(
+                args,
+            )) => {
                 let chain_ctx = ctx.take_chain_or_exit();
-                ledger::rollback(chain_ctx.config.ledger)
+                ledger::export_state_diff(chain_ctx.config.ledger, args);
+            }
+            cmds::Ledger::ExportGenesis(cmds::LedgerExportGenesis(args)) => {
+                let chain_ctx = ctx.take_chain_or_exit();
+                ledger::export_genesis(chain_ctx.config.ledger, args);
+            }
+            cmds::Ledger::ReplayStateDiffs(cmds::LedgerReplayStateDiffs(
+                args,
+            )) => {
+                let chain_ctx = ctx.take_chain_or_exit();
+                ledger::replay_state_diffs(chain_ctx.config.ledger, args);
+            }
+            cmds::Ledger::RollBack(cmds::LedgerRollBack(args)) => {
+                let chain_ctx = ctx.take_chain_or_exit();
+                ledger::rollback(chain_ctx.config.ledger, args)
                     .wrap_err("Failed to rollback the Namada node")?;
             }
         },
@@ -15,6 +15,7 @@ use masp_primitives::transaction::Transaction;
 use masp_primitives::zip32::ExtendedFullViewingKey;
 use masp_proofs::prover::LocalTxProver;
 use namada::governance::storage::proposal::ProposalType;
+use namada::governance::utils::VotingMode;
 use namada::governance::InitProposalData;
 use namada::ibc::apps::transfer::types::msgs::transfer::MsgTransfer;
 use namada::ibc::apps::transfer::types::packet::PacketData;
@@ -76,7 +77,8 @@ use namada::types::token::{Amount, DenominatedAmount, Transfer};
 use namada::vm::wasm::run;
 use namada::{proof_of_stake, tendermint};
 use namada_sdk::masp::{
-    self, ShieldedContext, ShieldedTransfer, ShieldedUtils,
+    self, NoteSelectionStrategy, ShieldedContext, ShieldedTransfer,
+    ShieldedUtils,
 };
 pub use namada_sdk::tx::{
     TX_BECOME_VALIDATOR_WASM, TX_BOND_WASM, TX_BRIDGE_POOL_WASM,
@@ -249,11 +251,15 @@ impl Default for BenchShell {
             InitProposalData {
                 id: 0,
                 content: content_section.get_hash(),
+                content_hash: Hash::sha256(b""),
+                content_uri: None,
                 author: defaults::albert_address(),
-                r#type: ProposalType::Default(None),
+                r#type: ProposalType::Text,
                 voting_start_epoch,
                 voting_end_epoch: voting_start_epoch + 3_u64,
                 grace_epoch: voting_start_epoch + 9_u64,
+                expedited: false,
+                voting_mode: VotingMode::Linear,
             },
             None,
             Some(vec![content_section]),
@@ -848,6 +854,7 @@ impl Client for BenchShell {
                             initialized_accounts: vec![],
                             ibc_events: BTreeSet::default(),
                             eth_bridge_events: BTreeSet::default(),
+                            tx_events: vec![],
                         };
                         namada::tendermint::abci::Event {
                             kind: "applied".to_string(),
@@ -1006,6 +1013,7 @@ impl BenchShieldedCtx {
                     &target,
                     &address::nam(),
                     denominated_amount,
+                    NoteSelectionStrategy::default(),
                 ),
             )
             .unwrap()
@@ -222,6 +222,8 @@ pub mod cmds {
                 // Governance transactions
                 .subcommand(TxInitProposal::def().display_order(1))
                 .subcommand(TxVoteProposal::def().display_order(1))
+                .subcommand(TxVoteProposalBatch::def().display_order(1))
+                .subcommand(TxWithdrawVote::def().display_order(1))
                 // PoS transactions
                 .subcommand(TxBecomeValidator::def().display_order(2))
                 .subcommand(TxInitValidator::def().display_order(2))
@@ -241,6 +243,7 @@ pub mod cmds {
                 // PGF transactions
                 .subcommand(TxUpdateStewardCommission::def().display_order(4))
                 .subcommand(TxResignSteward::def().display_order(4))
+                .subcommand(TxAttestPgfMilestone::def().display_order(4))
                 // Queries
                 .subcommand(QueryEpoch::def().display_order(5))
                 .subcommand(QueryAccount::def().display_order(5))
@@ -295,10 +298,16 @@ pub mod cmds {
                 Self::parse_with_ctx(matches, TxInitProposal);
             let tx_vote_proposal =
                 Self::parse_with_ctx(matches, TxVoteProposal);
+            let tx_vote_proposal_batch =
+                Self::parse_with_ctx(matches, TxVoteProposalBatch);
+            let tx_withdraw_vote =
+                Self::parse_with_ctx(matches, TxWithdrawVote);
             let tx_update_steward_commission =
                 Self::parse_with_ctx(matches, TxUpdateStewardCommission);
             let tx_resign_steward =
                 Self::parse_with_ctx(matches, TxResignSteward);
+            let tx_attest_pgf_milestone =
+                Self::parse_with_ctx(matches, TxAttestPgfMilestone);
             let tx_commission_rate_change =
                 Self::parse_with_ctx(matches, TxCommissionRateChange);
             let tx_change_consensus_key =
@@ -357,6 +366,8 @@ pub mod cmds {
                 .or(tx_reveal_pk)
                 .or(tx_init_proposal)
                 .or(tx_vote_proposal)
+                .or(tx_vote_proposal_batch)
+                .or(tx_withdraw_vote)
                 .or(tx_become_validator)
                 .or(tx_init_validator)
                 .or(tx_commission_rate_change)
@@ -373,6 +384,7 @@ pub mod cmds {
                 .or(add_to_eth_bridge_pool)
                 .or(tx_update_steward_commission)
                 .or(tx_resign_steward)
+                .or(tx_attest_pgf_milestone)
                 .or(query_epoch)
                 .or(query_transfers)
                 .or(query_conversions)
@@ -451,6 +463,8 @@ pub mod cmds {
         TxReactivateValidator(TxReactivateValidator),
         TxInitProposal(TxInitProposal),
         TxVoteProposal(TxVoteProposal),
+        TxVoteProposalBatch(TxVoteProposalBatch),
+        TxWithdrawVote(TxWithdrawVote),
         TxRevealPk(TxRevealPk),
         Bond(Bond),
         Unbond(Unbond),
@@ -460,6 +474,7 @@ pub mod cmds {
         AddToEthBridgePool(AddToEthBridgePool),
         TxUpdateStewardCommission(TxUpdateStewardCommission),
         TxResignSteward(TxResignSteward),
+        TxAttestPgfMilestone(TxAttestPgfMilestone),
         QueryEpoch(QueryEpoch),
         QueryAccount(QueryAccount),
         QueryTransfers(QueryTransfers),
@@ -501,6 +516,12 @@ pub mod cmds {
         KeyAddrFind(WalletFindKeysAddresses),
         /// Key export
         KeyExport(WalletExportKey),
+        /// View-only wallet export
+        KeyExportViewOnly(WalletExportViewOnly),
+        /// Key password change
+        KeyChangePassword(WalletChangePassword),
+        /// External key add
+        KeyAddExternal(WalletAddExternalKey),
         /// Key import
         KeyImport(WalletImportKey),
         /// Key / address add
@@ -517,6 +538,9 @@ pub mod cmds {
                 .subcommand(WalletListKeysAddresses::def())
                 .subcommand(WalletFindKeysAddresses::def())
                 .subcommand(WalletExportKey::def())
+                .subcommand(WalletExportViewOnly::def())
+                .subcommand(WalletChangePassword::def())
+                .subcommand(WalletAddExternalKey::def())
                 .subcommand(WalletImportKey::def())
                 .subcommand(WalletAddKeyAddress::def())
                 .subcommand(WalletRemoveKeyAddress::def())
@@ -529,6 +553,11 @@ pub mod cmds {
             let key_addr_list = SubCmd::parse(matches).map(Self::KeyAddrList);
             let key_addr_find = SubCmd::parse(matches).map(Self::KeyAddrFind);
             let export = SubCmd::parse(matches).map(Self::KeyExport);
+            let export_view_only =
+                SubCmd::parse(matches).map(Self::KeyExportViewOnly);
+            let change_password =
+                SubCmd::parse(matches).map(Self::KeyChangePassword);
+            let add_external = SubCmd::parse(matches).map(Self::KeyAddExternal);
             let import = SubCmd::parse(matches).map(Self::KeyImport);
             let key_addr_add = SubCmd::parse(matches).map(Self::KeyAddrAdd);
             let key_addr_remove =
@@ -538,6 +567,9 @@ pub mod cmds {
                 .or(key_addr_list)
                 .or(key_addr_find)
                 .or(export)
+                .or(export_view_only)
+                .or(change_password)
+                .or(add_external)
                 .or(import)
                 .or(key_addr_add)
                 .or(key_addr_remove)
@@ -704,6 +736,79 @@ pub mod cmds {
         }
     }
 
+    /// Export a view-only copy of the wallet
+    #[derive(Clone, Debug)]
+    pub struct WalletExportViewOnly(pub args::KeyExportViewOnly);
+
+    impl SubCmd for WalletExportViewOnly {
+        const CMD: &'static str = "export-view-only";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| Self(args::KeyExportViewOnly::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Exports a view-only copy of the wallet, holding only the \
+                     viewing keys, public keys and addresses. It can compute \
+                     balances and build unsigned transactions, but cannot \
+                     sign them.",
+                )
+                .add_args::<args::KeyExportViewOnly>()
+        }
+    }
+
+    /// Change the password of a key
+    #[derive(Clone, Debug)]
+    pub struct WalletChangePassword(pub args::KeyChangePassword);
+
+    impl SubCmd for WalletChangePassword {
+        const CMD: &'static str = "change-password";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| Self(args::KeyChangePassword::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Changes the password of a transparent keypair or a MASP \
+                     spending key. The keys encrypted by a previous version \
+                     of the wallet are migrated to the current encryption.",
+                )
+                .add_args::<args::KeyChangePassword>()
+        }
+    }
+
+    /// Add a key held outside of the wallet
+    #[derive(Clone, Debug)]
+    pub struct WalletAddExternalKey(pub args::KeyAddExternal);
+
+    impl SubCmd for WalletAddExternalKey {
+        const CMD: &'static str = "add-external";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| Self(args::KeyAddExternal::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Adds a transparent key held outside of the wallet, in \
+                     the keychain of the OS or by a remote signer. Only the \
+                     public key is stored in the wallet.",
+                )
+                .add_args::<args::KeyAddExternal>()
+        }
+    }
+
     /// Import key from a file
     #[derive(Clone, Debug)]
     pub struct WalletImportKey(pub args::KeyImport);
@@ -798,6 +903,9 @@ pub mod cmds {
         RunUntil(LedgerRunUntil),
         Reset(LedgerReset),
         DumpDb(LedgerDumpDb),
+        ExportStateDiff(LedgerExportStateDiff),
+        ExportGenesis(LedgerExportGenesis),
+        ReplayStateDiffs(LedgerReplayStateDiffs),
         RollBack(LedgerRollBack),
     }
 
@@ -809,10 +917,19 @@ pub mod cmds {
                 let run = SubCmd::parse(matches).map(Self::Run);
                 let reset = SubCmd::parse(matches).map(Self::Reset);
                 let dump_db = SubCmd::parse(matches).map(Self::DumpDb);
+                let export_state_diff =
+                    SubCmd::parse(matches).map(Self::ExportStateDiff);
+                let export_genesis =
+                    SubCmd::parse(matches).map(Self::ExportGenesis);
+                let replay_state_diffs =
+                    SubCmd::parse(matches).map(Self::ReplayStateDiffs);
                 let rollback = SubCmd::parse(matches).map(Self::RollBack);
                 let run_until = SubCmd::parse(matches).map(Self::RunUntil);
                 run.or(reset)
                     .or(dump_db)
+                    .or(export_state_diff)
+                    .or(export_genesis)
+                    .or(replay_state_diffs)
                     .or(rollback)
                     .or(run_until)
                     // The `run` command is the default if no sub-command given
@@ -832,6 +949,9 @@ pub mod cmds {
                 .subcommand(LedgerRunUntil::def())
                 .subcommand(LedgerReset::def())
                 .subcommand(LedgerDumpDb::def())
+                .subcommand(LedgerExportStateDiff::def())
+                .subcommand(LedgerExportGenesis::def())
+                .subcommand(LedgerReplayStateDiffs::def())
                 .subcommand(LedgerRollBack::def())
         }
     }
@@ -915,22 +1035,92 @@ pub mod cmds {
     }
 
     #[derive(Clone, Debug)]
-    pub struct LedgerRollBack;
+    pub struct LedgerExportStateDiff(pub args::LedgerExportStateDiff);
+
+    impl SubCmd for LedgerExportStateDiff {
+        const CMD: &'static str = "export-state-diff";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                Self(args::LedgerExportStateDiff::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Export the diff of the storage of a committed block into \
+                     a file.",
+                )
+                .add_args::<args::LedgerExportStateDiff>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct LedgerExportGenesis(pub args::LedgerExportGenesis);
+
+    impl SubCmd for LedgerExportGenesis {
+        const CMD: &'static str = "export-genesis";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| Self(args::LedgerExportGenesis::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Export the state at a committed block height into \
+                     genesis templates to restart the chain from.",
+                )
+                .add_args::<args::LedgerExportGenesis>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct LedgerReplayStateDiffs(pub args::LedgerReplayStateDiffs);
+
+    impl SubCmd for LedgerReplayStateDiffs {
+        const CMD: &'static str = "replay-state-diffs";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                Self(args::LedgerReplayStateDiffs::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Replay a series of exported state diffs onto a state \
+                     snapshot and dump the resulting storage into a file.",
+                )
+                .add_args::<args::LedgerReplayStateDiffs>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct LedgerRollBack(pub args::LedgerRollBack);
 
     impl SubCmd for LedgerRollBack {
         const CMD: &'static str = "rollback";
 
         fn parse(matches: &ArgMatches) -> Option<Self> {
-            matches.subcommand_matches(Self::CMD).map(|_matches| Self)
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| Self(args::LedgerRollBack::parse(matches)))
         }
 
         fn def() -> App {
-            App::new(Self::CMD).about(
-                "Roll Namada state back to the previous height. This command \
-                 does not create a backup of neither the Namada nor the \
-                 Tendermint state before execution: for extra safety, it is \
-                 recommended to make a backup in advance.",
-            )
+            App::new(Self::CMD)
+                .about(
+                    "Roll Namada state back to a previous height. This \
+                     command does not create a backup of neither the Namada \
+                     nor the Tendermint state before execution: for extra \
+                     safety, it is recommended to make a backup in advance.",
+                )
+                .add_args::<args::LedgerRollBack>()
         }
     }
 
@@ -1881,6 +2071,33 @@ pub mod cmds {
         }
     }
 
+    #[derive(Clone, Debug)]
+    pub struct TxAttestPgfMilestone(
+        pub args::AttestPgfMilestone<args::CliTypes>,
+    );
+
+    impl SubCmd for TxAttestPgfMilestone {
+        const CMD: &'static str = "attest-pgf-milestone";
+
+        fn parse(matches: &ArgMatches) -> Option<Self>
+        where
+            Self: Sized,
+        {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                TxAttestPgfMilestone(args::AttestPgfMilestone::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Craft a transaction to attest the completion of the next \
+                     milestone of a pgf grant.",
+                )
+                .add_args::<args::AttestPgfMilestone<args::CliTypes>>()
+        }
+    }
+
     #[derive(Clone, Debug)]
     pub struct TxCommissionRateChange(
         pub args::CommissionRateChange<args::CliTypes>,
@@ -1978,6 +2195,56 @@ pub mod cmds {
         }
     }
 
+    #[derive(Clone, Debug)]
+    pub struct TxVoteProposalBatch(pub args::VoteProposalBatch<args::CliTypes>);
+
+    impl SubCmd for TxVoteProposalBatch {
+        const CMD: &'static str = "vote-proposal-batch";
+
+        fn parse(matches: &ArgMatches) -> Option<Self>
+        where
+            Self: Sized,
+        {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                TxVoteProposalBatch(args::VoteProposalBatch::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Submit a batch of proposal votes signed off-chain by \
+                     their voters.",
+                )
+                .add_args::<args::VoteProposalBatch<args::CliTypes>>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct TxWithdrawVote(pub args::VoteWithdrawal<args::CliTypes>);
+
+    impl SubCmd for TxWithdrawVote {
+        const CMD: &'static str = "withdraw-vote";
+
+        fn parse(matches: &ArgMatches) -> Option<Self>
+        where
+            Self: Sized,
+        {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                TxWithdrawVote(args::VoteWithdrawal::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Withdraw the vote cast on a proposal while its voting \
+                     period is open.",
+                )
+                .add_args::<args::VoteWithdrawal<args::CliTypes>>()
+        }
+    }
+
     #[derive(Clone, Debug)]
     pub struct TxRevealPk(pub args::RevealPk<args::CliTypes>);
 
@@ -2840,17 +3107,19 @@ pub mod args {
     use namada::types::token;
     use namada::types::token::NATIVE_MAX_DECIMAL_PLACES;
     pub use namada_sdk::args::*;
+    use namada_sdk::masp::NoteSelectionStrategy;
     pub use namada_sdk::tx::{
-        TX_BECOME_VALIDATOR_WASM, TX_BOND_WASM, TX_BRIDGE_POOL_WASM,
-        TX_CHANGE_COMMISSION_WASM, TX_CHANGE_CONSENSUS_KEY_WASM,
-        TX_CHANGE_METADATA_WASM, TX_CLAIM_REWARDS_WASM,
-        TX_DEACTIVATE_VALIDATOR_WASM, TX_IBC_WASM, TX_INIT_ACCOUNT_WASM,
-        TX_INIT_PROPOSAL, TX_REACTIVATE_VALIDATOR_WASM, TX_REDELEGATE_WASM,
-        TX_RESIGN_STEWARD, TX_REVEAL_PK, TX_TRANSFER_WASM, TX_UNBOND_WASM,
-        TX_UNJAIL_VALIDATOR_WASM, TX_UPDATE_ACCOUNT_WASM,
-        TX_UPDATE_STEWARD_COMMISSION, TX_VOTE_PROPOSAL, TX_WITHDRAW_WASM,
-        VP_USER_WASM,
+        TX_ATTEST_PGF_MILESTONE, TX_BECOME_VALIDATOR_WASM, TX_BOND_WASM,
+        TX_BRIDGE_POOL_WASM, TX_CHANGE_COMMISSION_WASM,
+        TX_CHANGE_CONSENSUS_KEY_WASM, TX_CHANGE_METADATA_WASM,
+        TX_CLAIM_REWARDS_WASM, TX_DEACTIVATE_VALIDATOR_WASM, TX_IBC_WASM,
+        TX_INIT_ACCOUNT_WASM, TX_INIT_PROPOSAL, TX_REACTIVATE_VALIDATOR_WASM,
+        TX_REDELEGATE_WASM, TX_RESIGN_STEWARD, TX_REVEAL_PK, TX_TRANSFER_WASM,
+        TX_UNBOND_WASM, TX_UNJAIL_VALIDATOR_WASM, TX_UPDATE_ACCOUNT_WASM,
+        TX_UPDATE_STEWARD_COMMISSION, TX_VOTE_PROPOSAL, TX_VOTE_PROPOSAL_BATCH,
+        TX_WITHDRAW_VOTE, TX_WITHDRAW_WASM, VP_USER_WASM,
     };
+    use namada_sdk::wallet::KeyBackend;
 
     use super::context::*;
     use super::utils::*;
@@ -2870,6 +3139,7 @@ pub mod args {
     pub const AMOUNT: Arg<token::DenominatedAmount> = arg("amount");
     pub const ARCHIVE_DIR: ArgOpt<PathBuf> = arg_opt("archive-dir");
     pub const AVATAR_OPT: ArgOpt<String> = arg_opt("avatar");
+    pub const BALLOTS: ArgMulti<PathBuf, GlobPlus> = arg_multi("ballots");
     pub const BALANCE_OWNER: ArgOpt<WalletBalanceOwner> = arg_opt("owner");
     pub const BASE_DIR: ArgDefault<PathBuf> = arg_default(
         "base-dir",
@@ -2880,6 +3150,7 @@ pub mod args {
     );
     pub const BLOCK_HEIGHT: Arg<BlockHeight> = arg("block-height");
     pub const BLOCK_HEIGHT_OPT: ArgOpt<BlockHeight> = arg_opt("height");
+    pub const BLOCKS: ArgDefault<u64> = arg_default("blocks", DefaultFn(|| 1));
     pub const BRIDGE_POOL_GAS_AMOUNT: ArgDefault<token::DenominatedAmount> =
         arg_default(
             "pool-gas-amount",
@@ -2900,6 +3171,7 @@ pub mod args {
     );
     pub const BRIDGE_POOL_TARGET: Arg<EthAddress> = arg("target");
     pub const BROADCAST_ONLY: ArgFlag = flag("broadcast-only");
+    pub const CANCEL_PENDING_VP: ArgFlag = flag("cancel-pending-vp");
     pub const CHAIN_ID: Arg<ChainId> = arg("chain-id");
     pub const CHAIN_ID_OPT: ArgOpt<ChainId> = CHAIN_ID.opt();
     pub const CHAIN_ID_PREFIX: Arg<ChainIdPrefix> = arg("chain-prefix");
@@ -2974,6 +3246,7 @@ pub mod args {
         arg("genesis-validator").opt();
     pub const GENESIS_VALIDATOR_ADDRESS: Arg<EstablishedAddress> =
         arg("validator");
+    pub const GRANT_ID: Arg<u64> = arg("grant-id");
     pub const HALT_ACTION: ArgFlag = flag("halt");
     pub const HASH_LIST: Arg<String> = arg("hash-list");
     pub const HD_DERIVATION_PATH: ArgDefault<String> =
@@ -2984,6 +3257,7 @@ pub mod args {
     pub const HISTORIC: ArgFlag = flag("historic");
     pub const IBC_TRANSFER_MEMO_PATH: ArgOpt<PathBuf> = arg_opt("memo-path");
     pub const INPUT_OPT: ArgOpt<PathBuf> = arg_opt("input");
+    pub const KEY_BACKEND: Arg<KeyBackend> = arg("backend");
     pub const LEDGER_ADDRESS_ABOUT: &str =
         "Address of a ledger node as \"{scheme}://{host}:{port}\". If the \
          scheme is not supplied, it is assumed to be TCP.";
@@ -3005,6 +3279,10 @@ pub mod args {
     pub const NET_ADDRESS: Arg<SocketAddr> = arg("net-address");
     pub const NAMADA_START_TIME: ArgOpt<DateTimeUtc> = arg_opt("time");
     pub const NO_CONVERSIONS: ArgFlag = flag("no-conversions");
+    pub const NOTE_SELECTION: ArgDefault<NoteSelectionStrategy> = arg_default(
+        "note-selection",
+        DefaultFn(|| NoteSelectionStrategy::Oldest),
+    );
     pub const NUT: ArgFlag = flag("nut");
     pub const OUT_FILE_PATH_OPT: ArgOpt<PathBuf> = arg_opt("out-file-path");
     pub const OUTPUT: ArgOpt<PathBuf> = arg_opt("output");
@@ -3019,9 +3297,15 @@ pub mod args {
         DefaultFn(|| PortId::from_str("transfer").unwrap()),
     );
     pub const PRE_GENESIS: ArgFlag = flag("pre-genesis");
+    pub const PROPOSAL_BALLOT: ArgFlag = flag("ballot");
     pub const PROPOSAL_ETH: ArgFlag = flag("eth");
     pub const PROPOSAL_PGF_STEWARD: ArgFlag = flag("pgf-stewards");
     pub const PROPOSAL_PGF_FUNDING: ArgFlag = flag("pgf-funding");
+    pub const PROPOSAL_MASP_REWARDS: ArgFlag = flag("masp-rewards");
+    pub const PROPOSAL_PARAMETER_CHANGE: ArgFlag = flag("parameter-change");
+    pub const PROPOSAL_EMERGENCY_HALT: ArgFlag = flag("emergency-halt");
+    pub const PROPOSAL_WASM_ALLOWLIST: ArgFlag = flag("wasm-allowlist");
+    pub const PROPOSAL_SIGNALING: ArgFlag = flag("signaling");
     pub const PROPOSAL_OFFLINE: ArgFlag = flag("offline");
     pub const PROTOCOL_KEY: ArgOpt<WalletPublicKey> = arg_opt("protocol-key");
     pub const PRE_GENESIS_PATH: ArgOpt<PathBuf> = arg_opt("pre-genesis-path");
@@ -3063,6 +3347,8 @@ pub mod args {
     pub const SOURCE_OPT: ArgOpt<WalletAddress> = SOURCE.opt();
     pub const STEWARD: Arg<WalletAddress> = arg("steward");
     pub const SOURCE_VALIDATOR: Arg<WalletAddress> = arg("source-validator");
+    pub const STATE_DIFFS: ArgMulti<PathBuf, GlobPlus> =
+        arg_multi("state-diffs");
     pub const STORAGE_KEY: Arg<storage::Key> = arg("storage-key");
     pub const SUSPEND_ACTION: ArgFlag = flag("suspend");
     pub const TEMPLATES_PATH: Arg<PathBuf> = arg("templates-path");
@@ -3094,10 +3380,12 @@ pub mod args {
         arg_opt("eth-cold-key");
     pub const VALIDATOR_ETH_HOT_KEY: ArgOpt<WalletPublicKey> =
         arg_opt("eth-hot-key");
+    pub const VALIDATOR_NAME_OPT: ArgOpt<String> = arg_opt("name");
     pub const VALUE: Arg<String> = arg("value");
     pub const VOTER_OPT: ArgOpt<WalletAddress> = arg_opt("voter");
     pub const VIEWING_KEY: Arg<WalletViewingKey> = arg("key");
     pub const VP: ArgOpt<String> = arg_opt("vp");
+    pub const VP_UPDATE_TIMELOCK: ArgOpt<u64> = arg_opt("vp-update-timelock");
     pub const WALLET_ALIAS_FORCE: ArgFlag = flag("wallet-alias-force");
     pub const WASM_CHECKSUMS_PATH: Arg<PathBuf> = arg("wasm-checksums-path");
     pub const WASM_DIR: ArgOpt<PathBuf> = arg_opt("wasm-dir");
@@ -3263,6 +3551,131 @@ pub mod args {
         }
     }
 
+    #[derive(Clone, Debug)]
+    pub struct LedgerExportStateDiff {
+        pub block_height: Option<BlockHeight>,
+        pub out_file_path: PathBuf,
+    }
+
+    impl Args for LedgerExportStateDiff {
+        fn parse(matches: &ArgMatches) -> Self {
+            let block_height = BLOCK_HEIGHT_OPT.parse(matches);
+            let out_file_path = OUT_FILE_PATH_OPT
+                .parse(matches)
+                .unwrap_or_else(|| PathBuf::from("state_diff".to_string()));
+
+            Self {
+                block_height,
+                out_file_path,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(BLOCK_HEIGHT_OPT.def().help(
+                "The block height of the diff to export. Defaults to the \
+                 latest committed block. An older block can only be exported \
+                 with `persist_all_diffs` enabled in the config.",
+            ))
+            .arg(OUT_FILE_PATH_OPT.def().help(
+                "Path for the output file (omitting file extension). Defaults \
+                 to \"state_diff_{block_height}.diff\" in the current working \
+                 directory.",
+            ))
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct LedgerExportGenesis {
+        pub block_height: Option<BlockHeight>,
+        pub output_folder: Option<PathBuf>,
+    }
+
+    impl Args for LedgerExportGenesis {
+        fn parse(matches: &ArgMatches) -> Self {
+            let block_height = BLOCK_HEIGHT_OPT.parse(matches);
+            let output_folder = OUTPUT_FOLDER_PATH.parse(matches);
+
+            Self {
+                block_height,
+                output_folder,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(BLOCK_HEIGHT_OPT.def().help(
+                "The block height of the state to export. Defaults to the \
+                 latest committed block.",
+            ))
+            .arg(OUTPUT_FOLDER_PATH.def().help(
+                "The folder in which the genesis templates are written. \
+                 Defaults to \"exported_genesis_{block_height}\" in the \
+                 current working directory.",
+            ))
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct LedgerReplayStateDiffs {
+        pub snapshot_height: BlockHeight,
+        pub state_diffs: Vec<PathBuf>,
+        pub out_file_path: PathBuf,
+    }
+
+    impl Args for LedgerReplayStateDiffs {
+        fn parse(matches: &ArgMatches) -> Self {
+            let snapshot_height = BLOCK_HEIGHT.parse(matches);
+            let state_diffs = STATE_DIFFS.parse(matches);
+            let out_file_path = OUT_FILE_PATH_OPT
+                .parse(matches)
+                .unwrap_or_else(|| PathBuf::from("db_dump".to_string()));
+
+            Self {
+                snapshot_height,
+                state_diffs,
+                out_file_path,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(BLOCK_HEIGHT.def().help(
+                "The block height of the state snapshot to replay the diffs \
+                 onto.",
+            ))
+            .arg(STATE_DIFFS.def().help(
+                "The files of the state diffs to replay, as produced by \
+                 `export-state-diff`. The diffs must cover every block after \
+                 the snapshot up to the last one.",
+            ))
+            .arg(OUT_FILE_PATH_OPT.def().help(
+                "Path for the output file (omitting file extension). Defaults \
+                 to \"db_dump_{block_height}.toml\" in the current working \
+                 directory, where the block height is the one of the last \
+                 replayed diff.",
+            ))
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct LedgerRollBack {
+        pub blocks: u64,
+    }
+
+    impl Args for LedgerRollBack {
+        fn parse(matches: &ArgMatches) -> Self {
+            let blocks = BLOCKS.parse(matches);
+            Self { blocks }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(BLOCKS.def().help(
+                "The number of the last blocks to roll back. Several blocks \
+                 can only be rolled back if the keys that are not merklized, \
+                 e.g. the MASP keys, have not been changed after the first \
+                 rolled back block.",
+            ))
+        }
+    }
+
     #[derive(Clone, Debug)]
     pub struct UpdateLocalConfig {
         pub config_path: PathBuf,
@@ -3900,6 +4313,7 @@ pub mod args {
                 target: chain_ctx.get(&self.target),
                 token: chain_ctx.get(&self.token),
                 amount: self.amount,
+                note_selection: self.note_selection,
                 tx_code_path: self.tx_code_path.to_path_buf(),
             }
         }
@@ -3912,6 +4326,7 @@ pub mod args {
             let target = TRANSFER_TARGET.parse(matches);
             let token = TOKEN.parse(matches);
             let amount = InputAmount::Unvalidated(AMOUNT.parse(matches));
+            let note_selection = NOTE_SELECTION.parse(matches);
             let tx_code_path = PathBuf::from(TX_TRANSFER_WASM);
             Self {
                 tx,
@@ -3919,6 +4334,7 @@ pub mod args {
                 target,
                 token,
                 amount,
+                note_selection,
                 tx_code_path,
             }
         }
@@ -3935,6 +4351,12 @@ pub mod args {
                 ))
                 .arg(TOKEN.def().help("The transfer token."))
                 .arg(AMOUNT.def().help("The amount to transfer in decimal."))
+                .arg(NOTE_SELECTION.def().help(
+                    "The strategy used to select the notes spent by a \
+                     shielded source. One of `oldest` (the default), \
+                     `minimize-change`, `consolidate-dust` or \
+                     `prefer-privacy`.",
+                ))
         }
     }
 
@@ -4355,6 +4777,8 @@ pub mod args {
                     .map(|pk| chain_ctx.get(pk))
                     .collect(),
                 threshold: self.threshold,
+                vp_update_timelock: self.vp_update_timelock,
+                cancel_pending_vp_update: self.cancel_pending_vp_update,
             }
         }
     }
@@ -4367,6 +4791,8 @@ pub mod args {
             let tx_code_path = PathBuf::from(TX_UPDATE_ACCOUNT_WASM);
             let public_keys = PUBLIC_KEYS.parse(matches);
             let threshold = THRESHOLD.parse(matches);
+            let vp_update_timelock = VP_UPDATE_TIMELOCK.parse(matches);
+            let cancel_pending_vp_update = CANCEL_PENDING_VP.parse(matches);
             Self {
                 tx,
                 vp_code_path,
@@ -4374,6 +4800,8 @@ pub mod args {
                 tx_code_path,
                 public_keys,
                 threshold,
+                vp_update_timelock,
+                cancel_pending_vp_update,
             }
         }
 
@@ -4397,6 +4825,15 @@ pub mod args {
                      authorization. Must be less then the maximum number of \
                      public keys provided.",
                 ))
+                .arg(VP_UPDATE_TIMELOCK.def().help(
+                    "The number of epochs that any later update of the \
+                     account's validity predicate is delayed by. The timelock \
+                     can only be increased.",
+                ))
+                .arg(CANCEL_PENDING_VP.def().help(
+                    "Cancel a pending update of the account's validity \
+                     predicate.",
+                ))
         }
     }
 
@@ -4572,6 +5009,38 @@ pub mod args {
         }
     }
 
+    impl CliToSdk<AttestPgfMilestone<SdkTypes>> for AttestPgfMilestone<CliTypes> {
+        fn to_sdk(self, ctx: &mut Context) -> AttestPgfMilestone<SdkTypes> {
+            AttestPgfMilestone::<SdkTypes> {
+                tx: self.tx.to_sdk(ctx),
+                steward: ctx.borrow_chain_or_exit().get(&self.steward),
+                grant_id: self.grant_id,
+                tx_code_path: self.tx_code_path.to_path_buf(),
+            }
+        }
+    }
+
+    impl Args for AttestPgfMilestone<CliTypes> {
+        fn parse(matches: &ArgMatches) -> Self {
+            let tx = Tx::parse(matches);
+            let steward = STEWARD.parse(matches);
+            let grant_id = GRANT_ID.parse(matches);
+            let tx_code_path = PathBuf::from(TX_ATTEST_PGF_MILESTONE);
+            Self {
+                tx,
+                steward,
+                grant_id,
+                tx_code_path,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Tx<CliTypes>>()
+                .arg(STEWARD.def().help("Steward address."))
+                .arg(GRANT_ID.def().help("The pgf grant id."))
+        }
+    }
+
     impl CliToSdk<Redelegate<SdkTypes>> for Redelegate<CliTypes> {
         fn to_sdk(self, ctx: &mut Context) -> Redelegate<SdkTypes> {
             let tx = self.tx.to_sdk(ctx);
@@ -4639,6 +5108,11 @@ pub mod args {
                 is_offline: self.is_offline,
                 is_pgf_stewards: self.is_pgf_stewards,
                 is_pgf_funding: self.is_pgf_funding,
+                is_masp_rewards: self.is_masp_rewards,
+                is_parameter_change: self.is_parameter_change,
+                is_emergency_halt: self.is_emergency_halt,
+                is_wasm_allowlist: self.is_wasm_allowlist,
+                is_signaling: self.is_signaling,
                 tx_code_path: self.tx_code_path,
             }
         }
@@ -4651,6 +5125,11 @@ pub mod args {
             let is_offline = PROPOSAL_OFFLINE.parse(matches);
             let is_pgf_stewards = PROPOSAL_PGF_STEWARD.parse(matches);
             let is_pgf_funding = PROPOSAL_PGF_FUNDING.parse(matches);
+            let is_masp_rewards = PROPOSAL_MASP_REWARDS.parse(matches);
+            let is_parameter_change = PROPOSAL_PARAMETER_CHANGE.parse(matches);
+            let is_emergency_halt = PROPOSAL_EMERGENCY_HALT.parse(matches);
+            let is_wasm_allowlist = PROPOSAL_WASM_ALLOWLIST.parse(matches);
+            let is_signaling = PROPOSAL_SIGNALING.parse(matches);
             let tx_code_path = PathBuf::from(TX_INIT_PROPOSAL);
 
             Self {
@@ -4660,6 +5139,11 @@ pub mod args {
                 is_offline,
                 is_pgf_stewards,
                 is_pgf_funding,
+                is_masp_rewards,
+                is_parameter_change,
+                is_emergency_halt,
+                is_wasm_allowlist,
+                is_signaling,
             }
         }
 
@@ -4678,6 +5162,11 @@ pub mod args {
                         .conflicts_with_all([
                             PROPOSAL_PGF_FUNDING.name,
                             PROPOSAL_PGF_STEWARD.name,
+                            PROPOSAL_MASP_REWARDS.name,
+                            PROPOSAL_PARAMETER_CHANGE.name,
+                            PROPOSAL_EMERGENCY_HALT.name,
+                            PROPOSAL_WASM_ALLOWLIST.name,
+                            PROPOSAL_SIGNALING.name,
                             PROPOSAL_ETH.name,
                         ]),
                 )
@@ -4688,6 +5177,11 @@ pub mod args {
                         .conflicts_with_all([
                             PROPOSAL_PGF_FUNDING.name,
                             PROPOSAL_PGF_STEWARD.name,
+                            PROPOSAL_MASP_REWARDS.name,
+                            PROPOSAL_PARAMETER_CHANGE.name,
+                            PROPOSAL_EMERGENCY_HALT.name,
+                            PROPOSAL_WASM_ALLOWLIST.name,
+                            PROPOSAL_SIGNALING.name,
                         ]),
                 )
                 .arg(
@@ -4700,6 +5194,11 @@ pub mod args {
                         .conflicts_with_all([
                             PROPOSAL_ETH.name,
                             PROPOSAL_PGF_FUNDING.name,
+                            PROPOSAL_MASP_REWARDS.name,
+                            PROPOSAL_PARAMETER_CHANGE.name,
+                            PROPOSAL_EMERGENCY_HALT.name,
+                            PROPOSAL_WASM_ALLOWLIST.name,
+                            PROPOSAL_SIGNALING.name,
                         ]),
                 )
                 .arg(
@@ -4712,6 +5211,101 @@ pub mod args {
                         .conflicts_with_all([
                             PROPOSAL_ETH.name,
                             PROPOSAL_PGF_STEWARD.name,
+                            PROPOSAL_MASP_REWARDS.name,
+                            PROPOSAL_PARAMETER_CHANGE.name,
+                            PROPOSAL_EMERGENCY_HALT.name,
+                            PROPOSAL_WASM_ALLOWLIST.name,
+                            PROPOSAL_SIGNALING.name,
+                        ]),
+                )
+                .arg(
+                    PROPOSAL_MASP_REWARDS
+                        .def()
+                        .help(
+                            "Flag if the proposal is of type masp-rewards. \
+                             Used to update the shielded rewards parameters \
+                             of tokens.",
+                        )
+                        .conflicts_with_all([
+                            PROPOSAL_ETH.name,
+                            PROPOSAL_PGF_STEWARD.name,
+                            PROPOSAL_PGF_FUNDING.name,
+                            PROPOSAL_PARAMETER_CHANGE.name,
+                            PROPOSAL_EMERGENCY_HALT.name,
+                            PROPOSAL_WASM_ALLOWLIST.name,
+                            PROPOSAL_SIGNALING.name,
+                        ]),
+                )
+                .arg(
+                    PROPOSAL_PARAMETER_CHANGE
+                        .def()
+                        .help(
+                            "Flag if the proposal is of type \
+                             parameter-change. Used to update protocol \
+                             parameters.",
+                        )
+                        .conflicts_with_all([
+                            PROPOSAL_ETH.name,
+                            PROPOSAL_PGF_STEWARD.name,
+                            PROPOSAL_PGF_FUNDING.name,
+                            PROPOSAL_MASP_REWARDS.name,
+                            PROPOSAL_EMERGENCY_HALT.name,
+                            PROPOSAL_WASM_ALLOWLIST.name,
+                            PROPOSAL_SIGNALING.name,
+                        ]),
+                )
+                .arg(
+                    PROPOSAL_EMERGENCY_HALT
+                        .def()
+                        .help(
+                            "Flag if the proposal is of type emergency-halt. \
+                             Used to pause a subsystem for a number of epochs.",
+                        )
+                        .conflicts_with_all([
+                            PROPOSAL_ETH.name,
+                            PROPOSAL_PGF_STEWARD.name,
+                            PROPOSAL_PGF_FUNDING.name,
+                            PROPOSAL_MASP_REWARDS.name,
+                            PROPOSAL_PARAMETER_CHANGE.name,
+                            PROPOSAL_WASM_ALLOWLIST.name,
+                            PROPOSAL_SIGNALING.name,
+                        ]),
+                )
+                .arg(
+                    PROPOSAL_WASM_ALLOWLIST
+                        .def()
+                        .help(
+                            "Flag if the proposal is of type wasm-allowlist. \
+                             Used to add/remove tx and vp code hashes from \
+                             the allowlists, or to set their full set of \
+                             hashes.",
+                        )
+                        .conflicts_with_all([
+                            PROPOSAL_ETH.name,
+                            PROPOSAL_PGF_STEWARD.name,
+                            PROPOSAL_PGF_FUNDING.name,
+                            PROPOSAL_MASP_REWARDS.name,
+                            PROPOSAL_PARAMETER_CHANGE.name,
+                            PROPOSAL_EMERGENCY_HALT.name,
+                            PROPOSAL_SIGNALING.name,
+                        ]),
+                )
+                .arg(
+                    PROPOSAL_SIGNALING
+                        .def()
+                        .help(
+                            "Flag if the proposal is of type signaling. Used \
+                             to record the outcome of a vote without \
+                             executing anything, with a reduced deposit.",
+                        )
+                        .conflicts_with_all([
+                            PROPOSAL_ETH.name,
+                            PROPOSAL_PGF_STEWARD.name,
+                            PROPOSAL_PGF_FUNDING.name,
+                            PROPOSAL_MASP_REWARDS.name,
+                            PROPOSAL_PARAMETER_CHANGE.name,
+                            PROPOSAL_EMERGENCY_HALT.name,
+                            PROPOSAL_WASM_ALLOWLIST.name,
                         ]),
                 )
         }
@@ -4725,6 +5319,7 @@ pub mod args {
                 vote: self.vote,
                 voter: ctx.borrow_chain_or_exit().get(&self.voter),
                 is_offline: self.is_offline,
+                is_ballot: self.is_ballot,
                 proposal_data: self.proposal_data.map(|path| {
                     std::fs::read(path)
                         .expect("Should be able to read the file.")
@@ -4741,6 +5336,7 @@ pub mod args {
             let vote = PROPOSAL_VOTE.parse(matches);
             let voter = ADDRESS.parse(matches);
             let is_offline = PROPOSAL_OFFLINE.parse(matches);
+            let is_ballot = PROPOSAL_BALLOT.parse(matches);
             let proposal_data = DATA_PATH_OPT.parse(matches);
             let tx_code_path = PathBuf::from(TX_VOTE_PROPOSAL);
 
@@ -4749,6 +5345,7 @@ pub mod args {
                 proposal_id,
                 vote,
                 is_offline,
+                is_ballot,
                 voter,
                 proposal_data,
                 tx_code_path,
@@ -4766,11 +5363,10 @@ pub mod args {
                             DATA_PATH_OPT.name,
                         ]),
                 )
-                .arg(
-                    PROPOSAL_VOTE
-                        .def()
-                        .help("The vote for the proposal. Either yay or nay."),
-                )
+                .arg(PROPOSAL_VOTE.def().help(
+                    "The vote for the proposal. Either yay, nay, abstain or \
+                     veto.",
+                ))
                 .arg(
                     PROPOSAL_OFFLINE
                         .def()
@@ -4787,10 +5383,97 @@ pub mod args {
                         .requires(PROPOSAL_OFFLINE.name)
                         .conflicts_with(PROPOSAL_ID.name),
                 )
+                .arg(
+                    PROPOSAL_BALLOT
+                        .def()
+                        .help(
+                            "Flag if the vote should be signed off-chain as a \
+                             ballot, to be submitted in a batch by any \
+                             account.",
+                        )
+                        .conflicts_with(PROPOSAL_OFFLINE.name),
+                )
                 .arg(ADDRESS.def().help("The address of the voter."))
         }
     }
 
+    impl CliToSdk<VoteProposalBatch<SdkTypes>> for VoteProposalBatch<CliTypes> {
+        fn to_sdk(self, ctx: &mut Context) -> VoteProposalBatch<SdkTypes> {
+            VoteProposalBatch::<SdkTypes> {
+                tx: self.tx.to_sdk(ctx),
+                ballots: self
+                    .ballots
+                    .into_iter()
+                    .map(|path| {
+                        std::fs::read(path)
+                            .expect("Should be able to read the file.")
+                    })
+                    .collect(),
+                tx_code_path: self.tx_code_path.to_path_buf(),
+            }
+        }
+    }
+
+    impl Args for VoteProposalBatch<CliTypes> {
+        fn parse(matches: &ArgMatches) -> Self {
+            let tx = Tx::parse(matches);
+            let ballots = BALLOTS.parse(matches);
+            let tx_code_path = PathBuf::from(TX_VOTE_PROPOSAL_BATCH);
+
+            Self {
+                tx,
+                ballots,
+                tx_code_path,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Tx<CliTypes>>().arg(BALLOTS.def().help(
+                "The signed ballots files (json) to submit, as produced by \
+                 `vote-proposal --ballot`.",
+            ))
+        }
+    }
+
+    impl CliToSdk<VoteWithdrawal<SdkTypes>> for VoteWithdrawal<CliTypes> {
+        fn to_sdk(self, ctx: &mut Context) -> VoteWithdrawal<SdkTypes> {
+            let tx = self.tx.to_sdk(ctx);
+            let chain_ctx = ctx.borrow_mut_chain_or_exit();
+            VoteWithdrawal::<SdkTypes> {
+                tx,
+                proposal_id: self.proposal_id,
+                voter: chain_ctx.get(&self.voter),
+                tx_code_path: self.tx_code_path.to_path_buf(),
+            }
+        }
+    }
+
+    impl Args for VoteWithdrawal<CliTypes> {
+        fn parse(matches: &ArgMatches) -> Self {
+            let tx = Tx::parse(matches);
+            let proposal_id = PROPOSAL_ID.parse(matches);
+            let voter = ADDRESS.parse(matches);
+            let tx_code_path = PathBuf::from(TX_WITHDRAW_VOTE);
+
+            Self {
+                tx,
+                proposal_id,
+                voter,
+                tx_code_path,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Tx<CliTypes>>()
+                .arg(PROPOSAL_ID.def().help("The proposal identifier."))
+                .arg(
+                    ADDRESS
+                        .def()
+                        .help("The address of the voter withdrawing its vote."),
+                )
+        }
+    }
+
     impl CliToSdk<RevealPk<SdkTypes>> for RevealPk<CliTypes> {
         fn to_sdk(self, ctx: &mut Context) -> RevealPk<SdkTypes> {
             let tx = self.tx.to_sdk(ctx);
@@ -5413,6 +6096,7 @@ pub mod args {
                 website: self.website,
                 discord_handle: self.discord_handle,
                 avatar: self.avatar,
+                name: self.name,
                 commission_rate: self.commission_rate,
                 tx_code_path: self.tx_code_path.to_path_buf(),
             }
@@ -5428,6 +6112,7 @@ pub mod args {
             let website = WEBSITE_OPT.parse(matches);
             let discord_handle = DISCORD_OPT.parse(matches);
             let avatar = AVATAR_OPT.parse(matches);
+            let name = VALIDATOR_NAME_OPT.parse(matches);
             let commission_rate = COMMISSION_RATE_OPT.parse(matches);
             let tx_code_path = PathBuf::from(TX_CHANGE_METADATA_WASM);
             Self {
@@ -5438,6 +6123,7 @@ pub mod args {
                 website,
                 discord_handle,
                 avatar,
+                name,
                 commission_rate,
                 tx_code_path,
             }
@@ -5470,6 +6156,10 @@ pub mod args {
                     "The desired new validator avatar url. To remove the \
                      existing avatar, pass an empty string to this argument.",
                 ))
+                .arg(VALIDATOR_NAME_OPT.def().help(
+                    "The desired new validator name. To remove the existing \
+                     name, pass an empty string to this argument.",
+                ))
                 .arg(
                     COMMISSION_RATE_OPT
                         .def()
@@ -5904,7 +6594,10 @@ pub mod args {
             Tx::<SdkTypes> {
                 dry_run: self.dry_run,
                 dry_run_wrapper: self.dry_run_wrapper,
-                dump_tx: self.dump_tx,
+                // A view-only wallet can't sign, so its txs are dumped unless
+                // they are signed with a hardware wallet
+                dump_tx: self.dump_tx
+                    || (ctx.wallet.is_view_only() && !self.use_device),
                 output_folder: self.output_folder,
                 force: self.force,
                 broadcast_only: self.broadcast_only,
@@ -5985,8 +6678,10 @@ pub mod args {
             ))
             .arg(FEE_TOKEN.def().help("The token for paying the gas"))
             .arg(FEE_UNSHIELD_SPENDING_KEY.def().help(
-                "The spending key to be used for fee unshielding. If none is \
-                 provided, fee will be paid from the unshielded balance only.",
+                "The spending key to be used for fee unshielding. The fee of \
+                 any transaction can be unshielded, so that no transparent \
+                 balance is required. If none is provided, fee will be paid \
+                 from the unshielded balance only.",
             ))
             .arg(GAS_LIMIT.def().help(
                 "The multiplier of the gas limit resolution defining the \
@@ -6582,6 +7277,73 @@ pub mod args {
         }
     }
 
+    impl Args for KeyChangePassword {
+        fn parse(matches: &ArgMatches) -> Self {
+            let alias = ALIAS.parse(matches);
+            let unsafe_dont_encrypt = UNSAFE_DONT_ENCRYPT.parse(matches);
+            Self {
+                alias,
+                unsafe_dont_encrypt,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(ALIAS.def().help("The alias of the key.")).arg(
+                UNSAFE_DONT_ENCRYPT.def().help(
+                    "UNSAFE: Do not encrypt the key anymore. Do not use this \
+                     for keys used in a live network.",
+                ),
+            )
+        }
+    }
+
+    impl Args for KeyAddExternal {
+        fn parse(matches: &ArgMatches) -> Self {
+            let alias = ALIAS.parse(matches);
+            let alias_force = ALIAS_FORCE.parse(matches);
+            let public_key = RAW_PUBLIC_KEY.parse(matches);
+            let backend = KEY_BACKEND.parse(matches);
+            Self {
+                alias,
+                alias_force,
+                public_key,
+                backend,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(ALIAS.def().help("An alias to be associated with the key."))
+                .arg(ALIAS_FORCE.def().help(
+                    "Override the alias without confirmation if it already \
+                     exists.",
+                ))
+                .arg(RAW_PUBLIC_KEY.def().help("The public key of the key."))
+                .arg(KEY_BACKEND.def().help(
+                    "The backend holding the secret key. Either \
+                     keychain:<service> for a key stored in the keychain of \
+                     the OS under the given service and the alias of the key, \
+                     or remote:<auth key alias>@<endpoint> for a key held by \
+                     a remote signer at a tcp://<host:port> or unix://<path> \
+                     endpoint, with the requests authenticated by the key of \
+                     the wallet with the given alias.",
+                ))
+        }
+    }
+
+    impl Args for KeyExportViewOnly {
+        fn parse(matches: &ArgMatches) -> Self {
+            let output_folder = OUTPUT_FOLDER_PATH.parse(matches);
+            Self { output_folder }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(OUTPUT_FOLDER_PATH.def().help(
+                "The folder in which the view-only wallet file is written. \
+                 Defaults to the current directory.",
+            ))
+        }
+    }
+
     impl Args for KeyImport {
         fn parse(matches: &ArgMatches) -> Self {
             let file_path = FILE_PATH.parse(matches);
@@ -11,14 +11,13 @@ use itertools::sorted;
 use ledger_namada_rs::{BIP44Path, NamadaApp};
 use ledger_transport_hid::hidapi::HidApi;
 use ledger_transport_hid::TransportNativeHID;
-use masp_primitives::zip32::ExtendedFullViewingKey;
 use namada::types::address::{Address, DecodeError};
 use namada::types::io::Io;
 use namada::types::key::*;
 use namada::types::masp::{ExtendedSpendingKey, MaspValue, PaymentAddress};
-use namada_sdk::masp::find_valid_diversifier;
 use namada_sdk::wallet::{
     DecryptionError, DerivationPath, DerivationPathError, FindKeyError, Wallet,
+    WalletIo,
 };
 use namada_sdk::{display_line, edisplay_line};
 use rand_core::OsRng;
@@ -54,6 +53,15 @@ impl CliApi {
             cmds::NamadaWallet::KeyExport(cmds::WalletExportKey(args)) => {
                 key_export(ctx, io, args)
             }
+            cmds::NamadaWallet::KeyExportViewOnly(
+                cmds::WalletExportViewOnly(args),
+            ) => key_export_view_only(ctx, io, args),
+            cmds::NamadaWallet::KeyChangePassword(
+                cmds::WalletChangePassword(args),
+            ) => key_change_password(ctx, io, args),
+            cmds::NamadaWallet::KeyAddExternal(cmds::WalletAddExternalKey(
+                args,
+            )) => key_add_external(ctx, io, args),
             cmds::NamadaWallet::KeyImport(cmds::WalletImportKey(args)) => {
                 key_import(ctx, io, args)
             }
@@ -315,17 +323,8 @@ fn payment_address_gen(
 ) {
     let mut wallet = load_wallet(ctx);
     let alias = alias.to_lowercase();
-    let viewing_key = ExtendedFullViewingKey::from(viewing_key).fvk.vk;
-    let (div, _g_d) = find_valid_diversifier(&mut OsRng);
-    let payment_addr = viewing_key
-        .to_payment_address(div)
-        .expect("a PaymentAddress");
-    let alias = wallet
-        .insert_payment_addr(
-            alias,
-            PaymentAddress::from(payment_addr).pinned(pin),
-            alias_force,
-        )
+    let (alias, _payment_addr) = wallet
+        .derive_payment_addr(alias, viewing_key, pin, alias_force)
         .unwrap_or_else(|| {
             edisplay_line!(io, "Payment address not added");
             cli::safe_exit(1);
@@ -1243,6 +1242,90 @@ fn key_export(
         })
 }
 
+/// Export a view-only copy of the wallet into a directory.
+fn key_export_view_only(
+    ctx: Context,
+    io: &impl Io,
+    args::KeyExportViewOnly { output_folder }: args::KeyExportViewOnly,
+) {
+    let wallet = load_wallet(ctx);
+    let output_folder = output_folder.unwrap_or_default();
+    let mut view_only = CliWalletUtils::new(output_folder.clone());
+    *view_only.store_mut() = wallet.store().to_view_only();
+    view_only.save().unwrap_or_else(|err| {
+        edisplay_line!(io, "{}", err);
+        cli::safe_exit(1)
+    });
+    display_line!(
+        io,
+        "Exported a view-only wallet to {}",
+        output_folder.join("wallet.toml").to_string_lossy()
+    );
+}
+
+/// Change the password of a transparent keypair / MASP spending key, which
+/// also migrates the keys encrypted by a previous version of the wallet.
+fn key_change_password(
+    ctx: Context,
+    io: &impl Io,
+    args::KeyChangePassword {
+        alias,
+        unsafe_dont_encrypt,
+    }: args::KeyChangePassword,
+) {
+    let alias = alias.to_lowercase();
+    let mut wallet = load_wallet(ctx);
+    let is_encrypted = wallet
+        .is_encrypted_secret_key(&alias)
+        .or_else(|| wallet.is_encrypted_spending_key(&alias))
+        .unwrap_or_else(|| {
+            edisplay_line!(io, "No key matching {} found", alias);
+            cli::safe_exit(1)
+        });
+    let password = is_encrypted.then(|| CliWalletUtils::read_password(false));
+    let new_password =
+        read_and_confirm_encryption_password(unsafe_dont_encrypt);
+    wallet
+        .change_key_password(&alias, password, new_password)
+        .unwrap_or_else(|err| {
+            edisplay_line!(io, "{}", err);
+            cli::safe_exit(1)
+        });
+    wallet
+        .save()
+        .unwrap_or_else(|err| edisplay_line!(io, "{}", err));
+    display_line!(io, "Changed the password of the key {}", alias);
+}
+
+/// Add a transparent key held outside of the wallet by a backend.
+fn key_add_external(
+    ctx: Context,
+    io: &impl Io,
+    args::KeyAddExternal {
+        alias,
+        alias_force,
+        public_key,
+        backend,
+    }: args::KeyAddExternal,
+) {
+    let mut wallet = load_wallet(ctx);
+    let alias = wallet
+        .insert_external_key(alias, public_key, backend.clone(), alias_force)
+        .unwrap_or_else(|| {
+            edisplay_line!(io, "Key not added");
+            cli::safe_exit(1);
+        });
+    wallet
+        .save()
+        .unwrap_or_else(|err| edisplay_line!(io, "{}", err));
+    display_line!(
+        io,
+        "Successfully added a key held by {} with alias: \"{}\"",
+        backend,
+        alias
+    );
+}
+
 /// Import a transparent keypair / MASP spending key from a file.
 fn key_import(
     ctx: Context,
@@ -21,19 +21,25 @@ use namada::governance::cli::offline::{
 };
 use namada::governance::parameters::GovernanceParameters;
 use namada::governance::pgf::parameters::PgfParameters;
-use namada::governance::pgf::storage::steward::StewardDetail;
+use namada::governance::pgf::storage::grant::GrantDetail;
+use namada::governance::pgf::storage::steward::{
+    StewardDetail, StewardSpending,
+};
 use namada::governance::storage::keys as governance_storage;
 use namada::governance::storage::proposal::{
     StoragePgfFunding, StorageProposal,
 };
 use namada::governance::utils::{
-    compute_proposal_result, ProposalVotes, TallyType, TallyVote, VotePower,
+    compute_proposal_result, ProposalTally, ProposalVotes, TallyType,
+    TallyVote, VotePower,
 };
 use namada::ledger::events::Event;
 use namada::ledger::ibc::storage::{
     ibc_denom_key, ibc_denom_key_prefix, is_ibc_denom_key,
 };
-use namada::ledger::parameters::{storage as param_storage, EpochDuration};
+use namada::ledger::parameters::{
+    storage as param_storage, EpochDuration, InflationSplit, WasmLimits,
+};
 use namada::ledger::pos::types::{CommissionPair, Slash};
 use namada::ledger::pos::PosParams;
 use namada::ledger::queries::RPC;
@@ -87,6 +93,24 @@ pub async fn query_and_print_epoch(context: &impl Namada) -> Epoch {
     epoch
 }
 
+/// Load the on-chain names of the validators into the address book, to render
+/// them with their names. Failing to load them only falls back to displaying
+/// their addresses.
+async fn load_validator_names(context: &impl Namada, epoch: Epoch) {
+    if let Err(err) = context
+        .address_book_mut()
+        .await
+        .load_validator_names(context.client(), epoch)
+        .await
+    {
+        edisplay_line!(
+            context.io(),
+            "Couldn't load the names of the validators: {}",
+            err
+        );
+    }
+}
+
 /// Query the last committed block
 pub async fn query_block(context: &impl Namada) {
     let block = namada_sdk::rpc::query_block(context.client())
@@ -602,9 +626,8 @@ async fn print_balances(
     for (key, balance) in balances {
         // Get the token, the owner, and the balance with the token and the
         // owner
-        let (t, o, s) = match token::storage_key::is_any_token_balance_key(&key)
-        {
-            Some([tok, owner]) => (
+        let (t, o, s) = match token::storage_key::is_any_balance_key(&key) {
+            Some((tok, owner)) => (
                 tok.clone(),
                 owner.clone(),
                 format!(
@@ -817,6 +840,18 @@ pub async fn query_proposal(context: &impl Namada, args: args::QueryProposal) {
                 "{}",
                 proposal.to_string_with_status(current_epoch)
             );
+            let diffs = namada_sdk::rpc::query_proposal_parameter_diff(
+                context.client(),
+                id,
+            )
+            .await
+            .unwrap();
+            if let Some(diffs) = diffs {
+                display_line!(context.io(), "Parameter changes:");
+                for diff in diffs {
+                    display_line!(context.io(), "{:2}{}", "", diff);
+                }
+            }
         } else {
             edisplay_line!(context.io(), "No proposal found with id: {}", id);
         }
@@ -1213,6 +1248,14 @@ pub async fn query_proposal_result(
         if let Ok(Some(proposal_result)) = proposal_result {
             display_line!(context.io(), "Proposal Id: {} ", proposal_id);
             display_line!(context.io(), "{:4}{}", "", proposal_result);
+            if let Ok(Some(tally)) = namada_sdk::rpc::query_proposal_tally(
+                context.client(),
+                proposal_id,
+            )
+            .await
+            {
+                display_tally(context, &tally);
+            }
         } else {
             edisplay_line!(context.io(), "Proposal {} not found.", proposal_id);
         };
@@ -1302,6 +1345,33 @@ pub async fn query_proposal_result(
     }
 }
 
+fn display_tally(context: &impl Namada, tally: &ProposalTally) {
+    display_line!(
+        context.io(),
+        "{:4}Tally at epoch {}:",
+        "",
+        tally.tally_epoch
+    );
+    display_line!(context.io(), "{:6}Validators: {}", "", tally.validators);
+    display_line!(context.io(), "{:6}Delegators: {}", "", tally.delegators);
+    let voted_power = tally.projected_result.total_voted_power();
+    match tally.projected_result.quorum_power() {
+        Some(quorum_power) => display_line!(
+            context.io(),
+            "{:6}Quorum: {} of {} voting power required",
+            "",
+            voted_power.to_string_native(),
+            quorum_power.to_string_native()
+        ),
+        None => display_line!(
+            context.io(),
+            "{:6}Quorum: not required, {} voting power voted",
+            "",
+            voted_power.to_string_native()
+        ),
+    }
+}
+
 pub async fn query_account(context: &impl Namada, args: args::QueryAccount) {
     let account = rpc::get_account_info(context.client(), &args.owner)
         .await
@@ -1321,6 +1391,7 @@ pub async fn query_account(context: &impl Namada, args: args::QueryAccount) {
 pub async fn query_pgf(context: &impl Namada, _args: args::QueryPgf) {
     let stewards = query_pgf_stewards(context.client()).await;
     let fundings = query_pgf_fundings(context.client()).await;
+    let grants = query_pgf_grants(context.client()).await;
 
     match stewards.is_empty() {
         true => {
@@ -1333,6 +1404,25 @@ pub async fn query_pgf(context: &impl Namada, _args: args::QueryPgf) {
             display_line!(context.io(), "Pgf stewards:");
             for steward in stewards {
                 display_line!(context.io(), "{:4}- {}", "", steward.address);
+                let spending = query_pgf_steward_spending(
+                    context.client(),
+                    &steward.address,
+                )
+                .await;
+                if let Some(cap) = spending.cap {
+                    display_line!(
+                        context.io(),
+                        "{:4}  Spending cap: {}",
+                        "",
+                        cap.to_string_native()
+                    );
+                }
+                display_line!(
+                    context.io(),
+                    "{:4}  Spent: {}",
+                    "",
+                    spending.spent.to_string_native()
+                );
                 display_line!(context.io(), "{:4}  Reward distribution:", "");
                 for (address, percentage) in steward.reward_distribution {
                     display_line!(
@@ -1364,6 +1454,61 @@ pub async fn query_pgf(context: &impl Namada, _args: args::QueryPgf) {
                     funding.detail.target(),
                     funding.detail.amount().to_string_native()
                 );
+                if let Some(end_epoch) = funding.end_epoch {
+                    display_line!(
+                        context.io(),
+                        "{:4}  Stream end epoch: {}",
+                        "",
+                        end_epoch
+                    );
+                }
+                display_line!(
+                    context.io(),
+                    "{:4}  Disbursed: {}",
+                    "",
+                    funding.disbursed.to_string_native()
+                );
+            }
+        }
+    }
+
+    match grants.is_empty() {
+        true => {
+            display_line!(
+                context.io(),
+                "Pgf grants: no grants are currently set."
+            )
+        }
+        false => {
+            display_line!(context.io(), "Pgf grants:");
+            for (grant_id, grant) in grants {
+                display_line!(
+                    context.io(),
+                    "{:4}- {} for {} (proposal {})",
+                    "",
+                    grant_id,
+                    grant.target,
+                    grant.proposal_id
+                );
+                for (milestone, amount) in grant.milestones.iter().enumerate() {
+                    let status = match grant.attestations.get(milestone) {
+                        Some(steward)
+                            if (milestone as u64) < grant.released =>
+                        {
+                            format!("released, attested by {}", steward)
+                        }
+                        Some(steward) => format!("attested by {}", steward),
+                        None => "pending".to_string(),
+                    };
+                    display_line!(
+                        context.io(),
+                        "{:6}- Milestone {}: {} ({})",
+                        "",
+                        milestone,
+                        amount.to_string_native(),
+                        status
+                    );
+                }
             }
         }
     }
@@ -1382,6 +1527,14 @@ pub async fn query_protocol_parameters(
         "",
         governance_parameters.min_proposal_fund.to_string_native()
     );
+    display_line!(
+        context.io(),
+        "{:4}Min. signaling proposal fund: {}",
+        "",
+        governance_parameters
+            .min_signaling_proposal_fund
+            .to_string_native()
+    );
     display_line!(
         context.io(),
         "{:4}Max. proposal code size: {}",
@@ -1412,6 +1565,36 @@ pub async fn query_protocol_parameters(
         "",
         governance_parameters.min_proposal_grace_epochs
     );
+    display_line!(
+        context.io(),
+        "{:4}Veto threshold: {}",
+        "",
+        governance_parameters.veto_threshold
+    );
+    display_line!(
+        context.io(),
+        "{:4}Expedited voting period: {}",
+        "",
+        governance_parameters.expedited_voting_period
+    );
+    display_line!(
+        context.io(),
+        "{:4}Expedited quorum: {}",
+        "",
+        governance_parameters.expedited_quorum
+    );
+    display_line!(
+        context.io(),
+        "{:4}Expedited threshold: {}",
+        "",
+        governance_parameters.expedited_threshold
+    );
+    display_line!(
+        context.io(),
+        "{:4}Deposit policy: {}",
+        "",
+        governance_parameters.deposit_policy
+    );
 
     let pgf_parameters = query_pgf_parameters(context.client()).await;
     display_line!(context.io(), "Public Goods Funding Parameters\n");
@@ -1475,6 +1658,36 @@ pub async fn query_protocol_parameters(
         tx_allowlist
     );
 
+    let key = param_storage::get_native_tx_allowlist_storage_key();
+    let native_tx_allowlist: Vec<String> =
+        query_storage_value(context.client(), &key)
+            .await
+            .unwrap_or_default();
+    display_line!(
+        context.io(),
+        "{:4}Native transactions allowlist: {:?}",
+        "",
+        native_tx_allowlist
+    );
+
+    let key = param_storage::get_sub_tx_allowlist_storage_key();
+    let sub_tx_allowlist: Vec<String> =
+        query_storage_value(context.client(), &key)
+            .await
+            .unwrap_or_default();
+    display_line!(
+        context.io(),
+        "{:4}Sub-transactions allowlist: {:?}",
+        "",
+        sub_tx_allowlist
+    );
+
+    let key = param_storage::get_wasm_limits_key();
+    let wasm_limits: WasmLimits = query_storage_value(context.client(), &key)
+        .await
+        .unwrap_or_default();
+    display_line!(context.io(), "{:4}Wasm limits: {}", "", wasm_limits);
+
     let key = param_storage::get_max_block_gas_key();
     let max_block_gas: u64 = query_storage_value(context.client(), &key)
         .await
@@ -1515,6 +1728,18 @@ pub async fn query_protocol_parameters(
         display_line!(context.io(), "{:8}{}: {:?}", "", token, gas_cost);
     }
 
+    let (inflation_split, inflation_split_history) =
+        query_inflation_split(context.client()).await;
+    match inflation_split {
+        Some(split) => {
+            display_line!(context.io(), "{:4}Inflation split: {}", "", split)
+        }
+        None => display_line!(context.io(), "{:4}Inflation split: none", ""),
+    }
+    for (epoch, split) in inflation_split_history {
+        display_line!(context.io(), "{:8}Since epoch {}: {}", "", epoch, split);
+    }
+
     display_line!(context.io(), "PoS parameters");
     let pos_params = query_pos_parameters(context.client()).await;
     display_line!(
@@ -1609,18 +1834,43 @@ pub async fn query_consensus_keys<C: namada::ledger::queries::Client + Sync>(
     )
 }
 
+pub async fn query_inflation_split<
+    C: namada::ledger::queries::Client + Sync,
+>(
+    client: &C,
+) -> (Option<InflationSplit>, BTreeMap<Epoch, InflationSplit>) {
+    unwrap_client_response::<C, _>(RPC.shell().inflation_split(client).await)
+}
+
 pub async fn query_pgf_stewards<C: namada::ledger::queries::Client + Sync>(
     client: &C,
 ) -> Vec<StewardDetail> {
     unwrap_client_response::<C, _>(RPC.vp().pgf().stewards(client).await)
 }
 
+pub async fn query_pgf_steward_spending<
+    C: namada::ledger::queries::Client + Sync,
+>(
+    client: &C,
+    steward: &Address,
+) -> StewardSpending {
+    unwrap_client_response::<C, _>(
+        RPC.vp().pgf().steward_spending(client, steward).await,
+    )
+}
+
 pub async fn query_pgf_fundings<C: namada::ledger::queries::Client + Sync>(
     client: &C,
 ) -> Vec<StoragePgfFunding> {
     unwrap_client_response::<C, _>(RPC.vp().pgf().funding(client).await)
 }
 
+pub async fn query_pgf_grants<C: namada::ledger::queries::Client + Sync>(
+    client: &C,
+) -> Vec<(u64, GrantDetail)> {
+    unwrap_client_response::<C, _>(RPC.vp().pgf().grants(client).await)
+}
+
 pub async fn query_pgf_parameters<C: namada::ledger::queries::Client + Sync>(
     client: &C,
 ) -> PgfParameters {
@@ -1703,15 +1953,15 @@ pub async fn query_bonds(
     )
     .await
     .unwrap();
+    load_validator_names(context, epoch).await;
 
     for (bond_id, details) in &bonds_and_unbonds.data {
+        let source = context.lookup_address(&bond_id.source).await;
+        let validator = context.lookup_address(&bond_id.validator).await;
         let bond_type = if bond_id.source == bond_id.validator {
-            format!("Self-bonds from {}", bond_id.validator)
+            format!("Self-bonds from {}", validator)
         } else {
-            format!(
-                "Delegations from {} to {}",
-                bond_id.source, bond_id.validator
-            )
+            format!("Delegations from {} to {}", source, validator)
         };
         display_line!(context.io(), &mut w; "{}:", bond_type)?;
         for bond in &details.data.bonds {
@@ -1736,9 +1986,9 @@ pub async fn query_bonds(
 
         if !details.data.unbonds.is_empty() {
             let bond_type = if bond_id.source == bond_id.validator {
-                format!("Unbonded self-bonds from {}", bond_id.validator)
+                format!("Unbonded self-bonds from {}", validator)
             } else {
-                format!("Unbonded delegations from {}", bond_id.source)
+                format!("Unbonded delegations from {}", source)
             };
             display_line!(context.io(), &mut w; "{}:", bond_type)?;
             for unbond in &details.data.unbonds {
@@ -1818,10 +2068,11 @@ pub async fn query_bonded_stake<N: Namada>(
 
     match args.validator {
         Some(validator) => {
-            let validator = validator;
             // Find bonded stake for the given validator
             let stake =
                 get_validator_stake(context.client(), epoch, &validator).await;
+            load_validator_names(context, epoch).await;
+            let validator = context.lookup_address(&validator).await;
             match stake {
                 Some(stake) => {
                     // TODO: show if it's in consensus set, below capacity, or
@@ -1858,6 +2109,9 @@ pub async fn query_bonded_stake<N: Namada>(
                         )
                         .await,
                 );
+            load_validator_names(context, epoch).await;
+            let address_book = context.address_book().await;
+            let wallet = context.wallet().await;
 
             // Iterate all validators
             let stdout = io::stdout();
@@ -1870,7 +2124,7 @@ pub async fn query_bonded_stake<N: Namada>(
                     context.io(),
                     &mut w;
                     "  {}: {}",
-                    val.address.encode(),
+                    address_book.lookup(&wallet, &val.address),
                     val.bonded_stake.to_string_native()
                 )
                 .unwrap();
@@ -1883,7 +2137,7 @@ pub async fn query_bonded_stake<N: Namada>(
                         context.io(),
                         &mut w;
                         "  {}: {}",
-                        val.address.encode(),
+                        address_book.lookup(&wallet, &val.address),
                         val.bonded_stake.to_string_native()
                     )
                     .unwrap();
@@ -2050,6 +2304,7 @@ pub async fn query_and_print_metadata(
             website,
             discord_handle,
             avatar,
+            name,
         }) => {
             display_line!(
                 context.io(),
@@ -2057,6 +2312,11 @@ pub async fn query_and_print_metadata(
                 validator.encode(),
                 email
             );
+            if let Some(name) = name {
+                display_line!(context.io(), "Name: {}", name);
+            } else {
+                display_line!(context.io(), "No name");
+            }
             if let Some(description) = description {
                 display_line!(context.io(), "Description: {}", description);
             } else {
@@ -2297,9 +2557,12 @@ pub async fn query_delegations<N: Namada>(
     if delegations.is_empty() {
         display_line!(context.io(), "No delegations found");
     } else {
+        let epoch = query_epoch(context.client()).await.unwrap();
+        load_validator_names(context, epoch).await;
         display_line!(context.io(), "Found delegations to:");
         for delegation in delegations {
-            display_line!(context.io(), "  {delegation}");
+            let name = context.lookup_address(&delegation).await;
+            display_line!(context.io(), "  {name}");
         }
     }
 }
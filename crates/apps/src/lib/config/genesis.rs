@@ -1,6 +1,7 @@
 //! The parameters used for the chain's genesis
 
 pub mod chain;
+pub mod export;
 pub mod templates;
 pub mod transactions;
 pub mod utils;
@@ -469,6 +470,7 @@ pub fn make_dev_genesis(
                     website: None,
                     discord_handle: None,
                     avatar: None,
+                    name: None,
                 },
                 net_address: SocketAddr::new(
                     IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)),
@@ -379,19 +379,33 @@ impl Finalized {
     ) -> namada::governance::parameters::GovernanceParameters {
         let templates::GovernanceParams {
             min_proposal_fund,
+            min_signaling_proposal_fund,
             max_proposal_code_size,
             min_proposal_voting_period,
             max_proposal_period,
             max_proposal_content_size,
             min_proposal_grace_epochs,
+            veto_threshold,
+            expedited_voting_period,
+            expedited_quorum,
+            expedited_threshold,
+            deposit_policy,
         } = self.parameters.gov_params.clone();
         namada::governance::parameters::GovernanceParameters {
             min_proposal_fund: Amount::native_whole(min_proposal_fund),
+            min_signaling_proposal_fund: Amount::native_whole(
+                min_signaling_proposal_fund,
+            ),
             max_proposal_code_size,
             max_proposal_period,
             max_proposal_content_size,
             min_proposal_grace_epochs,
             min_proposal_voting_period,
+            veto_threshold,
+            expedited_voting_period,
+            expedited_quorum,
+            expedited_threshold,
+            deposit_policy,
         }
     }
 
@@ -0,0 +1,638 @@
+//! Export the state of a chain at a committed block height into genesis
+//! templates, which can be used to restart the chain from its state, e.g. for
+//! a hard fork.
+//!
+//! The templates are derived from the chain's finalized genesis. The VPs, the
+//! tokens and the established accounts are kept as they were in genesis, so
+//! that their addresses are derived the same again. The balances, the
+//! parameters, the validators and the bonds are read from the state.
+
+use std::collections::{BTreeMap, HashMap};
+use std::marker::PhantomData;
+use std::path::Path;
+
+use namada::governance::pgf::storage::get_stewards;
+use namada::ledger::pos::types::ValidatorMetaData;
+use namada::proof_of_stake::queries::bonds_and_unbonds;
+use namada::proof_of_stake::storage::{
+    read_all_validator_addresses, read_pos_params, read_validator_avatar,
+    read_validator_description, read_validator_discord_handle,
+    read_validator_email, read_validator_max_commission_rate_change,
+    read_validator_name, read_validator_website,
+    validator_commission_rate_handle, validator_consensus_key_handle,
+};
+use namada::proof_of_stake::types::BondId;
+use namada::proof_of_stake::{query_reward_tokens, ADDRESS as POS_ADDRESS};
+use namada::state::{iter_prefix, StorageRead, StorageResult};
+use namada::token::storage_key::{
+    balance_prefix, is_balance_key, read_masp_kd_gain, read_masp_kp_gain,
+    read_masp_locked_amount_target, read_masp_max_reward_rate,
+};
+use namada::types::address::Address;
+use namada::types::string_encoding::StringEncoded;
+use namada::types::token::{self, DenominatedAmount, MaspParams};
+use namada::types::uint::Uint;
+use serde::{Deserialize, Serialize};
+
+use super::chain::{
+    Finalized, FinalizedEstablishedAccountTx, FinalizedTokenConfig,
+    FinalizedValidatorAccountTx,
+};
+use super::templates::{self, Unvalidated};
+use super::transactions::{
+    BondTx, SignedValidatorAccountTx, Transactions, UnsignedTransactions,
+    UnsignedValidatorAccountTx, ValidatorAccountTx,
+};
+use super::utils::write_toml;
+use super::GenesisAddress;
+use crate::wallet::Alias;
+
+pub const UNSIGNED_TRANSACTIONS_FILE_NAME: &str = "unsigned-transactions.toml";
+pub const SKIPPED_FILE_NAME: &str = "skipped.toml";
+
+/// Genesis templates exported from the state of a chain
+#[derive(Clone, Debug)]
+pub struct ExportedGenesis {
+    /// The templates. Their transactions contain the established accounts
+    /// and the validator accounts that didn't change since genesis, with
+    /// their original signatures.
+    pub templates: templates::All<Unvalidated>,
+    /// The txs that have to be signed by their owners with
+    /// `sign-genesis-txs` and merged into the templates' transactions: the
+    /// validator accounts that changed since genesis and all the bonds. The
+    /// established accounts are included for the signers' keys.
+    pub unsigned_txs: UnsignedTransactions,
+    /// The parts of the state that cannot be re-created in genesis
+    pub skipped: Skipped,
+}
+
+/// The parts of the state left out of an [`ExportedGenesis`], each described
+/// in a line
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Skipped {
+    /// The balances of the owners that don't have a genesis address, i.e.
+    /// internal addresses, implicit addresses without a revealed PK and
+    /// established accounts that weren't created in genesis
+    pub balances: Vec<String>,
+    /// The validators that joined after genesis, whose bonds were credited
+    /// to their sources' balances, and the consensus keys that changed since
+    /// genesis
+    pub validators: Vec<String>,
+    /// The bonds of sources without a genesis address
+    pub bonds: Vec<String>,
+    /// The parameters that changed since genesis but cannot be exported
+    pub parameters: Vec<String>,
+}
+
+impl Skipped {
+    /// Check if nothing was left out
+    pub fn is_empty(&self) -> bool {
+        let Skipped {
+            balances,
+            validators,
+            bonds,
+            parameters,
+        } = self;
+        balances.is_empty()
+            && validators.is_empty()
+            && bonds.is_empty()
+            && parameters.is_empty()
+    }
+}
+
+impl ExportedGenesis {
+    /// Write the templates, the unsigned txs and the skipped parts of the
+    /// state to the given directory.
+    pub fn write_toml_files(&self, output_dir: &Path) -> eyre::Result<()> {
+        self.templates.write_toml_files(output_dir)?;
+        write_toml(
+            &self.unsigned_txs,
+            &output_dir.join(UNSIGNED_TRANSACTIONS_FILE_NAME),
+            "Unsigned transactions",
+        )?;
+        write_toml(
+            &self.skipped,
+            &output_dir.join(SKIPPED_FILE_NAME),
+            "Skipped state",
+        )
+    }
+}
+
+/// Export the state into genesis templates derived from the chain's
+/// finalized genesis. The state is read at its current block height and
+/// epoch.
+pub fn export_genesis<S>(
+    storage: &S,
+    genesis: &Finalized,
+) -> StorageResult<ExportedGenesis>
+where
+    S: StorageRead,
+{
+    let mut skipped = Skipped::default();
+    let accounts = genesis_accounts(genesis);
+    let mut balances =
+        read_balances(storage, genesis, &accounts, &mut skipped)?;
+    let (signed_validators, unsigned_validators) =
+        read_validators(storage, genesis, &mut skipped)?;
+    let bonds =
+        read_bonds(storage, genesis, &accounts, &mut balances, &mut skipped)?;
+    let parameters = read_parameters(storage, genesis, &mut skipped)?;
+    let tokens = read_tokens(storage, genesis)?;
+
+    let balances = templates::UndenominatedBalances {
+        token: balances
+            .into_iter()
+            .map(|(alias, balances)| {
+                let denom = tokens.token[&alias].denom;
+                let balances = balances
+                    .into_iter()
+                    .map(|(owner, amount)| {
+                        (owner, DenominatedAmount::new(amount, denom))
+                    })
+                    .collect();
+                (alias, templates::RawTokenBalances(balances))
+            })
+            .collect(),
+    };
+    let established_account = genesis
+        .transactions
+        .established_account
+        .as_ref()
+        .map(|txs| {
+            txs.iter()
+                .map(|FinalizedEstablishedAccountTx { tx, .. }| tx.clone())
+                .collect::<Vec<_>>()
+        });
+    let templates = templates::All {
+        vps: genesis.vps.clone(),
+        tokens,
+        balances,
+        parameters,
+        transactions: Transactions {
+            established_account: established_account.clone(),
+            validator_account: Some(signed_validators),
+            bond: None,
+        },
+    };
+    let unsigned_txs = UnsignedTransactions {
+        established_account,
+        validator_account: Some(unsigned_validators),
+        bond: Some(bonds),
+    };
+    Ok(ExportedGenesis {
+        templates,
+        unsigned_txs,
+        skipped,
+    })
+}
+
+/// The genesis addresses of the accounts created in genesis
+fn genesis_accounts(genesis: &Finalized) -> HashMap<Address, GenesisAddress> {
+    let balance_owners = genesis
+        .balances
+        .token
+        .values()
+        .flat_map(|balances| balances.0.keys().cloned());
+    let established_accounts = genesis
+        .transactions
+        .established_account
+        .iter()
+        .flatten()
+        .filter_map(|FinalizedEstablishedAccountTx { address, .. }| {
+            match address {
+                Address::Established(address) => {
+                    Some(GenesisAddress::EstablishedAddress(address.clone()))
+                }
+                _ => None,
+            }
+        });
+    let validators =
+        genesis.transactions.validator_account.iter().flatten().map(
+            |FinalizedValidatorAccountTx { tx }| {
+                GenesisAddress::EstablishedAddress(tx.data.address.raw.clone())
+            },
+        );
+    let bond_sources = genesis
+        .transactions
+        .bond
+        .iter()
+        .flatten()
+        .map(|bond| bond.source.clone());
+    balance_owners
+        .chain(established_accounts)
+        .chain(validators)
+        .chain(bond_sources)
+        .map(|genesis_address| (genesis_address.address(), genesis_address))
+        .collect()
+}
+
+/// Find the genesis address of an account created in genesis or of an
+/// implicit account with a revealed PK
+fn find_genesis_address<S>(
+    storage: &S,
+    accounts: &HashMap<Address, GenesisAddress>,
+    owner: &Address,
+) -> StorageResult<Option<GenesisAddress>>
+where
+    S: StorageRead,
+{
+    if let Some(genesis_address) = accounts.get(owner) {
+        return Ok(Some(genesis_address.clone()));
+    }
+    if let Address::Implicit(_) = owner {
+        let pks = namada::account::public_keys(storage, owner)?;
+        return Ok(pks
+            .into_iter()
+            .find(|pk| &Address::from(pk) == owner)
+            .map(|pk| GenesisAddress::PublicKey(StringEncoded::new(pk))));
+    }
+    Ok(None)
+}
+
+/// Read the balances of the genesis tokens
+fn read_balances<S>(
+    storage: &S,
+    genesis: &Finalized,
+    accounts: &HashMap<Address, GenesisAddress>,
+    skipped: &mut Skipped,
+) -> StorageResult<BTreeMap<Alias, BTreeMap<GenesisAddress, token::Amount>>>
+where
+    S: StorageRead,
+{
+    let mut balances = BTreeMap::new();
+    for (alias, FinalizedTokenConfig { address: token, .. }) in
+        &genesis.tokens.token
+    {
+        let token_balances: &mut BTreeMap<_, _> =
+            balances.entry(alias.clone()).or_default();
+        for result in
+            iter_prefix::<token::Amount>(storage, &balance_prefix(token))?
+        {
+            let (key, amount) = result?;
+            let Some(owner) = is_balance_key(token, &key) else {
+                continue;
+            };
+            // The tokens held by PoS are exported with the bonds
+            if amount.is_zero() || owner == &POS_ADDRESS {
+                continue;
+            }
+            match find_genesis_address(storage, accounts, owner)? {
+                Some(genesis_address) => {
+                    *token_balances.entry(genesis_address).or_default() +=
+                        amount;
+                }
+                None => skipped.balances.push(format!(
+                    "{} {alias} of {owner}",
+                    amount.to_string_native()
+                )),
+            }
+        }
+    }
+    Ok(balances)
+}
+
+/// Read the validators created in genesis. The validators whose commission
+/// or metadata changed have to sign their account txs again, so they are
+/// returned separately.
+fn read_validators<S>(
+    storage: &S,
+    genesis: &Finalized,
+    skipped: &mut Skipped,
+) -> StorageResult<(
+    Vec<SignedValidatorAccountTx>,
+    Vec<UnsignedValidatorAccountTx>,
+)>
+where
+    S: StorageRead,
+{
+    let params = read_pos_params(storage)?;
+    let epoch = storage.get_block_epoch()?;
+    let mut validators = read_all_validator_addresses(storage, epoch)?;
+
+    let mut signed = vec![];
+    let mut unsigned = vec![];
+    for FinalizedValidatorAccountTx { tx } in
+        genesis.transactions.validator_account.iter().flatten()
+    {
+        let address = Address::Established(tx.data.address.raw.clone());
+        if !validators.remove(&address) {
+            continue;
+        }
+        let commission_rate = validator_commission_rate_handle(&address)
+            .get(storage, epoch, &params)?
+            .unwrap_or(tx.data.commission_rate);
+        let max_commission_rate_change =
+            read_validator_max_commission_rate_change(storage, &address)?
+                .unwrap_or(tx.data.max_commission_rate_change);
+        let metadata = ValidatorMetaData {
+            email: read_validator_email(storage, &address)?
+                .unwrap_or_else(|| tx.data.metadata.email.clone()),
+            description: read_validator_description(storage, &address)?,
+            website: read_validator_website(storage, &address)?,
+            discord_handle: read_validator_discord_handle(storage, &address)?,
+            avatar: read_validator_avatar(storage, &address)?,
+            name: read_validator_name(storage, &address)?,
+        };
+        let consensus_key = validator_consensus_key_handle(&address)
+            .get(storage, epoch, &params)?;
+        if consensus_key.as_ref() != Some(&tx.data.consensus_key.pk.raw) {
+            skipped.validators.push(format!(
+                "The consensus key of {address} changed since genesis, the \
+                 genesis key is exported"
+            ));
+        }
+
+        let data = ValidatorAccountTx {
+            commission_rate,
+            max_commission_rate_change,
+            metadata,
+            ..tx.data.clone()
+        };
+        if data == tx.data {
+            signed.push(tx.clone());
+        } else {
+            unsigned.push(UnsignedValidatorAccountTx::from(&data));
+        }
+    }
+    // The remaining validators became validators after genesis. Their P2P
+    // address and Tendermint node key are not in the state.
+    skipped
+        .validators
+        .extend(validators.into_iter().map(|address| {
+            format!("The validator {address} became a validator after genesis")
+        }));
+    Ok((signed, unsigned))
+}
+
+/// Read the bonds of the native token. The unbonds that weren't withdrawn
+/// yet and the unclaimed rewards are credited to the sources' balances. So
+/// are the bonds to validators that are not exported.
+fn read_bonds<S>(
+    storage: &S,
+    genesis: &Finalized,
+    accounts: &HashMap<Address, GenesisAddress>,
+    balances: &mut BTreeMap<Alias, BTreeMap<GenesisAddress, token::Amount>>,
+    skipped: &mut Skipped,
+) -> StorageResult<Vec<BondTx<Unvalidated>>>
+where
+    S: StorageRead,
+{
+    let epoch = storage.get_block_epoch()?;
+    let native_token = &genesis.parameters.parameters.native_token;
+    let exported_validators: Vec<Address> = genesis
+        .transactions
+        .validator_account
+        .iter()
+        .flatten()
+        .map(|FinalizedValidatorAccountTx { tx }| {
+            Address::Established(tx.data.address.raw.clone())
+        })
+        .collect();
+
+    let mut bonds = vec![];
+    for (BondId { source, validator }, details) in
+        bonds_and_unbonds(storage, None, None)?
+    {
+        let bonded: token::Amount = details
+            .bonds
+            .iter()
+            .map(|bond| bond.amount - bond.slashed_amount.unwrap_or_default())
+            .sum();
+        let unbonded: token::Amount = details
+            .unbonds
+            .iter()
+            .map(|unbond| {
+                unbond.amount - unbond.slashed_amount.unwrap_or_default()
+            })
+            .sum();
+        let rewards =
+            query_reward_tokens(storage, Some(&source), &validator, epoch)?;
+
+        let Some(genesis_source) =
+            find_genesis_address(storage, accounts, &source)?
+        else {
+            skipped.bonds.push(format!(
+                "{} bonded, {} unbonded and {} of rewards from {source} to \
+                 {validator}",
+                bonded.to_string_native(),
+                unbonded.to_string_native(),
+                rewards.to_string_native(),
+            ));
+            continue;
+        };
+        // A genesis bond is paid from its source's balance, so the bonded
+        // amount is credited too
+        *balances
+            .entry(native_token.clone())
+            .or_default()
+            .entry(genesis_source.clone())
+            .or_default() += bonded + unbonded + rewards;
+        if !bonded.is_zero() && exported_validators.contains(&validator) {
+            bonds.push(BondTx {
+                source: genesis_source,
+                validator,
+                amount: bonded.native_denominated(),
+            });
+        }
+    }
+    Ok(bonds)
+}
+
+/// Read the parameters. The chain parameters that are stored as hashes, the
+/// minimum gas prices of the tokens that weren't created in genesis and the
+/// Ethereum bridge parameters are kept from genesis.
+fn read_parameters<S>(
+    storage: &S,
+    genesis: &Finalized,
+    skipped: &mut Skipped,
+) -> StorageResult<templates::Parameters<Unvalidated>>
+where
+    S: StorageRead,
+{
+    let chain_params = namada::ledger::parameters::read(storage)?;
+    let token_aliases: HashMap<&Address, &Alias> = genesis
+        .tokens
+        .token
+        .iter()
+        .map(|(alias, config)| (&config.address, alias))
+        .collect();
+    let mut minimum_gas_price = BTreeMap::new();
+    for (token, amount) in chain_params.minimum_gas_price {
+        match token_aliases.get(&token) {
+            Some(&alias) => {
+                let denom = genesis.tokens.token[alias].config.denom;
+                minimum_gas_price.insert(
+                    alias.clone(),
+                    DenominatedAmount::new(amount, denom),
+                );
+            }
+            None => skipped.parameters.push(format!(
+                "The minimum gas price of {token}, which wasn't created in \
+                 genesis"
+            )),
+        }
+    }
+    let genesis_chain_params = &genesis.parameters.parameters;
+    if chain_params.vp_allowlist
+        != genesis_chain_params
+            .vp_allowlist
+            .clone()
+            .unwrap_or_default()
+        || chain_params.tx_allowlist
+            != genesis_chain_params
+                .tx_allowlist
+                .clone()
+                .unwrap_or_default()
+    {
+        skipped.parameters.push(
+            "The VP and tx allowlists changed since genesis, the genesis \
+             allowlists are exported"
+                .to_string(),
+        );
+    }
+    let parameters = templates::ChainParams {
+        max_tx_bytes: chain_params.max_tx_bytes,
+        native_token: genesis_chain_params.native_token.clone(),
+        min_num_of_blocks: chain_params.epoch_duration.min_num_of_blocks,
+        max_expected_time_per_block: chain_params.max_expected_time_per_block.0
+            as i64,
+        max_proposal_bytes: chain_params.max_proposal_bytes,
+        vp_allowlist: genesis_chain_params.vp_allowlist.clone(),
+        tx_allowlist: genesis_chain_params.tx_allowlist.clone(),
+        implicit_vp: genesis_chain_params.implicit_vp.clone(),
+        epochs_per_year: chain_params.epochs_per_year,
+        max_signatures_per_transaction: chain_params
+            .max_signatures_per_transaction,
+        max_block_gas: chain_params.max_block_gas,
+        fee_unshielding_gas_limit: chain_params.fee_unshielding_gas_limit,
+        fee_unshielding_descriptions_limit: chain_params
+            .fee_unshielding_descriptions_limit,
+        minimum_gas_price,
+    };
+
+    let pos_params = read_pos_params(storage)?.owned;
+    let pos_params = templates::PosParams {
+        max_validator_slots: pos_params.max_validator_slots,
+        pipeline_len: pos_params.pipeline_len,
+        unbonding_len: pos_params.unbonding_len,
+        tm_votes_per_token: pos_params.tm_votes_per_token,
+        block_proposer_reward: pos_params.block_proposer_reward,
+        block_vote_reward: pos_params.block_vote_reward,
+        max_inflation_rate: pos_params.max_inflation_rate,
+        target_staked_ratio: pos_params.target_staked_ratio,
+        duplicate_vote_min_slash_rate: pos_params.duplicate_vote_min_slash_rate,
+        light_client_attack_min_slash_rate: pos_params
+            .light_client_attack_min_slash_rate,
+        cubic_slashing_window_length: pos_params.cubic_slashing_window_length,
+        validator_stake_threshold: pos_params.validator_stake_threshold,
+        liveness_window_check: pos_params.liveness_window_check,
+        liveness_threshold: pos_params.liveness_threshold,
+        rewards_gain_p: pos_params.rewards_gain_p,
+        rewards_gain_d: pos_params.rewards_gain_d,
+    };
+
+    let gov_params = namada::governance::storage::get_parameters(storage)?;
+    // The proposal funds are set in whole native tokens in genesis
+    let whole_native_tokens = |amount: token::Amount| {
+        amount
+            .checked_div(token::Amount::native_whole(1))
+            .unwrap_or_default()
+            .raw_amount()
+            .as_u64()
+    };
+    let gov_params = templates::GovernanceParams {
+        min_proposal_fund: whole_native_tokens(gov_params.min_proposal_fund),
+        min_signaling_proposal_fund: whole_native_tokens(
+            gov_params.min_signaling_proposal_fund,
+        ),
+        max_proposal_code_size: gov_params.max_proposal_code_size,
+        min_proposal_voting_period: gov_params.min_proposal_voting_period,
+        max_proposal_period: gov_params.max_proposal_period,
+        max_proposal_content_size: gov_params.max_proposal_content_size,
+        min_proposal_grace_epochs: gov_params.min_proposal_grace_epochs,
+        veto_threshold: gov_params.veto_threshold,
+        expedited_voting_period: gov_params.expedited_voting_period,
+        expedited_quorum: gov_params.expedited_quorum,
+        expedited_threshold: gov_params.expedited_threshold,
+        deposit_policy: gov_params.deposit_policy,
+    };
+
+    let pgf_params = namada::governance::pgf::storage::get_parameters(storage)?;
+    let pgf_params = templates::PgfParams {
+        stewards: get_stewards(storage)?
+            .into_iter()
+            .map(|steward| steward.address)
+            .collect(),
+        pgf_inflation_rate: pgf_params.pgf_inflation_rate,
+        stewards_inflation_rate: pgf_params.stewards_inflation_rate,
+        valid: PhantomData,
+    };
+
+    Ok(templates::Parameters {
+        parameters,
+        pos_params,
+        gov_params,
+        pgf_params,
+        eth_bridge_params: genesis.parameters.eth_bridge_params.clone(),
+    })
+}
+
+/// Read the configs of the genesis tokens with their current MASP reward
+/// parameters
+fn read_tokens<S>(
+    storage: &S,
+    genesis: &Finalized,
+) -> StorageResult<templates::Tokens>
+where
+    S: StorageRead,
+{
+    let mut tokens = BTreeMap::new();
+    for (alias, FinalizedTokenConfig { address, config }) in
+        &genesis.tokens.token
+    {
+        let mut config = config.clone();
+        if config.masp_params.is_some() {
+            config.masp_params =
+                read_masp_params(storage, address, config.denom)?
+                    .or(config.masp_params);
+        }
+        tokens.insert(alias.clone(), config);
+    }
+    Ok(templates::Tokens { token: tokens })
+}
+
+/// Read the MASP reward parameters of a token, if they are all set
+fn read_masp_params<S>(
+    storage: &S,
+    token: &Address,
+    denom: token::Denomination,
+) -> StorageResult<Option<MaspParams>>
+where
+    S: StorageRead,
+{
+    let (
+        Some(max_reward_rate),
+        Some(kd_gain_nom),
+        Some(kp_gain_nom),
+        Some(locked_amount_target),
+    ) = (
+        read_masp_max_reward_rate(storage, token)?,
+        read_masp_kd_gain(storage, token)?,
+        read_masp_kp_gain(storage, token)?,
+        read_masp_locked_amount_target(storage, token)?,
+    )
+    else {
+        return Ok(None);
+    };
+    // The target is stored in the token's smallest unit and set in whole
+    // tokens in genesis
+    let scale = Uint::from(10).pow(Uint::from(denom.0));
+    let locked_amount_target =
+        (locked_amount_target.raw_amount() / scale).as_u64();
+    Ok(Some(MaspParams {
+        max_reward_rate,
+        kd_gain_nom,
+        kp_gain_nom,
+        locked_amount_target,
+    }))
+}
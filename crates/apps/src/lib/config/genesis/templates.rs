@@ -8,6 +8,7 @@ use borsh::{BorshDeserialize, BorshSerialize};
 use namada::eth_bridge::storage::parameters::{
     Contracts, Erc20WhitelistEntry, MinimumConfirmations,
 };
+use namada::governance::parameters::DepositPolicy;
 use namada::types::address::Address;
 use namada::types::chain::ProposalBytes;
 use namada::types::dec::Dec;
@@ -423,6 +424,8 @@ pub struct PosParams {
 pub struct GovernanceParams {
     /// Min funds to stake to submit a proposal
     pub min_proposal_fund: u64,
+    /// Min funds to stake to submit a signaling proposal
+    pub min_signaling_proposal_fund: u64,
     /// Maximum size of proposal in kibibytes (KiB)
     pub max_proposal_code_size: u64,
     /// Minimum proposal period length in epochs
@@ -433,6 +436,19 @@ pub struct GovernanceParams {
     pub max_proposal_content_size: u64,
     /// Minimum number of epoch between end and grace epoch
     pub min_proposal_grace_epochs: u64,
+    /// Fraction of the voted power above which the veto votes reject a
+    /// proposal
+    pub veto_threshold: Dec,
+    /// Voting period length in epochs of an expedited proposal
+    pub expedited_voting_period: u64,
+    /// Fraction of the total voting power that must vote on an expedited
+    /// proposal
+    pub expedited_quorum: Dec,
+    /// Fraction of the yay and nay votes that must be yay for an expedited
+    /// proposal to pass
+    pub expedited_threshold: Dec,
+    /// The handling of the deposit of tallied proposals
+    pub deposit_policy: DepositPolicy,
 }
 
 #[derive(
@@ -311,6 +311,7 @@ pub fn init_validator(
             website,
             discord_handle,
             avatar,
+            name: None,
         },
     };
     let unsigned_validator_addr =
@@ -8,9 +8,11 @@ pub mod utils;
 use std::collections::HashMap;
 use std::fs::{create_dir_all, File};
 use std::io::Write;
+use std::net::SocketAddr;
 use std::path::{Path, PathBuf};
 
 use directories::ProjectDirs;
+use namada::state::{PruningMode, SyncPolicy};
 use namada::types::chain::ChainId;
 use namada::types::storage::BlockHeight;
 use namada::types::time::Rfc3339String;
@@ -108,9 +110,72 @@ pub struct Shell {
     /// Tx WASM compilation in-memory cache maximum size in bytes.
     /// When not set, defaults to 1/6 of the available memory.
     pub tx_wasm_compilation_cache_bytes: Option<u64>,
+    /// Cache of the storage values read from the DB maximum size in bytes.
+    /// When not set, defaults to 64 MiB. Set to 0 to disable the cache.
+    pub read_cache_bytes: Option<u64>,
     /// When set, will limit the how many block heights in the past can the
     /// storage be queried for reading values.
     pub storage_read_past_height_limit: Option<u64>,
+    /// When set, a state snapshot for CometBFT state sync is taken every
+    /// this many blocks.
+    pub snapshot_interval: Option<u64>,
+    /// The number of most recent state snapshots to keep. When not set,
+    /// defaults to 2.
+    pub snapshots_to_keep: Option<u64>,
+    /// The pruning mode of the data of the past blocks: `archive`,
+    /// `default` or `{ keep-recent = N }`. When not set, defaults to
+    /// `default`. With `keep-recent`, the queries are limited to the
+    /// retained blocks.
+    pub pruning: Option<PruningMode>,
+    /// When the writes of the committed blocks are synced to the disk: `os`,
+    /// `every-block` or `{ interval = N }`. When not set, defaults to `os`,
+    /// which leaves it to the DB and the OS and gives the lowest commit
+    /// latency.
+    pub sync_policy: Option<SyncPolicy>,
+    /// When set to `true`, the Merkle tree is updated on a separate thread
+    /// while the block's writes are prepared for the DB on a block commit.
+    /// When not set, defaults to `false`.
+    pub async_merkle_commit: Option<bool>,
+    /// When set to `true`, the checksums of the subspace values are written
+    /// with the values and the values read from the DB are verified, so
+    /// that a corrupted value is detected and, if possible, repaired. When
+    /// not set, defaults to `false`.
+    pub value_checksums: Option<bool>,
+    /// When set to `true`, the diffs of all the storage keys are persisted,
+    /// and not only the ones of the merklized keys, so that the state diff of
+    /// any block committed while enabled can be exported. When not set,
+    /// defaults to `false` and only the state diff of the last committed
+    /// block is complete.
+    pub persist_all_diffs: Option<bool>,
+    /// When set to `true`, the hashes of the txs in which every address
+    /// appeared, e.g. as a verifier or as the fee payer, are indexed to serve
+    /// the history of the accounts. Only the txs of the blocks committed
+    /// while enabled are indexed. When not set, defaults to `false`.
+    pub account_history: Option<bool>,
+    /// When set, the block results and the Merkle tree stores of the old
+    /// blocks are moved to a secondary DB, from which they are still read
+    /// transparently.
+    pub cold_storage: Option<ColdStorage>,
+    /// When set, the DB is compacted on a background thread, which is paused
+    /// while the blocks are being processed. Only supported with RocksDB.
+    pub compaction: Option<Compaction>,
+    /// When set to `true`, the queries are served on separate threads from a
+    /// snapshot of the last committed state, concurrently with the
+    /// processing of the blocks. The queries that read the event log are
+    /// still served by the shell. Only supported with RocksDB. When not set,
+    /// defaults to `false`.
+    pub concurrent_queries: Option<bool>,
+    /// The address to listen on for the WebSocket connections of the clients
+    /// subscribing to the events of the committed blocks with filters. When
+    /// not set, the event subscriptions are disabled.
+    pub event_subscriptions: Option<SocketAddr>,
+    /// The address to listen on for the gRPC query service defined in
+    /// `proto/query.proto`. When not set, the gRPC service is disabled.
+    pub grpc: Option<SocketAddr>,
+    /// The address to listen on for the HTTP/JSON gateway of the common
+    /// queries and of the tx broadcasts. When not set, the gateway is
+    /// disabled.
+    pub rest: Option<SocketAddr>,
     /// Use the [`Ledger::db_dir()`] method to read the value.
     db_dir: PathBuf,
     /// Use the [`Ledger::cometbft_dir()`] method to read the value.
@@ -121,6 +186,30 @@ pub struct Shell {
     pub tendermint_mode: TendermintMode,
 }
 
+/// The secondary DB to which the data of the old blocks is moved
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ColdStorage {
+    /// The directory of the secondary DB, e.g. on a cheaper disk. A relative
+    /// path is relative to the chain directory.
+    pub db_dir: PathBuf,
+    /// The number of the most recent blocks whose data is kept in the
+    /// primary DB
+    pub hot_blocks: u64,
+}
+
+/// The background compaction of the DB
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Compaction {
+    /// The number of seconds between the compaction runs
+    pub interval_secs: u64,
+    /// The number of milliseconds after a block commit before the compaction
+    /// resumes
+    pub resume_delay_ms: u64,
+    /// Only the DB files in which at least this ratio of the entries are
+    /// deleted entries are compacted. `0` compacts all the files.
+    pub min_deletions_ratio: f64,
+}
+
 impl Ledger {
     pub fn new(
         base_dir: impl AsRef<Path>,
@@ -141,8 +230,23 @@ impl Ledger {
                 block_cache_bytes: None,
                 vp_wasm_compilation_cache_bytes: None,
                 tx_wasm_compilation_cache_bytes: None,
+                read_cache_bytes: None,
                 // Default corresponds to 1 hour of past blocks at 1 block/sec
                 storage_read_past_height_limit: Some(3600),
+                snapshot_interval: None,
+                snapshots_to_keep: None,
+                pruning: None,
+                sync_policy: None,
+                async_merkle_commit: None,
+                value_checksums: None,
+                persist_all_diffs: None,
+                account_history: None,
+                cold_storage: None,
+                compaction: None,
+                concurrent_queries: None,
+                event_subscriptions: None,
+                grpc: None,
+                rest: None,
                 db_dir: DB_DIR.into(),
                 cometbft_dir: COMETBFT_DIR.into(),
                 action_at_height: None,
@@ -175,6 +279,15 @@ impl Shell {
         self.base_dir.join(chain_id.as_str()).join(&self.db_dir)
     }
 
+    /// Get the directory path to the cold storage DB, if it's enabled
+    pub fn cold_db_dir(&self, chain_id: &ChainId) -> Option<PathBuf> {
+        self.cold_storage.as_ref().map(|cold_storage| {
+            self.base_dir
+                .join(chain_id.as_str())
+                .join(&cold_storage.db_dir)
+        })
+    }
+
     /// Get the directory path to Tendermint
     pub fn cometbft_dir(&self, chain_id: &ChainId) -> PathBuf {
         self.base_dir
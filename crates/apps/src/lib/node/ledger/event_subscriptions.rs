@@ -0,0 +1,137 @@
+//! A WebSocket endpoint for the clients to subscribe to the events of the
+//! committed blocks, so that they don't have to poll for them.
+//!
+//! A client sends one or more [`EventFilter`]s as JSON text messages, after
+//! which every event of a committed block that matches any of its filters is
+//! pushed to it as a JSON [`PushedEvent`] text message. The events are
+//! published by the shell when a block is committed.
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use futures::{SinkExt, StreamExt};
+use namada::ledger::events::filter::{EventFilter, PushedEvent};
+use namada::ledger::events::Event;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, oneshot, Semaphore};
+use warp::http::StatusCode;
+use warp::ws::{Message, WebSocket, Ws};
+use warp::{Filter, Reply};
+
+/// The number of events that can be buffered for a subscriber before it
+/// misses events
+pub const EVENTS_CHANNEL_CAPACITY: usize = 10_000;
+
+/// The maximum number of concurrent subscribers
+pub const MAX_SUBSCRIBERS: usize = 1_000;
+
+/// The maximum number of filters of a subscriber
+pub const MAX_FILTERS_PER_SUBSCRIBER: usize = 100;
+
+/// The channel of the events of the committed blocks
+pub type EventSender = broadcast::Sender<Event>;
+
+/// Serve the event subscriptions on the given address until a signal is
+/// sent on the `abort_recv` channel.
+pub async fn serve(
+    listen_addr: SocketAddr,
+    events: EventSender,
+    abort_recv: oneshot::Receiver<()>,
+) {
+    let subscribers = Arc::new(Semaphore::new(MAX_SUBSCRIBERS));
+    let subscriptions = warp::path::end().and(warp::ws()).map(move |ws: Ws| {
+        // The permit is held until the subscriber disconnects
+        let Ok(permit) = subscribers.clone().try_acquire_owned() else {
+            return warp::reply::with_status(
+                "Too many event subscribers",
+                StatusCode::SERVICE_UNAVAILABLE,
+            )
+            .into_response();
+        };
+        let events = events.subscribe();
+        ws.on_upgrade(move |socket| async move {
+            subscribe(socket, events).await;
+            drop(permit);
+        })
+        .into_response()
+    });
+    let server = warp::serve(subscriptions).try_bind_with_graceful_shutdown(
+        listen_addr,
+        async move {
+            _ = abort_recv.await;
+            tracing::info!(
+                ?listen_addr,
+                "Stopping to serve the event subscriptions"
+            );
+        },
+    );
+    match server {
+        Ok((_, future)) => {
+            tracing::info!(?listen_addr, "Serving the event subscriptions");
+            future.await
+        }
+        Err(err) => tracing::error!(
+            ?listen_addr,
+            "Failed to serve the event subscriptions: {err}"
+        ),
+    }
+}
+
+/// Receive the filters of a subscriber and push it the events that match
+/// them, until it disconnects
+async fn subscribe(socket: WebSocket, mut events: broadcast::Receiver<Event>) {
+    let (mut sender, mut receiver) = socket.split();
+    let mut filters: Vec<EventFilter> = vec![];
+    loop {
+        let reply = tokio::select! {
+            msg = receiver.next() => match msg {
+                Some(Ok(msg)) if msg.is_close() => break,
+                Some(Ok(msg)) => match msg.to_str() {
+                    Ok(_) if filters.len() >= MAX_FILTERS_PER_SUBSCRIBER => {
+                        error_message(format!(
+                            "A subscriber can't have more than \
+                             {MAX_FILTERS_PER_SUBSCRIBER} event filters"
+                        ))
+                    }
+                    Ok(filter) => match serde_json::from_str(filter) {
+                        Ok(filter) => {
+                            filters.push(filter);
+                            continue;
+                        }
+                        Err(err) => error_message(format!(
+                            "Invalid event filter: {err}"
+                        )),
+                    },
+                    // Pings and other binary messages
+                    Err(()) => continue,
+                },
+                _ => break,
+            },
+            event = events.recv() => match event {
+                Ok(event) => {
+                    if !filters.iter().any(|filter| filter.matches(&event)) {
+                        continue;
+                    }
+                    let event = PushedEvent::from(&event);
+                    Message::text(
+                        serde_json::to_string(&event)
+                            .expect("Events must be serializable"),
+                    )
+                }
+                Err(RecvError::Lagged(missed)) => error_message(format!(
+                    "The subscriber is lagging behind and missed {missed} \
+                     events"
+                )),
+                Err(RecvError::Closed) => break,
+            },
+        };
+        if sender.send(reply).await.is_err() {
+            break;
+        }
+    }
+}
+
+/// A message reporting an error to a subscriber
+fn error_message(error: String) -> Message {
+    Message::text(serde_json::json!({ "error": error }).to_string())
+}
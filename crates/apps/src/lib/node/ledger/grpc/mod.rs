@@ -0,0 +1,304 @@
+//! A gRPC service for the core queries of the ledger and the broadcast of
+//! txs, defined in `proto/query.proto`, for the integrations that can't
+//! consume the ABCI query paths. The service is a gateway to the CometBFT RPC
+//! of the node, over which the queries are made with the SDK.
+
+mod generated {
+    pub mod query {
+        tonic::include_proto!("query");
+    }
+}
+
+use std::net::SocketAddr;
+use std::str::FromStr;
+
+use namada::governance::storage::keys as governance_storage;
+use namada::governance::storage::proposal::StorageProposal;
+use namada::tx::data::TxType;
+use namada::tx::Tx;
+use namada::types::address::Address;
+use namada::types::hash::Hash;
+use namada::types::storage::Epoch;
+use namada_sdk::rpc::{self, TxEventQuery};
+use tokio::sync::oneshot;
+use tonic::transport::Server;
+use tonic::{Request, Response, Status};
+
+pub use self::generated::query;
+use self::generated::query::query_server::{self, QueryServer};
+use crate::facade::tendermint_rpc::{Client, HttpClient};
+
+/// The number of proposals queried when the limit isn't set
+const DEFAULT_PROPOSALS_LIMIT: u64 = 10;
+
+/// The maximum number of proposals queried at once
+const MAX_PROPOSALS_LIMIT: u64 = 100;
+
+/// The queries served over the CometBFT RPC of the node
+pub struct QueryService {
+    client: HttpClient,
+}
+
+impl QueryService {
+    /// Create a new service that queries the node at the given CometBFT RPC
+    /// address
+    pub fn new(rpc_address: SocketAddr) -> Self {
+        Self {
+            client: HttpClient::new(format!("http://{}", rpc_address).as_str())
+                .unwrap(),
+        }
+    }
+
+    /// The given epoch or the epoch of the last committed block
+    async fn epoch_or_last(&self, epoch: Option<u64>) -> Result<Epoch, Status> {
+        match epoch {
+            Some(epoch) => Ok(Epoch(epoch)),
+            None => rpc::query_epoch(&self.client).await.map_err(unavailable),
+        }
+    }
+}
+
+/// Serve the gRPC queries on the given address until a signal is sent on the
+/// `abort_recv` channel.
+pub async fn serve(
+    listen_addr: SocketAddr,
+    rpc_address: SocketAddr,
+    abort_recv: oneshot::Receiver<()>,
+) {
+    tracing::info!(?listen_addr, "Serving the gRPC queries");
+    let res = Server::builder()
+        .add_service(QueryServer::new(QueryService::new(rpc_address)))
+        .serve_with_shutdown(listen_addr, async move {
+            _ = abort_recv.await;
+            tracing::info!(?listen_addr, "Stopping to serve the gRPC queries");
+        })
+        .await;
+    if let Err(err) = res {
+        tracing::error!(
+            ?listen_addr,
+            "Failed to serve the gRPC queries: {err}"
+        );
+    }
+}
+
+#[tonic::async_trait]
+impl query_server::Query for QueryService {
+    async fn epoch(
+        &self,
+        _request: Request<query::EpochRequest>,
+    ) -> Result<Response<query::EpochResponse>, Status> {
+        let epoch =
+            rpc::query_epoch(&self.client).await.map_err(unavailable)?;
+        Ok(Response::new(query::EpochResponse { epoch: epoch.0 }))
+    }
+
+    async fn balance(
+        &self,
+        request: Request<query::BalanceRequest>,
+    ) -> Result<Response<query::BalanceResponse>, Status> {
+        let request = request.into_inner();
+        let token = parse_address(&request.token)?;
+        let owner = parse_address(&request.owner)?;
+        let amount = rpc::get_token_balance(&self.client, &token, &owner)
+            .await
+            .map_err(unavailable)?;
+        Ok(Response::new(query::BalanceResponse {
+            amount: amount.to_string(),
+        }))
+    }
+
+    async fn validators(
+        &self,
+        request: Request<query::ValidatorsRequest>,
+    ) -> Result<Response<query::ValidatorsResponse>, Status> {
+        let epoch = self.epoch_or_last(request.into_inner().epoch).await?;
+        let addresses = rpc::get_all_validators(&self.client, epoch)
+            .await
+            .map_err(unavailable)?;
+        let mut validators = Vec::with_capacity(addresses.len());
+        for address in addresses {
+            let stake = rpc::get_validator_stake(&self.client, epoch, &address)
+                .await
+                .map_err(unavailable)?;
+            validators.push((address, stake));
+        }
+        // The validators with the most stake first
+        validators.sort_by(|(a, a_stake), (b, b_stake)| {
+            b_stake.cmp(a_stake).then_with(|| a.cmp(b))
+        });
+        Ok(Response::new(query::ValidatorsResponse {
+            epoch: epoch.0,
+            validators: validators
+                .into_iter()
+                .map(|(address, stake)| query::Validator {
+                    address: address.encode(),
+                    stake: stake.to_string(),
+                })
+                .collect(),
+        }))
+    }
+
+    async fn delegations(
+        &self,
+        request: Request<query::DelegationsRequest>,
+    ) -> Result<Response<query::DelegationsResponse>, Status> {
+        let request = request.into_inner();
+        let delegator = parse_address(&request.delegator)?;
+        let epoch = self.epoch_or_last(request.epoch).await?;
+        let mut delegations =
+            rpc::get_delegators_delegation_at(&self.client, &delegator, epoch)
+                .await
+                .map_err(unavailable)?
+                .into_iter()
+                .collect::<Vec<_>>();
+        delegations.sort();
+        Ok(Response::new(query::DelegationsResponse {
+            epoch: epoch.0,
+            delegations: delegations
+                .into_iter()
+                .map(|(validator, amount)| query::Delegation {
+                    validator: validator.encode(),
+                    amount: amount.to_string(),
+                })
+                .collect(),
+        }))
+    }
+
+    async fn proposal(
+        &self,
+        request: Request<query::ProposalRequest>,
+    ) -> Result<Response<query::ProposalResponse>, Status> {
+        let id = request.into_inner().id;
+        let proposal = rpc::query_proposal_by_id(&self.client, id)
+            .await
+            .map_err(unavailable)?;
+        Ok(Response::new(query::ProposalResponse {
+            proposal: proposal.map(proposal_message),
+        }))
+    }
+
+    async fn proposals(
+        &self,
+        request: Request<query::ProposalsRequest>,
+    ) -> Result<Response<query::ProposalsResponse>, Status> {
+        let request = request.into_inner();
+        let count: u64 = rpc::query_storage_value(
+            &self.client,
+            &governance_storage::get_counter_key(),
+        )
+        .await
+        .map_err(unavailable)?;
+        let limit = request
+            .limit
+            .map_or(DEFAULT_PROPOSALS_LIMIT, u64::from)
+            .min(MAX_PROPOSALS_LIMIT);
+        let start =
+            request.start.unwrap_or_else(|| count.saturating_sub(limit));
+        let mut proposals = vec![];
+        for id in start..count.min(start.saturating_add(limit)) {
+            if let Some(proposal) = rpc::query_proposal_by_id(&self.client, id)
+                .await
+                .map_err(unavailable)?
+            {
+                proposals.push(proposal_message(proposal));
+            }
+        }
+        Ok(Response::new(query::ProposalsResponse { count, proposals }))
+    }
+
+    async fn tx_status(
+        &self,
+        request: Request<query::TxStatusRequest>,
+    ) -> Result<Response<query::TxStatusResponse>, Status> {
+        let hash = Hash::from_str(&request.into_inner().hash)
+            .map_err(|err| Status::invalid_argument(err.to_string()))?
+            .to_string();
+        for (event_query, status) in [
+            (TxEventQuery::Applied(&hash), query::TxStatus::Applied),
+            (TxEventQuery::Accepted(&hash), query::TxStatus::Accepted),
+        ] {
+            let event = rpc::query_tx_events(&self.client, event_query)
+                .await
+                .map_err(unavailable)?;
+            if let Some(event) = event {
+                let attribute = |key: &str| event.get(key).cloned();
+                return Ok(Response::new(query::TxStatusResponse {
+                    status: status.into(),
+                    height: attribute("height")
+                        .and_then(|height| height.parse().ok())
+                        .unwrap_or_default(),
+                    code: attribute("code")
+                        .and_then(|code| code.parse().ok())
+                        .unwrap_or_default(),
+                    info: attribute("info").unwrap_or_default(),
+                    gas_used: attribute("gas_used").unwrap_or_default(),
+                }));
+            }
+        }
+        Ok(Response::new(query::TxStatusResponse::default()))
+    }
+
+    async fn broadcast_tx(
+        &self,
+        request: Request<query::BroadcastTxRequest>,
+    ) -> Result<Response<query::BroadcastTxResponse>, Status> {
+        let tx_bytes = request.into_inner().tx;
+        let mut tx = Tx::try_from(&tx_bytes[..])
+            .map_err(|err| Status::invalid_argument(err.to_string()))?;
+        let wrapper_hash = tx.header_hash().to_string();
+        let inner_hash =
+            tx.update_header(TxType::Raw).header_hash().to_string();
+        let response = self
+            .client
+            .broadcast_tx_sync(tx_bytes)
+            .await
+            .map_err(unavailable)?;
+        Ok(Response::new(query::BroadcastTxResponse {
+            wrapper_hash,
+            inner_hash,
+            code: response.code.value(),
+            log: response.log,
+        }))
+    }
+}
+
+/// The message of a governance proposal
+fn proposal_message(proposal: StorageProposal) -> query::Proposal {
+    query::Proposal {
+        id: proposal.id,
+        author: proposal.author.encode(),
+        r#type: proposal.r#type.to_string(),
+        content: proposal.content.into_iter().collect(),
+        voting_start_epoch: proposal.voting_start_epoch.0,
+        voting_end_epoch: proposal.voting_end_epoch.0,
+        grace_epoch: proposal.grace_epoch.0,
+    }
+}
+
+/// Decode a bech32m encoded address of a request
+fn parse_address(address: &str) -> Result<Address, Status> {
+    Address::decode(address).map_err(|err| {
+        Status::invalid_argument(format!("Invalid address {address}: {err}"))
+    })
+}
+
+/// The status of a failed query to the node
+fn unavailable(err: impl std::fmt::Display) -> Status {
+    Status::unavailable(err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use namada::types::address::testing::established_address_1;
+
+    use super::*;
+
+    /// Test that the addresses of the requests are validated
+    #[test]
+    fn test_parse_address() {
+        let address = established_address_1();
+        assert_eq!(parse_address(&address.encode()).unwrap(), address);
+        let err = parse_address("not an address").unwrap_err();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+    }
+}
@@ -1,6 +1,9 @@
 mod abortable;
 mod broadcaster;
 pub mod ethereum_oracle;
+mod event_subscriptions;
+pub mod grpc;
+mod rest;
 pub mod shell;
 pub mod shims;
 pub mod storage;
@@ -148,16 +151,16 @@ impl Shell {
                 Ok(Response::CheckTx(self.mempool_validate(&tx.tx, r#type)))
             }
             Request::ListSnapshots => {
-                Ok(Response::ListSnapshots(Default::default()))
+                Ok(Response::ListSnapshots(self.list_snapshots()))
             }
-            Request::OfferSnapshot(_) => {
-                Ok(Response::OfferSnapshot(Default::default()))
+            Request::OfferSnapshot(req) => {
+                Ok(Response::OfferSnapshot(self.offer_snapshot(req)))
             }
-            Request::LoadSnapshotChunk(_) => {
-                Ok(Response::LoadSnapshotChunk(Default::default()))
+            Request::LoadSnapshotChunk(req) => {
+                Ok(Response::LoadSnapshotChunk(self.load_snapshot_chunk(req)))
             }
-            Request::ApplySnapshotChunk(_) => {
-                Ok(Response::ApplySnapshotChunk(Default::default()))
+            Request::ApplySnapshotChunk(req) => {
+                Ok(Response::ApplySnapshotChunk(self.apply_snapshot_chunk(req)))
             }
         }
     }
@@ -221,12 +224,252 @@ pub fn dump_db(
     let db_path = config.shell.db_dir(&chain_id);
 
     let db = storage::PersistentDB::open(db_path, None);
+    #[cfg(not(feature = "sled"))]
     db.dump_block(out_file_path, historic, block_height);
+    #[cfg(feature = "sled")]
+    {
+        let _ = (db, out_file_path, historic, block_height);
+        eprintln!("Dumping the DB is not supported with the sled backend");
+        crate::cli::safe_exit(1)
+    }
 }
 
-/// Roll Namada state back to the previous height
-pub fn rollback(config: config::Ledger) -> Result<(), shell::Error> {
-    shell::rollback(config)
+/// Export the diff of the storage subspace of a committed block into a file
+pub fn export_state_diff(
+    config: config::Ledger,
+    args::LedgerExportStateDiff {
+        block_height,
+        out_file_path,
+    }: args::LedgerExportStateDiff,
+) {
+    use borsh_ext::BorshSerializeExt;
+    use namada::state::state_diff::StateDiff;
+    use namada::state::DB;
+
+    let chain_id = config.chain_id;
+    let db_path = config.shell.db_dir(&chain_id);
+
+    let db = storage::PersistentDB::open(db_path, None);
+    let last_height = match db.read_last_block() {
+        Ok(Some(last_block)) => last_block.height,
+        Ok(None) => {
+            eprintln!("No committed block found in the DB");
+            crate::cli::safe_exit(1)
+        }
+        Err(err) => {
+            eprintln!("Unable to read the last block from the DB: {err}");
+            crate::cli::safe_exit(1)
+        }
+    };
+    let height = block_height.unwrap_or(last_height);
+    if height > last_height {
+        eprintln!(
+            "The block height {height} hasn't been committed yet, the last \
+             committed block height is {last_height}"
+        );
+        crate::cli::safe_exit(1)
+    }
+    // The diffs of the keys that aren't merklized are pruned once the keys
+    // are written again in a later block
+    if height < last_height
+        && !config.shell.persist_all_diffs.unwrap_or_default()
+    {
+        eprintln!(
+            "The diff of the block at height {height} may be incomplete. Only \
+             the diff of the last committed block {last_height} can be \
+             exported, unless `persist_all_diffs` is enabled in the config of \
+             the node."
+        );
+        crate::cli::safe_exit(1)
+    }
+
+    let diff = StateDiff::read(&db, height);
+    let full_path = out_file_path
+        .with_file_name(format!(
+            "{}_{height}",
+            out_file_path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_else(|| "state_diff".to_string())
+        ))
+        .with_extension("diff");
+    std::fs::write(&full_path, diff.serialize_to_vec()).unwrap_or_else(|err| {
+        eprintln!("Unable to write to {}: {err}", full_path.to_string_lossy());
+        crate::cli::safe_exit(1)
+    });
+    println!(
+        "Exported the diff of {} keys at height {height} to {}",
+        diff.changes.len(),
+        full_path.to_string_lossy()
+    );
+}
+
+/// Export the state at a committed block height into genesis templates
+pub fn export_genesis(
+    config: config::Ledger,
+    args::LedgerExportGenesis {
+        block_height,
+        output_folder,
+    }: args::LedgerExportGenesis,
+) {
+    use namada::state::HistoricStorage;
+
+    use crate::config::genesis;
+
+    let chain_dir = config.chain_dir();
+    let genesis = genesis::chain::Finalized::read_toml_files(&chain_dir)
+        .unwrap_or_else(|err| {
+            eprintln!(
+                "Unable to read the genesis files from {}: {err}",
+                chain_dir.to_string_lossy()
+            );
+            crate::cli::safe_exit(1)
+        });
+    let mut state = storage::PersistentStorage::open(
+        config.db_dir(),
+        config.chain_id.clone(),
+        genesis.get_native_token().clone(),
+        None,
+        config.shell.storage_read_past_height_limit,
+        shell::is_merklized_storage_key,
+    );
+    if let Err(err) = state.load_last_state() {
+        eprintln!("Unable to load the last state from the DB: {err}");
+        crate::cli::safe_exit(1)
+    }
+    let height = block_height.unwrap_or_else(|| state.get_last_block_height());
+    let exported = HistoricStorage::new(&state, height)
+        .and_then(|storage| genesis::export::export_genesis(&storage, &genesis))
+        .unwrap_or_else(|err| {
+            eprintln!("Unable to export the state at height {height}: {err}");
+            crate::cli::safe_exit(1)
+        });
+
+    let output_folder = output_folder
+        .unwrap_or_else(|| PathBuf::from(format!("exported_genesis_{height}")));
+    std::fs::create_dir_all(&output_folder)
+        .map_err(eyre::Report::from)
+        .and_then(|()| exported.write_toml_files(&output_folder))
+        .unwrap_or_else(|err| {
+            eprintln!(
+                "Unable to write the genesis templates to {}: {err}",
+                output_folder.to_string_lossy()
+            );
+            crate::cli::safe_exit(1)
+        });
+    println!(
+        "Exported the state at height {height} into genesis templates in {}. \
+         The validators and the bond sources have to sign the txs in {} with \
+         `sign-genesis-txs` and the signed txs have to be added to {}.",
+        output_folder.to_string_lossy(),
+        genesis::export::UNSIGNED_TRANSACTIONS_FILE_NAME,
+        genesis::templates::TRANSACTIONS_FILE_NAME,
+    );
+    if !exported.skipped.is_empty() {
+        println!(
+            "Some of the state couldn't be exported, it is listed in {}.",
+            genesis::export::SKIPPED_FILE_NAME
+        );
+    }
+}
+
+/// Replay a series of state diffs onto the storage subspace of a state
+/// snapshot and dump the resulting subspace into a file
+pub fn replay_state_diffs(
+    config: config::Ledger,
+    args::LedgerReplayStateDiffs {
+        snapshot_height,
+        state_diffs,
+        out_file_path,
+    }: args::LedgerReplayStateDiffs,
+) {
+    use std::io::Write;
+
+    use borsh::BorshDeserialize;
+    use data_encoding::HEXLOWER;
+    use namada::state::state_diff::StateDiff;
+
+    let snapshots_dir = shell::snapshots::snapshots_dir(
+        &config.shell.base_dir,
+        &config.chain_id,
+    );
+    let mut subspace = shell::snapshots::read_snapshot_subspace(
+        &snapshots_dir,
+        snapshot_height.0,
+    )
+    .unwrap_or_else(|err| {
+        eprintln!(
+            "Unable to read the state snapshot at height {snapshot_height}: \
+             {err}"
+        );
+        crate::cli::safe_exit(1)
+    });
+
+    let mut diffs: Vec<StateDiff> = state_diffs
+        .iter()
+        .map(|path| {
+            std::fs::read(path)
+                .and_then(|bytes| StateDiff::try_from_slice(&bytes))
+                .unwrap_or_else(|err| {
+                    eprintln!(
+                        "Unable to read the state diff {}: {err}",
+                        path.to_string_lossy()
+                    );
+                    crate::cli::safe_exit(1)
+                })
+        })
+        .collect();
+    diffs.sort_by_key(|diff| diff.height);
+
+    let mut height = snapshot_height;
+    for diff in diffs {
+        if diff.height != height.next_height() {
+            eprintln!(
+                "Missing the state diff at height {}",
+                height.next_height()
+            );
+            crate::cli::safe_exit(1)
+        }
+        diff.apply(&mut subspace).unwrap_or_else(|err| {
+            eprintln!("Unable to replay the state diffs: {err}");
+            crate::cli::safe_exit(1)
+        });
+        height = diff.height;
+    }
+
+    let full_path = out_file_path
+        .with_file_name(format!(
+            "{}_{height}",
+            out_file_path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_else(|| "db_dump".to_string())
+        ))
+        .with_extension("toml");
+    let file = std::fs::File::options()
+        .write(true)
+        .create_new(true)
+        .open(&full_path)
+        .expect("Cannot open the output file");
+    let mut buf = std::io::BufWriter::new(file);
+    for (key, value) in subspace {
+        let val = HEXLOWER.encode(&value);
+        writeln!(buf, "\"{key}\" = \"{val}\"")
+            .expect("Unable to write to buffer");
+    }
+    buf.flush().expect("Unable to write to output file");
+    println!(
+        "Replayed the state diffs up to height {height} into {}",
+        full_path.to_string_lossy()
+    );
+}
+
+/// Roll Namada state back by the given number of blocks
+pub fn rollback(
+    config: config::Ledger,
+    args::LedgerRollBack { blocks }: args::LedgerRollBack,
+) -> Result<(), shell::Error> {
+    shell::rollback(config, blocks)
 }
 
 /// Runs and monitors a few concurrent tasks.
@@ -250,6 +493,14 @@ async fn run_aux(config: config::Ledger, wasm_dir: PathBuf) {
     }
     let setup_data = run_aux_setup(&config, &wasm_dir).await;
 
+    // Make sure that the MASP parameters aren't corrupted before starting
+    tracing::info!("Loading MASP verifying keys.");
+    if let Err(err) = namada_sdk::masp::try_preload_verifying_keys() {
+        tracing::error!("Failed to load the MASP verifying keys: {err}");
+        return;
+    }
+    tracing::info!("Done loading MASP verifying keys.");
+
     // Create an `AbortableSpawner` for signalling shut down from the shell or
     // from Tendermint
     let mut spawner = AbortableSpawner::new();
@@ -266,28 +517,39 @@ async fn run_aux(config: config::Ledger, wasm_dir: PathBuf) {
             }
         };
 
-    tracing::info!("Loading MASP verifying keys.");
-    let _ = namada_sdk::masp::preload_verifying_keys();
-    tracing::info!("Done loading MASP verifying keys.");
+    // Start the gRPC queries, if enabled
+    let grpc = start_grpc(&mut spawner, &config);
+
+    // Start the REST gateway, if enabled
+    let rest = start_rest(&mut spawner, &config);
 
     // Start ABCI server and broadcaster (the latter only if we are a validator
     // node)
-    let (abci, broadcaster, shell_handler) = start_abci_broadcaster_shell(
-        &mut spawner,
-        eth_oracle_channels,
-        wasm_dir,
-        setup_data,
-        config,
-    );
+    let (abci, broadcaster, event_subscriptions, shell_handler) =
+        start_abci_broadcaster_shell(
+            &mut spawner,
+            eth_oracle_channels,
+            wasm_dir,
+            setup_data,
+            config,
+        );
 
     // Wait for interrupt signal or abort message
     let aborted = spawner.wait_for_abort().await.child_terminated();
 
     // Wait for all managed tasks to finish.
-    let res = tokio::try_join!(tendermint_node, abci, eth_oracle, broadcaster);
+    let res = tokio::try_join!(
+        tendermint_node,
+        abci,
+        eth_oracle,
+        broadcaster,
+        event_subscriptions,
+        grpc,
+        rest
+    );
 
     match res {
-        Ok((tendermint_res, abci_res, _, _)) => {
+        Ok((tendermint_res, abci_res, _, _, _, _, _)) => {
             // we ignore errors on user-initiated shutdown
             if aborted {
                 if let Err(err) = tendermint_res {
@@ -428,6 +690,7 @@ fn start_abci_broadcaster_shell(
 ) -> (
     task::JoinHandle<shell::Result<()>>,
     task::JoinHandle<()>,
+    task::JoinHandle<()>,
     thread::JoinHandle<()>,
 ) {
     let rpc_address =
@@ -468,9 +731,34 @@ fn start_abci_broadcaster_shell(
         spawn_dummy_task(())
     };
 
+    // Start serving the event subscriptions, if enabled
+    let (event_sender, event_subscriptions) = if let Some(listen_addr) =
+        config.shell.event_subscriptions
+    {
+        let (event_sender, _) = tokio::sync::broadcast::channel(
+            event_subscriptions::EVENTS_CHANNEL_CAPACITY,
+        );
+        let (es_abort_send, es_abort_recv) =
+            tokio::sync::oneshot::channel::<()>();
+        let events = event_sender.clone();
+        let handle = spawner
+            .spawn_abortable("Event subscriptions", move |aborter| async move {
+                event_subscriptions::serve(listen_addr, events, es_abort_recv)
+                    .await;
+                tracing::info!("Event subscriptions are no longer served.");
+
+                drop(aborter);
+            })
+            .with_cleanup(async move {
+                let _ = es_abort_send.send(());
+            });
+        (Some(event_sender), handle)
+    } else {
+        (None, spawn_dummy_task(()))
+    };
+
     // Setup DB cache, it must outlive the DB instance that's in the shell
-    let db_cache =
-        rocksdb::Cache::new_lru_cache(db_block_cache_size_bytes as usize);
+    let db_cache = storage::new_db_cache(db_block_cache_size_bytes);
 
     // Construct our ABCI application.
     let tendermint_mode = config.shell.tendermint_mode.clone();
@@ -485,6 +773,7 @@ fn start_abci_broadcaster_shell(
         &db_cache,
         vp_wasm_compilation_cache,
         tx_wasm_compilation_cache,
+        event_sender,
     );
 
     // Channel for signalling shut down to ABCI server
@@ -525,7 +814,7 @@ fn start_abci_broadcaster_shell(
         })
         .expect("Must be able to start a thread for the shell");
 
-    (abci, broadcaster, shell_handler)
+    (abci, broadcaster, event_subscriptions, shell_handler)
 }
 
 /// Runs the an asynchronous ABCI server with four sub-components for consensus,
@@ -637,6 +926,56 @@ fn start_tendermint(
         })
 }
 
+/// Start serving the gRPC queries, if enabled in the config
+fn start_grpc(
+    spawner: &mut AbortableSpawner,
+    config: &config::Ledger,
+) -> task::JoinHandle<()> {
+    let Some(listen_addr) = config.shell.grpc else {
+        return spawn_dummy_task(());
+    };
+    let rpc_address =
+        convert_tm_addr_to_socket_addr(&config.cometbft.rpc.laddr);
+    let (grpc_abort_send, grpc_abort_recv) =
+        tokio::sync::oneshot::channel::<()>();
+
+    spawner
+        .spawn_abortable("gRPC", move |aborter| async move {
+            grpc::serve(listen_addr, rpc_address, grpc_abort_recv).await;
+            tracing::info!("gRPC queries are no longer served.");
+
+            drop(aborter);
+        })
+        .with_cleanup(async move {
+            let _ = grpc_abort_send.send(());
+        })
+}
+
+/// Start serving the REST gateway, if enabled in the config
+fn start_rest(
+    spawner: &mut AbortableSpawner,
+    config: &config::Ledger,
+) -> task::JoinHandle<()> {
+    let Some(listen_addr) = config.shell.rest else {
+        return spawn_dummy_task(());
+    };
+    let rpc_address =
+        convert_tm_addr_to_socket_addr(&config.cometbft.rpc.laddr);
+    let (rest_abort_send, rest_abort_recv) =
+        tokio::sync::oneshot::channel::<()>();
+
+    spawner
+        .spawn_abortable("REST", move |aborter| async move {
+            rest::serve(listen_addr, rpc_address, rest_abort_recv).await;
+            tracing::info!("REST gateway is no longer served.");
+
+            drop(aborter);
+        })
+        .with_cleanup(async move {
+            let _ = rest_abort_send.send(());
+        })
+}
+
 /// Represents a [`tokio::task`] in which an Ethereum oracle may be running, and
 /// if so, channels for communicating with it.
 enum EthereumOracleTask {
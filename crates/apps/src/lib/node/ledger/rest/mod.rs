@@ -0,0 +1,247 @@
+//! An HTTP/JSON gateway for the common queries of the ledger and the
+//! broadcast of txs, described by the OpenAPI spec in `openapi.yml` served at
+//! `/openapi.yml`. The gateway maps the requests to the gRPC
+//! [`QueryService`], so its responses are the JSON encoded messages of
+//! `proto/query.proto`.
+
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::oneshot;
+use tonic::{Code, Request, Response, Status};
+use warp::http::StatusCode;
+use warp::reply::Reply;
+use warp::Filter;
+
+use super::grpc::query::query_server::Query;
+use super::grpc::{query, QueryService};
+
+/// The OpenAPI spec of the gateway
+const OPENAPI_SPEC: &str = include_str!("openapi.yml");
+
+/// The maximum size of a broadcast tx, in bytes
+const MAX_TX_BYTES: u64 = 1024 * 1024;
+
+/// The parameters of the `/balance` endpoint
+#[derive(Debug, Deserialize)]
+struct BalanceParams {
+    token: String,
+    owner: String,
+}
+
+/// The parameters of the `/validators` endpoint
+#[derive(Debug, Deserialize)]
+struct ValidatorsParams {
+    epoch: Option<u64>,
+}
+
+/// The parameters of the `/delegations` endpoint
+#[derive(Debug, Deserialize)]
+struct DelegationsParams {
+    delegator: String,
+    epoch: Option<u64>,
+}
+
+/// The parameters of the `/proposals` endpoint
+#[derive(Debug, Deserialize)]
+struct ProposalsParams {
+    start: Option<u64>,
+    limit: Option<u32>,
+}
+
+/// Serve the gateway on the given address until a signal is sent on the
+/// `abort_recv` channel.
+pub async fn serve(
+    listen_addr: SocketAddr,
+    rpc_address: SocketAddr,
+    abort_recv: oneshot::Receiver<()>,
+) {
+    let service = Arc::new(QueryService::new(rpc_address));
+    let with_service = warp::any().map(move || service.clone());
+
+    let openapi = warp::path!("openapi.yml").and(warp::get()).map(|| {
+        warp::reply::with_header(
+            OPENAPI_SPEC,
+            "content-type",
+            "application/yaml",
+        )
+    });
+    let epoch = warp::path!("epoch")
+        .and(warp::get())
+        .and(with_service.clone())
+        .and_then(|service: Arc<QueryService>| async move {
+            let request = query::EpochRequest {};
+            reply(service.epoch(Request::new(request)).await)
+        });
+    let balance = warp::path!("balance")
+        .and(warp::get())
+        .and(warp::query::<BalanceParams>())
+        .and(with_service.clone())
+        .and_then(
+            |params: BalanceParams, service: Arc<QueryService>| async move {
+                let request = query::BalanceRequest {
+                    token: params.token,
+                    owner: params.owner,
+                };
+                reply(service.balance(Request::new(request)).await)
+            },
+        );
+    let validators = warp::path!("validators")
+        .and(warp::get())
+        .and(warp::query::<ValidatorsParams>())
+        .and(with_service.clone())
+        .and_then(
+            |params: ValidatorsParams, service: Arc<QueryService>| async move {
+                let request = query::ValidatorsRequest {
+                    epoch: params.epoch,
+                };
+                reply(service.validators(Request::new(request)).await)
+            },
+        );
+    let delegations = warp::path!("delegations")
+        .and(warp::get())
+        .and(warp::query::<DelegationsParams>())
+        .and(with_service.clone())
+        .and_then(
+            |params: DelegationsParams, service: Arc<QueryService>| async move {
+                let request = query::DelegationsRequest {
+                    delegator: params.delegator,
+                    epoch: params.epoch,
+                };
+                reply(service.delegations(Request::new(request)).await)
+            },
+        );
+    let proposals = warp::path!("proposals")
+        .and(warp::get())
+        .and(warp::query::<ProposalsParams>())
+        .and(with_service.clone())
+        .and_then(
+            |params: ProposalsParams, service: Arc<QueryService>| async move {
+                let request = query::ProposalsRequest {
+                    start: params.start,
+                    limit: params.limit,
+                };
+                reply(service.proposals(Request::new(request)).await)
+            },
+        );
+    let proposal = warp::path!("proposals" / u64)
+        .and(warp::get())
+        .and(with_service.clone())
+        .and_then(|id: u64, service: Arc<QueryService>| async move {
+            let request = query::ProposalRequest { id };
+            let response = service.proposal(Request::new(request)).await;
+            reply(response.and_then(|response| {
+                response
+                    .into_inner()
+                    .proposal
+                    .map(Response::new)
+                    .ok_or_else(|| {
+                        Status::not_found(format!("No proposal with id {id}"))
+                    })
+            }))
+        });
+    let broadcast_tx = warp::path!("tx")
+        .and(warp::post())
+        .and(warp::body::content_length_limit(MAX_TX_BYTES))
+        .and(warp::body::bytes())
+        .and(with_service.clone())
+        .and_then(|tx: bytes::Bytes, service: Arc<QueryService>| async move {
+            let request = query::BroadcastTxRequest { tx: tx.to_vec() };
+            reply(service.broadcast_tx(Request::new(request)).await)
+        });
+    let tx_status = warp::path!("tx" / String)
+        .and(warp::get())
+        .and(with_service)
+        .and_then(|hash: String, service: Arc<QueryService>| async move {
+            let request = query::TxStatusRequest { hash };
+            reply(service.tx_status(Request::new(request)).await)
+        });
+    let routes = openapi
+        .or(epoch)
+        .or(balance)
+        .or(validators)
+        .or(delegations)
+        .or(proposals)
+        .or(proposal)
+        .or(broadcast_tx)
+        .or(tx_status);
+
+    let server = warp::serve(routes).try_bind_with_graceful_shutdown(
+        listen_addr,
+        async move {
+            _ = abort_recv.await;
+            tracing::info!(?listen_addr, "Stopping to serve the REST gateway");
+        },
+    );
+    match server {
+        Ok((_, future)) => {
+            tracing::info!(?listen_addr, "Serving the REST gateway");
+            future.await
+        }
+        Err(err) => tracing::error!(
+            ?listen_addr,
+            "Failed to serve the REST gateway: {err}"
+        ),
+    }
+}
+
+/// The error of a failed request
+#[derive(Debug, Serialize)]
+struct ErrorReply {
+    error: String,
+}
+
+/// Reply with the JSON encoded response, or with the error and the HTTP
+/// status matching the status of the query
+fn reply<T: Serialize>(
+    response: Result<Response<T>, Status>,
+) -> Result<warp::reply::Response, Infallible> {
+    Ok(match response {
+        Ok(response) => warp::reply::json(response.get_ref()).into_response(),
+        Err(status) => warp::reply::with_status(
+            warp::reply::json(&ErrorReply {
+                error: status.message().to_string(),
+            }),
+            http_status(status.code()),
+        )
+        .into_response(),
+    })
+}
+
+/// The HTTP status of a failed query
+fn http_status(code: Code) -> StatusCode {
+    match code {
+        Code::InvalidArgument => StatusCode::BAD_REQUEST,
+        Code::NotFound => StatusCode::NOT_FOUND,
+        Code::Unavailable => StatusCode::BAD_GATEWAY,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test the HTTP status of the failed queries
+    #[test]
+    fn test_http_status() {
+        let response = reply::<query::EpochResponse>(Err(
+            Status::invalid_argument("Invalid address"),
+        ))
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(http_status(Code::NotFound), StatusCode::NOT_FOUND);
+        assert_eq!(http_status(Code::Unavailable), StatusCode::BAD_GATEWAY);
+        assert_eq!(
+            http_status(Code::Internal),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+
+        let response =
+            reply(Ok(Response::new(query::EpochResponse { epoch: 1 })))
+                .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
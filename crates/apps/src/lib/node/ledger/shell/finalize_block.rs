@@ -4,27 +4,38 @@ use data_encoding::HEXUPPER;
 use masp_primitives::merkle_tree::CommitmentTree;
 use masp_primitives::sapling::Node;
 use namada::governance::pgf::inflation as pgf_inflation;
+use namada::ibc::IbcMessage;
 use namada::ledger::events::EventType;
 use namada::ledger::gas::{GasMetering, TxGasMeter};
+use namada::ledger::pgf::utils::ProposalEvent as PgfEvent;
 use namada::ledger::pos::namada_proof_of_stake;
 use namada::ledger::protocol::{self, WrapperArgs};
 use namada::proof_of_stake::storage::{
     find_validator_by_raw_hash, read_last_block_proposer_address,
     write_last_block_proposer_address,
 };
+use namada::state::account_history::AccountTx;
 use namada::state::wl_storage::WriteLogAndStorage;
 use namada::state::write_log::StorageModification;
 use namada::state::{
     ResultExt, StorageRead, StorageWrite, EPOCH_SWITCH_BLOCKS_DELAY,
 };
+use namada::token::compact::{CompactBlock, CompactTx};
 use namada::token::conversion::update_allowed_conversions;
 use namada::tx::data::protocol::ProtocolTxType;
+use namada::tx::data::truncate_error_message;
+use namada::types::hash::Hash;
+use namada::types::ibc::{get_shielded_transfer, IbcEvent};
 use namada::types::key::tm_raw_hash_to_string;
 use namada::types::storage::{BlockHash, BlockResults, Epoch, Header};
+use namada::types::token::Transfer;
 use namada::vote_ext::ethereum_events::MultiSignedEthEvent;
 use namada::vote_ext::ethereum_tx_data_variants;
 
-use super::governance::execute_governance_proposals;
+use super::governance::{
+    emit_voting_started_events, execute_governance_proposals,
+    proposal_lifecycle_events,
+};
 use super::*;
 use crate::facade::tendermint::abci::types::{Misbehavior, VoteInfo};
 use crate::node::ledger::shell::stats::InternalStats;
@@ -95,6 +106,7 @@ where
             update_allowed_conversions(&mut self.wl_storage)?;
 
             execute_governance_proposals(self, &mut response)?;
+            emit_voting_started_events(self, &mut response)?;
 
             // Copy the new_epoch + pipeline_len - 1 validator set into
             // new_epoch + pipeline_len
@@ -182,6 +194,9 @@ where
             )?;
         }
 
+        // Prune the IBC packet statuses whose retention window has ended
+        namada::ibc::prune_packet_statuses(&mut self.wl_storage, height)?;
+
         let mut stats = InternalStats::default();
 
         let native_block_proposer_address = {
@@ -198,6 +213,11 @@ where
         // Tracks the accepted transactions
         self.wl_storage.storage.block.results = BlockResults::default();
         let mut changed_keys = BTreeSet::new();
+        // Tracks the compact data of the valid MASP transactions
+        let mut compact_block = CompactBlock {
+            height,
+            txs: vec![],
+        };
         for (tx_index, processed_tx) in req.txs.iter().enumerate() {
             let tx = if let Ok(tx) = Tx::try_from(processed_tx.tx.as_ref()) {
                 tx
@@ -428,6 +448,13 @@ where
                 .map_err(Error::TxApply);
             match tx_result {
                 Ok(result) => {
+                    self.record_account_tx(
+                        &tx_header.tx_type,
+                        &tx_event,
+                        height,
+                        tx_index,
+                        &result,
+                    );
                     if result.is_accepted() {
                         if let EventType::Accepted = tx_event.event_type {
                             // Wrapper transaction
@@ -441,6 +468,11 @@ where
                             {
                                 tx_event["is_valid_masp_tx"] =
                                     format!("{}", tx_index);
+                                compact_block.txs.extend(compact_masp_tx(
+                                    tx_index,
+                                    processed_tx.tx.as_ref(),
+                                    &result.ibc_events,
+                                ));
                             }
                             self.wl_storage.storage.tx_queue.push(TxInQueue {
                                 tx: wrapper.expect("Missing expected wrapper"),
@@ -460,6 +492,11 @@ where
                             ) {
                                 tx_event["is_valid_masp_tx"] =
                                     format!("{}", tx_index);
+                                compact_block.txs.extend(compact_masp_tx(
+                                    tx_index,
+                                    processed_tx.tx.as_ref(),
+                                    &result.ibc_events,
+                                ));
                             }
                             changed_keys
                                 .extend(result.changed_keys.iter().cloned());
@@ -496,8 +533,23 @@ where
                                         .eth_bridge_events
                                         .iter()
                                         .map(Event::from),
-                                ),
+                                )
+                                // events emitted by the tx
+                                .chain(result.tx_events.iter().map(
+                                    |emitted| {
+                                        let mut event = Event::from(emitted);
+                                        event["hash"] =
+                                            tx_event["hash"].clone();
+                                        event["height"] = height.to_string();
+                                        event
+                                    },
+                                )),
                         );
+                        // governance proposal lifecycle events
+                        response.events.extend(proposal_lifecycle_events(
+                            &self.wl_storage,
+                            &result.changed_keys,
+                        )?);
                     } else {
                         tracing::trace!(
                             "some VPs rejected transaction {} storage \
@@ -566,7 +618,11 @@ where
 
                     tx_event["gas_used"] =
                         tx_gas_meter.get_tx_consumed_gas().to_string();
-                    tx_event["info"] = msg.to_string();
+                    tx_event["info"] = truncate_error_message(msg.to_string());
+                    if let Error::TxApply(err) = &msg {
+                        tx_event["error_code"] =
+                            err.to_wasm_error().code.to_string();
+                    }
                     if let EventType::Accepted = tx_event.event_type {
                         // If wrapper, invalid tx error code
                         tx_event["code"] = ResultCode::InvalidTx.into();
@@ -578,6 +634,11 @@ where
                         {
                             tx_event["is_valid_masp_tx"] =
                                 format!("{}", tx_index);
+                            compact_block.txs.extend(compact_masp_tx(
+                                tx_index,
+                                processed_tx.tx.as_ref(),
+                                &BTreeSet::new(),
+                            ));
                         }
                     } else {
                         tx_event["code"] = ResultCode::WasmRuntimeError.into();
@@ -612,6 +673,12 @@ where
             self.wl_storage.write(&anchor_key, ())?;
         }
 
+        // Index the compact MASP data of the block for light wallets
+        token::compact::write_compact_block(
+            &mut self.wl_storage,
+            &compact_block,
+        )?;
+
         if update_for_tendermint {
             self.update_epoch(&mut response);
             // send the latest oracle configs. These may have changed due to
@@ -724,6 +791,13 @@ where
             response.events.push(event);
         }
 
+        // Pgf grant milestones
+        for release in pgf_inflation::release_grants(&mut self.wl_storage)? {
+            response
+                .events
+                .push(PgfEvent::pgf_grant_release(release).into());
+        }
+
         Ok(())
     }
 
@@ -781,6 +855,48 @@ where
             .delete_tx_hash(wrapper_tx.header_hash())
             .expect("Error while deleting tx hash from storage");
     }
+
+    // Record an applied tx in the history of the accounts it involved, i.e.
+    // its verifiers and the fee payer of a wrapper, if the account history is
+    // indexed
+    fn record_account_tx(
+        &mut self,
+        tx_type: &TxType,
+        tx_event: &Event,
+        height: BlockHeight,
+        tx_index: usize,
+        result: &namada::tx::data::TxResult,
+    ) {
+        let Some(account_history) =
+            self.wl_storage.storage.account_history.as_mut()
+        else {
+            return;
+        };
+        let Ok(hash) = Hash::try_from(tx_event["hash"].as_str()) else {
+            return;
+        };
+        let mut addresses: BTreeSet<Address> = result
+            .vps_result
+            .accepted_vps
+            .union(&result.vps_result.rejected_vps)
+            .cloned()
+            .collect();
+        if let TxType::Wrapper(wrapper) = tx_type {
+            addresses.insert(wrapper.fee_payer());
+        }
+        account_history.record(
+            AccountTx {
+                height,
+                index: TxIndex(
+                    tx_index
+                        .try_into()
+                        .expect("transaction index out of bounds"),
+                ),
+                hash,
+            },
+            addresses,
+        );
+    }
 }
 
 /// Convert ABCI vote info to PoS vote info. Any info which fails the conversion
@@ -846,6 +962,60 @@ fn pos_votes_from_abci(
         .collect()
 }
 
+/// Extract the compact data of a transaction accepted by the MASP VP. The MASP
+/// transaction is found in the same way as the shielded wallets do: in the fee
+/// unshielding section of a wrapper, in the shielded section of a transfer or
+/// in the IBC message or events of a shielded action over IBC.
+fn compact_masp_tx(
+    tx_index: usize,
+    tx_bytes: &[u8],
+    ibc_events: &BTreeSet<IbcEvent>,
+) -> Option<CompactTx> {
+    let tx_index = TxIndex(
+        tx_index
+            .try_into()
+            .expect("transaction index out of bounds"),
+    );
+    let transaction = Tx::try_from(tx_bytes).ok().and_then(|tx| {
+        let section_hash = match tx.header().wrapper() {
+            Some(wrapper) => wrapper.unshield_section_hash,
+            None => {
+                let data = tx.data()?;
+                match Transfer::try_from_slice(&data) {
+                    Ok(transfer) => transfer.shielded,
+                    Err(_) => {
+                        return match namada::ibc::decode_message(&data) {
+                            Ok(IbcMessage::ShieldedTransfer(msg)) => {
+                                Some(msg.shielded_transfer.masp_tx)
+                            }
+                            Ok(IbcMessage::Envelope(_)) => {
+                                ibc_events.iter().find_map(|event| {
+                                    get_shielded_transfer(event)
+                                        .ok()
+                                        .flatten()
+                                        .map(|transfer| transfer.masp_tx)
+                                })
+                            }
+                            _ => None,
+                        };
+                    }
+                }
+            }
+        };
+        tx.get_section(&section_hash?)?.masp_tx()
+    });
+    match transaction {
+        Some(transaction) => Some(CompactTx::new(tx_index, &transaction)),
+        None => {
+            tracing::error!(
+                "Couldn't find the MASP transaction of the valid MASP tx at \
+                 index {tx_index}"
+            );
+            None
+        }
+    }
+}
+
 /// We test the failure cases of [`finalize_block`]. The happy flows
 /// are covered by the e2e tests.
 #[cfg(test)]
@@ -861,8 +1031,12 @@ mod test_finalize_block {
     };
     use namada::eth_bridge::storage::min_confirmations_key;
     use namada::ethereum_bridge::storage::wrapped_erc20s;
-    use namada::governance::storage::keys::get_proposal_execution_key;
+    use namada::governance::storage::keys::{
+        get_counter_key, get_proposal_execution_key, get_proposal_type_key,
+        get_vote_proposal_key,
+    };
     use namada::governance::storage::proposal::ProposalType;
+    use namada::governance::utils::VotingMode;
     use namada::governance::{InitProposalData, VoteProposalData};
     use namada::ledger::gas::VpGasMeter;
     use namada::ledger::native_vp::parameters::ParametersVp;
@@ -1647,11 +1821,15 @@ mod test_finalize_block {
             let proposal = InitProposalData {
                 id: proposal_id,
                 content: Hash::default(),
+                content_hash: Hash::sha256(b""),
+                content_uri: None,
                 author: validator.clone(),
                 voting_start_epoch: Epoch::default(),
                 voting_end_epoch: Epoch::default().next(),
                 grace_epoch: Epoch::default().next(),
-                r#type: ProposalType::Default(None),
+                expedited: false,
+                voting_mode: VotingMode::Linear,
+                r#type: ProposalType::Text,
             };
 
             namada::governance::init_proposal(
@@ -1775,6 +1953,70 @@ mod test_finalize_block {
         }
     }
 
+    /// Test that the lifecycle events of the proposals created and voted by a
+    /// transaction are indexed by proposal id
+    #[test]
+    fn test_proposal_lifecycle_events() {
+        let (mut shell, _broadcaster, _, _eth_control) = setup();
+        let validator = shell.mode.get_validator_address().unwrap().clone();
+
+        let proposal = InitProposalData {
+            id: 0,
+            content: Hash::default(),
+            content_hash: Hash::sha256(b""),
+            content_uri: None,
+            author: validator.clone(),
+            voting_start_epoch: Epoch(1),
+            voting_end_epoch: Epoch(2),
+            grace_epoch: Epoch(3),
+            expedited: false,
+            voting_mode: VotingMode::Linear,
+            r#type: ProposalType::Text,
+        };
+        namada::governance::init_proposal(
+            &mut shell.wl_storage,
+            proposal,
+            vec![],
+            None,
+        )
+        .unwrap();
+        let vote = VoteProposalData {
+            id: 0,
+            vote: ProposalVote::Yay,
+            voter: validator.clone(),
+            delegations: vec![validator.clone()],
+        };
+        namada::governance::vote_proposal(&mut shell.wl_storage, vote).unwrap();
+
+        // The counter key is not a key of a proposal and is ignored
+        let changed_keys = BTreeSet::from([
+            get_counter_key(),
+            get_proposal_type_key(0),
+            get_vote_proposal_key(0, validator.clone(), validator.clone()),
+        ]);
+        let events =
+            proposal_lifecycle_events(&shell.wl_storage, &changed_keys)
+                .unwrap();
+
+        assert_eq!(events.len(), 2);
+        for event in &events {
+            assert_eq!(event.event_type, EventType::ProposalLifecycle);
+            assert_eq!(event["proposal_id"], "0");
+        }
+        let created = events
+            .iter()
+            .find(|event| event["stage"] == "created")
+            .expect("Test failed");
+        assert_eq!(created["author"], validator.to_string());
+        assert_eq!(created["voting_start_epoch"], "1");
+        let voted = events
+            .iter()
+            .find(|event| event["stage"] == "voted")
+            .expect("Test failed");
+        assert_eq!(voted["voter"], validator.to_string());
+        assert_eq!(voted["vote"], "yay");
+    }
+
     /// A unit test for PoS inflationary rewards
     #[test]
     fn test_inflation_accounting() {
@@ -1,30 +1,32 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet};
 
+use namada::governance::parameters::DepositAction;
 use namada::governance::pgf::storage::keys as pgf_storage;
-use namada::governance::pgf::storage::steward::StewardDetail;
 use namada::governance::pgf::{storage as pgf, ADDRESS};
 use namada::governance::storage::keys as gov_storage;
 use namada::governance::storage::proposal::{
-    AddRemove, PGFAction, PGFTarget, ProposalType, StoragePgfFunding,
+    AddRemove, EmergencyHalt, PGFAction, PGFTarget, ParameterChange,
+    ProposalType, StoragePgfFunding, WasmAllowlistUpdate,
 };
 use namada::governance::utils::{
-    compute_proposal_result, ProposalVotes, TallyResult, TallyType, TallyVote,
-    VotePower,
+    compute_proposal_result_with_voting_mode, TallyResult, TallyType,
+};
+use namada::governance::{
+    storage as gov_api, ProposalVote, ADDRESS as gov_address,
+};
+use namada::ledger::governance::utils::{
+    ProposalEvent, ProposalLifecycleEvent,
 };
-use namada::governance::{storage as gov_api, ADDRESS as gov_address};
-use namada::ledger::governance::utils::ProposalEvent;
-use namada::ledger::pos::BondId;
 use namada::ledger::protocol;
-use namada::proof_of_stake::bond_amount;
-use namada::proof_of_stake::parameters::PosParams;
-use namada::proof_of_stake::storage::read_total_stake;
+use namada::proof_of_stake::queries::{
+    compute_proposal_votes, compute_total_voting_power,
+};
 use namada::state::{DBIter, StorageHasher, StorageWrite, DB};
 use namada::tx::{Code, Data};
 use namada::types::address::Address;
 use namada::types::encode;
 use namada::types::storage::Epoch;
 use namada::{ibc, token};
-use namada_sdk::proof_of_stake::storage::read_validator_stake;
 
 use super::utils::force_read;
 use super::*;
@@ -62,29 +64,79 @@ where
 
         let is_steward = pgf::is_steward(&shell.wl_storage, &proposal_author)?;
 
+        // An expedited proposal is first tallied at the end of its expedited
+        // voting period, before the end of its standard one
+        let current_epoch = shell.wl_storage.get_block_epoch()?;
+        let expedited_end_epoch =
+            gov_api::get_expedited_end_epoch(&shell.wl_storage, id)?
+                .filter(|_| current_epoch <= proposal_end_epoch);
+        let tally_epoch = expedited_end_epoch.unwrap_or(proposal_end_epoch);
+
+        let voting_mode =
+            gov_api::get_proposal_voting_mode(&shell.wl_storage, id)?;
         let params = read_pos_params(&shell.wl_storage)?;
-        let total_voting_power =
-            read_total_stake(&shell.wl_storage, &params, proposal_end_epoch)?;
+        let total_voting_power = compute_total_voting_power(
+            &shell.wl_storage,
+            &params,
+            voting_mode,
+            tally_epoch,
+        )?;
 
         let tally_type = TallyType::from(proposal_type.clone(), is_steward);
         let votes = compute_proposal_votes(
             &shell.wl_storage,
             &params,
             id,
-            proposal_end_epoch,
+            proposal_type.kind(),
+            tally_epoch,
         )?;
-        let proposal_result =
-            compute_proposal_result(votes, total_voting_power, tally_type);
+        let veto_threshold = gov_api::get_veto_threshold(&shell.wl_storage)?;
+        let proposal_result = compute_proposal_result_with_voting_mode(
+            votes,
+            total_voting_power,
+            tally_type,
+            voting_mode,
+        )
+        .with_veto_threshold(veto_threshold);
+        let proposal_result = match expedited_end_epoch {
+            Some(expedited_end_epoch) => {
+                let gov_params = gov_api::get_parameters(&shell.wl_storage)?;
+                let proposal_result = proposal_result
+                    .with_expedited_thresholds(
+                        gov_params.expedited_quorum,
+                        gov_params.expedited_threshold,
+                    );
+                if !matches!(proposal_result.result, TallyResult::Passed) {
+                    tracing::info!(
+                        "Expedited governance proposal {} didn't reach the \
+                         expedited quorum and threshold, it continues as a \
+                         standard proposal.",
+                        id
+                    );
+                    continue;
+                }
+                gov_api::close_expedited_proposal(
+                    &mut shell.wl_storage,
+                    id,
+                    expedited_end_epoch,
+                )?;
+                proposal_result
+            }
+            None => proposal_result,
+        };
         gov_api::write_proposal_result(
             &mut shell.wl_storage,
             id,
             proposal_result,
         )?;
+        response
+            .events
+            .push(ProposalLifecycleEvent::tallied(id, &proposal_result).into());
 
-        let transfer_address = match proposal_result.result {
+        match proposal_result.result {
             TallyResult::Passed => {
-                let proposal_event = match proposal_type {
-                    ProposalType::Default(_) => {
+                let (proposal_event, result) = match proposal_type {
+                    ProposalType::Text | ProposalType::WasmCodeUpgrade(_) => {
                         let proposal_code =
                             gov_api::get_proposal_code(&shell.wl_storage, id)?;
                         let result = execute_default_proposal(
@@ -104,17 +156,20 @@ where
                             result
                         );
 
-                        ProposalEvent::default_proposal_event(
-                            id,
-                            proposal_code.is_some(),
+                        (
+                            ProposalEvent::default_proposal_event(
+                                id,
+                                proposal_code.is_some(),
+                                result,
+                            ),
                             result,
                         )
-                        .into()
                     }
-                    ProposalType::PGFSteward(stewards) => {
+                    ProposalType::PGFSteward(stewards, spending_cap) => {
                         let result = execute_pgf_steward_proposal(
                             &mut shell.wl_storage,
                             stewards,
+                            spending_cap,
                         )?;
                         tracing::info!(
                             "Governance proposal (pgf stewards){} has been \
@@ -122,22 +177,55 @@ where
                             id
                         );
 
-                        ProposalEvent::pgf_steward_proposal_event(id, result)
-                            .into()
+                        (
+                            ProposalEvent::pgf_steward_proposal_event(
+                                id, result,
+                            ),
+                            result,
+                        )
                     }
                     ProposalType::PGFPayment(payments) => {
                         let native_token =
                             &shell.wl_storage.get_native_token()?;
-                        let result = execute_pgf_funding_proposal(
-                            &mut shell.wl_storage,
-                            native_token,
-                            payments,
-                            id,
-                        )?;
+                        // The retro payments proposed by a steward are
+                        // bounded by its spending cap
+                        let retro_amount =
+                            PGFAction::total_retro_amount(&payments);
+                        let is_within_spending_cap = !is_steward
+                            || pgf::get_steward_spending(
+                                &shell.wl_storage,
+                                &proposal_author,
+                            )?
+                            .can_spend(retro_amount);
+                        let result = if is_within_spending_cap {
+                            if is_steward {
+                                pgf::record_steward_spending(
+                                    &mut shell.wl_storage,
+                                    &proposal_author,
+                                    retro_amount,
+                                )?;
+                            }
+                            execute_pgf_funding_proposal(
+                                &mut shell.wl_storage,
+                                native_token,
+                                payments,
+                                id,
+                            )?
+                        } else {
+                            tracing::info!(
+                                "Governance proposal (pgf funding) {} exceeds \
+                                 the spending cap of steward {} and won't be \
+                                 executed.",
+                                id,
+                                proposal_author
+                            );
+                            false
+                        };
                         tracing::info!(
                             "Governance proposal (pgf funding) {} has been \
-                             executed and passed.",
-                            id
+                             executed ({}) and passed.",
+                            id,
+                            result
                         );
 
                         for ibc_event in
@@ -154,16 +242,104 @@ where
                             response.events.push(event);
                         }
 
-                        ProposalEvent::pgf_payments_proposal_event(id, result)
-                            .into()
+                        (
+                            ProposalEvent::pgf_payments_proposal_event(
+                                id, result,
+                            ),
+                            result,
+                        )
+                    }
+                    ProposalType::MaspRewards(update) => {
+                        let result = execute_masp_rewards_proposal(
+                            &mut shell.wl_storage,
+                            update,
+                        )?;
+                        tracing::info!(
+                            "Governance proposal (masp rewards) {} has been \
+                             executed and passed.",
+                            id
+                        );
+
+                        (
+                            ProposalEvent::masp_rewards_proposal_event(
+                                id, result,
+                            ),
+                            result,
+                        )
+                    }
+                    ProposalType::ParameterChange(changes) => {
+                        let result = execute_parameter_change_proposal(
+                            &mut shell.wl_storage,
+                            changes,
+                        )?;
+                        tracing::info!(
+                            "Governance proposal (parameter change) {} has \
+                             been executed and passed.",
+                            id
+                        );
+
+                        (
+                            ProposalEvent::parameter_change_proposal_event(
+                                id, result,
+                            ),
+                            result,
+                        )
+                    }
+                    ProposalType::EmergencyHalt(halt) => {
+                        let result = execute_emergency_halt_proposal(
+                            &mut shell.wl_storage,
+                            halt,
+                        )?;
+                        tracing::info!(
+                            "Governance proposal (emergency halt) {} has been \
+                             executed and passed.",
+                            id
+                        );
+
+                        (
+                            ProposalEvent::emergency_halt_proposal_event(
+                                id, result,
+                            ),
+                            result,
+                        )
+                    }
+                    ProposalType::WasmAllowlist(updates) => {
+                        let result = execute_wasm_allowlist_proposal(
+                            &mut shell.wl_storage,
+                            updates,
+                        )?;
+                        tracing::info!(
+                            "Governance proposal (wasm allowlist) {} has been \
+                             executed ({}) and passed.",
+                            id,
+                            result
+                        );
+
+                        (
+                            ProposalEvent::wasm_allowlist_proposal_event(
+                                id, result,
+                            ),
+                            result,
+                        )
+                    }
+                    ProposalType::Signaling => {
+                        // Nothing to execute, only the outcome of the vote
+                        // is recorded
+                        tracing::info!(
+                            "Governance proposal (signaling) {} has passed.",
+                            id
+                        );
+
+                        (ProposalEvent::signaling_proposal_event(id), true)
                     }
                 };
-                response.events.push(proposal_event);
+                response.events.push(proposal_event.into());
+                response
+                    .events
+                    .push(ProposalLifecycleEvent::executed(id, result).into());
                 proposals_result.passed.push(id);
-
-                gov_api::get_proposal_author(&shell.wl_storage, id)?
             }
-            TallyResult::Rejected => {
+            TallyResult::Rejected | TallyResult::Vetoed => {
                 if let ProposalType::PGFPayment(_) = proposal_type {
                     if proposal_result.two_thirds_nay_over_two_thirds_total() {
                         pgf::remove_steward(
@@ -181,102 +357,159 @@ where
                         );
                     }
                 }
-                let proposal_event =
-                    ProposalEvent::rejected_proposal_event(id).into();
+                let is_vetoed =
+                    matches!(proposal_result.result, TallyResult::Vetoed);
+                let proposal_event = if is_vetoed {
+                    ProposalEvent::vetoed_proposal_event(id).into()
+                } else {
+                    ProposalEvent::rejected_proposal_event(id).into()
+                };
                 response.events.push(proposal_event);
                 proposals_result.rejected.push(id);
 
                 tracing::info!(
-                    "Governance proposal {} has been executed and rejected.",
-                    id
+                    "Governance proposal {} has been executed and {}.",
+                    id,
+                    proposal_result.result
                 );
-
-                None
             }
-        };
+        }
 
+        let deposit_policy = gov_api::get_deposit_policy(&shell.wl_storage)?;
+        let deposit_action = deposit_policy.action(&proposal_result);
         let native_token = shell.wl_storage.get_native_token()?;
-        if let Some(address) = transfer_address {
-            token::transfer(
+        match deposit_action {
+            DepositAction::Refund => token::transfer(
                 &mut shell.wl_storage,
                 &native_token,
                 &gov_address,
-                &address,
+                &proposal_author,
                 funds,
-            )?;
-        } else {
-            token::burn_tokens(
+            )?,
+            DepositAction::Burn => token::burn_tokens(
                 &mut shell.wl_storage,
                 &native_token,
                 &gov_address,
                 funds,
-            )?;
+            )?,
         }
+        tracing::info!(
+            "Governance proposal {} deposit of {} has been handled with \
+             action {}.",
+            id,
+            funds.to_string_native(),
+            deposit_action
+        );
+        response.events.push(
+            ProposalEvent::deposit_event(id, deposit_action, funds).into(),
+        );
     }
 
     Ok(proposals_result)
 }
 
-fn compute_proposal_votes<S>(
-    storage: &S,
-    params: &PosParams,
-    proposal_id: u64,
-    epoch: Epoch,
-) -> namada::state::StorageResult<ProposalVotes>
+/// Emit the lifecycle events of the proposals whose voting period starts at
+/// the current epoch
+pub fn emit_voting_started_events<D, H>(
+    shell: &mut Shell<D, H>,
+    response: &mut shim::response::FinalizeBlock,
+) -> Result<()>
 where
-    S: StorageRead,
+    D: DB + for<'iter> DBIter<'iter> + Sync + 'static,
+    H: StorageHasher + Sync + 'static,
 {
-    let votes = gov_api::get_proposal_votes(storage, proposal_id)?;
-
-    let mut validators_vote: HashMap<Address, TallyVote> = HashMap::default();
-    let mut validator_voting_power: HashMap<Address, VotePower> =
-        HashMap::default();
-    let mut delegators_vote: HashMap<Address, TallyVote> = HashMap::default();
-    let mut delegator_voting_power: HashMap<
-        Address,
-        HashMap<Address, VotePower>,
-    > = HashMap::default();
-
-    for vote in votes {
-        if vote.is_validator() {
-            let validator = vote.validator.clone();
-            let vote_data = vote.data.clone();
-
-            let validator_stake =
-                read_validator_stake(storage, params, &validator, epoch)
-                    .unwrap_or_default();
-
-            validators_vote.insert(validator.clone(), vote_data.into());
-            validator_voting_power.insert(validator, validator_stake);
-        } else {
-            let validator = vote.validator.clone();
-            let delegator = vote.delegator.clone();
-            let vote_data = vote.data.clone();
+    let current_epoch = shell.wl_storage.get_block_epoch()?;
+    let prefix =
+        gov_storage::get_voting_start_proposals_prefix(current_epoch.0);
+
+    let mut proposal_ids = BTreeSet::new();
+    for entry in namada::state::iter_prefix_bytes(&shell.wl_storage, &prefix)? {
+        let (key, _) = entry?;
+        // The prefix also matches the epochs that merely start with the
+        // digits of the current one, so these have to be skipped
+        if gov_storage::get_voting_start_proposal_epoch(&key)
+            != Some(current_epoch.0)
+        {
+            continue;
+        }
+        if let Some(id) = gov_storage::get_voting_start_proposal_id(&key) {
+            proposal_ids.insert(id);
+        }
+    }
+
+    for id in proposal_ids {
+        let voting_end_epoch: Epoch = force_read(
+            &shell.wl_storage,
+            &gov_storage::get_voting_end_epoch_key(id),
+        )?;
+        response.events.push(
+            ProposalLifecycleEvent::voting_started(id, voting_end_epoch).into(),
+        );
+    }
+
+    Ok(())
+}
 
-            let bond_id = BondId {
-                source: delegator.clone(),
-                validator: validator.clone(),
+/// Get the lifecycle events of the proposals created and voted by an applied
+/// transaction, from the keys it changed
+pub fn proposal_lifecycle_events<D, H>(
+    wl_storage: &WlStorage<D, H>,
+    changed_keys: &BTreeSet<Key>,
+) -> Result<Vec<Event>>
+where
+    D: DB + for<'iter> DBIter<'iter> + Sync + 'static,
+    H: StorageHasher + Sync + 'static,
+{
+    let mut events = vec![];
+
+    for key in changed_keys {
+        let is_proposal_type_key = gov_storage::is_proposal_type_key(key);
+        let is_vote_key = gov_storage::is_vote_key(key);
+        let id = match gov_storage::get_proposal_id(key) {
+            Some(id) if is_proposal_type_key || is_vote_key => id,
+            _ => continue,
+        };
+        if is_proposal_type_key {
+            let author: Address =
+                force_read(wl_storage, &gov_storage::get_author_key(id))?;
+            let proposal_type: ProposalType = force_read(wl_storage, key)?;
+            let voting_start_epoch: Epoch = force_read(
+                wl_storage,
+                &gov_storage::get_voting_start_epoch_key(id),
+            )?;
+            let voting_end_epoch: Epoch = force_read(
+                wl_storage,
+                &gov_storage::get_voting_end_epoch_key(id),
+            )?;
+            events.push(
+                ProposalLifecycleEvent::created(
+                    id,
+                    &author,
+                    proposal_type.kind(),
+                    voting_start_epoch,
+                    voting_end_epoch,
+                )
+                .into(),
+            );
+        } else {
+            let (validator, voter) = match (
+                gov_storage::get_vote_delegation_address(key),
+                gov_storage::get_voter_address(key),
+            ) {
+                (Some(validator), Some(voter)) => (validator, voter),
+                _ => continue,
             };
-            let delegator_stake = bond_amount(storage, &bond_id, epoch);
-
-            if let Ok(stake) = delegator_stake {
-                delegators_vote.insert(delegator.clone(), vote_data.into());
-                delegator_voting_power
-                    .entry(delegator)
-                    .or_default()
-                    .insert(validator, stake);
-            } else {
-                continue;
+            // A deleted vote key doesn't record a new vote
+            if let Some(vote) = wl_storage.read::<ProposalVote>(key)? {
+                events.push(
+                    ProposalLifecycleEvent::voted(id, voter, validator, &vote)
+                        .into(),
+                );
             }
         }
     }
 
-    Ok(ProposalVotes {
-        validators_vote,
-        validator_voting_power,
-        delegators_vote,
-        delegator_voting_power,
-    })
+    Ok(events)
 }
 
 fn execute_default_proposal<D, H>(
@@ -340,6 +573,7 @@ where
 fn execute_pgf_steward_proposal<S>(
     storage: &mut S,
     stewards: BTreeSet<AddRemove<Address>>,
+    spending_cap: Option<token::Amount>,
 ) -> Result<bool>
 where
     S: StorageRead + StorageWrite,
@@ -347,14 +581,10 @@ where
     for action in stewards {
         match action {
             AddRemove::Add(address) => {
-                pgf_storage::stewards_handle().insert(
-                    storage,
-                    address.to_owned(),
-                    StewardDetail::base(address),
-                )?;
+                pgf::nominate_steward(storage, address, spending_cap)?;
             }
             AddRemove::Remove(address) => {
-                pgf_storage::stewards_handle().remove(storage, &address)?;
+                pgf::remove_steward(storage, &address)?;
             }
         }
     }
@@ -362,6 +592,82 @@ where
     Ok(true)
 }
 
+fn execute_masp_rewards_proposal<S>(
+    storage: &mut S,
+    update: BTreeMap<Address, token::MaspParams>,
+) -> Result<bool>
+where
+    S: StorageRead + StorageWrite,
+{
+    for (address, params) in update {
+        let denom = match token::read_denom(storage, &address)? {
+            Some(denom) => denom,
+            None => {
+                tracing::warn!(
+                    "Skipping the masp rewards update of token {} which has \
+                     no denomination",
+                    address
+                );
+                continue;
+            }
+        };
+        token::write_reward_params(&params, storage, &address, &denom)?;
+    }
+
+    Ok(true)
+}
+
+fn execute_parameter_change_proposal<S>(
+    storage: &mut S,
+    changes: Vec<ParameterChange>,
+) -> Result<bool>
+where
+    S: StorageRead + StorageWrite,
+{
+    for change in changes {
+        gov_api::apply_parameter_change(storage, &change)?;
+        tracing::info!("Applied the protocol parameter change: {}", change);
+    }
+
+    Ok(true)
+}
+
+fn execute_emergency_halt_proposal<S>(
+    storage: &mut S,
+    halt: EmergencyHalt,
+) -> Result<bool>
+where
+    S: StorageRead + StorageWrite,
+{
+    let expiry = gov_api::halt_subsystem(storage, &halt)?;
+    tracing::info!("{} halted until epoch {}", halt.subsystem, expiry);
+
+    Ok(true)
+}
+
+fn execute_wasm_allowlist_proposal<S>(
+    storage: &mut S,
+    updates: Vec<WasmAllowlistUpdate>,
+) -> Result<bool>
+where
+    S: StorageRead + StorageWrite,
+{
+    let applied = gov_api::apply_wasm_allowlist_updates(storage, &updates)?;
+    if applied {
+        for update in updates {
+            tracing::info!("Applied the wasm allowlist update: {}", update);
+        }
+    } else {
+        tracing::info!(
+            "The wasm allowlist updates were not applied as they would empty \
+             an allowlist, allowing any code, or restrict an empty one \
+             without setting its full set of hashes."
+        );
+    }
+
+    Ok(applied)
+}
+
 fn execute_pgf_funding_proposal<D, H>(
     storage: &mut WlStorage<D, H>,
     token: &Address,
@@ -379,7 +685,11 @@ where
                     pgf_storage::fundings_handle().insert(
                         storage,
                         target.target().clone(),
-                        StoragePgfFunding::new(target.clone(), proposal_id),
+                        StoragePgfFunding::new(
+                            target.clone(),
+                            proposal_id,
+                            None,
+                        ),
                     )?;
                     tracing::info!(
                         "Added/Updated ContinousPgf from proposal id {}: set \
@@ -401,8 +711,55 @@ where
                     );
                 }
             },
+            PGFAction::Stream(stream) => {
+                pgf_storage::fundings_handle().insert(
+                    storage,
+                    stream.target.target().clone(),
+                    StoragePgfFunding::new(
+                        stream.target.clone(),
+                        proposal_id,
+                        Some(stream.end_epoch),
+                    ),
+                )?;
+                tracing::info!(
+                    "Added/Updated PgfStream from proposal id {}: set {} to \
+                     {} until epoch {}.",
+                    proposal_id,
+                    stream.target.amount().to_string_native(),
+                    stream.target.target(),
+                    stream.end_epoch
+                );
+            }
+            PGFAction::Grant(grant) => {
+                let target = grant.target.clone();
+                let milestones = grant.milestones.len();
+                match pgf::add_grant(storage, proposal_id, grant)? {
+                    Some(grant_id) => tracing::info!(
+                        "Added PgfGrant {} from proposal id {}: {} milestones \
+                         to {}.",
+                        grant_id,
+                        proposal_id,
+                        milestones,
+                        target
+                    ),
+                    None => tracing::warn!(
+                        "Error in PgfGrant from proposal id {} to {}: the \
+                         unreserved pgf balance can't cover the grant",
+                        proposal_id,
+                        target
+                    ),
+                }
+            }
             PGFAction::Retro(target) => {
+                // the amount reserved for the grants can't be spent
                 let result = match &target {
+                    _ if target.amount()
+                        > pgf::get_unreserved_balance(storage)? =>
+                    {
+                        Err(namada::state::StorageError::new_const(
+                            "Insufficient unreserved pgf balance",
+                        ))
+                    }
                     PGFTarget::Internal(target) => token::transfer(
                         storage,
                         token,
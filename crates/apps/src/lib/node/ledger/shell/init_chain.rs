@@ -339,13 +339,6 @@ where
                 .unwrap();
 
             let code_hash = CodeHash::sha256(&code);
-            let code_len = self
-                .validate(
-                    u64::try_from(code.len())
-                        .map_err(|e| Error::LoadingWasm(e.to_string())),
-                )
-                .or_placeholder(Some(1))?
-                .unwrap();
 
             let elements = full_name.split('.').collect::<Vec<&str>>();
             let checksum = self
@@ -387,20 +380,15 @@ where
                     self.vp_wasm_cache.pre_compile(&code);
                 }
 
-                let code_key = Key::wasm_code(&code_hash);
-                let code_len_key = Key::wasm_code_len(&code_hash);
-                let hash_key = Key::wasm_hash(name);
-                let code_name_key = Key::wasm_code_name(name.to_owned());
-
-                self.wl_storage.write_bytes(&code_key, code).unwrap();
-                self.wl_storage.write(&code_len_key, code_len).unwrap();
-                self.wl_storage.write_bytes(&hash_key, code_hash).unwrap();
+                namada::parameters::register_wasm_code(
+                    &mut self.wl_storage,
+                    name,
+                    code,
+                )
+                .unwrap();
                 if &Some(code_hash) == implicit_vp_code_hash {
                     is_implicit_vp_stored = true;
                 }
-                self.wl_storage
-                    .write_bytes(&code_name_key, code_hash)
-                    .unwrap();
             } else {
                 tracing::warn!("The wasm {name} isn't allowed.");
                 self.warn(Warning::DisallowedWasm(name.to_string()));
@@ -936,7 +924,7 @@ impl<T> Policy<T> {
 
 #[cfg(all(test, not(feature = "integration")))]
 mod test {
-    use std::collections::BTreeMap;
+    use std::collections::{BTreeMap, HashSet};
     use std::str::FromStr;
 
     use namada::state::DBIter;
@@ -1193,4 +1181,65 @@ mod test {
         )];
         assert_eq!(expected, initializer.warnings);
     }
+
+    /// Test that the genesis exported from the state of an initialized chain
+    /// has the genesis balances, validators and bonds.
+    #[test]
+    fn test_export_genesis() {
+        let (shell, _x, _y, _z) = test_utils::setup();
+        let genesis = genesis::make_dev_genesis(1, &shell.base_dir);
+
+        let exported =
+            genesis::export::export_genesis(&shell.wl_storage, &genesis)
+                .unwrap();
+
+        // The bonded tokens are credited back to the bonds' sources, so the
+        // balances are the same as in genesis
+        let amounts = |balances: &BTreeMap<_, token::DenominatedAmount>| {
+            balances
+                .iter()
+                .map(|(owner, amount)| (owner, amount.amount()))
+                .collect::<BTreeMap<_, _>>()
+        };
+        for (alias, TokenBalances(balances)) in &genesis.balances.token {
+            assert_eq!(
+                amounts(&exported.templates.balances.token[alias].0),
+                amounts(balances),
+                "The balances of {alias} must be exported"
+            );
+        }
+
+        // The validator didn't change, so its signed tx is kept
+        let validators = genesis
+            .transactions
+            .validator_account
+            .iter()
+            .flatten()
+            .map(|FinalizedValidatorAccountTx { tx }| tx.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            exported.templates.transactions.validator_account,
+            Some(validators)
+        );
+        assert_eq!(exported.unsigned_txs.validator_account, Some(vec![]));
+
+        // The bonds have to be signed again
+        let exported_bonds: HashSet<_> = exported
+            .unsigned_txs
+            .bond
+            .iter()
+            .flatten()
+            .map(|bond| (&bond.source, &bond.validator, bond.amount.amount()))
+            .collect();
+        let genesis_bonds: HashSet<_> = genesis
+            .transactions
+            .bond
+            .iter()
+            .flatten()
+            .map(|bond| (&bond.source, &bond.validator, bond.amount.amount()))
+            .collect();
+        assert_eq!(exported_bonds, genesis_bonds);
+        assert!(exported.skipped.bonds.is_empty());
+        assert!(exported.skipped.validators.is_empty());
+    }
 }
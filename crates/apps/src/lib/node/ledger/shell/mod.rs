@@ -13,7 +13,9 @@ pub use init_chain::InitChainValidation;
 use namada_sdk::tx::data::GasLimit;
 pub mod prepare_proposal;
 pub mod process_proposal;
+pub mod snapshots;
 pub(super) mod queries;
+pub use queries::{QuerySnapshot, SharedQuerySnapshot};
 mod stats;
 #[cfg(any(test, feature = "testing"))]
 #[allow(dead_code)]
@@ -24,6 +26,7 @@ mod vote_extensions;
 use std::collections::{BTreeSet, HashSet};
 use std::convert::{TryFrom, TryInto};
 use std::mem;
+use std::num::NonZeroUsize;
 use std::path::{Path, PathBuf};
 #[allow(unused_imports)]
 use std::rc::Rc;
@@ -51,12 +54,14 @@ use namada::parameters::validate_tx_bytes;
 use namada::proof_of_stake::slashing::{process_slashes, slash};
 use namada::proof_of_stake::storage::read_pos_params;
 use namada::proof_of_stake::{self};
+use namada::state::read_cache::DEFAULT_READ_CACHE_BYTES;
 use namada::state::tx_queue::{ExpiredTx, TxInQueue};
 use namada::state::wl_storage::WriteLogAndStorage;
 use namada::state::write_log::WriteLog;
 use namada::state::{
-    DBIter, Sha256Hasher, State, StorageHasher, StorageRead, TempWlStorage,
-    WlStorage, DB, EPOCH_SWITCH_BLOCKS_DELAY,
+    AccountHistory, ColdStorage, DBIter, PruningMode, ReadCache, Sha256Hasher,
+    State, StorageHasher, StorageRead, TempWlStorage, WlStorage, DB,
+    EPOCH_SWITCH_BLOCKS_DELAY,
 };
 use namada::token;
 pub use namada::tx::data::ResultCode;
@@ -67,7 +72,7 @@ use namada::types::address::Address;
 use namada::types::chain::ChainId;
 use namada::types::ethereum_events::EthereumEvent;
 use namada::types::key::*;
-use namada::types::storage::{BlockHeight, Key, TxIndex};
+use namada::types::storage::{BlockHeight, Epoch, Key, TxIndex};
 use namada::types::time::DateTimeUtc;
 use namada::vm::wasm::{TxCache, VpCache};
 use namada::vm::{WasmCacheAccess, WasmCacheRwAccess};
@@ -154,22 +159,44 @@ pub fn reset(config: config::Ledger) -> Result<()> {
     Ok(())
 }
 
-pub fn rollback(config: config::Ledger) -> Result<()> {
-    // Rollback Tendermint state
-    tracing::info!("Rollback Tendermint state");
-    let tendermint_block_height =
-        tendermint_node::rollback(config.cometbft_dir())
-            .map_err(Error::Tendermint)?;
-
-    // Rollback Namada state
+/// Roll the Tendermint and the Namada state back by the given number of
+/// blocks. The Namada state is checked first, so that the Tendermint state is
+/// left untouched if the blocks cannot be rolled back.
+#[cfg(not(feature = "sled"))]
+pub fn rollback(config: config::Ledger, blocks: u64) -> Result<()> {
     let db_path = config.shell.db_dir(&config.chain_id);
     let mut db = storage::PersistentDB::open(db_path, None);
-    tracing::info!("Rollback Namada state");
+    let height = db
+        .check_rollback(blocks, is_merklized_storage_key)
+        .map_err(|e| Error::Storage(namada::state::StorageError::new(e)))?;
+
+    // Rollback Tendermint state. The rolled back blocks are removed from the
+    // block store, except for the first one that is executed again.
+    tracing::info!("Rollback Tendermint state to height {height}");
+    let mut tendermint_block_height = BlockHeight::default();
+    for rolled_back in 1..=blocks {
+        tendermint_block_height = tendermint_node::rollback(
+            config.cometbft_dir(),
+            rolled_back < blocks,
+        )
+        .map_err(Error::Tendermint)?;
+    }
 
-    db.rollback(tendermint_block_height)
+    // Rollback Namada state
+    tracing::info!("Rollback Namada state");
+    db.rollback(tendermint_block_height, is_merklized_storage_key)
         .map_err(|e| Error::Storage(namada::state::StorageError::new(e)))
 }
 
+/// The rollback is not supported with the sled DB backend. This fails before
+/// touching the Tendermint state.
+#[cfg(feature = "sled")]
+pub fn rollback(_config: config::Ledger, _blocks: u64) -> Result<()> {
+    Err(Error::Storage(namada::state::StorageError::new_const(
+        "Rollback is not supported with the sled DB backend",
+    )))
+}
+
 #[derive(Debug)]
 #[allow(dead_code, clippy::large_enum_variant)]
 pub(super) enum ShellMode {
@@ -357,6 +384,16 @@ where
     /// limit the how many block heights in the past can the storage be
     /// queried for reading values.
     storage_read_past_height_limit: Option<u64>,
+    /// Taken from config `snapshot_interval`. When set, a state snapshot is
+    /// taken every this many blocks.
+    snapshot_interval: Option<u64>,
+    /// Taken from config `snapshots_to_keep`. The number of most recent
+    /// state snapshots to keep.
+    snapshots_to_keep: Option<u64>,
+    /// The background task writing the latest state snapshot, if any
+    snapshot_task: Option<std::thread::JoinHandle<()>>,
+    /// The state snapshot being restored by state sync, if any
+    snapshot_restore: Option<snapshots::SnapshotRestore>,
     /// Proposal execution tracking
     pub proposal_data: HashSet<u64>,
     /// Log of events emitted by `FinalizeBlock` ABCI calls.
@@ -413,8 +450,16 @@ where
         let db_path = config.shell.db_dir(&chain_id);
         let base_dir = config.shell.base_dir;
         let mode = config.shell.tendermint_mode;
-        let storage_read_past_height_limit =
-            config.shell.storage_read_past_height_limit;
+        let pruning = config.shell.pruning.unwrap_or_default();
+        // The queries of the pruned blocks cannot be served
+        let storage_read_past_height_limit = match pruning {
+            PruningMode::KeepRecent(blocks) => Some(
+                config.shell.storage_read_past_height_limit.unwrap_or(blocks),
+            ),
+            _ => config.shell.storage_read_past_height_limit,
+        };
+        let snapshot_interval = config.shell.snapshot_interval;
+        let snapshots_to_keep = config.shell.snapshots_to_keep;
         if !Path::new(&base_dir).is_dir() {
             std::fs::create_dir(&base_dir)
                 .expect("Creating directory for Namada should not fail");
@@ -437,9 +482,39 @@ where
             chain_id.clone(),
             native_token,
             db_cache,
-            config.shell.storage_read_past_height_limit,
+            storage_read_past_height_limit,
             is_merklized_storage_key,
         );
+        storage.pruning = pruning;
+        storage.sync_policy = config.shell.sync_policy.unwrap_or_default();
+        storage.async_merkle_commit =
+            config.shell.async_merkle_commit.unwrap_or_default();
+        storage.value_checksums =
+            config.shell.value_checksums.unwrap_or_default();
+        storage.persist_all_diffs =
+            config.shell.persist_all_diffs.unwrap_or_default();
+        storage.account_history = config
+            .shell
+            .account_history
+            .unwrap_or_default()
+            .then(AccountHistory::default);
+        let read_cache_bytes = config
+            .shell
+            .read_cache_bytes
+            .map_or(DEFAULT_READ_CACHE_BYTES, |bytes| bytes as usize);
+        storage.read_cache =
+            NonZeroUsize::new(read_cache_bytes).map(ReadCache::new);
+        if let Some(cold_storage) = &config.shell.cold_storage {
+            let cold_db_path = config
+                .shell
+                .cold_db_dir(&chain_id)
+                .expect("The cold storage is enabled");
+            storage.cold_storage = Some(ColdStorage {
+                db: D::open(cold_db_path, None),
+                hot_blocks: cold_storage.hot_blocks,
+                oldest_hot_height: None,
+            });
+        }
         storage
             .load_last_state()
             .map_err(|e| {
@@ -534,6 +609,10 @@ where
                 tx_wasm_compilation_cache as usize,
             ),
             storage_read_past_height_limit,
+            snapshot_interval,
+            snapshots_to_keep,
+            snapshot_task: None,
+            snapshot_restore: None,
             proposal_data: HashSet::new(),
             // TODO: config event log params
             event_log: EventLog::default(),
@@ -773,6 +852,7 @@ where
             retain_height: tendermint::block::Height::from(0_u32),
             ..Default::default()
         };
+        self.update_pruning_safeguard();
         // commit block's data from write log and store the in DB
         self.wl_storage.commit_block().unwrap_or_else(|e| {
             tracing::error!(
@@ -788,13 +868,65 @@ where
             self.wl_storage.storage.get_last_block_height(),
         );
         response.data = root.0.to_vec().into();
+        if let Some(read_cache) = &self.wl_storage.storage.read_cache {
+            let metrics = read_cache.take_metrics();
+            tracing::debug!(
+                "Storage read cache hits: {}, misses: {}, entries: {}, bytes: \
+                 {}",
+                metrics.hits,
+                metrics.misses,
+                metrics.entries,
+                metrics.bytes,
+            );
+        }
 
+        self.take_snapshot_if_due();
         self.bump_last_processed_eth_block();
         self.broadcast_queued_txs();
 
         response
     }
 
+    /// Prevent the pruning of the blocks that may still be referred to by
+    /// evidence of validator misbehaviour that's yet to be processed or by
+    /// the consensus states of the IBC clients.
+    fn update_pruning_safeguard(&mut self) {
+        if !matches!(
+            self.wl_storage.storage.pruning,
+            PruningMode::KeepRecent(_)
+        ) {
+            return;
+        }
+        let pos_params = read_pos_params(&self.wl_storage)
+            .expect("Could not find the PoS parameters");
+        // The evidence from the epochs older than this one is disregarded
+        // (see `record_slashes_from_evidence`)
+        let oldest_evidence_epoch = self
+            .wl_storage
+            .storage
+            .block
+            .epoch
+            .sub_or_default(Epoch(pos_params.unbonding_len));
+        let oldest_evidence_height = self
+            .wl_storage
+            .storage
+            .block
+            .pred_epochs
+            .get_start_height_of_epoch(oldest_evidence_epoch);
+        // The host heights at which the IBC clients were updated are still
+        // referred to by their consensus states
+        let oldest_ibc_client_height =
+            namada::ibc::oldest_client_update_height(&self.wl_storage)
+                .expect("Could not read the IBC client update heights");
+        self.wl_storage.storage.pruning_safeguard_height =
+            match (oldest_evidence_height, oldest_ibc_client_height) {
+                (Some(evidence_height), Some(ibc_height)) => {
+                    Some(std::cmp::min(evidence_height, ibc_height))
+                }
+                (evidence_height, ibc_height) => evidence_height.or(ibc_height),
+            };
+    }
+
     /// Updates the Ethereum oracle's last processed block.
     #[inline]
     fn bump_last_processed_eth_block(&mut self) {
@@ -1,13 +1,115 @@
 //! Shell methods for querying state
 
+use std::sync::{Arc, RwLock};
+
 use namada::ledger::dry_run_tx;
 use namada::ledger::queries::{RequestCtx, ResponseQuery};
 use namada::token;
 use namada::types::address::Address;
+use namada::vm::WasmCacheRoAccess;
 
 use super::*;
 use crate::node::ledger::response;
 
+/// The paths of the queries that read the event log, which is only kept by
+/// the shell
+const EVENT_LOG_QUERY_PATHS: [&str; 6] = [
+    "/shell/accepted/",
+    "/shell/applied/",
+    "/shell/proposal_events/",
+    "/shell/ibc_client_update/",
+    "/shell/ibc_packet/",
+    "/shell/eth_bridge/pool/transfer_status",
+];
+
+/// The [`QuerySnapshot`] shared with the threads that serve the queries, if
+/// any block has been committed yet. The lock is only held to clone or to
+/// replace the snapshot after a commit, so neither the queries nor the commit
+/// wait for each other. A replaced snapshot is dropped once the last query
+/// served from it is done.
+pub type SharedQuerySnapshot = Arc<RwLock<Option<Arc<QuerySnapshot>>>>;
+
+/// A read-only copy of the state of the last committed block on top of a
+/// checkpoint of the DB, which serves the queries concurrently with the
+/// processing and the commit of the next blocks
+#[derive(Debug)]
+pub struct QuerySnapshot {
+    /// Declared before the checkpoints, as it has to be dropped first
+    wl_storage: WlStorage<storage::PersistentDB, Sha256Hasher>,
+    /// The checkpoints of the DB and, if enabled, of the cold storage DB
+    _checkpoints: Vec<storage::PersistentDBCheckpoint>,
+    /// Always empty, as the queries that read the event log are served by
+    /// the shell
+    event_log: EventLog,
+    vp_wasm_cache: VpCache<WasmCacheRoAccess>,
+    tx_wasm_cache: TxCache<WasmCacheRoAccess>,
+    storage_read_past_height_limit: Option<u64>,
+}
+
+impl QuerySnapshot {
+    /// Check if the query can be served from a snapshot. The queries that
+    /// read the event log have to be served by the shell.
+    pub fn can_serve(query: &request::Query) -> bool {
+        !EVENT_LOG_QUERY_PATHS
+            .iter()
+            .any(|path| query.path.starts_with(path))
+    }
+
+    /// Serve the query from the state of the last committed block
+    pub fn query(&self, query: request::Query) -> response::Query {
+        let ctx = RequestCtx {
+            wl_storage: &self.wl_storage,
+            event_log: &self.event_log,
+            vp_wasm_cache: self.vp_wasm_cache.clone(),
+            tx_wasm_cache: self.tx_wasm_cache.clone(),
+            storage_read_past_height_limit: self.storage_read_past_height_limit,
+        };
+        handle_query(ctx, &query)
+    }
+}
+
+impl Shell {
+    /// Make a snapshot of the state of the last committed block to serve the
+    /// queries from, if any block has been committed yet. Not supported with
+    /// sled.
+    pub fn query_snapshot(&self) -> Option<QuerySnapshot> {
+        let storage = &self.wl_storage.storage;
+        let height = storage.last_block.as_ref()?.height;
+        let make_checkpoint = |db| {
+            let checkpoint = storage::query_checkpoint(db, height)?;
+            checkpoint
+                .map_err(|err| {
+                    tracing::error!(
+                        "Failed to make a DB checkpoint at height {height} to \
+                         serve the queries from: {err}"
+                    )
+                })
+                .ok()
+        };
+        let (checkpoint, db) = make_checkpoint(&storage.db)?;
+        let mut checkpoints = vec![checkpoint];
+        let cold_db = match &storage.cold_storage {
+            Some(cold_storage) => {
+                let (checkpoint, db) = make_checkpoint(&cold_storage.db)?;
+                checkpoints.push(checkpoint);
+                Some(db)
+            }
+            None => None,
+        };
+        Some(QuerySnapshot {
+            wl_storage: WlStorage::new(
+                WriteLog::default(),
+                storage.read_only_copy(db, cold_db),
+            ),
+            _checkpoints: checkpoints,
+            event_log: EventLog::default(),
+            vp_wasm_cache: self.vp_wasm_cache.read_only(),
+            tx_wasm_cache: self.tx_wasm_cache.read_only(),
+            storage_read_past_height_limit: self.storage_read_past_height_limit,
+        })
+    }
+}
+
 impl<D, H> Shell<D, H>
 where
     D: DB + for<'iter> DBIter<'iter> + Sync + 'static,
@@ -25,26 +127,7 @@ where
             tx_wasm_cache: self.tx_wasm_cache.read_only(),
             storage_read_past_height_limit: self.storage_read_past_height_limit,
         };
-
-        // Invoke the root RPC handler - returns borsh-encoded data on success
-        let result = if query.path == "/shell/dry_run_tx" {
-            dry_run_tx(ctx, &query)
-        } else {
-            namada::ledger::queries::handle_path(ctx, &query)
-        };
-        match result {
-            Ok(ResponseQuery { data, info, proof }) => response::Query {
-                value: data.into(),
-                info,
-                proof: proof.map(Into::into),
-                ..Default::default()
-            },
-            Err(err) => response::Query {
-                code: 1.into(),
-                info: format!("RPC error: {}", err),
-                ..Default::default()
-            },
-        }
+        handle_query(ctx, &query)
     }
 
     /// Simple helper function for the ledger to get balances
@@ -61,6 +144,43 @@ where
     }
 }
 
+/// Invoke the root RPC handler with the context and convert its result to the
+/// query response
+fn handle_query<D, H>(
+    ctx: RequestCtx<
+        '_,
+        D,
+        H,
+        VpCache<WasmCacheRoAccess>,
+        TxCache<WasmCacheRoAccess>,
+    >,
+    query: &request::Query,
+) -> response::Query
+where
+    D: DB + for<'iter> DBIter<'iter> + Sync + 'static,
+    H: StorageHasher + Sync + 'static,
+{
+    // Invoke the root RPC handler - returns borsh-encoded data on success
+    let result = if query.path == "/shell/dry_run_tx" {
+        dry_run_tx(ctx, query)
+    } else {
+        namada::ledger::queries::handle_path(ctx, query)
+    };
+    match result {
+        Ok(ResponseQuery { data, info, proof }) => response::Query {
+            value: data.into(),
+            info,
+            proof: proof.map(Into::into),
+            ..Default::default()
+        },
+        Err(err) => response::Query {
+            code: 1.into(),
+            info: format!("RPC error: {}", err),
+            ..Default::default()
+        },
+    }
+}
+
 // NOTE: we are testing `namada::ledger::queries_ext`,
 // which is not possible from `namada` since we do not have
 // access to the `Shell` there
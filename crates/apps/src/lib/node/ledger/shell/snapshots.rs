@@ -0,0 +1,976 @@
+//! State snapshots served to CometBFT state sync.
+//!
+//! A snapshot of the last committed block is taken every
+//! `snapshot_interval` blocks. The commit only takes a checkpoint of the DB,
+//! the snapshot is then written from the checkpoint in the background. It
+//! contains the state of the block followed by the Merkle tree stores and all
+//! the key-vals of the storage subspace, Borsh encoded as a stream of
+//! [`SnapshotItem`]s and split into fixed-size chunks. The chunks are written
+//! to `{base_dir}/{chain_id}/snapshots/{height}/chunk-{index}` together with
+//! a `metadata` file holding the hashes of the chunks, which allows a syncing
+//! node to verify each chunk it receives.
+//!
+//! A syncing node restores a snapshot once it has received all of its chunks.
+//! The Merkle tree is rebuilt from the subspace and its root must match the
+//! app hash verified by CometBFT for the snapshot's height.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io::{self, BufRead, Read, Write};
+use std::path::{Path, PathBuf};
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use borsh_ext::BorshSerializeExt;
+use namada::ledger::storage::tx_queue::TxQueue;
+use namada::state::changed_keys::ChangedKeysFilter;
+use namada::state::{
+    BlockStateRead, BlockStateWrite, DBCheckpoint, DBIter, MerkleTree,
+    MerkleTreeStoresRead, StorageHasher, StoreType, DB,
+};
+use namada::types::address::EstablishedAddressGen;
+use namada::types::chain::ChainId;
+use namada::types::eth_bridge_pool::is_pending_transfer_key;
+use namada::types::ethereum_structs;
+use namada::types::hash::Hash;
+use namada::types::storage::{
+    BlockHash, BlockHeight, BlockResults, Epoch, Epochs, EthEventsQueue, Key,
+};
+use namada::types::time::DateTimeUtc;
+use namada::types::token::ConversionState;
+
+use super::Shell;
+use crate::facade::tendermint::abci::response::ApplySnapshotChunkResult;
+use crate::facade::tendermint::abci::types::Snapshot;
+use crate::facade::tendermint::v0_37::abci::{request, response};
+
+/// The version of the snapshot format. Must be bumped on any change to the
+/// encoding of [`SnapshotItem`]s.
+pub const SNAPSHOT_FORMAT: u32 = 2;
+
+/// The size of a snapshot chunk in bytes. The last chunk may be smaller.
+const CHUNK_SIZE: usize = 10 * 1024 * 1024;
+
+/// The number of most recent snapshots to keep when not configured.
+const DEFAULT_SNAPSHOTS_TO_KEEP: u64 = 2;
+
+/// The name of the file holding the [`SnapshotMetadata`].
+const METADATA_FILE: &str = "metadata";
+
+/// The name of the dir in which the chunks of a snapshot being restored are
+/// received.
+const RESTORE_DIR: &str = "restore";
+
+/// The state of the snapshot's block which isn't part of the storage
+/// subspace
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+pub struct SnapshotBlockState {
+    /// Hash of the block
+    pub hash: BlockHash,
+    /// Height of the block
+    pub height: BlockHeight,
+    /// Time of the block
+    pub time: DateTimeUtc,
+    /// Epoch of the block
+    pub epoch: Epoch,
+    /// Predecessor block epochs
+    pub pred_epochs: Epochs,
+    /// Minimum block height at which the next epoch may start
+    pub next_epoch_min_start_height: BlockHeight,
+    /// Minimum block time at which the next epoch may start
+    pub next_epoch_min_start_time: DateTimeUtc,
+    /// Update epoch delay
+    pub update_epoch_blocks_delay: Option<u32>,
+    /// Established address generator
+    pub address_gen: EstablishedAddressGen,
+    /// Results of applying transactions
+    pub results: BlockResults,
+    /// The conversion state
+    pub conversion_state: ConversionState,
+    /// Wrapper txs to be decrypted in the next block proposal
+    pub tx_queue: TxQueue,
+    /// The latest block height on Ethereum processed, if the bridge is
+    /// enabled
+    pub ethereum_height: Option<ethereum_structs::BlockHeight>,
+    /// The queue of Ethereum events to be processed in order
+    pub eth_events_queue: EthEventsQueue,
+}
+
+/// An item of a snapshot
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+pub enum SnapshotItem {
+    /// The state of the snapshot's block, which is the first item
+    BlockState(Box<SnapshotBlockState>),
+    /// A key-val from the storage subspace
+    Subspace {
+        /// The storage key
+        key: String,
+        /// The value stored under the key
+        value: Vec<u8>,
+    },
+    /// A Merkle tree store of the last committed block
+    MerkleTreeStore {
+        /// The type of the store
+        store_type: StoreType,
+        /// The root of the store
+        root: Hash,
+        /// The encoded store
+        store: Vec<u8>,
+    },
+}
+
+/// The metadata of a snapshot, sent to syncing nodes as part of the offered
+/// snapshot
+#[derive(Debug, Clone, Default, BorshSerialize, BorshDeserialize)]
+pub struct SnapshotMetadata {
+    /// The hashes of the chunks, in order
+    pub chunk_hashes: Vec<Hash>,
+}
+
+impl SnapshotMetadata {
+    /// The hash of the snapshot, committing to all of its chunks
+    pub fn snapshot_hash(&self) -> Hash {
+        Hash::sha256(self.chunk_hashes.serialize_to_vec())
+    }
+}
+
+/// Get the directory of the snapshots of the given chain
+pub fn snapshots_dir(base_dir: &Path, chain_id: &ChainId) -> PathBuf {
+    base_dir.join(chain_id.as_str()).join("snapshots")
+}
+
+fn chunk_path(snapshot_dir: &Path, index: usize) -> PathBuf {
+    snapshot_dir.join(format!("chunk-{index}"))
+}
+
+/// Splits the written bytes into chunk files of [`CHUNK_SIZE`]
+struct ChunkWriter {
+    dir: PathBuf,
+    buf: Vec<u8>,
+    chunk_hashes: Vec<Hash>,
+}
+
+impl ChunkWriter {
+    fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            buf: Vec::with_capacity(CHUNK_SIZE),
+            chunk_hashes: vec![],
+        }
+    }
+
+    fn write_chunk(&mut self, len: usize) -> io::Result<()> {
+        let chunk: Vec<u8> = self.buf.drain(..len).collect();
+        fs::write(chunk_path(&self.dir, self.chunk_hashes.len()), &chunk)?;
+        self.chunk_hashes.push(Hash::sha256(&chunk));
+        Ok(())
+    }
+
+    /// Write out the remaining bytes and return the snapshot's metadata
+    fn finish(mut self) -> io::Result<SnapshotMetadata> {
+        if !self.buf.is_empty() || self.chunk_hashes.is_empty() {
+            self.write_chunk(self.buf.len())?;
+        }
+        Ok(SnapshotMetadata {
+            chunk_hashes: self.chunk_hashes,
+        })
+    }
+}
+
+impl Write for ChunkWriter {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        self.buf.extend_from_slice(bytes);
+        while self.buf.len() >= CHUNK_SIZE {
+            self.write_chunk(CHUNK_SIZE)?;
+        }
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Reads the bytes of the chunk files in order, verifying the hash of each
+/// chunk
+struct ChunkReader {
+    dir: PathBuf,
+    chunk_hashes: Vec<Hash>,
+    next_chunk: usize,
+    buf: io::Cursor<Vec<u8>>,
+}
+
+impl ChunkReader {
+    fn new(dir: PathBuf, metadata: SnapshotMetadata) -> Self {
+        Self {
+            dir,
+            chunk_hashes: metadata.chunk_hashes,
+            next_chunk: 0,
+            buf: io::Cursor::default(),
+        }
+    }
+
+    fn read_chunk(&mut self) -> io::Result<()> {
+        let index = self.next_chunk;
+        let chunk = fs::read(chunk_path(&self.dir, index))?;
+        if Hash::sha256(&chunk) != self.chunk_hashes[index] {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("The snapshot chunk {index} doesn't match its hash"),
+            ));
+        }
+        self.buf = io::Cursor::new(chunk);
+        self.next_chunk += 1;
+        Ok(())
+    }
+}
+
+impl Read for ChunkReader {
+    fn read(&mut self, bytes: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let read = self.buf.read(bytes)?;
+            if read > 0
+                || bytes.is_empty()
+                || self.next_chunk == self.chunk_hashes.len()
+            {
+                return Ok(read);
+            }
+            self.read_chunk()?;
+        }
+    }
+}
+
+/// Read the key-vals of the storage subspace from the snapshot at the given
+/// height in the given dir
+pub fn read_snapshot_subspace(
+    snapshots_dir: &Path,
+    height: u64,
+) -> io::Result<BTreeMap<String, Vec<u8>>> {
+    let metadata = read_metadata(snapshots_dir, height)?;
+    let mut reader = io::BufReader::new(ChunkReader::new(
+        snapshots_dir.join(height.to_string()),
+        metadata,
+    ));
+    let mut subspace = BTreeMap::new();
+    while !reader.fill_buf()?.is_empty() {
+        if let SnapshotItem::Subspace { key, value } =
+            SnapshotItem::deserialize_reader(&mut reader)?
+        {
+            subspace.insert(key, value);
+        }
+    }
+    Ok(subspace)
+}
+
+/// Read the metadata of the snapshot at the given height, if any
+fn read_metadata(
+    snapshots_dir: &Path,
+    height: u64,
+) -> io::Result<SnapshotMetadata> {
+    let bytes =
+        fs::read(snapshots_dir.join(height.to_string()).join(METADATA_FILE))?;
+    SnapshotMetadata::try_from_slice(&bytes)
+}
+
+/// Get the heights of the complete snapshots in the given dir, in ascending
+/// order
+fn snapshot_heights(snapshots_dir: &Path) -> io::Result<Vec<u64>> {
+    if !snapshots_dir.is_dir() {
+        return Ok(vec![]);
+    }
+    let mut heights = vec![];
+    for entry in fs::read_dir(snapshots_dir)? {
+        let entry = entry?;
+        // Snapshots that are still being written have a `.tmp` suffix
+        if let Some(height) = entry
+            .file_name()
+            .to_str()
+            .and_then(|name| name.parse::<u64>().ok())
+        {
+            heights.push(height);
+        }
+    }
+    heights.sort_unstable();
+    Ok(heights)
+}
+
+/// A snapshot being restored by state sync
+#[derive(Debug)]
+pub struct SnapshotRestore {
+    /// The height of the snapshot
+    height: u64,
+    /// The app hash of the snapshot's height, verified by CometBFT
+    app_hash: Vec<u8>,
+    /// The metadata of the offered snapshot
+    metadata: SnapshotMetadata,
+    /// The indices of the chunks received so far
+    received: BTreeSet<usize>,
+}
+
+/// Write a snapshot of the last block committed to the given DB checkpoint
+fn take_snapshot(
+    snapshots_dir: &Path,
+    checkpoint: &impl DBCheckpoint,
+) -> io::Result<(BlockHeight, SnapshotMetadata)> {
+    let BlockStateRead {
+        merkle_tree_stores,
+        hash,
+        height,
+        time,
+        epoch,
+        pred_epochs,
+        next_epoch_min_start_height,
+        next_epoch_min_start_time,
+        update_epoch_blocks_delay,
+        address_gen,
+        results,
+        conversion_state,
+        tx_queue,
+        ethereum_height,
+        eth_events_queue,
+    } = checkpoint
+        .read_last_block()
+        .map_err(invalid_data)?
+        .ok_or_else(|| invalid_data("No block has been committed"))?;
+    let snapshot_dir = snapshots_dir.join(height.to_string());
+    // Write to a temporary dir first, so that an incomplete snapshot is
+    // never served
+    let tmp_dir = snapshots_dir.join(format!("{height}.tmp"));
+    if tmp_dir.exists() {
+        fs::remove_dir_all(&tmp_dir)?;
+    }
+    fs::create_dir_all(&tmp_dir)?;
+
+    let mut writer = ChunkWriter::new(tmp_dir.clone());
+    SnapshotItem::BlockState(Box::new(SnapshotBlockState {
+        hash,
+        height,
+        time,
+        epoch,
+        pred_epochs,
+        next_epoch_min_start_height,
+        next_epoch_min_start_time,
+        update_epoch_blocks_delay,
+        address_gen,
+        results,
+        conversion_state,
+        tx_queue,
+        ethereum_height,
+        eth_events_queue,
+    }))
+    .serialize(&mut writer)?;
+    for st in StoreType::iter() {
+        SnapshotItem::MerkleTreeStore {
+            store_type: *st,
+            root: merkle_tree_stores.get_root(*st),
+            store: merkle_tree_stores.get_store(*st).encode(),
+        }
+        .serialize(&mut writer)?;
+    }
+    for (key, value) in checkpoint.iter_subspace() {
+        SnapshotItem::Subspace { key, value }.serialize(&mut writer)?;
+    }
+    let metadata = writer.finish()?;
+    fs::write(tmp_dir.join(METADATA_FILE), metadata.serialize_to_vec())?;
+
+    if snapshot_dir.exists() {
+        fs::remove_dir_all(&snapshot_dir)?;
+    }
+    fs::rename(&tmp_dir, &snapshot_dir)?;
+    Ok((height, metadata))
+}
+
+/// Remove all but the given number of most recent snapshots
+fn prune_snapshots(snapshots_dir: &Path, to_keep: usize) -> io::Result<()> {
+    let heights = snapshot_heights(snapshots_dir)?;
+    let to_remove = heights.len().saturating_sub(to_keep);
+    for height in &heights[..to_remove] {
+        fs::remove_dir_all(snapshots_dir.join(height.to_string()))?;
+    }
+    Ok(())
+}
+
+/// Read the state of the block and the Merkle tree stores at the start of a
+/// snapshot
+fn read_snapshot_header(
+    reader: &mut impl Read,
+) -> io::Result<(SnapshotBlockState, MerkleTreeStoresRead)> {
+    let SnapshotItem::BlockState(block_state) =
+        SnapshotItem::deserialize_reader(reader)?
+    else {
+        return Err(invalid_data(
+            "The snapshot must start with the block state",
+        ));
+    };
+    let mut stores = MerkleTreeStoresRead::default();
+    for st in StoreType::iter() {
+        match SnapshotItem::deserialize_reader(reader)? {
+            SnapshotItem::MerkleTreeStore {
+                store_type,
+                root,
+                store,
+            } if store_type == *st => {
+                stores.set_root(st, root);
+                stores.set_store(st.decode_store(store).map_err(invalid_data)?);
+            }
+            _ => {
+                return Err(invalid_data(format!(
+                    "The snapshot is missing the {st} Merkle tree store"
+                )));
+            }
+        }
+    }
+    Ok((*block_state, stores))
+}
+
+/// Read the next key-val of the storage subspace that follows the header of
+/// a snapshot, if any
+fn read_snapshot_subspace_val(
+    reader: &mut impl BufRead,
+) -> io::Result<Option<(Key, Vec<u8>)>> {
+    if reader.fill_buf()?.is_empty() {
+        return Ok(None);
+    }
+    match SnapshotItem::deserialize_reader(reader)? {
+        SnapshotItem::Subspace { key, value } => {
+            let key = Key::parse(key).map_err(invalid_data)?;
+            Ok(Some((key, value)))
+        }
+        _ => Err(invalid_data("Expected a key-val of the storage subspace")),
+    }
+}
+
+fn invalid_data<E>(err: E) -> io::Error
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+impl<D, H> Shell<D, H>
+where
+    D: DB + for<'iter> DBIter<'iter> + Sync + 'static,
+    H: StorageHasher + Sync + 'static,
+{
+    /// Get the directory of this chain's snapshots
+    fn snapshots_dir(&self) -> PathBuf {
+        snapshots_dir(&self.base_dir, &self.chain_id)
+    }
+
+    /// Take a snapshot of the last committed block, if it's due according to
+    /// the configured `snapshot_interval`, and prune the old snapshots. Only
+    /// the checkpoint of the DB is taken here, the snapshot is written from
+    /// it on another thread.
+    pub(super) fn take_snapshot_if_due(&mut self) {
+        let Some(interval) = self.snapshot_interval else {
+            return;
+        };
+        let height = self.wl_storage.storage.get_last_block_height();
+        if interval == 0 || height.0 == 0 || height.0 % interval != 0 {
+            return;
+        }
+        if matches!(&self.snapshot_task, Some(task) if !task.is_finished()) {
+            tracing::warn!(
+                "Skipping the state snapshot at height {} as the previous one \
+                 is still being written",
+                height
+            );
+            return;
+        }
+        let checkpoint = match self.wl_storage.storage.db.checkpoint() {
+            Ok(checkpoint) => checkpoint,
+            Err(err) => {
+                tracing::error!(
+                    "Failed to checkpoint the DB for a state snapshot at \
+                     height {}: {}",
+                    height,
+                    err
+                );
+                return;
+            }
+        };
+        let snapshots_dir = self.snapshots_dir();
+        let to_keep = self
+            .snapshots_to_keep
+            .unwrap_or(DEFAULT_SNAPSHOTS_TO_KEEP)
+            .max(1) as usize;
+        self.snapshot_task = Some(std::thread::spawn(move || {
+            match take_snapshot(&snapshots_dir, &checkpoint) {
+                Ok((height, metadata)) => tracing::info!(
+                    "Created a state snapshot at height {} with {} chunks",
+                    height,
+                    metadata.chunk_hashes.len()
+                ),
+                Err(err) => tracing::error!(
+                    "Failed to create a state snapshot at height {}: {}",
+                    height,
+                    err
+                ),
+            }
+            if let Err(err) = prune_snapshots(&snapshots_dir, to_keep) {
+                tracing::error!("Failed to prune old state snapshots: {}", err);
+            }
+        }));
+    }
+
+    /// List the available snapshots, for the ABCI `ListSnapshots` request
+    pub fn list_snapshots(&self) -> response::ListSnapshots {
+        let snapshots_dir = self.snapshots_dir();
+        let heights = snapshot_heights(&snapshots_dir).unwrap_or_else(|err| {
+            tracing::error!("Failed to list state snapshots: {}", err);
+            vec![]
+        });
+        let snapshots = heights
+            .into_iter()
+            .filter_map(|height| {
+                let metadata = read_metadata(&snapshots_dir, height)
+                    .map_err(|err| {
+                        tracing::error!(
+                            "Failed to read the metadata of the state \
+                             snapshot at height {}: {}",
+                            height,
+                            err
+                        )
+                    })
+                    .ok()?;
+                Some(Snapshot {
+                    height: height.try_into().ok()?,
+                    format: SNAPSHOT_FORMAT,
+                    chunks: metadata.chunk_hashes.len().try_into().ok()?,
+                    hash: metadata.snapshot_hash().0.to_vec().into(),
+                    metadata: metadata.serialize_to_vec().into(),
+                })
+            })
+            .collect();
+        response::ListSnapshots { snapshots }
+    }
+
+    /// Load a chunk of a snapshot, for the ABCI `LoadSnapshotChunk` request.
+    /// An empty chunk is returned if the requested chunk doesn't exist.
+    pub fn load_snapshot_chunk(
+        &self,
+        req: request::LoadSnapshotChunk,
+    ) -> response::LoadSnapshotChunk {
+        if req.format != SNAPSHOT_FORMAT {
+            return Default::default();
+        }
+        let snapshot_dir = self.snapshots_dir().join(req.height.to_string());
+        match fs::read(chunk_path(&snapshot_dir, req.chunk as usize)) {
+            Ok(chunk) => response::LoadSnapshotChunk {
+                chunk: chunk.into(),
+            },
+            Err(err) => {
+                tracing::error!(
+                    "Failed to load chunk {} of the state snapshot at height \
+                     {}: {}",
+                    req.chunk,
+                    req.height,
+                    err
+                );
+                Default::default()
+            }
+        }
+    }
+
+    /// Accept a snapshot offered by state sync, for the ABCI `OfferSnapshot`
+    /// request, if its format is supported and its metadata matches its hash
+    pub fn offer_snapshot(
+        &mut self,
+        req: request::OfferSnapshot,
+    ) -> response::OfferSnapshot {
+        let snapshot = req.snapshot;
+        if snapshot.format != SNAPSHOT_FORMAT {
+            return response::OfferSnapshot::RejectFormat;
+        }
+        let metadata =
+            match SnapshotMetadata::try_from_slice(&snapshot.metadata) {
+                Ok(metadata) => metadata,
+                Err(_) => return response::OfferSnapshot::Reject,
+            };
+        if snapshot.hash.as_ref() != metadata.snapshot_hash().0.as_slice()
+            || snapshot.chunks as usize != metadata.chunk_hashes.len()
+        {
+            return response::OfferSnapshot::Reject;
+        }
+        let restore_dir = self.snapshots_dir().join(RESTORE_DIR);
+        // Discard the chunks of a previously offered snapshot
+        let prepared = if restore_dir.exists() {
+            fs::remove_dir_all(&restore_dir)
+        } else {
+            Ok(())
+        };
+        if let Err(err) =
+            prepared.and_then(|()| fs::create_dir_all(&restore_dir))
+        {
+            tracing::error!(
+                "Failed to prepare the restore of a state snapshot: {}",
+                err
+            );
+            return response::OfferSnapshot::Abort;
+        }
+        self.snapshot_restore = Some(SnapshotRestore {
+            height: snapshot.height.value(),
+            app_hash: req.app_hash.as_bytes().to_vec(),
+            metadata,
+            received: BTreeSet::new(),
+        });
+        response::OfferSnapshot::Accept
+    }
+
+    /// Receive a chunk of the accepted snapshot, for the ABCI
+    /// `ApplySnapshotChunk` request. The snapshot is restored once all of its
+    /// chunks have been received.
+    pub fn apply_snapshot_chunk(
+        &mut self,
+        req: request::ApplySnapshotChunk,
+    ) -> response::ApplySnapshotChunk {
+        let respond = |result| response::ApplySnapshotChunk {
+            result,
+            refetch_chunks: vec![],
+            reject_senders: vec![],
+        };
+        let restore_dir = self.snapshots_dir().join(RESTORE_DIR);
+        let Some(restore) = self.snapshot_restore.as_mut() else {
+            return respond(ApplySnapshotChunkResult::Abort);
+        };
+        let index = req.index as usize;
+        match restore.metadata.chunk_hashes.get(index) {
+            Some(hash) if *hash == Hash::sha256(&req.chunk) => {}
+            Some(_) => {
+                // Fetch the chunk again from another peer
+                return response::ApplySnapshotChunk {
+                    result: ApplySnapshotChunkResult::Retry,
+                    refetch_chunks: vec![req.index],
+                    reject_senders: vec![req.sender],
+                };
+            }
+            None => return respond(ApplySnapshotChunkResult::RejectSnapshot),
+        }
+        if let Err(err) = fs::write(chunk_path(&restore_dir, index), &req.chunk)
+        {
+            tracing::error!(
+                "Failed to write chunk {} of the state snapshot at height {}: \
+                 {}",
+                index,
+                restore.height,
+                err
+            );
+            return respond(ApplySnapshotChunkResult::Abort);
+        }
+        restore.received.insert(index);
+        if restore.received.len() < restore.metadata.chunk_hashes.len() {
+            return respond(ApplySnapshotChunkResult::Accept);
+        }
+
+        let restore = self
+            .snapshot_restore
+            .take()
+            .expect("The restore must be in progress");
+        let restored = self.restore_snapshot(&restore_dir, &restore);
+        if let Err(err) = fs::remove_dir_all(&restore_dir) {
+            tracing::error!(
+                "Failed to remove the chunks of the restored state snapshot: \
+                 {}",
+                err
+            );
+        }
+        match restored {
+            Ok(()) => {
+                tracing::info!(
+                    "Restored the state snapshot at height {}",
+                    restore.height
+                );
+                respond(ApplySnapshotChunkResult::Accept)
+            }
+            Err(err) => {
+                tracing::error!(
+                    "Failed to restore the state snapshot at height {}: {}",
+                    restore.height,
+                    err
+                );
+                respond(ApplySnapshotChunkResult::RejectSnapshot)
+            }
+        }
+    }
+
+    /// Restore the snapshot whose chunks have all been received in the given
+    /// dir. The snapshot is verified against the app hash before anything is
+    /// written to the DB.
+    fn restore_snapshot(
+        &mut self,
+        restore_dir: &Path,
+        restore: &SnapshotRestore,
+    ) -> io::Result<()> {
+        let reader = || {
+            io::BufReader::new(ChunkReader::new(
+                restore_dir.to_path_buf(),
+                restore.metadata.clone(),
+            ))
+        };
+
+        // Rebuild the Merkle tree from the subspace and check its root
+        let mut reader_1 = reader();
+        let (block_state, stores) = read_snapshot_header(&mut reader_1)?;
+        if block_state.height.0 != restore.height {
+            return Err(invalid_data(format!(
+                "The snapshot's block height {} doesn't match the offered \
+                 height {}",
+                block_state.height, restore.height
+            )));
+        }
+        // The tree of the bridge pool stores the heights at which the pending
+        // transfers were added, which are taken from the snapshot's store
+        let snapshot_tree =
+            MerkleTree::<H>::new(stores).map_err(invalid_data)?;
+        let merkle_tree_key_filter =
+            self.wl_storage.storage.merkle_tree_key_filter;
+        let mut tree = MerkleTree::<H>::default();
+        while let Some((key, value)) =
+            read_snapshot_subspace_val(&mut reader_1)?
+        {
+            if is_pending_transfer_key(&key) {
+                let height = snapshot_tree.get(&key).map_err(invalid_data)?;
+                tree.update(&key, height).map_err(invalid_data)?;
+            } else if merkle_tree_key_filter(&key) {
+                tree.update(&key, value).map_err(invalid_data)?;
+            }
+        }
+        if tree.root().0.as_slice() != restore.app_hash.as_slice() {
+            return Err(invalid_data(
+                "The Merkle root of the snapshot doesn't match the app hash",
+            ));
+        }
+
+        // Write the verified state
+        let storage = &mut self.wl_storage.storage;
+        let mut reader_2 = reader();
+        read_snapshot_header(&mut reader_2)?;
+        while let Some((key, value)) =
+            read_snapshot_subspace_val(&mut reader_2)?
+        {
+            storage
+                .db
+                .write_subspace_val(block_state.height, &key, value, false)
+                .map_err(invalid_data)?;
+        }
+        let changed_keys = ChangedKeysFilter::new(std::iter::empty());
+        let state = BlockStateWrite {
+            merkle_tree_stores: tree.stores(),
+            header: None,
+            hash: &block_state.hash,
+            height: block_state.height,
+            time: block_state.time,
+            epoch: block_state.epoch,
+            pred_epochs: &block_state.pred_epochs,
+            next_epoch_min_start_height: block_state
+                .next_epoch_min_start_height,
+            next_epoch_min_start_time: block_state.next_epoch_min_start_time,
+            update_epoch_blocks_delay: block_state.update_epoch_blocks_delay,
+            address_gen: &block_state.address_gen,
+            results: &block_state.results,
+            conversion_state: &block_state.conversion_state,
+            tx_queue: &block_state.tx_queue,
+            ethereum_height: block_state.ethereum_height.as_ref(),
+            eth_events_queue: &block_state.eth_events_queue,
+            changed_keys: &changed_keys,
+        };
+        let mut batch = D::batch();
+        storage
+            .db
+            .add_block_to_batch(state, &mut batch, true)
+            .map_err(invalid_data)?;
+        storage.db.exec_batch(batch).map_err(invalid_data)?;
+        storage.load_last_state().map_err(invalid_data)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test_snapshots {
+    use namada::types::storage::Key;
+
+    use super::*;
+    use crate::facade::tendermint::AppHash;
+    use crate::node::ledger::shell::test_utils::{self, TestShell};
+
+    /// Commit the given number of blocks, waiting for the snapshots that are
+    /// due to be written
+    fn commit_blocks(shell: &mut TestShell, blocks: u64) {
+        for _ in 0..blocks {
+            shell.wl_storage.storage.block.height =
+                shell.wl_storage.storage.block.height.next_height();
+            shell.wl_storage.commit_block().unwrap();
+            shell.take_snapshot_if_due();
+            if let Some(task) = shell.snapshot_task.take() {
+                task.join().unwrap();
+            }
+        }
+    }
+
+    /// Test that snapshots are taken at the configured interval, that their
+    /// chunks decode to the committed state and that only the configured
+    /// number of snapshots is kept.
+    #[test]
+    fn test_snapshots() {
+        let (mut shell, _recv, _, _) = test_utils::setup();
+        shell.snapshot_interval = Some(2);
+        shell.snapshots_to_keep = Some(1);
+
+        let key = Key::parse("snapshot-test").unwrap();
+        let value = vec![1_u8, 2, 3];
+        shell
+            .wl_storage
+            .storage
+            .write(&key, value.clone())
+            .expect("Test failed");
+
+        commit_blocks(&mut shell, 4);
+
+        let snapshots = shell.list_snapshots().snapshots;
+        assert_eq!(snapshots.len(), 1);
+        let snapshot = &snapshots[0];
+        assert_eq!(snapshot.height.value(), 4);
+        assert_eq!(snapshot.format, SNAPSHOT_FORMAT);
+
+        let metadata =
+            SnapshotMetadata::try_from_slice(&snapshot.metadata).unwrap();
+        assert_eq!(snapshot.hash.as_ref(), &metadata.snapshot_hash().0);
+
+        let mut bytes = vec![];
+        for chunk in 0..snapshot.chunks {
+            let loaded = shell
+                .load_snapshot_chunk(request::LoadSnapshotChunk {
+                    height: snapshot.height,
+                    format: SNAPSHOT_FORMAT,
+                    chunk,
+                })
+                .chunk;
+            assert_eq!(
+                Hash::sha256(&loaded),
+                metadata.chunk_hashes[chunk as usize]
+            );
+            bytes.extend_from_slice(&loaded);
+        }
+        let mut reader = bytes.as_slice();
+        let mut items = vec![];
+        while !reader.is_empty() {
+            items.push(SnapshotItem::deserialize(&mut reader).unwrap());
+        }
+        match &items[0] {
+            SnapshotItem::BlockState(block_state) => {
+                assert_eq!(block_state.height, BlockHeight(4));
+                assert_eq!(
+                    block_state.epoch,
+                    shell.wl_storage.storage.block.epoch
+                );
+            }
+            item => panic!("Unexpected first item {item:?}"),
+        }
+        assert!(items.iter().any(|item| matches!(
+            item,
+            SnapshotItem::Subspace { key: k, value: v }
+                if *k == key.to_string() && *v == value
+        )));
+        let stores = items
+            .iter()
+            .filter(|item| matches!(item, SnapshotItem::MerkleTreeStore { .. }))
+            .count();
+        assert_eq!(stores, StoreType::iter().count());
+
+        let subspace =
+            read_snapshot_subspace(&shell.snapshots_dir(), 4).unwrap();
+        assert_eq!(subspace.get(&key.to_string()), Some(&value));
+    }
+
+    /// Test that a snapshot is restored from its chunks by a syncing node
+    /// only if it matches the app hash, and that a chunk that doesn't match
+    /// its hash is fetched again.
+    #[test]
+    fn test_restore_snapshot() {
+        let (mut shell, _recv, _, _) = test_utils::setup();
+        shell.snapshot_interval = Some(2);
+
+        let key = Key::parse("snapshot-test").unwrap();
+        let value = vec![1_u8, 2, 3];
+        shell
+            .wl_storage
+            .storage
+            .write(&key, value.clone())
+            .expect("Test failed");
+        commit_blocks(&mut shell, 2);
+
+        let snapshot = shell.list_snapshots().snapshots.remove(0);
+        let chunks: Vec<_> = (0..snapshot.chunks)
+            .map(|chunk| {
+                shell
+                    .load_snapshot_chunk(request::LoadSnapshotChunk {
+                        height: snapshot.height,
+                        format: SNAPSHOT_FORMAT,
+                        chunk,
+                    })
+                    .chunk
+            })
+            .collect();
+        let root = shell.wl_storage.storage.merkle_root();
+        let app_hash = AppHash::try_from(root.0.to_vec()).unwrap();
+
+        let restore = |app_hash: AppHash| {
+            let (mut syncing, _recv, _, _) = TestShell::new();
+            let offered = syncing.offer_snapshot(request::OfferSnapshot {
+                snapshot: snapshot.clone(),
+                app_hash,
+            });
+            assert_eq!(offered, response::OfferSnapshot::Accept);
+
+            // A tampered chunk must be fetched again
+            let mut tampered = chunks[0].to_vec();
+            tampered.push(0);
+            let applied =
+                syncing.apply_snapshot_chunk(request::ApplySnapshotChunk {
+                    index: 0,
+                    chunk: tampered.into(),
+                    sender: "peer".to_owned(),
+                });
+            assert_eq!(applied.result, ApplySnapshotChunkResult::Retry);
+            assert_eq!(applied.refetch_chunks, vec![0]);
+
+            let mut result = ApplySnapshotChunkResult::Unknown;
+            for (index, chunk) in chunks.iter().enumerate() {
+                result = syncing
+                    .apply_snapshot_chunk(request::ApplySnapshotChunk {
+                        index: index as u32,
+                        chunk: chunk.clone(),
+                        sender: "peer".to_owned(),
+                    })
+                    .result;
+            }
+            (syncing, result)
+        };
+
+        // A snapshot that doesn't match the app hash is rejected
+        let (syncing, result) =
+            restore(AppHash::try_from(vec![0_u8; 32]).unwrap());
+        assert_eq!(result, ApplySnapshotChunkResult::RejectSnapshot);
+        assert_eq!(syncing.wl_storage.storage.read(&key).unwrap().0, None);
+
+        let (syncing, result) = restore(app_hash);
+        assert_eq!(result, ApplySnapshotChunkResult::Accept);
+        let restored = &syncing.wl_storage.storage;
+        let storage = &shell.wl_storage.storage;
+        assert_eq!(restored.read(&key).unwrap().0, Some(value));
+        assert_eq!(restored.merkle_root().0, storage.merkle_root().0);
+        assert_eq!(restored.get_last_block_height(), BlockHeight(2));
+        assert_eq!(restored.block.epoch, storage.block.epoch);
+        assert_eq!(
+            restored.next_epoch_min_start_height,
+            storage.next_epoch_min_start_height
+        );
+        assert_eq!(
+            restored.address_gen.serialize_to_vec(),
+            storage.address_gen.serialize_to_vec()
+        );
+        assert_eq!(
+            restored.block.pred_epochs.serialize_to_vec(),
+            storage.block.pred_epochs.serialize_to_vec()
+        );
+    }
+}
@@ -2,10 +2,13 @@ use std::convert::TryFrom;
 use std::future::Future;
 use std::path::PathBuf;
 use std::pin::Pin;
+use std::sync::{Arc, RwLock};
 use std::task::{Context, Poll};
 
 use futures::future::FutureExt;
+use namada::ledger::events::Event;
 use namada::proof_of_stake::storage::find_validator_by_raw_hash;
+use namada::state::StorageError;
 use namada::tx::data::hash_tx;
 use namada::tx::Tx;
 use namada::types::hash::Hash;
@@ -25,7 +28,11 @@ use crate::facade::tendermint::v0_37::abci::{
 };
 use crate::facade::tendermint_proto::v0_37::abci::ResponseDeliverTx;
 use crate::facade::tower_abci::BoxError;
-use crate::node::ledger::shell::{EthereumOracleChannels, Shell};
+use crate::node::ledger::event_subscriptions::EventSender;
+use crate::node::ledger::shell::{
+    self, EthereumOracleChannels, QuerySnapshot, SharedQuerySnapshot, Shell,
+};
+use crate::node::ledger::storage;
 
 /// The shim wraps the shell, which implements ABCI++.
 /// The shim makes a crude translation between the ABCI interface currently used
@@ -35,6 +42,16 @@ pub struct AbcippShim {
     service: Shell,
     begin_block_request: Option<request::BeginBlock>,
     delivered_txs: Vec<TxBytes>,
+    /// The background compaction of the DB, if enabled
+    compaction: Option<storage::CompactionScheduler>,
+    /// The snapshot of the last committed state to serve the queries from,
+    /// if the concurrent queries are enabled
+    query_snapshot: Option<SharedQuerySnapshot>,
+    /// The channel to publish the events of the committed blocks to the
+    /// event subscribers on, if the event subscriptions are enabled
+    event_sender: Option<EventSender>,
+    /// The events of the block to publish when it's committed
+    block_events: Vec<Event>,
     shell_recv: std::sync::mpsc::Receiver<(
         Req,
         tokio::sync::oneshot::Sender<Result<Resp, BoxError>>,
@@ -50,9 +67,10 @@ impl AbcippShim {
         wasm_dir: PathBuf,
         broadcast_sender: UnboundedSender<Vec<u8>>,
         eth_oracle: Option<EthereumOracleChannels>,
-        db_cache: &rocksdb::Cache,
+        db_cache: &storage::PersistentDBCache,
         vp_wasm_compilation_cache: u64,
         tx_wasm_compilation_cache: u64,
+        event_sender: Option<EventSender>,
     ) -> (Self, AbciService, broadcast::Sender<()>) {
         // We can use an unbounded channel here, because tower-abci limits the
         // the number of requests that can come in
@@ -60,23 +78,53 @@ impl AbcippShim {
         let (shell_send, shell_recv) = std::sync::mpsc::channel();
         let (server_shutdown, _) = broadcast::channel::<()>(1);
         let action_at_height = config.shell.action_at_height.clone();
+        let compaction_config = config.shell.compaction.clone();
+        let concurrent_queries =
+            config.shell.concurrent_queries.unwrap_or_default();
+        let service = Shell::new(
+            config,
+            wasm_dir,
+            broadcast_sender,
+            eth_oracle,
+            Some(db_cache),
+            vp_wasm_compilation_cache,
+            tx_wasm_compilation_cache,
+        );
+        let compaction = compaction_config.and_then(|config| {
+            storage::start_compaction(&service.wl_storage.storage.db, &config)
+        });
+        let query_snapshot = if !concurrent_queries {
+            None
+        } else if cfg!(feature = "sled") {
+            tracing::warn!(
+                "The concurrent queries are not supported with sled"
+            );
+            None
+        } else {
+            if let Err(err) = storage::remove_query_checkpoints(
+                &service.wl_storage.storage.db,
+            ) {
+                tracing::warn!(
+                    "Failed to remove the stale query DB checkpoints: {err}"
+                );
+            }
+            let snapshot = service.query_snapshot().map(Arc::new);
+            Some(Arc::new(RwLock::new(snapshot)))
+        };
         (
             Self {
-                service: Shell::new(
-                    config,
-                    wasm_dir,
-                    broadcast_sender,
-                    eth_oracle,
-                    Some(db_cache),
-                    vp_wasm_compilation_cache,
-                    tx_wasm_compilation_cache,
-                ),
+                service,
                 begin_block_request: None,
                 delivered_txs: vec![],
+                compaction,
+                query_snapshot: query_snapshot.clone(),
+                event_sender,
+                block_events: vec![],
                 shell_recv,
             },
             AbciService {
                 shell_send,
+                query_snapshot,
                 shutdown: server_shutdown.clone(),
                 action_at_height,
                 suspended: false,
@@ -96,6 +144,18 @@ impl AbcippShim {
     /// [`AbciService`].
     pub fn run(mut self) {
         while let Ok((req, resp_sender)) = self.shell_recv.recv() {
+            let is_commit = matches!(req, Req::Commit);
+            if let Some(compaction) = &self.compaction {
+                // The DB isn't compacted while a block is being processed
+                if matches!(
+                    req,
+                    Req::PrepareProposal(_)
+                        | Req::ProcessProposal(_)
+                        | Req::BeginBlock(_)
+                ) {
+                    compaction.pause();
+                }
+            }
             let resp = match req {
                 Req::ProcessProposal(proposal) => self
                     .service
@@ -103,9 +163,25 @@ impl AbcippShim {
                     .map_err(Error::from)
                     .and_then(|resp| resp.try_into()),
                 Req::BeginBlock(block) => {
-                    // we save this data to be forwarded to finalize later
-                    self.begin_block_request = Some(block);
-                    Ok(Resp::BeginBlock(Default::default()))
+                    // A replayed block or a gap in the heights means that the
+                    // Tendermint and Namada states have diverged
+                    let height = BlockHeight(block.header.height.value());
+                    match self
+                        .service
+                        .wl_storage
+                        .storage
+                        .check_next_block_height(height)
+                    {
+                        Ok(()) => {
+                            // we save this data to be forwarded to finalize
+                            // later
+                            self.begin_block_request = Some(block);
+                            Ok(Resp::BeginBlock(Default::default()))
+                        }
+                        Err(err) => Err(Error::Shell(shell::Error::Storage(
+                            StorageError::new(err),
+                        ))),
+                    }
                 }
                 Req::DeliverTx(tx) => {
                     let mut deliver: DeliverTx = Default::default();
@@ -167,6 +243,9 @@ impl AbcippShim {
                         .map_err(Error::from)
                         .and_then(|res| match res {
                             Response::FinalizeBlock(resp) => {
+                                if self.event_sender.is_some() {
+                                    self.block_events = resp.events.clone();
+                                }
                                 Ok(Resp::EndBlock(crate::facade::tendermint_proto::v0_37::abci::ResponseEndBlock::from(resp).try_into().unwrap()))
                             }
                             _ => Err(Error::ConvertResp(res)),
@@ -182,6 +261,31 @@ impl AbcippShim {
                     Err(err) => Err(err),
                 },
             };
+            if let (Some(query_snapshot), true) =
+                (&self.query_snapshot, is_commit)
+            {
+                // The queries are served from the previous snapshot until
+                // the new one is made, and the last of them drops it
+                let snapshot = self.service.query_snapshot().map(Arc::new);
+                let _previous = std::mem::replace(
+                    &mut *query_snapshot.write().unwrap(),
+                    snapshot,
+                );
+            }
+            if is_commit {
+                if let Some(compaction) = &self.compaction {
+                    compaction.resume();
+                }
+                let events = std::mem::take(&mut self.block_events);
+                if let (Some(event_sender), true) =
+                    (&self.event_sender, resp.is_ok())
+                {
+                    for event in events {
+                        // There may be no subscribers
+                        _ = event_sender.send(event);
+                    }
+                }
+            }
             let resp = resp.map_err(|e| e.into());
             if resp_sender.send(resp).is_err() {
                 tracing::info!("ABCI response channel is closed")
@@ -190,6 +294,13 @@ impl AbcippShim {
     }
 }
 
+/// The channel for forwarding the requests to the shell with the senders of
+/// their responses
+type ShellSender = std::sync::mpsc::Sender<(
+    Req,
+    tokio::sync::oneshot::Sender<Result<Resp, BoxError>>,
+)>;
+
 /// Indicates how [`AbciService`] should
 /// check whether or not it needs to take
 /// action.
@@ -206,10 +317,10 @@ enum CheckAction {
 #[derive(Debug)]
 pub struct AbciService {
     /// A channel for forwarding requests to the shell
-    shell_send: std::sync::mpsc::Sender<(
-        Req,
-        tokio::sync::oneshot::Sender<Result<Resp, BoxError>>,
-    )>,
+    shell_send: ShellSender,
+    /// The snapshot of the last committed state to serve the queries from,
+    /// if the concurrent queries are enabled
+    query_snapshot: Option<SharedQuerySnapshot>,
     /// Indicates if the consensus connection is suspended.
     suspended: bool,
     /// This resolves the non-completing futures returned to tower-abci
@@ -291,8 +402,54 @@ impl AbciService {
     /// If we are not taking special action for this request,
     /// forward it normally.
     fn forward_request(&mut self, req: Req) -> <Self as Service<Req>>::Future {
+        match (req, &self.query_snapshot) {
+            (Req::Query(query), Some(snapshot))
+                if QuerySnapshot::can_serve(&query) =>
+            {
+                Self::serve_query(
+                    self.shell_send.clone(),
+                    snapshot.clone(),
+                    query,
+                )
+            }
+            (req, _) => Self::send_to_shell(&self.shell_send, req),
+        }
+    }
+
+    /// Serve the query from the snapshot on a blocking thread, or forward it
+    /// to the shell if no block has been committed yet
+    fn serve_query(
+        shell_send: ShellSender,
+        snapshot: SharedQuerySnapshot,
+        query: request::Query,
+    ) -> <Self as Service<Req>>::Future {
+        async move {
+            // The lock is released before the query is served
+            let snapshot = snapshot.read().unwrap().clone();
+            let served = tokio::task::spawn_blocking(move || match snapshot {
+                Some(snapshot) => Ok(snapshot.query(query)),
+                None => Err(query),
+            })
+            .await?;
+            match served {
+                Ok(resp) => Ok(Resp::Query(resp)),
+                Err(query) => {
+                    let resp =
+                        Self::send_to_shell(&shell_send, Req::Query(query));
+                    resp.await
+                }
+            }
+        }
+        .boxed()
+    }
+
+    /// Send the request to the shell and wait for its response
+    fn send_to_shell(
+        shell_send: &ShellSender,
+        req: Req,
+    ) -> <Self as Service<Req>>::Future {
         let (resp_send, recv) = tokio::sync::oneshot::channel();
-        let result = self.shell_send.send((req, resp_send));
+        let result = shell_send.send((req, resp_send));
 
         async move {
             if let Err(err) = result {
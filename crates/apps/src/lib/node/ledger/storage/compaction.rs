@@ -0,0 +1,338 @@
+//! The background compaction of the DB.
+//!
+//! A manual compaction of a large DB stalls the block production when it's
+//! run on the thread of the shell, so the DB is instead compacted on a
+//! separate thread, one range of keys at a time. Each run compacts the ranges
+//! of the DB files with the most deleted entries first, which reclaims the
+//! space of the pruned data. The compaction is paused while a block is being
+//! processed and for a while after its commit. A pause takes effect once the
+//! range that's being compacted is done.
+
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use crate::config;
+
+/// A range of keys of a column family of the DB to compact
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CompactionRange {
+    /// The name of the column family
+    pub column_family: String,
+    /// The first key of the range, if bounded
+    pub start: Option<Vec<u8>>,
+    /// The last key of the range, if bounded
+    pub end: Option<Vec<u8>>,
+    /// The size of the data in the range in bytes
+    pub size: u64,
+}
+
+/// A DB that can be compacted in the background, one range of keys at a time
+pub trait Compact: Send + 'static {
+    /// Get the ranges to compact in a run, in order. Only the ranges of the
+    /// DB files in which the ratio of deleted entries is at least
+    /// `min_deletions_ratio` are included.
+    fn ranges_to_compact(
+        &self,
+        min_deletions_ratio: f64,
+    ) -> Result<Vec<CompactionRange>, String>;
+
+    /// Compact the range
+    fn compact(&self, range: &CompactionRange) -> Result<(), String>;
+}
+
+/// The activity of a [`CompactionScheduler`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CompactionMetrics {
+    /// The number of completed compaction runs
+    pub runs: u64,
+    /// The number of compacted ranges
+    pub ranges: u64,
+    /// The total size of the compacted ranges in bytes
+    pub bytes: u64,
+    /// The total time spent compacting
+    pub busy: Duration,
+    /// The number of times the compaction had to wait for a block to be
+    /// processed
+    pub pauses: u64,
+}
+
+/// Compacts the DB on a background thread until it's dropped
+#[derive(Debug)]
+pub struct CompactionScheduler {
+    gate: Arc<Gate>,
+    resume_delay: Duration,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl CompactionScheduler {
+    /// Start compacting the DB on a background thread with the given config
+    pub fn start(db: impl Compact, config: &config::Compaction) -> Self {
+        let gate = Arc::new(Gate::default());
+        let interval = Duration::from_secs(config.interval_secs);
+        let min_deletions_ratio = config.min_deletions_ratio;
+        let thread_gate = gate.clone();
+        let thread = std::thread::Builder::new()
+            .name("db-compaction".into())
+            .spawn(move || {
+                while thread_gate.sleep(interval) {
+                    if !run_compaction(&db, &thread_gate, min_deletions_ratio) {
+                        break;
+                    }
+                }
+            })
+            .expect("Must be able to start a thread for the DB compaction");
+        tracing::info!(
+            "Compacting the DB in the background every {}s",
+            config.interval_secs
+        );
+        Self {
+            gate,
+            resume_delay: Duration::from_millis(config.resume_delay_ms),
+            thread: Some(thread),
+        }
+    }
+
+    /// Pause the compaction while a block is being processed
+    pub fn pause(&self) {
+        self.gate.lock().in_block = true;
+    }
+
+    /// Resume the compaction after the configured delay once a block has
+    /// been committed
+    pub fn resume(&self) {
+        let mut state = self.gate.lock();
+        state.in_block = false;
+        state.resume_at = Some(Instant::now() + self.resume_delay);
+        self.gate.cond.notify_all();
+    }
+
+    /// Get the activity of the compaction so far
+    pub fn metrics(&self) -> CompactionMetrics {
+        self.gate.lock().metrics
+    }
+}
+
+impl Drop for CompactionScheduler {
+    fn drop(&mut self) {
+        self.gate.lock().shutdown = true;
+        self.gate.cond.notify_all();
+        if let Some(thread) = self.thread.take() {
+            // Waits for the range that's being compacted
+            if thread.join().is_err() {
+                tracing::error!("The DB compaction thread panicked");
+            }
+        }
+    }
+}
+
+/// Compact the ranges to compact, waiting for the gate to be open before
+/// each range. Returns `false` if the scheduler has been shut down.
+fn run_compaction(
+    db: &impl Compact,
+    gate: &Gate,
+    min_deletions_ratio: f64,
+) -> bool {
+    let ranges = match db.ranges_to_compact(min_deletions_ratio) {
+        Ok(ranges) => ranges,
+        Err(err) => {
+            tracing::error!("Failed to find the DB ranges to compact: {err}");
+            return true;
+        }
+    };
+    let started = Instant::now();
+    let (mut compacted, mut bytes) = (0_u64, 0_u64);
+    for range in ranges {
+        if !gate.wait_until_open() {
+            return false;
+        }
+        let range_started = Instant::now();
+        if let Err(err) = db.compact(&range) {
+            tracing::error!(
+                "Failed to compact a range of the column family {}: {err}",
+                range.column_family
+            );
+            continue;
+        }
+        compacted += 1;
+        bytes += range.size;
+        let mut state = gate.lock();
+        state.metrics.ranges += 1;
+        state.metrics.bytes += range.size;
+        state.metrics.busy += range_started.elapsed();
+    }
+    let metrics = {
+        let mut state = gate.lock();
+        state.metrics.runs += 1;
+        state.metrics
+    };
+    tracing::info!(
+        "Compacted {compacted} DB ranges of {bytes} bytes in {:?}. Total \
+         runs: {}, ranges: {}, bytes: {}, time: {:?}, pauses: {}",
+        started.elapsed(),
+        metrics.runs,
+        metrics.ranges,
+        metrics.bytes,
+        metrics.busy,
+        metrics.pauses,
+    );
+    true
+}
+
+/// Lets the compaction proceed between the blocks
+#[derive(Debug, Default)]
+struct Gate {
+    state: Mutex<GateState>,
+    cond: Condvar,
+}
+
+#[derive(Debug, Default)]
+struct GateState {
+    /// A block is being processed
+    in_block: bool,
+    /// The compaction may not resume before this time
+    resume_at: Option<Instant>,
+    /// The scheduler has been dropped
+    shutdown: bool,
+    metrics: CompactionMetrics,
+}
+
+impl Gate {
+    fn lock(&self) -> MutexGuard<'_, GateState> {
+        self.state.lock().unwrap()
+    }
+
+    /// Sleep for the given duration. Returns `false` if the scheduler has
+    /// been shut down in the meantime.
+    fn sleep(&self, duration: Duration) -> bool {
+        let deadline = Instant::now() + duration;
+        let mut state = self.lock();
+        loop {
+            if state.shutdown {
+                return false;
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return true;
+            }
+            state = self.cond.wait_timeout(state, deadline - now).unwrap().0;
+        }
+    }
+
+    /// Wait until no block is being processed and the delay after the last
+    /// commit has passed. Returns `false` if the scheduler has been shut
+    /// down in the meantime.
+    fn wait_until_open(&self) -> bool {
+        let mut state = self.lock();
+        let mut paused = false;
+        loop {
+            if state.shutdown {
+                return false;
+            }
+            let now = Instant::now();
+            let wait = if state.in_block {
+                None
+            } else {
+                match state.resume_at {
+                    Some(resume_at) if resume_at > now => Some(resume_at - now),
+                    _ => return true,
+                }
+            };
+            if !paused {
+                paused = true;
+                state.metrics.pauses += 1;
+            }
+            state = match wait {
+                Some(wait) => self.cond.wait_timeout(state, wait).unwrap().0,
+                None => self.cond.wait(state).unwrap(),
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use super::*;
+
+    /// A DB that records the compacted ranges
+    #[derive(Clone, Default)]
+    struct TestDb {
+        compacted: Arc<Mutex<Vec<CompactionRange>>>,
+    }
+
+    impl Compact for TestDb {
+        fn ranges_to_compact(
+            &self,
+            _min_deletions_ratio: f64,
+        ) -> Result<Vec<CompactionRange>, String> {
+            Ok((0..3_u8)
+                .map(|ix| CompactionRange {
+                    column_family: "cf".to_string(),
+                    start: Some(vec![ix]),
+                    end: Some(vec![ix + 1]),
+                    size: 10,
+                })
+                .collect())
+        }
+
+        fn compact(&self, range: &CompactionRange) -> Result<(), String> {
+            self.compacted.lock().unwrap().push(range.clone());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_compaction_run() {
+        let db = TestDb::default();
+        let gate = Gate::default();
+        assert!(run_compaction(&db, &gate, 0.0));
+        assert_eq!(
+            db.compacted.lock().unwrap().clone(),
+            db.ranges_to_compact(0.0).unwrap()
+        );
+        let metrics = gate.lock().metrics;
+        assert_eq!(
+            (metrics.runs, metrics.ranges, metrics.bytes, metrics.pauses),
+            (1, 3, 30, 0)
+        );
+
+        // Nothing is compacted once the scheduler is shut down
+        gate.lock().shutdown = true;
+        assert!(!run_compaction(&db, &gate, 0.0));
+        assert_eq!(db.compacted.lock().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_compaction_pause() {
+        let gate = Arc::new(Gate::default());
+        let opened = Arc::new(AtomicBool::new(false));
+        gate.lock().in_block = true;
+
+        let thread = {
+            let gate = gate.clone();
+            let opened = opened.clone();
+            std::thread::spawn(move || {
+                assert!(gate.wait_until_open());
+                opened.store(true, Ordering::SeqCst);
+            })
+        };
+        std::thread::sleep(Duration::from_millis(50));
+        assert!(
+            !opened.load(Ordering::SeqCst),
+            "The compaction must wait while a block is being processed"
+        );
+
+        // The compaction resumes after the delay once the block is committed
+        {
+            let mut state = gate.lock();
+            state.in_block = false;
+            state.resume_at = Some(Instant::now() + Duration::from_millis(20));
+            gate.cond.notify_all();
+        }
+        thread.join().unwrap();
+        assert!(opened.load(Ordering::SeqCst));
+        assert_eq!(gate.lock().metrics.pauses, 1);
+    }
+}
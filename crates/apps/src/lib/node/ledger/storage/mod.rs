@@ -1,7 +1,11 @@
 //! The storage module handles both the current state in-memory and the stored
 //! state in DB.
 
+mod compaction;
+#[cfg(not(feature = "sled"))]
 mod rocksdb;
+#[cfg(feature = "sled")]
+mod sled;
 
 use std::fmt;
 
@@ -9,12 +13,27 @@ use arse_merkle_tree::blake2b::Blake2bHasher;
 use arse_merkle_tree::traits::Hasher;
 use arse_merkle_tree::H256;
 use blake2b_rs::{Blake2b, Blake2bBuilder};
-use namada::state::{State, StorageHasher};
+pub use compaction::{CompactionMetrics, CompactionScheduler};
+use namada::state::{DbResult, State, StorageHasher, DB};
+use namada::types::storage::BlockHeight;
+
+use crate::config;
 
 #[derive(Default)]
 pub struct PersistentStorageHasher(Blake2bHasher);
 
+/// The DB backend of the node. RocksDB by default, or sled with the `sled`
+/// feature.
+#[cfg(not(feature = "sled"))]
 pub type PersistentDB = rocksdb::RocksDB;
+#[cfg(feature = "sled")]
+pub type PersistentDB = sled::SledDB;
+
+/// The cache of the [`PersistentDB`]
+pub type PersistentDBCache = <PersistentDB as DB>::Cache;
+
+/// A checkpoint of the [`PersistentDB`]
+pub type PersistentDBCheckpoint = <PersistentDB as DB>::Checkpoint;
 
 pub type PersistentStorage = State<PersistentDB, PersistentStorageHasher>;
 
@@ -44,6 +63,72 @@ impl fmt::Debug for PersistentStorageHasher {
     }
 }
 
+/// Create the cache of the [`PersistentDB`] with the given capacity
+pub fn new_db_cache(capacity_bytes: u64) -> PersistentDBCache {
+    #[cfg(not(feature = "sled"))]
+    {
+        ::rocksdb::Cache::new_lru_cache(capacity_bytes as usize)
+    }
+    #[cfg(feature = "sled")]
+    {
+        capacity_bytes
+    }
+}
+
+/// Start the background compaction of the [`PersistentDB`] with the given
+/// config. The sled DB isn't compacted.
+pub fn start_compaction(
+    db: &PersistentDB,
+    config: &config::Compaction,
+) -> Option<CompactionScheduler> {
+    #[cfg(not(feature = "sled"))]
+    {
+        Some(CompactionScheduler::start(db.compaction_handle(), config))
+    }
+    #[cfg(feature = "sled")]
+    {
+        let _ = (db, config);
+        tracing::warn!("The background compaction is not supported with sled");
+        None
+    }
+}
+
+/// Make a checkpoint of the [`PersistentDB`] at the given committed height to
+/// serve the queries from, together with a handle to read it from another
+/// thread, which must be dropped before the checkpoint. Not supported with
+/// sled.
+pub fn query_checkpoint(
+    db: &PersistentDB,
+    height: BlockHeight,
+) -> Option<DbResult<(PersistentDBCheckpoint, PersistentDB)>> {
+    #[cfg(not(feature = "sled"))]
+    {
+        Some(db.query_checkpoint(height).map(|checkpoint| {
+            let handle = checkpoint.shared_handle();
+            (checkpoint, handle)
+        }))
+    }
+    #[cfg(feature = "sled")]
+    {
+        let _ = (db, height);
+        None
+    }
+}
+
+/// Remove the checkpoints of the [`PersistentDB`] made to serve the queries
+/// that have been left behind by a previous run of the node
+pub fn remove_query_checkpoints(db: &PersistentDB) -> DbResult<()> {
+    #[cfg(not(feature = "sled"))]
+    {
+        db.remove_query_checkpoints()
+    }
+    #[cfg(feature = "sled")]
+    {
+        let _ = db;
+        Ok(())
+    }
+}
+
 fn new_blake2b() -> Blake2b {
     Blake2bBuilder::new(32).personal(b"namada storage").build()
 }
@@ -61,14 +146,15 @@ mod tests {
     use namada::ledger::parameters::{EpochDuration, Parameters};
     use namada::state::write_log::WriteLog;
     use namada::state::{
-        self, StorageRead, StorageWrite, StoreType, WlStorage, DB,
+        self, ColdStorage, DBIter, PruningMode, StorageRead, StorageWrite,
+        StoreType, WlStorage, DB,
     };
     use namada::token::conversion::update_allowed_conversions;
     use namada::types::chain::ChainId;
     use namada::types::ethereum_events::Uint;
     use namada::types::hash::Hash;
     use namada::types::keccak::KeccakHash;
-    use namada::types::storage::{BlockHash, BlockHeight, Key};
+    use namada::types::storage::{BlockHash, BlockHeight, Key, KeySeg};
     use namada::types::time::DurationSecs;
     use namada::types::{address, storage};
     use namada::{parameters, types};
@@ -653,6 +739,208 @@ mod tests {
         assert!(result.is_err(), "The bridge pool tree should be pruned");
     }
 
+    /// Test that the `keep-recent` pruning mode prunes the epochs outside of
+    /// the retention window, unless they're held back by the safeguard.
+    #[test]
+    fn test_prune_blocks_keep_recent() {
+        let db_path =
+            TempDir::new().expect("Unable to create a temporary DB directory");
+        let mut storage = PersistentStorage::open(
+            db_path.path(),
+            ChainId::default(),
+            address::nam(),
+            None,
+            None,
+            is_merklized_storage_key,
+        );
+        storage.pruning = PruningMode::KeepRecent(2);
+        let key = Key::parse("key").unwrap();
+
+        // A new epoch starts at every odd height
+        let commit_blocks = |storage: &mut PersistentStorage, heights| {
+            for height in heights {
+                storage
+                    .begin_block(BlockHash::default(), BlockHeight(height))
+                    .expect("begin_block failed");
+                if height % 2 == 1 {
+                    if height > 1 {
+                        storage.block.epoch = storage.block.epoch.next();
+                    }
+                    storage.block.pred_epochs.new_epoch(BlockHeight(height));
+                }
+                storage
+                    .write(&key, types::encode(&height))
+                    .expect("write failed");
+                let batch = PersistentStorage::batch();
+                storage.commit_block(batch).expect("commit failed");
+            }
+        };
+        let result_heights = |storage: &PersistentStorage| {
+            storage
+                .db
+                .iter_results()
+                .map(|(height, _, _)| BlockHeight::parse(height).unwrap().0)
+                .sorted()
+                .collect::<Vec<_>>()
+        };
+
+        // The oldest retained height 3 is the start of epoch 1
+        commit_blocks(&mut storage, 1..=5);
+        assert_eq!(result_heights(&storage), vec![3, 4, 5]);
+        assert!(
+            storage
+                .get_merkle_tree(2.into(), Some(StoreType::Base))
+                .is_err(),
+            "The tree at height 2 should be pruned"
+        );
+        assert!(
+            storage
+                .get_merkle_tree(4.into(), Some(StoreType::Base))
+                .is_ok(),
+            "The tree at height 4 should be restored"
+        );
+        let (value, _gas) =
+            storage.read_with_height(&key, BlockHeight(3)).unwrap();
+        assert_eq!(value, Some(types::encode(&3_u64)));
+
+        // The epochs of the blocks at and above the safeguard height are kept
+        storage.pruning_safeguard_height = Some(BlockHeight(4));
+        commit_blocks(&mut storage, 6..=7);
+        assert_eq!(result_heights(&storage), vec![3, 4, 5, 6, 7]);
+
+        // Once the safeguard moves on, all the held back blocks are pruned
+        storage.pruning_safeguard_height = None;
+        commit_blocks(&mut storage, 8..=8);
+        assert_eq!(result_heights(&storage), vec![5, 6, 7, 8]);
+    }
+
+    /// Test that the data of the blocks older than the hot blocks is moved to
+    /// the cold storage and read through from it.
+    #[test]
+    fn test_cold_storage() {
+        let db_path =
+            TempDir::new().expect("Unable to create a temporary DB directory");
+        let cold_db_path =
+            TempDir::new().expect("Unable to create a temporary DB directory");
+        let mut storage = PersistentStorage::open(
+            db_path.path(),
+            ChainId::default(),
+            address::nam(),
+            None,
+            None,
+            is_merklized_storage_key,
+        );
+        storage.pruning = PruningMode::Archive;
+        storage.cold_storage = Some(ColdStorage {
+            db: PersistentDB::open(cold_db_path.path(), None),
+            hot_blocks: 2,
+            oldest_hot_height: None,
+        });
+        let key = Key::parse("key").unwrap();
+
+        // A new epoch starts at every odd height
+        let mut merkle_roots = HashMap::new();
+        for height in 1..=6 {
+            storage
+                .begin_block(BlockHash::default(), BlockHeight(height))
+                .expect("begin_block failed");
+            if height % 2 == 1 {
+                if height > 1 {
+                    storage.block.epoch = storage.block.epoch.next();
+                }
+                storage.block.pred_epochs.new_epoch(BlockHeight(height));
+            }
+            storage
+                .write(&key, types::encode(&height))
+                .expect("write failed");
+            let batch = PersistentStorage::batch();
+            storage.commit_block(batch).expect("commit failed");
+            merkle_roots.insert(height, storage.merkle_root());
+        }
+
+        let result_heights = |db: &PersistentDB| {
+            db.iter_results()
+                .map(|(height, _, _)| BlockHeight::parse(height).unwrap().0)
+                .sorted()
+                .collect::<Vec<_>>()
+        };
+        let cold_db = &storage.cold_storage.as_ref().unwrap().db;
+        assert_eq!(result_heights(&storage.db), vec![4, 5, 6]);
+        assert_eq!(result_heights(cold_db), vec![1, 2, 3]);
+        let (results, _gas) = storage.iter_results();
+        assert_eq!(results.count(), 6);
+
+        // The trees are restored from the stores in either DB
+        for height in 1..=6 {
+            let tree = storage
+                .get_merkle_tree(height.into(), None)
+                .expect("The tree should be restored");
+            assert_eq!(tree.root().0, merkle_roots[&height].0);
+        }
+    }
+
+    /// Test the read-only copy of the state on a checkpoint of the DB
+    #[cfg(not(feature = "sled"))]
+    #[test]
+    fn test_read_only_copy() {
+        let db_path =
+            TempDir::new().expect("Unable to create a temporary DB directory");
+        let mut storage = PersistentStorage::open(
+            db_path.path(),
+            ChainId::default(),
+            address::nam(),
+            None,
+            None,
+            is_merklized_storage_key,
+        );
+        let key = Key::parse("key").unwrap();
+        let commit_height = |storage: &mut PersistentStorage, height: u64| {
+            storage
+                .begin_block(BlockHash::default(), BlockHeight(height))
+                .expect("begin_block failed");
+            storage
+                .write(&key, types::encode(&height))
+                .expect("write failed");
+            let batch = PersistentStorage::batch();
+            storage.commit_block(batch).expect("commit failed");
+        };
+        commit_height(&mut storage, 1);
+        commit_height(&mut storage, 2);
+
+        let (checkpoint, db) = query_checkpoint(&storage.db, BlockHeight(2))
+            .unwrap()
+            .expect("checkpoint failed");
+        let copy = storage.read_only_copy(db, None);
+        assert_eq!(copy.get_last_block_height(), BlockHeight(2));
+        assert_eq!(copy.get_last_epoch(), storage.get_last_epoch());
+        let (value, _gas) = copy.read(&key).expect("read failed");
+        assert_eq!(value, Some(types::encode(&2_u64)));
+        let (value, _gas) = copy
+            .read_with_height(&key, BlockHeight(1))
+            .expect("read failed");
+        assert_eq!(value, Some(types::encode(&1_u64)));
+
+        // The tree of the committed block is restored from the DB
+        let tree = copy
+            .get_merkle_tree(BlockHeight(2), None)
+            .expect("The tree should be restored");
+        assert_eq!(tree.root().0, storage.merkle_root().0);
+
+        // The copy doesn't see the blocks committed after the checkpoint
+        commit_height(&mut storage, 3);
+        let (value, _gas) = storage.read(&key).expect("read failed");
+        assert_eq!(value, Some(types::encode(&3_u64)));
+        let (value, _gas) = copy.read(&key).expect("read failed");
+        assert_eq!(value, Some(types::encode(&2_u64)));
+
+        // Dropping the checkpoint removes it without affecting the DB
+        drop(copy);
+        drop(checkpoint);
+        assert!(!storage.db.query_checkpoints_dir().join("2").exists());
+        let (value, _gas) = storage.read(&key).expect("read failed");
+        assert_eq!(value, Some(types::encode(&3_u64)));
+    }
+
     /// Test the prefix iterator with RocksDB.
     #[test]
     fn test_persistent_storage_prefix_iter() {
@@ -18,6 +18,12 @@
 //!     - `next_epoch_min_start_height`
 //!     - `next_epoch_min_start_time`
 //!   - `conversion_state`: MASP conversion state
+//!   - `block_journal`: the journal of a block whose commit has started
+//!   - `storage_usage/{address}`: the number of bytes of the subspace values of
+//!     the address
+//!   - `balance_index/{token}/{owner}`: the latest balances of the token owners
+//!   - `account_history/{address}/{h}/{index}`: the hashes of the txs in which
+//!     the address appeared, if indexed
 //! - `subspace`: accounts sub-spaces
 //!   - `{address}/{dyn}`: any byte data associated with accounts
 //! - `diffs`: diffs in account subspaces' key-vals
@@ -34,15 +40,23 @@
 //!     - `epoch`: block epoch
 //!     - `address_gen`: established address generator
 //!     - `header`: block's header
+//!     - `changed_keys`: the filter of the subspace keys changed in the block
+//!     - `state/{key}`: the latest ledger state at the height, kept for the
+//!       recent blocks to be able to roll them back
 //! - `replay_protection`: hashes of processed tx
 //!     - `all`: the hashes included up to the last block
 //!     - `last`: the hashes included in the last block
+//!     - `block/{h}`: the hashes included in the block at height `h`, kept for
+//!       the recent blocks to be able to roll them back
+//! - `checksum`: the checksums of the subspace values, if enabled
+//!   - `{address}/{dyn}`: the checksum of the value of the subspace key
 
-use std::fs::File;
+use std::collections::BTreeSet;
+use std::fs::{self, File};
 use std::io::{BufWriter, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
 
 use borsh::BorshDeserialize;
 use borsh_ext::BorshSerializeExt;
@@ -52,27 +66,32 @@ use namada::eth_bridge::storage::proof::BridgePoolRootProof;
 use namada::ledger::eth_bridge::storage::bridge_pool;
 use namada::ledger::replay_protection;
 use namada::ledger::storage::tx_queue::TxQueue;
+use namada::state::changed_keys::ChangedKeysFilter;
 use namada::state::merkle_tree::{base_tree_key_prefix, subtree_key_prefix};
 use namada::state::types::PrefixIterator;
+use namada::state::value_checksum::ValueChecksum;
 use namada::state::{
-    BlockStateRead, BlockStateWrite, DBIter, DBWriteBatch, DbError as Error,
-    DbResult as Result, MerkleTreeStoresRead, StoreType, DB,
+    BlockJournal, BlockStateRead, BlockStateWrite, DBCheckpoint, DBIter,
+    DBWriteBatch, DbError as Error, DbResult as Result, MerkleTreeStoresRead,
+    StoreType, DB, MAX_ROLLBACK_BLOCKS,
 };
 use namada::types;
+use namada::types::address::Address;
 use namada::types::storage::{
     BlockHeight, BlockResults, Epoch, EthEventsQueue, Header, Key, KeySeg,
-    KEY_SEGMENT_SEPARATOR,
+    TxIndex, KEY_SEGMENT_SEPARATOR,
 };
 use namada::types::time::DateTimeUtc;
-use namada::types::token::ConversionState;
+use namada::types::token::{Amount, ConversionState};
 use namada::types::{ethereum_events, ethereum_structs};
 use rayon::prelude::*;
 use rocksdb::{
-    BlockBasedOptions, ColumnFamily, ColumnFamilyDescriptor, DBCompactionStyle,
-    DBCompressionType, Direction, FlushOptions, IteratorMode, Options,
-    ReadOptions, WriteBatch,
+    BlockBasedOptions, ColumnFamily, ColumnFamilyDescriptor, CompactOptions,
+    DBCompactionStyle, DBCompressionType, Direction, FlushOptions,
+    IteratorMode, Options, ReadOptions, WriteBatch,
 };
 
+use super::compaction::{Compact, CompactionRange};
 use crate::config::utils::num_of_threads;
 
 // TODO the DB schema will probably need some kind of versioning
@@ -87,18 +106,48 @@ const DIFFS_CF: &str = "diffs";
 const STATE_CF: &str = "state";
 const BLOCK_CF: &str = "block";
 const REPLAY_PROTECTION_CF: &str = "replay_protection";
+const VALUE_CHECKSUM_CF: &str = "checksum";
 
 const OLD_DIFF_PREFIX: &str = "old";
 const NEW_DIFF_PREFIX: &str = "new";
 
-/// RocksDB handle
+const BLOCK_JOURNAL_KEY: &str = "block_journal";
+const STORAGE_USAGE_PREFIX: &str = "storage_usage";
+const BALANCE_INDEX_PREFIX: &str = "balance_index";
+const ACCOUNT_HISTORY_PREFIX: &str = "account_history";
+
+/// The keys of the latest ledger state in the [`STATE_CF`] that are versioned
+/// by height in the [`BLOCK_CF`] to be able to roll back several blocks
+const VERSIONED_STATE_KEYS: [&str; 6] = [
+    "next_epoch_min_start_height",
+    "next_epoch_min_start_time",
+    "update_epoch_blocks_delay",
+    "tx_queue",
+    "ethereum_height",
+    "eth_events_queue",
+];
+
+/// RocksDB handle. The second field is `true` for the primary handle returned
+/// by [`open`], which is the only one that flushes the DB when dropped.
 #[derive(Debug)]
-pub struct RocksDB(rocksdb::DB);
+pub struct RocksDB(Arc<rocksdb::DB>, bool);
+
+/// A handle to compact the [`RocksDB`] in the background
+#[derive(Debug)]
+pub struct RocksDBCompaction(Arc<rocksdb::DB>);
 
 /// DB Handle for batch writes.
 #[derive(Default)]
 pub struct RocksDBWriteBatch(WriteBatch);
 
+/// A RocksDB checkpoint, i.e. a copy of the DB made of hard links to its
+/// files. The checkpoint is removed when dropped.
+#[derive(Debug)]
+pub struct RocksDBCheckpoint {
+    db: Option<RocksDB>,
+    path: PathBuf,
+}
+
 /// Open RocksDB for the DB
 pub fn open(
     path: impl AsRef<Path>,
@@ -190,18 +239,130 @@ pub fn open(
         replay_protection_cf_opts,
     ));
 
+    // for the checksums of the subspace values (read/update-intensive)
+    let mut value_checksum_cf_opts = Options::default();
+    // No compression since the checksums are random
+    value_checksum_cf_opts.set_level_compaction_dynamic_level_bytes(true);
+    value_checksum_cf_opts.set_compaction_style(DBCompactionStyle::Level);
+    value_checksum_cf_opts.set_block_based_table_factory(&table_opts);
+    cfs.push(ColumnFamilyDescriptor::new(
+        VALUE_CHECKSUM_CF,
+        value_checksum_cf_opts,
+    ));
+
     rocksdb::DB::open_cf_descriptors(&db_opts, path, cfs)
-        .map(RocksDB)
+        .map(|db| RocksDB(Arc::new(db), true))
         .map_err(|e| Error::DBError(e.into_string()))
 }
 
 impl Drop for RocksDB {
     fn drop(&mut self) {
-        self.flush(true).expect("flush failed");
+        if self.1 {
+            self.flush(true).expect("flush failed");
+        }
+    }
+}
+
+impl Drop for RocksDBCheckpoint {
+    fn drop(&mut self) {
+        // Close the checkpoint before removing its files
+        self.db = None;
+        if let Err(err) = fs::remove_dir_all(&self.path) {
+            tracing::error!(
+                "Failed to remove the DB checkpoint at {}: {}",
+                self.path.to_string_lossy(),
+                err
+            );
+        }
+    }
+}
+
+impl DBCheckpoint for RocksDBCheckpoint {
+    fn read_last_block(&self) -> Result<Option<BlockStateRead>> {
+        self.db().read_last_block()
+    }
+
+    fn iter_subspace(
+        &self,
+    ) -> Box<dyn Iterator<Item = (String, Vec<u8>)> + '_> {
+        Box::new(
+            self.db()
+                .iter_prefix(None)
+                .map(|(key, val, _gas)| (key, val)),
+        )
+    }
+}
+
+impl RocksDBCheckpoint {
+    fn db(&self) -> &RocksDB {
+        self.db
+            .as_ref()
+            .expect("The checkpoint is open until dropped")
+    }
+
+    /// Get another handle to the DB of the checkpoint to read it from another
+    /// thread. The handle must be dropped before the checkpoint.
+    pub fn shared_handle(&self) -> RocksDB {
+        self.db().shared_handle()
     }
 }
 
 impl RocksDB {
+    /// Get another handle to the same DB to read it from another thread.
+    /// Dropping it doesn't flush the DB.
+    pub fn shared_handle(&self) -> Self {
+        Self(self.0.clone(), false)
+    }
+
+    /// Make a checkpoint of the DB at the given committed height to serve the
+    /// queries from while the next blocks are being committed
+    pub fn query_checkpoint(
+        &self,
+        height: BlockHeight,
+    ) -> Result<RocksDBCheckpoint> {
+        let dir = self.query_checkpoints_dir();
+        fs::create_dir_all(&dir).map_err(|e| Error::DBError(e.to_string()))?;
+        self.checkpoint_at(dir.join(height.to_string()))
+    }
+
+    /// Remove the checkpoints made to serve the queries that have been left
+    /// behind by a previous run of the node
+    pub fn remove_query_checkpoints(&self) -> Result<()> {
+        let dir = self.query_checkpoints_dir();
+        if dir.exists() {
+            fs::remove_dir_all(&dir)
+                .map_err(|e| Error::DBError(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    /// Get the directory of the checkpoints made to serve the queries, which
+    /// is next to the DB
+    pub fn query_checkpoints_dir(&self) -> PathBuf {
+        let mut path = self.0.path().to_path_buf();
+        path.set_extension("queries");
+        path
+    }
+
+    fn checkpoint_at(&self, path: PathBuf) -> Result<RocksDBCheckpoint> {
+        // The checkpoint is created next to the DB, as its files are hard
+        // links which require the same file system
+        if path.exists() {
+            fs::remove_dir_all(&path)
+                .map_err(|e| Error::DBError(e.to_string()))?;
+        }
+        rocksdb::checkpoint::Checkpoint::new(&self.0)
+            .and_then(|checkpoint| checkpoint.create_checkpoint(&path))
+            .map_err(|e| Error::DBError(e.into_string()))?;
+        let db = open(&path, None)?;
+        Ok(RocksDBCheckpoint { db: Some(db), path })
+    }
+
+    /// Get a handle to compact the DB in the background
+    pub fn compaction_handle(&self) -> RocksDBCompaction {
+        RocksDBCompaction(self.0.clone())
+    }
+
     fn get_column_family(&self, cf_name: &str) -> Result<&ColumnFamily> {
         self.0
             .cf_handle(cf_name)
@@ -477,12 +638,48 @@ impl RocksDB {
         buf.flush().expect("Unable to write to output file");
     }
 
-    /// Rollback to previous block. Given the inner working of tendermint
-    /// rollback and of the key structure of Namada, calling rollback more than
+    /// Check that the given number of the last blocks can be rolled back,
+    /// without modifying the DB. Returns the height of the block to roll back
+    /// to.
+    pub fn check_rollback(
+        &self,
+        blocks: u64,
+        is_merklized: impl Fn(&Key) -> bool,
+    ) -> Result<BlockHeight> {
+        let last_block = self.read_last_block()?.ok_or(Error::DBError(
+            "Missing last block in storage".to_string(),
+        ))?;
+        let height = last_block
+            .height
+            .0
+            .checked_sub(blocks)
+            .filter(|height| blocks > 0 && *height > 0)
+            .map(BlockHeight)
+            .ok_or_else(|| {
+                Error::DBError(format!(
+                    "Cannot roll back {blocks} blocks from the last block \
+                     height {}",
+                    last_block.height
+                ))
+            })?;
+        self.read_rollback_state(&last_block, height, &is_merklized)?;
+        Ok(height)
+    }
+
+    /// Rollback to the height to which Tendermint has been rolled back. Given
+    /// the inner working of tendermint rollback, calling rollback more than
     /// once without restarting the chain results in a single rollback.
+    ///
+    /// The subspace is restored from the diffs and the rest of the state from
+    /// the data versioned by height, so at most [`MAX_ROLLBACK_BLOCKS`] blocks
+    /// can be rolled back, over at most one epoch change. Only the last diff
+    /// of the keys that are not merklized, for which `is_merklized` returns
+    /// `false`, is kept, so they must not have been changed after the first
+    /// rolled back block.
     pub fn rollback(
         &mut self,
         tendermint_block_height: BlockHeight,
+        is_merklized: impl Fn(&Key) -> bool + Sync,
     ) -> Result<()> {
         let last_block = self.read_last_block()?.ok_or(Error::DBError(
             "Missing last block in storage".to_string(),
@@ -502,141 +699,254 @@ impl RocksDB {
             );
             return Ok(());
         }
+        if tendermint_block_height > last_block.height {
+            return Err(Error::DBError(format!(
+                "The Tendermint height {tendermint_block_height} is ahead of \
+                 the Namada height {}",
+                last_block.height
+            )));
+        }
 
-        let mut batch = WriteBatch::default();
-        let previous_height =
-            BlockHeight::from(u64::from(last_block.height) - 1);
+        let height = tendermint_block_height;
+        let state =
+            self.read_rollback_state(&last_block, height, &is_merklized)?;
+        let rolled_back_heights =
+            || (height.0 + 1..=last_block.height.0).map(BlockHeight);
 
+        let mut batch = WriteBatch::default();
         let state_cf = self.get_column_family(STATE_CF)?;
         // Revert the non-height-prepended metadata storage keys which get
-        // updated with every block. Because of the way we save these
-        // three keys in storage we can only perform one rollback before
-        // restarting the chain
+        // updated with every block
         tracing::info!("Reverting non-height-prepended metadata keys");
-        batch.put_cf(state_cf, "height", types::encode(&previous_height));
-        for metadata_key in [
-            "next_epoch_min_start_height",
-            "next_epoch_min_start_time",
-            "tx_queue",
-        ] {
-            let previous_key = format!("pred/{}", metadata_key);
-            let previous_value = self
-                .0
-                .get_cf(state_cf, previous_key.as_bytes())
-                .map_err(|e| Error::DBError(e.to_string()))?
-                .ok_or(Error::UnknownKey { key: previous_key })?;
+        batch.put_cf(state_cf, "height", types::encode(&height));
+        for (key, value) in state {
+            batch.put_cf(state_cf, key, value);
+            // NOTE: we cannot restore the "pred/" keys themselves, but they
+            // are only needed to roll back a block committed before the
+            // state was versioned by height
+        }
 
-            batch.put_cf(state_cf, metadata_key, previous_value);
-            // NOTE: we cannot restore the "pred/" keys themselves since we
-            // don't have their predecessors in storage, but there's no need to
-            // since we cannot do more than one rollback anyway because of
-            // Tendermint.
+        // Delete the tx hashes included in the rolled back blocks
+        let reprot_cf = self.get_column_family(REPLAY_PROTECTION_CF)?;
+        tracing::info!("Removing replay protection hashes");
+        for rolled_back in rolled_back_heights() {
+            let prefix = replay_protection::block_prefix(rolled_back);
+            for (hash, _val, _gas) in
+                iter_prefix(self, reprot_cf, Some(&prefix), None)
+            {
+                batch.delete_cf(
+                    reprot_cf,
+                    format!("{}/{hash}", replay_protection::all_prefix()),
+                );
+            }
         }
+        // The hashes of the last block haven't been moved to `all` yet
+        let last_prefix = replay_protection::last_prefix();
+        batch.delete_range_cf(
+            reprot_cf,
+            format!("{last_prefix}/"),
+            format!("{last_prefix}0"),
+        );
 
-        // Revert conversion state if the epoch had been changed
-        if last_block.pred_epochs.get_epoch(previous_height)
-            != Some(last_block.epoch)
+        // Delete the txs of the rolled back blocks from the account history
+        tracing::info!("Removing the rolled back txs from the account history");
+        let history_prefix =
+            Key::from(ACCOUNT_HISTORY_PREFIX.to_owned().to_db_key());
+        for (key, _val, _gas) in
+            iter_prefix(self, state_cf, Some(&history_prefix), None)
         {
-            let previous_key = "pred/conversion_state".to_string();
-            let previous_value = self
-                .0
-                .get_cf(state_cf, previous_key.as_bytes())
-                .map_err(|e| Error::DBError(e.to_string()))?
-                .ok_or(Error::UnknownKey { key: previous_key })?;
-            batch.put_cf(state_cf, "conversion_state", previous_value);
+            let is_rolled_back = key
+                .split('/')
+                .nth(1)
+                .and_then(|tx_height| tx_height.parse::<u64>().ok())
+                .map(|tx_height| tx_height > height.0)
+                .unwrap_or_default();
+            if is_rolled_back {
+                batch.delete_cf(state_cf, format!("{history_prefix}/{key}"));
+            }
         }
 
-        // Delete block results for the last block
-        let block_cf = self.get_column_family(BLOCK_CF)?;
-        tracing::info!("Removing last block results");
-        batch.delete_cf(block_cf, format!("results/{}", last_block.height));
-
-        // Delete the tx hashes included in the last block
-        let reprot_cf = self.get_column_family(REPLAY_PROTECTION_CF)?;
-        tracing::info!("Removing replay protection hashes");
-        batch
-            .delete_cf(reprot_cf, replay_protection::last_prefix().to_string());
+        // The storage usage and the balance index are computed again from the
+        // restored subspace when the state is loaded
+        for prefix in [STORAGE_USAGE_PREFIX, BALANCE_INDEX_PREFIX] {
+            batch.delete_range_cf(
+                state_cf,
+                format!("{prefix}/"),
+                format!("{prefix}0"),
+            );
+        }
 
         // Execute next step in parallel
         let batch = Mutex::new(batch);
 
-        tracing::info!("Restoring previous height subspace diffs");
-        self.iter_prefix(None).par_bridge().try_for_each(
-            |(key, _value, _gas)| -> Result<()> {
-                // Restore previous height diff if present, otherwise delete the
-                // subspace key
-                let subspace_cf = self.get_column_family(SUBSPACE_CF)?;
-                match self.read_subspace_val_with_height(
-                    &Key::from(key.to_db_key()),
-                    previous_height,
-                    last_block.height,
-                )? {
-                    Some(previous_value) => batch.lock().unwrap().put_cf(
-                        subspace_cf,
-                        &key,
-                        previous_value,
-                    ),
-                    None => batch.lock().unwrap().delete_cf(subspace_cf, &key),
-                }
-
-                Ok(())
-            },
-        )?;
-
-        // Look for diffs in this block to find what has been deleted
-        let diff_new_key_prefix = Key {
-            segments: vec![
-                last_block.height.to_db_key(),
-                NEW_DIFF_PREFIX.to_string().to_db_key(),
-            ],
-        };
-        {
-            let mut batch_guard = batch.lock().unwrap();
+        tracing::info!("Restoring the subspace at height {height}");
+        let last_height = last_block.height;
+        let restore = |key: &String| -> Result<()> {
             let subspace_cf = self.get_column_family(SUBSPACE_CF)?;
-            for (key, val, _) in
-                iter_diffs_prefix(self, last_block.height, None, true)
+            // Restore the value at the height if present, otherwise delete
+            // the subspace key
+            match self.read_subspace_val_with_height(
+                &Key::from(key.to_db_key()),
+                height,
+                last_height,
+            )? {
+                Some(previous_value) => batch.lock().unwrap().put_cf(
+                    subspace_cf,
+                    key,
+                    previous_value,
+                ),
+                None => batch.lock().unwrap().delete_cf(subspace_cf, key),
+            }
+            Ok(())
+        };
+        // The keys that exist now...
+        self.iter_prefix(None)
+            .par_bridge()
+            .try_for_each(|(key, _value, _gas)| restore(&key))?;
+        // ... and the keys that have been deleted after the height
+        let mut deleted_keys = BTreeSet::new();
+        for rolled_back in rolled_back_heights() {
+            for (key, _val, _gas) in
+                iter_diffs_prefix(self, rolled_back, None, true)
             {
-                let key = Key::parse(key).unwrap();
-                let diff_new_key = diff_new_key_prefix.join(&key);
-                if self.read_subspace_val(&diff_new_key)?.is_none() {
-                    // If there is no new value, it has been deleted in this
-                    // block and we have to restore it
-                    batch_guard.put_cf(subspace_cf, key.to_string(), val)
+                let parsed_key = Key::parse(&key).map_err(Error::KeyError)?;
+                if self.read_subspace_val(&parsed_key)?.is_none() {
+                    deleted_keys.insert(key);
                 }
             }
         }
+        deleted_keys.iter().try_for_each(restore)?;
 
-        tracing::info!("Deleting keys prepended with the last height");
+        tracing::info!("Deleting keys prepended with the rolled back heights");
         let mut batch = batch.into_inner().unwrap();
-        let prefix = last_block.height.to_string();
-        let mut delete_keys = |cf: &ColumnFamily| {
-            let read_opts = make_iter_read_opts(Some(prefix.clone()));
-            let iter = self.0.iterator_cf_opt(
-                cf,
-                read_opts,
-                IteratorMode::From(prefix.as_bytes(), Direction::Forward),
-            );
-            for (key, _value, _gas) in PersistentPrefixIterator(
-                // Empty prefix string to prevent stripping
-                PrefixIterator::new(iter, String::default()),
-            ) {
-                batch.delete_cf(cf, key);
-            }
-        };
-        // Delete any height-prepended key in subspace diffs
         let diffs_cf = self.get_column_family(DIFFS_CF)?;
-        delete_keys(diffs_cf);
-        // Delete any height-prepended key in the block
-        delete_keys(block_cf);
+        let block_cf = self.get_column_family(BLOCK_CF)?;
+        let value_checksum_cf = self.get_column_family(VALUE_CHECKSUM_CF)?;
+        for rolled_back in rolled_back_heights() {
+            // The checksums of the restored values aren't known
+            for is_old in [true, false] {
+                for (key, _val, _gas) in
+                    iter_diffs_prefix(self, rolled_back, None, is_old)
+                {
+                    batch.delete_cf(value_checksum_cf, key);
+                }
+            }
+            // The end of the range is the prefix with its `/` separator
+            // incremented to `0`
+            let (start, end) = (
+                format!("{}/", rolled_back.raw()),
+                format!("{}0", rolled_back.raw()),
+            );
+            // Delete any height-prepended key in subspace diffs
+            batch.delete_range_cf(diffs_cf, &start, &end);
+            // Delete any height-prepended key in the block
+            batch.delete_range_cf(block_cf, &start, &end);
+            // Delete the block results
+            batch.delete_cf(block_cf, format!("results/{}", rolled_back.raw()));
+        }
 
         // Write the batch and persist changes to disk
         tracing::info!("Flushing restored state to disk");
         self.exec_batch(batch)
     }
+
+    /// Read the latest ledger state at the given height, checking that the
+    /// blocks after the height can be rolled back
+    fn read_rollback_state(
+        &self,
+        last_block: &BlockStateRead,
+        height: BlockHeight,
+        is_merklized: &impl Fn(&Key) -> bool,
+    ) -> Result<Vec<(&'static str, Vec<u8>)>> {
+        let blocks = last_block.height.0 - height.0;
+        if blocks > MAX_ROLLBACK_BLOCKS {
+            return Err(Error::DBError(format!(
+                "Cannot roll back {blocks} blocks, at most the last \
+                 {MAX_ROLLBACK_BLOCKS} blocks can be rolled back"
+            )));
+        }
+        let state_cf = self.get_column_family(STATE_CF)?;
+        let block_cf = self.get_column_family(BLOCK_CF)?;
+        let read = |cf: &ColumnFamily, key: &str| {
+            self.0
+                .get_cf(cf, key)
+                .map_err(|e| Error::DBError(e.into_string()))
+        };
+
+        // The results are pruned or moved to the cold DB together with the
+        // diffs and the Merkle tree stores needed to restore the block
+        if read(block_cf, &format!("results/{}", height.raw()))?.is_none() {
+            return Err(Error::DBError(format!(
+                "The block at height {height} has been pruned or moved to the \
+                 cold DB, it cannot be rolled back to"
+            )));
+        }
+
+        // Only the last diff of the keys that are not merklized is kept
+        for changed_height in height.0 + 2..=last_block.height.0 {
+            for is_old in [true, false] {
+                for (key, _val, _gas) in iter_diffs_prefix(
+                    self,
+                    BlockHeight(changed_height),
+                    None,
+                    is_old,
+                ) {
+                    let key = Key::parse(key).map_err(Error::KeyError)?;
+                    if !is_merklized(&key) {
+                        return Err(Error::DBError(format!(
+                            "The key {key} is not merklized and it has been \
+                             changed at height {changed_height}, so its value \
+                             at height {height} is no longer known"
+                        )));
+                    }
+                }
+            }
+        }
+
+        let mut state = vec![];
+        // Only the conversion state of the previous epoch is kept
+        let epoch =
+            last_block.pred_epochs.get_epoch(height).ok_or_else(|| {
+                Error::DBError(format!("Unknown epoch of the height {height}"))
+            })?;
+        if epoch != last_block.epoch {
+            if last_block.epoch.0 - epoch.0 > 1 {
+                return Err(Error::DBError(format!(
+                    "Cannot roll back from the epoch {} to the epoch {epoch}, \
+                     at most one epoch change can be rolled back",
+                    last_block.epoch
+                )));
+            }
+            let previous_key = "pred/conversion_state".to_string();
+            let previous_value = read(state_cf, &previous_key)?
+                .ok_or(Error::UnknownKey { key: previous_key })?;
+            state.push(("conversion_state", previous_value));
+        }
+
+        for key in VERSIONED_STATE_KEYS {
+            let versioned_key = versioned_state_key(height, key);
+            match read(block_cf, &versioned_key)? {
+                Some(value) => state.push((key, value)),
+                // A block committed before the state was versioned can only be
+                // rolled back from the predecessor values
+                None if blocks == 1 => {
+                    if let Some(value) = read(state_cf, &format!("pred/{key}"))?
+                    {
+                        state.push((key, value));
+                    }
+                }
+                None => {
+                    return Err(Error::UnknownKey { key: versioned_key });
+                }
+            }
+        }
+        Ok(state)
+    }
 }
 
 impl DB for RocksDB {
     type Cache = rocksdb::Cache;
+    type Checkpoint = RocksDBCheckpoint;
     type WriteBatch = RocksDBWriteBatch;
 
     fn open(
@@ -654,6 +964,20 @@ impl DB for RocksDB {
             .map_err(|e| Error::DBError(e.into_string()))
     }
 
+    fn sync(&self) -> Result<()> {
+        // The memtables don't have to be flushed, the writes are recovered
+        // from the synced WAL
+        self.0
+            .flush_wal(true)
+            .map_err(|e| Error::DBError(e.into_string()))
+    }
+
+    fn checkpoint(&self) -> Result<RocksDBCheckpoint> {
+        let mut path = self.0.path().to_path_buf();
+        path.set_extension("checkpoint");
+        self.checkpoint_at(path)
+    }
+
     fn read_last_block(&self) -> Result<Option<BlockStateRead>> {
         // Block height
         let state_cf = self.get_column_family(STATE_CF)?;
@@ -824,6 +1148,13 @@ impl DB for RocksDB {
                     "header" => {
                         // the block header doesn't have to be restored
                     }
+                    "state" => {
+                        // the versioned state is only read by a rollback
+                    }
+                    "changed_keys" => {
+                        // the filter of the changed keys is only read by
+                        // queries
+                    }
                     "hash" => {
                         hash = Some(
                             types::decode(bytes).map_err(Error::CodingError)?,
@@ -911,6 +1242,50 @@ impl DB for RocksDB {
         }
     }
 
+    fn read_block_journal(&self) -> Result<Option<BlockJournal>> {
+        let state_cf = self.get_column_family(STATE_CF)?;
+        match self
+            .0
+            .get_cf(state_cf, BLOCK_JOURNAL_KEY)
+            .map_err(|e| Error::DBError(e.into_string()))?
+        {
+            Some(bytes) => {
+                Ok(Some(types::decode(bytes).map_err(Error::CodingError)?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn write_block_journal(&mut self, journal: &BlockJournal) -> Result<()> {
+        let state_cf = self.get_column_family(STATE_CF)?;
+        self.0
+            .put_cf(state_cf, BLOCK_JOURNAL_KEY, types::encode(journal))
+            .map_err(|e| Error::DBError(e.into_string()))
+    }
+
+    fn batch_delete_block_journal(
+        &self,
+        batch: &mut Self::WriteBatch,
+    ) -> Result<()> {
+        let state_cf = self.get_column_family(STATE_CF)?;
+        batch.0.delete_cf(state_cf, BLOCK_JOURNAL_KEY);
+        Ok(())
+    }
+
+    fn delete_block_journal(&mut self) -> Result<()> {
+        let state_cf = self.get_column_family(STATE_CF)?;
+        self.0
+            .delete_cf(state_cf, BLOCK_JOURNAL_KEY)
+            .map_err(|e| Error::DBError(e.into_string()))
+    }
+
+    fn discard_block(&mut self, _height: BlockHeight) -> Result<()> {
+        // The data of a block is written with a single atomic batch, together
+        // with the deletion of its journal, so none of it has been written if
+        // the commit has been interrupted
+        Ok(())
+    }
+
     fn add_block_to_batch(
         &self,
         state: BlockStateWrite,
@@ -934,6 +1309,7 @@ impl DB for RocksDB {
             tx_queue,
             ethereum_height,
             eth_events_queue,
+            changed_keys,
         }: BlockStateWrite = state;
 
         // Epoch start height and time
@@ -1129,6 +1505,64 @@ impl DB for RocksDB {
                 types::encode(&address_gen),
             );
         }
+        // Changed keys
+        {
+            let key = prefix_key
+                .push(&"changed_keys".to_owned())
+                .map_err(Error::KeyError)?;
+            batch.0.put_cf(
+                block_cf,
+                key.to_string(),
+                types::encode(changed_keys),
+            );
+        }
+        // Versioned ledger state
+        {
+            for (key, value) in [
+                (
+                    "next_epoch_min_start_height",
+                    types::encode(&next_epoch_min_start_height),
+                ),
+                (
+                    "next_epoch_min_start_time",
+                    types::encode(&next_epoch_min_start_time),
+                ),
+                (
+                    "update_epoch_blocks_delay",
+                    types::encode(&update_epoch_blocks_delay),
+                ),
+                ("tx_queue", types::encode(&tx_queue)),
+                ("ethereum_height", types::encode(&ethereum_height)),
+                ("eth_events_queue", types::encode(&eth_events_queue)),
+            ] {
+                batch.0.put_cf(
+                    block_cf,
+                    versioned_state_key(height, key),
+                    value,
+                );
+            }
+        }
+        // The data of the block that is too old to be rolled back
+        if let Some(pruned_height) = height
+            .0
+            .checked_sub(MAX_ROLLBACK_BLOCKS)
+            .filter(|height| *height > 0)
+            .map(BlockHeight)
+        {
+            for key in VERSIONED_STATE_KEYS {
+                batch.0.delete_cf(
+                    block_cf,
+                    versioned_state_key(pruned_height, key),
+                );
+            }
+            let reprot_cf = self.get_column_family(REPLAY_PROTECTION_CF)?;
+            let prefix = replay_protection::block_prefix(pruned_height);
+            batch.0.delete_range_cf(
+                reprot_cf,
+                format!("{prefix}/"),
+                format!("{prefix}0"),
+            );
+        }
 
         // Block height
         batch.0.put_cf(state_cf, "height", types::encode(&height));
@@ -1155,6 +1589,21 @@ impl DB for RocksDB {
         }
     }
 
+    fn read_changed_keys_filter(
+        &self,
+        height: BlockHeight,
+    ) -> Result<Option<ChangedKeysFilter>> {
+        let block_cf = self.get_column_family(BLOCK_CF)?;
+        let key = Key::from(height.to_db_key())
+            .push(&"changed_keys".to_owned())
+            .map_err(Error::KeyError)?;
+        self.0
+            .get_cf(block_cf, key.to_string())
+            .map_err(|e| Error::DBError(e.into_string()))?
+            .map(|bytes| types::decode(bytes).map_err(Error::CodingError))
+            .transpose()
+    }
+
     fn read_merkle_tree_stores(
         &self,
         epoch: Epoch,
@@ -1513,6 +1962,63 @@ impl DB for RocksDB {
         Ok(())
     }
 
+    fn prune_block(
+        &mut self,
+        batch: &mut Self::WriteBatch,
+        height: BlockHeight,
+    ) -> Result<()> {
+        let block_cf = self.get_column_family(BLOCK_CF)?;
+        // Block results
+        batch
+            .0
+            .delete_cf(block_cf, format!("results/{}", height.raw()));
+        // Base Merkle tree
+        let key_prefix = base_tree_key_prefix(height);
+        let root_key = key_prefix.clone().with_segment("root".to_owned());
+        batch.0.delete_cf(block_cf, root_key.to_string());
+        let store_key = key_prefix.with_segment("store".to_owned());
+        batch.0.delete_cf(block_cf, store_key.to_string());
+        // Subspace diffs, all of which are prepended with the height. The
+        // end of the range is the prefix with its `/` separator incremented
+        // to `0`.
+        let diffs_cf = self.get_column_family(DIFFS_CF)?;
+        batch.0.delete_range_cf(
+            diffs_cf,
+            format!("{}/", height.raw()),
+            format!("{}0", height.raw()),
+        );
+        Ok(())
+    }
+
+    fn move_block_to_cold(
+        &mut self,
+        batch: &mut Self::WriteBatch,
+        cold: &mut Self,
+        height: BlockHeight,
+        epoch: Option<Epoch>,
+    ) -> Result<()> {
+        let block_cf = self.get_column_family(BLOCK_CF)?;
+        let cold_block_cf = cold.get_column_family(BLOCK_CF)?;
+        let keys = cold_block_keys(height, epoch);
+        let mut cold_batch = WriteBatch::default();
+        for key in &keys {
+            if let Some(value) = self
+                .0
+                .get_cf(block_cf, key)
+                .map_err(|e| Error::DBError(e.into_string()))?
+            {
+                cold_batch.put_cf(cold_block_cf, key, value);
+            }
+        }
+        cold.0
+            .write(cold_batch)
+            .map_err(|e| Error::DBError(e.into_string()))?;
+        for key in keys {
+            batch.0.delete_cf(block_cf, key);
+        }
+        Ok(())
+    }
+
     fn read_bridge_pool_signed_nonce(
         &self,
         height: BlockHeight,
@@ -1561,6 +2067,151 @@ impl DB for RocksDB {
 
         Ok(())
     }
+
+    fn batch_write_storage_usage(
+        &mut self,
+        batch: &mut Self::WriteBatch,
+        address: &Address,
+        bytes: u64,
+    ) -> Result<()> {
+        let state_cf = self.get_column_family(STATE_CF)?;
+        let key = format!("{STORAGE_USAGE_PREFIX}/{address}");
+        if bytes == 0 {
+            batch.0.delete_cf(state_cf, key);
+        } else {
+            batch.0.put_cf(state_cf, key, types::encode(&bytes));
+        }
+        Ok(())
+    }
+
+    fn read_indexed_balance(
+        &self,
+        token: &Address,
+        owner: &Address,
+    ) -> Result<Option<Amount>> {
+        let state_cf = self.get_column_family(STATE_CF)?;
+        self.0
+            .get_cf(state_cf, format!("{BALANCE_INDEX_PREFIX}/{token}/{owner}"))
+            .map_err(|e| Error::DBError(e.into_string()))?
+            .map(|bytes| types::decode(bytes).map_err(Error::CodingError))
+            .transpose()
+    }
+
+    fn batch_write_indexed_balance(
+        &mut self,
+        batch: &mut Self::WriteBatch,
+        token: &Address,
+        owner: &Address,
+        amount: Amount,
+    ) -> Result<()> {
+        let state_cf = self.get_column_family(STATE_CF)?;
+        let key = format!("{BALANCE_INDEX_PREFIX}/{token}/{owner}");
+        if amount.is_zero() {
+            batch.0.delete_cf(state_cf, key);
+        } else {
+            batch.0.put_cf(state_cf, key, types::encode(&amount));
+        }
+        Ok(())
+    }
+
+    fn batch_write_account_tx(
+        &mut self,
+        batch: &mut Self::WriteBatch,
+        address: &Address,
+        height: BlockHeight,
+        index: TxIndex,
+        hash: &namada::types::hash::Hash,
+    ) -> Result<()> {
+        let state_cf = self.get_column_family(STATE_CF)?;
+        // The height and the index are padded for the txs to be ordered by
+        // the keys
+        let key = format!(
+            "{ACCOUNT_HISTORY_PREFIX}/{address}/{:020}/{:010}",
+            height.0, index.0
+        );
+        batch.0.put_cf(state_cf, key, types::encode(hash));
+        Ok(())
+    }
+
+    fn read_value_checksum(&self, key: &Key) -> Result<Option<ValueChecksum>> {
+        let value_checksum_cf = self.get_column_family(VALUE_CHECKSUM_CF)?;
+        self.0
+            .get_cf(value_checksum_cf, key.to_string())
+            .map_err(|e| Error::DBError(e.into_string()))?
+            .map(|bytes| types::decode(bytes).map_err(Error::CodingError))
+            .transpose()
+    }
+
+    fn batch_write_value_checksum(
+        &self,
+        batch: &mut Self::WriteBatch,
+        key: &Key,
+        checksum: Option<&ValueChecksum>,
+    ) -> Result<()> {
+        let value_checksum_cf = self.get_column_family(VALUE_CHECKSUM_CF)?;
+        match checksum {
+            Some(checksum) => batch.0.put_cf(
+                value_checksum_cf,
+                key.to_string(),
+                types::encode(checksum),
+            ),
+            None => batch.0.delete_cf(value_checksum_cf, key.to_string()),
+        }
+        Ok(())
+    }
+
+    fn repair_subspace_val(&self, key: &Key, value: &[u8]) -> Result<()> {
+        let subspace_cf = self.get_column_family(SUBSPACE_CF)?;
+        self.0
+            .put_cf(subspace_cf, key.to_string(), value)
+            .map_err(|e| Error::DBError(e.into_string()))
+    }
+}
+
+impl Compact for RocksDBCompaction {
+    fn ranges_to_compact(
+        &self,
+        min_deletions_ratio: f64,
+    ) -> std::result::Result<Vec<CompactionRange>, String> {
+        let deletions_ratio = |file: &rocksdb::LiveFile| {
+            file.num_deletions as f64 / file.num_entries as f64
+        };
+        let mut files = self.0.live_files().map_err(|e| e.into_string())?;
+        files.retain(|file| {
+            file.num_entries > 0 && deletions_ratio(file) >= min_deletions_ratio
+        });
+        // The files with the most deleted entries first
+        files.sort_by(|a, b| deletions_ratio(b).total_cmp(&deletions_ratio(a)));
+        Ok(files
+            .into_iter()
+            .map(|file| CompactionRange {
+                column_family: file.column_family_name,
+                start: file.start_key,
+                end: file.end_key,
+                size: file.size as u64,
+            })
+            .collect())
+    }
+
+    fn compact(
+        &self,
+        range: &CompactionRange,
+    ) -> std::result::Result<(), String> {
+        let cf = self.0.cf_handle(&range.column_family).ok_or_else(|| {
+            format!("No {} column family", range.column_family)
+        })?;
+        let mut opts = CompactOptions::default();
+        // Don't hold off the automatic compactions, which would stall the
+        // writes of the blocks
+        opts.set_exclusive_manual_compaction(false);
+        self.0.compact_range_cf_opt(
+            cf,
+            range.start.as_deref(),
+            range.end.as_deref(),
+            &opts,
+        );
+        Ok(())
+    }
 }
 
 impl<'iter> DBIter<'iter> for RocksDB {
@@ -1613,6 +2264,49 @@ impl<'iter> DBIter<'iter> for RocksDB {
         let stripped_prefix = Some(replay_protection::last_prefix());
         iter_prefix(self, replay_protection_cf, stripped_prefix.as_ref(), None)
     }
+
+    fn iter_storage_usage(&'iter self) -> Self::PrefixIter {
+        let state_cf = self
+            .get_column_family(STATE_CF)
+            .expect("{STATE_CF} column family should exist");
+
+        let stripped_prefix =
+            Some(Key::from(STORAGE_USAGE_PREFIX.to_owned().to_db_key()));
+        iter_prefix(self, state_cf, stripped_prefix.as_ref(), None)
+    }
+
+    fn iter_indexed_balances(
+        &'iter self,
+        token: Option<&Address>,
+    ) -> Self::PrefixIter {
+        let state_cf = self
+            .get_column_family(STATE_CF)
+            .expect("{STATE_CF} column family should exist");
+
+        let mut stripped_prefix =
+            Key::from(BALANCE_INDEX_PREFIX.to_owned().to_db_key());
+        if let Some(token) = token {
+            stripped_prefix = stripped_prefix
+                .push(&token.to_string())
+                .expect("Cannot obtain a storage key");
+        }
+        iter_prefix(self, state_cf, Some(&stripped_prefix), None)
+    }
+
+    fn iter_account_history(
+        &'iter self,
+        address: &Address,
+    ) -> Self::PrefixIter {
+        let state_cf = self
+            .get_column_family(STATE_CF)
+            .expect("{STATE_CF} column family should exist");
+
+        let stripped_prefix =
+            Key::from(ACCOUNT_HISTORY_PREFIX.to_owned().to_db_key())
+                .push(&address.to_string())
+                .expect("Cannot obtain a storage key");
+        iter_prefix(self, state_cf, Some(&stripped_prefix), None)
+    }
 }
 
 fn iter_subspace_prefix<'iter>(
@@ -1747,6 +2441,29 @@ fn old_and_new_diff_key(
     Ok((old.to_string(), new.to_string()))
 }
 
+/// The key of the versioned ledger state at the given height in the
+/// [`BLOCK_CF`]
+fn versioned_state_key(height: BlockHeight, key: &str) -> String {
+    format!("{}/state/{key}", height.raw())
+}
+
+/// The keys of the data of a block that is moved to a cold DB
+fn cold_block_keys(height: BlockHeight, epoch: Option<Epoch>) -> Vec<String> {
+    let mut key_prefixes = vec![base_tree_key_prefix(height)];
+    if let Some(epoch) = epoch {
+        key_prefixes.extend(
+            StoreType::iter_subtrees().map(|st| subtree_key_prefix(st, epoch)),
+        );
+    }
+    let mut keys = vec![format!("results/{}", height.raw())];
+    for key_prefix in key_prefixes {
+        let root_key = key_prefix.clone().with_segment("root".to_owned());
+        let store_key = key_prefix.with_segment("store".to_owned());
+        keys.extend([root_key.to_string(), store_key.to_string()]);
+    }
+    keys
+}
+
 fn unknown_key_error(key: &str) -> Result<()> {
     Err(Error::UnknownKey {
         key: key.to_owned(),
@@ -1814,7 +2531,7 @@ mod imp {
 
 #[cfg(test)]
 mod test {
-    use namada::state::{MerkleTree, Sha256Hasher};
+    use namada::state::{conformance, MerkleTree, Sha256Hasher};
     use namada::types::address::{
         gen_established_address, EstablishedAddressGen,
     };
@@ -1824,6 +2541,16 @@ mod test {
 
     use super::*;
 
+    #[test]
+    fn test_rocksdb_conformance() {
+        let dir = tempdir().unwrap();
+        let mut count = 0_u32;
+        conformance::check_all(|| {
+            count += 1;
+            open(dir.path().join(count.to_string()), None).unwrap()
+        });
+    }
+
     /// Test that a block written can be loaded back from DB.
     #[test]
     fn test_load_state() {
@@ -2106,7 +2833,7 @@ mod test {
         assert_eq!(deleted, None);
 
         // Rollback to the first block height
-        db.rollback(height_0).unwrap();
+        db.rollback(height_0, |_| true).unwrap();
 
         // Check that the values are back to the state at the first block
         let added = db.read_subspace_val(&add_key).unwrap();
@@ -2124,6 +2851,117 @@ mod test {
         assert_eq!(conversion_state, types::encode(&conversion_state_0));
     }
 
+    #[test]
+    fn test_rollback_blocks() {
+        let dir = tempdir().unwrap();
+        let mut db = open(dir.path(), None).unwrap();
+
+        // A key that's overwritten in every block
+        let key = Key::parse("key").unwrap();
+        // A key that's only added on the last block
+        let last_key = Key::parse("last").unwrap();
+
+        let height_0 = BlockHeight(100);
+        let mut pred_epochs = Epochs::default();
+        pred_epochs.new_epoch(height_0);
+        let conversion_state = ConversionState::default();
+        for (ix, height) in (height_0.0..height_0.0 + 3).enumerate() {
+            let height = BlockHeight(height);
+            let mut batch = RocksDB::batch();
+            db.batch_write_subspace_val(
+                &mut batch,
+                height,
+                &key,
+                [ix as u8],
+                true,
+            )
+            .unwrap();
+            if ix == 2 {
+                db.batch_write_subspace_val(
+                    &mut batch,
+                    height,
+                    &last_key,
+                    [ix as u8],
+                    true,
+                )
+                .unwrap();
+            }
+            add_block_to_batch(
+                &db,
+                &mut batch,
+                height,
+                Epoch(1),
+                pred_epochs.clone(),
+                &conversion_state,
+            )
+            .unwrap();
+            db.exec_batch(batch.0).unwrap();
+        }
+
+        // The key added on the last block must be merklized to roll back
+        // more than one block
+        assert!(db.check_rollback(2, |k| k != &last_key).is_err());
+        assert_eq!(
+            db.check_rollback(1, |k| k != &last_key).unwrap(),
+            height_0.next_height()
+        );
+        // The block before the first block is not in the DB
+        assert!(db.check_rollback(3, |_| true).is_err());
+        assert!(db.check_rollback(0, |_| true).is_err());
+        assert_eq!(db.check_rollback(2, |_| true).unwrap(), height_0);
+
+        db.rollback(height_0, |_| true).unwrap();
+
+        assert_eq!(db.read_subspace_val(&key).unwrap(), Some(vec![0_u8]));
+        assert_eq!(db.read_subspace_val(&last_key).unwrap(), None);
+        let last_block = db.read_last_block().unwrap().unwrap();
+        assert_eq!(last_block.height, height_0);
+        // The data of the rolled back blocks is removed
+        let block_cf = db.get_column_family(BLOCK_CF).unwrap();
+        for height in [height_0.next_height(), BlockHeight(height_0.0 + 2)] {
+            assert_eq!(db.read_diffs_val(&key, height, false).unwrap(), None);
+            for key in VERSIONED_STATE_KEYS {
+                let versioned_key = versioned_state_key(height, key);
+                assert!(db
+                    .0
+                    .get_cf(block_cf, versioned_key)
+                    .unwrap()
+                    .is_none());
+            }
+        }
+    }
+
+    #[test]
+    fn test_compaction_ranges() {
+        let dir = tempdir().unwrap();
+        let mut db = open(dir.path(), None).unwrap();
+        let height = BlockHeight(1);
+
+        // Write and delete the keys so that the flushed file holds both the
+        // values and the deletions
+        for ix in 0..10_u8 {
+            let key = Key::parse(format!("key{ix}")).unwrap();
+            db.write_subspace_val(height, &key, [ix], false).unwrap();
+            db.flush(true).unwrap();
+            db.delete_subspace_val(height, &key, false).unwrap();
+        }
+        db.flush(true).unwrap();
+
+        let compaction = db.compaction_handle();
+        let ranges = compaction.ranges_to_compact(0.0).unwrap();
+        assert!(ranges
+            .iter()
+            .any(|range| range.column_family == SUBSPACE_CF));
+        // Only the files of the deletions are left with the highest ratio
+        let deletion_ranges = compaction.ranges_to_compact(1.0).unwrap();
+        assert!(!deletion_ranges.is_empty());
+        assert!(deletion_ranges.len() < ranges.len());
+        for range in ranges {
+            compaction.compact(&range).unwrap();
+        }
+        assert!(compaction.ranges_to_compact(1.0).unwrap().is_empty());
+    }
+
     #[test]
     fn test_diffs() {
         let dir = tempdir().unwrap();
@@ -2308,6 +3146,7 @@ mod test {
             tx_queue: &tx_queue,
             ethereum_height: None,
             eth_events_queue: &eth_events_queue,
+            changed_keys: &ChangedKeysFilter::default(),
         };
 
         db.add_block_to_batch(block, batch, true)
@@ -0,0 +1,143 @@
+//! The persistent storage in sled, a lighter alternative to RocksDB enabled
+//! with the `sled` feature.
+//!
+//! The sled tree is used as the ordered key-value store of a [`KvDB`], which
+//! defines the storage layout. Unlike with RocksDB, the writes are applied
+//! directly rather than in atomic batches and the `rollback` and `dump-db`
+//! commands are not supported.
+
+use std::fmt;
+use std::path::Path;
+
+use namada::state::kvdb::{KvDB, KvStore};
+use namada::state::types::KVBytes;
+use namada::state::{DbError as Error, DbResult as Result};
+
+/// DB backed by sled
+pub type SledDB = KvDB<SledStore>;
+
+/// sled handle
+#[derive(Debug)]
+pub struct SledStore(sled::Db);
+
+/// An iterator over the key-vals of the [`SledStore`]
+pub struct SledIter(sled::Iter);
+
+impl KvStore for SledStore {
+    /// The capacity of the page cache in bytes
+    type Cache = u64;
+    type Iter = SledIter;
+
+    fn open(path: &Path, cache: Option<&Self::Cache>) -> Result<Self> {
+        let mut config = sled::Config::new().path(path);
+        if let Some(capacity) = cache {
+            config = config.cache_capacity(*capacity);
+        }
+        config.open().map(SledStore).map_err(db_error)
+    }
+
+    fn flush(&self, wait: bool) -> Result<()> {
+        // Without waiting, rely on the periodic background flush of sled
+        if wait {
+            self.0.flush().map_err(db_error)?;
+        }
+        Ok(())
+    }
+
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        self.0
+            .get(key)
+            .map(|val| val.map(|val| val.to_vec()))
+            .map_err(db_error)
+    }
+
+    fn insert(&self, key: String, value: Vec<u8>) -> Result<Option<Vec<u8>>> {
+        self.0
+            .insert(key, value)
+            .map(|val| val.map(|val| val.to_vec()))
+            .map_err(db_error)
+    }
+
+    fn remove(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        self.0
+            .remove(key)
+            .map(|val| val.map(|val| val.to_vec()))
+            .map_err(db_error)
+    }
+
+    fn iter_prefix(&self, prefix: &str) -> Self::Iter {
+        SledIter(self.0.scan_prefix(prefix))
+    }
+
+    /// sled has no point-in-time reads, so the key-vals are copied into a
+    /// temporary store, which is removed when dropped
+    fn checkpoint(&self) -> Result<Self> {
+        let checkpoint = sled::Config::new()
+            .temporary(true)
+            .open()
+            .map_err(db_error)?;
+        for kv in self.0.iter() {
+            let (key, val) = kv.map_err(db_error)?;
+            checkpoint.insert(key, val).map_err(db_error)?;
+        }
+        Ok(SledStore(checkpoint))
+    }
+}
+
+impl Drop for SledStore {
+    fn drop(&mut self) {
+        self.flush(true).expect("flush failed");
+    }
+}
+
+impl Iterator for SledIter {
+    type Item = Result<KVBytes>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|result| {
+            result
+                .map(|(key, val)| (Box::from(&key[..]), Box::from(&val[..])))
+                .map_err(db_error)
+        })
+    }
+}
+
+impl fmt::Debug for SledIter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SledIter")
+    }
+}
+
+fn db_error(err: sled::Error) -> Error {
+    Error::DBError(err.to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use namada::state::conformance;
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[test]
+    fn test_sled_conformance() {
+        let dir = tempdir().unwrap();
+        let mut count = 0_u32;
+        conformance::check_all(|| {
+            count += 1;
+            SledDB::new(
+                SledStore::open(&dir.path().join(count.to_string()), None)
+                    .unwrap(),
+            )
+        });
+    }
+
+    #[test]
+    fn test_sled_block_journal_crash() {
+        let dir = tempdir().unwrap();
+        let store = SledStore::open(dir.path(), None).unwrap();
+        conformance::check_block_journal_crash(&mut KvDB::new(
+            conformance::CrashingStore::new(store),
+        ));
+    }
+}
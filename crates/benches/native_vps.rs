@@ -10,6 +10,7 @@ use namada::eth_bridge::storage::whitelist;
 use namada::governance::pgf::storage::steward::StewardDetail;
 use namada::governance::storage::proposal::ProposalType;
 use namada::governance::storage::vote::ProposalVote;
+use namada::governance::utils::VotingMode;
 use namada::governance::{InitProposalData, VoteProposalData};
 use namada::ibc::core::channel::types::channel::Order;
 use namada::ibc::core::channel::types::msgs::MsgChannelOpenInit;
@@ -46,6 +47,7 @@ use namada::token::{Amount, Transfer};
 use namada::tx::{Code, Section, Tx};
 use namada::types::address::InternalAddress;
 use namada::types::eth_bridge_pool::{GasFee, PendingTransfer};
+use namada::types::hash::Hash;
 use namada::types::masp::{TransferSource, TransferTarget};
 use namada_apps::bench_utils::{
     generate_foreign_key_tx, BenchShell, BenchShieldedCtx,
@@ -121,11 +123,15 @@ fn governance(c: &mut Criterion) {
                     InitProposalData {
                         id: 0,
                         content: content_section.get_hash(),
+                        content_hash: Hash::sha256(b""),
+                        content_uri: None,
                         author: defaults::albert_address(),
-                        r#type: ProposalType::Default(None),
+                        r#type: ProposalType::Text,
                         voting_start_epoch,
                         voting_end_epoch: voting_start_epoch + 3_u64,
                         grace_epoch: voting_start_epoch + 9_u64,
+                        expedited: false,
+                        voting_mode: VotingMode::Linear,
                     },
                     None,
                     Some(vec![content_section]),
@@ -149,10 +155,9 @@ fn governance(c: &mut Criterion) {
                     .expect(
                         "Missing max_proposal_content parameter in storage",
                     );
-                let content_section = Section::ExtraData(Code::new(
-                    vec![0; max_proposal_content_size as _],
-                    None,
-                ));
+                let content = vec![0; max_proposal_content_size as _];
+                let content_section =
+                    Section::ExtraData(Code::new(content.clone(), None));
                 let wasm_code_section = Section::ExtraData(Code::new(
                     vec![0; max_code_size as _],
                     None,
@@ -173,13 +178,17 @@ fn governance(c: &mut Criterion) {
                     InitProposalData {
                         id: 1,
                         content: content_section.get_hash(),
+                        content_hash: Hash::sha256(&content),
+                        content_uri: None,
                         author: defaults::albert_address(),
-                        r#type: ProposalType::Default(Some(
+                        r#type: ProposalType::WasmCodeUpgrade(
                             wasm_code_section.get_hash(),
-                        )),
+                        ),
                         voting_start_epoch,
                         voting_end_epoch: voting_start_epoch + 3_u64,
                         grace_epoch: voting_start_epoch + 9_u64,
+                        expedited: false,
+                        voting_mode: VotingMode::Linear,
                     },
                     None,
                     Some(vec![content_section, wasm_code_section]),
@@ -246,7 +255,7 @@ fn governance(c: &mut Criterion) {
 //              id: 0,
 //              content: content_section.get_hash(),
 //              author: defaults::albert_address(),
-//              r#type: ProposalType::Default(None),
+//              r#type: ProposalType::Text,
 //              voting_start_epoch: 12.into(),
 //              voting_end_epoch: 15.into(),
 //              grace_epoch: 18.into(),
@@ -9,6 +9,7 @@ use namada::core::types::key::{
 use namada::governance::pgf::storage::steward::StewardDetail;
 use namada::governance::storage::proposal::ProposalType;
 use namada::governance::storage::vote::ProposalVote;
+use namada::governance::utils::VotingMode;
 use namada::governance::{InitProposalData, VoteProposalData};
 use namada::ibc::core::channel::types::channel::Order;
 use namada::ibc::core::channel::types::msgs::MsgChannelOpenInit;
@@ -383,6 +384,8 @@ fn update_account(c: &mut Criterion) {
         )),
         public_keys: vec![defaults::albert_keypair().ref_to()],
         threshold: None,
+        vp_update_timelock: None,
+        cancel_pending_vp_update: false,
     };
     let vp = shell.generate_tx(
         TX_UPDATE_ACCOUNT_WASM,
@@ -424,6 +427,7 @@ fn init_account(c: &mut Criterion) {
     );
     let data = InitAccount {
         public_keys: vec![new_account.to_public()],
+        weights: vec![],
         vp_code_hash: extra_hash,
         threshold: 1,
     };
@@ -462,11 +466,15 @@ fn init_proposal(c: &mut Criterion) {
                                 InitProposalData {
                                     id: 0,
                                     content: content_section.get_hash(),
+                                    content_hash: Hash::sha256(b""),
+                                    content_uri: None,
                                     author: defaults::albert_address(),
-                                    r#type: ProposalType::Default(None),
+                                    r#type: ProposalType::Text,
                                     voting_start_epoch: 12.into(),
                                     voting_end_epoch: 15.into(),
                                     grace_epoch: 18.into(),
+                                    expedited: false,
+                                    voting_mode: VotingMode::Linear,
                                 },
                                 None,
                                 Some(vec![content_section]),
@@ -494,12 +502,11 @@ fn init_proposal(c: &mut Criterion) {
                                     "Missing max_proposal_content parameter \
                                      in storage",
                                 );
-                            let content_section =
-                                Section::ExtraData(Code::new(vec![
-                                    0;
-                                    max_proposal_content_size
-                                        as _
-                                ], None));
+                            let content =
+                                vec![0; max_proposal_content_size as _];
+                            let content_section = Section::ExtraData(
+                                Code::new(content.clone(), None),
+                            );
                             let wasm_code_section =
                                 Section::ExtraData(Code::new(vec![
                                     0;
@@ -512,13 +519,17 @@ fn init_proposal(c: &mut Criterion) {
                                 InitProposalData {
                                     id: 1,
                                     content: content_section.get_hash(),
+                                    content_hash: Hash::sha256(&content),
+                                    content_uri: None,
                                     author: defaults::albert_address(),
-                                    r#type: ProposalType::Default(Some(
+                                    r#type: ProposalType::WasmCodeUpgrade(
                                         wasm_code_section.get_hash(),
-                                    )),
+                                    ),
                                     voting_start_epoch: 12.into(),
                                     voting_end_epoch: 15.into(),
                                     grace_epoch: 18.into(),
+                                    expedited: false,
+                                    voting_mode: VotingMode::Linear,
                                 },
                                 None,
                                 Some(vec![content_section, wasm_code_section]),
@@ -720,6 +731,7 @@ fn change_validator_metadata(c: &mut Criterion) {
         website: None,
         discord_handle: None,
         avatar: None,
+        name: None,
         commission_rate: None,
     };
 
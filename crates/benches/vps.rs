@@ -84,6 +84,8 @@ fn vp_user(c: &mut Criterion) {
         )),
         public_keys: vec![defaults::albert_keypair().to_public()],
         threshold: None,
+        vp_update_timelock: None,
+        cancel_pending_vp_update: false,
     };
     let vp = shell.generate_tx(
         TX_UPDATE_ACCOUNT_WASM,
@@ -160,6 +162,7 @@ fn vp_user(c: &mut Criterion) {
                         &keys_changed,
                         &verifiers,
                         shell.vp_wasm_cache.clone(),
+                        shell.tx_wasm_cache.clone(),
                     )
                     .unwrap(),
                     "VP \"{bench_name}\" bench call failed"
@@ -306,6 +309,7 @@ fn vp_implicit(c: &mut Criterion) {
                         &keys_changed,
                         &verifiers,
                         shell.vp_wasm_cache.clone(),
+                        shell.tx_wasm_cache.clone(),
                     )
                     .unwrap()
                 )
@@ -370,6 +374,8 @@ fn vp_validator(c: &mut Criterion) {
         )),
         public_keys: vec![defaults::validator_account_keypair().to_public()],
         threshold: None,
+        vp_update_timelock: None,
+        cancel_pending_vp_update: false,
     };
     let vp = shell.generate_tx(
         TX_UPDATE_ACCOUNT_WASM,
@@ -458,6 +464,7 @@ fn vp_validator(c: &mut Criterion) {
                         &keys_changed,
                         &verifiers,
                         shell.vp_wasm_cache.clone(),
+                        shell.tx_wasm_cache.clone(),
                     )
                     .unwrap()
                 );
@@ -1,7 +1,7 @@
 //! Replay protection storage
 
 use crate::types::hash::Hash;
-use crate::types::storage::Key;
+use crate::types::storage::{BlockHeight, Key};
 
 const ERROR_MSG: &str = "Cannot obtain a valid db key";
 
@@ -24,3 +24,20 @@ pub fn last_prefix() -> Key {
 pub fn last_key(hash: &Hash) -> Key {
     last_prefix().push(&hash.to_string()).expect(ERROR_MSG)
 }
+
+/// Get the prefix of the transaction hashes included in the block at the
+/// given height, which are kept to be able to roll the block back
+pub fn block_prefix(height: BlockHeight) -> Key {
+    Key::parse("block")
+        .expect(ERROR_MSG)
+        .push(&height)
+        .expect(ERROR_MSG)
+}
+
+/// Get the key of a transaction hash included in the block at the given
+/// height
+pub fn block_key(height: BlockHeight, hash: &Hash) -> Key {
+    block_prefix(height)
+        .push(&hash.to_string())
+        .expect(ERROR_MSG)
+}
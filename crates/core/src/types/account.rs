@@ -23,8 +23,15 @@ pub struct AccountPublicKeysMap {
     pub pk_to_idx: HashMap<common::PublicKey, u8>,
     /// Hashmap from index key to public key
     pub idx_to_pk: HashMap<u8, common::PublicKey>,
+    /// Hashmap from index key to the weight of the public key. A key without
+    /// a weight has a weight of [`DEFAULT_KEY_WEIGHT`].
+    #[serde(default)]
+    pub idx_to_weight: HashMap<u8, u8>,
 }
 
+/// The weight of a public key of an account that has no explicit weight
+pub const DEFAULT_KEY_WEIGHT: u8 = 1;
+
 impl FromIterator<common::PublicKey> for AccountPublicKeysMap {
     fn from_iter<T: IntoIterator<Item = common::PublicKey>>(iter: T) -> Self {
         let mut pk_to_idx = HashMap::new();
@@ -43,6 +50,7 @@ impl FromIterator<common::PublicKey> for AccountPublicKeysMap {
         Self {
             pk_to_idx,
             idx_to_pk,
+            idx_to_weight: HashMap::new(),
         }
     }
 }
@@ -64,6 +72,39 @@ impl AccountPublicKeysMap {
         self.pk_to_idx.get(public_key).cloned()
     }
 
+    /// Set the weights of the public keys, given in the same order as the
+    /// keys
+    pub fn with_weights(
+        mut self,
+        weights: impl IntoIterator<Item = u8>,
+    ) -> Self {
+        self.idx_to_weight = weights
+            .into_iter()
+            .enumerate()
+            .map(|(index, weight)| (index as u8, weight))
+            .collect();
+        self
+    }
+
+    /// Retrieve the weight of the public key at the index
+    pub fn get_weight(&self, index: u8) -> u8 {
+        self.idx_to_weight
+            .get(&index)
+            .copied()
+            .unwrap_or(DEFAULT_KEY_WEIGHT)
+    }
+
+    /// Sum the weights of the public keys at the given indices
+    pub fn total_weight<'a>(
+        &self,
+        indices: impl IntoIterator<Item = &'a u8>,
+    ) -> u64 {
+        indices
+            .into_iter()
+            .map(|index| u64::from(self.get_weight(*index)))
+            .sum()
+    }
+
     /// Index the given set of secret keys
     pub fn index_secret_keys(
         &self,
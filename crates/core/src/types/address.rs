@@ -584,6 +584,19 @@ impl InternalAddress {
             _ => None,
         }
     }
+
+    /// The reserved alias of the internal address, if any
+    pub fn to_alias(&self) -> Option<&'static str> {
+        match self {
+            InternalAddress::PoS => Some("pos"),
+            InternalAddress::Ibc => Some("ibc"),
+            InternalAddress::EthBridge => Some("ethbridge"),
+            InternalAddress::EthBridgePool => Some("bridgepool"),
+            InternalAddress::Governance => Some("governance"),
+            InternalAddress::Masp => Some("masp"),
+            _ => None,
+        }
+    }
 }
 
 /// Temporary helper for testing
@@ -177,8 +177,9 @@ impl From<&H256> for Hash {
     }
 }
 
-/// The storage hasher used for the merkle tree.
-pub trait StorageHasher: Hasher + fmt::Debug + Default {
+/// The storage hasher used for the merkle tree. The hasher must be `Send`, so
+/// that the tree can be updated on a separate thread on a block commit.
+pub trait StorageHasher: Hasher + fmt::Debug + Default + Send {
     /// Hash the value to store
     fn hash(value: impl AsRef<[u8]>) -> H256;
 }
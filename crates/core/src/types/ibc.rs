@@ -19,12 +19,21 @@ use crate::ibc::core::handler::types::events::{
 use crate::ibc::primitives::proto::Protobuf;
 use crate::tendermint::abci::Event as AbciEvent;
 use crate::types::masp::PaymentAddress;
+use crate::types::storage::BlockHeight;
 use crate::types::token::Transfer;
 
 /// The event type defined in ibc-rs for receiving a token
 pub const EVENT_TYPE_PACKET: &str = "fungible_token_packet";
 /// The event type defined in ibc-rs for IBC denom
 pub const EVENT_TYPE_DENOM_TRACE: &str = "denomination_trace";
+/// The event type defined in ibc-rs for sending a packet
+pub const EVENT_TYPE_SEND_PACKET: &str = "send_packet";
+/// The event type defined in ibc-rs for receiving a packet
+pub const EVENT_TYPE_RECV_PACKET: &str = "recv_packet";
+/// The event type defined in ibc-rs for acknowledging a packet
+pub const EVENT_TYPE_ACK_PACKET: &str = "acknowledge_packet";
+/// The event type defined in ibc-rs for timing out a packet
+pub const EVENT_TYPE_TIMEOUT_PACKET: &str = "timeout_packet";
 
 /// IBC token hash derived from a denomination.
 #[derive(
@@ -200,6 +209,387 @@ pub fn is_ibc_denom(denom: impl AsRef<str>) -> Option<(TracePath, String)> {
     ))
 }
 
+/// Display metadata of an IBC token derived from its denomination trace
+#[derive(
+    Debug,
+    Clone,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+)]
+pub struct IbcDenomTrace {
+    /// The trace path, e.g. `transfer/channel-0`
+    pub path: String,
+    /// The base denomination on the source chain
+    pub base_denom: String,
+    /// The suggested symbol to display the token
+    pub symbol: String,
+}
+
+impl IbcDenomTrace {
+    /// Derive the trace from the given IBC denom. Returns `None` if the denom
+    /// doesn't have any trace prefix.
+    pub fn from_ibc_denom(denom: impl AsRef<str>) -> Option<Self> {
+        let (trace_path, base_denom) = is_ibc_denom(denom)?;
+        let symbol = base_denom.to_uppercase();
+        Some(Self {
+            path: trace_path.to_string(),
+            base_denom,
+            symbol,
+        })
+    }
+
+    /// The full IBC denom including the trace path
+    pub fn ibc_denom(&self) -> String {
+        format!("{}/{}", self.path, self.base_denom)
+    }
+}
+
+/// Packet ordering of an IBC channel
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+)]
+pub enum IbcChannelOrdering {
+    /// Packets can be delivered in any order
+    Unordered,
+    /// Packets are delivered exactly in the order which they were sent
+    Ordered,
+}
+
+/// Channel parameters which can be modified by the channel upgrade handshake
+#[derive(
+    Debug,
+    Clone,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+)]
+pub struct ChannelUpgradeFields {
+    /// The packet ordering after the upgrade
+    pub ordering: IbcChannelOrdering,
+    /// The connection hops after the upgrade
+    pub connection_hops: Vec<String>,
+    /// The application version after the upgrade
+    pub version: String,
+}
+
+/// The progress of a channel upgrade
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+)]
+pub enum ChannelUpgradeState {
+    /// The upgrade has been proposed on this end
+    Init,
+    /// Both ends agreed on the upgrade and in-flight packets are being
+    /// flushed
+    Flushing,
+    /// All in-flight packets on this end have been flushed
+    FlushComplete,
+}
+
+/// A proposed upgrade of an existing channel
+#[derive(
+    Debug,
+    Clone,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+)]
+pub struct ChannelUpgrade {
+    /// The proposed channel parameters
+    pub fields: ChannelUpgradeFields,
+    /// The counterparty block height after which the upgrade can't proceed.
+    /// Zero means no height timeout.
+    pub timeout_height: u64,
+    /// The counterparty timestamp in nanoseconds after which the upgrade
+    /// can't proceed. Zero means no timestamp timeout.
+    pub timeout_timestamp: u64,
+    /// The upgrade sequence of the channel
+    pub sequence: u64,
+    /// The next sequence of the packets sent on the channel when the
+    /// in-flight packets started to be flushed. Zero until then.
+    pub next_sequence_send: u64,
+    /// The progress of the upgrade
+    pub state: ChannelUpgradeState,
+}
+
+/// The error receipt written when an upgrade is aborted so that the
+/// counterparty can cancel the upgrade too
+#[derive(
+    Debug,
+    Clone,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+)]
+pub struct ChannelUpgradeErrorReceipt {
+    /// The upgrade sequence which has been aborted
+    pub sequence: u64,
+    /// The reason of the abort
+    pub message: String,
+}
+
+/// The proof of the counterparty's state at the given height
+#[derive(
+    Debug, Clone, BorshSerialize, BorshDeserialize, BorshSchema, PartialEq, Eq,
+)]
+pub struct CounterpartyUpgradeProof {
+    /// Commitment proof bytes
+    pub proof: Vec<u8>,
+    /// Revision number of the proof height
+    pub revision_number: u64,
+    /// Revision height of the proof height
+    pub revision_height: u64,
+}
+
+/// Messages of the channel upgrade handshake
+#[derive(
+    Debug, Clone, BorshSerialize, BorshDeserialize, BorshSchema, PartialEq, Eq,
+)]
+pub enum MsgChannelUpgrade {
+    /// Propose an upgrade. This can be executed only by a governance
+    /// proposal.
+    Init {
+        /// Port ID
+        port_id: String,
+        /// Channel ID
+        channel_id: String,
+        /// The proposed channel parameters
+        fields: ChannelUpgradeFields,
+        /// Timeout height on the counterparty chain
+        timeout_height: u64,
+        /// Timeout timestamp on the counterparty chain in nanoseconds
+        timeout_timestamp: u64,
+    },
+    /// Accept the upgrade proposed on the counterparty
+    Try {
+        /// Port ID
+        port_id: String,
+        /// Channel ID
+        channel_id: String,
+        /// The upgrade proposed on the counterparty
+        counterparty_upgrade: ChannelUpgrade,
+        /// The proof of the counterparty upgrade
+        proof: CounterpartyUpgradeProof,
+    },
+    /// Acknowledge the counterparty accepted the upgrade and start flushing
+    Ack {
+        /// Port ID
+        port_id: String,
+        /// Channel ID
+        channel_id: String,
+        /// The upgrade accepted on the counterparty
+        counterparty_upgrade: ChannelUpgrade,
+        /// The proof of the counterparty upgrade
+        proof: CounterpartyUpgradeProof,
+    },
+    /// Confirm the counterparty has started or completed flushing
+    Confirm {
+        /// Port ID
+        port_id: String,
+        /// Channel ID
+        channel_id: String,
+        /// The upgrade on the counterparty
+        counterparty_upgrade: ChannelUpgrade,
+        /// The proof of the counterparty upgrade
+        proof: CounterpartyUpgradeProof,
+    },
+    /// Open the upgraded channel after the counterparty has opened it
+    Open {
+        /// Port ID
+        port_id: String,
+        /// Channel ID
+        channel_id: String,
+        /// The proof of the upgraded counterparty channel end
+        proof: CounterpartyUpgradeProof,
+    },
+    /// Abort the upgrade. Without the proof of the counterparty error
+    /// receipt, this can be executed only by a governance proposal.
+    Cancel {
+        /// Port ID
+        port_id: String,
+        /// Channel ID
+        channel_id: String,
+        /// The counterparty error receipt and its proof
+        error_receipt:
+            Option<(ChannelUpgradeErrorReceipt, CounterpartyUpgradeProof)>,
+    },
+}
+
+impl MsgChannelUpgrade {
+    /// Port ID and channel ID of the upgrading channel
+    pub fn port_channel(&self) -> (&str, &str) {
+        match self {
+            Self::Init {
+                port_id,
+                channel_id,
+                ..
+            }
+            | Self::Try {
+                port_id,
+                channel_id,
+                ..
+            }
+            | Self::Ack {
+                port_id,
+                channel_id,
+                ..
+            }
+            | Self::Confirm {
+                port_id,
+                channel_id,
+                ..
+            }
+            | Self::Open {
+                port_id,
+                channel_id,
+                ..
+            }
+            | Self::Cancel {
+                port_id,
+                channel_id,
+                ..
+            } => (port_id, channel_id),
+        }
+    }
+
+    /// Check if the message has to be authorized by a governance proposal
+    pub fn requires_governance(&self) -> bool {
+        matches!(
+            self,
+            Self::Init { .. }
+                | Self::Cancel {
+                    error_receipt: None,
+                    ..
+                }
+        )
+    }
+}
+
+/// The direction of an IBC packet from the perspective of this chain
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+)]
+pub enum PacketDirection {
+    /// The packet has been sent from this chain
+    Outgoing,
+    /// The packet has been received on this chain
+    Incoming,
+}
+
+impl std::fmt::Display for PacketDirection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Outgoing => write!(f, "outgoing"),
+            Self::Incoming => write!(f, "incoming"),
+        }
+    }
+}
+
+/// The lifecycle stage of an IBC packet
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+)]
+pub enum PacketStage {
+    /// The packet has been sent and it is waiting for the acknowledgement or
+    /// the timeout
+    Sent,
+    /// The packet has been received and the acknowledgement has been written
+    Received,
+    /// The acknowledgement of the sent packet has been received
+    Acknowledged,
+    /// The sent packet has timed out and the escrowed or burned tokens have
+    /// been refunded
+    TimedOut,
+}
+
+impl PacketStage {
+    /// Check if the stage ends the lifecycle of the packet on this chain
+    pub fn is_terminal(&self) -> bool {
+        !matches!(self, Self::Sent)
+    }
+}
+
+/// The number of blocks for which the status of a packet is kept after the
+/// packet has reached a terminal stage
+pub const PACKET_STATUS_RETENTION_BLOCKS: u64 = 100_000;
+
+/// The latest lifecycle stage of an IBC packet and the block height at which
+/// the packet reached it. The events of the stage can be found at the height.
+///
+/// The status of a packet that has reached a terminal stage is pruned after
+/// [`PACKET_STATUS_RETENTION_BLOCKS`]. Its outcome can then only be found in
+/// the events.
+#[derive(
+    Debug,
+    Clone,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+)]
+pub struct PacketStatus {
+    /// The lifecycle stage
+    pub stage: PacketStage,
+    /// The block height at which the packet reached the stage
+    pub height: BlockHeight,
+}
+
 impl From<IbcShieldedTransfer> for Memo {
     fn from(shielded: IbcShieldedTransfer) -> Self {
         let bytes = shielded.serialize_to_vec();
@@ -244,3 +634,85 @@ pub fn get_shielded_transfer(
         .map(|memo| IbcShieldedTransfer::try_from(Memo::from(memo.clone())))
         .transpose()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ibc_denom_trace() {
+        let denom = "transfer/channel-0/transfer/channel-1/uatom";
+        let trace = IbcDenomTrace::from_ibc_denom(denom).unwrap();
+        assert_eq!(trace.path, "transfer/channel-0/transfer/channel-1");
+        assert_eq!(trace.base_denom, "uatom");
+        assert_eq!(trace.symbol, "UATOM");
+        assert_eq!(trace.ibc_denom(), denom);
+
+        let trace =
+            IbcDenomTrace::from_ibc_denom("transfer/channel-3/gamm/pool/1")
+                .unwrap();
+        assert_eq!(trace.base_denom, "gamm/pool/1");
+        assert_eq!(trace.symbol, "GAMM/POOL/1");
+
+        assert!(IbcDenomTrace::from_ibc_denom("uatom").is_none());
+    }
+
+    #[test]
+    fn test_channel_upgrade_authority() {
+        let fields = ChannelUpgradeFields {
+            ordering: IbcChannelOrdering::Unordered,
+            connection_hops: vec!["connection-0".to_string()],
+            version: "ics20-1".to_string(),
+        };
+        let init = MsgChannelUpgrade::Init {
+            port_id: "transfer".to_string(),
+            channel_id: "channel-0".to_string(),
+            fields: fields.clone(),
+            timeout_height: 100,
+            timeout_timestamp: 0,
+        };
+        assert!(init.requires_governance());
+        assert_eq!(init.port_channel(), ("transfer", "channel-0"));
+        let bytes = init.serialize_to_vec();
+        assert_eq!(MsgChannelUpgrade::try_from_slice(&bytes).unwrap(), init);
+
+        let proof = CounterpartyUpgradeProof {
+            proof: vec![],
+            revision_number: 0,
+            revision_height: 10,
+        };
+        let try_msg = MsgChannelUpgrade::Try {
+            port_id: "transfer".to_string(),
+            channel_id: "channel-1".to_string(),
+            counterparty_upgrade: ChannelUpgrade {
+                fields,
+                timeout_height: 100,
+                timeout_timestamp: 0,
+                sequence: 1,
+                next_sequence_send: 0,
+                state: ChannelUpgradeState::Init,
+            },
+            proof: proof.clone(),
+        };
+        assert!(!try_msg.requires_governance());
+
+        let cancel = MsgChannelUpgrade::Cancel {
+            port_id: "transfer".to_string(),
+            channel_id: "channel-0".to_string(),
+            error_receipt: None,
+        };
+        assert!(cancel.requires_governance());
+        let cancel = MsgChannelUpgrade::Cancel {
+            port_id: "transfer".to_string(),
+            channel_id: "channel-0".to_string(),
+            error_receipt: Some((
+                ChannelUpgradeErrorReceipt {
+                    sequence: 1,
+                    message: "timeout".to_string(),
+                },
+                proof,
+            )),
+        };
+        assert!(!cancel.requires_governance());
+    }
+}
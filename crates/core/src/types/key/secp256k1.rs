@@ -469,6 +469,36 @@ impl Signature {
         let r = self.0.r();
         (r.to_bytes().into(), s.to_bytes().into(), v + Self::V_FIX)
     }
+
+    /// Parse a [`Signature`] from its Ethereum `r || s || v` encoding. The
+    /// value of `v` may either be in the range [0, 1] or [27, 28].
+    pub fn try_from_eth_bytes(
+        sig: &[u8; SIGNATURE_SIZE],
+    ) -> Result<Self, ParseSignatureError> {
+        let mut sig = *sig;
+        if sig[64] >= Self::V_FIX {
+            sig[64] -= Self::V_FIX;
+        }
+        Self::try_from(&sig)
+    }
+
+    /// Recover the public key of the signer of the given 32 bytes message
+    /// hash from this [`Signature`].
+    pub fn recover_public_key(
+        &self,
+        msg_hash: &[u8; 32],
+    ) -> Result<PublicKey, VerifySigError> {
+        k256::ecdsa::VerifyingKey::recover_from_prehash(
+            msg_hash, &self.0, self.1,
+        )
+        .map(|vrf_key| PublicKey(k256::PublicKey::from(&vrf_key)))
+        .map_err(|e| {
+            VerifySigError::SigVerifyError(format!(
+                "Error recovering secp256k1 public key: {}",
+                e
+            ))
+        })
+    }
 }
 
 impl Encode<1> for Signature {
@@ -658,4 +688,30 @@ mod test {
             .expect("Test failed");
         assert_eq!(sig, signature);
     }
+
+    /// Test that the public key of the signer can be recovered from an
+    /// Ethereum encoded signature.
+    #[test]
+    fn test_recover_public_key() {
+        let sk_bytes = HEXLOWER.decode(SECRET_KEY_HEX.as_bytes()).unwrap();
+        let sk = SecretKey::try_from_slice(&sk_bytes[..]).unwrap();
+        let pk: PublicKey = sk.ref_to();
+        let msg_hash = [7u8; 32];
+        let sig_key = k256::ecdsa::SigningKey::from(sk.0.as_ref());
+        let (sig, recovery_id) =
+            sig_key.sign_prehash_recoverable(&msg_hash).unwrap();
+        let signature = Signature(sig, recovery_id);
+
+        let (r, s, v) = signature.clone().into_eth_rsv();
+        let mut eth_bytes = [0u8; SIGNATURE_SIZE];
+        eth_bytes[..32].copy_from_slice(&r);
+        eth_bytes[32..64].copy_from_slice(&s);
+        eth_bytes[64] = v;
+        let eth_sig = Signature::try_from_eth_bytes(&eth_bytes).unwrap();
+        assert_eq!(eth_sig.recover_public_key(&msg_hash).unwrap(), pk);
+        assert_eq!(signature.recover_public_key(&msg_hash).unwrap(), pk);
+
+        let other_hash = [8u8; 32];
+        assert_ne!(signature.recover_public_key(&other_hash).ok(), Some(pk));
+    }
 }
@@ -11,12 +11,12 @@ use sha2::{Digest, Sha256};
 
 use crate::impl_display_and_from_str_via_format;
 use crate::types::address::{Address, DecodeError, HASH_HEX_LEN, MASP};
-use crate::types::storage::Epoch;
+use crate::types::storage::{Epoch, IndexedTx};
 use crate::types::string_encoding::{
     self, MASP_EXT_FULL_VIEWING_KEY_HRP, MASP_EXT_SPENDING_KEY_HRP,
-    MASP_PAYMENT_ADDRESS_HRP,
+    MASP_PAYMENT_ADDRESS_HRP, MASP_PAYMENT_DISCLOSURE_HRP,
 };
-use crate::types::token::{Denomination, MaspDigitPos};
+use crate::types::token::{Amount, Denomination, MaspDigitPos};
 
 /// The plain representation of a MASP aaset
 #[derive(
@@ -540,3 +540,73 @@ impl FromStr for MaspValue {
             })
     }
 }
+
+/// A disclosure of a payment made by a shielded transaction. It reveals the
+/// opening of the commitment of a single output note, which lets anyone check
+/// that the transaction paid the amount of the token to the payment address
+/// without learning any viewing key.
+#[derive(Clone, Debug, BorshSerialize, BorshDeserialize)]
+pub struct PaymentDisclosure {
+    /// The transaction which created the note
+    pub indexed_tx: IndexedTx,
+    /// The index of the note among the shielded outputs of the transaction
+    pub output_index: u32,
+    /// The recipient of the note
+    pub payment_address: PaymentAddress,
+    /// The decoding of the asset type of the note
+    pub asset_data: AssetData,
+    /// The disclosed note
+    pub note: masp_primitives::sapling::Note,
+}
+
+impl PaymentDisclosure {
+    /// The token paid by the note
+    pub fn token(&self) -> &Address {
+        &self.asset_data.token
+    }
+
+    /// The amount of the token paid by the note
+    pub fn amount(&self) -> Amount {
+        Amount::from_masp_denominated(self.note.value, self.asset_data.position)
+    }
+
+    /// Compute the commitment of the note sent to the disclosed payment
+    /// address with the disclosed asset and value. `None` is returned if the
+    /// disclosed data don't make up a valid note.
+    pub fn note_commitment(&self) -> Option<[u8; 32]> {
+        let asset_type = self.asset_data.encode().ok()?;
+        let payment_address: masp_primitives::sapling::PaymentAddress =
+            self.payment_address.into();
+        let note = payment_address.create_note(
+            asset_type,
+            self.note.value,
+            self.note.rseed,
+        )?;
+        Some(note.cmu().to_bytes())
+    }
+
+    /// Check that the disclosure opens the given note commitment
+    pub fn verify(&self, cmu: &[u8; 32]) -> bool {
+        self.note_commitment()
+            .map_or(false, |commitment| commitment == *cmu)
+    }
+}
+
+impl string_encoding::Format for PaymentDisclosure {
+    type EncodedBytes<'a> = Vec<u8>;
+
+    const HRP: &'static str = MASP_PAYMENT_DISCLOSURE_HRP;
+
+    fn to_bytes(&self) -> Vec<u8> {
+        self.serialize_to_vec()
+    }
+
+    fn decode_bytes(
+        bytes: &[u8],
+    ) -> Result<Self, string_encoding::DecodeError> {
+        BorshDeserialize::try_from_slice(bytes)
+            .map_err(DecodeError::InvalidBytes)
+    }
+}
+
+impl_display_and_from_str_via_format!(PaymentDisclosure);
@@ -1,6 +1,9 @@
 //! Protocol parameters types
 
 use std::collections::BTreeMap;
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
 
 use super::address::Address;
 use super::chain::ProposalBytes;
@@ -76,3 +79,144 @@ pub struct EpochDuration {
     /// Minimum duration of an epoch
     pub min_duration: DurationSecs,
 }
+
+/// The routing of the epoch inflation computed by the PoS rewards controller
+/// among the PoS rewards, the PGF treasury and the MASP incentives. The shares
+/// must be non-negative and sum up to one.
+#[derive(
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    Hash,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Serialize,
+    Deserialize,
+)]
+pub struct InflationSplit {
+    /// Share of the inflation distributed as PoS rewards
+    pub pos_rewards: Dec,
+    /// Share of the inflation minted into the PGF treasury
+    pub pgf_treasury: Dec,
+    /// Share of the inflation that the MASP rewards can mint at most
+    pub masp_incentives: Dec,
+}
+
+impl InflationSplit {
+    /// Check that the shares are non-negative and sum up to one
+    pub fn is_valid(&self) -> bool {
+        let shares =
+            [self.pos_rewards, self.pgf_treasury, self.masp_incentives];
+        shares
+            .iter()
+            .all(|share| !share.is_negative() && *share <= Dec::one())
+            && shares
+                .into_iter()
+                .fold(Dec::zero(), |acc, share| acc + share)
+                == Dec::one()
+    }
+}
+
+impl Display for InflationSplit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "PoS rewards: {}, PGF treasury: {}, MASP incentives: {}",
+            self.pos_rewards, self.pgf_treasury, self.masp_incentives
+        )
+    }
+}
+
+/// Hard cap on the number of linear memory pages of a wasm instance. The wasm
+/// limits parameter cannot exceed it. A page is `64KiB`.
+pub const MAX_WASM_MEMORY_PAGES: u32 = 200; // 12.8 MiB
+
+/// Limits on the resources that a single wasm instance may request
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    Hash,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Serialize,
+    Deserialize,
+)]
+pub struct WasmInstanceLimits {
+    /// Max number of linear memory pages
+    pub max_memory_pages: u32,
+    /// Max number of elements of a table
+    pub max_table_elements: u32,
+    /// Max number of globals
+    pub max_globals: u32,
+}
+
+impl WasmInstanceLimits {
+    /// Check that the limits are non-zero and that the memory limit doesn't
+    /// exceed [`MAX_WASM_MEMORY_PAGES`]
+    pub fn is_valid(&self) -> bool {
+        (1..=MAX_WASM_MEMORY_PAGES).contains(&self.max_memory_pages)
+            && self.max_table_elements > 0
+            && self.max_globals > 0
+    }
+}
+
+impl Default for WasmInstanceLimits {
+    fn default() -> Self {
+        Self {
+            max_memory_pages: MAX_WASM_MEMORY_PAGES,
+            max_table_elements: 65_536,
+            max_globals: 1_024,
+        }
+    }
+}
+
+impl Display for WasmInstanceLimits {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "memory pages: {}, table elements: {}, globals: {}",
+            self.max_memory_pages, self.max_table_elements, self.max_globals
+        )
+    }
+}
+
+/// Limits on the wasm instances of transactions and validity predicates
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    Hash,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Serialize,
+    Deserialize,
+)]
+pub struct WasmLimits {
+    /// Limits of transaction instances
+    pub tx: WasmInstanceLimits,
+    /// Limits of validity predicate instances
+    pub vp: WasmInstanceLimits,
+}
+
+impl WasmLimits {
+    /// Check that both the tx and vp limits are valid
+    pub fn is_valid(&self) -> bool {
+        self.tx.is_valid() && self.vp.is_valid()
+    }
+}
+
+impl Display for WasmLimits {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "tx ({}), vp ({})", self.tx, self.vp)
+    }
+}
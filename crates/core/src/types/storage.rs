@@ -346,6 +346,27 @@ impl core::fmt::Debug for BlockHash {
     }
 }
 
+impl BlockHash {
+    /// Domain separator of the block entropy pre-image
+    const ENTROPY_DOMAIN: &'static [u8] = b"namada-block-entropy";
+
+    /// Derive the entropy available to the transaction at the given index in
+    /// this block, from the block hash and the tx index.
+    ///
+    /// __WARNING__: The block hash is chosen by the block proposer, who may
+    /// bias the resulting value by e.g. reordering or withholding txs. It must
+    /// not be relied upon where proposers have a stake in the outcome.
+    pub fn entropy(&self, tx_index: &TxIndex) -> Hash {
+        let mut preimage = Vec::with_capacity(
+            Self::ENTROPY_DOMAIN.len() + BLOCK_HASH_LENGTH + TX_INDEX_LENGTH,
+        );
+        preimage.extend_from_slice(Self::ENTROPY_DOMAIN);
+        preimage.extend_from_slice(&self.0);
+        preimage.extend_from_slice(&tx_index.0.to_be_bytes());
+        Hash::sha256(preimage)
+    }
+}
+
 /// The data from Tendermint header
 /// relevant for Namada storage
 #[derive(Clone, Debug, BorshSerialize, BorshDeserialize, Default)]
@@ -656,6 +677,14 @@ impl Key {
         Key { segments }
     }
 
+    /// Check if the given key is a key of the wasm code registry
+    pub fn is_wasm_key(&self) -> bool {
+        matches!(
+            &self.segments[..],
+            [DbKeySeg::StringSeg(prefix), ..] if prefix == WASM_KEY_PREFIX
+        )
+    }
+
     /// Returns a key of the validity predicate of the given address
     /// Only this function can push "?" segment for validity predicate
     pub fn validity_predicate(addr: &Address) -> Self {
@@ -1292,8 +1321,36 @@ pub struct PrefixValue {
     pub value: Vec<u8>,
 }
 
+/// A request for a page of a storage prefix iterator.
+#[derive(
+    Debug, Clone, Default, BorshSerialize, BorshDeserialize, BorshSchema,
+)]
+pub struct PrefixPageRequest {
+    /// Only return the keys after this key, i.e. the `next_cursor` of the
+    /// previous page. The first page is returned when `None`.
+    pub cursor: Option<Key>,
+    /// Only return the keys whose string representation matches this regular
+    /// expression
+    pub key_regex: Option<String>,
+    /// The maximum number of values in the page. The server may return fewer
+    /// values than this if it's above its own limit.
+    pub limit: Option<u32>,
+}
+
+/// A page of a storage prefix iterator.
+#[derive(
+    Debug, Clone, Default, BorshSerialize, BorshDeserialize, BorshSchema,
+)]
+pub struct PrefixPage {
+    /// The values of the page, ordered by their keys
+    pub values: Vec<PrefixValue>,
+    /// The cursor to request the next page with, if there are any more
+    /// values
+    pub next_cursor: Option<Key>,
+}
+
 /// Container of all Ethereum event queues.
-#[derive(Default, Debug, BorshSerialize, BorshDeserialize)]
+#[derive(Clone, Default, Debug, BorshSerialize, BorshDeserialize)]
 pub struct EthEventsQueue {
     /// Queue of transfer to Namada events.
     pub transfers_to_namada: InnerEthEventsQueue<TransfersToNamada>,
@@ -1306,7 +1363,7 @@ pub struct EthEventsQueue {
 /// number of events that have been confirmed with a nonce greater than
 /// or equal to `N`. Events in the queue must be returned in ascending
 /// order of their nonce.
-#[derive(Debug, BorshSerialize, BorshDeserialize)]
+#[derive(Clone, Debug, BorshSerialize, BorshDeserialize)]
 pub struct InnerEthEventsQueue<E> {
     next_nonce_to_process: Uint,
     inner: VecDeque<E>,
@@ -1514,6 +1571,18 @@ pub mod tests {
         }
     }
 
+    /// Test that the block entropy is deterministic and distinct for every tx
+    /// index and block hash.
+    #[test]
+    fn test_block_entropy() {
+        let block_hash = BlockHash([1; BLOCK_HASH_LENGTH]);
+        let entropy = block_hash.entropy(&TxIndex(0));
+        assert_eq!(entropy, block_hash.entropy(&TxIndex(0)));
+        assert_ne!(entropy, block_hash.entropy(&TxIndex(1)));
+        let other_block_hash = BlockHash([2; BLOCK_HASH_LENGTH]);
+        assert_ne!(entropy, other_block_hash.entropy(&TxIndex(0)));
+    }
+
     /// Test that providing an [`EthEventsQueue`] with an event containing
     /// a nonce identical to the next expected nonce in Namada yields the
     /// event itself.
@@ -26,7 +26,7 @@ use crate::types::storage::{DbKeySeg, Epoch, KeySeg};
 use crate::types::uint::{self, Uint, I256};
 
 /// A representation of the conversion state
-#[derive(Debug, Default, BorshSerialize, BorshDeserialize)]
+#[derive(Clone, Debug, Default, BorshSerialize, BorshDeserialize)]
 pub struct ConversionState {
     /// The last amount of the native token distributed
     pub normed_inflation: Option<u128>,
@@ -1029,6 +1029,17 @@ pub struct MaspParams {
     pub locked_amount_target: u64,
 }
 
+impl MaspParams {
+    /// Check that the parameters are well-formed: the max reward rate must be
+    /// within `[0, 1]` and the gains must not be negative
+    pub fn is_valid(&self) -> bool {
+        !self.max_reward_rate.is_negative()
+            && self.max_reward_rate <= Dec::one()
+            && !self.kp_gain_nom.is_negative()
+            && !self.kd_gain_nom.is_negative()
+    }
+}
+
 impl Default for MaspParams {
     fn default() -> Self {
         Self {
@@ -1069,6 +1080,32 @@ pub struct Transfer {
     pub shielded: Option<Hash>,
 }
 
+/// A transfer of the transparent balances of several tokens of an account
+/// into the shielded pool
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Hash,
+    Eq,
+    PartialOrd,
+    Serialize,
+    Deserialize,
+)]
+pub struct ShieldingSweep {
+    /// Source address will spend the tokens
+    pub source: Address,
+    /// The tokens and amounts to be shielded
+    pub tokens: Vec<(Address, DenominatedAmount)>,
+    /// The unused storage location at which to place TxId
+    pub key: Option<String>,
+    /// Shielded transaction part
+    pub shielded: Hash,
+}
+
 #[allow(missing_docs)]
 #[derive(Error, Debug)]
 pub enum AmountError {
@@ -1405,4 +1442,22 @@ mod tests {
             Ordering::Less
         );
     }
+
+    #[test]
+    fn test_masp_params_validity() {
+        let params = MaspParams::default();
+        assert!(params.is_valid());
+
+        let params = MaspParams {
+            max_reward_rate: Dec::from_str("1.1").unwrap(),
+            ..MaspParams::default()
+        };
+        assert!(!params.is_valid());
+
+        let params = MaspParams {
+            kp_gain_nom: Dec::from_str("-0.25").unwrap(),
+            ..MaspParams::default()
+        };
+        assert!(!params.is_valid());
+    }
 }
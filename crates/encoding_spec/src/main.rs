@@ -25,6 +25,7 @@ use itertools::Itertools;
 use lazy_static::lazy_static;
 use madato::types::TableRow;
 use namada::account;
+use namada::governance::storage::proposal::InitProposalData;
 use namada::ledger::parameters::Parameters;
 use namada::tx::data::{pos, TxType, WrapperTx};
 use namada::types::address::Address;
@@ -91,6 +92,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     // let tx_result_schema = schema_container_of::<transaction::TxResult>();
     let tx_type_schema = schema_container_of::<TxType>();
     let prefix_value_schema = schema_container_of::<storage::PrefixValue>();
+    let init_proposal_schema = schema_container_of::<InitProposalData>();
 
     // PoS
     // TODO add after <https://github.com/anoma/namada/issues/439>
@@ -117,6 +119,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     // definitions.extend(btree(&tx_result_schema));
     definitions.extend(btree(&tx_type_schema));
     definitions.extend(btree(&prefix_value_schema));
+    definitions.extend(btree(&init_proposal_schema));
     // definitions.extend(btree(&pos_bonds_schema));
     let mut tables: Vec<Table> = Vec::with_capacity(definitions.len());
 
@@ -240,6 +243,15 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         definition_to_table(prefix_value_schema.declaration(), prefix_value_definition).with_rust_doc_link("https://dev.namada.net/master/rustdoc/namada/types/transaction/prefix_values/enum.TxType.html");
     tables.push(prefix_value_table);
 
+    let init_proposal_definition = definitions
+        .remove(init_proposal_schema.declaration())
+        .unwrap();
+    let init_proposal_table = definition_to_table(
+        init_proposal_schema.declaration(),
+        init_proposal_definition,
+    );
+    tables.push(init_proposal_table);
+
     // Add PoS definitions
     // let pos_bonds_definition =
     //     definitions.remove(&pos_bonds_schema.declaration).unwrap();
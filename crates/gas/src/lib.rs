@@ -21,7 +21,6 @@ pub enum Error {
 
 const COMPILE_GAS_PER_BYTE: u64 = 24;
 const PARALLEL_GAS_DIVIDER: u64 = 10;
-const WASM_CODE_VALIDATION_GAS_PER_BYTE: u64 = 1;
 const WRAPPER_TX_VALIDATION_GAS: u64 = 58_371;
 const STORAGE_OCCUPATION_GAS_PER_BYTE: u64 =
     100 + PHYSICAL_STORAGE_LATENCY_PER_BYTE;
@@ -43,8 +42,20 @@ pub const STORAGE_ACCESS_GAS_PER_BYTE: u64 =
 /// The cost of writing data to storage, per byte
 pub const STORAGE_WRITE_GAS_PER_BYTE: u64 =
     MEMORY_ACCESS_GAS_PER_BYTE + 848 + STORAGE_OCCUPATION_GAS_PER_BYTE;
+/// The cost of seeking a storage prefix iterator from a wasm, on top of the
+/// cost of reading the prefix
+pub const STORAGE_ITER_SEEK_GAS: u64 = 100 * STORAGE_ACCESS_GAS_PER_BYTE;
+/// The cost of yielding an item from a storage prefix iterator to a wasm, on
+/// top of the cost of reading the item
+pub const STORAGE_ITER_NEXT_GAS: u64 = 10 * STORAGE_ACCESS_GAS_PER_BYTE;
+/// The cost of validating a wasm code, per byte
+pub const WASM_CODE_VALIDATION_GAS_PER_BYTE: u64 = 1;
 /// The cost of verifying a single signature of a transaction
 pub const VERIFY_TX_SIG_GAS: u64 = 9_793;
+/// The cost of verifying a signature over an arbitrary payload
+pub const VERIFY_SIGNATURE_GAS: u64 = 9_793;
+/// The cost of recovering a secp256k1 public key from a signature
+pub const RECOVER_PUBLIC_KEY_GAS: u64 = 9_793;
 /// The cost for requesting one more page in wasm (64KiB)
 pub const WASM_MEMORY_PAGE_GAS: u32 =
     MEMORY_ACCESS_GAS_PER_BYTE as u32 * 64 * 1_024;
@@ -325,6 +336,19 @@ impl VpGasMeter {
             current_gas: Gas::default(),
         }
     }
+
+    /// Get the gas consumed by the VP so far
+    pub fn get_vp_consumed_gas(&self) -> Gas {
+        self.current_gas
+    }
+
+    /// Get the amount of gas still available to the VP
+    pub fn get_available_gas(&self) -> Gas {
+        self.tx_gas_limit
+            .checked_sub(self.initial_gas)
+            .and_then(|gas| gas.checked_sub(self.current_gas))
+            .unwrap_or_default()
+    }
 }
 
 impl VpsGas {
@@ -250,6 +250,11 @@ impl OfflineVote {
         self.vote.is_abstain()
     }
 
+    /// Check if the vote is a veto
+    pub fn is_veto(&self) -> bool {
+        self.vote.is_veto()
+    }
+
     /// compute the hash of a proposal
     pub fn compute_hash(&self) -> Hash {
         let proposal_hash_data = self.proposal_hash.serialize_to_vec();
@@ -305,7 +310,7 @@ impl OfflineVote {
 }
 
 /// Compute the signatures index
-fn compute_signatures_index(
+pub(crate) fn compute_signatures_index(
     keys: &[common::SecretKey],
     account_public_keys_map: &AccountPublicKeysMap,
     hashed_data: &Hash,
@@ -322,7 +327,7 @@ fn compute_signatures_index(
 }
 
 /// Compute the total amount of signatures
-fn compute_total_valid_signatures(
+pub(crate) fn compute_total_valid_signatures(
     signatures: &BTreeSet<SignatureIndex>,
     account_public_keys_map: &AccountPublicKeysMap,
     hashed_data: &Hash,
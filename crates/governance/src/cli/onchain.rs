@@ -7,13 +7,20 @@ use namada_core::types::token;
 use serde::{Deserialize, Serialize};
 
 use super::validation::{
-    is_valid_author_balance, is_valid_content, is_valid_default_proposal_data,
-    is_valid_end_epoch, is_valid_grace_epoch, is_valid_pgf_funding_data,
-    is_valid_pgf_stewards_data, is_valid_proposal_period, is_valid_start_epoch,
-    ProposalValidation,
+    is_valid_author_balance, is_valid_content, is_valid_content_uri,
+    is_valid_default_proposal_data, is_valid_emergency_halt_data,
+    is_valid_end_epoch, is_valid_expedited_voting_period, is_valid_grace_epoch,
+    is_valid_masp_rewards_data, is_valid_parameter_change_data,
+    is_valid_pgf_funding_data, is_valid_pgf_stewards_data,
+    is_valid_proposal_period, is_valid_start_epoch, is_valid_voting_mode,
+    is_valid_wasm_allowlist_data, ProposalValidation,
 };
 use crate::parameters::GovernanceParameters;
-use crate::storage::proposal::PGFTarget;
+use crate::storage::proposal::{
+    EmergencyHalt, PGFGrant, PGFStream, PGFTarget, ParameterChange,
+    WasmAllowlistUpdate,
+};
+use crate::utils::VotingMode;
 
 #[derive(
     Debug, Clone, BorshSerialize, BorshDeserialize, Serialize, Deserialize,
@@ -24,6 +31,9 @@ pub struct OnChainProposal {
     pub id: u64,
     /// The proposal content
     pub content: BTreeMap<String, String>,
+    /// The URI where the proposal content is published (e.g. on IPFS)
+    #[serde(default)]
+    pub content_uri: Option<String>,
     /// The proposal author address
     pub author: Address,
     /// The epoch from which voting is allowed
@@ -32,6 +42,13 @@ pub struct OnChainProposal {
     pub voting_end_epoch: Epoch,
     /// The epoch from which this changes are executed
     pub grace_epoch: Epoch,
+    /// Whether the proposal is expedited, i.e. also tallied at the end of a
+    /// shorter voting period with a higher quorum and threshold
+    #[serde(default)]
+    pub expedited: bool,
+    /// The function applied to the stake of the voters to tally the proposal
+    #[serde(default)]
+    pub voting_mode: VotingMode,
 }
 
 /// Pgf default proposal
@@ -75,6 +92,12 @@ impl DefaultProposal {
             self.proposal.voting_end_epoch,
             governance_parameters.min_proposal_grace_epochs,
         )?;
+        is_valid_expedited_voting_period(
+            self.proposal.expedited,
+            self.proposal.voting_start_epoch,
+            self.proposal.voting_end_epoch,
+            governance_parameters.expedited_voting_period,
+        )?;
         is_valid_proposal_period(
             self.proposal.voting_start_epoch,
             self.proposal.grace_epoch,
@@ -88,6 +111,8 @@ impl DefaultProposal {
             &self.proposal.content,
             governance_parameters.max_proposal_content_size,
         )?;
+        is_valid_content_uri(&self.proposal.content_uri)?;
+        is_valid_voting_mode(&self.proposal.voting_mode, self.data.is_none())?;
         is_valid_default_proposal_data(
             &self.data,
             governance_parameters.max_proposal_code_size,
@@ -121,6 +146,10 @@ pub struct StewardsUpdate {
     pub add: Option<Address>,
     /// The stewards to remove
     pub remove: Vec<Address>,
+    /// The optional cap on the retro payments that the proposals of the added
+    /// steward can fund
+    #[serde(default)]
+    pub spending_cap: Option<token::Amount>,
 }
 
 impl PgfStewardProposal {
@@ -153,6 +182,12 @@ impl PgfStewardProposal {
             self.proposal.voting_end_epoch,
             governance_parameters.min_proposal_grace_epochs,
         )?;
+        is_valid_expedited_voting_period(
+            self.proposal.expedited,
+            self.proposal.voting_start_epoch,
+            self.proposal.voting_end_epoch,
+            governance_parameters.expedited_voting_period,
+        )?;
         is_valid_proposal_period(
             self.proposal.voting_start_epoch,
             self.proposal.grace_epoch,
@@ -166,6 +201,8 @@ impl PgfStewardProposal {
             &self.proposal.content,
             governance_parameters.max_proposal_content_size,
         )?;
+        is_valid_content_uri(&self.proposal.content_uri)?;
+        is_valid_voting_mode(&self.proposal.voting_mode, false)?;
         is_valid_pgf_stewards_data(&self.data, &self.proposal.author)?;
 
         Ok(self)
@@ -220,6 +257,12 @@ impl PgfFundingProposal {
             self.proposal.voting_end_epoch,
             governance_parameters.min_proposal_grace_epochs,
         )?;
+        is_valid_expedited_voting_period(
+            self.proposal.expedited,
+            self.proposal.voting_start_epoch,
+            self.proposal.voting_end_epoch,
+            governance_parameters.expedited_voting_period,
+        )?;
         is_valid_proposal_period(
             self.proposal.voting_start_epoch,
             self.proposal.grace_epoch,
@@ -229,7 +272,9 @@ impl PgfFundingProposal {
             &self.proposal.content,
             governance_parameters.max_proposal_content_size,
         )?;
-        is_valid_pgf_funding_data(&self.data)?;
+        is_valid_content_uri(&self.proposal.content_uri)?;
+        is_valid_voting_mode(&self.proposal.voting_mode, false)?;
+        is_valid_pgf_funding_data(&self.data, self.proposal.grace_epoch)?;
 
         Ok(self)
     }
@@ -243,6 +288,390 @@ impl TryFrom<&[u8]> for PgfFundingProposal {
     }
 }
 
+/// Masp rewards proposal
+#[derive(
+    Debug, Clone, BorshSerialize, BorshDeserialize, Serialize, Deserialize,
+)]
+pub struct MaspRewardsProposal {
+    /// The proposal data
+    pub proposal: OnChainProposal,
+    /// The masp rewards proposal extra data
+    pub data: MaspRewardsUpdate,
+}
+
+/// Masp rewards proposal extra data: the new reward parameters of each
+/// shielded token
+pub type MaspRewardsUpdate = BTreeMap<Address, token::MaspParams>;
+
+impl MaspRewardsProposal {
+    /// Validate a masp rewards proposal
+    pub fn validate(
+        self,
+        governance_parameters: &GovernanceParameters,
+        current_epoch: Epoch,
+        balance: token::Amount,
+        force: bool,
+    ) -> Result<Self, ProposalValidation> {
+        if force {
+            return Ok(self);
+        }
+        is_valid_start_epoch(
+            self.proposal.voting_start_epoch,
+            current_epoch,
+            governance_parameters.min_proposal_voting_period,
+        )?;
+        is_valid_end_epoch(
+            self.proposal.voting_start_epoch,
+            self.proposal.voting_end_epoch,
+            current_epoch,
+            governance_parameters.min_proposal_voting_period,
+            governance_parameters.min_proposal_voting_period,
+            governance_parameters.max_proposal_period,
+        )?;
+        is_valid_grace_epoch(
+            self.proposal.grace_epoch,
+            self.proposal.voting_end_epoch,
+            governance_parameters.min_proposal_grace_epochs,
+        )?;
+        is_valid_expedited_voting_period(
+            self.proposal.expedited,
+            self.proposal.voting_start_epoch,
+            self.proposal.voting_end_epoch,
+            governance_parameters.expedited_voting_period,
+        )?;
+        is_valid_proposal_period(
+            self.proposal.voting_start_epoch,
+            self.proposal.grace_epoch,
+            governance_parameters.max_proposal_period,
+        )?;
+        is_valid_author_balance(
+            balance,
+            governance_parameters.min_proposal_fund,
+        )?;
+        is_valid_content(
+            &self.proposal.content,
+            governance_parameters.max_proposal_content_size,
+        )?;
+        is_valid_content_uri(&self.proposal.content_uri)?;
+        is_valid_voting_mode(&self.proposal.voting_mode, false)?;
+        is_valid_masp_rewards_data(&self.data)?;
+
+        Ok(self)
+    }
+}
+
+impl TryFrom<&[u8]> for MaspRewardsProposal {
+    type Error = serde_json::Error;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        serde_json::from_slice(value)
+    }
+}
+
+/// Protocol parameters change proposal
+#[derive(
+    Debug, Clone, BorshSerialize, BorshDeserialize, Serialize, Deserialize,
+)]
+pub struct ParameterChangeProposal {
+    /// The proposal data
+    pub proposal: OnChainProposal,
+    /// The parameters change proposal extra data: the new values of the
+    /// changed parameters
+    pub data: Vec<ParameterChange>,
+}
+
+impl ParameterChangeProposal {
+    /// Validate a parameters change proposal
+    pub fn validate(
+        self,
+        governance_parameters: &GovernanceParameters,
+        current_epoch: Epoch,
+        balance: token::Amount,
+        force: bool,
+    ) -> Result<Self, ProposalValidation> {
+        if force {
+            return Ok(self);
+        }
+        is_valid_start_epoch(
+            self.proposal.voting_start_epoch,
+            current_epoch,
+            governance_parameters.min_proposal_voting_period,
+        )?;
+        is_valid_end_epoch(
+            self.proposal.voting_start_epoch,
+            self.proposal.voting_end_epoch,
+            current_epoch,
+            governance_parameters.min_proposal_voting_period,
+            governance_parameters.min_proposal_voting_period,
+            governance_parameters.max_proposal_period,
+        )?;
+        is_valid_grace_epoch(
+            self.proposal.grace_epoch,
+            self.proposal.voting_end_epoch,
+            governance_parameters.min_proposal_grace_epochs,
+        )?;
+        is_valid_expedited_voting_period(
+            self.proposal.expedited,
+            self.proposal.voting_start_epoch,
+            self.proposal.voting_end_epoch,
+            governance_parameters.expedited_voting_period,
+        )?;
+        is_valid_proposal_period(
+            self.proposal.voting_start_epoch,
+            self.proposal.grace_epoch,
+            governance_parameters.max_proposal_period,
+        )?;
+        is_valid_author_balance(
+            balance,
+            governance_parameters.min_proposal_fund,
+        )?;
+        is_valid_content(
+            &self.proposal.content,
+            governance_parameters.max_proposal_content_size,
+        )?;
+        is_valid_content_uri(&self.proposal.content_uri)?;
+        is_valid_voting_mode(&self.proposal.voting_mode, false)?;
+        is_valid_parameter_change_data(&self.data)?;
+
+        Ok(self)
+    }
+}
+
+impl TryFrom<&[u8]> for ParameterChangeProposal {
+    type Error = serde_json::Error;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        serde_json::from_slice(value)
+    }
+}
+
+/// Emergency halt proposal
+#[derive(
+    Debug, Clone, BorshSerialize, BorshDeserialize, Serialize, Deserialize,
+)]
+pub struct EmergencyHaltProposal {
+    /// The proposal data
+    pub proposal: OnChainProposal,
+    /// The emergency halt proposal extra data: the paused subsystem and the
+    /// duration of the pause
+    pub data: EmergencyHalt,
+}
+
+impl EmergencyHaltProposal {
+    /// Validate an emergency halt proposal
+    pub fn validate(
+        self,
+        governance_parameters: &GovernanceParameters,
+        current_epoch: Epoch,
+        balance: token::Amount,
+        force: bool,
+    ) -> Result<Self, ProposalValidation> {
+        if force {
+            return Ok(self);
+        }
+        is_valid_start_epoch(
+            self.proposal.voting_start_epoch,
+            current_epoch,
+            governance_parameters.min_proposal_voting_period,
+        )?;
+        is_valid_end_epoch(
+            self.proposal.voting_start_epoch,
+            self.proposal.voting_end_epoch,
+            current_epoch,
+            governance_parameters.min_proposal_voting_period,
+            governance_parameters.min_proposal_voting_period,
+            governance_parameters.max_proposal_period,
+        )?;
+        is_valid_grace_epoch(
+            self.proposal.grace_epoch,
+            self.proposal.voting_end_epoch,
+            governance_parameters.min_proposal_grace_epochs,
+        )?;
+        is_valid_expedited_voting_period(
+            self.proposal.expedited,
+            self.proposal.voting_start_epoch,
+            self.proposal.voting_end_epoch,
+            governance_parameters.expedited_voting_period,
+        )?;
+        is_valid_proposal_period(
+            self.proposal.voting_start_epoch,
+            self.proposal.grace_epoch,
+            governance_parameters.max_proposal_period,
+        )?;
+        is_valid_author_balance(
+            balance,
+            governance_parameters.min_proposal_fund,
+        )?;
+        is_valid_content(
+            &self.proposal.content,
+            governance_parameters.max_proposal_content_size,
+        )?;
+        is_valid_content_uri(&self.proposal.content_uri)?;
+        is_valid_voting_mode(&self.proposal.voting_mode, false)?;
+        is_valid_emergency_halt_data(&self.data)?;
+
+        Ok(self)
+    }
+}
+
+impl TryFrom<&[u8]> for EmergencyHaltProposal {
+    type Error = serde_json::Error;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        serde_json::from_slice(value)
+    }
+}
+
+/// Wasm allowlist proposal
+#[derive(
+    Debug, Clone, BorshSerialize, BorshDeserialize, Serialize, Deserialize,
+)]
+pub struct WasmAllowlistProposal {
+    /// The proposal data
+    pub proposal: OnChainProposal,
+    /// The wasm allowlist proposal extra data: the code hashes to add to or
+    /// remove from the allowlists
+    pub data: Vec<WasmAllowlistUpdate>,
+}
+
+impl WasmAllowlistProposal {
+    /// Validate a wasm allowlist proposal
+    pub fn validate(
+        self,
+        governance_parameters: &GovernanceParameters,
+        current_epoch: Epoch,
+        balance: token::Amount,
+        force: bool,
+    ) -> Result<Self, ProposalValidation> {
+        if force {
+            return Ok(self);
+        }
+        is_valid_start_epoch(
+            self.proposal.voting_start_epoch,
+            current_epoch,
+            governance_parameters.min_proposal_voting_period,
+        )?;
+        is_valid_end_epoch(
+            self.proposal.voting_start_epoch,
+            self.proposal.voting_end_epoch,
+            current_epoch,
+            governance_parameters.min_proposal_voting_period,
+            governance_parameters.min_proposal_voting_period,
+            governance_parameters.max_proposal_period,
+        )?;
+        is_valid_grace_epoch(
+            self.proposal.grace_epoch,
+            self.proposal.voting_end_epoch,
+            governance_parameters.min_proposal_grace_epochs,
+        )?;
+        is_valid_expedited_voting_period(
+            self.proposal.expedited,
+            self.proposal.voting_start_epoch,
+            self.proposal.voting_end_epoch,
+            governance_parameters.expedited_voting_period,
+        )?;
+        is_valid_proposal_period(
+            self.proposal.voting_start_epoch,
+            self.proposal.grace_epoch,
+            governance_parameters.max_proposal_period,
+        )?;
+        is_valid_author_balance(
+            balance,
+            governance_parameters.min_proposal_fund,
+        )?;
+        is_valid_content(
+            &self.proposal.content,
+            governance_parameters.max_proposal_content_size,
+        )?;
+        is_valid_content_uri(&self.proposal.content_uri)?;
+        is_valid_voting_mode(&self.proposal.voting_mode, false)?;
+        is_valid_wasm_allowlist_data(&self.data)?;
+
+        Ok(self)
+    }
+}
+
+impl TryFrom<&[u8]> for WasmAllowlistProposal {
+    type Error = serde_json::Error;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        serde_json::from_slice(value)
+    }
+}
+
+/// Signaling proposal
+#[derive(
+    Debug, Clone, BorshSerialize, BorshDeserialize, Serialize, Deserialize,
+)]
+pub struct SignalingProposal {
+    /// The proposal data
+    pub proposal: OnChainProposal,
+}
+
+impl SignalingProposal {
+    /// Validate a signaling proposal
+    pub fn validate(
+        self,
+        governance_parameters: &GovernanceParameters,
+        current_epoch: Epoch,
+        balance: token::Amount,
+        force: bool,
+    ) -> Result<Self, ProposalValidation> {
+        if force {
+            return Ok(self);
+        }
+        is_valid_start_epoch(
+            self.proposal.voting_start_epoch,
+            current_epoch,
+            governance_parameters.min_proposal_voting_period,
+        )?;
+        is_valid_end_epoch(
+            self.proposal.voting_start_epoch,
+            self.proposal.voting_end_epoch,
+            current_epoch,
+            governance_parameters.min_proposal_voting_period,
+            governance_parameters.min_proposal_voting_period,
+            governance_parameters.max_proposal_period,
+        )?;
+        is_valid_grace_epoch(
+            self.proposal.grace_epoch,
+            self.proposal.voting_end_epoch,
+            governance_parameters.min_proposal_grace_epochs,
+        )?;
+        is_valid_expedited_voting_period(
+            self.proposal.expedited,
+            self.proposal.voting_start_epoch,
+            self.proposal.voting_end_epoch,
+            governance_parameters.expedited_voting_period,
+        )?;
+        is_valid_proposal_period(
+            self.proposal.voting_start_epoch,
+            self.proposal.grace_epoch,
+            governance_parameters.max_proposal_period,
+        )?;
+        is_valid_author_balance(
+            balance,
+            governance_parameters.min_signaling_proposal_fund,
+        )?;
+        is_valid_content(
+            &self.proposal.content,
+            governance_parameters.max_proposal_content_size,
+        )?;
+        is_valid_content_uri(&self.proposal.content_uri)?;
+        is_valid_voting_mode(&self.proposal.voting_mode, true)?;
+
+        Ok(self)
+    }
+}
+
+impl TryFrom<&[u8]> for SignalingProposal {
+    type Error = serde_json::Error;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        serde_json::from_slice(value)
+    }
+}
+
 /// Pgf stewards
 #[derive(
     Debug, Clone, BorshSerialize, BorshDeserialize, Serialize, Deserialize,
@@ -281,6 +710,12 @@ pub struct PgfFunding {
     pub continuous: Vec<PGFTarget>,
     /// pgf retro fundings
     pub retro: Vec<PGFTarget>,
+    /// pgf continuous funding streams with an end epoch
+    #[serde(default)]
+    pub streams: Vec<PGFStream>,
+    /// pgf grants released by milestones
+    #[serde(default)]
+    pub grants: Vec<PGFGrant>,
 }
 
 /// Pgf continuous funding
@@ -5,7 +5,12 @@ use namada_core::types::storage::Epoch;
 use namada_core::types::token;
 use thiserror::Error;
 
-use super::onchain::{PgfFunding, StewardsUpdate};
+use super::onchain::{MaspRewardsUpdate, PgfFunding, StewardsUpdate};
+use crate::storage::proposal::{
+    EmergencyHalt, PGFGrant, ParameterChange, WasmAllowlistUpdate,
+    MAX_CONTENT_URI_LENGTH, MAX_EMERGENCY_HALT_EPOCHS,
+};
+use crate::utils::VotingMode;
 
 /// This enum raprresent a proposal data
 #[derive(Clone, Debug, PartialEq, Error)]
@@ -38,6 +43,13 @@ pub enum ProposalValidation {
          epoch must be at most {1}, but found {0}"
     )]
     InvalidProposalPeriod(u64, u64),
+    /// The expedited voting period doesn't end before the proposal voting
+    /// period
+    #[error(
+        "Invalid expedited proposal: the expedited voting period ({0}) must \
+         be shorter than the proposal voting period ({1})"
+    )]
+    InvalidExpeditedVotingPeriod(u64, u64),
     /// The proposal author does not have enough balance to pay for proposal
     /// fees
     #[error(
@@ -51,6 +63,18 @@ pub enum ProposalValidation {
          but maximum is {1}"
     )]
     InvalidContentLength(u64, u64),
+    /// The proposal content URI is empty or too large
+    #[error(
+        "Invalid proposal content URI: the URI must not be empty and its \
+         length is {0} but maximum is {1}"
+    )]
+    InvalidContentUri(usize, usize),
+    /// Invalid proposal voting mode
+    #[error(
+        "Invalid proposal voting mode {0}: only signaling proposals can use a \
+         non-linear voting mode and the cap must be positive"
+    )]
+    InvalidVotingMode(VotingMode),
     /// Invalid offline proposal tally epoch
     #[error(
         "Invalid proposal tally epoch: tally epoch ({0}) must be less than \
@@ -66,9 +90,44 @@ pub enum ProposalValidation {
     /// The pgf stewards data is not valid
     #[error("Invalid proposal extra data: cannot be empty.")]
     InvalidPgfStewardsExtraData,
+    /// The steward spending cap is not valid
+    #[error(
+        "Invalid proposal extra data: a spending cap can only be given to an \
+         added steward and must be positive."
+    )]
+    InvalidStewardSpendingCap,
     /// The pgf funding data is not valid
-    #[error("invalid proposal extra data: cannot be empty.")]
+    #[error(
+        "invalid proposal extra data: cannot be empty, the streams must end \
+         after the grace epoch and the grants must have non-empty milestones."
+    )]
     InvalidPgfFundingExtraData,
+    /// The masp rewards data is not valid
+    #[error(
+        "Invalid proposal extra data: cannot be empty and the max reward rate \
+         must be between 0 and 1 and the gains non-negative."
+    )]
+    InvalidMaspRewardsExtraData,
+    /// The parameters change data is not valid
+    #[error(
+        "Invalid proposal extra data: cannot be empty and the new parameter \
+         values must be valid."
+    )]
+    InvalidParameterChangeExtraData,
+    /// The emergency halt data is not valid
+    #[error(
+        "Invalid proposal extra data: the halt duration must be between 1 and \
+         {0} epochs."
+    )]
+    InvalidEmergencyHaltExtraData(u64),
+    /// The wasm allowlist updates are not valid
+    #[error(
+        "Invalid proposal extra data: there must be at least one update, the \
+         code hashes must be valid lowercase hashes, each hash can be updated \
+         only once per allowlist and a set allowlist can't be empty nor \
+         otherwise updated."
+    )]
+    InvalidWasmAllowlistExtraData,
 }
 
 pub fn is_valid_author_balance(
@@ -147,6 +206,27 @@ pub fn is_valid_grace_epoch(
     }
 }
 
+pub fn is_valid_expedited_voting_period(
+    expedited: bool,
+    proposal_start_epoch: Epoch,
+    proposal_end_epoch: Epoch,
+    expedited_voting_period: u64,
+) -> Result<(), ProposalValidation> {
+    let voting_period = proposal_end_epoch.0 - proposal_start_epoch.0;
+
+    if !expedited
+        || (expedited_voting_period > 0
+            && expedited_voting_period < voting_period)
+    {
+        Ok(())
+    } else {
+        Err(ProposalValidation::InvalidExpeditedVotingPeriod(
+            expedited_voting_period,
+            voting_period,
+        ))
+    }
+}
+
 pub fn is_valid_proposal_period(
     proposal_start_epoch: Epoch,
     proposal_grace_epoch: Epoch,
@@ -187,6 +267,36 @@ pub fn is_valid_content(
     }
 }
 
+pub fn is_valid_content_uri(
+    content_uri: &Option<String>,
+) -> Result<(), ProposalValidation> {
+    match content_uri {
+        Some(uri) if uri.is_empty() || uri.len() > MAX_CONTENT_URI_LENGTH => {
+            Err(ProposalValidation::InvalidContentUri(
+                uri.len(),
+                MAX_CONTENT_URI_LENGTH,
+            ))
+        }
+        _ => Ok(()),
+    }
+}
+
+pub fn is_valid_voting_mode(
+    voting_mode: &VotingMode,
+    is_signaling: bool,
+) -> Result<(), ProposalValidation> {
+    let is_valid = match voting_mode {
+        VotingMode::Linear => true,
+        VotingMode::Quadratic => is_signaling,
+        VotingMode::Capped(cap) => is_signaling && !cap.is_zero(),
+    };
+    if is_valid {
+        Ok(())
+    } else {
+        Err(ProposalValidation::InvalidVotingMode(*voting_mode))
+    }
+}
+
 pub fn is_valid_tally_epoch(
     tally_epoch: Epoch,
     current_epoch: Epoch,
@@ -225,6 +335,11 @@ pub fn is_valid_pgf_stewards_data(
     data: &StewardsUpdate,
     author: &Address,
 ) -> Result<(), ProposalValidation> {
+    if let Some(spending_cap) = data.spending_cap {
+        if data.add.is_none() || spending_cap.is_zero() {
+            return Err(ProposalValidation::InvalidStewardSpendingCap);
+        }
+    }
     if data.add.is_some() || !data.remove.is_empty() {
         if data.add.is_some() {
             let steward_address = data.add.clone().unwrap();
@@ -243,10 +358,64 @@ pub fn is_valid_pgf_stewards_data(
 
 pub fn is_valid_pgf_funding_data(
     data: &PgfFunding,
+    proposal_grace_epoch: Epoch,
 ) -> Result<(), ProposalValidation> {
-    if !data.continuous.is_empty() || !data.retro.is_empty() {
+    let is_empty = data.continuous.is_empty()
+        && data.retro.is_empty()
+        && data.streams.is_empty()
+        && data.grants.is_empty();
+    // A stream must be paid at least once after the proposal execution
+    let are_valid_streams = data
+        .streams
+        .iter()
+        .all(|stream| stream.end_epoch > proposal_grace_epoch);
+    let are_valid_grants = data.grants.iter().all(PGFGrant::is_valid);
+
+    if !is_empty && are_valid_streams && are_valid_grants {
         Ok(())
     } else {
         Err(ProposalValidation::InvalidPgfFundingExtraData)
     }
 }
+
+pub fn is_valid_masp_rewards_data(
+    data: &MaspRewardsUpdate,
+) -> Result<(), ProposalValidation> {
+    if !data.is_empty() && data.values().all(token::MaspParams::is_valid) {
+        Ok(())
+    } else {
+        Err(ProposalValidation::InvalidMaspRewardsExtraData)
+    }
+}
+
+pub fn is_valid_parameter_change_data(
+    data: &[ParameterChange],
+) -> Result<(), ProposalValidation> {
+    if !data.is_empty() && data.iter().all(ParameterChange::is_valid) {
+        Ok(())
+    } else {
+        Err(ProposalValidation::InvalidParameterChangeExtraData)
+    }
+}
+
+pub fn is_valid_emergency_halt_data(
+    data: &EmergencyHalt,
+) -> Result<(), ProposalValidation> {
+    if data.is_valid() {
+        Ok(())
+    } else {
+        Err(ProposalValidation::InvalidEmergencyHaltExtraData(
+            MAX_EMERGENCY_HALT_EPOCHS,
+        ))
+    }
+}
+
+pub fn is_valid_wasm_allowlist_data(
+    data: &[WasmAllowlistUpdate],
+) -> Result<(), ProposalValidation> {
+    if WasmAllowlistUpdate::is_valid_batch(data) {
+        Ok(())
+    } else {
+        Err(ProposalValidation::InvalidWasmAllowlistExtraData)
+    }
+}
@@ -12,9 +12,16 @@ pub mod storage;
 /// Governance utility functions/structs
 pub mod utils;
 
-pub use storage::proposal::{InitProposalData, ProposalType, VoteProposalData};
+pub use storage::proposal::{
+    InitProposalData, ProposalKind, ProposalType, SignedVoteProposalData,
+    VoteDelegationData, VoteProposalBatchData, VoteProposalData,
+    VoteWithdrawalData,
+};
 pub use storage::vote::ProposalVote;
-pub use storage::{init_proposal, is_proposal_accepted, vote_proposal};
+pub use storage::{
+    delegate_vote, init_proposal, is_proposal_accepted, is_subsystem_halted,
+    vote_proposal, vote_proposal_batch, withdraw_vote,
+};
 
 /// The governance internal address
 pub const ADDRESS: Address = address::GOV;
@@ -1,8 +1,106 @@
+use std::fmt::Display;
+
 use namada_core::borsh::{BorshDeserialize, BorshSerialize};
+use namada_core::types::dec::Dec;
 use namada_core::types::token;
 use namada_state::{StorageRead, StorageResult, StorageWrite};
+use serde::{Deserialize, Serialize};
 
 use super::storage::keys as goverance_storage;
+use crate::utils::{ProposalResult, TallyResult};
+
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    BorshSerialize,
+    BorshDeserialize,
+    Serialize,
+    Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+/// What happens to the deposit of a proposal once it has been tallied
+pub enum DepositAction {
+    /// The deposit is refunded to the proposal author
+    Refund,
+    /// The deposit is burned
+    Burn,
+}
+
+impl Display for DepositAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DepositAction::Refund => write!(f, "refund"),
+            DepositAction::Burn => write!(f, "burn"),
+        }
+    }
+}
+
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    BorshSerialize,
+    BorshDeserialize,
+    Serialize,
+    Deserialize,
+)]
+/// The handling of proposal deposits depending on the tally outcome
+pub struct DepositPolicy {
+    /// Action on the deposit of a passed proposal
+    pub passed: DepositAction,
+    /// Action on the deposit of a proposal rejected after reaching quorum
+    pub rejected: DepositAction,
+    /// Action on the deposit of a vetoed proposal
+    pub vetoed: DepositAction,
+    /// Action on the deposit of a proposal rejected without reaching quorum
+    pub expired: DepositAction,
+}
+
+impl Default for DepositPolicy {
+    fn default() -> Self {
+        Self {
+            passed: DepositAction::Refund,
+            rejected: DepositAction::Refund,
+            vetoed: DepositAction::Burn,
+            expired: DepositAction::Burn,
+        }
+    }
+}
+
+impl DepositPolicy {
+    /// Get the action to apply on the deposit of a tallied proposal
+    pub fn action(&self, proposal_result: &ProposalResult) -> DepositAction {
+        match proposal_result.result {
+            TallyResult::Passed => self.passed,
+            TallyResult::Vetoed => self.vetoed,
+            TallyResult::Rejected if proposal_result.is_expired() => {
+                self.expired
+            }
+            TallyResult::Rejected => self.rejected,
+        }
+    }
+}
+
+impl Display for DepositPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "passed: {}, rejected: {}, vetoed: {}, expired: {}",
+            self.passed, self.rejected, self.vetoed, self.expired
+        )
+    }
+}
 
 #[derive(
     Clone,
@@ -19,6 +117,8 @@ use super::storage::keys as goverance_storage;
 pub struct GovernanceParameters {
     /// Minimum amount of locked funds
     pub min_proposal_fund: token::Amount,
+    /// Minimum amount of locked funds of a signaling proposal
+    pub min_signaling_proposal_fund: token::Amount,
     /// Maximum kibibyte length for proposal code
     pub max_proposal_code_size: u64,
     /// Minimum proposal voting period in epochs
@@ -29,17 +129,36 @@ pub struct GovernanceParameters {
     pub max_proposal_content_size: u64,
     /// Minimum epochs between end and grace epochs
     pub min_proposal_grace_epochs: u64,
+    /// Fraction of the voted power above which the veto votes reject a
+    /// proposal and burn its deposit
+    pub veto_threshold: Dec,
+    /// Voting period in epochs of an expedited proposal
+    pub expedited_voting_period: u64,
+    /// Fraction of the total voting power that must have voted on an
+    /// expedited proposal
+    pub expedited_quorum: Dec,
+    /// Fraction of the yay and nay votes that must be yay for an expedited
+    /// proposal to pass
+    pub expedited_threshold: Dec,
+    /// The handling of the deposit of tallied proposals
+    pub deposit_policy: DepositPolicy,
 }
 
 impl Default for GovernanceParameters {
     fn default() -> Self {
         Self {
             min_proposal_fund: token::Amount::native_whole(500),
+            min_signaling_proposal_fund: token::Amount::native_whole(50),
             max_proposal_code_size: 300_000,
             min_proposal_voting_period: 3,
             max_proposal_period: 27,
             max_proposal_content_size: 10_000,
             min_proposal_grace_epochs: 6,
+            veto_threshold: Dec::one() / 3,
+            expedited_voting_period: 1,
+            expedited_quorum: Dec::two() / 3,
+            expedited_threshold: Dec::two() / 3,
+            deposit_policy: DepositPolicy::default(),
         }
     }
 }
@@ -52,17 +171,30 @@ impl GovernanceParameters {
     {
         let Self {
             min_proposal_fund,
+            min_signaling_proposal_fund,
             max_proposal_code_size,
             min_proposal_voting_period,
             max_proposal_period,
             max_proposal_content_size,
             min_proposal_grace_epochs,
+            veto_threshold,
+            expedited_voting_period,
+            expedited_quorum,
+            expedited_threshold,
+            deposit_policy,
         } = self;
 
         let min_proposal_fund_key =
             goverance_storage::get_min_proposal_fund_key();
         storage.write(&min_proposal_fund_key, min_proposal_fund)?;
 
+        let min_signaling_proposal_fund_key =
+            goverance_storage::get_min_signaling_proposal_fund_key();
+        storage.write(
+            &min_signaling_proposal_fund_key,
+            min_signaling_proposal_fund,
+        )?;
+
         let max_proposal_code_size_key =
             goverance_storage::get_max_proposal_code_size_key();
         storage.write(&max_proposal_code_size_key, max_proposal_code_size)?;
@@ -88,6 +220,24 @@ impl GovernanceParameters {
         storage
             .write(&min_proposal_grace_epoch_key, min_proposal_grace_epochs)?;
 
+        let veto_threshold_key = goverance_storage::get_veto_threshold_key();
+        storage.write(&veto_threshold_key, veto_threshold)?;
+
+        let expedited_voting_period_key =
+            goverance_storage::get_expedited_voting_period_key();
+        storage.write(&expedited_voting_period_key, expedited_voting_period)?;
+
+        let expedited_quorum_key =
+            goverance_storage::get_expedited_quorum_key();
+        storage.write(&expedited_quorum_key, expedited_quorum)?;
+
+        let expedited_threshold_key =
+            goverance_storage::get_expedited_threshold_key();
+        storage.write(&expedited_threshold_key, expedited_threshold)?;
+
+        let deposit_policy_key = goverance_storage::get_deposit_policy_key();
+        storage.write(&deposit_policy_key, deposit_policy)?;
+
         let counter_key = goverance_storage::get_counter_key();
         storage.write(&counter_key, u64::MIN)
     }
@@ -4,12 +4,20 @@ use namada_core::types::address::Address;
 use namada_core::types::token;
 use namada_parameters::storage as params_storage;
 use namada_state::{
-    DBIter, StorageHasher, StorageRead, StorageResult, WlStorage, DB,
+    DBIter, StorageError, StorageHasher, StorageRead, StorageResult,
+    StorageWrite, WlStorage, DB,
 };
 use namada_trans_token::credit_tokens;
 use namada_trans_token::storage_key::minted_balance_key;
 
-use crate::pgf::storage::{get_parameters, get_payments, get_stewards};
+use crate::pgf::storage::grant::GrantRelease;
+use crate::pgf::storage::keys::{
+    fundings_handle, get_grants_reserved_key, grants_handle,
+};
+use crate::pgf::storage::{
+    get_grants, get_grants_reserved, get_parameters, get_payments,
+    get_stewards, get_unreserved_balance,
+};
 use crate::storage::proposal::{PGFIbcTarget, PGFTarget};
 
 /// Apply the PGF inflation.
@@ -37,8 +45,22 @@ where
         .read(&minted_balance_key(&staking_token))?
         .expect("Total native token balance should exist in storage");
 
+    // With an inflation split, the treasury receives its share of the
+    // inflation computed by the PoS rewards controller for this epoch instead
+    // of the PGF inflation rate
     let pgf_inflation_amount =
-        (pgf_parameters.pgf_inflation_rate * total_supply) / epochs_per_year;
+        match namada_parameters::read_inflation_split(storage)? {
+            Some(split) => {
+                let pos_inflation_amount: token::Amount = storage
+                    .read(&params_storage::get_pos_inflation_amount_key())?
+                    .expect("PoS inflation amount should exist in storage");
+                split.pgf_treasury * pos_inflation_amount
+            }
+            None => {
+                (pgf_parameters.pgf_inflation_rate * total_supply)
+                    / epochs_per_year
+            }
+        };
 
     credit_tokens(
         storage,
@@ -58,8 +80,27 @@ where
     // we want to pay first the oldest fundings
     pgf_fundings.sort_by(|a, b| a.id.cmp(&b.id));
 
-    for funding in pgf_fundings {
+    let current_epoch = storage.get_block_epoch()?;
+    for mut funding in pgf_fundings {
+        // the streams past their end epoch are removed
+        if !funding.is_active(current_epoch) {
+            fundings_handle().remove(storage, &funding.detail.target())?;
+            tracing::info!(
+                "Pgf stream for {} project ended, {} tokens disbursed in \
+                 total.",
+                &funding.detail.target(),
+                funding.disbursed.to_string_native(),
+            );
+            continue;
+        }
+
+        // the amount reserved for the grants can't be spent on the fundings
         let result = match &funding.detail {
+            _ if funding.detail.amount() > get_unreserved_balance(storage)? => {
+                Err(StorageError::new_const(
+                    "Insufficient unreserved pgf balance",
+                ))
+            }
             PGFTarget::Internal(target) => namada_trans_token::transfer(
                 storage,
                 &staking_token,
@@ -81,6 +122,17 @@ where
                     funding.detail.amount().to_string_native(),
                     &funding.detail.target(),
                 );
+                funding.disbursed = funding
+                    .disbursed
+                    .checked_add(funding.detail.amount())
+                    .ok_or_else(|| {
+                        StorageError::new_const("Pgf disbursed amount overflow")
+                    })?;
+                fundings_handle().insert(
+                    storage,
+                    funding.detail.target(),
+                    funding,
+                )?;
             }
             Err(_) => {
                 tracing::warn!(
@@ -130,3 +182,160 @@ where
 
     Ok(())
 }
+
+/// Release the attested milestones of the pgf grants from the amount of the
+/// PGF account reserved when the grants were approved. The grants with all
+/// their milestones released are removed.
+pub fn release_grants<D, H>(
+    storage: &mut WlStorage<D, H>,
+) -> StorageResult<Vec<GrantRelease>>
+where
+    D: DB + for<'iter> DBIter<'iter> + Sync + 'static,
+    H: StorageHasher + Sync + 'static,
+{
+    let staking_token = storage.get_native_token()?;
+    let mut reserved = get_grants_reserved(storage)?;
+    let mut releases = vec![];
+
+    for (grant_id, mut grant) in get_grants(storage)? {
+        let attested = grant.attestations.len() as u64;
+        let released = grant.released;
+        while grant.released < attested {
+            let milestone = grant.released;
+            let amount = grant.milestones[milestone as usize];
+            reserved = reserved.checked_sub(amount).ok_or_else(|| {
+                StorageError::new_const(
+                    "Pgf grants reserved amount is lower than a milestone",
+                )
+            })?;
+            namada_trans_token::transfer(
+                storage,
+                &staking_token,
+                &super::ADDRESS,
+                &grant.target,
+                amount,
+            )?;
+            tracing::info!(
+                "Releasing milestone {} of pgf grant {}: {} tokens for {}.",
+                milestone,
+                grant_id,
+                amount.to_string_native(),
+                &grant.target,
+            );
+            releases.push(GrantRelease {
+                grant_id,
+                milestone,
+                target: grant.target.clone(),
+                amount,
+            });
+            grant.released += 1;
+        }
+
+        if grant.is_completed() {
+            grants_handle().remove(storage, &grant_id)?;
+        } else if grant.released != released {
+            grants_handle().insert(storage, grant_id, grant)?;
+        }
+    }
+    if !releases.is_empty() {
+        storage.write(&get_grants_reserved_key(), reserved)?;
+    }
+
+    Ok(releases)
+}
+
+#[cfg(test)]
+mod test {
+    use namada_core::types::address::testing::{
+        established_address_1, established_address_2,
+    };
+    use namada_state::testing::TestWlStorage;
+    use namada_trans_token::read_balance;
+
+    use super::*;
+    use crate::pgf::storage::{
+        add_grant, attest_milestone, get_grant, get_grants_reserved,
+        get_unreserved_balance,
+    };
+    use crate::storage::proposal::PGFGrant;
+
+    #[test]
+    fn test_release_grants() {
+        let mut storage = TestWlStorage::default();
+        let native_token = storage.storage.native_token.clone();
+        let steward = established_address_1();
+        let target = established_address_2();
+        credit_tokens(
+            &mut storage,
+            &native_token,
+            &super::super::ADDRESS,
+            token::Amount::native_whole(100),
+        )
+        .unwrap();
+
+        // The total of the grant is reserved when it's added, and grants
+        // exceeding the unreserved balance are refused
+        let grant = PGFGrant {
+            target: target.clone(),
+            milestones: vec![
+                token::Amount::native_whole(10),
+                token::Amount::native_whole(20),
+            ],
+        };
+        let grant_id = add_grant(&mut storage, 0, grant).unwrap().unwrap();
+        assert_eq!(
+            get_grants_reserved(&storage).unwrap(),
+            token::Amount::native_whole(30)
+        );
+        assert_eq!(
+            get_unreserved_balance(&storage).unwrap(),
+            token::Amount::native_whole(70)
+        );
+        let too_large = PGFGrant {
+            target: target.clone(),
+            milestones: vec![token::Amount::native_whole(71)],
+        };
+        assert_eq!(add_grant(&mut storage, 1, too_large).unwrap(), None);
+
+        // Nothing is released before an attestation, and the target can't
+        // attest its own milestones
+        assert!(release_grants(&mut storage).unwrap().is_empty());
+        assert!(attest_milestone(&mut storage, target.clone(), grant_id, 0)
+            .is_err());
+
+        // The attested milestone is released from the reserved amount
+        attest_milestone(&mut storage, steward.clone(), grant_id, 0).unwrap();
+        let releases = release_grants(&mut storage).unwrap();
+        assert_eq!(
+            releases,
+            vec![GrantRelease {
+                grant_id,
+                milestone: 0,
+                target: target.clone(),
+                amount: token::Amount::native_whole(10),
+            }]
+        );
+        assert_eq!(
+            read_balance(&storage, &native_token, &target).unwrap(),
+            token::Amount::native_whole(10)
+        );
+        assert_eq!(
+            get_grants_reserved(&storage).unwrap(),
+            token::Amount::native_whole(20)
+        );
+        assert!(release_grants(&mut storage).unwrap().is_empty());
+
+        // The grant is removed once all its milestones are released
+        attest_milestone(&mut storage, steward, grant_id, 1).unwrap();
+        assert_eq!(release_grants(&mut storage).unwrap().len(), 1);
+        assert_eq!(get_grant(&storage, grant_id).unwrap(), None);
+        assert_eq!(
+            get_grants_reserved(&storage).unwrap(),
+            token::Amount::zero()
+        );
+        assert_eq!(
+            read_balance(&storage, &native_token, &target).unwrap(),
+            token::Amount::native_whole(30)
+        );
+    }
+}
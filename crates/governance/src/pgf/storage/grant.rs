@@ -0,0 +1,57 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use namada_core::types::address::Address;
+use namada_core::types::token;
+
+use crate::storage::proposal::PGFGrant;
+
+#[derive(Clone, Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+/// Struct holding data about a pgf milestone grant
+pub struct GrantDetail {
+    /// The id of the proposal that created the grant
+    pub proposal_id: u64,
+    /// The grant recipient
+    pub target: Address,
+    /// The amount released at each milestone
+    pub milestones: Vec<token::Amount>,
+    /// The stewards who attested the completed milestones, in order
+    pub attestations: Vec<Address>,
+    /// The number of milestones already released
+    pub released: u64,
+}
+
+impl GrantDetail {
+    /// Create the detail of a grant approved by the given proposal
+    pub fn new(proposal_id: u64, grant: PGFGrant) -> Self {
+        Self {
+            proposal_id,
+            target: grant.target,
+            milestones: grant.milestones,
+            attestations: vec![],
+            released: 0,
+        }
+    }
+
+    /// The index of the next milestone to attest, if any
+    pub fn next_milestone(&self) -> Option<u64> {
+        let attested = self.attestations.len() as u64;
+        (attested < self.milestones.len() as u64).then_some(attested)
+    }
+
+    /// Check if all the milestones of the grant have been released
+    pub fn is_completed(&self) -> bool {
+        self.released >= self.milestones.len() as u64
+    }
+}
+
+/// A milestone of a pgf grant released to its target
+#[derive(Clone, Debug, PartialEq)]
+pub struct GrantRelease {
+    /// The grant id
+    pub grant_id: u64,
+    /// The index of the released milestone
+    pub milestone: u64,
+    /// The grant recipient
+    pub target: Address,
+    /// The released amount
+    pub amount: token::Amount,
+}
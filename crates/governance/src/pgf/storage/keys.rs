@@ -3,7 +3,8 @@ use namada_core::types::storage::{DbKeySeg, Key, KeySeg};
 use namada_macros::StorageKeys;
 use namada_state::collections::{lazy_map, LazyCollection, LazyMap};
 
-use crate::pgf::storage::steward::StewardDetail;
+use crate::pgf::storage::grant::GrantDetail;
+use crate::pgf::storage::steward::{StewardDetail, StewardSpending};
 use crate::pgf::ADDRESS;
 use crate::storage::proposal::StoragePgfFunding;
 
@@ -11,7 +12,11 @@ use crate::storage::proposal::StoragePgfFunding;
 #[derive(StorageKeys)]
 struct Keys {
     stewards: &'static str,
+    steward_spendings: &'static str,
     fundings: &'static str,
+    grants: &'static str,
+    grant_counter: &'static str,
+    grants_reserved: &'static str,
     pgf_inflation_rate: &'static str,
     steward_inflation_rate: &'static str,
 }
@@ -50,6 +55,40 @@ pub fn is_stewards_key(key: &Key) -> Option<&Address> {
     }
 }
 
+/// Obtain a storage key for the stewards spending accounting
+pub fn steward_spendings_key_prefix() -> Key {
+    Key {
+        segments: vec![
+            DbKeySeg::AddressSeg(ADDRESS.to_owned()),
+            DbKeySeg::StringSeg(Keys::VALUES.steward_spendings.to_string()),
+        ],
+    }
+}
+
+/// LazyMap handler for the stewards spending accounting subspace
+pub fn steward_spendings_handle() -> LazyMap<Address, StewardSpending> {
+    LazyMap::open(steward_spendings_key_prefix())
+}
+
+/// Check if the given storage key is a steward spending key. If it is, returns
+/// the steward address.
+pub fn is_steward_spendings_key(key: &Key) -> Option<&Address> {
+    match &key.segments[..] {
+        [
+            DbKeySeg::AddressSeg(pgf),
+            DbKeySeg::StringSeg(prefix),
+            DbKeySeg::StringSeg(data),
+            DbKeySeg::AddressSeg(steward),
+        ] if pgf.eq(&ADDRESS)
+            && prefix.as_str() == Keys::VALUES.steward_spendings
+            && data.as_str() == lazy_map::DATA_SUBKEY =>
+        {
+            Some(steward)
+        }
+        _ => None,
+    }
+}
+
 /// Obtain a storage key for pgf fundings.
 pub fn fundings_key_prefix() -> Key {
     Key {
@@ -72,6 +111,59 @@ pub fn is_fundings_key(key: &Key) -> bool {
                 && data.as_str() == lazy_map::DATA_SUBKEY)
 }
 
+/// Obtain a storage key for pgf grants.
+pub fn grants_key_prefix() -> Key {
+    Key {
+        segments: vec![
+            DbKeySeg::AddressSeg(ADDRESS.to_owned()),
+            DbKeySeg::StringSeg(Keys::VALUES.grants.to_string()),
+        ],
+    }
+}
+
+/// LazyMap handler for the pgf grants substorage
+pub fn grants_handle() -> LazyMap<u64, GrantDetail> {
+    LazyMap::open(grants_key_prefix())
+}
+
+/// Check if the given storage key is a pgf grant key. If it is, returns the
+/// grant id.
+pub fn is_grants_key(key: &Key) -> Option<u64> {
+    match &key.segments[..] {
+        [
+            DbKeySeg::AddressSeg(pgf),
+            DbKeySeg::StringSeg(prefix),
+            DbKeySeg::StringSeg(data),
+            DbKeySeg::StringSeg(id),
+        ] if pgf.eq(&ADDRESS)
+            && prefix.as_str() == Keys::VALUES.grants
+            && data.as_str() == lazy_map::DATA_SUBKEY =>
+        {
+            u64::parse(id.clone()).ok()
+        }
+        _ => None,
+    }
+}
+
+/// Get key for the pgf grant counter
+pub fn get_grant_counter_key() -> Key {
+    Key::from(ADDRESS.to_db_key())
+        .push(&Keys::VALUES.grant_counter.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Check if key is the pgf grant counter key
+pub fn is_grant_counter_key(key: &Key) -> bool {
+    matches!(&key.segments[..], [DbKeySeg::AddressSeg(addr), DbKeySeg::StringSeg(prefix)] if addr == &ADDRESS && prefix == Keys::VALUES.grant_counter)
+}
+
+/// Get key for the amount of the pgf account reserved to pay the grants
+pub fn get_grants_reserved_key() -> Key {
+    Key::from(ADDRESS.to_db_key())
+        .push(&Keys::VALUES.grants_reserved.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
 /// Check if key is inside governance address space
 pub fn is_pgf_key(key: &Key) -> bool {
     matches!(&key.segments[0], DbKeySeg::AddressSeg(addr) if addr == &ADDRESS)
@@ -1,5 +1,7 @@
 //! Pgf
 
+/// Pgf grant structures
+pub mod grant;
 /// Pgf storage keys
 pub mod keys;
 /// Pgf steward structures
@@ -9,12 +11,15 @@ use std::collections::HashMap;
 
 use namada_core::types::address::Address;
 use namada_core::types::dec::Dec;
-use namada_state::{StorageRead, StorageResult, StorageWrite};
+use namada_core::types::token;
+use namada_state::{StorageError, StorageRead, StorageResult, StorageWrite};
 
 use crate::pgf::parameters::PgfParameters;
+use crate::pgf::storage::grant::GrantDetail;
 use crate::pgf::storage::keys as pgf_keys;
-use crate::pgf::storage::steward::StewardDetail;
-use crate::storage::proposal::StoragePgfFunding;
+use crate::pgf::storage::steward::{StewardDetail, StewardSpending};
+use crate::pgf::ADDRESS;
+use crate::storage::proposal::{PGFGrant, StoragePgfFunding};
 
 /// Query the current pgf steward set
 pub fn get_stewards<S>(storage: &S) -> StorageResult<Vec<StewardDetail>>
@@ -51,6 +56,30 @@ where
     pgf_keys::stewards_handle().contains(storage, address)
 }
 
+/// Nominate a steward with an optional cap on the retro payments its
+/// proposals can fund
+pub fn nominate_steward<S>(
+    storage: &mut S,
+    address: Address,
+    spending_cap: Option<token::Amount>,
+) -> StorageResult<()>
+where
+    S: StorageRead + StorageWrite,
+{
+    pgf_keys::stewards_handle().insert(
+        storage,
+        address.clone(),
+        StewardDetail::base(address.clone()),
+    )?;
+    pgf_keys::steward_spendings_handle().insert(
+        storage,
+        address,
+        StewardSpending::new(spending_cap),
+    )?;
+
+    Ok(())
+}
+
 /// Remove a steward
 pub fn remove_steward<S>(
     storage: &mut S,
@@ -60,6 +89,44 @@ where
     S: StorageRead + StorageWrite,
 {
     pgf_keys::stewards_handle().remove(storage, address)?;
+    pgf_keys::steward_spendings_handle().remove(storage, address)?;
+
+    Ok(())
+}
+
+/// Query the spending accounting of a steward. Stewards nominated before the
+/// introduction of spending caps have no cap.
+pub fn get_steward_spending<S>(
+    storage: &S,
+    address: &Address,
+) -> StorageResult<StewardSpending>
+where
+    S: StorageRead,
+{
+    Ok(pgf_keys::steward_spendings_handle()
+        .get(storage, address)?
+        .unwrap_or_default())
+}
+
+/// Record the retro payments funded by a passed proposal of a steward
+pub fn record_steward_spending<S>(
+    storage: &mut S,
+    address: &Address,
+    amount: token::Amount,
+) -> StorageResult<()>
+where
+    S: StorageRead + StorageWrite,
+{
+    let mut spending = get_steward_spending(storage, address)?;
+    spending.spent = spending
+        .spent
+        .checked_add(amount)
+        .ok_or_else(|| StorageError::new_const("Steward spending overflow"))?;
+    pgf_keys::steward_spendings_handle().insert(
+        storage,
+        address.clone(),
+        spending,
+    )?;
 
     Ok(())
 }
@@ -80,6 +147,123 @@ where
     Ok(fundings)
 }
 
+/// Query the current pgf grants with their id
+pub fn get_grants<S>(storage: &S) -> StorageResult<Vec<(u64, GrantDetail)>>
+where
+    S: StorageRead,
+{
+    let grants = pgf_keys::grants_handle()
+        .iter(storage)?
+        .filter_map(|data| data.ok())
+        .collect::<Vec<(u64, GrantDetail)>>();
+
+    Ok(grants)
+}
+
+/// Query a pgf grant by id
+pub fn get_grant<S>(
+    storage: &S,
+    grant_id: u64,
+) -> StorageResult<Option<GrantDetail>>
+where
+    S: StorageRead,
+{
+    pgf_keys::grants_handle().get(storage, &grant_id)
+}
+
+/// Query the amount of the native token of the pgf account reserved to pay
+/// the milestones of the grants
+pub fn get_grants_reserved<S>(storage: &S) -> StorageResult<token::Amount>
+where
+    S: StorageRead,
+{
+    let reserved = storage.read(&pgf_keys::get_grants_reserved_key())?;
+    Ok(reserved.unwrap_or_default())
+}
+
+/// Query the balance of the native token of the pgf account that isn't
+/// reserved to pay the grants, and so can be spent on other fundings
+pub fn get_unreserved_balance<S>(storage: &S) -> StorageResult<token::Amount>
+where
+    S: StorageRead,
+{
+    let native_token = storage.get_native_token()?;
+    let balance =
+        namada_trans_token::read_balance(storage, &native_token, &ADDRESS)?;
+    let reserved = get_grants_reserved(storage)?;
+    Ok(balance.checked_sub(reserved).unwrap_or_default())
+}
+
+/// Add a grant approved by a proposal and reserve its total amount in the pgf
+/// account. Returns the id of the grant, or `None` if the unreserved balance
+/// of the pgf account can't cover the grant.
+pub fn add_grant<S>(
+    storage: &mut S,
+    proposal_id: u64,
+    grant: PGFGrant,
+) -> StorageResult<Option<u64>>
+where
+    S: StorageRead + StorageWrite,
+{
+    let total = grant
+        .milestones
+        .iter()
+        .try_fold(token::Amount::zero(), |acc, amount| {
+            acc.checked_add(*amount)
+        })
+        .ok_or_else(|| StorageError::new_const("Pgf grant amount overflow"))?;
+    if total > get_unreserved_balance(storage)? {
+        return Ok(None);
+    }
+    let reserved = get_grants_reserved(storage)?
+        .checked_add(total)
+        .ok_or_else(|| {
+            StorageError::new_const("Pgf grants reserved amount overflow")
+        })?;
+    storage.write(&pgf_keys::get_grants_reserved_key(), reserved)?;
+
+    let counter_key = pgf_keys::get_grant_counter_key();
+    let grant_id: u64 = storage.read(&counter_key)?.unwrap_or_default();
+    pgf_keys::grants_handle().insert(
+        storage,
+        grant_id,
+        GrantDetail::new(proposal_id, grant),
+    )?;
+    storage.write(&counter_key, grant_id + 1)?;
+
+    Ok(Some(grant_id))
+}
+
+/// Record the attestation of the next milestone of a grant by a steward
+pub fn attest_milestone<S>(
+    storage: &mut S,
+    steward: Address,
+    grant_id: u64,
+    milestone: u64,
+) -> StorageResult<()>
+where
+    S: StorageRead + StorageWrite,
+{
+    let mut grant = get_grant(storage, grant_id)?.ok_or_else(|| {
+        StorageError::new(format!("Pgf grant {grant_id} doesn't exist"))
+    })?;
+    if grant.target == steward {
+        return Err(StorageError::new(format!(
+            "The target of pgf grant {grant_id} can't attest its milestones"
+        )));
+    }
+    if grant.next_milestone() != Some(milestone) {
+        return Err(StorageError::new(format!(
+            "Milestone {milestone} is not the next milestone to attest of pgf \
+             grant {grant_id}"
+        )));
+    }
+    grant.attestations.push(steward);
+    pgf_keys::grants_handle().insert(storage, grant_id, grant)?;
+
+    Ok(())
+}
+
 /// Query the pgf parameters
 pub fn get_parameters<S>(storage: &S) -> StorageResult<PgfParameters>
 where
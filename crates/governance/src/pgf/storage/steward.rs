@@ -3,6 +3,7 @@ use std::collections::HashMap;
 use borsh::{BorshDeserialize, BorshSerialize};
 use namada_core::types::address::Address;
 use namada_core::types::dec::Dec;
+use namada_core::types::token;
 
 use crate::pgf::REWARD_DISTRIBUTION_LIMIT;
 
@@ -41,3 +42,40 @@ impl StewardDetail {
         sum <= Dec::one()
     }
 }
+
+#[derive(
+    Clone, Debug, Default, BorshSerialize, BorshDeserialize, PartialEq,
+)]
+/// Struct holding the accounting of the retro pgf payments funded by the
+/// proposals of a steward since its nomination
+pub struct StewardSpending {
+    /// The maximum amount of retro payments that the proposals of the steward
+    /// can fund, if any
+    pub cap: Option<token::Amount>,
+    /// The amount of retro payments funded by the passed proposals of the
+    /// steward
+    pub spent: token::Amount,
+}
+
+impl StewardSpending {
+    /// Create the accounting of a newly nominated steward
+    pub fn new(cap: Option<token::Amount>) -> Self {
+        Self {
+            cap,
+            spent: token::Amount::zero(),
+        }
+    }
+
+    /// The amount of retro payments that the steward can still fund, `None`
+    /// if it has no spending cap
+    pub fn remaining(&self) -> Option<token::Amount> {
+        self.cap
+            .map(|cap| cap.checked_sub(self.spent).unwrap_or_default())
+    }
+
+    /// Check if the steward can fund the given amount of retro payments
+    pub fn can_spend(&self, amount: token::Amount) -> bool {
+        self.remaining()
+            .map_or(true, |remaining| amount <= remaining)
+    }
+}
@@ -1,7 +1,11 @@
+use std::str::FromStr;
+
 use namada_core::types::address::Address;
-use namada_core::types::storage::{DbKeySeg, Key, KeySeg};
+use namada_core::types::hash::Hash;
+use namada_core::types::storage::{DbKeySeg, Epoch, Key, KeySeg};
 use namada_macros::StorageKeys;
 
+use crate::storage::proposal::{HaltedSubsystem, ProposalKind};
 use crate::ADDRESS;
 
 /// Storage keys for governance internal address.
@@ -12,21 +16,37 @@ struct Keys {
     author: &'static str,
     proposal_type: &'static str,
     content: &'static str,
+    content_hash: &'static str,
+    content_uri: &'static str,
+    voting_mode: &'static str,
     start_epoch: &'static str,
     end_epoch: &'static str,
     grace_epoch: &'static str,
     funds: &'static str,
     proposal_code: &'static str,
     committing_epoch: &'static str,
+    voting_start_epoch: &'static str,
     min_fund: &'static str,
+    min_signaling_fund: &'static str,
     max_code_size: &'static str,
     min_period: &'static str,
     max_period: &'static str,
     max_content: &'static str,
     min_grace_epoch: &'static str,
+    veto_threshold: &'static str,
+    expedited_voting_period: &'static str,
+    expedited_quorum: &'static str,
+    expedited_threshold: &'static str,
+    deposit_policy: &'static str,
+    expedited: &'static str,
     counter: &'static str,
     pending: &'static str,
     result: &'static str,
+    vote_delegation: &'static str,
+    vote_delegator: &'static str,
+    voter_vote: &'static str,
+    halt: &'static str,
+    consumed_ballot: &'static str,
 }
 
 /// Check if key is inside governance address space
@@ -54,6 +74,78 @@ pub fn is_vote_key(key: &Key) -> bool {
     }
 }
 
+/// Check if a key is a vote delegation key and return the delegator address,
+/// the kind of proposals and the epoch from which the delegation applies
+pub fn is_vote_delegation_key(
+    key: &Key,
+) -> Option<(&Address, ProposalKind, Epoch)> {
+    match &key.segments[..] {
+        [
+            DbKeySeg::AddressSeg(addr),
+            DbKeySeg::StringSeg(prefix),
+            DbKeySeg::AddressSeg(delegator),
+            DbKeySeg::StringSeg(kind),
+            DbKeySeg::StringSeg(epoch),
+        ] if addr == &ADDRESS && prefix == Keys::VALUES.vote_delegation => {
+            let kind = ProposalKind::from_str(kind).ok()?;
+            let epoch = Epoch::parse(epoch.clone()).ok()?;
+            Some((delegator, kind, epoch))
+        }
+        _ => None,
+    }
+}
+
+/// Check if a key is a key of the index of the vote delegations by delegate
+/// and return the delegate address, the kind of proposals and the delegator
+/// address
+pub fn is_vote_delegator_key(
+    key: &Key,
+) -> Option<(&Address, ProposalKind, &Address)> {
+    match &key.segments[..] {
+        [
+            DbKeySeg::AddressSeg(addr),
+            DbKeySeg::StringSeg(prefix),
+            DbKeySeg::AddressSeg(delegate),
+            DbKeySeg::StringSeg(kind),
+            DbKeySeg::AddressSeg(delegator),
+        ] if addr == &ADDRESS && prefix == Keys::VALUES.vote_delegator => {
+            let kind = ProposalKind::from_str(kind).ok()?;
+            Some((delegate, kind, delegator))
+        }
+        _ => None,
+    }
+}
+
+/// Check if a key is a key of the index of the votes of a voter and return
+/// the voter address and the proposal id
+pub fn is_voter_vote_key(key: &Key) -> Option<(&Address, u64)> {
+    match &key.segments[..] {
+        [
+            DbKeySeg::AddressSeg(addr),
+            DbKeySeg::StringSeg(prefix),
+            DbKeySeg::AddressSeg(voter),
+            DbKeySeg::StringSeg(id),
+        ] if addr == &ADDRESS && prefix == Keys::VALUES.voter_vote => {
+            let id = id.parse::<u64>().ok()?;
+            Some((voter, id))
+        }
+        _ => None,
+    }
+}
+
+/// Check if key is the key of a consumed off-chain signed ballot and return
+/// the hash of the ballot
+pub fn is_consumed_ballot_key(key: &Key) -> Option<Hash> {
+    match &key.segments[..] {
+        [DbKeySeg::AddressSeg(addr), DbKeySeg::StringSeg(prefix), DbKeySeg::StringSeg(hash)]
+            if addr == &ADDRESS && prefix == Keys::VALUES.consumed_ballot =>
+        {
+            Hash::parse(hash.clone()).ok()
+        }
+        _ => None,
+    }
+}
+
 /// Check if key is author key
 pub fn is_author_key(key: &Key) -> bool {
     match &key.segments[..] {
@@ -90,6 +182,24 @@ pub fn is_proposal_code_key(key: &Key) -> bool {
     }
 }
 
+/// Check if key is expedited proposal key
+pub fn is_expedited_key(key: &Key) -> bool {
+    match &key.segments[..] {
+        [
+            DbKeySeg::AddressSeg(addr),
+            DbKeySeg::StringSeg(prefix),
+            DbKeySeg::StringSeg(id),
+            DbKeySeg::StringSeg(expedited),
+        ] if addr == &ADDRESS
+            && prefix == Keys::VALUES.proposal
+            && expedited == Keys::VALUES.expedited =>
+        {
+            id.parse::<u64>().is_ok()
+        }
+        _ => false,
+    }
+}
+
 /// Check if key is grace epoch key
 pub fn is_grace_epoch_key(key: &Key) -> bool {
     match &key.segments[..] {
@@ -126,6 +236,60 @@ pub fn is_content_key(key: &Key) -> bool {
     }
 }
 
+/// Check if key is content hash key
+pub fn is_content_hash_key(key: &Key) -> bool {
+    match &key.segments[..] {
+        [
+            DbKeySeg::AddressSeg(addr),
+            DbKeySeg::StringSeg(prefix),
+            DbKeySeg::StringSeg(id),
+            DbKeySeg::StringSeg(content_hash),
+        ] if addr == &ADDRESS
+            && prefix == Keys::VALUES.proposal
+            && content_hash == Keys::VALUES.content_hash =>
+        {
+            id.parse::<u64>().is_ok()
+        }
+        _ => false,
+    }
+}
+
+/// Check if key is content URI key
+pub fn is_content_uri_key(key: &Key) -> bool {
+    match &key.segments[..] {
+        [
+            DbKeySeg::AddressSeg(addr),
+            DbKeySeg::StringSeg(prefix),
+            DbKeySeg::StringSeg(id),
+            DbKeySeg::StringSeg(content_uri),
+        ] if addr == &ADDRESS
+            && prefix == Keys::VALUES.proposal
+            && content_uri == Keys::VALUES.content_uri =>
+        {
+            id.parse::<u64>().is_ok()
+        }
+        _ => false,
+    }
+}
+
+/// Check if key is voting mode key
+pub fn is_voting_mode_key(key: &Key) -> bool {
+    match &key.segments[..] {
+        [
+            DbKeySeg::AddressSeg(addr),
+            DbKeySeg::StringSeg(prefix),
+            DbKeySeg::StringSeg(id),
+            DbKeySeg::StringSeg(voting_mode),
+        ] if addr == &ADDRESS
+            && prefix == Keys::VALUES.proposal
+            && voting_mode == Keys::VALUES.voting_mode =>
+        {
+            id.parse::<u64>().is_ok()
+        }
+        _ => false,
+    }
+}
+
 /// Check if key is balance key
 pub fn is_balance_key(key: &Key) -> bool {
     match &key.segments[..] {
@@ -211,6 +375,15 @@ pub fn is_min_proposal_fund_key(key: &Key) -> bool {
          ] if addr == &ADDRESS && min_funds_param == Keys::VALUES.min_fund)
 }
 
+/// Check if key is a signaling proposal fund parameter key
+pub fn is_min_signaling_proposal_fund_key(key: &Key) -> bool {
+    matches!(&key.segments[..], [
+             DbKeySeg::AddressSeg(addr),
+             DbKeySeg::StringSeg(min_funds_param),
+         ] if addr == &ADDRESS
+             && min_funds_param == Keys::VALUES.min_signaling_fund)
+}
+
 /// Check if key is a proposal max content parameter key
 pub fn is_max_content_size_key(key: &Key) -> bool {
     matches!(&key.segments[..], [
@@ -261,6 +434,20 @@ pub fn is_commit_proposal_key(key: &Key) -> bool {
     )
 }
 
+/// Check if key is a key of the index of the proposals by voting start epoch
+pub fn is_voting_start_proposal_key(key: &Key) -> bool {
+    matches!(&key.segments[..], [
+        DbKeySeg::AddressSeg(addr),
+        DbKeySeg::StringSeg(prefix),
+        DbKeySeg::StringSeg(epoch_prefix),
+        DbKeySeg::StringSeg(_epoch),
+        DbKeySeg::StringSeg(_id),
+    ] if addr == &ADDRESS
+        && prefix == Keys::VALUES.proposal
+        && epoch_prefix == Keys::VALUES.voting_start_epoch
+    )
+}
+
 /// Check if key is a commit proposal key
 pub fn is_min_grace_epoch_key(key: &Key) -> bool {
     matches!(&key.segments[..], [
@@ -270,14 +457,65 @@ pub fn is_min_grace_epoch_key(key: &Key) -> bool {
                     && min_grace_epoch_param == Keys::VALUES.min_grace_epoch)
 }
 
+/// Check if key is a veto threshold parameter key
+pub fn is_veto_threshold_key(key: &Key) -> bool {
+    matches!(&key.segments[..], [
+                    DbKeySeg::AddressSeg(addr),
+                    DbKeySeg::StringSeg(veto_threshold_param),
+                ] if addr == &ADDRESS
+                    && veto_threshold_param == Keys::VALUES.veto_threshold)
+}
+
+/// Check if key is an expedited voting period parameter key
+pub fn is_expedited_voting_period_key(key: &Key) -> bool {
+    matches!(&key.segments[..], [
+                    DbKeySeg::AddressSeg(addr),
+                    DbKeySeg::StringSeg(expedited_voting_period_param),
+                ] if addr == &ADDRESS
+                    && expedited_voting_period_param == Keys::VALUES.expedited_voting_period)
+}
+
+/// Check if key is an expedited quorum parameter key
+pub fn is_expedited_quorum_key(key: &Key) -> bool {
+    matches!(&key.segments[..], [
+                    DbKeySeg::AddressSeg(addr),
+                    DbKeySeg::StringSeg(expedited_quorum_param),
+                ] if addr == &ADDRESS
+                    && expedited_quorum_param == Keys::VALUES.expedited_quorum)
+}
+
+/// Check if key is an expedited threshold parameter key
+pub fn is_expedited_threshold_key(key: &Key) -> bool {
+    matches!(&key.segments[..], [
+                    DbKeySeg::AddressSeg(addr),
+                    DbKeySeg::StringSeg(expedited_threshold_param),
+                ] if addr == &ADDRESS
+                    && expedited_threshold_param == Keys::VALUES.expedited_threshold)
+}
+
+/// Check if key is a deposit policy parameter key
+pub fn is_deposit_policy_key(key: &Key) -> bool {
+    matches!(&key.segments[..], [
+                    DbKeySeg::AddressSeg(addr),
+                    DbKeySeg::StringSeg(deposit_policy_param),
+                ] if addr == &ADDRESS
+                    && deposit_policy_param == Keys::VALUES.deposit_policy)
+}
+
 /// Check if key is parameter key
 pub fn is_parameter_key(key: &Key) -> bool {
     is_min_proposal_fund_key(key)
+        || is_min_signaling_proposal_fund_key(key)
         || is_max_content_size_key(key)
         || is_max_proposal_code_size_key(key)
         || is_min_proposal_voting_period_key(key)
         || is_max_proposal_period_key(key)
         || is_min_grace_epoch_key(key)
+        || is_veto_threshold_key(key)
+        || is_expedited_voting_period_key(key)
+        || is_expedited_quorum_key(key)
+        || is_expedited_threshold_key(key)
+        || is_deposit_policy_key(key)
 }
 
 /// Check if key is start epoch or end epoch key
@@ -299,6 +537,13 @@ pub fn get_min_proposal_fund_key() -> Key {
         .expect("Cannot obtain a storage key")
 }
 
+/// Get key for the minimum signaling proposal fund
+pub fn get_min_signaling_proposal_fund_key() -> Key {
+    Key::from(ADDRESS.to_db_key())
+        .push(&Keys::VALUES.min_signaling_fund.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
 /// Get maximum proposal code size key
 pub fn get_max_proposal_code_size_key() -> Key {
     Key::from(ADDRESS.to_db_key())
@@ -334,6 +579,41 @@ pub fn get_min_proposal_grace_epoch_key() -> Key {
         .expect("Cannot obtain a storage key")
 }
 
+/// Get veto threshold key
+pub fn get_veto_threshold_key() -> Key {
+    Key::from(ADDRESS.to_db_key())
+        .push(&Keys::VALUES.veto_threshold.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Get expedited voting period key
+pub fn get_expedited_voting_period_key() -> Key {
+    Key::from(ADDRESS.to_db_key())
+        .push(&Keys::VALUES.expedited_voting_period.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Get expedited quorum key
+pub fn get_expedited_quorum_key() -> Key {
+    Key::from(ADDRESS.to_db_key())
+        .push(&Keys::VALUES.expedited_quorum.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Get expedited threshold key
+pub fn get_expedited_threshold_key() -> Key {
+    Key::from(ADDRESS.to_db_key())
+        .push(&Keys::VALUES.expedited_threshold.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Get deposit policy key
+pub fn get_deposit_policy_key() -> Key {
+    Key::from(ADDRESS.to_db_key())
+        .push(&Keys::VALUES.deposit_policy.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
 /// Get key of proposal ids counter
 pub fn get_counter_key() -> Key {
     Key::from(ADDRESS.to_db_key())
@@ -350,6 +630,33 @@ pub fn get_content_key(id: u64) -> Key {
         .expect("Cannot obtain a storage key")
 }
 
+/// Get key of proposal content hash
+pub fn get_content_hash_key(id: u64) -> Key {
+    proposal_prefix()
+        .push(&id.to_string())
+        .expect("Cannot obtain a storage key")
+        .push(&Keys::VALUES.content_hash.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Get key of proposal content URI
+pub fn get_content_uri_key(id: u64) -> Key {
+    proposal_prefix()
+        .push(&id.to_string())
+        .expect("Cannot obtain a storage key")
+        .push(&Keys::VALUES.content_uri.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Get key of proposal voting mode
+pub fn get_voting_mode_key(id: u64) -> Key {
+    proposal_prefix()
+        .push(&id.to_string())
+        .expect("Cannot obtain a storage key")
+        .push(&Keys::VALUES.voting_mode.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
 /// Get key of proposal author
 pub fn get_author_key(id: u64) -> Key {
     proposal_prefix()
@@ -413,6 +720,16 @@ pub fn get_commiting_proposals_prefix(epoch: u64) -> Key {
         .expect("Cannot obtain a storage key")
 }
 
+/// Get the prefix of the proposals whose voting period starts at the given
+/// epoch
+pub fn get_voting_start_proposals_prefix(epoch: u64) -> Key {
+    proposal_prefix()
+        .push(&Keys::VALUES.voting_start_epoch.to_owned())
+        .expect("Cannot obtain a storage key")
+        .push(&epoch.to_string())
+        .expect("Cannot obtain a storage key")
+}
+
 /// Get proposal code key
 pub fn get_proposal_code_key(id: u64) -> Key {
     proposal_prefix()
@@ -422,6 +739,16 @@ pub fn get_proposal_code_key(id: u64) -> Key {
         .expect("Cannot obtain a storage key")
 }
 
+/// Get the expedited proposal key, holding the epoch at which the expedited
+/// voting period ends
+pub fn get_expedited_key(id: u64) -> Key {
+    proposal_prefix()
+        .push(&id.to_string())
+        .expect("Cannot obtain a storage key")
+        .push(&Keys::VALUES.expedited.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
 /// Get the committing proposal key
 pub fn get_committing_proposals_key(id: u64, epoch: u64) -> Key {
     get_commiting_proposals_prefix(epoch)
@@ -429,6 +756,14 @@ pub fn get_committing_proposals_key(id: u64, epoch: u64) -> Key {
         .expect("Cannot obtain a storage key")
 }
 
+/// Get the key indexing a proposal by the epoch at which its voting period
+/// starts
+pub fn get_voting_start_proposals_key(id: u64, epoch: u64) -> Key {
+    get_voting_start_proposals_prefix(epoch)
+        .push(&id.to_string())
+        .expect("Cannot obtain a storage key")
+}
+
 /// Get proposal vote prefix key
 pub fn get_proposal_vote_prefix_key(id: u64) -> Key {
     proposal_prefix()
@@ -451,6 +786,111 @@ pub fn get_vote_proposal_key(
         .expect("Cannot obtain a storage key")
 }
 
+/// Get the prefix of the index of the votes cast by a voter
+pub fn get_voter_votes_prefix(voter: &Address) -> Key {
+    Key::from(ADDRESS.to_db_key())
+        .push(&Keys::VALUES.voter_vote.to_owned())
+        .expect("Cannot obtain a storage key")
+        .push(voter)
+        .expect("Cannot obtain a storage key")
+}
+
+/// Get the key of the index of the vote cast by a voter on a proposal,
+/// holding the validators the voter voted through
+pub fn get_voter_vote_key(voter: &Address, id: u64) -> Key {
+    get_voter_votes_prefix(voter)
+        .push(&id.to_string())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Get the prefix of all the vote delegations
+pub fn get_vote_delegation_prefix() -> Key {
+    Key::from(ADDRESS.to_db_key())
+        .push(&Keys::VALUES.vote_delegation.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Get the prefix of the vote delegations of an address for a kind of
+/// proposals
+pub fn get_vote_delegation_kind_prefix(
+    delegator: &Address,
+    kind: ProposalKind,
+) -> Key {
+    get_vote_delegation_prefix()
+        .push(delegator)
+        .expect("Cannot obtain a storage key")
+        .push(&kind.to_string())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Get the key of the vote delegation of an address for a kind of proposals,
+/// applying from the given epoch
+pub fn get_vote_delegation_key(
+    delegator: &Address,
+    kind: ProposalKind,
+    epoch: Epoch,
+) -> Key {
+    get_vote_delegation_kind_prefix(delegator, kind)
+        .push(&epoch)
+        .expect("Cannot obtain a storage key")
+}
+
+/// Get the prefix of the index of the vote delegations to a delegate for a
+/// kind of proposals
+pub fn get_vote_delegators_prefix(
+    delegate: &Address,
+    kind: ProposalKind,
+) -> Key {
+    Key::from(ADDRESS.to_db_key())
+        .push(&Keys::VALUES.vote_delegator.to_owned())
+        .expect("Cannot obtain a storage key")
+        .push(delegate)
+        .expect("Cannot obtain a storage key")
+        .push(&kind.to_string())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Get the key of the index of the vote delegation of a delegator to a
+/// delegate for a kind of proposals, holding the epoch from which the
+/// delegation applies
+pub fn get_vote_delegator_key(
+    delegate: &Address,
+    kind: ProposalKind,
+    delegator: &Address,
+) -> Key {
+    get_vote_delegators_prefix(delegate, kind)
+        .push(delegator)
+        .expect("Cannot obtain a storage key")
+}
+
+/// Get the key of an off-chain signed ballot that has already been submitted
+/// on chain, holding the id of the proposal it voted on
+pub fn get_consumed_ballot_key(ballot_hash: &Hash) -> Key {
+    Key::from(ADDRESS.to_db_key())
+        .push(&Keys::VALUES.consumed_ballot.to_owned())
+        .expect("Cannot obtain a storage key")
+        .push(ballot_hash)
+        .expect("Cannot obtain a storage key")
+}
+
+/// Get the key of the emergency halt of a subsystem, holding the epoch at
+/// which the halt expires
+pub fn get_halt_key(subsystem: &HaltedSubsystem) -> Key {
+    let prefix = Key::from(ADDRESS.to_db_key())
+        .push(&Keys::VALUES.halt.to_owned())
+        .expect("Cannot obtain a storage key");
+    let key = match subsystem {
+        HaltedSubsystem::IbcTransfers => {
+            prefix.push(&"ibc_transfers".to_owned())
+        }
+        HaltedSubsystem::Masp => prefix.push(&"masp".to_owned()),
+        HaltedSubsystem::Token(token) => prefix
+            .push(&"token".to_owned())
+            .and_then(|key| key.push(token)),
+    };
+    key.expect("Cannot obtain a storage key")
+}
+
 /// Get the proposal execution key
 pub fn get_proposal_execution_key(id: u64) -> Key {
     Key::from(ADDRESS.to_db_key())
@@ -501,6 +941,19 @@ pub fn get_commit_proposal_id(key: &Key) -> Option<u64> {
         None => None,
     }
 }
+
+/// Get the voting start epoch from a key of the index of the proposals by
+/// voting start epoch
+pub fn get_voting_start_proposal_epoch(key: &Key) -> Option<u64> {
+    get_commit_proposal_epoch(key)
+}
+
+/// Get the proposal id from a key of the index of the proposals by voting
+/// start epoch
+pub fn get_voting_start_proposal_id(key: &Key) -> Option<u64> {
+    get_commit_proposal_id(key)
+}
+
 /// Get the delegation address from vote key
 pub fn get_vote_delegation_address(key: &Key) -> Option<&Address> {
     match key.get_at(4) {
@@ -7,23 +7,30 @@ pub mod proposal;
 /// Vote structures
 pub mod vote;
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 
 use namada_core::borsh::BorshDeserialize;
 use namada_core::types::address::Address;
+use namada_core::types::chain::ChainId;
+use namada_core::types::dec::Dec;
+use namada_core::types::hash::Hash;
 use namada_core::types::storage::Epoch;
+use namada_core::types::time::DurationSecs;
 use namada_state::{
     iter_prefix, StorageError, StorageRead, StorageResult, StorageWrite,
 };
 use namada_trans_token as token;
 
-use crate::parameters::GovernanceParameters;
+use crate::parameters::{DepositPolicy, GovernanceParameters};
 use crate::storage::keys as governance_keys;
 use crate::storage::proposal::{
-    InitProposalData, ProposalType, StorageProposal, VoteProposalData,
+    EmergencyHalt, HaltedSubsystem, InitProposalData, ParameterChange,
+    ParameterDiff, ProposalKind, ProposalType, StorageProposal,
+    VoteDelegationData, VoteProposalBatchData, VoteProposalData,
+    VoteWithdrawalData, WasmAllowlistUpdate, MAX_CONTENT_URI_LENGTH,
 };
 use crate::storage::vote::ProposalVote;
-use crate::utils::{ProposalResult, Vote};
+use crate::utils::{ProposalResult, Vote, VotingMode};
 use crate::ADDRESS as governance_address;
 
 /// A proposal creation transaction.
@@ -36,12 +43,43 @@ pub fn init_proposal<S>(
 where
     S: StorageRead + StorageWrite,
 {
+    if !data.r#type.is_valid_content() {
+        return Err(StorageError::new_const("Invalid proposal content"));
+    }
+
     let counter_key = governance_keys::get_counter_key();
     let proposal_id = storage.read(&counter_key)?.expect(
         "Storage should have been initialized with an initial governance \
          proposal id",
     );
 
+    if Hash::sha256(&content) != data.content_hash {
+        return Err(StorageError::new_const(
+            "Proposal content doesn't match the committed content hash",
+        ));
+    }
+    let content_hash_key = governance_keys::get_content_hash_key(proposal_id);
+    storage.write(&content_hash_key, data.content_hash)?;
+
+    if let Some(content_uri) = &data.content_uri {
+        if content_uri.is_empty() || content_uri.len() > MAX_CONTENT_URI_LENGTH
+        {
+            return Err(StorageError::new_const(
+                "Invalid proposal content URI",
+            ));
+        }
+        let content_uri_key = governance_keys::get_content_uri_key(proposal_id);
+        storage.write(&content_uri_key, content_uri)?;
+    }
+
+    if !data.voting_mode.is_valid_for(&data.r#type) {
+        return Err(StorageError::new_const("Invalid proposal voting mode"));
+    }
+    if !data.voting_mode.is_linear() {
+        let voting_mode_key = governance_keys::get_voting_mode_key(proposal_id);
+        storage.write(&voting_mode_key, data.voting_mode)?;
+    }
+
     let content_key = governance_keys::get_content_key(proposal_id);
     storage.write_bytes(&content_key, content)?;
 
@@ -49,19 +87,7 @@ where
     storage.write(&author_key, data.author.clone())?;
 
     let proposal_type_key = governance_keys::get_proposal_type_key(proposal_id);
-    match data.r#type {
-        ProposalType::Default(Some(_)) => {
-            // Remove wasm code and write it under a different subkey
-            storage.write(&proposal_type_key, ProposalType::Default(None))?;
-            let proposal_code_key =
-                governance_keys::get_proposal_code_key(proposal_id);
-            let proposal_code = code
-                .clone()
-                .ok_or(StorageError::new_const("Missing proposal code"))?;
-            storage.write_bytes(&proposal_code_key, proposal_code)?
-        }
-        _ => storage.write(&proposal_type_key, data.r#type.clone())?,
-    }
+    storage.write(&proposal_type_key, data.r#type.clone())?;
 
     let voting_start_epoch_key =
         governance_keys::get_voting_start_epoch_key(proposal_id);
@@ -74,7 +100,8 @@ where
     let grace_epoch_key = governance_keys::get_grace_epoch_key(proposal_id);
     storage.write(&grace_epoch_key, data.grace_epoch)?;
 
-    if let ProposalType::Default(Some(_)) = data.r#type {
+    // The wasm code is written under a different subkey
+    if let ProposalType::WasmCodeUpgrade(_) = data.r#type {
         let proposal_code_key =
             governance_keys::get_proposal_code_key(proposal_id);
         let proposal_code =
@@ -84,9 +111,7 @@ where
 
     storage.write(&counter_key, proposal_id + 1)?;
 
-    let min_proposal_funds_key = governance_keys::get_min_proposal_fund_key();
-    let min_proposal_funds: token::Amount =
-        storage.read(&min_proposal_funds_key)?.unwrap();
+    let min_proposal_funds = get_min_proposal_fund(storage, &data.r#type)?;
 
     let funds_key = governance_keys::get_funds_key(proposal_id);
     storage.write(&funds_key, min_proposal_funds)?;
@@ -99,6 +124,29 @@ where
         );
     storage.write(&committing_proposals_key, ())?;
 
+    // index the proposal by the epoch at which its voting period starts
+    let voting_start_proposals_key =
+        governance_keys::get_voting_start_proposals_key(
+            proposal_id,
+            data.voting_start_epoch.0,
+        );
+    storage.write(&voting_start_proposals_key, ())?;
+
+    // an expedited proposal is also tallied at the end of its shorter voting
+    // period
+    if data.expedited {
+        let expedited_end_epoch =
+            data.voting_start_epoch + get_expedited_voting_period(storage)?;
+        let expedited_key = governance_keys::get_expedited_key(proposal_id);
+        storage.write(&expedited_key, expedited_end_epoch)?;
+        let committing_proposals_key =
+            governance_keys::get_committing_proposals_key(
+                proposal_id,
+                expedited_end_epoch.0,
+            );
+        storage.write(&committing_proposals_key, ())?;
+    }
+
     token::transfer(
         storage,
         &storage.get_native_token()?,
@@ -108,7 +156,189 @@ where
     )
 }
 
-/// A proposal vote transaction.
+/// Compare the value proposed by a protocol parameter change with the current
+/// value of the parameter. Only the protocol parameters, including the gas
+/// and the wasm limits, can be changed by a parameter change proposal. The PoS
+/// and governance parameters are only changed by the code of a default
+/// proposal, which can't be previewed.
+pub fn parameter_change_diff<S>(
+    storage: &S,
+    change: &ParameterChange,
+) -> StorageResult<ParameterDiff>
+where
+    S: StorageRead,
+{
+    use namada_parameters::storage as params_storage;
+
+    let current = match change {
+        ParameterChange::MaxExpectedTimePerBlock(_) => storage
+            .read::<DurationSecs>(
+                &params_storage::get_max_expected_time_per_block_key(),
+            )?
+            .map(|duration| format!("{}s", duration.0)),
+        ParameterChange::EpochsPerYear(_) => storage
+            .read::<u64>(&params_storage::get_epochs_per_year_key())?
+            .map(|epochs| epochs.to_string()),
+        ParameterChange::NativeTxAllowlist(_) => storage
+            .read::<Vec<String>>(
+                &params_storage::get_native_tx_allowlist_storage_key(),
+            )?
+            .map(|hashes| format!("[{}]", hashes.join(", "))),
+        ParameterChange::SubTxAllowlist(_) => storage
+            .read::<Vec<String>>(
+                &params_storage::get_sub_tx_allowlist_storage_key(),
+            )?
+            .map(|hashes| format!("[{}]", hashes.join(", "))),
+        ParameterChange::MaxSignaturesPerTransaction(_) => storage
+            .read::<u8>(
+                &params_storage::get_max_signatures_per_transaction_key(),
+            )?
+            .map(|max| max.to_string()),
+        ParameterChange::MaxTxBytes(_) => storage
+            .read::<u32>(&params_storage::get_max_tx_bytes_key())?
+            .map(|max| max.to_string()),
+        ParameterChange::MaxBlockGas(_) => storage
+            .read::<u64>(&params_storage::get_max_block_gas_key())?
+            .map(|max| max.to_string()),
+        ParameterChange::InflationSplit(_) => {
+            namada_parameters::read_inflation_split(storage)?
+                .map(|split| split.to_string())
+        }
+        ParameterChange::WasmLimits(_) => {
+            Some(namada_parameters::read_wasm_limits(storage)?.to_string())
+        }
+    };
+
+    Ok(ParameterDiff {
+        parameter: change.name().to_string(),
+        current,
+        proposed: change.value(),
+    })
+}
+
+/// Apply the protocol parameter change of a passed proposal
+pub fn apply_parameter_change<S>(
+    storage: &mut S,
+    change: &ParameterChange,
+) -> StorageResult<()>
+where
+    S: StorageRead + StorageWrite,
+{
+    match change {
+        ParameterChange::MaxExpectedTimePerBlock(duration) => {
+            namada_parameters::update_max_expected_time_per_block_parameter(
+                storage, duration,
+            )
+        }
+        ParameterChange::EpochsPerYear(epochs) => {
+            namada_parameters::update_epochs_per_year_parameter(storage, epochs)
+        }
+        ParameterChange::NativeTxAllowlist(hashes) => {
+            namada_parameters::update_native_tx_allowlist_parameter(
+                storage,
+                hashes.clone(),
+            )
+        }
+        ParameterChange::SubTxAllowlist(hashes) => {
+            namada_parameters::update_sub_tx_allowlist_parameter(
+                storage,
+                hashes.clone(),
+            )
+        }
+        ParameterChange::MaxSignaturesPerTransaction(max) => {
+            namada_parameters::update_max_signature_per_tx(storage, *max)
+        }
+        ParameterChange::MaxTxBytes(max) => {
+            namada_parameters::update_max_tx_bytes_parameter(storage, *max)
+        }
+        ParameterChange::MaxBlockGas(max) => {
+            namada_parameters::update_max_block_gas_parameter(storage, *max)
+        }
+        ParameterChange::InflationSplit(split) => {
+            namada_parameters::update_inflation_split_parameter(storage, split)
+        }
+        ParameterChange::WasmLimits(limits) => {
+            namada_parameters::update_wasm_limits_parameter(storage, limits)
+        }
+    }
+}
+
+/// Apply the wasm allowlist updates of a passed proposal. As an empty
+/// allowlist allows any code, the updates are not applied if they would empty
+/// an allowlist or restrict an empty one without setting its full set of
+/// hashes. Returns whether the updates were applied.
+pub fn apply_wasm_allowlist_updates<S>(
+    storage: &mut S,
+    updates: &[WasmAllowlistUpdate],
+) -> StorageResult<bool>
+where
+    S: StorageRead + StorageWrite,
+{
+    let tx_allowlist_key =
+        namada_parameters::storage::get_tx_allowlist_storage_key();
+    let vp_allowlist_key =
+        namada_parameters::storage::get_vp_allowlist_storage_key();
+    let mut tx_allowlist: Vec<String> =
+        storage.read(&tx_allowlist_key)?.unwrap_or_default();
+    let mut vp_allowlist: Vec<String> =
+        storage.read(&vp_allowlist_key)?.unwrap_or_default();
+
+    for update in updates {
+        let allowlist = if update.is_tx() {
+            &mut tx_allowlist
+        } else {
+            &mut vp_allowlist
+        };
+        if !update.update().apply(allowlist) {
+            return Ok(false);
+        }
+    }
+
+    namada_parameters::update_tx_allowlist_parameter(storage, tx_allowlist)?;
+    namada_parameters::update_vp_allowlist_parameter(storage, vp_allowlist)?;
+
+    Ok(true)
+}
+
+/// Pause a subsystem following a passed emergency halt proposal. The halt
+/// expires after the given number of epochs, unless an already active halt
+/// of the same subsystem lasts longer. Returns the expiry epoch.
+pub fn halt_subsystem<S>(
+    storage: &mut S,
+    halt: &EmergencyHalt,
+) -> StorageResult<Epoch>
+where
+    S: StorageRead + StorageWrite,
+{
+    let key = governance_keys::get_halt_key(&halt.subsystem);
+    let expiry = storage.get_block_epoch()? + halt.duration;
+    let expiry = match storage.read::<Epoch>(&key)? {
+        Some(active_expiry) => expiry.max(active_expiry),
+        None => expiry,
+    };
+    storage.write(&key, expiry)?;
+
+    Ok(expiry)
+}
+
+/// Check if a subsystem is currently paused by an emergency halt. A halt
+/// expires automatically at its expiry epoch.
+pub fn is_subsystem_halted<S>(
+    storage: &S,
+    subsystem: &HaltedSubsystem,
+) -> StorageResult<bool>
+where
+    S: StorageRead,
+{
+    let key = governance_keys::get_halt_key(subsystem);
+    match storage.read::<Epoch>(&key)? {
+        Some(expiry) => Ok(storage.get_block_epoch()? < expiry),
+        None => Ok(false),
+    }
+}
+
+/// A proposal vote transaction. A vote cast again while the voting window is
+/// open overwrites the previous one.
 pub fn vote_proposal<S>(
     storage: &mut S,
     data: VoteProposalData,
@@ -116,17 +346,201 @@ pub fn vote_proposal<S>(
 where
     S: StorageRead + StorageWrite,
 {
+    let voter_vote_key =
+        governance_keys::get_voter_vote_key(&data.voter, data.id);
+    let mut validators: BTreeSet<Address> =
+        storage.read(&voter_vote_key)?.unwrap_or_default();
     for delegation in data.delegations {
         let vote_key = governance_keys::get_vote_proposal_key(
             data.id,
             data.voter.clone(),
-            delegation,
+            delegation.clone(),
         );
         storage.write(&vote_key, data.vote.clone())?;
+        validators.insert(delegation);
+    }
+    storage.write(&voter_vote_key, validators)
+}
+
+/// A transaction withdrawing the vote of a voter while the voting window is
+/// open.
+pub fn withdraw_vote<S>(
+    storage: &mut S,
+    data: VoteWithdrawalData,
+) -> StorageResult<()>
+where
+    S: StorageRead + StorageWrite,
+{
+    let voter_vote_key =
+        governance_keys::get_voter_vote_key(&data.voter, data.id);
+    let mut validators: BTreeSet<Address> =
+        storage.read(&voter_vote_key)?.unwrap_or_default();
+    for delegation in data.delegations {
+        let vote_key = governance_keys::get_vote_proposal_key(
+            data.id,
+            data.voter.clone(),
+            delegation.clone(),
+        );
+        storage.delete(&vote_key)?;
+        validators.remove(&delegation);
+    }
+    if validators.is_empty() {
+        storage.delete(&voter_vote_key)
+    } else {
+        storage.write(&voter_vote_key, validators)
+    }
+}
+
+/// A transaction submitting a batch of off-chain signed votes. The ballot
+/// signatures are verified by the governance VP. Each ballot is recorded as
+/// consumed, so that it cannot be submitted again after the vote is withdrawn.
+pub fn vote_proposal_batch<S>(
+    storage: &mut S,
+    data: VoteProposalBatchData,
+) -> StorageResult<()>
+where
+    S: StorageRead + StorageWrite,
+{
+    let chain_id = ChainId(storage.get_chain_id()?);
+    for ballot in data.ballots {
+        let consumed_ballot_key = governance_keys::get_consumed_ballot_key(
+            &ballot.compute_hash(&chain_id),
+        );
+        if storage.has_key(&consumed_ballot_key)? {
+            return Err(StorageError::new_const(
+                "The signed ballot has already been submitted",
+            ));
+        }
+        storage.write(&consumed_ballot_key, ballot.data.id)?;
+        vote_proposal(storage, ballot.data)?;
+    }
+    Ok(())
+}
+
+/// A vote delegation transaction. The delegation (or its revocation) applies
+/// to the proposals tallied from the current epoch onward. It supersedes the
+/// previous delegations of the delegator for the same kinds of proposals,
+/// which are pruned together with their entries in the index of the
+/// delegations by delegate, as the proposals are never tallied at a past
+/// epoch.
+pub fn delegate_vote<S>(
+    storage: &mut S,
+    data: VoteDelegationData,
+) -> StorageResult<()>
+where
+    S: StorageRead + StorageWrite,
+{
+    if data.delegate.as_ref() == Some(&data.delegator) {
+        return Err(StorageError::new_const(
+            "Voting power cannot be delegated to oneself",
+        ));
+    }
+    let current_epoch = storage.get_block_epoch()?;
+    for kind in data.kinds {
+        let prefix = governance_keys::get_vote_delegation_kind_prefix(
+            &data.delegator,
+            kind,
+        );
+        let superseded: Vec<_> =
+            iter_prefix::<Option<Address>>(storage, &prefix)?
+                .collect::<StorageResult<_>>()?;
+        for (delegation_key, delegate) in superseded {
+            storage.delete(&delegation_key)?;
+            if let Some(delegate) = delegate {
+                storage.delete(&governance_keys::get_vote_delegator_key(
+                    &delegate,
+                    kind,
+                    &data.delegator,
+                ))?;
+            }
+        }
+        if let Some(delegate) = &data.delegate {
+            let delegation_key = governance_keys::get_vote_delegation_key(
+                &data.delegator,
+                kind,
+                current_epoch,
+            );
+            storage.write(&delegation_key, Some(delegate.clone()))?;
+            let delegator_key = governance_keys::get_vote_delegator_key(
+                delegate,
+                kind,
+                &data.delegator,
+            );
+            storage.write(&delegator_key, current_epoch)?;
+        }
     }
     Ok(())
 }
 
+/// Read the delegators of a delegate for a kind of proposals whose delegation
+/// is in effect at the given epoch, from the index of the delegations by
+/// delegate
+pub fn get_vote_delegators<S>(
+    storage: &S,
+    delegate: &Address,
+    kind: ProposalKind,
+    epoch: Epoch,
+) -> StorageResult<Vec<Address>>
+where
+    S: StorageRead,
+{
+    let prefix = governance_keys::get_vote_delegators_prefix(delegate, kind);
+    let mut delegators = vec![];
+    for entry in iter_prefix::<Epoch>(storage, &prefix)? {
+        let (key, delegation_epoch) = entry?;
+        if delegation_epoch > epoch {
+            continue;
+        }
+        if let Some((_, _, delegator)) =
+            governance_keys::is_vote_delegator_key(&key)
+        {
+            delegators.push(delegator.clone());
+        }
+    }
+    Ok(delegators)
+}
+
+/// Read the vote delegations for a kind of proposals in effect at the given
+/// epoch, as a map from the delegator to its delegate. The superseded
+/// delegations are pruned, so the delegations of a past epoch may be
+/// incomplete.
+pub fn get_vote_delegations<S>(
+    storage: &S,
+    kind: ProposalKind,
+    epoch: Epoch,
+) -> StorageResult<HashMap<Address, Address>>
+where
+    S: StorageRead,
+{
+    let prefix = governance_keys::get_vote_delegation_prefix();
+    let mut latest: HashMap<Address, (Epoch, Option<Address>)> =
+        HashMap::default();
+    for entry in iter_prefix::<Option<Address>>(storage, &prefix)? {
+        let (key, delegate) = entry?;
+        let (delegator, delegation_kind, delegation_epoch) =
+            match governance_keys::is_vote_delegation_key(&key) {
+                Some(data) => data,
+                None => continue,
+            };
+        if delegation_kind != kind || delegation_epoch > epoch {
+            continue;
+        }
+        match latest.get(delegator) {
+            Some((latest_epoch, _)) if *latest_epoch > delegation_epoch => {}
+            _ => {
+                latest.insert(delegator.clone(), (delegation_epoch, delegate));
+            }
+        }
+    }
+
+    Ok(latest
+        .into_iter()
+        .filter_map(|(delegator, (_, delegate))| {
+            delegate.map(|delegate| (delegator, delegate))
+        })
+        .collect())
+}
+
 /// Write the proposal result to storage.
 pub fn write_proposal_result<S>(
     storage: &mut S,
@@ -151,32 +565,57 @@ where
 {
     let author_key = governance_keys::get_author_key(id);
     let content = governance_keys::get_content_key(id);
+    let content_hash_key = governance_keys::get_content_hash_key(id);
+    let content_uri_key = governance_keys::get_content_uri_key(id);
+    let voting_mode_key = governance_keys::get_voting_mode_key(id);
     let start_epoch_key = governance_keys::get_voting_start_epoch_key(id);
     let end_epoch_key = governance_keys::get_voting_end_epoch_key(id);
     let grace_epoch_key = governance_keys::get_grace_epoch_key(id);
     let proposal_type_key = governance_keys::get_proposal_type_key(id);
+    let expedited_key = governance_keys::get_expedited_key(id);
 
     let author: Option<Address> = storage.read(&author_key)?;
     let content: Option<BTreeMap<String, String>> = storage.read(&content)?;
+    let content_hash: Option<Hash> = storage.read(&content_hash_key)?;
+    let content_uri: Option<String> = storage.read(&content_uri_key)?;
+    let voting_mode: Option<VotingMode> = storage.read(&voting_mode_key)?;
     let voting_start_epoch: Option<Epoch> = storage.read(&start_epoch_key)?;
     let voting_end_epoch: Option<Epoch> = storage.read(&end_epoch_key)?;
     let grace_epoch: Option<Epoch> = storage.read(&grace_epoch_key)?;
     let proposal_type: Option<ProposalType> =
         storage.read(&proposal_type_key)?;
+    let expedited_end_epoch: Option<Epoch> = storage.read(&expedited_key)?;
 
     let proposal = proposal_type.map(|proposal_type| StorageProposal {
         id,
         content: content.unwrap(),
+        content_hash,
+        content_uri,
+        voting_mode: voting_mode.unwrap_or_default(),
         author: author.unwrap(),
         r#type: proposal_type,
         voting_start_epoch: voting_start_epoch.unwrap(),
         voting_end_epoch: voting_end_epoch.unwrap(),
         grace_epoch: grace_epoch.unwrap(),
+        expedited_end_epoch,
     });
 
     Ok(proposal)
 }
 
+/// Read the voting mode of a proposal, defaulting to the linear one
+pub fn get_proposal_voting_mode<S>(
+    storage: &S,
+    proposal_id: u64,
+) -> StorageResult<VotingMode>
+where
+    S: StorageRead,
+{
+    let voting_mode_key = governance_keys::get_voting_mode_key(proposal_id);
+    let voting_mode: Option<VotingMode> = storage.read(&voting_mode_key)?;
+    Ok(voting_mode.unwrap_or_default())
+}
+
 /// Query all the votes for a proposal_id
 pub fn get_proposal_votes<S>(
     storage: &S,
@@ -215,6 +654,27 @@ where
     Ok(votes)
 }
 
+/// Read the index of the votes cast by a voter, as a map from the proposal id
+/// to the validators the voter voted through
+pub fn get_voter_votes<S>(
+    storage: &S,
+    voter: &Address,
+) -> StorageResult<BTreeMap<u64, BTreeSet<Address>>>
+where
+    S: StorageRead,
+{
+    let prefix = governance_keys::get_voter_votes_prefix(voter);
+    let mut votes = BTreeMap::new();
+    for entry in iter_prefix::<BTreeSet<Address>>(storage, &prefix)? {
+        let (key, validators) = entry?;
+        if let Some((_, proposal_id)) = governance_keys::is_voter_vote_key(&key)
+        {
+            votes.insert(proposal_id, validators);
+        }
+    }
+    Ok(votes)
+}
+
 /// Check if an accepted proposal is being executed
 pub fn is_proposal_accepted<S>(
     storage: &S,
@@ -274,6 +734,10 @@ where
     let min_proposal_fund: token::Amount =
         storage.read(&key)?.expect("Parameter should be defined.");
 
+    let key = governance_keys::get_min_signaling_proposal_fund_key();
+    let min_signaling_proposal_fund: token::Amount =
+        storage.read(&key)?.expect("Parameter should be defined.");
+
     let key = governance_keys::get_min_proposal_grace_epoch_key();
     let min_proposal_grace_epochs: u64 =
         storage.read(&key)?.expect("Parameter should be defined.");
@@ -284,16 +748,57 @@ where
 
     let max_proposal_period: u64 = get_max_proposal_period(storage)?;
 
+    let veto_threshold: Dec = get_veto_threshold(storage)?;
+
+    let expedited_voting_period: u64 = get_expedited_voting_period(storage)?;
+
+    let key = governance_keys::get_expedited_quorum_key();
+    let expedited_quorum: Dec =
+        storage.read(&key)?.expect("Parameter should be defined.");
+
+    let key = governance_keys::get_expedited_threshold_key();
+    let expedited_threshold: Dec =
+        storage.read(&key)?.expect("Parameter should be defined.");
+
+    let deposit_policy: DepositPolicy = get_deposit_policy(storage)?;
+
     Ok(GovernanceParameters {
         min_proposal_fund,
+        min_signaling_proposal_fund,
         max_proposal_code_size,
         min_proposal_voting_period,
         max_proposal_period,
         max_proposal_content_size,
         min_proposal_grace_epochs,
+        veto_threshold,
+        expedited_voting_period,
+        expedited_quorum,
+        expedited_threshold,
+        deposit_policy,
     })
 }
 
+/// Get the minimum amount of funds to lock for a proposal of the given type.
+/// Signaling proposals, which don't execute anything, require a reduced
+/// deposit.
+pub fn get_min_proposal_fund<S>(
+    storage: &S,
+    proposal_type: &ProposalType,
+) -> StorageResult<token::Amount>
+where
+    S: StorageRead,
+{
+    let key = match proposal_type {
+        ProposalType::Signaling => {
+            governance_keys::get_min_signaling_proposal_fund_key()
+        }
+        _ => governance_keys::get_min_proposal_fund_key(),
+    };
+    let min_proposal_fund: token::Amount =
+        storage.read(&key)?.expect("Parameter should be defined.");
+    Ok(min_proposal_fund)
+}
+
 /// Get governance "max_proposal_period" parameter
 pub fn get_max_proposal_period<S>(storage: &S) -> StorageResult<u64>
 where
@@ -305,6 +810,80 @@ where
     Ok(max_proposal_period)
 }
 
+/// Get governance "veto_threshold" parameter
+pub fn get_veto_threshold<S>(storage: &S) -> StorageResult<Dec>
+where
+    S: StorageRead,
+{
+    let key = governance_keys::get_veto_threshold_key();
+    let veto_threshold: Dec =
+        storage.read(&key)?.expect("Parameter should be defined.");
+    Ok(veto_threshold)
+}
+
+/// Get governance "expedited_voting_period" parameter
+pub fn get_expedited_voting_period<S>(storage: &S) -> StorageResult<u64>
+where
+    S: StorageRead,
+{
+    let key = governance_keys::get_expedited_voting_period_key();
+    let expedited_voting_period: u64 =
+        storage.read(&key)?.expect("Parameter should be defined.");
+    Ok(expedited_voting_period)
+}
+
+/// Get governance "deposit_policy" parameter
+pub fn get_deposit_policy<S>(storage: &S) -> StorageResult<DepositPolicy>
+where
+    S: StorageRead,
+{
+    let key = governance_keys::get_deposit_policy_key();
+    let deposit_policy: DepositPolicy =
+        storage.read(&key)?.expect("Parameter should be defined.");
+    Ok(deposit_policy)
+}
+
+/// Get the epoch at which the expedited voting period of a proposal ends, if
+/// the proposal is expedited
+pub fn get_expedited_end_epoch<S>(
+    storage: &S,
+    proposal_id: u64,
+) -> StorageResult<Option<Epoch>>
+where
+    S: StorageRead,
+{
+    let key = governance_keys::get_expedited_key(proposal_id);
+    storage.read(&key)
+}
+
+/// Close an expedited proposal which passed before the end of its voting
+/// period: voting is stopped at the end of the expedited voting period and
+/// the proposal isn't tallied again at its grace epoch
+pub fn close_expedited_proposal<S>(
+    storage: &mut S,
+    proposal_id: u64,
+    expedited_end_epoch: Epoch,
+) -> StorageResult<()>
+where
+    S: StorageRead + StorageWrite,
+{
+    let grace_epoch_key = governance_keys::get_grace_epoch_key(proposal_id);
+    let grace_epoch: Epoch = storage
+        .read(&grace_epoch_key)?
+        .expect("Grace epoch should be defined.");
+    let committing_proposals_key =
+        governance_keys::get_committing_proposals_key(
+            proposal_id,
+            grace_epoch.0,
+        );
+    storage.delete(&committing_proposals_key)?;
+
+    let voting_end_epoch_key =
+        governance_keys::get_voting_end_epoch_key(proposal_id);
+    storage.write(&voting_end_epoch_key, expedited_end_epoch)?;
+    storage.write(&grace_epoch_key, storage.get_block_epoch()?)
+}
+
 /// Get governance proposal result stored in storage if proposal ended
 pub fn get_proposal_result<S>(
     storage: &S,
@@ -317,3 +896,146 @@ where
     let proposal_result: Option<ProposalResult> = storage.read(&key)?;
     Ok(proposal_result)
 }
+
+#[cfg(test)]
+mod test {
+    use namada_core::types::address::testing::{
+        established_address_1, established_address_2, established_address_3,
+    };
+    use namada_state::testing::TestWlStorage;
+
+    use super::*;
+    use crate::storage::proposal::AllowlistUpdate;
+
+    #[test]
+    fn test_apply_allowlist_change() {
+        let mut wl_storage = TestWlStorage::default();
+        let allowlist_key =
+            namada_parameters::storage::get_sub_tx_allowlist_storage_key();
+        let hash = Hash::sha256(b"tx_transfer.wasm").to_string().to_lowercase();
+        let change = ParameterChange::SubTxAllowlist(vec![hash.clone()]);
+        assert!(change.is_valid());
+        let diff = parameter_change_diff(&wl_storage, &change).unwrap();
+        assert!(!diff.is_unchanged());
+
+        apply_parameter_change(&mut wl_storage, &change).unwrap();
+        let allowlist: Option<Vec<String>> =
+            wl_storage.read(&allowlist_key).unwrap();
+        assert_eq!(allowlist, Some(vec![hash]));
+        // The applied allowlist is the proposed one
+        let diff = parameter_change_diff(&wl_storage, &change).unwrap();
+        assert!(diff.is_unchanged());
+    }
+
+    #[test]
+    fn test_apply_wasm_allowlist_updates() {
+        let mut wl_storage = TestWlStorage::default();
+        let tx_allowlist_key =
+            namada_parameters::storage::get_tx_allowlist_storage_key();
+        let read_tx_allowlist = |wl_storage: &TestWlStorage| {
+            wl_storage
+                .read::<Vec<String>>(&tx_allowlist_key)
+                .unwrap()
+                .unwrap_or_default()
+        };
+        let hash = Hash::sha256(b"tx_transfer.wasm").to_string().to_lowercase();
+        let other_hash =
+            Hash::sha256(b"tx_bond.wasm").to_string().to_lowercase();
+
+        // Adding a hash to the empty allowlist, which allows any tx, would
+        // restrict it to that hash
+        let add = WasmAllowlistUpdate::Tx(AllowlistUpdate::Add(hash.clone()));
+        assert!(!apply_wasm_allowlist_updates(&mut wl_storage, &[add]).unwrap());
+        assert!(read_tx_allowlist(&wl_storage).is_empty());
+
+        // The full set of allowed hashes must be given instead
+        let set = WasmAllowlistUpdate::Tx(AllowlistUpdate::Set(vec![
+            hash.clone(),
+            other_hash.clone(),
+        ]));
+        assert!(apply_wasm_allowlist_updates(&mut wl_storage, &[set]).unwrap());
+        let allowlist: BTreeSet<String> =
+            read_tx_allowlist(&wl_storage).into_iter().collect();
+        assert_eq!(
+            allowlist,
+            BTreeSet::from([hash.clone(), other_hash.clone()])
+        );
+
+        // The allowlist can't be emptied
+        let remove_all = [hash.clone(), other_hash.clone()]
+            .map(|hash| WasmAllowlistUpdate::Tx(AllowlistUpdate::Remove(hash)));
+        let applied =
+            apply_wasm_allowlist_updates(&mut wl_storage, &remove_all).unwrap();
+        assert!(!applied);
+        assert_eq!(read_tx_allowlist(&wl_storage).len(), 2);
+
+        let remove = WasmAllowlistUpdate::Tx(AllowlistUpdate::Remove(hash));
+        assert!(
+            apply_wasm_allowlist_updates(&mut wl_storage, &[remove]).unwrap()
+        );
+        assert_eq!(read_tx_allowlist(&wl_storage), vec![other_hash]);
+    }
+
+    #[test]
+    fn test_delegate_vote() {
+        let mut wl_storage = TestWlStorage::default();
+        let alice = established_address_1();
+        let bob = established_address_2();
+        let carol = established_address_3();
+        let kind = ProposalKind::Default;
+        let delegate = |wl_storage: &mut TestWlStorage,
+                        delegate: Option<&Address>| {
+            delegate_vote(
+                wl_storage,
+                VoteDelegationData {
+                    delegator: alice.clone(),
+                    delegate: delegate.cloned(),
+                    kinds: BTreeSet::from([kind]),
+                },
+            )
+            .unwrap()
+        };
+        let delegations_count = |wl_storage: &TestWlStorage| {
+            let prefix =
+                governance_keys::get_vote_delegation_kind_prefix(&alice, kind);
+            iter_prefix::<Option<Address>>(wl_storage, &prefix)
+                .unwrap()
+                .count()
+        };
+
+        wl_storage.storage.block.epoch = Epoch(1);
+        delegate(&mut wl_storage, Some(&bob));
+        assert_eq!(
+            get_vote_delegators(&wl_storage, &bob, kind, Epoch(1)).unwrap(),
+            vec![alice.clone()]
+        );
+        // The delegation doesn't apply to a past epoch
+        assert!(get_vote_delegators(&wl_storage, &bob, kind, Epoch(0))
+            .unwrap()
+            .is_empty());
+
+        // A new delegation supersedes the previous one, which is pruned
+        wl_storage.storage.block.epoch = Epoch(2);
+        delegate(&mut wl_storage, Some(&carol));
+        assert_eq!(delegations_count(&wl_storage), 1);
+        assert!(get_vote_delegators(&wl_storage, &bob, kind, Epoch(2))
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            get_vote_delegators(&wl_storage, &carol, kind, Epoch(2)).unwrap(),
+            vec![alice.clone()]
+        );
+        assert_eq!(
+            get_vote_delegations(&wl_storage, kind, Epoch(2)).unwrap(),
+            HashMap::from([(alice.clone(), carol.clone())])
+        );
+
+        // A revocation deletes the delegation
+        wl_storage.storage.block.epoch = Epoch(3);
+        delegate(&mut wl_storage, None);
+        assert_eq!(delegations_count(&wl_storage), 0);
+        assert!(get_vote_delegators(&wl_storage, &carol, kind, Epoch(3))
+            .unwrap()
+            .is_empty());
+    }
+}
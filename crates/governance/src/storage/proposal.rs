@@ -1,21 +1,50 @@
 use std::collections::{BTreeMap, BTreeSet};
 use std::fmt::Display;
+use std::fs::File;
+use std::path::PathBuf;
+use std::str::FromStr;
 
-use borsh::{BorshDeserialize, BorshSerialize};
+use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
+use namada_core::borsh::BorshSerializeExt;
 use namada_core::ibc::core::host::types::identifiers::{ChannelId, PortId};
+use namada_core::types::account::AccountPublicKeysMap;
 use namada_core::types::address::Address;
+use namada_core::types::chain::ChainId;
 use namada_core::types::hash::Hash;
-use namada_core::types::storage::Epoch;
+use namada_core::types::key::common;
+use namada_core::types::parameters::{InflationSplit, WasmLimits};
+use namada_core::types::sign::SignatureIndex;
+use namada_core::types::storage::{Epoch, Key};
+use namada_core::types::time::DurationSecs;
+use namada_core::types::token::MaspParams;
 use namada_trans_token::Amount;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+use super::keys;
 use super::vote::ProposalVote;
+use crate::cli::offline::{
+    compute_signatures_index, compute_total_valid_signatures,
+};
 use crate::cli::onchain::{
-    DefaultProposal, PgfAction, PgfContinuous, PgfFundingProposal, PgfRetro,
-    PgfSteward, PgfStewardProposal, StewardsUpdate,
+    DefaultProposal, EmergencyHaltProposal, MaspRewardsProposal,
+    MaspRewardsUpdate, ParameterChangeProposal, PgfAction, PgfContinuous,
+    PgfFundingProposal, PgfRetro, PgfSteward, PgfStewardProposal,
+    SignalingProposal, StewardsUpdate, WasmAllowlistProposal,
 };
-use crate::utils::{ProposalStatus, TallyType};
+use crate::utils::{ProposalStatus, TallyType, VotingMode};
+
+/// The maximum length of the URI where a proposal content is published
+pub const MAX_CONTENT_URI_LENGTH: usize = 256;
+
+/// The maximum number of epochs a subsystem can be paused by a single
+/// emergency halt proposal
+pub const MAX_EMERGENCY_HALT_EPOCHS: u64 = 30;
+
+/// The JSON schema of the typed content of a proposal, i.e. of the JSON
+/// serialization of a [`ProposalType`]
+pub const PROPOSAL_TYPE_JSON_SCHEMA: &str =
+    include_str!("proposal_type.schema.json");
 
 #[allow(missing_docs)]
 #[derive(Debug, Error)]
@@ -31,6 +60,7 @@ pub enum ProposalError {
     PartialEq,
     BorshSerialize,
     BorshDeserialize,
+    BorshSchema,
     Serialize,
     Deserialize,
 )]
@@ -39,6 +69,11 @@ pub struct InitProposalData {
     pub id: u64,
     /// The proposal content
     pub content: Hash,
+    /// The sha256 hash of the proposal content, checked against the content
+    /// committed at initialization
+    pub content_hash: Hash,
+    /// The URI where the proposal content is published (e.g. on IPFS)
+    pub content_uri: Option<String>,
     /// The proposal author address
     pub author: Address,
     /// The proposal type
@@ -49,13 +84,18 @@ pub struct InitProposalData {
     pub voting_end_epoch: Epoch,
     /// The epoch from which this changes are executed
     pub grace_epoch: Epoch,
+    /// Whether the proposal is expedited, i.e. also tallied at the end of a
+    /// shorter voting period with a higher quorum and threshold
+    pub expedited: bool,
+    /// The function applied to the stake of the voters to tally the proposal
+    pub voting_mode: VotingMode,
 }
 
 impl InitProposalData {
     /// Get the hash of the corresponding extra data section
     pub fn get_section_code_hash(&self) -> Option<Hash> {
         match self.r#type {
-            ProposalType::Default(hash) => hash,
+            ProposalType::WasmCodeUpgrade(hash) => Some(hash),
             _ => None,
         }
     }
@@ -82,6 +122,171 @@ pub struct VoteProposalData {
     pub delegations: Vec<Address>,
 }
 
+/// A vote signed off-chain by the voter, so that it can be submitted on its
+/// behalf by any other account
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    Serialize,
+    Deserialize,
+)]
+pub struct SignedVoteProposalData {
+    /// The vote
+    pub data: VoteProposalData,
+    /// The voter signatures over the vote
+    pub signatures: BTreeSet<SignatureIndex>,
+}
+
+impl SignedVoteProposalData {
+    /// Create an unsigned ballot for a vote
+    pub fn new(data: VoteProposalData) -> Self {
+        Self {
+            data,
+            signatures: BTreeSet::default(),
+        }
+    }
+
+    /// Compute the hash signed by the voter. The chain id is included to
+    /// prevent replaying the ballot on another chain.
+    pub fn compute_hash(&self, chain_id: &ChainId) -> Hash {
+        Hash::sha256((&self.data, chain_id).serialize_to_vec())
+    }
+
+    /// Sign the ballot
+    pub fn sign(
+        self,
+        keypairs: Vec<common::SecretKey>,
+        account_public_keys_map: &AccountPublicKeysMap,
+        chain_id: &ChainId,
+    ) -> Self {
+        let ballot_hash = self.compute_hash(chain_id);
+        let signatures = compute_signatures_index(
+            &keypairs,
+            account_public_keys_map,
+            &ballot_hash,
+        );
+
+        Self { signatures, ..self }
+    }
+
+    /// Check whether the signatures are valid or not
+    pub fn check_signature(
+        &self,
+        account_public_keys_map: &AccountPublicKeysMap,
+        threshold: u8,
+        chain_id: &ChainId,
+    ) -> bool {
+        if self.signatures.len() < threshold as usize {
+            return false;
+        }
+        let ballot_hash = self.compute_hash(chain_id);
+
+        let valid_signatures = compute_total_valid_signatures(
+            &self.signatures,
+            account_public_keys_map,
+            &ballot_hash,
+        );
+
+        valid_signatures >= threshold
+    }
+
+    /// Serialize the ballot to file. Returns the filename if successful.
+    pub fn serialize(
+        &self,
+        output_folder: Option<PathBuf>,
+    ) -> Result<String, serde_json::Error> {
+        let ballot_filename =
+            format!("ballot_{}_{}.json", self.data.id, self.data.voter);
+        let filepath = match output_folder {
+            Some(base_path) => {
+                base_path.join(ballot_filename).to_str().unwrap().to_owned()
+            }
+            None => ballot_filename,
+        };
+        let out = File::create(&filepath).unwrap();
+        serde_json::to_writer_pretty(out, self)?;
+
+        Ok(filepath)
+    }
+}
+
+/// A tx data type to hold a batch of off-chain signed votes
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    Serialize,
+    Deserialize,
+)]
+pub struct VoteProposalBatchData {
+    /// The signed ballots
+    pub ballots: Vec<SignedVoteProposalData>,
+}
+
+impl VoteProposalBatchData {
+    /// Find the ballot that wrote the given vote key, if any
+    pub fn find_ballot(
+        &self,
+        vote_key: &Key,
+    ) -> Option<&SignedVoteProposalData> {
+        let proposal_id = keys::get_proposal_id(vote_key)?;
+        let voter = keys::get_voter_address(vote_key)?;
+        let delegation = keys::get_vote_delegation_address(vote_key)?;
+
+        self.ballots.iter().find(|ballot| {
+            ballot.data.id == proposal_id
+                && &ballot.data.voter == voter
+                && ballot.data.delegations.contains(delegation)
+        })
+    }
+}
+
+/// A tx data type to withdraw the vote cast on a proposal while its voting
+/// window is still open
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    Serialize,
+    Deserialize,
+)]
+pub struct VoteWithdrawalData {
+    /// The proposal id
+    pub id: u64,
+    /// The address of the voter withdrawing its vote
+    pub voter: Address,
+    /// Validators to who the voter has delegations to
+    pub delegations: Vec<Address>,
+}
+
+/// A tx data type to delegate the governance voting power of an account to
+/// another address, or to revoke a previous delegation
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    Serialize,
+    Deserialize,
+)]
+pub struct VoteDelegationData {
+    /// The address delegating its voting power
+    pub delegator: Address,
+    /// The address receiving the voting power, `None` to revoke the
+    /// delegation
+    pub delegate: Option<Address>,
+    /// The kinds of proposals the delegation applies to
+    pub kinds: BTreeSet<ProposalKind>,
+}
+
 impl TryFrom<DefaultProposal> for InitProposalData {
     type Error = ProposalError;
 
@@ -89,11 +294,15 @@ impl TryFrom<DefaultProposal> for InitProposalData {
         Ok(InitProposalData {
             id: value.proposal.id,
             content: Hash::default(),
+            content_hash: Hash::default(),
+            content_uri: value.proposal.content_uri,
             author: value.proposal.author,
-            r#type: ProposalType::Default(None),
+            r#type: ProposalType::Text,
             voting_start_epoch: value.proposal.voting_start_epoch,
             voting_end_epoch: value.proposal.voting_end_epoch,
             grace_epoch: value.proposal.grace_epoch,
+            expedited: value.proposal.expedited,
+            voting_mode: value.proposal.voting_mode,
         })
     }
 }
@@ -102,17 +311,22 @@ impl TryFrom<PgfStewardProposal> for InitProposalData {
     type Error = ProposalError;
 
     fn try_from(value: PgfStewardProposal) -> Result<Self, Self::Error> {
+        let spending_cap = value.data.spending_cap;
         let extra_data =
             BTreeSet::<AddRemove<Address>>::try_from(value.data).unwrap();
 
         Ok(InitProposalData {
             id: value.proposal.id,
             content: Hash::default(),
+            content_hash: Hash::default(),
+            content_uri: value.proposal.content_uri,
             author: value.proposal.author,
-            r#type: ProposalType::PGFSteward(extra_data),
+            r#type: ProposalType::PGFSteward(extra_data, spending_cap),
             voting_start_epoch: value.proposal.voting_start_epoch,
             voting_end_epoch: value.proposal.voting_end_epoch,
             grace_epoch: value.proposal.grace_epoch,
+            expedited: value.proposal.expedited,
+            voting_mode: value.proposal.voting_mode,
         })
     }
 }
@@ -135,71 +349,713 @@ impl TryFrom<PgfFundingProposal> for InitProposalData {
             })
             .collect::<BTreeSet<PGFAction>>();
 
-        let retro_fundings = value
-            .data
-            .retro
-            .iter()
-            .cloned()
-            .map(PGFAction::Retro)
-            .collect::<BTreeSet<PGFAction>>();
+        let retro_fundings = value
+            .data
+            .retro
+            .iter()
+            .cloned()
+            .map(PGFAction::Retro)
+            .collect::<BTreeSet<PGFAction>>();
+
+        let stream_fundings = value
+            .data
+            .streams
+            .iter()
+            .cloned()
+            .map(PGFAction::Stream)
+            .collect::<BTreeSet<PGFAction>>();
+
+        let grant_fundings = value
+            .data
+            .grants
+            .iter()
+            .cloned()
+            .map(PGFAction::Grant)
+            .collect::<BTreeSet<PGFAction>>();
+
+        continous_fundings.extend(retro_fundings);
+        continous_fundings.extend(stream_fundings);
+        continous_fundings.extend(grant_fundings);
+
+        Ok(InitProposalData {
+            id: value.proposal.id,
+            content: Hash::default(),
+            content_hash: Hash::default(),
+            content_uri: value.proposal.content_uri,
+            author: value.proposal.author,
+            r#type: ProposalType::PGFPayment(continous_fundings), /* here continous_fundings is contains also the retro funding */
+            voting_start_epoch: value.proposal.voting_start_epoch,
+            voting_end_epoch: value.proposal.voting_end_epoch,
+            grace_epoch: value.proposal.grace_epoch,
+            expedited: value.proposal.expedited,
+            voting_mode: value.proposal.voting_mode,
+        })
+    }
+}
+
+impl TryFrom<MaspRewardsProposal> for InitProposalData {
+    type Error = ProposalError;
+
+    fn try_from(value: MaspRewardsProposal) -> Result<Self, Self::Error> {
+        Ok(InitProposalData {
+            id: value.proposal.id,
+            content: Hash::default(),
+            content_hash: Hash::default(),
+            content_uri: value.proposal.content_uri,
+            author: value.proposal.author,
+            r#type: ProposalType::MaspRewards(value.data),
+            voting_start_epoch: value.proposal.voting_start_epoch,
+            voting_end_epoch: value.proposal.voting_end_epoch,
+            grace_epoch: value.proposal.grace_epoch,
+            expedited: value.proposal.expedited,
+            voting_mode: value.proposal.voting_mode,
+        })
+    }
+}
+
+impl TryFrom<ParameterChangeProposal> for InitProposalData {
+    type Error = ProposalError;
+
+    fn try_from(value: ParameterChangeProposal) -> Result<Self, Self::Error> {
+        Ok(InitProposalData {
+            id: value.proposal.id,
+            content: Hash::default(),
+            content_hash: Hash::default(),
+            content_uri: value.proposal.content_uri,
+            author: value.proposal.author,
+            r#type: ProposalType::ParameterChange(value.data),
+            voting_start_epoch: value.proposal.voting_start_epoch,
+            voting_end_epoch: value.proposal.voting_end_epoch,
+            grace_epoch: value.proposal.grace_epoch,
+            expedited: value.proposal.expedited,
+            voting_mode: value.proposal.voting_mode,
+        })
+    }
+}
+
+impl TryFrom<EmergencyHaltProposal> for InitProposalData {
+    type Error = ProposalError;
+
+    fn try_from(value: EmergencyHaltProposal) -> Result<Self, Self::Error> {
+        Ok(InitProposalData {
+            id: value.proposal.id,
+            content: Hash::default(),
+            content_hash: Hash::default(),
+            content_uri: value.proposal.content_uri,
+            author: value.proposal.author,
+            r#type: ProposalType::EmergencyHalt(value.data),
+            voting_start_epoch: value.proposal.voting_start_epoch,
+            voting_end_epoch: value.proposal.voting_end_epoch,
+            grace_epoch: value.proposal.grace_epoch,
+            expedited: value.proposal.expedited,
+            voting_mode: value.proposal.voting_mode,
+        })
+    }
+}
+
+impl TryFrom<SignalingProposal> for InitProposalData {
+    type Error = ProposalError;
+
+    fn try_from(value: SignalingProposal) -> Result<Self, Self::Error> {
+        Ok(InitProposalData {
+            id: value.proposal.id,
+            content: Hash::default(),
+            content_hash: Hash::default(),
+            content_uri: value.proposal.content_uri,
+            author: value.proposal.author,
+            r#type: ProposalType::Signaling,
+            voting_start_epoch: value.proposal.voting_start_epoch,
+            voting_end_epoch: value.proposal.voting_end_epoch,
+            grace_epoch: value.proposal.grace_epoch,
+            expedited: value.proposal.expedited,
+            voting_mode: value.proposal.voting_mode,
+        })
+    }
+}
+
+impl TryFrom<WasmAllowlistProposal> for InitProposalData {
+    type Error = ProposalError;
+
+    fn try_from(value: WasmAllowlistProposal) -> Result<Self, Self::Error> {
+        Ok(InitProposalData {
+            id: value.proposal.id,
+            content: Hash::default(),
+            content_hash: Hash::default(),
+            content_uri: value.proposal.content_uri,
+            author: value.proposal.author,
+            r#type: ProposalType::WasmAllowlist(value.data),
+            voting_start_epoch: value.proposal.voting_start_epoch,
+            voting_end_epoch: value.proposal.voting_end_epoch,
+            grace_epoch: value.proposal.grace_epoch,
+            expedited: value.proposal.expedited,
+            voting_mode: value.proposal.voting_mode,
+        })
+    }
+}
+
+/// Storage struture for pgf fundings
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    BorshSerialize,
+    BorshDeserialize,
+    Serialize,
+    Deserialize,
+)]
+pub struct StoragePgfFunding {
+    /// The data about the pgf funding
+    pub detail: PGFTarget,
+    /// The id of the proposal that added this funding
+    pub id: u64,
+    /// The last epoch at which a funding stream is paid, none for a
+    /// continuous funding without end
+    pub end_epoch: Option<Epoch>,
+    /// The total amount paid so far by this funding
+    pub disbursed: Amount,
+}
+
+impl StoragePgfFunding {
+    /// Init a new pgf funding struct
+    pub fn new(detail: PGFTarget, id: u64, end_epoch: Option<Epoch>) -> Self {
+        Self {
+            detail,
+            id,
+            end_epoch,
+            disbursed: Amount::zero(),
+        }
+    }
+
+    /// Check if the funding must still be paid at the given epoch
+    pub fn is_active(&self, epoch: Epoch) -> bool {
+        self.end_epoch.map_or(true, |end_epoch| epoch <= end_epoch)
+    }
+}
+
+/// The type of a Proposal
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Serialize,
+    Deserialize,
+)]
+pub enum ProposalType {
+    /// Text governance proposal, whose content is only its off-chain text
+    Text,
+    /// Governance proposal upgrading the protocol with the wasm code of the
+    /// extra data section with the given hash. The code is stored apart from
+    /// the proposal once initialized.
+    WasmCodeUpgrade(Hash),
+    /// PGF stewards proposal, with the optional cap on the retro payments
+    /// that the proposals of the nominated steward can fund
+    PGFSteward(BTreeSet<AddRemove<Address>>, Option<Amount>),
+    /// PGF funding proposal
+    PGFPayment(BTreeSet<PGFAction>),
+    /// MASP rewards parameters update proposal
+    MaspRewards(MaspRewardsUpdate),
+    /// Protocol parameters change proposal
+    ParameterChange(Vec<ParameterChange>),
+    /// Emergency halt of a subsystem
+    EmergencyHalt(EmergencyHalt),
+    /// Update of the tx and vp wasm allowlists
+    WasmAllowlist(Vec<WasmAllowlistUpdate>),
+    /// Signaling proposal, only recording the outcome of the vote without
+    /// executing anything
+    Signaling,
+}
+
+impl ProposalType {
+    /// Get the kind of this proposal
+    pub fn kind(&self) -> ProposalKind {
+        match self {
+            ProposalType::Text | ProposalType::WasmCodeUpgrade(_) => {
+                ProposalKind::Default
+            }
+            ProposalType::PGFSteward(..) => ProposalKind::PgfSteward,
+            ProposalType::PGFPayment(_) => ProposalKind::PgfPayment,
+            ProposalType::MaspRewards(_) => ProposalKind::MaspRewards,
+            ProposalType::ParameterChange(_) => ProposalKind::ParameterChange,
+            ProposalType::EmergencyHalt(_) => ProposalKind::EmergencyHalt,
+            ProposalType::WasmAllowlist(_) => ProposalKind::WasmAllowlist,
+            ProposalType::Signaling => ProposalKind::Signaling,
+        }
+    }
+
+    /// Check that the typed content of the proposal is well-formed. This is
+    /// checked when the proposal is initialized and by the governance VP. The
+    /// proposals that execute something can't have an empty content, a
+    /// signaling proposal must be used instead.
+    pub fn is_valid_content(&self) -> bool {
+        match self {
+            ProposalType::ParameterChange(changes) => {
+                !changes.is_empty()
+                    && changes.iter().all(ParameterChange::is_valid)
+            }
+            ProposalType::EmergencyHalt(halt) => halt.is_valid(),
+            ProposalType::WasmAllowlist(updates) => {
+                WasmAllowlistUpdate::is_valid_batch(updates)
+            }
+            // A spending cap can only be given to a nominated steward
+            ProposalType::PGFSteward(stewards, Some(spending_cap)) => {
+                !spending_cap.is_zero()
+                    && stewards
+                        .iter()
+                        .any(|action| matches!(action, AddRemove::Add(_)))
+            }
+            ProposalType::PGFSteward(stewards, None) => !stewards.is_empty(),
+            ProposalType::PGFPayment(actions) => !actions.is_empty(),
+            ProposalType::MaspRewards(update) => {
+                !update.is_empty() && update.values().all(MaspParams::is_valid)
+            }
+            ProposalType::Text
+            | ProposalType::WasmCodeUpgrade(_)
+            | ProposalType::Signaling => true,
+        }
+    }
+}
+
+/// The kind of a proposal, regardless of its content. Voting power can be
+/// delegated separately for each kind.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Hash,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    BorshSerialize,
+    BorshDeserialize,
+    Serialize,
+    Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum ProposalKind {
+    /// Default governance proposal
+    Default,
+    /// PGF stewards proposal
+    PgfSteward,
+    /// PGF funding proposal
+    PgfPayment,
+    /// MASP rewards parameters update proposal
+    MaspRewards,
+    /// Protocol parameters change proposal
+    ParameterChange,
+    /// Emergency halt proposal
+    EmergencyHalt,
+    /// Wasm allowlist update proposal
+    WasmAllowlist,
+    /// Signaling proposal
+    Signaling,
+}
+
+impl ProposalKind {
+    /// All the proposal kinds
+    pub const ALL: [ProposalKind; 8] = [
+        ProposalKind::Default,
+        ProposalKind::PgfSteward,
+        ProposalKind::PgfPayment,
+        ProposalKind::MaspRewards,
+        ProposalKind::ParameterChange,
+        ProposalKind::EmergencyHalt,
+        ProposalKind::WasmAllowlist,
+        ProposalKind::Signaling,
+    ];
+}
+
+impl Display for ProposalKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProposalKind::Default => write!(f, "default"),
+            ProposalKind::PgfSteward => write!(f, "pgf_steward"),
+            ProposalKind::PgfPayment => write!(f, "pgf_payment"),
+            ProposalKind::MaspRewards => write!(f, "masp_rewards"),
+            ProposalKind::ParameterChange => write!(f, "parameter_change"),
+            ProposalKind::EmergencyHalt => write!(f, "emergency_halt"),
+            ProposalKind::WasmAllowlist => write!(f, "wasm_allowlist"),
+            ProposalKind::Signaling => write!(f, "signaling"),
+        }
+    }
+}
+
+impl FromStr for ProposalKind {
+    type Err = ProposalTypeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ProposalKind::ALL
+            .into_iter()
+            .find(|kind| kind.to_string() == s.trim().to_lowercase())
+            .ok_or(ProposalTypeError::InvalidProposalType)
+    }
+}
+
+/// A subsystem that can be paused by an emergency halt proposal
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Eq,
+    Hash,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Serialize,
+    Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum HaltedSubsystem {
+    /// IBC token transfers, in and out of the chain
+    IbcTransfers,
+    /// Shielded actions
+    Masp,
+    /// Transfers of a specific token
+    Token(Address),
+}
+
+impl Display for HaltedSubsystem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HaltedSubsystem::IbcTransfers => write!(f, "IBC transfers"),
+            HaltedSubsystem::Masp => write!(f, "MASP"),
+            HaltedSubsystem::Token(token) => write!(f, "Token {}", token),
+        }
+    }
+}
+
+/// The content of an emergency halt proposal
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Serialize,
+    Deserialize,
+)]
+pub struct EmergencyHalt {
+    /// The paused subsystem
+    pub subsystem: HaltedSubsystem,
+    /// The number of epochs the subsystem stays paused once the proposal
+    /// is executed
+    pub duration: u64,
+}
+
+impl EmergencyHalt {
+    /// Check that the halt expires after a bounded, non-zero duration
+    pub fn is_valid(&self) -> bool {
+        self.duration > 0 && self.duration <= MAX_EMERGENCY_HALT_EPOCHS
+    }
+}
+
+impl Display for EmergencyHalt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} paused for {} epochs", self.subsystem, self.duration)
+    }
+}
+
+/// An update of an allowlist of wasm code hashes. Note that an empty allowlist
+/// allows any code, so an empty allowlist can only be restricted by setting
+/// the full set of allowed hashes.
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Serialize,
+    Deserialize,
+)]
+pub enum AllowlistUpdate {
+    /// Add a code hash to a non-empty allowlist
+    Add(String),
+    /// Remove a code hash from the allowlist, which can't be emptied
+    Remove(String),
+    /// Replace the allowlist with the given non-empty set of code hashes
+    Set(Vec<String>),
+}
+
+impl AllowlistUpdate {
+    /// Get the updated code hashes
+    pub fn code_hashes(&self) -> &[String] {
+        match self {
+            AllowlistUpdate::Add(hash) | AllowlistUpdate::Remove(hash) => {
+                std::slice::from_ref(hash)
+            }
+            AllowlistUpdate::Set(hashes) => hashes,
+        }
+    }
+
+    /// Check that the updated code hashes are valid lowercase hashes, as the
+    /// allowlists are checked against lowercase hashes, and that a set
+    /// allowlist isn't empty
+    pub fn is_valid(&self) -> bool {
+        let hashes = self.code_hashes();
+        !hashes.is_empty()
+            && hashes.iter().all(|hash| {
+                Hash::from_str(hash).is_ok() && *hash == hash.to_lowercase()
+            })
+    }
+
+    /// Apply the update to an allowlist. Returns `false`, leaving the
+    /// allowlist untouched, if the update would leave the allowlist empty or
+    /// restrict an empty one to a single hash.
+    pub fn apply(&self, allowlist: &mut Vec<String>) -> bool {
+        match self {
+            AllowlistUpdate::Add(hash) => {
+                if allowlist.is_empty() {
+                    return false;
+                }
+                if !allowlist.contains(hash) {
+                    allowlist.push(hash.clone());
+                }
+            }
+            AllowlistUpdate::Remove(hash) => {
+                if allowlist.iter().all(|allowed| allowed == hash) {
+                    return false;
+                }
+                allowlist.retain(|allowed| allowed != hash);
+            }
+            AllowlistUpdate::Set(hashes) => {
+                let hashes: BTreeSet<&String> = hashes.iter().collect();
+                *allowlist = hashes.into_iter().cloned().collect();
+            }
+        }
+        true
+    }
+}
+
+impl Display for AllowlistUpdate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AllowlistUpdate::Add(hash) => write!(f, "Add {}", hash),
+            AllowlistUpdate::Remove(hash) => write!(f, "Remove {}", hash),
+            AllowlistUpdate::Set(hashes) => {
+                write!(f, "Set [{}]", hashes.join(", "))
+            }
+        }
+    }
+}
+
+/// An update of one of the allowlists of wasm code hashes
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Serialize,
+    Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum WasmAllowlistUpdate {
+    /// Update the allowlist of tx code hashes
+    Tx(AllowlistUpdate),
+    /// Update the allowlist of validity predicate code hashes
+    Vp(AllowlistUpdate),
+}
+
+impl WasmAllowlistUpdate {
+    /// Get the update of the allowlist
+    pub fn update(&self) -> &AllowlistUpdate {
+        match self {
+            WasmAllowlistUpdate::Tx(update)
+            | WasmAllowlistUpdate::Vp(update) => update,
+        }
+    }
 
-        continous_fundings.extend(retro_fundings);
+    /// Check if the update targets the tx allowlist
+    pub fn is_tx(&self) -> bool {
+        matches!(self, WasmAllowlistUpdate::Tx(_))
+    }
 
-        Ok(InitProposalData {
-            id: value.proposal.id,
-            content: Hash::default(),
-            author: value.proposal.author,
-            r#type: ProposalType::PGFPayment(continous_fundings), /* here continous_fundings is contains also the retro funding */
-            voting_start_epoch: value.proposal.voting_start_epoch,
-            voting_end_epoch: value.proposal.voting_end_epoch,
-            grace_epoch: value.proposal.grace_epoch,
-        })
+    /// Check that a batch of updates is not empty, that all the updates are
+    /// valid, that each code hash is updated at most once per allowlist and
+    /// that an allowlist that is set isn't otherwise updated
+    pub fn is_valid_batch(updates: &[Self]) -> bool {
+        let mut updated_hashes = BTreeSet::new();
+        let mut set_allowlists = BTreeSet::new();
+        let mut updated_allowlists = BTreeSet::new();
+        for update in updates {
+            if !update.update().is_valid() {
+                return false;
+            }
+            if let AllowlistUpdate::Set(_) = update.update() {
+                if !set_allowlists.insert(update.is_tx()) {
+                    return false;
+                }
+            } else {
+                updated_allowlists.insert(update.is_tx());
+                for hash in update.update().code_hashes() {
+                    if !updated_hashes.insert((update.is_tx(), hash)) {
+                        return false;
+                    }
+                }
+            }
+        }
+        !updates.is_empty() && set_allowlists.is_disjoint(&updated_allowlists)
     }
 }
 
-/// Storage struture for pgf fundings
+impl Display for WasmAllowlistUpdate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let allowlist = if self.is_tx() { "tx" } else { "vp" };
+        write!(f, "{} in the {} allowlist", self.update(), allowlist)
+    }
+}
+
+/// A change of a protocol parameter
 #[derive(
     Debug,
     Clone,
     PartialEq,
-    Eq,
-    PartialOrd,
     BorshSerialize,
     BorshDeserialize,
+    BorshSchema,
     Serialize,
     Deserialize,
 )]
-pub struct StoragePgfFunding {
-    /// The data about the pgf funding
-    pub detail: PGFTarget,
-    /// The id of the proposal that added this funding
-    pub id: u64,
+#[serde(rename_all = "snake_case")]
+pub enum ParameterChange {
+    /// Max expected time per block
+    MaxExpectedTimePerBlock(DurationSecs),
+    /// Expected number of epochs per year
+    EpochsPerYear(u64),
+    /// Tx hashes executed by their native implementation, if any
+    NativeTxAllowlist(Vec<String>),
+    /// Tx hashes that may be invoked as sub-transactions by another tx
+    SubTxAllowlist(Vec<String>),
+    /// Max number of signatures per transaction
+    MaxSignaturesPerTransaction(u8),
+    /// Max size of a transaction, in bytes
+    MaxTxBytes(u32),
+    /// Max amount of gas per block
+    MaxBlockGas(u64),
+    /// Routing of the epoch inflation among the PoS rewards, the PGF treasury
+    /// and the MASP incentives
+    InflationSplit(InflationSplit),
+    /// Limits on the resources of the tx and VP wasm instances
+    WasmLimits(WasmLimits),
 }
 
-impl StoragePgfFunding {
-    /// Init a new pgf funding struct
-    pub fn new(detail: PGFTarget, id: u64) -> Self {
-        Self { detail, id }
+impl ParameterChange {
+    /// Check that the new value of the parameter is acceptable
+    pub fn is_valid(&self) -> bool {
+        match self {
+            ParameterChange::MaxExpectedTimePerBlock(duration) => {
+                duration.0 > 0
+            }
+            ParameterChange::EpochsPerYear(epochs) => *epochs > 0,
+            // The allowlists are checked against lowercase hashes
+            ParameterChange::NativeTxAllowlist(hashes)
+            | ParameterChange::SubTxAllowlist(hashes) => {
+                hashes.iter().all(|hash| {
+                    Hash::from_str(hash).is_ok() && *hash == hash.to_lowercase()
+                })
+            }
+            ParameterChange::MaxSignaturesPerTransaction(max) => *max > 0,
+            ParameterChange::MaxTxBytes(max) => *max > 0,
+            ParameterChange::MaxBlockGas(max) => *max > 0,
+            ParameterChange::InflationSplit(split) => split.is_valid(),
+            ParameterChange::WasmLimits(limits) => limits.is_valid(),
+        }
     }
 }
 
-/// The type of a Proposal
+impl ParameterChange {
+    /// The name of the changed parameter
+    pub fn name(&self) -> &'static str {
+        match self {
+            ParameterChange::MaxExpectedTimePerBlock(_) => {
+                "Max expected time per block"
+            }
+            ParameterChange::EpochsPerYear(_) => "Epochs per year",
+            ParameterChange::NativeTxAllowlist(_) => "Native tx allowlist",
+            ParameterChange::SubTxAllowlist(_) => "Sub-tx allowlist",
+            ParameterChange::MaxSignaturesPerTransaction(_) => {
+                "Max signatures per transaction"
+            }
+            ParameterChange::MaxTxBytes(_) => "Max tx bytes",
+            ParameterChange::MaxBlockGas(_) => "Max block gas",
+            ParameterChange::InflationSplit(_) => "Inflation split",
+            ParameterChange::WasmLimits(_) => "Wasm limits",
+        }
+    }
+
+    /// The new value of the changed parameter
+    pub fn value(&self) -> String {
+        match self {
+            ParameterChange::MaxExpectedTimePerBlock(duration) => {
+                format!("{}s", duration.0)
+            }
+            ParameterChange::EpochsPerYear(epochs) => epochs.to_string(),
+            ParameterChange::NativeTxAllowlist(hashes)
+            | ParameterChange::SubTxAllowlist(hashes) => {
+                format!("[{}]", hashes.join(", "))
+            }
+            ParameterChange::MaxSignaturesPerTransaction(max) => {
+                max.to_string()
+            }
+            ParameterChange::MaxTxBytes(max) => max.to_string(),
+            ParameterChange::MaxBlockGas(max) => max.to_string(),
+            ParameterChange::InflationSplit(split) => split.to_string(),
+            ParameterChange::WasmLimits(limits) => limits.to_string(),
+        }
+    }
+}
+
+impl Display for ParameterChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.name(), self.value())
+    }
+}
+
+/// The current and proposed values of a parameter changed by a proposal
 #[derive(
     Debug,
     Clone,
     PartialEq,
+    Eq,
     BorshSerialize,
     BorshDeserialize,
     Serialize,
     Deserialize,
 )]
-pub enum ProposalType {
-    /// Default governance proposal with the optional wasm code
-    Default(Option<Hash>),
-    /// PGF stewards proposal
-    PGFSteward(BTreeSet<AddRemove<Address>>),
-    /// PGF funding proposal
-    PGFPayment(BTreeSet<PGFAction>),
+pub struct ParameterDiff {
+    /// The name of the changed parameter
+    pub parameter: String,
+    /// The current value of the parameter, if it's set
+    pub current: Option<String>,
+    /// The value proposed for the parameter
+    pub proposed: String,
+}
+
+impl ParameterDiff {
+    /// Check if the proposed value is the same as the current one
+    pub fn is_unchanged(&self) -> bool {
+        self.current.as_ref() == Some(&self.proposed)
+    }
+}
+
+impl Display for ParameterDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: {} -> {}",
+            self.parameter,
+            self.current.as_deref().unwrap_or("unset"),
+            self.proposed
+        )
+    }
 }
 
 /// An add or remove action for PGF
@@ -213,6 +1069,7 @@ pub enum ProposalType {
     Ord,
     BorshSerialize,
     BorshDeserialize,
+    BorshSchema,
     Serialize,
     Deserialize,
 )]
@@ -230,6 +1087,7 @@ pub enum AddRemove<T> {
     PartialEq,
     BorshSerialize,
     BorshDeserialize,
+    BorshSchema,
     Serialize,
     Deserialize,
     Ord,
@@ -268,6 +1126,7 @@ impl PGFTarget {
     PartialEq,
     BorshSerialize,
     BorshDeserialize,
+    BorshSchema,
     Serialize,
     Deserialize,
     Ord,
@@ -360,6 +1219,57 @@ impl borsh::BorshSchema for PGFIbcTarget {
     }
 }
 
+/// A continuous PGF payment stream with an end
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Serialize,
+    Deserialize,
+    Ord,
+    Eq,
+    PartialOrd,
+)]
+pub struct PGFStream {
+    /// The target and the amount paid every epoch
+    pub target: PGFTarget,
+    /// The last epoch at which the stream is paid
+    pub end_epoch: Epoch,
+}
+
+/// A PGF grant paid milestone by milestone, each milestone being released once
+/// attested by a steward
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Serialize,
+    Deserialize,
+    Ord,
+    Eq,
+    PartialOrd,
+)]
+pub struct PGFGrant {
+    /// The grant recipient
+    pub target: Address,
+    /// The amount released at each milestone
+    pub milestones: Vec<Amount>,
+}
+
+impl PGFGrant {
+    /// Check that the grant has milestones and that none of them is empty
+    pub fn is_valid(&self) -> bool {
+        !self.milestones.is_empty()
+            && self.milestones.iter().all(|amount| !amount.is_zero())
+    }
+}
+
 /// The actions that a PGF Steward can propose to execute
 #[derive(
     Debug,
@@ -367,6 +1277,7 @@ impl borsh::BorshSchema for PGFIbcTarget {
     PartialEq,
     BorshSerialize,
     BorshDeserialize,
+    BorshSchema,
     Serialize,
     Deserialize,
     Eq,
@@ -378,21 +1289,33 @@ pub enum PGFAction {
     Continuous(AddRemove<PGFTarget>),
     /// A retro payment
     Retro(PGFTarget),
+    /// A continuous payment until an end epoch
+    Stream(PGFStream),
+    /// A payment released by milestones
+    Grant(PGFGrant),
 }
 
 impl ProposalType {
-    /// Check if the proposal type is default
-    pub fn is_default(&self) -> bool {
-        matches!(self, ProposalType::Default(_))
+    /// Check if the proposal upgrades the protocol with some wasm code
+    pub fn is_wasm_code_upgrade(&self) -> bool {
+        matches!(self, ProposalType::WasmCodeUpgrade(_))
     }
 }
 
 impl Display for ProposalType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            ProposalType::Default(_) => write!(f, "Default"),
-            ProposalType::PGFSteward(_) => write!(f, "Pgf steward"),
+            ProposalType::Text => write!(f, "Text"),
+            ProposalType::WasmCodeUpgrade(_) => write!(f, "Wasm code upgrade"),
+            ProposalType::PGFSteward(..) => write!(f, "Pgf steward"),
             ProposalType::PGFPayment(_) => write!(f, "Pgf funding"),
+            ProposalType::MaspRewards(_) => write!(f, "Masp rewards"),
+            ProposalType::ParameterChange(_) => {
+                write!(f, "Parameter change")
+            }
+            ProposalType::EmergencyHalt(_) => write!(f, "Emergency halt"),
+            ProposalType::WasmAllowlist(_) => write!(f, "Wasm allowlist"),
+            ProposalType::Signaling => write!(f, "Signaling"),
         }
     }
 }
@@ -444,6 +1367,26 @@ impl From<PgfContinuous> for PGFAction {
     }
 }
 
+impl PGFAction {
+    /// Get the amount of a retro payment, if this is one
+    pub fn retro_amount(&self) -> Option<Amount> {
+        match self {
+            PGFAction::Retro(target) => Some(target.amount()),
+            _ => None,
+        }
+    }
+
+    /// Get the total amount of the retro payments of a set of pgf actions
+    pub fn total_retro_amount<'a>(
+        actions: impl IntoIterator<Item = &'a PGFAction>,
+    ) -> Amount {
+        actions
+            .into_iter()
+            .filter_map(PGFAction::retro_amount)
+            .sum()
+    }
+}
+
 impl From<PgfRetro> for PGFAction {
     fn from(value: PgfRetro) -> Self {
         PGFAction::Retro(value.target)
@@ -457,6 +1400,10 @@ pub struct StorageProposal {
     pub id: u64,
     /// The proposal content
     pub content: BTreeMap<String, String>,
+    /// The sha256 hash of the proposal content committed at initialization
+    pub content_hash: Option<Hash>,
+    /// The URI where the proposal content is published (e.g. on IPFS)
+    pub content_uri: Option<String>,
     /// The proposal author address
     pub author: Address,
     /// The proposal type
@@ -467,6 +1414,11 @@ pub struct StorageProposal {
     pub voting_end_epoch: Epoch,
     /// The epoch from which this changes are executed
     pub grace_epoch: Epoch,
+    /// The epoch at which the expedited voting period ends, if the proposal
+    /// is expedited
+    pub expedited_end_epoch: Option<Epoch>,
+    /// The function applied to the stake of the voters to tally the proposal
+    pub voting_mode: VotingMode,
 }
 
 impl StorageProposal {
@@ -512,9 +1464,13 @@ impl StorageProposal {
         {:2}Type: {}
         {:2}Author: {}
         {:2}Content: {:?}
+        {:2}Content Hash: {}
+        {:2}Content URI: {}
         {:2}Start Epoch: {}
         {:2}End Epoch: {}
         {:2}Grace Epoch: {}
+        {:2}Expedited End Epoch: {}
+        {:2}Voting Mode: {}
         {:2}Status: {}
         ",
             self.id,
@@ -525,12 +1481,22 @@ impl StorageProposal {
             "",
             self.content,
             "",
+            self.content_hash
+                .map_or_else(|| "-".to_string(), |hash| hash.to_string()),
+            "",
+            self.content_uri.as_deref().unwrap_or("-"),
+            "",
             self.voting_start_epoch,
             "",
             self.voting_end_epoch,
             "",
             self.grace_epoch,
             "",
+            self.expedited_end_epoch
+                .map_or_else(|| "-".to_string(), |epoch| epoch.to_string()),
+            "",
+            self.voting_mode,
+            "",
             self.get_status(current_epoch)
         )
     }
@@ -566,7 +1532,13 @@ impl Display for StorageProposal {
 /// Testing helpers and and strategies for governance proposals
 pub mod testing {
     use namada_core::types::address::testing::arb_non_internal_address;
+    use namada_core::types::dec::Dec;
     use namada_core::types::hash::testing::arb_hash;
+    use namada_core::types::key::testing::arb_common_keypair;
+    use namada_core::types::key::{RefTo, SigScheme};
+    use namada_core::types::parameters::{
+        WasmInstanceLimits, MAX_WASM_MEMORY_PAGES,
+    };
     use namada_core::types::storage::testing::arb_epoch;
     use namada_core::types::token::testing::arb_amount;
     use proptest::prelude::*;
@@ -604,47 +1576,212 @@ pub mod testing {
         }
     }
 
+    prop_compose! {
+        /// Generate an arbitrary PGF stream
+        pub fn arb_pgf_stream()(
+            target in arb_pgf_target(),
+            end_epoch in arb_epoch(),
+        ) -> PGFStream {
+            PGFStream { target, end_epoch }
+        }
+    }
+
+    prop_compose! {
+        /// Generate an arbitrary PGF grant
+        pub fn arb_pgf_grant()(
+            target in arb_non_internal_address(),
+            milestones in collection::vec(arb_amount(), 1..5),
+        ) -> PGFGrant {
+            PGFGrant { target, milestones }
+        }
+    }
+
     /// Generate an arbitrary PGF action
     pub fn arb_pgf_action() -> impl Strategy<Value = PGFAction> {
         prop_oneof![
             arb_add_remove(arb_pgf_target()).prop_map(PGFAction::Continuous),
             arb_pgf_target().prop_map(PGFAction::Retro),
+            arb_pgf_stream().prop_map(PGFAction::Stream),
+            arb_pgf_grant().prop_map(PGFAction::Grant),
+        ]
+    }
+
+    /// Generate an arbitrary code hash of an allowlist
+    pub fn arb_allowlist_hash() -> impl Strategy<Value = String> {
+        arb_hash().prop_map(|hash| hash.to_string().to_lowercase())
+    }
+
+    /// Generate an arbitrary protocol parameter change
+    pub fn arb_parameter_change() -> impl Strategy<Value = ParameterChange> {
+        prop_oneof![
+            any::<u64>().prop_map(|secs| {
+                ParameterChange::MaxExpectedTimePerBlock(DurationSecs(secs))
+            }),
+            any::<u64>().prop_map(ParameterChange::EpochsPerYear),
+            collection::vec(arb_allowlist_hash(), 0..5)
+                .prop_map(ParameterChange::NativeTxAllowlist),
+            collection::vec(arb_allowlist_hash(), 0..5)
+                .prop_map(ParameterChange::SubTxAllowlist),
+            any::<u8>().prop_map(ParameterChange::MaxSignaturesPerTransaction),
+            any::<u32>().prop_map(ParameterChange::MaxTxBytes),
+            any::<u64>().prop_map(ParameterChange::MaxBlockGas),
+            arb_inflation_split().prop_map(ParameterChange::InflationSplit),
+            arb_wasm_limits().prop_map(ParameterChange::WasmLimits),
+        ]
+    }
+
+    prop_compose! {
+        /// Generate arbitrary valid wasm instance limits
+        pub fn arb_wasm_instance_limits()(
+            max_memory_pages in 1..=MAX_WASM_MEMORY_PAGES,
+            max_table_elements in 1..=u32::MAX,
+            max_globals in 1..=u32::MAX,
+        ) -> WasmInstanceLimits {
+            WasmInstanceLimits {
+                max_memory_pages,
+                max_table_elements,
+                max_globals,
+            }
+        }
+    }
+
+    prop_compose! {
+        /// Generate arbitrary valid wasm limits
+        pub fn arb_wasm_limits()(
+            tx in arb_wasm_instance_limits(),
+            vp in arb_wasm_instance_limits(),
+        ) -> WasmLimits {
+            WasmLimits { tx, vp }
+        }
+    }
+
+    prop_compose! {
+        /// Generate an arbitrary valid inflation split
+        pub fn arb_inflation_split()(
+            pos_rewards in 0..=100i128,
+        )(
+            pos_rewards in Just(pos_rewards),
+            pgf_treasury in 0..=(100 - pos_rewards),
+        ) -> InflationSplit {
+            InflationSplit {
+                pos_rewards: Dec::new(pos_rewards, 2).unwrap(),
+                pgf_treasury: Dec::new(pgf_treasury, 2).unwrap(),
+                masp_incentives: Dec::new(
+                    100 - pos_rewards - pgf_treasury,
+                    2,
+                )
+                .unwrap(),
+            }
+        }
+    }
+
+    /// Generate an arbitrary subsystem to halt
+    pub fn arb_halted_subsystem() -> impl Strategy<Value = HaltedSubsystem> {
+        prop_oneof![
+            Just(HaltedSubsystem::IbcTransfers),
+            Just(HaltedSubsystem::Masp),
+            arb_non_internal_address().prop_map(HaltedSubsystem::Token),
+        ]
+    }
+
+    prop_compose! {
+        /// Generate an arbitrary emergency halt
+        pub fn arb_emergency_halt()(
+            subsystem in arb_halted_subsystem(),
+            duration in 1..=MAX_EMERGENCY_HALT_EPOCHS,
+        ) -> EmergencyHalt {
+            EmergencyHalt {
+                subsystem,
+                duration,
+            }
+        }
+    }
+
+    /// Generate an arbitrary update of an allowlist
+    pub fn arb_allowlist_update() -> impl Strategy<Value = AllowlistUpdate> {
+        prop_oneof![
+            arb_allowlist_hash().prop_map(AllowlistUpdate::Add),
+            arb_allowlist_hash().prop_map(AllowlistUpdate::Remove),
+            collection::vec(arb_allowlist_hash(), 1..5)
+                .prop_map(AllowlistUpdate::Set),
+        ]
+    }
+
+    /// Generate an arbitrary wasm allowlist update
+    pub fn arb_wasm_allowlist_update(
+    ) -> impl Strategy<Value = WasmAllowlistUpdate> {
+        prop_oneof![
+            arb_allowlist_update().prop_map(WasmAllowlistUpdate::Tx),
+            arb_allowlist_update().prop_map(WasmAllowlistUpdate::Vp),
         ]
     }
 
     /// Generate an arbitrary proposal type
     pub fn arb_proposal_type() -> impl Strategy<Value = ProposalType> {
         prop_oneof![
-            option::of(arb_hash()).prop_map(ProposalType::Default),
-            collection::btree_set(
-                arb_add_remove(arb_non_internal_address()),
-                0..10,
+            Just(ProposalType::Text),
+            arb_hash().prop_map(ProposalType::WasmCodeUpgrade),
+            (
+                collection::btree_set(
+                    arb_add_remove(arb_non_internal_address()),
+                    0..10,
+                ),
+                option::of(arb_amount()),
             )
-            .prop_map(ProposalType::PGFSteward),
+                .prop_map(|(stewards, spending_cap)| {
+                    ProposalType::PGFSteward(stewards, spending_cap)
+                }),
             collection::btree_set(arb_pgf_action(), 0..10)
                 .prop_map(ProposalType::PGFPayment),
+            collection::vec(arb_parameter_change(), 0..10)
+                .prop_map(ProposalType::ParameterChange),
+            arb_emergency_halt().prop_map(ProposalType::EmergencyHalt),
+            collection::vec(arb_wasm_allowlist_update(), 0..10)
+                .prop_map(ProposalType::WasmAllowlist),
+        ]
+    }
+
+    /// Generate an arbitrary voting mode
+    pub fn arb_voting_mode() -> impl Strategy<Value = VotingMode> {
+        prop_oneof![
+            Just(VotingMode::Linear),
+            Just(VotingMode::Quadratic),
+            arb_amount().prop_map(VotingMode::Capped),
         ]
     }
 
+    /// Generate an arbitrary proposal kind
+    pub fn arb_proposal_kind() -> impl Strategy<Value = ProposalKind> {
+        proptest::sample::select(ProposalKind::ALL.to_vec())
+    }
+
     prop_compose! {
         /// Generate a proposal initialization
         pub fn arb_init_proposal()(
             id: u64,
             content in arb_hash(),
+            content_hash in arb_hash(),
+            content_uri in option::of("ipfs://[a-zA-Z0-9]{46}"),
             author in arb_non_internal_address(),
             r#type in arb_proposal_type(),
             voting_start_epoch in arb_epoch(),
             voting_end_epoch in arb_epoch(),
             grace_epoch in arb_epoch(),
+            expedited: bool,
+            voting_mode in arb_voting_mode(),
         ) -> InitProposalData {
             InitProposalData {
                 id,
                 content,
+                content_hash,
+                content_uri,
                 author,
                 r#type,
                 voting_start_epoch,
                 voting_end_epoch,
                 grace_epoch,
+                expedited,
+                voting_mode,
             }
         }
     }
@@ -665,4 +1802,352 @@ pub mod testing {
             }
         }
     }
+
+    prop_compose! {
+        /// Generate an arbitrary batch of signed votes
+        pub fn arb_vote_proposal_batch()(
+            ballots in collection::vec(
+                (arb_vote_proposal(), arb_common_keypair(), arb_hash()),
+                0..5,
+            ),
+        ) -> VoteProposalBatchData {
+            let ballots = ballots
+                .into_iter()
+                .map(|(data, keypair, hash)| {
+                    let signature = common::SigScheme::sign(&keypair, hash);
+                    let signatures = BTreeSet::from([
+                        SignatureIndex::from_single_signature(
+                            keypair.ref_to(),
+                            signature,
+                        ),
+                    ]);
+                    SignedVoteProposalData { data, signatures }
+                })
+                .collect();
+            VoteProposalBatchData { ballots }
+        }
+    }
+
+    prop_compose! {
+        /// Generate an arbitrary vote withdrawal
+        pub fn arb_vote_withdrawal()(
+            id: u64,
+            voter in arb_non_internal_address(),
+            delegations in collection::vec(arb_non_internal_address(), 0..10),
+        ) -> VoteWithdrawalData {
+            VoteWithdrawalData {
+                id,
+                voter,
+                delegations,
+            }
+        }
+    }
+
+    prop_compose! {
+        /// Generate an arbitrary vote delegation
+        pub fn arb_vote_delegation()(
+            delegator in arb_non_internal_address(),
+            delegate in option::of(arb_non_internal_address()),
+            kinds in collection::btree_set(arb_proposal_kind(), 1..5),
+        ) -> VoteDelegationData {
+            VoteDelegationData {
+                delegator,
+                delegate,
+                kinds,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use namada_core::types::address::testing::{
+        established_address_1, established_address_2,
+    };
+    use namada_core::types::dec::Dec;
+    use namada_core::types::key::testing::{keypair_1, keypair_2};
+    use namada_core::types::key::RefTo;
+    use namada_core::types::parameters::MAX_WASM_MEMORY_PAGES;
+
+    use super::*;
+
+    #[test]
+    fn test_signed_ballot_signature() {
+        let voter_keypair = keypair_1();
+        let public_keys_map =
+            AccountPublicKeysMap::from_iter([voter_keypair.ref_to()]);
+        let chain_id = ChainId("test-chain".to_string());
+        let data = VoteProposalData {
+            id: 0,
+            vote: ProposalVote::Yay,
+            voter: established_address_1(),
+            delegations: vec![established_address_2()],
+        };
+
+        let ballot = SignedVoteProposalData::new(data).sign(
+            vec![voter_keypair],
+            &public_keys_map,
+            &chain_id,
+        );
+        assert!(ballot.check_signature(&public_keys_map, 1, &chain_id));
+        assert!(!ballot.check_signature(&public_keys_map, 2, &chain_id));
+
+        // The ballot can't be replayed on another chain
+        let other_chain_id = ChainId("other-chain".to_string());
+        assert!(!ballot.check_signature(&public_keys_map, 1, &other_chain_id));
+
+        // The ballot can't be altered
+        let mut altered = ballot.clone();
+        altered.data.vote = ProposalVote::Nay;
+        assert!(!altered.check_signature(&public_keys_map, 1, &chain_id));
+
+        // A key that is not the voter's is not counted
+        let other_keys_map =
+            AccountPublicKeysMap::from_iter([keypair_2().ref_to()]);
+        assert!(!ballot.check_signature(&other_keys_map, 1, &chain_id));
+    }
+    #[test]
+    fn test_wasm_allowlist_updates_validity() {
+        let hash = Hash::sha256(b"tx_transfer.wasm").to_string().to_lowercase();
+        let other_hash =
+            Hash::sha256(b"vp_user.wasm").to_string().to_lowercase();
+
+        let updates = vec![
+            WasmAllowlistUpdate::Tx(AllowlistUpdate::Add(hash.clone())),
+            WasmAllowlistUpdate::Vp(AllowlistUpdate::Add(hash.clone())),
+            WasmAllowlistUpdate::Tx(AllowlistUpdate::Remove(
+                other_hash.clone(),
+            )),
+        ];
+        assert!(WasmAllowlistUpdate::is_valid_batch(&updates));
+
+        // An empty batch is not valid
+        assert!(!WasmAllowlistUpdate::is_valid_batch(&[]));
+
+        // The code hashes must be valid lowercase hashes
+        let updates = vec![WasmAllowlistUpdate::Tx(AllowlistUpdate::Add(
+            "not a hash".to_string(),
+        ))];
+        assert!(!WasmAllowlistUpdate::is_valid_batch(&updates));
+        let updates = vec![WasmAllowlistUpdate::Tx(AllowlistUpdate::Add(
+            hash.to_uppercase(),
+        ))];
+        assert!(!WasmAllowlistUpdate::is_valid_batch(&updates));
+
+        // A hash can't be updated twice in the same allowlist
+        let updates = vec![
+            WasmAllowlistUpdate::Tx(AllowlistUpdate::Add(hash.clone())),
+            WasmAllowlistUpdate::Tx(AllowlistUpdate::Remove(hash.clone())),
+        ];
+        assert!(!WasmAllowlistUpdate::is_valid_batch(&updates));
+
+        // An allowlist can't be set empty
+        let updates =
+            vec![WasmAllowlistUpdate::Vp(AllowlistUpdate::Set(vec![]))];
+        assert!(!WasmAllowlistUpdate::is_valid_batch(&updates));
+
+        // A set allowlist can't be otherwise updated
+        let set = WasmAllowlistUpdate::Tx(AllowlistUpdate::Set(vec![
+            hash.clone(),
+            other_hash.clone(),
+        ]));
+        let updates = vec![
+            set.clone(),
+            WasmAllowlistUpdate::Vp(AllowlistUpdate::Add(hash.clone())),
+        ];
+        assert!(WasmAllowlistUpdate::is_valid_batch(&updates));
+        let updates = vec![
+            set.clone(),
+            WasmAllowlistUpdate::Tx(AllowlistUpdate::Remove(other_hash)),
+        ];
+        assert!(!WasmAllowlistUpdate::is_valid_batch(&updates));
+        assert!(!WasmAllowlistUpdate::is_valid_batch(&[set.clone(), set]));
+    }
+
+    #[test]
+    fn test_apply_allowlist_update() {
+        let hash = Hash::sha256(b"tx_transfer.wasm").to_string().to_lowercase();
+        let other_hash =
+            Hash::sha256(b"vp_user.wasm").to_string().to_lowercase();
+
+        // An empty allowlist, allowing any code, can't be restricted to a
+        // single hash
+        let mut allowlist = vec![];
+        assert!(!AllowlistUpdate::Add(hash.clone()).apply(&mut allowlist));
+        assert!(!AllowlistUpdate::Remove(hash.clone()).apply(&mut allowlist));
+        assert!(allowlist.is_empty());
+
+        // but it can be given the full set of allowed hashes
+        let set = AllowlistUpdate::Set(vec![hash.clone(), hash.clone()]);
+        assert!(set.apply(&mut allowlist));
+        assert_eq!(allowlist, vec![hash.clone()]);
+
+        assert!(AllowlistUpdate::Add(other_hash.clone()).apply(&mut allowlist));
+        assert!(AllowlistUpdate::Add(other_hash.clone()).apply(&mut allowlist));
+        assert_eq!(allowlist, vec![hash.clone(), other_hash.clone()]);
+
+        assert!(AllowlistUpdate::Remove(hash.clone()).apply(&mut allowlist));
+        assert_eq!(allowlist, vec![other_hash.clone()]);
+
+        // The allowlist can't be emptied
+        assert!(
+            !AllowlistUpdate::Remove(other_hash.clone()).apply(&mut allowlist)
+        );
+        assert_eq!(allowlist, vec![other_hash]);
+    }
+
+    #[test]
+    fn test_inflation_split_change() {
+        let split = InflationSplit {
+            pos_rewards: Dec::new(6, 1).unwrap(),
+            pgf_treasury: Dec::new(3, 1).unwrap(),
+            masp_incentives: Dec::new(1, 1).unwrap(),
+        };
+        let change = ParameterChange::InflationSplit(split.clone());
+        assert!(change.is_valid());
+
+        // The shares must sum up to one
+        let change = ParameterChange::InflationSplit(InflationSplit {
+            masp_incentives: Dec::new(2, 1).unwrap(),
+            ..split.clone()
+        });
+        assert!(!change.is_valid());
+
+        // The shares can't be negative
+        let change = ParameterChange::InflationSplit(InflationSplit {
+            pos_rewards: Dec::new(8, 1).unwrap(),
+            pgf_treasury: Dec::new(3, 1).unwrap(),
+            masp_incentives: Dec::new(-1, 1).unwrap(),
+        });
+        assert!(!change.is_valid());
+
+        let diff = ParameterDiff {
+            parameter: change.name().to_string(),
+            current: None,
+            proposed: split.to_string(),
+        };
+        assert!(!diff.is_unchanged());
+        let diff = ParameterDiff {
+            current: Some(split.to_string()),
+            ..diff
+        };
+        assert!(diff.is_unchanged());
+    }
+
+    #[test]
+    fn test_wasm_limits_change() {
+        let limits = WasmLimits::default();
+        assert!(ParameterChange::WasmLimits(limits).is_valid());
+
+        // The memory limit can't exceed the hard cap
+        let mut over_cap = limits;
+        over_cap.vp.max_memory_pages = MAX_WASM_MEMORY_PAGES + 1;
+        assert!(!ParameterChange::WasmLimits(over_cap).is_valid());
+
+        // The limits can't be zero
+        let mut zero = limits;
+        zero.tx.max_globals = 0;
+        assert!(!ParameterChange::WasmLimits(zero).is_valid());
+    }
+
+    #[test]
+    fn test_allowlist_change_validity() {
+        let hash = Hash::sha256(b"tx_transfer.wasm").to_string();
+        let change = ParameterChange::SubTxAllowlist(vec![hash.to_lowercase()]);
+        assert!(change.is_valid());
+
+        // The allowlists are checked against lowercase hashes
+        let change = ParameterChange::SubTxAllowlist(vec![hash.to_uppercase()]);
+        assert!(!change.is_valid());
+        let change = ParameterChange::NativeTxAllowlist(vec![hash]);
+        assert!(!change.is_valid());
+    }
+
+    /// Get the names of the variants described by a `oneOf` JSON schema
+    fn json_schema_variants(schema: &serde_json::Value) -> BTreeSet<String> {
+        schema["oneOf"]
+            .as_array()
+            .expect("The schema should have variants")
+            .iter()
+            .map(|variant| match variant.get("const") {
+                Some(name) => name.as_str().unwrap().to_string(),
+                None => variant["required"][0].as_str().unwrap().to_string(),
+            })
+            .collect()
+    }
+
+    /// Get the name of the variant of an externally tagged enum serialized to
+    /// JSON
+    fn json_variant(value: impl Serialize) -> String {
+        match serde_json::to_value(value).unwrap() {
+            serde_json::Value::String(name) => name,
+            serde_json::Value::Object(map) => {
+                map.keys().next().expect("Missing variant").clone()
+            }
+            value => panic!("Unexpected variant encoding: {}", value),
+        }
+    }
+
+    #[test]
+    fn test_proposal_type_json_schema() {
+        let schema: serde_json::Value =
+            serde_json::from_str(PROPOSAL_TYPE_JSON_SCHEMA)
+                .expect("The schema should be valid JSON");
+
+        let proposal_types = [
+            ProposalType::Text,
+            ProposalType::WasmCodeUpgrade(Hash::default()),
+            ProposalType::PGFSteward(BTreeSet::new(), None),
+            ProposalType::PGFPayment(BTreeSet::new()),
+            ProposalType::MaspRewards(BTreeMap::new()),
+            ProposalType::ParameterChange(vec![]),
+            ProposalType::EmergencyHalt(EmergencyHalt {
+                subsystem: HaltedSubsystem::Masp,
+                duration: 1,
+            }),
+            ProposalType::WasmAllowlist(vec![]),
+            ProposalType::Signaling,
+        ];
+        let variants = json_schema_variants(&schema);
+        assert_eq!(variants.len(), proposal_types.len());
+        for proposal_type in proposal_types {
+            assert!(variants.contains(&json_variant(proposal_type)));
+        }
+
+        let parameter_changes = [
+            ParameterChange::MaxExpectedTimePerBlock(DurationSecs(1)),
+            ParameterChange::EpochsPerYear(1),
+            ParameterChange::NativeTxAllowlist(vec![]),
+            ParameterChange::SubTxAllowlist(vec![]),
+            ParameterChange::MaxSignaturesPerTransaction(1),
+            ParameterChange::MaxTxBytes(1),
+            ParameterChange::MaxBlockGas(1),
+            ParameterChange::InflationSplit(InflationSplit {
+                pos_rewards: Dec::one(),
+                pgf_treasury: Dec::zero(),
+                masp_incentives: Dec::zero(),
+            }),
+            ParameterChange::WasmLimits(WasmLimits::default()),
+        ];
+        let variants =
+            json_schema_variants(&schema["$defs"]["ParameterChange"]);
+        assert_eq!(variants.len(), parameter_changes.len());
+        for change in parameter_changes {
+            assert!(variants.contains(&json_variant(change)));
+        }
+    }
+
+    #[test]
+    fn test_signaling_proposal_content() {
+        assert!(ProposalType::Signaling.is_valid_content());
+        assert!(VotingMode::Quadratic.is_valid_for(&ProposalType::Signaling));
+
+        // Executable proposals can't be used as signaling ones
+        assert!(!ProposalType::ParameterChange(vec![]).is_valid_content());
+        assert!(!ProposalType::PGFPayment(BTreeSet::new()).is_valid_content());
+        assert!(
+            !ProposalType::PGFSteward(BTreeSet::new(), None).is_valid_content()
+        );
+        assert!(!ProposalType::MaspRewards(BTreeMap::new()).is_valid_content());
+    }
 }
@@ -21,6 +21,8 @@ pub enum ProposalVote {
     Nay,
     /// Abstain
     Abstain,
+    /// No, and veto the proposal
+    NayWithVeto,
 }
 
 impl ProposalVote {
@@ -29,15 +31,20 @@ impl ProposalVote {
         matches!(self, ProposalVote::Yay)
     }
 
-    /// Check if a vote is nay
+    /// Check if a vote is nay, with or without veto
     pub fn is_nay(&self) -> bool {
-        matches!(self, ProposalVote::Nay)
+        matches!(self, ProposalVote::Nay | ProposalVote::NayWithVeto)
     }
 
     /// Check if a vote is abstain
     pub fn is_abstain(&self) -> bool {
         matches!(self, ProposalVote::Abstain)
     }
+
+    /// Check if a vote is a veto
+    pub fn is_veto(&self) -> bool {
+        matches!(self, ProposalVote::NayWithVeto)
+    }
 }
 
 impl Display for ProposalVote {
@@ -46,6 +53,7 @@ impl Display for ProposalVote {
             ProposalVote::Yay => write!(f, "yay"),
             ProposalVote::Nay => write!(f, "nay"),
             ProposalVote::Abstain => write!(f, "abstain"),
+            ProposalVote::NayWithVeto => write!(f, "veto"),
         }
     }
 }
@@ -58,6 +66,7 @@ impl TryFrom<String> for ProposalVote {
             "yay" => Ok(ProposalVote::Yay),
             "nay" => Ok(ProposalVote::Nay),
             "abstain" => Ok(ProposalVote::Abstain),
+            "veto" | "nay_with_veto" => Ok(ProposalVote::NayWithVeto),
             _ => Err("invalid vote".to_string()),
         }
     }
@@ -76,6 +85,7 @@ pub mod testing {
             Just(ProposalVote::Yay),
             Just(ProposalVote::Nay),
             Just(ProposalVote::Abstain),
+            Just(ProposalVote::NayWithVeto),
         ]
     }
 }
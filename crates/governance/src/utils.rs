@@ -1,11 +1,13 @@
+use std::cmp;
 use std::collections::HashMap;
 use std::fmt::Display;
 
-use namada_core::borsh::{BorshDeserialize, BorshSerialize};
+use namada_core::borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
 use namada_core::types::address::Address;
 use namada_core::types::dec::Dec;
 use namada_core::types::storage::Epoch;
 use namada_core::types::token;
+use serde::{Deserialize, Serialize};
 
 use super::cli::offline::OfflineVote;
 use super::storage::proposal::ProposalType;
@@ -59,6 +61,33 @@ impl Vote {
     }
 }
 
+/// The number of votes in a page of the participation history of a voter
+pub const VOTER_VOTES_PAGE_SIZE: usize = 20;
+
+/// A vote cast by a voter, as recorded in its participation history
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+pub struct VoterVote {
+    /// The id of the proposal
+    pub proposal_id: u64,
+    /// The vote cast
+    pub vote: ProposalVote,
+    /// The voting power used, read at the end of the voting period or at
+    /// the current epoch if the voting period is still open
+    pub voting_power: VotePower,
+}
+
+impl Display for VoterVote {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Proposal {}: {} with voting power {}",
+            self.proposal_id,
+            self.vote,
+            self.voting_power.to_string_native()
+        )
+    }
+}
+
 /// Represent a tally type
 #[derive(Copy, Debug, Clone, BorshSerialize, BorshDeserialize)]
 pub enum TallyType {
@@ -77,14 +106,94 @@ impl TallyType {
     /// Compute the type of tally for a proposal
     pub fn from(proposal_type: ProposalType, is_steward: bool) -> Self {
         match (proposal_type, is_steward) {
-            (ProposalType::Default(_), _) => TallyType::TwoThirds,
-            (ProposalType::PGFSteward(_), _) => TallyType::OneHalfOverOneThird,
+            (ProposalType::Text | ProposalType::WasmCodeUpgrade(_), _) => {
+                TallyType::TwoThirds
+            }
+            (ProposalType::PGFSteward(..), _) => TallyType::OneHalfOverOneThird,
             (ProposalType::PGFPayment(_), true) => {
                 TallyType::LessOneHalfOverOneThirdNay
             }
             (ProposalType::PGFPayment(_), false) => {
                 TallyType::OneHalfOverOneThird
             }
+            (ProposalType::MaspRewards(_), _) => TallyType::TwoThirds,
+            (ProposalType::ParameterChange(_), _) => TallyType::TwoThirds,
+            (ProposalType::EmergencyHalt(_), _) => TallyType::TwoThirds,
+            (ProposalType::WasmAllowlist(_), _) => TallyType::TwoThirds,
+            (ProposalType::Signaling, _) => TallyType::TwoThirds,
+        }
+    }
+}
+
+/// The function applied to the stake of each account to get its voting power
+/// when tallying a proposal
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Serialize,
+    Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum VotingMode {
+    /// The voting power of an account is its stake
+    #[default]
+    Linear,
+    /// The voting power of an account is the square root of its stake
+    Quadratic,
+    /// The voting power of an account is its stake, up to the given cap
+    Capped(VotePower),
+}
+
+impl VotingMode {
+    /// Compute the voting power of an account from its stake
+    pub fn voting_power(&self, stake: VotePower) -> VotePower {
+        match self {
+            VotingMode::Linear => stake,
+            VotingMode::Quadratic => {
+                VotePower::from_uint(stake.raw_amount().integer_sqrt(), 0)
+                    .expect("The square root of an amount should be valid")
+            }
+            VotingMode::Capped(cap) => cmp::min(stake, *cap),
+        }
+    }
+
+    /// Check if the voting mode is the standard stake-weighted one
+    pub fn is_linear(&self) -> bool {
+        matches!(self, VotingMode::Linear)
+    }
+
+    /// Check if the voting mode can be used for a proposal of the given type.
+    /// Only proposals which don't execute anything can be tallied with a
+    /// voting mode other than the linear one.
+    pub fn is_valid_for(&self, proposal_type: &ProposalType) -> bool {
+        match self {
+            VotingMode::Linear => true,
+            VotingMode::Capped(cap) if cap.is_zero() => false,
+            VotingMode::Quadratic | VotingMode::Capped(_) => {
+                matches!(
+                    proposal_type,
+                    ProposalType::Text | ProposalType::Signaling
+                )
+            }
+        }
+    }
+}
+
+impl Display for VotingMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VotingMode::Linear => write!(f, "linear"),
+            VotingMode::Quadratic => write!(f, "quadratic"),
+            VotingMode::Capped(cap) => {
+                write!(f, "capped at {}", cap.to_string_native())
+            }
         }
     }
 }
@@ -96,6 +205,8 @@ pub enum TallyResult {
     Passed,
     /// Proposal was rejected
     Rejected,
+    /// Proposal was rejected because of too many vetoes
+    Vetoed,
 }
 
 impl Display for TallyResult {
@@ -103,6 +214,7 @@ impl Display for TallyResult {
         match self {
             TallyResult::Passed => write!(f, "passed"),
             TallyResult::Rejected => write!(f, "rejected"),
+            TallyResult::Vetoed => write!(f, "vetoed"),
         }
     }
 }
@@ -187,6 +299,8 @@ pub struct ProposalResult {
     pub total_nay_power: VotePower,
     /// The total voting power from abstained votes
     pub total_abstain_power: VotePower,
+    /// The total voting power from veto votes, also counted as nay votes
+    pub total_veto_power: VotePower,
 }
 
 impl ProposalResult {
@@ -204,6 +318,75 @@ impl ProposalResult {
 
         at_least_two_third_voted && at_least_two_thirds_voted_nay
     }
+
+    /// Reject the proposal as vetoed if the veto voting power is more than the
+    /// given fraction of the voted power
+    pub fn with_veto_threshold(self, veto_threshold: Dec) -> Self {
+        let total_voted_power = self.total_yay_power
+            + self.total_nay_power
+            + self.total_abstain_power;
+        if self.total_veto_power > total_voted_power.mul_ceil(veto_threshold) {
+            Self {
+                result: TallyResult::Vetoed,
+                ..self
+            }
+        } else {
+            self
+        }
+    }
+
+    /// Return the voting power that voted yay, nay or abstain
+    pub fn total_voted_power(&self) -> VotePower {
+        self.total_yay_power + self.total_nay_power + self.total_abstain_power
+    }
+
+    /// Return the voting power that must vote for the quorum of the tally type
+    /// to be reached, if the tally type has one
+    pub fn quorum_power(&self) -> Option<VotePower> {
+        let quorum = match self.tally_type {
+            TallyType::TwoThirds => Some(Dec::two() / 3),
+            TallyType::OneHalfOverOneThird => Some(Dec::one() / 3),
+            // A proposal with this tally type passes without quorum
+            TallyType::LessOneHalfOverOneThirdNay => None,
+        };
+        quorum.map(|quorum| self.total_voting_power.mul_ceil(quorum))
+    }
+
+    /// Return true if the proposal was rejected without reaching the quorum
+    /// of its tally type
+    pub fn is_expired(&self) -> bool {
+        matches!(self.result, TallyResult::Rejected)
+            && self.quorum_power().map_or(false, |quorum_power| {
+                self.total_voted_power() < quorum_power
+            })
+    }
+
+    /// Reject a passed proposal if less than the given quorum of the total
+    /// voting power voted or if less than the given threshold of the yay and
+    /// nay voting power voted yay, as required by an expedited tally
+    pub fn with_expedited_thresholds(
+        self,
+        expedited_quorum: Dec,
+        expedited_threshold: Dec,
+    ) -> Self {
+        let total_voted_power = self.total_yay_power
+            + self.total_nay_power
+            + self.total_abstain_power;
+        let quorum_reached = total_voted_power
+            >= self.total_voting_power.mul_ceil(expedited_quorum);
+        let threshold_reached = self.total_yay_power
+            >= (self.total_yay_power + self.total_nay_power)
+                .mul_ceil(expedited_threshold);
+        match self.result {
+            TallyResult::Passed if !(quorum_reached && threshold_reached) => {
+                Self {
+                    result: TallyResult::Rejected,
+                    ..self
+                }
+            }
+            _ => self,
+        }
+    }
 }
 
 impl Display for ProposalResult {
@@ -217,11 +400,12 @@ impl Display for ProposalResult {
 
         write!(
             f,
-            "{} with {} yay votes, {} nay votes and {} abstain votes, total \
-             voting power: {} threshold was: {}",
+            "{} with {} yay votes, {} nay votes (of which {} veto votes) and \
+             {} abstain votes, total voting power: {} threshold was: {}",
             self.result,
             self.total_yay_power.to_string_native(),
             self.total_nay_power.to_string_native(),
+            self.total_veto_power.to_string_native(),
             self.total_abstain_power.to_string_native(),
             self.total_voting_power.to_string_native(),
             threshold.to_string_native()
@@ -229,6 +413,130 @@ impl Display for ProposalResult {
     }
 }
 
+/// The voting power of a group of voters split by vote
+#[derive(Clone, Copy, Debug, Default, BorshSerialize, BorshDeserialize)]
+pub struct VotePowerBreakdown {
+    /// The voting power from yay votes
+    pub yay: VotePower,
+    /// The voting power from nay votes
+    pub nay: VotePower,
+    /// The voting power from abstained votes
+    pub abstain: VotePower,
+}
+
+impl VotePowerBreakdown {
+    /// Add the voting power of a vote to the side it voted for
+    pub fn add_vote(&mut self, vote: &TallyVote, voting_power: VotePower) {
+        if vote.is_yay() {
+            self.yay += voting_power;
+        } else if vote.is_nay() {
+            self.nay += voting_power;
+        } else if vote.is_abstain() {
+            self.abstain += voting_power;
+        }
+    }
+}
+
+impl Display for VotePowerBreakdown {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} yay, {} nay, {} abstain",
+            self.yay.to_string_native(),
+            self.nay.to_string_native(),
+            self.abstain.to_string_native()
+        )
+    }
+}
+
+/// The tally of a proposal computed on demand from the votes cast so far
+#[derive(Clone, Copy, BorshDeserialize, BorshSerialize)]
+pub struct ProposalTally {
+    /// The epoch of the voting power snapshot used for the tally
+    pub tally_epoch: Epoch,
+    /// The voting power of the validators' votes, without the voting power
+    /// of the delegators who voted themselves
+    pub validators: VotePowerBreakdown,
+    /// The voting power of the delegators' votes, cast directly or followed
+    /// from their delegate
+    pub delegators: VotePowerBreakdown,
+    /// The result of the proposal if the voting ended with these votes
+    pub projected_result: ProposalResult,
+}
+
+impl ProposalTally {
+    /// Compute the tally of a proposal from the given votes
+    pub fn new(
+        votes: ProposalVotes,
+        total_voting_power: VotePower,
+        tally_type: TallyType,
+        voting_mode: VotingMode,
+        tally_epoch: Epoch,
+    ) -> Self {
+        if !voting_mode.is_linear() {
+            let (validators, delegators, _) =
+                tally_by_account(&votes, voting_mode);
+            let projected_result = compute_proposal_result_with_voting_mode(
+                votes,
+                total_voting_power,
+                tally_type,
+                voting_mode,
+            );
+            return Self {
+                tally_epoch,
+                validators,
+                delegators,
+                projected_result,
+            };
+        }
+
+        let mut delegators = VotePowerBreakdown::default();
+        for (delegator, delegations) in &votes.delegator_voting_power {
+            let vote = match votes.delegators_vote.get(delegator) {
+                Some(vote) => vote,
+                None => continue,
+            };
+            for voting_power in delegations.values() {
+                delegators.add_vote(vote, *voting_power);
+            }
+        }
+
+        let projected_result =
+            compute_proposal_result(votes, total_voting_power, tally_type);
+        let validators = VotePowerBreakdown {
+            yay: projected_result
+                .total_yay_power
+                .checked_sub(delegators.yay)
+                .unwrap_or_default(),
+            nay: projected_result
+                .total_nay_power
+                .checked_sub(delegators.nay)
+                .unwrap_or_default(),
+            abstain: projected_result
+                .total_abstain_power
+                .checked_sub(delegators.abstain)
+                .unwrap_or_default(),
+        };
+
+        Self {
+            tally_epoch,
+            validators,
+            delegators,
+            projected_result,
+        }
+    }
+
+    /// Apply the veto threshold to the projected result
+    pub fn with_veto_threshold(self, veto_threshold: Dec) -> Self {
+        Self {
+            projected_result: self
+                .projected_result
+                .with_veto_threshold(veto_threshold),
+            ..self
+        }
+    }
+}
+
 /// General representation of a vote
 #[derive(Debug, Clone)]
 pub enum TallyVote {
@@ -275,6 +583,14 @@ impl TallyVote {
         }
     }
 
+    /// Check if a vote is a veto
+    pub fn is_veto(&self) -> bool {
+        match self {
+            TallyVote::OnChain(vote) => vote.is_veto(),
+            TallyVote::Offline(vote) => vote.is_veto(),
+        }
+    }
+
     /// Check if two votes are equal, returns an error if the variants of the
     /// two instances are different
     pub fn is_same_side(&self, other: &TallyVote) -> bool {
@@ -358,10 +674,14 @@ pub fn compute_proposal_result(
     let mut yay_voting_power = VotePower::default();
     let mut nay_voting_power = VotePower::default();
     let mut abstain_voting_power = VotePower::default();
+    let mut veto_voting_power = VotePower::default();
 
     for (address, vote_power) in votes.validator_voting_power {
         let vote_type = votes.validators_vote.get(&address);
         if let Some(vote) = vote_type {
+            if vote.is_veto() {
+                veto_voting_power += vote_power;
+            }
             if vote.is_yay() {
                 yay_voting_power += vote_power;
             } else if vote.is_nay() {
@@ -379,6 +699,15 @@ pub fn compute_proposal_result(
         };
         for (validator, voting_power) in delegations {
             let validator_vote = votes.validators_vote.get(&validator);
+            // The delegator's vote overrides the veto of its validator
+            match (
+                validator_vote.map_or(false, TallyVote::is_veto),
+                delegator_vote.is_veto(),
+            ) {
+                (false, true) => veto_voting_power += voting_power,
+                (true, false) => veto_voting_power -= voting_power,
+                _ => {}
+            }
             if let Some(validator_vote) = validator_vote {
                 let validator_vote_is_same_side =
                     validator_vote.is_same_side(delegator_vote);
@@ -432,9 +761,135 @@ pub fn compute_proposal_result(
         total_yay_power: yay_voting_power,
         total_nay_power: nay_voting_power,
         total_abstain_power: abstain_voting_power,
+        total_veto_power: veto_voting_power,
     }
 }
 
+/// Compute the result of a proposal tallied with the given voting mode. With
+/// the linear mode, this is the same as [`compute_proposal_result`].
+pub fn compute_proposal_result_with_voting_mode(
+    votes: ProposalVotes,
+    total_voting_power: VotePower,
+    tally_type: TallyType,
+    voting_mode: VotingMode,
+) -> ProposalResult {
+    if voting_mode.is_linear() {
+        return compute_proposal_result(votes, total_voting_power, tally_type);
+    }
+
+    let (validators, delegators, veto_voting_power) =
+        tally_by_account(&votes, voting_mode);
+    let yay_voting_power = validators.yay + delegators.yay;
+    let nay_voting_power = validators.nay + delegators.nay;
+    let abstain_voting_power = validators.abstain + delegators.abstain;
+
+    let tally_result = TallyResult::new(
+        &tally_type,
+        yay_voting_power,
+        nay_voting_power,
+        abstain_voting_power,
+        total_voting_power,
+    );
+
+    ProposalResult {
+        result: tally_result,
+        tally_type,
+        total_voting_power,
+        total_yay_power: yay_voting_power,
+        total_nay_power: nay_voting_power,
+        total_abstain_power: abstain_voting_power,
+        total_veto_power: veto_voting_power,
+    }
+}
+
+/// Split the votes between validators and delegators, each account voting
+/// with the voting power given by the voting mode to its own stake: the stake
+/// of the delegators who voted is not counted in the stake of their
+/// validators. Returns the validators' and the delegators' voting power and
+/// the voting power of the vetoes.
+fn tally_by_account(
+    votes: &ProposalVotes,
+    voting_mode: VotingMode,
+) -> (VotePowerBreakdown, VotePowerBreakdown, VotePower) {
+    let mut validator_stakes = votes.validator_voting_power.clone();
+    let mut delegators = VotePowerBreakdown::default();
+    let mut veto_voting_power = VotePower::default();
+
+    for (delegator, delegations) in &votes.delegator_voting_power {
+        let vote = match votes.delegators_vote.get(delegator) {
+            Some(vote) => vote,
+            None => continue,
+        };
+        let mut stake = VotePower::default();
+        for (validator, delegation) in delegations {
+            stake += *delegation;
+            if let Some(validator_stake) = validator_stakes.get_mut(validator) {
+                *validator_stake = validator_stake
+                    .checked_sub(*delegation)
+                    .unwrap_or_default();
+            }
+        }
+        let voting_power = voting_mode.voting_power(stake);
+        delegators.add_vote(vote, voting_power);
+        if vote.is_veto() {
+            veto_voting_power += voting_power;
+        }
+    }
+
+    let mut validators = VotePowerBreakdown::default();
+    for (validator, stake) in validator_stakes {
+        let vote = match votes.validators_vote.get(&validator) {
+            Some(vote) => vote,
+            None => continue,
+        };
+        let voting_power = voting_mode.voting_power(stake);
+        validators.add_vote(vote, voting_power);
+        if vote.is_veto() {
+            veto_voting_power += voting_power;
+        }
+    }
+
+    (validators, delegators, veto_voting_power)
+}
+
+/// The maximum length of a chain of vote delegations followed when tallying
+pub const MAX_VOTE_DELEGATION_DEPTH: usize = 10;
+
+/// Find the votes followed by the accounts which haven't voted, walking the
+/// chains of vote delegations back from the voters with the given lookup of
+/// the delegators of a delegate. An account follows the vote of the first
+/// delegate who has voted on its chain of delegations, unless the chain loops
+/// back before or is longer than [`MAX_VOTE_DELEGATION_DEPTH`].
+pub fn resolve_vote_delegations<V, E>(
+    votes: &HashMap<Address, V>,
+    mut get_delegators: impl FnMut(&Address) -> Result<Vec<Address>, E>,
+) -> Result<HashMap<Address, V>, E>
+where
+    V: Clone,
+{
+    let mut followed: HashMap<Address, V> = HashMap::default();
+    let mut delegates: Vec<(Address, V)> = votes
+        .iter()
+        .map(|(voter, vote)| (voter.clone(), vote.clone()))
+        .collect();
+    for _ in 0..MAX_VOTE_DELEGATION_DEPTH {
+        let mut next = vec![];
+        for (delegate, vote) in delegates {
+            for delegator in get_delegators(&delegate)? {
+                if votes.contains_key(&delegator)
+                    || followed.contains_key(&delegator)
+                {
+                    continue;
+                }
+                followed.insert(delegator.clone(), vote.clone());
+                next.push((delegator, vote.clone()));
+            }
+        }
+        delegates = next;
+    }
+    Ok(followed)
+}
+
 /// Calculate the valid voting window for validator given a proposal epoch
 /// details
 pub fn is_valid_validator_voting_period(
@@ -458,6 +913,7 @@ mod test {
     use namada_core::types::address;
 
     use super::*;
+    use crate::parameters::{DepositAction, DepositPolicy};
 
     #[test]
     fn test_proposal_result_no_votes_should_fail() {
@@ -1404,4 +1860,352 @@ mod test {
 
         assert!(!proposal_result.two_thirds_nay_over_two_thirds_total())
     }
+
+    #[test]
+    fn test_proposal_veto() {
+        let mut proposal_votes = ProposalVotes::default();
+
+        let validator_address = address::testing::established_address_1();
+        let validator_voting_power = token::Amount::from_u64(100);
+        proposal_votes.add_validator(
+            &validator_address,
+            validator_voting_power,
+            ProposalVote::NayWithVeto.into(),
+        );
+
+        let validator_address_two = address::testing::established_address_2();
+        let validator_voting_power_two = token::Amount::from_u64(200);
+        proposal_votes.add_validator(
+            &validator_address_two,
+            validator_voting_power_two,
+            ProposalVote::Yay.into(),
+        );
+
+        // The delegator overrides the veto of its validator
+        let delegator_address = address::testing::established_address_3();
+        let delegator_voting_power = token::Amount::from_u64(10);
+        proposal_votes.add_delegator(
+            &delegator_address,
+            &validator_address,
+            delegator_voting_power,
+            ProposalVote::Yay.into(),
+        );
+
+        let proposal_result = compute_proposal_result(
+            proposal_votes,
+            token::Amount::from_u64(300),
+            TallyType::OneHalfOverOneThird,
+        );
+
+        assert!(matches!(proposal_result.result, TallyResult::Passed));
+        assert_eq!(proposal_result.total_yay_power, token::Amount::from(210));
+        assert_eq!(proposal_result.total_nay_power, token::Amount::from(90));
+        assert_eq!(proposal_result.total_veto_power, token::Amount::from(90));
+
+        // The veto voting power is below a third of the voted power
+        let proposal_result_below_threshold =
+            proposal_result.with_veto_threshold(Dec::one() / 3);
+        assert!(matches!(
+            proposal_result_below_threshold.result,
+            TallyResult::Passed
+        ));
+
+        // The veto voting power is above a quarter of the voted power
+        let proposal_result_above_threshold =
+            proposal_result.with_veto_threshold(Dec::one() / 4);
+        assert!(matches!(
+            proposal_result_above_threshold.result,
+            TallyResult::Vetoed
+        ));
+    }
+
+    #[test]
+    fn test_proposal_expedited_thresholds() {
+        let mut proposal_votes = ProposalVotes::default();
+
+        let validator_address = address::testing::established_address_1();
+        let validator_voting_power = token::Amount::from_u64(100);
+        proposal_votes.add_validator(
+            &validator_address,
+            validator_voting_power,
+            ProposalVote::Yay.into(),
+        );
+
+        let validator_address_two = address::testing::established_address_2();
+        let validator_voting_power_two = token::Amount::from_u64(50);
+        proposal_votes.add_validator(
+            &validator_address_two,
+            validator_voting_power_two,
+            ProposalVote::Nay.into(),
+        );
+
+        let proposal_result = compute_proposal_result(
+            proposal_votes,
+            token::Amount::from_u64(200),
+            TallyType::OneHalfOverOneThird,
+        );
+        assert!(matches!(proposal_result.result, TallyResult::Passed));
+
+        // 3/4 of the total voting power voted and 2/3 of it voted yay
+        let expedited_result = proposal_result
+            .with_expedited_thresholds(Dec::two() / 3, Dec::two() / 3);
+        assert!(matches!(expedited_result.result, TallyResult::Passed));
+
+        // The quorum isn't reached
+        let expedited_result = proposal_result
+            .with_expedited_thresholds(Dec::new(8, 1).unwrap(), Dec::two() / 3);
+        assert!(matches!(expedited_result.result, TallyResult::Rejected));
+
+        // The threshold isn't reached
+        let expedited_result = proposal_result.with_expedited_thresholds(
+            Dec::two() / 3,
+            Dec::new(75, 2).unwrap(),
+        );
+        assert!(matches!(expedited_result.result, TallyResult::Rejected));
+    }
+
+    #[test]
+    fn test_proposal_deposit_policy() {
+        let policy = DepositPolicy::default();
+
+        // Nobody voted, the proposal is rejected without reaching quorum
+        let proposal_result = compute_proposal_result(
+            ProposalVotes::default(),
+            token::Amount::from_u64(300),
+            TallyType::OneHalfOverOneThird,
+        );
+        assert!(proposal_result.is_expired());
+        assert_eq!(policy.action(&proposal_result), DepositAction::Burn);
+
+        // The proposal is rejected after reaching quorum
+        let mut proposal_votes = ProposalVotes::default();
+        let validator_address = address::testing::established_address_1();
+        proposal_votes.add_validator(
+            &validator_address,
+            token::Amount::from_u64(200),
+            ProposalVote::Nay.into(),
+        );
+        let proposal_result = compute_proposal_result(
+            proposal_votes.clone(),
+            token::Amount::from_u64(300),
+            TallyType::OneHalfOverOneThird,
+        );
+        assert!(!proposal_result.is_expired());
+        assert_eq!(policy.action(&proposal_result), DepositAction::Refund);
+
+        // The proposal is vetoed
+        let vetoed_result = ProposalResult {
+            result: TallyResult::Vetoed,
+            ..proposal_result
+        };
+        assert_eq!(policy.action(&vetoed_result), DepositAction::Burn);
+
+        // A proposal with this tally type never expires
+        let proposal_result = compute_proposal_result(
+            proposal_votes,
+            token::Amount::from_u64(300),
+            TallyType::LessOneHalfOverOneThirdNay,
+        );
+        assert!(matches!(proposal_result.result, TallyResult::Rejected));
+        assert!(!proposal_result.is_expired());
+    }
+
+    #[test]
+    fn test_proposal_tally_breakdown() {
+        let mut proposal_votes = ProposalVotes::default();
+
+        let validator_address = address::testing::established_address_1();
+        proposal_votes.add_validator(
+            &validator_address,
+            token::Amount::from_u64(100),
+            ProposalVote::Yay.into(),
+        );
+
+        // A delegator of the validator overrides its vote
+        let delegator_address = address::testing::established_address_2();
+        proposal_votes.add_delegator(
+            &delegator_address,
+            &validator_address,
+            token::Amount::from_u64(30),
+            ProposalVote::Nay.into(),
+        );
+
+        // A delegator of a validator who hasn't voted
+        let delegator_address_two = address::testing::established_address_3();
+        proposal_votes.add_delegator(
+            &delegator_address_two,
+            &address::testing::established_address_4(),
+            token::Amount::from_u64(20),
+            ProposalVote::Abstain.into(),
+        );
+
+        let tally = ProposalTally::new(
+            proposal_votes,
+            token::Amount::from_u64(300),
+            TallyType::TwoThirds,
+            VotingMode::Linear,
+            Epoch(3),
+        );
+
+        assert_eq!(tally.validators.yay, token::Amount::from_u64(70));
+        assert_eq!(tally.validators.nay, token::Amount::zero());
+        assert_eq!(tally.validators.abstain, token::Amount::zero());
+        assert_eq!(tally.delegators.yay, token::Amount::zero());
+        assert_eq!(tally.delegators.nay, token::Amount::from_u64(30));
+        assert_eq!(tally.delegators.abstain, token::Amount::from_u64(20));
+
+        // Half of the total voting power voted, the quorum isn't reached yet
+        let projected_result = tally.projected_result;
+        assert_eq!(
+            projected_result.total_voted_power(),
+            token::Amount::from_u64(120)
+        );
+        assert_eq!(
+            projected_result.quorum_power(),
+            Some(token::Amount::from_u64(200))
+        );
+        assert!(matches!(projected_result.result, TallyResult::Rejected));
+    }
+
+    #[test]
+    fn test_proposal_voting_modes() {
+        let mut proposal_votes = ProposalVotes::default();
+
+        let validator_address = address::testing::established_address_1();
+        proposal_votes.add_validator(
+            &validator_address,
+            token::Amount::from_u64(100),
+            ProposalVote::Yay.into(),
+        );
+
+        // A delegator of the validator overrides its vote
+        proposal_votes.add_delegator(
+            &address::testing::established_address_2(),
+            &validator_address,
+            token::Amount::from_u64(36),
+            ProposalVote::Nay.into(),
+        );
+
+        // A delegator of a validator who hasn't voted
+        proposal_votes.add_delegator(
+            &address::testing::established_address_3(),
+            &address::testing::established_address_4(),
+            token::Amount::from_u64(16),
+            ProposalVote::Yay.into(),
+        );
+
+        let linear = compute_proposal_result_with_voting_mode(
+            proposal_votes.clone(),
+            token::Amount::from_u64(116),
+            TallyType::TwoThirds,
+            VotingMode::Linear,
+        );
+        assert_eq!(linear.total_yay_power, token::Amount::from_u64(80));
+        assert_eq!(linear.total_nay_power, token::Amount::from_u64(36));
+        assert!(matches!(linear.result, TallyResult::Passed));
+
+        // The accounts vote with the square root of their own stake
+        let quadratic = compute_proposal_result_with_voting_mode(
+            proposal_votes.clone(),
+            token::Amount::from_u64(18),
+            TallyType::TwoThirds,
+            VotingMode::Quadratic,
+        );
+        assert_eq!(quadratic.total_yay_power, token::Amount::from_u64(12));
+        assert_eq!(quadratic.total_nay_power, token::Amount::from_u64(6));
+        assert!(matches!(quadratic.result, TallyResult::Passed));
+
+        // The voting power of each account is capped
+        let capped = compute_proposal_result_with_voting_mode(
+            proposal_votes,
+            token::Amount::from_u64(56),
+            TallyType::TwoThirds,
+            VotingMode::Capped(token::Amount::from_u64(20)),
+        );
+        assert_eq!(capped.total_yay_power, token::Amount::from_u64(36));
+        assert_eq!(capped.total_nay_power, token::Amount::from_u64(20));
+        assert!(matches!(capped.result, TallyResult::Rejected));
+
+        // Only signaling proposals can use a non-linear voting mode
+        let signaling = ProposalType::Text;
+        let parameter_change = ProposalType::ParameterChange(vec![]);
+        assert!(VotingMode::Quadratic.is_valid_for(&signaling));
+        assert!(!VotingMode::Quadratic.is_valid_for(&parameter_change));
+        assert!(VotingMode::Linear.is_valid_for(&parameter_change));
+        assert!(
+            !VotingMode::Capped(token::Amount::zero()).is_valid_for(&signaling)
+        );
+    }
+
+    /// Resolve the vote delegations given as a map from the delegator to its
+    /// delegate
+    fn resolve(
+        delegations: &HashMap<Address, Address>,
+        votes: &HashMap<Address, ProposalVote>,
+    ) -> HashMap<Address, ProposalVote> {
+        resolve_vote_delegations(votes, |delegate| {
+            Ok::<_, ()>(
+                delegations
+                    .iter()
+                    .filter(|(_, d)| *d == delegate)
+                    .map(|(delegator, _)| delegator.clone())
+                    .collect(),
+            )
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_resolve_vote_delegations() {
+        let alice = address::testing::established_address_1();
+        let bob = address::testing::established_address_2();
+        let carol = address::testing::established_address_3();
+        let dave = address::testing::established_address_4();
+
+        // alice -> bob -> carol, only carol has voted
+        let delegations = HashMap::from([
+            (alice.clone(), bob.clone()),
+            (bob.clone(), carol.clone()),
+        ]);
+        let mut votes = HashMap::from([(carol.clone(), ProposalVote::Yay)]);
+        let followed = resolve(&delegations, &votes);
+        assert_eq!(followed.get(&alice), Some(&ProposalVote::Yay));
+        assert_eq!(followed.get(&bob), Some(&ProposalVote::Yay));
+        assert_eq!(followed.get(&dave), None);
+        assert_eq!(followed.get(&carol), None);
+
+        // The first delegate who has voted on the chain is followed
+        votes.insert(bob.clone(), ProposalVote::Nay);
+        let followed = resolve(&delegations, &votes);
+        assert_eq!(followed.get(&alice), Some(&ProposalVote::Nay));
+        assert_eq!(followed.get(&bob), None);
+
+        // A loop without any vote is not resolved
+        let delegations = HashMap::from([
+            (alice.clone(), bob.clone()),
+            (bob.clone(), alice.clone()),
+        ]);
+        let votes = HashMap::from([(carol, ProposalVote::Yay)]);
+        assert!(resolve(&delegations, &votes).is_empty());
+    }
+
+    #[test]
+    fn test_resolve_vote_delegations_max_depth() {
+        let addresses: Vec<Address> = (0..=MAX_VOTE_DELEGATION_DEPTH + 1)
+            .map(|_| address::testing::gen_established_address())
+            .collect();
+        let delegations: HashMap<Address, Address> = addresses
+            .windows(2)
+            .map(|pair| (pair[0].clone(), pair[1].clone()))
+            .collect();
+
+        // The last address is one delegation too far from the first one
+        let votes = HashMap::from([(
+            addresses[MAX_VOTE_DELEGATION_DEPTH + 1].clone(),
+            ProposalVote::Yay,
+        )]);
+        let followed = resolve(&delegations, &votes);
+        assert_eq!(followed.get(&addresses[0]), None);
+        assert_eq!(followed.get(&addresses[1]), Some(&ProposalVote::Yay));
+    }
 }
@@ -7,12 +7,14 @@ use namada_core::ibc::apps::transfer::types::msgs::transfer::MsgTransfer;
 use namada_core::ibc::apps::transfer::types::packet::PacketData;
 use namada_core::ibc::apps::transfer::types::PrefixedCoin;
 use namada_core::ibc::core::channel::types::timeout::TimeoutHeight;
+use namada_core::ibc::core::client::types::Height;
+use namada_core::ibc::primitives::proto::Protobuf;
 use namada_core::ibc::primitives::Msg;
 use namada_core::tendermint::Time as TmTime;
 use namada_core::types::address::{Address, InternalAddress};
 use namada_core::types::hash::Hash;
 use namada_core::types::ibc::IbcEvent;
-use namada_core::types::storage::Epochs;
+use namada_core::types::storage::{BlockHeight, Epochs, Key};
 use namada_core::types::time::DateTimeUtc;
 use namada_core::types::token::DenominatedAmount;
 use namada_governance::storage::proposal::PGFIbcTarget;
@@ -188,3 +190,155 @@ where
     let mut actions = IbcActions::new(Rc::new(RefCell::new(ctx)));
     actions.execute(&data).into_storage_result()
 }
+
+/// Prune the packet statuses whose retention window ends at the given height
+pub fn prune_packet_statuses<D, H>(
+    wl_storage: &mut WlStorage<D, H>,
+    height: BlockHeight,
+) -> StorageResult<()>
+where
+    D: DB + for<'iter> DBIter<'iter> + 'static,
+    H: StorageHasher + 'static,
+{
+    let prefix = crate::storage::packet_status_pruning_prefix(height);
+    let statuses: Vec<(Key, Key)> =
+        namada_storage::iter_prefix(wl_storage, &prefix)?
+            .collect::<StorageResult<_>>()?;
+    for (pruning_key, status_key) in statuses {
+        wl_storage.delete(&status_key)?;
+        wl_storage.delete(&pruning_key)?;
+    }
+    Ok(())
+}
+
+/// Get the oldest height of this chain at which an IBC client was updated
+/// with the consensus state it still refers to
+pub fn oldest_client_update_height<S>(
+    storage: &S,
+) -> StorageResult<Option<BlockHeight>>
+where
+    S: StorageRead,
+{
+    let prefix = crate::storage::clients_prefix();
+    let mut oldest_height: Option<BlockHeight> = None;
+    for entry in namada_storage::iter_prefix_bytes(storage, &prefix)? {
+        let (key, value) = entry?;
+        let is_update_height_key = match crate::storage::client_id(&key) {
+            Ok(client_id) => {
+                key == crate::storage::client_update_height_key(&client_id)
+            }
+            Err(_) => false,
+        };
+        if !is_update_height_key {
+            continue;
+        }
+        let height = Height::decode_vec(&value).map_err(|_| {
+            StorageError::new_const("Decoding a client update height failed")
+        })?;
+        let height = BlockHeight(height.revision_height());
+        oldest_height = Some(match oldest_height {
+            Some(oldest_height) => std::cmp::min(oldest_height, height),
+            None => height,
+        });
+    }
+    Ok(oldest_height)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use namada_core::ibc::core::host::types::identifiers::{
+        ChannelId, ClientId, PortId, Sequence,
+    };
+    use namada_core::types::ibc::{
+        PacketDirection, PacketStage, PACKET_STATUS_RETENTION_BLOCKS,
+    };
+    use namada_state::testing::TestWlStorage;
+
+    use super::*;
+    use crate::storage::{packet_status_key, packet_status_pruning_prefix};
+
+    #[test]
+    fn test_prune_packet_statuses() {
+        let mut wl_storage = TestWlStorage::default();
+        wl_storage.storage.block.height = BlockHeight(10);
+        let port_id = PortId::transfer();
+        let channel_id = ChannelId::new(0);
+        let statuses = [
+            (PacketDirection::Outgoing, 1, PacketStage::Sent),
+            (PacketDirection::Outgoing, 2, PacketStage::Acknowledged),
+            (PacketDirection::Outgoing, 3, PacketStage::TimedOut),
+            (PacketDirection::Incoming, 1, PacketStage::Received),
+        ];
+        let mut ctx = IbcProtocolContext {
+            wl_storage: &mut wl_storage,
+        };
+        for (direction, sequence, stage) in statuses {
+            ctx.store_packet_status(
+                direction,
+                &port_id,
+                &channel_id,
+                Sequence::from(sequence),
+                stage,
+            )
+            .unwrap();
+        }
+
+        // The statuses are kept until the end of the retention window
+        let pruning_height = BlockHeight(10) + PACKET_STATUS_RETENTION_BLOCKS;
+        prune_packet_statuses(&mut wl_storage, pruning_height.prev_height())
+            .unwrap();
+        for (direction, sequence, _) in statuses {
+            let key = packet_status_key(
+                direction,
+                &port_id,
+                &channel_id,
+                Sequence::from(sequence),
+            );
+            assert!(wl_storage.has_key(&key).unwrap());
+        }
+
+        // Only the status of the packet in flight is left
+        prune_packet_statuses(&mut wl_storage, pruning_height).unwrap();
+        for (direction, sequence, stage) in statuses {
+            let key = packet_status_key(
+                direction,
+                &port_id,
+                &channel_id,
+                Sequence::from(sequence),
+            );
+            assert_eq!(wl_storage.has_key(&key).unwrap(), !stage.is_terminal());
+        }
+        let prefix = packet_status_pruning_prefix(pruning_height);
+        assert!(namada_storage::iter_prefix_bytes(&wl_storage, &prefix)
+            .unwrap()
+            .next()
+            .is_none());
+    }
+
+    #[test]
+    fn test_oldest_client_update_height() {
+        let mut wl_storage = TestWlStorage::default();
+        assert_eq!(oldest_client_update_height(&wl_storage).unwrap(), None);
+
+        let client_ids = ["07-tendermint-0", "07-tendermint-1"]
+            .map(|client_id| ClientId::from_str(client_id).unwrap());
+        for (client_id, host_height) in client_ids.iter().zip([20, 10]) {
+            let key = crate::storage::client_update_height_key(client_id);
+            let height = Height::new(0, host_height).unwrap();
+            wl_storage.write_bytes(&key, height.encode_vec()).unwrap();
+            // the consensus states are at the heights of the counterparty
+            let key = crate::storage::consensus_state_key(
+                client_id,
+                Height::new(1, 5).unwrap(),
+            );
+            wl_storage.write_bytes(&key, vec![0]).unwrap();
+        }
+
+        assert_eq!(
+            oldest_client_update_height(&wl_storage).unwrap(),
+            Some(BlockHeight(10))
+        );
+    }
+}
@@ -0,0 +1,896 @@
+//! Channel upgrade handshake
+//!
+//! An open channel can change its packet ordering and its application
+//! version without opening a new channel. Keeping the channel ID keeps the
+//! denom traces of the tokens which have been transferred over the channel.
+//!
+//! The handshake follows ICS-004 channel upgrades:
+//! - `Init`: a governance proposal proposes the upgrade on one end
+//! - `Try`: the counterparty accepts the proposed upgrade
+//! - `Ack`: the initiator acknowledges the accepted upgrade
+//! - `Confirm`: each end confirms the counterparty has flushed the in-flight
+//!   packets. The upgrade is applied when both ends have been flushed.
+//! - `Open`: the remaining end applies the upgrade after the counterparty has
+//!   opened the upgraded channel
+//! - `Cancel`: either end aborts the upgrade with the proof of the counterparty
+//!   error receipt or by a governance proposal
+//!
+//! The upgrade and the error receipt are committed as the ICS-004 `Upgrade`
+//! and `ErrorReceipt` at the ICS-004 paths, so that they can be proven on
+//! the counterparty.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use namada_core::ibc::apps::transfer::types::VERSION as TRANSFER_VERSION;
+use namada_core::ibc::core::channel::types::channel::{
+    ChannelEnd, Counterparty, Order, State as ChannelState,
+};
+use namada_core::ibc::core::channel::types::Version;
+use namada_core::ibc::core::client::context::client_state::ClientStateCommon;
+use namada_core::ibc::core::client::context::consensus_state::ConsensusState;
+use namada_core::ibc::core::client::types::Height;
+use namada_core::ibc::core::commitment_types::commitment::{
+    CommitmentPrefix, CommitmentProofBytes, CommitmentRoot,
+};
+use namada_core::ibc::core::commitment_types::merkle::{
+    apply_prefix, MerkleProof,
+};
+use namada_core::ibc::core::commitment_types::proto::v1::MerkleProof as RawMerkleProof;
+use namada_core::ibc::core::connection::types::State as ConnectionState;
+use namada_core::ibc::core::handler::types::error::ContextError;
+use namada_core::ibc::core::host::types::identifiers::{ChannelId, PortId};
+use namada_core::ibc::core::host::types::path::{ChannelEndPath, Path};
+use namada_core::ibc::primitives::proto::Protobuf;
+use namada_core::types::ibc::{
+    ChannelUpgrade, ChannelUpgradeErrorReceipt, ChannelUpgradeFields,
+    ChannelUpgradeState, CounterpartyUpgradeProof, IbcChannelOrdering,
+    IbcEvent, MsgChannelUpgrade,
+};
+use namada_storage::StorageRead;
+use prost::Message;
+
+use crate::context::client::AnyClientState;
+use crate::{storage, Error, IbcCommonContext};
+
+/// The event type of the channel upgrade
+pub const EVENT_TYPE_CHANNEL_UPGRADE: &str = "channel_upgrade";
+
+/// The raw ICS-004 types committed for the channel upgrades
+mod raw {
+    use prost::Message;
+
+    #[derive(Clone, PartialEq, Message)]
+    pub struct Height {
+        #[prost(uint64, tag = "1")]
+        pub revision_number: u64,
+        #[prost(uint64, tag = "2")]
+        pub revision_height: u64,
+    }
+
+    #[derive(Clone, PartialEq, Message)]
+    pub struct Timeout {
+        #[prost(message, optional, tag = "1")]
+        pub height: Option<Height>,
+        #[prost(uint64, tag = "2")]
+        pub timestamp: u64,
+    }
+
+    #[derive(Clone, PartialEq, Message)]
+    pub struct UpgradeFields {
+        #[prost(int32, tag = "1")]
+        pub ordering: i32,
+        #[prost(string, repeated, tag = "2")]
+        pub connection_hops: Vec<String>,
+        #[prost(string, tag = "3")]
+        pub version: String,
+    }
+
+    #[derive(Clone, PartialEq, Message)]
+    pub struct Upgrade {
+        #[prost(message, optional, tag = "1")]
+        pub fields: Option<UpgradeFields>,
+        #[prost(message, optional, tag = "2")]
+        pub timeout: Option<Timeout>,
+        #[prost(uint64, tag = "3")]
+        pub next_sequence_send: u64,
+    }
+
+    #[derive(Clone, PartialEq, Message)]
+    pub struct ErrorReceipt {
+        #[prost(uint64, tag = "1")]
+        pub sequence: u64,
+        #[prost(string, tag = "2")]
+        pub message: String,
+    }
+}
+
+/// Encode the upgrade as the ICS-004 `Upgrade`. The timeout height of the
+/// upgrade is on the chain of the given revision.
+pub fn encode_upgrade(
+    upgrade: &ChannelUpgrade,
+    timeout_revision_number: u64,
+) -> Vec<u8> {
+    let ordering = match upgrade.fields.ordering {
+        IbcChannelOrdering::Unordered => 1,
+        IbcChannelOrdering::Ordered => 2,
+    };
+    raw::Upgrade {
+        fields: Some(raw::UpgradeFields {
+            ordering,
+            connection_hops: upgrade.fields.connection_hops.clone(),
+            version: upgrade.fields.version.clone(),
+        }),
+        timeout: Some(raw::Timeout {
+            height: Some(raw::Height {
+                revision_number: timeout_revision_number,
+                revision_height: upgrade.timeout_height,
+            }),
+            timestamp: upgrade.timeout_timestamp,
+        }),
+        next_sequence_send: upgrade.next_sequence_send,
+    }
+    .encode_to_vec()
+}
+
+/// Encode the error receipt as the ICS-004 `ErrorReceipt`
+pub fn encode_error_receipt(receipt: &ChannelUpgradeErrorReceipt) -> Vec<u8> {
+    raw::ErrorReceipt {
+        sequence: receipt.sequence,
+        message: receipt.message.clone(),
+    }
+    .encode_to_vec()
+}
+
+/// Decode the ICS-004 `ErrorReceipt`
+pub fn decode_error_receipt(
+    bytes: &[u8],
+) -> Result<ChannelUpgradeErrorReceipt, Error> {
+    let receipt = raw::ErrorReceipt::decode(bytes)
+        .map_err(|e| Error::ChannelUpgrade(e.to_string()))?;
+    Ok(ChannelUpgradeErrorReceipt {
+        sequence: receipt.sequence,
+        message: receipt.message,
+    })
+}
+
+/// The state change resulting from a handshake step
+#[derive(Debug)]
+enum Transition {
+    /// Store the new or updated upgrade
+    Store(ChannelUpgrade),
+    /// Apply the upgrade to the channel end
+    Open(ChannelEnd),
+    /// Abort the upgrade with the error receipt
+    Cancel(ChannelUpgradeErrorReceipt),
+}
+
+/// Validate a channel upgrade message
+pub fn validate<C>(ctx: &C, msg: &MsgChannelUpgrade) -> Result<(), Error>
+where
+    C: IbcCommonContext,
+{
+    transition(ctx, msg).map(|_| ())
+}
+
+/// Execute a channel upgrade message
+pub fn execute<C>(ctx: &mut C, msg: &MsgChannelUpgrade) -> Result<(), Error>
+where
+    C: IbcCommonContext,
+{
+    let (port_id, channel_id) = parse_port_channel(msg)?;
+    let (sequence, state) = match transition(ctx, msg)? {
+        Transition::Store(upgrade) => {
+            let channel_end = ctx
+                .channel_end(&port_id, &channel_id)
+                .map_err(context_error)?;
+            let client_state = counterparty_client_state(ctx, &channel_end)?;
+            ctx.store_channel_upgrade(
+                &port_id,
+                &channel_id,
+                &upgrade,
+                client_state.latest_height().revision_number(),
+            )
+            .map_err(context_error)?;
+            ctx.store_channel_upgrade_sequence(
+                &port_id,
+                &channel_id,
+                upgrade.sequence,
+            )
+            .map_err(context_error)?;
+            (upgrade.sequence, format!("{:?}", upgrade.state))
+        }
+        Transition::Open(channel_end) => {
+            let sequence = ctx
+                .channel_upgrade_sequence(&port_id, &channel_id)
+                .map_err(context_error)?;
+            ctx.store_channel(&port_id, &channel_id, channel_end)
+                .map_err(context_error)?;
+            ctx.delete_channel_upgrade(&port_id, &channel_id)
+                .map_err(context_error)?;
+            (sequence, "Open".to_string())
+        }
+        Transition::Cancel(receipt) => {
+            ctx.delete_channel_upgrade(&port_id, &channel_id)
+                .map_err(context_error)?;
+            ctx.store_channel_upgrade_error(&port_id, &channel_id, &receipt)
+                .map_err(context_error)?;
+            (receipt.sequence, "Cancelled".to_string())
+        }
+    };
+
+    let attributes = HashMap::from([
+        ("port_id".to_string(), port_id.to_string()),
+        ("channel_id".to_string(), channel_id.to_string()),
+        ("upgrade_sequence".to_string(), sequence.to_string()),
+        ("upgrade_state".to_string(), state),
+    ]);
+    ctx.emit_ibc_event(IbcEvent {
+        event_type: EVENT_TYPE_CHANNEL_UPGRADE.to_string(),
+        attributes,
+    })
+    .map_err(|e| Error::ChannelUpgrade(e.to_string()))
+}
+
+/// Check if packets can be sent on the channel. No packet can be sent while
+/// the in-flight packets are flushed for the upgrade.
+pub fn check_send_allowed<C>(
+    ctx: &C,
+    port_id: &PortId,
+    channel_id: &ChannelId,
+) -> Result<(), Error>
+where
+    C: IbcCommonContext,
+{
+    match ctx
+        .channel_upgrade(port_id, channel_id)
+        .map_err(context_error)?
+    {
+        Some(upgrade) if upgrade.state != ChannelUpgradeState::Init => {
+            Err(Error::ChannelUpgrade(format!(
+                "The channel is being upgraded: Port ID {port_id}, Channel ID \
+                 {channel_id}"
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn transition<C>(ctx: &C, msg: &MsgChannelUpgrade) -> Result<Transition, Error>
+where
+    C: IbcCommonContext,
+{
+    let (port_id, channel_id) = parse_port_channel(msg)?;
+    let channel_end = ctx
+        .channel_end(&port_id, &channel_id)
+        .map_err(context_error)?;
+    if !channel_end.state_matches(&ChannelState::Open) {
+        return Err(Error::ChannelUpgrade(format!(
+            "The channel isn't open: Port ID {port_id}, Channel ID \
+             {channel_id}"
+        )));
+    }
+    let current = ctx
+        .channel_upgrade(&port_id, &channel_id)
+        .map_err(context_error)?;
+    let last_sequence = ctx
+        .channel_upgrade_sequence(&port_id, &channel_id)
+        .map_err(context_error)?;
+
+    match msg {
+        MsgChannelUpgrade::Init {
+            fields,
+            timeout_height,
+            timeout_timestamp,
+            ..
+        } => {
+            if current.is_some() {
+                return Err(Error::ChannelUpgrade(
+                    "The channel upgrade is already in progress".to_string(),
+                ));
+            }
+            if *timeout_height == 0 && *timeout_timestamp == 0 {
+                return Err(Error::ChannelUpgrade(
+                    "Either the timeout height or the timeout timestamp \
+                     should be set"
+                        .to_string(),
+                ));
+            }
+            let current_hops: Vec<String> = channel_end
+                .connection_hops()
+                .iter()
+                .map(|hop| hop.to_string())
+                .collect();
+            if fields.connection_hops != current_hops {
+                return Err(Error::ChannelUpgrade(
+                    "Changing the connection hops isn't supported".to_string(),
+                ));
+            }
+            if to_order(fields.ordering) == *channel_end.ordering()
+                && fields.version == channel_end.version().to_string()
+            {
+                return Err(Error::ChannelUpgrade(
+                    "The upgrade doesn't change the channel".to_string(),
+                ));
+            }
+            validate_fields(ctx, &port_id, &channel_end, fields)?;
+            Ok(Transition::Store(ChannelUpgrade {
+                fields: fields.clone(),
+                timeout_height: *timeout_height,
+                timeout_timestamp: *timeout_timestamp,
+                sequence: last_sequence + 1,
+                next_sequence_send: 0,
+                state: ChannelUpgradeState::Init,
+            }))
+        }
+        MsgChannelUpgrade::Try {
+            counterparty_upgrade,
+            proof,
+            ..
+        } => {
+            if counterparty_upgrade.state != ChannelUpgradeState::Init {
+                return Err(Error::ChannelUpgrade(
+                    "The counterparty upgrade should be initialized"
+                        .to_string(),
+                ));
+            }
+            if counterparty_upgrade.sequence <= last_sequence {
+                return Err(Error::ChannelUpgrade(format!(
+                    "The counterparty upgrade sequence is stale: Sequence {}, \
+                     Last sequence {last_sequence}",
+                    counterparty_upgrade.sequence
+                )));
+            }
+            // Crossing hellos: both ends proposed the same upgrade
+            if let Some(upgrade) = &current {
+                if upgrade.state != ChannelUpgradeState::Init
+                    || !is_same_upgrade(
+                        &upgrade.fields,
+                        &counterparty_upgrade.fields,
+                    )
+                {
+                    return Err(Error::ChannelUpgrade(
+                        "The counterparty upgrade doesn't match the proposed \
+                         upgrade"
+                            .to_string(),
+                    ));
+                }
+            }
+            check_timeout(ctx, counterparty_upgrade)?;
+            let fields = ChannelUpgradeFields {
+                ordering: counterparty_upgrade.fields.ordering,
+                connection_hops: channel_end
+                    .connection_hops()
+                    .iter()
+                    .map(|hop| hop.to_string())
+                    .collect(),
+                version: counterparty_upgrade.fields.version.clone(),
+            };
+            validate_fields(ctx, &port_id, &channel_end, &fields)?;
+            verify_counterparty_upgrade(
+                ctx,
+                &channel_end,
+                proof,
+                counterparty_upgrade,
+            )?;
+            Ok(Transition::Store(ChannelUpgrade {
+                fields,
+                timeout_height: counterparty_upgrade.timeout_height,
+                timeout_timestamp: counterparty_upgrade.timeout_timestamp,
+                sequence: counterparty_upgrade.sequence,
+                next_sequence_send: next_sequence_send(
+                    ctx,
+                    &port_id,
+                    &channel_id,
+                )?,
+                state: flush_state(ctx, &port_id, &channel_id)?,
+            }))
+        }
+        MsgChannelUpgrade::Ack {
+            counterparty_upgrade,
+            proof,
+            ..
+        } => {
+            let upgrade = current.ok_or_else(no_upgrade)?;
+            if upgrade.state != ChannelUpgradeState::Init {
+                return Err(Error::ChannelUpgrade(
+                    "The upgrade has been already acknowledged".to_string(),
+                ));
+            }
+            if counterparty_upgrade.state == ChannelUpgradeState::Init {
+                return Err(Error::ChannelUpgrade(
+                    "The counterparty should accept the upgrade".to_string(),
+                ));
+            }
+            check_counterparty_upgrade(&upgrade, counterparty_upgrade)?;
+            check_timeout(ctx, &upgrade)?;
+            verify_counterparty_upgrade(
+                ctx,
+                &channel_end,
+                proof,
+                counterparty_upgrade,
+            )?;
+            Ok(Transition::Store(ChannelUpgrade {
+                next_sequence_send: next_sequence_send(
+                    ctx,
+                    &port_id,
+                    &channel_id,
+                )?,
+                state: flush_state(ctx, &port_id, &channel_id)?,
+                ..upgrade
+            }))
+        }
+        MsgChannelUpgrade::Confirm {
+            counterparty_upgrade,
+            proof,
+            ..
+        } => {
+            let upgrade = current.ok_or_else(no_upgrade)?;
+            if upgrade.state == ChannelUpgradeState::Init
+                || counterparty_upgrade.state == ChannelUpgradeState::Init
+            {
+                return Err(Error::ChannelUpgrade(
+                    "Both ends should accept the upgrade".to_string(),
+                ));
+            }
+            check_counterparty_upgrade(&upgrade, counterparty_upgrade)?;
+            verify_counterparty_upgrade(
+                ctx,
+                &channel_end,
+                proof,
+                counterparty_upgrade,
+            )?;
+            let state = flush_state(ctx, &port_id, &channel_id)?;
+            if state == ChannelUpgradeState::FlushComplete
+                && counterparty_upgrade.state
+                    == ChannelUpgradeState::FlushComplete
+            {
+                Ok(Transition::Open(upgraded_channel_end(
+                    &channel_end,
+                    &upgrade,
+                )?))
+            } else {
+                Ok(Transition::Store(ChannelUpgrade { state, ..upgrade }))
+            }
+        }
+        MsgChannelUpgrade::Open { proof, .. } => {
+            let upgrade = current.ok_or_else(no_upgrade)?;
+            if flush_state(ctx, &port_id, &channel_id)?
+                != ChannelUpgradeState::FlushComplete
+            {
+                return Err(Error::ChannelUpgrade(
+                    "The in-flight packets haven't been flushed".to_string(),
+                ));
+            }
+            verify_counterparty_channel(
+                ctx,
+                &port_id,
+                &channel_id,
+                &channel_end,
+                &upgrade,
+                proof,
+            )?;
+            Ok(Transition::Open(upgraded_channel_end(
+                &channel_end,
+                &upgrade,
+            )?))
+        }
+        MsgChannelUpgrade::Cancel { error_receipt, .. } => {
+            let upgrade = current.ok_or_else(no_upgrade)?;
+            let message = match error_receipt {
+                Some((receipt, proof)) => {
+                    if receipt.sequence < upgrade.sequence {
+                        return Err(Error::ChannelUpgrade(format!(
+                            "The counterparty error receipt is stale: \
+                             Sequence {}, Upgrade sequence {}",
+                            receipt.sequence, upgrade.sequence
+                        )));
+                    }
+                    verify_counterparty_error_receipt(
+                        ctx,
+                        &channel_end,
+                        proof,
+                        receipt,
+                    )?;
+                    format!(
+                        "Cancelled by the counterparty: {}",
+                        receipt.message
+                    )
+                }
+                // Authorized by a governance proposal
+                None => "Cancelled by governance".to_string(),
+            };
+            Ok(Transition::Cancel(ChannelUpgradeErrorReceipt {
+                sequence: upgrade.sequence,
+                message,
+            }))
+        }
+    }
+}
+
+fn parse_port_channel(
+    msg: &MsgChannelUpgrade,
+) -> Result<(PortId, ChannelId), Error> {
+    let (port_id, channel_id) = msg.port_channel();
+    let port_id = PortId::from_str(port_id)
+        .map_err(|e| Error::ChannelUpgrade(format!("Invalid port ID: {e}")))?;
+    let channel_id = ChannelId::from_str(channel_id).map_err(|e| {
+        Error::ChannelUpgrade(format!("Invalid channel ID: {e}"))
+    })?;
+    Ok((port_id, channel_id))
+}
+
+fn context_error(e: ContextError) -> Error {
+    Error::Context(Box::new(e))
+}
+
+fn no_upgrade() -> Error {
+    Error::ChannelUpgrade("No channel upgrade is in progress".to_string())
+}
+
+fn to_order(ordering: IbcChannelOrdering) -> Order {
+    match ordering {
+        IbcChannelOrdering::Unordered => Order::Unordered,
+        IbcChannelOrdering::Ordered => Order::Ordered,
+    }
+}
+
+/// The connection hops are specific to each end and aren't compared
+fn is_same_upgrade(a: &ChannelUpgradeFields, b: &ChannelUpgradeFields) -> bool {
+    a.ordering == b.ordering && a.version == b.version
+}
+
+fn check_counterparty_upgrade(
+    upgrade: &ChannelUpgrade,
+    counterparty_upgrade: &ChannelUpgrade,
+) -> Result<(), Error> {
+    if upgrade.sequence != counterparty_upgrade.sequence
+        || !is_same_upgrade(&upgrade.fields, &counterparty_upgrade.fields)
+    {
+        return Err(Error::ChannelUpgrade(format!(
+            "The counterparty upgrade mismatched: Upgrade {upgrade:?}, \
+             Counterparty upgrade {counterparty_upgrade:?}"
+        )));
+    }
+    Ok(())
+}
+
+/// Check the upgrade fields with the connection and the application bound to
+/// the port
+fn validate_fields<C>(
+    ctx: &C,
+    port_id: &PortId,
+    channel_end: &ChannelEnd,
+    fields: &ChannelUpgradeFields,
+) -> Result<(), Error>
+where
+    C: IbcCommonContext,
+{
+    for conn_id in channel_end.connection_hops() {
+        let conn = ctx.connection_end(conn_id).map_err(context_error)?;
+        if !conn.state_matches(&ConnectionState::Open) {
+            return Err(Error::ChannelUpgrade(format!(
+                "The connection isn't open: Connection ID {conn_id}"
+            )));
+        }
+    }
+
+    if *port_id != PortId::transfer() {
+        return Ok(());
+    }
+    // The token transfer requires an unordered channel. The fee middleware
+    // isn't supported, so the transfer version can't be wrapped.
+    if fields.ordering != IbcChannelOrdering::Unordered {
+        return Err(Error::ChannelUpgrade(
+            "The transfer channel should be unordered".to_string(),
+        ));
+    }
+    if fields.version != TRANSFER_VERSION {
+        return Err(Error::ChannelUpgrade(format!(
+            "Unsupported transfer version: {}",
+            fields.version
+        )));
+    }
+    Ok(())
+}
+
+/// The timeout of the upgrade is checked on the counterparty of the
+/// initiator
+fn check_timeout<C>(ctx: &C, upgrade: &ChannelUpgrade) -> Result<(), Error>
+where
+    C: IbcCommonContext,
+{
+    let height = ctx
+        .get_block_height()
+        .map_err(|e| Error::ChannelUpgrade(e.to_string()))?;
+    let timestamp = ctx.host_timestamp().map_err(context_error)?;
+    let height_expired =
+        upgrade.timeout_height != 0 && height.0 >= upgrade.timeout_height;
+    let timestamp_expired = upgrade.timeout_timestamp != 0
+        && timestamp.nanoseconds() >= upgrade.timeout_timestamp;
+    if height_expired || timestamp_expired {
+        return Err(Error::ChannelUpgrade(format!(
+            "The channel upgrade timed out: Upgrade {upgrade:?}"
+        )));
+    }
+    Ok(())
+}
+
+fn next_sequence_send<C>(
+    ctx: &C,
+    port_id: &PortId,
+    channel_id: &ChannelId,
+) -> Result<u64, Error>
+where
+    C: IbcCommonContext,
+{
+    ctx.get_next_sequence_send(port_id, channel_id)
+        .map(u64::from)
+        .map_err(context_error)
+}
+
+fn flush_state<C>(
+    ctx: &C,
+    port_id: &PortId,
+    channel_id: &ChannelId,
+) -> Result<ChannelUpgradeState, Error>
+where
+    C: IbcCommonContext,
+{
+    if ctx
+        .has_in_flight_packets(port_id, channel_id)
+        .map_err(context_error)?
+    {
+        Ok(ChannelUpgradeState::Flushing)
+    } else {
+        Ok(ChannelUpgradeState::FlushComplete)
+    }
+}
+
+fn upgraded_channel_end(
+    channel_end: &ChannelEnd,
+    upgrade: &ChannelUpgrade,
+) -> Result<ChannelEnd, Error> {
+    ChannelEnd::new(
+        ChannelState::Open,
+        to_order(upgrade.fields.ordering),
+        channel_end.counterparty().clone(),
+        channel_end.connection_hops().clone(),
+        Version::new(upgrade.fields.version.clone()),
+    )
+    .map_err(|e| Error::ChannelUpgrade(e.to_string()))
+}
+
+fn verify_counterparty_upgrade<C>(
+    ctx: &C,
+    channel_end: &ChannelEnd,
+    proof: &CounterpartyUpgradeProof,
+    counterparty_upgrade: &ChannelUpgrade,
+) -> Result<(), Error>
+where
+    C: IbcCommonContext,
+{
+    let (port_id, channel_id) = counterparty_port_channel(channel_end)?;
+    let path = storage::channel_upgrade_path(&port_id, &channel_id);
+    // The timeout height of the counterparty upgrade is on this chain
+    let value = encode_upgrade(counterparty_upgrade, 0);
+    verify_counterparty_value(ctx, channel_end, proof, path, value)
+}
+
+fn verify_counterparty_error_receipt<C>(
+    ctx: &C,
+    channel_end: &ChannelEnd,
+    proof: &CounterpartyUpgradeProof,
+    receipt: &ChannelUpgradeErrorReceipt,
+) -> Result<(), Error>
+where
+    C: IbcCommonContext,
+{
+    let (port_id, channel_id) = counterparty_port_channel(channel_end)?;
+    let path = storage::channel_upgrade_error_path(&port_id, &channel_id);
+    let value = encode_error_receipt(receipt);
+    verify_counterparty_value(ctx, channel_end, proof, path, value)
+}
+
+/// Verify the counterparty channel end has been upgraded
+fn verify_counterparty_channel<C>(
+    ctx: &C,
+    port_id: &PortId,
+    channel_id: &ChannelId,
+    channel_end: &ChannelEnd,
+    upgrade: &ChannelUpgrade,
+    proof: &CounterpartyUpgradeProof,
+) -> Result<(), Error>
+where
+    C: IbcCommonContext,
+{
+    let (cp_port_id, cp_channel_id) = counterparty_port_channel(channel_end)?;
+    let conn_id = channel_end.connection_hops().first().ok_or_else(|| {
+        Error::ChannelUpgrade("No connection hop".to_string())
+    })?;
+    let conn = ctx.connection_end(conn_id).map_err(context_error)?;
+    let cp_conn_id =
+        conn.counterparty()
+            .connection_id()
+            .cloned()
+            .ok_or_else(|| {
+                Error::ChannelUpgrade(
+                    "The counterparty connection ID is missing".to_string(),
+                )
+            })?;
+    let expected = ChannelEnd::new(
+        ChannelState::Open,
+        to_order(upgrade.fields.ordering),
+        Counterparty::new(port_id.clone(), Some(channel_id.clone())),
+        vec![cp_conn_id],
+        Version::new(upgrade.fields.version.clone()),
+    )
+    .map_err(|e| Error::ChannelUpgrade(e.to_string()))?;
+
+    let (client_state, prefix, root, proof_bytes, height) =
+        proof_context(ctx, channel_end, proof)?;
+    client_state
+        .validate_proof_height(height)
+        .map_err(|e| Error::ChannelUpgrade(e.to_string()))?;
+    client_state
+        .verify_membership(
+            &prefix,
+            &proof_bytes,
+            &root,
+            Path::ChannelEnd(ChannelEndPath(cp_port_id, cp_channel_id)),
+            expected.encode_vec(),
+        )
+        .map_err(|e| Error::ChannelUpgrade(e.to_string()))
+}
+
+fn counterparty_port_channel(
+    channel_end: &ChannelEnd,
+) -> Result<(PortId, ChannelId), Error> {
+    let counterparty = channel_end.counterparty();
+    let channel_id = counterparty.channel_id().cloned().ok_or_else(|| {
+        Error::ChannelUpgrade(
+            "The counterparty channel ID is missing".to_string(),
+        )
+    })?;
+    Ok((counterparty.port_id().clone(), channel_id))
+}
+
+type ProofContext = (
+    AnyClientState,
+    CommitmentPrefix,
+    CommitmentRoot,
+    CommitmentProofBytes,
+    Height,
+);
+
+/// Get the state of the client of the counterparty chain
+fn counterparty_client_state<C>(
+    ctx: &C,
+    channel_end: &ChannelEnd,
+) -> Result<AnyClientState, Error>
+where
+    C: IbcCommonContext,
+{
+    let conn_id = channel_end.connection_hops().first().ok_or_else(|| {
+        Error::ChannelUpgrade("No connection hop".to_string())
+    })?;
+    let conn = ctx.connection_end(conn_id).map_err(context_error)?;
+    ctx.client_state(conn.client_id()).map_err(context_error)
+}
+
+fn proof_context<C>(
+    ctx: &C,
+    channel_end: &ChannelEnd,
+    proof: &CounterpartyUpgradeProof,
+) -> Result<ProofContext, Error>
+where
+    C: IbcCommonContext,
+{
+    let conn_id = channel_end.connection_hops().first().ok_or_else(|| {
+        Error::ChannelUpgrade("No connection hop".to_string())
+    })?;
+    let conn = ctx.connection_end(conn_id).map_err(context_error)?;
+    let client_id = conn.client_id();
+    let client_state = ctx.client_state(client_id).map_err(context_error)?;
+    let height = Height::new(proof.revision_number, proof.revision_height)
+        .map_err(|e| Error::ChannelUpgrade(e.to_string()))?;
+    let consensus_state = ctx
+        .consensus_state(client_id, height)
+        .map_err(context_error)?;
+    let proof_bytes = CommitmentProofBytes::try_from(proof.proof.clone())
+        .map_err(|e| Error::ChannelUpgrade(e.to_string()))?;
+    Ok((
+        client_state,
+        conn.counterparty().prefix().clone(),
+        consensus_state.root().clone(),
+        proof_bytes,
+        height,
+    ))
+}
+
+/// Verify a value which isn't an ICS-024 path in the counterparty store
+fn verify_counterparty_value<C>(
+    ctx: &C,
+    channel_end: &ChannelEnd,
+    proof: &CounterpartyUpgradeProof,
+    path: String,
+    value: Vec<u8>,
+) -> Result<(), Error>
+where
+    C: IbcCommonContext,
+{
+    let (client_state, prefix, root, proof_bytes, height) =
+        proof_context(ctx, channel_end, proof)?;
+    client_state
+        .validate_proof_height(height)
+        .map_err(|e| Error::ChannelUpgrade(e.to_string()))?;
+    let proof_specs = match &client_state {
+        AnyClientState::Tendermint(cs) => &cs.inner().proof_specs,
+        AnyClientState::Localhost(cs) => &cs.proof_specs,
+        #[cfg(feature = "testing")]
+        AnyClientState::Mock(_) => return Ok(()),
+    };
+    let merkle_path = apply_prefix(&prefix, vec![path]);
+    let merkle_proof: MerkleProof = RawMerkleProof::try_from(proof_bytes)
+        .map_err(|e| Error::ChannelUpgrade(e.to_string()))?
+        .into();
+    merkle_proof
+        .verify_membership(proof_specs, root.into(), merkle_path, value, 0)
+        .map_err(|e| Error::ChannelUpgrade(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ics004_commitments() {
+        let port_id = PortId::transfer();
+        let channel_id = ChannelId::new(0);
+        assert_eq!(
+            storage::channel_upgrade_path(&port_id, &channel_id),
+            "channelUpgrades/upgrades/ports/transfer/channels/channel-0"
+        );
+        assert_eq!(
+            storage::channel_upgrade_error_path(&port_id, &channel_id),
+            "channelUpgrades/upgradeError/ports/transfer/channels/channel-0"
+        );
+
+        let receipt = ChannelUpgradeErrorReceipt {
+            sequence: 1,
+            message: "timeout".to_string(),
+        };
+        let bytes = encode_error_receipt(&receipt);
+        assert_eq!(bytes, b"\x08\x01\x12\x07timeout");
+        assert_eq!(decode_error_receipt(&bytes).unwrap(), receipt);
+
+        let upgrade = ChannelUpgrade {
+            fields: ChannelUpgradeFields {
+                ordering: IbcChannelOrdering::Unordered,
+                connection_hops: vec!["connection-0".to_string()],
+                version: TRANSFER_VERSION.to_string(),
+            },
+            timeout_height: 100,
+            timeout_timestamp: 0,
+            sequence: 1,
+            next_sequence_send: 5,
+            state: ChannelUpgradeState::Flushing,
+        };
+        let decoded =
+            raw::Upgrade::decode(&encode_upgrade(&upgrade, 2)[..]).unwrap();
+        let fields = decoded.fields.unwrap();
+        assert_eq!(fields.ordering, 1);
+        assert_eq!(fields.connection_hops, upgrade.fields.connection_hops);
+        assert_eq!(fields.version, TRANSFER_VERSION);
+        let timeout = decoded.timeout.unwrap();
+        assert_eq!(
+            timeout.height,
+            Some(raw::Height {
+                revision_number: 2,
+                revision_height: 100
+            })
+        );
+        assert_eq!(timeout.timestamp, 0);
+        assert_eq!(decoded.next_sequence_send, 5);
+    }
+}
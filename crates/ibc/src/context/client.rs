@@ -30,6 +30,8 @@ use namada_core::ibc::core::host::ExecutionContext;
 use namada_core::ibc::primitives::proto::Any;
 use prost::Message;
 
+use super::localhost::LocalhostClientState;
+
 // TODO: #[derive(ClientState)] doesn't support contexts with contexts generic
 // for now
 /// ClientState for light clients
@@ -37,6 +39,8 @@ use prost::Message;
 pub enum AnyClientState {
     /// Tendermint client state
     Tendermint(TmClientState),
+    /// Localhost client state
+    Localhost(LocalhostClientState),
 
     #[cfg(feature = "testing")]
     /// Mock client state for testing
@@ -49,6 +53,12 @@ impl From<TmClientState> for AnyClientState {
     }
 }
 
+impl From<LocalhostClientState> for AnyClientState {
+    fn from(cs: LocalhostClientState) -> Self {
+        Self::Localhost(cs)
+    }
+}
+
 #[cfg(feature = "testing")]
 impl From<MockClientState> for AnyClientState {
     fn from(cs: MockClientState) -> Self {
@@ -56,10 +66,23 @@ impl From<MockClientState> for AnyClientState {
     }
 }
 
+impl AnyClientState {
+    /// Check if the client has been frozen by a misbehaviour
+    pub fn is_frozen(&self) -> bool {
+        match self {
+            AnyClientState::Tendermint(cs) => cs.inner().is_frozen(),
+            AnyClientState::Localhost(_) => false,
+            #[cfg(feature = "testing")]
+            AnyClientState::Mock(cs) => cs.is_frozen(),
+        }
+    }
+}
+
 impl From<AnyClientState> for Any {
     fn from(client_state: AnyClientState) -> Self {
         match client_state {
             AnyClientState::Tendermint(cs) => cs.into(),
+            AnyClientState::Localhost(cs) => cs.into(),
             #[cfg(feature = "testing")]
             AnyClientState::Mock(cs) => cs.into(),
         }
@@ -75,6 +98,10 @@ impl TryFrom<Any> for AnyClientState {
             return Ok(cs.into());
         }
 
+        if let Ok(cs) = LocalhostClientState::try_from(client_state.clone()) {
+            return Ok(cs.into());
+        }
+
         let cs = TmClientState::try_from(client_state).map_err(|_| {
             ClientError::ClientSpecific {
                 description: "Unknown client state".to_string(),
@@ -176,6 +203,9 @@ impl ClientStateCommon for AnyClientState {
             AnyClientState::Tendermint(cs) => {
                 cs.verify_consensus_state(consensus_state)
             }
+            AnyClientState::Localhost(cs) => {
+                cs.verify_consensus_state(consensus_state)
+            }
             #[cfg(feature = "testing")]
             AnyClientState::Mock(cs) => {
                 cs.verify_consensus_state(consensus_state)
@@ -186,6 +216,7 @@ impl ClientStateCommon for AnyClientState {
     fn client_type(&self) -> ClientType {
         match self {
             AnyClientState::Tendermint(cs) => cs.client_type(),
+            AnyClientState::Localhost(cs) => cs.client_type(),
             #[cfg(feature = "testing")]
             AnyClientState::Mock(cs) => cs.client_type(),
         }
@@ -194,6 +225,7 @@ impl ClientStateCommon for AnyClientState {
     fn latest_height(&self) -> Height {
         match self {
             AnyClientState::Tendermint(cs) => cs.latest_height(),
+            AnyClientState::Localhost(cs) => cs.latest_height(),
             #[cfg(feature = "testing")]
             AnyClientState::Mock(cs) => cs.latest_height(),
         }
@@ -207,6 +239,9 @@ impl ClientStateCommon for AnyClientState {
             AnyClientState::Tendermint(cs) => {
                 cs.validate_proof_height(proof_height)
             }
+            AnyClientState::Localhost(cs) => {
+                cs.validate_proof_height(proof_height)
+            }
             #[cfg(feature = "testing")]
             AnyClientState::Mock(cs) => cs.validate_proof_height(proof_height),
         }
@@ -228,6 +263,13 @@ impl ClientStateCommon for AnyClientState {
                 proof_upgrade_consensus_state,
                 root,
             ),
+            AnyClientState::Localhost(cs) => cs.verify_upgrade_client(
+                upgraded_client_state,
+                upgraded_consensus_state,
+                proof_upgrade_client,
+                proof_upgrade_consensus_state,
+                root,
+            ),
             #[cfg(feature = "testing")]
             AnyClientState::Mock(cs) => cs.verify_upgrade_client(
                 upgraded_client_state,
@@ -251,6 +293,9 @@ impl ClientStateCommon for AnyClientState {
             AnyClientState::Tendermint(cs) => {
                 cs.verify_membership(prefix, proof, root, path, value)
             }
+            AnyClientState::Localhost(cs) => {
+                cs.verify_membership(prefix, proof, root, path, value)
+            }
             #[cfg(feature = "testing")]
             AnyClientState::Mock(cs) => {
                 cs.verify_membership(prefix, proof, root, path, value)
@@ -269,6 +314,9 @@ impl ClientStateCommon for AnyClientState {
             AnyClientState::Tendermint(cs) => {
                 cs.verify_non_membership(prefix, proof, root, path)
             }
+            AnyClientState::Localhost(cs) => {
+                cs.verify_non_membership(prefix, proof, root, path)
+            }
             #[cfg(feature = "testing")]
             AnyClientState::Mock(cs) => {
                 cs.verify_non_membership(prefix, proof, root, path)
@@ -297,6 +345,12 @@ where
                 client_message,
                 update_kind,
             ),
+            AnyClientState::Localhost(cs) => cs.verify_client_message(
+                ctx,
+                client_id,
+                client_message,
+                update_kind,
+            ),
         }
     }
 
@@ -314,6 +368,12 @@ where
                 client_message,
                 update_kind,
             ),
+            AnyClientState::Localhost(cs) => cs.check_for_misbehaviour(
+                ctx,
+                client_id,
+                client_message,
+                update_kind,
+            ),
         }
     }
 
@@ -324,6 +384,7 @@ where
     ) -> Result<Status, ClientError> {
         match self {
             AnyClientState::Tendermint(cs) => cs.status(ctx, client_id),
+            AnyClientState::Localhost(cs) => cs.status(ctx, client_id),
         }
     }
 }
@@ -353,6 +414,12 @@ where
                 client_message,
                 update_kind,
             ),
+            AnyClientState::Localhost(cs) => cs.verify_client_message(
+                ctx,
+                client_id,
+                client_message,
+                update_kind,
+            ),
             AnyClientState::Mock(cs) => cs.verify_client_message(
                 ctx,
                 client_id,
@@ -376,6 +443,12 @@ where
                 client_message,
                 update_kind,
             ),
+            AnyClientState::Localhost(cs) => cs.check_for_misbehaviour(
+                ctx,
+                client_id,
+                client_message,
+                update_kind,
+            ),
             AnyClientState::Mock(cs) => cs.check_for_misbehaviour(
                 ctx,
                 client_id,
@@ -392,6 +465,7 @@ where
     ) -> Result<Status, ClientError> {
         match self {
             AnyClientState::Tendermint(cs) => cs.status(ctx, client_id),
+            AnyClientState::Localhost(cs) => cs.status(ctx, client_id),
             AnyClientState::Mock(cs) => cs.status(ctx, client_id),
         }
     }
@@ -401,7 +475,8 @@ where
 impl<E> ClientStateExecution<E> for AnyClientState
 where
     E: ExecutionContext + TmExecutionContext,
-    <E as ClientExecutionContext>::AnyClientState: From<TmClientState>,
+    <E as ClientExecutionContext>::AnyClientState:
+        From<TmClientState> + From<LocalhostClientState>,
     <E as ClientExecutionContext>::AnyConsensusState: From<TmConsensusState>,
 {
     fn initialise(
@@ -414,6 +489,9 @@ where
             AnyClientState::Tendermint(cs) => {
                 cs.initialise(ctx, client_id, consensus_state)
             }
+            AnyClientState::Localhost(cs) => {
+                cs.initialise(ctx, client_id, consensus_state)
+            }
         }
     }
 
@@ -427,6 +505,9 @@ where
             AnyClientState::Tendermint(cs) => {
                 cs.update_state(ctx, client_id, header)
             }
+            AnyClientState::Localhost(cs) => {
+                cs.update_state(ctx, client_id, header)
+            }
         }
     }
 
@@ -444,6 +525,12 @@ where
                 client_message,
                 update_kind,
             ),
+            AnyClientState::Localhost(cs) => cs.update_state_on_misbehaviour(
+                ctx,
+                client_id,
+                client_message,
+                update_kind,
+            ),
         }
     }
 
@@ -461,6 +548,12 @@ where
                 upgraded_client_state,
                 upgraded_consensus_state,
             ),
+            AnyClientState::Localhost(cs) => cs.update_state_on_upgrade(
+                ctx,
+                client_id,
+                upgraded_client_state,
+                upgraded_consensus_state,
+            ),
         }
     }
 }
@@ -469,8 +562,9 @@ where
 impl<E> ClientStateExecution<E> for AnyClientState
 where
     E: ExecutionContext + TmExecutionContext + MockClientContext,
-    <E as ClientExecutionContext>::AnyClientState:
-        From<TmClientState> + From<MockClientState>,
+    <E as ClientExecutionContext>::AnyClientState: From<TmClientState>
+        + From<LocalhostClientState>
+        + From<MockClientState>,
     <E as ClientExecutionContext>::AnyConsensusState:
         From<TmConsensusState> + From<MockConsensusState>,
 {
@@ -484,6 +578,9 @@ where
             AnyClientState::Tendermint(cs) => {
                 cs.initialise(ctx, client_id, consensus_state)
             }
+            AnyClientState::Localhost(cs) => {
+                cs.initialise(ctx, client_id, consensus_state)
+            }
             AnyClientState::Mock(cs) => {
                 cs.initialise(ctx, client_id, consensus_state)
             }
@@ -500,6 +597,9 @@ where
             AnyClientState::Tendermint(cs) => {
                 cs.update_state(ctx, client_id, header)
             }
+            AnyClientState::Localhost(cs) => {
+                cs.update_state(ctx, client_id, header)
+            }
             AnyClientState::Mock(cs) => cs.update_state(ctx, client_id, header),
         }
     }
@@ -518,6 +618,12 @@ where
                 client_message,
                 update_kind,
             ),
+            AnyClientState::Localhost(cs) => cs.update_state_on_misbehaviour(
+                ctx,
+                client_id,
+                client_message,
+                update_kind,
+            ),
             AnyClientState::Mock(cs) => cs.update_state_on_misbehaviour(
                 ctx,
                 client_id,
@@ -541,6 +647,12 @@ where
                 upgraded_client_state,
                 upgraded_consensus_state,
             ),
+            AnyClientState::Localhost(cs) => cs.update_state_on_upgrade(
+                ctx,
+                client_id,
+                upgraded_client_state,
+                upgraded_consensus_state,
+            ),
             AnyClientState::Mock(cs) => cs.update_state_on_upgrade(
                 ctx,
                 client_id,
@@ -25,14 +25,24 @@ use namada_core::ibc::core::host::types::identifiers::{
 use namada_core::ibc::primitives::proto::{Any, Protobuf};
 use namada_core::ibc::primitives::Timestamp;
 use namada_core::tendermint::Time as TmTime;
+use namada_core::types::address::Address;
+use namada_core::types::ibc::{
+    ChannelUpgrade, ChannelUpgradeErrorReceipt, IbcDenomTrace, PacketDirection,
+    PacketStage, PacketStatus, PACKET_STATUS_RETENTION_BLOCKS,
+};
 use namada_core::types::storage::{BlockHeight, Key};
 use namada_core::types::time::DurationSecs;
 use namada_parameters::storage::get_max_expected_time_per_block_key;
+use namada_trans_token::storage_key::metadata_key;
 use prost::Message;
 use sha2::Digest;
 
 use super::client::{AnyClientState, AnyConsensusState};
+use super::localhost::is_localhost_client;
 use super::storage::IbcStorageContext;
+use crate::channel_upgrade::{
+    decode_error_receipt, encode_error_receipt, encode_upgrade,
+};
 use crate::storage;
 
 /// Result of IBC common function call
@@ -43,15 +53,25 @@ pub trait IbcCommonContext: IbcStorageContext {
     /// Get the ClientState
     fn client_state(&self, client_id: &ClientId) -> Result<AnyClientState> {
         let key = storage::client_state_key(client_id);
-        match self.read_bytes(&key)? {
+        let client_state: AnyClientState = match self.read_bytes(&key)? {
             Some(value) => Any::decode(&value[..])
                 .map_err(ClientError::Decode)?
-                .try_into()
-                .map_err(ContextError::from),
-            None => Err(ClientError::ClientStateNotFound {
-                client_id: client_id.clone(),
+                .try_into()?,
+            None => {
+                return Err(ClientError::ClientStateNotFound {
+                    client_id: client_id.clone(),
+                }
+                .into());
             }
-            .into()),
+        };
+        match client_state {
+            // The localhost client always follows the host height
+            AnyClientState::Localhost(cs) => {
+                let height = self.get_block_height()?;
+                let latest_height = Height::new(0, height.0)?;
+                Ok(cs.with_latest_height(latest_height).into())
+            }
+            _ => Ok(client_state),
         }
     }
 
@@ -72,6 +92,10 @@ pub trait IbcCommonContext: IbcStorageContext {
         client_id: &ClientId,
         height: Height,
     ) -> Result<AnyConsensusState> {
+        // The localhost client uses the consensus state of this chain
+        if is_localhost_client(client_id) {
+            return self.host_consensus_state(&height);
+        }
         let key = storage::consensus_state_key(client_id, height);
         match self.read_bytes(&key)? {
             Some(value) => Any::decode(&value[..])
@@ -405,6 +429,117 @@ pub trait IbcCommonContext: IbcStorageContext {
         self.write_bytes(&key, bytes).map_err(ContextError::from)
     }
 
+    /// Get the proposed upgrade of the channel if exists
+    fn channel_upgrade(
+        &self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+    ) -> Result<Option<ChannelUpgrade>> {
+        let key = storage::channel_upgrade_state_key(port_id, channel_id);
+        self.read(&key).map_err(|_| {
+            ChannelError::Other {
+                description: format!(
+                    "Reading the channel upgrade failed: Key {key}",
+                ),
+            }
+            .into()
+        })
+    }
+
+    /// Store the proposed upgrade of the channel. The timeout height of the
+    /// upgrade is on the counterparty chain of the given revision.
+    fn store_channel_upgrade(
+        &mut self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        upgrade: &ChannelUpgrade,
+        timeout_revision_number: u64,
+    ) -> Result<()> {
+        let key = storage::channel_upgrade_state_key(port_id, channel_id);
+        self.write(&key, upgrade).map_err(ContextError::from)?;
+        let key = storage::channel_upgrade_key(port_id, channel_id);
+        let bytes = encode_upgrade(upgrade, timeout_revision_number);
+        self.write_bytes(&key, bytes).map_err(ContextError::from)
+    }
+
+    /// Delete the proposed upgrade of the channel
+    fn delete_channel_upgrade(
+        &mut self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+    ) -> Result<()> {
+        let key = storage::channel_upgrade_state_key(port_id, channel_id);
+        self.delete(&key).map_err(ContextError::from)?;
+        let key = storage::channel_upgrade_key(port_id, channel_id);
+        self.delete(&key).map_err(ContextError::from)
+    }
+
+    /// Get the last upgrade error receipt of the channel if exists
+    fn channel_upgrade_error(
+        &self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+    ) -> Result<Option<ChannelUpgradeErrorReceipt>> {
+        let key = storage::channel_upgrade_error_key(port_id, channel_id);
+        let bytes = self.read_bytes(&key).map_err(ContextError::from)?;
+        bytes
+            .map(|bytes| decode_error_receipt(&bytes))
+            .transpose()
+            .map_err(|_| {
+                ChannelError::Other {
+                    description: format!(
+                        "Decoding the channel upgrade error failed: Key {key}",
+                    ),
+                }
+                .into()
+            })
+    }
+
+    /// Store the upgrade error receipt of the channel
+    fn store_channel_upgrade_error(
+        &mut self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        receipt: &ChannelUpgradeErrorReceipt,
+    ) -> Result<()> {
+        let key = storage::channel_upgrade_error_key(port_id, channel_id);
+        let bytes = encode_error_receipt(receipt);
+        self.write_bytes(&key, bytes).map_err(ContextError::from)
+    }
+
+    /// Get the last upgrade sequence of the channel
+    fn channel_upgrade_sequence(
+        &self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+    ) -> Result<u64> {
+        let key = storage::channel_upgrade_sequence_key(port_id, channel_id);
+        Ok(self.read::<u64>(&key)?.unwrap_or_default())
+    }
+
+    /// Store the last upgrade sequence of the channel
+    fn store_channel_upgrade_sequence(
+        &mut self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        sequence: u64,
+    ) -> Result<()> {
+        let key = storage::channel_upgrade_sequence_key(port_id, channel_id);
+        self.write(&key, sequence).map_err(ContextError::from)
+    }
+
+    /// Check if the channel has packets which haven't been acknowledged or
+    /// timed out yet
+    fn has_in_flight_packets(
+        &self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+    ) -> Result<bool> {
+        let prefix = storage::commitment_prefix(port_id, channel_id);
+        let mut iter = self.iter_prefix(&prefix)?;
+        Ok(self.iter_next(&mut iter)?.is_some())
+    }
+
     /// Get the NextSequenceSend
     fn get_next_sequence_send(
         &self,
@@ -628,6 +763,35 @@ pub trait IbcCommonContext: IbcStorageContext {
         self.delete(&key).map_err(ContextError::from)
     }
 
+    /// Store the lifecycle stage of the packet with the current block height.
+    /// A terminal stage is indexed to be pruned after the retention window.
+    fn store_packet_status(
+        &mut self,
+        direction: PacketDirection,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        sequence: Sequence,
+        stage: PacketStage,
+    ) -> Result<()> {
+        let key = storage::packet_status_key(
+            direction, port_id, channel_id, sequence,
+        );
+        let height = self.get_block_height()?;
+        if stage.is_terminal() {
+            let pruning_key = storage::packet_status_pruning_key(
+                height + PACKET_STATUS_RETENTION_BLOCKS,
+                direction,
+                port_id,
+                channel_id,
+                sequence,
+            );
+            self.write(&pruning_key, key.clone())
+                .map_err(ContextError::from)?;
+        }
+        self.write(&key, PacketStatus { stage, height })
+            .map_err(ContextError::from)
+    }
+
     /// Read a counter
     fn read_counter(&self, key: &Key) -> Result<u64> {
         match self.read::<u64>(key)? {
@@ -668,4 +832,27 @@ pub trait IbcCommonContext: IbcStorageContext {
         }
         Ok(())
     }
+
+    /// Write the display metadata of the IBC token derived from the denom
+    /// trace to the token metadata if it hasn't been stored
+    fn store_token_metadata(
+        &mut self,
+        token: &Address,
+        trace: &IbcDenomTrace,
+    ) -> Result<()> {
+        let key = metadata_key(token);
+        let has_key = self.has_key(&key).map_err(|_| ChannelError::Other {
+            description: format!(
+                "Reading the token metadata failed: Key {key}"
+            ),
+        })?;
+        if !has_key {
+            self.write(&key, trace).map_err(|_| ChannelError::Other {
+                description: format!(
+                    "Writing the token metadata failed: Key {key}",
+                ),
+            })?;
+        }
+        Ok(())
+    }
 }
@@ -0,0 +1,521 @@
+//! IBC module for the interchain query (ICQ) host
+//!
+//! Counterparty chains send a packet with a batch of storage queries on an
+//! `icqhost` channel and the results are returned in the acknowledgement. As
+//! the acknowledgement is committed to the IBC state of this chain, the
+//! counterparty can verify the results with its light client of this chain.
+
+use std::cell::RefCell;
+use std::fmt::Debug;
+use std::rc::Rc;
+
+use namada_core::ibc::core::channel::types::acknowledgement::{
+    Acknowledgement, AcknowledgementStatus, StatusValue,
+};
+use namada_core::ibc::core::channel::types::channel::{Counterparty, Order};
+use namada_core::ibc::core::channel::types::error::{
+    ChannelError, PacketError,
+};
+use namada_core::ibc::core::channel::types::packet::Packet;
+use namada_core::ibc::core::channel::types::Version;
+use namada_core::ibc::core::host::types::identifiers::{
+    ChannelId, ConnectionId, PortId,
+};
+use namada_core::ibc::core::router::module::Module;
+use namada_core::ibc::core::router::types::module::{ModuleExtras, ModuleId};
+use namada_core::ibc::primitives::Signer;
+use namada_core::types::storage::Key;
+use namada_storage::{iter_prefix_bytes, ResultExt, StorageRead};
+use serde::{Deserialize, Serialize};
+
+use super::common::IbcCommonContext;
+use super::transfer_mod::ModuleWrapper;
+use crate::storage;
+
+/// The port ID of the ICQ host
+pub const ICQ_PORT_ID_STR: &str = "icqhost";
+/// The module ID of the ICQ host
+pub const ICQ_MODULE_ID_STR: &str = "icqhost";
+/// The channel version of the ICQ host
+pub const ICQ_VERSION: &str = "icq-1";
+/// The max number of key-value pairs returned for a prefix query
+pub const MAX_ICQ_PREFIX_RESULTS: usize = 100;
+
+/// A storage query requested by a counterparty chain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IcqRequest {
+    /// The storage key or the key prefix to be queried
+    pub key: String,
+    /// Query all the key-value pairs under the key as a prefix
+    #[serde(default)]
+    pub prefix: bool,
+}
+
+/// The packet data of an ICQ packet
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IcqPacketData {
+    /// The batch of queries
+    pub requests: Vec<IcqRequest>,
+}
+
+/// A storage key and the raw value
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IcqKeyValue {
+    /// The storage key
+    pub key: String,
+    /// The raw value
+    pub value: Vec<u8>,
+}
+
+/// The result of a storage query
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IcqResponse {
+    /// The queried key or prefix
+    pub key: String,
+    /// The key-value pairs found. It's empty when the key doesn't exist.
+    pub values: Vec<IcqKeyValue>,
+}
+
+/// The acknowledgement result of an ICQ packet
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IcqAcknowledgement {
+    /// The block height at which the queries were answered
+    pub height: u64,
+    /// The results in the same order as the requests
+    pub responses: Vec<IcqResponse>,
+}
+
+/// Read the storage key prefixes allowed to be queried by the ICQ host
+pub fn read_icq_allowlist<S>(storage: &S) -> namada_storage::Result<Vec<Key>>
+where
+    S: StorageRead,
+{
+    let allowlist = storage
+        .read::<Vec<String>>(&storage::icq_allowlist_key())?
+        .unwrap_or_default();
+    allowlist
+        .iter()
+        .map(|prefix| Key::parse(prefix).into_storage_result())
+        .collect()
+}
+
+/// Check if the given key is covered by the allowlist
+pub fn is_icq_allowed(allowlist: &[Key], key: &Key) -> bool {
+    allowlist
+        .iter()
+        .any(|prefix| key.split_prefix(prefix).is_some())
+}
+
+/// Answer the given queries with the current storage
+pub fn answer_icq_requests<S>(
+    storage: &S,
+    data: &IcqPacketData,
+) -> Result<IcqAcknowledgement, String>
+where
+    S: StorageRead,
+{
+    let allowlist = read_icq_allowlist(storage).map_err(|e| e.to_string())?;
+    let mut responses = Vec::with_capacity(data.requests.len());
+    for request in &data.requests {
+        let key = Key::parse(&request.key).map_err(|e| {
+            format!("Invalid storage key {}: {}", request.key, e)
+        })?;
+        if !is_icq_allowed(&allowlist, &key) {
+            return Err(format!(
+                "The storage key isn't allowed to be queried: {}",
+                request.key
+            ));
+        }
+        let values = if request.prefix {
+            iter_prefix_bytes(storage, &key)
+                .map_err(|e| e.to_string())?
+                .take(MAX_ICQ_PREFIX_RESULTS)
+                .map(|res| {
+                    res.map(|(key, value)| IcqKeyValue {
+                        key: key.to_string(),
+                        value,
+                    })
+                    .map_err(|e| e.to_string())
+                })
+                .collect::<Result<Vec<_>, _>>()?
+        } else {
+            storage
+                .read_bytes(&key)
+                .map_err(|e| e.to_string())?
+                .map(|value| IcqKeyValue {
+                    key: key.to_string(),
+                    value,
+                })
+                .into_iter()
+                .collect()
+        };
+        responses.push(IcqResponse {
+            key: request.key.clone(),
+            values,
+        });
+    }
+    let height = storage.get_block_height().map_err(|e| e.to_string())?;
+    Ok(IcqAcknowledgement {
+        height: height.0,
+        responses,
+    })
+}
+
+/// IBC module for the ICQ host
+#[derive(Debug)]
+pub struct IcqModule<C>
+where
+    C: IbcCommonContext,
+{
+    /// IBC context
+    pub ctx: Rc<RefCell<C>>,
+}
+
+impl<C> IcqModule<C>
+where
+    C: IbcCommonContext,
+{
+    /// Make a new module
+    pub fn new(ctx: Rc<RefCell<C>>) -> Self {
+        Self { ctx }
+    }
+
+    /// Get the module ID
+    pub fn module_id(&self) -> ModuleId {
+        ModuleId::new(ICQ_MODULE_ID_STR.to_string())
+    }
+
+    /// Get the port ID
+    pub fn port_id(&self) -> PortId {
+        PortId::new(ICQ_PORT_ID_STR.to_string())
+            .expect("The ICQ port ID should be valid")
+    }
+}
+
+impl<C> ModuleWrapper for IcqModule<C>
+where
+    C: IbcCommonContext + Debug,
+{
+    fn as_module(&self) -> &dyn Module {
+        self
+    }
+
+    fn as_module_mut(&mut self) -> &mut dyn Module {
+        self
+    }
+}
+
+fn validate_channel(
+    order: Order,
+    version: &Version,
+) -> Result<(), ChannelError> {
+    if order != Order::Unordered {
+        return Err(ChannelError::AppModule {
+            description: format!(
+                "The ICQ channel should be unordered: Order {order:?}"
+            ),
+        });
+    }
+    if version.as_str() != ICQ_VERSION {
+        return Err(ChannelError::AppModule {
+            description: format!(
+                "The ICQ channel version is invalid: Version {version}"
+            ),
+        });
+    }
+    Ok(())
+}
+
+fn no_packet_sent() -> PacketError {
+    PacketError::AppModule {
+        description: "The ICQ host doesn't send any packet".to_string(),
+    }
+}
+
+impl<C> Module for IcqModule<C>
+where
+    C: IbcCommonContext + Debug,
+{
+    #[allow(clippy::too_many_arguments)]
+    fn on_chan_open_init_validate(
+        &self,
+        order: Order,
+        _connection_hops: &[ConnectionId],
+        _port_id: &PortId,
+        _channel_id: &ChannelId,
+        _counterparty: &Counterparty,
+        version: &Version,
+    ) -> Result<Version, ChannelError> {
+        validate_channel(order, version)?;
+        Ok(version.clone())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn on_chan_open_init_execute(
+        &mut self,
+        _order: Order,
+        _connection_hops: &[ConnectionId],
+        _port_id: &PortId,
+        _channel_id: &ChannelId,
+        _counterparty: &Counterparty,
+        version: &Version,
+    ) -> Result<(ModuleExtras, Version), ChannelError> {
+        Ok((ModuleExtras::empty(), version.clone()))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn on_chan_open_try_validate(
+        &self,
+        order: Order,
+        _connection_hops: &[ConnectionId],
+        _port_id: &PortId,
+        _channel_id: &ChannelId,
+        _counterparty: &Counterparty,
+        counterparty_version: &Version,
+    ) -> Result<Version, ChannelError> {
+        validate_channel(order, counterparty_version)?;
+        Ok(counterparty_version.clone())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn on_chan_open_try_execute(
+        &mut self,
+        _order: Order,
+        _connection_hops: &[ConnectionId],
+        _port_id: &PortId,
+        _channel_id: &ChannelId,
+        _counterparty: &Counterparty,
+        counterparty_version: &Version,
+    ) -> Result<(ModuleExtras, Version), ChannelError> {
+        Ok((ModuleExtras::empty(), counterparty_version.clone()))
+    }
+
+    fn on_chan_open_ack_validate(
+        &self,
+        _port_id: &PortId,
+        _channel_id: &ChannelId,
+        counterparty_version: &Version,
+    ) -> Result<(), ChannelError> {
+        validate_channel(Order::Unordered, counterparty_version)
+    }
+
+    fn on_chan_open_ack_execute(
+        &mut self,
+        _port_id: &PortId,
+        _channel_id: &ChannelId,
+        _counterparty_version: &Version,
+    ) -> Result<ModuleExtras, ChannelError> {
+        Ok(ModuleExtras::empty())
+    }
+
+    fn on_chan_open_confirm_validate(
+        &self,
+        _port_id: &PortId,
+        _channel_id: &ChannelId,
+    ) -> Result<(), ChannelError> {
+        Ok(())
+    }
+
+    fn on_chan_open_confirm_execute(
+        &mut self,
+        _port_id: &PortId,
+        _channel_id: &ChannelId,
+    ) -> Result<ModuleExtras, ChannelError> {
+        Ok(ModuleExtras::empty())
+    }
+
+    fn on_chan_close_init_validate(
+        &self,
+        _port_id: &PortId,
+        _channel_id: &ChannelId,
+    ) -> Result<(), ChannelError> {
+        Err(ChannelError::AppModule {
+            description: "The ICQ channel can't be closed by the host"
+                .to_string(),
+        })
+    }
+
+    fn on_chan_close_init_execute(
+        &mut self,
+        _port_id: &PortId,
+        _channel_id: &ChannelId,
+    ) -> Result<ModuleExtras, ChannelError> {
+        Ok(ModuleExtras::empty())
+    }
+
+    fn on_chan_close_confirm_validate(
+        &self,
+        _port_id: &PortId,
+        _channel_id: &ChannelId,
+    ) -> Result<(), ChannelError> {
+        Ok(())
+    }
+
+    fn on_chan_close_confirm_execute(
+        &mut self,
+        _port_id: &PortId,
+        _channel_id: &ChannelId,
+    ) -> Result<ModuleExtras, ChannelError> {
+        Ok(ModuleExtras::empty())
+    }
+
+    fn on_recv_packet_execute(
+        &mut self,
+        packet: &Packet,
+        _relayer: &Signer,
+    ) -> (ModuleExtras, Acknowledgement) {
+        let result = serde_json::from_slice::<IcqPacketData>(&packet.data)
+            .map_err(|e| format!("Decoding the ICQ packet data failed: {e}"))
+            .and_then(|data| answer_icq_requests(&*self.ctx.borrow(), &data))
+            .and_then(|ack| {
+                serde_json::to_string(&ack).map_err(|e| {
+                    format!("Encoding the ICQ acknowledgement failed: {e}")
+                })
+            });
+        let ack = match result {
+            Ok(ack) => AcknowledgementStatus::success(
+                StatusValue::new(ack)
+                    .expect("The ICQ acknowledgement shouldn't be empty"),
+            ),
+            Err(e) => AcknowledgementStatus::error(
+                StatusValue::new(e).expect("The error shouldn't be empty"),
+            ),
+        };
+        (ModuleExtras::empty(), ack.into())
+    }
+
+    fn on_acknowledgement_packet_validate(
+        &self,
+        _packet: &Packet,
+        _acknowledgement: &Acknowledgement,
+        _relayer: &Signer,
+    ) -> Result<(), PacketError> {
+        Err(no_packet_sent())
+    }
+
+    fn on_acknowledgement_packet_execute(
+        &mut self,
+        _packet: &Packet,
+        _acknowledgement: &Acknowledgement,
+        _relayer: &Signer,
+    ) -> (ModuleExtras, Result<(), PacketError>) {
+        (ModuleExtras::empty(), Err(no_packet_sent()))
+    }
+
+    fn on_timeout_packet_validate(
+        &self,
+        _packet: &Packet,
+        _relayer: &Signer,
+    ) -> Result<(), PacketError> {
+        Err(no_packet_sent())
+    }
+
+    fn on_timeout_packet_execute(
+        &mut self,
+        _packet: &Packet,
+        _relayer: &Signer,
+    ) -> (ModuleExtras, Result<(), PacketError>) {
+        (ModuleExtras::empty(), Err(no_packet_sent()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use namada_storage::testing::TestStorage;
+    use namada_storage::StorageWrite;
+
+    use super::*;
+
+    /// Init a storage allowing the queries of the keys under `allowed`
+    fn init_storage() -> TestStorage {
+        let mut s = TestStorage::default();
+        s.write(&storage::icq_allowlist_key(), vec!["allowed".to_string()])
+            .unwrap();
+        for key in ["allowed/a", "denied/a"] {
+            s.write_bytes(&Key::parse(key).unwrap(), key.as_bytes())
+                .unwrap();
+        }
+        s
+    }
+
+    fn request(key: &str, prefix: bool) -> IcqPacketData {
+        IcqPacketData {
+            requests: vec![IcqRequest {
+                key: key.to_string(),
+                prefix,
+            }],
+        }
+    }
+
+    #[test]
+    fn test_answer_icq_requests() {
+        let s = init_storage();
+
+        let ack = answer_icq_requests(&s, &request("allowed/a", false))
+            .expect("Answering the query shouldn't fail");
+        assert_eq!(ack.height, 1);
+        assert_eq!(
+            ack.responses,
+            vec![IcqResponse {
+                key: "allowed/a".to_string(),
+                values: vec![IcqKeyValue {
+                    key: "allowed/a".to_string(),
+                    value: b"allowed/a".to_vec(),
+                }],
+            }]
+        );
+
+        // A missing key is answered with no value
+        let ack = answer_icq_requests(&s, &request("allowed/b", false))
+            .expect("Answering the query shouldn't fail");
+        assert!(ack.responses[0].values.is_empty());
+    }
+
+    #[test]
+    fn test_answer_icq_requests_not_allowed() {
+        let mut s = init_storage();
+
+        // A single key out of the allowlist fails the whole batch
+        let mut data = request("allowed/a", false);
+        data.requests.push(IcqRequest {
+            key: "denied/a".to_string(),
+            prefix: false,
+        });
+        assert!(answer_icq_requests(&s, &data).is_err());
+        assert!(answer_icq_requests(&s, &request("denied", true)).is_err());
+
+        // Nothing can be queried without an allowlist
+        s.delete(&storage::icq_allowlist_key()).unwrap();
+        assert!(answer_icq_requests(&s, &request("allowed/a", false)).is_err());
+    }
+
+    #[test]
+    fn test_answer_icq_prefix_requests() {
+        let mut s = init_storage();
+        for i in 0..MAX_ICQ_PREFIX_RESULTS + 1 {
+            let key = Key::parse(format!("allowed/many/{i:03}")).unwrap();
+            s.write_bytes(&key, [1]).unwrap();
+        }
+
+        let ack = answer_icq_requests(&s, &request("allowed", true))
+            .expect("Answering the query shouldn't fail");
+        let values = &ack.responses[0].values;
+        assert_eq!(values.len(), MAX_ICQ_PREFIX_RESULTS);
+        assert_eq!(values[0].key, "allowed/a");
+        assert!(values.iter().all(|kv| kv.key.starts_with("allowed/")));
+
+        let ack = answer_icq_requests(&s, &request("allowed/none", true))
+            .expect("Answering the query shouldn't fail");
+        assert!(ack.responses[0].values.is_empty());
+    }
+
+    #[test]
+    fn test_validate_channel() {
+        let version = Version::new(ICQ_VERSION.to_string());
+        assert!(validate_channel(Order::Unordered, &version).is_ok());
+        assert!(validate_channel(Order::Ordered, &version).is_err());
+        assert!(validate_channel(Order::None, &version).is_err());
+        let ics20 = Version::new("ics20-1".to_string());
+        assert!(validate_channel(Order::Unordered, &ics20).is_err());
+    }
+}
@@ -0,0 +1,346 @@
+//! Localhost (09-localhost) light client
+//!
+//! The localhost client is a loopback client which tracks this chain itself.
+//! It enables modules on this chain to talk to other modules on the same
+//! chain through the IBC interface, e.g. for testing ICS-20 flows or
+//! developing middleware with the same-chain packet semantics.
+//!
+//! The client has no stored consensus state. Its latest height always follows
+//! the host height and the consensus state at a height is the host consensus
+//! state. A proof for the client is the Merkle proof of this chain's own
+//! storage, verified with the proof specs of this chain.
+
+use namada_core::ibc::clients::tendermint::context::CommonContext as TmCommonContext;
+use namada_core::ibc::core::client::context::client_state::{
+    ClientStateCommon, ClientStateExecution, ClientStateValidation,
+};
+use namada_core::ibc::core::client::context::{
+    ClientExecutionContext, ClientValidationContext,
+};
+use namada_core::ibc::core::client::types::error::ClientError;
+use namada_core::ibc::core::client::types::{Height, Status, UpdateKind};
+use namada_core::ibc::core::commitment_types::commitment::{
+    CommitmentPrefix, CommitmentProofBytes, CommitmentRoot,
+};
+use namada_core::ibc::core::commitment_types::merkle::{
+    apply_prefix, MerkleProof,
+};
+use namada_core::ibc::core::commitment_types::proto::v1::MerkleProof as RawMerkleProof;
+use namada_core::ibc::core::commitment_types::specs::ProofSpecs;
+use namada_core::ibc::core::handler::types::error::ContextError;
+use namada_core::ibc::core::host::types::identifiers::{ClientId, ClientType};
+use namada_core::ibc::core::host::types::path::{ClientStatePath, Path};
+use namada_core::ibc::primitives::proto::Any;
+use prost::Message;
+
+/// Client type of the localhost client
+pub const LOCALHOST_CLIENT_TYPE: &str = "09-localhost";
+/// Type URL of the localhost client state
+pub const LOCALHOST_CLIENT_STATE_TYPE_URL: &str =
+    "/ibc.lightclients.localhost.v2.ClientState";
+
+/// Raw localhost client state
+#[derive(Clone, PartialEq, Message)]
+struct RawLocalhostClientState {
+    #[prost(uint64, tag = "1")]
+    revision_number: u64,
+    #[prost(uint64, tag = "2")]
+    revision_height: u64,
+    #[prost(message, repeated, tag = "3")]
+    proof_specs: Vec<ics23::ProofSpec>,
+}
+
+/// Localhost client state
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocalhostClientState {
+    /// The latest height of this chain
+    pub latest_height: Height,
+    /// The proof specs of this chain
+    pub proof_specs: ProofSpecs,
+}
+
+impl LocalhostClientState {
+    /// Make a new localhost client state
+    pub fn new(latest_height: Height, proof_specs: ProofSpecs) -> Self {
+        Self {
+            latest_height,
+            proof_specs,
+        }
+    }
+
+    /// Update the latest height with the host height
+    pub fn with_latest_height(self, latest_height: Height) -> Self {
+        Self {
+            latest_height,
+            ..self
+        }
+    }
+
+    /// Check that the client verifies proofs with the proof specs of this
+    /// chain
+    pub fn validate_proof_specs(
+        &self,
+        proof_specs: &ProofSpecs,
+    ) -> Result<(), ClientError> {
+        if self.proof_specs != *proof_specs {
+            return Err(ClientError::ClientSpecific {
+                description: "The proof specs of the localhost client should \
+                              be the same as this chain's ones"
+                    .to_string(),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Check if the client ID is for a localhost client
+pub fn is_localhost_client(client_id: &ClientId) -> bool {
+    client_id
+        .as_str()
+        .strip_prefix(LOCALHOST_CLIENT_TYPE)
+        .map_or(false, |suffix| suffix.starts_with('-'))
+}
+
+impl From<LocalhostClientState> for Any {
+    fn from(client_state: LocalhostClientState) -> Self {
+        let raw = RawLocalhostClientState {
+            revision_number: client_state.latest_height.revision_number(),
+            revision_height: client_state.latest_height.revision_height(),
+            proof_specs: client_state.proof_specs.into(),
+        };
+        Any {
+            type_url: LOCALHOST_CLIENT_STATE_TYPE_URL.to_string(),
+            value: raw.encode_to_vec(),
+        }
+    }
+}
+
+impl TryFrom<Any> for LocalhostClientState {
+    type Error = ClientError;
+
+    fn try_from(any: Any) -> Result<Self, Self::Error> {
+        if any.type_url != LOCALHOST_CLIENT_STATE_TYPE_URL {
+            return Err(ClientError::UnknownClientStateType {
+                client_state_type: any.type_url,
+            });
+        }
+        let raw = RawLocalhostClientState::decode(&any.value[..])
+            .map_err(ClientError::Decode)?;
+        let latest_height =
+            Height::new(raw.revision_number, raw.revision_height)?;
+        Ok(Self::new(latest_height, raw.proof_specs.into()))
+    }
+}
+
+fn context_error(e: ContextError) -> ClientError {
+    match e {
+        ContextError::ClientError(e) => e,
+        _ => ClientError::Other {
+            description: e.to_string(),
+        },
+    }
+}
+
+fn unsupported(operation: &str) -> ClientError {
+    ClientError::ClientSpecific {
+        description: format!(
+            "The localhost client doesn't support {operation}"
+        ),
+    }
+}
+
+impl ClientStateCommon for LocalhostClientState {
+    fn verify_consensus_state(
+        &self,
+        _consensus_state: Any,
+    ) -> Result<(), ClientError> {
+        // The localhost client doesn't store any consensus state. The given
+        // one is ignored.
+        Ok(())
+    }
+
+    fn client_type(&self) -> ClientType {
+        ClientType::new(LOCALHOST_CLIENT_TYPE)
+            .expect("The localhost client type should be valid")
+    }
+
+    fn latest_height(&self) -> Height {
+        self.latest_height
+    }
+
+    fn validate_proof_height(
+        &self,
+        proof_height: Height,
+    ) -> Result<(), ClientError> {
+        if self.latest_height < proof_height {
+            return Err(ClientError::InvalidProofHeight {
+                latest_height: self.latest_height,
+                proof_height,
+            });
+        }
+        Ok(())
+    }
+
+    fn verify_upgrade_client(
+        &self,
+        _upgraded_client_state: Any,
+        _upgraded_consensus_state: Any,
+        _proof_upgrade_client: CommitmentProofBytes,
+        _proof_upgrade_consensus_state: CommitmentProofBytes,
+        _root: &CommitmentRoot,
+    ) -> Result<(), ClientError> {
+        Err(unsupported("upgrading"))
+    }
+
+    fn verify_membership(
+        &self,
+        prefix: &CommitmentPrefix,
+        proof: &CommitmentProofBytes,
+        root: &CommitmentRoot,
+        path: Path,
+        value: Vec<u8>,
+    ) -> Result<(), ClientError> {
+        let merkle_path = apply_prefix(prefix, vec![path.to_string()]);
+        let merkle_proof = decode_merkle_proof(proof)?;
+        merkle_proof
+            .verify_membership(
+                &self.proof_specs,
+                root.clone().into(),
+                merkle_path,
+                value,
+                0,
+            )
+            .map_err(|e| ClientError::Other {
+                description: e.to_string(),
+            })
+    }
+
+    fn verify_non_membership(
+        &self,
+        prefix: &CommitmentPrefix,
+        proof: &CommitmentProofBytes,
+        root: &CommitmentRoot,
+        path: Path,
+    ) -> Result<(), ClientError> {
+        let merkle_path = apply_prefix(prefix, vec![path.to_string()]);
+        let merkle_proof = decode_merkle_proof(proof)?;
+        merkle_proof
+            .verify_non_membership(
+                &self.proof_specs,
+                root.clone().into(),
+                merkle_path,
+            )
+            .map_err(|e| ClientError::Other {
+                description: e.to_string(),
+            })
+    }
+}
+
+fn decode_merkle_proof(
+    proof: &CommitmentProofBytes,
+) -> Result<MerkleProof, ClientError> {
+    let raw = RawMerkleProof::try_from(proof.clone()).map_err(|e| {
+        ClientError::Other {
+            description: e.to_string(),
+        }
+    })?;
+    Ok(raw.into())
+}
+
+impl<V> ClientStateValidation<V> for LocalhostClientState
+where
+    V: ClientValidationContext,
+{
+    fn verify_client_message(
+        &self,
+        _ctx: &V,
+        _client_id: &ClientId,
+        _client_message: Any,
+        _update_kind: &UpdateKind,
+    ) -> Result<(), ClientError> {
+        Err(unsupported("any client message"))
+    }
+
+    fn check_for_misbehaviour(
+        &self,
+        _ctx: &V,
+        _client_id: &ClientId,
+        _client_message: Any,
+        _update_kind: &UpdateKind,
+    ) -> Result<bool, ClientError> {
+        // This chain can't misbehave against itself
+        Ok(false)
+    }
+
+    fn status(
+        &self,
+        _ctx: &V,
+        _client_id: &ClientId,
+    ) -> Result<Status, ClientError> {
+        Ok(Status::Active)
+    }
+}
+
+impl<E> ClientStateExecution<E> for LocalhostClientState
+where
+    E: ClientExecutionContext + TmCommonContext,
+    <E as ClientExecutionContext>::AnyClientState: From<LocalhostClientState>,
+{
+    fn initialise(
+        &self,
+        ctx: &mut E,
+        client_id: &ClientId,
+        _consensus_state: Any,
+    ) -> Result<(), ClientError> {
+        let host_timestamp =
+            TmCommonContext::host_timestamp(ctx).map_err(context_error)?;
+        let host_height =
+            TmCommonContext::host_height(ctx).map_err(context_error)?;
+        ctx.store_client_state(
+            ClientStatePath(client_id.clone()),
+            self.clone().into(),
+        )
+        .map_err(context_error)?;
+        ctx.store_update_time(
+            client_id.clone(),
+            self.latest_height,
+            host_timestamp,
+        )
+        .map_err(context_error)?;
+        ctx.store_update_height(
+            client_id.clone(),
+            self.latest_height,
+            host_height,
+        )
+        .map_err(context_error)
+    }
+
+    fn update_state(
+        &self,
+        _ctx: &mut E,
+        _client_id: &ClientId,
+        _header: Any,
+    ) -> Result<Vec<Height>, ClientError> {
+        Err(unsupported("updating"))
+    }
+
+    fn update_state_on_misbehaviour(
+        &self,
+        _ctx: &mut E,
+        _client_id: &ClientId,
+        _client_message: Any,
+        _update_kind: &UpdateKind,
+    ) -> Result<(), ClientError> {
+        Err(unsupported("misbehaviour"))
+    }
+
+    fn update_state_on_upgrade(
+        &self,
+        _ctx: &mut E,
+        _client_id: &ClientId,
+        _upgraded_client_state: Any,
+        _upgraded_consensus_state: Any,
+    ) -> Result<Height, ClientError> {
+        Err(unsupported("upgrading"))
+    }
+}
@@ -3,6 +3,8 @@
 pub mod client;
 pub mod common;
 pub mod execution;
+pub mod icq_mod;
+pub mod localhost;
 pub mod router;
 pub mod storage;
 pub mod token_transfer;
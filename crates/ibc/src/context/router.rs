@@ -31,9 +31,19 @@ impl<'a> IbcRouter<'a> {
         &mut self,
         module_id: ModuleId,
         module: impl ModuleWrapper + 'a,
+    ) {
+        self.add_module(module_id, PortId::transfer(), module)
+    }
+
+    /// Add a route for the module bound to the given port
+    pub fn add_module(
+        &mut self,
+        module_id: ModuleId,
+        port_id: PortId,
+        module: impl ModuleWrapper + 'a,
     ) {
         self.modules.insert(module_id.clone(), Rc::new(module));
-        self.ports.insert(PortId::transfer(), module_id);
+        self.ports.insert(port_id, module_id);
     }
 }
 
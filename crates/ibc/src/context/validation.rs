@@ -31,6 +31,7 @@ use namada_core::ibc::primitives::{Signer, Timestamp};
 
 use super::client::{AnyClientState, AnyConsensusState};
 use super::common::IbcCommonContext;
+use super::localhost::LocalhostClientState;
 use super::IbcContext;
 use crate::storage;
 
@@ -216,6 +217,15 @@ where
             }
         }
 
+        // The counterparty of a localhost client is this chain itself
+        if let Ok(cs) =
+            LocalhostClientState::try_from(counterparty_client_state.clone())
+        {
+            return cs
+                .validate_proof_specs(&self.validation_params.proof_specs)
+                .map_err(ContextError::from);
+        }
+
         ValidateSelfClientContext::validate_self_tendermint_client(
             self,
             counterparty_client_state,
@@ -1,6 +1,7 @@
 //! IBC library code
 
 mod actions;
+pub mod channel_upgrade;
 pub mod context;
 pub mod storage;
 
@@ -9,9 +10,13 @@ use std::fmt::Debug;
 use std::rc::Rc;
 use std::str::FromStr;
 
-pub use actions::transfer_over_ibc;
+pub use actions::{
+    oldest_client_update_height, prune_packet_statuses, transfer_over_ibc,
+};
 use borsh::BorshDeserialize;
 pub use context::common::IbcCommonContext;
+pub use context::icq_mod::IcqModule;
+use context::localhost::LocalhostClientState;
 use context::router::IbcRouter;
 pub use context::storage::{IbcStorageContext, ProofSpec};
 pub use context::token_transfer::TokenTransferContext;
@@ -27,19 +32,24 @@ use namada_core::ibc::apps::transfer::types::{
     is_receiver_chain_source, PrefixedDenom, TracePrefix,
 };
 use namada_core::ibc::core::channel::types::msgs::PacketMsg;
+use namada_core::ibc::core::client::types::msgs::ClientMsg;
 use namada_core::ibc::core::entrypoint::{execute, validate};
 use namada_core::ibc::core::handler::types::error::ContextError;
 use namada_core::ibc::core::handler::types::msgs::MsgEnvelope;
 use namada_core::ibc::core::host::types::error::IdentifierError;
-use namada_core::ibc::core::host::types::identifiers::{ChannelId, PortId};
+use namada_core::ibc::core::host::types::identifiers::{
+    ChannelId, PortId, Sequence,
+};
 use namada_core::ibc::core::router::types::error::RouterError;
 use namada_core::ibc::core::router::types::module::ModuleId;
 use namada_core::ibc::primitives::proto::Any;
 pub use namada_core::ibc::*;
 use namada_core::types::address::{Address, MASP};
 use namada_core::types::ibc::{
-    get_shielded_transfer, is_ibc_denom, MsgShieldedTransfer,
-    EVENT_TYPE_DENOM_TRACE, EVENT_TYPE_PACKET,
+    get_shielded_transfer, is_ibc_denom, IbcDenomTrace, IbcEvent,
+    MsgChannelUpgrade, MsgShieldedTransfer, PacketDirection, PacketStage,
+    EVENT_TYPE_ACK_PACKET, EVENT_TYPE_DENOM_TRACE, EVENT_TYPE_PACKET,
+    EVENT_TYPE_RECV_PACKET, EVENT_TYPE_SEND_PACKET, EVENT_TYPE_TIMEOUT_PACKET,
 };
 use namada_core::types::masp::PaymentAddress;
 use prost::Message;
@@ -62,6 +72,10 @@ pub enum Error {
     ChainId(IdentifierError),
     #[error("Handling MASP transaction error: {0}")]
     MaspTx(String),
+    #[error("Channel upgrade error: {0}")]
+    ChannelUpgrade(String),
+    #[error("Packet status error: {0}")]
+    PacketStatus(String),
 }
 
 /// IBC actions to handle IBC operations
@@ -95,6 +109,16 @@ where
         self.router.add_transfer_module(module_id, module)
     }
 
+    /// Add the interchain query (ICQ) host route
+    pub fn add_icq_module(
+        &mut self,
+        module_id: ModuleId,
+        port_id: PortId,
+        module: impl ModuleWrapper + 'a,
+    ) {
+        self.router.add_module(module_id, port_id, module)
+    }
+
     /// Set the validation parameters
     pub fn set_validation_params(&mut self, params: ValidationParams) {
         self.ctx.validation_params = params;
@@ -105,6 +129,11 @@ where
         let message = decode_message(tx_data)?;
         match &message {
             IbcMessage::Transfer(msg) => {
+                channel_upgrade::check_send_allowed(
+                    &*self.ctx.inner.borrow(),
+                    &msg.port_id_on_a,
+                    &msg.chan_id_on_a,
+                )?;
                 let mut token_transfer_ctx =
                     TokenTransferContext::new(self.ctx.inner.clone());
                 send_transfer_execute(
@@ -112,9 +141,15 @@ where
                     &mut token_transfer_ctx,
                     msg.clone(),
                 )
-                .map_err(Error::TokenTransfer)
+                .map_err(Error::TokenTransfer)?;
+                self.store_packet_status()
             }
             IbcMessage::ShieldedTransfer(msg) => {
+                channel_upgrade::check_send_allowed(
+                    &*self.ctx.inner.borrow(),
+                    &msg.message.port_id_on_a,
+                    &msg.message.chan_id_on_a,
+                )?;
                 let mut token_transfer_ctx =
                     TokenTransferContext::new(self.ctx.inner.clone());
                 send_transfer_execute(
@@ -123,6 +158,7 @@ where
                     msg.message.clone(),
                 )
                 .map_err(Error::TokenTransfer)?;
+                self.store_packet_status()?;
                 self.handle_masp_tx(message)
             }
             IbcMessage::Envelope(envelope) => {
@@ -131,9 +167,13 @@ where
                 // the current ibc-rs execution doesn't store the denom for the
                 // token hash when transfer with MsgRecvPacket
                 self.store_denom(envelope)?;
+                self.store_packet_status()?;
                 // For receiving the token to a shielded address
                 self.handle_masp_tx(message)
             }
+            IbcMessage::ChannelUpgrade(msg) => {
+                channel_upgrade::execute(&mut *self.ctx.inner.borrow_mut(), msg)
+            }
         }
     }
 
@@ -162,7 +202,7 @@ where
                     self.ctx
                         .inner
                         .borrow_mut()
-                        .store_ibc_denom(base_token, trace_hash, &ibc_denom)
+                        .store_ibc_denom(base_token, &trace_hash, &ibc_denom)
                         .map_err(|e| {
                             Error::Denom(format!(
                                 "Writing the IBC denom failed: {}",
@@ -170,6 +210,76 @@ where
                             ))
                         })?;
                 }
+                // Store the display metadata for wallets to resolve the
+                // hashed IBC token
+                if let Some(trace) = IbcDenomTrace::from_ibc_denom(&ibc_denom) {
+                    self.ctx
+                        .inner
+                        .borrow_mut()
+                        .store_token_metadata(
+                            &storage::ibc_token(&ibc_denom),
+                            &trace,
+                        )
+                        .map_err(|e| {
+                            Error::Denom(format!(
+                                "Writing the IBC token metadata failed: {}",
+                                e
+                            ))
+                        })?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Store the lifecycle stages of the packets handled in the transaction
+    /// so that a transfer can be tracked with the channel and the sequence.
+    /// The statuses in a terminal stage are pruned by [`prune_packet_statuses`]
+    /// after the retention window.
+    fn store_packet_status(&mut self) -> Result<(), Error> {
+        let stages = [
+            (
+                EVENT_TYPE_SEND_PACKET,
+                PacketDirection::Outgoing,
+                PacketStage::Sent,
+            ),
+            (
+                EVENT_TYPE_RECV_PACKET,
+                PacketDirection::Incoming,
+                PacketStage::Received,
+            ),
+            (
+                EVENT_TYPE_ACK_PACKET,
+                PacketDirection::Outgoing,
+                PacketStage::Acknowledged,
+            ),
+            (
+                EVENT_TYPE_TIMEOUT_PACKET,
+                PacketDirection::Outgoing,
+                PacketStage::TimedOut,
+            ),
+        ];
+        for (event_type, direction, stage) in stages {
+            let events = self
+                .ctx
+                .inner
+                .borrow()
+                .get_ibc_events(event_type)
+                .map_err(|e| Error::PacketStatus(e.to_string()))?;
+            for event in events {
+                let (port_id, channel_id, sequence) =
+                    packet_event_ids(&event, direction)?;
+                self.ctx
+                    .inner
+                    .borrow_mut()
+                    .store_packet_status(
+                        direction,
+                        &port_id,
+                        &channel_id,
+                        sequence,
+                        stage,
+                    )
+                    .map_err(|e| Error::PacketStatus(e.to_string()))?;
             }
         }
         Ok(())
@@ -233,12 +343,22 @@ where
         let message = decode_message(tx_data)?;
         match message {
             IbcMessage::Transfer(msg) => {
+                channel_upgrade::check_send_allowed(
+                    &*self.ctx.inner.borrow(),
+                    &msg.port_id_on_a,
+                    &msg.chan_id_on_a,
+                )?;
                 let token_transfer_ctx =
                     TokenTransferContext::new(self.ctx.inner.clone());
                 send_transfer_validate(&self.ctx, &token_transfer_ctx, msg)
                     .map_err(Error::TokenTransfer)
             }
             IbcMessage::ShieldedTransfer(msg) => {
+                channel_upgrade::check_send_allowed(
+                    &*self.ctx.inner.borrow(),
+                    &msg.message.port_id_on_a,
+                    &msg.message.chan_id_on_a,
+                )?;
                 let token_transfer_ctx =
                     TokenTransferContext::new(self.ctx.inner.clone());
                 send_transfer_validate(
@@ -249,10 +369,33 @@ where
                 .map_err(Error::TokenTransfer)
             }
             IbcMessage::Envelope(envelope) => {
+                self.validate_localhost_creation(&envelope)?;
                 validate(&self.ctx, &self.router, envelope)
                     .map_err(|e| Error::Context(Box::new(e)))
             }
+            IbcMessage::ChannelUpgrade(msg) => {
+                channel_upgrade::validate(&*self.ctx.inner.borrow(), &msg)
+            }
+        }
+    }
+
+    /// A localhost client should be created with the proof specs of this
+    /// chain
+    fn validate_localhost_creation(
+        &self,
+        envelope: &MsgEnvelope,
+    ) -> Result<(), Error> {
+        if let MsgEnvelope::Client(ClientMsg::CreateClient(msg)) = envelope {
+            if let Ok(cs) =
+                LocalhostClientState::try_from(msg.client_state.clone())
+            {
+                cs.validate_proof_specs(
+                    &self.ctx.validation_params.proof_specs,
+                )
+                .map_err(|e| Error::Context(Box::new(e.into())))?;
+            }
         }
+        Ok(())
     }
 
     /// Handle the MASP transaction if needed
@@ -304,6 +447,8 @@ pub enum IbcMessage {
     Transfer(MsgTransfer),
     /// Ibc shielded transfer
     ShieldedTransfer(MsgShieldedTransfer),
+    /// Channel upgrade handshake
+    ChannelUpgrade(MsgChannelUpgrade),
 }
 
 /// Tries to decode transaction data to an `IbcMessage`
@@ -323,9 +468,50 @@ pub fn decode_message(tx_data: &[u8]) -> Result<IbcMessage, Error> {
         return Ok(IbcMessage::ShieldedTransfer(msg));
     }
 
+    // Message of the channel upgrade handshake
+    if let Ok(msg) = MsgChannelUpgrade::try_from_slice(tx_data) {
+        return Ok(IbcMessage::ChannelUpgrade(msg));
+    }
+
     Err(Error::DecodingData)
 }
 
+/// Check if the transaction data is a light-client misbehaviour submission
+pub fn is_misbehaviour_message(tx_data: &[u8]) -> bool {
+    matches!(
+        decode_message(tx_data),
+        Ok(IbcMessage::Envelope(MsgEnvelope::Client(
+            ClientMsg::Misbehaviour(_)
+        )))
+    )
+}
+
+/// Get the port ID, the channel ID and the sequence of the packet on this
+/// chain from the packet event
+fn packet_event_ids(
+    event: &IbcEvent,
+    direction: PacketDirection,
+) -> Result<(PortId, ChannelId, Sequence), Error> {
+    let (port_attr, channel_attr) = match direction {
+        PacketDirection::Outgoing => ("packet_src_port", "packet_src_channel"),
+        PacketDirection::Incoming => ("packet_dst_port", "packet_dst_channel"),
+    };
+    let attribute = |key: &str| {
+        event.attributes.get(key).ok_or_else(|| {
+            Error::PacketStatus(format!(
+                "The attribute {key} is missing: Event {event}"
+            ))
+        })
+    };
+    let port_id = PortId::from_str(attribute(port_attr)?)
+        .map_err(|e| Error::PacketStatus(e.to_string()))?;
+    let channel_id = ChannelId::from_str(attribute(channel_attr)?)
+        .map_err(|e| Error::PacketStatus(e.to_string()))?;
+    let sequence = Sequence::from_str(attribute("packet_sequence")?)
+        .map_err(|e| Error::PacketStatus(e.to_string()))?;
+    Ok((port_id, channel_id, sequence))
+}
+
 /// Get the IbcToken from the source/destination ports and channels
 pub fn received_ibc_token(
     ibc_denom: &PrefixedDenom,
@@ -525,3 +711,42 @@ pub mod testing {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    #[test]
+    fn test_packet_event_ids() {
+        let attributes = [
+            ("packet_src_port", "transfer"),
+            ("packet_src_channel", "channel-0"),
+            ("packet_dst_port", "icqhost"),
+            ("packet_dst_channel", "channel-1"),
+            ("packet_sequence", "7"),
+        ];
+        let mut event = IbcEvent {
+            event_type: EVENT_TYPE_ACK_PACKET.to_string(),
+            attributes: attributes
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect::<HashMap<_, _>>(),
+        };
+
+        let (port_id, channel_id, sequence) =
+            packet_event_ids(&event, PacketDirection::Outgoing).unwrap();
+        assert_eq!(port_id, PortId::transfer());
+        assert_eq!(channel_id, ChannelId::new(0));
+        assert_eq!(sequence, Sequence::from(7));
+        let (port_id, channel_id, _) =
+            packet_event_ids(&event, PacketDirection::Incoming).unwrap();
+        assert_eq!(port_id.as_str(), "icqhost");
+        assert_eq!(channel_id, ChannelId::new(1));
+
+        // The status of a packet can't be tracked without its sequence
+        event.attributes.remove("packet_sequence");
+        assert!(packet_event_ids(&event, PacketDirection::Outgoing).is_err());
+    }
+}
@@ -14,8 +14,8 @@ use namada_core::ibc::core::host::types::path::{
 use namada_core::types::address::{
     Address, InternalAddress, HASH_LEN, SHA_HASH_LEN,
 };
-use namada_core::types::ibc::IbcTokenHash;
-use namada_core::types::storage::{DbKeySeg, Key, KeySeg};
+use namada_core::types::ibc::{IbcTokenHash, PacketDirection};
+use namada_core::types::storage::{BlockHeight, DbKeySeg, Key, KeySeg};
 use sha2::{Digest, Sha256};
 use thiserror::Error;
 
@@ -24,6 +24,15 @@ const CONNECTIONS_COUNTER_PREFIX: &str = "connections";
 const CHANNELS_COUNTER_PREFIX: &str = "channelEnds";
 const COUNTER_SEG: &str = "counter";
 const DENOM: &str = "ibc_denom";
+const ICQ_PREFIX: &str = "icq";
+const ICQ_ALLOWLIST: &str = "allowlist";
+const CHANNEL_UPGRADES_PREFIX: &str = "channelUpgrades";
+const UPGRADES: &str = "upgrades";
+const UPGRADE_ERRORS: &str = "upgradeError";
+const UPGRADE_STATES: &str = "states";
+const UPGRADE_SEQUENCES: &str = "sequences";
+const PACKET_STATUS_PREFIX: &str = "packetStatus";
+const PACKET_STATUS_PRUNING: &str = "pruning";
 
 #[allow(missing_docs)]
 #[derive(Error, Debug)]
@@ -57,6 +66,12 @@ pub fn client_counter_key() -> Key {
     ibc_key(path).expect("Creating a key for the client counter shouldn't fail")
 }
 
+/// Returns a key prefix for the clients
+pub fn clients_prefix() -> Key {
+    ibc_key(CLIENTS_COUNTER_PREFIX)
+        .expect("Creating a key prefix for the clients shouldn't fail")
+}
+
 /// Returns a key of the IBC connection counter
 pub fn connection_counter_key() -> Key {
     let path = format!("{}/{}", CONNECTIONS_COUNTER_PREFIX, COUNTER_SEG);
@@ -118,6 +133,139 @@ pub fn channel_key(port_id: &PortId, channel_id: &ChannelId) -> Key {
         .expect("Creating a key for the channel shouldn't fail")
 }
 
+/// Returns the path of the proposed upgrade of the channel
+pub fn channel_upgrade_path(
+    port_id: &PortId,
+    channel_id: &ChannelId,
+) -> String {
+    format!(
+        "{CHANNEL_UPGRADES_PREFIX}/{UPGRADES}/ports/{port_id}/channels/\
+         {channel_id}"
+    )
+}
+
+/// Returns the path of the upgrade error receipt of the channel
+pub fn channel_upgrade_error_path(
+    port_id: &PortId,
+    channel_id: &ChannelId,
+) -> String {
+    format!(
+        "{CHANNEL_UPGRADES_PREFIX}/{UPGRADE_ERRORS}/ports/{port_id}/channels/\
+         {channel_id}"
+    )
+}
+
+/// Returns a key for the proposed upgrade of the channel. The upgrade is
+/// committed as the ICS-004 `Upgrade` to be proven on the counterparty.
+pub fn channel_upgrade_key(port_id: &PortId, channel_id: &ChannelId) -> Key {
+    ibc_key(channel_upgrade_path(port_id, channel_id))
+        .expect("Creating a key for the channel upgrade shouldn't fail")
+}
+
+/// Returns a key for the progress of the proposed upgrade of the channel
+pub fn channel_upgrade_state_key(
+    port_id: &PortId,
+    channel_id: &ChannelId,
+) -> Key {
+    let path = format!(
+        "{CHANNEL_UPGRADES_PREFIX}/{UPGRADE_STATES}/ports/{port_id}/channels/\
+         {channel_id}"
+    );
+    ibc_key(path)
+        .expect("Creating a key for the channel upgrade state shouldn't fail")
+}
+
+/// Returns a key for the upgrade error receipt of the channel
+pub fn channel_upgrade_error_key(
+    port_id: &PortId,
+    channel_id: &ChannelId,
+) -> Key {
+    ibc_key(channel_upgrade_error_path(port_id, channel_id))
+        .expect("Creating a key for the channel upgrade error shouldn't fail")
+}
+
+/// Returns a key for the last upgrade sequence of the channel
+pub fn channel_upgrade_sequence_key(
+    port_id: &PortId,
+    channel_id: &ChannelId,
+) -> Key {
+    let path = format!(
+        "{CHANNEL_UPGRADES_PREFIX}/{UPGRADE_SEQUENCES}/ports/{port_id}/\
+         channels/{channel_id}"
+    );
+    ibc_key(path)
+        .expect("Creating a key for the upgrade sequence shouldn't fail")
+}
+
+/// Returns a key prefix for the packet commitments of the channel
+pub fn commitment_prefix(port_id: &PortId, channel_id: &ChannelId) -> Key {
+    let path = format!(
+        "commitments/ports/{}/channels/{}/sequences",
+        port_id, channel_id
+    );
+    ibc_key(path)
+        .expect("Creating a key prefix for the commitments shouldn't fail")
+}
+
+/// Returns a key for the lifecycle status of the packet
+pub fn packet_status_key(
+    direction: PacketDirection,
+    port_id: &PortId,
+    channel_id: &ChannelId,
+    sequence: Sequence,
+) -> Key {
+    let path = format!(
+        "{PACKET_STATUS_PREFIX}/{direction}/ports/{port_id}/channels/\
+         {channel_id}/sequences/{sequence}"
+    );
+    ibc_key(path).expect("Creating a key for the packet status shouldn't fail")
+}
+
+/// Returns a key prefix of the index of the packet statuses to be pruned at
+/// the given height
+pub fn packet_status_pruning_prefix(height: BlockHeight) -> Key {
+    let path =
+        format!("{PACKET_STATUS_PREFIX}/{PACKET_STATUS_PRUNING}/{height}");
+    ibc_key(path).expect(
+        "Creating a key prefix for the packet status pruning shouldn't fail",
+    )
+}
+
+/// Returns a key of the index of the packet statuses to be pruned at the
+/// given height, whose value is the key of the packet status
+pub fn packet_status_pruning_key(
+    height: BlockHeight,
+    direction: PacketDirection,
+    port_id: &PortId,
+    channel_id: &ChannelId,
+    sequence: Sequence,
+) -> Key {
+    let path = format!(
+        "{PACKET_STATUS_PREFIX}/{PACKET_STATUS_PRUNING}/{height}/{direction}/\
+         ports/{port_id}/channels/{channel_id}/sequences/{sequence}"
+    );
+    ibc_key(path)
+        .expect("Creating a key for the packet status pruning shouldn't fail")
+}
+
+/// Returns the client ID if the given key is the client state key
+pub fn is_client_state_key(key: &Key) -> Option<ClientId> {
+    match &key.segments[..] {
+        [
+            DbKeySeg::AddressSeg(addr),
+            DbKeySeg::StringSeg(prefix),
+            DbKeySeg::StringSeg(client_id),
+            DbKeySeg::StringSeg(suffix),
+        ] if addr == &Address::Internal(InternalAddress::Ibc)
+            && prefix == "clients"
+            && suffix == "clientState" =>
+        {
+            ClientId::from_str(&client_id.raw()).ok()
+        }
+        _ => None,
+    }
+}
+
 /// Returns a key for the connection list
 pub fn client_connections_key(client_id: &ClientId) -> Key {
     let path = Path::ClientConnection(ClientConnectionPath(client_id.clone()));
@@ -397,6 +545,29 @@ pub fn ibc_denom_key(
         .expect("Cannot obtain a storage key")
 }
 
+/// Returns a key of the allowlist of storage key prefixes that can be queried
+/// by the interchain query (ICQ) host
+pub fn icq_allowlist_key() -> Key {
+    let path = format!("{}/{}", ICQ_PREFIX, ICQ_ALLOWLIST);
+    ibc_key(path).expect("Creating a key for the ICQ allowlist shouldn't fail")
+}
+
+/// Returns true if the given key is for a channel upgrade
+pub fn is_channel_upgrade_key(key: &Key) -> bool {
+    matches!(&key.segments[..],
+    [DbKeySeg::AddressSeg(addr), DbKeySeg::StringSeg(prefix), ..]
+        if addr == &Address::Internal(InternalAddress::Ibc)
+            && prefix == CHANNEL_UPGRADES_PREFIX)
+}
+
+/// Returns true if the given key is for the ICQ host
+pub fn is_icq_key(key: &Key) -> bool {
+    matches!(&key.segments[..],
+    [DbKeySeg::AddressSeg(addr), DbKeySeg::StringSeg(prefix), ..]
+        if addr == &Address::Internal(InternalAddress::Ibc)
+            && prefix == ICQ_PREFIX)
+}
+
 /// Hash the denom
 #[inline]
 pub fn calc_hash(denom: impl AsRef<str>) -> String {
@@ -1,6 +1,6 @@
 use namada_sdk::governance::parameters::GovernanceParameters;
 use namada_sdk::governance::storage::proposal::StorageProposal;
-use namada_sdk::governance::utils::Vote;
+use namada_sdk::governance::utils::{ProposalTally, Vote};
 
 use super::*;
 
@@ -41,3 +41,16 @@ pub async fn query_proposal_votes(
     .map_err(|e| Error::Other(e.to_string()))?;
     rpc::query_proposal_votes(&client, proposal_id).await
 }
+
+/// Query the tally of a proposal computed from the votes cast so far
+pub async fn query_proposal_tally(
+    tendermint_addr: &str,
+    proposal_id: u64,
+) -> Result<Option<ProposalTally>, Error> {
+    let client = HttpClient::new(
+        TendermintAddress::from_str(tendermint_addr)
+            .map_err(|e| Error::Other(e.to_string()))?,
+    )
+    .map_err(|e| Error::Other(e.to_string()))?;
+    rpc::query_proposal_tally(&client, proposal_id).await
+}
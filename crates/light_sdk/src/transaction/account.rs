@@ -26,6 +26,7 @@ impl InitAccount {
     ) -> Self {
         let init_account = namada_sdk::account::InitAccount {
             public_keys,
+            weights: vec![],
             vp_code_hash,
             threshold,
         };
@@ -177,6 +178,8 @@ impl UpdateAccount {
         vp_code_hash: Option<Hash>,
         public_keys: Vec<common::PublicKey>,
         threshold: Option<u8>,
+        vp_update_timelock: Option<u64>,
+        cancel_pending_vp_update: bool,
         args: GlobalArgs,
     ) -> Self {
         let update_account = namada_sdk::account::UpdateAccount {
@@ -184,6 +187,8 @@ impl UpdateAccount {
             vp_code_hash,
             public_keys,
             threshold,
+            vp_update_timelock,
+            cancel_pending_vp_update,
         };
 
         Self(transaction::build_tx(
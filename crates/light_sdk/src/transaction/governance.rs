@@ -1,3 +1,4 @@
+use namada_sdk::governance::utils::VotingMode;
 use namada_sdk::governance::{ProposalType, ProposalVote};
 use namada_sdk::tx::data::GasLimit;
 use namada_sdk::tx::{Signature, Tx, TxError};
@@ -12,6 +13,7 @@ use crate::transaction;
 
 const TX_INIT_PROPOSAL_WASM: &str = "tx_init_proposal.wasm";
 const TX_VOTE_PROPOSAL: &str = "tx_vote_proposal.wasm";
+const TX_VOTE_PROPOSAL_BATCH: &str = "tx_vote_proposal_batch.wasm";
 
 /// Transaction to initialize a governance proposal
 pub struct InitProposal(Tx);
@@ -22,21 +24,29 @@ impl InitProposal {
     pub fn new(
         id: u64,
         content: Hash,
+        content_hash: Hash,
+        content_uri: Option<String>,
         author: Address,
         r#type: ProposalType,
         voting_start_epoch: Epoch,
         voting_end_epoch: Epoch,
         grace_epoch: Epoch,
+        expedited: bool,
+        voting_mode: VotingMode,
         args: GlobalArgs,
     ) -> Self {
         let init_proposal = namada_sdk::governance::InitProposalData {
             id,
             content,
+            content_hash,
+            content_uri,
             author,
             r#type,
             voting_start_epoch,
             voting_end_epoch,
             grace_epoch,
+            expedited,
+            voting_mode,
         };
 
         Self(transaction::build_tx(
@@ -182,3 +192,80 @@ impl VoteProposal {
         self.0.validate_tx()
     }
 }
+
+/// Transaction to submit a batch of off-chain signed votes
+pub struct VoteProposalBatch(Tx);
+
+impl VoteProposalBatch {
+    /// Build a raw VoteProposalBatch transaction from the given signed ballots
+    pub fn new(
+        ballots: Vec<namada_sdk::governance::SignedVoteProposalData>,
+        args: GlobalArgs,
+    ) -> Self {
+        let vote_proposal_batch =
+            namada_sdk::governance::VoteProposalBatchData { ballots };
+
+        Self(transaction::build_tx(
+            args,
+            vote_proposal_batch,
+            TX_VOTE_PROPOSAL_BATCH.to_string(),
+        ))
+    }
+
+    /// Get the bytes to sign for the given transaction
+    pub fn get_sign_bytes(&self) -> Vec<Hash> {
+        transaction::get_sign_bytes(&self.0)
+    }
+
+    /// Attach the provided signatures to the tx
+    pub fn attach_signatures(
+        self,
+        signer: common::PublicKey,
+        signature: common::Signature,
+    ) -> Self {
+        Self(transaction::attach_raw_signatures(
+            self.0, signer, signature,
+        ))
+    }
+
+    /// Attach the fee data to the tx
+    pub fn attach_fee(
+        self,
+        fee: DenominatedAmount,
+        token: Address,
+        fee_payer: common::PublicKey,
+        epoch: Epoch,
+        gas_limit: GasLimit,
+    ) -> Self {
+        Self(attach_fee(self.0, fee, token, fee_payer, epoch, gas_limit))
+    }
+
+    /// Get the bytes of the fee data to sign
+    pub fn get_fee_sig_bytes(&self) -> Hash {
+        transaction::get_wrapper_sign_bytes(&self.0)
+    }
+
+    /// Attach a signature of the fee to the tx
+    pub fn attach_fee_signature(
+        self,
+        signer: common::PublicKey,
+        signature: common::Signature,
+    ) -> Self {
+        Self(attach_fee_signature(self.0, signer, signature))
+    }
+
+    /// Generates the protobuf encoding of this transaction
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0.to_bytes()
+    }
+
+    /// Gets the inner transaction without the domain wrapper
+    pub fn payload(self) -> Tx {
+        self.0
+    }
+
+    /// Validate this wrapper transaction
+    pub fn validate_tx(&self) -> Result<Option<&Signature>, TxError> {
+        self.0.validate_tx()
+    }
+}
@@ -464,6 +464,7 @@ impl ChangeMetaData {
         website: Option<String>,
         discord_handle: Option<String>,
         avatar: Option<String>,
+        name: Option<String>,
         commission_rate: Option<Dec>,
         args: GlobalArgs,
     ) -> Self {
@@ -474,6 +475,7 @@ impl ChangeMetaData {
             website,
             discord_handle,
             avatar,
+            name,
             commission_rate,
         };
 
@@ -63,6 +63,11 @@ pub fn transaction(attr: TokenStream, input: TokenStream) -> TokenStream {
         // The module entrypoint callable by wasm runtime
         #[no_mangle]
         extern "C" fn _apply_tx(tx_data_ptr: u64, tx_data_len: u64) {
+            // Report the message of a panic in the tx result
+            std::panic::set_hook(Box::new(|info| {
+                namada_tx_prelude::abort(info.to_string())
+            }));
+
             let slice = unsafe {
                 core::slice::from_raw_parts(
                     tx_data_ptr as *const u8,
@@ -80,8 +85,8 @@ pub fn transaction(attr: TokenStream, input: TokenStream) -> TokenStream {
 
             if let Err(err) = #ident(&mut ctx, tx_data) {
                 namada_tx_prelude::debug_log!("Transaction error: {}", err);
-                // crash the transaction to abort
-                panic!();
+                // abort the transaction with the error reported in the result
+                namada_tx_prelude::abort(err.to_string());
             }
         }
     };
@@ -155,6 +160,11 @@ pub fn validity_predicate(
             verifiers_ptr: u64,
             verifiers_len: u64,
         ) -> u64 {
+            // Report the message of a panic in the tx result
+            std::panic::set_hook(Box::new(|info| {
+                namada_vp_prelude::abort(info.to_string())
+            }));
+
             let slice = unsafe {
                 core::slice::from_raw_parts(addr_ptr as *const u8, addr_len as _)
             };
@@ -198,7 +208,8 @@ pub fn validity_predicate(
                 Ok(false) => 0,
                 Err(err) => {
                     namada_vp_prelude::debug_log!("Validity predicate error: {}", err);
-                    0
+                    // reject the tx with the error reported in the result
+                    namada_vp_prelude::abort(err.to_string())
                 },
             }
         }
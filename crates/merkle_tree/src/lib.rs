@@ -814,6 +814,97 @@ impl From<Proof> for namada_core::tendermint::merkle::proof::ProofOps {
     }
 }
 
+impl TryFrom<&namada_core::tendermint::merkle::proof::ProofOps> for Proof {
+    type Error = Error;
+
+    fn try_from(
+        proof_ops: &namada_core::tendermint::merkle::proof::ProofOps,
+    ) -> Result<Self> {
+        use prost::Message;
+
+        let [sub_proof_op, base_proof_op] = proof_ops.ops.as_slice() else {
+            return Err(Error::TendermintProof);
+        };
+        let key = std::str::from_utf8(&sub_proof_op.key)
+            .map_err(|e| Error::MerkleTree(e.to_string()))?;
+        let key = Key::parse(key).map_err(Error::InvalidKey)?;
+        let decode = |data: &[u8]| {
+            CommitmentProof::decode(data)
+                .map_err(|e| Error::MerkleTree(e.to_string()))
+        };
+        Ok(Self {
+            key,
+            sub_proof: decode(&sub_proof_op.data)?,
+            base_proof: decode(&base_proof_op.data)?,
+        })
+    }
+}
+
+impl Proof {
+    /// Verify the proof against the given root of the whole Merkle tree. With
+    /// `Some` value, this is verified as a proof of the existence of the key
+    /// with the value, otherwise as a proof of the non-existence of the key.
+    pub fn verify<H: StorageHasher>(
+        &self,
+        value: Option<&[u8]>,
+        root: &MerkleRoot,
+    ) -> Result<bool> {
+        use ics23::HostFunctionsManager;
+
+        let (store_type, sub_key) = StoreType::sub_key(&self.key)?;
+        let specs = match store_type {
+            StoreType::Ibc => ics23_specs::ibc_proof_specs::<H>(),
+            StoreType::Account | StoreType::PoS => {
+                ics23_specs::proof_specs::<H>()
+            }
+            StoreType::Base | StoreType::BridgePool => {
+                return Err(Error::StoreType(store_type.to_string()));
+            }
+        };
+        // The root of the sub-tree committed to by the sub proof
+        let existence_proof = match &self.sub_proof.proof {
+            Some(Ics23Proof::Exist(ep)) => Some(ep),
+            Some(Ics23Proof::Nonexist(nep)) => {
+                nep.left.as_ref().or(nep.right.as_ref())
+            }
+            _ => None,
+        };
+        let Some(existence_proof) = existence_proof else {
+            return Ok(false);
+        };
+        let sub_root = ics23::calculate_existence_root::<HostFunctionsManager>(
+            existence_proof,
+        )
+        .map_err(|e| Error::MerkleTree(e.to_string()))?;
+
+        let sub_key = sub_key.to_string();
+        let is_sub_proof_valid = match value {
+            Some(value) => ics23::verify_membership::<HostFunctionsManager>(
+                &self.sub_proof,
+                &specs[0],
+                &sub_root,
+                sub_key.as_bytes(),
+                value,
+            ),
+            None => ics23::verify_non_membership::<HostFunctionsManager>(
+                &self.sub_proof,
+                &specs[0],
+                &sub_root,
+                sub_key.as_bytes(),
+            ),
+        };
+        let is_base_proof_valid =
+            ics23::verify_membership::<HostFunctionsManager>(
+                &self.base_proof,
+                &specs[1],
+                &root.0.to_vec(),
+                store_type.to_string().as_bytes(),
+                &sub_root,
+            );
+        Ok(is_sub_proof_valid && is_base_proof_valid)
+    }
+}
+
 impl<'a, H: StorageHasher + Default> SubTreeRead for &'a Smt<H> {
     fn root(&self) -> MerkleRoot {
         Smt::<H>::root(self).into()
@@ -1095,6 +1186,59 @@ mod test {
         assert!(basetree_verification_res);
     }
 
+    #[test]
+    fn test_verify_proof_ops() {
+        use namada_core::tendermint::merkle::proof::ProofOps;
+
+        let mut tree = MerkleTree::<Sha256Hasher>::default();
+
+        let key_prefix: Key =
+            Address::Internal(InternalAddress::Ibc).to_db_key().into();
+        let ibc_key = key_prefix.push(&"test".to_string()).unwrap();
+        let ibc_non_key = key_prefix.push(&"test2".to_string()).unwrap();
+        let key_prefix: Key =
+            Address::Internal(InternalAddress::PoS).to_db_key().into();
+        let pos_key = key_prefix.push(&"test".to_string()).unwrap();
+
+        let ibc_val = [1u8; 8].to_vec();
+        tree.update(&ibc_key, ibc_val).unwrap();
+        let pos_val = [2u8; 8].to_vec();
+        tree.update(&pos_key, pos_val.clone()).unwrap();
+        let root = tree.root();
+
+        // An existence proof decoded from the ProofOps
+        let proof = match tree
+            .get_sub_tree_existence_proof(
+                std::array::from_ref(&pos_key),
+                vec![&pos_val],
+            )
+            .unwrap()
+        {
+            MembershipProof::ICS23(proof) => proof,
+            _ => panic!("Test failed"),
+        };
+        let proof_ops: ProofOps =
+            tree.get_sub_tree_proof(&pos_key, proof).unwrap().into();
+        let proof = Proof::try_from(&proof_ops).unwrap();
+        assert_eq!(proof.key, pos_key);
+        assert!(proof.verify::<Sha256Hasher>(Some(&pos_val), &root).unwrap());
+        assert!(!proof.verify::<Sha256Hasher>(Some(&[3u8; 8]), &root).unwrap());
+        assert!(!proof.verify::<Sha256Hasher>(None, &root).unwrap());
+        assert!(
+            !proof
+                .verify::<Sha256Hasher>(Some(&pos_val), &MerkleRoot([0; 32]))
+                .unwrap()
+        );
+
+        // A non-existence proof decoded from the ProofOps
+        let proof_ops: ProofOps =
+            tree.get_non_existence_proof(&ibc_non_key).unwrap().into();
+        let proof = Proof::try_from(&proof_ops).unwrap();
+        assert_eq!(proof.key, ibc_non_key);
+        assert!(proof.verify::<Sha256Hasher>(None, &root).unwrap());
+        assert!(!proof.verify::<Sha256Hasher>(Some(&[1u8; 8]), &root).unwrap());
+    }
+
     #[test]
     fn test_restore_tree() {
         let mut tree = MerkleTree::<Sha256Hasher>::default();
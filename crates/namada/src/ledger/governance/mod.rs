@@ -2,14 +2,16 @@
 
 pub mod utils;
 
+use std::cmp;
 use std::collections::BTreeSet;
 
 use borsh::BorshDeserialize;
 use namada_governance::storage::proposal::{
-    AddRemove, PGFAction, ProposalType,
+    AddRemove, PGFAction, PGFStream, ProposalKind, ProposalType,
+    VoteProposalBatchData, MAX_CONTENT_URI_LENGTH,
 };
 use namada_governance::storage::{is_proposal_accepted, keys as gov_storage};
-use namada_governance::utils::is_valid_validator_voting_period;
+use namada_governance::utils::{is_valid_validator_voting_period, VotingMode};
 use namada_governance::ProposalVote;
 use namada_proof_of_stake::is_validator;
 use namada_proof_of_stake::queries::find_delegations;
@@ -20,11 +22,13 @@ use thiserror::Error;
 
 use self::utils::ReadType;
 use crate::ledger::native_vp::{Ctx, NativeVp};
-use crate::ledger::{native_vp, pos};
-use crate::token;
+use crate::ledger::{native_vp, pgf, pos};
 use crate::types::address::{Address, InternalAddress};
+use crate::types::chain::ChainId;
+use crate::types::hash::Hash;
 use crate::types::storage::{Epoch, Key};
 use crate::vm::WasmCacheAccess;
+use crate::{account, token};
 
 /// for handling Governance NativeVP errors
 pub type Result<T> = std::result::Result<T, Error>;
@@ -86,13 +90,32 @@ where
 
             let result = match (key_type, proposal_id) {
                 (KeyType::VOTE, Some(proposal_id)) => {
-                    self.is_valid_vote_key(proposal_id, key, verifiers)
+                    self.is_valid_vote_key(proposal_id, key, tx_data, verifiers)
+                }
+                (KeyType::VOTE_DELEGATION, _) => {
+                    self.is_valid_vote_delegation_key(key, verifiers)
+                }
+                (KeyType::VOTE_DELEGATOR, _) => {
+                    self.is_valid_vote_delegator_key(key, verifiers)
+                }
+                (KeyType::VOTER_VOTE, _) => self.is_valid_voter_vote_key(key),
+                (KeyType::CONSUMED_BALLOT, _) => {
+                    self.is_valid_consumed_ballot_key(key, tx_data)
                 }
                 (KeyType::CONTENT, Some(proposal_id)) => {
                     self.is_valid_content_key(proposal_id)
                 }
+                (KeyType::CONTENT_HASH, Some(proposal_id)) => {
+                    self.is_valid_content_hash(proposal_id)
+                }
+                (KeyType::CONTENT_URI, Some(proposal_id)) => {
+                    self.is_valid_content_uri(proposal_id)
+                }
+                (KeyType::VOTING_MODE, Some(proposal_id)) => {
+                    self.is_valid_voting_mode(proposal_id)
+                }
                 (KeyType::TYPE, Some(proposal_id)) => {
-                    self.is_valid_proposal_type(proposal_id)
+                    self.is_valid_proposal_type(proposal_id, verifiers)
                 }
                 (KeyType::PROPOSAL_CODE, Some(proposal_id)) => {
                     self.is_valid_proposal_code(proposal_id)
@@ -100,6 +123,9 @@ where
                 (KeyType::GRACE_EPOCH, Some(proposal_id)) => {
                     self.is_valid_grace_epoch(proposal_id)
                 }
+                (KeyType::EXPEDITED, Some(proposal_id)) => {
+                    self.is_valid_expedited(proposal_id)
+                }
                 (KeyType::START_EPOCH, Some(proposal_id)) => {
                     self.is_valid_start_epoch(proposal_id)
                 }
@@ -113,7 +139,8 @@ where
                     self.is_valid_author(proposal_id, verifiers)
                 }
                 (KeyType::COUNTER, _) => self.is_valid_counter(set_count),
-                (KeyType::PROPOSAL_COMMIT, _) => {
+                (KeyType::PROPOSAL_COMMIT, _)
+                | (KeyType::VOTING_START_INDEX, _) => {
                     self.is_valid_proposal_commit()
                 }
                 (KeyType::PARAMETER, _) => self.is_valid_parameter(tx_data),
@@ -160,6 +187,7 @@ where
             let mandatory_keys = BTreeSet::from([
                 counter_key.clone(),
                 gov_storage::get_content_key(counter),
+                gov_storage::get_content_hash_key(counter),
                 gov_storage::get_author_key(counter),
                 gov_storage::get_proposal_type_key(counter),
                 gov_storage::get_funds_key(counter),
@@ -172,6 +200,20 @@ where
             if !keys.is_superset(&mandatory_keys) {
                 return Ok((false, 0));
             }
+
+            // The proposal must be indexed by its voting start epoch
+            let start_epoch: Epoch = self.force_read(
+                &gov_storage::get_voting_start_epoch_key(counter),
+                ReadType::Post,
+            )?;
+            let voting_start_proposals_key =
+                gov_storage::get_voting_start_proposals_key(
+                    counter,
+                    start_epoch.0,
+                );
+            if !keys.contains(&voting_start_proposals_key) {
+                return Ok((false, 0));
+            }
         }
 
         Ok((true, post_counter - pre_counter))
@@ -181,6 +223,7 @@ where
         &self,
         proposal_id: u64,
         key: &Key,
+        tx_data: &Tx,
         verifiers: &BTreeSet<Address>,
     ) -> Result<bool> {
         let counter_key = gov_storage::get_counter_key();
@@ -223,15 +266,44 @@ where
             delegation_address.clone(),
         );
 
-        if self
-            .force_read::<ProposalVote>(&vote_key, ReadType::Post)
-            .is_err()
-        {
+        // A vote can be overwritten or withdrawn while the voting window is
+        // open. A withdrawn vote must have been cast before.
+        let post_vote = self.ctx.post().read::<ProposalVote>(&vote_key)?;
+        if post_vote.is_none() && !self.ctx.has_key_pre(&vote_key)? {
             return Err(Error::InvalidVoteKey(key.to_string()));
         }
 
+        // The index of the votes of the voter must be kept in sync
+        let voter_vote_key =
+            gov_storage::get_voter_vote_key(voter_address, proposal_id);
+        let indexed_validators: BTreeSet<Address> =
+            self.ctx.post().read(&voter_vote_key)?.unwrap_or_default();
+        if indexed_validators.contains(delegation_address)
+            != post_vote.is_some()
+        {
+            tracing::info!("Vote key {key} is not indexed for its voter.");
+            return Ok(false);
+        }
+
+        // A vote submitted in a batch on behalf of the voter must come from a
+        // valid off-chain signed ballot
+        if let Some(batch) = tx_data
+            .data()
+            .and_then(|data| VoteProposalBatchData::try_from_slice(&data).ok())
+        {
+            if post_vote.is_none()
+                || !self.is_valid_ballot(&batch, &vote_key)?
+            {
+                tracing::info!("Invalid signed ballot for vote key {key}.");
+                return Ok(false);
+            }
+        }
+
+        // A voter holds a single ballot for all its delegations, so the vote
+        // keys of all the delegations must hold the same vote, or must all be
+        // withdrawn together
         // TODO: We should refactor this by modifying the vote proposal tx
-        let all_delegations_are_valid = if let Ok(delegations) =
+        let is_single_ballot = if let Ok(delegations) =
             find_delegations(&self.ctx.pre(), voter_address, &current_epoch)
         {
             if delegations.is_empty() {
@@ -243,13 +315,16 @@ where
                         voter_address.clone(),
                         address.clone(),
                     );
-                    self.ctx.post().has_key(&vote_key).unwrap_or(false)
+                    self.ctx
+                        .post()
+                        .read::<ProposalVote>(&vote_key)
+                        .map_or(false, |vote| vote == post_vote)
                 })
             }
         } else {
             return Ok(false);
         };
-        if !all_delegations_are_valid {
+        if !is_single_ballot {
             return Ok(false);
         }
 
@@ -299,6 +374,236 @@ where
         Ok(is_delegator)
     }
 
+    /// Validate a vote written from an off-chain signed ballot. The ballot
+    /// must match the written vote and be signed by the voter, and it cannot
+    /// overwrite an existing vote so that it can't be replayed.
+    fn is_valid_ballot(
+        &self,
+        batch: &VoteProposalBatchData,
+        vote_key: &Key,
+    ) -> Result<bool> {
+        let ballot = match batch.find_ballot(vote_key) {
+            Some(ballot) => ballot,
+            None => return Ok(false),
+        };
+        if self.ctx.has_key_pre(vote_key)? {
+            return Ok(false);
+        }
+        let post_vote: ProposalVote =
+            self.force_read(vote_key, ReadType::Post)?;
+        if post_vote != ballot.data.vote {
+            return Ok(false);
+        }
+
+        let voter = &ballot.data.voter;
+        let public_keys_map =
+            account::public_keys_index_map(&self.ctx.pre(), voter)?;
+        let threshold =
+            account::threshold(&self.ctx.pre(), voter)?.unwrap_or(1);
+        let chain_id = ChainId(self.ctx.get_chain_id()?);
+
+        // A ballot can only be submitted once, even if the vote it cast has
+        // been withdrawn since
+        let consumed_ballot_key = gov_storage::get_consumed_ballot_key(
+            &ballot.compute_hash(&chain_id),
+        );
+        if self.ctx.has_key_pre(&consumed_ballot_key)?
+            || !self.ctx.has_key_post(&consumed_ballot_key)?
+        {
+            return Ok(false);
+        }
+
+        Ok(ballot.check_signature(&public_keys_map, threshold, &chain_id))
+    }
+
+    /// Validate the key of a consumed ballot. It can only be written once, by
+    /// the batch that submitted the ballot.
+    fn is_valid_consumed_ballot_key(
+        &self,
+        key: &Key,
+        tx_data: &Tx,
+    ) -> Result<bool> {
+        let ballot_hash = match gov_storage::is_consumed_ballot_key(key) {
+            Some(hash) => hash,
+            None => return Err(Error::InvalidVoteKey(key.to_string())),
+        };
+        if self.ctx.has_key_pre(key)? {
+            return Ok(false);
+        }
+        let batch = match tx_data
+            .data()
+            .and_then(|data| VoteProposalBatchData::try_from_slice(&data).ok())
+        {
+            Some(batch) => batch,
+            None => return Ok(false),
+        };
+        let chain_id = ChainId(self.ctx.get_chain_id()?);
+        let post_id: u64 = self.force_read(key, ReadType::Post)?;
+
+        Ok(batch.ballots.iter().any(|ballot| {
+            ballot.compute_hash(&chain_id) == ballot_hash
+                && ballot.data.id == post_id
+        }))
+    }
+
+    /// Validate a key of the index of the votes of a voter. The index must
+    /// only reference the votes the voter currently holds.
+    fn is_valid_voter_vote_key(&self, key: &Key) -> Result<bool> {
+        let (voter, proposal_id) = match gov_storage::is_voter_vote_key(key) {
+            Some(data) => data,
+            None => return Err(Error::InvalidVoteKey(key.to_string())),
+        };
+
+        let pre_validators: BTreeSet<Address> =
+            self.ctx.pre().read(key)?.unwrap_or_default();
+        let post_validators: BTreeSet<Address> =
+            self.ctx.post().read(key)?.unwrap_or_default();
+
+        for validator in pre_validators.union(&post_validators) {
+            let vote_key = gov_storage::get_vote_proposal_key(
+                proposal_id,
+                voter.clone(),
+                validator.clone(),
+            );
+            if self.ctx.has_key_post(&vote_key)?
+                != post_validators.contains(validator)
+            {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Validate a vote delegation key. A delegation can be revoked or pruned
+    /// at any epoch, but only written at the current epoch.
+    fn is_valid_vote_delegation_key(
+        &self,
+        key: &Key,
+        verifiers: &BTreeSet<Address>,
+    ) -> Result<bool> {
+        let (delegator, kind, epoch) =
+            match gov_storage::is_vote_delegation_key(key) {
+                Some(data) => data,
+                None => return Err(Error::InvalidVoteKey(key.to_string())),
+            };
+
+        if self.ctx.has_key_post(key)? {
+            let current_epoch = self.ctx.get_block_epoch()?;
+            if epoch != current_epoch {
+                tracing::info!(
+                    "Invalid vote delegation epoch. Expected {current_epoch}, \
+                     got {epoch}."
+                );
+                return Ok(false);
+            }
+
+            let delegate: Option<Address> =
+                self.force_read(key, ReadType::Post)?;
+            match delegate {
+                Some(delegate) if &delegate == delegator => {
+                    tracing::info!(
+                        "Voting power cannot be delegated to oneself."
+                    );
+                    return Ok(false);
+                }
+                Some(_) => {}
+                None => {
+                    tracing::info!(
+                        "A revoked vote delegation must be deleted."
+                    );
+                    return Ok(false);
+                }
+            }
+        }
+
+        Ok(verifiers.contains(delegator)
+            && self.is_valid_vote_delegation_index(delegator, kind, None)?)
+    }
+
+    /// Validate a key of the index of the vote delegations by delegate
+    fn is_valid_vote_delegator_key(
+        &self,
+        key: &Key,
+        verifiers: &BTreeSet<Address>,
+    ) -> Result<bool> {
+        let (delegate, kind, delegator) =
+            match gov_storage::is_vote_delegator_key(key) {
+                Some(data) => data,
+                None => return Err(Error::InvalidVoteKey(key.to_string())),
+            };
+
+        Ok(verifiers.contains(delegator)
+            && self.is_valid_vote_delegation_index(
+                delegator,
+                kind,
+                Some(delegate),
+            )?)
+    }
+
+    /// Check that a delegator has at most one vote delegation for a kind of
+    /// proposals in the post state, as a new delegation supersedes the
+    /// previous ones, and that the index of the delegations by delegate
+    /// only holds this delegation for the previous, current and the given
+    /// delegates.
+    fn is_valid_vote_delegation_index(
+        &self,
+        delegator: &Address,
+        kind: ProposalKind,
+        delegate: Option<&Address>,
+    ) -> Result<bool> {
+        let prefix =
+            gov_storage::get_vote_delegation_kind_prefix(delegator, kind);
+        let post_delegations: Vec<(Key, Option<Address>)> =
+            namada_state::iter_prefix(&self.ctx.post(), &prefix)?
+                .collect::<namada_state::StorageResult<_>>()?;
+        let current = match post_delegations.as_slice() {
+            [] => None,
+            [(key, delegate)] => {
+                let epoch = gov_storage::is_vote_delegation_key(key)
+                    .map(|(_, _, epoch)| epoch);
+                delegate.clone().zip(epoch)
+            }
+            _ => {
+                tracing::info!(
+                    "The previous vote delegations of {delegator} for the \
+                     {kind} proposals must be pruned."
+                );
+                return Ok(false);
+            }
+        };
+
+        let mut delegates = BTreeSet::new();
+        let pre = self.ctx.pre();
+        let pre_delegations =
+            namada_state::iter_prefix::<Option<Address>>(&pre, &prefix)?;
+        for entry in pre_delegations {
+            if let (_, Some(delegate)) = entry? {
+                delegates.insert(delegate);
+            }
+        }
+        delegates.extend(current.iter().map(|(delegate, _)| delegate.clone()));
+        delegates.extend(delegate.cloned());
+
+        for delegate in delegates {
+            let delegator_key =
+                gov_storage::get_vote_delegator_key(&delegate, kind, delegator);
+            let indexed_epoch: Option<Epoch> =
+                self.ctx.post().read(&delegator_key)?;
+            let expected_epoch = current
+                .as_ref()
+                .filter(|(current_delegate, _)| *current_delegate == delegate)
+                .map(|(_, epoch)| *epoch);
+            if indexed_epoch != expected_epoch {
+                tracing::info!(
+                    "Invalid index of the vote delegation of {delegator} to \
+                     {delegate} for the {kind} proposals."
+                );
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
     /// Validate a content key
     pub fn is_valid_content_key(&self, proposal_id: u64) -> Result<bool> {
         let content_key: Key = gov_storage::get_content_key(proposal_id);
@@ -325,14 +630,105 @@ where
         Ok(is_valid)
     }
 
+    /// Validate the proposal content hash: it must match the hash of the
+    /// committed content
+    pub fn is_valid_content_hash(&self, proposal_id: u64) -> Result<bool> {
+        let content_key = gov_storage::get_content_key(proposal_id);
+        let content_hash_key = gov_storage::get_content_hash_key(proposal_id);
+
+        let has_pre_content_hash: bool =
+            self.ctx.has_key_pre(&content_hash_key)?;
+        if has_pre_content_hash {
+            return Ok(false);
+        }
+
+        let post_content_hash: Hash =
+            self.force_read(&content_hash_key, ReadType::Post)?;
+        let post_content =
+            self.ctx.read_bytes_post(&content_key)?.unwrap_or_default();
+
+        let is_valid = post_content_hash == Hash::sha256(post_content);
+        if !is_valid {
+            tracing::info!(
+                "Proposal {proposal_id} content doesn't match the content \
+                 hash {post_content_hash}."
+            );
+        }
+        Ok(is_valid)
+    }
+
+    /// Validate the proposal content URI
+    pub fn is_valid_content_uri(&self, proposal_id: u64) -> Result<bool> {
+        let content_uri_key = gov_storage::get_content_uri_key(proposal_id);
+
+        let has_pre_content_uri: bool =
+            self.ctx.has_key_pre(&content_uri_key)?;
+        if has_pre_content_uri {
+            return Ok(false);
+        }
+
+        let post_content_uri: String =
+            self.force_read(&content_uri_key, ReadType::Post)?;
+
+        let is_valid = !post_content_uri.is_empty()
+            && post_content_uri.len() <= MAX_CONTENT_URI_LENGTH;
+        if !is_valid {
+            tracing::info!(
+                "Max content URI length {MAX_CONTENT_URI_LENGTH}, got {}.",
+                post_content_uri.len()
+            );
+        }
+        Ok(is_valid)
+    }
+
+    /// Validate the proposal voting mode
+    pub fn is_valid_voting_mode(&self, proposal_id: u64) -> Result<bool> {
+        let voting_mode_key = gov_storage::get_voting_mode_key(proposal_id);
+        let proposal_type_key = gov_storage::get_proposal_type_key(proposal_id);
+
+        let has_pre_voting_mode: bool =
+            self.ctx.has_key_pre(&voting_mode_key)?;
+        if has_pre_voting_mode {
+            return Ok(false);
+        }
+
+        let post_voting_mode: VotingMode =
+            self.force_read(&voting_mode_key, ReadType::Post)?;
+        let proposal_type: ProposalType =
+            self.force_read(&proposal_type_key, ReadType::Post)?;
+
+        let is_valid = post_voting_mode.is_valid_for(&proposal_type);
+        if !is_valid {
+            tracing::info!(
+                "Governance proposal {} can't be tallied with the {} voting \
+                 mode.",
+                proposal_id,
+                post_voting_mode
+            );
+        }
+        Ok(is_valid)
+    }
+
     /// Validate the proposal type
-    pub fn is_valid_proposal_type(&self, proposal_id: u64) -> Result<bool> {
+    pub fn is_valid_proposal_type(
+        &self,
+        proposal_id: u64,
+        verifiers: &BTreeSet<Address>,
+    ) -> Result<bool> {
         let proposal_type_key = gov_storage::get_proposal_type_key(proposal_id);
         let proposal_type: ProposalType =
             self.force_read(&proposal_type_key, ReadType::Post)?;
 
+        if !proposal_type.is_valid_content() {
+            tracing::info!(
+                "Governance proposal {} has an invalid content",
+                proposal_id
+            );
+            return Ok(false);
+        }
+
         match proposal_type {
-            ProposalType::PGFSteward(stewards) => {
+            ProposalType::PGFSteward(stewards, _) => {
                 let stewards_added = stewards
                     .iter()
                     .filter_map(|pgf_action| match pgf_action {
@@ -382,7 +778,8 @@ where
                 let are_continous_add_targets_unique = fundings
                     .iter()
                     .filter_map(|funding| match funding {
-                        PGFAction::Continuous(AddRemove::Add(target)) => {
+                        PGFAction::Continuous(AddRemove::Add(target))
+                        | PGFAction::Stream(PGFStream { target, .. }) => {
                             Some(target.target().to_lowercase())
                         }
                         _ => None,
@@ -406,15 +803,21 @@ where
                     .filter(|funding| matches!(funding, PGFAction::Retro(_)))
                     .count();
 
+                let total_grants = fundings
+                    .iter()
+                    .filter(|funding| matches!(funding, PGFAction::Grant(_)))
+                    .count();
+
                 let is_total_fundings_valid = fundings.len() < MAX_PGF_ACTIONS;
 
                 // check that they are unique by checking that the set of add
-                // plus the set of remove plus the set of retro is equal to the
-                // total fundings
+                // plus the set of remove plus the set of retro and grants is
+                // equal to the total fundings
                 let are_continous_fundings_unique =
                     are_continous_add_targets_unique.len()
                         + are_continous_remove_targets_unique.len()
                         + total_retro_targerts
+                        + total_grants
                         == fundings.len();
 
                 // can't remove and add the same target in the same proposal
@@ -423,11 +826,47 @@ where
                     .count() as u64
                     == 0;
 
+                // a stream must be paid at least once after the proposal
+                // execution
+                let grace_epoch_key =
+                    gov_storage::get_grace_epoch_key(proposal_id);
+                let grace_epoch: Epoch =
+                    self.force_read(&grace_epoch_key, ReadType::Post)?;
+                let are_streams_valid =
+                    fundings.iter().all(|funding| match funding {
+                        PGFAction::Stream(stream) => {
+                            stream.end_epoch > grace_epoch
+                        }
+                        _ => true,
+                    });
+
+                let are_grants_valid =
+                    fundings.iter().all(|funding| match funding {
+                        PGFAction::Grant(grant) => grant.is_valid(),
+                        _ => true,
+                    });
+
+                // The spending caps of the stewards are enforced by the PGF VP
+                let is_pgf_verifier = verifiers.contains(&pgf::ADDRESS);
+
                 Ok(is_total_fundings_valid
                     && are_continous_fundings_unique
-                    && are_targets_unique)
+                    && are_targets_unique
+                    && are_streams_valid
+                    && are_grants_valid
+                    && is_pgf_verifier)
             }
-            _ => Ok(true), // default proposal
+            // The rewards parameters are validated by the MASP VP
+            ProposalType::MaspRewards(_) => {
+                Ok(verifiers
+                    .contains(&Address::Internal(InternalAddress::Masp)))
+            }
+            // The wasm code must be written along with the proposal
+            ProposalType::WasmCodeUpgrade(_) => {
+                let code_key = gov_storage::get_proposal_code_key(proposal_id);
+                Ok(self.ctx.has_key_post(&code_key)?)
+            }
+            _ => Ok(true), // text proposal
         }
     }
 
@@ -437,7 +876,7 @@ where
         let proposal_type: ProposalType =
             self.force_read(&proposal_type_key, ReadType::Post)?;
 
-        if !proposal_type.is_default() {
+        if !proposal_type.is_wasm_code_upgrade() {
             return Ok(false);
         }
 
@@ -520,6 +959,57 @@ where
             && is_valid_max_proposal_period)
     }
 
+    /// Validate an expedited key
+    pub fn is_valid_expedited(&self, proposal_id: u64) -> Result<bool> {
+        let start_epoch_key =
+            gov_storage::get_voting_start_epoch_key(proposal_id);
+        let end_epoch_key = gov_storage::get_voting_end_epoch_key(proposal_id);
+        let expedited_key = gov_storage::get_expedited_key(proposal_id);
+        let expedited_voting_period_key =
+            gov_storage::get_expedited_voting_period_key();
+
+        let has_pre_expedited = self.ctx.has_key_pre(&expedited_key)?;
+        let has_pre_start_epoch = self.ctx.has_key_pre(&start_epoch_key)?;
+        if has_pre_expedited || has_pre_start_epoch {
+            return Ok(false);
+        }
+
+        let start_epoch: Epoch =
+            self.force_read(&start_epoch_key, ReadType::Post)?;
+        let end_epoch: Epoch =
+            self.force_read(&end_epoch_key, ReadType::Post)?;
+        let expedited_end_epoch: Epoch =
+            self.force_read(&expedited_key, ReadType::Post)?;
+        let expedited_voting_period: u64 =
+            self.force_read(&expedited_voting_period_key, ReadType::Pre)?;
+
+        let committing_epoch_key = gov_storage::get_committing_proposals_key(
+            proposal_id,
+            expedited_end_epoch.into(),
+        );
+        let has_post_committing_epoch =
+            self.ctx.has_key_post(&committing_epoch_key)?;
+        if !has_post_committing_epoch {
+            tracing::info!("Expedited committing proposal key is missing");
+        }
+
+        let is_valid_expedited_end_epoch = expedited_end_epoch
+            == start_epoch + expedited_voting_period
+            && expedited_end_epoch < end_epoch;
+        if !is_valid_expedited_end_epoch {
+            tracing::info!(
+                "Expected the expedited voting period to be \
+                 {expedited_voting_period} epochs and to end before the end \
+                 epoch, but got expedited end = {}, start = {}, end = {}",
+                expedited_end_epoch,
+                start_epoch,
+                end_epoch
+            );
+        }
+
+        Ok(has_post_committing_epoch && is_valid_expedited_end_epoch)
+    }
+
     /// Validate a start_epoch key
     pub fn is_valid_start_epoch(&self, proposal_id: u64) -> Result<bool> {
         let start_epoch_key =
@@ -604,7 +1094,16 @@ where
             native_token_address,
             self.ctx.address,
         );
-        let min_funds_parameter_key = gov_storage::get_min_proposal_fund_key();
+        let proposal_type_key = gov_storage::get_proposal_type_key(proposal_id);
+        let proposal_type: ProposalType =
+            self.force_read(&proposal_type_key, ReadType::Post)?;
+        // Signaling proposals require a reduced deposit
+        let min_funds_parameter_key = match proposal_type {
+            ProposalType::Signaling => {
+                gov_storage::get_min_signaling_proposal_fund_key()
+            }
+            _ => gov_storage::get_min_proposal_fund_key(),
+        };
 
         let min_funds_parameter: token::Amount =
             self.force_read(&min_funds_parameter_key, ReadType::Pre)?;
@@ -633,12 +1132,18 @@ where
             self.ctx.address,
         );
         let min_funds_parameter_key = gov_storage::get_min_proposal_fund_key();
+        let min_signaling_funds_parameter_key =
+            gov_storage::get_min_signaling_proposal_fund_key();
 
         let pre_balance: Option<token::Amount> =
             self.ctx.pre().read(&balance_key)?;
 
-        let min_funds_parameter: token::Amount =
-            self.force_read(&min_funds_parameter_key, ReadType::Pre)?;
+        // The funds of each proposal are checked against the minimum of its
+        // type by the funds key, here only the lowest one is required
+        let min_funds_parameter: token::Amount = cmp::min(
+            self.force_read(&min_funds_parameter_key, ReadType::Pre)?,
+            self.force_read(&min_signaling_funds_parameter_key, ReadType::Pre)?,
+        );
         let post_balance: token::Amount =
             self.force_read(&balance_key, ReadType::Post)?;
 
@@ -785,16 +1290,34 @@ enum KeyType {
     #[allow(non_camel_case_types)]
     VOTE,
     #[allow(non_camel_case_types)]
+    VOTE_DELEGATION,
+    #[allow(non_camel_case_types)]
+    VOTE_DELEGATOR,
+    #[allow(non_camel_case_types)]
+    VOTER_VOTE,
+    #[allow(non_camel_case_types)]
+    CONSUMED_BALLOT,
+    #[allow(non_camel_case_types)]
     CONTENT,
     #[allow(non_camel_case_types)]
+    CONTENT_HASH,
+    #[allow(non_camel_case_types)]
+    CONTENT_URI,
+    #[allow(non_camel_case_types)]
+    VOTING_MODE,
+    #[allow(non_camel_case_types)]
     PROPOSAL_CODE,
     #[allow(non_camel_case_types)]
     TYPE,
     #[allow(non_camel_case_types)]
     PROPOSAL_COMMIT,
     #[allow(non_camel_case_types)]
+    VOTING_START_INDEX,
+    #[allow(non_camel_case_types)]
     GRACE_EPOCH,
     #[allow(non_camel_case_types)]
+    EXPEDITED,
+    #[allow(non_camel_case_types)]
     START_EPOCH,
     #[allow(non_camel_case_types)]
     END_EPOCH,
@@ -816,18 +1339,36 @@ impl KeyType {
     fn from_key(key: &Key, native_token: &Address) -> Self {
         if gov_storage::is_vote_key(key) {
             Self::VOTE
+        } else if gov_storage::is_vote_delegation_key(key).is_some() {
+            Self::VOTE_DELEGATION
+        } else if gov_storage::is_vote_delegator_key(key).is_some() {
+            Self::VOTE_DELEGATOR
+        } else if gov_storage::is_voter_vote_key(key).is_some() {
+            Self::VOTER_VOTE
+        } else if gov_storage::is_consumed_ballot_key(key).is_some() {
+            Self::CONSUMED_BALLOT
         } else if gov_storage::is_content_key(key) {
             KeyType::CONTENT
+        } else if gov_storage::is_content_hash_key(key) {
+            KeyType::CONTENT_HASH
+        } else if gov_storage::is_content_uri_key(key) {
+            KeyType::CONTENT_URI
+        } else if gov_storage::is_voting_mode_key(key) {
+            KeyType::VOTING_MODE
         } else if gov_storage::is_proposal_type_key(key) {
             Self::TYPE
         } else if gov_storage::is_proposal_code_key(key) {
             Self::PROPOSAL_CODE
         } else if gov_storage::is_grace_epoch_key(key) {
             KeyType::GRACE_EPOCH
+        } else if gov_storage::is_expedited_key(key) {
+            KeyType::EXPEDITED
         } else if gov_storage::is_start_epoch_key(key) {
             KeyType::START_EPOCH
         } else if gov_storage::is_commit_proposal_key(key) {
             KeyType::PROPOSAL_COMMIT
+        } else if gov_storage::is_voting_start_proposal_key(key) {
+            KeyType::VOTING_START_INDEX
         } else if gov_storage::is_end_epoch_key(key) {
             KeyType::END_EPOCH
         } else if gov_storage::is_balance_key(key) {
@@ -849,3 +1390,290 @@ impl KeyType {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use borsh_ext::BorshSerializeExt;
+    use namada_gas::TxGasMeter;
+    use namada_governance::storage::proposal::{
+        SignedVoteProposalData, VoteProposalData,
+    };
+    use namada_governance::storage::{vote_proposal, vote_proposal_batch};
+    use namada_state::testing::TestWlStorage;
+    use namada_state::StorageWrite;
+    use namada_tx::data::TxType;
+    use namada_tx::Data;
+
+    use super::*;
+    use crate::core::types::address::nam;
+    use crate::core::types::address::testing::{
+        established_address_1, established_address_2, established_address_3,
+    };
+    use crate::core::types::key::testing::common_sk_from_simple_seed;
+    use crate::ledger::gas::VpGasMeter;
+    use crate::ledger::native_vp::ibc::get_dummy_genesis_validator;
+    use crate::ledger::pos::namada_proof_of_stake::bond_tokens;
+    use crate::ledger::pos::test_utils::test_init_genesis;
+    use crate::ledger::pos::OwnedPosParams;
+    use crate::token::{credit_tokens, Amount};
+    use crate::types::storage::TxIndex;
+    use crate::vm::wasm::compilation_cache::common::testing::cache as wasm_cache;
+
+    const PROPOSAL_ID: u64 = 0;
+
+    /// Init a storage with two validators, a delegator bonded to both of them
+    /// and a proposal whose voting window spans the epochs 4 to 8. The
+    /// delegator has voted yay with both delegations if `voted` is set.
+    fn init_storage(voted: bool) -> (TestWlStorage, Address, [Address; 2]) {
+        let mut s = TestWlStorage::default();
+        let validator_1 = get_dummy_genesis_validator();
+        let mut validator_2 = get_dummy_genesis_validator();
+        validator_2.address = established_address_2();
+        validator_2.consensus_key = common_sk_from_simple_seed(2).to_public();
+        validator_2.protocol_key = common_sk_from_simple_seed(3).to_public();
+        let validators = [validator_1.address.clone(), established_address_2()];
+        test_init_genesis(
+            &mut s,
+            OwnedPosParams::default(),
+            [validator_1, validator_2].into_iter(),
+            Epoch(0),
+        )
+        .unwrap();
+
+        let delegator = established_address_3();
+        credit_tokens(&mut s, &nam(), &delegator, Amount::native_whole(2))
+            .unwrap();
+        for validator in &validators {
+            let amount = Amount::native_whole(1);
+            bond_tokens(
+                &mut s,
+                Some(&delegator),
+                validator,
+                amount,
+                Epoch(0),
+                None,
+            )
+            .unwrap();
+        }
+
+        s.write(&gov_storage::get_counter_key(), PROPOSAL_ID + 1)
+            .unwrap();
+        s.write(
+            &gov_storage::get_voting_start_epoch_key(PROPOSAL_ID),
+            Epoch(4),
+        )
+        .unwrap();
+        s.write(
+            &gov_storage::get_voting_end_epoch_key(PROPOSAL_ID),
+            Epoch(8),
+        )
+        .unwrap();
+        if voted {
+            let yay = ProposalVote::Yay;
+            vote(&mut s, &mut BTreeSet::new(), &delegator, &validators, yay);
+        }
+        s.commit_tx();
+        s.storage.block.epoch = Epoch(5);
+        (s, delegator, validators)
+    }
+
+    /// Write the vote of the voter for the given delegations and index it
+    fn vote(
+        s: &mut TestWlStorage,
+        keys: &mut BTreeSet<Key>,
+        voter: &Address,
+        validators: &[Address],
+        vote: ProposalVote,
+    ) {
+        for validator in validators {
+            let key = gov_storage::get_vote_proposal_key(
+                PROPOSAL_ID,
+                voter.clone(),
+                validator.clone(),
+            );
+            s.write(&key, vote.clone()).unwrap();
+            keys.insert(key);
+        }
+        let voted: BTreeSet<Address> = validators.iter().cloned().collect();
+        s.write(&gov_storage::get_voter_vote_key(voter, PROPOSAL_ID), voted)
+            .unwrap();
+    }
+
+    /// Delete the votes of the voter for the given delegations and keep the
+    /// given ones indexed
+    fn withdraw(
+        s: &mut TestWlStorage,
+        keys: &mut BTreeSet<Key>,
+        voter: &Address,
+        withdrawn: &[Address],
+        kept: &[Address],
+    ) {
+        for validator in withdrawn {
+            let key = gov_storage::get_vote_proposal_key(
+                PROPOSAL_ID,
+                voter.clone(),
+                validator.clone(),
+            );
+            s.delete(&key).unwrap();
+            keys.insert(key);
+        }
+        let index_key = gov_storage::get_voter_vote_key(voter, PROPOSAL_ID);
+        if kept.is_empty() {
+            s.delete(&index_key).unwrap();
+        } else {
+            let kept: BTreeSet<Address> = kept.iter().cloned().collect();
+            s.write(&index_key, kept).unwrap();
+        }
+    }
+
+    /// Run the checks of the governance VP on the changed vote keys
+    fn valid_votes(
+        s: &TestWlStorage,
+        keys: &BTreeSet<Key>,
+        tx: &Tx,
+        voter: &Address,
+    ) -> bool {
+        let tx_index = TxIndex::default();
+        let gas_meter = VpGasMeter::new_from_tx_meter(
+            &TxGasMeter::new_from_sub_limit(u64::MAX.into()),
+        );
+        let (vp_wasm_cache, _vp_cache_dir) = wasm_cache();
+        let verifiers = BTreeSet::from([voter.clone()]);
+        let ctx = Ctx::new(
+            &ADDRESS,
+            &s.storage,
+            &s.write_log,
+            tx,
+            &tx_index,
+            gas_meter,
+            keys,
+            &verifiers,
+            vp_wasm_cache,
+        );
+        let vp = GovernanceVp { ctx };
+        keys.iter().all(|key| {
+            matches!(
+                vp.is_valid_vote_key(PROPOSAL_ID, key, tx, &verifiers),
+                Ok(true)
+            )
+        })
+    }
+
+    /// Test that a vote can be overwritten while the voting window is open
+    #[test]
+    fn test_vote_overwrite() {
+        let (mut s, delegator, validators) = init_storage(true);
+        let tx = Tx::from_type(TxType::Raw);
+        let mut keys = BTreeSet::new();
+        let nay = ProposalVote::Nay;
+        vote(&mut s, &mut keys, &delegator, &validators, nay);
+        assert!(valid_votes(&s, &keys, &tx, &delegator));
+    }
+
+    /// Test that a vote can be withdrawn for all the delegations at once,
+    /// but not for only some of them
+    #[test]
+    fn test_vote_withdrawal() {
+        let (mut s, delegator, validators) = init_storage(true);
+        let tx = Tx::from_type(TxType::Raw);
+        let mut keys = BTreeSet::new();
+        withdraw(&mut s, &mut keys, &delegator, &validators, &[]);
+        assert!(valid_votes(&s, &keys, &tx, &delegator));
+
+        let (mut s, delegator, validators) = init_storage(true);
+        let mut keys = BTreeSet::new();
+        let (withdrawn, kept) = validators.split_at(1);
+        withdraw(&mut s, &mut keys, &delegator, withdrawn, kept);
+        assert!(!valid_votes(&s, &keys, &tx, &delegator));
+    }
+
+    /// Test that a vote that was never cast can't be withdrawn
+    #[test]
+    fn test_withdraw_missing_vote() {
+        let (mut s, delegator, validators) = init_storage(false);
+        let tx = Tx::from_type(TxType::Raw);
+        let mut keys = BTreeSet::new();
+        withdraw(&mut s, &mut keys, &delegator, &validators, &[]);
+        assert!(!valid_votes(&s, &keys, &tx, &delegator));
+    }
+
+    /// Test that a vote can't be withdrawn after the end of the voting
+    /// window
+    #[test]
+    fn test_withdrawal_after_end_epoch() {
+        let (mut s, delegator, validators) = init_storage(true);
+        s.storage.block.epoch = Epoch(9);
+        let tx = Tx::from_type(TxType::Raw);
+        let mut keys = BTreeSet::new();
+        withdraw(&mut s, &mut keys, &delegator, &validators, &[]);
+        assert!(!valid_votes(&s, &keys, &tx, &delegator));
+    }
+
+    /// Test that the signed ballots of a batch can't delete a vote
+    #[test]
+    fn test_batch_ballot_delete() {
+        let (mut s, delegator, validators) = init_storage(true);
+        let mut tx = Tx::from_type(TxType::Raw);
+        let batch = VoteProposalBatchData { ballots: vec![] };
+        tx.set_data(Data::new(batch.serialize_to_vec()));
+        let mut keys = BTreeSet::new();
+        withdraw(&mut s, &mut keys, &delegator, &validators, &[]);
+        assert!(!valid_votes(&s, &keys, &tx, &delegator));
+    }
+
+    /// Test that a signed ballot can't be submitted again after the vote it
+    /// cast has been withdrawn
+    #[test]
+    fn test_batch_ballot_replay() {
+        let (mut s, delegator, validators) = init_storage(false);
+        let sk = common_sk_from_simple_seed(4);
+        account::init_account_storage(&mut s, &delegator, &[sk.to_public()], 1)
+            .unwrap();
+        s.commit_tx();
+
+        let public_keys_map =
+            account::public_keys_index_map(&s, &delegator).unwrap();
+        let chain_id = s.storage.chain_id.clone();
+        let ballot = SignedVoteProposalData {
+            data: VoteProposalData {
+                id: PROPOSAL_ID,
+                vote: ProposalVote::Yay,
+                voter: delegator.clone(),
+                delegations: validators.to_vec(),
+            },
+            signatures: BTreeSet::new(),
+        }
+        .sign(vec![sk], &public_keys_map, &chain_id);
+        let batch = VoteProposalBatchData {
+            ballots: vec![ballot],
+        };
+        let mut tx = Tx::from_type(TxType::Raw);
+        tx.set_data(Data::new(batch.serialize_to_vec()));
+        let vote_keys: BTreeSet<Key> = validators
+            .iter()
+            .map(|validator| {
+                gov_storage::get_vote_proposal_key(
+                    PROPOSAL_ID,
+                    delegator.clone(),
+                    validator.clone(),
+                )
+            })
+            .collect();
+
+        // The first submission is accepted
+        vote_proposal_batch(&mut s, batch.clone()).unwrap();
+        assert!(valid_votes(&s, &vote_keys, &tx, &delegator));
+        s.commit_tx();
+
+        // The voter withdraws the vote
+        let mut keys = BTreeSet::new();
+        withdraw(&mut s, &mut keys, &delegator, &validators, &[]);
+        s.commit_tx();
+
+        // The same ballot can't be submitted again
+        assert!(vote_proposal_batch(&mut s, batch.clone()).is_err());
+        s.write_log.drop_tx();
+        vote_proposal(&mut s, batch.ballots[0].data.clone()).unwrap();
+        assert!(!valid_votes(&s, &vote_keys, &tx, &delegator));
+    }
+}
@@ -1,12 +1,18 @@
 //! Governance utility functions
 
 use std::collections::HashMap;
+use std::fmt::Display;
 
-use namada_governance::utils::TallyResult;
+use namada_governance::parameters::DepositAction;
+use namada_governance::storage::vote::ProposalVote;
+use namada_governance::utils::{ProposalResult, TallyResult};
 use namada_sdk::events::{Event, EventLevel};
 use thiserror::Error;
 
 use crate::ledger::events::EventType;
+use crate::token;
+use crate::types::address::Address;
+use crate::types::storage::Epoch;
 
 pub(super) enum ReadType {
     Pre,
@@ -83,6 +89,17 @@ impl ProposalEvent {
         )
     }
 
+    /// Create a new proposal event for vetoed proposal
+    pub fn vetoed_proposal_event(proposal_id: u64) -> Self {
+        ProposalEvent::new(
+            EventType::Proposal.to_string(),
+            TallyResult::Vetoed,
+            proposal_id,
+            false,
+            false,
+        )
+    }
+
     /// Create a new proposal event for default proposal
     pub fn default_proposal_event(
         proposal_id: u64,
@@ -120,6 +137,87 @@ impl ProposalEvent {
         )
     }
 
+    /// Create a new proposal event for masp rewards proposal
+    pub fn masp_rewards_proposal_event(proposal_id: u64, result: bool) -> Self {
+        ProposalEvent::new(
+            EventType::Proposal.to_string(),
+            TallyResult::Passed,
+            proposal_id,
+            false,
+            result,
+        )
+    }
+
+    /// Create a new proposal event for parameter change proposal
+    pub fn parameter_change_proposal_event(
+        proposal_id: u64,
+        result: bool,
+    ) -> Self {
+        ProposalEvent::new(
+            EventType::Proposal.to_string(),
+            TallyResult::Passed,
+            proposal_id,
+            false,
+            result,
+        )
+    }
+
+    /// Create a new proposal event for emergency halt proposal
+    pub fn emergency_halt_proposal_event(
+        proposal_id: u64,
+        result: bool,
+    ) -> Self {
+        ProposalEvent::new(
+            EventType::Proposal.to_string(),
+            TallyResult::Passed,
+            proposal_id,
+            false,
+            result,
+        )
+    }
+
+    /// Create a new proposal event for wasm allowlist proposal
+    pub fn wasm_allowlist_proposal_event(
+        proposal_id: u64,
+        result: bool,
+    ) -> Self {
+        ProposalEvent::new(
+            EventType::Proposal.to_string(),
+            TallyResult::Passed,
+            proposal_id,
+            false,
+            result,
+        )
+    }
+
+    /// Create a new proposal event for signaling proposal
+    pub fn signaling_proposal_event(proposal_id: u64) -> Self {
+        ProposalEvent::new(
+            EventType::Proposal.to_string(),
+            TallyResult::Passed,
+            proposal_id,
+            false,
+            true,
+        )
+    }
+
+    /// Create a new proposal event for the handling of a proposal deposit
+    pub fn deposit_event(
+        proposal_id: u64,
+        action: DepositAction,
+        amount: token::Amount,
+    ) -> Self {
+        let attributes = HashMap::from([
+            ("proposal_id".to_string(), proposal_id.to_string()),
+            ("deposit_action".to_string(), action.to_string()),
+            ("deposit_amount".to_string(), amount.to_string_native()),
+        ]);
+        Self {
+            event_type: EventType::Proposal.to_string(),
+            attributes,
+        }
+    }
+
     /// Create a new proposal event for eth proposal
     pub fn eth_proposal_event(proposal_id: u64, result: bool) -> Self {
         ProposalEvent::new(
@@ -131,3 +229,148 @@ impl ProposalEvent {
         )
     }
 }
+
+/// The stages of the lifecycle of a proposal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProposalStage {
+    /// The proposal has been submitted
+    Created,
+    /// The voting period of the proposal has started
+    VotingStarted,
+    /// A vote has been cast on the proposal
+    Voted,
+    /// The votes of the proposal have been tallied
+    Tallied,
+    /// The proposal has been executed
+    Executed,
+}
+
+impl Display for ProposalStage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProposalStage::Created => write!(f, "created"),
+            ProposalStage::VotingStarted => write!(f, "voting_started"),
+            ProposalStage::Voted => write!(f, "voted"),
+            ProposalStage::Tallied => write!(f, "tallied"),
+            ProposalStage::Executed => write!(f, "executed"),
+        }
+    }
+}
+
+/// Proposal lifecycle event definition, indexed by proposal id so that the
+/// whole lifecycle of a proposal can be followed from the events
+pub struct ProposalLifecycleEvent {
+    /// Proposal lifecycle event attributes
+    pub attributes: HashMap<String, String>,
+}
+
+impl From<ProposalLifecycleEvent> for Event {
+    fn from(lifecycle_event: ProposalLifecycleEvent) -> Self {
+        Self {
+            event_type: EventType::ProposalLifecycle,
+            level: EventLevel::Block,
+            attributes: lifecycle_event.attributes,
+        }
+    }
+}
+
+impl ProposalLifecycleEvent {
+    /// Create a proposal lifecycle event
+    pub fn new(
+        stage: ProposalStage,
+        proposal_id: u64,
+        attributes: impl IntoIterator<Item = (&'static str, String)>,
+    ) -> Self {
+        let attributes = [
+            ("stage", stage.to_string()),
+            ("proposal_id", proposal_id.to_string()),
+        ]
+        .into_iter()
+        .chain(attributes)
+        .map(|(key, value)| (key.to_string(), value))
+        .collect();
+        Self { attributes }
+    }
+
+    /// Create a new lifecycle event for a submitted proposal
+    pub fn created(
+        proposal_id: u64,
+        author: &Address,
+        kind: impl Display,
+        voting_start_epoch: Epoch,
+        voting_end_epoch: Epoch,
+    ) -> Self {
+        Self::new(
+            ProposalStage::Created,
+            proposal_id,
+            [
+                ("author", author.to_string()),
+                ("proposal_kind", kind.to_string()),
+                ("voting_start_epoch", voting_start_epoch.to_string()),
+                ("voting_end_epoch", voting_end_epoch.to_string()),
+            ],
+        )
+    }
+
+    /// Create a new lifecycle event for the start of the voting period of a
+    /// proposal
+    pub fn voting_started(proposal_id: u64, voting_end_epoch: Epoch) -> Self {
+        Self::new(
+            ProposalStage::VotingStarted,
+            proposal_id,
+            [("voting_end_epoch", voting_end_epoch.to_string())],
+        )
+    }
+
+    /// Create a new lifecycle event for a vote cast on a proposal
+    pub fn voted(
+        proposal_id: u64,
+        voter: &Address,
+        validator: &Address,
+        vote: &ProposalVote,
+    ) -> Self {
+        Self::new(
+            ProposalStage::Voted,
+            proposal_id,
+            [
+                ("voter", voter.to_string()),
+                ("validator", validator.to_string()),
+                ("vote", vote.to_string()),
+            ],
+        )
+    }
+
+    /// Create a new lifecycle event for the tally of a proposal
+    pub fn tallied(proposal_id: u64, result: &ProposalResult) -> Self {
+        Self::new(
+            ProposalStage::Tallied,
+            proposal_id,
+            [
+                ("tally_result", result.result.to_string()),
+                (
+                    "total_voting_power",
+                    result.total_voting_power.to_string_native(),
+                ),
+                ("total_yay_power", result.total_yay_power.to_string_native()),
+                ("total_nay_power", result.total_nay_power.to_string_native()),
+                (
+                    "total_abstain_power",
+                    result.total_abstain_power.to_string_native(),
+                ),
+                (
+                    "total_veto_power",
+                    result.total_veto_power.to_string_native(),
+                ),
+            ],
+        )
+    }
+
+    /// Create a new lifecycle event for the execution of a passed proposal
+    pub fn executed(proposal_id: u64, execution_status: bool) -> Self {
+        Self::new(
+            ProposalStage::Executed,
+            proposal_id,
+            [("execution_status", execution_status.to_string())],
+        )
+    }
+}
@@ -40,10 +40,12 @@ mod dry_run_tx {
         H: 'static + StorageHasher + Sync,
         CA: 'static + WasmCacheAccess + Sync,
     {
+        use std::collections::BTreeSet;
+
         use borsh_ext::BorshSerializeExt;
         use namada_gas::{Gas, GasMetering, TxGasMeter};
         use namada_state::TempWlStorage;
-        use namada_tx::data::{DecryptedTx, TxType};
+        use namada_tx::data::{DecryptedTx, DryRunResult, TxType};
         use namada_tx::Tx;
 
         use crate::ledger::protocol::ShellParams;
@@ -54,13 +56,15 @@ mod dry_run_tx {
 
         let mut temp_wl_storage = TempWlStorage::new(&ctx.wl_storage.storage);
         let mut cumulated_gas = Gas::default();
+        let mut wrapper_gas_used = None;
+        let mut wrapper_changed_keys = BTreeSet::new();
 
         // Wrapper dry run to allow estimating the gas cost of a transaction
         let mut tx_gas_meter = match tx.header().tx_type {
             TxType::Wrapper(wrapper) => {
                 let mut tx_gas_meter =
                     TxGasMeter::new(wrapper.gas_limit.to_owned());
-                protocol::apply_wrapper_tx(
+                wrapper_changed_keys = protocol::apply_wrapper_tx(
                     tx.clone(),
                     &wrapper,
                     None,
@@ -77,6 +81,7 @@ mod dry_run_tx {
 
                 temp_wl_storage.write_log.commit_tx();
                 cumulated_gas = tx_gas_meter.get_tx_consumed_gas();
+                wrapper_gas_used = Some(cumulated_gas);
 
                 tx.update_header(TxType::Decrypted(DecryptedTx::Decrypted));
                 TxGasMeter::new_from_sub_limit(tx_gas_meter.get_available_gas())
@@ -102,27 +107,29 @@ mod dry_run_tx {
             }
         };
 
-        let mut data = protocol::apply_wasm_tx(
-            tx,
+        let mut tx_result = protocol::simulate_tx(
+            &tx,
             &TxIndex(0),
-            ShellParams::new(
-                &mut tx_gas_meter,
-                &mut temp_wl_storage,
-                &mut ctx.vp_wasm_cache,
-                &mut ctx.tx_wasm_cache,
-            ),
+            temp_wl_storage.storage,
+            &temp_wl_storage.write_log,
+            &mut tx_gas_meter,
+            &mut ctx.vp_wasm_cache,
+            &mut ctx.tx_wasm_cache,
         )
         .into_storage_result()?;
-        cumulated_gas = cumulated_gas
-            .checked_add(tx_gas_meter.get_tx_consumed_gas())
-            .ok_or(namada_state::StorageError::SimpleMessage(
-                "Overflow in gas",
-            ))?;
+        let inner_gas_used = tx_gas_meter.get_tx_consumed_gas();
+        cumulated_gas = cumulated_gas.checked_add(inner_gas_used).ok_or(
+            namada_state::StorageError::SimpleMessage("Overflow in gas"),
+        )?;
         // Account gas for both inner and wrapper (if available)
-        data.gas_used = cumulated_gas;
-        // NOTE: the keys changed by the wrapper transaction (if any) are not
-        // returned from this function
-        let data = data.serialize_to_vec();
+        tx_result.gas_used = cumulated_gas;
+        let data = DryRunResult {
+            tx_result,
+            wrapper_gas_used,
+            inner_gas_used,
+            wrapper_changed_keys,
+        }
+        .serialize_to_vec();
         Ok(EncodedResponseQuery {
             data,
             proof: None,
@@ -137,9 +144,10 @@ mod test {
     use borsh_ext::BorshSerializeExt;
     use namada_core::types::address;
     use namada_core::types::hash::Hash;
-    use namada_core::types::storage::{BlockHeight, Key};
+    use namada_core::types::storage::{BlockHeight, Key, PrefixPageRequest};
     use namada_sdk::queries::{
-        EncodedResponseQuery, RequestCtx, RequestQuery, Router, RPC,
+        BatchQuery, BatchResult, EncodedResponseQuery, RequestCtx,
+        RequestQuery, Router, MAX_QUERIES_PER_BATCH, RPC,
     };
     use namada_sdk::tendermint_rpc::{self, Error as RpcError, Response};
     use namada_state::testing::TestWlStorage;
@@ -303,6 +311,10 @@ mod test {
             .await
             .unwrap();
         assert!(result.data.is_accepted());
+        // Without a wrapper, all the gas is used by the inner tx
+        assert!(result.data.wrapper_gas_used.is_none());
+        assert!(result.data.wrapper_changed_keys.is_empty());
+        assert_eq!(result.data.inner_gas_used, result.data.gas_used());
 
         // Request storage value for a balance key ...
         let token_addr = address::testing::established_address_1();
@@ -369,4 +381,174 @@ mod test {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_storage_prefix_page() -> namada_state::StorageResult<()> {
+        let mut client = TestClient::new(RPC);
+        let token_addr = address::testing::established_address_1();
+        let owners = [
+            address::testing::established_address_2(),
+            address::testing::established_address_3(),
+            address::testing::established_address_4(),
+        ];
+        for owner in &owners {
+            let balance_key =
+                token::storage_key::balance_key(&token_addr, owner);
+            let balance = token::Amount::native_whole(1);
+            StorageWrite::write(&mut client.wl_storage, &balance_key, balance)?;
+        }
+        client.wl_storage.commit_tx();
+        client.wl_storage.commit_block().unwrap();
+        let balance_prefix = token::storage_key::balance_prefix(&token_addr);
+
+        // Page through the balances two at a time
+        let mut request = PrefixPageRequest {
+            limit: Some(2),
+            ..Default::default()
+        };
+        let first_page = RPC
+            .shell()
+            .storage_prefix_page(
+                &client,
+                Some(request.serialize_to_vec()),
+                None,
+                false,
+                &balance_prefix,
+            )
+            .await
+            .unwrap()
+            .data;
+        assert_eq!(first_page.values.len(), 2);
+        let cursor = first_page.next_cursor.clone().unwrap();
+        assert_eq!(cursor, first_page.values[1].key);
+        request.cursor = Some(cursor);
+        let second_page = RPC
+            .shell()
+            .storage_prefix_page(
+                &client,
+                Some(request.serialize_to_vec()),
+                None,
+                false,
+                &balance_prefix,
+            )
+            .await
+            .unwrap()
+            .data;
+        assert_eq!(second_page.values.len(), 1);
+        assert!(second_page.next_cursor.is_none());
+        let read_keys: Vec<Key> = first_page
+            .values
+            .into_iter()
+            .chain(second_page.values)
+            .map(|value| value.key)
+            .collect();
+        let mut expected_keys: Vec<Key> = owners
+            .iter()
+            .map(|owner| token::storage_key::balance_key(&token_addr, owner))
+            .collect();
+        expected_keys.sort_by_key(|key| key.to_string());
+        assert_eq!(read_keys, expected_keys);
+
+        // Filter the keys with a regex
+        let owner = &owners[1];
+        let request = PrefixPageRequest {
+            key_regex: Some(format!("{owner}$")),
+            ..Default::default()
+        };
+        let page = RPC
+            .shell()
+            .storage_prefix_page(
+                &client,
+                Some(request.serialize_to_vec()),
+                None,
+                false,
+                &balance_prefix,
+            )
+            .await
+            .unwrap()
+            .data;
+        assert_eq!(page.values.len(), 1);
+        assert_eq!(
+            page.values[0].key,
+            token::storage_key::balance_key(&token_addr, owner)
+        );
+        assert!(page.next_cursor.is_none());
+
+        // An invalid regex is rejected
+        let request = PrefixPageRequest {
+            key_regex: Some("(".to_string()),
+            ..Default::default()
+        };
+        let result = RPC
+            .shell()
+            .storage_prefix_page(
+                &client,
+                Some(request.serialize_to_vec()),
+                None,
+                false,
+                &balance_prefix,
+            )
+            .await;
+        assert!(result.is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_batch_query() -> namada_state::StorageResult<()> {
+        let mut client = TestClient::new(RPC);
+        let token_addr = address::testing::established_address_1();
+        let owner = address::testing::established_address_2();
+        let balance_key = token::storage_key::balance_key(&token_addr, &owner);
+        let balance = token::Amount::native_whole(1000);
+        StorageWrite::write(&mut client.wl_storage, &balance_key, balance)?;
+        client.wl_storage.commit_tx();
+        client.wl_storage.commit_block().unwrap();
+
+        let queries = vec![
+            BatchQuery::Balance {
+                token: token_addr.clone(),
+                owner,
+            },
+            BatchQuery::Balance {
+                token: token_addr,
+                owner: address::testing::established_address_3(),
+            },
+            BatchQuery::Proposal { id: 0 },
+        ];
+        let response = RPC
+            .shell()
+            .batch(&client, Some(queries.serialize_to_vec()), None, false)
+            .await
+            .unwrap()
+            .data;
+        assert_eq!(
+            response.height,
+            client.wl_storage.storage.get_last_block_height()
+        );
+        assert_eq!(response.results.len(), 3);
+        assert!(matches!(
+            response.results[0],
+            Ok(BatchResult::Balance(amount)) if amount == balance
+        ));
+        assert!(matches!(
+            response.results[1],
+            Ok(BatchResult::Balance(amount)) if amount.is_zero()
+        ));
+        assert!(matches!(
+            response.results[2],
+            Ok(BatchResult::Proposal(None))
+        ));
+
+        // The batches are limited in size
+        let queries =
+            vec![BatchQuery::Proposal { id: 0 }; MAX_QUERIES_PER_BATCH + 1];
+        let result = RPC
+            .shell()
+            .batch(&client, Some(queries.serialize_to_vec()), None, false)
+            .await;
+        assert!(result.is_err());
+
+        Ok(())
+    }
 }
@@ -10,7 +10,7 @@ use namada_tx::Tx;
 use namada_vp_env::VpEnv;
 
 use crate::ledger::native_vp::{Ctx, NativeVp};
-use crate::token::storage_key::is_any_token_balance_key;
+use crate::token::storage_key::is_any_balance_key;
 use crate::token::Amount;
 use crate::vm::WasmCacheAccess;
 
@@ -60,15 +60,14 @@ where
             return Ok(false);
         }
 
-        let nut_owners =
-            keys_changed.iter().filter_map(
-                |key| match is_any_token_balance_key(key) {
-                    Some(
-                        [Address::Internal(InternalAddress::Nut(_)), owner],
-                    ) => Some((key, owner)),
-                    _ => None,
-                },
-            );
+        let nut_owners = keys_changed.iter().filter_map(|key| {
+            match is_any_balance_key(key) {
+                Some((Address::Internal(InternalAddress::Nut(_)), owner)) => {
+                    Some((key, owner))
+                }
+                _ => None,
+            }
+        });
 
         for (changed_key, token_owner) in nut_owners {
             let pre: Amount = self
@@ -8,23 +8,40 @@ use std::rc::Rc;
 use std::time::Duration;
 
 use context::{PseudoExecutionContext, VpValidationContext};
-use namada_core::types::address::Address;
+use namada_core::types::address::{Address, InternalAddress};
+use namada_core::types::ibc::IbcDenomTrace;
 use namada_core::types::storage::Key;
 use namada_gas::{IBC_ACTION_EXECUTE_GAS, IBC_ACTION_VALIDATE_GAS};
+use namada_governance::is_subsystem_halted;
+use namada_governance::storage::proposal::HaltedSubsystem;
+use namada_ibc::context::client::AnyClientState;
 use namada_ibc::{
-    Error as ActionError, IbcActions, TransferModule, ValidationParams,
+    decode_message, Error as ActionError, IbcActions, IbcMessage, IcqModule,
+    TransferModule, ValidationParams,
 };
 use namada_proof_of_stake::storage::read_pos_params;
 use namada_state::write_log::StorageModification;
 use namada_state::StorageHasher;
 use namada_tx::Tx;
 use namada_vp_env::VpEnv;
+use prost::Message;
 use thiserror::Error;
 
-use crate::ibc::core::host::types::identifiers::ChainId as IbcChainId;
-use crate::ledger::ibc::storage::{calc_hash, is_ibc_denom_key, is_ibc_key};
+use crate::ibc::core::channel::types::msgs::PacketMsg;
+use crate::ibc::core::client::types::error::ClientError;
+use crate::ibc::core::client::types::msgs::ClientMsg;
+use crate::ibc::core::handler::types::msgs::MsgEnvelope;
+use crate::ibc::core::host::types::identifiers::{
+    ChainId as IbcChainId, PortId,
+};
+use crate::ibc::primitives::proto::Any;
+use crate::ledger::ibc::storage::{
+    calc_hash, ibc_token, is_channel_upgrade_key, is_client_state_key,
+    is_ibc_denom_key, is_ibc_key, is_icq_key,
+};
 use crate::ledger::native_vp::{self, Ctx, NativeVp};
 use crate::ledger::parameters::read_epoch_duration_parameter;
+use crate::token::storage_key::is_any_metadata_key;
 use crate::vm::WasmCacheAccess;
 
 #[allow(missing_docs)]
@@ -75,6 +92,47 @@ where
         let signed = tx_data;
         let tx_data = signed.data().ok_or(Error::NoTxData)?;
 
+        // A governance proposal can update the ICQ allowlist and initialize
+        // or cancel a channel upgrade. Any other change has to be validated.
+        if namada_governance::storage::is_proposal_accepted(
+            &self.ctx.pre(),
+            &tx_data,
+        )
+        .map_err(Error::NativeVpError)?
+            && keys_changed
+                .iter()
+                .filter(|k| is_ibc_key(k))
+                .all(|k| is_icq_key(k) || is_channel_upgrade_key(k))
+        {
+            return Ok(true);
+        }
+
+        // The ICQ allowlist can be updated only by a governance proposal
+        if keys_changed
+            .iter()
+            .filter(|k| is_ibc_key(k))
+            .all(is_icq_key)
+        {
+            return Ok(false);
+        }
+
+        if self.is_halted_transfer(&tx_data)? {
+            tracing::debug!(
+                "IBC transfers are halted by a governance proposal"
+            );
+            return Ok(false);
+        }
+
+        if let Ok(IbcMessage::ChannelUpgrade(msg)) = decode_message(&tx_data) {
+            if msg.requires_governance() {
+                return Err(ActionError::ChannelUpgrade(format!(
+                    "The channel upgrade message should be executed by a \
+                     governance proposal: {msg:?}"
+                ))
+                .into());
+            }
+        }
+
         // Pseudo execution and compare them
         self.validate_state(&tx_data, keys_changed)?;
 
@@ -84,6 +142,9 @@ where
         // Validate the denom store if a denom key has been changed
         self.validate_denom(keys_changed)?;
 
+        // Validate the frozen clients
+        self.validate_client_freeze(&tx_data, keys_changed)?;
+
         Ok(true)
     }
 }
@@ -94,6 +155,25 @@ where
     H: 'static + StorageHasher,
     CA: 'static + WasmCacheAccess,
 {
+    /// Check if the message moves tokens while IBC transfers are halted.
+    /// Acknowledgements and timeouts are still accepted to refund the
+    /// pending transfers.
+    fn is_halted_transfer(&self, tx_data: &[u8]) -> VpResult<bool> {
+        let is_transfer = match decode_message(tx_data) {
+            Ok(IbcMessage::Transfer(_))
+            | Ok(IbcMessage::ShieldedTransfer(_)) => true,
+            Ok(IbcMessage::Envelope(MsgEnvelope::Packet(PacketMsg::Recv(
+                msg,
+            )))) => msg.packet.port_id_on_b == PortId::transfer(),
+            _ => false,
+        };
+        if !is_transfer {
+            return Ok(false);
+        }
+        is_subsystem_halted(&self.ctx.pre(), &HaltedSubsystem::IbcTransfers)
+            .map_err(Error::NativeVpError)
+    }
+
     fn validate_state(
         &self,
         tx_data: &[u8],
@@ -105,6 +185,12 @@ where
         let mut actions = IbcActions::new(ctx.clone());
         let module = TransferModule::new(ctx.clone());
         actions.add_transfer_module(module.module_id(), module);
+        let icq_module = IcqModule::new(ctx.clone());
+        actions.add_icq_module(
+            icq_module.module_id(),
+            icq_module.port_id(),
+            icq_module,
+        );
         // Charge gas for the expensive execution
         self.ctx
             .charge_gas(IBC_ACTION_EXECUTE_GAS)
@@ -149,8 +235,14 @@ where
         let mut actions = IbcActions::new(ctx.clone());
         actions.set_validation_params(self.validation_params()?);
 
-        let module = TransferModule::new(ctx);
+        let module = TransferModule::new(ctx.clone());
         actions.add_transfer_module(module.module_id(), module);
+        let icq_module = IcqModule::new(ctx);
+        actions.add_icq_module(
+            icq_module.module_id(),
+            icq_module.port_id(),
+            icq_module,
+        );
         // Charge gas for the expensive validation
         self.ctx
             .charge_gas(IBC_ACTION_VALIDATE_GAS)
@@ -206,11 +298,100 @@ where
                     }
                 }
             }
+            // The metadata of an IBC token should be derived from the denom
+            // trace of the token
+            if let Some(
+                token @ Address::Internal(InternalAddress::IbcToken(_)),
+            ) = is_any_metadata_key(key)
+            {
+                match self.ctx.read_post::<IbcDenomTrace>(key).map_err(|e| {
+                    ActionError::Denom(format!(
+                        "Getting the token metadata failed: Key {}, Error {}",
+                        key, e
+                    ))
+                })? {
+                    Some(trace) => {
+                        if &ibc_token(trace.ibc_denom()) != token {
+                            return Err(ActionError::Denom(format!(
+                                "The token metadata is invalid: Key {}, \
+                                 Metadata {:?}",
+                                key, trace
+                            ))
+                            .into());
+                        }
+                    }
+                    None => {
+                        return Err(ActionError::Denom(format!(
+                            "The token metadata can't be deleted: Key {}",
+                            key
+                        ))
+                        .into());
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// A frozen client can't be modified and a submitted misbehaviour should
+    /// freeze the client
+    fn validate_client_freeze(
+        &self,
+        tx_data: &[u8],
+        keys_changed: &BTreeSet<Key>,
+    ) -> VpResult<()> {
+        let mut frozen_clients = HashSet::new();
+        for key in keys_changed {
+            let client_id = match is_client_state_key(key) {
+                Some(client_id) => client_id,
+                None => continue,
+            };
+            let pre =
+                self.ctx.read_bytes_pre(key).map_err(Error::NativeVpError)?;
+            let post = self
+                .ctx
+                .read_bytes_post(key)
+                .map_err(Error::NativeVpError)?;
+            if let Some(pre) = &pre {
+                if decode_client_state(pre)?.is_frozen()
+                    && post.as_ref() != Some(pre)
+                {
+                    return Err(Error::StateChange(format!(
+                        "The frozen client can't be modified: Client ID \
+                         {client_id}"
+                    )));
+                }
+            }
+            if let Some(post) = &post {
+                if decode_client_state(post)?.is_frozen() {
+                    frozen_clients.insert(client_id);
+                }
+            }
+        }
+
+        if let Ok(IbcMessage::Envelope(MsgEnvelope::Client(
+            ClientMsg::Misbehaviour(msg),
+        ))) = decode_message(tx_data)
+        {
+            if !frozen_clients.contains(&msg.client_id) {
+                return Err(Error::StateChange(format!(
+                    "The client wasn't frozen by the misbehaviour: Client ID \
+                     {}",
+                    msg.client_id
+                )));
+            }
         }
         Ok(())
     }
 }
 
+fn decode_client_state(bytes: &[u8]) -> VpResult<AnyClientState> {
+    Any::decode(bytes)
+        .map_err(|e| Error::StateChange(e.to_string()))?
+        .try_into()
+        .map_err(|e: ClientError| Error::StateChange(e.to_string()))
+}
+
 fn match_value(
     key: &Key,
     actual: Option<Vec<u8>>,
@@ -319,8 +500,11 @@ mod tests {
     use ibc_testkit::testapp::ibc::clients::mock::header::MockHeader;
     use namada_gas::TxGasMeter;
     use namada_governance::parameters::GovernanceParameters;
+    use namada_ibc::context::localhost::{
+        LocalhostClientState, LOCALHOST_CLIENT_TYPE,
+    };
     use namada_state::testing::TestWlStorage;
-    use namada_state::StorageRead;
+    use namada_state::{Sha256Hasher, StorageRead};
     use namada_tx::data::TxType;
     use namada_tx::{Code, Data, Section, Signature, Tx};
     use prost::Message;
@@ -369,6 +553,7 @@ mod tests {
     use crate::ibc::core::commitment_types::commitment::{
         CommitmentPrefix, CommitmentProofBytes,
     };
+    use crate::ibc::core::commitment_types::specs::ProofSpecs;
     use crate::ibc::core::connection::types::events::{
         OpenAck as ConnOpenAck, OpenConfirm as ConnOpenConfirm,
         OpenInit as ConnOpenInit, OpenTry as ConnOpenTry,
@@ -385,7 +570,7 @@ mod tests {
         IbcEvent as RawIbcEvent, MessageEvent,
     };
     use crate::ibc::core::host::types::identifiers::{
-        ChannelId, ClientId, ConnectionId, PortId, Sequence,
+        ChannelId, ClientId, ClientType, ConnectionId, PortId, Sequence,
     };
     use crate::ibc::core::router::types::event::ModuleEvent;
     use crate::ibc::primitives::proto::{Any, Protobuf};
@@ -396,7 +581,8 @@ mod tests {
         client_update_height_key, client_update_timestamp_key, commitment_key,
         connection_counter_key, connection_key, consensus_state_key,
         ibc_denom_key, next_sequence_ack_key, next_sequence_recv_key,
-        next_sequence_send_key, receipt_key,
+        next_sequence_send_key, packet_status_key, packet_status_pruning_key,
+        receipt_key,
     };
     use crate::ledger::gas::VpGasMeter;
     use crate::ledger::parameters::storage::{
@@ -405,8 +591,12 @@ mod tests {
     use crate::ledger::parameters::EpochDuration;
     use crate::ledger::{ibc, pos};
     use crate::tendermint::time::Time as TmTime;
-    use crate::token::storage_key::balance_key;
+    use crate::token::storage_key::{balance_key, metadata_key};
     use crate::token::Amount;
+    use crate::types::ibc::{
+        PacketDirection, PacketStage, PacketStatus,
+        PACKET_STATUS_RETENTION_BLOCKS,
+    };
     use crate::types::key::testing::keypair_1;
     use crate::types::storage::{BlockHash, BlockHeight, TxIndex};
     use crate::types::time::DurationSecs;
@@ -567,6 +757,39 @@ mod tests {
         ChanCounterparty::new(counterpart_port_id, Some(counterpart_channel_id))
     }
 
+    fn write_packet_status(
+        wl_storage: &mut TestWlStorage,
+        keys_changed: &mut BTreeSet<Key>,
+        direction: PacketDirection,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        sequence: Sequence,
+        stage: PacketStage,
+    ) {
+        let key = packet_status_key(direction, port_id, channel_id, sequence);
+        let height = wl_storage.storage.get_block_height().0;
+        if stage.is_terminal() {
+            let pruning_key = packet_status_pruning_key(
+                height + PACKET_STATUS_RETENTION_BLOCKS,
+                direction,
+                port_id,
+                channel_id,
+                sequence,
+            );
+            wl_storage
+                .write_log
+                .write(&pruning_key, key.serialize_to_vec())
+                .expect("write failed");
+            keys_changed.insert(pruning_key);
+        }
+        let status = PacketStatus { stage, height };
+        wl_storage
+            .write_log
+            .write(&key, status.serialize_to_vec())
+            .expect("write failed");
+        keys_changed.insert(key);
+    }
+
     fn get_next_seq(wl_storage: &TestWlStorage, key: &Key) -> Sequence {
         let (val, _) = wl_storage.storage.read(key).expect("read failed");
         match val {
@@ -827,6 +1050,165 @@ mod tests {
         assert_matches!(result, Error::StateChange(_));
     }
 
+    /// Write the result of the localhost client creation and make the tx
+    fn create_localhost_client(
+        wl_storage: &mut TestWlStorage,
+        client_state: LocalhostClientState,
+    ) -> (Tx, BTreeSet<Key>) {
+        let mut keys_changed = BTreeSet::new();
+        let client_type = ClientType::new(LOCALHOST_CLIENT_TYPE).unwrap();
+        let client_id = ClientId::new(client_type.clone(), 0).unwrap();
+        // message
+        let height = Height::new(0, 1).unwrap();
+        let header = MockHeader {
+            height,
+            timestamp: Timestamp::now(),
+        };
+        let msg = MsgCreateClient {
+            client_state: client_state.clone().into(),
+            // the consensus state is ignored
+            consensus_state: MockConsensusState::new(header).into(),
+            signer: "account0".to_string().into(),
+        };
+        // client state
+        let client_state_key = client_state_key(&client_id);
+        let bytes = Any::from(client_state.clone()).encode_to_vec();
+        wl_storage
+            .write_log
+            .write(&client_state_key, bytes)
+            .expect("write failed");
+        keys_changed.insert(client_state_key);
+        // client update time
+        let client_update_time_key = client_update_timestamp_key(&client_id);
+        let time = wl_storage
+            .storage
+            .get_block_header(None)
+            .unwrap()
+            .0
+            .unwrap()
+            .time;
+        let bytes = TmTime::try_from(time).unwrap().encode_vec();
+        wl_storage
+            .write_log
+            .write(&client_update_time_key, bytes)
+            .expect("write failed");
+        keys_changed.insert(client_update_time_key);
+        // client update height
+        let client_update_height_key = client_update_height_key(&client_id);
+        let host_height = wl_storage.storage.get_block_height().0;
+        let host_height =
+            Height::new(0, host_height.0).expect("invalid height");
+        wl_storage
+            .write_log
+            .write(&client_update_height_key, host_height.encode_vec())
+            .expect("write failed");
+        keys_changed.insert(client_update_height_key);
+        // client counter
+        let client_counter_key = client_counter_key();
+        increment_counter(wl_storage, &client_counter_key);
+        keys_changed.insert(client_counter_key);
+        // event
+        let event = RawIbcEvent::CreateClient(CreateClient::new(
+            client_id,
+            client_type,
+            client_state.latest_height,
+        ));
+        let message_event = RawIbcEvent::Message(MessageEvent::Client);
+        wl_storage
+            .write_log
+            .emit_ibc_event(message_event.try_into().unwrap());
+        wl_storage
+            .write_log
+            .emit_ibc_event(event.try_into().unwrap());
+
+        let mut tx_data = vec![];
+        msg.to_any().encode(&mut tx_data).expect("encoding failed");
+        let mut tx = Tx::new(wl_storage.storage.chain_id.clone(), None);
+        tx.add_code(vec![], None)
+            .add_serialized_data(tx_data)
+            .sign_wrapper(keypair_1());
+
+        (tx, keys_changed)
+    }
+
+    #[test]
+    fn test_create_localhost_client() {
+        let mut wl_storage = init_storage();
+        let proof_specs =
+            namada_state::ics23_specs::ibc_proof_specs::<Sha256Hasher>();
+        let client_state = LocalhostClientState::new(
+            Height::new(0, 1).unwrap(),
+            proof_specs.into(),
+        );
+        let (tx, keys_changed) =
+            create_localhost_client(&mut wl_storage, client_state);
+
+        let tx_index = TxIndex::default();
+        let gas_meter = VpGasMeter::new_from_tx_meter(
+            &TxGasMeter::new_from_sub_limit(TX_GAS_LIMIT.into()),
+        );
+        let (vp_wasm_cache, _vp_cache_dir) =
+            wasm::compilation_cache::common::testing::cache();
+
+        let verifiers = BTreeSet::new();
+        let ctx = Ctx::new(
+            &ADDRESS,
+            &wl_storage.storage,
+            &wl_storage.write_log,
+            &tx,
+            &tx_index,
+            gas_meter,
+            &keys_changed,
+            &verifiers,
+            vp_wasm_cache,
+        );
+
+        let ibc = Ibc { ctx };
+        assert!(
+            ibc.validate_tx(&tx, &keys_changed, &verifiers)
+                .expect("validation failed")
+        );
+    }
+
+    #[test]
+    fn test_create_localhost_client_with_other_proof_specs() {
+        let mut wl_storage = init_storage();
+        // the default proof specs are for Cosmos SDK chains
+        let client_state = LocalhostClientState::new(
+            Height::new(0, 1).unwrap(),
+            ProofSpecs::default(),
+        );
+        let (tx, keys_changed) =
+            create_localhost_client(&mut wl_storage, client_state);
+
+        let tx_index = TxIndex::default();
+        let gas_meter = VpGasMeter::new_from_tx_meter(
+            &TxGasMeter::new_from_sub_limit(TX_GAS_LIMIT.into()),
+        );
+        let (vp_wasm_cache, _vp_cache_dir) =
+            wasm::compilation_cache::common::testing::cache();
+
+        let verifiers = BTreeSet::new();
+        let ctx = Ctx::new(
+            &ADDRESS,
+            &wl_storage.storage,
+            &wl_storage.write_log,
+            &tx,
+            &tx_index,
+            gas_meter,
+            &keys_changed,
+            &verifiers,
+            vp_wasm_cache,
+        );
+
+        let ibc = Ibc { ctx };
+        // this should fail because the client can't verify proofs of this
+        // chain
+        let result =
+            ibc.validate_tx(&tx, &keys_changed, &verifiers).unwrap_err();
+        assert_matches!(result, Error::IbcAction(_));
+    }
+
     #[test]
     fn test_update_client() {
         let mut keys_changed = BTreeSet::new();
@@ -2035,6 +2417,16 @@ mod tests {
             .write(&commitment_key, bytes)
             .expect("write failed");
         keys_changed.insert(commitment_key);
+        // packet status
+        write_packet_status(
+            &mut wl_storage,
+            &mut keys_changed,
+            PacketDirection::Outgoing,
+            &msg.port_id_on_a,
+            &msg.chan_id_on_a,
+            sequence,
+            PacketStage::Sent,
+        );
         // event
         let transfer_event = TransferEvent {
             sender: msg.packet_data.sender.clone(),
@@ -2208,6 +2600,25 @@ mod tests {
             .write(&denom_key, bytes)
             .expect("write failed");
         keys_changed.insert(denom_key);
+        let metadata_key = metadata_key(&ibc_token(coin.denom.to_string()));
+        let bytes = IbcDenomTrace::from_ibc_denom(coin.denom.to_string())
+            .expect("the denom should have the trace")
+            .serialize_to_vec();
+        wl_storage
+            .write_log
+            .write(&metadata_key, bytes)
+            .expect("write failed");
+        keys_changed.insert(metadata_key);
+        // packet status
+        write_packet_status(
+            &mut wl_storage,
+            &mut keys_changed,
+            PacketDirection::Incoming,
+            &packet.port_id_on_b,
+            &packet.chan_id_on_b,
+            packet.seq_on_a,
+            PacketStage::Received,
+        );
         // event
         let recv_event = RecvEvent {
             sender: sender.to_string().into(),
@@ -2346,6 +2757,15 @@ mod tests {
             .write_log
             .write(&commitment_key, bytes)
             .expect("write failed");
+        write_packet_status(
+            &mut wl_storage,
+            &mut BTreeSet::new(),
+            PacketDirection::Outgoing,
+            &transfer_msg.port_id_on_a,
+            &transfer_msg.chan_id_on_a,
+            sequence,
+            PacketStage::Sent,
+        );
         wl_storage.write_log.commit_tx();
         wl_storage.commit_block().expect("commit failed");
         // for next block
@@ -2374,6 +2794,16 @@ mod tests {
             .delete(&commitment_key)
             .expect("delete failed");
         keys_changed.insert(commitment_key);
+        // packet status
+        write_packet_status(
+            &mut wl_storage,
+            &mut keys_changed,
+            PacketDirection::Outgoing,
+            &packet.port_id_on_a,
+            &packet.chan_id_on_a,
+            packet.seq_on_a,
+            PacketStage::Acknowledged,
+        );
         // event
         let data = serde_json::from_slice::<PacketData>(&packet.data)
             .expect("decoding packet data failed");
@@ -2501,6 +2931,15 @@ mod tests {
             .write_log
             .write(&commitment_key, bytes)
             .expect("write failed");
+        write_packet_status(
+            &mut wl_storage,
+            &mut BTreeSet::new(),
+            PacketDirection::Outgoing,
+            &transfer_msg.port_id_on_a,
+            &transfer_msg.chan_id_on_a,
+            sequence,
+            PacketStage::Sent,
+        );
         wl_storage.write_log.commit_tx();
         wl_storage.commit_block().expect("commit failed");
         // for next block
@@ -2528,6 +2967,16 @@ mod tests {
             .delete(&commitment_key)
             .expect("delete failed");
         keys_changed.insert(commitment_key);
+        // packet status
+        write_packet_status(
+            &mut wl_storage,
+            &mut keys_changed,
+            PacketDirection::Outgoing,
+            &packet.port_id_on_a,
+            &packet.chan_id_on_a,
+            packet.seq_on_a,
+            PacketStage::TimedOut,
+        );
         // event
         let data = serde_json::from_slice::<PacketData>(&packet.data)
             .expect("decoding packet data failed");
@@ -2652,6 +3101,15 @@ mod tests {
             .write_log
             .write(&commitment_key, bytes)
             .expect("write failed");
+        write_packet_status(
+            &mut wl_storage,
+            &mut BTreeSet::new(),
+            PacketDirection::Outgoing,
+            &transfer_msg.port_id_on_a,
+            &transfer_msg.chan_id_on_a,
+            sequence,
+            PacketStage::Sent,
+        );
         wl_storage.write_log.commit_tx();
         wl_storage.commit_block().expect("commit failed");
         // for next block
@@ -2680,6 +3138,16 @@ mod tests {
             .delete(&commitment_key)
             .expect("delete failed");
         keys_changed.insert(commitment_key);
+        // packet status
+        write_packet_status(
+            &mut wl_storage,
+            &mut keys_changed,
+            PacketDirection::Outgoing,
+            &packet.port_id_on_a,
+            &packet.chan_id_on_a,
+            packet.seq_on_a,
+            PacketStage::TimedOut,
+        );
         // event
         let data = serde_json::from_slice::<PacketData>(&packet.data)
             .expect("decoding packet data failed");
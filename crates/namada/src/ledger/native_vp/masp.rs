@@ -14,6 +14,9 @@ use namada_core::types::address::InternalAddress::Masp;
 use namada_core::types::masp::encode_asset_type;
 use namada_core::types::storage::{IndexedTx, Key};
 use namada_gas::MASP_VERIFY_SHIELDED_TX_GAS;
+use namada_governance::is_subsystem_halted;
+use namada_governance::storage::keys::is_proposal_type_key;
+use namada_governance::storage::proposal::{HaltedSubsystem, ProposalType};
 use namada_sdk::masp::verify_shielded_tx;
 use namada_state::{OptionExt, ResultExt};
 use namada_token::read_denom;
@@ -27,7 +30,7 @@ use token::storage_key::{
     balance_key, is_any_shielded_action_balance_key, is_masp_allowed_key,
     is_masp_key, is_masp_nullifier_key, is_masp_tx_pin_key,
     masp_commitment_anchor_key, masp_commitment_tree_key,
-    masp_convert_anchor_key, masp_nullifier_key,
+    masp_convert_anchor_key, masp_max_reward_rate_key, masp_nullifier_key,
 };
 use token::Amount;
 
@@ -241,10 +244,48 @@ where
         Ok(true)
     }
 
+    // Check that the MASP rewards proposals initialized by the transaction
+    // only update the parameters of the tokens already rewarded in the
+    // shielded pool with well-formed values
+    fn valid_masp_rewards_proposals(
+        &self,
+        keys_changed: &BTreeSet<Key>,
+    ) -> Result<bool> {
+        for key in keys_changed.iter().filter(|key| is_proposal_type_key(key)) {
+            let update = match self.ctx.read_post::<ProposalType>(key)? {
+                Some(ProposalType::MaspRewards(update)) => update,
+                _ => continue,
+            };
+            if update.is_empty() {
+                tracing::debug!("Empty MASP rewards proposal");
+                return Ok(false);
+            }
+            for (token, params) in &update {
+                if !self.ctx.has_key_pre(&masp_max_reward_rate_key(token))? {
+                    tracing::debug!(
+                        "The token {token} of the MASP rewards proposal \
+                         doesn't earn MASP rewards"
+                    );
+                    return Ok(false);
+                }
+                if !params.is_valid() {
+                    tracing::debug!(
+                        "Invalid MASP rewards parameters for the token {token}"
+                    );
+                    return Ok(false);
+                }
+            }
+        }
+
+        Ok(true)
+    }
+
+    // Check the state changes and get the transparent transfers of the
+    // transaction. The transfers share the same source and target.
     fn validate_state_and_get_transfer_data(
         &self,
         keys_changed: &BTreeSet<Key>,
-    ) -> Result<TransparentTransferData> {
+    ) -> Result<Vec<TransparentTransferData>> {
         // Check that the transaction didn't write unallowed masp keys
         let masp_keys_changed: Vec<&Key> =
             keys_changed.iter().filter(|key| is_masp_key(key)).collect();
@@ -303,118 +344,118 @@ where
             .iter()
             .filter(|addresses| addresses[1] == &Address::Internal(Masp))
             .collect();
-        let token = match masp_balances.len() {
-            0 => {
-                // No masp balance modification found, assume shielded
-                // transaction and return dummy transparent data
-                return Ok(TransparentTransferData {
-                    source: Address::Internal(Masp),
-                    target: Address::Internal(Masp),
-                    token: self.ctx.get_native_token()?,
-                    amount: Amount::zero(),
-                });
-            }
-            1 => masp_balances[0][0].to_owned(),
-            _ => {
-                // Only one transparent balance of MASP can be updated by the
-                // shielding or unshielding transaction
-                return Err(Error::NativeVpError(
-                    native_vp::Error::SimpleMessage(
-                        "More than one MASP transparent balance was modified",
-                    ),
-                ));
-            }
-        };
+        if masp_balances.is_empty() {
+            // No masp balance modification found, assume shielded
+            // transaction and return dummy transparent data
+            return Ok(vec![TransparentTransferData {
+                source: Address::Internal(Masp),
+                target: Address::Internal(Masp),
+                token: self.ctx.get_native_token()?,
+                amount: Amount::zero(),
+            }]);
+        }
 
         let counterparts: Vec<&[&Address; 2]> = balance_addresses
             .iter()
             .filter(|addresses| addresses[1] != &Address::Internal(Masp))
             .collect();
-        // NOTE: since we don't allow more than one transfer per tx in this vp,
-        // there's no need to check the token address in the balance key nor the
-        // change to the actual balance, the multitoken VP will verify these
-        let counterpart = match counterparts.len() {
-            1 => counterparts[0][1].to_owned(),
-            _ => {
-                return Err(Error::NativeVpError(
-                    native_vp::Error::SimpleMessage(
-                        "An invalid number of non-MASP transparent balances \
-                         was modified",
-                    ),
-                ));
-            }
-        };
+        // NOTE: there must be exactly one counterpart for each token of the
+        // MASP, the multitoken VP will verify the changes to the actual
+        // balances
+        if counterparts.len() != masp_balances.len() {
+            return Err(Error::NativeVpError(native_vp::Error::SimpleMessage(
+                "An invalid number of non-MASP transparent balances was \
+                 modified",
+            )));
+        }
 
-        let pre_masp_balance: Amount = self
-            .ctx
-            .read_pre(&balance_key(&token, &Address::Internal(Masp)))?
-            .unwrap_or_default();
-        let post_masp_balance: Amount = self
-            .ctx
-            .read_post(&balance_key(&token, &Address::Internal(Masp)))?
-            .unwrap_or_default();
-        let (amount, source, target) =
-            match pre_masp_balance.cmp(&post_masp_balance) {
-                Ordering::Equal => {
+        let mut transfers = Vec::with_capacity(masp_balances.len());
+        for addresses in masp_balances {
+            let token: &Address = addresses[0];
+            let counterpart = match counterparts
+                .iter()
+                .find(|addresses| addresses[0] == token)
+            {
+                Some(addresses) => addresses[1].to_owned(),
+                None => {
                     return Err(Error::NativeVpError(
                         native_vp::Error::SimpleMessage(
-                            "Found a MASP transaction that moves no \
-                             transparent funds",
+                            "Missing the non-MASP transparent balance of a \
+                             MASP token",
                         ),
                     ));
                 }
-                Ordering::Less => (
-                    post_masp_balance - pre_masp_balance,
-                    counterpart,
-                    Address::Internal(Masp),
-                ),
-                Ordering::Greater => (
-                    pre_masp_balance - post_masp_balance,
-                    Address::Internal(Masp),
-                    counterpart,
-                ),
             };
 
-        Ok(TransparentTransferData {
-            source,
-            target,
-            token,
-            amount,
-        })
-    }
-}
+            let pre_masp_balance: Amount = self
+                .ctx
+                .read_pre(&balance_key(token, &Address::Internal(Masp)))?
+                .unwrap_or_default();
+            let post_masp_balance: Amount = self
+                .ctx
+                .read_post(&balance_key(token, &Address::Internal(Masp)))?
+                .unwrap_or_default();
+            let (amount, source, target) =
+                match pre_masp_balance.cmp(&post_masp_balance) {
+                    Ordering::Equal => {
+                        return Err(Error::NativeVpError(
+                            native_vp::Error::SimpleMessage(
+                                "Found a MASP transaction that moves no \
+                                 transparent funds",
+                            ),
+                        ));
+                    }
+                    Ordering::Less => (
+                        post_masp_balance - pre_masp_balance,
+                        counterpart,
+                        Address::Internal(Masp),
+                    ),
+                    Ordering::Greater => (
+                        pre_masp_balance - post_masp_balance,
+                        Address::Internal(Masp),
+                        counterpart,
+                    ),
+                };
+            transfers.push(TransparentTransferData {
+                source,
+                target,
+                token: token.to_owned(),
+                amount,
+            });
+        }
 
-// Make a map to help recognize asset types lacking an epoch
-fn unepoched_tokens(
-    token: &Address,
-    denom: token::Denomination,
-) -> Result<HashMap<AssetType, (Address, token::Denomination, MaspDigitPos)>> {
-    let mut unepoched_tokens = HashMap::new();
-    for digit in MaspDigitPos::iter() {
-        let asset_type = encode_asset_type(token.clone(), denom, digit, None)
-            .wrap_err("unable to create asset type")?;
-        unepoched_tokens.insert(asset_type, (token.clone(), denom, digit));
-    }
-    Ok(unepoched_tokens)
-}
+        // Several MASP transparent balances can only be updated by the
+        // shielding of several tokens from the same source
+        if let [first, rest @ ..] = &transfers[..] {
+            if !rest.is_empty()
+                && (first.target != Address::Internal(Masp)
+                    || rest.iter().any(|transfer| {
+                        transfer.source != first.source
+                            || transfer.target != first.target
+                    }))
+            {
+                return Err(Error::NativeVpError(
+                    native_vp::Error::SimpleMessage(
+                        "More than one MASP transparent balance was modified \
+                         by a transaction other than a shielding from a \
+                         single source",
+                    ),
+                ));
+            }
+        }
 
-impl<'a, DB, H, CA> NativeVp for MaspVp<'a, DB, H, CA>
-where
-    DB: 'static + namada_state::DB + for<'iter> namada_state::DBIter<'iter>,
-    H: 'static + namada_state::StorageHasher,
-    CA: 'static + WasmCacheAccess,
-{
-    type Error = Error;
+        Ok(transfers)
+    }
 
-    fn validate_tx(
+    // Check that the transparent and shielded parts of a shielded transaction
+    // match the state changes, short of verifying its proofs
+    fn valid_shielded_action(
         &self,
-        tx_data: &Tx,
+        shielded_tx: &Transaction,
         keys_changed: &BTreeSet<Key>,
-        _verifiers: &BTreeSet<Address>,
     ) -> Result<bool> {
         let epoch = self.ctx.get_block_epoch()?;
         let conversion_state = self.ctx.storage.get_conversion_state();
-        let shielded_tx = self.ctx.get_shielded_action(tx_data)?;
 
         if u64::from(self.ctx.get_block_height()?)
             > u64::from(shielded_tx.expiry_height())
@@ -428,13 +469,19 @@ where
         transparent_tx_pool += shielded_tx.sapling_value_balance();
 
         // Check the validity of the keys and get the transfer data
-        let transfer =
+        let transfers =
             self.validate_state_and_get_transfer_data(keys_changed)?;
-
-        let denom = read_denom(&self.ctx.pre(), &transfer.token)?
-            .ok_or_err_msg(
-                "No denomination found in storage for the given token",
-            )?;
+        let mut denoms = HashMap::new();
+        for transfer in &transfers {
+            let denom = read_denom(&self.ctx.pre(), &transfer.token)?
+                .ok_or_err_msg(
+                    "No denomination found in storage for the given token",
+                )?;
+            denoms.insert(transfer.token.clone(), denom);
+        }
+        // The transfers share the same source and target
+        let transfer = &transfers[0];
+        let denom = denoms[&transfer.token];
 
         if transfer.source != Address::Internal(Masp) {
             // No shielded spends nor shielded converts are allowed
@@ -449,17 +496,21 @@ where
                 shielded_tx.transparent_bundle().ok_or_err_msg(
                     "Expected transparent outputs in shielding transaction",
                 )?;
-            let mut total_in_values = token::Amount::zero();
+            let mut total_in_values = HashMap::<Address, token::Amount>::new();
             let source_enc = transfer.source.serialize_to_vec();
             let hash =
                 ripemd::Ripemd160::digest(sha2::Sha256::digest(&source_enc));
 
             // To help recognize asset types not in the conversion tree
-            let unepoched_tokens = unepoched_tokens(&transfer.token, denom)?;
+            let mut unepoched_tokens_map = HashMap::new();
+            for (token, denom) in &denoms {
+                unepoched_tokens_map.extend(unepoched_tokens(token, *denom)?);
+            }
             // Handle transparent input
             // The following boundary conditions must be satisfied
-            // 1. Total of transparent input values equals containing transfer
-            // amount 2. Asset type must be properly derived
+            // 1. Total of transparent input values of each token equals the
+            // amount of the containing transfer of this token
+            // 2. Asset type must be properly derived
             // 3. Public key must be the hash of the source
             for vin in &transp_bundle.vin {
                 // Non-masp sources add to the transparent tx pool
@@ -484,7 +535,10 @@ where
                     );
                     return Ok(false);
                 }
-                match conversion_state.assets.get(&vin.asset_type) {
+                let (token, digit) = match conversion_state
+                    .assets
+                    .get(&vin.asset_type)
+                {
                     // Satisfies 2. Note how the asset's epoch must be equal to
                     // the present: users must never be allowed to backdate
                     // transparent inputs to a transaction for they would then
@@ -495,26 +549,17 @@ where
                         asset_epoch,
                         _,
                         _,
-                    )) if *address == transfer.token
-                        && *asset_denom == denom
+                    )) if denoms.get(address) == Some(asset_denom)
                         && *asset_epoch == epoch =>
                     {
-                        total_in_values = total_in_values
-                            .checked_add(token::Amount::from_masp_denominated(
-                                vin.value, *digit,
-                            ))
-                            .ok_or_else(|| {
-                                Error::NativeVpError(
-                                    native_vp::Error::SimpleMessage(
-                                        "Overflow in total in value sum",
-                                    ),
-                                )
-                            })?;
+                        (address, *digit)
                     }
                     // Maybe the asset type has no attached epoch
-                    None if unepoched_tokens.contains_key(&vin.asset_type) => {
+                    None if unepoched_tokens_map
+                        .contains_key(&vin.asset_type) =>
+                    {
                         let (token, denom, digit) =
-                            &unepoched_tokens[&vin.asset_type];
+                            &unepoched_tokens_map[&vin.asset_type];
                         // Determine what the asset type would be if it were
                         // epoched
                         let epoched_asset_type = encode_asset_type(
@@ -533,30 +578,34 @@ where
                             // unepoched variant
                             tracing::debug!("epoch is missing from asset type");
                             return Ok(false);
-                        } else {
-                            // Otherwise note the contribution to this
-                            // trransparent input
-                            total_in_values = total_in_values
-                                .checked_add(
-                                    token::Amount::from_masp_denominated(
-                                        vin.value, *digit,
-                                    ),
-                                )
-                                .ok_or_else(|| {
-                                    Error::NativeVpError(
-                                        native_vp::Error::SimpleMessage(
-                                            "Overflow in total in values sum",
-                                        ),
-                                    )
-                                })?;
                         }
+                        // Otherwise note the contribution to this
+                        // trransparent input
+                        (token, *digit)
                     }
                     // unrecognized asset
                     _ => return Ok(false),
                 };
+                let total_in_value =
+                    total_in_values.entry(token.clone()).or_default();
+                *total_in_value = total_in_value
+                    .checked_add(token::Amount::from_masp_denominated(
+                        vin.value, digit,
+                    ))
+                    .ok_or_else(|| {
+                        Error::NativeVpError(native_vp::Error::SimpleMessage(
+                            "Overflow in total in values sum",
+                        ))
+                    })?;
             }
             // Satisfies 1.
-            if total_in_values != transfer.amount {
+            if transfers.iter().any(|transfer| {
+                total_in_values
+                    .get(&transfer.token)
+                    .copied()
+                    .unwrap_or_default()
+                    != transfer.amount
+            }) {
                 return Ok(false);
             }
         } else {
@@ -579,9 +628,9 @@ where
                 }
             }
 
-            if !(self.valid_spend_descriptions_anchor(&shielded_tx)?
-                && self.valid_convert_descriptions_anchor(&shielded_tx)?
-                && self.valid_nullifiers_reveal(keys_changed, &shielded_tx)?)
+            if !(self.valid_spend_descriptions_anchor(shielded_tx)?
+                && self.valid_convert_descriptions_anchor(shielded_tx)?
+                && self.valid_nullifiers_reveal(keys_changed, shielded_tx)?)
             {
                 return Ok(false);
             }
@@ -589,7 +638,7 @@ where
 
         // The transaction must correctly update the note commitment tree
         // in storage with the new output descriptions
-        if !self.valid_note_commitment_update(&shielded_tx)? {
+        if !self.valid_note_commitment_update(shielded_tx)? {
             return Ok(false);
         }
 
@@ -732,6 +781,63 @@ where
             _ => {}
         }
 
+        Ok(true)
+    }
+}
+
+// Make a map to help recognize asset types lacking an epoch
+fn unepoched_tokens(
+    token: &Address,
+    denom: token::Denomination,
+) -> Result<HashMap<AssetType, (Address, token::Denomination, MaspDigitPos)>> {
+    let mut unepoched_tokens = HashMap::new();
+    for digit in MaspDigitPos::iter() {
+        let asset_type = encode_asset_type(token.clone(), denom, digit, None)
+            .wrap_err("unable to create asset type")?;
+        unepoched_tokens.insert(asset_type, (token.clone(), denom, digit));
+    }
+    Ok(unepoched_tokens)
+}
+
+impl<'a, DB, H, CA> NativeVp for MaspVp<'a, DB, H, CA>
+where
+    DB: 'static + namada_state::DB + for<'iter> namada_state::DBIter<'iter>,
+    H: 'static + namada_state::StorageHasher,
+    CA: 'static + WasmCacheAccess,
+{
+    type Error = Error;
+
+    fn validate_tx(
+        &self,
+        tx_data: &Tx,
+        keys_changed: &BTreeSet<Key>,
+        _verifiers: &BTreeSet<Address>,
+    ) -> Result<bool> {
+        let is_proposal = keys_changed.iter().any(is_proposal_type_key);
+        if is_proposal && !self.valid_masp_rewards_proposals(keys_changed)? {
+            return Ok(false);
+        }
+        // Any change to the MASP keys or to the MASP transparent balances
+        // must come with a valid shielded action
+        let is_shielded_action = keys_changed.iter().any(|key| {
+            is_masp_key(key)
+                || is_any_shielded_action_balance_key(key)
+                    .is_some_and(|[_, owner]| owner == &Address::Internal(Masp))
+        });
+        if is_proposal && !is_shielded_action {
+            return Ok(true);
+        }
+
+        if is_subsystem_halted(&self.ctx.pre(), &HaltedSubsystem::Masp)? {
+            tracing::debug!("The MASP is halted by a governance proposal");
+            return Ok(false);
+        }
+
+        let shielded_tx = self.ctx.get_shielded_action(tx_data)?;
+        if !self.valid_shielded_action(&shielded_tx, keys_changed)? {
+            return Ok(false);
+        }
+
         // Verify the proofs and charge the gas for the expensive execution
         self.ctx
             .charge_gas(MASP_VERIFY_SHIELDED_TX_GAS)
@@ -745,3 +851,298 @@ impl From<native_vp::Error> for Error {
         Self::NativeVpError(err)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use masp_primitives::consensus::{BlockHeight, TestNetwork};
+    use masp_primitives::memo::MemoBytes;
+    use masp_primitives::transaction::builder::Builder;
+    use masp_primitives::transaction::components::{TxOut, U64Sum};
+    use masp_primitives::transaction::fees::fixed::FeeRule;
+    use masp_primitives::transaction::TransparentAddress;
+    use masp_primitives::zip32::ExtendedSpendingKey;
+    use namada_gas::TxGasMeter;
+    use namada_sdk::masp::testing::MockTxProver;
+    use namada_state::testing::TestWlStorage;
+    use namada_state::{StorageRead, StorageWrite};
+    use namada_tx::data::TxType;
+    use rand_core::OsRng;
+
+    use super::*;
+    use crate::core::types::address::testing::{
+        established_address_1, established_address_2,
+    };
+    use crate::core::types::address::{btc, eth, nam};
+    use crate::ledger::gas::VpGasMeter;
+    use crate::token::{read_balance, write_denom};
+    use crate::types::storage::TxIndex;
+    use crate::vm::wasm::compilation_cache::common::testing::cache as wasm_cache;
+
+    const ADDRESS: Address = Address::Internal(Masp);
+
+    /// Init a storage in which the given owners and the MASP hold some NAM
+    /// and BTC
+    fn init_storage(owners: &[&Address]) -> TestWlStorage {
+        let mut s = TestWlStorage::default();
+        write_denom(&mut s, &nam(), 6.into()).unwrap();
+        write_denom(&mut s, &btc(), 8.into()).unwrap();
+        s.write(&masp_commitment_tree_key(), CommitmentTree::<Node>::empty())
+            .unwrap();
+        for owner in owners.iter().copied().chain([&ADDRESS]) {
+            for token in [nam(), btc()] {
+                s.write(&balance_key(&token, owner), Amount::from_u64(100))
+                    .unwrap();
+            }
+        }
+        s.commit_tx();
+        s
+    }
+
+    /// Apply a transparent transfer of a raw amount of the token
+    fn transfer(
+        s: &mut TestWlStorage,
+        keys: &mut BTreeSet<Key>,
+        token: &Address,
+        source: &Address,
+        target: &Address,
+        amount: u64,
+    ) {
+        let amount = Amount::from_u64(amount);
+        let source_balance = read_balance(s, token, source).unwrap() - amount;
+        let target_balance = read_balance(s, token, target).unwrap() + amount;
+        for (owner, balance) in
+            [(source, source_balance), (target, target_balance)]
+        {
+            let key = balance_key(token, owner);
+            s.write(&key, balance).unwrap();
+            keys.insert(key);
+        }
+    }
+
+    /// Build a MASP transaction moving the given raw amounts of the tokens
+    /// from the transparent inputs of their sources to shielded outputs and
+    /// append its notes to the commitment tree
+    fn shielding_tx(
+        s: &mut TestWlStorage,
+        inputs: &[(&Address, &Address, u64)],
+        outputs: &[(&Address, u64)],
+    ) -> Transaction {
+        let asset_type = |s: &TestWlStorage, token: &Address| {
+            let denom = read_denom(s, token).unwrap().unwrap();
+            encode_asset_type(token.clone(), denom, MaspDigitPos::Zero, None)
+                .unwrap()
+        };
+        let target = ExtendedSpendingKey::master(&[0; 32]).default_address().1;
+        let mut builder = Builder::<TestNetwork, _>::new_with_rng(
+            TestNetwork,
+            BlockHeight::from_u32(1_000),
+            OsRng,
+        );
+        for (source, token, value) in inputs {
+            let hash = ripemd::Ripemd160::digest(sha2::Sha256::digest(
+                source.serialize_to_vec(),
+            ));
+            builder
+                .add_transparent_input(TxOut {
+                    asset_type: asset_type(s, token),
+                    value: *value,
+                    address: TransparentAddress(hash.into()),
+                })
+                .unwrap();
+        }
+        for (token, value) in outputs {
+            builder
+                .add_sapling_output(
+                    None,
+                    target,
+                    asset_type(s, token),
+                    *value,
+                    MemoBytes::empty(),
+                )
+                .unwrap();
+        }
+        let (tx, _metadata) = builder
+            .build(
+                &MockTxProver(Mutex::new(OsRng)),
+                &FeeRule::non_standard(U64Sum::zero()),
+            )
+            .unwrap();
+
+        let mut tree: CommitmentTree<Node> =
+            s.read(&masp_commitment_tree_key()).unwrap().unwrap();
+        let bundle = tx.sapling_bundle().unwrap();
+        for description in &bundle.shielded_outputs {
+            tree.append(Node::from_scalar(description.cmu)).unwrap();
+        }
+        s.write(&masp_commitment_tree_key(), tree).unwrap();
+        tx
+    }
+
+    /// Run the checks of the MASP VP on the shielded transaction, short of
+    /// the verification of its proofs
+    fn validate(
+        s: &TestWlStorage,
+        keys: &BTreeSet<Key>,
+        shielded_tx: &Transaction,
+    ) -> Result<bool> {
+        let tx_index = TxIndex::default();
+        let tx = Tx::from_type(TxType::Raw);
+        let gas_meter = VpGasMeter::new_from_tx_meter(
+            &TxGasMeter::new_from_sub_limit(u64::MAX.into()),
+        );
+        let (vp_wasm_cache, _vp_cache_dir) = wasm_cache();
+        let verifiers = BTreeSet::new();
+        let ctx = Ctx::new(
+            &ADDRESS,
+            &s.storage,
+            &s.write_log,
+            &tx,
+            &tx_index,
+            gas_meter,
+            keys,
+            &verifiers,
+            vp_wasm_cache,
+        );
+        MaspVp { ctx }.valid_shielded_action(shielded_tx, keys)
+    }
+
+    /// Test that a sweep shielding several tokens from the same source is
+    /// accepted
+    #[test]
+    fn test_multi_token_shielding_sweep() {
+        let source = established_address_1();
+        let mut s = init_storage(&[&source]);
+        let mut keys = BTreeSet::new();
+        transfer(&mut s, &mut keys, &nam(), &source, &ADDRESS, 10);
+        transfer(&mut s, &mut keys, &btc(), &source, &ADDRESS, 20);
+        let tx = shielding_tx(
+            &mut s,
+            &[(&source, &nam(), 10), (&source, &btc(), 20)],
+            &[(&nam(), 10), (&btc(), 20)],
+        );
+
+        assert!(validate(&s, &keys, &tx).unwrap());
+    }
+
+    /// Test that a sweep whose transparent inputs don't match the transfer
+    /// of each token is rejected, even if the totals match
+    #[test]
+    fn test_shielding_sweep_value_mismatch() {
+        let source = established_address_1();
+        let mut s = init_storage(&[&source]);
+        let mut keys = BTreeSet::new();
+        transfer(&mut s, &mut keys, &nam(), &source, &ADDRESS, 10);
+        transfer(&mut s, &mut keys, &btc(), &source, &ADDRESS, 20);
+        let tx = shielding_tx(
+            &mut s,
+            &[(&source, &nam(), 20), (&source, &btc(), 10)],
+            &[(&nam(), 20), (&btc(), 10)],
+        );
+
+        assert!(!validate(&s, &keys, &tx).unwrap());
+    }
+
+    /// Test that shielding several tokens from different sources is rejected
+    #[test]
+    fn test_shielding_sweep_mixed_sources() {
+        let source = established_address_1();
+        let other = established_address_2();
+        let mut s = init_storage(&[&source, &other]);
+        let mut keys = BTreeSet::new();
+        transfer(&mut s, &mut keys, &nam(), &source, &ADDRESS, 10);
+        transfer(&mut s, &mut keys, &btc(), &other, &ADDRESS, 20);
+        let tx = shielding_tx(
+            &mut s,
+            &[(&source, &nam(), 10), (&other, &btc(), 20)],
+            &[(&nam(), 10), (&btc(), 20)],
+        );
+
+        assert!(validate(&s, &keys, &tx).is_err());
+    }
+
+    /// Test that unshielding several tokens is rejected
+    #[test]
+    fn test_multi_token_unshielding() {
+        let target = established_address_1();
+        let mut s = init_storage(&[&target]);
+        let mut keys = BTreeSet::new();
+        transfer(&mut s, &mut keys, &nam(), &ADDRESS, &target, 10);
+        transfer(&mut s, &mut keys, &btc(), &ADDRESS, &target, 20);
+        let tx = shielding_tx(
+            &mut s,
+            &[(&target, &nam(), 10), (&target, &btc(), 20)],
+            &[(&nam(), 10), (&btc(), 20)],
+        );
+
+        assert!(validate(&s, &keys, &tx).is_err());
+    }
+
+    /// Test that a change to the MASP balance of a token without the
+    /// matching change to a transparent balance of this token is rejected
+    #[test]
+    fn test_shielding_missing_counterpart() {
+        let source = established_address_1();
+        let other = established_address_2();
+        let inputs = [(&source, &nam(), 10), (&source, &btc(), 20)];
+        let outputs = [(&nam(), 10), (&btc(), 20)];
+        let init = || {
+            let mut s = init_storage(&[&source, &other]);
+            let mut keys = BTreeSet::new();
+            transfer(&mut s, &mut keys, &nam(), &source, &ADDRESS, 10);
+            let key = balance_key(&btc(), &ADDRESS);
+            s.write(&key, Amount::from_u64(120)).unwrap();
+            keys.insert(key);
+            (s, keys)
+        };
+
+        // The MASP BTC balance changes without any counterpart
+        let (mut s, keys) = init();
+        let tx = shielding_tx(&mut s, &inputs, &outputs);
+        assert!(validate(&s, &keys, &tx).is_err());
+
+        // The only other transparent balance changed is of another token
+        let (mut s, mut keys) = init();
+        let key = balance_key(&eth(), &other);
+        s.write(&key, Amount::from_u64(80)).unwrap();
+        keys.insert(key);
+        let tx = shielding_tx(&mut s, &inputs, &outputs);
+        assert!(validate(&s, &keys, &tx).is_err());
+    }
+
+    /// Test that a transaction initializing a proposal can't also debit the
+    /// MASP balance without a valid shielded action
+    #[test]
+    fn test_proposal_with_masp_debit() {
+        let target = established_address_1();
+        let mut s = init_storage(&[&target]);
+        let mut keys = BTreeSet::new();
+        let proposal_key =
+            namada_governance::storage::keys::get_proposal_type_key(0);
+        s.write(&proposal_key, ProposalType::Text).unwrap();
+        keys.insert(proposal_key);
+        transfer(&mut s, &mut keys, &nam(), &ADDRESS, &target, 10);
+
+        let tx_index = TxIndex::default();
+        let tx = Tx::from_type(TxType::Raw);
+        let gas_meter = VpGasMeter::new_from_tx_meter(
+            &TxGasMeter::new_from_sub_limit(u64::MAX.into()),
+        );
+        let (vp_wasm_cache, _vp_cache_dir) = wasm_cache();
+        let verifiers = BTreeSet::new();
+        let ctx = Ctx::new(
+            &ADDRESS,
+            &s.storage,
+            &s.write_log,
+            &tx,
+            &tx_index,
+            gas_meter,
+            &keys,
+            &verifiers,
+            vp_wasm_cache,
+        );
+        let res = MaspVp { ctx }.validate_tx(&tx, &keys, &verifiers);
+        assert!(!matches!(res, Ok(true)));
+    }
+}
@@ -537,6 +537,9 @@ where
                 PrefixIterators::default();
             let mut result_buffer: Option<Vec<u8>> = None;
             let mut vp_wasm_cache = self.vp_wasm_cache.clone();
+            // Native VPs don't hold a tx compilation cache, so the VPs they
+            // evaluate can't simulate sub-transactions
+            let mut tx_wasm_cache = None;
 
             let ctx = VpCtx::new(
                 self.address,
@@ -552,6 +555,7 @@ where
                 self.keys_changed,
                 &eval_runner,
                 &mut vp_wasm_cache,
+                &mut tx_wasm_cache,
             );
             match eval_runner.eval_native_result(ctx, vp_code_hash, input_data)
             {
@@ -2,7 +2,8 @@
 
 use std::collections::{BTreeSet, HashMap};
 
-use namada_governance::is_proposal_accepted;
+use namada_governance::storage::proposal::HaltedSubsystem;
+use namada_governance::{is_proposal_accepted, is_subsystem_halted};
 use namada_token::storage_key::is_any_token_parameter_key;
 use namada_tx::Tx;
 use namada_vp_env::VpEnv;
@@ -10,8 +11,8 @@ use thiserror::Error;
 
 use crate::ledger::native_vp::{self, Ctx, NativeVp};
 use crate::token::storage_key::{
-    is_any_minted_balance_key, is_any_minter_key, is_any_token_balance_key,
-    minter_key,
+    is_any_balance_key, is_any_metadata_key, is_any_minted_balance_key,
+    is_any_minter_key, minter_key,
 };
 use crate::token::Amount;
 use crate::types::address::{Address, InternalAddress};
@@ -58,7 +59,7 @@ where
         let mut inc_mints: HashMap<Address, Amount> = HashMap::new();
         let mut dec_mints: HashMap<Address, Amount> = HashMap::new();
         for key in keys_changed {
-            if let Some([token, _]) = is_any_token_balance_key(key) {
+            if let Some((token, _)) = is_any_balance_key(key) {
                 let pre: Amount = self.ctx.read_pre(key)?.unwrap_or_default();
                 let post: Amount = self.ctx.read_post(key)?.unwrap_or_default();
                 match post.checked_sub(pre) {
@@ -126,6 +127,18 @@ where
                 if !self.is_valid_minter(token, verifiers)? {
                     return Ok(false);
                 }
+            } else if let Some(token) = is_any_metadata_key(key) {
+                // The metadata of an IBC token is written by its minter when
+                // the token is received, the others by governance
+                let is_valid = match token {
+                    Address::Internal(InternalAddress::IbcToken(_)) => {
+                        self.is_valid_minter(token, verifiers)?
+                    }
+                    _ => self.is_valid_parameter(tx_data)?,
+                };
+                if !is_valid {
+                    return Ok(false);
+                }
             } else if is_any_token_parameter_key(key).is_some() {
                 return self.is_valid_parameter(tx_data);
             } else if key.segments.get(0)
@@ -145,6 +158,17 @@ where
         all_tokens.extend(inc_mints.keys().cloned());
         all_tokens.extend(dec_mints.keys().cloned());
 
+        for token in &all_tokens {
+            let subsystem = HaltedSubsystem::Token(token.clone());
+            if is_subsystem_halted(&self.ctx.pre(), &subsystem)? {
+                tracing::debug!(
+                    "Transfers of the token {token} are halted by a \
+                     governance proposal"
+                );
+                return Ok(false);
+            }
+        }
+
         Ok(all_tokens.iter().all(|token| {
             let inc_change =
                 inc_changes.get(token).cloned().unwrap_or_default();
@@ -218,6 +242,7 @@ mod tests {
 
     use borsh_ext::BorshSerializeExt;
     use namada_gas::TxGasMeter;
+    use namada_governance::storage::keys::get_halt_key;
     use namada_state::testing::TestWlStorage;
     use namada_tx::data::TxType;
     use namada_tx::{Code, Data, Section, Signature, Tx};
@@ -227,15 +252,16 @@ mod tests {
     use crate::core::types::address::testing::{
         established_address_1, established_address_2,
     };
+    use crate::core::types::ibc::IbcDenomTrace;
     use crate::ledger::gas::VpGasMeter;
     use crate::ledger::ibc::storage::ibc_token;
     use crate::token::storage_key::{
-        balance_key, minted_balance_key, minter_key,
+        balance_key, metadata_key, minted_balance_key, minter_key,
     };
     use crate::token::Amount;
     use crate::types::address::{Address, InternalAddress};
     use crate::types::key::testing::keypair_1;
-    use crate::types::storage::TxIndex;
+    use crate::types::storage::{Epoch, TxIndex};
     use crate::vm::wasm::compilation_cache::common::testing::cache as wasm_cache;
 
     const ADDRESS: Address = Address::Internal(InternalAddress::Multitoken);
@@ -367,6 +393,69 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_halted_token_transfer() {
+        let mut wl_storage = TestWlStorage::default();
+        let mut keys_changed = BTreeSet::new();
+
+        // halt the token until the next epoch
+        let halt_key = get_halt_key(&HaltedSubsystem::Token(nam()));
+        wl_storage
+            .storage
+            .write(&halt_key, Epoch(1).serialize_to_vec())
+            .expect("write failed");
+
+        let sender = established_address_1();
+        let sender_key = balance_key(&nam(), &sender);
+        let amount = Amount::native_whole(100);
+        wl_storage
+            .storage
+            .write(&sender_key, amount.serialize_to_vec())
+            .expect("write failed");
+
+        // transfer 10
+        let amount = Amount::native_whole(90);
+        wl_storage
+            .write_log
+            .write(&sender_key, amount.serialize_to_vec())
+            .expect("write failed");
+        keys_changed.insert(sender_key);
+        let receiver = established_address_2();
+        let receiver_key = balance_key(&nam(), &receiver);
+        let amount = Amount::native_whole(10);
+        wl_storage
+            .write_log
+            .write(&receiver_key, amount.serialize_to_vec())
+            .expect("write failed");
+        keys_changed.insert(receiver_key);
+
+        let tx_index = TxIndex::default();
+        let tx = dummy_tx(&wl_storage);
+        let gas_meter = VpGasMeter::new_from_tx_meter(
+            &TxGasMeter::new_from_sub_limit(u64::MAX.into()),
+        );
+        let (vp_wasm_cache, _vp_cache_dir) = wasm_cache();
+        let mut verifiers = BTreeSet::new();
+        verifiers.insert(sender);
+        let ctx = Ctx::new(
+            &ADDRESS,
+            &wl_storage.storage,
+            &wl_storage.write_log,
+            &tx,
+            &tx_index,
+            gas_meter,
+            &keys_changed,
+            &verifiers,
+            vp_wasm_cache,
+        );
+
+        let vp = MultitokenVp { ctx };
+        assert!(
+            !vp.validate_tx(&tx, &keys_changed, &verifiers)
+                .expect("validation failed")
+        );
+    }
+
     #[test]
     fn test_valid_mint() {
         let mut wl_storage = TestWlStorage::default();
@@ -688,4 +777,63 @@ mod tests {
                 .expect("validation failed")
         );
     }
+
+    #[test]
+    fn test_ibc_token_metadata() {
+        let mut wl_storage = TestWlStorage::default();
+        let mut keys_changed = BTreeSet::new();
+
+        let denom = "transfer/channel-0/uatom";
+        let token = ibc_token(denom);
+        let metadata_key = metadata_key(&token);
+        let metadata = IbcDenomTrace::from_ibc_denom(denom).unwrap();
+        wl_storage
+            .write_log
+            .write(&metadata_key, metadata.serialize_to_vec())
+            .expect("write failed");
+        keys_changed.insert(metadata_key);
+        let minter_key = minter_key(&token);
+        wl_storage
+            .write_log
+            .write(
+                &minter_key,
+                Address::Internal(InternalAddress::Ibc).serialize_to_vec(),
+            )
+            .expect("write failed");
+        keys_changed.insert(minter_key);
+
+        // The metadata is only valid when written by the minter
+        for (verifiers, expected) in [
+            (BTreeSet::new(), false),
+            (
+                BTreeSet::from([Address::Internal(InternalAddress::Ibc)]),
+                true,
+            ),
+        ] {
+            let tx_index = TxIndex::default();
+            let tx = dummy_tx(&wl_storage);
+            let gas_meter = VpGasMeter::new_from_tx_meter(
+                &TxGasMeter::new_from_sub_limit(u64::MAX.into()),
+            );
+            let (vp_wasm_cache, _vp_cache_dir) = wasm_cache();
+            let ctx = Ctx::new(
+                &ADDRESS,
+                &wl_storage.storage,
+                &wl_storage.write_log,
+                &tx,
+                &tx_index,
+                gas_meter,
+                &keys_changed,
+                &verifiers,
+                vp_wasm_cache,
+            );
+
+            let vp = MultitokenVp { ctx };
+            assert_eq!(
+                vp.validate_tx(&tx, &keys_changed, &verifiers)
+                    .expect("validation failed"),
+                expected
+            );
+        }
+    }
 }
@@ -53,7 +53,7 @@ where
                 return false;
             };
             match key_type {
-                KeyType::PARAMETER => {
+                KeyType::PARAMETER | KeyType::WASM_REGISTRY => {
                     namada_governance::storage::is_proposal_accepted(
                         &self.ctx.pre(),
                         &data,
@@ -80,6 +80,9 @@ enum KeyType {
     PARAMETER,
     #[allow(clippy::upper_case_acronyms)]
     #[allow(non_camel_case_types)]
+    WASM_REGISTRY,
+    #[allow(clippy::upper_case_acronyms)]
+    #[allow(non_camel_case_types)]
     UNKNOWN_PARAMETER,
     #[allow(clippy::upper_case_acronyms)]
     UNKNOWN,
@@ -89,6 +92,8 @@ impl From<&Key> for KeyType {
     fn from(value: &Key) -> Self {
         if namada_parameters::storage::is_protocol_parameter_key(value) {
             KeyType::PARAMETER
+        } else if value.is_wasm_key() {
+            KeyType::WASM_REGISTRY
         } else if namada_parameters::storage::is_parameter_key(value) {
             KeyType::UNKNOWN_PARAMETER
         } else {
@@ -6,8 +6,11 @@ pub mod utils;
 use std::collections::BTreeSet;
 
 use namada_governance::pgf::storage::keys as pgf_storage;
+use namada_governance::storage::keys as gov_storage;
+use namada_governance::storage::proposal::{PGFAction, ProposalType};
 use namada_governance::{is_proposal_accepted, pgf};
 use namada_tx::Tx;
+use namada_vp_env::VpEnv;
 use thiserror::Error;
 
 use crate::ledger::native_vp;
@@ -54,6 +57,12 @@ where
         keys_changed: &BTreeSet<Key>,
         verifiers: &BTreeSet<Address>,
     ) -> Result<bool> {
+        if keys_changed.iter().any(gov_storage::is_proposal_type_key)
+            && !self.valid_steward_payment_proposals(keys_changed)?
+        {
+            return Ok(false);
+        }
+
         let result = keys_changed.iter().all(|key| {
             let key_type = KeyType::from(key);
 
@@ -97,7 +106,14 @@ where
 
                     Ok(is_valid)
                 }
+                KeyType::STEWARD_SPENDINGS(address) => {
+                    self.is_valid_steward_resignation(key, &address, verifiers)
+                }
                 KeyType::FUNDINGS => Ok(false),
+                KeyType::GRANTS(grant_id) => {
+                    self.is_valid_milestone_attestation(grant_id, verifiers)
+                }
+                KeyType::GRANT_COUNTER => Ok(false),
                 KeyType::PGF_INFLATION_RATE
                 | KeyType::STEWARD_INFLATION_RATE => {
                     self.is_valid_parameter_change(tx_data)
@@ -125,6 +141,105 @@ where
             None => Ok(false),
         }
     }
+
+    /// Validate the pgf payment proposals initialized by the transaction. The
+    /// retro payments of a proposal authored by a steward must fit in the
+    /// remaining spending cap of the steward.
+    pub fn valid_steward_payment_proposals(
+        &self,
+        keys_changed: &BTreeSet<Key>,
+    ) -> Result<bool> {
+        for key in keys_changed
+            .iter()
+            .filter(|key| gov_storage::is_proposal_type_key(key))
+        {
+            let fundings = match self.ctx.read_post::<ProposalType>(key)? {
+                Some(ProposalType::PGFPayment(fundings)) => fundings,
+                _ => continue,
+            };
+            let proposal_id = match gov_storage::get_proposal_id(key) {
+                Some(id) => id,
+                None => return Ok(false),
+            };
+            let author_key = gov_storage::get_author_key(proposal_id);
+            let author = match self.ctx.read_post::<Address>(&author_key)? {
+                Some(author) => author,
+                None => return Ok(false),
+            };
+            if !pgf::storage::is_steward(&self.ctx.pre(), &author)? {
+                continue;
+            }
+
+            let spending =
+                pgf::storage::get_steward_spending(&self.ctx.pre(), &author)?;
+            let amount = PGFAction::total_retro_amount(&fundings);
+            if !spending.can_spend(amount) {
+                tracing::debug!(
+                    "The retro payments of proposal {proposal_id} exceed the \
+                     spending cap of the steward {author}"
+                );
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Validate a change to the spending accounting of a steward. The
+    /// accounting can only be updated by the protocol, so the only valid
+    /// change is dropping it when the steward resigns.
+    pub fn is_valid_steward_resignation(
+        &self,
+        key: &Key,
+        steward: &Address,
+        verifiers: &BTreeSet<Address>,
+    ) -> Result<bool> {
+        let is_removed = !self.ctx.has_key_post(key)?
+            && !pgf::storage::is_steward(&self.ctx.post(), steward)?;
+
+        Ok(is_removed && verifiers.contains(steward))
+    }
+
+    /// Validate the attestation of the next milestone of a grant. Grants can
+    /// only be created and released by the protocol, so the only valid change
+    /// is a steward appending itself to the attestations.
+    pub fn is_valid_milestone_attestation(
+        &self,
+        grant_id: u64,
+        verifiers: &BTreeSet<Address>,
+    ) -> Result<bool> {
+        let grant_pre = pgf::storage::get_grant(&self.ctx.pre(), grant_id)?;
+        let grant_post = pgf::storage::get_grant(&self.ctx.post(), grant_id)?;
+
+        let (grant_pre, grant_post) = match (grant_pre, grant_post) {
+            (Some(pre), Some(post)) => (pre, post),
+            _ => return Ok(false),
+        };
+
+        let is_valid_milestone = grant_pre.next_milestone().is_some()
+            && grant_post.attestations.len()
+                == grant_pre.attestations.len() + 1;
+        let is_unchanged = grant_post.proposal_id == grant_pre.proposal_id
+            && grant_post.target == grant_pre.target
+            && grant_post.milestones == grant_pre.milestones
+            && grant_post.released == grant_pre.released
+            && grant_post.attestations.starts_with(&grant_pre.attestations);
+        if !(is_valid_milestone && is_unchanged) {
+            return Ok(false);
+        }
+
+        // the attestation must be signed by a steward other than the target
+        let steward = match grant_post.attestations.last() {
+            Some(steward) => steward,
+            None => return Ok(false),
+        };
+        if steward == &grant_post.target {
+            return Ok(false);
+        }
+        let is_steward = pgf::storage::is_steward(&self.ctx.pre(), steward)?;
+
+        Ok(is_steward && verifiers.contains(steward))
+    }
 }
 
 #[allow(clippy::upper_case_acronyms)]
@@ -133,8 +248,14 @@ enum KeyType {
     #[allow(non_camel_case_types)]
     STEWARDS,
     #[allow(non_camel_case_types)]
+    STEWARD_SPENDINGS(Address),
+    #[allow(non_camel_case_types)]
     FUNDINGS,
     #[allow(non_camel_case_types)]
+    GRANTS(u64),
+    #[allow(non_camel_case_types)]
+    GRANT_COUNTER,
+    #[allow(non_camel_case_types)]
     PGF_INFLATION_RATE,
     #[allow(non_camel_case_types)]
     STEWARD_INFLATION_RATE,
@@ -148,8 +269,15 @@ impl From<&Key> for KeyType {
     fn from(key: &Key) -> Self {
         if pgf_storage::is_stewards_key(key).is_some() {
             Self::STEWARDS
+        } else if let Some(address) = pgf_storage::is_steward_spendings_key(key)
+        {
+            KeyType::STEWARD_SPENDINGS(address.clone())
         } else if pgf_storage::is_fundings_key(key) {
             KeyType::FUNDINGS
+        } else if let Some(grant_id) = pgf_storage::is_grants_key(key) {
+            KeyType::GRANTS(grant_id)
+        } else if pgf_storage::is_grant_counter_key(key) {
+            KeyType::GRANT_COUNTER
         } else if pgf_storage::is_pgf_inflation_rate_key(key) {
             Self::PGF_INFLATION_RATE
         } else if pgf_storage::is_steward_inflation_rate_key(key) {
@@ -161,3 +289,174 @@ impl From<&Key> for KeyType {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use borsh_ext::BorshSerializeExt;
+    use namada_gas::TxGasMeter;
+    use namada_governance::pgf::storage::grant::GrantDetail;
+    use namada_governance::pgf::storage::steward::StewardDetail;
+    use namada_governance::storage::proposal::PGFGrant;
+    use namada_state::testing::TestWlStorage;
+    use namada_tx::data::TxType;
+
+    use super::*;
+    use crate::core::types::address::testing::{
+        established_address_1, established_address_2, established_address_3,
+    };
+    use crate::ledger::gas::VpGasMeter;
+    use crate::token::Amount;
+    use crate::types::storage::TxIndex;
+    use crate::vm::wasm::compilation_cache::common::testing::cache as wasm_cache;
+
+    const GRANT_ID: u64 = 0;
+
+    /// Init the storage with the given stewards and a grant of two milestones
+    /// to the target
+    fn init_storage(stewards: &[&Address], target: &Address) -> TestWlStorage {
+        let mut wl_storage = TestWlStorage::default();
+        for steward in stewards {
+            let key = pgf_storage::stewards_handle().get_data_key(steward);
+            let detail = StewardDetail::base((*steward).clone());
+            wl_storage
+                .storage
+                .write(&key, detail.serialize_to_vec())
+                .expect("write failed");
+        }
+        let grant = GrantDetail::new(
+            0,
+            PGFGrant {
+                target: target.clone(),
+                milestones: vec![
+                    Amount::native_whole(10),
+                    Amount::native_whole(20),
+                ],
+            },
+        );
+        let key = pgf_storage::grants_handle().get_data_key(&GRANT_ID);
+        wl_storage
+            .storage
+            .write(&key, grant.serialize_to_vec())
+            .expect("write failed");
+        wl_storage
+    }
+
+    /// Write the post state of the grant
+    fn write_grant(
+        wl_storage: &mut TestWlStorage,
+        update: impl FnOnce(&mut GrantDetail),
+    ) {
+        let mut grant = pgf::storage::get_grant(&*wl_storage, GRANT_ID)
+            .unwrap()
+            .unwrap();
+        update(&mut grant);
+        pgf_storage::grants_handle()
+            .insert(wl_storage, GRANT_ID, grant)
+            .unwrap();
+    }
+
+    /// Run the pgf VP on the changes of the write log
+    fn validate(
+        wl_storage: &TestWlStorage,
+        verifiers: BTreeSet<Address>,
+    ) -> bool {
+        let (_, keys_changed) =
+            wl_storage.write_log.verifiers_and_changed_keys(&verifiers);
+        let mut tx = Tx::from_type(TxType::Raw);
+        tx.header.chain_id = wl_storage.storage.chain_id.clone();
+        let tx_index = TxIndex::default();
+        let gas_meter = VpGasMeter::new_from_tx_meter(
+            &TxGasMeter::new_from_sub_limit(u64::MAX.into()),
+        );
+        let (vp_wasm_cache, _vp_cache_dir) = wasm_cache();
+        let ctx = Ctx::new(
+            &ADDRESS,
+            &wl_storage.storage,
+            &wl_storage.write_log,
+            &tx,
+            &tx_index,
+            gas_meter,
+            &keys_changed,
+            &verifiers,
+            vp_wasm_cache,
+        );
+        let vp = PgfVp { ctx };
+        vp.validate_tx(&tx, &keys_changed, &verifiers)
+            .expect("validation failed")
+    }
+
+    #[test]
+    fn test_valid_milestone_attestation() {
+        let steward = established_address_1();
+        let target = established_address_2();
+        let mut wl_storage = init_storage(&[&steward], &target);
+
+        pgf::storage::attest_milestone(
+            &mut wl_storage,
+            steward.clone(),
+            GRANT_ID,
+            0,
+        )
+        .unwrap();
+        assert!(validate(&wl_storage, BTreeSet::from([steward])));
+        // The steward must be a verifier to check its signature
+        assert!(!validate(&wl_storage, BTreeSet::new()));
+    }
+
+    #[test]
+    fn test_milestone_attestation_by_non_steward() {
+        let steward = established_address_1();
+        let target = established_address_2();
+        let other = established_address_3();
+        let mut wl_storage = init_storage(&[&steward], &target);
+
+        write_grant(&mut wl_storage, |grant| {
+            grant.attestations.push(other.clone())
+        });
+        assert!(!validate(&wl_storage, BTreeSet::from([other])));
+    }
+
+    #[test]
+    fn test_milestone_attestation_by_target() {
+        let target = established_address_1();
+        let mut wl_storage = init_storage(&[&target], &target);
+
+        write_grant(&mut wl_storage, |grant| {
+            grant.attestations.push(target.clone())
+        });
+        assert!(!validate(&wl_storage, BTreeSet::from([target])));
+    }
+
+    #[test]
+    fn test_skipped_milestone_attestation() {
+        let steward = established_address_1();
+        let target = established_address_2();
+        let mut wl_storage = init_storage(&[&steward], &target);
+
+        write_grant(&mut wl_storage, |grant| {
+            grant.attestations.push(steward.clone());
+            grant.attestations.push(steward.clone());
+        });
+        assert!(!validate(&wl_storage, BTreeSet::from([steward])));
+    }
+
+    #[test]
+    fn test_tampered_milestone_attestation() {
+        let steward = established_address_1();
+        let target = established_address_2();
+        let tamperings: [Box<dyn Fn(&mut GrantDetail)>; 4] = [
+            Box::new(|grant| grant.milestones[1] = Amount::native_whole(200)),
+            Box::new(|grant| grant.target = established_address_3()),
+            Box::new(|grant| grant.released = 1),
+            Box::new(|grant| grant.proposal_id = 1),
+        ];
+        for tamper in tamperings {
+            let mut wl_storage = init_storage(&[&steward], &target);
+            write_grant(&mut wl_storage, |grant| {
+                grant.attestations.push(steward.clone());
+                tamper(grant);
+            });
+            assert!(!validate(&wl_storage, BTreeSet::from([steward.clone()])));
+        }
+    }
+}
@@ -1,8 +1,9 @@
 use std::collections::HashMap;
 
 use namada_core::types::address::Address;
+use namada_governance::pgf::storage::grant::GrantRelease;
 
-use crate::ledger::events::EventType;
+use crate::ledger::events::{Event, EventLevel, EventType};
 use crate::token;
 
 /// Proposal event definition
@@ -13,6 +14,16 @@ pub struct ProposalEvent {
     pub attributes: HashMap<String, String>,
 }
 
+impl From<ProposalEvent> for Event {
+    fn from(proposal_event: ProposalEvent) -> Self {
+        Self {
+            event_type: EventType::PgfPayment,
+            level: EventLevel::Block,
+            attributes: proposal_event.attributes,
+        }
+    }
+}
+
 impl ProposalEvent {
     /// Create a proposal event
     pub fn new(
@@ -63,4 +74,22 @@ impl ProposalEvent {
             success,
         )
     }
+
+    /// Create a new proposal event for the release of a grant milestone
+    pub fn pgf_grant_release(release: GrantRelease) -> Self {
+        let mut event = ProposalEvent::new(
+            EventType::PgfPayment.to_string(),
+            release.target,
+            release.amount,
+            false,
+            true,
+        );
+        event
+            .attributes
+            .insert("grant_id".to_string(), release.grant_id.to_string());
+        event
+            .attributes
+            .insert("milestone".to_string(), release.milestone.to_string());
+        event
+    }
 }
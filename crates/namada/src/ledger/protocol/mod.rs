@@ -1,6 +1,8 @@
 //! The ledger's protocol
 use std::collections::BTreeSet;
+use std::sync::Mutex;
 
+use borsh::BorshDeserialize;
 use borsh_ext::BorshSerializeExt;
 use eyre::{eyre, WrapErr};
 use masp_primitives::transaction::Transaction;
@@ -12,13 +14,15 @@ use namada_state::wl_storage::WriteLogAndStorage;
 use namada_state::StorageRead;
 use namada_tx::data::protocol::ProtocolTxType;
 use namada_tx::data::{
-    DecryptedTx, GasLimit, TxResult, TxType, VpsResult, WrapperTx,
+    DecryptedTx, GasLimit, TxResult, TxType, VpsResult, WasmError,
+    WasmErrorCode, WrapperTx,
 };
 use namada_tx::{Section, Tx};
 use namada_vote_ext::EthereumTxData;
 use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
 use thiserror::Error;
 
+use crate::account;
 use crate::ledger::gas::{GasMetering, VpGasMeter};
 use crate::ledger::governance::GovernanceVp;
 use crate::ledger::native_vp::ethereum_bridge::bridge_pool_vp::BridgePoolVp;
@@ -31,7 +35,7 @@ use crate::ledger::native_vp::parameters::{self, ParametersVp};
 use crate::ledger::native_vp::{self, NativeVp};
 use crate::ledger::pgf::PgfVp;
 use crate::ledger::pos::{self, PosVP};
-use crate::state::write_log::WriteLog;
+use crate::state::write_log::{CachedVpResult, WriteLog};
 use crate::state::{DBIter, State, StorageHasher, WlStorage, DB};
 use crate::token::Amount;
 use crate::types::address::{Address, InternalAddress};
@@ -136,6 +140,25 @@ where
 /// Result of applying a transaction
 pub type Result<T> = std::result::Result<T, Error>;
 
+impl Error {
+    /// Get the protocol-defined code and the size-limited message of the
+    /// error to be reported in a tx result
+    pub fn to_wasm_error(&self) -> WasmError {
+        match self {
+            Error::TxRunnerError(err) | Error::VpRunnerError(err) => {
+                err.to_wasm_error()
+            }
+            Error::GasError(_) => {
+                WasmError::new(WasmErrorCode::OutOfGas, self.to_string())
+            }
+            Error::InvalidTxSignature => {
+                WasmError::new(WasmErrorCode::InvalidSig, self.to_string())
+            }
+            _ => WasmError::new(WasmErrorCode::Other, self.to_string()),
+        }
+    }
+}
+
 /// Arguments needed to execute a Wrapper transaction
 pub struct WrapperArgs<'a> {
     /// The block proposer for the current block
@@ -205,6 +228,7 @@ where
                 initialized_accounts: vec![],
                 ibc_events: BTreeSet::default(),
                 eth_bridge_events: BTreeSet::default(),
+                tx_events: vec![],
             })
         }
         TxType::Decrypted(DecryptedTx::Undecryptable) => {
@@ -618,21 +642,83 @@ where
         write_log,
         verifiers_from_tx: &verifiers,
         vp_wasm_cache,
+        tx_wasm_cache,
+    })?;
+
+    Ok(tx_result(tx_gas_meter, write_log, vps_result))
+}
+
+/// Simulate the application of a wasm transaction on a fork of the given
+/// write log, which is left untouched. The transaction is executed and
+/// checked by the triggered validity predicates as in [`apply_wasm_tx`], but
+/// none of its storage modifications are committed. Returns the would-be
+/// result of the transaction.
+pub fn simulate_tx<D, H, CA>(
+    tx: &Tx,
+    tx_index: &TxIndex,
+    storage: &State<D, H>,
+    write_log: &WriteLog,
+    tx_gas_meter: &mut TxGasMeter,
+    vp_wasm_cache: &mut VpCache<CA>,
+    tx_wasm_cache: &mut TxCache<CA>,
+) -> Result<TxResult>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+    CA: 'static + WasmCacheAccess + Sync,
+{
+    let tx_hash = tx.raw_header_hash();
+    if let Some(true) = write_log.has_replay_protection_entry(&tx_hash) {
+        return Err(Error::ReplayAttempt(tx_hash));
+    }
+
+    let (mut fork, verifiers) = wasm::run::simulate_tx(
+        storage,
+        write_log,
+        tx_gas_meter,
+        tx_index,
+        tx,
+        vp_wasm_cache,
+        tx_wasm_cache,
+        0,
+    )
+    .map_err(tx_runner_error)?;
+
+    let vps_result = check_vps(CheckVps {
+        tx,
+        tx_index,
+        storage,
+        tx_gas_meter,
+        write_log: &mut fork,
+        verifiers_from_tx: &verifiers,
+        vp_wasm_cache,
+        tx_wasm_cache,
     })?;
 
+    Ok(tx_result(tx_gas_meter, &mut fork, vps_result))
+}
+
+/// Collect the result of a wasm transaction from its current write log
+fn tx_result(
+    tx_gas_meter: &TxGasMeter,
+    write_log: &mut WriteLog,
+    vps_result: VpsResult,
+) -> TxResult {
     let gas_used = tx_gas_meter.get_tx_consumed_gas();
     let initialized_accounts = write_log.get_initialized_accounts();
     let changed_keys = write_log.get_keys();
     let ibc_events = write_log.take_ibc_events();
+    let tx_events = write_log.take_tx_events();
 
-    Ok(TxResult {
+    TxResult {
         gas_used,
         changed_keys,
         vps_result,
         initialized_accounts,
         ibc_events,
         eth_bridge_events: BTreeSet::default(),
-    })
+        tx_events,
+    }
 }
 
 /// Returns [`Error::DisallowedTx`] when the given tx is inner (decrypted) tx
@@ -764,11 +850,16 @@ where
         vp_wasm_cache,
         tx_wasm_cache,
     )
-    .map_err(|err| match err {
+    .map_err(tx_runner_error)
+}
+
+/// Convert an error of the execution of a transaction code
+fn tx_runner_error(err: wasm::run::Error) -> Error {
+    match err {
         wasm::run::Error::GasError(msg) => Error::GasError(msg),
         wasm::run::Error::MissingSection(msg) => Error::MissingSection(msg),
         _ => Error::TxRunnerError(err),
-    })
+    }
 }
 
 /// Arguments to [`check_vps`].
@@ -782,9 +873,10 @@ where
     tx_index: &'a TxIndex,
     storage: &'a State<D, H>,
     tx_gas_meter: &'a mut TxGasMeter,
-    write_log: &'a WriteLog,
+    write_log: &'a mut WriteLog,
     verifiers_from_tx: &'a BTreeSet<Address>,
     vp_wasm_cache: &'a mut VpCache<CA>,
+    tx_wasm_cache: &'a mut TxCache<CA>,
 }
 
 /// Check the acceptance of a transaction by validity predicates
@@ -797,6 +889,7 @@ fn check_vps<D, H, CA>(
         write_log,
         verifiers_from_tx,
         vp_wasm_cache,
+        tx_wasm_cache,
     }: CheckVps<'_, D, H, CA>,
 ) -> Result<VpsResult>
 where
@@ -807,7 +900,7 @@ where
     let (verifiers, keys_changed) =
         write_log.verifiers_and_changed_keys(verifiers_from_tx);
 
-    let vps_result = execute_vps(
+    let (vps_result, vp_results) = execute_vps(
         verifiers,
         keys_changed,
         tx,
@@ -816,8 +909,10 @@ where
         write_log,
         tx_gas_meter,
         vp_wasm_cache,
+        tx_wasm_cache,
     )?;
     tracing::debug!("Total VPs gas cost {:?}", vps_result.gas_used);
+    write_log.cache_vp_results(vp_results);
 
     tx_gas_meter
         .add_vps_gas(&vps_result.gas_used)
@@ -826,7 +921,10 @@ where
     Ok(vps_result)
 }
 
-/// Execute verifiers' validity predicates
+/// Execute verifiers' validity predicates. The results of the wasm VPs run
+/// with the same inputs earlier in the block are reused instead of running the
+/// VPs again. Returns the results of the wasm VPs that had to be run, to be
+/// cached for the rest of the block.
 #[allow(clippy::too_many_arguments)]
 fn execute_vps<D, H, CA>(
     verifiers: BTreeSet<Address>,
@@ -837,12 +935,30 @@ fn execute_vps<D, H, CA>(
     write_log: &WriteLog,
     tx_gas_meter: &TxGasMeter,
     vp_wasm_cache: &mut VpCache<CA>,
-) -> Result<VpsResult>
+    tx_wasm_cache: &mut TxCache<CA>,
+) -> Result<(VpsResult, Vec<(Hash, CachedVpResult)>)>
 where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
     H: 'static + StorageHasher + Sync,
     CA: 'static + WasmCacheAccess + Sync,
 {
+    // The inputs shared by all the VPs triggered by the tx. The VPs can read
+    // any section of the tx, its index and the block entropy derived from it
+    // and any storage key, so the results are only reused for the same tx at
+    // the same index. The write log digest guards the cached results against
+    // any change of the storage state that the VPs may read.
+    let vps_inputs_digest = Hash::sha256(
+        [
+            tx.serialize_to_vec(),
+            tx_index.serialize_to_vec(),
+            keys_changed.serialize_to_vec(),
+            verifiers.serialize_to_vec(),
+            write_log.digest().0.to_vec(),
+        ]
+        .concat(),
+    );
+    let new_vp_results = Mutex::new(Vec::new());
+
     let vps_result = verifiers
         .par_iter()
         .try_fold(VpsResult::default, |mut result, addr| {
@@ -858,31 +974,73 @@ where
                     let Some(vp_code_hash) = vp_hash else {
                         return Err(Error::MissingAddress(addr.clone()));
                     };
-
-                    // NOTE: because of the whitelisted gas and the gas
-                    // metering for the exposed vm
-                    // env functions,    the first
-                    // signature verification (if any) is accounted
-                    // twice
-                    wasm::run::vp(
-                        vp_code_hash,
-                        tx,
-                        tx_index,
-                        addr,
+                    let vp_code_hash = effective_vp_code_hash(
                         storage,
-                        write_log,
+                        addr,
+                        vp_code_hash,
                         &mut gas_meter,
-                        &keys_changed,
-                        &verifiers,
-                        vp_wasm_cache.clone(),
-                    )
-                    .map_err(|err| match err {
-                        wasm::run::Error::GasError(msg) => Error::GasError(msg),
-                        wasm::run::Error::InvalidTxSignature => {
-                            Error::InvalidTxSignature
-                        }
-                        _ => Error::VpRunnerError(err),
-                    })
+                    )?;
+
+                    let inputs_digest = Hash::sha256(
+                        [
+                            vps_inputs_digest.0.as_slice(),
+                            vp_code_hash.0.as_slice(),
+                            &addr.serialize_to_vec(),
+                        ]
+                        .concat(),
+                    );
+                    if let Some(cached) =
+                        write_log.get_vp_result(&inputs_digest)
+                    {
+                        // Charge the same gas as the original run
+                        gas_meter
+                            .consume(cached.gas_used.into())
+                            .map_err(|err| Error::GasError(err.to_string()))?;
+                        Ok(cached.accepted)
+                    } else {
+                        let initial_gas = gas_meter.get_vp_consumed_gas();
+                        // NOTE: because of the whitelisted gas and the gas
+                        // metering for the exposed vm
+                        // env functions,    the first
+                        // signature verification (if any) is accounted
+                        // twice
+                        wasm::run::vp(
+                            vp_code_hash,
+                            tx,
+                            tx_index,
+                            addr,
+                            storage,
+                            write_log,
+                            &mut gas_meter,
+                            &keys_changed,
+                            &verifiers,
+                            vp_wasm_cache.clone(),
+                            tx_wasm_cache.clone(),
+                        )
+                        .map_err(|err| match err {
+                            wasm::run::Error::GasError(msg) => {
+                                Error::GasError(msg)
+                            }
+                            wasm::run::Error::InvalidTxSignature => {
+                                Error::InvalidTxSignature
+                            }
+                            _ => Error::VpRunnerError(err),
+                        })
+                        .map(|accepted| {
+                            let gas_used = gas_meter
+                                .get_vp_consumed_gas()
+                                .checked_sub(initial_gas)
+                                .unwrap_or_default();
+                            new_vp_results
+                                .lock()
+                                .expect("VP results lock shouldn't be poisoned")
+                                .push((
+                                    inputs_digest,
+                                    CachedVpResult { accepted, gas_used },
+                                ));
+                            accepted
+                        })
+                    }
                 }
                 Address::Internal(internal_addr) => {
                     let ctx = native_vp::Ctx::new(
@@ -1098,7 +1256,7 @@ where
                     }
                     _ => {
                         result.rejected_vps.insert(addr.clone());
-                        result.errors.push((addr.clone(), err.to_string()));
+                        result.errors.push((addr.clone(), err.to_wasm_error()));
                     }
                 },
             }
@@ -1114,7 +1272,42 @@ where
             merge_vp_results(a, b, tx_gas_meter)
         })?;
 
-    Ok(vps_result)
+    let new_vp_results = new_vp_results
+        .into_inner()
+        .expect("VP results lock shouldn't be poisoned");
+    Ok((vps_result, new_vp_results))
+}
+
+/// Get the code hash of the VP of an account that is in effect, which is the
+/// account's pending VP update once its timelock has passed, if any
+fn effective_vp_code_hash<D, H>(
+    storage: &State<D, H>,
+    addr: &Address,
+    vp_code_hash: Hash,
+    gas_meter: &mut VpGasMeter,
+) -> Result<Hash>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    let (pending, gas) = storage
+        .read(&account::pending_vp_key(addr))
+        .map_err(Error::StateError)?;
+    gas_meter
+        .consume(gas)
+        .map_err(|err| Error::GasError(err.to_string()))?;
+    let Some(pending) = pending else {
+        return Ok(vp_code_hash);
+    };
+    let pending =
+        account::PendingVpUpdate::try_from_slice(&pending).map_err(|err| {
+            Error::StorageError(namada_state::StorageError::new(err))
+        })?;
+    Ok(if pending.is_active(storage.block.epoch) {
+        pending.vp_code_hash
+    } else {
+        vp_code_hash
+    })
 }
 
 /// Merge VP results from parallel runs
@@ -1157,7 +1350,7 @@ mod tests {
         EthereumEvent, TransferToNamada,
     };
     use namada_core::types::keccak::keccak_hash;
-    use namada_core::types::storage::BlockHeight;
+    use namada_core::types::storage::{BlockHeight, KeySeg};
     use namada_core::types::voting_power::FractionalVotingPower;
     use namada_core::types::{address, key};
     use namada_ethereum_bridge::protocol::transactions::votes::{
@@ -1168,12 +1361,14 @@ mod tests {
     use namada_ethereum_bridge::storage::{vote_tallies, vp};
     use namada_ethereum_bridge::test_utils;
     use namada_state::StorageRead;
+    use namada_test_utils::TestWasms;
     use namada_token::Amount;
     use namada_tx::{SignableEthMessage, Signed};
     use namada_vote_ext::bridge_pool_roots::BridgePoolRootVext;
     use namada_vote_ext::ethereum_events::EthereumEventsVext;
 
     use super::*;
+    use crate::state::testing::TestStorage;
 
     fn apply_eth_tx<D, H>(
         tx: EthereumTxData,
@@ -1349,4 +1544,76 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_vp_results_cache() {
+        let mut storage = TestStorage::default();
+        let mut write_log = WriteLog::default();
+        let (mut vp_cache, _) =
+            wasm::compilation_cache::common::testing::cache();
+        let (mut tx_cache, _) =
+            wasm::compilation_cache::common::testing::cache();
+        let tx_gas_meter = TxGasMeter::new_from_sub_limit(u64::MAX.into());
+
+        // An account whose VP rejects any change
+        let addr = storage.address_gen.generate_address("rng seed");
+        let vp_code = TestWasms::VpAlwaysFalse.read_bytes();
+        let vp_code_hash = Hash::sha256(&vp_code);
+        let code_len = (vp_code.len() as u64).serialize_to_vec();
+        storage
+            .write(&Key::wasm_code(&vp_code_hash), vp_code)
+            .unwrap();
+        storage
+            .write(&Key::wasm_code_len(&vp_code_hash), code_len)
+            .unwrap();
+        storage
+            .write(
+                &Key::validity_predicate(&addr),
+                vp_code_hash.serialize_to_vec(),
+            )
+            .unwrap();
+
+        // Two distinct txs with the same code and data
+        let mut tx = Tx::from_type(TxType::Raw);
+        tx.set_code(namada_tx::Code::new(vec![], None));
+        tx.set_data(namada_tx::Data::new(vec![1]));
+        let mut other_tx = tx.clone();
+        other_tx.header.timestamp = tx.header.timestamp.next_second();
+
+        let key = Key::from(addr.to_db_key())
+            .push(&"balance".to_string())
+            .unwrap();
+        let verifiers = BTreeSet::from([addr.clone()]);
+        let keys_changed = BTreeSet::from([key.clone()]);
+        write_log.write(&key, vec![1]).unwrap();
+        let mut run = |tx: &Tx, tx_index: u32, write_log: &mut WriteLog| {
+            let (result, vp_results) = execute_vps(
+                verifiers.clone(),
+                keys_changed.clone(),
+                tx,
+                &TxIndex(tx_index),
+                &storage,
+                write_log,
+                &tx_gas_meter,
+                &mut vp_cache,
+                &mut tx_cache,
+            )
+            .unwrap();
+            assert!(result.rejected_vps.contains(&addr));
+            let ran = !vp_results.is_empty();
+            write_log.cache_vp_results(vp_results);
+            ran
+        };
+
+        // The VP has to be run the first time
+        assert!(run(&tx, 0, &mut write_log));
+        // The result is reused for the same tx at the same index with the
+        // same state
+        assert!(!run(&tx, 0, &mut write_log));
+        // Another tx, another index or another state run the VP again
+        assert!(run(&other_tx, 0, &mut write_log));
+        assert!(run(&tx, 1, &mut write_log));
+        write_log.write(&key, vec![2]).unwrap();
+        assert!(run(&tx, 0, &mut write_log));
+    }
 }
@@ -1,3 +1,5 @@
 //! Ledger's state storage with key-value backed store and a merkle tree
 
+pub mod schema_registry;
+
 pub use namada_state::{write_log, PrefixIter, WlStorage, *};
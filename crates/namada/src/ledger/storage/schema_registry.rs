@@ -0,0 +1,67 @@
+//! The registry of the storage key schemas declared by the ledger's modules
+//! with the `key_schemas!` macro.
+
+use namada_state::key_schema::KeySchema;
+
+use crate::types::storage::Key;
+
+/// Get the schemas of the storage keys of all the modules. When a key matches
+/// several schemas, the more specific schema comes first.
+pub fn key_schemas() -> impl Iterator<Item = &'static KeySchema> {
+    namada_token::storage_key::key_schemas()
+}
+
+/// Find the most specific schema of the given storage key, if any
+pub fn find_key_schema(key: &Key) -> Option<&'static KeySchema> {
+    key_schemas().find(|schema| (schema.matches)(key))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use namada_token::storage_key::{
+        balance_key, masp_kp_gain_key, minted_balance_key, token_parameter_key,
+    };
+
+    use super::*;
+    use crate::types::address::nam;
+    use crate::types::address::testing::established_address_1;
+
+    #[test]
+    fn test_find_key_schema() {
+        let token = nam();
+        let owner = established_address_1();
+
+        let find = |key: &Key| {
+            find_key_schema(key).map(|schema| (schema.owner, schema.name))
+        };
+        assert_eq!(
+            find(&balance_key(&token, &owner)),
+            Some(("namada_trans_token::storage_key", "balance"))
+        );
+        assert_eq!(
+            find(&minted_balance_key(&token)),
+            Some(("namada_trans_token::storage_key", "minted_balance"))
+        );
+        assert_eq!(
+            find(&masp_kp_gain_key(&token)),
+            Some(("namada_shielded_token::storage_key", "masp_kp_gain"))
+        );
+        assert_eq!(
+            find(&token_parameter_key(&token, &"other".to_string())),
+            Some(("namada_trans_token::storage_key", "token_parameter"))
+        );
+        assert_eq!(find(&Key::parse("unknown").unwrap()), None);
+
+        let mut names = HashSet::new();
+        for schema in key_schemas() {
+            assert!(
+                names.insert((schema.owner, schema.name)),
+                "The schema {} of {} is registered twice",
+                schema.name,
+                schema.owner
+            );
+        }
+    }
+}
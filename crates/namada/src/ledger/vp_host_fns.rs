@@ -43,6 +43,10 @@ pub enum RuntimeError {
     InvalidCodeHash,
     #[error("No value found in result buffer")]
     NoValueInResultBuffer,
+    #[error("Too many prefix iterators, the maximum is {0}")]
+    TooManyIterators(u64),
+    #[error("Validity predicate aborted: {0}")]
+    Aborted(String),
 }
 
 /// VP environment function result
@@ -299,6 +303,28 @@ where
     Ok(hash)
 }
 
+/// Getting the block entropy derived from the hash of the block to which the
+/// current transaction is being applied and the index of the transaction. See
+/// [`BlockHash::entropy`] for the caveats.
+pub fn get_block_entropy<DB, H>(
+    gas_meter: &mut VpGasMeter,
+    storage: &State<DB, H>,
+    tx_index: &TxIndex,
+    sentinel: &mut VpSentinel,
+) -> EnvResult<Hash>
+where
+    DB: namada_state::DB + for<'iter> namada_state::DBIter<'iter>,
+    H: StorageHasher,
+{
+    let hash = get_block_hash(gas_meter, storage, sentinel)?;
+    add_gas(
+        gas_meter,
+        HASH_LENGTH as u64 * MEMORY_ACCESS_GAS_PER_BYTE,
+        sentinel,
+    )?;
+    Ok(hash.entropy(tx_index))
+}
+
 /// Getting the block hash. The height is that of the block to which the
 /// current transaction is being applied.
 pub fn get_tx_code_hash(
@@ -13,12 +13,15 @@ use namada_core::types::storage::{Epochs, TX_INDEX_LENGTH};
 use namada_core::types::validity_predicate::VpSentinel;
 use namada_gas::{
     self as gas, GasMetering, TxGasMeter, VpGasMeter,
-    MEMORY_ACCESS_GAS_PER_BYTE,
+    MEMORY_ACCESS_GAS_PER_BYTE, STORAGE_ITER_NEXT_GAS, STORAGE_ITER_SEEK_GAS,
 };
 use namada_state::write_log::{self, WriteLog};
 use namada_state::{self, ResultExt, State, StorageError, StorageHasher};
 use namada_token::storage_key::is_any_token_parameter_key;
-use namada_tx::data::TxSentinel;
+use namada_tx::data::{
+    TxEvent, TxSentinel, MAX_TX_EVENTS, MAX_TX_EVENT_BYTES,
+    MAX_WASM_ERROR_MSG_LEN,
+};
 use namada_tx::Tx;
 use thiserror::Error;
 
@@ -27,19 +30,25 @@ use super::wasm::TxCache;
 #[cfg(feature = "wasm-runtime")]
 use super::wasm::VpCache;
 use super::WasmCacheAccess;
+use crate::account;
 use crate::ledger::vp_host_fns;
 use crate::token::storage_key::{
-    balance_key, is_any_minted_balance_key, is_any_minter_key,
-    is_any_token_balance_key, minted_balance_key, minter_key,
+    balance_key, is_any_balance_key, is_any_minted_balance_key,
+    is_any_minter_key, minted_balance_key, minter_key,
 };
 use crate::types::address::{self, Address};
-use crate::types::hash::Hash;
+use crate::types::hash::{Hash, HASH_LENGTH};
 use crate::types::ibc::IbcEvent;
 use crate::types::internal::HostEnvResult;
+use crate::types::key::{common, secp256k1, SigScheme};
 use crate::types::storage::{BlockHeight, Epoch, Key, TxIndex};
 use crate::vm::memory::VmMemory;
-use crate::vm::prefix_iter::{PrefixIteratorId, PrefixIterators};
-use crate::vm::{HostRef, MutHostRef};
+use crate::vm::prefix_iter::{
+    PrefixIteratorId, PrefixIterators, MAX_PREFIX_ITERATORS,
+};
+use crate::vm::{
+    validate_untrusted_wasm, HostRef, MutHostRef, WasmValidationError,
+};
 
 /// These runtime errors will abort tx WASM execution immediately
 #[allow(missing_docs)]
@@ -56,6 +65,11 @@ pub enum TxRuntimeError {
     InvalidVpCodeHash(String),
     #[error("A validity predicate of an account cannot be deleted")]
     CannotDeleteVp,
+    #[error(
+        "A pending validity predicate update can only be written by updating \
+         the validity predicate"
+    )]
+    CannotWritePendingVp,
     #[error("Storage modification error: {0}")]
     StorageModificationError(write_log::Error),
     #[error("State error: {0}")]
@@ -80,6 +94,16 @@ pub enum TxRuntimeError {
     NoValueInResultBuffer,
     #[error("VP code is not allowed in allowlist parameter.")]
     DisallowedVp,
+    #[error("Invalid VP code: {0}")]
+    InvalidVpCode(WasmValidationError),
+    #[error("Invalid tx event: {0}")]
+    InvalidTxEvent(String),
+    #[error("Sub-transaction failed: {0}")]
+    SubTxError(String),
+    #[error("Too many prefix iterators, the maximum is {0}")]
+    TooManyIterators(u64),
+    #[error("Transaction aborted: {0}")]
+    Aborted(String),
 }
 
 /// Result of a tx host env fn call
@@ -126,6 +150,9 @@ where
     pub verifiers: MutHostRef<'a, &'a BTreeSet<Address>>,
     /// Cache for 2-step reads from host environment.
     pub result_buffer: MutHostRef<'a, &'a Option<Vec<u8>>>,
+    /// The depth of nested sub-transaction invocations of this tx, 0 for the
+    /// top-level tx
+    pub sub_tx_depth: u8,
     /// VP WASM compilation cache (this is available in tx context, because
     /// we're pre-compiling VPs from [`tx_init_account`])
     #[cfg(feature = "wasm-runtime")]
@@ -190,6 +217,7 @@ where
             tx_index,
             verifiers,
             result_buffer,
+            sub_tx_depth: 0,
             #[cfg(feature = "wasm-runtime")]
             vp_wasm_cache,
             #[cfg(feature = "wasm-runtime")]
@@ -234,6 +262,7 @@ where
             tx_index: self.tx_index.clone(),
             verifiers: self.verifiers.clone(),
             result_buffer: self.result_buffer.clone(),
+            sub_tx_depth: self.sub_tx_depth,
             #[cfg(feature = "wasm-runtime")]
             vp_wasm_cache: self.vp_wasm_cache.clone(),
             #[cfg(feature = "wasm-runtime")]
@@ -296,6 +325,11 @@ where
     /// VP WASM compilation cache
     #[cfg(feature = "wasm-runtime")]
     pub vp_wasm_cache: MutHostRef<'a, &'a VpCache<CA>>,
+    /// TX WASM compilation cache, used to simulate sub-transactions. It's
+    /// missing for the VPs evaluated from native VPs, which can't simulate
+    /// sub-transactions.
+    #[cfg(feature = "wasm-runtime")]
+    pub tx_wasm_cache: MutHostRef<'a, &'a Option<TxCache<CA>>>,
     /// To avoid unused parameter without "wasm-runtime" feature
     #[cfg(not(feature = "wasm-runtime"))]
     pub cache_access: std::marker::PhantomData<CA>,
@@ -356,6 +390,9 @@ where
         keys_changed: &BTreeSet<Key>,
         eval_runner: &EVAL,
         #[cfg(feature = "wasm-runtime")] vp_wasm_cache: &mut VpCache<CA>,
+        #[cfg(feature = "wasm-runtime")] tx_wasm_cache: &mut Option<
+            TxCache<CA>,
+        >,
     ) -> Self {
         let ctx = VpCtx::new(
             address,
@@ -372,6 +409,8 @@ where
             eval_runner,
             #[cfg(feature = "wasm-runtime")]
             vp_wasm_cache,
+            #[cfg(feature = "wasm-runtime")]
+            tx_wasm_cache,
         );
 
         Self { memory, ctx }
@@ -423,6 +462,9 @@ where
         keys_changed: &BTreeSet<Key>,
         eval_runner: &EVAL,
         #[cfg(feature = "wasm-runtime")] vp_wasm_cache: &mut VpCache<CA>,
+        #[cfg(feature = "wasm-runtime")] tx_wasm_cache: &mut Option<
+            TxCache<CA>,
+        >,
     ) -> Self {
         let address = unsafe { HostRef::new(address) };
         let storage = unsafe { HostRef::new(storage) };
@@ -438,6 +480,8 @@ where
         let eval_runner = unsafe { HostRef::new(eval_runner) };
         #[cfg(feature = "wasm-runtime")]
         let vp_wasm_cache = unsafe { MutHostRef::new(vp_wasm_cache) };
+        #[cfg(feature = "wasm-runtime")]
+        let tx_wasm_cache = unsafe { MutHostRef::new(tx_wasm_cache) };
         Self {
             address,
             storage,
@@ -453,6 +497,8 @@ where
             verifiers,
             #[cfg(feature = "wasm-runtime")]
             vp_wasm_cache,
+            #[cfg(feature = "wasm-runtime")]
+            tx_wasm_cache,
             #[cfg(not(feature = "wasm-runtime"))]
             cache_access: std::marker::PhantomData,
         }
@@ -482,6 +528,8 @@ where
             verifiers: self.verifiers.clone(),
             #[cfg(feature = "wasm-runtime")]
             vp_wasm_cache: self.vp_wasm_cache.clone(),
+            #[cfg(feature = "wasm-runtime")]
+            tx_wasm_cache: self.tx_wasm_cache.clone(),
             #[cfg(not(feature = "wasm-runtime"))]
             cache_access: std::marker::PhantomData,
         }
@@ -727,10 +775,13 @@ where
     let storage = unsafe { env.ctx.storage.get() };
     let (iter, gas) =
         namada_state::iter_prefix_post(write_log, storage, &prefix);
-    tx_charge_gas(env, gas)?;
+    tx_charge_gas(env, gas + STORAGE_ITER_SEEK_GAS)?;
 
     let iterators = unsafe { env.ctx.iterators.get() };
-    Ok(iterators.insert(iter).id())
+    iterators
+        .insert(iter)
+        .map(|id| id.id())
+        .ok_or(TxRuntimeError::TooManyIterators(MAX_PREFIX_ITERATORS))
 }
 
 /// Storage prefix iterator next function exposed to the wasm VM Tx environment.
@@ -759,7 +810,7 @@ where
             &Key::parse(key.clone())
                 .map_err(TxRuntimeError::StorageDataError)?,
         );
-        tx_charge_gas(env, iter_gas + log_gas)?;
+        tx_charge_gas(env, iter_gas + log_gas + STORAGE_ITER_NEXT_GAS)?;
         match log_val {
             Some(write_log::StorageModification::Write { ref value }) => {
                 let key_val = borsh::to_vec(&KeyVal {
@@ -842,12 +893,19 @@ where
     tracing::debug!("tx_update {}, {:?}", key, value);
 
     let key = Key::parse(key).map_err(TxRuntimeError::StorageDataError)?;
+    if account::is_pending_vp_key(&key).is_some() {
+        return Err(TxRuntimeError::CannotWritePendingVp);
+    }
     if key.is_validity_predicate().is_some() {
         tx_validate_vp_code_hash(env, &value, &None)?;
     }
 
     check_address_existence(env, &key)?;
 
+    if let Some(addr) = key.is_validity_predicate() {
+        return tx_write_vp(env, addr, value);
+    }
+
     let write_log = unsafe { env.ctx.write_log.get() };
     let (gas, _size_diff) = write_log
         .write(&key, value)
@@ -906,9 +964,9 @@ where
     CA: WasmCacheAccess,
 {
     // Get the token if the key is a balance or minter key
-    let token = if let Some([token, _]) = is_any_token_balance_key(key) {
+    let token = if let Some((token, _)) = is_any_balance_key(key) {
         Some(token)
-    } else if let Some(token) = is_any_token_parameter_key(key) {
+    } else if let Some((token, _)) = is_any_token_parameter_key(key) {
         Some(token)
     } else {
         is_any_minted_balance_key(key).or_else(|| is_any_minter_key(key))
@@ -1008,6 +1066,45 @@ where
     tx_charge_gas(env, gas)
 }
 
+/// Emitting an application-level event function exposed to the wasm VM Tx
+/// environment. The event must be valid and the number of events per tx is
+/// limited.
+pub fn tx_emit_event<MEM, DB, H, CA>(
+    env: &TxVmEnv<MEM, DB, H, CA>,
+    event_ptr: u64,
+    event_len: u64,
+) -> TxResult<()>
+where
+    MEM: VmMemory,
+    DB: namada_state::DB + for<'iter> namada_state::DBIter<'iter>,
+    H: StorageHasher,
+    CA: WasmCacheAccess,
+{
+    let (event, gas) = env
+        .memory
+        .read_bytes(event_ptr, event_len as _)
+        .map_err(|e| TxRuntimeError::MemoryError(Box::new(e)))?;
+    tx_charge_gas(env, gas)?;
+    let event: TxEvent = BorshDeserialize::try_from_slice(&event)
+        .map_err(TxRuntimeError::EncodingError)?;
+    if !event.is_valid() {
+        return Err(TxRuntimeError::InvalidTxEvent(format!(
+            "the event {} is empty, larger than {} bytes or sets a reserved \
+             attribute",
+            event.event_type, MAX_TX_EVENT_BYTES
+        )));
+    }
+    let write_log = unsafe { env.ctx.write_log.get() };
+    if write_log.get_tx_events().len() >= MAX_TX_EVENTS {
+        return Err(TxRuntimeError::InvalidTxEvent(format!(
+            "a tx cannot emit more than {} events",
+            MAX_TX_EVENTS
+        )));
+    }
+    let gas = write_log.emit_tx_event(event);
+    tx_charge_gas(env, gas)
+}
+
 /// Getting an IBC event function exposed to the wasm VM Tx environment.
 pub fn tx_get_ibc_events<MEM, DB, H, CA>(
     env: &TxVmEnv<MEM, DB, H, CA>,
@@ -1319,6 +1416,7 @@ where
     let prefix = Key::parse(prefix)
         .map_err(vp_host_fns::RuntimeError::StorageDataError)?;
 
+    vp_host_fns::add_gas(gas_meter, STORAGE_ITER_SEEK_GAS, sentinel)?;
     let write_log = unsafe { env.ctx.write_log.get() };
     let storage = unsafe { env.ctx.storage.get() };
     let iter = vp_host_fns::iter_prefix_pre(
@@ -1326,7 +1424,9 @@ where
     )?;
 
     let iterators = unsafe { env.ctx.iterators.get() };
-    Ok(iterators.insert(iter).id())
+    iterators.insert(iter).map(|id| id.id()).ok_or(
+        vp_host_fns::RuntimeError::TooManyIterators(MAX_PREFIX_ITERATORS),
+    )
 }
 
 /// Storage prefix iterator function for posterior state (after tx execution)
@@ -1358,6 +1458,7 @@ where
     let prefix = Key::parse(prefix)
         .map_err(vp_host_fns::RuntimeError::StorageDataError)?;
 
+    vp_host_fns::add_gas(gas_meter, STORAGE_ITER_SEEK_GAS, sentinel)?;
     let write_log = unsafe { env.ctx.write_log.get() };
     let storage = unsafe { env.ctx.storage.get() };
     let iter = vp_host_fns::iter_prefix_post(
@@ -1365,7 +1466,9 @@ where
     )?;
 
     let iterators = unsafe { env.ctx.iterators.get() };
-    Ok(iterators.insert(iter).id())
+    iterators.insert(iter).map(|id| id.id()).ok_or(
+        vp_host_fns::RuntimeError::TooManyIterators(MAX_PREFIX_ITERATORS),
+    )
 }
 
 /// Storage prefix iterator for prior or posterior state function
@@ -1394,6 +1497,7 @@ where
         if let Some((key, val)) =
             vp_host_fns::iter_next(gas_meter, iter, sentinel)?
         {
+            vp_host_fns::add_gas(gas_meter, STORAGE_ITER_NEXT_GAS, sentinel)?;
             let key_val = borsh::to_vec(&KeyVal { key, val })
                 .map_err(vp_host_fns::RuntimeError::EncodingError)?;
             let len: i64 = key_val
@@ -1472,7 +1576,6 @@ where
     let code_tag = Option::<String>::try_from_slice(&code_tag)
         .map_err(TxRuntimeError::EncodingError)?;
 
-    let key = Key::validity_predicate(&addr);
     let (code_hash, gas) = env
         .memory
         .read_bytes(code_hash_ptr, code_hash_len as _)
@@ -1481,11 +1584,50 @@ where
 
     tx_validate_vp_code_hash(env, &code_hash, &code_tag)?;
 
-    let write_log = unsafe { env.ctx.write_log.get() };
-    let (gas, _size_diff) = write_log
-        .write(&key, code_hash)
-        .map_err(TxRuntimeError::StorageModificationError)?;
-    tx_charge_gas(env, gas)
+    tx_write_vp(env, &addr, code_hash)
+}
+
+/// Write the VP code hash of an account. If the account has a VP update
+/// timelock, the new VP is instead recorded as a pending update that only takes
+/// effect once the timelock has passed.
+fn tx_write_vp<MEM, DB, H, CA>(
+    env: &TxVmEnv<MEM, DB, H, CA>,
+    addr: &Address,
+    code_hash: Vec<u8>,
+) -> TxResult<()>
+where
+    MEM: VmMemory,
+    DB: namada_state::DB + for<'iter> namada_state::DBIter<'iter>,
+    H: StorageHasher,
+    CA: WasmCacheAccess,
+{
+    let mut ctx = env.ctx.clone();
+    let key = Key::validity_predicate(addr);
+    let timelock = account::vp_update_timelock(&ctx, addr)?;
+    if timelock == 0 {
+        let write_log = unsafe { env.ctx.write_log.get() };
+        let (gas, _size_diff) = write_log
+            .write(&key, code_hash)
+            .map_err(TxRuntimeError::StorageModificationError)?;
+        return tx_charge_gas(env, gas);
+    }
+
+    let vp_code_hash = Hash::try_from(&code_hash[..])
+        .map_err(|e| TxRuntimeError::InvalidVpCodeHash(e.to_string()))?;
+    let current_epoch = ctx.get_block_epoch()?;
+    // A pending update that is already in effect becomes the account's VP
+    // before it gets replaced
+    if let Some(pending) = account::pending_vp_update(&ctx, addr)? {
+        if pending.is_active(current_epoch) {
+            ctx.write_bytes(&key, pending.vp_code_hash.0)?;
+        }
+    }
+    let pending = account::PendingVpUpdate {
+        vp_code_hash,
+        activation_epoch: Epoch(current_epoch.0.saturating_add(timelock)),
+    };
+    ctx.write(&account::pending_vp_key(addr), pending)?;
+    Ok(())
 }
 
 /// Initialize a new account established address.
@@ -1633,6 +1775,33 @@ where
     tx_charge_gas(env, gas)
 }
 
+/// Getting the block entropy function exposed to the wasm VM Tx environment.
+/// The entropy is derived from the hash of the block to which the current
+/// transaction is being applied and the index of the transaction. It is
+/// biasable by the block proposer, see [`BlockHash::entropy`].
+pub fn tx_get_block_entropy<MEM, DB, H, CA>(
+    env: &TxVmEnv<MEM, DB, H, CA>,
+    result_ptr: u64,
+) -> TxResult<()>
+where
+    MEM: VmMemory,
+    DB: namada_state::DB + for<'iter> namada_state::DBIter<'iter>,
+    H: StorageHasher,
+    CA: WasmCacheAccess,
+{
+    let storage = unsafe { env.ctx.storage.get() };
+    let tx_index = unsafe { env.ctx.tx_index.get() };
+    let (hash, gas) = storage.get_block_hash();
+    tx_charge_gas(env, gas)?;
+    tx_charge_gas(env, HASH_LENGTH as u64 * MEMORY_ACCESS_GAS_PER_BYTE)?;
+    let entropy = hash.entropy(tx_index);
+    let gas = env
+        .memory
+        .write_bytes(result_ptr, entropy.0)
+        .map_err(|e| TxRuntimeError::MemoryError(Box::new(e)))?;
+    tx_charge_gas(env, gas)
+}
+
 /// Getting the block epoch function exposed to the wasm VM Tx
 /// environment. The epoch is that of the block to which the current
 /// transaction is being applied.
@@ -1832,6 +2001,34 @@ where
     vp_host_fns::add_gas(gas_meter, gas, sentinel)
 }
 
+/// Getting the block entropy function exposed to the wasm VM VP environment.
+/// The entropy is derived from the hash of the block to which the current
+/// transaction is being applied and the index of the transaction. It is
+/// biasable by the block proposer, see [`BlockHash::entropy`].
+pub fn vp_get_block_entropy<MEM, DB, H, EVAL, CA>(
+    env: &VpVmEnv<MEM, DB, H, EVAL, CA>,
+    result_ptr: u64,
+) -> vp_host_fns::EnvResult<()>
+where
+    MEM: VmMemory,
+    DB: namada_state::DB + for<'iter> namada_state::DBIter<'iter>,
+    H: StorageHasher,
+    EVAL: VpEvaluator,
+    CA: WasmCacheAccess,
+{
+    let gas_meter = unsafe { env.ctx.gas_meter.get() };
+    let sentinel = unsafe { env.ctx.sentinel.get() };
+    let storage = unsafe { env.ctx.storage.get() };
+    let tx_index = unsafe { env.ctx.tx_index.get() };
+    let entropy =
+        vp_host_fns::get_block_entropy(gas_meter, storage, tx_index, sentinel)?;
+    let gas = env
+        .memory
+        .write_bytes(result_ptr, entropy.0)
+        .map_err(|e| vp_host_fns::RuntimeError::MemoryError(Box::new(e)))?;
+    vp_host_fns::add_gas(gas_meter, gas, sentinel)
+}
+
 /// Getting the transaction hash function exposed to the wasm VM VP environment.
 pub fn vp_get_tx_code_hash<MEM, DB, H, EVAL, CA>(
     env: &VpVmEnv<MEM, DB, H, EVAL, CA>,
@@ -2028,6 +2225,120 @@ where
     }
 }
 
+/// Verify the signature of an arbitrary message with the given public key, for
+/// a fixed gas cost. Returns 1 if the signature is valid, -1 otherwise.
+pub fn vp_verify_signature<MEM, DB, H, EVAL, CA>(
+    env: &VpVmEnv<MEM, DB, H, EVAL, CA>,
+    pk_ptr: u64,
+    pk_len: u64,
+    msg_ptr: u64,
+    msg_len: u64,
+    sig_ptr: u64,
+    sig_len: u64,
+) -> vp_host_fns::EnvResult<i64>
+where
+    MEM: VmMemory,
+    DB: namada_state::DB + for<'iter> namada_state::DBIter<'iter>,
+    H: StorageHasher,
+    EVAL: VpEvaluator,
+    CA: WasmCacheAccess,
+{
+    let gas_meter = unsafe { env.ctx.gas_meter.get() };
+    let sentinel = unsafe { env.ctx.sentinel.get() };
+
+    let (pk, gas) = env
+        .memory
+        .read_bytes(pk_ptr, pk_len as _)
+        .map_err(|e| vp_host_fns::RuntimeError::MemoryError(Box::new(e)))?;
+    vp_host_fns::add_gas(gas_meter, gas, sentinel)?;
+    let pk = common::PublicKey::try_from_slice(&pk)
+        .map_err(vp_host_fns::RuntimeError::EncodingError)?;
+
+    let (msg, gas) = env
+        .memory
+        .read_bytes(msg_ptr, msg_len as _)
+        .map_err(|e| vp_host_fns::RuntimeError::MemoryError(Box::new(e)))?;
+    vp_host_fns::add_gas(gas_meter, gas, sentinel)?;
+
+    let (sig, gas) = env
+        .memory
+        .read_bytes(sig_ptr, sig_len as _)
+        .map_err(|e| vp_host_fns::RuntimeError::MemoryError(Box::new(e)))?;
+    vp_host_fns::add_gas(gas_meter, gas, sentinel)?;
+    let sig = common::Signature::try_from_slice(&sig)
+        .map_err(vp_host_fns::RuntimeError::EncodingError)?;
+
+    vp_host_fns::add_gas(gas_meter, gas::VERIFY_SIGNATURE_GAS, sentinel)?;
+    Ok(HostEnvResult::from(
+        common::SigScheme::verify_signature(&pk, &msg, &sig).is_ok(),
+    )
+    .to_i64())
+}
+
+/// Recover the secp256k1 public key of the signer of a 32 bytes message hash
+/// from an Ethereum encoded `r || s || v` signature, for a fixed gas cost.
+/// On success, the Borsh encoded [`common::PublicKey`] is written into the
+/// result buffer and its length is returned, otherwise returns -1.
+pub fn vp_recover_secp256k1_public_key<MEM, DB, H, EVAL, CA>(
+    env: &VpVmEnv<MEM, DB, H, EVAL, CA>,
+    hash_ptr: u64,
+    hash_len: u64,
+    sig_ptr: u64,
+    sig_len: u64,
+) -> vp_host_fns::EnvResult<i64>
+where
+    MEM: VmMemory,
+    DB: namada_state::DB + for<'iter> namada_state::DBIter<'iter>,
+    H: StorageHasher,
+    EVAL: VpEvaluator,
+    CA: WasmCacheAccess,
+{
+    let gas_meter = unsafe { env.ctx.gas_meter.get() };
+    let sentinel = unsafe { env.ctx.sentinel.get() };
+
+    let (hash, gas) = env
+        .memory
+        .read_bytes(hash_ptr, hash_len as _)
+        .map_err(|e| vp_host_fns::RuntimeError::MemoryError(Box::new(e)))?;
+    vp_host_fns::add_gas(gas_meter, gas, sentinel)?;
+
+    let (sig, gas) = env
+        .memory
+        .read_bytes(sig_ptr, sig_len as _)
+        .map_err(|e| vp_host_fns::RuntimeError::MemoryError(Box::new(e)))?;
+    vp_host_fns::add_gas(gas_meter, gas, sentinel)?;
+
+    vp_host_fns::add_gas(gas_meter, gas::RECOVER_PUBLIC_KEY_GAS, sentinel)?;
+    match recover_secp256k1_public_key(&hash, &sig) {
+        Some(pk) => {
+            let pk = pk.serialize_to_vec();
+            let len: i64 = pk
+                .len()
+                .try_into()
+                .map_err(vp_host_fns::RuntimeError::NumConversionError)?;
+            let result_buffer = unsafe { env.ctx.result_buffer.get() };
+            result_buffer.replace(pk);
+            Ok(len)
+        }
+        None => Ok(HostEnvResult::Fail.to_i64()),
+    }
+}
+
+/// Recover the secp256k1 public key of the signer of the given message hash.
+/// Returns `None` if either the hash or the signature are malformed or if the
+/// key cannot be recovered.
+fn recover_secp256k1_public_key(
+    hash: &[u8],
+    sig: &[u8],
+) -> Option<common::PublicKey> {
+    let hash: &[u8; 32] = hash.try_into().ok()?;
+    let sig: &[u8; secp256k1::SIGNATURE_SIZE] = sig.try_into().ok()?;
+    let sig = secp256k1::Signature::try_from_eth_bytes(sig).ok()?;
+    sig.recover_public_key(hash)
+        .ok()
+        .map(common::PublicKey::Secp256k1)
+}
+
 /// Log a string from exposed to the wasm VM Tx environment. The message will be
 /// printed at the [`tracing::Level::INFO`]. This function is for development
 /// only.
@@ -2050,6 +2361,30 @@ where
     Ok(())
 }
 
+/// Abort the tx execution with an error message exposed to the wasm VM Tx
+/// environment. The message, truncated to [`MAX_WASM_ERROR_MSG_LEN`] bytes, is
+/// reported in the tx result.
+pub fn tx_abort<MEM, DB, H, CA>(
+    env: &TxVmEnv<MEM, DB, H, CA>,
+    msg_ptr: u64,
+    msg_len: u64,
+) -> TxResult<()>
+where
+    MEM: VmMemory,
+    DB: namada_state::DB + for<'iter> namada_state::DBIter<'iter>,
+    H: StorageHasher,
+    CA: WasmCacheAccess,
+{
+    let (msg, gas) = env
+        .memory
+        .read_bytes(msg_ptr, msg_len.min(MAX_WASM_ERROR_MSG_LEN as u64) as _)
+        .map_err(|e| TxRuntimeError::MemoryError(Box::new(e)))?;
+    tx_charge_gas(env, gas)?;
+    let msg = String::from_utf8_lossy(&msg).into_owned();
+    tracing::info!("WASM Transaction aborted: {}", msg);
+    Err(TxRuntimeError::Aborted(msg))
+}
+
 /// Execute IBC tx.
 // Temporarily the IBC tx execution is implemented via a host function to
 // workaround wasm issue.
@@ -2065,7 +2400,7 @@ where
     use std::cell::RefCell;
     use std::rc::Rc;
 
-    use namada_ibc::{IbcActions, TransferModule};
+    use namada_ibc::{IbcActions, IcqModule, TransferModule};
 
     let tx_data = unsafe { env.ctx.tx.get().data() }.ok_or_else(|| {
         let sentinel = unsafe { env.ctx.sentinel.get() };
@@ -2074,8 +2409,14 @@ where
     })?;
     let ctx = Rc::new(RefCell::new(env.ctx.clone()));
     let mut actions = IbcActions::new(ctx.clone());
-    let module = TransferModule::new(ctx);
+    let module = TransferModule::new(ctx.clone());
     actions.add_transfer_module(module.module_id(), module);
+    let icq_module = IcqModule::new(ctx);
+    actions.add_icq_module(
+        icq_module.module_id(),
+        icq_module.port_id(),
+        icq_module,
+    );
     actions.execute(&tx_data)?;
 
     Ok(())
@@ -2134,19 +2475,34 @@ where
     let write_log = unsafe { env.ctx.write_log.get() };
     let (result, gas) = write_log.read(&code_key);
     tx_charge_gas(env, gas)?;
-    if result.is_none() {
-        let storage = unsafe { env.ctx.storage.get() };
-        let (is_present, gas) = storage
-            .has_key(&code_key)
-            .map_err(TxRuntimeError::StateError)?;
-        tx_charge_gas(env, gas)?;
-        if !is_present {
-            return Err(TxRuntimeError::InvalidVpCodeHash(
-                "The corresponding VP code doesn't exist".to_string(),
-            ));
+    let code = match result {
+        Some(write_log::StorageModification::Write { value }) => {
+            Some(value.clone())
         }
-    }
-    Ok(())
+        _ => {
+            let storage = unsafe { env.ctx.storage.get() };
+            let (code, gas) = storage
+                .read(&code_key)
+                .map_err(TxRuntimeError::StateError)?;
+            tx_charge_gas(env, gas)?;
+            code
+        }
+    };
+    let code = code.ok_or_else(|| {
+        TxRuntimeError::InvalidVpCodeHash(
+            "The corresponding VP code doesn't exist".to_string(),
+        )
+    })?;
+
+    // Finally check that the VP code doesn't use any forbidden wasm features,
+    // such as the non-deterministic SIMD instructions, which could otherwise
+    // lead to a consensus divergence once the VP gets executed
+    tx_charge_gas(
+        env,
+        (code.len() as u64)
+            .saturating_mul(gas::WASM_CODE_VALIDATION_GAS_PER_BYTE),
+    )?;
+    validate_untrusted_wasm(&code).map_err(TxRuntimeError::InvalidVpCode)
 }
 
 /// Set the sentinel for an invalid tx section commitment
@@ -2235,6 +2591,247 @@ where
     }
 }
 
+/// Verify the signature of an arbitrary message with the given public key, for
+/// a fixed gas cost. Returns 1 if the signature is valid, -1 otherwise.
+pub fn tx_verify_signature<MEM, DB, H, CA>(
+    env: &TxVmEnv<MEM, DB, H, CA>,
+    pk_ptr: u64,
+    pk_len: u64,
+    msg_ptr: u64,
+    msg_len: u64,
+    sig_ptr: u64,
+    sig_len: u64,
+) -> TxResult<i64>
+where
+    MEM: VmMemory,
+    DB: namada_state::DB + for<'iter> namada_state::DBIter<'iter>,
+    H: StorageHasher,
+    CA: WasmCacheAccess,
+{
+    let (pk, gas) = env
+        .memory
+        .read_bytes(pk_ptr, pk_len as _)
+        .map_err(|e| TxRuntimeError::MemoryError(Box::new(e)))?;
+    tx_charge_gas(env, gas)?;
+    let pk = common::PublicKey::try_from_slice(&pk)
+        .map_err(TxRuntimeError::EncodingError)?;
+
+    let (msg, gas) = env
+        .memory
+        .read_bytes(msg_ptr, msg_len as _)
+        .map_err(|e| TxRuntimeError::MemoryError(Box::new(e)))?;
+    tx_charge_gas(env, gas)?;
+
+    let (sig, gas) = env
+        .memory
+        .read_bytes(sig_ptr, sig_len as _)
+        .map_err(|e| TxRuntimeError::MemoryError(Box::new(e)))?;
+    tx_charge_gas(env, gas)?;
+    let sig = common::Signature::try_from_slice(&sig)
+        .map_err(TxRuntimeError::EncodingError)?;
+
+    tx_charge_gas(env, gas::VERIFY_SIGNATURE_GAS)?;
+    Ok(HostEnvResult::from(
+        common::SigScheme::verify_signature(&pk, &msg, &sig).is_ok(),
+    )
+    .to_i64())
+}
+
+/// Recover the secp256k1 public key of the signer of a 32 bytes message hash
+/// from an Ethereum encoded `r || s || v` signature, for a fixed gas cost.
+/// On success, the Borsh encoded [`common::PublicKey`] is written into the
+/// result buffer and its length is returned, otherwise returns -1.
+pub fn tx_recover_secp256k1_public_key<MEM, DB, H, CA>(
+    env: &TxVmEnv<MEM, DB, H, CA>,
+    hash_ptr: u64,
+    hash_len: u64,
+    sig_ptr: u64,
+    sig_len: u64,
+) -> TxResult<i64>
+where
+    MEM: VmMemory,
+    DB: namada_state::DB + for<'iter> namada_state::DBIter<'iter>,
+    H: StorageHasher,
+    CA: WasmCacheAccess,
+{
+    let (hash, gas) = env
+        .memory
+        .read_bytes(hash_ptr, hash_len as _)
+        .map_err(|e| TxRuntimeError::MemoryError(Box::new(e)))?;
+    tx_charge_gas(env, gas)?;
+
+    let (sig, gas) = env
+        .memory
+        .read_bytes(sig_ptr, sig_len as _)
+        .map_err(|e| TxRuntimeError::MemoryError(Box::new(e)))?;
+    tx_charge_gas(env, gas)?;
+
+    tx_charge_gas(env, gas::RECOVER_PUBLIC_KEY_GAS)?;
+    match recover_secp256k1_public_key(&hash, &sig) {
+        Some(pk) => {
+            let pk = pk.serialize_to_vec();
+            let len: i64 = pk
+                .len()
+                .try_into()
+                .map_err(TxRuntimeError::NumConversionError)?;
+            let result_buffer = unsafe { env.ctx.result_buffer.get() };
+            result_buffer.replace(pk);
+            Ok(len)
+        }
+        None => Ok(HostEnvResult::Fail.to_i64()),
+    }
+}
+
+/// Invoke the allowlisted tx code with the given hash as a sub-transaction with
+/// the given data. The sub-transaction shares the write log, the gas meter and
+/// the verifiers of the calling tx. Nested invocations are bounded by
+/// [`super::wasm::run::MAX_SUB_TX_DEPTH`]. A failure of the sub-transaction
+/// aborts the calling tx, otherwise returns 1.
+#[cfg(feature = "wasm-runtime")]
+pub fn tx_invoke_sub_tx<MEM, DB, H, CA>(
+    env: &TxVmEnv<MEM, DB, H, CA>,
+    code_hash_ptr: u64,
+    code_hash_len: u64,
+    data_ptr: u64,
+    data_len: u64,
+) -> TxResult<i64>
+where
+    MEM: VmMemory,
+    DB: 'static + namada_state::DB + for<'iter> namada_state::DBIter<'iter>,
+    H: 'static + StorageHasher,
+    CA: 'static + WasmCacheAccess,
+{
+    let (code_hash, gas) = env
+        .memory
+        .read_bytes(code_hash_ptr, code_hash_len as _)
+        .map_err(|e| TxRuntimeError::MemoryError(Box::new(e)))?;
+    tx_charge_gas(env, gas)?;
+    let code_hash = Hash::try_from(&code_hash[..])
+        .map_err(|e| TxRuntimeError::SubTxError(e.to_string()))?;
+
+    let (data, gas) = env
+        .memory
+        .read_bytes(data_ptr, data_len as _)
+        .map_err(|e| TxRuntimeError::MemoryError(Box::new(e)))?;
+    tx_charge_gas(env, gas)?;
+
+    let storage = unsafe { env.ctx.storage.get() };
+    let write_log = unsafe { env.ctx.write_log.get() };
+    let gas_meter = unsafe { env.ctx.gas_meter.get() };
+    let sentinel = unsafe { env.ctx.sentinel.get() };
+    let tx = unsafe { env.ctx.tx.get() };
+    let tx_index = unsafe { env.ctx.tx_index.get() };
+    let verifiers = unsafe { env.ctx.verifiers.get() };
+    let vp_wasm_cache = unsafe { env.ctx.vp_wasm_cache.get() };
+    let tx_wasm_cache = unsafe { env.ctx.tx_wasm_cache.get() };
+    match super::wasm::run::sub_tx(
+        storage,
+        write_log,
+        gas_meter,
+        tx_index,
+        tx,
+        code_hash,
+        data,
+        vp_wasm_cache,
+        tx_wasm_cache,
+        env.ctx.sub_tx_depth + 1,
+    ) {
+        Ok(sub_tx_verifiers) => {
+            verifiers.extend(sub_tx_verifiers);
+            Ok(HostEnvResult::Success.to_i64())
+        }
+        Err(err) => {
+            if let super::wasm::run::Error::GasError(_) = err {
+                sentinel.set_out_of_gas();
+            }
+            Err(TxRuntimeError::SubTxError(err.to_string()))
+        }
+    }
+}
+
+/// Simulate the allowlisted tx code with the given hash as a sub-transaction
+/// with the given data on a fork of the write log of the calling tx, which is
+/// left untouched. The gas of the simulation is charged to the calling tx. The
+/// would-be effects of the sub-transaction are written to the result buffer as
+/// a [`namada_tx::data::SimulatedTx`], whose length is returned. Returns -1 if
+/// the sub-transaction fails, but aborts the calling tx if it runs out of gas.
+#[cfg(feature = "wasm-runtime")]
+pub fn tx_simulate_sub_tx<MEM, DB, H, CA>(
+    env: &TxVmEnv<MEM, DB, H, CA>,
+    code_hash_ptr: u64,
+    code_hash_len: u64,
+    data_ptr: u64,
+    data_len: u64,
+) -> TxResult<i64>
+where
+    MEM: VmMemory,
+    DB: 'static + namada_state::DB + for<'iter> namada_state::DBIter<'iter>,
+    H: 'static + StorageHasher,
+    CA: 'static + WasmCacheAccess,
+{
+    let (code_hash, gas) = env
+        .memory
+        .read_bytes(code_hash_ptr, code_hash_len as _)
+        .map_err(|e| TxRuntimeError::MemoryError(Box::new(e)))?;
+    tx_charge_gas(env, gas)?;
+    let code_hash = Hash::try_from(&code_hash[..])
+        .map_err(|e| TxRuntimeError::SubTxError(e.to_string()))?;
+
+    let (data, gas) = env
+        .memory
+        .read_bytes(data_ptr, data_len as _)
+        .map_err(|e| TxRuntimeError::MemoryError(Box::new(e)))?;
+    tx_charge_gas(env, gas)?;
+
+    let storage = unsafe { env.ctx.storage.get() };
+    let write_log = unsafe { env.ctx.write_log.get() };
+    let gas_meter = unsafe { env.ctx.gas_meter.get() };
+    let sentinel = unsafe { env.ctx.sentinel.get() };
+    let tx = unsafe { env.ctx.tx.get() };
+    let tx_index = unsafe { env.ctx.tx_index.get() };
+    let vp_wasm_cache = unsafe { env.ctx.vp_wasm_cache.get() };
+    let tx_wasm_cache = unsafe { env.ctx.tx_wasm_cache.get() };
+    match super::wasm::run::simulate_sub_tx(
+        storage,
+        write_log,
+        gas_meter,
+        tx_index,
+        tx,
+        code_hash,
+        data,
+        vp_wasm_cache,
+        tx_wasm_cache,
+        env.ctx.sub_tx_depth + 1,
+    ) {
+        Ok((mut fork, verifiers)) => {
+            let (verifiers, changed_keys) =
+                fork.verifiers_and_changed_keys(&verifiers);
+            let simulated = namada_tx::data::SimulatedTx {
+                changed_keys,
+                verifiers,
+                ibc_events: fork.take_ibc_events(),
+                tx_events: fork.take_tx_events(),
+            }
+            .serialize_to_vec();
+            let len: i64 = simulated
+                .len()
+                .try_into()
+                .map_err(TxRuntimeError::NumConversionError)?;
+            let result_buffer = unsafe { env.ctx.result_buffer.get() };
+            result_buffer.replace(simulated);
+            Ok(len)
+        }
+        Err(super::wasm::run::Error::GasError(err)) => {
+            sentinel.set_out_of_gas();
+            Err(TxRuntimeError::SubTxError(err))
+        }
+        Err(err) => {
+            tracing::debug!("Simulated sub-transaction failed: {}", err);
+            Ok(HostEnvResult::Fail.to_i64())
+        }
+    }
+}
+
 /// Appends the new note commitments to the tree in storage
 pub fn tx_update_masp_note_commitment_tree<MEM, DB, H, CA>(
     env: &TxVmEnv<MEM, DB, H, CA>,
@@ -2316,6 +2913,104 @@ where
         .to_i64())
 }
 
+/// Simulate the allowlisted tx code with the given hash as a sub-transaction
+/// of the validated tx with the given data, on a fork of the write log holding
+/// the modifications of the validated tx. The gas of the simulation is charged
+/// to the VP. The would-be effects of the sub-transaction are written to the
+/// result buffer as a [`namada_tx::data::SimulatedTx`], whose length is
+/// returned. Returns -1 if the sub-transaction fails or can't be simulated
+/// from this VP, but aborts the VP if it runs out of gas.
+#[cfg(feature = "wasm-runtime")]
+pub fn vp_simulate_sub_tx<MEM, DB, H, EVAL, CA>(
+    env: &VpVmEnv<MEM, DB, H, EVAL, CA>,
+    code_hash_ptr: u64,
+    code_hash_len: u64,
+    data_ptr: u64,
+    data_len: u64,
+) -> vp_host_fns::EnvResult<i64>
+where
+    MEM: VmMemory,
+    DB: 'static + namada_state::DB + for<'iter> namada_state::DBIter<'iter>,
+    H: 'static + StorageHasher,
+    EVAL: VpEvaluator,
+    CA: 'static + WasmCacheAccess,
+{
+    let gas_meter = unsafe { env.ctx.gas_meter.get() };
+    let sentinel = unsafe { env.ctx.sentinel.get() };
+    let (code_hash, gas) = env
+        .memory
+        .read_bytes(code_hash_ptr, code_hash_len as _)
+        .map_err(|e| vp_host_fns::RuntimeError::MemoryError(Box::new(e)))?;
+    vp_host_fns::add_gas(gas_meter, gas, sentinel)?;
+    let code_hash = Hash::try_from(&code_hash[..])
+        .map_err(|_| vp_host_fns::RuntimeError::InvalidCodeHash)?;
+
+    let (data, gas) = env
+        .memory
+        .read_bytes(data_ptr, data_len as _)
+        .map_err(|e| vp_host_fns::RuntimeError::MemoryError(Box::new(e)))?;
+    vp_host_fns::add_gas(gas_meter, gas, sentinel)?;
+
+    let tx_wasm_cache = unsafe { env.ctx.tx_wasm_cache.get() };
+    let Some(tx_wasm_cache) = tx_wasm_cache.as_mut() else {
+        tracing::debug!(
+            "Sub-transactions can't be simulated from a VP evaluated by a \
+             native VP"
+        );
+        return Ok(HostEnvResult::Fail.to_i64());
+    };
+    let storage = unsafe { env.ctx.storage.get() };
+    let write_log = unsafe { env.ctx.write_log.get() };
+    let tx = unsafe { env.ctx.tx.get() };
+    let tx_index = unsafe { env.ctx.tx_index.get() };
+    let vp_wasm_cache = unsafe { env.ctx.vp_wasm_cache.get() };
+    // The simulation is metered against the gas left to the VP and then
+    // charged to it
+    let mut simulation_gas_meter =
+        TxGasMeter::new_from_sub_limit(gas_meter.get_available_gas());
+    let result = super::wasm::run::simulate_sub_tx(
+        storage,
+        write_log,
+        &mut simulation_gas_meter,
+        tx_index,
+        tx,
+        code_hash,
+        data,
+        vp_wasm_cache,
+        tx_wasm_cache,
+        1,
+    );
+    vp_host_fns::add_gas(
+        gas_meter,
+        simulation_gas_meter.get_tx_consumed_gas().into(),
+        sentinel,
+    )?;
+    match result {
+        Ok((mut fork, verifiers)) => {
+            let (verifiers, changed_keys) =
+                fork.verifiers_and_changed_keys(&verifiers);
+            let simulated = namada_tx::data::SimulatedTx {
+                changed_keys,
+                verifiers,
+                ibc_events: fork.take_ibc_events(),
+                tx_events: fork.take_tx_events(),
+            }
+            .serialize_to_vec();
+            let len: i64 = simulated
+                .len()
+                .try_into()
+                .map_err(vp_host_fns::RuntimeError::NumConversionError)?;
+            let result_buffer = unsafe { env.ctx.result_buffer.get() };
+            result_buffer.replace(simulated);
+            Ok(len)
+        }
+        Err(err) => {
+            tracing::debug!("Simulated sub-transaction failed: {}", err);
+            Ok(HostEnvResult::Fail.to_i64())
+        }
+    }
+}
+
 /// Get the native token's address
 pub fn vp_get_native_token<MEM, DB, H, EVAL, CA>(
     env: &VpVmEnv<MEM, DB, H, EVAL, CA>,
@@ -2364,6 +3059,33 @@ where
     Ok(())
 }
 
+/// Abort the VP execution with an error message exposed to the wasm VM VP
+/// environment, which rejects the tx. The message, truncated to
+/// [`MAX_WASM_ERROR_MSG_LEN`] bytes, is reported in the tx result.
+pub fn vp_abort<MEM, DB, H, EVAL, CA>(
+    env: &VpVmEnv<MEM, DB, H, EVAL, CA>,
+    msg_ptr: u64,
+    msg_len: u64,
+) -> vp_host_fns::EnvResult<()>
+where
+    MEM: VmMemory,
+    DB: namada_state::DB + for<'iter> namada_state::DBIter<'iter>,
+    H: StorageHasher,
+    EVAL: VpEvaluator,
+    CA: WasmCacheAccess,
+{
+    let (msg, gas) = env
+        .memory
+        .read_bytes(msg_ptr, msg_len.min(MAX_WASM_ERROR_MSG_LEN as u64) as _)
+        .map_err(|e| vp_host_fns::RuntimeError::MemoryError(Box::new(e)))?;
+    let gas_meter = unsafe { env.ctx.gas_meter.get() };
+    let sentinel = unsafe { env.ctx.sentinel.get() };
+    vp_host_fns::add_gas(gas_meter, gas, sentinel)?;
+    let msg = String::from_utf8_lossy(&msg).into_owned();
+    tracing::info!("WASM Validity predicate aborted: {}", msg);
+    Err(vp_host_fns::RuntimeError::Aborted(msg))
+}
+
 // Temp. workaround for <https://github.com/anoma/namada/issues/1831>
 use namada_state::StorageRead;
 
@@ -2437,7 +3159,10 @@ where
         ibc_tx_charge_gas(self, gas)?;
 
         let iterators = unsafe { self.iterators.get() };
-        Ok(iterators.insert(iter).id())
+        iterators
+            .insert(iter)
+            .map(|id| id.id())
+            .ok_or(StorageError::new_const("Too many prefix iterators"))
     }
 
     fn iter_next<'iter>(
@@ -2625,6 +3350,7 @@ where
             dest_bal.receive(&amount).into_storage_result()?;
             self.write(&src_key, src_bal)?;
             self.write(&dest_key, dest_bal)?;
+            crate::account::record_spending(self, src, token, amount)?;
         }
         Ok(())
     }
@@ -2800,8 +3526,11 @@ pub mod testing {
         CA: WasmCacheAccess,
     {
         let store = crate::vm::wasm::compilation_cache::common::store();
-        let initial_memory =
-            crate::vm::wasm::memory::prepare_tx_memory(&store).unwrap();
+        let initial_memory = crate::vm::wasm::memory::prepare_tx_memory(
+            &store,
+            crate::vm::wasm::memory::TX_MEMORY_MAX_PAGES,
+        )
+        .unwrap();
         let mut wasm_memory = WasmMemory::default();
         wasm_memory.inner.initialize(initial_memory);
 
@@ -2839,6 +3568,9 @@ pub mod testing {
         keys_changed: &BTreeSet<Key>,
         eval_runner: &EVAL,
         #[cfg(feature = "wasm-runtime")] vp_wasm_cache: &mut VpCache<CA>,
+        #[cfg(feature = "wasm-runtime")] tx_wasm_cache: &mut Option<
+            TxCache<CA>,
+        >,
     ) -> VpVmEnv<'static, NativeMemory, DB, H, EVAL, CA>
     where
         DB: 'static + namada_state::DB + for<'iter> namada_state::DBIter<'iter>,
@@ -2862,6 +3594,8 @@ pub mod testing {
             eval_runner,
             #[cfg(feature = "wasm-runtime")]
             vp_wasm_cache,
+            #[cfg(feature = "wasm-runtime")]
+            tx_wasm_cache,
         )
     }
 }
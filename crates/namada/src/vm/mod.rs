@@ -14,6 +14,11 @@ pub mod types;
 pub mod wasm;
 use thiserror::Error;
 
+/// The wasm features allowed in untrusted code. The SIMD instructions are
+/// forbidden as their results may differ between platforms. The floating point
+/// instructions are allowed as the only non-determinism in their results, the
+/// bit patterns of NaNs, is removed by the compiler of untrusted code which
+/// canonicalizes the NaNs.
 const UNTRUSTED_WASM_FEATURES: WasmFeatures = WasmFeatures {
     mutable_global: false,
     saturating_float_to_int: false,
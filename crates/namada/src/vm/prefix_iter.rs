@@ -5,6 +5,10 @@ use std::collections::HashMap;
 
 use namada_state::PrefixIter;
 
+/// The maximum number of prefix iterators that can be created during a wasm
+/// run
+pub const MAX_PREFIX_ITERATORS: u64 = 1_024;
+
 /// A temporary iterators storage, used during a wasm run after which it's
 /// dropped. Each iterator is assigned a [`PrefixIteratorId`].
 #[derive(Debug)]
@@ -20,12 +24,19 @@ impl<'iter, DB> PrefixIterators<'iter, DB>
 where
     DB: namada_state::DB + namada_state::DBIter<'iter>,
 {
-    /// Insert a new prefix iterator to the temporary storage.
-    pub fn insert(&mut self, iter: PrefixIter<'iter, DB>) -> PrefixIteratorId {
+    /// Insert a new prefix iterator to the temporary storage. Returns `None`
+    /// when [`MAX_PREFIX_ITERATORS`] iterators have already been created.
+    pub fn insert(
+        &mut self,
+        iter: PrefixIter<'iter, DB>,
+    ) -> Option<PrefixIteratorId> {
+        if self.index.id() >= MAX_PREFIX_ITERATORS {
+            return None;
+        }
         let id = self.index;
         self.iterators.insert(id, iter);
         self.index = id.next_id();
-        id
+        Some(id)
     }
 
     /// Get the next item in the given prefix iterator.
@@ -77,3 +88,29 @@ impl PrefixIteratorId {
         PrefixIteratorId(self.0 + 1)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use namada_state::testing::TestStorage;
+    use namada_state::write_log::WriteLog;
+
+    use super::*;
+    use crate::types::storage::Key;
+
+    #[test]
+    fn test_max_prefix_iterators() {
+        let storage = TestStorage::default();
+        let write_log = WriteLog::default();
+        let prefix = Key::parse("prefix").unwrap();
+        let mut iterators = PrefixIterators::default();
+
+        for id in 0..MAX_PREFIX_ITERATORS {
+            let (iter, _gas) =
+                namada_state::iter_prefix_post(&write_log, &storage, &prefix);
+            assert_eq!(iterators.insert(iter), Some(PrefixIteratorId::new(id)));
+        }
+        let (iter, _gas) =
+            namada_state::iter_prefix_post(&write_log, &storage, &prefix);
+        assert_eq!(iterators.insert(iter), None);
+    }
+}
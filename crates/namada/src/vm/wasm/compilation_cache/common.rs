@@ -3,22 +3,31 @@
 //! limit and a file system cache of compiled modules (either to dynamic libs
 //! compiled via the `dylib` module, or serialized modules compiled via the
 //! `universal` module).
+//!
+//! The compiled modules are persisted keyed by their code hash, under a
+//! directory specific to the wasmer version that compiled them, so that they
+//! can be reused across node restarts. Each file is prefixed with the hash of
+//! the compiled module to detect corrupted files, which are removed and
+//! compiled again. The least recently used files are evicted when the total
+//! size of the persisted modules goes over a limit.
 
 use std::collections::hash_map::RandomState;
 use std::collections::HashMap;
 use std::fs;
+use std::io::Write;
 use std::marker::PhantomData;
 use std::num::NonZeroUsize;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use std::sync::{Arc, RwLock};
 use std::thread::sleep;
-use std::time::Duration;
+use std::time::{Duration, SystemTime};
 
 use clru::{CLruCache, CLruCacheConfig, WeightScale};
-use wasmer::{Module, Store};
-use wasmer_cache::{FileSystemCache, Hash as CacheHash};
+use memmap2::Mmap;
+use wasmer::{DeserializeError, Module, Store};
 
-use crate::core::types::hash::Hash;
+use crate::core::types::hash::{Hash, HASH_LENGTH};
 use crate::types::control_flow::time::{ExponentialBackoff, SleepStrategy};
 use crate::vm::wasm::run::untrusted_wasm_store;
 use crate::vm::wasm::{self, memory};
@@ -33,6 +42,8 @@ pub struct Cache<N: CacheName, A: WasmCacheAccess> {
     progress: Arc<RwLock<HashMap<Hash, Compilation>>>,
     /// In-memory LRU cache of compiled modules
     in_memory: Arc<RwLock<MemoryCache>>,
+    /// Index of the compiled modules persisted to files
+    on_disk: Arc<RwLock<DiskCache>>,
     /// The cache's name
     name: PhantomData<N>,
     /// Cache access level
@@ -66,6 +77,97 @@ impl WeightScale<Hash, Module> for ModuleCacheScale {
     }
 }
 
+/// The default limit of the total size of the compiled modules persisted to
+/// files
+pub const DEFAULT_MAX_DISK_BYTES: u64 = 2 * 1024 * 1024 * 1024; // 2 GiB
+
+/// Index of the compiled modules persisted to files, used to evict the least
+/// recently used ones when the total size goes over the limit
+#[derive(Debug)]
+struct DiskCache {
+    /// Limit of the total size of the files
+    max_bytes: u64,
+    /// Current total size of the files
+    total_bytes: u64,
+    /// Size and last use of the file of each module
+    modules: HashMap<Hash, DiskEntry>,
+    /// Logical clock ordering the uses of the files
+    clock: u64,
+}
+
+/// A compiled module persisted to a file
+#[derive(Debug)]
+struct DiskEntry {
+    /// Size of the file
+    size: u64,
+    /// Last use of the file
+    last_use: u64,
+}
+
+impl DiskCache {
+    fn new(max_bytes: u64) -> Self {
+        Self {
+            max_bytes,
+            total_bytes: 0,
+            modules: HashMap::default(),
+            clock: 0,
+        }
+    }
+
+    /// Mark the file of a module as used
+    fn touch(&mut self, hash: &Hash) {
+        self.clock += 1;
+        if let Some(entry) = self.modules.get_mut(hash) {
+            entry.last_use = self.clock;
+        }
+    }
+
+    /// Index a new file and return the modules whose files must be evicted to
+    /// stay within the size limit. The new file itself is never evicted.
+    fn insert(&mut self, hash: Hash, size: u64) -> Vec<Hash> {
+        self.remove(&hash);
+        self.clock += 1;
+        self.modules.insert(
+            hash,
+            DiskEntry {
+                size,
+                last_use: self.clock,
+            },
+        );
+        self.total_bytes += size;
+        self.evict(Some(&hash))
+    }
+
+    /// Evict the least recently used files until the total size is within
+    /// the limit, except for the given module. Returns the evicted modules.
+    fn evict(&mut self, keep: Option<&Hash>) -> Vec<Hash> {
+        let mut evicted = vec![];
+        while self.total_bytes > self.max_bytes {
+            let lru = self
+                .modules
+                .iter()
+                .filter(|(lru, _)| Some(*lru) != keep)
+                .min_by_key(|(_, entry)| entry.last_use)
+                .map(|(lru, _)| *lru);
+            match lru {
+                Some(lru) => {
+                    self.remove(&lru);
+                    evicted.push(lru);
+                }
+                None => break,
+            }
+        }
+        evicted
+    }
+
+    /// Remove the file of a module from the index
+    fn remove(&mut self, hash: &Hash) {
+        if let Some(entry) = self.modules.remove(hash) {
+            self.total_bytes -= entry.size;
+        }
+    }
+}
+
 impl<N: CacheName, A: WasmCacheAccess> Cache<N, A> {
     /// Create a wasm in-memory cache with a given size limit and a file
     /// system cache.
@@ -80,18 +182,41 @@ impl<N: CacheName, A: WasmCacheAccess> Cache<N, A> {
         let in_memory = Arc::new(RwLock::new(cache));
         let dir = dir.into();
 
-        fs::create_dir_all(&dir)
+        fs::create_dir_all(modules_dir(&dir))
             .expect("Couldn't create the wasm cache directory");
+        let on_disk = Arc::new(RwLock::new(load_disk_cache(
+            &dir,
+            DEFAULT_MAX_DISK_BYTES,
+        )));
 
         Self {
             dir,
             progress: Default::default(),
             in_memory,
+            on_disk,
             name: Default::default(),
             access: Default::default(),
         }
     }
 
+    /// Set the limit of the total size of the compiled modules persisted to
+    /// files. The least recently used files are evicted when it's exceeded.
+    pub fn with_max_disk_bytes(self, max_bytes: u64) -> Self {
+        let mut on_disk = self.on_disk.write().unwrap();
+        on_disk.max_bytes = max_bytes;
+        for hash in on_disk.evict(None) {
+            remove_module_file(&self.dir, &hash);
+        }
+        drop(on_disk);
+        self
+    }
+
+    /// Forget a module whose file couldn't be loaded, so that it gets compiled
+    /// again
+    fn forget(&self, hash: &Hash) {
+        self.progress.write().unwrap().remove(hash);
+    }
+
     /// Get a WASM module from LRU cache, from a file or compile it and cache
     /// it. If the cache access is set to [`crate::vm::WasmCacheRwAccess`], it
     /// updates the position in the LRU cache. Otherwise, the compiled
@@ -157,7 +282,7 @@ impl<N: CacheName, A: WasmCacheAccess> Cache<N, A> {
                     }
 
                     if let Ok((module, store)) =
-                        file_load_module(&self.dir, hash)
+                        file_load_module(&self.dir, &self.on_disk, hash)
                     {
                         tracing::info!(
                             "{} found {} in file cache.",
@@ -170,6 +295,9 @@ impl<N: CacheName, A: WasmCacheAccess> Cache<N, A> {
 
                         return Ok(Some((module, store)));
                     } else {
+                        // The file was evicted or corrupted
+                        drop(in_memory);
+                        self.forget(hash);
                         return Ok(None);
                     }
                 }
@@ -193,7 +321,9 @@ impl<N: CacheName, A: WasmCacheAccess> Cache<N, A> {
                             N::name(),
                             hash.to_string()
                         );
-                        if let Ok(res) = file_load_module(&self.dir, hash) {
+                        if let Ok(res) =
+                            file_load_module(&self.dir, &self.on_disk, hash)
+                        {
                             res
                         } else {
                             return Ok(None);
@@ -257,7 +387,7 @@ impl<N: CacheName, A: WasmCacheAccess> Cache<N, A> {
                     }
 
                     if let Ok((module, store)) =
-                        file_load_module(&self.dir, hash)
+                        file_load_module(&self.dir, &self.on_disk, hash)
                     {
                         tracing::info!(
                             "{} found {} in file cache.",
@@ -266,6 +396,9 @@ impl<N: CacheName, A: WasmCacheAccess> Cache<N, A> {
                         );
                         return Ok(Some((module, store)));
                     } else {
+                        // The file was evicted or corrupted
+                        drop(in_memory);
+                        self.forget(hash);
                         return Ok(None);
                     }
                 }
@@ -289,7 +422,9 @@ impl<N: CacheName, A: WasmCacheAccess> Cache<N, A> {
                             N::name(),
                             hash.to_string()
                         );
-                        if let Ok(res) = file_load_module(&self.dir, hash) {
+                        if let Ok(res) =
+                            file_load_module(&self.dir, &self.on_disk, hash)
+                        {
                             return Ok(Some(res));
                         } else {
                             return Ok(None);
@@ -335,7 +470,7 @@ impl<N: CacheName, A: WasmCacheAccess> Cache<N, A> {
             Ok(code) => match compile(code) {
                 Ok((module, store)) => {
                     // Write the file
-                    file_write_module(&self.dir, &module, &hash);
+                    file_write_module(&self.dir, &self.on_disk, &module, &hash);
 
                     // Update progress
                     let mut progress = self.progress.write().unwrap();
@@ -391,6 +526,7 @@ impl<N: CacheName, A: WasmCacheAccess> Cache<N, A> {
                     let progress = self.progress.clone();
                     let code = code.as_ref().to_vec();
                     let dir = self.dir.clone();
+                    let on_disk = self.on_disk.clone();
                     std::thread::spawn(move || {
                         tracing::info!("Compiling WASM {}.", hash.to_string());
 
@@ -418,7 +554,9 @@ impl<N: CacheName, A: WasmCacheAccess> Cache<N, A> {
                                                 N::name()
                                             )
                                         }
-                                        file_write_module(&dir, &module, &hash);
+                                        file_write_module(
+                                            &dir, &on_disk, &module, &hash,
+                                        );
                                         (module, store)
                                     }
                                     Err(err) => {
@@ -460,6 +598,7 @@ impl<N: CacheName, A: WasmCacheAccess> Cache<N, A> {
             dir: self.dir.clone(),
             progress: self.progress.clone(),
             in_memory: self.in_memory.clone(),
+            on_disk: self.on_disk.clone(),
             name: Default::default(),
             access: Default::default(),
         }
@@ -491,49 +630,200 @@ pub(crate) fn store() -> Store {
     universal::store()
 }
 
-fn file_write_module(dir: impl AsRef<Path>, module: &Module, hash: &Hash) {
-    use wasmer_cache::Cache;
-    let mut fs_cache = fs_cache(dir, hash);
-    fs_cache.store(CacheHash::new(hash.0), module).unwrap();
+/// Persist a compiled module to a file, prefixed with its hash, and evict the
+/// least recently used files if the size limit is exceeded
+fn file_write_module(
+    dir: impl AsRef<Path>,
+    on_disk: &RwLock<DiskCache>,
+    module: &Module,
+    hash: &Hash,
+) {
+    let bytes = match module.serialize() {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            tracing::error!("Error serializing wasm {hash}: {err}.");
+            return;
+        }
+    };
+    let checksum = Hash::sha256(&bytes);
+    let path = module_file_path(&dir, hash);
+    // Write to a temporary file first so that a crash can't leave a
+    // partially written module behind
+    let tmp_path = path.with_extension(TMP_FILE_EXT);
+    let write = || -> std::io::Result<()> {
+        let mut file = fs::File::create(&tmp_path)?;
+        file.write_all(&checksum.0)?;
+        file.write_all(&bytes)?;
+        file.sync_all()?;
+        fs::rename(&tmp_path, &path)
+    };
+    if let Err(err) = write() {
+        tracing::error!("Error writing cached wasm {hash}: {err}.");
+        let _ = fs::remove_file(&tmp_path);
+        return;
+    }
+
+    let size = (checksum.0.len() + bytes.len()) as u64;
+    let evicted = on_disk.write().unwrap().insert(*hash, size);
+    for hash in evicted {
+        tracing::info!("Evicting cached wasm {hash} from file cache.");
+        remove_module_file(&dir, &hash);
+    }
 }
 
+/// Load a compiled module from its memory-mapped file. A corrupted file is
+/// removed.
 fn file_load_module(
     dir: impl AsRef<Path>,
+    on_disk: &RwLock<DiskCache>,
     hash: &Hash,
-) -> Result<(Module, Store), wasmer::DeserializeError> {
-    use wasmer_cache::Cache;
-    let fs_cache = fs_cache(dir, hash);
+) -> Result<(Module, Store), DeserializeError> {
+    let path = module_file_path(&dir, hash);
+    let file = fs::File::open(path)?;
+    // Safety: the file is only written to a temporary path and then renamed,
+    // so a mapped file is never modified
+    let mmap = unsafe { Mmap::map(&file) }?;
     let store = store();
-    let hash = CacheHash::new(hash.0);
-    let module = unsafe { fs_cache.load(&store, hash) };
-    if let Err(err) = module.as_ref() {
-        tracing::error!(
-            "Error loading cached wasm {}: {err}.",
-            hash.to_string()
-        );
+    let module = match verified_module_bytes(&mmap) {
+        // Safety: the module was serialized by this cache with the same
+        // wasmer version
+        Some(bytes) => unsafe { Module::deserialize(&store, bytes) },
+        None => Err(DeserializeError::CorruptedBinary(
+            "Checksum mismatch".to_string(),
+        )),
+    };
+    match module {
+        Ok(module) => {
+            on_disk.write().unwrap().touch(hash);
+            Ok((module, store))
+        }
+        Err(err) => {
+            tracing::error!("Error loading cached wasm {hash}: {err}.");
+            on_disk.write().unwrap().remove(hash);
+            remove_module_file(&dir, hash);
+            Err(err)
+        }
     }
-    Ok((module?, store))
 }
 
-fn fs_cache(dir: impl AsRef<Path>, hash: &Hash) -> FileSystemCache {
-    let path = dir.as_ref().join(hash.to_string().to_lowercase());
-    let mut fs_cache = FileSystemCache::new(path).unwrap();
-    fs_cache.set_cache_extension(Some(file_ext()));
-    fs_cache
+/// Get the bytes of a compiled module from the contents of its file, if the
+/// hash it's prefixed with matches
+fn verified_module_bytes(contents: &[u8]) -> Option<&[u8]> {
+    if contents.len() < HASH_LENGTH {
+        return None;
+    }
+    let (checksum, bytes) = contents.split_at(HASH_LENGTH);
+    (Hash::sha256(bytes).0 == checksum).then_some(bytes)
+}
+
+/// Index the compiled modules persisted in the cache directory, removing the
+/// corrupted files and the ones compiled by other wasmer versions or compiler
+/// configurations or persisted with the previous layout
+fn load_disk_cache(dir: impl AsRef<Path>, max_bytes: u64) -> DiskCache {
+    let modules_dir = modules_dir(&dir);
+    if let Ok(entries) = fs::read_dir(&dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let is_stale = path.is_dir()
+                && path != modules_dir
+                && path.file_name().and_then(|name| name.to_str()).map_or(
+                    false,
+                    |name| {
+                        name.starts_with("wasmer-")
+                            || Hash::from_str(name).is_ok()
+                    },
+                );
+            if is_stale {
+                tracing::info!(
+                    "Removing stale wasm cache directory {}.",
+                    path.to_string_lossy()
+                );
+                let _ = fs::remove_dir_all(path);
+            }
+        }
+    }
+
+    let mut modules = vec![];
+    if let Ok(entries) = fs::read_dir(&modules_dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let hash = match path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| Hash::from_str(stem).ok())
+            {
+                Some(hash) if path.extension() == Some(file_ext().as_ref()) => {
+                    hash
+                }
+                _ => {
+                    // Leftover temporary file or unknown file
+                    let _ = fs::remove_file(path);
+                    continue;
+                }
+            };
+            let is_valid = fs::File::open(&path)
+                .and_then(|file| unsafe { Mmap::map(&file) })
+                .map(|mmap| verified_module_bytes(&mmap).is_some())
+                .unwrap_or(false);
+            if !is_valid {
+                tracing::error!("Removing corrupted cached wasm {hash}.");
+                let _ = fs::remove_file(path);
+                continue;
+            }
+            let metadata = match entry.metadata() {
+                Ok(metadata) => metadata,
+                Err(_) => continue,
+            };
+            let modified =
+                metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            modules.push((modified, hash, metadata.len()));
+        }
+    }
+
+    // Index the files from the least recently modified one
+    modules.sort();
+    let mut on_disk = DiskCache::new(max_bytes);
+    for (_, hash, size) in modules {
+        for hash in on_disk.insert(hash, size) {
+            remove_module_file(&dir, &hash);
+        }
+    }
+    on_disk
+}
+
+/// The directory of the compiled modules, specific to the wasmer version
+/// The version of the configuration of the compiler of untrusted wasm code,
+/// to be bumped on any change of the configuration so that the modules compiled
+/// with a previous configuration are discarded
+const COMPILER_CONFIG_VERSION: u32 = 1;
+
+fn modules_dir(dir: impl AsRef<Path>) -> PathBuf {
+    dir.as_ref().join(format!(
+        "wasmer-{}-{}",
+        wasmer::VERSION,
+        COMPILER_CONFIG_VERSION
+    ))
+}
+
+fn module_file_path(dir: impl AsRef<Path>, hash: &Hash) -> PathBuf {
+    modules_dir(dir).join(format!(
+        "{}.{}",
+        hash.to_string().to_lowercase(),
+        file_ext()
+    ))
+}
+
+fn remove_module_file(dir: impl AsRef<Path>, hash: &Hash) {
+    let _ = fs::remove_file(module_file_path(dir, hash));
 }
 
 fn module_file_exists(dir: impl AsRef<Path>, hash: &Hash) -> bool {
-    let file =
-        dir.as_ref()
-            .join(hash.to_string().to_lowercase())
-            .join(format!(
-                "{}.{}",
-                hash.to_string().to_lowercase(),
-                file_ext()
-            ));
-    file.exists()
+    module_file_path(dir, hash).exists()
 }
 
+/// The extension of the temporary files written before being renamed
+const TMP_FILE_EXT: &str = "tmp";
+
 /// A universal engine compilation. The module can be serialized to/from bytes.
 mod universal {
     use super::*;
@@ -585,7 +875,7 @@ mod dylib {
     /// Dylib WASM store
     #[allow(dead_code)]
     pub fn store() -> Store {
-        let compiler = wasmer_compiler_singlepass::Singlepass::default();
+        let compiler = wasm::run::untrusted_wasm_compiler();
         let engine = wasmer_engine_dylib::Dylib::new(compiler).engine();
         Store::new_with_tunables(&engine, memory::vp_limit())
     }
@@ -1037,6 +1327,70 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_persisted_modules() {
+        let tx_read_storage_key = load_wasm(TestWasms::TxReadStorageKey.path());
+        let tx_no_op = load_wasm(TestWasms::TxNoOp.path());
+
+        let (mut cache, tmp_dir) = cache(10);
+        cache.compile_or_fetch(&tx_read_storage_key.code).unwrap();
+        cache.compile_or_fetch(&tx_no_op.code).unwrap();
+
+        // The modules are loaded from the files after a restart
+        {
+            let mut cache: Cache<TestCache, WasmCacheRwAccess> =
+                Cache::new(tmp_dir.path(), 10);
+            let fetched = cache.fetch(&tx_no_op.hash).unwrap();
+            assert_matches!(
+                fetched,
+                Some(_),
+                "The module must be loaded from the file"
+            );
+        }
+
+        // A corrupted file is removed on restart and the module compiled again
+        {
+            let path = module_file_path(&cache.dir, &tx_no_op.hash);
+            let mut contents = fs::read(&path).unwrap();
+            let last = contents.len() - 1;
+            contents[last] ^= 1;
+            fs::write(&path, contents).unwrap();
+
+            let mut cache: Cache<TestCache, WasmCacheRwAccess> =
+                Cache::new(tmp_dir.path(), 10);
+            assert!(
+                !module_file_exists(&cache.dir, &tx_no_op.hash),
+                "The corrupted file must be removed"
+            );
+            let fetched = cache.fetch(&tx_no_op.hash).unwrap();
+            assert_matches!(fetched, None, "The module must not be in cache");
+            let fetched = cache.compile_or_fetch(&tx_no_op.code).unwrap();
+            assert_matches!(fetched, Some(_), "The code should be compiled");
+            assert!(
+                module_file_exists(&cache.dir, &tx_no_op.hash),
+                "The file must be written"
+            );
+        }
+
+        // The least recently used file is evicted over the size limit
+        {
+            let size =
+                fs::metadata(module_file_path(&cache.dir, &tx_no_op.hash))
+                    .unwrap()
+                    .len();
+            let cache: Cache<TestCache, WasmCacheRwAccess> =
+                Cache::new(tmp_dir.path(), 10).with_max_disk_bytes(size);
+            assert!(
+                !module_file_exists(&cache.dir, &tx_read_storage_key.hash),
+                "The least recently used file must be evicted"
+            );
+            assert!(
+                module_file_exists(&cache.dir, &tx_no_op.hash),
+                "The most recently used file must be kept"
+            );
+        }
+    }
+
     /// Get the WASM code bytes, its hash and find the compiled module's size
     fn load_wasm(file: impl AsRef<Path>) -> WasmWithMeta {
         // When `WeightScale` calls `loupe::size_of_val` in the cache, for some
@@ -51,9 +51,9 @@ pub fn tx_imports<DB, H, CA>(
     env: TxVmEnv<'static, WasmMemory, DB, H, CA>,
 ) -> ImportObject
 where
-    DB: namada_state::DB + for<'iter> namada_state::DBIter<'iter>,
-    H: StorageHasher,
-    CA: WasmCacheAccess,
+    DB: 'static + namada_state::DB + for<'iter> namada_state::DBIter<'iter>,
+    H: 'static + StorageHasher,
+    CA: 'static + WasmCacheAccess,
 {
     wasmer::imports! {
         // default namespace
@@ -75,19 +75,26 @@ where
             "namada_tx_update_validity_predicate" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_update_validity_predicate),
             "namada_tx_init_account" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_init_account),
             "namada_tx_emit_ibc_event" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_emit_ibc_event),
+            "namada_tx_emit_event" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_emit_event),
             "namada_tx_get_ibc_events" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_get_ibc_events),
             "namada_tx_get_chain_id" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_get_chain_id),
             "namada_tx_get_tx_index" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_get_tx_index),
             "namada_tx_get_block_height" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_get_block_height),
             "namada_tx_get_block_header" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_get_block_header),
             "namada_tx_get_block_hash" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_get_block_hash),
+            "namada_tx_get_block_entropy" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_get_block_entropy),
             "namada_tx_get_block_epoch" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_get_block_epoch),
             "namada_tx_get_pred_epochs" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_get_pred_epochs),
             "namada_tx_get_native_token" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_get_native_token),
             "namada_tx_log_string" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_log_string),
+            "namada_tx_abort" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_abort),
             "namada_tx_ibc_execute" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_ibc_execute),
             "namada_tx_set_commitment_sentinel" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_set_commitment_sentinel),
             "namada_tx_verify_tx_section_signature" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_verify_tx_section_signature),
+            "namada_tx_verify_signature" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_verify_signature),
+            "namada_tx_recover_secp256k1_public_key" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_recover_secp256k1_public_key),
+            "namada_tx_invoke_sub_tx" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_invoke_sub_tx),
+            "namada_tx_simulate_sub_tx" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_simulate_sub_tx),
             "namada_tx_update_masp_note_commitment_tree" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_update_masp_note_commitment_tree)
         },
     }
@@ -101,10 +108,10 @@ pub fn vp_imports<DB, H, EVAL, CA>(
     env: VpVmEnv<'static, WasmMemory, DB, H, EVAL, CA>,
 ) -> ImportObject
 where
-    DB: namada_state::DB + for<'iter> namada_state::DBIter<'iter>,
-    H: StorageHasher,
+    DB: 'static + namada_state::DB + for<'iter> namada_state::DBIter<'iter>,
+    H: 'static + StorageHasher,
     EVAL: VpEvaluator<Db = DB, H = H, Eval = EVAL, CA = CA>,
-    CA: WasmCacheAccess,
+    CA: 'static + WasmCacheAccess,
 {
     wasmer::imports! {
         // default namespace
@@ -128,14 +135,19 @@ where
             "namada_vp_get_block_height" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_get_block_height),
             "namada_vp_get_block_header" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_get_block_header),
             "namada_vp_get_block_hash" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_get_block_hash),
+            "namada_vp_get_block_entropy" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_get_block_entropy),
             "namada_vp_get_tx_code_hash" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_get_tx_code_hash),
             "namada_vp_get_block_epoch" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_get_block_epoch),
             "namada_vp_get_pred_epochs" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_get_pred_epochs),
             "namada_vp_get_ibc_events" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_get_ibc_events),
             "namada_vp_verify_tx_section_signature" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_verify_tx_section_signature),
+            "namada_vp_verify_signature" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_verify_signature),
+            "namada_vp_recover_secp256k1_public_key" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_recover_secp256k1_public_key),
             "namada_vp_eval" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_eval),
+            "namada_vp_simulate_sub_tx" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_simulate_sub_tx),
             "namada_vp_get_native_token" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_get_native_token),
             "namada_vp_log_string" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_log_string),
+            "namada_vp_abort" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_abort),
         },
     }
 }
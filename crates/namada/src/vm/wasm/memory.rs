@@ -7,6 +7,7 @@ use std::sync::Arc;
 
 use borsh_ext::BorshSerializeExt;
 use namada_gas::MEMORY_ACCESS_GAS_PER_BYTE;
+use namada_parameters::MAX_WASM_MEMORY_PAGES;
 use namada_tx::Tx;
 use thiserror::Error;
 use wasmer::{
@@ -46,27 +47,35 @@ pub type Result<T> = std::result::Result<T, Error>;
 /// Initial pages in tx memory
 pub const TX_MEMORY_INIT_PAGES: u32 = 100; // 6.4 MiB
 /// Mamixmum pages in tx memory
-pub const TX_MEMORY_MAX_PAGES: u32 = 200; // 12.8 MiB
+pub const TX_MEMORY_MAX_PAGES: u32 = MAX_WASM_MEMORY_PAGES; // 12.8 MiB
 /// Initial pages in VP memory
 pub const VP_MEMORY_INIT_PAGES: u32 = 100; // 6.4 MiB
 /// Mamixmum pages in VP memory
-pub const VP_MEMORY_MAX_PAGES: u32 = 200; // 12.8 MiB
-
-/// Prepare memory for instantiating a transaction module
-pub fn prepare_tx_memory(store: &wasmer::Store) -> Result<wasmer::Memory> {
+pub const VP_MEMORY_MAX_PAGES: u32 = MAX_WASM_MEMORY_PAGES; // 12.8 MiB
+
+/// Prepare memory for instantiating a transaction module, bounded to the given
+/// maximum number of pages
+pub fn prepare_tx_memory(
+    store: &wasmer::Store,
+    max_pages: u32,
+) -> Result<wasmer::Memory> {
     let mem_type = wasmer::MemoryType::new(
-        TX_MEMORY_INIT_PAGES,
-        Some(TX_MEMORY_MAX_PAGES),
+        TX_MEMORY_INIT_PAGES.min(max_pages),
+        Some(max_pages),
         false,
     );
     Memory::new(store, mem_type).map_err(Error::InitMemoryError)
 }
 
-/// Prepare memory for instantiating a validity predicate module
-pub fn prepare_vp_memory(store: &wasmer::Store) -> Result<wasmer::Memory> {
+/// Prepare memory for instantiating a validity predicate module, bounded to
+/// the given maximum number of pages
+pub fn prepare_vp_memory(
+    store: &wasmer::Store,
+    max_pages: u32,
+) -> Result<wasmer::Memory> {
     let mem_type = wasmer::MemoryType::new(
-        VP_MEMORY_INIT_PAGES,
-        Some(VP_MEMORY_MAX_PAGES),
+        VP_MEMORY_INIT_PAGES.min(max_pages),
+        Some(max_pages),
         false,
     );
     let memory =
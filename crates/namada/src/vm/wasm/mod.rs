@@ -3,6 +3,7 @@
 pub mod compilation_cache;
 pub mod host_env;
 pub mod memory;
+pub mod native;
 pub mod run;
 
 pub use compilation_cache::common::{Cache, CacheName};
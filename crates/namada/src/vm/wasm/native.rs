@@ -0,0 +1,212 @@
+//! Native implementations of wasm transactions.
+//!
+//! A transaction whose code hash is in the native tx allowlist parameter and
+//! matches the hash of one of the wasm codes with a native implementation is
+//! executed natively instead of in the wasm runtime. Only the storage accesses
+//! are charged, the wasm loading and compilation gas is skipped. Any other
+//! transaction falls back to the wasm runtime.
+
+use std::collections::BTreeSet;
+
+use borsh::BorshDeserialize;
+use namada_gas::TxGasMeter;
+use namada_ibc::IbcStorageContext;
+use namada_state::{State, StorageHasher};
+use namada_tx::data::TxSentinel;
+use namada_tx::Tx;
+
+use super::memory::WasmMemory;
+use super::run::{Error, Result};
+use super::{TxCache, VpCache};
+use crate::state::write_log::WriteLog;
+use crate::token;
+use crate::types::address::Address;
+use crate::types::hash::Hash;
+use crate::types::key::common;
+use crate::types::storage::{Key, TxIndex};
+use crate::vm::host_env::{TxCtx, TxVmEnv};
+use crate::vm::prefix_iter::PrefixIterators;
+use crate::vm::WasmCacheAccess;
+
+/// A transaction with a native implementation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NativeTx {
+    /// Reveal a public key
+    RevealPk,
+    /// Transfer tokens
+    Transfer,
+}
+
+impl NativeTx {
+    /// All the transactions with a native implementation
+    pub const ALL: [NativeTx; 2] = [NativeTx::RevealPk, NativeTx::Transfer];
+
+    /// The name of the wasm code implemented by this transaction
+    pub fn wasm_name(&self) -> &'static str {
+        match self {
+            NativeTx::RevealPk => "tx_reveal_pk.wasm",
+            NativeTx::Transfer => "tx_transfer.wasm",
+        }
+    }
+}
+
+/// Find the native implementation of the tx code with the given hash. Returns
+/// `None` if the hash is not in the native tx allowlist or if the code doesn't
+/// have a native implementation.
+pub fn find_native_tx<DB, H>(
+    storage: &State<DB, H>,
+    code_hash: &Hash,
+) -> Result<Option<NativeTx>>
+where
+    DB: 'static + namada_state::DB + for<'iter> namada_state::DBIter<'iter>,
+    H: 'static + StorageHasher,
+{
+    let allowlist_key =
+        namada_parameters::storage::get_native_tx_allowlist_storage_key();
+    let allowlist: Vec<String> =
+        read_from_storage(storage, &allowlist_key)?.unwrap_or_default();
+    if !allowlist.contains(&code_hash.to_string().to_lowercase()) {
+        return Ok(None);
+    }
+
+    for native_tx in NativeTx::ALL {
+        let hash_key = Key::wasm_hash(native_tx.wasm_name());
+        if read_from_storage::<_, _, Hash>(storage, &hash_key)?.as_ref()
+            == Some(code_hash)
+        {
+            return Ok(Some(native_tx));
+        }
+    }
+    Ok(None)
+}
+
+/// Execute a transaction natively. Returns the set verifiers addresses
+/// requested by the transaction.
+#[allow(clippy::too_many_arguments)]
+pub fn tx<DB, H, CA>(
+    native_tx: NativeTx,
+    storage: &State<DB, H>,
+    write_log: &mut WriteLog,
+    gas_meter: &mut TxGasMeter,
+    tx_index: &TxIndex,
+    tx: &Tx,
+    vp_wasm_cache: &mut VpCache<CA>,
+    tx_wasm_cache: &mut TxCache<CA>,
+) -> Result<BTreeSet<Address>>
+where
+    DB: 'static + namada_state::DB + for<'iter> namada_state::DBIter<'iter>,
+    H: 'static + StorageHasher,
+    CA: 'static + WasmCacheAccess,
+{
+    tracing::debug!("Executing the native implementation of {:?}", native_tx);
+
+    let mut iterators: PrefixIterators<'_, DB> = PrefixIterators::default();
+    let mut verifiers = BTreeSet::new();
+    let mut result_buffer: Option<Vec<u8>> = None;
+
+    let mut sentinel = TxSentinel::default();
+    let TxVmEnv { mut ctx, .. } = TxVmEnv::new(
+        WasmMemory::default(),
+        storage,
+        write_log,
+        &mut iterators,
+        gas_meter,
+        &mut sentinel,
+        tx,
+        tx_index,
+        &mut verifiers,
+        &mut result_buffer,
+        vp_wasm_cache,
+        tx_wasm_cache,
+    );
+
+    let result = match native_tx {
+        NativeTx::RevealPk => reveal_pk(&mut ctx, tx),
+        NativeTx::Transfer => transfer(&mut ctx, tx),
+    };
+    result.map_err(|err| match (err, sentinel) {
+        (Error::NativeTxError(err), TxSentinel::OutOfGas) => {
+            Error::GasError(err.to_string())
+        }
+        (err, _) => err,
+    })?;
+
+    Ok(verifiers)
+}
+
+/// Native implementation of `tx_reveal_pk.wasm`
+fn reveal_pk<DB, H, CA>(ctx: &mut TxCtx<'_, DB, H, CA>, tx: &Tx) -> Result<()>
+where
+    DB: 'static + namada_state::DB + for<'iter> namada_state::DBIter<'iter>,
+    H: 'static + StorageHasher,
+    CA: 'static + WasmCacheAccess,
+{
+    let data = tx
+        .data()
+        .ok_or_else(|| Error::MissingSection("Missing data".to_string()))?;
+    let pk = common::PublicKey::try_from_slice(&data[..])
+        .map_err(|e| Error::ConversionError(e.to_string()))?;
+
+    namada_account::reveal_pk(ctx, &pk).map_err(Error::NativeTxError)
+}
+
+/// Native implementation of `tx_transfer.wasm`
+fn transfer<DB, H, CA>(ctx: &mut TxCtx<'_, DB, H, CA>, tx: &Tx) -> Result<()>
+where
+    DB: 'static + namada_state::DB + for<'iter> namada_state::DBIter<'iter>,
+    H: 'static + StorageHasher,
+    CA: 'static + WasmCacheAccess,
+{
+    let data = tx
+        .data()
+        .ok_or_else(|| Error::MissingSection("Missing data".to_string()))?;
+    let transfer = token::Transfer::try_from_slice(&data[..])
+        .map_err(|e| Error::ConversionError(e.to_string()))?;
+
+    ctx.transfer_token(
+        &transfer.source,
+        &transfer.target,
+        &transfer.token,
+        transfer.amount,
+    )
+    .map_err(Error::NativeTxError)?;
+
+    if let Some(hash) = transfer.shielded.as_ref() {
+        let shielded = tx
+            .get_section(hash)
+            .and_then(|x| x.as_ref().masp_tx())
+            .ok_or_else(|| {
+                Error::MissingSection(
+                    "unable to find shielded section".to_string(),
+                )
+            })?;
+        ctx.handle_masp_tx(&shielded, transfer.key.as_deref())
+            .map_err(Error::NativeTxError)?;
+    }
+    Ok(())
+}
+
+fn read_from_storage<DB, H, T>(
+    storage: &State<DB, H>,
+    key: &Key,
+) -> Result<Option<T>>
+where
+    DB: 'static + namada_state::DB + for<'iter> namada_state::DBIter<'iter>,
+    H: 'static + StorageHasher,
+    T: BorshDeserialize,
+{
+    storage
+        .read(key)
+        .map_err(|e| {
+            Error::LoadWasmCode(format!(
+                "Read failed from storage: key {}, error {}",
+                key, e
+            ))
+        })?
+        .0
+        .map(|value| {
+            T::try_from_slice(&value)
+                .map_err(|e| Error::ConversionError(e.to_string()))
+        })
+        .transpose()
+}
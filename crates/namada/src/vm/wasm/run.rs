@@ -1,15 +1,19 @@
 //! Wasm runners
 
 use std::collections::BTreeSet;
+use std::error::Error as _;
 use std::marker::PhantomData;
 
 use borsh::BorshDeserialize;
 use namada_core::types::validity_predicate::VpSentinel;
-use namada_gas::{GasMetering, TxGasMeter, WASM_MEMORY_PAGE_GAS};
+use namada_gas::{
+    GasMetering, TxGasMeter, MEMORY_ACCESS_GAS_PER_BYTE, WASM_MEMORY_PAGE_GAS,
+};
+use namada_parameters::{WasmInstanceLimits, WasmLimits};
 use namada_state::write_log::StorageModification;
 use namada_state::{State, StorageHasher};
-use namada_tx::data::TxSentinel;
-use namada_tx::{Commitment, Section, Tx};
+use namada_tx::data::{TxSentinel, WasmError, WasmErrorCode};
+use namada_tx::{Code, Commitment, Data, Section, Tx};
 use parity_wasm::elements;
 use thiserror::Error;
 use wasmer::{BaseTunables, Module, Store};
@@ -17,16 +21,19 @@ use wasmer::{BaseTunables, Module, Store};
 use super::memory::{Limit, WasmMemory};
 use super::TxCache;
 use crate::ledger::gas::VpGasMeter;
+use crate::ledger::vp_host_fns;
 use crate::state::write_log::WriteLog;
 use crate::types::address::Address;
 use crate::types::hash::{Error as TxHashError, Hash};
 use crate::types::internal::HostEnvResult;
 use crate::types::storage::{Key, TxIndex};
-use crate::vm::host_env::{TxVmEnv, VpCtx, VpEvaluator, VpVmEnv};
+use crate::vm::host_env::{
+    TxRuntimeError, TxVmEnv, VpCtx, VpEvaluator, VpVmEnv,
+};
 use crate::vm::prefix_iter::PrefixIterators;
 use crate::vm::types::VpInput;
 use crate::vm::wasm::host_env::{tx_imports, vp_imports};
-use crate::vm::wasm::{memory, Cache, CacheName, VpCache};
+use crate::vm::wasm::{memory, native, Cache, CacheName, VpCache};
 use crate::vm::{
     validate_untrusted_wasm, WasmCacheAccess, WasmValidationError,
 };
@@ -35,6 +42,9 @@ const TX_ENTRYPOINT: &str = "_apply_tx";
 const VP_ENTRYPOINT: &str = "_validate_tx";
 const WASM_STACK_LIMIT: u32 = u16::MAX as u32;
 
+/// The max depth of nested sub-transaction invocations
+pub const MAX_SUB_TX_DEPTH: u8 = 4;
+
 #[allow(missing_docs)]
 #[derive(Error, Debug)]
 pub enum Error {
@@ -83,11 +93,73 @@ pub enum Error {
     ConversionError(String),
     #[error("Invalid transaction signature")]
     InvalidTxSignature,
+    #[error("Native tx error: {0}")]
+    NativeTxError(namada_state::StorageError),
+    #[error(
+        "Wasm module requests {requested} memory pages, exceeding the limit \
+         of {limit}"
+    )]
+    MemoryLimitExceeded { requested: u32, limit: u32 },
+    #[error(
+        "Wasm module requests {requested} table elements, exceeding the limit \
+         of {limit}"
+    )]
+    TableLimitExceeded { requested: u32, limit: u32 },
+    #[error(
+        "Wasm module declares {requested} globals, exceeding the limit of \
+         {limit}"
+    )]
+    GlobalsLimitExceeded { requested: usize, limit: u32 },
+    #[error("Tx code {0} is not allowed in the sub-tx allowlist parameter")]
+    DisallowedSubTx(Hash),
+    #[error("Sub-transactions cannot be nested deeper than {0}")]
+    SubTxDepthExceeded(u8),
 }
 
 /// Result for functions that may fail
 pub type Result<T> = std::result::Result<T, Error>;
 
+impl Error {
+    /// Get the protocol-defined code and the size-limited message of the
+    /// error to be reported in a tx result
+    pub fn to_wasm_error(&self) -> WasmError {
+        match self {
+            Error::RuntimeError(err) => {
+                let source = err.source();
+                let aborted = source
+                    .and_then(|err| err.downcast_ref::<TxRuntimeError>())
+                    .and_then(|err| match err {
+                        TxRuntimeError::Aborted(msg) => Some(msg),
+                        _ => None,
+                    })
+                    .or_else(|| {
+                        source
+                            .and_then(|err| {
+                                err.downcast_ref::<vp_host_fns::RuntimeError>()
+                            })
+                            .and_then(|err| match err {
+                                vp_host_fns::RuntimeError::Aborted(msg) => {
+                                    Some(msg)
+                                }
+                                _ => None,
+                            })
+                    });
+                match aborted {
+                    Some(msg) => WasmError::new(WasmErrorCode::Aborted, msg),
+                    None => WasmError::new(WasmErrorCode::Trap, err.message()),
+                }
+            }
+            Error::GasError(_) => {
+                WasmError::new(WasmErrorCode::OutOfGas, self.to_string())
+            }
+            Error::InvalidTxSignature => {
+                WasmError::new(WasmErrorCode::InvalidSig, self.to_string())
+            }
+            _ => WasmError::new(WasmErrorCode::Other, self.to_string()),
+        }
+    }
+}
+
 /// Execute a transaction code. Returns the set verifiers addresses requested by
 /// the transaction.
 #[allow(clippy::too_many_arguments)]
@@ -100,6 +172,204 @@ pub fn tx<DB, H, CA>(
     vp_wasm_cache: &mut VpCache<CA>,
     tx_wasm_cache: &mut TxCache<CA>,
 ) -> Result<BTreeSet<Address>>
+where
+    DB: 'static + namada_state::DB + for<'iter> namada_state::DBIter<'iter>,
+    H: 'static + StorageHasher,
+    CA: 'static + WasmCacheAccess,
+{
+    tx_at_depth(
+        storage,
+        write_log,
+        gas_meter,
+        tx_index,
+        tx,
+        vp_wasm_cache,
+        tx_wasm_cache,
+        0,
+    )
+}
+
+/// Execute the allowlisted tx code with the given hash as a sub-transaction of
+/// the given transaction, with the given data. The sub-transaction shares the
+/// write log and the gas meter of its parent. Returns the set verifiers
+/// addresses requested by the sub-transaction.
+#[allow(clippy::too_many_arguments)]
+pub fn sub_tx<DB, H, CA>(
+    storage: &State<DB, H>,
+    write_log: &mut WriteLog,
+    gas_meter: &mut TxGasMeter,
+    tx_index: &TxIndex,
+    tx: &Tx,
+    code_hash: Hash,
+    data: Vec<u8>,
+    vp_wasm_cache: &mut VpCache<CA>,
+    tx_wasm_cache: &mut TxCache<CA>,
+    sub_tx_depth: u8,
+) -> Result<BTreeSet<Address>>
+where
+    DB: 'static + namada_state::DB + for<'iter> namada_state::DBIter<'iter>,
+    H: 'static + StorageHasher,
+    CA: 'static + WasmCacheAccess,
+{
+    let sub_tx =
+        prepare_sub_tx(storage, gas_meter, tx, code_hash, data, sub_tx_depth)?;
+    tx_at_depth(
+        storage,
+        write_log,
+        gas_meter,
+        tx_index,
+        &sub_tx,
+        vp_wasm_cache,
+        tx_wasm_cache,
+        sub_tx_depth,
+    )
+}
+
+/// Simulate the execution of a transaction at the given depth of nested
+/// sub-transaction invocations on a fork of the given write log, which is left
+/// untouched. The gas of the execution is charged to the given gas meter.
+/// Returns the forked write log, holding only the would-be storage
+/// modifications and events of the transaction in its current tx log, and the
+/// set of verifiers addresses requested by the transaction. The copy of the
+/// write log is charged to the gas meter in proportion to its size.
+#[allow(clippy::too_many_arguments)]
+pub fn simulate_tx<DB, H, CA>(
+    storage: &State<DB, H>,
+    write_log: &WriteLog,
+    gas_meter: &mut TxGasMeter,
+    tx_index: &TxIndex,
+    tx: &Tx,
+    vp_wasm_cache: &mut VpCache<CA>,
+    tx_wasm_cache: &mut TxCache<CA>,
+    sub_tx_depth: u8,
+) -> Result<(WriteLog, BTreeSet<Address>)>
+where
+    DB: 'static + namada_state::DB + for<'iter> namada_state::DBIter<'iter>,
+    H: 'static + StorageHasher,
+    CA: 'static + WasmCacheAccess,
+{
+    gas_meter
+        .consume(write_log.size().saturating_mul(MEMORY_ACCESS_GAS_PER_BYTE))
+        .map_err(|e| Error::GasError(e.to_string()))?;
+    let mut fork = write_log.clone();
+    // Set aside the modifications and events of the current tx, which remain
+    // readable by the simulated tx, to only keep its own in the tx log
+    fork.precommit_tx();
+    fork.take_ibc_events();
+    fork.take_tx_events();
+
+    let verifiers = tx_at_depth(
+        storage,
+        &mut fork,
+        gas_meter,
+        tx_index,
+        tx,
+        vp_wasm_cache,
+        tx_wasm_cache,
+        sub_tx_depth,
+    )?;
+    Ok((fork, verifiers))
+}
+
+/// Simulate the execution of the allowlisted tx code with the given hash as a
+/// sub-transaction of the given transaction, with the given data, on a fork of
+/// the given write log (see [`sub_tx`] and [`simulate_tx`]).
+#[allow(clippy::too_many_arguments)]
+pub fn simulate_sub_tx<DB, H, CA>(
+    storage: &State<DB, H>,
+    write_log: &WriteLog,
+    gas_meter: &mut TxGasMeter,
+    tx_index: &TxIndex,
+    tx: &Tx,
+    code_hash: Hash,
+    data: Vec<u8>,
+    vp_wasm_cache: &mut VpCache<CA>,
+    tx_wasm_cache: &mut TxCache<CA>,
+    sub_tx_depth: u8,
+) -> Result<(WriteLog, BTreeSet<Address>)>
+where
+    DB: 'static + namada_state::DB + for<'iter> namada_state::DBIter<'iter>,
+    H: 'static + StorageHasher,
+    CA: 'static + WasmCacheAccess,
+{
+    let sub_tx =
+        prepare_sub_tx(storage, gas_meter, tx, code_hash, data, sub_tx_depth)?;
+    simulate_tx(
+        storage,
+        write_log,
+        gas_meter,
+        tx_index,
+        &sub_tx,
+        vp_wasm_cache,
+        tx_wasm_cache,
+        sub_tx_depth,
+    )
+}
+
+/// Check that the tx code with the given hash is allowlisted for
+/// sub-transactions and that the depth of nested invocations is within
+/// bounds. Returns the sub-transaction to be executed.
+fn prepare_sub_tx<DB, H>(
+    storage: &State<DB, H>,
+    gas_meter: &mut TxGasMeter,
+    tx: &Tx,
+    code_hash: Hash,
+    data: Vec<u8>,
+    sub_tx_depth: u8,
+) -> Result<Tx>
+where
+    DB: 'static + namada_state::DB + for<'iter> namada_state::DBIter<'iter>,
+    H: 'static + StorageHasher,
+{
+    if sub_tx_depth > MAX_SUB_TX_DEPTH {
+        return Err(Error::SubTxDepthExceeded(MAX_SUB_TX_DEPTH));
+    }
+
+    let allowlist_key =
+        namada_parameters::storage::get_sub_tx_allowlist_storage_key();
+    let (allowlist, gas) = storage.read(&allowlist_key).map_err(|e| {
+        Error::LoadWasmCode(format!(
+            "Read failed from storage: key {}, error {}",
+            allowlist_key, e
+        ))
+    })?;
+    gas_meter
+        .consume(gas)
+        .map_err(|e| Error::GasError(e.to_string()))?;
+    let allowlist: Vec<String> = allowlist
+        .map(|value| Vec::<String>::try_from_slice(&value))
+        .transpose()
+        .map_err(|e| Error::ConversionError(e.to_string()))?
+        .unwrap_or_default();
+    if !allowlist.contains(&code_hash.to_string().to_lowercase()) {
+        return Err(Error::DisallowedSubTx(code_hash));
+    }
+
+    // The sections of the sub-tx must not be salted with the local time for
+    // its execution to be deterministic
+    let mut sub_tx = tx.clone();
+    sub_tx.set_code(Code {
+        salt: [0; 8],
+        code: Commitment::Hash(code_hash),
+        tag: None,
+    });
+    sub_tx.set_data(Data { salt: [0; 8], data });
+    Ok(sub_tx)
+}
+
+/// Execute a transaction code at the given depth of nested sub-transaction
+/// invocations.
+#[allow(clippy::too_many_arguments)]
+fn tx_at_depth<DB, H, CA>(
+    storage: &State<DB, H>,
+    write_log: &mut WriteLog,
+    gas_meter: &mut TxGasMeter,
+    tx_index: &TxIndex,
+    tx: &Tx,
+    vp_wasm_cache: &mut VpCache<CA>,
+    tx_wasm_cache: &mut TxCache<CA>,
+    sub_tx_depth: u8,
+) -> Result<BTreeSet<Address>>
 where
     DB: 'static + namada_state::DB + for<'iter> namada_state::DBIter<'iter>,
     H: 'static + StorageHasher,
@@ -145,6 +415,23 @@ where
         }
     }
 
+    // Execute the native implementation of the tx code, if any, instead of
+    // the wasm code
+    if let Some(native_tx) =
+        native::find_native_tx(storage, &tx_code.code.hash())?
+    {
+        return native::tx(
+            native_tx,
+            storage,
+            write_log,
+            gas_meter,
+            tx_index,
+            tx,
+            vp_wasm_cache,
+            tx_wasm_cache,
+        );
+    }
+
     let (module, store) = fetch_or_compile(
         tx_wasm_cache,
         &tx_code.code,
@@ -152,13 +439,15 @@ where
         storage,
         gas_meter,
     )?;
+    let limits = read_wasm_limits(write_log, storage)?.tx;
+    check_wasm_limits(&module, &limits)?;
 
     let mut iterators: PrefixIterators<'_, DB> = PrefixIterators::default();
     let mut verifiers = BTreeSet::new();
     let mut result_buffer: Option<Vec<u8>> = None;
 
     let mut sentinel = TxSentinel::default();
-    let env = TxVmEnv::new(
+    let mut env = TxVmEnv::new(
         WasmMemory::default(),
         storage,
         write_log,
@@ -172,9 +461,11 @@ where
         vp_wasm_cache,
         tx_wasm_cache,
     );
+    env.ctx.sub_tx_depth = sub_tx_depth;
 
     let initial_memory =
-        memory::prepare_tx_memory(&store).map_err(Error::MemoryError)?;
+        memory::prepare_tx_memory(&store, limits.max_memory_pages)
+            .map_err(Error::MemoryError)?;
     let imports = tx_imports(&store, initial_memory, env);
 
     // Instantiate the wasm module
@@ -230,6 +521,7 @@ pub fn vp<DB, H, CA>(
     keys_changed: &BTreeSet<Key>,
     verifiers: &BTreeSet<Address>,
     mut vp_wasm_cache: VpCache<CA>,
+    tx_wasm_cache: TxCache<CA>,
 ) -> Result<bool>
 where
     DB: 'static + namada_state::DB + for<'iter> namada_state::DBIter<'iter>,
@@ -244,6 +536,8 @@ where
         storage,
         gas_meter,
     )?;
+    let limits = read_wasm_limits(write_log, storage)?.vp;
+    check_wasm_limits(&module, &limits)?;
 
     let mut iterators: PrefixIterators<'_, DB> = PrefixIterators::default();
     let mut result_buffer: Option<Vec<u8>> = None;
@@ -254,6 +548,7 @@ where
     };
 
     let mut sentinel = VpSentinel::default();
+    let mut tx_wasm_cache = Some(tx_wasm_cache);
     let env = VpVmEnv::new(
         WasmMemory::default(),
         address,
@@ -269,10 +564,12 @@ where
         keys_changed,
         &eval_runner,
         &mut vp_wasm_cache,
+        &mut tx_wasm_cache,
     );
 
     let initial_memory =
-        memory::prepare_vp_memory(&store).map_err(Error::MemoryError)?;
+        memory::prepare_vp_memory(&store, limits.max_memory_pages)
+            .map_err(Error::MemoryError)?;
     let imports = vp_imports(&store, initial_memory, env);
 
     match run_vp(
@@ -307,6 +604,9 @@ where
         Err(err) => {
             if sentinel.is_out_of_gas() {
                 Err(Error::GasError(err.to_string()))
+            } else if sentinel.is_invalid_signature() {
+                // The VP aborted after finding an invalid signature
+                Err(Error::InvalidTxSignature)
             } else {
                 Err(err)
             }
@@ -399,7 +699,7 @@ impl<DB, H, CA> VpEvaluator for VpEvalWasm<DB, H, CA>
 where
     DB: 'static + namada_state::DB + for<'iter> namada_state::DBIter<'iter>,
     H: 'static + StorageHasher,
-    CA: WasmCacheAccess,
+    CA: 'static + WasmCacheAccess,
 {
     type CA = CA;
     type Db = DB;
@@ -426,7 +726,7 @@ impl<DB, H, CA> VpEvalWasm<DB, H, CA>
 where
     DB: 'static + namada_state::DB + for<'iter> namada_state::DBIter<'iter>,
     H: 'static + StorageHasher,
-    CA: WasmCacheAccess,
+    CA: 'static + WasmCacheAccess,
 {
     /// Evaluate the given VP.
     pub fn eval_native_result(
@@ -455,9 +755,12 @@ where
             storage,
             gas_meter,
         )?;
+        let limits = read_wasm_limits(write_log, storage)?.vp;
+        check_wasm_limits(&module, &limits)?;
 
         let initial_memory =
-            memory::prepare_vp_memory(&store).map_err(Error::MemoryError)?;
+            memory::prepare_vp_memory(&store, limits.max_memory_pages)
+                .map_err(Error::MemoryError)?;
 
         let imports = vp_imports(&store, initial_memory, env);
 
@@ -474,12 +777,22 @@ where
     }
 }
 
+/// Prepare a compiler for untrusted code.
+///
+/// The NaNs produced by floating point operations are canonicalized, as their
+/// bit patterns are otherwise platform dependent and could lead to a consensus
+/// divergence between validators.
+pub fn untrusted_wasm_compiler() -> wasmer_compiler_singlepass::Singlepass {
+    let mut compiler = wasmer_compiler_singlepass::Singlepass::default();
+    compiler.canonicalize_nans(true);
+    compiler
+}
+
 /// Prepare a wasm store for untrusted code.
 pub fn untrusted_wasm_store(limit: Limit<BaseTunables>) -> wasmer::Store {
-    // Use Singlepass compiler with the default settings
-    let compiler = wasmer_compiler_singlepass::Singlepass::default();
     wasmer::Store::new_with_tunables(
-        &wasmer_engine_universal::Universal::new(compiler).engine(),
+        &wasmer_engine_universal::Universal::new(untrusted_wasm_compiler())
+            .engine(),
         limit,
     )
 }
@@ -502,6 +815,75 @@ pub fn prepare_wasm_code<T: AsRef<[u8]>>(code: T) -> Result<Vec<u8>> {
     elements::serialize(module).map_err(Error::SerializationError)
 }
 
+// Read the wasm instance limits parameter, if any, or the default limits
+fn read_wasm_limits<DB, H>(
+    write_log: &WriteLog,
+    storage: &State<DB, H>,
+) -> Result<WasmLimits>
+where
+    DB: 'static + namada_state::DB + for<'iter> namada_state::DBIter<'iter>,
+    H: 'static + StorageHasher,
+{
+    let key = namada_parameters::storage::get_wasm_limits_key();
+    let value = match write_log.read(&key).0 {
+        Some(StorageModification::Write { value }) => Some(value.clone()),
+        _ => {
+            storage
+                .read(&key)
+                .map_err(|e| {
+                    Error::LoadWasmCode(format!(
+                        "Read wasm limits failed from storage: key {}, error \
+                         {}",
+                        key, e
+                    ))
+                })?
+                .0
+        }
+    };
+    value
+        .map(|value| {
+            WasmLimits::try_from_slice(&value)
+                .map_err(|e| Error::ConversionError(e.to_string()))
+        })
+        .transpose()
+        .map(Option::unwrap_or_default)
+}
+
+// Check that the memories, tables and globals declared by the wasm module
+// don't exceed the given limits. The memories cannot grow past the hard
+// memory cap enforced by the store's tunables.
+fn check_wasm_limits(
+    module: &Module,
+    limits: &WasmInstanceLimits,
+) -> Result<()> {
+    let info = module.info();
+    for memory in info.memories.values() {
+        let requested = memory.maximum.unwrap_or(memory.minimum).0;
+        if requested > limits.max_memory_pages {
+            return Err(Error::MemoryLimitExceeded {
+                requested,
+                limit: limits.max_memory_pages,
+            });
+        }
+    }
+    for table in info.tables.values() {
+        let requested = table.maximum.unwrap_or(table.minimum);
+        if requested > limits.max_table_elements {
+            return Err(Error::TableLimitExceeded {
+                requested,
+                limit: limits.max_table_elements,
+            });
+        }
+    }
+    if info.globals.len() > limits.max_globals as usize {
+        return Err(Error::GlobalsLimitExceeded {
+            requested: info.globals.len(),
+            limit: limits.max_globals,
+        });
+    }
+    Ok(())
+}
+
 // Fetch or compile a WASM code from the cache or storage. Account for the
 // loading and code compilation gas costs.
 fn fetch_or_compile<DB, H, CN, CA>(
@@ -636,14 +1018,15 @@ mod tests {
     use itertools::Either;
     use namada_test_utils::TestWasms;
     use namada_tx::data::TxType;
-    use namada_tx::{Code, Data};
     use test_log::test;
     use wasmer_vm::TrapCode;
 
     use super::*;
     use crate::state::testing::TestStorage;
+    use crate::token;
     use crate::tx::data::eval_vp::EvalVp;
     use crate::types::hash::Hash;
+    use crate::types::key::RefTo;
     use crate::vm::host_env::TxRuntimeError;
     use crate::vm::wasm;
 
@@ -870,6 +1253,7 @@ mod tests {
         outer_tx.add_code(vec![], None).add_data(eval_vp);
 
         let (vp_cache, _) = wasm::compilation_cache::common::testing::cache();
+        let (tx_cache, _) = wasm::compilation_cache::common::testing::cache();
         // When the `eval`ed VP doesn't run out of memory, it should return
         // `true`
         let passed = vp(
@@ -883,6 +1267,7 @@ mod tests {
             &keys_changed,
             &verifiers,
             vp_cache.clone(),
+            tx_cache.clone(),
         )
         .unwrap();
         assert!(passed);
@@ -915,6 +1300,7 @@ mod tests {
             &keys_changed,
             &verifiers,
             vp_cache,
+            tx_cache,
         )
         .unwrap();
 
@@ -956,6 +1342,7 @@ mod tests {
         outer_tx.set_data(Data::new(tx_data));
         outer_tx.set_code(Code::new(vec![], None));
         let (vp_cache, _) = wasm::compilation_cache::common::testing::cache();
+        let (tx_cache, _) = wasm::compilation_cache::common::testing::cache();
         let result = vp(
             code_hash,
             &outer_tx,
@@ -967,6 +1354,7 @@ mod tests {
             &keys_changed,
             &verifiers,
             vp_cache.clone(),
+            tx_cache.clone(),
         );
         assert!(result.is_ok(), "Expected success, got {:?}", result);
 
@@ -987,6 +1375,7 @@ mod tests {
             &keys_changed,
             &verifiers,
             vp_cache,
+            tx_cache,
         )
         .expect_err("Expected to run out of memory");
 
@@ -1088,6 +1477,7 @@ mod tests {
         outer_tx.set_data(Data::new(tx_data));
         outer_tx.set_code(Code::new(vec![], None));
         let (vp_cache, _) = wasm::compilation_cache::common::testing::cache();
+        let (tx_cache, _) = wasm::compilation_cache::common::testing::cache();
         let result = vp(
             code_hash,
             &outer_tx,
@@ -1099,6 +1489,7 @@ mod tests {
             &keys_changed,
             &verifiers,
             vp_cache,
+            tx_cache,
         );
         // Depending on platform, we get a different error from the running out
         // of memory
@@ -1214,6 +1605,7 @@ mod tests {
         outer_tx.set_data(Data::new(tx_data));
         outer_tx.set_code(Code::new(vec![], None));
         let (vp_cache, _) = wasm::compilation_cache::common::testing::cache();
+        let (tx_cache, _) = wasm::compilation_cache::common::testing::cache();
         let error = vp(
             code_hash,
             &outer_tx,
@@ -1225,6 +1617,7 @@ mod tests {
             &keys_changed,
             &verifiers,
             vp_cache,
+            tx_cache,
         )
         .expect_err("Expected to run out of memory");
 
@@ -1291,6 +1684,7 @@ mod tests {
         outer_tx.add_code(vec![], None).add_data(eval_vp);
 
         let (vp_cache, _) = wasm::compilation_cache::common::testing::cache();
+        let (tx_cache, _) = wasm::compilation_cache::common::testing::cache();
         let passed = vp(
             code_hash,
             &outer_tx,
@@ -1302,11 +1696,602 @@ mod tests {
             &keys_changed,
             &verifiers,
             vp_cache,
+            tx_cache,
         )
         .unwrap();
         assert!(!passed);
     }
 
+    /// Test that an allowlisted tx code hash with a native implementation is
+    /// executed natively and that the wasm runtime is used otherwise.
+    #[test]
+    fn test_native_tx() {
+        let mut storage = TestStorage::default();
+        let tx_index = TxIndex::default();
+
+        // the wasm code is not in storage, only its hash
+        let code_hash = Hash::sha256(b"tx_reveal_pk");
+        storage
+            .write(
+                &Key::wasm_hash(native::NativeTx::RevealPk.wasm_name()),
+                code_hash.serialize_to_vec(),
+            )
+            .unwrap();
+
+        let pk = crate::types::key::testing::keypair_1().ref_to();
+        let owner = Address::from(&pk);
+        let mut outer_tx = Tx::from_type(TxType::Raw);
+        outer_tx.set_code(Code::from_hash(code_hash, None));
+        outer_tx.set_data(Data::new(pk.serialize_to_vec()));
+
+        let run_tx = |storage: &TestStorage| {
+            let mut write_log = WriteLog::default();
+            let mut gas_meter =
+                TxGasMeter::new_from_sub_limit(TX_GAS_LIMIT.into());
+            let (mut vp_cache, _) =
+                wasm::compilation_cache::common::testing::cache();
+            let (mut tx_cache, _) =
+                wasm::compilation_cache::common::testing::cache();
+            tx(
+                storage,
+                &mut write_log,
+                &mut gas_meter,
+                &tx_index,
+                &outer_tx,
+                &mut vp_cache,
+                &mut tx_cache,
+            )
+            .map(|_| write_log)
+        };
+
+        // without the allowlist, the tx falls back to the missing wasm code
+        let error = run_tx(&storage).expect_err("Expected a wasm load error");
+        assert!(matches!(error, Error::LoadWasmCode(_)), "{error}");
+
+        let allowlist_key =
+            namada_parameters::storage::get_native_tx_allowlist_storage_key();
+        storage
+            .write(
+                &allowlist_key,
+                vec![code_hash.to_string().to_lowercase()].serialize_to_vec(),
+            )
+            .unwrap();
+        let write_log = run_tx(&storage).expect("Expected native execution");
+        let pk_key = namada_account::pks_handle(&owner).get_data_key(&0);
+        assert!(matches!(
+            write_log.read(&pk_key).0,
+            Some(StorageModification::Write { value })
+                if value == &pk.serialize_to_vec()
+        ));
+    }
+
+    /// Test that the native transfer records the debit of the source against
+    /// its daily spending limit, like the wasm transfer does.
+    #[test]
+    fn test_native_transfer_records_spending() {
+        let mut storage = TestStorage::default();
+        storage.header =
+            Some(crate::ledger::native_vp::ibc::get_dummy_header());
+        let tx_index = TxIndex::default();
+
+        let code_hash = Hash::sha256(b"tx_transfer");
+        storage
+            .write(
+                &Key::wasm_hash(native::NativeTx::Transfer.wasm_name()),
+                code_hash.serialize_to_vec(),
+            )
+            .unwrap();
+        storage
+            .write(
+                &namada_parameters::storage::get_native_tx_allowlist_storage_key(
+                ),
+                vec![code_hash.to_string().to_lowercase()].serialize_to_vec(),
+            )
+            .unwrap();
+
+        let source = crate::types::address::testing::established_address_1();
+        let target = crate::types::address::testing::established_address_2();
+        let token = crate::types::address::nam();
+        storage
+            .write(
+                &token::storage_key::denom_key(&token),
+                token::Denomination(token::NATIVE_MAX_DECIMAL_PLACES)
+                    .serialize_to_vec(),
+            )
+            .unwrap();
+        storage
+            .write(
+                &token::storage_key::balance_key(&token, &source),
+                token::Amount::native_whole(10).serialize_to_vec(),
+            )
+            .unwrap();
+        let policy = namada_account::AccountPolicy {
+            spending_limits: [(token.clone(), token::Amount::native_whole(5))]
+                .into_iter()
+                .collect(),
+            session_keys: vec![],
+        };
+        storage
+            .write(
+                &namada_account::policy_key(&source),
+                policy.serialize_to_vec(),
+            )
+            .unwrap();
+
+        let amount = token::Amount::native_whole(2);
+        let transfer = token::Transfer {
+            source: source.clone(),
+            target,
+            token: token.clone(),
+            amount: token::DenominatedAmount::native(amount),
+            key: None,
+            shielded: None,
+        };
+        let mut outer_tx = Tx::from_type(TxType::Raw);
+        outer_tx.set_code(Code::from_hash(code_hash, None));
+        outer_tx.set_data(Data::new(transfer.serialize_to_vec()));
+
+        let mut write_log = WriteLog::default();
+        let mut gas_meter = TxGasMeter::new_from_sub_limit(TX_GAS_LIMIT.into());
+        let (mut vp_cache, _) =
+            wasm::compilation_cache::common::testing::cache();
+        let (mut tx_cache, _) =
+            wasm::compilation_cache::common::testing::cache();
+        tx(
+            &storage,
+            &mut write_log,
+            &mut gas_meter,
+            &tx_index,
+            &outer_tx,
+            &mut vp_cache,
+            &mut tx_cache,
+        )
+        .expect("Expected native execution");
+
+        let spending_key = namada_account::daily_spending_key(&source, &token);
+        let spending = match write_log.read(&spending_key).0 {
+            Some(StorageModification::Write { value }) => {
+                namada_account::DailySpending::try_from_slice(value).unwrap()
+            }
+            _ => panic!("Expected the daily spending to be recorded"),
+        };
+        assert_eq!(spending.amount, amount);
+    }
+
+    /// Test that a tx wasm module that declares more memory, table elements
+    /// or globals than allowed by the wasm limits parameter is rejected.
+    #[test]
+    fn test_tx_wasm_limits() {
+        let tx_code = wasmer::wat2wasm(
+            r#"
+            (module
+                (func (param i64 i64))
+                (table 2 2 funcref)
+                (memory 16)
+                (global (mut i32) (i32.const 0))
+                (global (mut i32) (i32.const 0))
+                (export "memory" (memory 0))
+                (export "_apply_tx" (func 0))
+            )
+            "#
+            .as_bytes(),
+        )
+        .expect("unexpected error converting wat2wasm")
+        .into_owned();
+
+        let tx_index = TxIndex::default();
+        let storage = TestStorage::default();
+        let code_hash = Hash::sha256(&tx_code);
+        let code_len = (tx_code.len() as u64).serialize_to_vec();
+        let mut outer_tx = Tx::from_type(TxType::Raw);
+        outer_tx.set_code(Code::from_hash(code_hash, None));
+        outer_tx.set_data(Data::new(vec![]));
+
+        let run_tx = |limits: WasmInstanceLimits| {
+            let mut write_log = WriteLog::default();
+            write_log
+                .write(&Key::wasm_code(&code_hash), tx_code.clone())
+                .unwrap();
+            write_log
+                .write(&Key::wasm_code_len(&code_hash), code_len.clone())
+                .unwrap();
+            let wasm_limits = WasmLimits {
+                tx: limits,
+                ..Default::default()
+            };
+            write_log
+                .write(
+                    &namada_parameters::storage::get_wasm_limits_key(),
+                    wasm_limits.serialize_to_vec(),
+                )
+                .unwrap();
+            let mut gas_meter =
+                TxGasMeter::new_from_sub_limit(TX_GAS_LIMIT.into());
+            let (mut vp_cache, _) =
+                wasm::compilation_cache::common::testing::cache();
+            let (mut tx_cache, _) =
+                wasm::compilation_cache::common::testing::cache();
+            tx(
+                &storage,
+                &mut write_log,
+                &mut gas_meter,
+                &tx_index,
+                &outer_tx,
+                &mut vp_cache,
+                &mut tx_cache,
+            )
+        };
+
+        let limits = WasmInstanceLimits::default();
+        let result = run_tx(limits);
+        assert!(result.is_ok(), "Expected success, got {:?}", result);
+
+        let error = run_tx(WasmInstanceLimits {
+            max_memory_pages: 8,
+            ..limits
+        })
+        .expect_err("Expected to exceed the memory limit");
+        assert!(
+            matches!(
+                error,
+                Error::MemoryLimitExceeded {
+                    requested: 16,
+                    limit: 8
+                }
+            ),
+            "{error}"
+        );
+
+        let error = run_tx(WasmInstanceLimits {
+            max_table_elements: 1,
+            ..limits
+        })
+        .expect_err("Expected to exceed the table limit");
+        assert!(
+            matches!(
+                error,
+                Error::TableLimitExceeded {
+                    requested: 2,
+                    limit: 1
+                }
+            ),
+            "{error}"
+        );
+
+        let error = run_tx(WasmInstanceLimits {
+            max_globals: 1,
+            ..limits
+        })
+        .expect_err("Expected to exceed the globals limit");
+        // the stack limiter injects a global on top of the declared ones
+        assert!(
+            matches!(error, Error::GlobalsLimitExceeded { limit: 1, .. }),
+            "{error}"
+        );
+    }
+
+    /// Test that the NaNs produced by floating point instructions in a VP are
+    /// canonicalized, to be deterministic across platforms.
+    #[test]
+    fn test_vp_nan_canonicalization() {
+        // A validity predicate accepting only if the NaN resulting from a
+        // division of zero by zero has the canonical bit pattern (on x86 the
+        // sign bit would otherwise be set)
+        let vp_code = wasmer::wat2wasm(
+            r#"
+            (module
+                (type (;0;) (func (param i64 i64 i64 i64 i64 i64 i64 i64) (result i64)))
+                (func $_validate_tx (type 0) (param i64 i64 i64 i64 i64 i64 i64 i64) (result i64)
+                (i64.extend_i32_u
+                (i32.eq
+                (i32.reinterpret_f32 (f32.div (f32.const 0) (f32.const 0)))
+                (i32.const 0x7fc00000))))
+                (table (;0;) 1 1 funcref)
+                (memory (;0;) 16)
+                (global (;0;) (mut i32) (i32.const 1048576))
+                (export "memory" (memory 0))
+                (export "_validate_tx" (func $_validate_tx)))
+            "#
+            .as_bytes(),
+        )
+        .expect("unexpected error converting wat2wasm")
+        .into_owned();
+
+        let outer_tx = Tx::from_type(TxType::Raw);
+        let tx_index = TxIndex::default();
+        let mut storage = TestStorage::default();
+        let addr = storage.address_gen.generate_address("rng seed");
+        let write_log = WriteLog::default();
+        let mut gas_meter = VpGasMeter::new_from_tx_meter(
+            &TxGasMeter::new_from_sub_limit(TX_GAS_LIMIT.into()),
+        );
+        let keys_changed = BTreeSet::new();
+        let verifiers = BTreeSet::new();
+        let (vp_cache, _) = wasm::compilation_cache::common::testing::cache();
+        let (tx_cache, _) = wasm::compilation_cache::common::testing::cache();
+        // store the vp code
+        let code_hash = Hash::sha256(&vp_code);
+        let code_len = (vp_code.len() as u64).serialize_to_vec();
+        let key = Key::wasm_code(&code_hash);
+        let len_key = Key::wasm_code_len(&code_hash);
+        storage.write(&key, vp_code).unwrap();
+        storage.write(&len_key, code_len).unwrap();
+
+        let accepted = vp(
+            code_hash,
+            &outer_tx,
+            &tx_index,
+            &addr,
+            &storage,
+            &write_log,
+            &mut gas_meter,
+            &keys_changed,
+            &verifiers,
+            vp_cache,
+            tx_cache,
+        )
+        .unwrap();
+        assert!(accepted);
+    }
+
+    /// Test that a tx can invoke an allowlisted tx code as a sub-transaction
+    /// and that the invocations are bounded by the allowlist and the depth.
+    #[test]
+    fn test_sub_tx() {
+        let sub_tx_code = wasmer::wat2wasm(
+            r#"
+            (module
+                (func (param i64 i64))
+                (memory 1)
+                (export "memory" (memory 0))
+                (export "_apply_tx" (func 0))
+            )
+            "#
+            .as_bytes(),
+        )
+        .expect("unexpected error converting wat2wasm")
+        .into_owned();
+        let sub_tx_hash = Hash::sha256(&sub_tx_code);
+        let sub_tx_hash_data = sub_tx_hash
+            .0
+            .iter()
+            .map(|byte| format!("\\{:02x}", byte))
+            .collect::<String>();
+        let tx_code = wasmer::wat2wasm(
+            format!(
+                r#"
+                (module
+                    (import "env" "namada_tx_invoke_sub_tx"
+                        (func $invoke_sub_tx (param i64 i64 i64 i64)
+                            (result i64)))
+                    (func $apply_tx (param i64 i64)
+                        (drop (call $invoke_sub_tx
+                            (i64.const 0) (i64.const 32)
+                            (i64.const 32) (i64.const 0))))
+                    (memory 1)
+                    (data (i32.const 0) "{sub_tx_hash_data}")
+                    (export "memory" (memory 0))
+                    (export "_apply_tx" (func $apply_tx))
+                )
+                "#
+            )
+            .as_bytes(),
+        )
+        .expect("unexpected error converting wat2wasm")
+        .into_owned();
+        let code_hash = Hash::sha256(&tx_code);
+
+        let tx_index = TxIndex::default();
+        let mut storage = TestStorage::default();
+        let mut write_log = WriteLog::default();
+        for code in [&tx_code, &sub_tx_code] {
+            let hash = Hash::sha256(code);
+            let code_len = (code.len() as u64).serialize_to_vec();
+            write_log
+                .write(&Key::wasm_code(&hash), code.clone())
+                .unwrap();
+            write_log
+                .write(&Key::wasm_code_len(&hash), code_len)
+                .unwrap();
+        }
+        let mut outer_tx = Tx::from_type(TxType::Raw);
+        outer_tx.set_code(Code::from_hash(code_hash, None));
+        outer_tx.set_data(Data::new(vec![]));
+        let (mut vp_cache, _) =
+            wasm::compilation_cache::common::testing::cache();
+        let (mut tx_cache, _) =
+            wasm::compilation_cache::common::testing::cache();
+
+        // without the allowlist, the sub-tx invocation fails
+        let mut gas_meter = TxGasMeter::new_from_sub_limit(TX_GAS_LIMIT.into());
+        let result = tx(
+            &storage,
+            &mut write_log,
+            &mut gas_meter,
+            &tx_index,
+            &outer_tx,
+            &mut vp_cache,
+            &mut tx_cache,
+        );
+        assert!(result.is_err(), "Expected a failure, got {:?}", result);
+        let error = sub_tx(
+            &storage,
+            &mut write_log,
+            &mut gas_meter,
+            &tx_index,
+            &outer_tx,
+            sub_tx_hash,
+            vec![],
+            &mut vp_cache,
+            &mut tx_cache,
+            1,
+        )
+        .expect_err("Expected a disallowed sub-tx");
+        assert!(matches!(error, Error::DisallowedSubTx(_)), "{error}");
+
+        storage
+            .write(
+                &namada_parameters::storage::get_sub_tx_allowlist_storage_key(),
+                vec![sub_tx_hash.to_string().to_lowercase()].serialize_to_vec(),
+            )
+            .unwrap();
+        let mut gas_meter = TxGasMeter::new_from_sub_limit(TX_GAS_LIMIT.into());
+        let result = tx(
+            &storage,
+            &mut write_log,
+            &mut gas_meter,
+            &tx_index,
+            &outer_tx,
+            &mut vp_cache,
+            &mut tx_cache,
+        );
+        assert!(result.is_ok(), "Expected success, got {:?}", result);
+
+        // the sub-tx invocations cannot be nested deeper than the max depth
+        let error = sub_tx(
+            &storage,
+            &mut write_log,
+            &mut gas_meter,
+            &tx_index,
+            &outer_tx,
+            sub_tx_hash,
+            vec![],
+            &mut vp_cache,
+            &mut tx_cache,
+            MAX_SUB_TX_DEPTH + 1,
+        )
+        .expect_err("Expected to exceed the max depth");
+        assert!(matches!(error, Error::SubTxDepthExceeded(_)), "{error}");
+    }
+
+    /// Test that a simulated sub-transaction is executed on a fork of the
+    /// write log, which only holds the sub-transaction's own modifications,
+    /// while the original write log is left untouched.
+    #[test]
+    fn test_simulate_sub_tx() {
+        use crate::types::storage::KeySeg;
+
+        let sub_tx_code = TestWasms::TxWriteStorageKey.read_bytes();
+        let sub_tx_hash = Hash::sha256(&sub_tx_code);
+
+        let tx_index = TxIndex::default();
+        let mut storage = TestStorage::default();
+        let mut write_log = WriteLog::default();
+        let code_len = (sub_tx_code.len() as u64).serialize_to_vec();
+        write_log
+            .write(&Key::wasm_code(&sub_tx_hash), sub_tx_code)
+            .unwrap();
+        write_log
+            .write(&Key::wasm_code_len(&sub_tx_hash), code_len)
+            .unwrap();
+        storage
+            .write(
+                &namada_parameters::storage::get_sub_tx_allowlist_storage_key(),
+                vec![sub_tx_hash.to_string().to_lowercase()].serialize_to_vec(),
+            )
+            .unwrap();
+
+        // a modification of the parent tx
+        let owner = crate::types::address::testing::gen_implicit_address();
+        let parent_key = Key::from(owner.to_db_key())
+            .push(&"parent".to_string())
+            .unwrap();
+        write_log.write(&parent_key, vec![1]).unwrap();
+        let parent_keys = write_log.get_keys();
+
+        let sub_tx_key = Key::from(owner.to_db_key())
+            .push(&"sub_tx".to_string())
+            .unwrap();
+        let data = namada_test_utils::tx_data::TxWriteData {
+            key: sub_tx_key.clone(),
+            value: vec![2],
+        }
+        .serialize_to_vec();
+        let outer_tx = Tx::from_type(TxType::Raw);
+        let (mut vp_cache, _) =
+            wasm::compilation_cache::common::testing::cache();
+        let (mut tx_cache, _) =
+            wasm::compilation_cache::common::testing::cache();
+        let mut gas_meter = TxGasMeter::new_from_sub_limit(TX_GAS_LIMIT.into());
+
+        let (fork, _verifiers) = simulate_sub_tx(
+            &storage,
+            &write_log,
+            &mut gas_meter,
+            &tx_index,
+            &outer_tx,
+            sub_tx_hash,
+            data,
+            &mut vp_cache,
+            &mut tx_cache,
+            1,
+        )
+        .expect("Expected a successful simulation");
+
+        assert_eq!(fork.get_keys(), BTreeSet::from([sub_tx_key.clone()]));
+        assert_eq!(write_log.get_keys(), parent_keys);
+        assert!(write_log.read(&sub_tx_key).0.is_none());
+        assert!(gas_meter.get_tx_consumed_gas() > 0.into());
+    }
+
+    /// Test that the copy of the write log forked by a simulation is charged
+    /// in proportion to the size of the write log
+    #[test]
+    fn test_simulate_tx_meters_fork() {
+        let tx_code = TestWasms::TxNoOp.read_bytes();
+        let code_hash = Hash::sha256(&tx_code);
+        let code_len = (tx_code.len() as u64).serialize_to_vec();
+        let mut outer_tx = Tx::from_type(TxType::Raw);
+        outer_tx.set_code(Code::from_hash(code_hash, None));
+        outer_tx.set_data(Data::new(vec![]));
+
+        let storage = TestStorage::default();
+        let mut write_log = WriteLog::default();
+        write_log
+            .write(&Key::wasm_code(&code_hash), tx_code)
+            .unwrap();
+        write_log
+            .write(&Key::wasm_code_len(&code_hash), code_len)
+            .unwrap();
+
+        let simulate = |write_log: &WriteLog| {
+            let (mut vp_cache, _) =
+                wasm::compilation_cache::common::testing::cache();
+            let (mut tx_cache, _) =
+                wasm::compilation_cache::common::testing::cache();
+            let mut gas_meter =
+                TxGasMeter::new_from_sub_limit(TX_GAS_LIMIT.into());
+            simulate_tx(
+                &storage,
+                write_log,
+                &mut gas_meter,
+                &TxIndex::default(),
+                &outer_tx,
+                &mut vp_cache,
+                &mut tx_cache,
+                0,
+            )
+            .expect("Expected a successful simulation");
+            u64::from(gas_meter.get_tx_consumed_gas())
+        };
+        let small_log_gas = simulate(&write_log);
+
+        // grow the block write log of the simulated block
+        let key = Key::parse("big_value").unwrap();
+        let value = vec![0_u8; 1_000_000];
+        let size = (key.len() + value.len()) as u64;
+        let mut big_write_log = write_log.clone();
+        big_write_log.write(&key, value).unwrap();
+        big_write_log.commit_tx();
+        assert_eq!(big_write_log.size(), write_log.size() + size);
+        let big_log_gas = simulate(&big_write_log);
+
+        assert_eq!(
+            big_log_gas - small_log_gas,
+            size * MEMORY_ACCESS_GAS_PER_BYTE
+        );
+    }
+
     fn execute_tx_with_code(tx_code: Vec<u8>) -> Result<BTreeSet<Address>> {
         let tx_data = vec![];
         let tx_index = TxIndex::default();
@@ -1419,6 +2404,7 @@ mod tests {
         let keys_changed = BTreeSet::new();
         let verifiers = BTreeSet::new();
         let (vp_cache, _) = wasm::compilation_cache::common::testing::cache();
+        let (tx_cache, _) = wasm::compilation_cache::common::testing::cache();
         // store the vp code
         let code_hash = Hash::sha256(&vp_code);
         let code_len = (vp_code.len() as u64).serialize_to_vec();
@@ -1438,6 +2424,7 @@ mod tests {
             &keys_changed,
             &verifiers,
             vp_cache,
+            tx_cache,
         )
     }
 
@@ -1,6 +1,7 @@
 //! Protocol parameters
 pub mod storage;
 mod wasm_allowlist;
+mod wasm_registry;
 use std::collections::BTreeMap;
 
 use namada_core::types::address::{Address, InternalAddress};
@@ -8,13 +9,14 @@ use namada_core::types::chain::ProposalBytes;
 use namada_core::types::dec::Dec;
 use namada_core::types::hash::Hash;
 pub use namada_core::types::parameters::*;
-use namada_core::types::storage::Key;
+use namada_core::types::storage::{Epoch, Key};
 use namada_core::types::time::DurationSecs;
 use namada_core::types::token;
 use namada_storage::{self, ResultExt, StorageRead, StorageWrite};
 pub use storage::get_max_block_gas;
 use thiserror::Error;
 pub use wasm_allowlist::{is_tx_allowed, is_vp_allowed};
+pub use wasm_registry::register_wasm_code;
 
 /// The internal address for storage keys representing parameters than
 /// can be changed via governance.
@@ -212,6 +214,45 @@ where
     )
 }
 
+/// Update the native tx allowlist parameter in storage. The allowlisted tx
+/// code hashes with a native implementation are executed natively instead of
+/// in the wasm runtime.
+pub fn update_native_tx_allowlist_parameter<S>(
+    storage: &mut S,
+    value: Vec<String>,
+) -> namada_storage::Result<()>
+where
+    S: StorageRead + StorageWrite,
+{
+    let key = storage::get_native_tx_allowlist_storage_key();
+    storage.write(
+        &key,
+        value
+            .iter()
+            .map(|id| id.to_lowercase())
+            .collect::<Vec<String>>(),
+    )
+}
+
+/// Update the sub-tx allowlist parameter in storage. Only the allowlisted tx
+/// code hashes may be invoked as sub-transactions by another transaction.
+pub fn update_sub_tx_allowlist_parameter<S>(
+    storage: &mut S,
+    value: Vec<String>,
+) -> namada_storage::Result<()>
+where
+    S: StorageRead + StorageWrite,
+{
+    let key = storage::get_sub_tx_allowlist_storage_key();
+    storage.write(
+        &key,
+        value
+            .iter()
+            .map(|id| id.to_lowercase())
+            .collect::<Vec<String>>(),
+    )
+}
+
 /// Update the epoch parameter in storage. Returns the parameters and gas
 /// cost.
 pub fn update_epoch_parameter<S>(
@@ -290,6 +331,93 @@ where
     storage.write(&key, value)
 }
 
+/// Update the max tx bytes parameter in storage
+pub fn update_max_tx_bytes_parameter<S>(
+    storage: &mut S,
+    value: u32,
+) -> namada_storage::Result<()>
+where
+    S: StorageRead + StorageWrite,
+{
+    let key = storage::get_max_tx_bytes_key();
+    storage.write(&key, value)
+}
+
+/// Update the max block gas parameter in storage
+pub fn update_max_block_gas_parameter<S>(
+    storage: &mut S,
+    value: u64,
+) -> namada_storage::Result<()>
+where
+    S: StorageRead + StorageWrite,
+{
+    let key = storage::get_max_block_gas_key();
+    storage.write(&key, value)
+}
+
+/// Update the inflation split parameter in storage and record the new split
+/// in its history at the current epoch
+pub fn update_inflation_split_parameter<S>(
+    storage: &mut S,
+    value: &InflationSplit,
+) -> namada_storage::Result<()>
+where
+    S: StorageRead + StorageWrite,
+{
+    let current_epoch = storage.get_block_epoch()?;
+    let mut history = read_inflation_split_history(storage)?;
+    history.insert(current_epoch, value.clone());
+
+    storage.write(&storage::get_inflation_split_history_key(), history)?;
+    storage.write(&storage::get_inflation_split_key(), value)
+}
+
+/// Update the wasm instance limits parameter in storage
+pub fn update_wasm_limits_parameter<S>(
+    storage: &mut S,
+    value: &WasmLimits,
+) -> namada_storage::Result<()>
+where
+    S: StorageRead + StorageWrite,
+{
+    storage.write(&storage::get_wasm_limits_key(), value)
+}
+
+/// Read the wasm instance limits parameter, defaulting to
+/// [`WasmLimits::default`] if it hasn't been set
+pub fn read_wasm_limits<S>(storage: &S) -> namada_storage::Result<WasmLimits>
+where
+    S: StorageRead,
+{
+    Ok(storage
+        .read(&storage::get_wasm_limits_key())?
+        .unwrap_or_default())
+}
+
+/// Read the inflation split parameter. Without a split, the PoS rewards, the
+/// PGF treasury and the MASP rewards each compute their own inflation.
+pub fn read_inflation_split<S>(
+    storage: &S,
+) -> namada_storage::Result<Option<InflationSplit>>
+where
+    S: StorageRead,
+{
+    storage.read(&storage::get_inflation_split_key())
+}
+
+/// Read the past values of the inflation split parameter, indexed by the
+/// epoch they were set at
+pub fn read_inflation_split_history<S>(
+    storage: &S,
+) -> namada_storage::Result<BTreeMap<Epoch, InflationSplit>>
+where
+    S: StorageRead,
+{
+    Ok(storage
+        .read(&storage::get_inflation_split_history_key())?
+        .unwrap_or_default())
+}
+
 /// Read the the epoch duration parameter from store
 pub fn read_epoch_duration_parameter<S>(
     storage: &S,
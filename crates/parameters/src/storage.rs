@@ -29,6 +29,8 @@ struct Keys {
     // ========================================
     pos_inflation_amount: &'static str,
     staked_ratio: &'static str,
+    inflation_split: &'static str,
+    inflation_split_history: &'static str,
     // ========================================
     // Core parameters
     // ========================================
@@ -38,6 +40,9 @@ struct Keys {
     max_expected_time_per_block: &'static str,
     tx_allowlist: &'static str,
     vp_allowlist: &'static str,
+    native_tx_allowlist: &'static str,
+    sub_tx_allowlist: &'static str,
+    wasm_limits: &'static str,
     max_proposal_bytes: &'static str,
     max_tx_bytes: &'static str,
     max_block_gas: &'static str,
@@ -130,6 +135,21 @@ pub fn get_tx_allowlist_storage_key() -> Key {
     get_tx_allowlist_key_at_addr(ADDRESS)
 }
 
+/// Storage key used for native tx allowlist parameter.
+pub fn get_native_tx_allowlist_storage_key() -> Key {
+    get_native_tx_allowlist_key_at_addr(ADDRESS)
+}
+
+/// Storage key used for sub-tx allowlist parameter.
+pub fn get_sub_tx_allowlist_storage_key() -> Key {
+    get_sub_tx_allowlist_key_at_addr(ADDRESS)
+}
+
+/// Storage key used for the wasm instance limits parameter.
+pub fn get_wasm_limits_key() -> Key {
+    get_wasm_limits_key_at_addr(ADDRESS)
+}
+
 /// Storage key used for the fee unshielding gas limit
 pub fn get_fee_unshielding_gas_limit_key() -> Key {
     get_fee_unshielding_gas_limit_key_at_addr(ADDRESS)
@@ -165,6 +185,16 @@ pub fn get_pos_inflation_amount_key() -> Key {
     get_pos_inflation_amount_key_at_addr(ADDRESS)
 }
 
+/// Storage key used for the inflation split parameter.
+pub fn get_inflation_split_key() -> Key {
+    get_inflation_split_key_at_addr(ADDRESS)
+}
+
+/// Storage key used for the history of the inflation split parameter.
+pub fn get_inflation_split_history_key() -> Key {
+    get_inflation_split_history_key_at_addr(ADDRESS)
+}
+
 /// Storage key used for the max proposal bytes.
 pub fn get_max_proposal_bytes_key() -> Key {
     get_max_proposal_bytes_key_at_addr(ADDRESS)
@@ -0,0 +1,27 @@
+//! The on-chain registry of wasm codes. Txs reference the registered tx and vp
+//! codes by their hash instead of carrying the code bytes. The registry is
+//! populated at genesis and can only be updated by governance.
+
+use namada_core::types::hash::Hash;
+use namada_core::types::storage::Key;
+use namada_storage::{Result, StorageRead, StorageWrite};
+
+/// Register the given wasm code under its hash and its name. Returns the hash
+/// of the code.
+pub fn register_wasm_code<S>(
+    storage: &mut S,
+    name: &str,
+    code: Vec<u8>,
+) -> Result<Hash>
+where
+    S: StorageRead + StorageWrite,
+{
+    let code_hash = Hash::sha256(&code);
+    let code_len = code.len() as u64;
+
+    storage.write_bytes(&Key::wasm_code(&code_hash), code)?;
+    storage.write(&Key::wasm_code_len(&code_hash), code_len)?;
+    storage.write(&Key::wasm_hash(name), code_hash)?;
+    storage.write(&Key::wasm_code_name(name.to_owned()), code_hash)?;
+    Ok(code_hash)
+}
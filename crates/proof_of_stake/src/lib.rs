@@ -68,7 +68,7 @@ use crate::storage::{
     write_pos_params, write_validator_address_raw_hash, write_validator_avatar,
     write_validator_description, write_validator_discord_handle,
     write_validator_email, write_validator_max_commission_rate_change,
-    write_validator_metadata, write_validator_website,
+    write_validator_metadata, write_validator_name, write_validator_website,
 };
 use crate::storage_key::{bonds_for_source_prefix, is_bond_key};
 use crate::types::{
@@ -2585,6 +2585,7 @@ pub fn change_validator_metadata<S>(
     website: Option<String>,
     discord_handle: Option<String>,
     avatar: Option<String>,
+    name: Option<String>,
     commission_rate: Option<Dec>,
     current_epoch: Epoch,
 ) -> namada_storage::Result<()>
@@ -2606,6 +2607,9 @@ where
     if let Some(avatar) = avatar {
         write_validator_avatar(storage, validator, &avatar)?;
     }
+    if let Some(name) = name {
+        write_validator_name(storage, validator, &name)?;
+    }
     if let Some(commission_rate) = commission_rate {
         change_validator_commission_rate(
             storage,
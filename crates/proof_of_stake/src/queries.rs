@@ -8,16 +8,30 @@ use namada_core::types::address::Address;
 use namada_core::types::dec::Dec;
 use namada_core::types::storage::Epoch;
 use namada_core::types::token;
+use namada_governance::storage::keys::get_vote_proposal_key;
+use namada_governance::storage::proposal::ProposalKind;
+use namada_governance::storage::{
+    get_proposal_by_id, get_proposal_votes, get_vote_delegators,
+    get_voter_votes,
+};
+use namada_governance::utils::{
+    resolve_vote_delegations, ProposalVotes, TallyVote, VotePower, VoterVote,
+    VotingMode,
+};
+use namada_governance::ProposalVote;
 use namada_storage::collections::lazy_map::{NestedSubKey, SubKey};
 use namada_storage::{self, StorageRead};
 
 use crate::slashing::{find_validator_slashes, get_slashed_amount};
-use crate::storage::{bond_handle, read_pos_params, unbond_handle};
+use crate::storage::{
+    bond_handle, read_all_validator_addresses, read_pos_params,
+    read_total_stake, read_validator_stake, unbond_handle,
+};
 use crate::types::{
     BondDetails, BondId, BondsAndUnbondsDetail, BondsAndUnbondsDetails, Slash,
     UnbondDetails,
 };
-use crate::{storage_key, PosParams};
+use crate::{bond_amount, is_validator, storage_key, PosParams};
 
 /// Find all validators to which a given bond `owner` (or source) has a
 /// delegation
@@ -460,3 +474,207 @@ fn make_unbond_details(
         slashed_amount,
     }
 }
+
+/// Compute the total voting power at the given epoch of a governance proposal
+/// tallied with the given voting mode. For a non-linear voting mode, the mode
+/// is applied to the stake of each account separately: to the bonded stake of
+/// each delegator and to the remaining stake of each validator.
+pub fn compute_total_voting_power<S>(
+    storage: &S,
+    params: &PosParams,
+    voting_mode: VotingMode,
+    epoch: Epoch,
+) -> namada_storage::Result<VotePower>
+where
+    S: StorageRead,
+{
+    if voting_mode.is_linear() {
+        return read_total_stake(storage, params, epoch);
+    }
+
+    let mut validator_stakes: HashMap<Address, VotePower> = HashMap::new();
+    for validator in read_all_validator_addresses(storage, epoch)? {
+        let stake = read_validator_stake(storage, params, &validator, epoch)?;
+        validator_stakes.insert(validator, stake);
+    }
+
+    let bonds_prefix = storage_key::bonds_prefix();
+    let mut delegations: HashSet<BondId> = HashSet::new();
+    for iter_result in
+        namada_storage::iter_prefix_bytes(storage, &bonds_prefix)?
+    {
+        let (key, _bond_bytes) = iter_result?;
+        if let Some((bond_id, _start_epoch)) = storage_key::is_bond_key(&key) {
+            if bond_id.source != bond_id.validator {
+                delegations.insert(bond_id);
+            }
+        }
+    }
+
+    let mut delegator_stakes: HashMap<Address, VotePower> = HashMap::new();
+    for bond_id in delegations {
+        let stake = bond_amount(storage, &bond_id, epoch)?;
+        if let Some(validator_stake) =
+            validator_stakes.get_mut(&bond_id.validator)
+        {
+            *validator_stake =
+                validator_stake.checked_sub(stake).unwrap_or_default();
+        }
+        *delegator_stakes.entry(bond_id.source).or_default() += stake;
+    }
+
+    Ok(validator_stakes
+        .into_values()
+        .chain(delegator_stakes.into_values())
+        .map(|stake| voting_mode.voting_power(stake))
+        .sum())
+}
+
+/// Compute the votes of a governance proposal with the voting power of each
+/// voter at the given epoch. The accounts which haven't voted follow the vote
+/// of their delegate, if any. Votes follow last-write-wins semantics: only the
+/// last vote cast by a voter before the end of the voting window is tallied,
+/// and a withdrawn vote is not counted.
+pub fn compute_proposal_votes<S>(
+    storage: &S,
+    params: &PosParams,
+    proposal_id: u64,
+    proposal_kind: ProposalKind,
+    epoch: Epoch,
+) -> namada_storage::Result<ProposalVotes>
+where
+    S: StorageRead,
+{
+    let votes = get_proposal_votes(storage, proposal_id)?;
+    let direct_votes: HashMap<Address, TallyVote> = votes
+        .iter()
+        .map(|vote| (vote.delegator.clone(), vote.data.clone().into()))
+        .collect();
+
+    let mut validators_vote: HashMap<Address, TallyVote> = HashMap::default();
+    let mut validator_voting_power: HashMap<Address, VotePower> =
+        HashMap::default();
+    let mut delegators_vote: HashMap<Address, TallyVote> = HashMap::default();
+    let mut delegator_voting_power: HashMap<
+        Address,
+        HashMap<Address, VotePower>,
+    > = HashMap::default();
+
+    for vote in votes {
+        if vote.is_validator() {
+            let validator = vote.validator.clone();
+            let vote_data = vote.data.clone();
+
+            let validator_stake =
+                read_validator_stake(storage, params, &validator, epoch)
+                    .unwrap_or_default();
+
+            validators_vote.insert(validator.clone(), vote_data.into());
+            validator_voting_power.insert(validator, validator_stake);
+        } else {
+            let validator = vote.validator.clone();
+            let delegator = vote.delegator.clone();
+            let vote_data = vote.data.clone();
+
+            let bond_id = BondId {
+                source: delegator.clone(),
+                validator: validator.clone(),
+            };
+            let delegator_stake = bond_amount(storage, &bond_id, epoch);
+
+            if let Ok(stake) = delegator_stake {
+                delegators_vote.insert(delegator.clone(), vote_data.into());
+                delegator_voting_power
+                    .entry(delegator)
+                    .or_default()
+                    .insert(validator, stake);
+            } else {
+                continue;
+            }
+        }
+    }
+
+    // The accounts which haven't voted follow the vote of their delegate,
+    // resolving the chains of delegations in effect at the tally epoch back
+    // from the voters with the index of the delegations by delegate.
+    // Validators always vote with their own voting power.
+    let followed_votes = resolve_vote_delegations(&direct_votes, |delegate| {
+        get_vote_delegators(storage, delegate, proposal_kind, epoch)
+    })?;
+    for (delegator, vote) in followed_votes {
+        if is_validator(storage, &delegator)? {
+            continue;
+        }
+        for (validator, stake) in find_delegations(storage, &delegator, &epoch)?
+        {
+            if stake.is_zero() {
+                continue;
+            }
+            delegators_vote.insert(delegator.clone(), vote.clone());
+            delegator_voting_power
+                .entry(delegator.clone())
+                .or_default()
+                .insert(validator, stake);
+        }
+    }
+
+    Ok(ProposalVotes {
+        validators_vote,
+        validator_voting_power,
+        delegators_vote,
+        delegator_voting_power,
+    })
+}
+
+/// Compute the participation history of a voter: the vote it holds on each
+/// proposal, ordered by proposal id, with the voting power used. The voting
+/// power is read at the end of the voting period of the proposal, or at the
+/// given current epoch if it's still open.
+pub fn compute_voter_votes<S>(
+    storage: &S,
+    params: &PosParams,
+    voter: &Address,
+    current_epoch: Epoch,
+) -> namada_storage::Result<Vec<VoterVote>>
+where
+    S: StorageRead,
+{
+    let mut votes = Vec::new();
+    for (proposal_id, validators) in get_voter_votes(storage, voter)? {
+        let proposal = match get_proposal_by_id(storage, proposal_id)? {
+            Some(proposal) => proposal,
+            None => continue,
+        };
+        let epoch = cmp::min(current_epoch, proposal.voting_end_epoch);
+
+        let mut vote = None;
+        let mut stake = VotePower::default();
+        for validator in validators {
+            let vote_key = get_vote_proposal_key(
+                proposal_id,
+                voter.clone(),
+                validator.clone(),
+            );
+            vote = storage.read::<ProposalVote>(&vote_key)?.or(vote);
+            stake += if &validator == voter {
+                read_validator_stake(storage, params, &validator, epoch)
+                    .unwrap_or_default()
+            } else {
+                let bond_id = BondId {
+                    source: voter.clone(),
+                    validator,
+                };
+                bond_amount(storage, &bond_id, epoch).unwrap_or_default()
+            };
+        }
+
+        if let Some(vote) = vote {
+            votes.push(VoterVote {
+                proposal_id,
+                vote,
+                voting_power: proposal.voting_mode.voting_power(stake),
+            });
+        }
+    }
+    Ok(votes)
+}
@@ -312,6 +312,14 @@ where
     let inflation =
         token::Amount::from_uint(inflation, 0).into_storage_result()?;
 
+    // With an inflation split, only its PoS rewards share of the inflation is
+    // distributed to the stakers, the rest being routed to the PGF treasury
+    // and the MASP incentives
+    let pos_rewards = match namada_parameters::read_inflation_split(storage)? {
+        Some(split) => split.pos_rewards * inflation,
+        None => inflation,
+    };
+
     // Mint inflation and partition rewards among all accounts that earn a
     // portion of it
     update_rewards_products_and_mint_inflation(
@@ -319,7 +327,7 @@ where
         &params,
         last_epoch,
         num_blocks_in_last_epoch,
-        inflation,
+        pos_rewards,
         &staking_token,
     )?;
 
@@ -55,6 +55,7 @@ const VALIDATOR_DESCRIPTION_KEY: &str = "description";
 const VALIDATOR_WEBSITE_KEY: &str = "website";
 const VALIDATOR_DISCORD_KEY: &str = "discord_handle";
 const VALIDATOR_AVATAR_KEY: &str = "avatar";
+const VALIDATOR_NAME_KEY: &str = "name";
 const LIVENESS_PREFIX: &str = "liveness";
 const LIVENESS_MISSED_VOTES: &str = "missed_votes";
 const LIVENESS_MISSED_VOTES_SUM: &str = "sum_missed_votes";
@@ -273,6 +274,7 @@ pub fn is_validator_metadata_key(key: &Key) -> Option<&Address> {
                     | VALIDATOR_WEBSITE_KEY
                     | VALIDATOR_DISCORD_KEY
                     | VALIDATOR_AVATAR_KEY
+                        | VALIDATOR_NAME_KEY
             ) =>
         {
             Some(validator)
@@ -1024,6 +1026,13 @@ pub fn validator_avatar_key(validator: &Address) -> Key {
         .expect("Cannot obtain a storage key")
 }
 
+/// Storage key for a validator's name
+pub fn validator_name_key(validator: &Address) -> Key {
+    validator_prefix(validator)
+        .push(&VALIDATOR_NAME_KEY.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
 /// Storage prefix for the liveness data of the cosnensus validator set.
 pub fn liveness_data_prefix() -> Key {
     Key::from(ADDRESS.to_db_key())
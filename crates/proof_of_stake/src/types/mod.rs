@@ -363,6 +363,8 @@ pub struct ValidatorMetaData {
     /// URL that points to a picture (e.g. PNG),
     /// identifying the validator
     pub avatar: Option<String>,
+    /// Validator's name
+    pub name: Option<String>,
 }
 
 #[cfg(any(test, feature = "testing"))]
@@ -374,6 +376,7 @@ impl Default for ValidatorMetaData {
             website: Default::default(),
             discord_handle: Default::default(),
             avatar: Default::default(),
+            name: Default::default(),
         }
     }
 }
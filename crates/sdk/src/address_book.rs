@@ -0,0 +1,211 @@
+//! An address book resolving addresses to human-readable names and back.
+//!
+//! The names of an address are, in order of precedence, its alias in the
+//! local wallet, the name set in the on-chain metadata of a validator and the
+//! alias of an internal address. Addresses without a name are rendered in
+//! their encoded form.
+
+use std::collections::BTreeMap;
+
+use namada_core::types::address::Address;
+use namada_core::types::storage::Epoch;
+use thiserror::Error;
+
+use crate::error::Error;
+use crate::rpc;
+use crate::wallet::{Alias, Wallet};
+
+#[allow(missing_docs)]
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum AddressBookError {
+    #[error("No address found for the name {0}")]
+    NotFound(String),
+    #[error("The name {name} is used by several validators: {addresses:?}")]
+    Ambiguous {
+        name: String,
+        addresses: Vec<Address>,
+    },
+}
+
+/// The names of the addresses known from the chain, to be used alongside the
+/// aliases of the wallet
+#[derive(Debug, Default, Clone)]
+pub struct AddressBook {
+    /// The names set in the metadata of the validators, or `None` for the
+    /// validators without a name
+    validator_names: BTreeMap<Address, Option<String>>,
+}
+
+impl AddressBook {
+    /// Create an empty address book
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cache the name of a validator, or its absence
+    pub fn insert_validator_name(
+        &mut self,
+        validator: Address,
+        name: Option<String>,
+    ) {
+        self.validator_names
+            .insert(validator, name.filter(|name| !name.is_empty()));
+    }
+
+    /// Get the cached name of a validator
+    pub fn validator_name(&self, validator: &Address) -> Option<&str> {
+        self.validator_names.get(validator)?.as_deref()
+    }
+
+    /// Query the name of a validator, unless it's cached already
+    pub async fn load_validator_name<C: crate::queries::Client + Sync>(
+        &mut self,
+        client: &C,
+        validator: &Address,
+    ) -> Result<(), Error> {
+        if !self.validator_names.contains_key(validator) {
+            let name = rpc::get_validator_name(client, validator).await?;
+            self.insert_validator_name(validator.clone(), name);
+        }
+        Ok(())
+    }
+
+    /// Query the names of the validators of the given epoch that aren't
+    /// cached yet
+    pub async fn load_validator_names<C: crate::queries::Client + Sync>(
+        &mut self,
+        client: &C,
+        epoch: Epoch,
+    ) -> Result<(), Error> {
+        let validators = rpc::get_all_validators(client, epoch).await?;
+        for validator in validators {
+            self.load_validator_name(client, &validator).await?;
+        }
+        Ok(())
+    }
+
+    /// Find the name of an address, if any
+    pub fn find_name<U>(
+        &self,
+        wallet: &Wallet<U>,
+        address: &Address,
+    ) -> Option<String> {
+        if let Some(alias) = wallet.find_alias(address) {
+            return Some(alias.to_string());
+        }
+        if let Some(name) = self.validator_name(address) {
+            return Some(name.to_owned());
+        }
+        match address {
+            Address::Internal(internal) => {
+                internal.to_alias().map(ToOwned::to_owned)
+            }
+            _ => None,
+        }
+    }
+
+    /// Render an address with its name, or in its encoded form if it has
+    /// none
+    pub fn lookup<U>(&self, wallet: &Wallet<U>, address: &Address) -> String {
+        self.find_name(wallet, address)
+            .unwrap_or_else(|| address.encode())
+    }
+
+    /// Find the address with the given name. The name can also be an encoded
+    /// address. The validator names are matched case-insensitively and must
+    /// be unique.
+    pub fn find_address<U>(
+        &self,
+        wallet: &Wallet<U>,
+        name: impl AsRef<str>,
+    ) -> Result<Address, AddressBookError> {
+        let name = name.as_ref();
+        if let Ok(address) = Address::decode(name) {
+            return Ok(address);
+        }
+        if let Some(address) = wallet.find_address(name) {
+            return Ok(address.into_owned());
+        }
+        let normalized = Alias::from(name);
+        let mut addresses: Vec<Address> = self
+            .validator_names
+            .iter()
+            .filter_map(|(address, validator_name)| {
+                validator_name
+                    .as_ref()
+                    .filter(|validator_name| {
+                        Alias::from(validator_name.as_str()) == normalized
+                    })
+                    .map(|_| address.clone())
+            })
+            .collect();
+        match addresses.len() {
+            0 => Err(AddressBookError::NotFound(name.to_owned())),
+            1 => Ok(addresses.remove(0)),
+            _ => Err(AddressBookError::Ambiguous {
+                name: name.to_owned(),
+                addresses,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use namada_core::types::address::testing::{
+        established_address_1, established_address_2, established_address_3,
+    };
+    use namada_core::types::address::InternalAddress;
+
+    use super::*;
+    use crate::wallet::{Store, WalletIo};
+
+    #[derive(Clone)]
+    struct TestWalletIo;
+
+    impl WalletIo for TestWalletIo {
+        type Rng = rand_core::OsRng;
+    }
+
+    #[test]
+    fn test_address_book() {
+        let account = established_address_1();
+        let validator = established_address_2();
+        let namesake = established_address_3();
+        let mut wallet = Wallet::new(TestWalletIo, Store::default());
+        wallet.insert_address("alice", account.clone(), false);
+
+        let mut book = AddressBook::new();
+        book.insert_validator_name(validator.clone(), Some("Nova".to_owned()));
+        book.insert_validator_name(account.clone(), Some("Alice".to_owned()));
+        book.insert_validator_name(namesake.clone(), Some(String::new()));
+
+        // The wallet alias takes precedence over the validator name
+        assert_eq!(book.lookup(&wallet, &account), "alice");
+        assert_eq!(book.lookup(&wallet, &validator), "Nova");
+        assert_eq!(book.lookup(&wallet, &namesake), namesake.encode());
+        assert_eq!(
+            book.lookup(&wallet, &Address::Internal(InternalAddress::PoS)),
+            "pos"
+        );
+
+        // Reverse lookup by alias, name or encoded address
+        assert_eq!(book.find_address(&wallet, "alice"), Ok(account.clone()));
+        assert_eq!(book.find_address(&wallet, "nova"), Ok(validator.clone()));
+        assert_eq!(
+            book.find_address(&wallet, namesake.encode()),
+            Ok(namesake.clone())
+        );
+        assert_eq!(
+            book.find_address(&wallet, "bob"),
+            Err(AddressBookError::NotFound("bob".to_owned()))
+        );
+
+        // Names shared by several validators are ambiguous
+        book.insert_validator_name(namesake.clone(), Some("NOVA".to_owned()));
+        assert!(matches!(
+            book.find_address(&wallet, "Nova"),
+            Err(AddressBookError::Ambiguous { .. })
+        ));
+    }
+}
@@ -15,16 +15,21 @@ use namada_core::types::storage::Epoch;
 use namada_core::types::time::DateTimeUtc;
 use namada_core::types::{storage, token};
 use namada_governance::cli::onchain::{
-    DefaultProposal, PgfFundingProposal, PgfStewardProposal,
+    DefaultProposal, EmergencyHaltProposal, MaspRewardsProposal,
+    ParameterChangeProposal, PgfFundingProposal, PgfStewardProposal,
+    SignalingProposal, WasmAllowlistProposal,
 };
+use namada_governance::storage::proposal::ProposalKind;
 use namada_tx::data::GasLimit;
 use namada_tx::Memo;
 use serde::{Deserialize, Serialize};
 use zeroize::Zeroizing;
 
 use crate::eth_bridge::bridge_pool;
-use crate::ibc::core::host::types::identifiers::{ChannelId, PortId};
+use crate::ibc::core::host::types::identifiers::{ChannelId, ClientId, PortId};
+use crate::masp::NoteSelectionStrategy;
 use crate::signing::SigningTxData;
+use crate::wallet::KeyBackend;
 use crate::{rpc, tx, Namada};
 
 /// [`Duration`](StdDuration) wrapper that provides a
@@ -236,6 +241,8 @@ pub struct TxTransfer<C: NamadaTypes = SdkTypes> {
     pub token: C::Address,
     /// Transferred token amount
     pub amount: InputAmount,
+    /// Strategy to select the notes spent from a shielded source
+    pub note_selection: NoteSelectionStrategy,
     /// Path to the TX WASM code file
     pub tx_code_path: PathBuf,
 }
@@ -273,6 +280,14 @@ impl<C: NamadaTypes> TxTransfer<C> {
         Self { amount, ..self }
     }
 
+    /// Strategy to select the notes spent from a shielded source
+    pub fn note_selection(self, note_selection: NoteSelectionStrategy) -> Self {
+        Self {
+            note_selection,
+            ..self
+        }
+    }
+
     /// Path to the TX WASM code file
     pub fn tx_code_path(self, tx_code_path: PathBuf) -> Self {
         Self {
@@ -293,6 +308,69 @@ impl TxTransfer {
     }
 }
 
+/// Shielding sweep transaction arguments
+#[derive(Clone, Debug)]
+pub struct TxShieldingSweep<C: NamadaTypes = SdkTypes> {
+    /// Common tx arguments
+    pub tx: Tx<C>,
+    /// Transparent address whose balances are shielded
+    pub source: C::Address,
+    /// Shielded payment address receiving the tokens
+    pub target: C::TransferTarget,
+    /// The tokens whose balances are shielded. The ones with a zero balance
+    /// are skipped.
+    pub tokens: Vec<C::Address>,
+    /// Path to the TX WASM code file
+    pub tx_code_path: PathBuf,
+}
+
+impl<C: NamadaTypes> TxBuilder<C> for TxShieldingSweep<C> {
+    fn tx<F>(self, func: F) -> Self
+    where
+        F: FnOnce(Tx<C>) -> Tx<C>,
+    {
+        TxShieldingSweep {
+            tx: func(self.tx),
+            ..self
+        }
+    }
+}
+
+impl<C: NamadaTypes> TxShieldingSweep<C> {
+    /// Transparent address whose balances are shielded
+    pub fn source(self, source: C::Address) -> Self {
+        Self { source, ..self }
+    }
+
+    /// Shielded payment address receiving the tokens
+    pub fn receiver(self, target: C::TransferTarget) -> Self {
+        Self { target, ..self }
+    }
+
+    /// The tokens whose balances are shielded
+    pub fn tokens(self, tokens: Vec<C::Address>) -> Self {
+        Self { tokens, ..self }
+    }
+
+    /// Path to the TX WASM code file
+    pub fn tx_code_path(self, tx_code_path: PathBuf) -> Self {
+        Self {
+            tx_code_path,
+            ..self
+        }
+    }
+}
+
+impl TxShieldingSweep {
+    /// Build a transaction from this builder
+    pub async fn build(
+        &mut self,
+        context: &impl Namada,
+    ) -> crate::error::Result<(namada_tx::Tx, SigningTxData, Epoch)> {
+        tx::build_shielding_sweep(context, self).await
+    }
+}
+
 /// IBC transfer transaction arguments
 #[derive(Clone, Debug)]
 pub struct TxIbcTransfer<C: NamadaTypes = SdkTypes> {
@@ -407,6 +485,71 @@ impl TxIbcTransfer {
     }
 }
 
+/// IBC light client misbehaviour submission transaction arguments
+#[derive(Clone, Debug)]
+pub struct TxIbcMisbehaviour<C: NamadaTypes = SdkTypes> {
+    /// Common tx arguments
+    pub tx: Tx<C>,
+    /// Address of the misbehaviour submitter
+    pub submitter: C::Address,
+    /// ID of the misbehaving client
+    pub client_id: ClientId,
+    /// Protobuf-encoded `Any` of the misbehaviour evidence
+    pub misbehaviour: C::Data,
+    /// Path to the TX WASM code file
+    pub tx_code_path: PathBuf,
+}
+
+impl<C: NamadaTypes> TxBuilder<C> for TxIbcMisbehaviour<C> {
+    fn tx<F>(self, func: F) -> Self
+    where
+        F: FnOnce(Tx<C>) -> Tx<C>,
+    {
+        TxIbcMisbehaviour {
+            tx: func(self.tx),
+            ..self
+        }
+    }
+}
+
+impl<C: NamadaTypes> TxIbcMisbehaviour<C> {
+    /// Address of the misbehaviour submitter
+    pub fn submitter(self, submitter: C::Address) -> Self {
+        Self { submitter, ..self }
+    }
+
+    /// ID of the misbehaving client
+    pub fn client_id(self, client_id: ClientId) -> Self {
+        Self { client_id, ..self }
+    }
+
+    /// Protobuf-encoded `Any` of the misbehaviour evidence
+    pub fn misbehaviour(self, misbehaviour: C::Data) -> Self {
+        Self {
+            misbehaviour,
+            ..self
+        }
+    }
+
+    /// Path to the TX WASM code file
+    pub fn tx_code_path(self, tx_code_path: PathBuf) -> Self {
+        Self {
+            tx_code_path,
+            ..self
+        }
+    }
+}
+
+impl TxIbcMisbehaviour {
+    /// Build a transaction from this builder
+    pub async fn build(
+        &self,
+        context: &impl Namada,
+    ) -> crate::error::Result<(namada_tx::Tx, SigningTxData)> {
+        tx::build_ibc_misbehaviour(context, self).await
+    }
+}
+
 /// Transaction to initialize create a new proposal
 #[derive(Clone, Debug)]
 pub struct InitProposal<C: NamadaTypes = SdkTypes> {
@@ -420,6 +563,16 @@ pub struct InitProposal<C: NamadaTypes = SdkTypes> {
     pub is_pgf_stewards: bool,
     /// Flag if proposal is of type Pgf funding
     pub is_pgf_funding: bool,
+    /// Flag if proposal is of type Masp rewards
+    pub is_masp_rewards: bool,
+    /// Flag if proposal is of type parameter change
+    pub is_parameter_change: bool,
+    /// Flag if proposal is of type emergency halt
+    pub is_emergency_halt: bool,
+    /// Flag if proposal is of type wasm allowlist
+    pub is_wasm_allowlist: bool,
+    /// Flag if proposal is of type signaling
+    pub is_signaling: bool,
     /// Path to the tx WASM file
     pub tx_code_path: PathBuf,
 }
@@ -466,6 +619,46 @@ impl<C: NamadaTypes> InitProposal<C> {
         }
     }
 
+    /// Flag if proposal is of type Masp rewards
+    pub fn is_masp_rewards(self, is_masp_rewards: bool) -> Self {
+        Self {
+            is_masp_rewards,
+            ..self
+        }
+    }
+
+    /// Flag if proposal is of type parameter change
+    pub fn is_parameter_change(self, is_parameter_change: bool) -> Self {
+        Self {
+            is_parameter_change,
+            ..self
+        }
+    }
+
+    /// Flag if proposal is of type emergency halt
+    pub fn is_emergency_halt(self, is_emergency_halt: bool) -> Self {
+        Self {
+            is_emergency_halt,
+            ..self
+        }
+    }
+
+    /// Flag if proposal is of type wasm allowlist
+    pub fn is_wasm_allowlist(self, is_wasm_allowlist: bool) -> Self {
+        Self {
+            is_wasm_allowlist,
+            ..self
+        }
+    }
+
+    /// Flag if proposal is of type signaling
+    pub fn is_signaling(self, is_signaling: bool) -> Self {
+        Self {
+            is_signaling,
+            ..self
+        }
+    }
+
     /// Path to the tx WASM file
     pub fn tx_code_path(self, tx_code_path: PathBuf) -> Self {
         Self {
@@ -528,6 +721,146 @@ impl InitProposal {
                 })?;
 
             tx::build_pgf_stewards_proposal(context, self, proposal).await
+        } else if self.is_masp_rewards {
+            let proposal = MaspRewardsProposal::try_from(
+                self.proposal_data.as_ref(),
+            )
+            .map_err(|e| {
+                crate::error::TxSubmitError::FailedGovernaneProposalDeserialize(
+                    e.to_string(),
+                )
+            })?;
+            let nam_address = context.native_token();
+            let author_balance = rpc::get_token_balance(
+                context.client(),
+                &nam_address,
+                &proposal.proposal.author,
+            )
+            .await?;
+            let proposal = proposal
+                .validate(
+                    &governance_parameters,
+                    current_epoch,
+                    author_balance,
+                    self.tx.force,
+                )
+                .map_err(|e| {
+                    crate::error::TxSubmitError::InvalidProposal(e.to_string())
+                })?;
+
+            tx::build_masp_rewards_proposal(context, self, proposal).await
+        } else if self.is_parameter_change {
+            let proposal = ParameterChangeProposal::try_from(
+                self.proposal_data.as_ref(),
+            )
+            .map_err(|e| {
+                crate::error::TxSubmitError::FailedGovernaneProposalDeserialize(
+                    e.to_string(),
+                )
+            })?;
+            let nam_address = context.native_token();
+            let author_balance = rpc::get_token_balance(
+                context.client(),
+                &nam_address,
+                &proposal.proposal.author,
+            )
+            .await?;
+            let proposal = proposal
+                .validate(
+                    &governance_parameters,
+                    current_epoch,
+                    author_balance,
+                    self.tx.force,
+                )
+                .map_err(|e| {
+                    crate::error::TxSubmitError::InvalidProposal(e.to_string())
+                })?;
+
+            tx::build_parameter_change_proposal(context, self, proposal).await
+        } else if self.is_emergency_halt {
+            let proposal = EmergencyHaltProposal::try_from(
+                self.proposal_data.as_ref(),
+            )
+            .map_err(|e| {
+                crate::error::TxSubmitError::FailedGovernaneProposalDeserialize(
+                    e.to_string(),
+                )
+            })?;
+            let nam_address = context.native_token();
+            let author_balance = rpc::get_token_balance(
+                context.client(),
+                &nam_address,
+                &proposal.proposal.author,
+            )
+            .await?;
+            let proposal = proposal
+                .validate(
+                    &governance_parameters,
+                    current_epoch,
+                    author_balance,
+                    self.tx.force,
+                )
+                .map_err(|e| {
+                    crate::error::TxSubmitError::InvalidProposal(e.to_string())
+                })?;
+
+            tx::build_emergency_halt_proposal(context, self, proposal).await
+        } else if self.is_wasm_allowlist {
+            let proposal = WasmAllowlistProposal::try_from(
+                self.proposal_data.as_ref(),
+            )
+            .map_err(|e| {
+                crate::error::TxSubmitError::FailedGovernaneProposalDeserialize(
+                    e.to_string(),
+                )
+            })?;
+            let nam_address = context.native_token();
+            let author_balance = rpc::get_token_balance(
+                context.client(),
+                &nam_address,
+                &proposal.proposal.author,
+            )
+            .await?;
+            let proposal = proposal
+                .validate(
+                    &governance_parameters,
+                    current_epoch,
+                    author_balance,
+                    self.tx.force,
+                )
+                .map_err(|e| {
+                    crate::error::TxSubmitError::InvalidProposal(e.to_string())
+                })?;
+
+            tx::build_wasm_allowlist_proposal(context, self, proposal).await
+        } else if self.is_signaling {
+            let proposal = SignalingProposal::try_from(
+                self.proposal_data.as_ref(),
+            )
+            .map_err(|e| {
+                crate::error::TxSubmitError::FailedGovernaneProposalDeserialize(
+                    e.to_string(),
+                )
+            })?;
+            let nam_address = context.native_token();
+            let author_balance = rpc::get_token_balance(
+                context.client(),
+                &nam_address,
+                &proposal.proposal.author,
+            )
+            .await?;
+            let proposal = proposal
+                .validate(
+                    &governance_parameters,
+                    current_epoch,
+                    author_balance,
+                    self.tx.force,
+                )
+                .map_err(|e| {
+                    crate::error::TxSubmitError::InvalidProposal(e.to_string())
+                })?;
+
+            tx::build_signaling_proposal(context, self, proposal).await
         } else {
             let proposal = DefaultProposal::try_from(
                 self.proposal_data.as_ref(),
@@ -572,6 +905,9 @@ pub struct VoteProposal<C: NamadaTypes = SdkTypes> {
     pub voter: C::Address,
     /// Flag if proposal vote should be run offline
     pub is_offline: bool,
+    /// Flag if the vote should be signed off-chain as a ballot, to be
+    /// submitted in a batch by any account
+    pub is_ballot: bool,
     /// The proposal file path
     pub proposal_data: Option<C::Data>,
     /// Path to the TX WASM code file
@@ -614,6 +950,11 @@ impl<C: NamadaTypes> VoteProposal<C> {
         Self { is_offline, ..self }
     }
 
+    /// Flag if the vote should be signed off-chain as a ballot
+    pub fn is_ballot(self, is_ballot: bool) -> Self {
+        Self { is_ballot, ..self }
+    }
+
     /// The proposal file path
     pub fn proposal_data(self, proposal_data: C::Data) -> Self {
         Self {
@@ -642,6 +983,189 @@ impl VoteProposal {
     }
 }
 
+/// Transaction to submit a batch of off-chain signed votes
+#[derive(Clone, Debug)]
+pub struct VoteProposalBatch<C: NamadaTypes = SdkTypes> {
+    /// Common tx arguments
+    pub tx: Tx<C>,
+    /// The signed ballots files (json)
+    pub ballots: Vec<C::Data>,
+    /// Path to the TX WASM code file
+    pub tx_code_path: PathBuf,
+}
+
+impl<C: NamadaTypes> TxBuilder<C> for VoteProposalBatch<C> {
+    fn tx<F>(self, func: F) -> Self
+    where
+        F: FnOnce(Tx<C>) -> Tx<C>,
+    {
+        VoteProposalBatch {
+            tx: func(self.tx),
+            ..self
+        }
+    }
+}
+
+impl<C: NamadaTypes> VoteProposalBatch<C> {
+    /// The signed ballots
+    pub fn ballots(self, ballots: Vec<C::Data>) -> Self {
+        Self { ballots, ..self }
+    }
+
+    /// Path to the TX WASM code file
+    pub fn tx_code_path(self, tx_code_path: PathBuf) -> Self {
+        Self {
+            tx_code_path,
+            ..self
+        }
+    }
+}
+
+impl VoteProposalBatch {
+    /// Build a transaction from this builder
+    pub async fn build(
+        &self,
+        context: &impl Namada,
+    ) -> crate::error::Result<(namada_tx::Tx, SigningTxData)> {
+        let current_epoch = rpc::query_epoch(context.client()).await?;
+        tx::build_vote_proposal_batch(context, self, current_epoch).await
+    }
+}
+
+/// Transaction to withdraw the vote cast on a proposal
+#[derive(Clone, Debug)]
+pub struct VoteWithdrawal<C: NamadaTypes = SdkTypes> {
+    /// Common tx arguments
+    pub tx: Tx<C>,
+    /// Proposal id
+    pub proposal_id: u64,
+    /// The address of the voter
+    pub voter: C::Address,
+    /// Path to the TX WASM code file
+    pub tx_code_path: PathBuf,
+}
+
+impl<C: NamadaTypes> TxBuilder<C> for VoteWithdrawal<C> {
+    fn tx<F>(self, func: F) -> Self
+    where
+        F: FnOnce(Tx<C>) -> Tx<C>,
+    {
+        VoteWithdrawal {
+            tx: func(self.tx),
+            ..self
+        }
+    }
+}
+
+impl<C: NamadaTypes> VoteWithdrawal<C> {
+    /// Proposal id
+    pub fn proposal_id(self, proposal_id: u64) -> Self {
+        Self {
+            proposal_id,
+            ..self
+        }
+    }
+
+    /// The address of the voter
+    pub fn voter(self, voter: C::Address) -> Self {
+        Self { voter, ..self }
+    }
+
+    /// Path to the TX WASM code file
+    pub fn tx_code_path(self, tx_code_path: PathBuf) -> Self {
+        Self {
+            tx_code_path,
+            ..self
+        }
+    }
+}
+
+impl VoteWithdrawal {
+    /// Build a transaction from this builder
+    pub async fn build(
+        &self,
+        context: &impl Namada,
+    ) -> crate::error::Result<(namada_tx::Tx, SigningTxData)> {
+        let current_epoch = rpc::query_epoch(context.client()).await?;
+        tx::build_vote_withdrawal(context, self, current_epoch).await
+    }
+}
+
+/// Transaction to delegate the governance voting power of an account
+#[derive(Clone, Debug)]
+pub struct VoteDelegation<C: NamadaTypes = SdkTypes> {
+    /// Common tx arguments
+    pub tx: Tx<C>,
+    /// The address delegating its voting power
+    pub delegator: C::Address,
+    /// The address receiving the voting power, `None` to revoke the
+    /// delegation
+    pub delegate: Option<C::Address>,
+    /// The kinds of proposals the delegation applies to, all of them if
+    /// empty
+    pub kinds: Vec<ProposalKind>,
+    /// Path to the TX WASM code file
+    pub tx_code_path: PathBuf,
+}
+
+impl<C: NamadaTypes> TxBuilder<C> for VoteDelegation<C> {
+    fn tx<F>(self, func: F) -> Self
+    where
+        F: FnOnce(Tx<C>) -> Tx<C>,
+    {
+        VoteDelegation {
+            tx: func(self.tx),
+            ..self
+        }
+    }
+}
+
+impl<C: NamadaTypes> VoteDelegation<C> {
+    /// The address delegating its voting power
+    pub fn delegator(self, delegator: C::Address) -> Self {
+        Self { delegator, ..self }
+    }
+
+    /// The address receiving the voting power
+    pub fn delegate(self, delegate: C::Address) -> Self {
+        Self {
+            delegate: Some(delegate),
+            ..self
+        }
+    }
+
+    /// Revoke the delegation instead of setting a delegate
+    pub fn revoke(self) -> Self {
+        Self {
+            delegate: None,
+            ..self
+        }
+    }
+
+    /// The kinds of proposals the delegation applies to
+    pub fn kinds(self, kinds: Vec<ProposalKind>) -> Self {
+        Self { kinds, ..self }
+    }
+
+    /// Path to the TX WASM code file
+    pub fn tx_code_path(self, tx_code_path: PathBuf) -> Self {
+        Self {
+            tx_code_path,
+            ..self
+        }
+    }
+}
+
+impl VoteDelegation {
+    /// Build a transaction from this builder
+    pub async fn build(
+        &self,
+        context: &impl Namada,
+    ) -> crate::error::Result<(namada_tx::Tx, SigningTxData)> {
+        tx::build_vote_delegation(context, self).await
+    }
+}
+
 /// Transaction to initialize a new account
 #[derive(Clone, Debug)]
 pub struct TxInitAccount<C: NamadaTypes = SdkTypes> {
@@ -808,6 +1332,10 @@ pub struct TxUpdateAccount<C: NamadaTypes = SdkTypes> {
     pub public_keys: Vec<C::PublicKey>,
     /// The account threshold
     pub threshold: Option<u8>,
+    /// The number of epochs that an update of the account's VP is delayed by
+    pub vp_update_timelock: Option<u64>,
+    /// Whether to cancel a pending update of the account's VP
+    pub cancel_pending_vp_update: bool,
 }
 
 impl<C: NamadaTypes> TxBuilder<C> for TxUpdateAccount<C> {
@@ -859,6 +1387,25 @@ impl<C: NamadaTypes> TxUpdateAccount<C> {
             ..self
         }
     }
+
+    /// The number of epochs that an update of the account's VP is delayed by
+    pub fn vp_update_timelock(self, vp_update_timelock: u64) -> Self {
+        Self {
+            vp_update_timelock: Some(vp_update_timelock),
+            ..self
+        }
+    }
+
+    /// Cancel a pending update of the account's VP
+    pub fn cancel_pending_vp_update(
+        self,
+        cancel_pending_vp_update: bool,
+    ) -> Self {
+        Self {
+            cancel_pending_vp_update,
+            ..self
+        }
+    }
 }
 
 impl TxUpdateAccount {
@@ -1457,6 +2004,8 @@ pub struct MetaDataChange<C: NamadaTypes = SdkTypes> {
     pub discord_handle: Option<String>,
     /// New validator avatar url
     pub avatar: Option<String>,
+    /// New validator name
+    pub name: Option<String>,
     /// New validator commission rate
     pub commission_rate: Option<Dec>,
     /// Path to the TX WASM code file
@@ -1529,6 +2078,14 @@ impl<C: NamadaTypes> MetaDataChange<C> {
         }
     }
 
+    /// New validator name
+    pub fn name(self, name: String) -> Self {
+        Self {
+            name: Some(name),
+            ..self
+        }
+    }
+
     /// New validator commission rate
     pub fn commission_rate(self, commission_rate: Dec) -> Self {
         Self {
@@ -1651,6 +2208,61 @@ impl ResignSteward {
     }
 }
 
+#[derive(Clone, Debug)]
+/// Pgf milestone attestation args
+pub struct AttestPgfMilestone<C: NamadaTypes = SdkTypes> {
+    /// Common tx arguments
+    pub tx: Tx<C>,
+    /// Steward address
+    pub steward: C::Address,
+    /// The grant id
+    pub grant_id: u64,
+    /// Path to the TX WASM code file
+    pub tx_code_path: PathBuf,
+}
+
+impl<C: NamadaTypes> TxBuilder<C> for AttestPgfMilestone<C> {
+    fn tx<F>(self, func: F) -> Self
+    where
+        F: FnOnce(Tx<C>) -> Tx<C>,
+    {
+        AttestPgfMilestone {
+            tx: func(self.tx),
+            ..self
+        }
+    }
+}
+
+impl<C: NamadaTypes> AttestPgfMilestone<C> {
+    /// Steward address
+    pub fn steward(self, steward: C::Address) -> Self {
+        Self { steward, ..self }
+    }
+
+    /// The grant id
+    pub fn grant_id(self, grant_id: u64) -> Self {
+        Self { grant_id, ..self }
+    }
+
+    /// Path to the TX WASM code file
+    pub fn tx_code_path(self, tx_code_path: PathBuf) -> Self {
+        Self {
+            tx_code_path,
+            ..self
+        }
+    }
+}
+
+impl AttestPgfMilestone {
+    /// Build a transaction from this builder
+    pub async fn build(
+        &self,
+        context: &impl Namada,
+    ) -> crate::error::Result<(namada_tx::Tx, SigningTxData)> {
+        tx::build_attest_pgf_milestone(context, self).await
+    }
+}
+
 #[derive(Clone, Debug)]
 /// Re-activate a jailed validator args
 pub struct TxUnjailValidator<C: NamadaTypes = SdkTypes> {
@@ -2173,6 +2785,35 @@ pub struct KeyExport {
     pub alias: String,
 }
 
+/// Wallet key password change arguments
+#[derive(Clone, Debug)]
+pub struct KeyChangePassword {
+    /// Key alias
+    pub alias: String,
+    /// Don't encrypt the key anymore
+    pub unsafe_dont_encrypt: bool,
+}
+
+/// Wallet external key addition arguments
+#[derive(Clone, Debug)]
+pub struct KeyAddExternal {
+    /// Key alias
+    pub alias: String,
+    /// Whether to force overwrite the alias
+    pub alias_force: bool,
+    /// The public key of the key
+    pub public_key: common::PublicKey,
+    /// The backend holding the secret key
+    pub backend: KeyBackend,
+}
+
+/// Wallet view-only export arguments
+#[derive(Clone, Debug)]
+pub struct KeyExportViewOnly {
+    /// The folder in which the view-only wallet is written
+    pub output_folder: Option<PathBuf>,
+}
+
 /// Wallet key import arguments
 #[derive(Clone, Debug)]
 pub struct KeyImport {
@@ -0,0 +1,498 @@
+//! Fluent builders of the common txs that validate their arguments against
+//! the state of the chain before the txs are built and signed. The invalid
+//! arguments, e.g. a bond to an address that isn't a validator or a vote out
+//! of the voting period of a proposal, are reported with a
+//! [`ValidationError`] instead of letting the txs fail on-chain.
+//!
+//! The builders implement [`TxBuilder`], so the common tx arguments are set
+//! as with the builders of [`crate::args`].
+
+use namada_core::types::address::Address;
+use namada_core::types::masp::{TransferSource, TransferTarget};
+use namada_core::types::storage::Epoch;
+use namada_core::types::token::{self, DenominatedAmount, Denomination};
+use namada_governance::storage::vote::ProposalVote;
+use namada_ibc::storage::channel_key;
+use namada_proof_of_stake::types::ValidatorState;
+use namada_tx::Tx;
+
+use crate::args::{self, InputAmount, SdkTypes, TxBuilder};
+use crate::error::{Error, Result, ValidationError};
+use crate::ibc::core::host::types::identifiers::{ChannelId, PortId};
+use crate::signing::SigningTxData;
+use crate::{rpc, Namada};
+
+/// A transfer of tokens
+#[derive(Clone, Debug)]
+pub struct TransferBuilder {
+    args: args::TxTransfer,
+}
+
+impl TransferBuilder {
+    /// A transfer of an amount of a token from a source to a target
+    pub fn new(
+        context: &impl Namada,
+        source: TransferSource,
+        target: TransferTarget,
+        token: Address,
+        amount: InputAmount,
+    ) -> Self {
+        Self {
+            args: context.new_transfer(source, target, token, amount),
+        }
+    }
+
+    /// The arguments of the transfer
+    pub fn args(&self) -> &args::TxTransfer {
+        &self.args
+    }
+
+    /// Validate the transfer against the state of the chain: the amount must
+    /// be positive and fit the denomination of the token, a transparent
+    /// source must hold the amount and a transparent target must exist.
+    pub async fn validate(&self, context: &impl Namada) -> Result<()> {
+        let args::TxTransfer {
+            source,
+            target,
+            token,
+            amount,
+            ..
+        } = &self.args;
+        let amount = validate_amount(context, token, amount).await?;
+        if let Some(source) = source.address() {
+            require_balance(context, &source, token, amount).await?;
+        }
+        if let Some(target) = target.address() {
+            require_account(context, &target).await?;
+        }
+        Ok(())
+    }
+
+    /// Validate and build the transfer
+    pub async fn build(
+        &mut self,
+        context: &impl Namada,
+    ) -> Result<(Tx, SigningTxData, Option<Epoch>)> {
+        self.validate(context).await?;
+        self.args.build(context).await
+    }
+}
+
+impl From<args::TxTransfer> for TransferBuilder {
+    fn from(args: args::TxTransfer) -> Self {
+        Self { args }
+    }
+}
+
+impl TxBuilder<SdkTypes> for TransferBuilder {
+    fn tx<F>(self, func: F) -> Self
+    where
+        F: FnOnce(args::Tx<SdkTypes>) -> args::Tx<SdkTypes>,
+    {
+        Self {
+            args: self.args.tx(func),
+        }
+    }
+}
+
+/// A bond of native tokens to a validator
+#[derive(Clone, Debug)]
+pub struct BondBuilder {
+    args: args::Bond,
+}
+
+impl BondBuilder {
+    /// A self-bond of the validator, or a delegation once a source is set
+    pub fn new(
+        context: &impl Namada,
+        validator: Address,
+        amount: token::Amount,
+    ) -> Self {
+        Self {
+            args: context.new_bond(validator, amount),
+        }
+    }
+
+    /// The source of a delegation
+    pub fn source(self, source: Address) -> Self {
+        Self {
+            args: self.args.source(source),
+        }
+    }
+
+    /// The arguments of the bond
+    pub fn args(&self) -> &args::Bond {
+        &self.args
+    }
+
+    /// Validate the bond against the state of the chain: the amount must be
+    /// positive, the validator must be active at the pipeline epoch and the
+    /// source must exist and hold the amount.
+    pub async fn validate(&self, context: &impl Namada) -> Result<()> {
+        let args::Bond {
+            validator,
+            amount,
+            source,
+            ..
+        } = &self.args;
+        if amount.is_zero() {
+            return Err(ValidationError::ZeroAmount.into());
+        }
+        if !rpc::is_validator(context.client(), validator).await? {
+            return Err(
+                ValidationError::NotAValidator(validator.clone()).into()
+            );
+        }
+        let params = rpc::get_pos_params(context.client()).await?;
+        let epoch =
+            rpc::query_epoch(context.client()).await? + params.pipeline_len;
+        let state =
+            rpc::get_validator_state(context.client(), validator, Some(epoch))
+                .await?;
+        if state == Some(ValidatorState::Inactive) {
+            return Err(ValidationError::InactiveValidator {
+                validator: validator.clone(),
+                epoch,
+            }
+            .into());
+        }
+        let source = source.as_ref().unwrap_or(validator);
+        require_account(context, source).await?;
+        let amount = DenominatedAmount::native(*amount);
+        require_balance(context, source, &context.native_token(), amount).await
+    }
+
+    /// Validate and build the bond
+    pub async fn build(
+        &self,
+        context: &impl Namada,
+    ) -> Result<(Tx, SigningTxData)> {
+        self.validate(context).await?;
+        self.args.build(context).await
+    }
+}
+
+impl From<args::Bond> for BondBuilder {
+    fn from(args: args::Bond) -> Self {
+        Self { args }
+    }
+}
+
+impl TxBuilder<SdkTypes> for BondBuilder {
+    fn tx<F>(self, func: F) -> Self
+    where
+        F: FnOnce(args::Tx<SdkTypes>) -> args::Tx<SdkTypes>,
+    {
+        Self {
+            args: self.args.tx(func),
+        }
+    }
+}
+
+/// A vote on a governance proposal
+#[derive(Clone, Debug)]
+pub struct VoteBuilder {
+    args: args::VoteProposal,
+}
+
+impl VoteBuilder {
+    /// A vote of the voter on the proposal, one of `yay`, `nay`, `abstain`
+    /// or `veto`
+    pub fn new(
+        context: &impl Namada,
+        proposal_id: u64,
+        vote: String,
+        voter: Address,
+    ) -> Self {
+        Self {
+            args: context
+                .new_vote_prposal(vote, voter)
+                .proposal_id(proposal_id),
+        }
+    }
+
+    /// The arguments of the vote
+    pub fn args(&self) -> &args::VoteProposal {
+        &self.args
+    }
+
+    /// Validate the vote against the state of the chain: the proposal must
+    /// exist and be in its voting period for the voter, who must have
+    /// delegations at the start of the voting period.
+    pub async fn validate(&self, context: &impl Namada) -> Result<()> {
+        let args::VoteProposal {
+            vote,
+            voter,
+            proposal_id,
+            ..
+        } = &self.args;
+        ProposalVote::try_from(vote.clone())
+            .map_err(|_| ValidationError::InvalidVote(vote.clone()))?;
+        let id = proposal_id.ok_or_else(|| {
+            Error::Other("The proposal id must be defined".to_string())
+        })?;
+        let proposal = rpc::query_proposal_by_id(context.client(), id)
+            .await?
+            .ok_or(ValidationError::UnknownProposal(id))?;
+        let epoch = rpc::query_epoch(context.client()).await?;
+        let is_validator = rpc::is_validator(context.client(), voter).await?;
+        if !proposal.can_be_voted(epoch, is_validator) {
+            return Err(ValidationError::VotingPeriodClosed {
+                id,
+                voter: voter.clone(),
+                epoch,
+                voting_start_epoch: proposal.voting_start_epoch,
+                voting_end_epoch: proposal.voting_end_epoch,
+            }
+            .into());
+        }
+        let delegations = rpc::get_delegators_delegation_at(
+            context.client(),
+            voter,
+            proposal.voting_start_epoch,
+        )
+        .await?;
+        if delegations.is_empty() {
+            return Err(ValidationError::NoDelegations(voter.clone()).into());
+        }
+        Ok(())
+    }
+
+    /// Validate and build the vote
+    pub async fn build(
+        &self,
+        context: &impl Namada,
+    ) -> Result<(Tx, SigningTxData)> {
+        self.validate(context).await?;
+        self.args.build(context).await
+    }
+}
+
+impl From<args::VoteProposal> for VoteBuilder {
+    fn from(args: args::VoteProposal) -> Self {
+        Self { args }
+    }
+}
+
+impl TxBuilder<SdkTypes> for VoteBuilder {
+    fn tx<F>(self, func: F) -> Self
+    where
+        F: FnOnce(args::Tx<SdkTypes>) -> args::Tx<SdkTypes>,
+    {
+        Self {
+            args: self.args.tx(func),
+        }
+    }
+}
+
+/// An IBC transfer of tokens to another chain
+#[derive(Clone, Debug)]
+pub struct IbcTransferBuilder {
+    args: args::TxIbcTransfer,
+}
+
+impl IbcTransferBuilder {
+    /// A transfer of an amount of a token from a source to a receiver on the
+    /// counterparty chain of the channel
+    pub fn new(
+        context: &impl Namada,
+        source: TransferSource,
+        receiver: String,
+        token: Address,
+        amount: InputAmount,
+        channel_id: ChannelId,
+    ) -> Self {
+        Self {
+            args: context
+                .new_ibc_transfer(source, receiver, token, amount, channel_id),
+        }
+    }
+
+    /// The port of the transfer, `transfer` by default
+    pub fn port_id(self, port_id: PortId) -> Self {
+        Self {
+            args: args::TxIbcTransfer {
+                port_id,
+                ..self.args
+            },
+        }
+    }
+
+    /// The memo of the transfer
+    pub fn memo(self, memo: String) -> Self {
+        Self {
+            args: args::TxIbcTransfer {
+                memo: Some(memo),
+                ..self.args
+            },
+        }
+    }
+
+    /// The arguments of the transfer
+    pub fn args(&self) -> &args::TxIbcTransfer {
+        &self.args
+    }
+
+    /// Validate the transfer against the state of the chain: the amount must
+    /// be positive and fit the denomination of the token, a transparent
+    /// source must hold the amount, the receiver must be set and the channel
+    /// must exist.
+    pub async fn validate(&self, context: &impl Namada) -> Result<()> {
+        let args::TxIbcTransfer {
+            source,
+            receiver,
+            token,
+            amount,
+            port_id,
+            channel_id,
+            ..
+        } = &self.args;
+        let amount = validate_amount(context, token, amount).await?;
+        if let Some(source) = source.address() {
+            require_balance(context, &source, token, amount).await?;
+        }
+        if receiver.trim().is_empty() {
+            return Err(ValidationError::EmptyReceiver.into());
+        }
+        let key = channel_key(port_id, channel_id);
+        if !rpc::query_has_storage_key(context.client(), &key).await? {
+            return Err(ValidationError::UnknownChannel {
+                port: port_id.to_string(),
+                channel: channel_id.to_string(),
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Validate and build the transfer
+    pub async fn build(
+        &self,
+        context: &impl Namada,
+    ) -> Result<(Tx, SigningTxData, Option<Epoch>)> {
+        self.validate(context).await?;
+        self.args.build(context).await
+    }
+}
+
+impl From<args::TxIbcTransfer> for IbcTransferBuilder {
+    fn from(args: args::TxIbcTransfer) -> Self {
+        Self { args }
+    }
+}
+
+impl TxBuilder<SdkTypes> for IbcTransferBuilder {
+    fn tx<F>(self, func: F) -> Self
+    where
+        F: FnOnce(args::Tx<SdkTypes>) -> args::Tx<SdkTypes>,
+    {
+        Self {
+            args: self.args.tx(func),
+        }
+    }
+}
+
+/// Validate an amount against the denomination of its token, and denominate
+/// it with it
+async fn validate_amount(
+    context: &impl Namada,
+    token: &Address,
+    amount: &InputAmount,
+) -> Result<DenominatedAmount> {
+    let denom = rpc::query_denom(context.client(), token)
+        .await
+        .ok_or_else(|| ValidationError::UnknownToken(token.clone()))?;
+    Ok(denominate(token, amount, denom)?)
+}
+
+/// Denominate a positive amount with the denomination of its token
+fn denominate(
+    token: &Address,
+    amount: &InputAmount,
+    denom: Denomination,
+) -> std::result::Result<DenominatedAmount, ValidationError> {
+    let amount = match amount {
+        InputAmount::Validated(amount) | InputAmount::Unvalidated(amount) => {
+            *amount
+        }
+    };
+    if amount.is_zero() {
+        return Err(ValidationError::ZeroAmount);
+    }
+    amount.canonical().increase_precision(denom).map_err(|_| {
+        ValidationError::PrecisionTooHigh {
+            token: token.clone(),
+            amount,
+            denom: denom.0,
+        }
+    })
+}
+
+/// Require the balance of an owner to cover an amount of a token
+async fn require_balance(
+    context: &impl Namada,
+    owner: &Address,
+    token: &Address,
+    amount: DenominatedAmount,
+) -> Result<()> {
+    let balance =
+        rpc::get_token_balance(context.client(), token, owner).await?;
+    if balance < amount.amount() {
+        return Err(ValidationError::InsufficientBalance {
+            owner: owner.clone(),
+            token: token.clone(),
+            balance: DenominatedAmount::new(balance, amount.denom()),
+            amount,
+        }
+        .into());
+    }
+    Ok(())
+}
+
+/// Require an account to exist
+async fn require_account(
+    context: &impl Namada,
+    address: &Address,
+) -> Result<()> {
+    if !rpc::known_address(context.client(), address).await? {
+        return Err(ValidationError::UnknownAccount(address.clone()).into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use namada_core::types::address::nam;
+
+    use super::*;
+
+    /// Test the validation of the amounts against the denomination of their
+    /// token
+    #[test]
+    fn test_denominate_amount() {
+        let token = nam();
+        let denom = Denomination(6);
+        let amount = |amount: u64, denom: u8| {
+            InputAmount::Unvalidated(DenominatedAmount::new(
+                token::Amount::from_u64(amount),
+                Denomination(denom),
+            ))
+        };
+
+        // 1.5 with 6 decimal places
+        let denominated = denominate(&token, &amount(15, 1), denom).unwrap();
+        assert_eq!(denominated.amount(), token::Amount::from_u64(1_500_000));
+        assert_eq!(denominated.denom(), denom);
+        // The trailing zeros don't count
+        assert!(denominate(&token, &amount(1_000, 9), denom).is_ok());
+
+        assert!(matches!(
+            denominate(&token, &amount(1, 7), denom),
+            Err(ValidationError::PrecisionTooHigh { denom: 6, .. })
+        ));
+        assert!(matches!(
+            denominate(&token, &amount(0, 0), denom),
+            Err(ValidationError::ZeroAmount)
+        ));
+    }
+}
@@ -0,0 +1,303 @@
+//! A client of the RPC of the ledger that manages the connections to the
+//! nodes, so that the apps don't each have to. Every attempt of a request is
+//! timed out, and the requests that fail to reach a node are retried with an
+//! exponential backoff, failing over to the next endpoint. The errors
+//! returned by the nodes themselves are not retried.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use tendermint_rpc::error::ErrorDetail;
+use tendermint_rpc::{Error as RpcError, HttpClient, SimpleRequest};
+use thiserror::Error;
+
+use crate::control_flow::time::{self, Duration, Instant};
+use crate::queries::Client;
+
+/// The default timeout of an attempt of a request
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The default number of retries of a failed request
+pub const DEFAULT_MAX_RETRIES: usize = 3;
+
+/// The default backoff before the first retry of a failed request
+pub const DEFAULT_RETRY_BACKOFF: Duration = Duration::from_millis(500);
+
+/// The errors of the requests of a [`NamadaClient`]
+#[derive(Error, Debug)]
+pub enum ClientError {
+    /// No endpoint was configured
+    #[error("No RPC endpoint was configured")]
+    NoEndpoints,
+    /// An endpoint isn't a valid URL
+    #[error("Invalid RPC endpoint {endpoint}: {error}")]
+    InvalidEndpoint {
+        /// The endpoint
+        endpoint: String,
+        /// The error of the URL
+        error: String,
+    },
+    /// The request couldn't reach any node
+    #[error(
+        "The request failed after {attempts} attempts, the last one on \
+         {endpoint}: {error}"
+    )]
+    Unavailable {
+        /// The number of attempts of the request
+        attempts: usize,
+        /// The endpoint of the last attempt
+        endpoint: String,
+        /// The error of the last attempt
+        error: String,
+    },
+    /// The node returned an error for the request
+    #[error("The request was rejected by {endpoint}: {error}")]
+    Rejected {
+        /// The endpoint that rejected the request
+        endpoint: String,
+        /// The error returned by the node
+        error: RpcError,
+    },
+}
+
+/// The configuration of a [`RetryClient`]
+#[derive(Debug, Clone)]
+pub struct ClientConfig {
+    /// The URLs of the RPC endpoints, in the order of preference
+    pub endpoints: Vec<String>,
+    /// The timeout of every attempt of a request
+    pub request_timeout: Duration,
+    /// The number of retries of a request that failed to reach a node
+    pub max_retries: usize,
+    /// The backoff before the first retry, doubled on every retry
+    pub retry_backoff: Duration,
+}
+
+impl ClientConfig {
+    /// The default configuration for the given endpoints
+    pub fn new(endpoints: Vec<String>) -> Self {
+        Self {
+            endpoints,
+            request_timeout: DEFAULT_REQUEST_TIMEOUT,
+            max_retries: DEFAULT_MAX_RETRIES,
+            retry_backoff: DEFAULT_RETRY_BACKOFF,
+        }
+    }
+}
+
+/// A client of the RPC of the ledger, usable with all the queries of the SDK,
+/// that surfaces the failures of the requests as typed errors
+#[cfg_attr(feature = "async-send", async_trait::async_trait)]
+#[cfg_attr(not(feature = "async-send"), async_trait::async_trait(?Send))]
+pub trait NamadaClient: Client + Sync {
+    /// The endpoint the next request will be sent to
+    fn current_endpoint(&self) -> &str;
+
+    /// Perform a request, retrying it and failing over to the other
+    /// endpoints as needed
+    async fn try_perform<R>(
+        &self,
+        request: R,
+    ) -> Result<R::Output, ClientError>
+    where
+        R: SimpleRequest;
+}
+
+/// A [`NamadaClient`] over the HTTP endpoints of the nodes
+pub struct RetryClient {
+    /// The endpoints with their clients, in the order of preference
+    endpoints: Vec<(String, HttpClient)>,
+    /// The index of the endpoint the requests are sent to first
+    current: AtomicUsize,
+    request_timeout: Duration,
+    max_retries: usize,
+    retry_backoff: Duration,
+}
+
+impl RetryClient {
+    /// Create a client of the configured endpoints
+    pub fn new(config: ClientConfig) -> Result<Self, ClientError> {
+        if config.endpoints.is_empty() {
+            return Err(ClientError::NoEndpoints);
+        }
+        let endpoints = config
+            .endpoints
+            .into_iter()
+            .map(|endpoint| {
+                let client =
+                    HttpClient::new(endpoint.as_str()).map_err(|err| {
+                        ClientError::InvalidEndpoint {
+                            endpoint: endpoint.clone(),
+                            error: err.to_string(),
+                        }
+                    })?;
+                Ok((endpoint, client))
+            })
+            .collect::<Result<_, ClientError>>()?;
+        Ok(Self {
+            endpoints,
+            current: AtomicUsize::new(0),
+            request_timeout: config.request_timeout,
+            max_retries: config.max_retries,
+            retry_backoff: config.retry_backoff,
+        })
+    }
+
+    /// Perform a request until it reaches a node or exhausts the retries.
+    async fn perform_with_retries<R>(
+        &self,
+        request: R,
+    ) -> Result<R::Output, ClientError>
+    where
+        R: SimpleRequest,
+    {
+        // The requests are consumed by the client, so they are re-created
+        // from their serialization for the retries
+        let request = serde_json::to_string(&request)
+            .expect("RPC requests must be serializable");
+        let mut backoff = self.retry_backoff;
+        let mut attempts = 0;
+        loop {
+            let index = self.current.load(Ordering::Relaxed);
+            let (endpoint, client) = &self.endpoints[index];
+            let attempt: R = serde_json::from_str(&request)
+                .expect("RPC requests must be deserializable");
+            attempts += 1;
+
+            let deadline = Instant::now() + self.request_timeout;
+            let response = time::timeout_at(
+                deadline,
+                tendermint_rpc::client::Client::perform(client, attempt),
+            )
+            .await;
+            let error = match response {
+                Ok(Ok(response)) => return Ok(response),
+                Ok(Err(error)) if !is_transport_error(&error) => {
+                    return Err(ClientError::Rejected {
+                        endpoint: endpoint.clone(),
+                        error,
+                    });
+                }
+                Ok(Err(error)) => error.to_string(),
+                Err(_) => format!(
+                    "The request timed out after {:?}",
+                    self.request_timeout
+                ),
+            };
+
+            // Fail over to the next endpoint, unless a concurrent request
+            // already did
+            let next = (index + 1) % self.endpoints.len();
+            _ = self.current.compare_exchange(
+                index,
+                next,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            );
+            if attempts > self.max_retries {
+                return Err(ClientError::Unavailable {
+                    attempts,
+                    endpoint: endpoint.clone(),
+                    error,
+                });
+            }
+            tracing::debug!(
+                endpoint,
+                attempts,
+                "The RPC request failed, retrying in {backoff:?}: {error}"
+            );
+            time::sleep(backoff).await;
+            backoff = backoff.saturating_mul(2);
+        }
+    }
+}
+
+#[cfg_attr(feature = "async-send", async_trait::async_trait)]
+#[cfg_attr(not(feature = "async-send"), async_trait::async_trait(?Send))]
+impl NamadaClient for RetryClient {
+    fn current_endpoint(&self) -> &str {
+        &self.endpoints[self.current.load(Ordering::Relaxed)].0
+    }
+
+    async fn try_perform<R>(&self, request: R) -> Result<R::Output, ClientError>
+    where
+        R: SimpleRequest,
+    {
+        self.perform_with_retries(request).await
+    }
+}
+
+/// The [`RetryClient`] can be used wherever a `tendermint_rpc` client is
+/// expected, which also makes it a [`Client`] of the queries of the SDK.
+#[async_trait::async_trait]
+impl tendermint_rpc::client::Client for RetryClient {
+    async fn perform<R>(&self, request: R) -> Result<R::Output, RpcError>
+    where
+        R: SimpleRequest,
+    {
+        self.perform_with_retries(request)
+            .await
+            .map_err(|err| match err {
+                ClientError::Rejected { error, .. } => error,
+                err => RpcError::client_internal(err.to_string()),
+            })
+    }
+}
+
+/// Whether the request failed to reach the node, as opposed to an error
+/// returned by the node
+fn is_transport_error(error: &RpcError) -> bool {
+    !matches!(error.detail(), ErrorDetail::Response(_))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test that the endpoints of the client are validated
+    #[test]
+    fn test_client_endpoints() {
+        assert!(matches!(
+            RetryClient::new(ClientConfig::new(vec![])),
+            Err(ClientError::NoEndpoints)
+        ));
+        assert!(matches!(
+            RetryClient::new(ClientConfig::new(vec!["not a url".to_string()])),
+            Err(ClientError::InvalidEndpoint { .. })
+        ));
+        let client = RetryClient::new(ClientConfig::new(vec![
+            "http://127.0.0.1:26657".to_string(),
+        ]))
+        .unwrap();
+        assert_eq!(client.current_endpoint(), "http://127.0.0.1:26657");
+    }
+
+    /// Test that the failed requests are retried on the next endpoints
+    #[tokio::test]
+    async fn test_client_failover() {
+        let first = "http://127.0.0.1:1".to_string();
+        let second = "http://127.0.0.1:2".to_string();
+        let client = RetryClient::new(ClientConfig {
+            endpoints: vec![first.clone(), second.clone()],
+            request_timeout: Duration::from_secs(5),
+            max_retries: 2,
+            retry_backoff: Duration::from_millis(1),
+        })
+        .unwrap();
+
+        let err = client
+            .try_perform(tendermint_rpc::endpoint::status::Request)
+            .await
+            .unwrap_err();
+        match err {
+            ClientError::Unavailable {
+                attempts, endpoint, ..
+            } => {
+                assert_eq!(attempts, 3);
+                assert_eq!(endpoint, first);
+            }
+            err => panic!("Unexpected error {err}"),
+        }
+        // The last attempt failed over to the second endpoint
+        assert_eq!(client.current_endpoint(), second);
+    }
+}
@@ -5,6 +5,7 @@ use namada_core::types::dec::Dec;
 use namada_core::types::ethereum_events::EthAddress;
 use namada_core::types::storage;
 use namada_core::types::storage::Epoch;
+use namada_core::types::token::DenominatedAmount;
 use namada_tx::Tx;
 use prost::EncodeError;
 use tendermint_rpc::Error as RpcError;
@@ -42,6 +43,9 @@ pub enum Error {
     /// Ethereum bridge related errors
     #[error("{0}")]
     EthereumBridge(#[from] EthereumBridgeError),
+    /// Invalid arguments of a tx, given the state of the chain
+    #[error("Invalid tx arguments: {0}")]
+    Validation(#[from] ValidationError),
     /// Any Other errors that are uncategorized
     #[error("{0}")]
     Other(String),
@@ -115,6 +119,102 @@ pub enum EncodingError {
     Conversion(String),
 }
 
+/// Errors of the validation of the arguments of a tx against the state of the
+/// chain, before the tx is signed
+#[derive(Error, Debug, Clone)]
+pub enum ValidationError {
+    /// The amount is zero
+    #[error("The amount must be positive")]
+    ZeroAmount,
+    /// The token has no denomination
+    #[error("The token {0} has no denomination, it may not exist")]
+    UnknownToken(Address),
+    /// The amount has more decimal places than the token
+    #[error(
+        "The amount {amount} is more precise than the {denom} decimal places \
+         of the token {token}"
+    )]
+    PrecisionTooHigh {
+        /// The token
+        token: Address,
+        /// The requested amount
+        amount: DenominatedAmount,
+        /// The number of decimal places of the token
+        denom: u8,
+    },
+    /// The balance doesn't cover the amount
+    #[error(
+        "The balance of {owner} is {balance} of the token {token}, lower than \
+         the amount {amount}"
+    )]
+    InsufficientBalance {
+        /// The owner of the balance
+        owner: Address,
+        /// The token
+        token: Address,
+        /// The balance of the owner
+        balance: DenominatedAmount,
+        /// The requested amount
+        amount: DenominatedAmount,
+    },
+    /// The account doesn't exist
+    #[error("The account {0} doesn't exist")]
+    UnknownAccount(Address),
+    /// The address is not a validator
+    #[error("The address {0} is not a validator")]
+    NotAValidator(Address),
+    /// The validator is inactive at the pipeline epoch
+    #[error(
+        "The validator {validator} is inactive at the pipeline epoch {epoch}"
+    )]
+    InactiveValidator {
+        /// The validator
+        validator: Address,
+        /// The pipeline epoch
+        epoch: Epoch,
+    },
+    /// The proposal doesn't exist
+    #[error("The proposal {0} doesn't exist")]
+    UnknownProposal(u64),
+    /// The vote can't be parsed
+    #[error("Invalid proposal vote {0}, expected yay, nay, abstain or veto")]
+    InvalidVote(String),
+    /// The proposal isn't in its voting period for the voter
+    #[error(
+        "The proposal {id} can't be voted by {voter} at epoch {epoch}, its \
+         voting period is from epoch {voting_start_epoch} to epoch \
+         {voting_end_epoch}"
+    )]
+    VotingPeriodClosed {
+        /// The id of the proposal
+        id: u64,
+        /// The voter
+        voter: Address,
+        /// The current epoch
+        epoch: Epoch,
+        /// The first epoch of the voting period
+        voting_start_epoch: Epoch,
+        /// The last epoch of the voting period
+        voting_end_epoch: Epoch,
+    },
+    /// The voter has no delegations to vote with
+    #[error(
+        "The voter {0} has no delegations at the start of the voting period"
+    )]
+    NoDelegations(Address),
+    /// The receiver of an IBC transfer is empty
+    #[error("The receiver of the IBC transfer must not be empty")]
+    EmptyReceiver,
+    /// The IBC channel doesn't exist
+    #[error("The IBC channel {channel} of the port {port} doesn't exist")]
+    UnknownChannel {
+        /// The port
+        port: String,
+        /// The channel
+        channel: String,
+    },
+}
+
 /// Errors to do with transaction events.
 #[derive(Error, Debug, Clone)]
 pub enum TxSubmitError {
@@ -174,6 +274,9 @@ pub enum TxSubmitError {
     /// The address is not a valid steward
     #[error("The address {0} is not a valid steward.")]
     InvalidSteward(Address),
+    /// The pgf grant doesn't have a milestone left to attest
+    #[error("The pgf grant {0} doesn't exist or is fully attested.")]
+    InvalidPgfGrant(u64),
     /// Rate of epoch change too large for current epoch
     #[error(
         "New rate, {0}, is too large of a change with respect to the \
@@ -261,6 +364,9 @@ pub enum TxSubmitError {
     /// The proposal vote is not valid
     #[error("Proposal vote is invalid")]
     InvalidProposalVote,
+    /// The signed ballot is not valid
+    #[error("Signed ballot is invalid: {0}")]
+    InvalidBallot(String),
     /// The proposal can't be voted
     #[error("Proposal {0} can't be voted")]
     InvalidProposalVotingPeriod(u64),
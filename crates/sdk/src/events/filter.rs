@@ -0,0 +1,208 @@
+//! Filters of the events pushed to the clients subscribed to the events of
+//! the ledger.
+//!
+//! A client subscribes with one or more [`EventFilter`]s, serialized as JSON,
+//! and is pushed every event of a committed block that matches any of them,
+//! as a JSON [`PushedEvent`].
+
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use namada_core::types::address::Address;
+use namada_token::storage_key::is_balance_key;
+use namada_tx::data::TxResult;
+use serde::{Deserialize, Serialize};
+
+use crate::error::EventError;
+use crate::events::{Event, EventLevel, EventType};
+
+/// A filter of the events a client subscribes to. An event matches the
+/// filter if it matches all of its criteria that are set, so the default
+/// filter matches all the events.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EventFilter {
+    /// The type of the events, e.g. `applied`
+    pub event_type: Option<String>,
+    /// The hash of the tx that emitted the events
+    pub tx_hash: Option<String>,
+    /// An address among the verifiers of the applied tx
+    pub verifier: Option<Address>,
+    /// A token of which the applied tx changed a balance
+    pub token: Option<Address>,
+}
+
+impl EventFilter {
+    /// Check if the event matches this filter. The verifier and the token
+    /// criteria are only matched by the events of the applied txs, which
+    /// carry the result of the tx.
+    pub fn matches(&self, event: &Event) -> bool {
+        if let Some(event_type) = &self.event_type {
+            if event.event_type.to_string() != *event_type {
+                return false;
+            }
+        }
+        if let Some(tx_hash) = &self.tx_hash {
+            match event.get("hash") {
+                Some(hash) if hash.eq_ignore_ascii_case(tx_hash) => {}
+                _ => return false,
+            }
+        }
+        if self.verifier.is_none() && self.token.is_none() {
+            return true;
+        }
+        let Some(result) = event
+            .get("inner_tx")
+            .and_then(|result| TxResult::from_str(result).ok())
+        else {
+            return false;
+        };
+        if let Some(verifier) = &self.verifier {
+            if !result.vps_result.accepted_vps.contains(verifier)
+                && !result.vps_result.rejected_vps.contains(verifier)
+            {
+                return false;
+            }
+        }
+        if let Some(token) = &self.token {
+            if !result
+                .changed_keys
+                .iter()
+                .any(|key| is_balance_key(token, key).is_some())
+            {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// An event pushed to the subscribed clients
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PushedEvent {
+    /// The type of the event
+    pub event_type: String,
+    /// Whether the event relates to a block or an individual tx
+    pub block_level: bool,
+    /// Key-value attributes of the event
+    pub attributes: HashMap<String, String>,
+}
+
+impl From<&Event> for PushedEvent {
+    fn from(event: &Event) -> Self {
+        Self {
+            event_type: event.event_type.to_string(),
+            block_level: event.level == EventLevel::Block,
+            attributes: event.attributes.clone(),
+        }
+    }
+}
+
+impl TryFrom<PushedEvent> for Event {
+    type Error = EventError;
+
+    fn try_from(event: PushedEvent) -> Result<Self, Self::Error> {
+        Ok(Self {
+            event_type: EventType::from_str(&event.event_type)?,
+            level: if event.block_level {
+                EventLevel::Block
+            } else {
+                EventLevel::Tx
+            },
+            attributes: event.attributes,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use namada_core::types::address::nam;
+    use namada_core::types::address::testing::{
+        established_address_1, established_address_2,
+    };
+    use namada_token::storage_key::balance_key;
+    use namada_tx::data::VpsResult;
+
+    use super::*;
+
+    const HASH: &str =
+        "DEADBEEFDEADBEEFDEADBEEFDEADBEEFDEADBEEFDEADBEEFDEADBEEFDEADBEEF";
+
+    /// Test the matching of the events by every criterion of the filters
+    #[test]
+    fn test_event_filter_matching() {
+        let owner = established_address_1();
+        let result = TxResult {
+            changed_keys: BTreeSet::from([balance_key(&nam(), &owner)]),
+            vps_result: VpsResult {
+                accepted_vps: BTreeSet::from([owner.clone()]),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut applied = Event {
+            event_type: EventType::Applied,
+            level: EventLevel::Tx,
+            attributes: HashMap::new(),
+        };
+        applied["hash"] = HASH.to_string();
+        applied["inner_tx"] = result.to_string();
+        let mut accepted = applied.clone();
+        accepted.event_type = EventType::Accepted;
+        accepted.attributes.remove("inner_tx");
+
+        let filter = EventFilter::default();
+        assert!(filter.matches(&applied));
+        assert!(filter.matches(&accepted));
+
+        let filter = EventFilter {
+            event_type: Some("applied".to_string()),
+            tx_hash: Some(HASH.to_lowercase()),
+            ..Default::default()
+        };
+        assert!(filter.matches(&applied));
+        assert!(!filter.matches(&accepted));
+
+        let filter = EventFilter {
+            verifier: Some(owner.clone()),
+            token: Some(nam()),
+            ..Default::default()
+        };
+        assert!(filter.matches(&applied));
+        assert!(!filter.matches(&accepted));
+
+        let filter = EventFilter {
+            verifier: Some(established_address_2()),
+            ..Default::default()
+        };
+        assert!(!filter.matches(&applied));
+
+        let filter = EventFilter {
+            token: Some(owner),
+            ..Default::default()
+        };
+        assert!(!filter.matches(&applied));
+
+        // The pushed events are decoded back to the same events
+        let pushed = PushedEvent::from(&applied);
+        assert_eq!(Event::try_from(pushed).unwrap(), applied);
+    }
+
+    /// Test that the filters can be decoded from JSON with the missing
+    /// criteria left unset
+    #[test]
+    fn test_event_filter_json() {
+        let filter: EventFilter =
+            serde_json::from_str(&format!(r#"{{"tx_hash":"{HASH}"}}"#))
+                .unwrap();
+        assert_eq!(
+            filter,
+            EventFilter {
+                tx_hash: Some(HASH.to_string()),
+                ..Default::default()
+            }
+        );
+    }
+}
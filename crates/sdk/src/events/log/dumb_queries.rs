@@ -61,6 +61,17 @@ impl QueryMatcher {
         }
     }
 
+    /// Returns a query matching the lifecycle events of the given governance
+    /// proposal.
+    pub fn proposal_lifecycle(proposal_id: u64) -> Self {
+        let mut attributes = HashMap::new();
+        attributes.insert("proposal_id".to_string(), proposal_id.to_string());
+        Self {
+            event_type: EventType::ProposalLifecycle,
+            attributes,
+        }
+    }
+
     /// Returns a query matching the given IBC UpdateClient parameters
     pub fn ibc_update_client(
         client_id: ClientId,
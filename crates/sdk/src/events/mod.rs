@@ -1,4 +1,5 @@
 //! Logic to do with events emitted by the ledger.
+pub mod filter;
 pub mod log;
 
 use std::collections::HashMap;
@@ -10,7 +11,7 @@ use std::str::FromStr;
 use borsh::{BorshDeserialize, BorshSerialize};
 use namada_core::types::ethereum_structs::{BpTransferStatus, EthBridgeEvent};
 use namada_core::types::ibc::IbcEvent;
-use namada_tx::data::TxType;
+use namada_tx::data::{TxEvent, TxType};
 use serde_json::Value;
 
 // use crate::ledger::governance::utils::ProposalEvent;
@@ -84,8 +85,12 @@ pub enum EventType {
     Proposal,
     /// The pgf payment
     PgfPayment,
+    /// A stage of the lifecycle of a governance proposal
+    ProposalLifecycle,
     /// Ethereum Bridge event
     EthereumBridge,
+    /// An application-level event emitted by a wasm transaction
+    Wasm,
 }
 
 impl Display for EventType {
@@ -96,7 +101,9 @@ impl Display for EventType {
             EventType::Ibc(t) => write!(f, "{}", t),
             EventType::Proposal => write!(f, "proposal"),
             EventType::PgfPayment => write!(f, "pgf_payment"),
+            EventType::ProposalLifecycle => write!(f, "proposal_lifecycle"),
             EventType::EthereumBridge => write!(f, "ethereum_bridge"),
+            EventType::Wasm => write!(f, "wasm"),
         }?;
         Ok(())
     }
@@ -111,6 +118,7 @@ impl FromStr for EventType {
             "applied" => Ok(EventType::Applied),
             "proposal" => Ok(EventType::Proposal),
             "pgf_payments" => Ok(EventType::PgfPayment),
+            "proposal_lifecycle" => Ok(EventType::ProposalLifecycle),
             // IBC
             "update_client" => Ok(EventType::Ibc("update_client".to_string())),
             "send_packet" => Ok(EventType::Ibc("send_packet".to_string())),
@@ -118,6 +126,7 @@ impl FromStr for EventType {
                 Ok(EventType::Ibc("write_acknowledgement".to_string()))
             }
             "ethereum_bridge" => Ok(EventType::EthereumBridge),
+            "wasm" => Ok(EventType::Wasm),
             _ => Err(EventError::InvalidEventType),
         }
     }
@@ -205,6 +214,19 @@ impl From<IbcEvent> for Event {
     }
 }
 
+impl From<&TxEvent> for Event {
+    fn from(tx_event: &TxEvent) -> Self {
+        let mut attributes: HashMap<String, String> =
+            tx_event.attributes.clone().into_iter().collect();
+        attributes.insert("kind".to_string(), tx_event.event_type.clone());
+        Self {
+            event_type: EventType::Wasm,
+            level: EventLevel::Tx,
+            attributes,
+        }
+    }
+}
+
 /// Convert our custom event into the necessary tendermint proto type
 impl From<Event> for crate::tendermint_proto::v0_37::abci::Event {
     fn from(event: Event) -> Self {
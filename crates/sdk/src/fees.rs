@@ -0,0 +1,177 @@
+//! Suggestion of the gas price of a tx from the fees paid by the wrapper txs
+//! included in the recent blocks.
+//!
+//! The blocks whose gas is mostly unused don't make the txs compete for their
+//! inclusion, so the minimum gas price of the fee token is suggested while the
+//! recent blocks are under-utilized. Otherwise, the gas prices paid in the fee
+//! token by the recent wrapper txs are ranked and a percentile of them is
+//! suggested, which is higher the fewer blocks the tx is meant to be included
+//! within.
+
+use namada_core::types::address::Address;
+use namada_core::types::token::{Amount, DenominatedAmount};
+use namada_tx::data::TxType;
+use namada_tx::Tx;
+
+use crate::error::{Error, QueryError};
+use crate::queries::Client;
+use crate::{rpc, Namada};
+
+/// The number of recent blocks whose fees are inspected
+pub const FEE_HISTORY_BLOCKS: u64 = 20;
+
+/// The percentage of the gas of the recent blocks that must be used for the
+/// txs to compete for their inclusion
+pub const CONGESTION_THRESHOLD: u64 = 50;
+
+/// The percentile of the recent gas prices suggested for an inclusion in the
+/// next block. It's divided by the number of target blocks for longer
+/// targets.
+pub const NEXT_BLOCK_PERCENTILE: u64 = 90;
+
+/// The fees paid in a token by the wrapper txs of a block
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BlockFees {
+    /// The gas prices of the wrapper txs paying their fees in the token
+    pub gas_prices: Vec<Amount>,
+    /// The sum of the gas limits of all the wrapper txs of the block
+    pub gas_used: u64,
+}
+
+/// Collect the fees paid in the given token by the wrapper txs of a block
+pub fn block_fees<'a>(
+    txs: impl IntoIterator<Item = &'a [u8]>,
+    token: &Address,
+) -> BlockFees {
+    let mut fees = BlockFees::default();
+    for tx in txs {
+        let Ok(tx) = Tx::try_from(tx) else { continue };
+        if let TxType::Wrapper(wrapper) = tx.header().tx_type {
+            fees.gas_used =
+                fees.gas_used.saturating_add(u64::from(wrapper.gas_limit));
+            if &wrapper.fee.token == token {
+                fees.gas_prices
+                    .push(wrapper.fee.amount_per_gas_unit.amount());
+            }
+        }
+    }
+    fees
+}
+
+/// Suggest the gas price for a tx to be included within the target number of
+/// blocks from the fees of the recent blocks, the maximum gas of a block and
+/// the minimum gas price of the fee token
+pub fn suggest_gas_price(
+    blocks: &[BlockFees],
+    max_block_gas: u64,
+    minimum_gas_price: Amount,
+    target_blocks: u64,
+) -> Amount {
+    let available_gas = max_block_gas.saturating_mul(blocks.len() as u64);
+    let gas_used = blocks
+        .iter()
+        .fold(0u64, |acc, block| acc.saturating_add(block.gas_used));
+    // Nothing to compete for while the blocks have gas to spare
+    if available_gas == 0
+        || u128::from(gas_used) * 100
+            < u128::from(available_gas) * u128::from(CONGESTION_THRESHOLD)
+    {
+        return minimum_gas_price;
+    }
+    let mut gas_prices: Vec<Amount> = blocks
+        .iter()
+        .flat_map(|block| block.gas_prices.iter().copied())
+        .collect();
+    if gas_prices.is_empty() {
+        return minimum_gas_price;
+    }
+    gas_prices.sort_unstable();
+    let percentile = NEXT_BLOCK_PERCENTILE / target_blocks.max(1);
+    let rank = (gas_prices.len() - 1) * percentile as usize / 100;
+    gas_prices[rank].max(minimum_gas_price)
+}
+
+/// Suggest the `amount_per_gas_unit` of the fees paid in the given token for a
+/// tx to be included within the target number of blocks, from the fees paid
+/// by the wrapper txs of the last [`FEE_HISTORY_BLOCKS`] blocks
+pub async fn suggest_fee(
+    context: &impl Namada,
+    token: &Address,
+    target_blocks: u64,
+) -> Result<DenominatedAmount, Error> {
+    let chain_info = rpc::query_chain_info(context.client()).await?;
+    let minimum_gas_price = chain_info
+        .minimum_gas_price
+        .get(token)
+        .copied()
+        .ok_or_else(|| {
+            Error::Other(format!(
+                "The token {token} can't be used to pay the fees"
+            ))
+        })?;
+    let last_height = rpc::query_block(context.client())
+        .await?
+        .map(|block| block.height.0)
+        .unwrap_or_default();
+    let first_height =
+        last_height.saturating_sub(FEE_HISTORY_BLOCKS - 1).max(1);
+    let mut blocks = vec![];
+    for height in first_height..=last_height {
+        let block = context
+            .client()
+            .block(height as u32)
+            .await
+            .map_err(|err| Error::from(QueryError::General(err.to_string())))?
+            .block;
+        blocks
+            .push(block_fees(block.data.iter().map(|tx| tx.as_slice()), token));
+    }
+    let gas_price = suggest_gas_price(
+        &blocks,
+        chain_info.max_block_gas,
+        minimum_gas_price,
+        target_blocks,
+    );
+    Ok(context.denominate_amount(token, gas_price).await)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn block(gas_prices: &[u64], gas_used: u64) -> BlockFees {
+        BlockFees {
+            gas_prices: gas_prices
+                .iter()
+                .copied()
+                .map(Amount::from_u64)
+                .collect(),
+            gas_used,
+        }
+    }
+
+    #[test]
+    fn test_suggest_gas_price() {
+        let minimum = Amount::from_u64(2);
+
+        // The minimum is suggested while the blocks are under-utilized
+        let quiet = [block(&[10, 20], 100), block(&[30], 300)];
+        assert_eq!(suggest_gas_price(&quiet, 1_000, minimum, 1), minimum);
+        assert_eq!(suggest_gas_price(&[], 1_000, minimum, 1), minimum);
+
+        // The suggested price is lower the more blocks the tx can wait for
+        let busy = [
+            block(&[1, 3, 5, 7, 9], 900),
+            block(&[11, 13, 15, 17, 19], 800),
+        ];
+        assert_eq!(
+            suggest_gas_price(&busy, 1_000, minimum, 1),
+            Amount::from_u64(17)
+        );
+        assert_eq!(
+            suggest_gas_price(&busy, 1_000, minimum, 2),
+            Amount::from_u64(9)
+        );
+        assert_eq!(suggest_gas_price(&busy, 1_000, minimum, 10), minimum);
+    }
+}
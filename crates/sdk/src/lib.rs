@@ -9,15 +9,22 @@ pub use {
     namada_state as state, namada_storage as storage, zeroize,
 };
 
+pub mod address_book;
 pub mod eth_bridge;
 
 pub mod rpc;
 
 pub mod args;
+pub mod builders;
+#[cfg(feature = "http-client")]
+pub mod client;
+pub mod fees;
 pub mod masp;
+pub mod psnt;
 pub mod signing;
 #[allow(clippy::result_large_err)]
 pub mod tx;
+pub mod tx_tracking;
 
 pub mod control_flow;
 pub mod error;
@@ -36,34 +43,40 @@ use std::path::PathBuf;
 use std::str::FromStr;
 
 use args::{InputAmount, SdkTypes};
-use namada_core::ibc::core::host::types::identifiers::{ChannelId, PortId};
+use namada_core::ibc::core::host::types::identifiers::{
+    ChannelId, ClientId, PortId,
+};
 use namada_core::types::address::Address;
 use namada_core::types::dec::Dec;
 use namada_core::types::ethereum_events::EthAddress;
 use namada_core::types::key::*;
-use namada_core::types::masp::{TransferSource, TransferTarget};
+use namada_core::types::masp::{
+    PaymentAddress, TransferSource, TransferTarget,
+};
 use namada_core::types::token;
 use namada_tx::data::wrapper::GasLimit;
 use namada_tx::Tx;
 use tokio::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
 
+use crate::address_book::{AddressBook, AddressBookError};
 use crate::io::Io;
-use crate::masp::{ShieldedContext, ShieldedUtils};
+use crate::masp::{NoteSelectionStrategy, ShieldedContext, ShieldedUtils};
 use crate::rpc::{
     denominate_amount, format_denominated_amount, query_native_token,
 };
 use crate::signing::SigningTxData;
 use crate::token::{DenominatedAmount, NATIVE_MAX_DECIMAL_PLACES};
 use crate::tx::{
-    ProcessTxResponse, TX_BECOME_VALIDATOR_WASM, TX_BOND_WASM,
-    TX_BRIDGE_POOL_WASM, TX_CHANGE_COMMISSION_WASM,
+    ProcessTxResponse, TX_ATTEST_PGF_MILESTONE, TX_BECOME_VALIDATOR_WASM,
+    TX_BOND_WASM, TX_BRIDGE_POOL_WASM, TX_CHANGE_COMMISSION_WASM,
     TX_CHANGE_CONSENSUS_KEY_WASM, TX_CHANGE_METADATA_WASM,
-    TX_CLAIM_REWARDS_WASM, TX_DEACTIVATE_VALIDATOR_WASM, TX_IBC_WASM,
-    TX_INIT_ACCOUNT_WASM, TX_INIT_PROPOSAL, TX_REACTIVATE_VALIDATOR_WASM,
-    TX_REDELEGATE_WASM, TX_RESIGN_STEWARD, TX_REVEAL_PK, TX_TRANSFER_WASM,
+    TX_CLAIM_REWARDS_WASM, TX_DEACTIVATE_VALIDATOR_WASM, TX_DELEGATE_VOTE,
+    TX_IBC_MISBEHAVIOUR_WASM, TX_IBC_WASM, TX_INIT_ACCOUNT_WASM,
+    TX_INIT_PROPOSAL, TX_REACTIVATE_VALIDATOR_WASM, TX_REDELEGATE_WASM,
+    TX_RESIGN_STEWARD, TX_REVEAL_PK, TX_SHIELDING_SWEEP_WASM, TX_TRANSFER_WASM,
     TX_UNBOND_WASM, TX_UNJAIL_VALIDATOR_WASM, TX_UPDATE_ACCOUNT_WASM,
-    TX_UPDATE_STEWARD_COMMISSION, TX_VOTE_PROPOSAL, TX_WITHDRAW_WASM,
-    VP_USER_WASM,
+    TX_UPDATE_STEWARD_COMMISSION, TX_VOTE_PROPOSAL, TX_VOTE_PROPOSAL_BATCH,
+    TX_WITHDRAW_VOTE, TX_WITHDRAW_WASM, VP_USER_WASM,
 };
 use crate::wallet::{Wallet, WalletIo, WalletStorage};
 
@@ -115,6 +128,12 @@ pub trait Namada: Sized + MaybeSync + MaybeSend {
         &self,
     ) -> RwLockWriteGuard<ShieldedContext<Self::ShieldedUtils>>;
 
+    /// Obtain read guard on the address book
+    async fn address_book(&self) -> RwLockReadGuard<AddressBook>;
+
+    /// Obtain write guard on the address book
+    async fn address_book_mut(&self) -> RwLockWriteGuard<AddressBook>;
+
     /// Return the native token
     fn native_token(&self) -> Address;
 
@@ -163,11 +182,28 @@ pub trait Namada: Sized + MaybeSync + MaybeSend {
             target,
             token,
             amount,
+            note_selection: NoteSelectionStrategy::default(),
             tx_code_path: PathBuf::from(TX_TRANSFER_WASM),
             tx: self.tx_builder(),
         }
     }
 
+    /// Make a TxShieldingSweep builder from the given minimum set of arguments
+    fn new_shielding_sweep(
+        &self,
+        source: Address,
+        target: PaymentAddress,
+        tokens: Vec<Address>,
+    ) -> args::TxShieldingSweep {
+        args::TxShieldingSweep {
+            source,
+            target: TransferTarget::PaymentAddress(target),
+            tokens,
+            tx_code_path: PathBuf::from(TX_SHIELDING_SWEEP_WASM),
+            tx: self.tx_builder(),
+        }
+    }
+
     /// Make a InitAccount builder from the given minimum set of arguments
     fn new_init_account(
         &self,
@@ -268,6 +304,23 @@ pub trait Namada: Sized + MaybeSync + MaybeSend {
         }
     }
 
+    /// Make a TxIbcMisbehaviour builder from the given minimum set of
+    /// arguments
+    fn new_ibc_misbehaviour(
+        &self,
+        submitter: Address,
+        client_id: ClientId,
+        misbehaviour: Vec<u8>,
+    ) -> args::TxIbcMisbehaviour {
+        args::TxIbcMisbehaviour {
+            submitter,
+            client_id,
+            misbehaviour,
+            tx: self.tx_builder(),
+            tx_code_path: PathBuf::from(TX_IBC_MISBEHAVIOUR_WASM),
+        }
+    }
+
     /// Make a InitProposal builder from the given minimum set of arguments
     fn new_init_proposal(&self, proposal_data: Vec<u8>) -> args::InitProposal {
         args::InitProposal {
@@ -275,6 +328,11 @@ pub trait Namada: Sized + MaybeSync + MaybeSend {
             is_offline: false,
             is_pgf_stewards: false,
             is_pgf_funding: false,
+            is_masp_rewards: false,
+            is_parameter_change: false,
+            is_emergency_halt: false,
+            is_wasm_allowlist: false,
+            is_signaling: false,
             tx_code_path: PathBuf::from(TX_INIT_PROPOSAL),
             tx: self.tx_builder(),
         }
@@ -287,6 +345,8 @@ pub trait Namada: Sized + MaybeSync + MaybeSend {
             vp_code_path: None,
             public_keys: vec![],
             threshold: None,
+            vp_update_timelock: None,
+            cancel_pending_vp_update: false,
             tx_code_path: PathBuf::from(TX_UPDATE_ACCOUNT_WASM),
             tx: self.tx_builder(),
         }
@@ -303,12 +363,54 @@ pub trait Namada: Sized + MaybeSync + MaybeSend {
             voter,
             proposal_id: None,
             is_offline: false,
+            is_ballot: false,
             proposal_data: None,
             tx_code_path: PathBuf::from(TX_VOTE_PROPOSAL),
             tx: self.tx_builder(),
         }
     }
 
+    /// Make a VoteProposalBatch builder from the given signed ballots
+    fn new_vote_proposal_batch(
+        &self,
+        ballots: Vec<Vec<u8>>,
+    ) -> args::VoteProposalBatch {
+        args::VoteProposalBatch {
+            ballots,
+            tx_code_path: PathBuf::from(TX_VOTE_PROPOSAL_BATCH),
+            tx: self.tx_builder(),
+        }
+    }
+
+    /// Make a VoteWithdrawal builder from the given minimum set of arguments
+    fn new_vote_withdrawal(
+        &self,
+        proposal_id: u64,
+        voter: Address,
+    ) -> args::VoteWithdrawal {
+        args::VoteWithdrawal {
+            proposal_id,
+            voter,
+            tx_code_path: PathBuf::from(TX_WITHDRAW_VOTE),
+            tx: self.tx_builder(),
+        }
+    }
+
+    /// Make a VoteDelegation builder from the given minimum set of arguments
+    fn new_vote_delegation(
+        &self,
+        delegator: Address,
+        delegate: Option<Address>,
+    ) -> args::VoteDelegation {
+        args::VoteDelegation {
+            delegator,
+            delegate,
+            kinds: vec![],
+            tx_code_path: PathBuf::from(TX_DELEGATE_VOTE),
+            tx: self.tx_builder(),
+        }
+    }
+
     /// Make a CommissionRateChange builder from the given minimum set of
     /// arguments
     fn new_change_commission_rate(
@@ -348,6 +450,7 @@ pub trait Namada: Sized + MaybeSync + MaybeSend {
             website: None,
             discord_handle: None,
             avatar: None,
+            name: None,
             commission_rate: None,
             tx_code_path: PathBuf::from(TX_CHANGE_METADATA_WASM),
             tx: self.tx_builder(),
@@ -508,6 +611,21 @@ pub trait Namada: Sized + MaybeSync + MaybeSend {
         }
     }
 
+    /// Make a AttestPgfMilestone builder from the given minimum set of
+    /// arguments
+    fn new_attest_pgf_milestone(
+        &self,
+        steward: Address,
+        grant_id: u64,
+    ) -> args::AttestPgfMilestone {
+        args::AttestPgfMilestone {
+            steward,
+            grant_id,
+            tx: self.tx_builder(),
+            tx_code_path: PathBuf::from(TX_ATTEST_PGF_MILESTONE),
+        }
+    }
+
     /// Make a UpdateStewardCommission builder from the given minimum set of
     /// arguments
     fn new_update_steward_rewards(
@@ -590,9 +708,30 @@ pub trait Namada: Sized + MaybeSync + MaybeSend {
     ) -> String {
         format_denominated_amount(self.client(), self.io(), token, amount).await
     }
+
+    /// Render an address with its wallet alias or on-chain validator name, or
+    /// in its encoded form if it has none
+    async fn lookup_address(&self, address: &Address) -> String {
+        let wallet = self.wallet().await;
+        self.address_book().await.lookup(&wallet, address)
+    }
+
+    /// Find the address with the given wallet alias or on-chain validator
+    /// name. The name can also be an encoded address.
+    async fn find_address(
+        &self,
+        name: &str,
+    ) -> Result<Address, AddressBookError> {
+        let wallet = self.wallet().await;
+        self.address_book().await.find_address(&wallet, name)
+    }
 }
 
-/// Provides convenience methods for common Namada interactions
+/// Provides convenience methods for common Namada interactions. The wallet,
+/// the shielded context and the address book are behind their own locks, so
+/// that with the `async-send` feature, a context shared in an `Arc` can serve
+/// concurrent queries and tx builds from the tasks of a multi-threaded
+/// runtime.
 pub struct NamadaImpl<C, U, V, I>
 where
     C: queries::Client,
@@ -606,6 +745,8 @@ where
     pub wallet: RwLock<Wallet<U>>,
     /// Stores the current state of the shielded pool
     pub shielded: RwLock<ShieldedContext<V>>,
+    /// Caches the names of the addresses known from the chain
+    pub address_book: RwLock<AddressBook>,
     /// Captures the input/output streams used by this object
     pub io: I,
     /// The address of the native token
@@ -629,16 +770,19 @@ where
         io: I,
         native_token: Address,
     ) -> Self {
+        // A view-only wallet can't sign, so its txs are dumped by default
+        let dump_tx = wallet.is_view_only();
         NamadaImpl {
             client,
             wallet: RwLock::new(wallet),
             shielded: RwLock::new(shielded),
+            address_book: RwLock::new(AddressBook::default()),
             io,
             native_token: native_token.clone(),
             prototype: args::Tx {
                 dry_run: false,
                 dry_run_wrapper: false,
-                dump_tx: false,
+                dump_tx,
                 output_folder: None,
                 force: false,
                 broadcast_only: false,
@@ -738,6 +882,14 @@ where
     fn wallet_lock(&self) -> &RwLock<Wallet<Self::WalletUtils>> {
         &self.wallet
     }
+
+    async fn address_book(&self) -> RwLockReadGuard<AddressBook> {
+        self.address_book.read().await
+    }
+
+    async fn address_book_mut(&self) -> RwLockWriteGuard<AddressBook> {
+        self.address_book.write().await
+    }
 }
 
 /// Allow the prototypical Tx builder to be modified
@@ -779,11 +931,15 @@ pub mod testing {
     };
     use namada_core::types::token::Transfer;
     use namada_governance::storage::proposal::testing::{
-        arb_init_proposal, arb_vote_proposal,
+        arb_init_proposal, arb_vote_delegation, arb_vote_proposal,
+        arb_vote_proposal_batch, arb_vote_withdrawal,
+    };
+    use namada_governance::{
+        InitProposalData, VoteDelegationData, VoteProposalBatchData,
+        VoteProposalData, VoteWithdrawalData,
     };
-    use namada_governance::{InitProposalData, VoteProposalData};
     use namada_ibc::testing::arb_ibc_any;
-    use namada_tx::data::pgf::UpdateStewardCommission;
+    use namada_tx::data::pgf::{MilestoneAttestation, UpdateStewardCommission};
     use namada_tx::data::pos::{
         BecomeValidator, Bond, CommissionChange, ConsensusKeyChange,
         MetaDataChange, Redelegation, Unbond, Withdraw,
@@ -800,7 +956,9 @@ pub mod testing {
     use crate::masp::testing::{
         arb_deshielding_transfer, arb_shielded_transfer, arb_shielding_transfer,
     };
-    use crate::tx::data::pgf::tests::arb_update_steward_commission;
+    use crate::tx::data::pgf::tests::{
+        arb_milestone_attestation, arb_update_steward_commission,
+    };
     use crate::tx::data::pos::tests::{
         arb_become_validator, arb_bond, arb_commission_change,
         arb_consensus_key_change, arb_metadata_change, arb_redelegation,
@@ -830,12 +988,16 @@ pub mod testing {
         UnjailValidator(Address),
         UpdateAccount(UpdateAccount),
         VoteProposal(VoteProposalData),
+        VoteWithdrawal(VoteWithdrawalData),
+        VoteDelegation(VoteDelegationData),
+        VoteProposalBatch(VoteProposalBatchData),
         Withdraw(Withdraw),
         Transfer(Transfer),
         Bond(Bond),
         Redelegation(Redelegation),
         UpdateStewardCommission(UpdateStewardCommission),
         ResignSteward(Address),
+        AttestPgfMilestone(MilestoneAttestation),
         PendingTransfer(PendingTransfer),
         IbcAny(Any),
         Custom(Box<dyn std::fmt::Debug>),
@@ -1143,7 +1305,7 @@ pub mod testing {
             let mut tx = Tx { header, sections: vec![] };
             let content_hash = tx.add_section(Section::ExtraData(content_extra_data)).get_hash();
             init_proposal.content = content_hash;
-            if let ProposalType::Default(Some(hash)) = &mut init_proposal.r#type {
+            if let ProposalType::WasmCodeUpgrade(hash) = &mut init_proposal.r#type {
                 let type_hash = tx.add_section(Section::ExtraData(type_extra_data)).get_hash();
                 *hash = type_hash;
             }
@@ -1169,6 +1331,57 @@ pub mod testing {
         }
     }
 
+    prop_compose! {
+        // Generate an arbitrary vote withdrawal transaction
+        pub fn arb_vote_withdrawal_tx()(
+            mut header in arb_header(),
+            wrapper in arb_wrapper_tx(),
+            vote_withdrawal in arb_vote_withdrawal(),
+            code_hash in arb_hash(),
+        ) -> (Tx, TxData) {
+            header.tx_type = TxType::Wrapper(Box::new(wrapper));
+            let mut tx = Tx { header, sections: vec![] };
+            tx.add_data(vote_withdrawal.clone());
+            tx.add_code_from_hash(code_hash, Some(TX_WITHDRAW_VOTE.to_owned()));
+            (tx, TxData::VoteWithdrawal(vote_withdrawal))
+        }
+    }
+
+    prop_compose! {
+        // Generate an arbitrary vote delegation transaction
+        pub fn arb_vote_delegation_tx()(
+            mut header in arb_header(),
+            wrapper in arb_wrapper_tx(),
+            vote_delegation in arb_vote_delegation(),
+            code_hash in arb_hash(),
+        ) -> (Tx, TxData) {
+            header.tx_type = TxType::Wrapper(Box::new(wrapper));
+            let mut tx = Tx { header, sections: vec![] };
+            tx.add_data(vote_delegation.clone());
+            tx.add_code_from_hash(code_hash, Some(TX_DELEGATE_VOTE.to_owned()));
+            (tx, TxData::VoteDelegation(vote_delegation))
+        }
+    }
+
+    prop_compose! {
+        // Generate an arbitrary batch of signed votes transaction
+        pub fn arb_vote_proposal_batch_tx()(
+            mut header in arb_header(),
+            wrapper in arb_wrapper_tx(),
+            vote_proposal_batch in arb_vote_proposal_batch(),
+            code_hash in arb_hash(),
+        ) -> (Tx, TxData) {
+            header.tx_type = TxType::Wrapper(Box::new(wrapper));
+            let mut tx = Tx { header, sections: vec![] };
+            tx.add_data(vote_proposal_batch.clone());
+            tx.add_code_from_hash(
+                code_hash,
+                Some(TX_VOTE_PROPOSAL_BATCH.to_owned()),
+            );
+            (tx, TxData::VoteProposalBatch(vote_proposal_batch))
+        }
+    }
+
     prop_compose! {
         // Generate an arbitrary reveal public key transaction
         pub fn arb_reveal_pk_tx()(
@@ -1382,6 +1595,22 @@ pub mod testing {
         }
     }
 
+    prop_compose! {
+        // Generate an arbitrary pgf milestone attestation transaction
+        pub fn arb_attest_pgf_milestone_tx()(
+            mut header in arb_header(),
+            wrapper in arb_wrapper_tx(),
+            attestation in arb_milestone_attestation(),
+            code_hash in arb_hash(),
+        ) -> (Tx, TxData) {
+            header.tx_type = TxType::Wrapper(Box::new(wrapper));
+            let mut tx = Tx { header, sections: vec![] };
+            tx.add_data(attestation.clone());
+            tx.add_code_from_hash(code_hash, Some(TX_ATTEST_PGF_MILESTONE.to_owned()));
+            (tx, TxData::AttestPgfMilestone(attestation))
+        }
+    }
+
     prop_compose! {
         // Generate an arbitrary pending transfer transaction
         pub fn arb_pending_transfer_tx()(
@@ -1427,6 +1656,9 @@ pub mod testing {
             arb_become_validator_tx(),
             arb_init_proposal_tx(),
             arb_vote_proposal_tx(),
+            arb_vote_withdrawal_tx(),
+            arb_vote_delegation_tx(),
+            arb_vote_proposal_batch_tx(),
             arb_reveal_pk_tx(),
             arb_update_account_tx(),
             arb_withdraw_tx(),
@@ -1440,6 +1672,7 @@ pub mod testing {
             arb_redelegation_tx(),
             arb_update_steward_commission_tx(),
             arb_resign_steward_tx(),
+            arb_attest_pgf_milestone_tx(),
             arb_pending_transfer_tx(),
             arb_ibc_any_tx(),
         ]
@@ -47,15 +47,15 @@ use masp_primitives::transaction::{
     TransparentAddress, Unauthorized,
 };
 use masp_primitives::zip32::{ExtendedFullViewingKey, ExtendedSpendingKey};
-use masp_proofs::bellman::groth16::PreparedVerifyingKey;
+use masp_proofs::bellman::groth16::{PreparedVerifyingKey, VerifyingKey};
 use masp_proofs::bls12_381::Bls12;
 use masp_proofs::prover::LocalTxProver;
-use masp_proofs::sapling::SaplingVerificationContext;
+use masp_proofs::sapling::{BatchValidator, SaplingVerificationContext};
 use namada_core::types::address::{Address, MASP};
 use namada_core::types::dec::Dec;
 use namada_core::types::masp::{
     encode_asset_type, AssetData, BalanceOwner, ExtendedViewingKey,
-    PaymentAddress, TransferSource, TransferTarget,
+    PaymentAddress, PaymentDisclosure, TransferSource, TransferTarget,
 };
 use namada_core::types::storage::{BlockHeight, Epoch, IndexedTx, TxIndex};
 use namada_core::types::time::{DateTimeUtc, DurationSecs};
@@ -99,6 +99,10 @@ pub const ENV_VAR_MASP_TEST_SEED: &str = "NAMADA_MASP_TEST_SEED";
 /// A directory to save serialized proofs for tests.
 pub const MASP_TEST_PROOFS_DIR: &str = "test_fixtures/masp_proofs";
 
+/// The number of blocks whose shielded transactions are fetched and scanned
+/// together before the shielded context is saved as a checkpoint
+pub const SHIELDED_SYNC_BATCH_SIZE: u64 = 1000;
+
 /// The network to use for MASP
 #[cfg(feature = "mainnet")]
 const NETWORK: MainNetwork = MainNetwork;
@@ -112,6 +116,53 @@ pub const SPEND_NAME: &str = "masp-spend.params";
 pub const OUTPUT_NAME: &str = "masp-output.params";
 /// Convert circuit name
 pub const CONVERT_NAME: &str = "masp-convert.params";
+/// The expected sizes in bytes of the MASP circuit parameters
+pub const MASP_PARAMS_SIZES: [(&str, u64); 3] = [
+    (SPEND_NAME, 49848572),
+    (OUTPUT_NAME, 16398620),
+    (CONVERT_NAME, 22570940),
+];
+/// The BLAKE2b-512 hashes of the MASP circuit parameters of the Namada
+/// trusted setup
+pub const MASP_PARAMS_HASHES: [(&str, &str); 3] = [
+    (
+        SPEND_NAME,
+        "196e7c717f25e16653431559ce2c8816e750a4490f98696e3c031efca37e25e0\
+         647182b7b013660806db11eb2b1e365fb2d6a0f24dbbd9a4a8314fef10a7cba2",
+    ),
+    (
+        OUTPUT_NAME,
+        "eafc3b1746cccc8b9eed2b69395692c5892f6aca83552a07dceb2dcbaa64dcd0\
+         e22434260b3aa3b049b633a08b008988cbe0d31effc77e2bc09bfab690a23724",
+    ),
+    (
+        CONVERT_NAME,
+        "dc4aaf3c3ce056ab448b6c4a7f43c1d68502c2902ea89ab8769b1524a2e8ace9\
+         a5369621a73ee1daa52aec826907a19974a37874391cf8f11bbe0b0420de1ab7",
+    ),
+];
+/// Spend circuit verifying key name
+pub const SPEND_VK_NAME: &str = "masp-spend.vk";
+/// Output circuit verifying key name
+pub const OUTPUT_VK_NAME: &str = "masp-output.vk";
+/// Convert circuit verifying key name
+pub const CONVERT_VK_NAME: &str = "masp-convert.vk";
+/// The SHA-256 hashes of the verifying keys of the MASP circuit parameters,
+/// as written by [`write_verifying_keys`]
+pub const MASP_VK_HASHES: [(&str, &str); 3] = [
+    (
+        SPEND_VK_NAME,
+        "2dba395c5951ba56176ed2937c3c6b5163b9a9bd4658070bf938ac6f99176a56",
+    ),
+    (
+        OUTPUT_VK_NAME,
+        "d9ed0ae9cb0616b0e06502f4ff2f51f69e6ebb617f6e4fba3b9f83df383d26f1",
+    ),
+    (
+        CONVERT_VK_NAME,
+        "771d423cafd7238a21bdbe8663e86bf138b53727a41bad88a66c01707ecc1058",
+    ),
+];
 
 /// Shielded transfer
 #[derive(Clone, Debug, BorshSerialize, BorshDeserialize)]
@@ -137,6 +188,20 @@ pub struct MaspTokenRewardData {
     pub locked_amount_target: Uint,
 }
 
+/// The current shielded rewards of a token
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct MaspTokenRewards {
+    /// The reward parameters of the token
+    pub reward_data: MaspTokenRewardData,
+    /// The inflation handed out for the token at the last epoch
+    pub last_inflation: token::Amount,
+    /// The amount of the token locked in the shielded pool at the last epoch
+    pub last_locked_amount: token::Amount,
+    /// The conversions accrued by the asset types of the token, from their
+    /// digit position and epoch to the current epoch
+    pub conversions: Vec<(MaspDigitPos, Epoch, I128Sum)>,
+}
+
 #[cfg(feature = "testing")]
 #[derive(Clone, Copy, Debug)]
 enum LoadOrSaveProofs {
@@ -145,6 +210,59 @@ enum LoadOrSaveProofs {
     Neither,
 }
 
+/// The maximum number of small notes spent first by the
+/// [`NoteSelectionStrategy::ConsolidateDust`] strategy. Bounding it keeps the
+/// number of spend proofs of a transaction reasonable.
+pub const MAX_DUST_NOTES: usize = 16;
+
+/// Strategy to select the unspent notes spent by a shielded transfer. The
+/// notes are taken in the order of the strategy until they cover the amount
+/// to transfer, so that the amount is split across as many notes as needed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NoteSelectionStrategy {
+    /// Spend the notes in the order they have been received
+    #[default]
+    Oldest,
+    /// Spend the smallest note covering the amount alone if any, otherwise
+    /// the largest notes first. This keeps the number of spent notes, thus
+    /// the size of the proofs, and the change low.
+    MinimizeChange,
+    /// Spend the smallest notes first, up to [`MAX_DUST_NOTES`], to merge them
+    /// into the change note
+    ConsolidateDust,
+    /// Spend the notes in a random order not to reveal anything about when
+    /// they have been received
+    PreferPrivacy,
+}
+
+impl FromStr for NoteSelectionStrategy {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "oldest" => Ok(Self::Oldest),
+            "minimize-change" => Ok(Self::MinimizeChange),
+            "consolidate-dust" => Ok(Self::ConsolidateDust),
+            "prefer-privacy" => Ok(Self::PreferPrivacy),
+            _ => Err(Error::Other(format!(
+                "Unknown note selection strategy {s}, expected one of oldest, \
+                 minimize-change, consolidate-dust or prefer-privacy"
+            ))),
+        }
+    }
+}
+
+impl std::fmt::Display for NoteSelectionStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Oldest => write!(f, "oldest"),
+            Self::MinimizeChange => write!(f, "minimize-change"),
+            Self::ConsolidateDust => write!(f, "consolidate-dust"),
+            Self::PreferPrivacy => write!(f, "prefer-privacy"),
+        }
+    }
+}
+
 /// A return type for gen_shielded_transfer
 #[derive(Error, Debug)]
 pub enum TransferErr {
@@ -156,52 +274,271 @@ pub enum TransferErr {
     General(#[from] Error),
 }
 
+/// The magic bytes prefixing an exported shielded context
+const SHIELDED_CONTEXT_MAGIC: &[u8; 8] = b"NAMSHCTX";
+
+/// The current version of the exported shielded context format
+pub const SHIELDED_CONTEXT_EXPORT_VERSION: u8 = 1;
+
+/// Errors that can occur when importing an exported shielded context
+#[derive(Error, Debug)]
+pub enum ShieldedContextImportError {
+    /// The data doesn't start with the expected magic bytes
+    #[error("The data is not an exported shielded context")]
+    InvalidMagic,
+    /// The format version isn't supported by this client
+    #[error(
+        "Unsupported shielded context format version {0}, expected version \
+         {SHIELDED_CONTEXT_EXPORT_VERSION}"
+    )]
+    UnsupportedVersion(u8),
+    /// The checksum doesn't match the exported data
+    #[error("The checksum of the exported shielded context does not match")]
+    ChecksumMismatch,
+    /// The shielded context couldn't be decoded
+    #[error("Failed to decode the exported shielded context: {0}")]
+    Decoding(std::io::Error),
+    /// The decoded shielded context isn't consistent
+    #[error("The exported shielded context is inconsistent: {0}")]
+    Inconsistent(String),
+}
+
+/// Errors that can occur when fetching or verifying the MASP parameters
+#[derive(Error, Debug)]
+pub enum MaspParamsError {
+    /// A parameter file is missing
+    #[error("The MASP parameter file {0} is missing")]
+    Missing(PathBuf),
+    /// The parameters couldn't be downloaded
+    #[error("Failed to download the MASP parameters: {0}")]
+    Download(String),
+    /// A parameter file doesn't have the expected size
+    #[error(
+        "The MASP parameter file {path} has {found} bytes, expected {expected}"
+    )]
+    InvalidSize {
+        /// The path of the parameter file
+        path: PathBuf,
+        /// The expected size in bytes
+        expected: u64,
+        /// The actual size in bytes
+        found: u64,
+    },
+    /// The parameters don't match the known hashes
+    #[error("The MASP parameters are corrupted: {0}")]
+    Corrupted(String),
+    /// The parameter files couldn't be accessed
+    #[error("Failed to access the MASP parameters: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// Verify that the MASP parameters in the given directory are present and
+/// match the known sizes and hashes of the MASP circuits
+pub fn verify_masp_params(
+    params_dir: &std::path::Path,
+) -> Result<(), MaspParamsError> {
+    for (name, expected) in MASP_PARAMS_SIZES {
+        let path = params_dir.join(name);
+        if !path.exists() {
+            return Err(MaspParamsError::Missing(path));
+        }
+        let found = std::fs::metadata(&path)?.len();
+        if found != expected {
+            return Err(MaspParamsError::InvalidSize {
+                path,
+                expected,
+                found,
+            });
+        }
+    }
+    // The files are only hashed, they're parsed when the verifying keys are
+    // loaded
+    for (name, expected) in MASP_PARAMS_HASHES {
+        let file = std::fs::File::open(params_dir.join(name))?;
+        let mut reader = masp_proofs::hashreader::HashReader::new(
+            std::io::BufReader::new(file),
+        );
+        std::io::copy(&mut reader, &mut std::io::sink())?;
+        if reader.into_hash() != expected {
+            return Err(MaspParamsError::Corrupted(format!(
+                "{name} doesn't match its known hash"
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Verify that the given encoded verifying key matches the known hash of the
+/// key with the given name
+fn verify_vk_hash(name: &str, vk: &[u8]) -> Result<(), MaspParamsError> {
+    let expected = MASP_VK_HASHES
+        .iter()
+        .find_map(|(vk_name, hash)| (*vk_name == name).then_some(*hash))
+        .ok_or_else(|| {
+            MaspParamsError::Corrupted(format!(
+                "{name} is not a known verifying key"
+            ))
+        })?;
+    if data_encoding::HEXLOWER.encode(&sha2::Sha256::digest(vk)) != expected {
+        return Err(MaspParamsError::Corrupted(format!(
+            "{name} doesn't match its known hash"
+        )));
+    }
+    Ok(())
+}
+
+/// Fetch the MASP parameters into the parameters directory if any of them is
+/// missing, then verify them. Return the parameters directory.
+pub fn fetch_masp_params() -> Result<PathBuf, MaspParamsError> {
+    let params_dir = get_params_dir();
+    #[cfg(feature = "download-params")]
+    if MASP_PARAMS_SIZES
+        .iter()
+        .any(|(name, _)| !params_dir.join(name).exists())
+    {
+        let paths = masp_proofs::download_masp_parameters(None)
+            .map_err(|e| MaspParamsError::Download(e.to_string()))?;
+        if paths.spend.parent() != Some(params_dir.as_path()) {
+            return Err(MaspParamsError::Download(format!(
+                "the parameters have been downloaded to an unfamiliar path {}",
+                paths.spend.to_string_lossy()
+            )));
+        }
+    }
+    verify_masp_params(&params_dir)?;
+    Ok(params_dir)
+}
+
+/// Write the verifying keys of the verified MASP parameters in `params_dir`
+/// into `vks_dir`, from which they can be embedded into the binary with the
+/// `embedded-verifying-keys` feature
+pub fn write_verifying_keys(
+    params_dir: &std::path::Path,
+    vks_dir: &std::path::Path,
+) -> Result<(), MaspParamsError> {
+    verify_masp_params(params_dir)?;
+    let params = masp_proofs::load_parameters(
+        &params_dir.join(SPEND_NAME),
+        &params_dir.join(OUTPUT_NAME),
+        &params_dir.join(CONVERT_NAME),
+    );
+    for (name, vk) in [
+        (SPEND_VK_NAME, &params.spend_params.vk),
+        (CONVERT_VK_NAME, &params.convert_params.vk),
+        (OUTPUT_VK_NAME, &params.output_params.vk),
+    ] {
+        let mut bytes = vec![];
+        vk.write(&mut bytes)?;
+        verify_vk_hash(name, &bytes)?;
+        std::fs::write(vks_dir.join(name), bytes)?;
+    }
+    Ok(())
+}
+
+/// The verifying keys embedded into the binary, generated with
+/// [`write_verifying_keys`] into the directory given by the
+/// `NAMADA_MASP_VERIFYING_KEYS_DIR` env var at build time
+#[cfg(feature = "embedded-verifying-keys")]
+const EMBEDDED_VKS: [(&str, &[u8]); 3] = [
+    (
+        SPEND_VK_NAME,
+        include_bytes!(concat!(
+            env!("NAMADA_MASP_VERIFYING_KEYS_DIR"),
+            "/masp-spend.vk"
+        )),
+    ),
+    (
+        CONVERT_VK_NAME,
+        include_bytes!(concat!(
+            env!("NAMADA_MASP_VERIFYING_KEYS_DIR"),
+            "/masp-convert.vk"
+        )),
+    ),
+    (
+        OUTPUT_VK_NAME,
+        include_bytes!(concat!(
+            env!("NAMADA_MASP_VERIFYING_KEYS_DIR"),
+            "/masp-output.vk"
+        )),
+    ),
+];
+
+/// Verify that the verifying keys embedded into the binary match their known
+/// hashes
+#[cfg(feature = "embedded-verifying-keys")]
+fn verify_embedded_vks() -> Result<(), MaspParamsError> {
+    EMBEDDED_VKS
+        .iter()
+        .try_for_each(|(name, vk)| verify_vk_hash(name, vk))
+}
+
 /// MASP verifying keys
 pub struct PVKs {
     /// spend verifying key
-    spend_vk: PreparedVerifyingKey<Bls12>,
+    spend_vk: VerifyingKey<Bls12>,
     /// convert verifying key
-    convert_vk: PreparedVerifyingKey<Bls12>,
+    convert_vk: VerifyingKey<Bls12>,
     /// output verifying key
-    output_vk: PreparedVerifyingKey<Bls12>,
+    output_vk: VerifyingKey<Bls12>,
 }
 
 lazy_static! {
     /// MASP verifying keys load from parameters
-    static ref VERIFIYING_KEYS: PVKs =
-        {
-        let params_dir = get_params_dir();
-        let [spend_path, convert_path, output_path] =
-            [SPEND_NAME, CONVERT_NAME, OUTPUT_NAME].map(|p| params_dir.join(p));
-
-        #[cfg(feature = "download-params")]
-        if !spend_path.exists() || !convert_path.exists() || !output_path.exists() {
-            let paths = masp_proofs::download_masp_parameters(None).expect(
-                "MASP parameters were not present, expected the download to \
-                succeed",
-            );
-            if paths.spend != spend_path
-                || paths.convert != convert_path
-                || paths.output != output_path
-            {
-                panic!(
-                    "unrecoverable: downloaded missing masp params, but to an \
-                    unfamiliar path"
-                )
-            }
-        }
-        // size and blake2b checked here
-        let params = masp_proofs::load_parameters(
-            spend_path.as_path(),
-            output_path.as_path(),
-            convert_path.as_path(),
+    static ref VERIFIYING_KEYS: PVKs = load_verifying_keys();
+}
+
+/// Load the verifying keys embedded into the binary, which must match their
+/// known hashes
+#[cfg(feature = "embedded-verifying-keys")]
+fn load_verifying_keys() -> PVKs {
+    if let Err(err) = verify_embedded_vks() {
+        panic!("The embedded MASP verifying keys are invalid: {err}");
+    }
+    let [spend_vk, convert_vk, output_vk] = EMBEDDED_VKS.map(|(_, bytes)| {
+        VerifyingKey::read(bytes).expect("Embedded MASP verifying key is valid")
+    });
+    PVKs {
+        spend_vk,
+        convert_vk,
+        output_vk,
+    }
+}
+
+/// Load the verifying keys from the MASP parameters, which are downloaded if
+/// missing
+#[cfg(not(feature = "embedded-verifying-keys"))]
+fn load_verifying_keys() -> PVKs {
+    let params_dir = get_params_dir();
+    let [spend_path, convert_path, output_path] =
+        [SPEND_NAME, CONVERT_NAME, OUTPUT_NAME].map(|p| params_dir.join(p));
+
+    #[cfg(feature = "download-params")]
+    if !spend_path.exists() || !convert_path.exists() || !output_path.exists() {
+        let paths = masp_proofs::download_masp_parameters(None).expect(
+            "MASP parameters were not present, expected the download to \
+             succeed",
         );
-        PVKs {
-            spend_vk: params.spend_vk,
-            convert_vk: params.convert_vk,
-            output_vk: params.output_vk
+        if paths.spend != spend_path
+            || paths.convert != convert_path
+            || paths.output != output_path
+        {
+            panic!(
+                "unrecoverable: downloaded missing masp params, but to an \
+                 unfamiliar path"
+            )
         }
-    };
+    }
+    // size and blake2b checked here
+    let params = masp_proofs::load_parameters(
+        spend_path.as_path(),
+        output_path.as_path(),
+        convert_path.as_path(),
+    );
+    PVKs {
+        spend_vk: params.spend_params.vk,
+        convert_vk: params.convert_params.vk,
+        output_vk: params.output_params.vk,
+    }
 }
 
 /// Make sure the MASP params are present and load verifying keys into memory
@@ -209,6 +546,16 @@ pub fn preload_verifying_keys() -> &'static PVKs {
     &VERIFIYING_KEYS
 }
 
+/// Fetch and verify the MASP params, or verify the verifying keys if they're
+/// embedded into the binary, then load the verifying keys into memory
+pub fn try_preload_verifying_keys() -> Result<&'static PVKs, MaspParamsError> {
+    #[cfg(feature = "embedded-verifying-keys")]
+    verify_embedded_vks()?;
+    #[cfg(not(feature = "embedded-verifying-keys"))]
+    fetch_masp_params()?;
+    Ok(preload_verifying_keys())
+}
+
 fn load_pvks() -> &'static PVKs {
     &VERIFIYING_KEYS
 }
@@ -348,39 +695,24 @@ pub fn verify_shielded_tx(transaction: &Transaction) -> bool {
         output_vk,
     } = load_pvks();
 
-    let mut ctx = SaplingVerificationContext::new(true);
-    let spends_valid = sapling_bundle
-        .shielded_spends
-        .iter()
-        .all(|spend| check_spend(spend, sighash.as_ref(), &mut ctx, spend_vk));
-    let converts_valid = sapling_bundle
-        .shielded_converts
-        .iter()
-        .all(|convert| check_convert(convert, &mut ctx, convert_vk));
-    let outputs_valid = sapling_bundle
-        .shielded_outputs
-        .iter()
-        .all(|output| check_output(output, &mut ctx, output_vk));
-
-    if !(spends_valid && outputs_valid && converts_valid) {
+    // Queue the proofs of all the spend, convert and output descriptions
+    // together with their signatures so that they are verified in a single
+    // batch rather than one pairing check per description
+    let mut ctx = BatchValidator::new();
+    if !ctx.check_bundle(sapling_bundle.clone(), *sighash.as_ref()) {
+        tracing::info!("failed bundle check");
         return false;
     }
 
-    tracing::info!("passed spend/output verification");
-
-    let assets_and_values: I128Sum = sapling_bundle.value_balance.clone();
-
     tracing::info!(
-        "accumulated {} assets/values",
-        assets_and_values.components().len()
+        "queued {} spends, {} converts and {} outputs for batch verification",
+        sapling_bundle.shielded_spends.len(),
+        sapling_bundle.shielded_converts.len(),
+        sapling_bundle.shielded_outputs.len(),
     );
 
-    let result = ctx.final_check(
-        assets_and_values,
-        sighash.as_ref(),
-        sapling_bundle.authorization.binding_sig,
-    );
-    tracing::info!("final check result {result}");
+    let result = ctx.validate(spend_vk, convert_vk, output_vk, OsRng);
+    tracing::info!("batch verification result {result}");
     result
 }
 
@@ -459,6 +791,37 @@ pub fn to_viewing_key(esk: &ExtendedSpendingKey) -> FullViewingKey {
     ExtendedFullViewingKey::from(esk).fvk
 }
 
+/// Personalization of the hash deriving the diversifiers of a viewing key
+const DIVERSIFIER_PERSONALIZATION: &[u8] = b"Namada_Diversifier";
+
+/// Derive the payment address of the given viewing key at the first
+/// diversifier index starting from `index` that yields a valid diversifier.
+/// The derivation is deterministic so that the diversified payment addresses
+/// of a viewing key can be recovered from the key and their indices. Return
+/// the index that has been used along with the address.
+pub fn derive_diversified_address(
+    viewing_key: &ViewingKey,
+    mut index: u64,
+) -> (u64, masp_primitives::sapling::PaymentAddress) {
+    let vk_bytes = viewing_key.serialize_to_vec();
+    loop {
+        let mut hasher = sha2::Sha256::new();
+        hasher.update(DIVERSIFIER_PERSONALIZATION);
+        hasher.update(&vk_bytes);
+        hasher.update(index.to_le_bytes());
+        let hash = hasher.finalize();
+        let mut d = [0; 11];
+        d.copy_from_slice(&hash[..11]);
+        // Not all diversifiers have a diversified base
+        if let Some(pa) = viewing_key.to_payment_address(Diversifier(d)) {
+            return (index, pa);
+        }
+        index = index
+            .checked_add(1)
+            .expect("The diversifier indices should not be exhausted");
+    }
+}
+
 /// Generate a valid diversifier, i.e. one that has a diversified base. Return
 /// also this diversified base.
 pub fn find_valid_diversifier<R: RngCore + CryptoRng>(
@@ -518,6 +881,101 @@ pub type TransferDelta = HashMap<Address, MaspChange>;
 /// Represents the changes that were made to a list of shielded accounts
 pub type TransactionDelta = HashMap<ViewingKey, I128Sum>;
 
+/// Represents the notes of a transaction decrypted by the viewing keys,
+/// indexed by the position of the shielded output in the transaction
+pub type DecryptedNotes =
+    BTreeMap<usize, (ViewingKey, Note, Diversifier, MemoBytes)>;
+
+/// Represents the shielded transactions fetched from a node
+pub type ShieldedTxs = BTreeMap<
+    IndexedTx,
+    (
+        Epoch,
+        BTreeSet<namada_core::types::storage::Key>,
+        Transaction,
+    ),
+>;
+
+/// Get the transaction to be fetched next to the given one
+fn next_indexed_tx(last_indexed_tx: Option<IndexedTx>) -> IndexedTx {
+    match last_indexed_tx {
+        Some(last) => IndexedTx {
+            height: last.height,
+            index: TxIndex(last.index.0 + 1),
+        },
+        None => IndexedTx {
+            height: BlockHeight::first(),
+            index: TxIndex(0),
+        },
+    }
+}
+
+/// Get the last height of the sync batch starting at the given height
+fn batch_end_height(
+    first_height: BlockHeight,
+    last_block_height: BlockHeight,
+) -> BlockHeight {
+    BlockHeight(
+        (first_height.0 + SHIELDED_SYNC_BATCH_SIZE - 1)
+            .min(last_block_height.0),
+    )
+}
+
+/// Try to decrypt the shielded outputs of the given transaction with the
+/// given viewing keys
+pub fn trial_decrypt(
+    shielded: &Transaction,
+    vks: &[ViewingKey],
+) -> DecryptedNotes {
+    let mut decrypted = DecryptedNotes::new();
+    for (idx, so) in shielded
+        .sapling_bundle()
+        .map_or(&vec![], |x| &x.shielded_outputs)
+        .iter()
+        .enumerate()
+    {
+        for vk in vks {
+            let decres = try_sapling_note_decryption::<_, OutputDescription<<<Authorized as Authorization>::SaplingAuth as masp_primitives::transaction::components::sapling::Authorization>::Proof>>(
+                &NETWORK,
+                1.into(),
+                &PreparedIncomingViewingKey::new(&vk.ivk()),
+                so,
+            );
+            if let Some((note, pa, memo)) = decres {
+                decrypted.insert(idx, (*vk, note, *pa.diversifier(), memo));
+                break;
+            }
+        }
+    }
+    decrypted
+}
+
+/// Try to decrypt the shielded outputs of all the given transactions with the
+/// given viewing keys. The transactions are processed in parallel when the
+/// `multicore` feature is enabled.
+pub fn trial_decrypt_txs(
+    txs: &ShieldedTxs,
+    vks: &[ViewingKey],
+) -> BTreeMap<IndexedTx, DecryptedNotes> {
+    #[cfg(feature = "multicore")]
+    {
+        use rayon::prelude::*;
+        txs.par_iter()
+            .map(|(indexed_tx, (_, _, stx))| {
+                (*indexed_tx, trial_decrypt(stx, vks))
+            })
+            .collect()
+    }
+    #[cfg(not(feature = "multicore"))]
+    {
+        txs.iter()
+            .map(|(indexed_tx, (_, _, stx))| {
+                (*indexed_tx, trial_decrypt(stx, vks))
+            })
+            .collect()
+    }
+}
+
 /// Represents the current state of the shielded pool from the perspective of
 /// the chosen viewing keys.
 #[derive(BorshSerialize, BorshDeserialize, Debug)]
@@ -615,8 +1073,101 @@ impl<U: ShieldedUtils + MaybeSend + MaybeSync> ShieldedContext<U> {
         }
     }
 
+    /// Export this shielded context, i.e. the scanned height, the commitment
+    /// tree, the witnesses, the note positions and the nullifiers, so that it
+    /// can be imported on another machine without rescanning the chain. The
+    /// data consists of magic bytes, the format version, a checksum and the
+    /// serialized context.
+    pub fn export(&self) -> Vec<u8> {
+        let payload = self.serialize_to_vec();
+        let checksum = sha2::Sha256::digest(&payload);
+        let mut bytes = Vec::with_capacity(
+            SHIELDED_CONTEXT_MAGIC.len() + 1 + checksum.len() + payload.len(),
+        );
+        bytes.extend_from_slice(SHIELDED_CONTEXT_MAGIC);
+        bytes.push(SHIELDED_CONTEXT_EXPORT_VERSION);
+        bytes.extend_from_slice(&checksum);
+        bytes.extend_from_slice(&payload);
+        bytes
+    }
+
+    /// Replace the state of this shielded context with an exported one after
+    /// checking its format, its integrity and its consistency. If this fails,
+    /// then leave the current context unchanged.
+    pub fn import(
+        &mut self,
+        bytes: &[u8],
+    ) -> Result<(), ShieldedContextImportError> {
+        let bytes = bytes
+            .strip_prefix(SHIELDED_CONTEXT_MAGIC.as_slice())
+            .ok_or(ShieldedContextImportError::InvalidMagic)?;
+        let (version, bytes) = bytes
+            .split_first()
+            .ok_or(ShieldedContextImportError::InvalidMagic)?;
+        if *version != SHIELDED_CONTEXT_EXPORT_VERSION {
+            return Err(ShieldedContextImportError::UnsupportedVersion(
+                *version,
+            ));
+        }
+        const CHECKSUM_LEN: usize = 32;
+        if bytes.len() < CHECKSUM_LEN {
+            return Err(ShieldedContextImportError::ChecksumMismatch);
+        }
+        let (checksum, payload) = bytes.split_at(CHECKSUM_LEN);
+        if sha2::Sha256::digest(payload).as_slice() != checksum {
+            return Err(ShieldedContextImportError::ChecksumMismatch);
+        }
+        let imported = ShieldedContext::<U>::try_from_slice(payload)
+            .map_err(ShieldedContextImportError::Decoding)?;
+        imported
+            .check_consistency()
+            .map_err(ShieldedContextImportError::Inconsistent)?;
+        *self = ShieldedContext {
+            utils: self.utils.clone(),
+            ..imported
+        };
+        Ok(())
+    }
+
+    /// Check that the witnesses match the commitment tree and that all the
+    /// note positions refer to notes of the tree
+    fn check_consistency(&self) -> Result<(), String> {
+        let tree_size = self.tree.size();
+        let root = self.tree.root();
+        for (pos, witness) in &self.witness_map {
+            if *pos >= tree_size {
+                return Err(format!("witness of unknown note {pos}"));
+            }
+            if witness.root() != root {
+                return Err(format!(
+                    "witness of note {pos} does not match the commitment tree"
+                ));
+            }
+        }
+        if let Some(pos) = self.note_map.keys().find(|pos| **pos >= tree_size) {
+            return Err(format!("note {pos} is not in the commitment tree"));
+        }
+        let positions = self
+            .pos_map
+            .values()
+            .flatten()
+            .chain(self.nf_map.values())
+            .chain(self.spents.iter())
+            .chain(self.memo_map.keys())
+            .chain(self.div_map.keys())
+            .chain(self.vk_map.keys());
+        for pos in positions {
+            if !self.note_map.contains_key(pos) {
+                return Err(format!("reference to unknown note {pos}"));
+            }
+        }
+        Ok(())
+    }
+
     /// Fetch the current state of the multi-asset shielded pool into a
-    /// ShieldedContext
+    /// ShieldedContext. The transactions are fetched and scanned in batches of
+    /// [`SHIELDED_SYNC_BATCH_SIZE`] blocks, and this context is saved after
+    /// each batch so that an interrupted sync resumes from the last batch.
     pub async fn fetch<C: Client + Sync>(
         &mut self,
         client: &C,
@@ -639,14 +1190,10 @@ impl<U: ShieldedUtils + MaybeSend + MaybeSync> ShieldedContext<U> {
             }
         }
 
+        let native_token = query_native_token(client).await?;
         // If unknown keys are being used, we need to scan older transactions
         // for any unspent notes
-        let native_token = query_native_token(client).await?;
-        let (txs, mut tx_iter);
         if !unknown_keys.is_empty() {
-            // Load all transactions accepted until this point
-            txs = Self::fetch_shielded_transfers(client, None).await?;
-            tx_iter = txs.iter();
             // Do this by constructing a shielding context only for unknown keys
             let mut tx_ctx = Self {
                 utils: self.utils.clone(),
@@ -656,71 +1203,114 @@ impl<U: ShieldedUtils + MaybeSend + MaybeSync> ShieldedContext<U> {
                 tx_ctx.pos_map.entry(vk).or_insert_with(BTreeSet::new);
             }
             // Update this unknown shielded context until it is level with self
-            while tx_ctx.last_indexed != self.last_indexed {
-                if let Some((indexed_tx, (epoch, changed_keys, stx))) =
-                    tx_iter.next()
-                {
-                    tx_ctx.scan_tx(
-                        *indexed_tx,
-                        *epoch,
-                        changed_keys,
-                        stx,
-                        native_token.clone(),
-                    )?;
-                } else {
-                    break;
+            if let Some(last_indexed) = self.last_indexed {
+                let mut first_tx = next_indexed_tx(None);
+                while first_tx.height <= last_indexed.height {
+                    let last_query_height =
+                        batch_end_height(first_tx.height, last_indexed.height);
+                    let mut txs = Self::fetch_shielded_transfers_range(
+                        client,
+                        first_tx,
+                        last_query_height,
+                    )
+                    .await?;
+                    txs.retain(|indexed_tx, _| *indexed_tx <= last_indexed);
+                    tx_ctx.scan_txs(&txs, &native_token)?;
+                    first_tx = IndexedTx {
+                        height: last_query_height.next_height(),
+                        index: TxIndex(0),
+                    };
                 }
             }
             // Merge the context data originating from the unknown keys into the
             // current context
+            tx_ctx.last_indexed = self.last_indexed;
             self.merge(tx_ctx);
-        } else {
-            // Load only transactions accepted from last_txid until this point
-            txs = Self::fetch_shielded_transfers(client, self.last_indexed)
-                .await?;
-            tx_iter = txs.iter();
         }
+
+        // Query for the last produced block height
+        let last_block_height = query_block(client)
+            .await?
+            .map_or_else(BlockHeight::first, |block| block.height);
         // Now that we possess the unspent notes corresponding to both old and
         // new keys up until tx_pos, proceed to scan the new transactions.
-        for (indexed_tx, (epoch, changed_keys, stx)) in &mut tx_iter {
-            self.scan_tx(
+        let mut first_tx = next_indexed_tx(self.last_indexed);
+        while first_tx.height <= last_block_height {
+            let last_query_height =
+                batch_end_height(first_tx.height, last_block_height);
+            let txs = Self::fetch_shielded_transfers_range(
+                client,
+                first_tx,
+                last_query_height,
+            )
+            .await?;
+            self.scan_txs(&txs, &native_token)?;
+            // Save the scanned batch as a checkpoint
+            self.save().await.map_err(|e| Error::Other(e.to_string()))?;
+            first_tx = IndexedTx {
+                height: last_query_height.next_height(),
+                index: TxIndex(0),
+            };
+        }
+        Ok(())
+    }
+
+    /// Trial-decrypt the given transactions with the viewing keys of this
+    /// context, then apply them to this context in order
+    pub fn scan_txs(
+        &mut self,
+        txs: &ShieldedTxs,
+        native_token: &Address,
+    ) -> Result<(), Error> {
+        let vks: Vec<_> = self.pos_map.keys().cloned().collect();
+        let mut decrypted = trial_decrypt_txs(txs, &vks);
+        for (indexed_tx, (epoch, changed_keys, stx)) in txs {
+            self.scan_decrypted_tx(
                 *indexed_tx,
                 *epoch,
                 changed_keys,
                 stx,
                 native_token.clone(),
+                decrypted.remove(indexed_tx).unwrap_or_default(),
             )?;
         }
         Ok(())
     }
 
     /// Obtain a chronologically-ordered list of all accepted shielded
-    /// transactions from a node.
+    /// transactions from a node after the given transaction until the given
+    /// height. If no height is given, the last block height is used.
     pub async fn fetch_shielded_transfers<C: Client + Sync>(
         client: &C,
         last_indexed_tx: Option<IndexedTx>,
-    ) -> Result<
-        BTreeMap<
-            IndexedTx,
-            (
-                Epoch,
-                BTreeSet<namada_core::types::storage::Key>,
-                Transaction,
-            ),
-        >,
-        Error,
-    > {
+        last_query_height: Option<BlockHeight>,
+    ) -> Result<ShieldedTxs, Error> {
         // Query for the last produced block height
-        let last_block_height = query_block(client)
-            .await?
-            .map_or_else(BlockHeight::first, |block| block.height);
+        let last_block_height = match last_query_height {
+            Some(height) => height,
+            None => query_block(client)
+                .await?
+                .map_or_else(BlockHeight::first, |block| block.height),
+        };
+        Self::fetch_shielded_transfers_range(
+            client,
+            next_indexed_tx(last_indexed_tx),
+            last_block_height,
+        )
+        .await
+    }
 
+    /// Obtain a chronologically-ordered list of the accepted shielded
+    /// transactions from the given transaction until the given height
+    async fn fetch_shielded_transfers_range<C: Client + Sync>(
+        client: &C,
+        first_tx: IndexedTx,
+        last_block_height: BlockHeight,
+    ) -> Result<ShieldedTxs, Error> {
         let mut shielded_txs = BTreeMap::new();
         // Fetch all the transactions we do not have yet
-        let first_height_to_query =
-            last_indexed_tx.map_or_else(|| 1, |last| last.height.0);
-        let first_idx_to_query =
-            last_indexed_tx.map_or_else(|| 0, |last| last.index.0 + 1);
+        let first_height_to_query = first_tx.height.0;
+        let first_idx_to_query = first_tx.index.0;
         for height in first_height_to_query..=last_block_height.0 {
             // Get the valid masp transactions at the specified height
             let epoch = query_epoch_at_height(client, height.into())
@@ -946,13 +1536,38 @@ impl<U: ShieldedUtils + MaybeSend + MaybeSync> ShieldedContext<U> {
         tx_changed_keys: &BTreeSet<namada_core::types::storage::Key>,
         shielded: &Transaction,
         native_token: Address,
+    ) -> Result<(), Error> {
+        let vks: Vec<_> = self.pos_map.keys().cloned().collect();
+        let decrypted = trial_decrypt(shielded, &vks);
+        self.scan_decrypted_tx(
+            indexed_tx,
+            epoch,
+            tx_changed_keys,
+            shielded,
+            native_token,
+            decrypted,
+        )
+    }
+
+    /// Applies the given transaction whose notes have already been
+    /// trial-decrypted to the supplied context. See [`Self::scan_tx`].
+    fn scan_decrypted_tx(
+        &mut self,
+        indexed_tx: IndexedTx,
+        epoch: Epoch,
+        tx_changed_keys: &BTreeSet<namada_core::types::storage::Key>,
+        shielded: &Transaction,
+        native_token: Address,
+        mut decrypted: DecryptedNotes,
     ) -> Result<(), Error> {
         // For tracking the account changes caused by this Transaction
         let mut transaction_delta = TransactionDelta::new();
         // Listen for notes sent to our viewing keys
-        for so in shielded
+        for (idx, so) in shielded
             .sapling_bundle()
             .map_or(&vec![], |x| &x.shielded_outputs)
+            .iter()
+            .enumerate()
         {
             // Create merkle tree leaf node from note commitment
             let node = Node::new(so.cmu.to_repr());
@@ -971,55 +1586,39 @@ impl<U: ShieldedUtils + MaybeSend + MaybeSync> ShieldedContext<U> {
             // note
             let witness = IncrementalWitness::<Node>::from_tree(&self.tree);
             self.witness_map.insert(note_pos, witness);
-            // Let's try to see if any of our viewing keys can decrypt latest
-            // note
-            let mut pos_map = HashMap::new();
-            std::mem::swap(&mut pos_map, &mut self.pos_map);
-            for (vk, notes) in pos_map.iter_mut() {
-                let decres = try_sapling_note_decryption::<_, OutputDescription<<<Authorized as Authorization>::SaplingAuth as masp_primitives::transaction::components::sapling::Authorization>::Proof>>(
-                    &NETWORK,
-                    1.into(),
-                    &PreparedIncomingViewingKey::new(&vk.ivk()),
-                    so,
+            // Check if any of our viewing keys has decrypted latest note
+            if let Some((vk, note, diversifier, memo)) = decrypted.remove(&idx)
+            {
+                // Add this note to list of notes decrypted by this viewing key
+                self.pos_map.entry(vk).or_default().insert(note_pos);
+                // Compute the nullifier now to quickly recognize when spent
+                let nf = note.nf(
+                    &vk.nk,
+                    note_pos.try_into().map_err(|_| {
+                        Error::Other("Can not get nullifier".to_string())
+                    })?,
                 );
-                // So this current viewing key does decrypt this current note...
-                if let Some((note, pa, memo)) = decres {
-                    // Add this note to list of notes decrypted by this viewing
-                    // key
-                    notes.insert(note_pos);
-                    // Compute the nullifier now to quickly recognize when spent
-                    let nf = note.nf(
-                        &vk.nk,
-                        note_pos.try_into().map_err(|_| {
-                            Error::Other("Can not get nullifier".to_string())
-                        })?,
-                    );
-                    self.note_map.insert(note_pos, note);
-                    self.memo_map.insert(note_pos, memo);
-                    // The payment address' diversifier is required to spend
-                    // note
-                    self.div_map.insert(note_pos, *pa.diversifier());
-                    self.nf_map.insert(nf, note_pos);
-                    // Note the account changes
-                    let balance = transaction_delta
-                        .entry(*vk)
-                        .or_insert_with(I128Sum::zero);
-                    *balance += I128Sum::from_nonnegative(
-                        note.asset_type,
-                        note.value as i128,
+                self.note_map.insert(note_pos, note);
+                self.memo_map.insert(note_pos, memo);
+                // The payment address' diversifier is required to spend note
+                self.div_map.insert(note_pos, diversifier);
+                self.nf_map.insert(nf, note_pos);
+                // Note the account changes
+                let balance =
+                    transaction_delta.entry(vk).or_insert_with(I128Sum::zero);
+                *balance += I128Sum::from_nonnegative(
+                    note.asset_type,
+                    note.value as i128,
+                )
+                .map_err(|()| {
+                    Error::Other(
+                        "found note with invalid value or asset type"
+                            .to_string(),
                     )
-                    .map_err(|()| {
-                        Error::Other(
-                            "found note with invalid value or asset type"
-                                .to_string(),
-                        )
-                    })?;
+                })?;
 
-                    self.vk_map.insert(note_pos, *vk);
-                    break;
-                }
+                self.vk_map.insert(note_pos, vk);
             }
-            std::mem::swap(&mut pos_map, &mut self.pos_map);
         }
         // Cancel out those of our notes that have been spent
         for ss in shielded
@@ -1187,6 +1786,32 @@ impl<U: ShieldedUtils + MaybeSend + MaybeSync> ShieldedContext<U> {
         &self.delta_map
     }
 
+    /// Get the diversified payment address that received the note at the
+    /// given position, as detected when the note was decrypted during scanning
+    pub fn note_payment_address(
+        &self,
+        note_pos: usize,
+    ) -> Option<PaymentAddress> {
+        let vk = self.vk_map.get(&note_pos)?;
+        let div = self.div_map.get(&note_pos)?;
+        vk.to_payment_address(*div).map(PaymentAddress::from)
+    }
+
+    /// Group the note positions of the given viewing key by the diversified
+    /// payment address that received them
+    pub fn notes_by_payment_address(
+        &self,
+        vk: &ViewingKey,
+    ) -> BTreeMap<PaymentAddress, BTreeSet<usize>> {
+        let mut notes = BTreeMap::<_, BTreeSet<_>>::new();
+        for note_pos in self.pos_map.get(vk).into_iter().flatten() {
+            if let Some(pa) = self.note_payment_address(*note_pos) {
+                notes.entry(pa).or_default().insert(*note_pos);
+            }
+        }
+        notes
+    }
+
     /// Compute the total unspent notes associated with the viewing key in the
     /// context. If the key is not in the context, then we do not know the
     /// balance and hence we return None.
@@ -1232,32 +1857,8 @@ impl<U: ShieldedUtils + MaybeSend + MaybeSync> ShieldedContext<U> {
         &mut self,
         context: &N,
     ) -> Result<(), Error> {
-        // To facilitate lookups of human-readable token names
-        for token in context.wallet().await.get_addresses().values() {
-            let Some(denom) = query_denom(context.client(), token).await else {
-                return Err(Error::Query(QueryError::General(format!(
-                    "denomination for token {token}"
-                ))))
-            };
-            for position in MaspDigitPos::iter() {
-                let asset_type =
-                    encode_asset_type(token.clone(), denom, position, None)
-                        .map_err(|_| {
-                            Error::Other(
-                                "unable to create asset type".to_string(),
-                            )
-                        })?;
-                self.asset_types.insert(
-                    asset_type,
-                    AssetData {
-                        token: token.clone(),
-                        denom,
-                        position,
-                        epoch: None,
-                    },
-                );
-            }
-        }
+        let asset_types = query_wallet_asset_types(context).await?;
+        self.asset_types.extend(asset_types);
         Ok(())
     }
 
@@ -1272,23 +1873,9 @@ impl<U: ShieldedUtils + MaybeSend + MaybeSync> ShieldedContext<U> {
         if let decoded @ Some(_) = self.asset_types.get(&asset_type) {
             return decoded.cloned();
         }
-        // Query for the ID of the last accepted transaction
-        let (token, denom, position, ep, _conv, _path): (
-            Address,
-            Denomination,
-            MaspDigitPos,
-            _,
-            I128Sum,
-            MerklePath<Node>,
-        ) = rpc::query_conversion(client, asset_type).await?;
-        let pre_asset_type = AssetData {
-            token,
-            denom,
-            position,
-            epoch: Some(ep),
-        };
-        self.asset_types.insert(asset_type, pre_asset_type.clone());
-        Some(pre_asset_type)
+        let decoded = query_asset_type(client, asset_type).await?;
+        self.asset_types.insert(asset_type, decoded.clone());
+        Some(decoded)
     }
 
     /// Query the ledger for the conversion that is allowed for the given asset
@@ -1530,6 +2117,94 @@ impl<U: ShieldedUtils + MaybeSend + MaybeSync> ShieldedContext<U> {
         Ok((output, normed_output, conversions))
     }
 
+    /// Order the unspent notes among the given ones according to the note
+    /// selection strategy. The notes that can't contribute to the target are
+    /// put last.
+    async fn order_unspent_notes<C: Client + Sync>(
+        &mut self,
+        client: &C,
+        notes: BTreeSet<usize>,
+        target: &I128Sum,
+        note_selection: NoteSelectionStrategy,
+    ) -> Result<Vec<usize>, Error> {
+        // Spent notes cannot contribute a new transaction's pool
+        let mut notes: Vec<usize> = notes
+            .into_iter()
+            .filter(|note_idx| !self.spents.contains(note_idx))
+            .collect();
+        match note_selection {
+            NoteSelectionStrategy::Oldest => return Ok(notes),
+            NoteSelectionStrategy::PreferPrivacy => {
+                use rand::seq::SliceRandom;
+                notes.shuffle(&mut OsRng);
+                return Ok(notes);
+            }
+            NoteSelectionStrategy::MinimizeChange
+            | NoteSelectionStrategy::ConsolidateDust => {}
+        }
+
+        // Express the target and the notes in the units of their token
+        let mut target_tokens = HashSet::new();
+        let mut target_value = token::Change::zero();
+        for (asset_type, val) in target.components() {
+            if let Some(decoded) =
+                self.decode_asset_type(client, *asset_type).await
+            {
+                target_value += token::Change::from_masp_denominated(
+                    *val,
+                    decoded.position,
+                )
+                .ok_or_else(|| {
+                    Error::Other("the target amount overflows".to_string())
+                })?;
+                target_tokens.insert(decoded.token);
+            }
+        }
+        let mut valued_notes = vec![];
+        let mut other_notes = vec![];
+        for note_idx in notes {
+            let note = *self.note_map.get(&note_idx).ok_or_else(|| {
+                Error::Other(format!("Unable to get note {note_idx}"))
+            })?;
+            let value =
+                match self.decode_asset_type(client, note.asset_type).await {
+                    Some(decoded) if target_tokens.contains(&decoded.token) => {
+                        token::Change::from_masp_denominated(
+                            note.value as i128,
+                            decoded.position,
+                        )
+                    }
+                    _ => None,
+                };
+            match value {
+                Some(value) => valued_notes.push((value, note_idx)),
+                None => other_notes.push(note_idx),
+            }
+        }
+
+        // Sort the notes from the smallest to the largest
+        valued_notes.sort();
+        if note_selection == NoteSelectionStrategy::MinimizeChange {
+            let covering = valued_notes
+                .iter()
+                .position(|(value, _)| *value >= target_value)
+                .map(|pos| valued_notes.remove(pos));
+            valued_notes.reverse();
+            if let Some(covering) = covering {
+                valued_notes.insert(0, covering);
+            }
+        } else {
+            let largest =
+                valued_notes.split_off(MAX_DUST_NOTES.min(valued_notes.len()));
+            valued_notes.extend(largest.into_iter().rev());
+        }
+        Ok(valued_notes
+            .into_iter()
+            .map(|(_, note_idx)| note_idx)
+            .chain(other_notes)
+            .collect())
+    }
+
     /// Collect enough unspent notes in this context to exceed the given amount
     /// of the specified asset type. Return the total value accumulated plus
     /// notes and the corresponding diversifiers/merkle paths that were used to
@@ -1540,6 +2215,7 @@ impl<U: ShieldedUtils + MaybeSend + MaybeSync> ShieldedContext<U> {
         vk: &ViewingKey,
         target: I128Sum,
         target_epoch: Epoch,
+        note_selection: NoteSelectionStrategy,
     ) -> Result<
         (
             I128Sum,
@@ -1555,16 +2231,20 @@ impl<U: ShieldedUtils + MaybeSend + MaybeSync> ShieldedContext<U> {
         let mut notes = Vec::new();
         // Retrieve the notes that can be spent by this key
         if let Some(avail_notes) = self.pos_map.get(vk).cloned() {
+            let avail_notes = self
+                .order_unspent_notes(
+                    context.client(),
+                    avail_notes,
+                    &target,
+                    note_selection,
+                )
+                .await?;
             for note_idx in &avail_notes {
                 // No more transaction inputs are required once we have met
                 // the target amount
                 if normed_val_acc >= target {
                     break;
                 }
-                // Spent notes cannot contribute a new transaction's pool
-                if self.spents.contains(note_idx) {
-                    continue;
-                }
                 // Get note, merkle path, diversifier associated with this ID
                 let note = *self.note_map.get(note_idx).ok_or_else(|| {
                     Error::Other(format!("Unable to get note {note_idx}"))
@@ -1759,6 +2439,118 @@ impl<U: ShieldedUtils + MaybeSend + MaybeSync> ShieldedContext<U> {
         Ok((decoded, undecoded, ep))
     }
 
+    /// Generate a disclosure of the payment made by the output note at the
+    /// given index in the given transaction. The note must be decryptable with
+    /// the supplied viewing key, which isn't part of the disclosure.
+    pub async fn generate_payment_disclosure<C: Client + Sync>(
+        &mut self,
+        client: &C,
+        indexed_tx: IndexedTx,
+        output_index: u32,
+        viewing_key: &ViewingKey,
+    ) -> Result<PaymentDisclosure, Error> {
+        let block = client
+            .block(indexed_tx.height.0 as u32)
+            .await
+            .map_err(|e| Error::from(QueryError::General(e.to_string())))?
+            .block
+            .data;
+        let tx_bytes =
+            block.get(indexed_tx.index.0 as usize).ok_or_else(|| {
+                Error::Other(format!(
+                    "Missing transaction {} at block height {}",
+                    indexed_tx.index, indexed_tx.height
+                ))
+            })?;
+        let tx = Tx::try_from(tx_bytes.as_ref())
+            .map_err(|e| Error::Other(e.to_string()))?;
+        let (_, shielded) = Self::extract_masp_tx(
+            &tx,
+            ExtractShieldedActionArg::Request((
+                client,
+                indexed_tx.height,
+                Some(indexed_tx.index),
+            )),
+            true,
+        )
+        .await?;
+
+        let output = shielded
+            .sapling_bundle()
+            .and_then(|bundle| {
+                bundle.shielded_outputs.get(output_index as usize)
+            })
+            .ok_or_else(|| {
+                Error::Other(format!(
+                    "Missing shielded output {output_index} in the transaction"
+                ))
+            })?;
+        let (note, payment_address, _memo) = try_sapling_note_decryption::<_, OutputDescription<<<Authorized as Authorization>::SaplingAuth as masp_primitives::transaction::components::sapling::Authorization>::Proof>>(
+            &NETWORK,
+            1.into(),
+            &PreparedIncomingViewingKey::new(&viewing_key.ivk()),
+            output,
+        )
+        .ok_or_else(|| {
+            Error::Other(
+                "The viewing key can't decrypt the shielded output".to_string(),
+            )
+        })?;
+        let asset_data = self
+            .decode_asset_type(client, note.asset_type)
+            .await
+            .ok_or_else(|| {
+                Error::Other(format!(
+                    "Unable to decode the asset type {}",
+                    note.asset_type
+                ))
+            })?;
+
+        Ok(PaymentDisclosure {
+            indexed_tx,
+            output_index,
+            payment_address: payment_address.into(),
+            asset_data,
+            note,
+        })
+    }
+
+    /// Verify a payment disclosure against the note commitments of the
+    /// disclosed transaction. On success, the transaction is known to have
+    /// paid the disclosed amount of the token to the payment address.
+    pub async fn verify_payment_disclosure<C: Client + Sync>(
+        client: &C,
+        disclosure: &PaymentDisclosure,
+    ) -> Result<(), Error> {
+        let IndexedTx { height, index } = disclosure.indexed_tx;
+        let cmu = rpc::query_masp_compact_blocks(client, height, height)
+            .await?
+            .into_iter()
+            .flat_map(|block| block.txs)
+            .find(|compact_tx| compact_tx.index == index)
+            .and_then(|compact_tx| {
+                compact_tx
+                    .outputs
+                    .get(disclosure.output_index as usize)
+                    .map(|output| output.cmu)
+            })
+            .ok_or_else(|| {
+                Error::Other(format!(
+                    "Missing shielded output {} in the transaction {index} at \
+                     block height {height}",
+                    disclosure.output_index
+                ))
+            })?;
+        if !disclosure.verify(&cmu) {
+            return Err(Error::Other(
+                "The payment disclosure doesn't open the note commitment of \
+                 the transaction"
+                    .to_string(),
+            ));
+        }
+        Ok(())
+    }
+
     /// Convert an amount whose units are AssetTypes to one whose units are
     /// Addresses that they decode to. All asset types not corresponding to
     /// the given epoch are ignored.
@@ -1853,54 +2645,14 @@ impl<U: ShieldedUtils + MaybeSend + MaybeSync> ShieldedContext<U> {
         res
     }
 
-    /// Make shielded components to embed within a Transfer object. If no
-    /// shielded payment address nor spending key is specified, then no
-    /// shielded components are produced. Otherwise a transaction containing
-    /// nullifiers and/or note commitments are produced. Dummy transparent
-    /// UTXOs are sometimes used to make transactions balanced, but it is
-    /// understood that transparent account changes are effected only by the
-    /// amounts and signatures specified by the containing Transfer object.
-    pub async fn gen_shielded_transfer(
+    /// Make a new builder of shielded transactions, expiring at the height
+    /// matching the expiration of the transactions of the given context
+    async fn new_tx_builder(
         context: &impl Namada,
-        source: &TransferSource,
-        target: &TransferTarget,
-        token: &Address,
-        amount: token::DenominatedAmount,
-    ) -> Result<Option<ShieldedTransfer>, TransferErr> {
-        // No shielded components are needed when neither source nor destination
-        // are shielded
-
+    ) -> Result<Builder<TestNetwork, rand::rngs::StdRng>, Error> {
         use rand::rngs::StdRng;
         use rand_core::SeedableRng;
 
-        let spending_key = source.spending_key();
-        let payment_address = target.payment_address();
-        // No shielded components are needed when neither source nor
-        // destination are shielded
-        if spending_key.is_none() && payment_address.is_none() {
-            return Ok(None);
-        }
-        // We want to fund our transaction solely from supplied spending key
-        let spending_key = spending_key.map(|x| x.into());
-        let spending_keys: Vec<_> = spending_key.into_iter().collect();
-        {
-            // Load the current shielded context given the spending key we
-            // possess
-            let mut shielded = context.shielded_mut().await;
-            let _ = shielded.load().await;
-            shielded
-                .fetch(context.client(), &spending_keys, &[])
-                .await?;
-            // Save the update state so that future fetches can be
-            // short-circuited
-            let _ = shielded.save().await;
-        }
-        // Determine epoch in which to submit potential shielded transaction
-        let epoch = rpc::query_epoch(context.client()).await?;
-        // Context required for storing which notes are in the source's
-        // possession
-        let memo = MemoBytes::empty();
-
         // Try to get a seed from env var, if any.
         let rng = if let Ok(seed) = env::var(ENV_VAR_MASP_TEST_SEED)
             .map_err(|e| Error::Other(e.to_string()))
@@ -1920,7 +2672,6 @@ impl<U: ShieldedUtils + MaybeSend + MaybeSync> ShieldedContext<U> {
             StdRng::from_rng(OsRng).unwrap()
         };
 
-        // Now we build up the transaction within this object
         let expiration_height: u32 = match context.tx_builder().expiration {
             Some(expiration) => {
                 // Try to match a DateTime expiration with a plausible
@@ -1957,14 +2708,64 @@ impl<U: ShieldedUtils + MaybeSend + MaybeSync> ShieldedContext<U> {
                 u32::MAX - 20
             }
         };
-        let mut builder = Builder::<TestNetwork, _>::new_with_rng(
+        Ok(Builder::<TestNetwork, _>::new_with_rng(
             NETWORK,
             // NOTE: this is going to add 20 more blocks to the actual
             // expiration but there's no other exposed function that we could
             // use from the masp crate to specify the expiration better
             expiration_height.into(),
             rng,
-        );
+        ))
+    }
+
+    /// Make shielded components to embed within a Transfer object. If no
+    /// shielded payment address nor spending key is specified, then no
+    /// shielded components are produced. Otherwise a transaction containing
+    /// nullifiers and/or note commitments are produced. Dummy transparent
+    /// UTXOs are sometimes used to make transactions balanced, but it is
+    /// understood that transparent account changes are effected only by the
+    /// amounts and signatures specified by the containing Transfer object.
+    pub async fn gen_shielded_transfer(
+        context: &impl Namada,
+        source: &TransferSource,
+        target: &TransferTarget,
+        token: &Address,
+        amount: token::DenominatedAmount,
+        note_selection: NoteSelectionStrategy,
+    ) -> Result<Option<ShieldedTransfer>, TransferErr> {
+        // No shielded components are needed when neither source nor destination
+        // are shielded
+
+        let spending_key = source.spending_key();
+        let payment_address = target.payment_address();
+        // No shielded components are needed when neither source nor
+        // destination are shielded
+        if spending_key.is_none() && payment_address.is_none() {
+            return Ok(None);
+        }
+        // We want to fund our transaction solely from supplied spending key
+        let spending_key = spending_key.map(|x| x.into());
+        let spending_keys: Vec<_> = spending_key.into_iter().collect();
+        {
+            // Load the current shielded context given the spending key we
+            // possess
+            let mut shielded = context.shielded_mut().await;
+            let _ = shielded.load().await;
+            shielded
+                .fetch(context.client(), &spending_keys, &[])
+                .await?;
+            // Save the update state so that future fetches can be
+            // short-circuited
+            let _ = shielded.save().await;
+        }
+        // Determine epoch in which to submit potential shielded transaction
+        let epoch = rpc::query_epoch(context.client()).await?;
+        // Context required for storing which notes are in the source's
+        // possession
+        let memo = MemoBytes::empty();
+
+        // Now we build up the transaction within this object
+        let mut builder = Self::new_tx_builder(context).await?;
 
         // Convert transaction amount into MASP types
         let Some(denom) = query_denom(context.client(), token).await else {
@@ -2001,6 +2802,7 @@ impl<U: ShieldedUtils + MaybeSend + MaybeSync> ShieldedContext<U> {
                     &to_viewing_key(&sk).vk,
                     I128Sum::from_sum(masp_amount),
                     epoch,
+                    note_selection,
                 )
                 .await?;
             // Commit the notes found to our transaction
@@ -2305,18 +3107,108 @@ impl<U: ShieldedUtils + MaybeSend + MaybeSync> ShieldedContext<U> {
                         .await
                         .map_err(|e| Error::Other(e.to_string()))?;
                 }
-                Ok(Some(built))
+                Ok(Some(built))
+            }
+        }
+
+        #[cfg(not(feature = "testing"))]
+        {
+            // Build and return the constructed transaction
+            let built = build_transfer(
+                context.shielded().await.utils.local_tx_prover(),
+            )?;
+            Ok(Some(built))
+        }
+    }
+
+    /// Make the shielded components of a transaction moving the given
+    /// transparent amounts of several tokens from the source address into
+    /// the shielded pool at the given payment address. Since the inputs are
+    /// exactly the given amounts, one output is produced per token and
+    /// denomination digit and no change is left.
+    pub async fn gen_shielding_sweep(
+        context: &impl Namada,
+        source: &Address,
+        target: &PaymentAddress,
+        amounts: &[(Address, token::DenominatedAmount)],
+    ) -> Result<ShieldedTransfer, TransferErr> {
+        // Determine epoch in which to submit the shielded transaction
+        let epoch = rpc::query_epoch(context.client()).await?;
+        let memo = MemoBytes::empty();
+        let mut builder = Self::new_tx_builder(context).await?;
+
+        // The transparent inputs are all tied to the source, but only the
+        // source of the parent ShieldingSweep object is used to validate
+        // fund availability
+        let source_enc = source.serialize_to_vec();
+        let hash = ripemd::Ripemd160::digest(sha2::Sha256::digest(
+            source_enc.as_ref(),
+        ));
+        let script = TransparentAddress(hash.into());
+
+        for (token, amount) in amounts {
+            // Convert the amount of the token into MASP types
+            let Some(denom) = query_denom(context.client(), token).await else {
+                return Err(TransferErr::General(Error::from(
+                    QueryError::General(format!(
+                        "denomination for token {token}"
+                    )),
+                )));
+            };
+            let (asset_types, _) = {
+                let mut shielded = context.shielded_mut().await;
+                let amount = shielded
+                    .convert_amount(
+                        context.client(),
+                        epoch,
+                        token,
+                        denom,
+                        amount.amount(),
+                    )
+                    .await?;
+                // Make sure to save any decodings of the asset types used so
+                // that balance queries involving them are successful
+                let _ = shielded.save().await;
+                amount
+            };
+            // Move each non-zero digit of the amount from a transparent input
+            // to a shielded output of the same asset type
+            for (digit, asset_type) in
+                MaspDigitPos::iter().zip(asset_types.iter())
+            {
+                let amount_part = digit.denominate(&amount.amount());
+                if amount_part == 0 {
+                    continue;
+                }
+                builder
+                    .add_transparent_input(TxOut {
+                        asset_type: *asset_type,
+                        value: amount_part,
+                        address: script,
+                    })
+                    .map_err(builder::Error::TransparentBuild)?;
+                builder
+                    .add_sapling_output(
+                        None,
+                        (*target).into(),
+                        *asset_type,
+                        amount_part,
+                        memo.clone(),
+                    )
+                    .map_err(builder::Error::SaplingBuild)?;
             }
         }
 
-        #[cfg(not(feature = "testing"))]
-        {
-            // Build and return the constructed transaction
-            let built = build_transfer(
-                context.shielded().await.utils.local_tx_prover(),
-            )?;
-            Ok(Some(built))
-        }
+        let builder_clone = builder.clone().map_builder(WalletMap);
+        let prover = context.shielded().await.utils.local_tx_prover();
+        let (masp_tx, metadata) =
+            builder.build(&prover, &FeeRule::non_standard(U64Sum::zero()))?;
+        Ok(ShieldedTransfer {
+            builder: builder_clone,
+            masp_tx,
+            metadata,
+            epoch,
+        })
     }
 
     /// Obtain the known effects of all accepted shielded and transparent
@@ -2500,6 +3392,106 @@ impl<U: ShieldedUtils + MaybeSend + MaybeSync> ShieldedContext<U> {
     }
 }
 
+/// Query the ledger for the decoding of the given asset type
+async fn query_asset_type<C: Client + Sync>(
+    client: &C,
+    asset_type: AssetType,
+) -> Option<AssetData> {
+    let (token, denom, position, ep, _conv, _path): (
+        Address,
+        Denomination,
+        MaspDigitPos,
+        _,
+        I128Sum,
+        MerklePath<Node>,
+    ) = rpc::query_conversion(client, asset_type).await?;
+    Some(AssetData {
+        token,
+        denom,
+        position,
+        epoch: Some(ep),
+    })
+}
+
+/// Query the ledger for the decodings of the undated asset types of the
+/// addresses stored in the wallet
+async fn query_wallet_asset_types<N: Namada>(
+    context: &N,
+) -> Result<Vec<(AssetType, AssetData)>, Error> {
+    // Release the wallet before querying the ledger
+    let tokens: Vec<Address> = context
+        .wallet()
+        .await
+        .get_addresses()
+        .into_values()
+        .collect();
+    let mut asset_types = vec![];
+    // To facilitate lookups of human-readable token names
+    for token in tokens {
+        let Some(denom) = query_denom(context.client(), &token).await else {
+            return Err(Error::Query(QueryError::General(format!(
+                "denomination for token {token}"
+            ))));
+        };
+        for position in MaspDigitPos::iter() {
+            let asset_type =
+                encode_asset_type(token.clone(), denom, position, None)
+                    .map_err(|_| {
+                        Error::Other("unable to create asset type".to_string())
+                    })?;
+            asset_types.push((
+                asset_type,
+                AssetData {
+                    token: token.clone(),
+                    denom,
+                    position,
+                    epoch: None,
+                },
+            ));
+        }
+    }
+    Ok(asset_types)
+}
+
+/// Decode the given asset type with the shielded context of the Namada
+/// context, caching its decoding. Unlike
+/// [`ShieldedContext::decode_asset_type`], the shielded context is only locked
+/// to read and update the cache, so that the other tasks sharing the context
+/// aren't blocked while the ledger is queried.
+pub async fn decode_shared_asset_type(
+    context: &impl Namada,
+    asset_type: AssetType,
+) -> Option<AssetData> {
+    let cached = context
+        .shielded()
+        .await
+        .asset_types
+        .get(&asset_type)
+        .cloned();
+    if cached.is_some() {
+        return cached;
+    }
+    let decoded = query_asset_type(context.client(), asset_type).await?;
+    context
+        .shielded_mut()
+        .await
+        .asset_types
+        .insert(asset_type, decoded.clone());
+    Some(decoded)
+}
+
+/// Use the addresses stored in the wallet to precompute as many asset types
+/// as possible in the shielded context of the Namada context. Unlike
+/// [`ShieldedContext::precompute_asset_types`], neither the wallet nor the
+/// shielded context are locked while the ledger is queried.
+pub async fn precompute_shared_asset_types(
+    context: &impl Namada,
+) -> Result<(), Error> {
+    let asset_types = query_wallet_asset_types(context).await?;
+    context.shielded_mut().await.asset_types.extend(asset_types);
+    Ok(())
+}
+
 /// Extract the payload from the given Tx object
 fn extract_payload(
     tx: Tx,
@@ -2679,7 +3671,298 @@ async fn extract_payload_from_shielded_action<'args, C: Client + Sync>(
     Ok(result)
 }
 
+#[cfg(test)]
 mod tests {
+    use masp_primitives::sapling::prover::TxProver;
+
+    use super::*;
+
+    /// Build a transaction shielding the given raw amounts of a token from a
+    /// transparent source to the payment addresses, one output per amount
+    fn shielding_tx(
+        prover: &impl TxProver,
+        source: &Address,
+        outputs: &[(masp_primitives::sapling::PaymentAddress, u64)],
+    ) -> Transaction {
+        let asset_type = AssetType::new(b"test").unwrap();
+        let hash = ripemd::Ripemd160::digest(sha2::Sha256::digest(
+            source.serialize_to_vec(),
+        ));
+        let mut builder = Builder::new_with_rng(
+            NETWORK,
+            masp_primitives::consensus::BlockHeight::from_u32(1),
+            OsRng,
+        );
+        builder
+            .add_transparent_input(TxOut {
+                asset_type,
+                value: outputs.iter().map(|(_, value)| value).sum(),
+                address: TransparentAddress(hash.into()),
+            })
+            .unwrap();
+        for (target, value) in outputs {
+            builder
+                .add_sapling_output(
+                    None,
+                    *target,
+                    asset_type,
+                    *value,
+                    MemoBytes::empty(),
+                )
+                .unwrap();
+        }
+        let (tx, _metadata) = builder
+            .build(prover, &FeeRule::non_standard(U64Sum::zero()))
+            .unwrap();
+        tx
+    }
+
+    /// Test that scanning the transactions in a batch, whose notes are
+    /// trial-decrypted together, yields the same context as scanning them
+    /// one by one
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_batched_scan_matches_sequential() {
+        use std::sync::Mutex;
+
+        use namada_core::types::address::nam;
+        use namada_core::types::address::testing::established_address_1;
+
+        use super::fs::FsShieldedUtils;
+        use super::testing::MockTxProver;
+
+        let ours = ExtendedSpendingKey::master(&[0; 32]);
+        let theirs = ExtendedSpendingKey::master(&[1; 32]);
+        let vk = ExtendedFullViewingKey::from(&ours).fvk.vk;
+        let prover = MockTxProver(Mutex::new(OsRng));
+        let source = established_address_1();
+        let changed_keys = BTreeSet::from([
+            token::storage_key::balance_key(&nam(), &source),
+            token::storage_key::balance_key(&nam(), &MASP),
+        ]);
+        let txs: ShieldedTxs = (1..=3)
+            .map(|height| {
+                let outputs = [
+                    (ours.default_address().1, height),
+                    (theirs.default_address().1, 10),
+                ];
+                let indexed_tx = IndexedTx {
+                    height: BlockHeight(height),
+                    index: TxIndex(0),
+                };
+                let tx = shielding_tx(&prover, &source, &outputs);
+                (indexed_tx, (Epoch(0), changed_keys.clone(), tx))
+            })
+            .collect();
+
+        let mut batched = ShieldedContext::<FsShieldedUtils>::default();
+        batched.pos_map.insert(vk, BTreeSet::new());
+        let mut sequential = ShieldedContext::<FsShieldedUtils>::default();
+        sequential.pos_map.insert(vk, BTreeSet::new());
+
+        batched.scan_txs(&txs, &nam()).unwrap();
+        for (indexed_tx, (epoch, changed_keys, tx)) in &txs {
+            sequential
+                .scan_tx(*indexed_tx, *epoch, changed_keys, tx, nam())
+                .unwrap();
+        }
+
+        // Only the notes of our viewing key are found
+        assert_eq!(batched.note_map.len(), 3);
+        assert_eq!(batched.pos_map[&vk].len(), 3);
+        assert_eq!(batched.tree.size(), 6);
+        assert_eq!(batched.export(), sequential.export());
+    }
+
+    /// Test that a single invalid proof among the valid ones of a transaction
+    /// fails the batch verification of the whole transaction
+    #[test]
+    fn test_batch_verification_bad_proof() {
+        use namada_core::types::address::testing::established_address_1;
+
+        let params_dir = fetch_masp_params().unwrap();
+        let prover = LocalTxProver::new(
+            &params_dir.join(SPEND_NAME),
+            &params_dir.join(OUTPUT_NAME),
+            &params_dir.join(CONVERT_NAME),
+        );
+        let target = ExtendedSpendingKey::master(&[0; 32]).default_address().1;
+        let tx = shielding_tx(
+            &prover,
+            &established_address_1(),
+            &[(target, 10), (target, 20)],
+        );
+        assert!(verify_shielded_tx(&tx));
+
+        // Give the first output the proof of the second one
+        let mut bundle = tx.sapling_bundle().unwrap().clone();
+        bundle.shielded_outputs[0].zkproof = bundle.shielded_outputs[1].zkproof;
+        let tampered = TransactionData::from_parts(
+            tx.version(),
+            tx.consensus_branch_id(),
+            tx.lock_time(),
+            tx.expiry_height(),
+            tx.transparent_bundle().cloned(),
+            Some(bundle),
+        )
+        .freeze()
+        .unwrap();
+        assert!(!verify_shielded_tx(&tampered));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_shielded_context_export_import() {
+        use std::sync::Mutex;
+
+        use namada_core::types::address::nam;
+        use namada_core::types::address::testing::established_address_1;
+
+        use super::fs::FsShieldedUtils;
+        use super::testing::MockTxProver;
+        use super::{
+            ShieldedContext, ShieldedContextImportError,
+            SHIELDED_CONTEXT_EXPORT_VERSION,
+        };
+
+        // Populate a context with the notes, the witnesses and the view key
+        // state of a few scanned transactions, one note being spent
+        let sk = ExtendedSpendingKey::master(&[0; 32]);
+        let vk = ExtendedFullViewingKey::from(&sk).fvk.vk;
+        let prover = MockTxProver(Mutex::new(OsRng));
+        let source = established_address_1();
+        let changed_keys = BTreeSet::from([
+            token::storage_key::balance_key(&nam(), &source),
+            token::storage_key::balance_key(&nam(), &MASP),
+        ]);
+        let txs: ShieldedTxs = (1..=3)
+            .map(|height| {
+                let indexed_tx = IndexedTx {
+                    height: BlockHeight(height),
+                    index: TxIndex(0),
+                };
+                let outputs = [(sk.default_address().1, height)];
+                let tx = shielding_tx(&prover, &source, &outputs);
+                (indexed_tx, (Epoch(0), changed_keys.clone(), tx))
+            })
+            .collect();
+        let mut ctx = ShieldedContext::<FsShieldedUtils>::default();
+        ctx.pos_map.insert(vk, BTreeSet::new());
+        ctx.scan_txs(&txs, &nam()).unwrap();
+        ctx.spents.insert(0);
+        assert_eq!(ctx.note_map.len(), 3);
+        assert_eq!(ctx.witness_map.len(), 3);
+        let exported = ctx.export();
+
+        let mut imported = ShieldedContext::<FsShieldedUtils>::default();
+        imported.import(&exported).expect("import should succeed");
+        assert_eq!(imported.export(), exported);
+        assert_eq!(imported.tree.root(), ctx.tree.root());
+        assert_eq!(imported.note_map, ctx.note_map);
+        assert_eq!(imported.pos_map, ctx.pos_map);
+        assert_eq!(imported.spents, ctx.spents);
+        assert_eq!(imported.nf_map, ctx.nf_map);
+        assert_eq!(imported.vk_map, ctx.vk_map);
+        for (pos, witness) in &ctx.witness_map {
+            assert_eq!(imported.witness_map[pos].root(), witness.root());
+        }
+
+        // An inconsistent context is rejected and the current one is kept
+        let mut inconsistent = ShieldedContext::<FsShieldedUtils>::default();
+        inconsistent.import(&exported).unwrap();
+        inconsistent.note_map.remove(&0);
+        assert!(matches!(
+            imported.import(&inconsistent.export()),
+            Err(ShieldedContextImportError::Inconsistent(_))
+        ));
+        assert_eq!(imported.export(), exported);
+
+        // Corrupted data is rejected
+        let mut corrupted = exported.clone();
+        *corrupted.last_mut().unwrap() ^= 1;
+        assert!(matches!(
+            imported.import(&corrupted),
+            Err(ShieldedContextImportError::ChecksumMismatch)
+        ));
+
+        // Unknown versions are rejected
+        let mut unknown_version = exported.clone();
+        unknown_version[8] = SHIELDED_CONTEXT_EXPORT_VERSION + 1;
+        assert!(matches!(
+            imported.import(&unknown_version),
+            Err(ShieldedContextImportError::UnsupportedVersion(_))
+        ));
+
+        // Other data is rejected
+        assert!(matches!(
+            imported.import(b"shielded.dat"),
+            Err(ShieldedContextImportError::InvalidMagic)
+        ));
+    }
+
+    /// The shielded and wallet contexts can be shared between the threads of
+    /// a service
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_contexts_are_send_sync() {
+        use super::fs::FsShieldedUtils;
+        use super::ShieldedContext;
+        use crate::wallet::fs::FsWalletUtils;
+        use crate::wallet::Wallet;
+
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<ShieldedContext<FsShieldedUtils>>();
+        assert_send_sync::<Wallet<FsWalletUtils>>();
+    }
+
+    #[test]
+    fn test_verify_masp_params() {
+        use super::{
+            verify_masp_params, MaspParamsError, CONVERT_NAME, OUTPUT_NAME,
+            SPEND_NAME,
+        };
+
+        let tempdir = tempfile::tempdir().expect("expected a temp dir");
+        assert!(matches!(
+            verify_masp_params(tempdir.path()),
+            Err(MaspParamsError::Missing(_))
+        ));
+
+        for name in [SPEND_NAME, OUTPUT_NAME, CONVERT_NAME] {
+            std::fs::write(tempdir.path().join(name), b"fake params")
+                .expect("expected a writable temp file");
+        }
+        assert!(matches!(
+            verify_masp_params(tempdir.path()),
+            Err(MaspParamsError::InvalidSize { found: 11, .. })
+        ));
+
+        // Files of the right sizes must still match the known hashes
+        for (name, size) in super::MASP_PARAMS_SIZES {
+            std::fs::File::create(tempdir.path().join(name))
+                .and_then(|file| file.set_len(size))
+                .expect("expected a writable temp file");
+        }
+        assert!(matches!(
+            verify_masp_params(tempdir.path()),
+            Err(MaspParamsError::Corrupted(_))
+        ));
+    }
+
+    #[test]
+    fn test_verify_vk_hash() {
+        use super::{verify_vk_hash, MaspParamsError, SPEND_VK_NAME};
+
+        assert!(matches!(
+            verify_vk_hash(SPEND_VK_NAME, b"fake verifying key"),
+            Err(MaspParamsError::Corrupted(_))
+        ));
+        assert!(matches!(
+            verify_vk_hash("masp-unknown.vk", b"fake verifying key"),
+            Err(MaspParamsError::Corrupted(_))
+        ));
+    }
+
     /// quick and dirty test. will fail on size check
     #[test]
     #[should_panic(expected = "parameter file size is not correct")]
@@ -2723,7 +4006,7 @@ mod tests {
         use masp_proofs::bellman::{Circuit, ConstraintSystem, SynthesisError};
         use masp_proofs::bls12_381::{Bls12, Scalar};
 
-        use super::{CONVERT_NAME, OUTPUT_NAME, SPEND_NAME};
+        use super::MASP_PARAMS_SIZES;
 
         struct FakeCircuit<E: PrimeField> {
             x: E,
@@ -2757,13 +4040,8 @@ mod tests {
         let tempdir = tempfile::tempdir()
             .expect("expected a temp dir")
             .into_path();
-        // TODO: get masp to export these consts
-        let fake_params_paths = [
-            (SPEND_NAME, 49848572u64),
-            (OUTPUT_NAME, 16398620u64),
-            (CONVERT_NAME, 22570940u64),
-        ]
-        .map(|(p, s)| (tempdir.join(p), s));
+        let fake_params_paths =
+            MASP_PARAMS_SIZES.map(|(p, s)| (tempdir.join(p), s));
         for (path, size) in &fake_params_paths {
             let mut f =
                 std::fs::File::create(path).expect("expected a temp file");
@@ -2788,6 +4066,148 @@ mod tests {
             &fake_params_paths[2].0,
         );
     }
+
+    /// Build a shielded context holding the given raw amounts of tokens, the
+    /// note at index `i` being the `i`-th amount
+    #[cfg(feature = "std")]
+    fn context_with_notes(
+        notes: &[(&Address, u64)],
+    ) -> ShieldedContext<super::fs::FsShieldedUtils> {
+        let mut ctx = ShieldedContext::default();
+        let owner = ExtendedSpendingKey::master(&[0; 32]).default_address().1;
+        for (idx, (token, value)) in notes.iter().enumerate() {
+            let asset_data = AssetData {
+                token: (*token).clone(),
+                denom: Denomination(0),
+                position: MaspDigitPos::Zero,
+                epoch: None,
+            };
+            let asset_type = asset_data.encode().unwrap();
+            ctx.asset_types.insert(asset_type, asset_data);
+            let note = owner
+                .create_note(
+                    asset_type,
+                    *value,
+                    masp_primitives::sapling::Rseed::AfterZip212([0; 32]),
+                )
+                .unwrap();
+            ctx.note_map.insert(idx, note);
+        }
+        ctx
+    }
+
+    /// The target of the given raw amount of NAM
+    #[cfg(feature = "std")]
+    fn nam_target(value: i128) -> I128Sum {
+        use namada_core::types::address::nam;
+
+        let asset_type =
+            encode_asset_type(nam(), Denomination(0), MaspDigitPos::Zero, None)
+                .unwrap();
+        I128Sum::from_pair(asset_type, value)
+    }
+
+    /// Test that the notes are ordered by value and then by index, that the
+    /// spent notes are left out and that the notes of other tokens are put
+    /// last
+    #[cfg(feature = "std")]
+    #[tokio::test]
+    async fn test_order_unspent_notes_minimize_change() {
+        use namada_core::types::address::{btc, nam};
+
+        use crate::queries::testing::TestClient;
+        use crate::queries::RPC;
+
+        let client = TestClient::new(RPC);
+        let (nam, btc) = (nam(), btc());
+        let mut ctx = context_with_notes(&[
+            (&nam, 5),
+            (&nam, 30),
+            (&nam, 10),
+            (&nam, 40),
+            (&nam, 30),
+            (&btc, 100),
+            (&nam, 25),
+        ]);
+        ctx.spents.insert(6);
+        let notes = (0..7).collect::<BTreeSet<_>>();
+
+        // The smallest note covering the target comes first, the lowest index
+        // winning the tie, then the others from the largest
+        let ordered = ctx
+            .order_unspent_notes(
+                &client,
+                notes.clone(),
+                &nam_target(25),
+                NoteSelectionStrategy::MinimizeChange,
+            )
+            .await
+            .unwrap();
+        assert_eq!(ordered, vec![1, 3, 4, 2, 0, 5]);
+
+        // Without a covering note, all of them come from the largest, the
+        // equal values in the reverse order of their indices
+        let ordered = ctx
+            .order_unspent_notes(
+                &client,
+                notes.clone(),
+                &nam_target(100),
+                NoteSelectionStrategy::MinimizeChange,
+            )
+            .await
+            .unwrap();
+        assert_eq!(ordered, vec![3, 4, 1, 2, 0, 5]);
+
+        // The oldest notes come first, whatever their values and tokens
+        let ordered = ctx
+            .order_unspent_notes(
+                &client,
+                notes,
+                &nam_target(25),
+                NoteSelectionStrategy::Oldest,
+            )
+            .await
+            .unwrap();
+        assert_eq!(ordered, vec![0, 1, 2, 3, 4, 5]);
+    }
+
+    /// Test that the smallest notes up to `MAX_DUST_NOTES` come first, from
+    /// the smallest with the equal values by index, then the largest ones
+    /// from the largest
+    #[cfg(feature = "std")]
+    #[tokio::test]
+    async fn test_order_unspent_notes_consolidate_dust() {
+        use namada_core::types::address::nam;
+
+        use crate::queries::testing::TestClient;
+        use crate::queries::RPC;
+
+        let client = TestClient::new(RPC);
+        let nam = nam();
+        // The values decrease with the index, and the last note has the same
+        // value as the one before it
+        let count = MAX_DUST_NOTES as u64 + 2;
+        let notes = (0..count)
+            .map(|idx| (&nam, count - idx))
+            .chain([(&nam, 1)])
+            .collect::<Vec<_>>();
+        let mut ctx = context_with_notes(&notes);
+
+        let ordered = ctx
+            .order_unspent_notes(
+                &client,
+                (0..notes.len()).collect(),
+                &nam_target(10),
+                NoteSelectionStrategy::ConsolidateDust,
+            )
+            .await
+            .unwrap();
+        let dust = [count as usize - 1, count as usize]
+            .into_iter()
+            .chain((3..count as usize - 1).rev());
+        let largest = 0..3;
+        assert_eq!(ordered, dust.chain(largest).collect::<Vec<_>>());
+    }
 }
 
 #[cfg(any(test, feature = "testing"))]
@@ -2894,7 +4314,7 @@ pub mod testing {
     // An implementation of TxProver that does everything except generating
     // valid zero-knowledge proofs. Uses the supplied source of randomness to
     // carry out its operations.
-    pub struct MockTxProver<R: RngCore>(Mutex<R>);
+    pub struct MockTxProver<R: RngCore>(pub Mutex<R>);
 
     impl<R: RngCore> TxProver for MockTxProver<R> {
         type SaplingProvingContext = SaplingProvingContext;
@@ -3511,11 +4931,470 @@ pub mod testing {
     }
 }
 
+/// Services generating the proofs of shielded transactions in the background so
+/// that the callers building shielded transactions, e.g. wallet UIs, are not
+/// blocked. Only the local proving is provided here, other provers can be
+/// plugged in by implementing [`ProofGenerator`].
+pub mod prover {
+    use std::num::NonZeroUsize;
+    use std::sync::atomic::{self, AtomicBool};
+    #[cfg(not(target_family = "wasm"))]
+    use std::sync::mpsc;
+    use std::sync::{Arc, Mutex};
+    use std::task::{Poll, Waker};
+
+    #[cfg(not(target_family = "wasm"))]
+    use futures::future;
+    #[cfg(not(target_family = "wasm"))]
+    use futures::StreamExt;
+    use masp_primitives::sapling::prover::TxProver;
+
+    use super::*;
+
+    /// The progress of a proof generation job
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum ProofProgress {
+        /// The job is waiting for a free proving worker
+        Queued,
+        /// The proofs of the transaction are being generated
+        Started,
+        /// All the proofs of the transaction have been generated
+        Finished,
+    }
+
+    #[derive(Debug, Default)]
+    struct Cancellation {
+        cancelled: AtomicBool,
+        // The tasks waiting for the cancellation
+        wakers: Mutex<Vec<Waker>>,
+    }
+
+    /// A token to cancel a proof generation job. Clones of a token are
+    /// cancelled together.
+    #[derive(Debug, Clone, Default)]
+    pub struct CancellationToken(Arc<Cancellation>);
+
+    impl CancellationToken {
+        /// Cancel the jobs of this token
+        pub fn cancel(&self) {
+            self.0.cancelled.store(true, atomic::Ordering::SeqCst);
+            let wakers = std::mem::take(&mut *self.0.wakers.lock().unwrap());
+            for waker in wakers {
+                waker.wake();
+            }
+        }
+
+        /// Check if the jobs of this token have been cancelled
+        pub fn is_cancelled(&self) -> bool {
+            self.0.cancelled.load(atomic::Ordering::SeqCst)
+        }
+
+        /// Wait until the jobs of this token are cancelled
+        pub async fn cancelled(&self) {
+            futures::future::poll_fn(|cx| {
+                // Checked under the lock so that a concurrent cancellation
+                // either is seen here or wakes the registered waker
+                let mut wakers = self.0.wakers.lock().unwrap();
+                if self.is_cancelled() {
+                    return Poll::Ready(());
+                }
+                if !wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+                    wakers.push(cx.waker().clone());
+                }
+                Poll::Pending
+            })
+            .await
+        }
+    }
+
+    /// Errors of a proof generation job
+    #[derive(Error, Debug)]
+    pub enum ProofGenError {
+        /// The job has been cancelled
+        #[error("The proof generation has been cancelled")]
+        Cancelled,
+        /// The transaction couldn't be built
+        #[error("Failed to build the shielded transaction: {0}")]
+        Build(#[from] builder::Error<std::convert::Infallible>),
+        /// The prover couldn't complete the job
+        #[error("The prover is unavailable: {0}")]
+        Unavailable(String),
+    }
+
+    /// A service generating the proofs of shielded transactions
+    #[cfg_attr(feature = "async-send", async_trait::async_trait)]
+    #[cfg_attr(not(feature = "async-send"), async_trait::async_trait(?Send))]
+    pub trait ProofGenerator {
+        /// Generate the proofs of the transaction in the given builder and
+        /// return the built transaction. The progress of the job is reported
+        /// to the given callback. Once the given token has been cancelled,
+        /// the job fails with [`ProofGenError::Cancelled`] and its proofs are
+        /// discarded.
+        async fn prove<R, F>(
+            &self,
+            builder: Builder<TestNetwork, R>,
+            progress: F,
+            cancel: CancellationToken,
+        ) -> Result<(Transaction, SaplingMetadata), ProofGenError>
+        where
+            R: RngCore + CryptoRng + Send + 'static,
+            F: Fn(ProofProgress) + MaybeSend + MaybeSync;
+    }
+
+    /// A job run by a worker of a [`ProvingPool`]
+    #[cfg(not(target_family = "wasm"))]
+    type Job = Box<dyn FnOnce() + Send + 'static>;
+
+    /// A fixed number of threads running the submitted jobs in their order of
+    /// submission. The threads stop once the pool is dropped.
+    #[cfg(not(target_family = "wasm"))]
+    struct ProvingPool {
+        // The sender isn't `Sync` on all the supported toolchains
+        queue: Mutex<mpsc::Sender<Job>>,
+    }
+
+    #[cfg(not(target_family = "wasm"))]
+    impl ProvingPool {
+        fn new(workers: NonZeroUsize) -> Self {
+            let (sender, receiver) = mpsc::channel::<Job>();
+            let receiver = Arc::new(Mutex::new(receiver));
+            for _ in 0..workers.get() {
+                let receiver = receiver.clone();
+                std::thread::spawn(move || {
+                    // The lock is released before running the job
+                    while let Ok(job) = receiver.lock().unwrap().recv() {
+                        // A panicking job must not take its worker down
+                        let _ = std::panic::catch_unwind(
+                            std::panic::AssertUnwindSafe(job),
+                        );
+                    }
+                });
+            }
+            Self {
+                queue: Mutex::new(sender),
+            }
+        }
+
+        /// Queue the job until a worker is free
+        fn submit(&self, job: Job) -> Result<(), ProofGenError> {
+            self.queue.lock().unwrap().send(job).map_err(|_| {
+                ProofGenError::Unavailable(
+                    "The proving workers have stopped".to_string(),
+                )
+            })
+        }
+    }
+
+    /// The updates sent by a worker about its job
+    #[cfg(not(target_family = "wasm"))]
+    enum JobUpdate {
+        Started,
+        Done(Result<(Transaction, SaplingMetadata), ProofGenError>),
+    }
+
+    /// A proof generator proving with the MASP parameters available locally.
+    /// The jobs are run by a bounded pool of worker threads, except on wasm
+    /// targets where they are run in place.
+    ///
+    /// The cancellation is cooperative: the queued jobs of a cancelled token
+    /// are skipped by the workers and the callers waiting for them are
+    /// released right away, but the proofs already being generated can't be
+    /// interrupted and keep their worker busy until they're done.
+    pub struct LocalProofGenerator<P = LocalTxProver> {
+        prover: Arc<P>,
+        #[cfg(not(target_family = "wasm"))]
+        pool: Arc<ProvingPool>,
+    }
+
+    impl<P> Clone for LocalProofGenerator<P> {
+        fn clone(&self) -> Self {
+            Self {
+                prover: self.prover.clone(),
+                #[cfg(not(target_family = "wasm"))]
+                pool: self.pool.clone(),
+            }
+        }
+    }
+
+    impl<P> LocalProofGenerator<P> {
+        /// Make a proof generator from the given local prover, running at
+        /// most the given number of jobs at once
+        pub fn new(prover: P, workers: NonZeroUsize) -> Self {
+            #[cfg(target_family = "wasm")]
+            let _ = workers;
+            Self {
+                prover: Arc::new(prover),
+                #[cfg(not(target_family = "wasm"))]
+                pool: Arc::new(ProvingPool::new(workers)),
+            }
+        }
+    }
+
+    #[cfg_attr(feature = "async-send", async_trait::async_trait)]
+    #[cfg_attr(not(feature = "async-send"), async_trait::async_trait(?Send))]
+    impl<P> ProofGenerator for LocalProofGenerator<P>
+    where
+        P: TxProver + Send + Sync + 'static,
+    {
+        async fn prove<R, F>(
+            &self,
+            builder: Builder<TestNetwork, R>,
+            progress: F,
+            cancel: CancellationToken,
+        ) -> Result<(Transaction, SaplingMetadata), ProofGenError>
+        where
+            R: RngCore + CryptoRng + Send + 'static,
+            F: Fn(ProofProgress) + MaybeSend + MaybeSync,
+        {
+            if cancel.is_cancelled() {
+                return Err(ProofGenError::Cancelled);
+            }
+            let prover = self.prover.clone();
+            #[cfg(not(target_family = "wasm"))]
+            let built = {
+                let (sender, mut updates) = futures::channel::mpsc::unbounded();
+                let token = cancel.clone();
+                self.pool.submit(Box::new(move || {
+                    // The receiver is gone if the job has been dropped
+                    if token.is_cancelled() {
+                        let _ = sender.unbounded_send(JobUpdate::Done(Err(
+                            ProofGenError::Cancelled,
+                        )));
+                        return;
+                    }
+                    let _ = sender.unbounded_send(JobUpdate::Started);
+                    let built = builder.build(
+                        prover.as_ref(),
+                        &FeeRule::non_standard(U64Sum::zero()),
+                    );
+                    let _ = sender.unbounded_send(JobUpdate::Done(
+                        built.map_err(Into::into),
+                    ));
+                }))?;
+                progress(ProofProgress::Queued);
+                let cancelled = cancel.cancelled();
+                futures::pin_mut!(cancelled);
+                loop {
+                    let update = match future::select(
+                        updates.next(),
+                        cancelled.as_mut(),
+                    )
+                    .await
+                    {
+                        future::Either::Left((update, _)) => update,
+                        future::Either::Right(_) => {
+                            return Err(ProofGenError::Cancelled);
+                        }
+                    };
+                    match update {
+                        Some(JobUpdate::Started) => {
+                            progress(ProofProgress::Started)
+                        }
+                        Some(JobUpdate::Done(built)) => break built,
+                        None => {
+                            return Err(ProofGenError::Unavailable(
+                                "The proving worker has stopped".to_string(),
+                            ));
+                        }
+                    }
+                }
+            };
+            #[cfg(target_family = "wasm")]
+            let built = {
+                progress(ProofProgress::Started);
+                builder
+                    .build(
+                        prover.as_ref(),
+                        &FeeRule::non_standard(U64Sum::zero()),
+                    )
+                    .map_err(Into::into)
+            };
+            if cancel.is_cancelled() {
+                return Err(ProofGenError::Cancelled);
+            }
+            let built = built?;
+            progress(ProofProgress::Finished);
+            Ok(built)
+        }
+    }
+
+    #[cfg(test)]
+    mod test {
+        use std::sync::atomic::AtomicUsize;
+        use std::time::Duration;
+
+        use masp_primitives::consensus::BlockHeight as MaspHeight;
+
+        use super::*;
+        use crate::masp::testing::MockTxProver;
+
+        fn generator(
+            workers: usize,
+        ) -> LocalProofGenerator<MockTxProver<OsRng>> {
+            LocalProofGenerator::new(
+                MockTxProver(Mutex::new(OsRng)),
+                NonZeroUsize::new(workers).unwrap(),
+            )
+        }
+
+        /// A builder moving a transparent input to a shielded output
+        fn builder() -> Builder<TestNetwork, OsRng> {
+            let asset_type = AssetType::new(b"prover").unwrap();
+            let target =
+                ExtendedSpendingKey::master(&[0; 32]).default_address().1;
+            let mut builder = Builder::<TestNetwork, _>::new_with_rng(
+                TestNetwork,
+                MaspHeight::from_u32(1_000),
+                OsRng,
+            );
+            builder
+                .add_transparent_input(TxOut {
+                    asset_type,
+                    value: 10,
+                    address: TransparentAddress([0; 20]),
+                })
+                .unwrap();
+            builder
+                .add_sapling_output(
+                    None,
+                    target,
+                    asset_type,
+                    10,
+                    MemoBytes::empty(),
+                )
+                .unwrap();
+            builder
+        }
+
+        /// Occupy the only worker of the generator until the returned sender
+        /// is used or dropped
+        fn block_worker(
+            generator: &LocalProofGenerator<MockTxProver<OsRng>>,
+        ) -> mpsc::Sender<()> {
+            let (gate, blocked) = mpsc::channel();
+            let (started, running) = mpsc::channel();
+            generator
+                .pool
+                .submit(Box::new(move || {
+                    started.send(()).unwrap();
+                    let _ = blocked.recv();
+                }))
+                .unwrap();
+            running.recv().unwrap();
+            gate
+        }
+
+        /// Test that a proving job reports its progress and returns the
+        /// built transaction
+        #[tokio::test]
+        async fn test_prove() {
+            let seen = Mutex::new(vec![]);
+            let (tx, _metadata) = generator(1)
+                .prove(
+                    builder(),
+                    |p| seen.lock().unwrap().push(p),
+                    CancellationToken::default(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(tx.sapling_bundle().unwrap().shielded_outputs.len(), 1);
+            assert_eq!(
+                seen.into_inner().unwrap(),
+                vec![
+                    ProofProgress::Queued,
+                    ProofProgress::Started,
+                    ProofProgress::Finished
+                ]
+            );
+        }
+
+        /// Test that the cancelled jobs fail without waiting for a worker
+        #[tokio::test]
+        async fn test_prove_cancelled() {
+            let generator = generator(1);
+
+            // A job cancelled before its submission is never queued
+            let cancel = CancellationToken::default();
+            cancel.cancel();
+            let seen = Mutex::new(vec![]);
+            let res = generator
+                .prove(builder(), |p| seen.lock().unwrap().push(p), cancel)
+                .await;
+            assert!(matches!(res, Err(ProofGenError::Cancelled)));
+            assert!(seen.lock().unwrap().is_empty());
+
+            // A queued job is released as soon as it's cancelled, while the
+            // only worker is still busy
+            let gate = block_worker(&generator);
+            let cancel = CancellationToken::default();
+            let token = cancel.clone();
+            let seen = Mutex::new(vec![]);
+            let res = generator
+                .prove(
+                    builder(),
+                    |p| {
+                        seen.lock().unwrap().push(p);
+                        if p == ProofProgress::Queued {
+                            token.cancel();
+                        }
+                    },
+                    cancel,
+                )
+                .await;
+            assert!(matches!(res, Err(ProofGenError::Cancelled)));
+            assert_eq!(seen.into_inner().unwrap(), vec![ProofProgress::Queued]);
+
+            // The worker skips the cancelled job and runs the next one
+            gate.send(()).unwrap();
+            let seen = Mutex::new(vec![]);
+            generator
+                .prove(
+                    builder(),
+                    |p| seen.lock().unwrap().push(p),
+                    CancellationToken::default(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(seen.into_inner().unwrap().len(), 3);
+        }
+
+        /// Test that the pool runs at most as many jobs at once as it has
+        /// workers and survives the panicking jobs
+        #[test]
+        fn test_proving_pool() {
+            let pool = ProvingPool::new(NonZeroUsize::new(2).unwrap());
+            let active = Arc::new(AtomicUsize::new(0));
+            let max_active = Arc::new(AtomicUsize::new(0));
+            let (done, finished) = mpsc::channel();
+            pool.submit(Box::new(|| panic!("A failing job"))).unwrap();
+            for _ in 0..6 {
+                let active = active.clone();
+                let max_active = max_active.clone();
+                let done = done.clone();
+                pool.submit(Box::new(move || {
+                    let now = active.fetch_add(1, atomic::Ordering::SeqCst);
+                    max_active.fetch_max(now + 1, atomic::Ordering::SeqCst);
+                    std::thread::sleep(Duration::from_millis(20));
+                    active.fetch_sub(1, atomic::Ordering::SeqCst);
+                    done.send(()).unwrap();
+                }))
+                .unwrap();
+            }
+            for _ in 0..6 {
+                finished.recv_timeout(Duration::from_secs(10)).unwrap();
+            }
+            let max_active = max_active.load(atomic::Ordering::SeqCst);
+            assert!((1..=2).contains(&max_active));
+        }
+    }
+}
+
 #[cfg(feature = "std")]
 /// Implementation of MASP functionality depending on a standard filesystem
 pub mod fs {
     use std::fs::{File, OpenOptions};
     use std::io::{Read, Write};
+    use std::path::Path;
 
     use super::*;
 
@@ -3638,4 +5517,32 @@ pub mod fs {
             Ok(())
         }
     }
+
+    /// Export the given shielded context into a new file at the given path
+    pub fn export_shielded_context<U>(
+        ctx: &ShieldedContext<U>,
+        path: &Path,
+    ) -> std::io::Result<()>
+    where
+        U: ShieldedUtils + MaybeSend + MaybeSync,
+    {
+        let mut file =
+            OpenOptions::new().write(true).create_new(true).open(path)?;
+        file.write_all(&ctx.export())
+    }
+
+    /// Replace the state of the given shielded context with the one exported
+    /// into the file at the given path
+    pub fn import_shielded_context<U>(
+        ctx: &mut ShieldedContext<U>,
+        path: &Path,
+    ) -> std::io::Result<()>
+    where
+        U: ShieldedUtils + MaybeSend + MaybeSync,
+    {
+        let bytes = std::fs::read(path)?;
+        ctx.import(&bytes).map_err(|err| {
+            std::io::Error::new(std::io::ErrorKind::InvalidData, err)
+        })
+    }
 }
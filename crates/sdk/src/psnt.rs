@@ -0,0 +1,347 @@
+//! Partially signed transactions (PSNT), a portable format to collect the
+//! signatures of a multisig account offline.
+//!
+//! A [`PartiallySignedTx`] carries the unsigned tx along with the public keys
+//! that may sign it, the threshold to be met by their weights and the
+//! signatures collected so far. It is passed around between the signers, each
+//! adding their own signatures, or the files signed separately are merged
+//! together. Once the threshold is met, it is finalized into a tx holding the
+//! signatures of the raw header, which then only needs the signature of the
+//! fee payer to be submitted.
+
+use std::collections::BTreeMap;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use borsh_ext::BorshSerializeExt;
+use data_encoding::HEXUPPER;
+use namada_core::types::account::AccountPublicKeysMap;
+use namada_core::types::address::Address;
+use namada_core::types::hash::Hash;
+use namada_core::types::key::*;
+use namada_core::types::sign::SignatureIndex;
+use namada_tx::{Section, Signature, Signer, Tx};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::signing::SigningTxData;
+
+/// The current version of the format of the partially signed txs
+pub const PSNT_VERSION: u8 = 1;
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum PsntError {
+    #[error("Unsupported version {0} of partially signed tx")]
+    UnsupportedVersion(u8),
+    #[error("Unable to decode the partially signed tx: {0}")]
+    Decode(String),
+    #[error("The public key {0} is not a signer of the tx")]
+    UnknownSigner(common::PublicKey),
+    #[error("Invalid signature of the public key {0}")]
+    InvalidSignature(common::PublicKey),
+    #[error("The partially signed txs are for different txs or signers")]
+    Mismatch,
+    #[error("The signature threshold is not met: {weight} < {threshold}")]
+    ThresholdNotMet { weight: u64, threshold: u8 },
+}
+
+/// A tx along with the signatures of its raw header collected so far from
+/// its signers
+#[derive(
+    Clone, Debug, BorshSerialize, BorshDeserialize, Serialize, Deserialize,
+)]
+pub struct PartiallySignedTx {
+    /// The version of the format
+    pub version: u8,
+    /// The unsigned tx
+    pub tx: Tx,
+    /// The account owning the tx, signed for by its public keys. Without an
+    /// owner, each key signs for itself.
+    pub owner: Option<Address>,
+    /// The public keys that may sign the tx, by their index
+    pub signers: AccountPublicKeysMap,
+    /// The weight of signatures required to finalize the tx
+    pub threshold: u8,
+    /// The signatures collected so far, by the index of their public key
+    pub signatures: BTreeMap<u8, SignatureIndex>,
+}
+
+impl PartiallySignedTx {
+    /// Create a partially signed tx without signatures
+    pub fn new(
+        tx: Tx,
+        owner: Option<Address>,
+        signers: AccountPublicKeysMap,
+        threshold: u8,
+    ) -> Self {
+        Self {
+            version: PSNT_VERSION,
+            tx,
+            owner,
+            signers,
+            threshold,
+            signatures: BTreeMap::new(),
+        }
+    }
+
+    /// Create a partially signed tx without signatures from the signing data
+    /// of the tx
+    pub fn from_signing_data(tx: Tx, signing_data: &SigningTxData) -> Self {
+        let signers = signing_data
+            .account_public_keys_map
+            .clone()
+            .unwrap_or_else(|| {
+                signing_data.public_keys.iter().cloned().collect()
+            });
+        Self::new(
+            tx,
+            signing_data.owner.clone(),
+            signers,
+            signing_data.threshold,
+        )
+    }
+
+    /// The section committing to the raw header of the tx, whose hash is
+    /// signed by the given public key
+    fn section(&self, public_key: &common::PublicKey) -> Signature {
+        let signer = match &self.owner {
+            Some(owner) => Signer::Address(owner.clone()),
+            None => Signer::PubKeys(vec![public_key.clone()]),
+        };
+        Signature {
+            targets: vec![self.tx.raw_header_hash()],
+            signer,
+            signatures: BTreeMap::new(),
+        }
+    }
+
+    /// The hash of the raw header of the tx to be signed by the given public
+    /// key
+    pub fn signing_hash(&self, public_key: &common::PublicKey) -> Hash {
+        self.section(public_key).get_raw_hash()
+    }
+
+    /// Sign the tx with the given secret keys. The keys that are not signers
+    /// of the tx are ignored. Returns the number of signatures added.
+    pub fn sign(&mut self, secret_keys: &[common::SecretKey]) -> usize {
+        let mut added = 0;
+        for secret_key in secret_keys {
+            let public_key = secret_key.ref_to();
+            let Some(index) =
+                self.signers.get_index_from_public_key(&public_key)
+            else {
+                continue;
+            };
+            let signature = common::SigScheme::sign(
+                secret_key,
+                self.signing_hash(&public_key),
+            );
+            self.signatures.insert(
+                index,
+                SignatureIndex {
+                    pubkey: public_key,
+                    index: self.owner.clone().map(|owner| (owner, index)),
+                    signature,
+                },
+            );
+            added += 1;
+        }
+        added
+    }
+
+    /// Add a signature made offline, e.g. by a hardware wallet. The signature
+    /// is verified before it's added.
+    pub fn add_signature(
+        &mut self,
+        signature: SignatureIndex,
+    ) -> Result<(), PsntError> {
+        let index = self
+            .signers
+            .get_index_from_public_key(&signature.pubkey)
+            .ok_or_else(|| {
+            PsntError::UnknownSigner(signature.pubkey.clone())
+        })?;
+        let expected_index = self.owner.clone().map(|owner| (owner, index));
+        if signature.index != expected_index {
+            return Err(PsntError::InvalidSignature(signature.pubkey));
+        }
+        common::SigScheme::verify_signature(
+            &signature.pubkey,
+            &self.signing_hash(&signature.pubkey),
+            &signature.signature,
+        )
+        .map_err(|_| PsntError::InvalidSignature(signature.pubkey.clone()))?;
+        self.signatures.insert(index, signature);
+        Ok(())
+    }
+
+    /// Merge the signatures collected in another partially signed tx of the
+    /// same tx and signers
+    pub fn merge(&mut self, other: PartiallySignedTx) -> Result<(), PsntError> {
+        if self.tx.raw_header_hash() != other.tx.raw_header_hash()
+            || self.owner != other.owner
+            || self.threshold != other.threshold
+        {
+            return Err(PsntError::Mismatch);
+        }
+        for signature in other.signatures.into_values() {
+            self.add_signature(signature)?;
+        }
+        Ok(())
+    }
+
+    /// The total weight of the signatures collected so far
+    pub fn signed_weight(&self) -> u64 {
+        self.signers.total_weight(self.signatures.keys())
+    }
+
+    /// Check if the collected signatures meet the threshold
+    pub fn is_complete(&self) -> bool {
+        self.signed_weight() >= self.threshold.into()
+    }
+
+    /// The public keys of the signers that haven't signed yet
+    pub fn missing_signers(&self) -> Vec<common::PublicKey> {
+        let mut missing: Vec<_> = self
+            .signers
+            .idx_to_pk
+            .iter()
+            .filter(|(index, _)| !self.signatures.contains_key(index))
+            .collect();
+        missing.sort_unstable_by_key(|(index, _)| **index);
+        missing.into_iter().map(|(_, pk)| pk.clone()).collect()
+    }
+
+    /// Finalize the tx by adding the collected signatures to it, if they meet
+    /// the threshold
+    pub fn finalize(self) -> Result<Tx, PsntError> {
+        if !self.is_complete() {
+            return Err(PsntError::ThresholdNotMet {
+                weight: self.signed_weight(),
+                threshold: self.threshold,
+            });
+        }
+        let mut tx = self.tx.clone();
+        tx.protocol_filter();
+        let sections: Vec<Signature> = match &self.owner {
+            // All the keys sign for the owner under a single section
+            Some(_) => self
+                .signatures
+                .values()
+                .next()
+                .map(|first| {
+                    let mut section = self.section(&first.pubkey);
+                    section.signatures = self
+                        .signatures
+                        .iter()
+                        .map(|(index, signature)| {
+                            (*index, signature.signature.clone())
+                        })
+                        .collect();
+                    section
+                })
+                .into_iter()
+                .collect(),
+            // Each key signs for itself under its own section
+            None => self
+                .signatures
+                .values()
+                .map(|signature| {
+                    let mut section = self.section(&signature.pubkey);
+                    section.signatures.insert(0, signature.signature.clone());
+                    section
+                })
+                .collect(),
+        };
+        for section in sections {
+            tx.add_section(Section::Signature(section));
+        }
+        Ok(tx)
+    }
+
+    /// Serialize to a hex string
+    pub fn serialize(&self) -> String {
+        HEXUPPER.encode(&self.serialize_to_vec())
+    }
+
+    /// Deserialize from a hex string in JSON, as written to files
+    pub fn deserialize(data: &[u8]) -> Result<Self, PsntError> {
+        let hex = serde_json::from_slice::<String>(data)
+            .map_err(|err| PsntError::Decode(err.to_string()))?;
+        let bytes = HEXUPPER
+            .decode(hex.as_bytes())
+            .map_err(|err| PsntError::Decode(err.to_string()))?;
+        let psnt = Self::try_from_slice(&bytes)
+            .map_err(|err| PsntError::Decode(err.to_string()))?;
+        if psnt.version != PSNT_VERSION {
+            return Err(PsntError::UnsupportedVersion(psnt.version));
+        }
+        Ok(psnt)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use namada_core::types::address::testing::established_address_1;
+    use namada_core::types::chain::ChainId;
+    use namada_core::types::key::testing::{
+        keypair_1, keypair_2, keypair_3, keypair_4,
+    };
+    use namada_tx::data::TxType;
+
+    use super::*;
+
+    #[test]
+    fn test_multisig_psnt() {
+        let keys = [keypair_1(), keypair_2(), keypair_3()];
+        let signers: AccountPublicKeysMap =
+            keys.iter().map(RefTo::ref_to).collect();
+        let owner = established_address_1();
+        let mut tx = Tx::from_type(TxType::Raw);
+        tx.header.chain_id = ChainId("test-chain".to_string());
+        let psnt =
+            PartiallySignedTx::new(tx, Some(owner.clone()), signers.clone(), 2);
+
+        // Two signers sign their own copy, with a key that isn't a signer
+        // ignored
+        let mut first = psnt.clone();
+        assert_eq!(first.sign(&[keys[0].clone(), keypair_4()]), 1);
+        assert!(!first.is_complete());
+        assert!(matches!(
+            first.clone().finalize(),
+            Err(PsntError::ThresholdNotMet {
+                weight: 1,
+                threshold: 2
+            })
+        ));
+        let mut second = PartiallySignedTx::deserialize(
+            &serde_json::to_vec(&psnt.serialize()).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(second.sign(&[keys[2].clone()]), 1);
+
+        // The copies are merged, and the signature of a different signer is
+        // rejected
+        first.merge(second.clone()).unwrap();
+        assert!(first.is_complete());
+        assert_eq!(first.missing_signers(), vec![keys[1].ref_to()]);
+        let mut forged = second.signatures[&2].clone();
+        forged.pubkey = keys[1].ref_to();
+        assert!(matches!(
+            first.add_signature(forged),
+            Err(PsntError::InvalidSignature(_))
+        ));
+
+        // The finalized tx holds the signatures of the raw header
+        let tx = first.finalize().unwrap();
+        tx.verify_signatures(
+            &[tx.raw_header_hash()],
+            signers,
+            &Some(owner),
+            2,
+            None,
+            || Ok(()),
+        )
+        .unwrap();
+    }
+}
@@ -12,10 +12,14 @@ pub use types::{
 };
 use vp::{Vp, VP};
 
+pub use self::shell::batch::{
+    BatchQuery, BatchResponse, BatchResult, MAX_QUERIES_PER_BATCH,
+};
 pub use self::shell::eth_bridge::{
     Erc20FlowControl, GenBridgePoolProofReq, GenBridgePoolProofRsp,
     TransferToErcArgs, TransferToEthereumStatus,
 };
+pub use self::shell::{ChainFeatures, ChainInfo};
 use crate::MaybeSend;
 
 #[macro_use]
@@ -71,6 +75,49 @@ where
     Ok(())
 }
 
+/// For queries that support arbitrary heights, get the height of the state to
+/// be queried, where `0` means the latest committed height. The height must
+/// have been committed and it mustn't be older than the configured
+/// `storage_read_past_height_limit`.
+pub fn queried_height<D, H, V, T>(
+    ctx: &RequestCtx<'_, D, H, V, T>,
+    request: &RequestQuery,
+) -> namada_storage::Result<BlockHeight>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    let last_committed_height = ctx.wl_storage.storage.get_last_block_height();
+    let height: BlockHeight = request.height.into();
+    let is_last_height_query = height.0 == 0;
+    if namada_core::hints::likely(is_last_height_query) {
+        return Ok(last_committed_height);
+    }
+
+    if height > last_committed_height {
+        return Err(namada_storage::Error::new(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!(
+                "The block height {height} hasn't been committed yet, the \
+                 latest committed block height is {last_committed_height}."
+            ),
+        )));
+    }
+    if let Some(past_height_limit) = ctx.storage_read_past_height_limit {
+        if height + past_height_limit < last_committed_height {
+            return Err(namada_storage::Error::new(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "Cannot query more than {past_height_limit} blocks in the \
+                     past (configured via \
+                     `shell.storage_read_past_height_limit`)."
+                ),
+            )));
+        }
+    }
+    Ok(height)
+}
+
 /// For queries that do not support proofs, check that proof is not requested,
 /// otherwise return an error.
 pub fn require_no_proof(request: &RequestQuery) -> namada_storage::Result<()> {
@@ -95,7 +142,7 @@ pub fn require_no_data(request: &RequestQuery) -> namada_storage::Result<()> {
 
 /// Queries testing helpers
 #[cfg(any(test, feature = "testing"))]
-mod testing {
+pub(crate) mod testing {
 
     use namada_core::types::storage::BlockHeight;
     use namada_state::testing::TestWlStorage;
@@ -1,36 +1,47 @@
 use std::collections::BTreeMap;
 
+pub(super) mod batch;
 pub(super) mod eth_bridge;
 
-use borsh::BorshDeserialize;
+use borsh::{BorshDeserialize, BorshSerialize};
 use borsh_ext::BorshSerializeExt;
 use masp_primitives::asset_type::AssetType;
 use masp_primitives::merkle_tree::MerklePath;
-use masp_primitives::sapling::Node;
+use masp_primitives::sapling::{Node, Nullifier};
 use namada_account::{Account, AccountPublicKeysMap};
-use namada_core::hints;
 use namada_core::types::address::Address;
+use namada_core::types::chain::ChainId;
 use namada_core::types::dec::Dec;
 use namada_core::types::hash::Hash;
+use namada_core::types::parameters::{EpochDuration, InflationSplit};
 use namada_core::types::storage::{
-    self, BlockHeight, BlockResults, Epoch, KeySeg, PrefixValue,
+    self, BlockHeight, BlockResults, Epoch, KeySeg, PrefixPage,
+    PrefixPageRequest, PrefixValue,
 };
-use namada_core::types::token::{Denomination, MaspDigitPos};
+use namada_core::types::token::{Amount, Denomination, MaspDigitPos};
 use namada_core::types::uint::Uint;
-use namada_state::{DBIter, LastBlock, StorageHasher, DB};
+use namada_ethereum_bridge::storage::eth_bridge_queries::EthBridgeQueries;
+use namada_state::account_history::{read_account_history, AccountHistoryPage};
+use namada_state::{DBIter, HistoricStorage, LastBlock, StorageHasher, DB};
 use namada_storage::{self, ResultExt, StorageRead};
+use namada_token::compact::{
+    read_compact_block, CompactBlock, MAX_COMPACT_BLOCKS_PER_QUERY,
+};
+use namada_token::utils::{is_nullifier_revealed, MAX_NULLIFIERS_PER_QUERY};
 #[cfg(any(test, feature = "async-client"))]
-use namada_tx::data::TxResult;
+use namada_tx::data::DryRunResult;
+use regex::Regex;
 
+use self::batch::{BatchQuery, BatchResponse, MAX_QUERIES_PER_BATCH};
 use self::eth_bridge::{EthBridge, ETH_BRIDGE};
 use crate::events::log::dumb_queries;
 use crate::events::{Event, EventType};
 use crate::ibc::core::host::types::identifiers::{
     ChannelId, ClientId, PortId, Sequence,
 };
-use crate::masp::MaspTokenRewardData;
+use crate::masp::{MaspTokenRewardData, MaspTokenRewards};
 use crate::queries::types::{RequestCtx, RequestQuery};
-use crate::queries::{require_latest_height, EncodedResponseQuery};
+use crate::queries::{queried_height, require_no_proof, EncodedResponseQuery};
 use crate::tendermint::merkle::proof::ProofOps;
 
 type ConversionWithoutPath = (
@@ -50,6 +61,54 @@ type Conversion = (
     MerklePath<Node>,
 );
 
+/// The current inflation split, if any, and its past values indexed by the
+/// epoch they were set at
+type InflationSplits =
+    (Option<InflationSplit>, BTreeMap<Epoch, InflationSplit>);
+
+/// The metadata of the chain, for the clients to configure themselves instead
+/// of hard-coding the constants of every network
+#[derive(Clone, Debug, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct ChainInfo {
+    /// The ID of the chain
+    pub chain_id: ChainId,
+    /// The version of the protocol implemented by the queried node
+    pub protocol_version: String,
+    /// The address of the native token
+    pub native_token: Address,
+    /// The minimum duration of an epoch
+    pub epoch_duration: EpochDuration,
+    /// The maximum size of a tx, in bytes
+    pub max_tx_bytes: u32,
+    /// The maximum gas of a block
+    pub max_block_gas: u64,
+    /// The gas limit of the fee unshielding
+    pub fee_unshielding_gas_limit: u64,
+    /// The minimum gas price of the tokens allowed to pay the fees
+    pub minimum_gas_price: BTreeMap<Address, Amount>,
+    /// The optional features that are enabled
+    pub features: ChainFeatures,
+}
+
+/// The optional features of the chain and of the node serving the queries
+#[derive(
+    Clone, Debug, Default, PartialEq, Eq, BorshSerialize, BorshDeserialize,
+)]
+pub struct ChainFeatures {
+    /// Whether the Ethereum bridge is active
+    pub eth_bridge: bool,
+    /// Whether the node indexes the history of the accounts
+    pub account_history: bool,
+}
+
+/// The maximum number of values returned by a storage prefix query. Larger
+/// prefixes have to be paged through with the `prefix_page` query.
+pub const MAX_PREFIX_VALUES_PER_QUERY: usize = 1000;
+
+/// The maximum number of blocks searched by a query of the blocks that changed
+/// a key
+pub const MAX_KEY_CHANGE_BLOCKS_PER_QUERY: u64 = 10_000;
+
 router! {SHELL,
     // Shell provides storage read access, block metadata and can dry-run a tx
 
@@ -62,6 +121,9 @@ router! {SHELL,
     // The address of the native token
     ( "native_token" ) -> Address = native_token,
 
+    // The metadata of the chain needed to configure a client
+    ( "chain_info" ) -> ChainInfo = chain_info,
+
     // Epoch of the input block height
     ( "epoch_at_height" / [height: BlockHeight]) -> Option<Epoch> = epoch_at_height,
 
@@ -73,12 +135,21 @@ router! {SHELL,
         -> Vec<u8> = (with_options storage_value),
 
     // Dry run a transaction
-    ( "dry_run_tx" ) -> TxResult = (with_options dry_run_tx),
+    ( "dry_run_tx" ) -> DryRunResult = (with_options dry_run_tx),
 
     // Raw storage access - prefix iterator
     ( "prefix" / [storage_key: storage::Key] )
         -> Vec<PrefixValue> = (with_options storage_prefix),
 
+    // Raw storage access - a page of a prefix iterator, requested with
+    // `PrefixPageRequest` in the request data
+    ( "prefix_page" / [storage_key: storage::Key] )
+        -> PrefixPage = (with_options storage_prefix_page),
+
+    // A batch of the queries given in the request data, evaluated at the same
+    // height
+    ( "batch" ) -> BatchResponse = (with_options batch),
+
     // Raw storage access - is given storage key present?
     ( "has_key" / [storage_key: storage::Key] )
         -> bool = storage_has_key,
@@ -92,6 +163,19 @@ router! {SHELL,
     // Conversion state access - read conversion
     ( "masp_reward_tokens" ) -> Vec<MaspTokenRewardData> = masp_reward_tokens,
 
+    // The current shielded rewards of the tokens that earn masp rewards
+    ( "masp_rewards" ) -> Vec<MaspTokenRewards> = masp_rewards,
+
+    // Has the given MASP nullifier been revealed?
+    ( "masp_nullifier" / [nullifier: Hash] ) -> bool = masp_nullifier,
+
+    // Have the MASP nullifiers given in the request data been revealed?
+    ( "masp_nullifiers" ) -> Vec<bool> = (with_options masp_nullifiers),
+
+    // MASP compact blocks in the given range of heights
+    ( "masp_compact_blocks" / [from: BlockHeight] / [to: BlockHeight] )
+        -> Vec<CompactBlock> = masp_compact_blocks,
+
     // Block results access - read bit-vec
     ( "results" ) -> Vec<BlockResults> = read_results,
 
@@ -101,12 +185,29 @@ router! {SHELL,
     // was the transaction applied?
     ( "applied" / [tx_hash: Hash] ) -> Option<Event> = applied,
 
+    // The current and past routings of the epoch inflation
+    ( "inflation_split" ) -> InflationSplits = inflation_split,
+
+    // Lifecycle events of a governance proposal still held in the event log
+    ( "proposal_events" / [proposal_id: u64] ) -> Vec<Event> = proposal_events,
+
     // Query account subspace
     ( "account" / [owner: Address] ) -> Option<Account> = account,
 
     // Query public key revealad
     ( "revealed" / [owner: Address] ) -> bool = revealed,
 
+    // The number of bytes of the subspace values of the address
+    ( "storage_usage" / [address: Address] ) -> u64 = storage_usage,
+
+    // The heights of the blocks in the given range that changed the key
+    ( "blocks_changing_key" / [storage_key: storage::Key] / [from: BlockHeight] / [to: BlockHeight] )
+        -> Vec<BlockHeight> = blocks_changing_key,
+
+    // A page of the txs in which the address appeared, if indexed by the node
+    ( "account_history" / [address: Address] / [page: u64] )
+        -> AccountHistoryPage = account_history,
+
     // IBC UpdateClient event
     ( "ibc_client_update" / [client_id: ClientId] / [consensus_height: BlockHeight] ) -> Option<Event> = ibc_client_update,
 
@@ -221,6 +322,125 @@ where
     }
 }
 
+/// Query to read the MASP compact blocks in the given inclusive range of
+/// heights. The blocks without MASP transactions are skipped.
+fn masp_compact_blocks<D, H, V, T>(
+    ctx: RequestCtx<'_, D, H, V, T>,
+    from: BlockHeight,
+    to: BlockHeight,
+) -> namada_storage::Result<Vec<CompactBlock>>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    if from > to {
+        return Err(namada_storage::Error::new(format!(
+            "Invalid range of heights: {from} is greater than {to}"
+        )));
+    }
+    if to.0 - from.0 >= MAX_COMPACT_BLOCKS_PER_QUERY {
+        return Err(namada_storage::Error::new(format!(
+            "At most {MAX_COMPACT_BLOCKS_PER_QUERY} blocks can be queried at \
+             once"
+        )));
+    }
+    let mut blocks = vec![];
+    for height in from.0..=to.0 {
+        if let Some(block) =
+            read_compact_block(ctx.wl_storage, BlockHeight(height))?
+        {
+            blocks.push(block);
+        }
+    }
+    Ok(blocks)
+}
+
+/// Query to check if the given MASP nullifier has been revealed, i.e. if the
+/// note it belongs to has already been spent.
+fn masp_nullifier<D, H, V, T>(
+    ctx: RequestCtx<'_, D, H, V, T>,
+    nullifier: Hash,
+) -> namada_storage::Result<bool>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    is_nullifier_revealed(ctx.wl_storage, &Nullifier(nullifier.0))
+}
+
+/// Query to check which of the MASP nullifiers encoded in the request data
+/// have been revealed. The results are in the order of the given nullifiers.
+fn masp_nullifiers<D, H, V, T>(
+    ctx: RequestCtx<'_, D, H, V, T>,
+    request: &RequestQuery,
+) -> namada_storage::Result<EncodedResponseQuery>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    let nullifiers =
+        Vec::<Hash>::try_from_slice(&request.data).into_storage_result()?;
+    if nullifiers.len() > MAX_NULLIFIERS_PER_QUERY {
+        return Err(namada_storage::Error::new(format!(
+            "At most {MAX_NULLIFIERS_PER_QUERY} nullifiers can be queried at \
+             once"
+        )));
+    }
+    let revealed = nullifiers
+        .into_iter()
+        .map(|nullifier| {
+            is_nullifier_revealed(ctx.wl_storage, &Nullifier(nullifier.0))
+        })
+        .collect::<namada_storage::Result<Vec<bool>>>()?;
+    Ok(EncodedResponseQuery {
+        data: revealed.serialize_to_vec(),
+        ..Default::default()
+    })
+}
+
+/// Evaluate the batch of queries given in the request data against the storage
+/// of the queried height
+fn batch<D, H, V, T>(
+    ctx: RequestCtx<'_, D, H, V, T>,
+    request: &RequestQuery,
+) -> namada_storage::Result<EncodedResponseQuery>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    require_no_proof(request)?;
+    let queries = Vec::<BatchQuery>::try_from_slice(&request.data)
+        .into_storage_result()?;
+    if queries.len() > MAX_QUERIES_PER_BATCH {
+        return Err(namada_storage::Error::new(format!(
+            "At most {MAX_QUERIES_PER_BATCH} queries can be batched"
+        )));
+    }
+    let height = queried_height(&ctx, request)?;
+    let storage = HistoricStorage::new(&ctx.wl_storage.storage, height)?;
+    let epoch = if height == ctx.wl_storage.storage.get_last_block_height() {
+        ctx.wl_storage.storage.last_epoch
+    } else {
+        storage.get_block_epoch()?
+    };
+    let results = queries
+        .iter()
+        .map(|query| {
+            batch::evaluate(&storage, epoch, query)
+                .map_err(|err| err.to_string())
+        })
+        .collect();
+    let response = BatchResponse {
+        height,
+        epoch,
+        results,
+    };
+    Ok(EncodedResponseQuery {
+        data: response.serialize_to_vec(),
+        ..Default::default()
+    })
+}
+
 /// Query to read the tokens that earn masp rewards.
 fn masp_reward_tokens<D, H, V, T>(
     ctx: RequestCtx<'_, D, H, V, T>,
@@ -300,6 +520,52 @@ where
     Ok(data)
 }
 
+/// Query the current shielded rewards of the tokens that earn masp rewards:
+/// their reward parameters, their last inflation and locked amount and the
+/// conversions accrued by their asset types.
+fn masp_rewards<D, H, V, T>(
+    ctx: RequestCtx<'_, D, H, V, T>,
+) -> namada_storage::Result<Vec<MaspTokenRewards>>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    let wl_storage = ctx.wl_storage;
+    let reward_tokens = masp_reward_tokens(ctx)?;
+    let mut rewards = Vec::with_capacity(reward_tokens.len());
+    for reward_data in reward_tokens {
+        let token = &reward_data.address;
+        let last_inflation = wl_storage
+            .read::<Amount>(
+                &namada_token::storage_key::masp_last_inflation_key(token),
+            )?
+            .unwrap_or_default();
+        let last_locked_amount = wl_storage
+            .read::<Amount>(
+                &namada_token::storage_key::masp_last_locked_amount_key(token),
+            )?
+            .unwrap_or_default();
+        let mut conversions: Vec<_> = wl_storage
+            .storage
+            .conversion_state
+            .assets
+            .values()
+            .filter(|((addr, _, _), _, _, _)| addr == token)
+            .map(|((_, _, digit), epoch, conv, _)| {
+                (*digit, *epoch, conv.clone().into())
+            })
+            .collect();
+        conversions.sort_by_key(|(digit, epoch, _)| (*epoch, *digit));
+        rewards.push(MaspTokenRewards {
+            reward_data,
+            last_inflation,
+            last_locked_amount,
+            conversions,
+        });
+    }
+    Ok(rewards)
+}
+
 fn epoch<D, H, V, T>(
     ctx: RequestCtx<'_, D, H, V, T>,
 ) -> namada_storage::Result<Epoch>
@@ -322,6 +588,91 @@ where
     Ok(data)
 }
 
+fn chain_info<D, H, V, T>(
+    ctx: RequestCtx<'_, D, H, V, T>,
+) -> namada_storage::Result<ChainInfo>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    let parameters = namada_parameters::read(ctx.wl_storage)?;
+    Ok(ChainInfo {
+        chain_id: ctx.wl_storage.storage.chain_id.clone(),
+        protocol_version: env!("CARGO_PKG_VERSION").to_string(),
+        native_token: ctx.wl_storage.storage.native_token.clone(),
+        epoch_duration: parameters.epoch_duration,
+        max_tx_bytes: parameters.max_tx_bytes,
+        max_block_gas: parameters.max_block_gas,
+        fee_unshielding_gas_limit: parameters.fee_unshielding_gas_limit,
+        minimum_gas_price: parameters.minimum_gas_price,
+        features: ChainFeatures {
+            eth_bridge: ctx.wl_storage.ethbridge_queries().is_bridge_active(),
+            account_history: ctx.wl_storage.storage.account_history.is_some(),
+        },
+    })
+}
+
+fn storage_usage<D, H, V, T>(
+    ctx: RequestCtx<'_, D, H, V, T>,
+    address: Address,
+) -> namada_storage::Result<u64>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    Ok(ctx.wl_storage.storage.storage_usage.get(&address))
+}
+
+/// Query to find the heights of the blocks in the given inclusive range that
+/// changed the key. The heights of the blocks whose diffs of the key are no
+/// longer kept may include a few false positives.
+fn blocks_changing_key<D, H, V, T>(
+    ctx: RequestCtx<'_, D, H, V, T>,
+    storage_key: storage::Key,
+    from: BlockHeight,
+    to: BlockHeight,
+) -> namada_storage::Result<Vec<BlockHeight>>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    if from > to {
+        return Err(namada_storage::Error::new(format!(
+            "Invalid range of heights: {from} is greater than {to}"
+        )));
+    }
+    if to.0 - from.0 >= MAX_KEY_CHANGE_BLOCKS_PER_QUERY {
+        return Err(namada_storage::Error::new(format!(
+            "At most {MAX_KEY_CHANGE_BLOCKS_PER_QUERY} blocks can be searched \
+             at once"
+        )));
+    }
+    ctx.wl_storage
+        .storage
+        .find_blocks_changing_key(&storage_key, from, to)
+        .into_storage_result()
+}
+
+/// Query a page of the history of an account, from the oldest tx. Only the
+/// nodes with the account history index enabled can answer it.
+fn account_history<D, H, V, T>(
+    ctx: RequestCtx<'_, D, H, V, T>,
+    address: Address,
+    page: u64,
+) -> namada_storage::Result<AccountHistoryPage>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    if ctx.wl_storage.storage.account_history.is_none() {
+        return Err(namada_storage::Error::new_const(
+            "The account history isn't indexed by this node",
+        ));
+    }
+    read_account_history(&ctx.wl_storage.storage.db, &address, page)
+        .into_storage_result()
+}
+
 fn epoch_at_height<D, H, V, T>(
     ctx: RequestCtx<'_, D, H, V, T>,
     height: BlockHeight,
@@ -356,29 +707,16 @@ where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
     H: 'static + StorageHasher + Sync,
 {
-    let last_committed_height = ctx.wl_storage.storage.get_last_block_height();
-    let queried_height = {
-        let height: BlockHeight = request.height.into();
-        let is_last_height_query = height.0 == 0;
-
-        if hints::likely(is_last_height_query) {
-            last_committed_height
-        } else {
-            height
-        }
-    };
+    let queried_height = queried_height(&ctx, request)?;
 
-    if let Some(past_height_limit) = ctx.storage_read_past_height_limit {
-        if queried_height + past_height_limit < last_committed_height {
-            return Err(namada_storage::Error::new(std::io::Error::new(
-                std::io::ErrorKind::InvalidInput,
-                format!(
-                    "Cannot query more than {past_height_limit} blocks in the \
-                     past (configured via \
-                     `shell.storage_read_past_height_limit`)."
-                ),
-            )));
-        }
+    // The values of the keys that are not merklized cannot be proven
+    if request.prove
+        && !(ctx.wl_storage.storage.merkle_tree_key_filter)(&storage_key)
+    {
+        return Err(namada_storage::Error::new(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("The key {storage_key} is not merklized"),
+        )));
     }
 
     match ctx
@@ -433,30 +771,17 @@ where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
     H: 'static + StorageHasher + Sync,
 {
-    require_latest_height(&ctx, request)?;
+    let queried_height = queried_height(&ctx, request)?;
 
-    let iter = namada_storage::iter_prefix_bytes(ctx.wl_storage, &storage_key)?;
-    let data: namada_storage::Result<Vec<PrefixValue>> = iter
-        .map(|iter_result| {
-            let (key, value) = iter_result?;
-            Ok(PrefixValue { key, value })
-        })
-        .collect();
-    let data = data?;
+    let last_committed_height = ctx.wl_storage.storage.get_last_block_height();
+    let data = if queried_height == last_committed_height {
+        read_prefix(ctx.wl_storage, &storage_key)?
+    } else {
+        let storage =
+            HistoricStorage::new(&ctx.wl_storage.storage, queried_height)?;
+        read_prefix(&storage, &storage_key)?
+    };
     let proof = if request.prove {
-        let queried_height = {
-            let last_committed_height =
-                ctx.wl_storage.storage.get_last_block_height();
-
-            let height: BlockHeight = request.height.into();
-            let is_last_height_query = height.0 == 0;
-
-            if hints::likely(is_last_height_query) {
-                last_committed_height
-            } else {
-                height
-            }
-        };
         let mut ops = vec![];
         for PrefixValue { key, value } in &data {
             let mut proof = ctx
@@ -480,6 +805,117 @@ where
     })
 }
 
+fn read_prefix(
+    storage: &impl StorageRead,
+    prefix: &storage::Key,
+) -> namada_storage::Result<Vec<PrefixValue>> {
+    let data = namada_storage::iter_prefix_bytes(storage, prefix)?
+        .take(MAX_PREFIX_VALUES_PER_QUERY + 1)
+        .map(|iter_result| {
+            let (key, value) = iter_result?;
+            Ok(PrefixValue { key, value })
+        })
+        .collect::<namada_storage::Result<Vec<_>>>()?;
+    if data.len() > MAX_PREFIX_VALUES_PER_QUERY {
+        return Err(namada_storage::Error::new(format!(
+            "The prefix {prefix} has more than {MAX_PREFIX_VALUES_PER_QUERY} \
+             values, use the `prefix_page` query to page through them"
+        )));
+    }
+    Ok(data)
+}
+
+fn storage_prefix_page<D, H, V, T>(
+    ctx: RequestCtx<'_, D, H, V, T>,
+    request: &RequestQuery,
+    storage_key: storage::Key,
+) -> namada_storage::Result<EncodedResponseQuery>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    require_no_proof(request)?;
+    let page_request = if request.data.is_empty() {
+        PrefixPageRequest::default()
+    } else {
+        PrefixPageRequest::try_from_slice(&request.data)
+            .into_storage_result()?
+    };
+    let key_regex = page_request
+        .key_regex
+        .as_deref()
+        .map(Regex::new)
+        .transpose()
+        .into_storage_result()?;
+    let limit = page_request
+        .limit
+        .map_or(MAX_PREFIX_VALUES_PER_QUERY, |limit| {
+            (limit as usize).clamp(1, MAX_PREFIX_VALUES_PER_QUERY)
+        });
+    let queried_height = queried_height(&ctx, request)?;
+
+    let last_committed_height = ctx.wl_storage.storage.get_last_block_height();
+    let page = if queried_height == last_committed_height {
+        read_prefix_page(
+            ctx.wl_storage,
+            &storage_key,
+            page_request.cursor.as_ref(),
+            key_regex.as_ref(),
+            limit,
+        )?
+    } else {
+        let storage =
+            HistoricStorage::new(&ctx.wl_storage.storage, queried_height)?;
+        read_prefix_page(
+            &storage,
+            &storage_key,
+            page_request.cursor.as_ref(),
+            key_regex.as_ref(),
+            limit,
+        )?
+    };
+    Ok(EncodedResponseQuery {
+        data: page.serialize_to_vec(),
+        ..Default::default()
+    })
+}
+
+/// Read at most `limit` values of the keys with the given prefix that come
+/// after the cursor and match the regex.
+fn read_prefix_page(
+    storage: &impl StorageRead,
+    prefix: &storage::Key,
+    cursor: Option<&storage::Key>,
+    key_regex: Option<&Regex>,
+    limit: usize,
+) -> namada_storage::Result<PrefixPage> {
+    // The keys are iterated in the order of their string representation
+    let cursor = cursor.map(storage::Key::to_string);
+    let mut values = vec![];
+    for iter_result in namada_storage::iter_prefix_bytes(storage, prefix)? {
+        let (key, value) = iter_result?;
+        let key_str = key.to_string();
+        if cursor.as_ref().is_some_and(|cursor| key_str <= *cursor)
+            || key_regex.is_some_and(|regex| !regex.is_match(&key_str))
+        {
+            continue;
+        }
+        if values.len() == limit {
+            // There are more values after the page
+            let next_cursor = values.last().map(|value| value.key.clone());
+            return Ok(PrefixPage {
+                values,
+                next_cursor,
+            });
+        }
+        values.push(PrefixValue { key, value });
+    }
+    Ok(PrefixPage {
+        values,
+        next_cursor: None,
+    })
+}
+
 fn storage_has_key<D, H, V, T>(
     ctx: RequestCtx<'_, D, H, V, T>,
     storage_key: storage::Key,
@@ -526,6 +962,36 @@ where
         .cloned())
 }
 
+fn inflation_split<D, H, V, T>(
+    ctx: RequestCtx<'_, D, H, V, T>,
+) -> namada_storage::Result<InflationSplits>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    let current = namada_parameters::read_inflation_split(ctx.wl_storage)?;
+    let history =
+        namada_parameters::read_inflation_split_history(ctx.wl_storage)?;
+    Ok((current, history))
+}
+
+fn proposal_events<D, H, V, T>(
+    ctx: RequestCtx<'_, D, H, V, T>,
+    proposal_id: u64,
+) -> namada_storage::Result<Vec<Event>>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    let matcher = dumb_queries::QueryMatcher::proposal_lifecycle(proposal_id);
+    // The event log iterates from the most recent event, return the events
+    // in the order they were emitted
+    let mut events: Vec<Event> =
+        ctx.event_log.iter_with_matcher(matcher).cloned().collect();
+    events.reverse();
+    Ok(events)
+}
+
 fn ibc_client_update<D, H, V, T>(
     ctx: RequestCtx<'_, D, H, V, T>,
     client_id: ClientId,
@@ -625,6 +1091,9 @@ mod test {
         let path = RPC.shell().epoch_path();
         assert_eq!("/shell/epoch", path);
 
+        let path = RPC.shell().chain_info_path();
+        assert_eq!("/shell/chain_info", path);
+
         let token_addr = address::testing::established_address_1();
         let owner = address::testing::established_address_2();
         let key = balance_key(&token_addr, &owner);
@@ -637,7 +1106,16 @@ mod test {
         let path = RPC.shell().storage_prefix_path(&key);
         assert_eq!(format!("/shell/prefix/{}", key), path);
 
+        let path = RPC.shell().storage_prefix_page_path(&key);
+        assert_eq!(format!("/shell/prefix_page/{}", key), path);
+
         let path = RPC.shell().storage_has_key_path(&key);
         assert_eq!(format!("/shell/has_key/{}", key), path);
+
+        let path = RPC.shell().masp_nullifiers_path();
+        assert_eq!("/shell/masp_nullifiers", path);
+
+        let path = RPC.shell().batch_path();
+        assert_eq!("/shell/batch", path);
     }
 }
@@ -0,0 +1,105 @@
+//! Batches of typed queries, evaluated together against the storage of the
+//! same block height and answered in one round trip.
+
+use std::collections::BTreeMap;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use namada_core::types::address::Address;
+use namada_core::types::storage::{BlockHeight, Epoch};
+use namada_core::types::token::Amount;
+use namada_governance::storage::get_proposal_by_id;
+use namada_governance::storage::proposal::StorageProposal;
+use namada_proof_of_stake::bond_amount;
+use namada_proof_of_stake::queries::find_delegations;
+use namada_proof_of_stake::types::BondId;
+use namada_storage::StorageRead;
+
+/// The maximum number of queries in a batch
+pub const MAX_QUERIES_PER_BATCH: usize = 100;
+
+/// A query of a batch
+#[derive(Clone, Debug, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub enum BatchQuery {
+    /// The balance of an owner of a token
+    Balance {
+        /// The token
+        token: Address,
+        /// The owner of the balance
+        owner: Address,
+    },
+    /// The bond of a source to a validator at the epoch of the queried
+    /// height, with the slashes applied
+    Bond {
+        /// The source of the bond
+        source: Address,
+        /// The validator
+        validator: Address,
+    },
+    /// The delegations of a delegator at the epoch of the queried height
+    Delegations {
+        /// The delegator
+        delegator: Address,
+    },
+    /// A governance proposal by its id
+    Proposal {
+        /// The id of the proposal
+        id: u64,
+    },
+}
+
+/// The result of a query of a batch, of the variant of the query
+#[derive(Clone, Debug, BorshSerialize, BorshDeserialize)]
+pub enum BatchResult {
+    /// The balance of an owner of a token
+    Balance(Amount),
+    /// The bond of a source to a validator
+    Bond(Amount),
+    /// The delegated amounts by validator
+    Delegations(BTreeMap<Address, Amount>),
+    /// The proposal, if there's one with the id
+    Proposal(Option<StorageProposal>),
+}
+
+/// The response to a batch of queries
+#[derive(Clone, Debug, BorshSerialize, BorshDeserialize)]
+pub struct BatchResponse {
+    /// The height at which all the queries were evaluated
+    pub height: BlockHeight,
+    /// The epoch of the height
+    pub epoch: Epoch,
+    /// The results of the queries in their order in the batch. A failed
+    /// query doesn't fail the others.
+    pub results: Vec<Result<BatchResult, String>>,
+}
+
+/// Evaluate a query of a batch against the storage of the queried height
+pub(super) fn evaluate<S>(
+    storage: &S,
+    epoch: Epoch,
+    query: &BatchQuery,
+) -> namada_storage::Result<BatchResult>
+where
+    S: StorageRead,
+{
+    match query {
+        BatchQuery::Balance { token, owner } => {
+            namada_token::read_balance(storage, token, owner)
+                .map(BatchResult::Balance)
+        }
+        BatchQuery::Bond { source, validator } => {
+            let bond_id = BondId {
+                source: source.clone(),
+                validator: validator.clone(),
+            };
+            bond_amount(storage, &bond_id, epoch).map(BatchResult::Bond)
+        }
+        BatchQuery::Delegations { delegator } => {
+            find_delegations(storage, delegator, &epoch).map(|delegations| {
+                BatchResult::Delegations(delegations.into_iter().collect())
+            })
+        }
+        BatchQuery::Proposal { id } => {
+            get_proposal_by_id(storage, *id).map(BatchResult::Proposal)
+        }
+    }
+}
@@ -1,9 +1,30 @@
 // cd namada && cargo expand ledger::queries::vp::governance
 
+use std::collections::HashMap;
+
+use namada_core::types::address::Address;
+use namada_core::types::dec::Dec;
+use namada_core::types::storage::{Epoch, Key};
+use namada_core::types::token::{Amount, MaspParams};
+use namada_core::types::uint::Uint;
 use namada_governance::parameters::GovernanceParameters;
-use namada_governance::storage::proposal::StorageProposal;
-use namada_governance::utils::{ProposalResult, Vote};
+use namada_governance::storage::proposal::{
+    ParameterDiff, ProposalKind, ProposalType, StorageProposal,
+};
+use namada_governance::utils::{
+    ProposalResult, ProposalTally, Vote, VoterVote, VOTER_VOTES_PAGE_SIZE,
+};
+use namada_proof_of_stake::queries::{
+    compute_proposal_votes, compute_total_voting_power, compute_voter_votes,
+};
+use namada_proof_of_stake::storage::read_pos_params;
 use namada_state::{DBIter, StorageHasher, DB};
+use namada_storage::StorageRead;
+use namada_token::read_denom;
+use namada_token::storage_key::{
+    masp_kd_gain_key, masp_kp_gain_key, masp_locked_amount_target_key,
+    masp_max_reward_rate_key,
+};
 
 use crate::queries::types::RequestCtx;
 
@@ -11,8 +32,12 @@ use crate::queries::types::RequestCtx;
 router! {GOV,
     ( "proposal" / [id: u64 ] ) -> Option<StorageProposal> = proposal_id,
     ( "proposal" / [id: u64 ] / "votes" ) -> Vec<Vote> = proposal_id_votes,
+    ( "proposal" / [id: u64 ] / "tally" ) -> Option<ProposalTally> = proposal_tally,
+    ( "proposal" / [id: u64 ] / "parameter_diff" ) -> Option<Vec<ParameterDiff>> = proposal_parameter_diff,
     ( "parameters" ) -> GovernanceParameters = parameters,
     ( "stored_proposal_result" / [id: u64] ) -> Option<ProposalResult> = proposal_result,
+    ( "vote_delegations" / [kind: ProposalKind] / [epoch: Epoch] ) -> HashMap<Address, Address> = vote_delegations,
+    ( "voter_votes" / [voter: Address] / [page: u64] ) -> Vec<VoterVote> = votes_by_voter,
 }
 
 /// Query the provided proposal id
@@ -39,6 +64,160 @@ where
     namada_governance::storage::get_proposal_votes(ctx.wl_storage, id)
 }
 
+/// Compute the tally of the given proposal id from the votes cast so far. The
+/// voting power is read at the current epoch, or at the end epoch of the
+/// voting period if it's already over.
+fn proposal_tally<D, H, V, T>(
+    ctx: RequestCtx<'_, D, H, V, T>,
+    id: u64,
+) -> namada_storage::Result<Option<ProposalTally>>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    let proposal = match namada_governance::storage::get_proposal_by_id(
+        ctx.wl_storage,
+        id,
+    )? {
+        Some(proposal) => proposal,
+        None => return Ok(None),
+    };
+    let is_steward = namada_governance::pgf::storage::is_steward(
+        ctx.wl_storage,
+        &proposal.author,
+    )?;
+    let tally_type = proposal.get_tally_type(is_steward);
+    let tally_epoch = std::cmp::min(
+        ctx.wl_storage.storage.last_epoch,
+        proposal.voting_end_epoch,
+    );
+
+    let params = read_pos_params(ctx.wl_storage)?;
+    let total_voting_power = compute_total_voting_power(
+        ctx.wl_storage,
+        &params,
+        proposal.voting_mode,
+        tally_epoch,
+    )?;
+    let votes = compute_proposal_votes(
+        ctx.wl_storage,
+        &params,
+        id,
+        proposal.r#type.kind(),
+        tally_epoch,
+    )?;
+    let veto_threshold =
+        namada_governance::storage::get_veto_threshold(ctx.wl_storage)?;
+
+    Ok(Some(
+        ProposalTally::new(
+            votes,
+            total_voting_power,
+            tally_type,
+            proposal.voting_mode,
+            tally_epoch,
+        )
+        .with_veto_threshold(veto_threshold),
+    ))
+}
+
+/// Compare the parameter values proposed by the given proposal id with the
+/// current ones. Only the protocol parameters of a parameter change proposal
+/// and the MASP rewards parameters of the tokens of a MASP rewards proposal
+/// are compared. Returns `None` if the proposal doesn't exist or is of another
+/// type, e.g. a default proposal whose code may change any parameter.
+fn proposal_parameter_diff<D, H, V, T>(
+    ctx: RequestCtx<'_, D, H, V, T>,
+    id: u64,
+) -> namada_storage::Result<Option<Vec<ParameterDiff>>>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    let proposal = match namada_governance::storage::get_proposal_by_id(
+        ctx.wl_storage,
+        id,
+    )? {
+        Some(proposal) => proposal,
+        None => return Ok(None),
+    };
+
+    match proposal.r#type {
+        ProposalType::ParameterChange(changes) => changes
+            .iter()
+            .map(|change| {
+                namada_governance::storage::parameter_change_diff(
+                    ctx.wl_storage,
+                    change,
+                )
+            })
+            .collect::<namada_storage::Result<Vec<_>>>()
+            .map(Some),
+        ProposalType::MaspRewards(update) => {
+            let mut diffs = vec![];
+            for (token, params) in update {
+                diffs.extend(masp_rewards_diff(
+                    ctx.wl_storage,
+                    &token,
+                    params,
+                )?);
+            }
+            Ok(Some(diffs))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Compare the proposed MASP rewards parameters of a token with the current
+/// ones
+fn masp_rewards_diff<S>(
+    storage: &S,
+    token: &Address,
+    params: MaspParams,
+) -> namada_storage::Result<Vec<ParameterDiff>>
+where
+    S: StorageRead,
+{
+    let read_dec = |key: Key| -> namada_storage::Result<Option<String>> {
+        Ok(storage.read::<Dec>(&key)?.map(|value| value.to_string()))
+    };
+    // The locked amount target is stored in the base unit of the token but
+    // proposed in whole units
+    let current_locked_amount_target = match read_denom(storage, token)? {
+        Some(denom) => storage
+            .read::<Amount>(&masp_locked_amount_target_key(token))?
+            .and_then(|target| {
+                Uint::from(10)
+                    .checked_pow(Uint::from(denom.0))
+                    .map(|unit| (target.raw_amount() / unit).to_string())
+            }),
+        None => None,
+    };
+
+    Ok(vec![
+        ParameterDiff {
+            parameter: format!("MASP max reward rate of {}", token),
+            current: read_dec(masp_max_reward_rate_key(token))?,
+            proposed: params.max_reward_rate.to_string(),
+        },
+        ParameterDiff {
+            parameter: format!("MASP proportional gain of {}", token),
+            current: read_dec(masp_kp_gain_key(token))?,
+            proposed: params.kp_gain_nom.to_string(),
+        },
+        ParameterDiff {
+            parameter: format!("MASP derivative gain of {}", token),
+            current: read_dec(masp_kd_gain_key(token))?,
+            proposed: params.kd_gain_nom.to_string(),
+        },
+        ParameterDiff {
+            parameter: format!("MASP locked amount target of {}", token),
+            current: current_locked_amount_target,
+            proposed: params.locked_amount_target.to_string(),
+        },
+    ])
+}
+
 /// Get the governance parameters
 fn parameters<D, H, V, T>(
     ctx: RequestCtx<'_, D, H, V, T>,
@@ -61,3 +240,50 @@ where
 {
     namada_governance::storage::get_proposal_result(ctx.wl_storage, id)
 }
+
+/// Get the vote delegations for a kind of proposals in effect at the given
+/// epoch
+fn vote_delegations<D, H, V, T>(
+    ctx: RequestCtx<'_, D, H, V, T>,
+    kind: ProposalKind,
+    epoch: Epoch,
+) -> namada_storage::Result<HashMap<Address, Address>>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    namada_governance::storage::get_vote_delegations(
+        ctx.wl_storage,
+        kind,
+        epoch,
+    )
+}
+
+/// Query a page of the participation history of a voter, from the most
+/// recent proposal. A page holds up to `VOTER_VOTES_PAGE_SIZE` votes, with
+/// the first page numbered 0.
+fn votes_by_voter<D, H, V, T>(
+    ctx: RequestCtx<'_, D, H, V, T>,
+    voter: Address,
+    page: u64,
+) -> namada_storage::Result<Vec<VoterVote>>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    let params = read_pos_params(ctx.wl_storage)?;
+    let current_epoch = ctx.wl_storage.storage.last_epoch;
+    let votes =
+        compute_voter_votes(ctx.wl_storage, &params, &voter, current_epoch)?;
+
+    let skip = usize::try_from(page)
+        .ok()
+        .and_then(|page| page.checked_mul(VOTER_VOTES_PAGE_SIZE))
+        .unwrap_or(usize::MAX);
+    Ok(votes
+        .into_iter()
+        .rev()
+        .skip(skip)
+        .take(VOTER_VOTES_PAGE_SIZE)
+        .collect())
+}
@@ -1,6 +1,9 @@
 use namada_core::types::address::Address;
 use namada_governance::pgf::parameters::PgfParameters;
-use namada_governance::pgf::storage::steward::StewardDetail;
+use namada_governance::pgf::storage::grant::GrantDetail;
+use namada_governance::pgf::storage::steward::{
+    StewardDetail, StewardSpending,
+};
 use namada_governance::storage::proposal::StoragePgfFunding;
 use namada_state::{DBIter, StorageHasher, DB};
 
@@ -8,9 +11,12 @@ use crate::queries::types::RequestCtx;
 
 // PoS validity predicate queries
 router! {PGF,
+    ( "stewards" / [ address: Address ] / "spending" ) -> StewardSpending = steward_spending,
     ( "stewards" / [ address: Address ] ) -> bool = is_steward,
     ( "stewards" ) -> Vec<StewardDetail> = stewards,
     ( "fundings" ) -> Vec<StoragePgfFunding> = funding,
+    ( "grants" / [ id: u64 ] ) -> Option<GrantDetail> = grant,
+    ( "grants" ) -> Vec<(u64, GrantDetail)> = grants,
     ( "parameters" ) -> PgfParameters = parameters,
 }
 
@@ -37,6 +43,21 @@ where
     namada_governance::pgf::storage::is_steward(ctx.wl_storage, &address)
 }
 
+/// Query the retro pgf spending accounting of a steward
+fn steward_spending<D, H, V, T>(
+    ctx: RequestCtx<'_, D, H, V, T>,
+    address: Address,
+) -> namada_storage::Result<StewardSpending>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    namada_governance::pgf::storage::get_steward_spending(
+        ctx.wl_storage,
+        &address,
+    )
+}
+
 /// Query the continuous pgf fundings
 fn funding<D, H, V, T>(
     ctx: RequestCtx<'_, D, H, V, T>,
@@ -48,6 +69,29 @@ where
     namada_governance::pgf::storage::get_payments(ctx.wl_storage)
 }
 
+/// Query the pgf milestone grants with their id
+fn grants<D, H, V, T>(
+    ctx: RequestCtx<'_, D, H, V, T>,
+) -> namada_storage::Result<Vec<(u64, GrantDetail)>>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    namada_governance::pgf::storage::get_grants(ctx.wl_storage)
+}
+
+/// Query a pgf milestone grant by id
+fn grant<D, H, V, T>(
+    ctx: RequestCtx<'_, D, H, V, T>,
+    id: u64,
+) -> namada_storage::Result<Option<GrantDetail>>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    namada_governance::pgf::storage::get_grant(ctx.wl_storage, id)
+}
+
 /// Query the PGF parameters
 fn parameters<D, H, V, T>(
     ctx: RequestCtx<'_, D, H, V, T>,
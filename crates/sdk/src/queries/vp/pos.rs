@@ -21,9 +21,10 @@ use namada_proof_of_stake::storage::{
     read_total_stake, read_validator_avatar, read_validator_description,
     read_validator_discord_handle, read_validator_email,
     read_validator_last_slash_epoch, read_validator_max_commission_rate_change,
-    read_validator_stake, read_validator_website, unbond_handle,
-    validator_commission_rate_handle, validator_incoming_redelegations_handle,
-    validator_slashes_handle, validator_state_handle,
+    read_validator_name, read_validator_stake, read_validator_website,
+    unbond_handle, validator_commission_rate_handle,
+    validator_incoming_redelegations_handle, validator_slashes_handle,
+    validator_state_handle,
 };
 use namada_proof_of_stake::types::{
     BondId, BondsAndUnbondsDetail, BondsAndUnbondsDetails, CommissionPair,
@@ -285,6 +286,7 @@ where
     let discord_handle =
         read_validator_discord_handle(ctx.wl_storage, &validator)?;
     let avatar = read_validator_avatar(ctx.wl_storage, &validator)?;
+    let name = read_validator_name(ctx.wl_storage, &validator)?;
 
     // Email is the only required field for a validator in storage
     match email {
@@ -294,6 +296,7 @@ where
             website,
             discord_handle,
             avatar,
+            name,
         })),
         _ => Ok(None),
     }
@@ -2,7 +2,8 @@
 
 use namada_core::types::address::Address;
 use namada_core::types::token;
-use namada_state::{DBIter, StorageHasher, DB};
+use namada_state::{balance_index, DBIter, StorageHasher, DB};
+use namada_storage::ResultExt;
 use namada_token::{read_denom, read_total_supply};
 
 use crate::queries::RequestCtx;
@@ -10,6 +11,8 @@ use crate::queries::RequestCtx;
 router! {TOKEN,
     ( "denomination" / [addr: Address] ) -> Option<token::Denomination> = denomination,
     ( "total_supply" / [addr: Address] ) -> token::Amount = total_supply,
+    ( "balance" / [token: Address] / [owner: Address] ) -> token::Amount = balance,
+    ( "holders" / [token: Address] ) -> Vec<(Address, token::Amount)> = holders,
 }
 
 /// Get the number of decimal places (in base 10) for a
@@ -37,40 +40,31 @@ where
     read_total_supply(ctx.wl_storage, &addr)
 }
 
-#[cfg(any(test, feature = "async-client"))]
-pub mod client_only_methods {
-    use borsh::BorshDeserialize;
-    use namada_core::types::address::Address;
-    use namada_core::types::token;
-    use namada_token::storage_key::balance_key;
-
-    use super::Token;
-    use crate::queries::{Client, RPC};
-
-    impl Token {
-        /// Get the balance of the given `token` belonging to the given `owner`.
-        pub async fn balance<CLIENT>(
-            &self,
-            client: &CLIENT,
-            token: &Address,
-            owner: &Address,
-        ) -> Result<token::Amount, <CLIENT as Client>::Error>
-        where
-            CLIENT: Client + Sync,
-        {
-            let balance_key = balance_key(token, owner);
-            let response = RPC
-                .shell()
-                .storage_value(client, None, None, false, &balance_key)
-                .await?;
+/// Get the balance of the given `token` belonging to the given `owner` in the
+/// last committed block, from the balance index
+fn balance<D, H, V, T>(
+    ctx: RequestCtx<'_, D, H, V, T>,
+    token: Address,
+    owner: Address,
+) -> namada_storage::Result<token::Amount>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    balance_index::read_balance(&ctx.wl_storage.storage.db, &token, &owner)
+        .into_storage_result()
+}
 
-            let balance = if response.data.is_empty() {
-                token::Amount::zero()
-            } else {
-                token::Amount::try_from_slice(&response.data)
-                    .unwrap_or_default()
-            };
-            Ok(balance)
-        }
-    }
+/// Get the owners of the given `token` with a non-zero balance in the last
+/// committed block and their balances, from the balance index
+fn holders<D, H, V, T>(
+    ctx: RequestCtx<'_, D, H, V, T>,
+    token: Address,
+) -> namada_storage::Result<Vec<(Address, token::Amount)>>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    balance_index::read_holders(&ctx.wl_storage.storage.db, &token)
+        .into_storage_result()
 }
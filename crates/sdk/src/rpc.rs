@@ -6,15 +6,24 @@ use std::ops::ControlFlow;
 use std::str::FromStr;
 
 use borsh::BorshDeserialize;
+use borsh_ext::BorshSerializeExt;
 use masp_primitives::asset_type::AssetType;
 use masp_primitives::merkle_tree::MerklePath;
-use masp_primitives::sapling::Node;
+use masp_primitives::sapling::{Node, Nullifier};
+use masp_primitives::transaction::components::I128Sum;
+use masp_primitives::zip32::ExtendedFullViewingKey;
 use namada_account::Account;
 use namada_core::types::address::{Address, InternalAddress};
 use namada_core::types::hash::Hash;
+use namada_core::types::ibc::{
+    ChannelUpgrade, IbcDenomTrace, IbcTokenHash, PacketDirection, PacketStatus,
+};
 use namada_core::types::key::common;
+use namada_core::types::masp::ExtendedViewingKey;
+use namada_core::types::parameters::InflationSplit;
 use namada_core::types::storage::{
-    BlockHeight, BlockResults, Epoch, Key, PrefixValue,
+    BlockHeight, BlockResults, Epoch, Key, PrefixPage, PrefixPageRequest,
+    PrefixValue,
 };
 use namada_core::types::token::{
     Amount, DenominatedAmount, Denomination, MaspDigitPos,
@@ -22,19 +31,28 @@ use namada_core::types::token::{
 use namada_core::types::{storage, token};
 use namada_governance::parameters::GovernanceParameters;
 use namada_governance::pgf::parameters::PgfParameters;
-use namada_governance::pgf::storage::steward::StewardDetail;
-use namada_governance::storage::proposal::StorageProposal;
+use namada_governance::pgf::storage::grant::GrantDetail;
+use namada_governance::pgf::storage::steward::{
+    StewardDetail, StewardSpending,
+};
+use namada_governance::storage::proposal::{
+    ParameterDiff, ProposalKind, StorageProposal,
+};
 use namada_governance::utils::{
-    compute_proposal_result, ProposalResult, ProposalVotes, Vote,
+    ProposalResult, ProposalTally, Vote, VoterVote,
 };
 use namada_ibc::storage::{
-    ibc_denom_key, ibc_denom_key_prefix, is_ibc_denom_key,
+    channel_upgrade_state_key, ibc_denom_key, ibc_denom_key_prefix,
+    icq_allowlist_key, is_ibc_denom_key, packet_status_key,
 };
 use namada_proof_of_stake::parameters::PosParams;
 use namada_proof_of_stake::types::{
     BondsAndUnbondsDetails, CommissionPair, ValidatorMetaData, ValidatorState,
 };
+use namada_state::account_history::AccountHistoryPage;
 use namada_state::LastBlock;
+use namada_token::compact::CompactBlock;
+use namada_token::utils::MAX_NULLIFIERS_PER_QUERY;
 use namada_tx::data::{ResultCode, TxResult};
 use serde::Serialize;
 
@@ -42,11 +60,14 @@ use crate::args::InputAmount;
 use crate::control_flow::time;
 use crate::error::{EncodingError, Error, QueryError, TxSubmitError};
 use crate::events::Event;
+use crate::ibc::core::host::types::identifiers::{ChannelId, PortId, Sequence};
 use crate::internal_macros::echo_error;
 use crate::io::Io;
-use crate::masp::MaspTokenRewardData;
+use crate::masp::{MaspTokenRewardData, MaspTokenRewards};
 use crate::queries::vp::pos::EnrichedBondsAndUnbondsDetails;
-use crate::queries::{Client, RPC};
+use crate::queries::{
+    BatchQuery, BatchResponse, ChainInfo, Client, MAX_QUERIES_PER_BATCH, RPC,
+};
 use crate::tendermint::block::Height;
 use crate::tendermint::merkle::proof::ProofOps;
 use crate::tendermint_rpc::error::Error as TError;
@@ -126,6 +147,14 @@ pub async fn query_native_token<C: crate::queries::Client + Sync>(
     convert_response::<C, _>(RPC.shell().native_token(client).await)
 }
 
+/// Query the metadata of the chain, i.e. its ID, native token, epoch duration,
+/// gas parameters and enabled features, to configure a client for it
+pub async fn query_chain_info<C: crate::queries::Client + Sync>(
+    client: &C,
+) -> Result<ChainInfo, error::Error> {
+    convert_response::<C, _>(RPC.shell().chain_info(client).await)
+}
+
 /// Query the epoch of the given block height, if it exists.
 /// Will return none if the input block height is greater than
 /// the latest committed block height.
@@ -181,6 +210,48 @@ pub async fn get_token_balance<C: crate::queries::Client + Sync>(
     )
 }
 
+/// Query the owners of the token with a non-zero balance and their balances
+pub async fn query_token_holders<C: crate::queries::Client + Sync>(
+    client: &C,
+    token: &Address,
+) -> Result<Vec<(Address, token::Amount)>, error::Error> {
+    convert_response::<C, _>(RPC.vp().token().holders(client, token).await)
+}
+
+/// Query a page of the history of an account, i.e. the txs in which it
+/// appeared from the oldest. The pages are numbered from zero and the node
+/// must have the account history index enabled.
+pub async fn query_account_history<C: crate::queries::Client + Sync>(
+    client: &C,
+    address: &Address,
+    page: u64,
+) -> Result<AccountHistoryPage, error::Error> {
+    convert_response::<C, _>(
+        RPC.shell().account_history(client, address, &page).await,
+    )
+}
+
+/// Query token amount of owner at the given committed height. The balance
+/// can only be read at the heights whose subspace diffs haven't been pruned.
+pub async fn get_token_balance_at_height<C: crate::queries::Client + Sync>(
+    client: &C,
+    token: &Address,
+    owner: &Address,
+    height: BlockHeight,
+) -> Result<token::Amount, error::Error> {
+    let balance_key = namada_token::storage_key::balance_key(token, owner);
+    let (value, _proof) =
+        query_storage_value_bytes(client, &balance_key, Some(height), false)
+            .await?;
+    value
+        .map(|bytes| {
+            token::Amount::try_from_slice(&bytes).map_err(|err| {
+                Error::from(EncodingError::Decoding(err.to_string()))
+            })
+        })
+        .unwrap_or_else(|| Ok(token::Amount::zero()))
+}
+
 /// Query token total supply.
 pub async fn get_token_total_supply<C: crate::queries::Client + Sync>(
     client: &C,
@@ -248,6 +319,26 @@ pub async fn query_pgf_stewards<C: crate::queries::Client + Sync>(
     )
 }
 
+/// Query the retro pgf spending accounting of a steward
+pub async fn query_pgf_steward_spending<C: crate::queries::Client + Sync>(
+    client: &C,
+    steward: &Address,
+) -> Result<StewardSpending, error::Error> {
+    convert_response::<C, StewardSpending>(
+        RPC.vp().pgf().steward_spending(client, steward).await,
+    )
+}
+
+/// Query a pgf milestone grant by id
+pub async fn query_pgf_grant<C: crate::queries::Client + Sync>(
+    client: &C,
+    grant_id: u64,
+) -> Result<Option<GrantDetail>, error::Error> {
+    convert_response::<C, Option<GrantDetail>>(
+        RPC.vp().pgf().grant(client, &grant_id).await,
+    )
+}
+
 /// Query the consensus key by validator address
 pub async fn query_validator_consensus_keys<
     C: crate::queries::Client + Sync,
@@ -329,6 +420,140 @@ pub async fn query_masp_reward_tokens<C: crate::queries::Client + Sync>(
     convert_response::<C, _>(RPC.shell().masp_reward_tokens(client).await)
 }
 
+/// Query the current shielded rewards of the tokens that earn masp rewards:
+/// their reward parameters, which can be updated by governance, and the
+/// conversions accrued by their asset types.
+pub async fn query_masp_rewards<C: crate::queries::Client + Sync>(
+    client: &C,
+) -> Result<Vec<MaspTokenRewards>, Error> {
+    convert_response::<C, _>(RPC.shell().masp_rewards(client).await)
+}
+
+/// Query the MASP compact blocks in the given inclusive range of heights. The
+/// blocks without MASP transactions are not returned.
+pub async fn query_masp_compact_blocks<C: crate::queries::Client + Sync>(
+    client: &C,
+    from: BlockHeight,
+    to: BlockHeight,
+) -> Result<Vec<CompactBlock>, Error> {
+    convert_response::<C, _>(
+        RPC.shell().masp_compact_blocks(client, &from, &to).await,
+    )
+}
+
+/// Query whether the given MASP nullifier has been revealed, i.e. whether the
+/// note it belongs to has already been spent.
+pub async fn query_masp_nullifier<C: crate::queries::Client + Sync>(
+    client: &C,
+    nullifier: &Nullifier,
+) -> Result<bool, Error> {
+    convert_response::<C, _>(
+        RPC.shell().masp_nullifier(client, &Hash(nullifier.0)).await,
+    )
+}
+
+/// Query whether each of the given MASP nullifiers has been revealed. The
+/// results are in the order of the given nullifiers.
+pub async fn query_masp_nullifiers<C: crate::queries::Client + Sync>(
+    client: &C,
+    nullifiers: &[Nullifier],
+) -> Result<Vec<bool>, Error> {
+    let mut revealed = Vec::with_capacity(nullifiers.len());
+    for chunk in nullifiers.chunks(MAX_NULLIFIERS_PER_QUERY) {
+        let data = chunk
+            .iter()
+            .map(|nullifier| Hash(nullifier.0))
+            .collect::<Vec<_>>()
+            .serialize_to_vec();
+        let response = convert_response::<C, _>(
+            RPC.shell()
+                .masp_nullifiers(client, Some(data), None, false)
+                .await,
+        )?;
+        revealed.extend(response.data);
+    }
+    Ok(revealed)
+}
+
+/// Evaluate a batch of queries against the storage of the same block height,
+/// the given one or the last committed one, in a single request. The results
+/// are in the order of the given queries. The batches larger than
+/// [`MAX_QUERIES_PER_BATCH`] are split in several requests, all evaluated at
+/// the height of the first one.
+pub async fn query_batch<C: crate::queries::Client + Sync>(
+    client: &C,
+    queries: &[BatchQuery],
+    height: Option<BlockHeight>,
+) -> Result<BatchResponse, Error> {
+    let mut chunks = queries.chunks(MAX_QUERIES_PER_BATCH);
+    let first = chunks.next().unwrap_or_default();
+    let mut response = query_batch_chunk(client, first, height).await?;
+    for chunk in chunks {
+        let next =
+            query_batch_chunk(client, chunk, Some(response.height)).await?;
+        response.results.extend(next.results);
+    }
+    Ok(response)
+}
+
+/// Request the evaluation of a batch of at most [`MAX_QUERIES_PER_BATCH`]
+/// queries
+async fn query_batch_chunk<C: crate::queries::Client + Sync>(
+    client: &C,
+    queries: &[BatchQuery],
+    height: Option<BlockHeight>,
+) -> Result<BatchResponse, Error> {
+    let data = Some(queries.to_vec().serialize_to_vec());
+    let response = convert_response::<C, _>(
+        RPC.shell().batch(client, data, height, false).await,
+    )?;
+    Ok(response.data)
+}
+
+/// Query the shielded balance of the given viewing key per token. The shielded
+/// context is synchronized with the chain first. The unspent notes of the key
+/// are then exchanged to the asset types of the given epoch, or of the current
+/// one if none is given, with the allowed conversions. The notes whose asset
+/// type can't be decoded are left out.
+pub async fn query_shielded_balance(
+    context: &impl Namada,
+    viewing_key: &ExtendedViewingKey,
+    epoch: Option<Epoch>,
+) -> Result<BTreeMap<Address, Amount>, error::Error> {
+    let viewing_key = ExtendedFullViewingKey::from(*viewing_key).fvk.vk;
+    let epoch = match epoch {
+        Some(epoch) => epoch,
+        None => query_epoch(context.client()).await?,
+    };
+    let mut shielded = context.shielded_mut().await;
+    let _ = shielded.load().await;
+    shielded
+        .fetch(context.client(), &[], &[viewing_key])
+        .await?;
+    // Precompute asset types to increase chances of success in decoding
+    let _ = shielded.precompute_asset_types(context).await;
+    // Save the update state so that future fetches can be short-circuited
+    let _ = shielded.save().await;
+
+    let balance = shielded
+        .compute_exchanged_balance(
+            context.client(),
+            context.io(),
+            &viewing_key,
+            epoch,
+        )
+        .await?
+        .unwrap_or_else(I128Sum::zero);
+    let (decoded, _undecoded) = shielded
+        .decode_combine_sum_to_epoch(context.client(), balance, epoch)
+        .await;
+    Ok(decoded
+        .components()
+        .filter(|(_, change)| !change.is_zero())
+        .map(|(token, change)| (token.clone(), Amount::from_change(*change)))
+        .collect())
+}
+
 /// Query a wasm code hash
 pub async fn query_wasm_code_hash(
     context: &impl Namada,
@@ -411,9 +636,70 @@ pub async fn query_storage_value_bytes<C: crate::queries::Client + Sync>(
     })
 }
 
+/// Query a storage value without decoding, together with the proof of its
+/// existence, or of the non-existence of the key, in the Merkle tree at the
+/// given height. When no height is given, the last committed height is used.
+///
+/// The returned height is the height of the block whose state is proven. The
+/// proof can be verified with
+/// [`Proof::verify`](namada_state::merkle_tree::Proof::verify) against the
+/// app hash of the header of the following block, obtained from a trusted
+/// source, e.g. a light client.
+///
+/// Note that the non-existence proofs are only supported for the IBC keys.
+pub async fn query_storage_value_with_proof<C>(
+    client: &C,
+    key: &storage::Key,
+    height: Option<BlockHeight>,
+) -> Result<(Option<Vec<u8>>, ProofOps, BlockHeight), error::Error>
+where
+    C: crate::queries::Client + Sync,
+{
+    // Pin the height so that it's known which state is proven
+    let height = match height {
+        Some(height) => height,
+        None => query_block(client)
+            .await?
+            .map(|block| block.height)
+            .ok_or_else(|| {
+                Error::from(QueryError::General(
+                    "No block has been committed yet".to_string(),
+                ))
+            })?,
+    };
+    let (value, proof) =
+        query_storage_value_bytes(client, key, Some(height), true).await?;
+    let proof = proof.ok_or_else(|| {
+        Error::from(QueryError::General(format!(
+            "No proof was returned for the key {key}"
+        )))
+    })?;
+    Ok((value, proof, height))
+}
+
+/// Query a page of the storage values with a matching prefix without decoding
+/// them. When no height is given, the last committed height is used. The node
+/// caps the number of values in a page, so the page's `next_cursor` should be
+/// checked for more values.
+pub async fn query_storage_prefix_page<C: crate::queries::Client + Sync>(
+    client: &C,
+    prefix: &storage::Key,
+    request: &PrefixPageRequest,
+    height: Option<BlockHeight>,
+) -> Result<PrefixPage, error::Error> {
+    let data = Some(request.serialize_to_vec());
+    let response = convert_response::<C, _>(
+        RPC.shell()
+            .storage_prefix_page(client, data, height, false, prefix)
+            .await,
+    )?;
+    Ok(response.data)
+}
+
 /// Query a range of storage values with a matching prefix and decode them with
 /// [`BorshDeserialize`]. Returns an iterator of the storage keys paired with
-/// their associated values.
+/// their associated values. The values are queried page by page, each at the
+/// last committed height.
 pub async fn query_storage_prefix<'a, 'b, N: Namada, T>(
     context: &'b N,
     key: &storage::Key,
@@ -421,11 +707,18 @@ pub async fn query_storage_prefix<'a, 'b, N: Namada, T>(
 where
     T: BorshDeserialize,
 {
-    let values = convert_response::<N::Client, _>(
-        RPC.shell()
-            .storage_prefix(context.client(), None, None, false, key)
-            .await,
-    )?;
+    let mut values = vec![];
+    let mut request = PrefixPageRequest::default();
+    loop {
+        let page =
+            query_storage_prefix_page(context.client(), key, &request, None)
+                .await?;
+        values.extend(page.values);
+        match page.next_cursor {
+            Some(cursor) => request.cursor = Some(cursor),
+            None => break,
+        }
+    }
     let decode =
         |PrefixValue { key, value }: PrefixValue| match T::try_from_slice(
             &value[..],
@@ -441,10 +734,10 @@ where
             }
             Ok(value) => Some((key, value)),
         };
-    Ok(if values.data.is_empty() {
+    Ok(if values.is_empty() {
         None
     } else {
-        Some(values.data.into_iter().filter_map(decode))
+        Some(values.into_iter().filter_map(decode))
     })
 }
 
@@ -517,11 +810,12 @@ pub async fn query_tx_events<C: crate::queries::Client + Sync>(
     }
 }
 
-/// Dry run a transaction
+/// Dry run a transaction, displaying the gas it would use, the storage keys it
+/// would change and the events it would emit
 pub async fn dry_run_tx<N: Namada>(
     context: &N,
     tx_bytes: Vec<u8>,
-) -> Result<namada_tx::data::TxResult, Error> {
+) -> Result<namada_tx::data::DryRunResult, Error> {
     let (data, height, prove) = (Some(tx_bytes), None, false);
     let result = convert_response::<N::Client, _>(
         RPC.shell()
@@ -529,20 +823,54 @@ pub async fn dry_run_tx<N: Namada>(
             .await,
     )?
     .data;
+    let gas_str = match result.wrapper_gas_used {
+        Some(wrapper_gas) => format!(
+            "{} gas (wrapper: {}, inner tx: {})",
+            result.gas_used(),
+            wrapper_gas,
+            result.inner_gas_used
+        ),
+        None => format!("{} gas", result.gas_used()),
+    };
     let result_str = if result.is_accepted() {
-        format!(
-            "Transaction was successfully applied. Used {} gas.",
-            result.gas_used
-        )
+        format!("Transaction was successfully applied. Used {gas_str}.")
     } else {
         format!(
-            "Transaction was rejected by VPs: {}.\nChanged key: {}",
-            serde_json::to_string_pretty(&result.vps_result.rejected_vps)
-                .unwrap(),
-            serde_json::to_string_pretty(&result.changed_keys).unwrap(),
+            "Transaction was rejected by VPs: {}. Used {gas_str}.",
+            serde_json::to_string_pretty(
+                &result.tx_result.vps_result.rejected_vps
+            )
+            .unwrap(),
         )
     };
     display_line!(context.io(), "Dry-run result: {result_str}");
+    display_line!(
+        context.io(),
+        "Changed keys: {}",
+        serde_json::to_string_pretty(&result.changed_keys()).unwrap()
+    );
+    if result.events_count() > 0 {
+        display_line!(context.io(), "Emitted events:");
+        for event in &result.tx_result.tx_events {
+            display_line!(
+                context.io(),
+                "  {}: {}",
+                event.event_type,
+                serde_json::to_string(&event.attributes).unwrap()
+            );
+        }
+        for event in &result.tx_result.ibc_events {
+            display_line!(
+                context.io(),
+                "  {}: {}",
+                event.event_type,
+                serde_json::to_string(&event.attributes).unwrap()
+            );
+        }
+        for event in &result.tx_result.eth_bridge_events {
+            display_line!(context.io(), "  {:?}", event);
+        }
+    }
     Ok(result)
 }
 
@@ -769,6 +1097,17 @@ pub async fn get_all_validators<C: crate::queries::Client + Sync>(
     )
 }
 
+/// Get the name set in the metadata of a validator, if any
+pub async fn get_validator_name<C: crate::queries::Client + Sync>(
+    client: &C,
+    validator: &Address,
+) -> Result<Option<String>, error::Error> {
+    convert_response::<C, Option<ValidatorMetaData>>(
+        RPC.vp().pos().validator_metadata(client, validator).await,
+    )
+    .map(|metadata| metadata.and_then(|metadata| metadata.name))
+}
+
 /// Get the total staked tokens in the given epoch
 pub async fn get_total_staked_tokens<C: crate::queries::Client + Sync>(
     client: &C,
@@ -842,6 +1181,41 @@ pub async fn query_proposal_by_id<C: crate::queries::Client + Sync>(
     )
 }
 
+/// Query the current and proposed values of the protocol parameters changed by
+/// a parameter change proposal or of the MASP rewards parameters changed by a
+/// MASP rewards proposal. Returns `None` if the proposal doesn't exist or is
+/// of another type.
+pub async fn query_proposal_parameter_diff<C: crate::queries::Client + Sync>(
+    client: &C,
+    proposal_id: u64,
+) -> Result<Option<Vec<ParameterDiff>>, Error> {
+    convert_response::<C, _>(
+        RPC.vp()
+            .gov()
+            .proposal_parameter_diff(client, &proposal_id)
+            .await,
+    )
+}
+
+/// Query the current inflation split, if any, and its past values indexed by
+/// the epoch they were set at
+pub async fn query_inflation_split<C: crate::queries::Client + Sync>(
+    client: &C,
+) -> Result<(Option<InflationSplit>, BTreeMap<Epoch, InflationSplit>), Error> {
+    convert_response::<C, _>(RPC.shell().inflation_split(client).await)
+}
+
+/// Query the lifecycle events of a governance proposal still held in the event
+/// log of the node, in the order they were emitted
+pub async fn query_proposal_events<C: crate::queries::Client + Sync>(
+    client: &C,
+    proposal_id: u64,
+) -> Result<Vec<Event>, Error> {
+    convert_response::<C, _>(
+        RPC.shell().proposal_events(client, &proposal_id).await,
+    )
+}
+
 /// Query and return validator's commission rate and max commission rate change
 /// per epoch
 pub async fn query_commission_rate<C: crate::queries::Client + Sync>(
@@ -934,6 +1308,33 @@ pub async fn is_public_key_revealed<C: crate::queries::Client + Sync>(
     convert_response::<C, bool>(RPC.shell().revealed(client, owner).await)
 }
 
+/// Query the storage usage of the address, i.e. the number of bytes of the
+/// subspace values under its keys at the last committed block
+pub async fn query_storage_usage<C: crate::queries::Client + Sync>(
+    client: &C,
+    address: &Address,
+) -> Result<u64, error::Error> {
+    convert_response::<C, u64>(RPC.shell().storage_usage(client, address).await)
+}
+
+/// Query the heights of the blocks in the given inclusive range that changed
+/// the key, e.g. to find the txs that affected a wallet's balance without
+/// their events. The number of blocks that can be searched at once is
+/// limited by the node. The heights of the blocks whose diffs of
+/// the key are no longer kept by the node may include a few false positives.
+pub async fn query_tx_affecting_key<C: crate::queries::Client + Sync>(
+    client: &C,
+    key: &Key,
+    from: BlockHeight,
+    to: BlockHeight,
+) -> Result<Vec<BlockHeight>, error::Error> {
+    convert_response::<C, Vec<BlockHeight>>(
+        RPC.shell()
+            .blocks_changing_key(client, key, &from, &to)
+            .await,
+    )
+}
+
 /// Query an account substorage at a specific index
 pub async fn get_public_key_at<C: crate::queries::Client + Sync>(
     client: &C,
@@ -950,82 +1351,33 @@ pub async fn get_public_key_at<C: crate::queries::Client + Sync>(
     }
 }
 
-/// Query the proposal result
+/// Query the tally of a proposal computed by the node from the votes cast so
+/// far, with the voting power of validators and delegators and the projected
+/// result
+pub async fn query_proposal_tally<C: crate::queries::Client + Sync>(
+    client: &C,
+    proposal_id: u64,
+) -> Result<Option<ProposalTally>, Error> {
+    convert_response::<C, Option<ProposalTally>>(
+        RPC.vp().gov().proposal_tally(client, &proposal_id).await,
+    )
+}
+
+/// Query the proposal result. The result of a proposal whose voting period
+/// isn't tallied yet is projected from the votes cast so far.
 pub async fn query_proposal_result<C: crate::queries::Client + Sync>(
     client: &C,
     proposal_id: u64,
 ) -> Result<Option<ProposalResult>, Error> {
-    let proposal = query_proposal_by_id(client, proposal_id).await?;
-    let proposal = if let Some(proposal) = proposal {
-        proposal
-    } else {
-        return Ok(None);
-    };
     let stored_proposal_result = convert_response::<C, Option<ProposalResult>>(
         RPC.vp().gov().proposal_result(client, &proposal_id).await,
     )?;
-    let proposal_result = match stored_proposal_result {
-        Some(proposal_result) => proposal_result,
-        None => {
-            let tally_epoch = proposal.voting_end_epoch;
-
-            let is_author_pgf_steward =
-                is_steward(client, &proposal.author).await;
-            let votes = query_proposal_votes(client, proposal_id)
-                .await
-                .unwrap_or_default();
-            let tally_type = proposal.get_tally_type(is_author_pgf_steward);
-            let total_staked_token =
-                get_total_staked_tokens(client, tally_epoch)
-                    .await
-                    .unwrap_or_default();
-
-            let mut proposal_votes = ProposalVotes::default();
-
-            for vote in votes {
-                match vote.is_validator() {
-                    true => {
-                        let voting_power = get_validator_stake(
-                            client,
-                            tally_epoch,
-                            &vote.validator,
-                        )
-                        .await
-                        .unwrap_or_default();
-
-                        proposal_votes.add_validator(
-                            &vote.validator,
-                            voting_power,
-                            vote.data.into(),
-                        );
-                    }
-                    false => {
-                        let voting_power = get_bond_amount_at(
-                            client,
-                            &vote.delegator,
-                            &vote.validator,
-                            tally_epoch,
-                        )
-                        .await
-                        .unwrap_or_default();
-
-                        proposal_votes.add_delegator(
-                            &vote.delegator,
-                            &vote.validator,
-                            voting_power,
-                            vote.data.into(),
-                        );
-                    }
-                }
-            }
-            compute_proposal_result(
-                proposal_votes,
-                total_staked_token,
-                tally_type,
-            )
-        }
-    };
-    Ok(Some(proposal_result))
+    match stored_proposal_result {
+        Some(proposal_result) => Ok(Some(proposal_result)),
+        None => Ok(query_proposal_tally(client, proposal_id)
+            .await?
+            .map(|tally| tally.projected_result)),
+    }
 }
 
 /// Query a validator's unbonds for a given epoch
@@ -1122,6 +1474,31 @@ pub async fn query_proposal_votes<C: crate::queries::Client + Sync>(
     )
 }
 
+/// Query the vote delegations for a kind of proposals in effect at the given
+/// epoch, as a map from the delegator to its delegate
+pub async fn query_vote_delegations<C: crate::queries::Client + Sync>(
+    client: &C,
+    kind: ProposalKind,
+    epoch: Epoch,
+) -> Result<HashMap<Address, Address>, error::Error> {
+    convert_response::<C, _>(
+        RPC.vp().gov().vote_delegations(client, &kind, &epoch).await,
+    )
+}
+
+/// Query a page of the participation history of a voter: the proposals it
+/// voted on, from the most recent one, with its votes and the voting power
+/// used. The first page is numbered 0.
+pub async fn query_votes_by_voter<C: crate::queries::Client + Sync>(
+    client: &C,
+    voter: &Address,
+    page: u64,
+) -> Result<Vec<VoterVote>, error::Error> {
+    convert_response::<C, _>(
+        RPC.vp().gov().votes_by_voter(client, voter, &page).await,
+    )
+}
+
 /// Get the bond amount at the given epoch
 pub async fn get_bond_amount_at<C: crate::queries::Client + Sync>(
     client: &C,
@@ -1382,3 +1759,91 @@ pub async fn query_ibc_denom<N: Namada>(
 
     token.as_ref().to_string()
 }
+
+/// Resolve the hash of an IBC token to the denom trace and the display
+/// metadata stored in the token metadata. The hash can be given with the
+/// `ibc/` prefix.
+pub async fn query_ibc_denom_trace<C: crate::queries::Client + Sync>(
+    client: &C,
+    hash: impl AsRef<str>,
+) -> Result<Option<IbcDenomTrace>, Error> {
+    let hash = hash.as_ref();
+    let hash = hash
+        .strip_prefix("ibc/")
+        .or_else(|| hash.strip_prefix("IBC/"))
+        .unwrap_or(hash);
+    let hash = IbcTokenHash::from_str(hash).map_err(|err| {
+        Error::Other(format!("Invalid IBC token hash {hash}: {}", err.error))
+    })?;
+    let token = Address::Internal(InternalAddress::IbcToken(hash));
+    let key = namada_token::storage_key::metadata_key(&token);
+    let (value, _proof) =
+        query_storage_value_bytes(client, &key, None, false).await?;
+    value
+        .map(|bytes| {
+            IbcDenomTrace::try_from_slice(&bytes).map_err(|err| {
+                Error::from(EncodingError::Decoding(err.to_string()))
+            })
+        })
+        .transpose()
+}
+
+/// Query the storage key prefixes that counterparty chains are allowed to
+/// query via the interchain query (ICQ) host
+pub async fn query_icq_allowlist<C: crate::queries::Client + Sync>(
+    client: &C,
+) -> Result<Vec<String>, Error> {
+    let key = icq_allowlist_key();
+    let (value, _proof) =
+        query_storage_value_bytes(client, &key, None, false).await?;
+    value
+        .map(|bytes| {
+            Vec::<String>::try_from_slice(&bytes).map_err(|err| {
+                Error::from(EncodingError::Decoding(err.to_string()))
+            })
+        })
+        .transpose()
+        .map(Option::unwrap_or_default)
+}
+
+/// Query the channel upgrade in progress on the given channel
+pub async fn query_channel_upgrade<C: crate::queries::Client + Sync>(
+    client: &C,
+    port_id: &PortId,
+    channel_id: &ChannelId,
+) -> Result<Option<ChannelUpgrade>, Error> {
+    let key = channel_upgrade_state_key(port_id, channel_id);
+    let (value, _proof) =
+        query_storage_value_bytes(client, &key, None, false).await?;
+    value
+        .map(|bytes| {
+            ChannelUpgrade::try_from_slice(&bytes).map_err(|err| {
+                Error::from(EncodingError::Decoding(err.to_string()))
+            })
+        })
+        .transpose()
+}
+
+/// Query the lifecycle stage of an IBC packet. An outgoing packet is
+/// identified by the source port and channel on this chain, and an incoming
+/// packet by the destination port and channel on this chain. A packet that has
+/// been received, acknowledged or has timed out is pruned after
+/// [`PACKET_STATUS_RETENTION_BLOCKS`](namada_core::types::ibc::PACKET_STATUS_RETENTION_BLOCKS).
+pub async fn query_packet_status<C: crate::queries::Client + Sync>(
+    client: &C,
+    direction: PacketDirection,
+    port_id: &PortId,
+    channel_id: &ChannelId,
+    sequence: Sequence,
+) -> Result<Option<PacketStatus>, Error> {
+    let key = packet_status_key(direction, port_id, channel_id, sequence);
+    let (value, _proof) =
+        query_storage_value_bytes(client, &key, None, false).await?;
+    value
+        .map(|bytes| {
+            PacketStatus::try_from_slice(&bytes).map_err(|err| {
+                Error::from(EncodingError::Decoding(err.to_string()))
+            })
+        })
+        .transpose()
+}
@@ -23,14 +23,17 @@ use namada_core::types::token::Transfer;
 // use namada_core::types::storage::Key;
 use namada_core::types::token::{Amount, DenominatedAmount};
 use namada_governance::storage::proposal::{
-    InitProposalData, ProposalType, VoteProposalData,
+    InitProposalData, ProposalType, VoteDelegationData, VoteProposalBatchData,
+    VoteProposalData, VoteWithdrawalData,
 };
 use namada_governance::storage::vote::ProposalVote;
 use namada_parameters::storage as parameter_storage;
 use namada_token::storage_key::balance_key;
-use namada_tx::data::pgf::UpdateStewardCommission;
+use namada_tx::data::pgf::{MilestoneAttestation, UpdateStewardCommission};
 use namada_tx::data::pos::BecomeValidator;
 use namada_tx::data::{pos, Fee};
+#[cfg(not(target_family = "wasm"))]
+use namada_tx::Signer;
 use namada_tx::{MaspBuilder, Section, Tx};
 use prost::Message;
 use rand::rngs::OsRng;
@@ -38,7 +41,7 @@ use serde::{Deserialize, Serialize};
 use sha2::Digest;
 use tokio::sync::RwLock;
 
-use super::masp::{ShieldedContext, ShieldedTransfer};
+use super::masp::{NoteSelectionStrategy, ShieldedContext, ShieldedTransfer};
 use crate::args::SdkTypes;
 use crate::error::{EncodingError, Error, TxSubmitError};
 use crate::ibc::apps::transfer::types::msgs::transfer::MsgTransfer;
@@ -46,17 +49,19 @@ use crate::ibc::primitives::proto::Any;
 use crate::io::*;
 use crate::rpc::validate_amount;
 use crate::tx::{
-    TX_BECOME_VALIDATOR_WASM, TX_BOND_WASM, TX_BRIDGE_POOL_WASM,
-    TX_CHANGE_COMMISSION_WASM, TX_CHANGE_CONSENSUS_KEY_WASM,
-    TX_CHANGE_METADATA_WASM, TX_CLAIM_REWARDS_WASM,
-    TX_DEACTIVATE_VALIDATOR_WASM, TX_IBC_WASM, TX_INIT_ACCOUNT_WASM,
-    TX_INIT_PROPOSAL, TX_REACTIVATE_VALIDATOR_WASM, TX_REDELEGATE_WASM,
-    TX_RESIGN_STEWARD, TX_REVEAL_PK, TX_TRANSFER_WASM, TX_UNBOND_WASM,
-    TX_UNJAIL_VALIDATOR_WASM, TX_UPDATE_ACCOUNT_WASM,
-    TX_UPDATE_STEWARD_COMMISSION, TX_VOTE_PROPOSAL, TX_WITHDRAW_WASM,
-    VP_USER_WASM,
+    TX_ATTEST_PGF_MILESTONE, TX_BECOME_VALIDATOR_WASM, TX_BOND_WASM,
+    TX_BRIDGE_POOL_WASM, TX_CHANGE_COMMISSION_WASM,
+    TX_CHANGE_CONSENSUS_KEY_WASM, TX_CHANGE_METADATA_WASM,
+    TX_CLAIM_REWARDS_WASM, TX_DEACTIVATE_VALIDATOR_WASM, TX_DELEGATE_VOTE,
+    TX_IBC_WASM, TX_INIT_ACCOUNT_WASM, TX_INIT_PROPOSAL,
+    TX_REACTIVATE_VALIDATOR_WASM, TX_REDELEGATE_WASM, TX_RESIGN_STEWARD,
+    TX_REVEAL_PK, TX_TRANSFER_WASM, TX_UNBOND_WASM, TX_UNJAIL_VALIDATOR_WASM,
+    TX_UPDATE_ACCOUNT_WASM, TX_UPDATE_STEWARD_COMMISSION, TX_VOTE_PROPOSAL,
+    TX_VOTE_PROPOSAL_BATCH, TX_WITHDRAW_VOTE, TX_WITHDRAW_WASM, VP_USER_WASM,
 };
 use crate::types::eth_bridge_pool::PendingTransfer;
+#[cfg(not(target_family = "wasm"))]
+use crate::wallet::backend::remote;
 pub use crate::wallet::store::AddressVpType;
 use crate::wallet::{Wallet, WalletIo};
 use crate::{args, display_line, rpc, MaybeSend, Namada};
@@ -136,6 +141,54 @@ pub fn find_key_by_pk<U: WalletIo>(
         })
 }
 
+/// Sign the given targets with the remote signer holding the key of the given
+/// public key, and return the signature section under the given index of the
+/// signer. Return nothing if the key is not held by a remote signer.
+#[cfg(not(target_family = "wasm"))]
+async fn sign_remotely<U: WalletIo>(
+    wallet: &RwLock<Wallet<U>>,
+    args: &args::Tx,
+    public_key: &common::PublicKey,
+    targets: Vec<namada_core::types::hash::Hash>,
+    signer: Signer,
+    index: u8,
+) -> Result<Option<namada_tx::Signature>, Error> {
+    let remote_signer = {
+        // Only lock the wallet to get the authentication key, not during the
+        // request
+        let mut wallet = wallet.write().await;
+        wallet.find_remote_signer(public_key, args.password.clone())
+    };
+    let Some(remote_signer) = remote_signer else {
+        return Ok(None);
+    };
+    let (endpoint, auth_key) = remote_signer.map_err(|err| {
+        Error::Other(format!(
+            "Unable to load the key authenticating to the remote signer of \
+             public key {}. Failed with: {}",
+            public_key, err
+        ))
+    })?;
+    // Commit to the given targets, as done for the keys of the wallet
+    let partial = namada_tx::Signature {
+        targets,
+        signer,
+        signatures: BTreeMap::new(),
+    };
+    let signature = remote::request_signature(
+        &endpoint,
+        &auth_key,
+        public_key,
+        partial.get_raw_hash(),
+    )
+    .await
+    .map_err(|err| Error::Other(err.to_string()))?;
+    Ok(Some(namada_tx::Signature {
+        signatures: [(index, signature)].into_iter().collect(),
+        ..partial
+    }))
+}
+
 /// Given CLI arguments and some defaults, determine the rightful transaction
 /// signer. Return the given signing key or public key of the given signer if
 /// possible. If no explicit signer given, use the `default`. If no `default`
@@ -226,7 +279,7 @@ where
     }
 
     // Then try to sign the raw header with private keys in the software wallet
-    if let Some(account_public_keys_map) = signing_data.account_public_keys_map
+    if let Some(account_public_keys_map) = &signing_data.account_public_keys_map
     {
         let mut wallet = wallet.write().await;
         let signing_tx_keypairs = signing_data
@@ -249,12 +302,42 @@ where
         if !signing_tx_keypairs.is_empty() {
             tx.sign_raw(
                 signing_tx_keypairs,
-                account_public_keys_map,
-                signing_data.owner,
+                account_public_keys_map.clone(),
+                signing_data.owner.clone(),
             );
         }
     }
 
+    // Then try to sign the raw header with the remote signers of the keys
+    #[cfg(not(target_family = "wasm"))]
+    for pubkey in &signing_data.public_keys {
+        if used_pubkeys.contains(pubkey) {
+            continue;
+        }
+        let (signer, index) = match (
+            &signing_data.owner,
+            &signing_data.account_public_keys_map,
+        ) {
+            (Some(owner), Some(account_public_keys_map)) => {
+                let Some(index) =
+                    account_public_keys_map.get_index_from_public_key(pubkey)
+                else {
+                    continue;
+                };
+                (Signer::Address(owner.clone()), index)
+            }
+            _ => (Signer::PubKeys(vec![pubkey.clone()]), 0),
+        };
+        let targets = vec![tx.raw_header_hash()];
+        if let Some(signature) =
+            sign_remotely(wallet, args, pubkey, targets, signer, index).await?
+        {
+            tx.protocol_filter();
+            tx.add_section(Section::Signature(signature));
+            used_pubkeys.insert(pubkey.clone());
+        }
+    }
+
     // Then try to sign the raw header using the hardware wallet
     for pubkey in signing_data.public_keys {
         if !used_pubkeys.contains(&pubkey) && pubkey != signing_data.fee_payer {
@@ -285,6 +368,26 @@ where
             tx.sign_wrapper(fee_payer_keypair);
         }
         Err(_) => {
+            // Try the remote signer of the fee payer key, if any
+            #[cfg(not(target_family = "wasm"))]
+            {
+                let mut filtered = tx.clone();
+                filtered.protocol_filter();
+                if let Some(signature) = sign_remotely(
+                    wallet,
+                    args,
+                    &signing_data.fee_payer,
+                    filtered.sechashes(),
+                    Signer::PubKeys(vec![signing_data.fee_payer.clone()]),
+                    0,
+                )
+                .await?
+                {
+                    *tx = filtered;
+                    tx.add_section(Section::Signature(signature));
+                    return Ok(());
+                }
+            }
             *tx = sign(
                 tx.clone(),
                 signing_data.fee_payer.clone(),
@@ -339,11 +442,7 @@ pub async fn aux_signing_data(
     };
 
     let fee_payer = if args.disposable_signing_key {
-        context
-            .wallet_mut()
-            .await
-            .gen_disposable_signing_key(&mut OsRng)
-            .to_public()
+        gen_disposable_fee_payer(context).await?
     } else {
         match &args.wrapper_fee_payer {
             Some(keypair) => keypair.clone(),
@@ -379,11 +478,7 @@ pub async fn init_validator_signing_data(
         Some(AccountPublicKeysMap::from_iter(validator_keys));
 
     let fee_payer = if args.disposable_signing_key {
-        context
-            .wallet_mut()
-            .await
-            .gen_disposable_signing_key(&mut OsRng)
-            .to_public()
+        gen_disposable_fee_payer(context).await?
     } else {
         match &args.wrapper_fee_payer {
             Some(keypair) => keypair.clone(),
@@ -403,6 +498,22 @@ pub async fn init_validator_signing_data(
     })
 }
 
+/// Generate a disposable keypair in the wallet to pay the fees of a tx and
+/// return its public key
+async fn gen_disposable_fee_payer(
+    context: &impl Namada,
+) -> Result<common::PublicKey, Error> {
+    let mut wallet = context.wallet_mut().await;
+    if wallet.is_view_only() {
+        return Err(Error::Other(
+            "A disposable signing key cannot be generated in a view-only \
+             wallet"
+                .to_string(),
+        ));
+    }
+    Ok(wallet.gen_disposable_signing_key(&mut OsRng).to_public())
+}
+
 /// Information about the post-tx balance of the tx's source. Used to correctly
 /// handle fee validation in the wrapper tx
 pub struct TxSourcePostBalance {
@@ -525,6 +636,7 @@ pub async fn wrap_tx<N: Namada>(
                         &target,
                         &args.fee_token,
                         fee_amount,
+                        NoteSelectionStrategy::default(),
                     )
                     .await
                 {
@@ -900,6 +1012,7 @@ impl<'a> Display for LedgerProposalVote<'a> {
             ProposalVote::Yay => write!(f, "yay"),
             ProposalVote::Nay => write!(f, "nay"),
             ProposalVote::Abstain => write!(f, "abstain"),
+            ProposalVote::NayWithVeto => write!(f, "veto"),
         }
     }
 }
@@ -911,8 +1024,8 @@ struct LedgerProposalType<'a>(&'a ProposalType, &'a Tx);
 impl<'a> Display for LedgerProposalType<'a> {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self.0 {
-            ProposalType::Default(None) => write!(f, "Default"),
-            ProposalType::Default(Some(hash)) => {
+            ProposalType::Text => write!(f, "Default"),
+            ProposalType::WasmCodeUpgrade(hash) => {
                 let extra = self
                     .1
                     .get_section(hash)
@@ -922,8 +1035,15 @@ impl<'a> Display for LedgerProposalType<'a> {
                     .hash();
                 write!(f, "{}", HEXLOWER.encode(&extra.0))
             }
-            ProposalType::PGFSteward(_) => write!(f, "PGF Steward"),
+            ProposalType::PGFSteward(..) => write!(f, "PGF Steward"),
             ProposalType::PGFPayment(_) => write!(f, "PGF Payment"),
+            ProposalType::MaspRewards(_) => write!(f, "MASP Rewards"),
+            ProposalType::ParameterChange(_) => {
+                write!(f, "Parameter Change")
+            }
+            ProposalType::EmergencyHalt(_) => write!(f, "Emergency Halt"),
+            ProposalType::WasmAllowlist(_) => write!(f, "Wasm Allowlist"),
+            ProposalType::Signaling => write!(f, "Signaling"),
         }
     }
 }
@@ -1103,6 +1223,18 @@ pub async fn to_ledger_vector(
             format!("Grace epoch : {}", init_proposal_data.grace_epoch),
             format!("Content : {}", HEXLOWER.encode(&extra.0)),
         ]);
+        if init_proposal_data.expedited {
+            tv.output.push("Expedited : true".to_string());
+        }
+        if let Some(content_uri) = &init_proposal_data.content_uri {
+            tv.output.push(format!("Content URI : {}", content_uri));
+        }
+        if !init_proposal_data.voting_mode.is_linear() {
+            tv.output.push(format!(
+                "Voting mode : {}",
+                init_proposal_data.voting_mode
+            ));
+        }
 
         tv.output_expert
             .push(format!("ID : {}", init_proposal_data.id));
@@ -1123,6 +1255,19 @@ pub async fn to_ledger_vector(
             format!("Grace epoch : {}", init_proposal_data.grace_epoch),
             format!("Content : {}", HEXLOWER.encode(&extra.0)),
         ]);
+        if init_proposal_data.expedited {
+            tv.output_expert.push("Expedited : true".to_string());
+        }
+        if let Some(content_uri) = &init_proposal_data.content_uri {
+            tv.output_expert
+                .push(format!("Content URI : {}", content_uri));
+        }
+        if !init_proposal_data.voting_mode.is_linear() {
+            tv.output_expert.push(format!(
+                "Voting mode : {}",
+                init_proposal_data.voting_mode
+            ));
+        }
     } else if code_sec.tag == Some(TX_VOTE_PROPOSAL.to_string()) {
         let vote_proposal = VoteProposalData::try_from_slice(
             &tx.data()
@@ -1153,6 +1298,92 @@ pub async fn to_ledger_vector(
             tv.output_expert
                 .push(format!("Delegation : {}", delegation));
         }
+    } else if code_sec.tag == Some(TX_WITHDRAW_VOTE.to_string()) {
+        let vote_withdrawal = VoteWithdrawalData::try_from_slice(
+            &tx.data()
+                .ok_or_else(|| Error::Other("Invalid Data".to_string()))?,
+        )
+        .map_err(|err| {
+            Error::from(EncodingError::Conversion(err.to_string()))
+        })?;
+
+        tv.name = "Withdraw_Vote_0".to_string();
+
+        tv.output.extend(vec![
+            format!("Type : Withdraw Vote"),
+            format!("ID : {}", vote_withdrawal.id),
+            format!("Voter : {}", vote_withdrawal.voter),
+        ]);
+        for delegation in &vote_withdrawal.delegations {
+            tv.output.push(format!("Delegation : {}", delegation));
+        }
+
+        tv.output_expert.extend(vec![
+            format!("ID : {}", vote_withdrawal.id),
+            format!("Voter : {}", vote_withdrawal.voter),
+        ]);
+        for delegation in vote_withdrawal.delegations {
+            tv.output_expert
+                .push(format!("Delegation : {}", delegation));
+        }
+    } else if code_sec.tag == Some(TX_VOTE_PROPOSAL_BATCH.to_string()) {
+        let vote_proposal_batch = VoteProposalBatchData::try_from_slice(
+            &tx.data()
+                .ok_or_else(|| Error::Other("Invalid Data".to_string()))?,
+        )
+        .map_err(|err| {
+            Error::from(EncodingError::Conversion(err.to_string()))
+        })?;
+
+        tv.name = "Vote_Proposal_Batch_0".to_string();
+
+        tv.output.push(format!("Type : Vote Proposal Batch"));
+        for ballot in &vote_proposal_batch.ballots {
+            tv.output.extend(vec![
+                format!("ID : {}", ballot.data.id),
+                format!("Vote : {}", LedgerProposalVote(&ballot.data.vote)),
+                format!("Voter : {}", ballot.data.voter),
+            ]);
+        }
+
+        for ballot in &vote_proposal_batch.ballots {
+            tv.output_expert.extend(vec![
+                format!("ID : {}", ballot.data.id),
+                format!("Vote : {}", LedgerProposalVote(&ballot.data.vote)),
+                format!("Voter : {}", ballot.data.voter),
+            ]);
+        }
+    } else if code_sec.tag == Some(TX_DELEGATE_VOTE.to_string()) {
+        let vote_delegation = VoteDelegationData::try_from_slice(
+            &tx.data()
+                .ok_or_else(|| Error::Other("Invalid Data".to_string()))?,
+        )
+        .map_err(|err| {
+            Error::from(EncodingError::Conversion(err.to_string()))
+        })?;
+
+        tv.name = "Delegate_Vote_0".to_string();
+
+        let delegate = vote_delegation
+            .delegate
+            .as_ref()
+            .map_or_else(|| "(revoked)".to_string(), ToString::to_string);
+        tv.output.extend(vec![
+            format!("Type : Delegate Vote"),
+            format!("Delegator : {}", vote_delegation.delegator),
+            format!("Delegate : {}", delegate),
+        ]);
+        for kind in &vote_delegation.kinds {
+            tv.output.push(format!("Proposal kind : {}", kind));
+        }
+
+        tv.output_expert.extend(vec![
+            format!("Delegator : {}", vote_delegation.delegator),
+            format!("Delegate : {}", delegate),
+        ]);
+        for kind in vote_delegation.kinds {
+            tv.output_expert.push(format!("Proposal kind : {}", kind));
+        }
     } else if code_sec.tag == Some(TX_REVEAL_PK.to_string()) {
         let public_key = common::PublicKey::try_from_slice(
             &tx.data()
@@ -1218,6 +1449,13 @@ pub async fn to_ledger_vector(
         if let Some((vp_code, _)) = &vp_code_data {
             tv.output.extend(vec![format!("VP type : {}", vp_code)]);
         }
+        if let Some(timelock) = update_account.vp_update_timelock {
+            tv.output
+                .extend(vec![format!("VP timelock : {}", timelock)]);
+        }
+        if update_account.cancel_pending_vp_update {
+            tv.output.extend(vec![format!("Cancel pending VP : true")]);
+        }
         tv.output_expert
             .extend(vec![format!("Address : {}", update_account.addr)]);
         tv.output_expert.extend(
@@ -1236,6 +1474,14 @@ pub async fn to_ledger_vector(
                 HEXLOWER.encode(&extra_code_hash.0)
             )]);
         }
+        if let Some(timelock) = update_account.vp_update_timelock {
+            tv.output_expert
+                .extend(vec![format!("VP timelock : {}", timelock)]);
+        }
+        if update_account.cancel_pending_vp_update {
+            tv.output_expert
+                .extend(vec![format!("Cancel pending VP : true")]);
+        }
     } else if code_sec.tag == Some(TX_TRANSFER_WASM.to_string()) {
         let transfer = Transfer::try_from_slice(
             &tx.data()
@@ -1523,6 +1769,13 @@ pub async fn to_ledger_vector(
                     .push(format!("New discord handle : {}", discord_handle));
             }
         }
+        if let Some(name) = metadata_change.name {
+            if name.is_empty() {
+                other_items.push("Name removed".to_string());
+            } else {
+                other_items.push(format!("New name : {}", name));
+            }
+        }
 
         tv.output.extend(other_items.clone());
         tv.output_expert.extend(other_items);
@@ -1676,6 +1929,29 @@ pub async fn to_ledger_vector(
         ]);
 
         tv.output_expert.push(format!("Steward : {}", address));
+    } else if code_sec.tag == Some(TX_ATTEST_PGF_MILESTONE.to_string()) {
+        let attestation = MilestoneAttestation::try_from_slice(
+            &tx.data()
+                .ok_or_else(|| Error::Other("Invalid Data".to_string()))?,
+        )
+        .map_err(|err| {
+            Error::from(EncodingError::Conversion(err.to_string()))
+        })?;
+
+        tv.name = "Attest_Pgf_Milestone_0".to_string();
+
+        tv.output.extend(vec![
+            format!("Type : Attest Pgf Milestone"),
+            format!("Steward : {}", attestation.steward),
+            format!("Grant ID : {}", attestation.grant_id),
+            format!("Milestone : {}", attestation.milestone),
+        ]);
+
+        tv.output_expert.extend(vec![
+            format!("Steward : {}", attestation.steward),
+            format!("Grant ID : {}", attestation.grant_id),
+            format!("Milestone : {}", attestation.milestone),
+        ]);
     } else if code_sec.tag == Some(TX_BRIDGE_POOL_WASM.to_string()) {
         let transfer = PendingTransfer::try_from_slice(
             &tx.data()
@@ -23,8 +23,10 @@ use namada_core::ibc::apps::transfer::types::msgs::transfer::MsgTransfer;
 use namada_core::ibc::apps::transfer::types::packet::PacketData;
 use namada_core::ibc::apps::transfer::types::PrefixedCoin;
 use namada_core::ibc::core::channel::types::timeout::TimeoutHeight;
+use namada_core::ibc::core::client::types::msgs::MsgSubmitMisbehaviour;
 use namada_core::ibc::core::client::types::Height as IbcHeight;
 use namada_core::ibc::core::host::types::identifiers::{ChannelId, PortId};
+use namada_core::ibc::primitives::proto::Any;
 use namada_core::ibc::primitives::{Msg, Timestamp as IbcTimestamp};
 use namada_core::types::address::{Address, InternalAddress, MASP};
 use namada_core::types::dec::Dec;
@@ -36,19 +38,24 @@ use namada_core::types::storage::Epoch;
 use namada_core::types::time::DateTimeUtc;
 use namada_core::types::{storage, token};
 use namada_governance::cli::onchain::{
-    DefaultProposal, OnChainProposal, PgfFundingProposal, PgfStewardProposal,
+    DefaultProposal, EmergencyHaltProposal, MaspRewardsProposal,
+    OnChainProposal, ParameterChangeProposal, PgfFundingProposal,
+    PgfStewardProposal, SignalingProposal, WasmAllowlistProposal,
 };
 use namada_governance::pgf::cli::steward::Commission;
 use namada_governance::storage::proposal::{
-    InitProposalData, ProposalType, VoteProposalData,
+    InitProposalData, ProposalKind, ProposalType, SignedVoteProposalData,
+    VoteDelegationData, VoteProposalBatchData, VoteProposalData,
+    VoteWithdrawalData,
 };
 use namada_governance::storage::vote::ProposalVote;
 use namada_ibc::storage::channel_key;
+use namada_parameters::storage as parameter_storage;
 use namada_proof_of_stake::parameters::PosParams;
 use namada_proof_of_stake::types::{CommissionPair, ValidatorState};
 use namada_token::storage_key::balance_key;
-use namada_tx::data::pgf::UpdateStewardCommission;
-use namada_tx::data::{pos, ResultCode, TxResult};
+use namada_tx::data::pgf::{MilestoneAttestation, UpdateStewardCommission};
+use namada_tx::data::{pos, DryRunResult, ResultCode, TxResult};
 pub use namada_tx::{Signature, *};
 
 use crate::args::{self, InputAmount};
@@ -56,7 +63,10 @@ use crate::control_flow::time;
 use crate::error::{EncodingError, Error, QueryError, Result, TxSubmitError};
 use crate::io::Io;
 use crate::masp::TransferErr::Build;
-use crate::masp::{ShieldedContext, ShieldedTransfer};
+use crate::masp::{
+    decode_shared_asset_type, precompute_shared_asset_types,
+    NoteSelectionStrategy, ShieldedContext, ShieldedTransfer,
+};
 use crate::queries::Client;
 use crate::rpc::{
     self, query_wasm_code_hash, validate_amount, InnerTxResult,
@@ -82,14 +92,28 @@ pub const TX_REACTIVATE_VALIDATOR_WASM: &str = "tx_reactivate_validator.wasm";
 pub const TX_INIT_PROPOSAL: &str = "tx_init_proposal.wasm";
 /// Vote transaction WASM path
 pub const TX_VOTE_PROPOSAL: &str = "tx_vote_proposal.wasm";
+/// Batch of off-chain signed votes transaction WASM path
+pub const TX_VOTE_PROPOSAL_BATCH: &str = "tx_vote_proposal_batch.wasm";
+/// Vote delegation transaction WASM path
+pub const TX_DELEGATE_VOTE: &str = "tx_delegate_vote.wasm";
+/// Vote withdrawal transaction WASM path
+pub const TX_WITHDRAW_VOTE: &str = "tx_withdraw_vote.wasm";
 /// Reveal public key transaction WASM path
 pub const TX_REVEAL_PK: &str = "tx_reveal_pk.wasm";
 /// Update validity predicate WASM path
 pub const TX_UPDATE_ACCOUNT_WASM: &str = "tx_update_account.wasm";
+/// Rotate account keys WASM path
+pub const TX_ROTATE_ACCOUNT_KEYS_WASM: &str = "tx_rotate_account_keys.wasm";
+/// Update account policy WASM path
+pub const TX_UPDATE_ACCOUNT_POLICY_WASM: &str = "tx_update_account_policy.wasm";
 /// Transfer transaction WASM path
 pub const TX_TRANSFER_WASM: &str = "tx_transfer.wasm";
+/// Shielding sweep transaction WASM path
+pub const TX_SHIELDING_SWEEP_WASM: &str = "tx_shielding_sweep.wasm";
 /// IBC transaction WASM path
 pub const TX_IBC_WASM: &str = "tx_ibc.wasm";
+/// IBC misbehaviour submission transaction WASM path
+pub const TX_IBC_MISBEHAVIOUR_WASM: &str = "tx_ibc_misbehaviour.wasm";
 /// User validity predicate WASM path
 pub const VP_USER_WASM: &str = "vp_user.wasm";
 /// Bond WASM path
@@ -111,6 +135,8 @@ pub const TX_CHANGE_CONSENSUS_KEY_WASM: &str = "tx_change_consensus_key.wasm";
 pub const TX_CHANGE_METADATA_WASM: &str = "tx_change_validator_metadata.wasm";
 /// Resign steward WASM path
 pub const TX_RESIGN_STEWARD: &str = "tx_resign_steward.wasm";
+/// Attest pgf milestone WASM path
+pub const TX_ATTEST_PGF_MILESTONE: &str = "tx_attest_pgf_milestone.wasm";
 /// Update steward commission WASM path
 pub const TX_UPDATE_STEWARD_COMMISSION: &str =
     "tx_update_steward_commission.wasm";
@@ -129,7 +155,7 @@ pub enum ProcessTxResponse {
     /// Result of submitting a transaction to the mempool
     Broadcast(Response),
     /// Result of dry running transaction
-    DryRun(TxResult),
+    DryRun(DryRunResult),
 }
 
 impl ProcessTxResponse {
@@ -467,6 +493,14 @@ pub fn display_inner_resp(context: &impl Namada, resp: &TxResponse) {
                 resp.height,
                 inner.gas_used,
             );
+            for event in &inner.tx_events {
+                display_line!(
+                    context.io(),
+                    "Emitted event {}: {}",
+                    event.event_type,
+                    serde_json::to_string(&event.attributes).unwrap(),
+                );
+            }
         }
         InnerTxResult::VpsRejected(inner) => {
             let changed_keys: Vec<_> = inner
@@ -682,6 +716,7 @@ pub async fn build_validator_metadata_change(
         website,
         discord_handle,
         avatar,
+        name,
         commission_rate,
         tx_code_path,
     }: &args::MetaDataChange,
@@ -788,6 +823,7 @@ pub async fn build_validator_metadata_change(
         description: description.clone(),
         discord_handle: discord_handle.clone(),
         avatar: avatar.clone(),
+        name: name.clone(),
         commission_rate: *commission_rate,
     };
 
@@ -907,6 +943,72 @@ pub async fn build_resign_steward(
     .map(|tx| (tx, signing_data))
 }
 
+/// Craft transaction to attest the next milestone of a pgf grant
+pub async fn build_attest_pgf_milestone(
+    context: &impl Namada,
+    args::AttestPgfMilestone {
+        tx: tx_args,
+        steward,
+        grant_id,
+        tx_code_path,
+    }: &args::AttestPgfMilestone,
+) -> Result<(Tx, SigningTxData)> {
+    let default_signer = Some(steward.clone());
+    let signing_data = signing::aux_signing_data(
+        context,
+        tx_args,
+        Some(steward.clone()),
+        default_signer,
+    )
+    .await?;
+
+    if !rpc::is_steward(context.client(), steward).await && !tx_args.force {
+        edisplay_line!(
+            context.io(),
+            "The given address {} is not a steward.",
+            &steward
+        );
+        return Err(Error::from(TxSubmitError::InvalidSteward(
+            steward.clone(),
+        )));
+    };
+
+    let grant = rpc::query_pgf_grant(context.client(), *grant_id).await?;
+    let milestone = match grant
+        .as_ref()
+        .and_then(|grant| grant.next_milestone())
+    {
+        Some(milestone) => milestone,
+        None => {
+            edisplay_line!(
+                context.io(),
+                "The pgf grant {} doesn't exist or has no milestone left to \
+                 attest.",
+                grant_id
+            );
+            return Err(Error::from(TxSubmitError::InvalidPgfGrant(*grant_id)));
+        }
+    };
+
+    let data = MilestoneAttestation {
+        steward: steward.clone(),
+        grant_id: *grant_id,
+        milestone,
+    };
+
+    build(
+        context,
+        tx_args,
+        tx_code_path.clone(),
+        data,
+        do_nothing,
+        &signing_data.fee_payer,
+        None,
+    )
+    .await
+    .map(|tx| (tx, signing_data))
+}
+
 /// Submit transaction to unjail a jailed validator
 pub async fn build_unjail_validator(
     context: &impl Namada,
@@ -1233,8 +1335,8 @@ pub async fn build_redelegation(
             "The source validator {} has an incoming redelegation from the \
              delegator {} that may still be subject to future slashing. \
              Redelegation is not allowed until this is no longer the case.",
-            &src_validator,
-            &owner
+            lookup_validator(context, &src_validator).await,
+            context.lookup_address(&owner).await
         );
         if !tx_args.force {
             return Err(Error::from(
@@ -1263,7 +1365,7 @@ pub async fn build_redelegation(
             "WARNING: the given destination validator address {} is inactive \
              at the pipeline epoch {}. If you would still like to bond to the \
              inactive validator, use the --force option.",
-            &dest_validator,
+            lookup_validator(context, &dest_validator).await,
             &pipeline_epoch
         );
         return Err(Error::from(TxSubmitError::ValidatorInactive(
@@ -1692,7 +1794,7 @@ pub async fn build_bond(
             "WARNING: the given validator address {} is inactive at the \
              pipeline epoch {}. If you would still like to bond to the \
              inactive validator, use the --force option.",
-            &validator,
+            lookup_validator(context, &validator).await,
             &pipeline_epoch
         );
         return Err(Error::from(TxSubmitError::ValidatorInactive(
@@ -1761,6 +1863,11 @@ pub async fn build_default_proposal(
         is_offline: _,
         is_pgf_stewards: _,
         is_pgf_funding: _,
+        is_masp_rewards: _,
+        is_parameter_change: _,
+        is_emergency_halt: _,
+        is_wasm_allowlist: _,
+        is_signaling: _,
         tx_code_path,
     }: &args::InitProposal,
     proposal: DefaultProposal,
@@ -1779,15 +1886,17 @@ pub async fn build_default_proposal(
 
     let push_data =
         |tx_builder: &mut Tx, init_proposal_data: &mut InitProposalData| {
-            let (_, extra_section_hash) = tx_builder
-                .add_extra_section(proposal_to_vec(proposal.proposal)?, None);
+            let content = proposal_to_vec(proposal.proposal)?;
+            init_proposal_data.content_hash = Hash::sha256(&content);
+            let (_, extra_section_hash) =
+                tx_builder.add_extra_section(content, None);
             init_proposal_data.content = extra_section_hash;
 
             if let Some(init_proposal_code) = proposal.data {
                 let (_, extra_section_hash) =
                     tx_builder.add_extra_section(init_proposal_code, None);
                 init_proposal_data.r#type =
-                    ProposalType::Default(Some(extra_section_hash));
+                    ProposalType::WasmCodeUpgrade(extra_section_hash);
             };
             Ok(())
         };
@@ -1807,26 +1916,88 @@ pub async fn build_default_proposal(
 /// Build a proposal vote
 pub async fn build_vote_proposal(
     context: &impl Namada,
-    args::VoteProposal {
-        tx,
-        proposal_id,
-        vote,
-        voter,
-        is_offline: _,
-        proposal_data: _,
-        tx_code_path,
-    }: &args::VoteProposal,
+    args: &args::VoteProposal,
     epoch: Epoch,
 ) -> Result<(Tx, SigningTxData)> {
-    let default_signer = Some(voter.clone());
+    let default_signer = Some(args.voter.clone());
     let signing_data = signing::aux_signing_data(
         context,
-        tx,
-        Some(voter.clone()),
+        &args.tx,
+        Some(args.voter.clone()),
         default_signer.clone(),
     )
     .await?;
 
+    let data = vote_proposal_data(context, args, epoch).await?;
+
+    build(
+        context,
+        &args.tx,
+        args.tx_code_path.clone(),
+        data,
+        do_nothing,
+        &signing_data.fee_payer,
+        None,
+    )
+    .await
+    .map(|tx| (tx, signing_data))
+}
+
+/// Build a proposal vote signed off-chain by the voter, that can be submitted
+/// in a batch by any account
+pub async fn build_signed_ballot(
+    context: &impl Namada,
+    args: &args::VoteProposal,
+    epoch: Epoch,
+) -> Result<SignedVoteProposalData> {
+    let default_signer = Some(args.voter.clone());
+    let signing_data = signing::aux_signing_data(
+        context,
+        &args.tx,
+        Some(args.voter.clone()),
+        default_signer,
+    )
+    .await?;
+    let account_public_keys_map =
+        signing_data.account_public_keys_map.ok_or_else(|| {
+            Error::from(TxSubmitError::InvalidAccount(args.voter.encode()))
+        })?;
+    let chain_id = args.tx.chain_id.clone().ok_or_else(|| {
+        Error::Other("The chain id must be defined to sign a ballot.".into())
+    })?;
+
+    let data = vote_proposal_data(context, args, epoch).await?;
+
+    let keypairs = {
+        let mut wallet = context.wallet_mut().await;
+        signing_data
+            .public_keys
+            .iter()
+            .map(|public_key| {
+                signing::find_key_by_pk(&mut wallet, &args.tx, public_key)
+            })
+            .collect::<Result<Vec<_>>>()?
+    };
+
+    Ok(SignedVoteProposalData::new(data).sign(
+        keypairs,
+        &account_public_keys_map,
+        &chain_id,
+    ))
+}
+
+/// Validate the arguments of a proposal vote and build its data
+async fn vote_proposal_data(
+    context: &impl Namada,
+    args::VoteProposal {
+        tx,
+        proposal_id,
+        vote,
+        voter,
+        ..
+    }: &args::VoteProposal,
+    epoch: Epoch,
+) -> Result<VoteProposalData> {
     let proposal_vote = ProposalVote::try_from(vote.clone())
         .map_err(|_| TxSubmitError::InvalidProposalVote)?;
 
@@ -1871,11 +2042,193 @@ pub async fn build_vote_proposal(
         ));
     }
 
-    let data = VoteProposalData {
+    Ok(VoteProposalData {
         id: proposal_id,
         vote: proposal_vote,
         voter: voter.clone(),
         delegations,
+    })
+}
+
+/// Build a transaction submitting a batch of off-chain signed votes
+pub async fn build_vote_proposal_batch(
+    context: &impl Namada,
+    args::VoteProposalBatch {
+        tx,
+        ballots,
+        tx_code_path,
+    }: &args::VoteProposalBatch,
+    epoch: Epoch,
+) -> Result<(Tx, SigningTxData)> {
+    let signing_data =
+        signing::aux_signing_data(context, tx, None, None).await?;
+
+    if ballots.is_empty() {
+        return Err(Error::Other(
+            "At least one signed ballot must be submitted".to_string(),
+        ));
+    }
+
+    let ballots = ballots
+        .iter()
+        .map(|ballot| {
+            serde_json::from_slice::<SignedVoteProposalData>(ballot)
+                .map_err(|e| TxSubmitError::InvalidBallot(e.to_string()))
+        })
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    for ballot in &ballots {
+        let proposal_id = ballot.data.id;
+        let proposal = if let Some(proposal) =
+            rpc::query_proposal_by_id(context.client(), proposal_id).await?
+        {
+            proposal
+        } else {
+            return Err(Error::from(TxSubmitError::ProposalDoesNotExist(
+                proposal_id,
+            )));
+        };
+
+        let is_validator =
+            rpc::is_validator(context.client(), &ballot.data.voter).await?;
+        if !proposal.can_be_voted(epoch, is_validator) {
+            if tx.force {
+                eprintln!("Invalid proposal {} vote period.", proposal_id);
+            } else {
+                return Err(Error::from(
+                    TxSubmitError::InvalidProposalVotingPeriod(proposal_id),
+                ));
+            }
+        }
+    }
+
+    let data = VoteProposalBatchData { ballots };
+
+    build(
+        context,
+        tx,
+        tx_code_path.clone(),
+        data,
+        do_nothing,
+        &signing_data.fee_payer,
+        None,
+    )
+    .await
+    .map(|tx| (tx, signing_data))
+}
+
+/// Build a transaction withdrawing the vote cast on a proposal
+pub async fn build_vote_withdrawal(
+    context: &impl Namada,
+    args::VoteWithdrawal {
+        tx,
+        proposal_id,
+        voter,
+        tx_code_path,
+    }: &args::VoteWithdrawal,
+    epoch: Epoch,
+) -> Result<(Tx, SigningTxData)> {
+    let default_signer = Some(voter.clone());
+    let signing_data = signing::aux_signing_data(
+        context,
+        tx,
+        Some(voter.clone()),
+        default_signer,
+    )
+    .await?;
+
+    let proposal = if let Some(proposal) =
+        rpc::query_proposal_by_id(context.client(), *proposal_id).await?
+    {
+        proposal
+    } else {
+        return Err(Error::from(TxSubmitError::ProposalDoesNotExist(
+            *proposal_id,
+        )));
+    };
+
+    // A vote can only be withdrawn while it could still be cast
+    let is_validator = rpc::is_validator(context.client(), voter).await?;
+    if !proposal.can_be_voted(epoch, is_validator) {
+        if tx.force {
+            eprintln!("Invalid proposal {} vote period.", proposal_id);
+        } else {
+            return Err(Error::from(
+                TxSubmitError::InvalidProposalVotingPeriod(*proposal_id),
+            ));
+        }
+    }
+
+    let delegations = rpc::get_delegators_delegation_at(
+        context.client(),
+        voter,
+        proposal.voting_start_epoch,
+    )
+    .await?
+    .keys()
+    .cloned()
+    .collect::<Vec<Address>>();
+
+    if delegations.is_empty() {
+        return Err(Error::Other(
+            "Voter address must have delegations".to_string(),
+        ));
+    }
+
+    let data = VoteWithdrawalData {
+        id: *proposal_id,
+        voter: voter.clone(),
+        delegations,
+    };
+
+    build(
+        context,
+        tx,
+        tx_code_path.clone(),
+        data,
+        do_nothing,
+        &signing_data.fee_payer,
+        None,
+    )
+    .await
+    .map(|tx| (tx, signing_data))
+}
+
+/// Build a vote delegation transaction
+pub async fn build_vote_delegation(
+    context: &impl Namada,
+    args::VoteDelegation {
+        tx,
+        delegator,
+        delegate,
+        kinds,
+        tx_code_path,
+    }: &args::VoteDelegation,
+) -> Result<(Tx, SigningTxData)> {
+    let default_signer = Some(delegator.clone());
+    let signing_data = signing::aux_signing_data(
+        context,
+        tx,
+        Some(delegator.clone()),
+        default_signer,
+    )
+    .await?;
+
+    if delegate.as_ref() == Some(delegator) {
+        return Err(Error::Other(
+            "Voting power cannot be delegated to oneself".to_string(),
+        ));
+    }
+
+    let kinds = if kinds.is_empty() {
+        ProposalKind::ALL.into_iter().collect()
+    } else {
+        kinds.iter().copied().collect()
+    };
+    let data = VoteDelegationData {
+        delegator: delegator.clone(),
+        delegate: delegate.clone(),
+        kinds,
     };
 
     build(
@@ -1900,6 +2253,11 @@ pub async fn build_pgf_funding_proposal(
         is_offline: _,
         is_pgf_stewards: _,
         is_pgf_funding: _,
+        is_masp_rewards: _,
+        is_parameter_change: _,
+        is_emergency_halt: _,
+        is_wasm_allowlist: _,
+        is_signaling: _,
         tx_code_path,
     }: &args::InitProposal,
     proposal: PgfFundingProposal,
@@ -1917,8 +2275,9 @@ pub async fn build_pgf_funding_proposal(
         .map_err(|e| TxSubmitError::InvalidProposal(e.to_string()))?;
 
     let add_section = |tx: &mut Tx, data: &mut InitProposalData| {
-        let (_, extra_section_hash) =
-            tx.add_extra_section(proposal_to_vec(proposal.proposal)?, None);
+        let content = proposal_to_vec(proposal.proposal)?;
+        data.content_hash = Hash::sha256(&content);
+        let (_, extra_section_hash) = tx.add_extra_section(content, None);
         data.content = extra_section_hash;
         Ok(())
     };
@@ -1944,6 +2303,11 @@ pub async fn build_pgf_stewards_proposal(
         is_offline: _,
         is_pgf_stewards: _,
         is_pgf_funding: _,
+        is_masp_rewards: _,
+        is_parameter_change: _,
+        is_emergency_halt: _,
+        is_wasm_allowlist: _,
+        is_signaling: _,
         tx_code_path,
     }: &args::InitProposal,
     proposal: PgfStewardProposal,
@@ -1961,8 +2325,9 @@ pub async fn build_pgf_stewards_proposal(
         .map_err(|e| TxSubmitError::InvalidProposal(e.to_string()))?;
 
     let add_section = |tx: &mut Tx, data: &mut InitProposalData| {
-        let (_, extra_section_hash) =
-            tx.add_extra_section(proposal_to_vec(proposal.proposal)?, None);
+        let content = proposal_to_vec(proposal.proposal)?;
+        data.content_hash = Hash::sha256(&content);
+        let (_, extra_section_hash) = tx.add_extra_section(content, None);
         data.content = extra_section_hash;
         Ok(())
     };
@@ -1980,22 +2345,277 @@ pub async fn build_pgf_stewards_proposal(
     .map(|tx| (tx, signing_data))
 }
 
-/// Submit an IBC transfer
-pub async fn build_ibc_transfer(
+/// Build a masp rewards proposal governance
+pub async fn build_masp_rewards_proposal(
     context: &impl Namada,
-    args: &args::TxIbcTransfer,
-) -> Result<(Tx, SigningTxData, Option<Epoch>)> {
-    let source = args.source.effective_address();
+    args::InitProposal {
+        tx,
+        proposal_data: _,
+        is_offline: _,
+        is_pgf_stewards: _,
+        is_pgf_funding: _,
+        is_masp_rewards: _,
+        is_parameter_change: _,
+        is_emergency_halt: _,
+        is_wasm_allowlist: _,
+        is_signaling: _,
+        tx_code_path,
+    }: &args::InitProposal,
+    proposal: MaspRewardsProposal,
+) -> Result<(Tx, SigningTxData)> {
+    let default_signer = Some(proposal.proposal.author.clone());
     let signing_data = signing::aux_signing_data(
         context,
-        &args.tx,
-        Some(source.clone()),
-        Some(source.clone()),
+        tx,
+        Some(proposal.proposal.author.clone()),
+        default_signer,
     )
     .await?;
-    // Check that the source address exists on chain
-    let source =
-        source_exists_or_err(source.clone(), args.tx.force, context).await?;
+
+    let init_proposal_data = InitProposalData::try_from(proposal.clone())
+        .map_err(|e| TxSubmitError::InvalidProposal(e.to_string()))?;
+
+    let add_section = |tx: &mut Tx, data: &mut InitProposalData| {
+        let content = proposal_to_vec(proposal.proposal)?;
+        data.content_hash = Hash::sha256(&content);
+        let (_, extra_section_hash) = tx.add_extra_section(content, None);
+        data.content = extra_section_hash;
+        Ok(())
+    };
+
+    build(
+        context,
+        tx,
+        tx_code_path.clone(),
+        init_proposal_data,
+        add_section,
+        &signing_data.fee_payer,
+        None, // TODO: need to pay the fee to submit a proposal
+    )
+    .await
+    .map(|tx| (tx, signing_data))
+}
+
+/// Build a parameter change proposal governance
+pub async fn build_parameter_change_proposal(
+    context: &impl Namada,
+    args::InitProposal {
+        tx,
+        proposal_data: _,
+        is_offline: _,
+        is_pgf_stewards: _,
+        is_pgf_funding: _,
+        is_masp_rewards: _,
+        is_parameter_change: _,
+        is_emergency_halt: _,
+        is_wasm_allowlist: _,
+        is_signaling: _,
+        tx_code_path,
+    }: &args::InitProposal,
+    proposal: ParameterChangeProposal,
+) -> Result<(Tx, SigningTxData)> {
+    let default_signer = Some(proposal.proposal.author.clone());
+    let signing_data = signing::aux_signing_data(
+        context,
+        tx,
+        Some(proposal.proposal.author.clone()),
+        default_signer,
+    )
+    .await?;
+
+    let init_proposal_data = InitProposalData::try_from(proposal.clone())
+        .map_err(|e| TxSubmitError::InvalidProposal(e.to_string()))?;
+
+    let add_section = |tx: &mut Tx, data: &mut InitProposalData| {
+        let content = proposal_to_vec(proposal.proposal)?;
+        data.content_hash = Hash::sha256(&content);
+        let (_, extra_section_hash) = tx.add_extra_section(content, None);
+        data.content = extra_section_hash;
+        Ok(())
+    };
+
+    build(
+        context,
+        tx,
+        tx_code_path.clone(),
+        init_proposal_data,
+        add_section,
+        &signing_data.fee_payer,
+        None, // TODO: need to pay the fee to submit a proposal
+    )
+    .await
+    .map(|tx| (tx, signing_data))
+}
+
+/// Build an emergency halt proposal governance
+pub async fn build_emergency_halt_proposal(
+    context: &impl Namada,
+    args::InitProposal {
+        tx,
+        proposal_data: _,
+        is_offline: _,
+        is_pgf_stewards: _,
+        is_pgf_funding: _,
+        is_masp_rewards: _,
+        is_parameter_change: _,
+        is_emergency_halt: _,
+        is_wasm_allowlist: _,
+        is_signaling: _,
+        tx_code_path,
+    }: &args::InitProposal,
+    proposal: EmergencyHaltProposal,
+) -> Result<(Tx, SigningTxData)> {
+    let default_signer = Some(proposal.proposal.author.clone());
+    let signing_data = signing::aux_signing_data(
+        context,
+        tx,
+        Some(proposal.proposal.author.clone()),
+        default_signer,
+    )
+    .await?;
+
+    let init_proposal_data = InitProposalData::try_from(proposal.clone())
+        .map_err(|e| TxSubmitError::InvalidProposal(e.to_string()))?;
+
+    let add_section = |tx: &mut Tx, data: &mut InitProposalData| {
+        let content = proposal_to_vec(proposal.proposal)?;
+        data.content_hash = Hash::sha256(&content);
+        let (_, extra_section_hash) = tx.add_extra_section(content, None);
+        data.content = extra_section_hash;
+        Ok(())
+    };
+
+    build(
+        context,
+        tx,
+        tx_code_path.clone(),
+        init_proposal_data,
+        add_section,
+        &signing_data.fee_payer,
+        None, // TODO: need to pay the fee to submit a proposal
+    )
+    .await
+    .map(|tx| (tx, signing_data))
+}
+
+/// Build a wasm allowlist proposal governance
+pub async fn build_wasm_allowlist_proposal(
+    context: &impl Namada,
+    args::InitProposal {
+        tx,
+        proposal_data: _,
+        is_offline: _,
+        is_pgf_stewards: _,
+        is_pgf_funding: _,
+        is_masp_rewards: _,
+        is_parameter_change: _,
+        is_emergency_halt: _,
+        is_wasm_allowlist: _,
+        is_signaling: _,
+        tx_code_path,
+    }: &args::InitProposal,
+    proposal: WasmAllowlistProposal,
+) -> Result<(Tx, SigningTxData)> {
+    let default_signer = Some(proposal.proposal.author.clone());
+    let signing_data = signing::aux_signing_data(
+        context,
+        tx,
+        Some(proposal.proposal.author.clone()),
+        default_signer,
+    )
+    .await?;
+
+    let init_proposal_data = InitProposalData::try_from(proposal.clone())
+        .map_err(|e| TxSubmitError::InvalidProposal(e.to_string()))?;
+
+    let add_section = |tx: &mut Tx, data: &mut InitProposalData| {
+        let content = proposal_to_vec(proposal.proposal)?;
+        data.content_hash = Hash::sha256(&content);
+        let (_, extra_section_hash) = tx.add_extra_section(content, None);
+        data.content = extra_section_hash;
+        Ok(())
+    };
+
+    build(
+        context,
+        tx,
+        tx_code_path.clone(),
+        init_proposal_data,
+        add_section,
+        &signing_data.fee_payer,
+        None, // TODO: need to pay the fee to submit a proposal
+    )
+    .await
+    .map(|tx| (tx, signing_data))
+}
+
+/// Build a signaling proposal governance
+pub async fn build_signaling_proposal(
+    context: &impl Namada,
+    args::InitProposal {
+        tx,
+        proposal_data: _,
+        is_offline: _,
+        is_pgf_stewards: _,
+        is_pgf_funding: _,
+        is_masp_rewards: _,
+        is_parameter_change: _,
+        is_emergency_halt: _,
+        is_wasm_allowlist: _,
+        is_signaling: _,
+        tx_code_path,
+    }: &args::InitProposal,
+    proposal: SignalingProposal,
+) -> Result<(Tx, SigningTxData)> {
+    let default_signer = Some(proposal.proposal.author.clone());
+    let signing_data = signing::aux_signing_data(
+        context,
+        tx,
+        Some(proposal.proposal.author.clone()),
+        default_signer,
+    )
+    .await?;
+
+    let init_proposal_data = InitProposalData::try_from(proposal.clone())
+        .map_err(|e| TxSubmitError::InvalidProposal(e.to_string()))?;
+
+    let add_section = |tx: &mut Tx, data: &mut InitProposalData| {
+        let content = proposal_to_vec(proposal.proposal)?;
+        data.content_hash = Hash::sha256(&content);
+        let (_, extra_section_hash) = tx.add_extra_section(content, None);
+        data.content = extra_section_hash;
+        Ok(())
+    };
+
+    build(
+        context,
+        tx,
+        tx_code_path.clone(),
+        init_proposal_data,
+        add_section,
+        &signing_data.fee_payer,
+        None, // TODO: need to pay the fee to submit a proposal
+    )
+    .await
+    .map(|tx| (tx, signing_data))
+}
+
+/// Submit an IBC transfer
+pub async fn build_ibc_transfer(
+    context: &impl Namada,
+    args: &args::TxIbcTransfer,
+) -> Result<(Tx, SigningTxData, Option<Epoch>)> {
+    let source = args.source.effective_address();
+    let signing_data = signing::aux_signing_data(
+        context,
+        &args.tx,
+        Some(source.clone()),
+        Some(source.clone()),
+    )
+    .await?;
+    // Check that the source address exists on chain
+    let source =
+        source_exists_or_err(source.clone(), args.tx.force, context).await?;
     // We cannot check the receiver
 
     // validate the amount given
@@ -2041,6 +2661,7 @@ pub async fn build_ibc_transfer(
         &TransferTarget::Address(Address::Internal(InternalAddress::Ibc)),
         &args.token,
         validated_amount,
+        NoteSelectionStrategy::default(),
     )
     .await?;
     let shielded_tx_epoch = shielded_parts.as_ref().map(|trans| trans.0.epoch);
@@ -2160,6 +2781,60 @@ pub async fn build_ibc_transfer(
     Ok((tx, signing_data, shielded_tx_epoch))
 }
 
+/// Submit the misbehaviour evidence of an IBC light client
+pub async fn build_ibc_misbehaviour(
+    context: &impl Namada,
+    args: &args::TxIbcMisbehaviour,
+) -> Result<(Tx, SigningTxData)> {
+    let signing_data = signing::aux_signing_data(
+        context,
+        &args.tx,
+        Some(args.submitter.clone()),
+        Some(args.submitter.clone()),
+    )
+    .await?;
+
+    let misbehaviour: Any = prost::Message::decode(&args.misbehaviour[..])
+        .map_err(|e| {
+            Error::Other(format!("Invalid misbehaviour evidence: {e}"))
+        })?;
+    let message = MsgSubmitMisbehaviour {
+        client_id: args.client_id.clone(),
+        misbehaviour,
+        signer: args.submitter.to_string().into(),
+    };
+    let mut data = vec![];
+    prost::Message::encode(&message.to_any(), &mut data)
+        .map_err(TxSubmitError::EncodeFailure)?;
+
+    let tx_code_hash =
+        query_wasm_code_hash(context, args.tx_code_path.to_str().unwrap())
+            .await
+            .map_err(|e| Error::from(QueryError::Wasm(e.to_string())))?;
+
+    let chain_id = args.tx.chain_id.clone().unwrap();
+    let mut tx = Tx::new(chain_id, args.tx.expiration);
+    if let Some(memo) = &args.tx.memo {
+        tx.add_memo(memo);
+    }
+    tx.add_code_from_hash(
+        tx_code_hash,
+        Some(args.tx_code_path.to_string_lossy().into_owned()),
+    )
+    .add_serialized_data(data);
+
+    prepare_tx(
+        context,
+        &args.tx,
+        &mut tx,
+        signing_data.fee_payer.clone(),
+        None,
+    )
+    .await?;
+
+    Ok((tx, signing_data))
+}
+
 /// Abstraction for helping build transactions
 #[allow(clippy::too_many_arguments)]
 pub async fn build<F, D>(
@@ -2239,11 +2914,8 @@ async fn add_asset_type(
     context: &impl Namada,
     asset_type: AssetType,
 ) -> bool {
-    if let Some(asset_type) = context
-        .shielded_mut()
-        .await
-        .decode_asset_type(context.client(), asset_type)
-        .await
+    if let Some(asset_type) =
+        decode_shared_asset_type(context, asset_type).await
     {
         asset_types.insert(asset_type)
     } else {
@@ -2356,6 +3028,7 @@ pub async fn build_transfer<N: Namada>(
         &args.target,
         &args.token,
         validated_amount,
+        args.note_selection,
     )
     .await?;
     let shielded_tx_epoch = shielded_parts.as_ref().map(|trans| trans.0.epoch);
@@ -2421,16 +3094,18 @@ async fn construct_shielded_parts<N: Namada>(
     target: &TransferTarget,
     token: &Address,
     amount: token::DenominatedAmount,
+    note_selection: NoteSelectionStrategy,
 ) -> Result<Option<(ShieldedTransfer, HashSet<AssetData>)>> {
     // Precompute asset types to increase chances of success in decoding
-    let _ = context
-        .shielded_mut()
-        .await
-        .precompute_asset_types(context)
-        .await;
+    let _ = precompute_shared_asset_types(context).await;
     let stx_result =
         ShieldedContext::<N::ShieldedUtils>::gen_shielded_transfer(
-            context, source, target, token, amount,
+            context,
+            source,
+            target,
+            token,
+            amount,
+            note_selection,
         )
         .await;
 
@@ -2459,6 +3134,185 @@ async fn construct_shielded_parts<N: Namada>(
     Ok(Some((shielded_parts, asset_types)))
 }
 
+/// Build a transaction moving all the transparent balances of the given
+/// tokens of the source into the shielded pool
+pub async fn build_shielding_sweep<N: Namada>(
+    context: &N,
+    args: &args::TxShieldingSweep,
+) -> Result<(Tx, SigningTxData, Epoch)> {
+    let source = args.source.clone();
+    let signing_data = signing::aux_signing_data(
+        context,
+        &args.tx,
+        Some(source.clone()),
+        Some(source.clone()),
+    )
+    .await?;
+
+    // Check that the source address exists on chain
+    source_exists_or_err(source.clone(), args.tx.force, context).await?;
+    let payment_address = args.target.payment_address().ok_or_else(|| {
+        Error::Other(
+            "The target of a shielding sweep must be a payment address"
+                .to_string(),
+        )
+    })?;
+
+    // The fees are paid before the sweep is applied, so enough of the fee
+    // token must be left to the source when it's also the fee payer
+    let fee_payer = Address::from(&signing_data.fee_payer);
+    let fee_reserve = if fee_payer == source {
+        let gas_cost_key = parameter_storage::get_gas_cost_key();
+        let minimum_gas_price = rpc::query_storage_value::<
+            _,
+            BTreeMap<Address, token::Amount>,
+        >(context.client(), &gas_cost_key)
+        .await
+        .ok()
+        .and_then(|map| map.get(&args.tx.fee_token).copied())
+        .unwrap_or_default();
+        let gas_price = match args.tx.fee_amount {
+            Some(amount) => validate_amount(
+                context,
+                amount,
+                &args.tx.fee_token,
+                args.tx.force,
+            )
+            .await?
+            .amount()
+            .max(minimum_gas_price),
+            None => minimum_gas_price,
+        };
+        gas_price * u64::from(args.tx.gas_limit)
+    } else {
+        token::Amount::zero()
+    };
+
+    // Query the balances to be shielded
+    let mut balances = vec![];
+    for token in &args.tokens {
+        let balance =
+            rpc::get_token_balance(context.client(), token, &source).await?;
+        balances.push((token.clone(), balance));
+    }
+    let (balances, tx_source_balance) = shielding_sweep_balances(
+        &source,
+        balances,
+        &args.tx.fee_token,
+        (fee_payer == source).then_some(fee_reserve),
+    );
+    let mut tokens = vec![];
+    for (token, balance) in balances {
+        let amount = context.denominate_amount(&token, balance).await;
+        tokens.push((token, amount));
+    }
+    if tokens.is_empty() {
+        return Err(Error::Other(format!(
+            "The address {source} has no transparent balance to be shielded"
+        )));
+    }
+
+    // Precompute asset types to increase chances of success in decoding
+    let _ = precompute_shared_asset_types(context).await;
+    let shielded_parts =
+        ShieldedContext::<N::ShieldedUtils>::gen_shielding_sweep(
+            context,
+            &source,
+            &payment_address,
+            &tokens,
+        )
+        .await
+        .map_err(|err| TxSubmitError::MaspError(err.to_string()))?;
+    let shielded_tx_epoch = shielded_parts.epoch;
+    // Get the decoded asset types used in the transaction to give offline
+    // wallet users more information
+    let asset_types = used_asset_types(context, &shielded_parts.builder)
+        .await
+        .unwrap_or_default();
+
+    // Determine whether to pin this transaction to a storage key
+    let key = if payment_address.is_pinned() {
+        Some(payment_address.hash())
+    } else {
+        None
+    };
+    let sweep = token::ShieldingSweep {
+        source,
+        tokens,
+        key,
+        // Replaced by the hash of the MASP Transaction below
+        shielded: Hash::default(),
+    };
+
+    let add_shielded = |tx: &mut Tx, sweep: &mut token::ShieldingSweep| {
+        let ShieldedTransfer {
+            builder,
+            masp_tx,
+            metadata,
+            epoch: _,
+        } = shielded_parts;
+        // Add a MASP Transaction section to the Tx and get the tx hash
+        let masp_tx_hash = tx.add_masp_tx_section(masp_tx).1;
+        // Link the ShieldingSweep to the MASP Transaction by hash code
+        sweep.shielded = masp_tx_hash;
+
+        tracing::debug!("Shielding sweep data {:?}", sweep);
+
+        tx.add_masp_builder(MaspBuilder {
+            asset_types,
+            // Store how the Info objects map to Descriptors/Outputs
+            metadata,
+            // Store the data that was used to construct the Transaction
+            builder,
+            // Link the Builder to the Transaction by hash code
+            target: masp_tx_hash,
+        });
+        Ok(())
+    };
+    let tx = build_pow_flag(
+        context,
+        &args.tx,
+        args.tx_code_path.clone(),
+        sweep,
+        add_shielded,
+        &signing_data.fee_payer,
+        tx_source_balance,
+    )
+    .await?;
+    Ok((tx, signing_data, shielded_tx_epoch))
+}
+
+/// Deduct the fee reserve, if the source of a shielding sweep pays its fees,
+/// from the balance of the fee token to be shielded. Returns the non-zero
+/// balances to be shielded and the balance of the fee token left to the
+/// source.
+fn shielding_sweep_balances(
+    source: &Address,
+    balances: Vec<(Address, token::Amount)>,
+    fee_token: &Address,
+    fee_reserve: Option<token::Amount>,
+) -> (Vec<(Address, token::Amount)>, Option<TxSourcePostBalance>) {
+    let mut to_shield = vec![];
+    let mut tx_source_balance = None;
+    for (token, mut balance) in balances {
+        match fee_reserve {
+            Some(fee_reserve) if token == *fee_token => {
+                balance = balance.checked_sub(fee_reserve).unwrap_or_default();
+                tx_source_balance = Some(TxSourcePostBalance {
+                    post_balance: fee_reserve,
+                    source: source.clone(),
+                    token: token.clone(),
+                });
+            }
+            _ => {}
+        }
+        if !balance.is_zero() {
+            to_shield.push((token, balance));
+        }
+    }
+    (to_shield, tx_source_balance)
+}
+
 /// Submit a transaction to initialize an account
 pub async fn build_init_account(
     context: &impl Namada,
@@ -2490,6 +3344,7 @@ pub async fn build_init_account(
 
     let data = InitAccount {
         public_keys: public_keys.clone(),
+        weights: vec![],
         // We will add the hash inside the add_code_hash function
         vp_code_hash: Hash::zero(),
         threshold,
@@ -2526,6 +3381,8 @@ pub async fn build_update_account(
         addr,
         public_keys,
         threshold,
+        vp_update_timelock,
+        cancel_pending_vp_update,
     }: &args::TxUpdateAccount,
 ) -> Result<(Tx, SigningTxData)> {
     let default_signer = Some(addr.clone());
@@ -2576,6 +3433,8 @@ pub async fn build_update_account(
         vp_code_hash: extra_section_hash,
         public_keys: public_keys.clone(),
         threshold: *threshold,
+        vp_update_timelock: *vp_update_timelock,
+        cancel_pending_vp_update: *cancel_pending_vp_update,
     };
 
     let add_code_hash = |tx: &mut Tx, data: &mut UpdateAccount| {
@@ -2690,11 +3549,7 @@ pub async fn gen_ibc_shielded_transfer<N: Namada>(
         validate_amount(context, args.amount, &token, false).await?;
 
     // Precompute asset types to increase chances of success in decoding
-    let _ = context
-        .shielded_mut()
-        .await
-        .precompute_asset_types(context)
-        .await;
+    let _ = precompute_shared_asset_types(context).await;
 
     let shielded_transfer =
         ShieldedContext::<N::ShieldedUtils>::gen_shielded_transfer(
@@ -2703,6 +3558,7 @@ pub async fn gen_ibc_shielded_transfer<N: Namada>(
             &args.target,
             &token,
             validated_amount,
+            NoteSelectionStrategy::default(),
         )
         .await
         .map_err(|err| TxSubmitError::MaspError(err.to_string()))?;
@@ -2818,6 +3674,21 @@ async fn known_validator_or_err(
     }
 }
 
+/// Render a validator with its name from the address book, loading its
+/// on-chain name if needed. Failing to query the name only falls back to the
+/// address.
+async fn lookup_validator(
+    context: &impl Namada,
+    validator: &Address,
+) -> String {
+    let _ = context
+        .address_book_mut()
+        .await
+        .load_validator_name(context.client(), validator)
+        .await;
+    context.lookup_address(validator).await
+}
+
 /// general pattern for checking if an address exists on the chain, or
 /// throwing an error if it's not forced. Takes a generic error
 /// message and the error type.
@@ -2961,3 +3832,54 @@ fn proposal_to_vec(proposal: OnChainProposal) -> Result<Vec<u8>> {
     borsh::to_vec(&proposal.content)
         .map_err(|e| Error::from(EncodingError::Conversion(e.to_string())))
 }
+
+#[cfg(test)]
+mod test {
+    use namada_core::types::address::testing::established_address_1;
+    use namada_core::types::address::{btc, nam};
+
+    use super::*;
+
+    #[test]
+    fn test_shielding_sweep_fee_reserve() {
+        let source = established_address_1();
+        let balances = vec![
+            (nam(), token::Amount::native_whole(10)),
+            (btc(), token::Amount::from_u64(20)),
+        ];
+
+        // Everything is shielded when the source doesn't pay the fees
+        let (to_shield, tx_source_balance) =
+            shielding_sweep_balances(&source, balances.clone(), &nam(), None);
+        assert_eq!(to_shield, balances);
+        assert!(tx_source_balance.is_none());
+
+        // The fees are reserved from the fee token balance
+        let reserve = token::Amount::native_whole(1);
+        let (to_shield, tx_source_balance) = shielding_sweep_balances(
+            &source,
+            balances.clone(),
+            &nam(),
+            Some(reserve),
+        );
+        assert_eq!(
+            to_shield,
+            vec![
+                (nam(), token::Amount::native_whole(9)),
+                (btc(), token::Amount::from_u64(20)),
+            ]
+        );
+        let tx_source_balance = tx_source_balance.unwrap();
+        assert_eq!(tx_source_balance.post_balance, reserve);
+        assert_eq!(tx_source_balance.source, source);
+        assert_eq!(tx_source_balance.token, nam());
+
+        // The fee token isn't shielded if its balance doesn't exceed the
+        // reserve
+        let reserve = token::Amount::native_whole(10);
+        let (to_shield, tx_source_balance) =
+            shielding_sweep_balances(&source, balances, &nam(), Some(reserve));
+        assert_eq!(to_shield, vec![(btc(), token::Amount::from_u64(20))]);
+        assert_eq!(tx_source_balance.unwrap().post_balance, reserve);
+    }
+}
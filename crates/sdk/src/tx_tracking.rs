@@ -0,0 +1,524 @@
+//! Tracking of the status of a broadcast tx through its confirmation levels.
+//!
+//! A [`TxTracker`] reports the transitions of a tx from the mempool to the
+//! acceptance of its wrapper, the application of its inner tx and the
+//! finality of the block of the inner tx. The events of the tx are awaited
+//! on an [`EventSubscription`] to the node when one is given, and the node is
+//! polled for them otherwise, or whenever the subscription fails.
+
+use futures::Stream;
+use namada_core::types::address::Address;
+use namada_core::types::hash::Hash;
+use namada_core::types::storage::BlockHeight;
+use namada_tx::data::{ResultCode, TxResult};
+
+use crate::control_flow::time::{self, Duration, Instant};
+use crate::error::{EncodingError, Error, QueryError, TxSubmitError};
+use crate::events::filter::{EventFilter, PushedEvent};
+use crate::events::Event;
+use crate::queries::Client;
+use crate::rpc::{self, InnerTxResult, TxEventQuery, TxResponse};
+use crate::MaybeSend;
+
+/// The default interval between the polls of the node
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The default time to wait for every transition of the status of a tx
+pub const DEFAULT_TRANSITION_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// The status of a tracked tx
+#[derive(Clone, Debug)]
+pub enum TxStatus {
+    /// The tx was broadcast and waits in the mempool to be included in a
+    /// block
+    Mempool,
+    /// The wrapper tx was accepted in a block, so its inner tx will be
+    /// applied
+    WrapperAccepted {
+        /// The height of the block of the wrapper tx
+        height: BlockHeight,
+    },
+    /// The wrapper tx was rejected, so its inner tx won't be applied
+    WrapperRejected {
+        /// The height of the block of the wrapper tx
+        height: BlockHeight,
+        /// The result code of the wrapper tx
+        code: ResultCode,
+        /// The error of the wrapper tx
+        error: String,
+    },
+    /// The inner tx was applied and accepted by all the VPs
+    InnerApplied {
+        /// The height of the block of the inner tx
+        height: BlockHeight,
+        /// The result of the inner tx
+        result: TxResult,
+    },
+    /// The inner tx failed or was rejected by some VPs
+    InnerRejected {
+        /// The height of the block of the inner tx
+        height: BlockHeight,
+        /// The result code of the inner tx
+        code: ResultCode,
+        /// The error of the inner tx
+        error: String,
+    },
+    /// The block of the inner tx is final, as the next block, which commits
+    /// to its state, was committed
+    Finalized {
+        /// The height of the block of the inner tx
+        height: BlockHeight,
+    },
+}
+
+/// A subscription to the events of the committed blocks of a node
+#[cfg_attr(feature = "async-send", async_trait::async_trait)]
+#[cfg_attr(not(feature = "async-send"), async_trait::async_trait(?Send))]
+pub trait EventSubscription: MaybeSend {
+    /// Subscribe to the events that match the filter, in addition to the
+    /// events of the previous filters
+    async fn subscribe(&mut self, filter: EventFilter) -> Result<(), Error>;
+
+    /// The next event that matches any of the filters, or `None` once the
+    /// subscription is closed
+    async fn next_event(&mut self) -> Option<PushedEvent>;
+}
+
+/// The stage of a tracked tx, i.e. its next status transition
+#[derive(Clone, Copy, Debug)]
+enum Stage {
+    Broadcast,
+    Wrapper,
+    Inner,
+    Finality(BlockHeight),
+    Done,
+}
+
+/// The tracker of the status of a broadcast tx, created with [`track_tx`]
+pub struct TxTracker<'a, C> {
+    client: &'a C,
+    wrapper_hash: String,
+    inner_hash: String,
+    subscription: Option<Box<dyn EventSubscription + 'a>>,
+    subscribed: bool,
+    poll_interval: Duration,
+    transition_timeout: Duration,
+    stage: Stage,
+}
+
+/// Track the status of a broadcast tx by the hashes of its wrapper and of its
+/// inner tx. The node is polled for the events of the tx, unless a
+/// subscription is given with [`TxTracker::with_subscription`].
+pub fn track_tx<C>(
+    client: &C,
+    wrapper_hash: Hash,
+    inner_hash: Hash,
+) -> TxTracker<'_, C>
+where
+    C: Client + Sync,
+{
+    TxTracker {
+        client,
+        wrapper_hash: wrapper_hash.to_string(),
+        inner_hash: inner_hash.to_string(),
+        subscription: None,
+        subscribed: false,
+        poll_interval: DEFAULT_POLL_INTERVAL,
+        transition_timeout: DEFAULT_TRANSITION_TIMEOUT,
+        stage: Stage::Broadcast,
+    }
+}
+
+impl<'a, C> TxTracker<'a, C>
+where
+    C: Client + Sync,
+{
+    /// Await the events of the tx on the given subscription. The node is
+    /// still polled for the events emitted before the subscription, and
+    /// after it fails.
+    pub fn with_subscription(
+        mut self,
+        subscription: impl EventSubscription + 'a,
+    ) -> Self {
+        self.subscription = Some(Box::new(subscription));
+        self
+    }
+
+    /// Set the interval between the polls of the node
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Set the time to wait for every transition of the status of the tx
+    pub fn with_transition_timeout(mut self, timeout: Duration) -> Self {
+        self.transition_timeout = timeout;
+        self
+    }
+
+    /// Wait for the next status of the tx. Returns `None` once the tx
+    /// reached its last status or after an error.
+    pub async fn next_status(&mut self) -> Option<Result<TxStatus, Error>> {
+        let status = match self.stage {
+            Stage::Broadcast => Ok(TxStatus::Mempool),
+            Stage::Wrapper => {
+                let hash = self.wrapper_hash.clone();
+                self.wait_for_event(TxEventQuery::Accepted(&hash))
+                    .await
+                    .map(wrapper_status)
+            }
+            Stage::Inner => {
+                let hash = self.inner_hash.clone();
+                self.wait_for_event(TxEventQuery::Applied(&hash))
+                    .await
+                    .map(inner_status)
+            }
+            Stage::Finality(height) => self
+                .wait_for_finality(height)
+                .await
+                .map(|()| TxStatus::Finalized { height }),
+            Stage::Done => return None,
+        };
+        self.stage = match &status {
+            Ok(TxStatus::Mempool) => Stage::Wrapper,
+            Ok(TxStatus::WrapperAccepted { .. }) => Stage::Inner,
+            Ok(
+                TxStatus::InnerApplied { height, .. }
+                | TxStatus::InnerRejected { height, .. },
+            ) => Stage::Finality(*height),
+            _ => Stage::Done,
+        };
+        Some(status)
+    }
+
+    /// The stream of the statuses of the tx
+    pub fn into_stream(self) -> impl Stream<Item = Result<TxStatus, Error>> + 'a
+    where
+        C: 'a,
+    {
+        futures::stream::unfold(self, |mut tracker| async move {
+            tracker.next_status().await.map(|status| (status, tracker))
+        })
+    }
+
+    /// Wait for the outcome of the tx, i.e. the rejection of its wrapper or
+    /// the result of its inner tx once its block is final
+    pub async fn wait(mut self) -> Result<TxStatus, Error> {
+        let mut outcome = None;
+        while let Some(status) = self.next_status().await {
+            if let status @ (TxStatus::WrapperRejected { .. }
+            | TxStatus::InnerApplied { .. }
+            | TxStatus::InnerRejected { .. }) = status?
+            {
+                outcome = Some(status);
+            }
+        }
+        Ok(outcome.expect("The tracking must end with the outcome of the tx"))
+    }
+
+    /// Subscribe to the events of the tx, once
+    async fn subscribe(&mut self) {
+        if self.subscribed {
+            return;
+        }
+        self.subscribed = true;
+        let Some(subscription) = self.subscription.as_mut() else {
+            return;
+        };
+        let filters = [
+            ("accepted", &self.wrapper_hash),
+            ("applied", &self.inner_hash),
+        ];
+        for (event_type, hash) in filters {
+            let filter = EventFilter {
+                event_type: Some(event_type.to_string()),
+                tx_hash: Some(hash.clone()),
+                ..Default::default()
+            };
+            if let Err(err) = subscription.subscribe(filter).await {
+                tracing::debug!(
+                    %err,
+                    "Failed to subscribe to the tx events, falling back to \
+                     polling"
+                );
+                self.subscription = None;
+                return;
+            }
+        }
+    }
+
+    /// Wait for the event of the tx matching the query
+    async fn wait_for_event(
+        &mut self,
+        query: TxEventQuery<'_>,
+    ) -> Result<TxResponse, Error> {
+        self.subscribe().await;
+        let deadline = Instant::now() + self.transition_timeout;
+        loop {
+            match rpc::query_tx_events(self.client, query).await {
+                Ok(Some(event)) => return tx_response(event),
+                Ok(None) => {}
+                Err(err) => tracing::debug!(
+                    ?query,
+                    %err,
+                    "Failed to query the tx events, retrying"
+                ),
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(Error::Tx(match query {
+                    TxEventQuery::Accepted(_) => TxSubmitError::AcceptTimeout,
+                    TxEventQuery::Applied(_) => TxSubmitError::AppliedTimeout,
+                }));
+            }
+
+            // Until the next poll, wait for the event to be pushed
+            let subscription = &mut self.subscription;
+            let pushed = time::timeout_at(
+                (now + self.poll_interval).min(deadline),
+                async {
+                    match subscription.as_mut() {
+                        Some(subscription) => subscription.next_event().await,
+                        None => futures::future::pending().await,
+                    }
+                },
+            )
+            .await;
+            match pushed {
+                Ok(Some(event))
+                    if event.event_type == query.event_type()
+                        && event.attributes.get("hash").is_some_and(
+                            |hash| hash.eq_ignore_ascii_case(query.tx_hash()),
+                        ) =>
+                {
+                    let event = Event::try_from(event)?;
+                    return tx_response(event);
+                }
+                // An event of the other query of the tx
+                Ok(Some(_)) => {}
+                Ok(None) => {
+                    tracing::debug!(
+                        "The subscription to the tx events was closed, \
+                         falling back to polling"
+                    );
+                    self.subscription = None;
+                }
+                // The next poll is due
+                Err(_) => {}
+            }
+        }
+    }
+
+    /// Wait for the block at the given height to be final
+    async fn wait_for_finality(
+        &mut self,
+        height: BlockHeight,
+    ) -> Result<(), Error> {
+        let deadline = Instant::now() + self.transition_timeout;
+        loop {
+            match rpc::query_block(self.client).await {
+                Ok(Some(block)) if block.height > height => return Ok(()),
+                Ok(_) => {}
+                Err(err) => tracing::debug!(
+                    %err,
+                    "Failed to query the last committed block, retrying"
+                ),
+            }
+            if Instant::now() >= deadline {
+                return Err(Error::Query(QueryError::General(format!(
+                    "The block at height {height} wasn't finalized before the \
+                     deadline"
+                ))));
+            }
+            time::sleep(self.poll_interval).await;
+        }
+    }
+}
+
+/// Parse the response of a tx event
+fn tx_response(event: Event) -> Result<TxResponse, Error> {
+    TxResponse::try_from(event)
+        .map_err(|err| Error::Encode(EncodingError::Decoding(err)))
+}
+
+/// The status of a wrapper tx from its response
+fn wrapper_status(response: TxResponse) -> TxStatus {
+    if response.code == ResultCode::Ok {
+        TxStatus::WrapperAccepted {
+            height: response.height,
+        }
+    } else {
+        TxStatus::WrapperRejected {
+            height: response.height,
+            code: response.code,
+            error: response.info,
+        }
+    }
+}
+
+/// The status of an inner tx from its response
+fn inner_status(response: TxResponse) -> TxStatus {
+    let error = match response.inner_tx_result() {
+        InnerTxResult::Success(_) if response.code == ResultCode::Ok => None,
+        InnerTxResult::VpsRejected(result) => Some(format!(
+            "The tx was rejected by the VPs of {}",
+            result
+                .vps_result
+                .rejected_vps
+                .iter()
+                .map(Address::encode)
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+        _ => Some(response.info.clone()),
+    };
+    match error {
+        None => TxStatus::InnerApplied {
+            height: response.height,
+            result: response.inner_tx.unwrap_or_default(),
+        },
+        Some(error) => TxStatus::InnerRejected {
+            height: response.height,
+            code: response.code,
+            error,
+        },
+    }
+}
+
+#[cfg(all(feature = "ws-client", not(target_family = "wasm")))]
+pub use ws::WsSubscription;
+
+#[cfg(all(feature = "ws-client", not(target_family = "wasm")))]
+mod ws {
+    use futures::{SinkExt, StreamExt};
+    use tokio::net::TcpStream;
+    use tokio_tungstenite::tungstenite::Message;
+    use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+    use super::*;
+
+    /// A subscription to the WebSocket endpoint of the event subscriptions of
+    /// a node
+    pub struct WsSubscription {
+        socket: WebSocketStream<MaybeTlsStream<TcpStream>>,
+    }
+
+    impl WsSubscription {
+        /// Connect to the endpoint at the given URL, e.g.
+        /// `ws://127.0.0.1:26660`
+        pub async fn connect(url: &str) -> Result<Self, Error> {
+            let (socket, _) =
+                tokio_tungstenite::connect_async(url).await.map_err(|err| {
+                    Error::Other(format!(
+                        "Failed to connect to the event subscriptions at \
+                         {url}: {err}"
+                    ))
+                })?;
+            Ok(Self { socket })
+        }
+    }
+
+    #[cfg_attr(feature = "async-send", async_trait::async_trait)]
+    #[cfg_attr(not(feature = "async-send"), async_trait::async_trait(?Send))]
+    impl EventSubscription for WsSubscription {
+        async fn subscribe(
+            &mut self,
+            filter: EventFilter,
+        ) -> Result<(), Error> {
+            let filter = serde_json::to_string(&filter)
+                .expect("Event filters must be serializable");
+            self.socket
+                .send(Message::Text(filter))
+                .await
+                .map_err(|err| {
+                    Error::Other(format!(
+                        "Failed to send an event filter: {err}"
+                    ))
+                })
+        }
+
+        async fn next_event(&mut self) -> Option<PushedEvent> {
+            while let Some(msg) = self.socket.next().await {
+                match msg {
+                    Ok(Message::Text(msg)) => {
+                        match serde_json::from_str(&msg) {
+                            Ok(event) => return Some(event),
+                            // The errors reported by the node, e.g. when
+                            // events were missed, which the polls make up for
+                            Err(_) => tracing::debug!(
+                                "Event subscription message: {msg}"
+                            ),
+                        }
+                    }
+                    Ok(Message::Close(_)) | Err(_) => return None,
+                    Ok(_) => {}
+                }
+            }
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use namada_core::types::address::testing::established_address_1;
+    use namada_tx::data::VpsResult;
+
+    use super::*;
+
+    /// A response to a tx event
+    fn response(code: ResultCode, inner_tx: Option<TxResult>) -> TxResponse {
+        TxResponse {
+            inner_tx,
+            info: "Failed".to_string(),
+            log: String::new(),
+            height: BlockHeight(2),
+            hash: String::new(),
+            code,
+            gas_used: "0".to_string(),
+        }
+    }
+
+    /// Test the statuses of the wrapper and the inner txs from their events
+    #[test]
+    fn test_tx_status_from_response() {
+        assert!(matches!(
+            wrapper_status(response(ResultCode::Ok, None)),
+            TxStatus::WrapperAccepted {
+                height: BlockHeight(2)
+            }
+        ));
+        assert!(matches!(
+            wrapper_status(response(ResultCode::InvalidTx, None)),
+            TxStatus::WrapperRejected {
+                code: ResultCode::InvalidTx,
+                error,
+                ..
+            } if error == "Failed"
+        ));
+
+        let accepted = TxResult::default();
+        assert!(matches!(
+            inner_status(response(ResultCode::Ok, Some(accepted))),
+            TxStatus::InnerApplied { .. }
+        ));
+        let rejected = TxResult {
+            vps_result: VpsResult {
+                rejected_vps: BTreeSet::from([established_address_1()]),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert!(matches!(
+            inner_status(response(ResultCode::Ok, Some(rejected))),
+            TxStatus::InnerRejected { error, .. }
+                if error.contains(&established_address_1().encode())
+        ));
+        assert!(matches!(
+            inner_status(response(ResultCode::WasmRuntimeError, None)),
+            TxStatus::InnerRejected { error, .. } if error == "Failed"
+        ));
+    }
+}
@@ -0,0 +1,603 @@
+//! Backends holding the secret keys of the wallet outside of its store. A key
+//! can be kept in the keychain of the OS (the macOS Keychain or the Secret
+//! Service on Linux), or by a remote signer that signs on behalf of the wallet
+//! so that the key never reaches the host of the wallet. The store only keeps
+//! the public key and the backend of such a key, by its alias.
+
+use std::fmt::Display;
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use thiserror::Error;
+
+use super::alias::Alias;
+
+const KEYCHAIN_PREFIX: &str = "keychain:";
+const REMOTE_SIGNER_PREFIX: &str = "remote:";
+const TCP_SCHEME: &str = "tcp://";
+const UNIX_SCHEME: &str = "unix://";
+
+/// The default service of the keys stored in the keychain of the OS
+pub const DEFAULT_KEYCHAIN_SERVICE: &str = "namada";
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum KeyBackendError {
+    #[error("Invalid key backend {0}")]
+    InvalidBackend(String),
+    #[error("Keychain error: {0}")]
+    Keychain(String),
+    #[error("The remote signer at {endpoint} failed: {error}")]
+    RemoteSigner { endpoint: String, error: String },
+    #[error("The remote signer rejected the request: {0}")]
+    Rejected(String),
+    #[error("The key {0} is held by a remote signer and cannot be exported")]
+    RemoteKey(String),
+}
+
+/// The endpoint of a remote signer
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SignerEndpoint {
+    /// A TCP socket address, as `host:port`
+    Tcp(String),
+    /// The path of a Unix socket
+    Unix(std::path::PathBuf),
+}
+
+impl Display for SignerEndpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SignerEndpoint::Tcp(addr) => write!(f, "{TCP_SCHEME}{addr}"),
+            SignerEndpoint::Unix(path) => {
+                write!(f, "{UNIX_SCHEME}{}", path.to_string_lossy())
+            }
+        }
+    }
+}
+
+impl FromStr for SignerEndpoint {
+    type Err = KeyBackendError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(addr) = s.strip_prefix(TCP_SCHEME) {
+            Ok(SignerEndpoint::Tcp(addr.to_string()))
+        } else if let Some(path) = s.strip_prefix(UNIX_SCHEME) {
+            Ok(SignerEndpoint::Unix(path.into()))
+        } else {
+            Err(KeyBackendError::InvalidBackend(format!(
+                "{s}: expected a {TCP_SCHEME} or {UNIX_SCHEME} endpoint"
+            )))
+        }
+    }
+}
+
+/// The backend of a key held outside of the store of the wallet
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum KeyBackend {
+    /// The secret key is stored in the keychain of the OS, under the given
+    /// service and the alias of the key
+    Keychain {
+        /// The service of the key in the keychain
+        service: String,
+    },
+    /// The key is held by a remote signer
+    RemoteSigner {
+        /// The endpoint of the signer
+        endpoint: SignerEndpoint,
+        /// The alias of the key of the wallet authenticating the requests to
+        /// the signer
+        auth_key: Alias,
+    },
+}
+
+impl Display for KeyBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KeyBackend::Keychain { service } => {
+                write!(f, "{KEYCHAIN_PREFIX}{service}")
+            }
+            KeyBackend::RemoteSigner { endpoint, auth_key } => {
+                write!(f, "{REMOTE_SIGNER_PREFIX}{auth_key}@{endpoint}")
+            }
+        }
+    }
+}
+
+impl FromStr for KeyBackend {
+    type Err = KeyBackendError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(service) = s.strip_prefix(KEYCHAIN_PREFIX) {
+            Ok(KeyBackend::Keychain {
+                service: service.to_string(),
+            })
+        } else if let Some(remote) = s.strip_prefix(REMOTE_SIGNER_PREFIX) {
+            let (auth_key, endpoint) =
+                remote.split_once('@').ok_or_else(|| {
+                    KeyBackendError::InvalidBackend(format!(
+                        "{s}: expected {REMOTE_SIGNER_PREFIX}<auth key \
+                         alias>@<endpoint>"
+                    ))
+                })?;
+            Ok(KeyBackend::RemoteSigner {
+                endpoint: endpoint.parse()?,
+                auth_key: auth_key.into(),
+            })
+        } else {
+            Err(KeyBackendError::InvalidBackend(s.to_string()))
+        }
+    }
+}
+
+impl Serialize for KeyBackend {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        // String encoded, because toml doesn't support enums
+        s.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for KeyBackend {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        let string = String::deserialize(d)?;
+        string.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// The storage of the secret keys in the keychain of the OS
+#[cfg(all(feature = "keychain", not(target_family = "wasm")))]
+pub mod keychain {
+    use namada_core::types::key::common;
+    use zeroize::Zeroizing;
+
+    use super::KeyBackendError;
+
+    fn entry(
+        service: &str,
+        alias: &str,
+    ) -> Result<keyring::Entry, KeyBackendError> {
+        keyring::Entry::new(service, alias)
+            .map_err(|err| KeyBackendError::Keychain(err.to_string()))
+    }
+
+    /// Store a secret key in the keychain under the given service and alias
+    pub fn store_key(
+        service: &str,
+        alias: &str,
+        secret_key: &common::SecretKey,
+    ) -> Result<(), KeyBackendError> {
+        let encoded = Zeroizing::new(secret_key.to_string());
+        entry(service, alias)?
+            .set_password(&encoded)
+            .map_err(|err| KeyBackendError::Keychain(err.to_string()))
+    }
+
+    /// Load the secret key stored in the keychain under the given service and
+    /// alias
+    pub fn load_key(
+        service: &str,
+        alias: &str,
+    ) -> Result<common::SecretKey, KeyBackendError> {
+        let encoded = entry(service, alias)?
+            .get_password()
+            .map(Zeroizing::new)
+            .map_err(|err| KeyBackendError::Keychain(err.to_string()))?;
+        encoded
+            .parse()
+            .map_err(|err| KeyBackendError::Keychain(format!("{err}")))
+    }
+
+    /// Delete the secret key stored in the keychain under the given service
+    /// and alias
+    pub fn delete_key(
+        service: &str,
+        alias: &str,
+    ) -> Result<(), KeyBackendError> {
+        entry(service, alias)?
+            .delete_password()
+            .map_err(|err| KeyBackendError::Keychain(err.to_string()))
+    }
+}
+
+/// The protocol of the remote signers. The requests and the responses are
+/// Borsh encoded, each prefixed with its length as a big-endian `u32`. The
+/// requests are signed with a key of the wallet that the signer must
+/// authorize, and are only valid for a short period after their timestamp.
+#[cfg(not(target_family = "wasm"))]
+pub mod remote {
+    use std::collections::{HashMap, HashSet};
+    use std::sync::Arc;
+
+    use borsh::{BorshDeserialize, BorshSerialize};
+    use borsh_ext::BorshSerializeExt;
+    use namada_core::types::hash::Hash;
+    use namada_core::types::key::{common, RefTo, SigScheme};
+    use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+    use super::{KeyBackendError, SignerEndpoint};
+    use crate::control_flow::time::{self, Duration, Instant};
+
+    /// The maximum size of a request or a response, in bytes
+    const MAX_FRAME_LEN: u32 = 64 * 1024;
+
+    /// The timeout of a request to a remote signer
+    pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+    /// The maximum difference between the timestamp of a request and the
+    /// clock of the signer
+    pub const MAX_CLOCK_SKEW: Duration = Duration::from_secs(30);
+
+    /// A request to a remote signer to sign a message with one of its keys
+    #[derive(Clone, Debug, BorshSerialize, BorshDeserialize)]
+    pub struct SignRequest {
+        /// The public key of the key to sign with
+        pub public_key: common::PublicKey,
+        /// The message to sign
+        pub message: Hash,
+        /// The time of the request, in milliseconds since the Unix epoch
+        pub timestamp: u64,
+        /// The public key authenticating the request
+        pub auth_key: common::PublicKey,
+        /// The signature of the request by the authentication key
+        pub auth_signature: common::Signature,
+    }
+
+    impl SignRequest {
+        /// A request signed with the authentication key
+        pub fn new(
+            public_key: common::PublicKey,
+            message: Hash,
+            timestamp: u64,
+            auth_key: &common::SecretKey,
+        ) -> Self {
+            let auth_key_pk = auth_key.ref_to();
+            let digest =
+                Self::digest(&public_key, &message, timestamp, &auth_key_pk);
+            Self {
+                public_key,
+                message,
+                timestamp,
+                auth_key: auth_key_pk,
+                auth_signature: common::SigScheme::sign(auth_key, digest),
+            }
+        }
+
+        /// The digest of the request signed by the authentication key
+        fn digest(
+            public_key: &common::PublicKey,
+            message: &Hash,
+            timestamp: u64,
+            auth_key: &common::PublicKey,
+        ) -> Hash {
+            Hash::sha256(
+                (public_key, message, timestamp, auth_key).serialize_to_vec(),
+            )
+        }
+    }
+
+    /// The response of a remote signer
+    #[derive(Clone, Debug, BorshSerialize, BorshDeserialize)]
+    pub enum SignResponse {
+        /// The signature of the message
+        Signature(common::Signature),
+        /// The reason the request was rejected
+        Rejected(String),
+    }
+
+    /// Request the signature of a message by the remote signer at the
+    /// endpoint, with the request authenticated by the given key. The
+    /// signature is verified before it's returned.
+    pub async fn request_signature(
+        endpoint: &SignerEndpoint,
+        auth_key: &common::SecretKey,
+        public_key: &common::PublicKey,
+        message: Hash,
+    ) -> Result<common::Signature, KeyBackendError> {
+        let signer_err = |error: String| KeyBackendError::RemoteSigner {
+            endpoint: endpoint.to_string(),
+            error,
+        };
+        let request =
+            SignRequest::new(public_key.clone(), message, now_ms(), auth_key);
+        let deadline = Instant::now() + REQUEST_TIMEOUT;
+        let response = time::timeout_at(deadline, async {
+            match endpoint {
+                SignerEndpoint::Tcp(addr) => {
+                    let stream = tokio::net::TcpStream::connect(addr).await?;
+                    exchange(stream, &request).await
+                }
+                #[cfg(unix)]
+                SignerEndpoint::Unix(path) => {
+                    let stream = tokio::net::UnixStream::connect(path).await?;
+                    exchange(stream, &request).await
+                }
+                #[cfg(not(unix))]
+                SignerEndpoint::Unix(_) => Err(std::io::Error::new(
+                    std::io::ErrorKind::Unsupported,
+                    "Unix sockets are not supported on this platform",
+                )),
+            }
+        })
+        .await
+        .map_err(|_| signer_err("The request timed out".to_string()))?
+        .map_err(|err| signer_err(err.to_string()))?;
+
+        match response {
+            SignResponse::Signature(signature) => {
+                common::SigScheme::verify_signature(
+                    public_key, &message, &signature,
+                )
+                .map_err(|err| {
+                    signer_err(format!("Invalid signature: {err}"))
+                })?;
+                Ok(signature)
+            }
+            SignResponse::Rejected(reason) => {
+                Err(KeyBackendError::Rejected(reason))
+            }
+        }
+    }
+
+    /// Send a request on the stream and read its response
+    pub(super) async fn exchange<S>(
+        mut stream: S,
+        request: &SignRequest,
+    ) -> std::io::Result<SignResponse>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        write_frame(&mut stream, request).await?;
+        read_frame(&mut stream).await?.ok_or_else(|| {
+            std::io::Error::from(std::io::ErrorKind::UnexpectedEof)
+        })
+    }
+
+    /// A remote signer holding secret keys, that signs the requests
+    /// authenticated by the authorized keys
+    #[derive(Debug, Default)]
+    pub struct RemoteSigner {
+        keys: HashMap<common::PublicKey, common::SecretKey>,
+        authorized: HashSet<common::PublicKey>,
+    }
+
+    impl RemoteSigner {
+        /// A signer of the given keys, serving the requests authenticated by
+        /// the authorized keys
+        pub fn new(
+            keys: impl IntoIterator<Item = common::SecretKey>,
+            authorized: impl IntoIterator<Item = common::PublicKey>,
+        ) -> Self {
+            Self {
+                keys: keys.into_iter().map(|sk| (sk.ref_to(), sk)).collect(),
+                authorized: authorized.into_iter().collect(),
+            }
+        }
+
+        /// Sign a request received at the given time, in milliseconds since
+        /// the Unix epoch
+        pub fn sign(&self, request: &SignRequest, now: u64) -> SignResponse {
+            let reject = |reason: &str| SignResponse::Rejected(reason.into());
+            if !self.authorized.contains(&request.auth_key) {
+                return reject("The authentication key is not authorized");
+            }
+            let digest = SignRequest::digest(
+                &request.public_key,
+                &request.message,
+                request.timestamp,
+                &request.auth_key,
+            );
+            if common::SigScheme::verify_signature(
+                &request.auth_key,
+                &digest,
+                &request.auth_signature,
+            )
+            .is_err()
+            {
+                return reject("Invalid authentication signature");
+            }
+            if now.abs_diff(request.timestamp)
+                > MAX_CLOCK_SKEW.as_millis() as u64
+            {
+                return reject("The request has expired");
+            }
+            match self.keys.get(&request.public_key) {
+                Some(secret_key) => SignResponse::Signature(
+                    common::SigScheme::sign(secret_key, request.message),
+                ),
+                None => reject("Unknown key"),
+            }
+        }
+
+        /// Serve the requests received on a connection until it's closed
+        pub async fn serve_connection<S>(
+            &self,
+            mut stream: S,
+        ) -> std::io::Result<()>
+        where
+            S: AsyncRead + AsyncWrite + Unpin,
+        {
+            while let Some(request) =
+                read_frame::<_, SignRequest>(&mut stream).await?
+            {
+                let response = self.sign(&request, now_ms());
+                write_frame(&mut stream, &response).await?;
+            }
+            Ok(())
+        }
+
+        /// Serve the requests received on the endpoint
+        pub async fn serve(
+            self: Arc<Self>,
+            endpoint: &SignerEndpoint,
+        ) -> std::io::Result<()> {
+            match endpoint {
+                SignerEndpoint::Tcp(addr) => {
+                    let listener = tokio::net::TcpListener::bind(addr).await?;
+                    loop {
+                        let (stream, _) = listener.accept().await?;
+                        self.clone().spawn_connection(stream);
+                    }
+                }
+                #[cfg(unix)]
+                SignerEndpoint::Unix(path) => {
+                    let listener = tokio::net::UnixListener::bind(path)?;
+                    loop {
+                        let (stream, _) = listener.accept().await?;
+                        self.clone().spawn_connection(stream);
+                    }
+                }
+                #[cfg(not(unix))]
+                SignerEndpoint::Unix(_) => Err(std::io::Error::new(
+                    std::io::ErrorKind::Unsupported,
+                    "Unix sockets are not supported on this platform",
+                )),
+            }
+        }
+
+        fn spawn_connection<S>(self: Arc<Self>, stream: S)
+        where
+            S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+        {
+            tokio::spawn(async move {
+                if let Err(err) = self.serve_connection(stream).await {
+                    tracing::debug!("A remote signer connection failed: {err}");
+                }
+            });
+        }
+    }
+
+    /// Write a length-prefixed Borsh encoded frame
+    async fn write_frame<S, T>(stream: &mut S, value: &T) -> std::io::Result<()>
+    where
+        S: AsyncWrite + Unpin,
+        T: BorshSerialize,
+    {
+        let bytes = value.serialize_to_vec();
+        stream.write_u32(bytes.len() as u32).await?;
+        stream.write_all(&bytes).await?;
+        stream.flush().await
+    }
+
+    /// Read a length-prefixed Borsh encoded frame, or nothing if the stream
+    /// was closed
+    async fn read_frame<S, T>(stream: &mut S) -> std::io::Result<Option<T>>
+    where
+        S: AsyncRead + Unpin,
+        T: BorshDeserialize,
+    {
+        let len = match stream.read_u32().await {
+            Ok(len) => len,
+            Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => {
+                return Ok(None);
+            }
+            Err(err) => return Err(err),
+        };
+        if len > MAX_FRAME_LEN {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("The frame of {len} bytes is too large"),
+            ));
+        }
+        let mut bytes = vec![0; len as usize];
+        stream.read_exact(&mut bytes).await?;
+        T::try_from_slice(&bytes).map(Some)
+    }
+
+    /// The current time in milliseconds since the Unix epoch
+    pub(super) fn now_ms() -> u64 {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|now| now.as_millis() as u64)
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_backend_encoding() {
+        let backends = [
+            KeyBackend::Keychain {
+                service: DEFAULT_KEYCHAIN_SERVICE.to_string(),
+            },
+            KeyBackend::RemoteSigner {
+                endpoint: SignerEndpoint::Tcp("127.0.0.1:7000".to_string()),
+                auth_key: "operator".into(),
+            },
+            KeyBackend::RemoteSigner {
+                endpoint: SignerEndpoint::Unix("/run/signer.sock".into()),
+                auth_key: "operator".into(),
+            },
+        ];
+        for backend in backends {
+            assert_eq!(
+                backend.to_string().parse::<KeyBackend>().unwrap(),
+                backend
+            );
+        }
+        assert_eq!(
+            KeyBackend::from_str("remote:operator@tcp://10.0.0.1:7000")
+                .unwrap()
+                .to_string(),
+            "remote:operator@tcp://10.0.0.1:7000"
+        );
+        assert!(KeyBackend::from_str("remote:tcp://10.0.0.1:7000").is_err());
+        assert!(KeyBackend::from_str("remote:op@http://10.0.0.1").is_err());
+        assert!(KeyBackend::from_str("ledger").is_err());
+    }
+
+    #[cfg(not(target_family = "wasm"))]
+    #[tokio::test]
+    async fn test_remote_signer() {
+        use namada_core::types::hash::Hash;
+        use namada_core::types::key::{common, RefTo, SchemeType, SigScheme};
+
+        use super::remote::*;
+
+        let gen_key = || {
+            crate::wallet::gen_secret_key(
+                SchemeType::Ed25519,
+                &mut rand_core::OsRng,
+            )
+        };
+        let (key, auth_key, other_key) = (gen_key(), gen_key(), gen_key());
+        let signer = RemoteSigner::new([key.clone()], [auth_key.ref_to()]);
+        let message = Hash::sha256(b"tx");
+
+        let (client, server) = tokio::io::duplex(1024);
+        tokio::spawn(async move { signer.serve_connection(server).await });
+
+        let request =
+            SignRequest::new(key.ref_to(), message, now_ms(), &auth_key);
+        let SignResponse::Signature(signature) =
+            exchange(client, &request).await.unwrap()
+        else {
+            panic!("The request must be signed")
+        };
+        common::SigScheme::verify_signature(
+            &key.ref_to(),
+            &message,
+            &signature,
+        )
+        .unwrap();
+
+        // The requests of unauthorized keys, for unknown keys or expired are
+        // rejected
+        let signer = RemoteSigner::new([key.clone()], [auth_key.ref_to()]);
+        let unauthorized =
+            SignRequest::new(key.ref_to(), message, now_ms(), &other_key);
+        let unknown =
+            SignRequest::new(other_key.ref_to(), message, now_ms(), &auth_key);
+        let expired = SignRequest::new(key.ref_to(), message, 0, &auth_key);
+        let mut tampered =
+            SignRequest::new(key.ref_to(), message, now_ms(), &auth_key);
+        tampered.message = Hash::sha256(b"other tx");
+        for request in [unauthorized, unknown, expired, tampered] {
+            assert!(matches!(
+                signer.sign(&request, now_ms()),
+                SignResponse::Rejected(_)
+            ));
+        }
+    }
+}
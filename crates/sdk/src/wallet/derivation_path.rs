@@ -204,6 +204,52 @@ impl DerivationPath {
         Self::zip32(0, None)
     }
 
+    /// The BIP44 path of an account and address index for the scheme,
+    /// hardened for ed25519
+    pub fn for_transparent_account(
+        scheme: SchemeType,
+        account: u32,
+        address: u32,
+    ) -> Self {
+        Self::bip44(scheme, account, 0, address).hardened(scheme)
+    }
+
+    /// The ZIP32 path of an account, with an optional address index
+    pub fn for_shielded_account(account: u32, address: Option<u32>) -> Self {
+        Self::zip32(account, address)
+    }
+
+    /// The account index of a BIP44 or ZIP32 path, i.e. its third index
+    pub fn account(&self) -> Option<u32> {
+        self.0.as_ref().get(2).map(|idx| idx.to_u32())
+    }
+
+    /// The path with its account index replaced, keeping its hardening. The
+    /// paths without an account index are extended with a hardened one.
+    pub fn with_account(&self, account: u32) -> Self {
+        let mut indexes = self.0.as_ref().to_vec();
+        match indexes.get_mut(2) {
+            Some(ChildIndex::Hardened(idx) | ChildIndex::Normal(idx)) => {
+                *idx = account;
+            }
+            None => {
+                indexes.resize(2, ChildIndex::Hardened(0));
+                indexes.push(ChildIndex::Hardened(account));
+            }
+        }
+        Self::new(indexes)
+    }
+
+    /// Whether the paths only differ by their account index
+    pub fn same_but_account(&self, other: &Self) -> bool {
+        let (this, other) = (self.0.as_ref(), other.0.as_ref());
+        this.len() == other.len()
+            && this.len() > 2
+            && this.iter().zip(other).enumerate().all(|(i, (a, b))| {
+                (i == 2 && a.is_hardened() == b.is_hardened()) || a == b
+            })
+    }
+
     pub fn from_path_string(path: &str) -> Result<Self, DerivationPathError> {
         let inner = DerivationPathInner::from_str(path).map_err(|err| {
             DerivationPathError::InvalidDerivationPath(err.to_string())
@@ -386,4 +432,40 @@ mod tests {
         );
         assert!(path_z_2.is_namada_shielded_compliant());
     }
+
+    #[test]
+    fn path_accounts() {
+        let path =
+            DerivationPath::for_transparent_account(SchemeType::Ed25519, 3, 0);
+        assert_eq!(path.to_string(), "m/44'/877'/3'/0'/0'");
+        assert_eq!(path.account(), Some(3));
+        assert!(path.is_namada_transparent_compliant(SchemeType::Ed25519));
+        let path = DerivationPath::for_transparent_account(
+            SchemeType::Secp256k1,
+            1,
+            2,
+        );
+        assert_eq!(path.to_string(), "m/44'/60'/1'/0/2");
+        assert!(path.is_namada_transparent_compliant(SchemeType::Secp256k1));
+        let path = DerivationPath::for_shielded_account(7, None);
+        assert_eq!(path.to_string(), "m/32'/877'/7'");
+        assert!(path.is_namada_shielded_compliant());
+
+        // The account index of a custom path is replaced
+        let custom = DerivationPath::from_path_string("m/44'/877'/0'/5'/9'")
+            .expect("Path construction cannot fail.");
+        let other = custom.with_account(12);
+        assert_eq!(other.to_string(), "m/44'/877'/12'/5'/9'");
+        assert!(custom.same_but_account(&other));
+        assert!(!custom.same_but_account(
+            &DerivationPath::for_transparent_account(
+                SchemeType::Ed25519,
+                12,
+                0
+            )
+        ));
+        let short = DerivationPath::from_path_string("m/32'")
+            .expect("Path construction cannot fail.");
+        assert_eq!(short.with_account(1).to_string(), "m/32'/0'/1'");
+    }
 }
@@ -4,6 +4,7 @@ use std::fmt::Display;
 use std::marker::PhantomData;
 use std::str::FromStr;
 
+use argon2::{Algorithm, Argon2, Version};
 use borsh::{BorshDeserialize, BorshSerialize};
 use borsh_ext::BorshSerializeExt;
 use data_encoding::HEXLOWER;
@@ -16,6 +17,12 @@ use crate::wallet::WalletIo;
 
 const ENCRYPTED_KEY_PREFIX: &str = "encrypted:";
 const UNENCRYPTED_KEY_PREFIX: &str = "unencrypted:";
+const ARGON2ID_KEY_PREFIX: &str = "argon2id:";
+
+/// The length of the salts of the encryption keys
+const SALT_LEN: usize = 16;
+/// The length of the encryption keys
+const KEY_LEN: usize = 32;
 
 /// A keypair stored in a wallet
 #[derive(Debug)]
@@ -90,11 +97,6 @@ where
         {
             FromStr::from_str(encrypted)
                 .map(Self::Encrypted)
-                .map_err(|err| {
-                    DeserializeStoredKeypairError::InvalidStoredKeypairString(
-                        err.to_string(),
-                    )
-                })
                 .map_err(D::Error::custom)
         } else {
             Err(DeserializeStoredKeypairError::MissingPrefix)
@@ -112,24 +114,100 @@ pub enum DeserializeStoredKeypairError {
     MissingPrefix,
 }
 
-/// An encrypted keypair stored in a wallet
+/// The parameters of the Argon2id derivation of the encryption keys
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, BorshSerialize, BorshDeserialize,
+)]
+pub struct Argon2Params {
+    /// The memory size, in KiB
+    pub m_cost: u32,
+    /// The number of iterations
+    pub t_cost: u32,
+    /// The degree of parallelism
+    pub p_cost: u32,
+}
+
+impl Default for Argon2Params {
+    fn default() -> Self {
+        Self {
+            m_cost: 1 << 17,
+            t_cost: 3,
+            p_cost: 1,
+        }
+    }
+}
+
+/// The derivation of the encryption key of an encrypted keypair from its
+/// password
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyDerivation {
+    /// The Argon2i derivation of the keys encrypted by the previous versions
+    /// of the wallet, to be migrated
+    LegacyArgon2i,
+    /// The Argon2id derivation with the given parameters
+    Argon2id(Argon2Params),
+}
+
+/// An encrypted keypair stored in a wallet, with the salt of its encryption
+/// key prepended to its cipher
 #[derive(Debug)]
-pub struct EncryptedKeypair<T: BorshSerialize + BorshDeserialize>(
-    Vec<u8>,
-    PhantomData<T>,
-);
+pub struct EncryptedKeypair<T: BorshSerialize + BorshDeserialize> {
+    kdf: KeyDerivation,
+    data: Vec<u8>,
+    phantom: PhantomData<T>,
+}
 
 impl<T: BorshSerialize + BorshDeserialize> Display for EncryptedKeypair<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", HEXLOWER.encode(self.0.as_ref()))
+        match &self.kdf {
+            KeyDerivation::LegacyArgon2i => {
+                write!(f, "{}", HEXLOWER.encode(&self.data))
+            }
+            KeyDerivation::Argon2id(params) => {
+                let encoded = [params.serialize_to_vec(), self.data.clone()];
+                write!(
+                    f,
+                    "{}{}",
+                    ARGON2ID_KEY_PREFIX,
+                    HEXLOWER.encode(&encoded.concat())
+                )
+            }
+        }
     }
 }
 
 impl<T: BorshSerialize + BorshDeserialize> FromStr for EncryptedKeypair<T> {
-    type Err = data_encoding::DecodeError;
+    type Err = DeserializeStoredKeypairError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        HEXLOWER.decode(s.as_ref()).map(|x| Self(x, PhantomData))
+        let invalid = |err: &dyn Display| {
+            DeserializeStoredKeypairError::InvalidStoredKeypairString(
+                err.to_string(),
+            )
+        };
+        let (kdf, data) = match s.strip_prefix(ARGON2ID_KEY_PREFIX) {
+            Some(encoded) => {
+                let encoded = HEXLOWER
+                    .decode(encoded.as_ref())
+                    .map_err(|err| invalid(&err))?;
+                let mut data = encoded.as_slice();
+                let params = Argon2Params::deserialize(&mut data)
+                    .map_err(|err| invalid(&err))?;
+                (KeyDerivation::Argon2id(params), data.to_vec())
+            }
+            None => (
+                KeyDerivation::LegacyArgon2i,
+                HEXLOWER.decode(s.as_ref()).map_err(|err| invalid(&err))?,
+            ),
+        };
+        if data.len() < SALT_LEN {
+            return Err(invalid(&"missing encryption salt"));
+        }
+        Ok(Self {
+            kdf,
+            data,
+            phantom: PhantomData,
+        })
     }
 }
 
@@ -146,6 +224,8 @@ pub enum DecryptionError {
     NotDecrypting,
     #[error("Empty password provided")]
     EmptyPassword,
+    #[error("Invalid key derivation parameters: {0}")]
+    InvalidKdfParams(String),
 }
 
 impl<T: BorshSerialize + BorshDeserialize + Display + FromStr + Clone>
@@ -196,22 +276,73 @@ where
             StoredKeypair::Raw(_) => false,
         }
     }
+
+    /// Indicates whether this key has been encrypted by a previous version of
+    /// the wallet, and should be migrated by changing its password
+    pub fn is_legacy_encrypted(&self) -> bool {
+        matches!(
+            self,
+            StoredKeypair::Encrypted(EncryptedKeypair {
+                kdf: KeyDerivation::LegacyArgon2i,
+                ..
+            })
+        )
+    }
+
+    /// Decrypt the key with its password, if it's encrypted, and re-encrypt
+    /// it with a new password and the current key derivation. Without a new
+    /// password, the key is stored raw.
+    pub fn change_password(
+        &self,
+        password: Option<Zeroizing<String>>,
+        new_password: Option<Zeroizing<String>>,
+    ) -> Result<Self, DecryptionError> {
+        let keypair = match self {
+            StoredKeypair::Encrypted(encrypted_keypair) => encrypted_keypair
+                .decrypt(password.ok_or(DecryptionError::NotDecrypting)?)?,
+            StoredKeypair::Raw(keypair) => keypair.clone(),
+        };
+        Ok(Self::new(keypair, new_password).0)
+    }
 }
 
 impl<T: BorshSerialize + BorshDeserialize> EncryptedKeypair<T> {
-    /// Encrypt a keypair and store it with its salt.
+    /// Encrypt a keypair with a key derived from the password with Argon2id
+    /// and the default parameters, and store it with its salt.
     pub fn new(keypair: &T, password: Zeroizing<String>) -> Self {
+        Self::new_with_params(keypair, password, Argon2Params::default())
+    }
+
+    /// Encrypt a keypair with a key derived from the password with Argon2id
+    /// and the given parameters, and store it with its salt.
+    pub fn new_with_params(
+        keypair: &T,
+        password: Zeroizing<String>,
+        params: Argon2Params,
+    ) -> Self {
         let salt = encryption_salt();
-        let encryption_key = encryption_key(&salt, &password);
+        let derivation = KeyDerivation::Argon2id(params);
+        let encryption_key =
+            encryption_key(&derivation, salt.as_ref(), &password)
+                .expect("Generation of encryption secret key shouldn't fail");
 
-        let data = keypair.serialize_to_vec();
+        let data = Zeroizing::new(keypair.serialize_to_vec());
 
         let encrypted_keypair = aead::seal(&encryption_key, &data)
             .expect("Encryption of data shouldn't fail");
 
-        let encrypted_data = [salt.as_ref(), &encrypted_keypair].concat();
+        let data = [salt.as_ref(), &encrypted_keypair].concat();
 
-        Self(encrypted_data, PhantomData)
+        Self {
+            kdf: derivation,
+            data,
+            phantom: PhantomData,
+        }
+    }
+
+    /// The derivation of the encryption key of the keypair
+    pub fn key_derivation(&self) -> KeyDerivation {
+        self.kdf
     }
 
     /// Decrypt an encrypted keypair
@@ -223,15 +354,12 @@ impl<T: BorshSerialize + BorshDeserialize> EncryptedKeypair<T> {
             return Err(DecryptionError::EmptyPassword);
         }
 
-        let salt_len = encryption_salt().len();
-        let (raw_salt, cipher) = self.0.split_at(salt_len);
-
-        let salt = kdf::Salt::from_slice(raw_salt)
-            .map_err(|_| DecryptionError::BadSalt)?;
+        let (salt, cipher) = self.data.split_at(SALT_LEN);
 
-        let encryption_key = encryption_key(&salt, &password);
+        let encryption_key = encryption_key(&self.kdf, salt, &password)?;
 
         let decrypted_data = aead::open(&encryption_key, cipher)
+            .map(Zeroizing::new)
             .map_err(|_| DecryptionError::DecryptionError)?;
 
         T::try_from_slice(&decrypted_data)
@@ -241,12 +369,138 @@ impl<T: BorshSerialize + BorshDeserialize> EncryptedKeypair<T> {
 
 /// Keypair encryption salt
 fn encryption_salt() -> kdf::Salt {
-    kdf::Salt::default()
+    kdf::Salt::generate(SALT_LEN)
+        .expect("Generation of encryption salt shouldn't fail")
 }
 
 /// Make encryption secret key from a password.
-fn encryption_key(salt: &kdf::Salt, password: &str) -> kdf::SecretKey {
-    kdf::Password::from_slice(password.as_bytes())
-        .and_then(|password| kdf::derive_key(&password, salt, 3, 1 << 17, 32))
-        .expect("Generation of encryption secret key shouldn't fail")
+fn encryption_key(
+    derivation: &KeyDerivation,
+    salt: &[u8],
+    password: &str,
+) -> Result<aead::SecretKey, DecryptionError> {
+    match derivation {
+        KeyDerivation::LegacyArgon2i => {
+            let salt = kdf::Salt::from_slice(salt)
+                .map_err(|_| DecryptionError::BadSalt)?;
+            kdf::Password::from_slice(password.as_bytes())
+                .and_then(|password| {
+                    kdf::derive_key(&password, &salt, 3, 1 << 17, 32)
+                })
+                .map_err(|_| DecryptionError::DecryptionError)
+        }
+        KeyDerivation::Argon2id(params) => {
+            let params = argon2::Params::new(
+                params.m_cost,
+                params.t_cost,
+                params.p_cost,
+                Some(KEY_LEN),
+            )
+            .map_err(|err| {
+                DecryptionError::InvalidKdfParams(err.to_string())
+            })?;
+            let mut key = Zeroizing::new([0; KEY_LEN]);
+            Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+                .hash_password_into(password.as_bytes(), salt, key.as_mut())
+                .map_err(|err| {
+                    DecryptionError::InvalidKdfParams(err.to_string())
+                })?;
+            Ok(aead::SecretKey::from_slice(key.as_ref())
+                .expect("The length of the encryption key is valid"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use namada_core::types::key::{common, SchemeType};
+
+    use super::*;
+
+    /// Cheap parameters of the key derivation for the tests
+    const TEST_PARAMS: Argon2Params = Argon2Params {
+        m_cost: 64,
+        t_cost: 1,
+        p_cost: 1,
+    };
+
+    #[test]
+    fn test_encrypted_keypair_roundtrip() {
+        let sk = crate::wallet::gen_secret_key(
+            SchemeType::Ed25519,
+            &mut rand_core::OsRng,
+        );
+        let password = Zeroizing::new("password".to_string());
+        let encrypted = EncryptedKeypair::new_with_params(
+            &sk,
+            password.clone(),
+            TEST_PARAMS,
+        );
+        assert_eq!(
+            encrypted.key_derivation(),
+            KeyDerivation::Argon2id(TEST_PARAMS)
+        );
+
+        let decoded: EncryptedKeypair<common::SecretKey> =
+            encrypted.to_string().parse().unwrap();
+        assert_eq!(decoded.key_derivation(), encrypted.key_derivation());
+        assert_eq!(
+            decoded.decrypt(password).unwrap().to_string(),
+            sk.to_string()
+        );
+        assert!(matches!(
+            decoded.decrypt(Zeroizing::new("wrong".to_string())),
+            Err(DecryptionError::DecryptionError)
+        ));
+    }
+
+    #[test]
+    fn test_legacy_keypair_migration() {
+        let sk = crate::wallet::gen_secret_key(
+            SchemeType::Ed25519,
+            &mut rand_core::OsRng,
+        );
+        let password = Zeroizing::new("password".to_string());
+
+        // Encrypt the key as the previous versions of the wallet did
+        let salt = encryption_salt();
+        let encryption_key = encryption_key(
+            &KeyDerivation::LegacyArgon2i,
+            salt.as_ref(),
+            &password,
+        )
+        .unwrap();
+        let cipher = aead::seal(&encryption_key, &sk.serialize_to_vec())
+            .expect("Encryption of data shouldn't fail");
+        let legacy = format!(
+            "{ENCRYPTED_KEY_PREFIX}{}",
+            HEXLOWER.encode(&[salt.as_ref(), &cipher].concat())
+        );
+        let stored: StoredKeypair<common::SecretKey> =
+            serde_json::from_value(legacy.into()).unwrap();
+        assert!(stored.is_legacy_encrypted());
+
+        // Changing the password migrates the key
+        let new_password = Zeroizing::new("new password".to_string());
+        let migrated = stored
+            .change_password(Some(password), Some(new_password.clone()))
+            .unwrap();
+        assert!(migrated.is_encrypted());
+        assert!(!migrated.is_legacy_encrypted());
+        let StoredKeypair::Encrypted(encrypted) = &migrated else {
+            panic!("The migrated key must be encrypted")
+        };
+        assert_eq!(
+            encrypted.decrypt(new_password.clone()).unwrap().to_string(),
+            sk.to_string()
+        );
+        assert!(matches!(
+            migrated.change_password(None, None),
+            Err(DecryptionError::NotDecrypting)
+        ));
+
+        // Without a new password, the key is stored raw
+        let raw = migrated.change_password(Some(new_password), None).unwrap();
+        assert!(!raw.is_encrypted());
+    }
 }
@@ -1,5 +1,6 @@
 //! Provides functionality for managing keys and addresses for a user
 pub mod alias;
+pub mod backend;
 mod derivation_path;
 mod keys;
 pub mod pre_genesis;
@@ -7,11 +8,13 @@ pub mod store;
 
 use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt::Display;
+use std::ops::Range;
 use std::str::FromStr;
 
 use alias::Alias;
 use bip39::{Language, Mnemonic, MnemonicType, Seed};
 use borsh::{BorshDeserialize, BorshSerialize};
+use masp_primitives::zip32::ExtendedFullViewingKey;
 use namada_core::types::address::Address;
 use namada_core::types::key::*;
 use namada_core::types::masp::{
@@ -24,9 +27,16 @@ pub use store::{AddressVpType, Store};
 use thiserror::Error;
 use zeroize::Zeroizing;
 
+pub use self::backend::{KeyBackend, KeyBackendError, SignerEndpoint};
 pub use self::derivation_path::{DerivationPath, DerivationPathError};
-pub use self::keys::{DecryptionError, StoredKeypair};
-pub use self::store::{ConfirmationResponse, ValidatorData, ValidatorKeys};
+pub use self::keys::{
+    Argon2Params, DecryptionError, EncryptedKeypair, KeyDerivation,
+    StoredKeypair,
+};
+pub use self::store::{
+    ConfirmationResponse, DiversifiedAddress, ValidatorData, ValidatorKeys,
+};
+use crate::masp::derive_diversified_address;
 use crate::wallet::store::{derive_hd_secret_key, derive_hd_spending_key};
 
 /// Captures the interactive parts of the wallet's functioning
@@ -240,6 +250,9 @@ pub enum FindKeyError {
     /// Could not decrypt a given key in the wallet
     #[error("{0}")]
     KeyDecryptionError(keys::DecryptionError),
+    /// Could not obtain a given key from its backend
+    #[error("{0}")]
+    KeyBackendError(KeyBackendError),
 }
 
 /// Represents a collection of keys and addresses while caching key decryptions
@@ -308,6 +321,13 @@ impl<U> Wallet<U> {
         &mut self.store
     }
 
+    /// Check if this wallet only holds viewing keys, public keys and
+    /// addresses. A view-only wallet can compute balances and build
+    /// transactions, but not sign them.
+    pub fn is_view_only(&self) -> bool {
+        self.store.is_view_only()
+    }
+
     /// Extend this wallet from pre-genesis validator wallet.
     pub fn extend_from_pre_genesis_validator(
         &mut self,
@@ -467,6 +487,46 @@ impl<U> Wallet<U> {
             .collect()
     }
 
+    /// Get the derivation paths of all the keys derived from a seed, by their
+    /// alias
+    pub fn get_derivation_paths(&self) -> BTreeMap<String, DerivationPath> {
+        self.store
+            .get_derivation_paths()
+            .iter()
+            .map(|(alias, path)| (alias.into(), path.clone()))
+            .collect()
+    }
+
+    /// Get the account index following the ones of the stored keys derived
+    /// with the given path, but for their account index
+    pub fn next_hd_account(&self, path: &DerivationPath) -> u32 {
+        self.store
+            .get_derivation_paths()
+            .values()
+            .filter(|stored| stored.same_but_account(path))
+            .filter_map(DerivationPath::account)
+            .max()
+            .map_or(0, |account| account.saturating_add(1))
+    }
+
+    /// Get the aliases of the secret and spending keys encrypted by a previous
+    /// version of the wallet, to be migrated by changing their password
+    pub fn get_legacy_encrypted_keys(&self) -> Vec<String> {
+        self.store
+            .get_legacy_encrypted_keys()
+            .into_iter()
+            .map(Into::into)
+            .collect()
+    }
+
+    /// Forget the keys decrypted so far, so that their passwords are required
+    /// again on their next use. The decrypted secret keys are zeroized as they
+    /// are dropped.
+    pub fn lock(&mut self) {
+        self.decrypted_key_cache.clear();
+        self.decrypted_spendkey_cache.clear();
+    }
+
     /// Check if alias is an encrypted secret key
     pub fn is_encrypted_secret_key(
         &self,
@@ -709,6 +769,92 @@ impl<U: WalletIo> Wallet<U> {
         .map(|alias| (alias, spend_key))
     }
 
+    /// Derive the keypairs of a range of accounts from the given seed, using
+    /// the given path with its account index replaced, and insert them into
+    /// the store with the aliases `<alias_prefix>-<account>`. The derivation is
+    /// deterministic, so restoring the wallet from the same mnemonic with the
+    /// same path and accounts re-derives the same keys under the same aliases.
+    /// Returns the aliases and the keys of the inserted accounts.
+    #[allow(clippy::too_many_arguments)]
+    pub fn derive_store_hd_secret_keys(
+        &mut self,
+        scheme: SchemeType,
+        alias_prefix: &str,
+        alias_force: bool,
+        seed: &Seed,
+        derivation_path: &DerivationPath,
+        accounts: Range<u32>,
+        password: Option<Zeroizing<String>>,
+    ) -> Vec<(String, common::SecretKey)> {
+        accounts
+            .filter_map(|account| {
+                let derivation_path = derivation_path.with_account(account);
+                let sk = derive_hd_secret_key(
+                    scheme,
+                    seed.as_bytes(),
+                    derivation_path.clone(),
+                );
+                self.insert_keypair(
+                    format!("{alias_prefix}-{account}"),
+                    alias_force,
+                    sk.clone(),
+                    password.clone(),
+                    None,
+                    Some(derivation_path),
+                )
+                .map(|alias| (alias, sk))
+            })
+            .collect()
+    }
+
+    /// Derive the masp spending keys of a range of accounts from the given
+    /// seed, similarly to [`Self::derive_store_hd_secret_keys`].
+    pub fn derive_store_hd_spending_keys(
+        &mut self,
+        alias_prefix: &str,
+        alias_force: bool,
+        seed: &Seed,
+        derivation_path: &DerivationPath,
+        accounts: Range<u32>,
+        password: Option<Zeroizing<String>>,
+    ) -> Vec<(String, ExtendedSpendingKey)> {
+        accounts
+            .filter_map(|account| {
+                let derivation_path = derivation_path.with_account(account);
+                let spend_key = derive_hd_spending_key(
+                    seed.as_bytes(),
+                    derivation_path.clone(),
+                );
+                self.insert_spending_key(
+                    format!("{alias_prefix}-{account}"),
+                    alias_force,
+                    spend_key,
+                    password.clone(),
+                    Some(derivation_path),
+                )
+                .map(|alias| (alias, spend_key))
+            })
+            .collect()
+    }
+
+    /// Change the password of the secret or spending key with the given alias,
+    /// re-encrypting it with the current key derivation, or store it raw
+    /// without a new password. This also migrates the keys encrypted by a
+    /// previous version of the wallet. If the key is encrypted and no password
+    /// is provided, it will be prompted for.
+    pub fn change_key_password(
+        &mut self,
+        alias: impl AsRef<str>,
+        password: Option<Zeroizing<String>>,
+        new_password: Option<Zeroizing<String>>,
+    ) -> Result<(), FindKeyError> {
+        self.store.change_key_password::<U>(
+            &alias.as_ref().into(),
+            password,
+            new_password,
+        )
+    }
+
     /// Generate a disposable signing key for fee payment and store it under the
     /// precomputed alias in the wallet. This is simply a wrapper around
     /// `gen_key` to manage the alias
@@ -759,12 +905,17 @@ impl<U: WalletIo> Wallet<U> {
             return Ok(cached_key.clone());
         }
         // If not cached, look-up in store
-        let stored_key = self
-            .store
-            .find_secret_key(alias_pkh_or_pk.as_ref())
-            .ok_or_else(|| {
-            FindKeyError::KeyNotFound(alias_pkh_or_pk.as_ref().to_string())
-        })?;
+        let Some(stored_key) =
+            self.store.find_secret_key(alias_pkh_or_pk.as_ref())
+        else {
+            // Otherwise, look-up in the backend of the key
+            let alias = self.find_backend_alias(alias_pkh_or_pk.as_ref());
+            return self.find_backend_key(alias).unwrap_or_else(|| {
+                Err(FindKeyError::KeyNotFound(
+                    alias_pkh_or_pk.as_ref().to_string(),
+                ))
+            });
+        };
         Self::decrypt_stored_key::<_>(
             &mut self.decrypted_key_cache,
             stored_key,
@@ -871,11 +1022,12 @@ impl<U: WalletIo> Wallet<U> {
         if let Some(cached_key) = self.decrypted_key_cache.get(&alias) {
             return Ok(cached_key.clone());
         }
-        // Look-up from store
-        let stored_key = self
-            .store
-            .find_key_by_pkh(pkh)
-            .ok_or_else(|| FindKeyError::KeyNotFound(pkh.to_string()))?;
+        // Look-up from store, otherwise from the backend of the key
+        let Some(stored_key) = self.store.find_key_by_pkh(pkh) else {
+            return self.find_backend_key(alias).unwrap_or_else(|| {
+                Err(FindKeyError::KeyNotFound(pkh.to_string()))
+            });
+        };
         Self::decrypt_stored_key(
             &mut self.decrypted_key_cache,
             stored_key,
@@ -884,6 +1036,76 @@ impl<U: WalletIo> Wallet<U> {
         )
     }
 
+    /// Find the alias of a key held outside of the store by an alias, a public
+    /// key hash or a public key.
+    fn find_backend_alias(&self, alias_pkh_or_pk: &str) -> Alias {
+        let alias = Alias::from(alias_pkh_or_pk);
+        if self.store.find_key_backend(&alias).is_some() {
+            return alias;
+        }
+        PublicKeyHash::from_str(alias_pkh_or_pk)
+            .ok()
+            .or_else(|| {
+                let pk = common::PublicKey::from_str(alias_pkh_or_pk).ok()?;
+                Some(PublicKeyHash::from(&pk))
+            })
+            .and_then(|pkh| self.store.find_alias_by_pkh(&pkh))
+            .unwrap_or(alias)
+    }
+
+    /// Load the secret key with the given alias from its backend, if the key
+    /// is held outside of the store, and cache it. Keys held by remote
+    /// signers cannot be loaded.
+    fn find_backend_key(
+        &mut self,
+        alias: Alias,
+    ) -> Option<Result<common::SecretKey, FindKeyError>> {
+        let key = match self.store.find_key_backend(&alias)? {
+            #[cfg(all(feature = "keychain", not(target_family = "wasm")))]
+            KeyBackend::Keychain { service } => {
+                backend::keychain::load_key(service, &alias.normalize())
+            }
+            #[cfg(not(all(
+                feature = "keychain",
+                not(target_family = "wasm")
+            )))]
+            KeyBackend::Keychain { .. } => Err(KeyBackendError::Keychain(
+                "the keychain support is not enabled".to_string(),
+            )),
+            KeyBackend::RemoteSigner { .. } => {
+                Err(KeyBackendError::RemoteKey(alias.to_string()))
+            }
+        }
+        .map_err(FindKeyError::KeyBackendError)
+        .map(|key| {
+            self.decrypted_key_cache.insert(alias, key.clone());
+            key
+        });
+        Some(key)
+    }
+
+    /// Find the remote signer holding the key of the given public key, and
+    /// the secret key authenticating the requests to it. If the
+    /// authentication key is encrypted and password not supplied, then
+    /// password will be interactively prompted for. Returns nothing if the key
+    /// is not held by a remote signer.
+    pub fn find_remote_signer(
+        &mut self,
+        pk: &common::PublicKey,
+        password: Option<Zeroizing<String>>,
+    ) -> Option<Result<(SignerEndpoint, common::SecretKey), FindKeyError>> {
+        let alias = self.store.find_alias_by_pkh(&pk.into())?;
+        let Some(KeyBackend::RemoteSigner { endpoint, auth_key }) =
+            self.store.find_key_backend(&alias).cloned()
+        else {
+            return None;
+        };
+        Some(
+            self.find_secret_key(auth_key, password)
+                .map(|auth_key| (endpoint, auth_key)),
+        )
+    }
+
     /// Decrypt stored key, if it's not stored un-encrypted.
     /// If a given storage key needs to be decrypted and password is not
     /// supplied, then interactively prompt for password and if successfully
@@ -962,6 +1184,60 @@ impl<U: WalletIo> Wallet<U> {
             })
     }
 
+    /// Insert a new key held outside of the store by the given backend, with
+    /// the given alias. If the alias is already used, then display a prompt
+    /// for overwrite confirmation.
+    pub fn insert_external_key(
+        &mut self,
+        alias: String,
+        pubkey: common::PublicKey,
+        backend: KeyBackend,
+        force_alias: bool,
+    ) -> Option<String> {
+        self.store
+            .insert_external_key::<U>(
+                alias.into(),
+                pubkey,
+                backend,
+                force_alias,
+            )
+            .map(Into::into)
+    }
+
+    /// Insert a new secret key with the given alias into the keychain of the
+    /// OS, under the given service. Only the public key is kept in the store.
+    /// If the alias is already used, then display a prompt for overwrite
+    /// confirmation.
+    #[cfg(all(feature = "keychain", not(target_family = "wasm")))]
+    pub fn insert_keychain_key(
+        &mut self,
+        alias: String,
+        alias_force: bool,
+        sk: common::SecretKey,
+        service: String,
+    ) -> Result<Option<String>, FindKeyError> {
+        let Some(alias) = self.store.insert_external_key::<U>(
+            alias.into(),
+            sk.ref_to(),
+            KeyBackend::Keychain {
+                service: service.clone(),
+            },
+            alias_force,
+        ) else {
+            return Ok(None);
+        };
+        if let Err(err) =
+            backend::keychain::store_key(&service, &alias.normalize(), &sk)
+        {
+            // Don't keep a key that is missing from the keychain
+            self.store.remove_alias(&alias);
+            return Err(FindKeyError::KeyBackendError(err));
+        }
+        // Cache the newly added key
+        self.decrypted_key_cache.insert(alias.clone(), sk);
+        Ok(Some(alias.into()))
+    }
+
     /// Insert a new public key with the given alias. If the alias is already
     /// used, then display a prompt for overwrite confirmation.
     pub fn insert_public_key(
@@ -1033,6 +1309,45 @@ impl<U: WalletIo> Wallet<U> {
             .map(Into::into)
     }
 
+    /// Derive the next diversified payment address of the given viewing key
+    /// and insert it into the wallet under the given alias, which labels the
+    /// address. Return the alias and the address if it has been added.
+    pub fn derive_payment_addr(
+        &mut self,
+        alias: String,
+        viewing_key: ExtendedViewingKey,
+        pin: bool,
+        force_alias: bool,
+    ) -> Option<(String, PaymentAddress)> {
+        let index = self.store.next_diversifier_index(&viewing_key);
+        let vk = ExtendedFullViewingKey::from(viewing_key).fvk.vk;
+        let (index, payment_addr) = derive_diversified_address(&vk, index);
+        let payment_addr = PaymentAddress::from(payment_addr).pinned(pin);
+        self.store
+            .insert_diversified_payment_addr::<U>(
+                alias.into(),
+                payment_addr,
+                DiversifiedAddress { viewing_key, index },
+                force_alias,
+            )
+            .map(|alias| (alias.into(), payment_addr))
+    }
+
+    /// Get the payment addresses derived from the given viewing key by their
+    /// diversifier index, along with their alias
+    pub fn get_diversified_payment_addrs(
+        &self,
+        viewing_key: &ExtendedViewingKey,
+    ) -> BTreeMap<u64, (String, PaymentAddress)> {
+        self.store
+            .get_diversified_payment_addrs(viewing_key)
+            .into_iter()
+            .map(|(index, (alias, payment_addr))| {
+                (index, (alias.into(), *payment_addr))
+            })
+            .collect()
+    }
+
     /// Extend this wallet from another wallet (typically pre-genesis).
     /// Note that this method ignores `store.validator_data` if any.
     pub fn extend(&mut self, wallet: Self) {
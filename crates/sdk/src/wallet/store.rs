@@ -5,6 +5,7 @@ use std::fmt::Display;
 use std::str::FromStr;
 
 use bimap::BiBTreeMap;
+use borsh::{BorshDeserialize, BorshSerialize};
 use itertools::Itertools;
 use masp_primitives::zip32;
 use namada_core::types::address::{Address, ImplicitAddress};
@@ -17,9 +18,10 @@ use slip10_ed25519;
 use zeroize::Zeroizing;
 
 use super::alias::{self, Alias};
+use super::backend::KeyBackend;
 use super::derivation_path::DerivationPath;
 use super::pre_genesis;
-use crate::wallet::{StoredKeypair, WalletIo};
+use crate::wallet::{FindKeyError, StoredKeypair, WalletIo};
 
 /// Actions that can be taken when there is an alias conflict
 pub enum ConfirmationResponse {
@@ -56,6 +58,15 @@ pub struct ValidatorData {
     pub keys: ValidatorKeys,
 }
 
+/// The origin of a payment address derived from a viewing key in the wallet
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct DiversifiedAddress {
+    /// The viewing key from which the address has been derived
+    pub viewing_key: ExtendedViewingKey,
+    /// The diversifier index of the address
+    pub index: u64,
+}
+
 /// A Storage area for keys and addresses
 #[derive(Serialize, Deserialize, Debug, Default)]
 pub struct Store {
@@ -80,6 +91,16 @@ pub struct Store {
     pub(crate) validator_data: Option<ValidatorData>,
     /// Namada address vp type
     address_vp_types: BTreeMap<AddressVpType, HashSet<Address>>,
+    /// Origins of the diversified payment addresses by their alias
+    #[serde(default)]
+    diversified_addrs: BTreeMap<Alias, DiversifiedAddress>,
+    /// Whether this store only holds viewing keys, public keys and addresses.
+    /// No secret keys can be added to a view-only store.
+    #[serde(default)]
+    view_only: bool,
+    /// Backends of the keys held outside of the store, by their alias
+    #[serde(default)]
+    key_backends: BTreeMap<Alias, KeyBackend>,
 }
 
 /// Grouping of addresses by validity predicate.
@@ -261,6 +282,57 @@ impl Store {
         &self.spend_keys
     }
 
+    /// Get the aliases of the secret and spending keys encrypted by a previous
+    /// version of the wallet, to be migrated by changing their password.
+    pub fn get_legacy_encrypted_keys(&self) -> Vec<&Alias> {
+        let secret_keys = self
+            .secret_keys
+            .iter()
+            .filter(|(_, key)| key.is_legacy_encrypted())
+            .map(|(alias, _)| alias);
+        let spend_keys = self
+            .spend_keys
+            .iter()
+            .filter(|(_, key)| key.is_legacy_encrypted())
+            .map(|(alias, _)| alias);
+        secret_keys.chain(spend_keys).collect()
+    }
+
+    /// Re-encrypt the secret or spending key with the given alias with a new
+    /// password, or store it raw without one. If the key is encrypted and no
+    /// password is provided, it will be prompted for.
+    pub fn change_key_password<U: WalletIo>(
+        &mut self,
+        alias: &Alias,
+        password: Option<Zeroizing<String>>,
+        new_password: Option<Zeroizing<String>>,
+    ) -> Result<(), FindKeyError> {
+        if let Some(key) = self.secret_keys.get_mut(alias) {
+            change_password::<_, U>(key, password, new_password)
+        } else if let Some(key) = self.spend_keys.get_mut(alias) {
+            change_password::<_, U>(key, password, new_password)
+        } else {
+            Err(FindKeyError::KeyNotFound(alias.to_string()))
+        }
+    }
+
+    /// Get the derivation paths of all the keys derived from a seed, by their
+    /// alias.
+    pub fn get_derivation_paths(&self) -> &BTreeMap<Alias, DerivationPath> {
+        &self.derivation_paths
+    }
+
+    /// Get all known key backends by their alias
+    pub fn get_key_backends(&self) -> &BTreeMap<Alias, KeyBackend> {
+        &self.key_backends
+    }
+
+    /// Find the backend of the key with the given alias, if the key is held
+    /// outside of the store.
+    pub fn find_key_backend(&self, alias: &Alias) -> Option<&KeyBackend> {
+        self.key_backends.get(alias)
+    }
+
     /// Add validator data to the store
     pub fn add_validator_data(
         &mut self,
@@ -290,6 +362,33 @@ impl Store {
         self.validator_data
     }
 
+    /// Check if this store only holds viewing keys, public keys and addresses
+    pub fn is_view_only(&self) -> bool {
+        self.view_only
+    }
+
+    /// Make a view-only copy of this store. All the secret keys, spending
+    /// keys and validator data are left out, while the viewing keys, public
+    /// keys and addresses are kept to compute balances and build unsigned
+    /// transactions.
+    pub fn to_view_only(&self) -> Self {
+        Self {
+            view_keys: self.view_keys.clone(),
+            spend_keys: BTreeMap::new(),
+            payment_addrs: self.payment_addrs.clone(),
+            secret_keys: BTreeMap::new(),
+            public_keys: self.public_keys.clone(),
+            derivation_paths: self.derivation_paths.clone(),
+            addresses: self.addresses.clone(),
+            pkhs: self.pkhs.clone(),
+            validator_data: None,
+            address_vp_types: self.address_vp_types.clone(),
+            diversified_addrs: self.diversified_addrs.clone(),
+            view_only: true,
+            key_backends: BTreeMap::new(),
+        }
+    }
+
     /// Insert a new secret key with the given alias. If the alias is already
     /// used, will prompt for overwrite/reselection confirmation. If declined,
     /// then keypair is not inserted and nothing is returned, otherwise selected
@@ -303,6 +402,11 @@ impl Store {
         path: Option<DerivationPath>,
         force: bool,
     ) -> Option<Alias> {
+        // abort if the store is view-only
+        if self.view_only {
+            println!("Secret keys cannot be added to a view-only wallet");
+            return None;
+        }
         // abort if the key already exists
         let pubkey = keypair.ref_to();
         let pkh = PublicKeyHash::from(&pubkey);
@@ -360,6 +464,71 @@ impl Store {
         Some(alias)
     }
 
+    /// Insert a key held outside of the store by the given backend, with the
+    /// given alias. Only the public key is stored, along with the implicit
+    /// address of the key. If the alias is already used, will prompt for
+    /// overwrite/reselection confirmation. If declined, then the key is not
+    /// inserted and nothing is returned, otherwise selected alias is returned.
+    pub fn insert_external_key<U: WalletIo>(
+        &mut self,
+        mut alias: Alias,
+        pubkey: common::PublicKey,
+        backend: KeyBackend,
+        force: bool,
+    ) -> Option<Alias> {
+        // abort if the store is view-only
+        if self.view_only {
+            println!("Signing keys cannot be added to a view-only wallet");
+            return None;
+        }
+        // abort if the key already exists
+        let pkh = PublicKeyHash::from(&pubkey);
+        let address = Address::Implicit(ImplicitAddress(pkh.clone()));
+        if !force {
+            if self.pkhs.contains_key(&pkh) {
+                println!("The key already exists.");
+                return None;
+            } else if let Some(alias) = self.addresses.get_by_right(&address) {
+                println!(
+                    "Address {} already exists in the wallet with alias {}",
+                    address.encode(),
+                    alias,
+                );
+                return None;
+            }
+        }
+
+        // abort if the alias is reserved
+        if Alias::is_reserved(&alias).is_some() {
+            println!("The alias {} is reserved", alias);
+            return None;
+        }
+
+        if alias.is_empty() {
+            alias = pkh.to_string().into();
+            println!("Empty alias given, defaulting to {}.", alias);
+        }
+        if self.contains_alias(&alias) && !force {
+            match U::show_overwrite_confirmation(&alias, "a key") {
+                ConfirmationResponse::Replace => {}
+                ConfirmationResponse::Reselect(new_alias) => {
+                    return self.insert_external_key::<U>(
+                        new_alias, pubkey, backend, false,
+                    );
+                }
+                ConfirmationResponse::Skip => {
+                    return None;
+                }
+            }
+        }
+        self.remove_alias(&alias);
+        self.public_keys.insert(alias.clone(), pubkey);
+        self.pkhs.insert(pkh, alias.clone());
+        self.addresses.insert(alias.clone(), address);
+        self.key_backends.insert(alias.clone(), backend);
+        Some(alias)
+    }
+
     /// Insert spending keys similarly to how it's done for keypairs
     pub fn insert_spending_key<U: WalletIo>(
         &mut self,
@@ -369,6 +538,11 @@ impl Store {
         path: Option<DerivationPath>,
         force: bool,
     ) -> Option<Alias> {
+        // abort if the store is view-only
+        if self.view_only {
+            println!("Spending keys cannot be added to a view-only wallet");
+            return None;
+        }
         // abort if the alias is reserved
         if Alias::is_reserved(&alias).is_some() {
             println!("The alias {} is reserved", alias);
@@ -487,6 +661,51 @@ impl Store {
         Some(alias)
     }
 
+    /// Get the payment addresses derived from the given viewing key by their
+    /// diversifier index, along with their alias
+    pub fn get_diversified_payment_addrs(
+        &self,
+        viewing_key: &ExtendedViewingKey,
+    ) -> BTreeMap<u64, (&Alias, &PaymentAddress)> {
+        self.diversified_addrs
+            .iter()
+            .filter(|(_, origin)| origin.viewing_key == *viewing_key)
+            .filter_map(|(alias, origin)| {
+                let payment_addr = self.payment_addrs.get_by_left(alias)?;
+                Some((origin.index, (alias, payment_addr)))
+            })
+            .collect()
+    }
+
+    /// Get the first diversifier index following the ones already used to
+    /// derive payment addresses from the given viewing key
+    pub fn next_diversifier_index(
+        &self,
+        viewing_key: &ExtendedViewingKey,
+    ) -> u64 {
+        self.diversified_addrs
+            .values()
+            .filter(|origin| origin.viewing_key == *viewing_key)
+            .map(|origin| origin.index + 1)
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// Insert a payment address derived from a viewing key of the wallet,
+    /// similarly to how it's done for other payment addresses
+    pub fn insert_diversified_payment_addr<U: WalletIo>(
+        &mut self,
+        alias: Alias,
+        payment_addr: PaymentAddress,
+        origin: DiversifiedAddress,
+        force: bool,
+    ) -> Option<Alias> {
+        let alias =
+            self.insert_payment_addr::<U>(alias, payment_addr, force)?;
+        self.diversified_addrs.insert(alias.clone(), origin);
+        Some(alias)
+    }
+
     /// Insert payment addresses similarly to how it's done for keypairs
     pub fn insert_payment_addr<U: WalletIo>(
         &mut self,
@@ -577,6 +796,7 @@ impl Store {
             || self.pkhs.values().contains(alias)
             || self.public_keys.contains_key(alias)
             || self.derivation_paths.contains_key(alias)
+            || self.key_backends.contains_key(alias)
     }
 
     /// Completely remove the given alias from all maps in the wallet
@@ -589,10 +809,13 @@ impl Store {
         self.pkhs.retain(|_key, val| val != alias);
         self.public_keys.remove(alias);
         self.derivation_paths.remove(alias);
+        self.diversified_addrs.remove(alias);
+        self.key_backends.remove(alias);
     }
 
     /// Extend this store from another store (typically pre-genesis).
-    /// Note that this method ignores `validator_data` if any.
+    /// Note that this method ignores `validator_data` if any, as well as the
+    /// secret and spending keys when this store is view-only.
     pub fn extend(&mut self, store: Store) {
         let Self {
             view_keys,
@@ -605,16 +828,23 @@ impl Store {
             pkhs,
             validator_data: _,
             address_vp_types,
+            diversified_addrs,
+            view_only,
+            key_backends,
         } = self;
         view_keys.extend(store.view_keys);
-        spend_keys.extend(store.spend_keys);
+        if !*view_only {
+            spend_keys.extend(store.spend_keys);
+            secret_keys.extend(store.secret_keys);
+            key_backends.extend(store.key_backends);
+        }
         payment_addrs.extend(store.payment_addrs);
-        secret_keys.extend(store.secret_keys);
         public_keys.extend(store.public_keys);
         derivation_paths.extend(store.derivation_paths);
         addresses.extend(store.addresses);
         pkhs.extend(store.pkhs);
         address_vp_types.extend(store.address_vp_types);
+        diversified_addrs.extend(store.diversified_addrs);
     }
 
     /// Extend this store from pre-genesis validator wallet.
@@ -750,6 +980,29 @@ pub fn derive_hd_spending_key(
     .into()
 }
 
+/// Re-encrypt a stored key with a new password, prompting for its current
+/// password if it's encrypted and none is provided.
+fn change_password<T, U>(
+    key: &mut StoredKeypair<T>,
+    password: Option<Zeroizing<String>>,
+    new_password: Option<Zeroizing<String>>,
+) -> Result<(), FindKeyError>
+where
+    T: BorshSerialize + BorshDeserialize + Display + FromStr + Clone,
+    <T as FromStr>::Err: Display,
+    U: WalletIo,
+{
+    let password = if key.is_encrypted() {
+        Some(password.unwrap_or_else(|| U::read_password(false)))
+    } else {
+        None
+    };
+    *key = key
+        .change_password(password, new_password)
+        .map_err(FindKeyError::KeyDecryptionError)?;
+    Ok(())
+}
+
 impl Display for AddressVpType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -798,6 +1051,7 @@ mod test_wallet {
 
     use super::super::derivation_path::DerivationPath;
     use super::*;
+    use crate::wallet::Wallet;
 
     #[test]
     fn gen_sk_from_mnemonic_code_secp256k1() {
@@ -952,4 +1206,187 @@ mod test_wallet {
             do_test_gen_sk_from_seed_and_derivation_path(SCHEME, SEED, "m/0'/2147483647'/1'/2147483646'/2'", "551d333177df541ad876a60ea71f00447931c0a9da16f227c11ea080d7391b8d");
         }
     }
+
+    #[derive(Clone)]
+    struct TestWalletIo;
+
+    impl WalletIo for TestWalletIo {
+        type Rng = rand_core::OsRng;
+    }
+
+    #[test]
+    fn test_derive_hd_accounts() {
+        let mnemonic = Mnemonic::from_entropy(&[7; 32], Language::English)
+            .expect("Mnemonic construction cannot fail.");
+        let seed = Seed::new(&mnemonic, "");
+        let path =
+            DerivationPath::default_for_transparent_scheme(SchemeType::Ed25519);
+        let shielded_path = DerivationPath::default_for_shielded();
+
+        let mut wallet = Wallet::new(TestWalletIo, Store::default());
+        assert_eq!(wallet.next_hd_account(&path), 0);
+        let keys = wallet.derive_store_hd_secret_keys(
+            SchemeType::Ed25519,
+            "acc",
+            false,
+            &seed,
+            &path,
+            0..3,
+            None,
+        );
+        assert_eq!(keys.len(), 3);
+        assert_eq!(keys[2].0, "acc-2");
+        let spend_keys = wallet.derive_store_hd_spending_keys(
+            "shielded",
+            false,
+            &seed,
+            &shielded_path,
+            0..2,
+            None,
+        );
+        assert_eq!(spend_keys.len(), 2);
+        assert_eq!(wallet.next_hd_account(&path), 3);
+        assert_eq!(wallet.next_hd_account(&shielded_path), 2);
+
+        // All the derived accounts are listed with their paths
+        let paths = wallet.get_derivation_paths();
+        assert_eq!(paths.len(), 5);
+        assert_eq!(paths["acc-1"].to_string(), "m/44'/877'/1'/0'/0'");
+        assert_eq!(paths["shielded-1"].to_string(), "m/32'/877'/1'");
+
+        // Restoring from the same seed re-derives the same accounts
+        let mut restored = Wallet::new(TestWalletIo, Store::default());
+        let restored_keys = restored.derive_store_hd_secret_keys(
+            SchemeType::Ed25519,
+            "acc",
+            false,
+            &seed,
+            &path,
+            0..3,
+            None,
+        );
+        let restored_spend_keys = restored.derive_store_hd_spending_keys(
+            "shielded",
+            false,
+            &seed,
+            &shielded_path,
+            0..2,
+            None,
+        );
+        fn encode<K: Display>(keys: &[(String, K)]) -> Vec<String> {
+            keys.iter()
+                .map(|(alias, key)| format!("{alias}:{key}"))
+                .collect()
+        }
+        assert_eq!(encode(&restored_keys), encode(&keys));
+        assert_eq!(encode(&restored_spend_keys), encode(&spend_keys));
+    }
+
+    #[test]
+    fn test_view_only_store() {
+        let mut store = Store::default();
+        let sk = crate::wallet::gen_secret_key(
+            SchemeType::Ed25519,
+            &mut rand_core::OsRng,
+        );
+        let pk = sk.ref_to();
+        store
+            .insert_keypair::<TestWalletIo>(
+                "key".into(),
+                sk.clone(),
+                None,
+                None,
+                None,
+                false,
+            )
+            .expect("Inserting a keypair cannot fail");
+        assert!(!store.is_view_only());
+
+        // The secret keys are left out of the view-only copy
+        let view_only = store.to_view_only();
+        assert!(view_only.is_view_only());
+        assert!(view_only.find_secret_key("key").is_none());
+        assert_eq!(view_only.find_public_key("key"), Some(&pk));
+
+        // The view-only mode is persisted
+        let mut decoded = Store::decode(view_only.encode())
+            .expect("Decoding the store cannot fail");
+        assert!(decoded.is_view_only());
+
+        // No secret key can be added to a view-only store
+        assert!(
+            decoded
+                .insert_keypair::<TestWalletIo>(
+                    "other".into(),
+                    sk,
+                    None,
+                    None,
+                    None,
+                    true,
+                )
+                .is_none()
+        );
+        assert!(decoded.find_secret_key("other").is_none());
+    }
+
+    #[test]
+    fn test_external_keys() {
+        let gen_sk = || {
+            crate::wallet::gen_secret_key(
+                SchemeType::Ed25519,
+                &mut rand_core::OsRng,
+            )
+        };
+        let (sk, auth_sk) = (gen_sk(), gen_sk());
+        let backend = KeyBackend::RemoteSigner {
+            endpoint: "tcp://127.0.0.1:7000".parse().unwrap(),
+            auth_key: "auth".into(),
+        };
+        let mut store = Store::default();
+        store
+            .insert_keypair::<TestWalletIo>(
+                "auth".into(),
+                auth_sk.clone(),
+                None,
+                None,
+                None,
+                false,
+            )
+            .expect("Inserting a keypair cannot fail");
+        store
+            .insert_external_key::<TestWalletIo>(
+                "remote".into(),
+                sk.ref_to(),
+                backend.clone(),
+                false,
+            )
+            .expect("Inserting an external key cannot fail");
+
+        // Only the public key and the backend of the key are stored
+        let decoded =
+            Store::decode(store.encode()).expect("Decoding cannot fail");
+        assert!(decoded.find_secret_key("remote").is_none());
+        assert_eq!(decoded.find_public_key("remote"), Some(&sk.ref_to()));
+        assert_eq!(decoded.find_key_backend(&"remote".into()), Some(&backend));
+        assert!(decoded.to_view_only().get_key_backends().is_empty());
+
+        // The key of a remote signer cannot be exported, but the signer and
+        // the key authenticating to it can be found
+        let mut wallet = Wallet::new(TestWalletIo, decoded);
+        assert!(matches!(
+            wallet.find_key_by_pk(&sk.ref_to(), None),
+            Err(FindKeyError::KeyBackendError(_))
+        ));
+        let (endpoint, auth_key) = wallet
+            .find_remote_signer(&sk.ref_to(), None)
+            .expect("The key must be held by a remote signer")
+            .expect("The authentication key must be found");
+        assert_eq!(endpoint.to_string(), "tcp://127.0.0.1:7000");
+        assert_eq!(auth_key.ref_to(), auth_sk.ref_to());
+        assert!(wallet.find_remote_signer(&auth_sk.ref_to(), None).is_none());
+
+        // The backend is removed along with the alias
+        wallet.store_mut().remove_alias(&"remote".into());
+        assert!(wallet.store().get_key_backends().is_empty());
+    }
 }
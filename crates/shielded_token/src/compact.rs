@@ -0,0 +1,156 @@
+//! Compact MASP blocks
+//!
+//! A compact block only carries the data a light shielded wallet needs to
+//! find its notes: the note commitments, the ephemeral keys and the prefix of
+//! the note ciphertexts required to trial-decrypt the new notes, and the
+//! nullifiers revealed to detect the spent ones. Compact blocks are indexed by
+//! the node when a block is committed so that wallets don't have to download
+//! the full transactions.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use masp_primitives::transaction::Transaction;
+use namada_core::types::storage::{BlockHeight, TxIndex};
+use namada_storage::{Result, StorageRead, StorageWrite};
+
+use crate::storage_key::masp_compact_block_key;
+
+/// The size of the note plaintext prefix needed to trial-decrypt a note: the
+/// lead byte, the diversifier, the asset type, the value and the random seed
+pub const COMPACT_NOTE_SIZE: usize = 1 + 11 + 32 + 8 + 32;
+/// The maximum number of blocks which can be requested at once
+pub const MAX_COMPACT_BLOCKS_PER_QUERY: u64 = 1000;
+
+/// The compact data of a shielded output
+#[derive(Clone, Debug, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct CompactOutput {
+    /// The note commitment
+    pub cmu: [u8; 32],
+    /// The ephemeral public key
+    pub ephemeral_key: [u8; 32],
+    /// The first [`COMPACT_NOTE_SIZE`] bytes of the note ciphertext
+    pub enc_ciphertext: Vec<u8>,
+}
+
+/// The compact data of a MASP transaction
+#[derive(Clone, Debug, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct CompactTx {
+    /// The index of the transaction in the block
+    pub index: TxIndex,
+    /// The nullifiers of the spent notes
+    pub nullifiers: Vec<[u8; 32]>,
+    /// The shielded outputs in the order they have been appended to the note
+    /// commitment tree
+    pub outputs: Vec<CompactOutput>,
+}
+
+impl CompactTx {
+    /// Extract the compact data of the given MASP transaction
+    pub fn new(index: TxIndex, transaction: &Transaction) -> Self {
+        let (nullifiers, outputs) = match transaction.sapling_bundle() {
+            Some(bundle) => (
+                bundle
+                    .shielded_spends
+                    .iter()
+                    .map(|description| description.nullifier.0)
+                    .collect(),
+                bundle
+                    .shielded_outputs
+                    .iter()
+                    .map(|description| CompactOutput {
+                        cmu: description.cmu.to_bytes(),
+                        ephemeral_key: description.ephemeral_key.0,
+                        enc_ciphertext: description.enc_ciphertext
+                            [..COMPACT_NOTE_SIZE]
+                            .to_vec(),
+                    })
+                    .collect(),
+            ),
+            None => (vec![], vec![]),
+        };
+        Self {
+            index,
+            nullifiers,
+            outputs,
+        }
+    }
+}
+
+/// The compact MASP data of a block
+#[derive(
+    Clone, Debug, Default, PartialEq, Eq, BorshSerialize, BorshDeserialize,
+)]
+pub struct CompactBlock {
+    /// The height of the block
+    pub height: BlockHeight,
+    /// The MASP transactions of the block in order
+    pub txs: Vec<CompactTx>,
+}
+
+/// Write the compact block to storage. Nothing is written for a block without
+/// MASP transactions.
+pub fn write_compact_block<S>(
+    storage: &mut S,
+    block: &CompactBlock,
+) -> Result<()>
+where
+    S: StorageWrite,
+{
+    if block.txs.is_empty() {
+        return Ok(());
+    }
+    storage.write(&masp_compact_block_key(block.height), block)
+}
+
+/// Read the compact block at the given height. `None` is returned if the block
+/// doesn't contain any MASP transaction.
+pub fn read_compact_block<S>(
+    storage: &S,
+    height: BlockHeight,
+) -> Result<Option<CompactBlock>>
+where
+    S: StorageRead,
+{
+    storage.read(&masp_compact_block_key(height))
+}
+
+#[cfg(test)]
+mod tests {
+    use namada_state::testing::TestWlStorage;
+
+    use super::*;
+
+    #[test]
+    fn test_compact_block_storage() {
+        let mut wl_storage = TestWlStorage::default();
+
+        // A block without MASP transactions isn't written
+        let empty_block = CompactBlock {
+            height: BlockHeight(1),
+            txs: vec![],
+        };
+        write_compact_block(&mut wl_storage, &empty_block).unwrap();
+        assert!(
+            read_compact_block(&wl_storage, BlockHeight(1))
+                .unwrap()
+                .is_none()
+        );
+
+        let block = CompactBlock {
+            height: BlockHeight(2),
+            txs: vec![CompactTx {
+                index: TxIndex(3),
+                nullifiers: vec![[1; 32]],
+                outputs: vec![CompactOutput {
+                    cmu: [2; 32],
+                    ephemeral_key: [3; 32],
+                    enc_ciphertext: vec![4; COMPACT_NOTE_SIZE],
+                }],
+            }],
+        };
+        write_compact_block(&mut wl_storage, &block).unwrap();
+        assert_eq!(
+            read_compact_block(&wl_storage, BlockHeight(2)).unwrap(),
+            Some(block)
+        );
+    }
+}
@@ -3,19 +3,21 @@
 use namada_core::ledger::inflation::{
     ShieldedRewardsController, ShieldedValsToUpdate,
 };
-use namada_core::types::address::{Address, MASP};
-use namada_core::types::dec::Dec;
+use namada_core::types::address::{Address, InternalAddress, MASP};
 use namada_core::types::uint::Uint;
 use namada_parameters as parameters;
 use namada_state::{DBIter, StorageHasher, WlStorage, DB};
 use namada_storage::{StorageRead, StorageWrite};
 use namada_trans_token::storage_key::{balance_key, minted_balance_key};
-use namada_trans_token::{read_denom, Amount, DenominatedAmount, Denomination};
+use namada_trans_token::{
+    credit_tokens, read_denom, Amount, DenominatedAmount, Denomination,
+};
 
 use crate::storage_key::{
-    masp_kd_gain_key, masp_kp_gain_key, masp_last_inflation_key,
-    masp_last_locked_amount_key, masp_locked_amount_target_key,
-    masp_max_reward_rate_key,
+    read_masp_kd_gain, read_masp_kp_gain, read_masp_last_inflation,
+    read_masp_last_locked_amount, read_masp_locked_amount_target,
+    read_masp_max_reward_rate, write_masp_last_inflation,
+    write_masp_last_locked_amount,
 };
 
 /// Compute the precision of MASP rewards for the given token. This function
@@ -75,30 +77,25 @@ where
         .expect("epochs per year should properly decode");
 
     //// Values from the last epoch
-    let last_inflation: Amount = wl_storage
-        .read(&masp_last_inflation_key(token))?
+    let last_inflation = read_masp_last_inflation(wl_storage, token)?
         .expect("failure to read last inflation");
 
-    let last_locked_amount: Amount = wl_storage
-        .read(&masp_last_locked_amount_key(token))?
+    let last_locked_amount = read_masp_last_locked_amount(wl_storage, token)?
         .expect("failure to read last inflation");
 
     //// Parameters for each token
-    let max_reward_rate: Dec = wl_storage
-        .read(&masp_max_reward_rate_key(token))?
+    let max_reward_rate = read_masp_max_reward_rate(wl_storage, token)?
         .expect("max reward should properly decode");
 
-    let kp_gain_nom: Dec = wl_storage
-        .read(&masp_kp_gain_key(token))?
+    let kp_gain_nom = read_masp_kp_gain(wl_storage, token)?
         .expect("kp_gain_nom reward should properly decode");
 
-    let kd_gain_nom: Dec = wl_storage
-        .read(&masp_kd_gain_key(token))?
+    let kd_gain_nom = read_masp_kd_gain(wl_storage, token)?
         .expect("kd_gain_nom reward should properly decode");
 
-    let target_locked_amount: Amount = wl_storage
-        .read(&masp_locked_amount_target_key(token))?
-        .expect("locked ratio target should properly decode");
+    let target_locked_amount =
+        read_masp_locked_amount_target(wl_storage, token)?
+            .expect("locked ratio target should properly decode");
 
     // Creating the PD controller for handing out tokens
     let controller = ShieldedRewardsController {
@@ -173,14 +170,137 @@ where
     // but we should make sure the return value's ratio matches
     // this new inflation rate in 'update_allowed_conversions',
     // otherwise we will have an inaccurate view of inflation
-    wl_storage.write(&masp_last_inflation_key(token), inflation_amount)?;
-
-    wl_storage
-        .write(&masp_last_locked_amount_key(token), total_tokens_in_masp)?;
+    write_masp_last_inflation(wl_storage, token, inflation_amount)?;
+    write_masp_last_locked_amount(wl_storage, token, total_tokens_in_masp)?;
 
     Ok(((noterized_inflation, precision), denomination))
 }
 
+/// Compute the normed inflation of the native token following the given one
+/// after an epoch of MASP rewards at the given rate. Falls back to the given
+/// normed inflation if the next one overflows.
+#[cfg(any(feature = "multicore", test))]
+fn next_normed_inflation(
+    token: &Address,
+    normed_inflation: u128,
+    reward: (u128, u128),
+) -> u128 {
+    Uint::from(normed_inflation)
+        .checked_add(
+            (Uint::from(normed_inflation) * Uint::from(reward.0)) / reward.1,
+        )
+        .and_then(|x| x.try_into().ok())
+        .unwrap_or_else(|| {
+            tracing::warn!(
+                "MASP reward for {} assumed to be 0 because the computed \
+                 value is too large. Please check the inflation parameters.",
+                token
+            );
+            normed_inflation
+        })
+}
+
+/// Compute the amount of native tokens minted into the MASP to back the
+/// rewards of the given shielded balance of a token at the given rate. The
+/// rewards of the native token are minted by reinflating its balance from the
+/// current to the next normed inflation, given as a pair, whereas the other
+/// tokens are rewarded `reward.0` native tokens for every `reward.1` units.
+#[cfg(any(feature = "multicore", test))]
+fn minted_masp_reward(
+    addr_bal: Amount,
+    reward: (u128, u128),
+    normed_inflation: Option<(u128, u128)>,
+) -> Amount {
+    match normed_inflation {
+        Some((normed_inflation, new_normed_inflation)) => {
+            let native_reward =
+                addr_bal * (new_normed_inflation, normed_inflation);
+            native_reward
+                .0
+                .checked_add(native_reward.1)
+                .unwrap_or(Amount::max())
+                .checked_sub(addr_bal)
+                .unwrap_or_default()
+        }
+        None => (addr_bal * reward).0,
+    }
+}
+
+/// Scale down the MASP rewards of the given tokens so that the native tokens
+/// minted for them do not exceed the MASP incentives share of the last epoch
+/// inflation, if an inflation split is set. The last inflation of every token
+/// is updated to the scaled down reward. Returns the MASP incentives budget,
+/// if any.
+#[cfg(any(feature = "multicore", test))]
+fn cap_masp_rewards<D, H>(
+    wl_storage: &mut WlStorage<D, H>,
+    masp_rewards: &mut std::collections::BTreeMap<
+        Address,
+        ((u128, u128), Denomination),
+    >,
+    normed_inflation: u128,
+) -> namada_storage::Result<Option<Amount>>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter>,
+    H: 'static + StorageHasher,
+{
+    let Some(split) = parameters::read_inflation_split(wl_storage)? else {
+        return Ok(None);
+    };
+    let last_inflation: Amount = wl_storage
+        .read(&parameters::storage::get_pos_inflation_amount_key())?
+        .unwrap_or_default();
+    let budget = split.masp_incentives * last_inflation;
+    let native_token = wl_storage.storage.native_token.clone();
+
+    let mut total_reward = Amount::zero();
+    for (token, (reward, _denom)) in masp_rewards.iter() {
+        let addr_bal: Amount = wl_storage
+            .read(&balance_key(token, &MASP))?
+            .unwrap_or_default();
+        let normed_inflation = (*token == native_token).then(|| {
+            (
+                normed_inflation,
+                next_normed_inflation(token, normed_inflation, *reward),
+            )
+        });
+        total_reward = total_reward
+            .checked_add(minted_masp_reward(
+                addr_bal,
+                *reward,
+                normed_inflation,
+            ))
+            .unwrap_or(Amount::max());
+    }
+    if total_reward <= budget {
+        return Ok(Some(budget));
+    }
+    tracing::info!(
+        "Scaling the MASP rewards of {} down to the MASP incentives budget of \
+         {}",
+        total_reward.to_string_native(),
+        budget.to_string_native()
+    );
+
+    for (token, ((reward, precision), _denom)) in masp_rewards.iter_mut() {
+        *reward = Uint::from(*reward)
+            .checked_mul_div(budget.raw_amount(), total_reward.raw_amount())
+            .and_then(|x| x.0.try_into().ok())
+            .unwrap_or_default();
+        let addr_bal: Amount = wl_storage
+            .read(&balance_key(token, &MASP))?
+            .unwrap_or_default();
+        let inflation_amount = Amount::from_uint(
+            (addr_bal.raw_amount() / *precision) * Uint::from(*reward),
+            0,
+        )
+        .unwrap();
+        write_masp_last_inflation(wl_storage, token, inflation_amount)?;
+    }
+
+    Ok(Some(budget))
+}
+
 // This is only enabled when "wasm-runtime" is on, because we're using rayon
 #[cfg(any(feature = "multicore", test))]
 /// Update the MASP's allowed conversions
@@ -278,9 +398,24 @@ where
     let ref_inflation =
         calculate_masp_rewards_precision(wl_storage, &native_token)?.0;
 
+    // Compute the rewards of all the tokens first, as they may have to be
+    // scaled down to the MASP incentives share of the inflation
+    let mut masp_rewards = BTreeMap::new();
+    for token in &masp_reward_keys {
+        let reward = calculate_masp_rewards(wl_storage, token)?;
+        masp_rewards.insert(token.clone(), reward);
+    }
+    let normed_inflation = wl_storage
+        .storage
+        .conversion_state
+        .normed_inflation
+        .unwrap_or(ref_inflation);
+    let masp_budget =
+        cap_masp_rewards(wl_storage, &mut masp_rewards, normed_inflation)?;
+
     // Reward all tokens according to above reward rates
     for token in &masp_reward_keys {
-        let (reward, denom) = calculate_masp_rewards(wl_storage, token)?;
+        let (reward, denom) = masp_rewards[token];
         masp_reward_denoms.insert(token.clone(), denom);
         // Dispense a transparent reward in parallel to the shielded rewards
         let addr_bal: Amount = wl_storage
@@ -315,21 +450,8 @@ where
                 // The amount that will be given of the new native token for
                 // every amount of the native token given in the
                 // previous epoch
-                let new_normed_inflation = Uint::from(*normed_inflation)
-                    .checked_add(
-                        (Uint::from(*normed_inflation) * Uint::from(reward.0))
-                            / reward.1,
-                    )
-                    .and_then(|x| x.try_into().ok())
-                    .unwrap_or_else(|| {
-                        tracing::warn!(
-                            "MASP reward for {} assumed to be 0 because the \
-                             computed value is too large. Please check the \
-                             inflation parameters.",
-                            token
-                        );
-                        *normed_inflation
-                    });
+                let new_normed_inflation =
+                    next_normed_inflation(token, *normed_inflation, reward);
                 // The conversion is computed such that if consecutive
                 // conversions are added together, the
                 // intermediate native tokens cancel/
@@ -350,16 +472,13 @@ where
                 );
                 // Operations that happen exactly once for each token
                 if digit == MaspDigitPos::Three {
-                    // The reward for each reward.1 units of the current asset
-                    // is reward.0 units of the reward token
-                    let native_reward =
-                        addr_bal * (new_normed_inflation, *normed_inflation);
-                    total_reward += native_reward
-                        .0
-                        .checked_add(native_reward.1)
-                        .unwrap_or(Amount::max())
-                        .checked_sub(addr_bal)
-                        .unwrap_or_default();
+                    // The native balance is reinflated to the new normed
+                    // inflation
+                    total_reward += minted_masp_reward(
+                        addr_bal,
+                        reward,
+                        Some((*normed_inflation, new_normed_inflation)),
+                    );
                     // Save the new normed inflation
                     *normed_inflation = new_normed_inflation;
                 }
@@ -400,7 +519,7 @@ where
                 if digit == MaspDigitPos::Three {
                     // The reward for each reward.1 units of the current asset
                     // is reward.0 units of the reward token
-                    total_reward += (addr_bal * (reward.0, reward.1)).0;
+                    total_reward += minted_masp_reward(addr_bal, reward, None);
                 }
             }
             // Add a conversion from the previous asset type
@@ -455,6 +574,17 @@ where
     let addr_bal: Amount = wl_storage.read(&reward_key)?.unwrap_or_default();
     let new_bal = addr_bal + total_reward;
     wl_storage.write(&reward_key, new_bal)?;
+    // Route the share of the MASP incentives left unused by the rewards to the
+    // PGF treasury
+    if let Some(budget) = masp_budget {
+        let unused = budget.checked_sub(total_reward).unwrap_or_default();
+        credit_tokens(
+            wl_storage,
+            &native_token,
+            &Address::Internal(InternalAddress::Pgf),
+            unused,
+        )?;
+    }
     // Try to distribute Merkle tree construction as evenly as possible
     // across multiple cores
     // Merkle trees must have exactly 2^n leaves to be mergeable
@@ -523,6 +653,7 @@ mod tests {
 
     use namada_core::types::address;
     use namada_core::types::dec::testing::arb_non_negative_dec;
+    use namada_core::types::dec::Dec;
     use namada_core::types::time::DurationSecs;
     use namada_core::types::token::testing::arb_amount;
     use namada_parameters::{EpochDuration, Parameters};
@@ -555,32 +686,10 @@ mod tests {
         const ROUNDS: usize = 10;
 
         let mut s = TestWlStorage::default();
-        let params = Parameters {
-            max_tx_bytes: 1024 * 1024,
-            epoch_duration: EpochDuration {
-                min_num_of_blocks: 1,
-                min_duration: DurationSecs(3600),
-            },
-            max_expected_time_per_block: DurationSecs(3600),
-            max_proposal_bytes: Default::default(),
-            max_block_gas: 100,
-            vp_allowlist: vec![],
-            tx_allowlist: vec![],
-            implicit_vp_code_hash: Default::default(),
-            epochs_per_year: 365,
-            max_signatures_per_transaction: 10,
-            staked_ratio: Default::default(),
-            pos_inflation_amount: Default::default(),
-            fee_unshielding_gas_limit: 0,
-            fee_unshielding_descriptions_limit: 0,
-            minimum_gas_price: Default::default(),
-        };
+        init_parameters(&mut s);
 
         // Initialize the state
         {
-            // Parameters
-            namada_parameters::init_storage(&params, &mut s).unwrap();
-
             // Tokens
             let token_params = MaspParams {
                 max_reward_rate: Dec::from_str("0.1").unwrap(),
@@ -624,6 +733,142 @@ mod tests {
         }
     }
 
+    /// Test that the native tokens minted for the MASP rewards are capped to
+    /// the MASP incentives share of the inflation and that the unused share is
+    /// routed to the PGF treasury
+    #[test]
+    fn test_masp_rewards_capped_to_incentives() {
+        // The native tokens minted for the rewards without an inflation split
+        let mut s = masp_rewards_storage();
+        update_allowed_conversions(&mut s).unwrap();
+        let uncapped = masp_native_balance(&s);
+        assert!(!uncapped.is_zero());
+        assert!(pgf_balance(&s).is_zero());
+
+        // A budget above the rewards leaves them unchanged and the rest of the
+        // budget goes to the PGF treasury
+        let mut s = masp_rewards_storage();
+        let budget = uncapped + Amount::native_whole(1);
+        write_masp_incentives_budget(&mut s, budget);
+        update_allowed_conversions(&mut s).unwrap();
+        assert_eq!(masp_native_balance(&s), uncapped);
+        assert_eq!(pgf_balance(&s), Amount::native_whole(1));
+
+        // A budget below the rewards scales them down to it
+        let mut s = masp_rewards_storage();
+        let budget = uncapped / 3;
+        write_masp_incentives_budget(&mut s, budget);
+        let supply_pre: Amount = s
+            .read(&minted_balance_key(&address::nam()))
+            .unwrap()
+            .unwrap();
+        update_allowed_conversions(&mut s).unwrap();
+        let minted = masp_native_balance(&s);
+        assert!(!minted.is_zero());
+        assert!(minted <= budget);
+        assert_eq!(minted + pgf_balance(&s), budget);
+        // The unused share is minted into the PGF treasury
+        let supply_post: Amount = s
+            .read(&minted_balance_key(&address::nam()))
+            .unwrap()
+            .unwrap();
+        assert_eq!(supply_post, supply_pre + pgf_balance(&s));
+    }
+
+    /// Init a storage with some BTC and ETH shielded, whose rewards are
+    /// computed w.r.t. the native token
+    fn masp_rewards_storage() -> TestWlStorage {
+        let mut s = TestWlStorage::default();
+        init_parameters(&mut s);
+
+        let token_params = MaspParams {
+            max_reward_rate: Dec::from_str("0.1").unwrap(),
+            kp_gain_nom: Dec::from_str("0.1").unwrap(),
+            kd_gain_nom: Dec::from_str("0.1").unwrap(),
+            locked_amount_target: 10_000_u64,
+        };
+        write_denom(&mut s, &address::nam(), 6.into()).unwrap();
+        s.write(
+            &minted_balance_key(&address::nam()),
+            Amount::native_whole(1_000_000),
+        )
+        .unwrap();
+        for (token_addr, alias, decimals) in
+            [(address::btc(), "btc", 8u8), (address::eth(), "eth", 18u8)]
+        {
+            let denom = Denomination::from(decimals);
+            namada_trans_token::write_params(&mut s, &token_addr).unwrap();
+            crate::write_params(&token_params, &mut s, &token_addr, &denom)
+                .unwrap();
+            write_denom(&mut s, &token_addr, denom).unwrap();
+            let shielded = Amount::from_uint(100, decimals).unwrap();
+            s.write(&minted_balance_key(&token_addr), shielded).unwrap();
+            s.write(&balance_key(&token_addr, &address::MASP), shielded)
+                .unwrap();
+            s.storage
+                .conversion_state
+                .tokens
+                .insert(alias.to_string(), token_addr);
+        }
+        s
+    }
+
+    /// Set an inflation split whose MASP incentives share of the last
+    /// inflation is the given budget
+    fn write_masp_incentives_budget(s: &mut TestWlStorage, budget: Amount) {
+        let split = namada_core::types::parameters::InflationSplit {
+            pos_rewards: Dec::from_str("0.5").unwrap(),
+            pgf_treasury: Dec::from_str("0.4").unwrap(),
+            masp_incentives: Dec::from_str("0.1").unwrap(),
+        };
+        namada_parameters::update_inflation_split_parameter(s, &split).unwrap();
+        s.write(
+            &parameters::storage::get_pos_inflation_amount_key(),
+            budget * 10,
+        )
+        .unwrap();
+    }
+
+    fn masp_native_balance(s: &TestWlStorage) -> Amount {
+        s.read(&balance_key(&address::nam(), &address::MASP))
+            .unwrap()
+            .unwrap_or_default()
+    }
+
+    fn pgf_balance(s: &TestWlStorage) -> Amount {
+        s.read(&balance_key(
+            &address::nam(),
+            &Address::Internal(InternalAddress::Pgf),
+        ))
+        .unwrap()
+        .unwrap_or_default()
+    }
+
+    fn init_parameters(s: &mut TestWlStorage) {
+        let params = Parameters {
+            max_tx_bytes: 1024 * 1024,
+            epoch_duration: EpochDuration {
+                min_num_of_blocks: 1,
+                min_duration: DurationSecs(3600),
+            },
+            max_expected_time_per_block: DurationSecs(3600),
+            max_proposal_bytes: Default::default(),
+            max_block_gas: 100,
+            vp_allowlist: vec![],
+            tx_allowlist: vec![],
+            implicit_vp_code_hash: Default::default(),
+            epochs_per_year: 365,
+            max_signatures_per_transaction: 10,
+            staked_ratio: Default::default(),
+            pos_inflation_amount: Default::default(),
+            fee_unshielding_gas_limit: 0,
+            fee_unshielding_descriptions_limit: 0,
+            minimum_gas_price: Default::default(),
+        };
+
+        namada_parameters::init_storage(&params, s).unwrap();
+    }
+
     pub fn tokens() -> HashMap<Address, (&'static str, Denomination)> {
         vec![
             (address::nam(), ("nam", 6.into())),
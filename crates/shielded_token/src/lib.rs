@@ -1,5 +1,6 @@
 //! Namada shielded token.
 
+pub mod compact;
 pub mod conversion;
 mod storage;
 pub mod storage_key;
@@ -15,6 +15,23 @@ pub fn write_params<S>(
     address: &Address,
     denom: &token::Denomination,
 ) -> storage::Result<()>
+where
+    S: StorageRead + StorageWrite,
+{
+    storage.write(&masp_last_inflation_key(address), Amount::zero())?;
+    storage.write(&masp_last_locked_amount_key(address), Amount::zero())?;
+    write_reward_params(params, storage, address, denom)
+}
+
+/// Write the reward parameters of the token, leaving its last inflation and
+/// last locked amount untouched. Used to update the parameters of a token
+/// already rewarded in the shielded pool.
+pub fn write_reward_params<S>(
+    params: &token::MaspParams,
+    storage: &mut S,
+    address: &Address,
+    denom: &token::Denomination,
+) -> storage::Result<()>
 where
     S: StorageRead + StorageWrite,
 {
@@ -24,8 +41,6 @@ where
         kp_gain_nom,
         locked_amount_target,
     } = params;
-    storage.write(&masp_last_inflation_key(address), Amount::zero())?;
-    storage.write(&masp_last_locked_amount_key(address), Amount::zero())?;
     storage.write(&masp_max_reward_rate_key(address), max_rate)?;
     storage.write(&masp_kp_gain_key(address), kp_gain_nom)?;
     storage.write(&masp_kd_gain_key(address), kd_gain_nom)?;
@@ -2,10 +2,12 @@
 
 use masp_primitives::bls12_381::Scalar;
 use masp_primitives::sapling::Nullifier;
-use namada_core::types::address::{self, Address};
+use namada_core::types::address::{self, Address, MULTITOKEN};
+use namada_core::types::dec::Dec;
 use namada_core::types::hash::Hash;
-use namada_core::types::storage::{self, DbKeySeg, KeySeg};
-use namada_trans_token::storage_key::parameter_prefix;
+use namada_core::types::storage::{self, BlockHeight, DbKeySeg, KeySeg};
+use namada_core::types::token::Amount;
+use namada_trans_token::storage_key::PARAMETERS_STORAGE_KEY;
 
 /// Key segment prefix for pinned shielded transactions
 pub const PIN_KEY_PREFIX: &str = "pin-";
@@ -15,6 +17,8 @@ pub const MASP_NULLIFIERS_KEY: &str = "nullifiers";
 pub const MASP_NOTE_COMMITMENT_TREE_KEY: &str = "commitment_tree";
 /// Key segment prefix for the note commitment anchor
 pub const MASP_NOTE_COMMITMENT_ANCHOR_PREFIX: &str = "note_commitment_anchor";
+/// Key segment prefix for the compact blocks
+pub const MASP_COMPACT_BLOCK_PREFIX: &str = "compact_block";
 /// Key segment prefix for the convert anchor
 pub const MASP_CONVERT_ANCHOR_KEY: &str = "convert_anchor";
 /// Last calculated inflation value handed out
@@ -31,38 +35,49 @@ pub const MASP_LOCKED_AMOUNT_TARGET_KEY: &str = "locked_ratio_target";
 /// The key for the max reward rate for a given asset
 pub const MASP_MAX_REWARD_RATE_KEY: &str = "max_reward_rate";
 
-/// Obtain the nominal proportional key for the given token
-pub fn masp_kp_gain_key(token_addr: &Address) -> storage::Key {
-    parameter_prefix(token_addr).with_segment(MASP_KP_GAIN_KEY.to_owned())
-}
-
-/// Obtain the nominal derivative key for the given token
-pub fn masp_kd_gain_key(token_addr: &Address) -> storage::Key {
-    parameter_prefix(token_addr).with_segment(MASP_KD_GAIN_KEY.to_owned())
-}
-
-/// The max reward rate key for the given token
-pub fn masp_max_reward_rate_key(token_addr: &Address) -> storage::Key {
-    parameter_prefix(token_addr)
-        .with_segment(MASP_MAX_REWARD_RATE_KEY.to_owned())
-}
-
-/// Obtain the locked target ratio key for the given token
-pub fn masp_locked_amount_target_key(token_addr: &Address) -> storage::Key {
-    parameter_prefix(token_addr)
-        .with_segment(MASP_LOCKED_AMOUNT_TARGET_KEY.to_owned())
-}
-
-/// Obtain the storage key for the last locked ratio of a token
-pub fn masp_last_locked_amount_key(token_address: &Address) -> storage::Key {
-    parameter_prefix(token_address)
-        .with_segment(MASP_LAST_LOCKED_AMOUNT_KEY.to_owned())
-}
-
-/// Obtain the storage key for the last inflation of a token
-pub fn masp_last_inflation_key(token_address: &Address) -> storage::Key {
-    parameter_prefix(token_address)
-        .with_segment(MASP_LAST_INFLATION_KEY.to_owned())
+namada_storage::key_schemas! {
+    /// Obtain the nominal proportional key for the given token
+    masp_kp_gain(token: Address) -> Dec = [
+        { MULTITOKEN },
+        token,
+        { PARAMETERS_STORAGE_KEY },
+        { MASP_KP_GAIN_KEY },
+    ];
+    /// Obtain the nominal derivative key for the given token
+    masp_kd_gain(token: Address) -> Dec = [
+        { MULTITOKEN },
+        token,
+        { PARAMETERS_STORAGE_KEY },
+        { MASP_KD_GAIN_KEY },
+    ];
+    /// The max reward rate key for the given token
+    masp_max_reward_rate(token: Address) -> Dec = [
+        { MULTITOKEN },
+        token,
+        { PARAMETERS_STORAGE_KEY },
+        { MASP_MAX_REWARD_RATE_KEY },
+    ];
+    /// Obtain the locked target ratio key for the given token
+    masp_locked_amount_target(token: Address) -> Amount = [
+        { MULTITOKEN },
+        token,
+        { PARAMETERS_STORAGE_KEY },
+        { MASP_LOCKED_AMOUNT_TARGET_KEY },
+    ];
+    /// Obtain the storage key for the last locked ratio of a token
+    masp_last_locked_amount(token: Address) -> Amount = [
+        { MULTITOKEN },
+        token,
+        { PARAMETERS_STORAGE_KEY },
+        { MASP_LAST_LOCKED_AMOUNT_KEY },
+    ];
+    /// Obtain the storage key for the last inflation of a token
+    masp_last_inflation(token: Address) -> Amount = [
+        { MULTITOKEN },
+        token,
+        { PARAMETERS_STORAGE_KEY },
+        { MASP_LAST_INFLATION_KEY },
+    ];
 }
 
 /// Check if the given storage key is a masp key
@@ -140,6 +155,15 @@ pub fn masp_commitment_anchor_key(anchor: impl Into<Scalar>) -> storage::Key {
         .expect("Cannot obtain a storage key")
 }
 
+/// Get the key for the masp compact block at the given height
+pub fn masp_compact_block_key(height: BlockHeight) -> storage::Key {
+    storage::Key::from(address::MASP.to_db_key())
+        .push(&MASP_COMPACT_BLOCK_PREFIX.to_owned())
+        .expect("Cannot obtain a storage key")
+        .push(&height)
+        .expect("Cannot obtain a storage key")
+}
+
 /// Get the key for the masp convert tree anchor
 pub fn masp_convert_anchor_key() -> storage::Key {
     storage::Key::from(address::MASP.to_db_key())
@@ -1,7 +1,7 @@
 //! MASP utilities
 
 use masp_primitives::merkle_tree::CommitmentTree;
-use masp_primitives::sapling::Node;
+use masp_primitives::sapling::{Node, Nullifier};
 use masp_primitives::transaction::Transaction;
 use namada_core::types::storage::IndexedTx;
 use namada_storage::{Error, Result, StorageRead, StorageWrite};
@@ -10,6 +10,21 @@ use crate::storage_key::{
     masp_commitment_tree_key, masp_nullifier_key, masp_pin_tx_key,
 };
 
+/// The maximum number of nullifiers which can be checked at once
+pub const MAX_NULLIFIERS_PER_QUERY: usize = 1000;
+
+/// Check if the given nullifier has been revealed, i.e. if the note it belongs
+/// to has already been spent
+pub fn is_nullifier_revealed<S>(
+    storage: &S,
+    nullifier: &Nullifier,
+) -> Result<bool>
+where
+    S: StorageRead,
+{
+    storage.has_key(&masp_nullifier_key(nullifier))
+}
+
 // Writes the nullifiers of the provided masp transaction to storage
 fn reveal_nullifiers(
     ctx: &mut impl StorageWrite,
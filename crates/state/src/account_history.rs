@@ -0,0 +1,168 @@
+//! The optional index of the history of the accounts, i.e. the hashes of the
+//! txs in which every address appeared, keyed by the address, the height and
+//! the index of the tx. The txs of a block are written to the index with the
+//! block, so that wallets can read the history of their accounts without an
+//! external indexer.
+
+use std::collections::BTreeSet;
+
+use namada_core::borsh::{BorshDeserialize, BorshSerialize};
+use namada_core::types::address::Address;
+use namada_core::types::hash::Hash;
+use namada_core::types::storage::{BlockHeight, TxIndex};
+use namada_storage::{DBIter, DbError as Error, DbResult as Result, DB};
+
+/// The maximum number of txs in a page of the history of an account
+pub const MAX_ACCOUNT_TXS_PER_PAGE: usize = 100;
+
+/// A tx in the history of an account
+#[derive(Clone, Debug, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct AccountTx {
+    /// The height of the block of the tx
+    pub height: BlockHeight,
+    /// The index of the tx in the block
+    pub index: TxIndex,
+    /// The hash of the tx, as reported in its events
+    pub hash: Hash,
+}
+
+/// A page of the history of an account, ordered from the oldest tx
+#[derive(
+    Clone, Debug, Default, PartialEq, Eq, BorshSerialize, BorshDeserialize,
+)]
+pub struct AccountHistoryPage {
+    /// The txs of the page
+    pub txs: Vec<AccountTx>,
+    /// Whether there are more txs in the next pages
+    pub has_more: bool,
+}
+
+/// The txs of the current block, to be written to the account history index
+#[derive(Clone, Debug, Default)]
+pub struct AccountHistory {
+    txs: Vec<(Address, AccountTx)>,
+}
+
+impl AccountHistory {
+    /// Record a tx in the history of every address it involved, e.g. as a
+    /// verifier or as the fee payer
+    pub fn record(&mut self, tx: AccountTx, addresses: BTreeSet<Address>) {
+        self.txs
+            .extend(addresses.into_iter().map(|address| (address, tx.clone())));
+    }
+
+    /// Write the recorded txs to the DB with the batch
+    pub fn batch_write<D: DB>(
+        &mut self,
+        db: &mut D,
+        batch: &mut D::WriteBatch,
+    ) -> Result<()> {
+        for (address, tx) in std::mem::take(&mut self.txs) {
+            db.batch_write_account_tx(
+                batch, &address, tx.height, tx.index, &tx.hash,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Read a page of the history of the account from the index. The pages are
+/// numbered from zero.
+pub fn read_account_history<D>(
+    db: &D,
+    address: &Address,
+    page: u64,
+) -> Result<AccountHistoryPage>
+where
+    D: for<'iter> DBIter<'iter>,
+{
+    let skip = usize::try_from(page)
+        .ok()
+        .and_then(|page| page.checked_mul(MAX_ACCOUNT_TXS_PER_PAGE))
+        .unwrap_or(usize::MAX);
+    let mut iter = db.iter_account_history(address).skip(skip);
+    let txs = iter
+        .by_ref()
+        .take(MAX_ACCOUNT_TXS_PER_PAGE)
+        .map(|(key, value, _gas)| {
+            let invalid_key =
+                || Error::DBError(format!("Invalid account history key {key}"));
+            let (height, index) =
+                key.split_once('/').ok_or_else(invalid_key)?;
+            let height = height.parse().map_err(|_| invalid_key())?;
+            let index = index.parse().map_err(|_| invalid_key())?;
+            let hash = Hash::try_from_slice(&value)
+                .map_err(Error::BorshCodingError)?;
+            Ok(AccountTx {
+                height: BlockHeight(height),
+                index: TxIndex(index),
+                hash,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let has_more = iter.next().is_some();
+    Ok(AccountHistoryPage { txs, has_more })
+}
+
+#[cfg(test)]
+mod tests {
+    use namada_core::types::address::testing::{
+        established_address_1, established_address_2,
+    };
+
+    use super::*;
+    use crate::mockdb::MockDB;
+
+    #[test]
+    fn test_account_history() {
+        let mut db = MockDB::default();
+        let owner = established_address_1();
+        let other = established_address_2();
+
+        // The txs are ordered by height and index, not by the order they're
+        // written in
+        let mut history = AccountHistory::default();
+        let tx = |height, index| AccountTx {
+            height: BlockHeight(height),
+            index: TxIndex(index),
+            hash: Hash::sha256([height as u8, index as u8]),
+        };
+        history.record(tx(10, 2), BTreeSet::from([owner.clone()]));
+        history.record(tx(10, 0), BTreeSet::from([owner.clone()]));
+        history
+            .record(tx(9, 1), BTreeSet::from([owner.clone(), other.clone()]));
+        let mut batch = MockDB::batch();
+        history.batch_write(&mut db, &mut batch).unwrap();
+        db.exec_batch(batch).unwrap();
+        assert_eq!(
+            read_account_history(&db, &owner, 0).unwrap(),
+            AccountHistoryPage {
+                txs: vec![tx(9, 1), tx(10, 0), tx(10, 2)],
+                has_more: false,
+            }
+        );
+        assert_eq!(
+            read_account_history(&db, &other, 0).unwrap().txs,
+            vec![tx(9, 1)]
+        );
+        assert_eq!(
+            read_account_history(&db, &other, 1).unwrap(),
+            AccountHistoryPage::default()
+        );
+
+        // The history is paged
+        let mut history = AccountHistory::default();
+        for index in 0..MAX_ACCOUNT_TXS_PER_PAGE as u32 {
+            history.record(tx(11, index), BTreeSet::from([other.clone()]));
+        }
+        let mut batch = MockDB::batch();
+        history.batch_write(&mut db, &mut batch).unwrap();
+        db.exec_batch(batch).unwrap();
+        let first = read_account_history(&db, &other, 0).unwrap();
+        assert_eq!(first.txs.len(), MAX_ACCOUNT_TXS_PER_PAGE);
+        assert!(first.has_more);
+        let second = read_account_history(&db, &other, 1).unwrap();
+        assert_eq!(second.txs, vec![tx(11, 99)]);
+        assert!(!second.has_more);
+    }
+}
@@ -0,0 +1,157 @@
+//! The flat index of the token balances, i.e. the latest balance of every
+//! owner of every token keyed by the token and the owner. The balances
+//! changed by the committed writes and deletes are written to the index with
+//! every block, so that the balances and the holders of a token can be read
+//! without going through the subspace.
+
+use std::collections::BTreeMap;
+
+use namada_core::borsh::BorshDeserialize;
+use namada_core::types::address::{Address, MULTITOKEN};
+use namada_core::types::storage::{Key, KeySeg};
+use namada_core::types::token::Amount;
+use namada_storage::{DBIter, DbError as Error, DbResult as Result, DB};
+use namada_trans_token::storage_key::is_any_balance_key;
+
+/// The balances changed since they were last written to the index
+#[derive(Clone, Debug, Default)]
+pub struct BalanceIndex {
+    changed: BTreeMap<(Address, Address), Amount>,
+}
+
+impl BalanceIndex {
+    /// Check if the index has to be built, i.e. when it's not been built yet
+    /// or when it's been discarded by a rollback
+    pub fn is_missing<D>(db: &D) -> bool
+    where
+        D: for<'iter> DBIter<'iter>,
+    {
+        db.iter_indexed_balances(None).next().is_none()
+    }
+
+    /// Build the index from all the balances in the subspace and write it to
+    /// the DB
+    pub fn build<D>(db: &mut D) -> Result<()>
+    where
+        D: DB + for<'iter> DBIter<'iter>,
+    {
+        let mut index = Self::default();
+        let prefix = Key::from(MULTITOKEN.to_db_key());
+        for (key, value, _gas) in db.iter_prefix(Some(&prefix)) {
+            let key = Key::parse(key).map_err(Error::KeyError)?;
+            index.update(&key, Some(&value));
+        }
+        let mut batch = D::batch();
+        index.batch_write(db, &mut batch)?;
+        db.exec_batch(batch)
+    }
+
+    /// Record the write (`Some` value) or the deletion of the value of the
+    /// key, if it's a balance key
+    pub fn update(&mut self, key: &Key, value: Option<&[u8]>) {
+        let Some((token, owner)) = is_any_balance_key(key) else {
+            return;
+        };
+        let amount = value
+            .and_then(|value| Amount::try_from_slice(value).ok())
+            .unwrap_or_default();
+        self.changed.insert((token.clone(), owner.clone()), amount);
+    }
+
+    /// Write the balances that changed to the DB with the batch
+    pub fn batch_write<D: DB>(
+        &mut self,
+        db: &mut D,
+        batch: &mut D::WriteBatch,
+    ) -> Result<()> {
+        for ((token, owner), amount) in std::mem::take(&mut self.changed) {
+            db.batch_write_indexed_balance(batch, &token, &owner, amount)?;
+        }
+        Ok(())
+    }
+}
+
+/// Read the balance of the owner of the token from the index
+pub fn read_balance<D: DB>(
+    db: &D,
+    token: &Address,
+    owner: &Address,
+) -> Result<Amount> {
+    Ok(db.read_indexed_balance(token, owner)?.unwrap_or_default())
+}
+
+/// Read the owners of the token with a non-zero balance from the index,
+/// ordered by the owners
+pub fn read_holders<D>(
+    db: &D,
+    token: &Address,
+) -> Result<Vec<(Address, Amount)>>
+where
+    D: for<'iter> DBIter<'iter>,
+{
+    db.iter_indexed_balances(Some(token))
+        .map(|(owner, value, _gas)| {
+            let owner = Address::decode(&owner)
+                .map_err(|e| Error::DBError(e.to_string()))?;
+            let amount = Amount::try_from_slice(&value)
+                .map_err(Error::BorshCodingError)?;
+            Ok((owner, amount))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use namada_core::borsh::BorshSerializeExt;
+    use namada_core::types::address::nam;
+    use namada_core::types::address::testing::{
+        established_address_1, established_address_2,
+    };
+    use namada_trans_token::storage_key::balance_key;
+
+    use super::*;
+    use crate::mockdb::MockDB;
+
+    #[test]
+    fn test_balance_index() {
+        let mut db = MockDB::default();
+        let token = nam();
+        let owner = established_address_1();
+        let other = established_address_2();
+
+        let mut index = BalanceIndex::default();
+        index.update(
+            &balance_key(&token, &owner),
+            Some(&Amount::from(10_u64).serialize_to_vec()),
+        );
+        index.update(
+            &balance_key(&token, &other),
+            Some(&Amount::from(3_u64).serialize_to_vec()),
+        );
+        // A key that isn't a balance key isn't indexed
+        index.update(&Key::from(owner.to_db_key()), None);
+        let mut batch = MockDB::batch();
+        index.batch_write(&mut db, &mut batch).unwrap();
+        db.exec_batch(batch).unwrap();
+        assert_eq!(
+            read_balance(&db, &token, &owner).unwrap(),
+            Amount::from(10_u64)
+        );
+        let holders = read_holders(&db, &token).unwrap();
+        assert_eq!(holders.len(), 2);
+        assert!(holders.contains(&(owner.clone(), Amount::from(10_u64))));
+        assert!(holders.contains(&(other.clone(), Amount::from(3_u64))));
+        assert!(read_holders(&db, &owner).unwrap().is_empty());
+
+        // A deleted balance is removed from the index
+        index.update(&balance_key(&token, &other), None);
+        let mut batch = MockDB::batch();
+        index.batch_write(&mut db, &mut batch).unwrap();
+        db.exec_batch(batch).unwrap();
+        assert_eq!(read_balance(&db, &token, &other).unwrap(), Amount::zero());
+        assert_eq!(
+            read_holders(&db, &token).unwrap(),
+            vec![(owner, Amount::from(10_u64))]
+        );
+    }
+}
@@ -0,0 +1,241 @@
+//! Read-only storage at a committed block height, which may be in the past.
+
+use std::collections::BTreeSet;
+
+use namada_core::types::address::Address;
+use namada_core::types::hash::StorageHasher;
+use namada_core::types::storage::{
+    self, BlockHash, BlockHeight, Epoch, Epochs, Header, TxIndex,
+};
+use namada_storage::{OptionExt, ResultExt, StorageRead};
+
+use crate::{DBIter, State, DB};
+
+/// Read-only storage at a committed block height. At a past height, the
+/// values are restored from the subspace diffs, so only the merklized keys
+/// can be read and the height must not have been pruned.
+#[derive(Debug)]
+pub struct HistoricStorage<'a, D, H>
+where
+    D: DB + for<'iter> DBIter<'iter>,
+    H: StorageHasher,
+{
+    storage: &'a State<D, H>,
+    height: BlockHeight,
+}
+
+impl<'a, D, H> HistoricStorage<'a, D, H>
+where
+    D: DB + for<'iter> DBIter<'iter>,
+    H: StorageHasher,
+{
+    /// Read the storage at the given height. Fails if the block at the height
+    /// hasn't been committed yet.
+    pub fn new(
+        storage: &'a State<D, H>,
+        height: BlockHeight,
+    ) -> namada_storage::Result<Self> {
+        let last_height = storage.get_last_block_height();
+        if height > last_height {
+            return Err(namada_storage::Error::new(format!(
+                "The block at height {height} hasn't been committed yet, the \
+                 last committed block height is {last_height}"
+            )));
+        }
+        Ok(Self { storage, height })
+    }
+
+    /// The height at which the storage is read
+    pub fn height(&self) -> BlockHeight {
+        self.height
+    }
+
+    fn is_last_height(&self) -> bool {
+        self.height == self.storage.get_last_block_height()
+    }
+}
+
+impl<'a, D, H> StorageRead for HistoricStorage<'a, D, H>
+where
+    D: DB + for<'iter> DBIter<'iter>,
+    H: StorageHasher,
+{
+    type PrefixIter<'iter> = std::vec::IntoIter<(String, Vec<u8>)> where Self: 'iter;
+
+    fn read_bytes(
+        &self,
+        key: &storage::Key,
+    ) -> namada_storage::Result<Option<Vec<u8>>> {
+        if self.is_last_height() {
+            return self
+                .storage
+                .db
+                .read_subspace_val(key)
+                .into_storage_result();
+        }
+        if !(self.storage.merkle_tree_key_filter)(key) {
+            return Err(namada_storage::Error::new(format!(
+                "The key {key} is not merklized, its value cannot be read at \
+                 the past height {}",
+                self.height
+            )));
+        }
+        let (value, _gas) = self
+            .storage
+            .read_with_height(key, self.height)
+            .into_storage_result()?;
+        Ok(value)
+    }
+
+    fn has_key(&self, key: &storage::Key) -> namada_storage::Result<bool> {
+        Ok(self.read_bytes(key)?.is_some())
+    }
+
+    fn iter_prefix<'iter>(
+        &'iter self,
+        prefix: &storage::Key,
+    ) -> namada_storage::Result<Self::PrefixIter<'iter>> {
+        let db = &self.storage.db;
+        // The keys that exist now...
+        let mut keys: BTreeSet<String> = db
+            .iter_prefix(Some(prefix))
+            .map(|(key, _val, _gas)| key)
+            .collect();
+        // ... and the keys that had a value before they were changed in one of
+        // the blocks after the height, which includes the deleted keys
+        if !self.is_last_height() {
+            let last_height = self.storage.get_last_block_height();
+            for height in self.height.0 + 1..=last_height.0 {
+                keys.extend(
+                    db.iter_old_diffs(BlockHeight(height), Some(prefix))
+                        .map(|(key, _val, _gas)| key),
+                );
+            }
+        }
+        let mut key_vals = Vec::with_capacity(keys.len());
+        for key in keys {
+            let parsed_key = storage::Key::parse(&key).into_storage_result()?;
+            if let Some(value) = self.read_bytes(&parsed_key)? {
+                key_vals.push((key, value));
+            }
+        }
+        Ok(key_vals.into_iter())
+    }
+
+    fn iter_next<'iter>(
+        &'iter self,
+        iter: &mut Self::PrefixIter<'iter>,
+    ) -> namada_storage::Result<Option<(String, Vec<u8>)>> {
+        Ok(iter.next())
+    }
+
+    fn get_chain_id(&self) -> namada_storage::Result<String> {
+        Ok(self.storage.chain_id.to_string())
+    }
+
+    fn get_block_height(&self) -> namada_storage::Result<BlockHeight> {
+        Ok(self.height)
+    }
+
+    fn get_block_header(
+        &self,
+        height: BlockHeight,
+    ) -> namada_storage::Result<Option<Header>> {
+        self.storage
+            .db
+            .read_block_header(height)
+            .into_storage_result()
+    }
+
+    fn get_block_hash(&self) -> namada_storage::Result<BlockHash> {
+        let header = self
+            .get_block_header(self.height)?
+            .ok_or_err_msg("The block header of the height is not stored")?;
+        Ok(header.hash.into())
+    }
+
+    fn get_block_epoch(&self) -> namada_storage::Result<Epoch> {
+        self.storage
+            .block
+            .pred_epochs
+            .get_epoch(self.height)
+            .ok_or_err_msg("The epoch of the height is not known")
+    }
+
+    fn get_pred_epochs(&self) -> namada_storage::Result<Epochs> {
+        Ok(self.storage.block.pred_epochs.clone())
+    }
+
+    fn get_tx_index(&self) -> namada_storage::Result<TxIndex> {
+        Ok(TxIndex::default())
+    }
+
+    fn get_native_token(&self) -> namada_storage::Result<Address> {
+        Ok(self.storage.native_token.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use namada_core::borsh::BorshSerializeExt;
+    use namada_storage::{iter_prefix_bytes, StorageWrite};
+
+    use super::*;
+    use crate::testing::TestWlStorage;
+
+    #[test]
+    fn test_historic_reads() {
+        let mut wls = TestWlStorage::default();
+        let prefix = storage::Key::parse("prefix").unwrap();
+        let key_a = prefix.push(&"a".to_string()).unwrap();
+        let key_b = prefix.push(&"b".to_string()).unwrap();
+
+        // Height 1: `a` = 1
+        wls.storage.block.height = BlockHeight(1);
+        wls.write(&key_a, 1_u64).unwrap();
+        wls.commit_block().unwrap();
+        // Height 2: `a` = 2, `b` = 2
+        wls.storage.block.height = BlockHeight(2);
+        wls.write(&key_a, 2_u64).unwrap();
+        wls.write(&key_b, 2_u64).unwrap();
+        wls.commit_block().unwrap();
+        // Height 3: `a` deleted
+        wls.storage.block.height = BlockHeight(3);
+        wls.delete(&key_a).unwrap();
+        wls.commit_block().unwrap();
+
+        let iter_at = |height| {
+            let storage = HistoricStorage::new(&wls.storage, height).unwrap();
+            iter_prefix_bytes(&storage, &prefix)
+                .unwrap()
+                .map(|res| res.unwrap())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            iter_at(BlockHeight(1)),
+            vec![(key_a.clone(), 1_u64.serialize_to_vec())]
+        );
+        assert_eq!(
+            iter_at(BlockHeight(2)),
+            vec![
+                (key_a.clone(), 2_u64.serialize_to_vec()),
+                (key_b.clone(), 2_u64.serialize_to_vec())
+            ]
+        );
+        assert_eq!(
+            iter_at(BlockHeight(3)),
+            vec![(key_b.clone(), 2_u64.serialize_to_vec())]
+        );
+
+        let storage =
+            HistoricStorage::new(&wls.storage, BlockHeight(1)).unwrap();
+        assert_eq!(storage.read::<u64>(&key_a).unwrap(), Some(1));
+        assert!(!storage.has_key(&key_b).unwrap());
+        assert_eq!(storage.get_block_height().unwrap(), BlockHeight(1));
+
+        assert!(
+            HistoricStorage::new(&wls.storage, BlockHeight(4)).is_err(),
+            "A height that hasn't been committed cannot be read"
+        );
+    }
+}
@@ -1,12 +1,21 @@
 //! Ledger's state storage with key-value backed store and a merkle tree
 
+pub mod account_history;
+pub mod balance_index;
+pub mod historic;
+pub mod read_cache;
+pub mod storage_usage;
 pub mod wl_storage;
 pub mod write_log;
 
 use core::fmt::Debug;
 use std::cmp::Ordering;
+use std::collections::BTreeSet;
 use std::format;
 
+pub use account_history::AccountHistory;
+pub use balance_index::BalanceIndex;
+pub use historic::HistoricStorage;
 use namada_core::borsh::{BorshDeserialize, BorshSerialize, BorshSerializeExt};
 use namada_core::tendermint::merkle::proof::ProofOps;
 use namada_core::types::address::{
@@ -34,7 +43,12 @@ pub use namada_merkle_tree::{
 };
 use namada_merkle_tree::{Error as MerkleTreeError, MerkleRoot};
 use namada_parameters::{self, EpochDuration, Parameters};
+use namada_storage::changed_keys::ChangedKeysFilter;
+use namada_storage::value_checksum::ValueChecksum;
 pub use namada_storage::{Error as StorageError, Result as StorageResult, *};
+pub use read_cache::{ReadCache, ReadCacheMetrics};
+use serde::{Deserialize, Serialize};
+pub use storage_usage::StorageUsage;
 use thiserror::Error;
 use tx_queue::{ExpiredTxsQueue, TxQueue};
 pub use wl_storage::{
@@ -106,6 +120,103 @@ where
     pub storage_read_past_height_limit: Option<u64>,
     /// Static merkle tree storage key filter
     pub merkle_tree_key_filter: fn(&storage::Key) -> bool,
+    /// The pruning mode of the data of the past blocks
+    pub pruning: PruningMode,
+    /// When set, the blocks at and above this height are never pruned, e.g.
+    /// because they may still be referred to by evidence of validator
+    /// misbehaviour. Updated by the ledger before committing a block.
+    pub pruning_safeguard_height: Option<BlockHeight>,
+    /// The oldest block that hasn't been pruned yet, if known
+    pub oldest_unpruned_height: Option<BlockHeight>,
+    /// When the writes of the committed blocks are synced to the disk
+    pub sync_policy: SyncPolicy,
+    /// When `true`, the Merkle tree is updated on a separate thread while the
+    /// block's writes are being added to the DB batch on a block commit
+    pub async_merkle_commit: bool,
+    /// When set, the data of the old blocks is moved to a secondary DB
+    pub cold_storage: Option<ColdStorage<D>>,
+    /// When set, the subspace values read from the DB are cached
+    pub read_cache: Option<ReadCache>,
+    /// When `true`, the checksums of the subspace values are written with the
+    /// values and the values read from the DB are verified
+    pub value_checksums: bool,
+    /// When `true`, the diffs of all the subspace keys are persisted, and not
+    /// only the ones of the merklized keys, so that the state diffs of the
+    /// blocks are complete
+    pub persist_all_diffs: bool,
+    /// The storage usage of the addresses
+    pub storage_usage: StorageUsage,
+    /// The balances changed since the last committed block, to be written to
+    /// the balance index
+    pub balance_index: BalanceIndex,
+    /// When set, the txs of the block to be written to the account history
+    /// index
+    pub account_history: Option<AccountHistory>,
+}
+
+/// A secondary DB to which the results and the Merkle tree stores of the old
+/// blocks are moved to keep the primary DB small. The moved data is read
+/// through from the secondary DB transparently.
+#[derive(Debug)]
+pub struct ColdStorage<D> {
+    /// The secondary DB
+    pub db: D,
+    /// The number of the most recent blocks whose data is kept in the primary
+    /// DB. At least the last committed block is always kept.
+    pub hot_blocks: u64,
+    /// The oldest block whose data hasn't been moved yet, if known
+    pub oldest_hot_height: Option<BlockHeight>,
+}
+
+/// The pruning mode of the data of the past blocks
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum PruningMode {
+    /// Never prune the data of the past blocks
+    Archive,
+    /// Only prune the Merkle tree stores of the epochs that are older than
+    /// the `storage_read_past_height_limit`
+    #[default]
+    Default,
+    /// Additionally prune the results, the subspace diffs and the base Merkle
+    /// tree of the blocks in the epochs that are older than the given number
+    /// of the most recent blocks. The blocks within the
+    /// `storage_read_past_height_limit` are always kept, so that queries and
+    /// IBC proofs can be served for them.
+    KeepRecent(u64),
+}
+
+/// When the writes of the committed blocks are synced to the disk
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum SyncPolicy {
+    /// Leave the syncing to the DB and the OS. The blocks committed shortly
+    /// before a crash of the machine may be lost, in which case they are
+    /// replayed from CometBFT on restart.
+    #[default]
+    Os,
+    /// Sync the writes on every block commit
+    EveryBlock,
+    /// Sync the writes on the commit of every given number of blocks
+    Interval(u64),
+}
+
+impl SyncPolicy {
+    /// Check if the writes of the block at the given height have to be
+    /// synced on its commit
+    pub fn is_sync_due(&self, height: BlockHeight) -> bool {
+        match self {
+            Self::Os => false,
+            Self::EveryBlock => true,
+            Self::Interval(interval) => {
+                *interval != 0 && height.0 % interval == 0
+            }
+        }
+    }
 }
 
 /// Last committed block
@@ -142,12 +253,111 @@ pub struct BlockStorage<H: StorageHasher> {
     pub results: BlockResults,
     /// Predecessor block epochs
     pub pred_epochs: Epochs,
+    /// The subspace keys changed since the last committed block
+    pub changed_keys: BTreeSet<Key>,
 }
 
 pub fn merklize_all_keys(_key: &storage::Key) -> bool {
     true
 }
 
+/// Update the Merkle tree with the write (`Some` value) or the deletion
+/// (`None`) of the given key at the given height
+fn update_merkle_tree<H: StorageHasher>(
+    tree: &mut MerkleTree<H>,
+    merkle_tree_key_filter: fn(&storage::Key) -> bool,
+    height: BlockHeight,
+    key: &Key,
+    value: Option<&[u8]>,
+) -> Result<()> {
+    match value {
+        Some(_) if is_pending_transfer_key(key) => {
+            // The tree of the bridge pool stores the current height for the
+            // pending transfer
+            tree.update(key, height.serialize_to_vec())?;
+        }
+        Some(value) if merkle_tree_key_filter(key) => {
+            tree.update(key, value)?;
+        }
+        None if merkle_tree_key_filter(key) => {
+            tree.delete(key)?;
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Write the checksum of the value written (`Some`) under the given key at
+/// the given height or, if the key is deleted (`None`), delete it
+fn batch_write_value_checksum<D: DB>(
+    db: &D,
+    batch: &mut D::WriteBatch,
+    height: BlockHeight,
+    key: &Key,
+    value: Option<&[u8]>,
+) -> Result<()> {
+    let checksum = value.map(|value| ValueChecksum::new(height, value));
+    Ok(db.batch_write_value_checksum(batch, key, checksum.as_ref())?)
+}
+
+/// The parts of the state touched by the DB side of a subspace write,
+/// borrowed separately from the Merkle tree so that the tree can be updated
+/// concurrently
+struct SubspaceDbWriter<'a, D: DB> {
+    db: &'a D,
+    height: BlockHeight,
+    merkle_tree_key_filter: fn(&storage::Key) -> bool,
+    persist_all_diffs: bool,
+    value_checksums: bool,
+    storage_usage: &'a mut StorageUsage,
+    balance_index: &'a mut BalanceIndex,
+    changed_keys: &'a mut BTreeSet<Key>,
+}
+
+impl<'a, D: DB> SubspaceDbWriter<'a, D> {
+    /// Batch write (`Some` value) or delete (`None`) the value of the given
+    /// key and keep the storage usage, the balance index, the changed keys
+    /// and the value checksums in sync. Returns the size difference from the
+    /// previous value.
+    fn batch_write(
+        &mut self,
+        batch: &mut D::WriteBatch,
+        key: &Key,
+        value: Option<&[u8]>,
+    ) -> Result<i64> {
+        let persist_diffs =
+            (self.merkle_tree_key_filter)(key) || self.persist_all_diffs;
+        let size_diff = match value {
+            Some(value) => self.db.batch_write_subspace_val(
+                batch,
+                self.height,
+                key,
+                value,
+                persist_diffs,
+            )?,
+            None => -self.db.batch_delete_subspace_val(
+                batch,
+                self.height,
+                key,
+                persist_diffs,
+            )?,
+        };
+        self.storage_usage.update(key, size_diff);
+        self.balance_index.update(key, value);
+        self.changed_keys.insert(key.clone());
+        if self.value_checksums {
+            batch_write_value_checksum(
+                self.db,
+                batch,
+                self.height,
+                key,
+                value,
+            )?;
+        }
+        Ok(size_diff)
+    }
+}
+
 #[allow(missing_docs)]
 #[derive(Error, Debug)]
 pub enum Error {
@@ -171,6 +381,30 @@ pub enum Error {
     InvalidCodeHash(HashError),
     #[error("DB error: {0}")]
     DbError(#[from] namada_storage::DbError),
+    #[error(
+        "The block at height {height} has already been committed, the last \
+         committed block height is {last_height}"
+    )]
+    BlockReplay {
+        height: BlockHeight,
+        last_height: BlockHeight,
+    },
+    #[error(
+        "The blocks between the last committed block height {last_height} and \
+         the block height {height} are missing"
+    )]
+    MissingBlocks {
+        height: BlockHeight,
+        last_height: BlockHeight,
+    },
+    #[error(
+        "The interrupted commit of the block at height {height} doesn't \
+         follow the last committed block height {last_height}"
+    )]
+    InvalidBlockJournal {
+        height: BlockHeight,
+        last_height: BlockHeight,
+    },
 }
 
 impl<D, H> State<D, H>
@@ -194,6 +428,7 @@ where
             epoch: Epoch::default(),
             pred_epochs: Epochs::default(),
             results: BlockResults::default(),
+            changed_keys: BTreeSet::default(),
         };
         State::<D, H> {
             db: D::open(db_path, cache),
@@ -217,12 +452,111 @@ where
             eth_events_queue: EthEventsQueue::default(),
             storage_read_past_height_limit,
             merkle_tree_key_filter,
+            pruning: PruningMode::default(),
+            pruning_safeguard_height: None,
+            oldest_unpruned_height: None,
+            sync_policy: SyncPolicy::default(),
+            async_merkle_commit: false,
+            cold_storage: None,
+            read_cache: None,
+            value_checksums: false,
+            persist_all_diffs: false,
+            storage_usage: StorageUsage::default(),
+            balance_index: BalanceIndex::default(),
+            account_history: None,
+        }
+    }
+
+    /// Make a read-only copy of the in-memory state of the last committed
+    /// block on top of the given handles of the DB and, if enabled, of the
+    /// cold storage DB, e.g. to serve the queries while the next block is
+    /// being processed. The Merkle tree isn't copied, so the proofs are
+    /// served only for the committed heights, from the Merkle tree stores in
+    /// the DB. The values read from the DB aren't cached.
+    pub fn read_only_copy(&self, db: D, cold_db: Option<D>) -> Self {
+        let block = BlockStorage {
+            tree: MerkleTree::default(),
+            hash: self.block.hash.clone(),
+            height: self.block.height,
+            epoch: self.block.epoch,
+            pred_epochs: self.block.pred_epochs.clone(),
+            results: self.block.results.clone(),
+            changed_keys: BTreeSet::default(),
+        };
+        let cold_storage = self.cold_storage.as_ref().zip(cold_db).map(
+            |(cold_storage, db)| ColdStorage {
+                db,
+                hot_blocks: cold_storage.hot_blocks,
+                oldest_hot_height: cold_storage.oldest_hot_height,
+            },
+        );
+        State::<D, H> {
+            db,
+            chain_id: self.chain_id.clone(),
+            block,
+            header: self.header.clone(),
+            last_block: self.last_block.clone(),
+            last_epoch: self.last_epoch,
+            next_epoch_min_start_height: self.next_epoch_min_start_height,
+            next_epoch_min_start_time: self.next_epoch_min_start_time,
+            address_gen: self.address_gen.clone(),
+            update_epoch_blocks_delay: self.update_epoch_blocks_delay,
+            tx_index: self.tx_index,
+            conversion_state: self.conversion_state.clone(),
+            tx_queue: self.tx_queue.clone(),
+            expired_txs_queue: ExpiredTxsQueue::default(),
+            native_token: self.native_token.clone(),
+            ethereum_height: self.ethereum_height.clone(),
+            eth_events_queue: self.eth_events_queue.clone(),
+            storage_read_past_height_limit: self.storage_read_past_height_limit,
+            merkle_tree_key_filter: self.merkle_tree_key_filter,
+            pruning: self.pruning,
+            pruning_safeguard_height: self.pruning_safeguard_height,
+            oldest_unpruned_height: self.oldest_unpruned_height,
+            sync_policy: self.sync_policy,
+            async_merkle_commit: false,
+            cold_storage,
+            read_cache: None,
+            value_checksums: self.value_checksums,
+            persist_all_diffs: self.persist_all_diffs,
+            storage_usage: self.storage_usage.clone(),
+            balance_index: BalanceIndex::default(),
+            account_history: self
+                .account_history
+                .as_ref()
+                .map(|_| AccountHistory::default()),
         }
     }
 
     /// Load the full state at the last committed height, if any. Returns the
     /// Merkle root hash and the height of the committed block.
+    ///
+    /// If the commit of a block has been interrupted, the data of the block
+    /// is discarded first, so that the block is applied again.
     pub fn load_last_state(&mut self) -> Result<()> {
+        let journal = self.db.read_block_journal()?;
+        if let Some(journal) = &journal {
+            tracing::warn!(
+                "The commit of the block at height {} with hash {} has been \
+                 interrupted, discarding its data",
+                journal.height,
+                journal.hash
+            );
+            self.db.discard_block(journal.height)?;
+        }
+        let last_block = self.db.read_last_block()?;
+        if let Some(journal) = journal {
+            // The first block may be at any height
+            if let Some(last_block) = &last_block {
+                if journal.height != last_block.height.next_height() {
+                    return Err(Error::InvalidBlockJournal {
+                        height: journal.height,
+                        last_height: last_block.height,
+                    });
+                }
+            }
+            self.db.delete_block_journal()?;
+        }
         if let Some(BlockStateRead {
             merkle_tree_stores,
             hash,
@@ -239,7 +573,7 @@ where
             tx_queue,
             ethereum_height,
             eth_events_queue,
-        }) = self.db.read_last_block()?
+        }) = last_block
         {
             self.block.hash = hash.clone();
             self.block.height = height;
@@ -259,6 +593,18 @@ where
             self.tx_queue = tx_queue;
             self.ethereum_height = ethereum_height;
             self.eth_events_queue = eth_events_queue;
+            self.storage_usage = StorageUsage::read(&self.db)?;
+            // The usage is missing when it's not been tracked yet or when it's
+            // been discarded by a rollback
+            if self.storage_usage.is_empty() {
+                tracing::info!("Computing the storage usage of the addresses");
+                self.storage_usage = StorageUsage::compute(&self.db)?;
+            }
+            // Likewise for the balance index
+            if BalanceIndex::is_missing(&self.db) {
+                tracing::info!("Building the balance index");
+                BalanceIndex::build(&mut self.db)?;
+            }
             tracing::debug!("Loaded storage from DB");
         } else {
             tracing::info!("No state could be found");
@@ -276,6 +622,40 @@ where
         }
     }
 
+    /// Write the journal of the current block to the database before any of
+    /// its data, to be able to discard the data if the commit of the block is
+    /// interrupted
+    pub fn write_block_journal(&mut self) -> Result<()> {
+        let journal = BlockJournal {
+            height: self.block.height,
+            hash: self.block.hash.clone(),
+        };
+        Ok(self.db.write_block_journal(&journal)?)
+    }
+
+    /// Check that the block at the given height follows the last committed
+    /// block, i.e. that it's neither replayed nor skipping any blocks
+    pub fn check_next_block_height(&self, height: BlockHeight) -> Result<()> {
+        // The first block may be at any height
+        let last_height = match &self.last_block {
+            Some(last_block) => last_block.height,
+            None => return Ok(()),
+        };
+        if height <= last_height {
+            Err(Error::BlockReplay {
+                height,
+                last_height,
+            })
+        } else if height != last_height.next_height() {
+            Err(Error::MissingBlocks {
+                height,
+                last_height,
+            })
+        } else {
+            Ok(())
+        }
+    }
+
     /// Persist the current block's state to the database
     pub fn commit_block(&mut self, mut batch: D::WriteBatch) -> Result<()> {
         // All states are written only when the first height or a new epoch
@@ -295,6 +675,8 @@ where
             }
         }
 
+        let changed_keys =
+            ChangedKeysFilter::new(self.block.changed_keys.iter());
         let state = BlockStateWrite {
             merkle_tree_stores: self.block.tree.stores(),
             header: self.header.as_ref(),
@@ -316,9 +698,11 @@ where
             tx_queue: &self.tx_queue,
             ethereum_height: self.ethereum_height.as_ref(),
             eth_events_queue: &self.eth_events_queue,
+            changed_keys: &changed_keys,
         };
         self.db
             .add_block_to_batch(state, &mut batch, is_full_commit)?;
+        self.block.changed_keys.clear();
         let header = self
             .header
             .take()
@@ -333,7 +717,20 @@ where
             // prune old merkle tree stores
             self.prune_merkle_tree_stores(&mut batch)?;
         }
+        self.prune_blocks(&mut batch)?;
+        self.move_blocks_to_cold(&mut batch)?;
+        self.storage_usage.batch_write(&mut self.db, &mut batch)?;
+        self.balance_index.batch_write(&mut self.db, &mut batch)?;
+        if let Some(account_history) = self.account_history.as_mut() {
+            account_history.batch_write(&mut self.db, &mut batch)?;
+        }
+        // Deleting the journal completes the commit
+        self.db.batch_delete_block_journal(&mut batch)?;
+        // All the writes of the block are applied at once
         self.db.exec_batch(batch)?;
+        if self.sync_policy.is_sync_due(self.block.height) {
+            self.db.sync()?;
+        }
         Ok(())
     }
 
@@ -346,7 +743,7 @@ where
     /// gas cost.
     pub fn has_key(&self, key: &Key) -> Result<(bool, u64)> {
         Ok((
-            self.db.read_subspace_val(key)?.is_some(),
+            self.read_subspace_val(key)?.is_some(),
             key.len() as u64 * STORAGE_ACCESS_GAS_PER_BYTE,
         ))
     }
@@ -355,7 +752,7 @@ where
     pub fn read(&self, key: &Key) -> Result<(Option<Vec<u8>>, u64)> {
         tracing::debug!("storage read key {}", key);
 
-        match self.db.read_subspace_val(key)? {
+        match self.read_subspace_val(key)? {
             Some(v) => {
                 let gas =
                     (key.len() + v.len()) as u64 * STORAGE_ACCESS_GAS_PER_BYTE;
@@ -365,6 +762,61 @@ where
         }
     }
 
+    /// Returns a value from the specified subspace without gas accounting.
+    /// The value is read through the read cache, if it's enabled.
+    pub fn read_subspace_val(&self, key: &Key) -> Result<Option<Vec<u8>>> {
+        match &self.read_cache {
+            Some(cache) => cache.read(key, || self.read_db_subspace_val(key)),
+            None => self.read_db_subspace_val(key),
+        }
+        .map_err(Error::from)
+    }
+
+    // Read a subspace value from the DB and, with the `value_checksums`
+    // enabled, verify it with its checksum. A corrupted value is repaired
+    // from the diff written with it, if it's still kept.
+    fn read_db_subspace_val(&self, key: &Key) -> DbResult<Option<Vec<u8>>> {
+        let value = self.db.read_subspace_val(key)?;
+        if !self.value_checksums {
+            return Ok(value);
+        }
+        let checksum = match self.db.read_value_checksum(key)? {
+            Some(checksum) => checksum,
+            // Written before the checksums were enabled or deleted
+            None => return Ok(value),
+        };
+        if matches!(&value, Some(value) if checksum.matches(value)) {
+            return Ok(value);
+        }
+        tracing::error!(
+            "The value of the key {key} doesn't match its checksum written at \
+             the height {}",
+            checksum.height
+        );
+        match self.db.read_diffs_val(key, checksum.height, false)? {
+            Some(repaired) if checksum.matches(&repaired) => {
+                self.db.repair_subspace_val(key, &repaired)?;
+                tracing::warn!(
+                    "Repaired the value of the key {key} from its diff at the \
+                     height {}",
+                    checksum.height
+                );
+                Ok(Some(repaired))
+            }
+            _ => Err(DbError::CorruptedValue {
+                key: key.clone(),
+                height: checksum.height,
+            }),
+        }
+    }
+
+    // Invalidate the cached value of a key that is being written to the DB
+    fn invalidate_read_cache(&self, key: &Key) {
+        if let Some(cache) = &self.read_cache {
+            cache.invalidate(key);
+        }
+    }
+
     /// Returns a value from the specified subspace at the given height (or the
     /// last committed height when 0) and the gas cost.
     pub fn read_with_height(
@@ -412,9 +864,57 @@ where
         )
     }
 
-    /// Returns an iterator over the block results
-    pub fn iter_results(&self) -> (<D as DBIter<'_>>::PrefixIter, u64) {
-        (self.db.iter_results(), 0)
+    /// Returns an iterator over the block results, including the ones moved
+    /// to the cold storage
+    pub fn iter_results(
+        &self,
+    ) -> (impl Iterator<Item = (String, Vec<u8>, u64)> + '_, u64) {
+        let cold_results = self
+            .cold_storage
+            .iter()
+            .flat_map(|cold| cold.db.iter_results());
+        (self.db.iter_results().chain(cold_results), 0)
+    }
+
+    /// Find the heights of the committed blocks in the given inclusive range
+    /// that changed the key. The blocks are looked up with the filters of
+    /// their changed keys. A match of a filter is confirmed with the diffs of
+    /// the key when they are kept, i.e. for a merklized key in a block that
+    /// hasn't been pruned, so that only the matches of the other blocks may
+    /// be false positives. A block committed without a filter is only found
+    /// with the diffs.
+    pub fn find_blocks_changing_key(
+        &self,
+        key: &Key,
+        from: BlockHeight,
+        to: BlockHeight,
+    ) -> Result<Vec<BlockHeight>> {
+        let is_key_merklized = (self.merkle_tree_key_filter)(key);
+        let to = std::cmp::min(to, self.get_last_block_height());
+        let mut heights = vec![];
+        for height in (from.0..=to.0).map(BlockHeight) {
+            let filter = self.db.read_changed_keys_filter(height)?;
+            if matches!(&filter, Some(filter) if !filter.may_contain(key)) {
+                continue;
+            }
+            let are_diffs_kept = is_key_merklized
+                && match self.pruning {
+                    PruningMode::KeepRecent(_) => self
+                        .oldest_unpruned_height
+                        .map_or(false, |oldest| height >= oldest),
+                    _ => true,
+                };
+            let is_changed = if are_diffs_kept {
+                self.db.read_diffs_val(key, height, false)?.is_some()
+                    || self.db.read_diffs_val(key, height, true)?.is_some()
+            } else {
+                filter.is_some()
+            };
+            if is_changed {
+                heights.push(height);
+            }
+        }
+        Ok(heights)
     }
 
     /// Write a value to the specified subspace and returns the gas cost and the
@@ -444,12 +944,27 @@ where
 
         let len = value.len();
         let gas = (key.len() + len) as u64 * STORAGE_WRITE_GAS_PER_BYTE;
+        self.invalidate_read_cache(key);
         let size_diff = self.db.write_subspace_val(
             self.block.height,
             key,
             value,
-            is_key_merklized,
+            is_key_merklized || self.persist_all_diffs,
         )?;
+        self.storage_usage.update(key, size_diff);
+        self.balance_index.update(key, Some(value));
+        self.block.changed_keys.insert(key.clone());
+        if self.value_checksums {
+            let mut batch = D::batch();
+            batch_write_value_checksum(
+                &self.db,
+                &mut batch,
+                self.block.height,
+                key,
+                Some(value),
+            )?;
+            self.db.exec_batch(batch)?;
+        }
         Ok((gas, size_diff))
     }
 
@@ -464,11 +979,26 @@ where
             if is_key_merklized {
                 self.block.tree.delete(key)?;
             }
+            self.invalidate_read_cache(key);
             deleted_bytes_len = self.db.delete_subspace_val(
                 self.block.height,
                 key,
-                is_key_merklized,
+                is_key_merklized || self.persist_all_diffs,
             )?;
+            self.storage_usage.update(key, -deleted_bytes_len);
+            self.balance_index.update(key, None);
+            self.block.changed_keys.insert(key.clone());
+            if self.value_checksums {
+                let mut batch = D::batch();
+                batch_write_value_checksum(
+                    &self.db,
+                    &mut batch,
+                    self.block.height,
+                    key,
+                    None,
+                )?;
+                self.db.exec_batch(batch)?;
+            }
         }
         let gas = (key.len() + deleted_bytes_len as usize) as u64
             * STORAGE_WRITE_GAS_PER_BYTE;
@@ -582,7 +1112,6 @@ where
                 None => BlockHeight(1),
             };
         let stores = self
-            .db
             .read_merkle_tree_stores(epoch, epoch_start_height, store_type)?
             .ok_or(Error::NoMerkleTree { height })?;
         let prefix = store_type.and_then(|st| st.provable_prefix());
@@ -685,7 +1214,6 @@ where
         if let Some(st) = store_type {
             // Add the base tree with the given height
             let mut stores = self
-                .db
                 .read_merkle_tree_stores(epoch, height, Some(StoreType::Base))?
                 .ok_or(Error::NoMerkleTree { height })?;
             let restored_stores = tree.stores();
@@ -903,27 +1431,7 @@ where
         key: &Key,
         value: impl AsRef<[u8]>,
     ) -> Result<i64> {
-        let value = value.as_ref();
-        let is_key_merklized = (self.merkle_tree_key_filter)(key);
-
-        if is_pending_transfer_key(key) {
-            // The tree of the bridge pool stores the current height for the
-            // pending transfer
-            let height = self.block.height.serialize_to_vec();
-            self.block.tree.update(key, height)?;
-        } else {
-            // Update the merkle tree
-            if is_key_merklized {
-                self.block.tree.update(key, value)?;
-            }
-        }
-        Ok(self.db.batch_write_subspace_val(
-            batch,
-            self.block.height,
-            key,
-            value,
-            is_key_merklized,
-        )?)
+        self.batch_write_subspace_modification(batch, key, Some(value.as_ref()))
     }
 
     /// Batch delete the value with the given height and account subspace key
@@ -934,17 +1442,105 @@ where
         batch: &mut D::WriteBatch,
         key: &Key,
     ) -> Result<i64> {
-        let is_key_merklized = (self.merkle_tree_key_filter)(key);
-        // Update the merkle tree
-        if is_key_merklized {
-            self.block.tree.delete(key)?;
-        }
-        Ok(self.db.batch_delete_subspace_val(
-            batch,
+        let size_diff =
+            self.batch_write_subspace_modification(batch, key, None)?;
+        Ok(-size_diff)
+    }
+
+    /// Update the Merkle tree and batch write (`Some` value) or delete
+    /// (`None`) the value of the given key. Returns the size difference from
+    /// the previous value.
+    fn batch_write_subspace_modification(
+        &mut self,
+        batch: &mut D::WriteBatch,
+        key: &Key,
+        value: Option<&[u8]>,
+    ) -> Result<i64> {
+        update_merkle_tree(
+            &mut self.block.tree,
+            self.merkle_tree_key_filter,
             self.block.height,
             key,
-            is_key_merklized,
-        )?)
+            value,
+        )?;
+        // The batch is executed in the same block commit
+        self.invalidate_read_cache(key);
+        self.subspace_db_writer().batch_write(batch, key, value)
+    }
+
+    /// Borrow the parts of the state that are updated by the DB side of the
+    /// subspace writes
+    fn subspace_db_writer(&mut self) -> SubspaceDbWriter<'_, D> {
+        SubspaceDbWriter {
+            db: &self.db,
+            height: self.block.height,
+            merkle_tree_key_filter: self.merkle_tree_key_filter,
+            persist_all_diffs: self.persist_all_diffs,
+            value_checksums: self.value_checksums,
+            storage_usage: &mut self.storage_usage,
+            balance_index: &mut self.balance_index,
+            changed_keys: &mut self.block.changed_keys,
+        }
+    }
+
+    /// Batch write the given modifications of the subspace at the current
+    /// height, where a `None` value is a deletion. With the
+    /// `async_merkle_commit` enabled, the Merkle tree is updated on a
+    /// separate thread while the modifications are added to the batch.
+    pub fn batch_write_subspace_modifications(
+        &mut self,
+        batch: &mut D::WriteBatch,
+        modifications: &[(&Key, Option<&[u8]>)],
+    ) -> Result<()> {
+        if !self.async_merkle_commit {
+            for (key, value) in modifications {
+                self.batch_write_subspace_modification(batch, key, *value)?;
+            }
+            return Ok(());
+        }
+
+        for (key, _value) in modifications {
+            self.invalidate_read_cache(key);
+        }
+        let key_filter = self.merkle_tree_key_filter;
+        let height = self.block.height;
+        // Split the borrows of the Merkle tree and of the DB side state
+        let State {
+            db,
+            block,
+            persist_all_diffs,
+            value_checksums,
+            storage_usage,
+            balance_index,
+            ..
+        } = self;
+        let tree = &mut block.tree;
+        let mut db_writer = SubspaceDbWriter {
+            db,
+            height,
+            merkle_tree_key_filter: key_filter,
+            persist_all_diffs: *persist_all_diffs,
+            value_checksums: *value_checksums,
+            storage_usage,
+            balance_index,
+            changed_keys: &mut block.changed_keys,
+        };
+        std::thread::scope(|scope| {
+            let tree_update = scope.spawn(move || {
+                modifications.iter().try_for_each(|(key, value)| {
+                    update_merkle_tree(tree, key_filter, height, key, *value)
+                })
+            });
+            let db_writes =
+                modifications.iter().try_for_each(|(key, value)| {
+                    db_writer.batch_write(batch, key, *value).map(|_| ())
+                });
+            let tree_update = tree_update
+                .join()
+                .expect("The Merkle tree update thread shouldn't panic");
+            db_writes?;
+            tree_update
+        })
     }
 
     // Prune merkle tree stores. Use after updating self.block.height in the
@@ -953,7 +1549,7 @@ where
         &mut self,
         batch: &mut D::WriteBatch,
     ) -> Result<()> {
-        if self.block.epoch.0 == 0 {
+        if self.block.epoch.0 == 0 || self.pruning == PruningMode::Archive {
             return Ok(());
         }
         // Prune non-provable stores at the previous epoch
@@ -996,6 +1592,141 @@ where
         Ok(())
     }
 
+    // Prune the data of the blocks that fell out of the retention window of
+    // the `KeepRecent` pruning mode. Use after updating self.block.height in
+    // the commit.
+    fn prune_blocks(&mut self, batch: &mut D::WriteBatch) -> Result<()> {
+        if !matches!(self.pruning, PruningMode::KeepRecent(_)) {
+            return Ok(());
+        }
+        // The Merkle tree at a retained height is restored from the stores
+        // at the start of its epoch and the diffs since, so only whole epochs
+        // can be pruned
+        let oldest_height = match self
+            .block
+            .pred_epochs
+            .get_start_height_of_epoch(self.get_oldest_epoch())
+        {
+            Some(height) if height.0 > 0 => height,
+            _ => return Ok(()),
+        };
+        let mut height = match self.oldest_unpruned_height {
+            Some(height) => height,
+            // Look up the oldest block that still has its results, e.g. after
+            // a restart or a change of the pruning mode
+            None => self
+                .db
+                .iter_results()
+                .filter_map(|(height, _, _)| BlockHeight::parse(height).ok())
+                .min()
+                .unwrap_or(oldest_height),
+        };
+        while height < oldest_height {
+            self.db.prune_block(batch, height)?;
+            height = height.next_height();
+        }
+        self.oldest_unpruned_height = Some(height);
+        Ok(())
+    }
+
+    // Move the results and the Merkle tree stores of the blocks that are older
+    // than the hot blocks to the cold storage. Use after updating
+    // self.block.height in the commit.
+    fn move_blocks_to_cold(&mut self, batch: &mut D::WriteBatch) -> Result<()> {
+        let cold = match self.cold_storage.as_mut() {
+            Some(cold) => cold,
+            None => return Ok(()),
+        };
+        // The block being committed must stay in the primary DB, because its
+        // data is only in the batch
+        let hot_blocks = std::cmp::max(cold.hot_blocks, 1);
+        let oldest_hot_height =
+            match self.block.height.0.checked_sub(hot_blocks) {
+                Some(height) if height > 0 => BlockHeight(height),
+                _ => return Ok(()),
+            };
+        let mut height = match cold.oldest_hot_height {
+            Some(height) => height,
+            // Look up the oldest block that still has its results in the
+            // primary DB, e.g. after a restart
+            None => self
+                .db
+                .iter_results()
+                .filter_map(|(height, _, _)| BlockHeight::parse(height).ok())
+                .min()
+                .unwrap_or(oldest_hot_height),
+        };
+        let is_moving = height < oldest_hot_height;
+        while height < oldest_hot_height {
+            let next_height = height.next_height();
+            // The subtree stores of an epoch are moved with its last block
+            let pred_epochs = &self.block.pred_epochs;
+            let ended_epoch = pred_epochs.get_epoch(height).filter(|epoch| {
+                pred_epochs.get_epoch(next_height) != Some(*epoch)
+            });
+            self.db.move_block_to_cold(
+                batch,
+                &mut cold.db,
+                height,
+                ended_epoch,
+            )?;
+            height = next_height;
+        }
+        if is_moving {
+            // The moved data has to be persisted before it's deleted from the
+            // primary DB with the batch
+            cold.db.sync()?;
+        }
+        cold.oldest_hot_height = Some(height);
+        Ok(())
+    }
+
+    // Read the Merkle tree stores from the DB or, if they have been moved,
+    // from the cold storage. The base tree and the subtrees of an epoch may
+    // be in different DBs.
+    fn read_merkle_tree_stores(
+        &self,
+        epoch: Epoch,
+        base_height: BlockHeight,
+        store_type: Option<StoreType>,
+    ) -> Result<Option<MerkleTreeStoresRead>> {
+        let cold = match &self.cold_storage {
+            Some(cold) => cold,
+            None => {
+                return Ok(self.db.read_merkle_tree_stores(
+                    epoch,
+                    base_height,
+                    store_type,
+                )?);
+            }
+        };
+        let store_types = match &store_type {
+            Some(st) => std::slice::from_ref(st).iter(),
+            None => StoreType::iter(),
+        };
+        let mut stores = MerkleTreeStoresRead::default();
+        for st in store_types {
+            let read = match self.db.read_merkle_tree_stores(
+                epoch,
+                base_height,
+                Some(*st),
+            )? {
+                Some(read) => read,
+                None => match cold.db.read_merkle_tree_stores(
+                    epoch,
+                    base_height,
+                    Some(*st),
+                )? {
+                    Some(read) => read,
+                    None => return Ok(None),
+                },
+            };
+            stores.set_root(st, read.get_root(*st));
+            stores.set_store(read.get_store(*st).to_owned());
+        }
+        Ok(Some(stores))
+    }
+
     /// Get the height of the last committed block or 0 if no block has been
     /// committed yet. The first block is at height 1.
     pub fn get_last_block_height(&self) -> BlockHeight {
@@ -1005,17 +1736,35 @@ where
             .unwrap_or_default()
     }
 
-    /// Get the oldest epoch where we can read a value
-    pub fn get_oldest_epoch(&self) -> Epoch {
-        let oldest_height = match self.storage_read_past_height_limit {
-            Some(limit) if limit < self.get_last_block_height().0 => {
-                (self.get_last_block_height().0 - limit).into()
+    /// Get the oldest height of the blocks whose data must be retained
+    /// according to the pruning mode and its safeguards
+    pub fn get_oldest_height(&self) -> BlockHeight {
+        let blocks_to_keep = match self.pruning {
+            PruningMode::Archive => None,
+            PruningMode::Default => self.storage_read_past_height_limit,
+            PruningMode::KeepRecent(blocks) => Some(std::cmp::max(
+                blocks,
+                self.storage_read_past_height_limit.unwrap_or_default(),
+            )),
+        };
+        let last_height = self.get_last_block_height().0;
+        let oldest_height = match blocks_to_keep {
+            Some(limit) if limit < last_height => {
+                BlockHeight(last_height - limit)
             }
             _ => BlockHeight(1),
         };
+        match self.pruning_safeguard_height {
+            Some(safeguard) => std::cmp::min(oldest_height, safeguard),
+            None => oldest_height,
+        }
+    }
+
+    /// Get the oldest epoch where we can read a value
+    pub fn get_oldest_epoch(&self) -> Epoch {
         self.block
             .pred_epochs
-            .get_epoch(oldest_height)
+            .get_epoch(self.get_oldest_height())
             .unwrap_or_default()
     }
 
@@ -1129,6 +1878,7 @@ pub mod testing {
                 epoch: Epoch::default(),
                 pred_epochs: Epochs::default(),
                 results: BlockResults::default(),
+                changed_keys: BTreeSet::default(),
             };
             Self {
                 db: MockDB::default(),
@@ -1152,6 +1902,18 @@ pub mod testing {
                 eth_events_queue: EthEventsQueue::default(),
                 storage_read_past_height_limit: Some(1000),
                 merkle_tree_key_filter: merklize_all_keys,
+                pruning: PruningMode::default(),
+                pruning_safeguard_height: None,
+                oldest_unpruned_height: None,
+                sync_policy: SyncPolicy::default(),
+                async_merkle_commit: false,
+                cold_storage: None,
+                read_cache: None,
+                value_checksums: false,
+                persist_all_diffs: false,
+                storage_usage: StorageUsage::default(),
+                balance_index: BalanceIndex::default(),
+                account_history: None,
             }
         }
     }
@@ -1172,10 +1934,14 @@ mod tests {
     use std::collections::BTreeMap;
 
     use chrono::{TimeZone, Utc};
+    use namada_core::types::address::testing::established_address_1;
     use namada_core::types::dec::Dec;
+    use namada_core::types::storage::DbKeySeg;
     use namada_core::types::time::{self, Duration};
     use namada_core::types::token;
     use namada_parameters::Parameters;
+    use namada_storage::state_diff::StateDiff;
+    use namada_trans_token::storage_key::balance_key;
     use proptest::prelude::*;
     use proptest::test_runner::Config;
 
@@ -1578,4 +2344,314 @@ mod tests {
             .unwrap();
         assert!(res2.is_none());
     }
+
+    #[test]
+    fn test_async_merkle_commit() {
+        let mut sync_wls = TestWlStorage::default();
+        let mut async_wls = TestWlStorage::default();
+        async_wls.storage.async_merkle_commit = true;
+        for wls in [&mut sync_wls, &mut async_wls] {
+            (wls.storage.merkle_tree_key_filter) = merkle_tree_key_filter;
+        }
+
+        let key1 = test_key_1();
+        let key2 = test_key_2();
+        for wls in [&mut sync_wls, &mut async_wls] {
+            wls.write(&key1, 1u64).unwrap();
+            wls.write(&key2, 2u64).unwrap();
+            wls.commit_block().unwrap();
+            wls.storage.block.height = wls.storage.block.height.next_height();
+        }
+        assert_eq!(
+            sync_wls.storage.merkle_root(),
+            async_wls.storage.merkle_root()
+        );
+        assert_eq!(
+            async_wls.storage.db.read_subspace_val(&key2).unwrap(),
+            Some(2u64.serialize_to_vec())
+        );
+
+        for wls in [&mut sync_wls, &mut async_wls] {
+            wls.delete(&key1).unwrap();
+            wls.commit_block().unwrap();
+        }
+        assert_eq!(
+            sync_wls.storage.merkle_root(),
+            async_wls.storage.merkle_root()
+        );
+        assert!(!async_wls.storage.block.tree.has_key(&key1).unwrap());
+        assert!(async_wls
+            .storage
+            .db
+            .read_subspace_val(&key1)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_read_cache_invalidation() {
+        let mut wls = TestWlStorage::default();
+        wls.storage.read_cache =
+            Some(ReadCache::new(std::num::NonZeroUsize::new(1024).unwrap()));
+        let key = test_key_1();
+
+        // The absence of the value is cached
+        assert_eq!(wls.read::<u64>(&key).unwrap(), None);
+        wls.write(&key, 1u64).unwrap();
+        wls.commit_block().unwrap();
+        assert_eq!(wls.read::<u64>(&key).unwrap(), Some(1));
+        // The cached value is served until the key is written again
+        assert_eq!(wls.read::<u64>(&key).unwrap(), Some(1));
+        wls.write(&key, 2u64).unwrap();
+        wls.commit_block().unwrap();
+        assert_eq!(wls.read::<u64>(&key).unwrap(), Some(2));
+        wls.delete(&key).unwrap();
+        wls.commit_block().unwrap();
+        assert_eq!(wls.read::<u64>(&key).unwrap(), None);
+
+        let metrics = wls.storage.read_cache.as_ref().unwrap().take_metrics();
+        assert_eq!((metrics.hits, metrics.misses), (1, 4));
+    }
+
+    #[test]
+    fn test_interrupted_block_commit() {
+        let mut wls = TestWlStorage::default();
+        let key = test_key_1();
+
+        wls.storage.block.height = BlockHeight(1);
+        wls.write(&key, 1u64).unwrap();
+        wls.commit_block().unwrap();
+
+        // The commit of the next block is interrupted after some of its data
+        // has been written
+        wls.storage.block.height = BlockHeight(2);
+        wls.storage.write_block_journal().unwrap();
+        let mut batch = TestStorage::batch();
+        wls.storage
+            .batch_write_subspace_val(&mut batch, &key, 2u64.serialize_to_vec())
+            .unwrap();
+
+        // On a restart, the data of the block is discarded
+        wls.storage.load_last_state().unwrap();
+        assert_eq!(wls.storage.get_last_block_height(), BlockHeight(1));
+        assert_eq!(wls.storage.db.read_block_journal().unwrap(), None);
+        assert_eq!(
+            wls.storage.db.read_subspace_val(&key).unwrap(),
+            Some(1u64.serialize_to_vec())
+        );
+
+        // Only the block following the last committed block can be applied
+        assert!(matches!(
+            wls.storage.check_next_block_height(BlockHeight(1)),
+            Err(Error::BlockReplay { .. })
+        ));
+        assert!(matches!(
+            wls.storage.check_next_block_height(BlockHeight(3)),
+            Err(Error::MissingBlocks { .. })
+        ));
+        wls.storage.check_next_block_height(BlockHeight(2)).unwrap();
+    }
+
+    #[test]
+    fn test_storage_usage() {
+        let mut wls = TestWlStorage::default();
+        let owner = established_address_1();
+        let key_a =
+            Key::from(owner.to_db_key()).push(&"a".to_string()).unwrap();
+        let key_b =
+            Key::from(owner.to_db_key()).push(&"b".to_string()).unwrap();
+
+        wls.storage.block.height = BlockHeight(1);
+        wls.write(&key_a, 1u64).unwrap();
+        wls.write(&key_b, vec![1u8; 3]).unwrap();
+        wls.commit_block().unwrap();
+        // A u64 and a vec of 3 bytes with its u32 length
+        assert_eq!(wls.storage.storage_usage.get(&owner), 8 + 7);
+
+        wls.storage.block.height = BlockHeight(2);
+        wls.delete(&key_a).unwrap();
+        wls.write(&key_b, vec![1u8; 1]).unwrap();
+        wls.commit_block().unwrap();
+        assert_eq!(wls.storage.storage_usage.get(&owner), 5);
+
+        // The usage is persisted with the block...
+        wls.storage.storage_usage = StorageUsage::default();
+        wls.storage.load_last_state().unwrap();
+        assert_eq!(wls.storage.storage_usage.get(&owner), 5);
+        // ... and it's computed from the subspace when it's missing
+        let mut batch = TestStorage::batch();
+        wls.storage
+            .db
+            .batch_write_storage_usage(&mut batch, &owner, 0)
+            .unwrap();
+        wls.storage.exec_batch(batch).unwrap();
+        wls.storage.load_last_state().unwrap();
+        assert_eq!(wls.storage.storage_usage.get(&owner), 5);
+    }
+
+    #[test]
+    fn test_balance_index() {
+        let mut wls = TestWlStorage::default();
+        let nam = namada_core::types::address::nam();
+        let owner = established_address_1();
+        let key = balance_key(&nam, &owner);
+        let read_balance = |wls: &TestWlStorage| {
+            balance_index::read_balance(&wls.storage.db, &nam, &owner).unwrap()
+        };
+
+        wls.storage.block.height = BlockHeight(1);
+        wls.write(&key, token::Amount::from(10_u64)).unwrap();
+        wls.commit_block().unwrap();
+        assert_eq!(read_balance(&wls), token::Amount::from(10_u64));
+        assert_eq!(
+            balance_index::read_holders(&wls.storage.db, &nam).unwrap(),
+            vec![(owner.clone(), token::Amount::from(10_u64))]
+        );
+
+        wls.storage.block.height = BlockHeight(2);
+        wls.delete(&key).unwrap();
+        wls.commit_block().unwrap();
+        assert_eq!(read_balance(&wls), token::Amount::zero());
+        assert!(balance_index::read_holders(&wls.storage.db, &nam)
+            .unwrap()
+            .is_empty());
+
+        // The index is built from the subspace when it's missing
+        wls.storage.block.height = BlockHeight(3);
+        wls.write(&key, token::Amount::from(5_u64)).unwrap();
+        wls.commit_block().unwrap();
+        let mut batch = TestStorage::batch();
+        wls.storage
+            .db
+            .batch_write_indexed_balance(
+                &mut batch,
+                &nam,
+                &owner,
+                token::Amount::zero(),
+            )
+            .unwrap();
+        wls.storage.exec_batch(batch).unwrap();
+        assert!(BalanceIndex::is_missing(&wls.storage.db));
+        wls.storage.load_last_state().unwrap();
+        assert_eq!(read_balance(&wls), token::Amount::from(5_u64));
+    }
+
+    #[test]
+    fn test_find_blocks_changing_key() {
+        let mut wls = TestWlStorage::default();
+        // The keys under `unmerklized` don't have their diffs kept
+        wls.storage.merkle_tree_key_filter = |key: &Key| {
+            key.first() != Some(&DbKeySeg::StringSeg("unmerklized".into()))
+        };
+        let key_a = Key::parse("a").unwrap();
+        let key_b = Key::parse("b").unwrap();
+        let key_c = Key::parse("unmerklized/c").unwrap();
+
+        wls.storage.block.height = BlockHeight(1);
+        wls.write(&key_a, 1u64).unwrap();
+        wls.write(&key_c, 1u64).unwrap();
+        wls.commit_block().unwrap();
+        wls.storage.block.height = BlockHeight(2);
+        wls.write(&key_b, 1u64).unwrap();
+        wls.commit_block().unwrap();
+        wls.storage.block.height = BlockHeight(3);
+        wls.delete(&key_a).unwrap();
+        wls.write(&key_c, 2u64).unwrap();
+        wls.commit_block().unwrap();
+
+        let find = |key: &Key, from: u64, to: u64| {
+            wls.storage
+                .find_blocks_changing_key(
+                    key,
+                    BlockHeight(from),
+                    BlockHeight(to),
+                )
+                .unwrap()
+        };
+        assert_eq!(find(&key_a, 1, 3), vec![BlockHeight(1), BlockHeight(3)]);
+        assert_eq!(find(&key_a, 2, 2), vec![]);
+        assert_eq!(find(&key_b, 0, 10), vec![BlockHeight(2)]);
+        // The blocks that changed a key without diffs are found with the
+        // filters only, which may have false positives
+        let found = find(&key_c, 1, 3);
+        assert!(found.contains(&BlockHeight(1)));
+        assert!(found.contains(&BlockHeight(3)));
+        // The matches of a key with diffs are confirmed with the diffs
+        assert_eq!(find(&Key::parse("unknown").unwrap(), 1, 3), vec![]);
+    }
+
+    #[test]
+    fn test_value_checksums() {
+        let mut wls = TestWlStorage::default();
+        wls.storage.value_checksums = true;
+        let key = Key::parse("a").unwrap();
+        let other = Key::parse("b").unwrap();
+
+        wls.storage.block.height = BlockHeight(1);
+        wls.write(&key, 1u64).unwrap();
+        wls.write(&other, 2u64).unwrap();
+        wls.commit_block().unwrap();
+        assert_eq!(wls.read::<u64>(&key).unwrap(), Some(1));
+
+        // A corrupted value is repaired from its diff
+        wls.storage
+            .db
+            .repair_subspace_val(&key, b"corrupted")
+            .unwrap();
+        assert_eq!(wls.read::<u64>(&key).unwrap(), Some(1));
+        assert_eq!(
+            wls.storage.db.read_subspace_val(&key).unwrap(),
+            Some(1u64.serialize_to_vec())
+        );
+
+        // A corrupted value without its diff cannot be repaired
+        let mut batch = TestStorage::batch();
+        wls.storage
+            .db
+            .prune_block(&mut batch, BlockHeight(1))
+            .unwrap();
+        wls.storage.exec_batch(batch).unwrap();
+        wls.storage
+            .db
+            .repair_subspace_val(&other, b"corrupted")
+            .unwrap();
+        assert!(matches!(
+            wls.storage.read(&other),
+            Err(Error::DbError(DbError::CorruptedValue { .. }))
+        ));
+
+        // A deleted value isn't verified
+        wls.storage.block.height = BlockHeight(2);
+        wls.delete(&other).unwrap();
+        wls.commit_block().unwrap();
+        assert_eq!(wls.read::<u64>(&other).unwrap(), None);
+    }
+
+    #[test]
+    fn test_persist_all_diffs() {
+        for persist_all_diffs in [false, true] {
+            let mut wls = TestWlStorage::default();
+            (wls.storage.merkle_tree_key_filter) = merkle_tree_key_filter;
+            wls.storage.persist_all_diffs = persist_all_diffs;
+            // A key that isn't merklized
+            let key = test_key_2();
+
+            wls.storage.block.height = BlockHeight(1);
+            wls.write(&key, 1u64).unwrap();
+            wls.commit_block().unwrap();
+            wls.storage.block.height = BlockHeight(2);
+            wls.write(&key, 2u64).unwrap();
+            wls.commit_block().unwrap();
+
+            // The diff of the first block is only complete when the diffs of
+            // all the keys are persisted
+            let diff = StateDiff::read(&wls.storage.db, BlockHeight(1));
+            assert_eq!(
+                diff.changes.contains_key(&key.to_string()),
+                persist_all_diffs
+            );
+            let diff = StateDiff::read(&wls.storage.db, BlockHeight(2));
+            assert!(diff.changes.contains_key(&key.to_string()));
+        }
+    }
 }
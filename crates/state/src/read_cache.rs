@@ -0,0 +1,151 @@
+//! An in-memory LRU cache of the subspace values read from the DB. A cached
+//! value is invalidated when its key is written to the DB, so the cache
+//! doesn't change the values or the gas of the reads.
+
+use std::collections::hash_map::RandomState;
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
+
+use clru::{CLruCache, CLruCacheConfig, WeightScale};
+use namada_core::types::storage::Key;
+
+/// The default maximum size of the read cache in bytes
+pub const DEFAULT_READ_CACHE_BYTES: usize = 64 * 1024 * 1024; // 64 MiB
+
+/// LRU cache of the subspace values read from the DB, including the absence
+/// of a value. Thread-safe.
+#[derive(Debug)]
+pub struct ReadCache(Mutex<Inner>);
+
+#[derive(Debug)]
+struct Inner {
+    cache: CLruCache<Key, Option<Vec<u8>>, RandomState, EntryScale>,
+    hits: u64,
+    misses: u64,
+}
+
+/// Weighs the cache entries by their size in bytes, which limits the
+/// capacity of the cache (CLruCache::len + CLruCache::weight <=
+/// CLruCache::capacity).
+#[derive(Debug)]
+struct EntryScale;
+
+impl WeightScale<Key, Option<Vec<u8>>> for EntryScale {
+    fn weight(&self, key: &Key, value: &Option<Vec<u8>>) -> usize {
+        key.len() + value.as_ref().map_or(0, Vec::len)
+    }
+}
+
+/// The usage of a [`ReadCache`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ReadCacheMetrics {
+    /// The number of reads served from the cache
+    pub hits: u64,
+    /// The number of reads that had to go to the DB
+    pub misses: u64,
+    /// The number of cached entries
+    pub entries: usize,
+    /// The total size of the cached entries in bytes
+    pub bytes: usize,
+}
+
+impl ReadCache {
+    /// Create a cache with the given maximum size in bytes
+    pub fn new(max_bytes: NonZeroUsize) -> Self {
+        let cache = CLruCache::with_config(
+            CLruCacheConfig::new(max_bytes).with_scale(EntryScale),
+        );
+        Self(Mutex::new(Inner {
+            cache,
+            hits: 0,
+            misses: 0,
+        }))
+    }
+
+    /// Read the value of the key from the cache or, on a miss, with the given
+    /// function and cache the result.
+    pub fn read<E>(
+        &self,
+        key: &Key,
+        read: impl FnOnce() -> Result<Option<Vec<u8>>, E>,
+    ) -> Result<Option<Vec<u8>>, E> {
+        {
+            let mut inner = self.0.lock().unwrap();
+            if let Some(value) = inner.cache.get(key).cloned() {
+                inner.hits += 1;
+                return Ok(value);
+            }
+            inner.misses += 1;
+        }
+        // The lock isn't held while reading from the DB. The keys are only
+        // invalidated on a write, which requires an exclusive access to the
+        // storage, so the read value cannot be outdated.
+        let value = read()?;
+        // An entry that is larger than the cache is not cached
+        let _ = self
+            .0
+            .lock()
+            .unwrap()
+            .cache
+            .put_with_weight(key.clone(), value.clone());
+        Ok(value)
+    }
+
+    /// Remove the cached value of the key, if any. Must be called whenever
+    /// the key is written to the DB.
+    pub fn invalidate(&self, key: &Key) {
+        self.0.lock().unwrap().cache.pop(key);
+    }
+
+    /// Get the usage of the cache and reset its hit and miss counters
+    pub fn take_metrics(&self) -> ReadCacheMetrics {
+        let mut inner = self.0.lock().unwrap();
+        let metrics = ReadCacheMetrics {
+            hits: inner.hits,
+            misses: inner.misses,
+            entries: inner.cache.len(),
+            bytes: inner.cache.weight(),
+        };
+        inner.hits = 0;
+        inner.misses = 0;
+        metrics
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_cache() {
+        let cache = ReadCache::new(NonZeroUsize::new(1024).unwrap());
+        let key = Key::parse("key").unwrap();
+        let read_db =
+            |value: Option<Vec<u8>>| move || -> Result<_, ()> { Ok(value) };
+
+        // A miss reads the DB ...
+        assert_eq!(cache.read(&key, read_db(Some(vec![1]))), Ok(Some(vec![1])));
+        // ... and a hit doesn't
+        assert_eq!(cache.read(&key, read_db(Some(vec![2]))), Ok(Some(vec![1])));
+        // An invalidated key is read from the DB again
+        cache.invalidate(&key);
+        assert_eq!(cache.read(&key, read_db(None)), Ok(None));
+        assert_eq!(cache.read(&key, read_db(Some(vec![3]))), Ok(None));
+        // A failed read isn't cached
+        cache.invalidate(&key);
+        assert_eq!(cache.read(&key, || Err(())), Err(()));
+        assert_eq!(cache.read(&key, read_db(Some(vec![4]))), Ok(Some(vec![4])));
+
+        assert_eq!(
+            cache.take_metrics(),
+            ReadCacheMetrics {
+                hits: 2,
+                misses: 4,
+                entries: 1,
+                bytes: key.len() + 1,
+            }
+        );
+        let metrics = cache.take_metrics();
+        assert_eq!((metrics.hits, metrics.misses), (0, 0));
+    }
+}
@@ -0,0 +1,140 @@
+//! The storage usage of the addresses, i.e. the number of bytes of the
+//! subspace values under the keys whose first segment is the address. The
+//! usage is updated with the committed writes and deletes and it's persisted
+//! in the DB with every block.
+
+use std::collections::{BTreeSet, HashMap};
+
+use namada_core::borsh::BorshDeserialize;
+use namada_core::types::address::Address;
+use namada_core::types::storage::{DbKeySeg, Key};
+use namada_storage::{DBIter, DbError as Error, DbResult as Result, DB};
+
+/// The storage usage of the addresses
+#[derive(Clone, Debug, Default)]
+pub struct StorageUsage {
+    bytes: HashMap<Address, u64>,
+    /// The addresses whose usage changed since it was last written to the DB
+    changed: BTreeSet<Address>,
+}
+
+impl StorageUsage {
+    /// Read the storage usage persisted in the DB
+    pub fn read<D>(db: &D) -> Result<Self>
+    where
+        D: for<'iter> DBIter<'iter>,
+    {
+        let mut bytes = HashMap::new();
+        for (address, value, _gas) in db.iter_storage_usage() {
+            let address = Address::decode(&address)
+                .map_err(|e| Error::DBError(e.to_string()))?;
+            let value =
+                u64::try_from_slice(&value).map_err(Error::BorshCodingError)?;
+            bytes.insert(address, value);
+        }
+        Ok(Self {
+            bytes,
+            changed: BTreeSet::new(),
+        })
+    }
+
+    /// Compute the storage usage from all the subspace values in the DB. The
+    /// computed usage of every address is written to the DB with the next
+    /// [`StorageUsage::batch_write`].
+    pub fn compute<D>(db: &D) -> Result<Self>
+    where
+        D: for<'iter> DBIter<'iter>,
+    {
+        let mut usage = Self::default();
+        for (key, value, _gas) in db.iter_prefix(None) {
+            let key = Key::parse(key).map_err(Error::KeyError)?;
+            usage.update(&key, value.len() as i64);
+        }
+        Ok(usage)
+    }
+
+    /// The number of bytes of the subspace values of the address
+    pub fn get(&self, address: &Address) -> u64 {
+        self.bytes.get(address).copied().unwrap_or_default()
+    }
+
+    /// Whether no address uses any storage
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    /// Apply the size difference of a value written or, if negative, deleted
+    /// under the key. A key that doesn't start with an address isn't
+    /// accounted.
+    pub fn update(&mut self, key: &Key, size_diff: i64) {
+        let Some(DbKeySeg::AddressSeg(address)) = key.first() else {
+            return;
+        };
+        if size_diff == 0 {
+            return;
+        }
+        let bytes = self.bytes.entry(address.clone()).or_default();
+        *bytes = bytes.saturating_add_signed(size_diff);
+        if *bytes == 0 {
+            self.bytes.remove(address);
+        }
+        self.changed.insert(address.clone());
+    }
+
+    /// Write the usage of the addresses that changed to the DB with the batch
+    pub fn batch_write<D: DB>(
+        &mut self,
+        db: &mut D,
+        batch: &mut D::WriteBatch,
+    ) -> Result<()> {
+        for address in std::mem::take(&mut self.changed) {
+            db.batch_write_storage_usage(batch, &address, self.get(&address))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use namada_core::types::address::testing::{
+        established_address_1, established_address_2,
+    };
+    use namada_core::types::storage::KeySeg;
+
+    use super::*;
+    use crate::mockdb::MockDB;
+
+    #[test]
+    fn test_storage_usage() {
+        let mut db = MockDB::default();
+        let owner = established_address_1();
+        let other = established_address_2();
+        let key = |address: &Address, name: &str| {
+            Key::from(address.to_db_key())
+                .push(&name.to_owned())
+                .unwrap()
+        };
+
+        let mut usage = StorageUsage::default();
+        usage.update(&key(&owner, "a"), 10);
+        usage.update(&key(&owner, "b"), 5);
+        usage.update(&key(&other, "a"), 3);
+        usage.update(&Key::parse("no_address").unwrap(), 7);
+        assert_eq!(usage.get(&owner), 15);
+        assert_eq!(usage.get(&other), 3);
+
+        // The usage that dropped to zero is deleted
+        let mut batch = MockDB::batch();
+        usage.batch_write(&mut db, &mut batch).unwrap();
+        db.exec_batch(batch).unwrap();
+        usage.update(&key(&other, "a"), -3);
+        let mut batch = MockDB::batch();
+        usage.batch_write(&mut db, &mut batch).unwrap();
+        db.exec_batch(batch).unwrap();
+
+        let read = StorageUsage::read(&db).unwrap();
+        assert_eq!(read.get(&owner), 15);
+        assert_eq!(read.get(&other), 0);
+        assert_eq!(read.bytes.len(), 1);
+    }
+}
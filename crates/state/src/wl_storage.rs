@@ -189,6 +189,7 @@ where
     /// Commit the current block's write log to the storage and commit the block
     /// to DB. Starts a new block write log.
     pub fn commit_block(&mut self) -> namada_storage::Result<()> {
+        self.storage.write_block_journal().into_storage_result()?;
         if self.storage.last_epoch != self.storage.block.epoch {
             self.storage
                 .update_epoch_in_merkle_tree()
@@ -449,7 +450,6 @@ macro_rules! impl_storage_traits {
                     None => {
                         // when not found in write log, try to read from the storage
                         self.storage()
-                            .db
                             .read_subspace_val(key)
                             .into_storage_result()
                     }
@@ -4,6 +4,7 @@
 use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 
 use itertools::Itertools;
+use namada_core::borsh::BorshSerializeExt;
 use namada_core::ledger::replay_protection;
 use namada_core::types::address::{
     Address, EstablishedAddressGen, InternalAddress,
@@ -11,11 +12,12 @@ use namada_core::types::address::{
 use namada_core::types::hash::{Hash, StorageHasher};
 use namada_core::types::ibc::IbcEvent;
 use namada_core::types::storage;
-use namada_gas::{MEMORY_ACCESS_GAS_PER_BYTE, STORAGE_WRITE_GAS_PER_BYTE};
+use namada_gas::{Gas, MEMORY_ACCESS_GAS_PER_BYTE, STORAGE_WRITE_GAS_PER_BYTE};
 use namada_trans_token::storage_key::{
-    is_any_minted_balance_key, is_any_minter_key, is_any_token_balance_key,
+    is_any_balance_key, is_any_minted_balance_key, is_any_minter_key,
     is_any_token_parameter_key,
 };
+use namada_tx::data::TxEvent;
 use thiserror::Error;
 
 use crate::{DBIter, State, DB};
@@ -68,6 +70,15 @@ pub enum StorageModification {
     },
 }
 
+/// The result of a validity predicate run, cached for the rest of the block
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CachedVpResult {
+    /// Whether the VP accepted the transaction
+    pub accepted: bool,
+    /// The gas consumed by the VP run
+    pub gas_used: Gas,
+}
+
 #[derive(Debug, Clone)]
 /// A replay protection storage modification
 enum ReProtStorageModification {
@@ -100,9 +111,16 @@ pub struct WriteLog {
     tx_precommit_write_log: HashMap<storage::Key, StorageModification>,
     /// The IBC events for the current transaction
     ibc_events: BTreeSet<IbcEvent>,
+    /// The events emitted by the current transaction
+    tx_events: Vec<TxEvent>,
     /// Storage modifications for the replay protection storage, always
     /// committed regardless of the result of the transaction
     replay_protection: HashMap<Hash, ReProtStorageModification>,
+    /// Version of the block write log, bumped on every modification of it
+    block_write_log_version: u64,
+    /// The results of the validity predicates run in the current block,
+    /// indexed by the digest of their inputs
+    vp_results: HashMap<Hash, CachedVpResult>,
 }
 
 /// Write log prefix iterator
@@ -129,7 +147,10 @@ impl Default for WriteLog {
             tx_write_log: HashMap::with_capacity(100),
             tx_precommit_write_log: HashMap::with_capacity(100),
             ibc_events: BTreeSet::new(),
+            tx_events: Vec::new(),
             replay_protection: HashMap::with_capacity(1_000),
+            block_write_log_version: 0,
+            vp_results: HashMap::new(),
         }
     }
 }
@@ -244,6 +265,7 @@ impl WriteLog {
         key: &storage::Key,
         value: Vec<u8>,
     ) -> Result<()> {
+        self.block_write_log_version += 1;
         if let Some(prev) = self
             .block_write_log
             .insert(key.clone(), StorageModification::Write { value })
@@ -336,6 +358,7 @@ impl WriteLog {
         if key.is_validity_predicate().is_some() {
             return Err(Error::DeleteVp);
         }
+        self.block_write_log_version += 1;
         if let Some(prev) = self
             .block_write_log
             .insert(key.clone(), StorageModification::Delete)
@@ -382,6 +405,13 @@ impl WriteLog {
         len as u64 * MEMORY_ACCESS_GAS_PER_BYTE
     }
 
+    /// Set an event emitted by the transaction and return the gas cost.
+    pub fn emit_tx_event(&mut self, event: TxEvent) -> u64 {
+        let len = event.size();
+        self.tx_events.push(event);
+        len as u64 * MEMORY_ACCESS_GAS_PER_BYTE
+    }
+
     /// Get the storage keys changed and accounts keys initialized in the
     /// current transaction. The account keys point to the validity predicates
     /// of the newly created accounts. The keys in the precommit are not
@@ -446,6 +476,87 @@ impl WriteLog {
         &self.ibc_events
     }
 
+    /// Take the events emitted by the current transaction
+    pub fn take_tx_events(&mut self) -> Vec<TxEvent> {
+        std::mem::take(&mut self.tx_events)
+    }
+
+    /// Get the events emitted by the current transaction
+    pub fn get_tx_events(&self) -> &[TxEvent] {
+        &self.tx_events
+    }
+
+    /// Get the size in bytes of the storage modifications in the write log,
+    /// i.e. the amount of memory copied by a clone of the write log
+    pub fn size(&self) -> u64 {
+        [
+            &self.block_write_log,
+            &self.tx_precommit_write_log,
+            &self.tx_write_log,
+        ]
+        .into_iter()
+        .flat_map(|log| log.iter())
+        .map(|(key, modification)| {
+            let value_len = match modification {
+                StorageModification::Write { value }
+                | StorageModification::Temp { value } => value.len(),
+                StorageModification::Delete => 0,
+                StorageModification::InitAccount { vp_code_hash } => {
+                    vp_code_hash.0.len()
+                }
+            };
+            (key.len() + value_len) as u64
+        })
+        .sum()
+    }
+
+    /// Get a digest of the storage modifications in the write log. The block
+    /// write log is only accounted for by its version, so the digest is only
+    /// meaningful within the current block.
+    pub fn digest(&self) -> Hash {
+        let mut bytes = self.block_write_log_version.to_le_bytes().to_vec();
+        for log in [&self.tx_precommit_write_log, &self.tx_write_log] {
+            bytes.extend(log.len().to_le_bytes());
+            for (key, modification) in log.iter().sorted_by_key(|(k, _)| *k) {
+                bytes.extend(key.serialize_to_vec());
+                match modification {
+                    StorageModification::Write { value } => {
+                        bytes.push(0);
+                        bytes.extend(value.serialize_to_vec());
+                    }
+                    StorageModification::Delete => bytes.push(1),
+                    StorageModification::InitAccount { vp_code_hash } => {
+                        bytes.push(2);
+                        bytes.extend(vp_code_hash.0);
+                    }
+                    StorageModification::Temp { value } => {
+                        bytes.push(3);
+                        bytes.extend(value.serialize_to_vec());
+                    }
+                }
+            }
+        }
+        Hash::sha256(bytes)
+    }
+
+    /// Get the cached result of a validity predicate run in the current block
+    /// with the given inputs digest
+    pub fn get_vp_result(
+        &self,
+        inputs_digest: &Hash,
+    ) -> Option<CachedVpResult> {
+        self.vp_results.get(inputs_digest).copied()
+    }
+
+    /// Cache the results of validity predicate runs, indexed by the digest of
+    /// their inputs, for the rest of the current block
+    pub fn cache_vp_results(
+        &mut self,
+        results: impl IntoIterator<Item = (Hash, CachedVpResult)>,
+    ) {
+        self.vp_results.extend(results)
+    }
+
     /// Add the entire content of the tx write log to the precommit one. The tx
     /// log gets reset in the process.
     pub fn precommit_tx(&mut self) {
@@ -473,8 +584,10 @@ impl WriteLog {
             HashMap::with_capacity(100),
         );
 
+        self.block_write_log_version += 1;
         self.block_write_log.extend(tx_precommit_write_log);
         self.take_ibc_events();
+        self.take_tx_events();
     }
 
     /// Drop the current transaction's write log and precommit when it's
@@ -483,6 +596,7 @@ impl WriteLog {
     pub fn drop_tx(&mut self) {
         self.tx_precommit_write_log.clear();
         self.tx_write_log.clear();
+        self.tx_events.clear();
     }
 
     /// Drop the current transaction's write log but keep the precommit one.
@@ -504,39 +618,46 @@ impl WriteLog {
         D: 'static + DB + for<'iter> DBIter<'iter>,
         H: StorageHasher,
     {
-        for (key, entry) in self.block_write_log.iter() {
-            match entry {
+        let modifications: Vec<(&storage::Key, Option<&[u8]>)> = self
+            .block_write_log
+            .iter()
+            .filter_map(|(key, entry)| match entry {
                 StorageModification::Write { value } => {
-                    storage
-                        .batch_write_subspace_val(batch, key, value.clone())
-                        .map_err(Error::StorageError)?;
-                }
-                StorageModification::Delete => {
-                    storage
-                        .batch_delete_subspace_val(batch, key)
-                        .map_err(Error::StorageError)?;
+                    Some((key, Some(value.as_slice())))
                 }
+                StorageModification::Delete => Some((key, None)),
                 StorageModification::InitAccount { vp_code_hash } => {
-                    storage
-                        .batch_write_subspace_val(batch, key, *vp_code_hash)
-                        .map_err(Error::StorageError)?;
+                    Some((key, Some(vp_code_hash.as_ref())))
                 }
                 // temporary value isn't persisted
-                StorageModification::Temp { .. } => {}
-            }
-        }
+                StorageModification::Temp { .. } => None,
+            })
+            .collect();
+        storage
+            .batch_write_subspace_modifications(batch, &modifications)
+            .map_err(Error::StorageError)?;
 
         // Replay protections specifically
+        let height = storage.block.height;
         for (hash, entry) in self.replay_protection.iter() {
             match entry {
-                ReProtStorageModification::Write => storage
-                    .write_replay_protection_entry(
-                        batch,
-                        // Can only write tx hashes to the previous block, no
-                        // further
-                        &replay_protection::last_key(hash),
-                    )
-                    .map_err(Error::StorageError)?,
+                ReProtStorageModification::Write => {
+                    storage
+                        .write_replay_protection_entry(
+                            batch,
+                            // Can only write tx hashes to the previous block,
+                            // no further
+                            &replay_protection::last_key(hash),
+                        )
+                        .map_err(Error::StorageError)?;
+                    // Remember the block of the hash for a rollback
+                    storage
+                        .write_replay_protection_entry(
+                            batch,
+                            &replay_protection::block_key(height, hash),
+                        )
+                        .map_err(Error::StorageError)?
+                }
                 ReProtStorageModification::Delete => storage
                     .delete_replay_protection_entry(
                         batch,
@@ -566,7 +687,9 @@ impl WriteLog {
             storage.address_gen = address_gen
         }
         self.block_write_log.clear();
+        self.block_write_log_version += 1;
         self.replay_protection.clear();
+        self.vp_results.clear();
         Ok(())
     }
 
@@ -591,7 +714,7 @@ impl WriteLog {
             // TODO: this should not be a special case, as it is error prone.
             // any internal addresses corresponding to tokens which have
             // native vp equivalents should be automatically added as verifiers
-            if let Some([token, owner]) = is_any_token_balance_key(key) {
+            if let Some((token, owner)) = is_any_balance_key(key) {
                 if matches!(&token, Address::Internal(InternalAddress::Nut(_)))
                 {
                     verifiers.insert(token.clone());
@@ -605,6 +728,10 @@ impl WriteLog {
             {
                 verifiers
                     .insert(Address::Internal(InternalAddress::Multitoken));
+            } else if key.is_wasm_key() {
+                // the wasm code registry is governed like the parameters
+                verifiers
+                    .insert(Address::Internal(InternalAddress::Parameters));
             } else {
                 for addr in key.iter_addresses() {
                     if verifiers_from_tx.contains(addr)
@@ -955,6 +1082,65 @@ mod tests {
         assert_eq!(value, None);
     }
 
+    #[test]
+    fn test_digest_and_vp_results() {
+        let mut storage = crate::testing::TestStorage::default();
+        let mut write_log = WriteLog::default();
+        let mut batch = crate::testing::TestStorage::batch();
+
+        let key =
+            storage::Key::parse("key").expect("cannot parse the key string");
+        let empty_digest = write_log.digest();
+
+        // the digest depends on the tx modifications
+        write_log.write(&key, vec![1]).unwrap();
+        let digest = write_log.digest();
+        assert_ne!(digest, empty_digest);
+        write_log.write(&key, vec![2]).unwrap();
+        assert_ne!(write_log.digest(), digest);
+        write_log.drop_tx();
+        assert_eq!(write_log.digest(), empty_digest);
+
+        // committing to the block write log changes the digest
+        write_log.write(&key, vec![1]).unwrap();
+        write_log.commit_tx();
+        assert_ne!(write_log.digest(), empty_digest);
+        assert_ne!(write_log.digest(), digest);
+
+        // the VP results are cached until the end of the block
+        let inputs_digest = Hash::sha256("vp inputs".as_bytes());
+        let result = CachedVpResult {
+            accepted: true,
+            gas_used: Gas::from(100),
+        };
+        write_log.cache_vp_results([(inputs_digest, result)]);
+        assert_eq!(write_log.get_vp_result(&inputs_digest), Some(result));
+        write_log.commit_tx();
+        assert_eq!(write_log.get_vp_result(&inputs_digest), Some(result));
+        write_log
+            .commit_block(&mut storage, &mut batch)
+            .expect("commit failed");
+        assert_eq!(write_log.get_vp_result(&inputs_digest), None);
+    }
+
+    #[test]
+    fn test_wasm_registry_verifier() {
+        let mut write_log = WriteLog::default();
+        let code = "code".as_bytes().to_vec();
+        let code_hash = Hash::sha256(&code);
+        write_log
+            .write(&storage::Key::wasm_code(&code_hash), code)
+            .unwrap();
+
+        // the registry is guarded by the parameters VP
+        let (verifiers, _changed_keys) =
+            write_log.verifiers_and_changed_keys(&BTreeSet::new());
+        assert_eq!(
+            verifiers,
+            BTreeSet::from([Address::Internal(InternalAddress::Parameters)])
+        );
+    }
+
     #[test]
     fn test_replay_protection_commit() {
         let mut storage = crate::testing::TestStorage::default();
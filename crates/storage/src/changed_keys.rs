@@ -0,0 +1,92 @@
+//! The filters of the storage keys changed in the blocks.
+//!
+//! A bloom filter of the subspace keys written or deleted in a block is
+//! persisted with the block. It's a few bytes per changed key and it's kept
+//! after the block is pruned, so the blocks that changed a key can be found
+//! without reading the diffs or the events of every block. The filter has no
+//! false negatives and about 1% of false positives.
+
+use namada_core::borsh::{BorshDeserialize, BorshSerialize};
+use namada_core::types::hash::Hash;
+use namada_core::types::storage::Key;
+
+/// The number of bits of a filter per changed key
+const BITS_PER_KEY: usize = 10;
+/// The number of bits set in a filter for every changed key
+const NUM_HASHES: u8 = 7;
+
+/// A bloom filter of the storage keys changed in a block
+#[derive(
+    Clone, Debug, Default, PartialEq, Eq, BorshSerialize, BorshDeserialize,
+)]
+pub struct ChangedKeysFilter {
+    num_hashes: u8,
+    bits: Vec<u8>,
+}
+
+impl ChangedKeysFilter {
+    /// Build the filter of the given changed keys
+    pub fn new<'a>(keys: impl ExactSizeIterator<Item = &'a Key>) -> Self {
+        if keys.len() == 0 {
+            return Self::default();
+        }
+        let num_bytes = (keys.len() * BITS_PER_KEY + 7) / 8;
+        let mut filter = Self {
+            num_hashes: NUM_HASHES,
+            bits: vec![0; num_bytes],
+        };
+        for key in keys {
+            for bit in filter.bit_positions(key) {
+                filter.bits[bit / 8] |= 1 << (bit % 8);
+            }
+        }
+        filter
+    }
+
+    /// Check if the key may have been changed. A `false` is certain, while a
+    /// `true` may be a false positive.
+    pub fn may_contain(&self, key: &Key) -> bool {
+        !self.bits.is_empty()
+            && self
+                .bit_positions(key)
+                .all(|bit| self.bits[bit / 8] & (1 << (bit % 8)) != 0)
+    }
+
+    /// The positions of the bits of the key, derived from its hash by double
+    /// hashing
+    fn bit_positions(&self, key: &Key) -> impl Iterator<Item = usize> {
+        let hash = Hash::sha256(key.to_string());
+        let [h1, h2] = [0, 8].map(|offset| {
+            let mut bytes = [0; 8];
+            bytes.copy_from_slice(&hash.0[offset..offset + 8]);
+            u64::from_le_bytes(bytes)
+        });
+        let num_bits = self.bits.len() as u64 * 8;
+        (0..u64::from(self.num_hashes)).map(move |ix| {
+            (h1.wrapping_add(ix.wrapping_mul(h2 | 1)) % num_bits) as usize
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changed_keys_filter() {
+        let key = |ix: usize| Key::parse(format!("key/{ix}")).unwrap();
+        let changed: Vec<Key> = (0..100).map(key).collect();
+        let filter = ChangedKeysFilter::new(changed.iter());
+        assert!(changed.iter().all(|key| filter.may_contain(key)));
+
+        // The rate of false positives is about 1%
+        let false_positives = (100..10_100)
+            .filter(|ix| filter.may_contain(&key(*ix)))
+            .count();
+        assert!(false_positives < 300, "{false_positives} false positives");
+
+        // An empty filter doesn't contain any key
+        let empty = ChangedKeysFilter::new(std::iter::empty());
+        assert!(!empty.may_contain(&key(0)));
+    }
+}
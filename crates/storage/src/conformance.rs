@@ -0,0 +1,1022 @@
+//! A conformance test suite for the [`DB`] implementations.
+//!
+//! Every check is given a new empty DB instance and asserts a behavior that
+//! the ledger relies upon, regardless of the backend.
+
+use std::cell::Cell;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use namada_core::borsh::BorshDeserialize;
+use namada_core::ledger::replay_protection;
+use namada_core::types::address::testing::{
+    established_address_1, established_address_2, established_address_3,
+};
+use namada_core::types::address::{Address, EstablishedAddressGen};
+use namada_core::types::eth_bridge_pool::{Segments, BRIDGE_POOL_ADDRESS};
+use namada_core::types::ethereum_events::Uint;
+use namada_core::types::hash::{Hash, Sha256Hasher};
+use namada_core::types::keccak::KeccakHash;
+use namada_core::types::storage::{
+    BlockHash, BlockHeight, BlockResults, DbKeySeg, Epoch, Epochs,
+    EthEventsQueue, Header, Key, KeySeg, TxIndex,
+};
+use namada_core::types::time::DateTimeUtc;
+use namada_core::types::token::{Amount, ConversionState};
+use namada_merkle_tree::{MerkleTree, StoreType};
+
+use crate::changed_keys::ChangedKeysFilter;
+use crate::db::{BlockJournal, BlockStateWrite, DBIter, Error, Result, DB};
+use crate::kvdb::{KvDB, KvStore};
+use crate::state_diff::{KeyDiff, StateDiff};
+use crate::tx_queue::TxQueue;
+use crate::value_checksum::ValueChecksum;
+
+/// Run all the conformance checks, each with a new empty DB obtained from
+/// `open_db`.
+pub fn check_all<D>(mut open_db: impl FnMut() -> D)
+where
+    D: DB + for<'iter> DBIter<'iter>,
+{
+    check_block_state(&mut open_db());
+    check_subspace_read_write(&mut open_db());
+    check_subspace_val_with_height(&mut open_db());
+    check_prefix_iter(&mut open_db());
+    check_diffs(&mut open_db());
+    check_prune_block(&mut open_db());
+    check_move_block_to_cold(&mut open_db(), &mut open_db());
+    check_state_diff(&mut open_db());
+    check_replay_protection(&mut open_db());
+    check_block_journal(&mut open_db());
+    check_storage_usage(&mut open_db());
+    check_balance_index(&mut open_db());
+    check_account_history(&mut open_db());
+    check_value_checksum(&mut open_db());
+    check_bridge_pool_signed_nonce(&mut open_db());
+}
+
+/// Check that a committed block state and the filter of its changed keys can
+/// be loaded back and that the Merkle tree stores can be read and pruned.
+pub fn check_block_state<D: DB>(db: &mut D) {
+    assert!(
+        db.read_last_block().unwrap().is_none(),
+        "An empty DB mustn't have a last block"
+    );
+
+    let merkle_tree = MerkleTree::<Sha256Hasher>::default();
+    let height = BlockHeight(10);
+    let epoch = Epoch(1);
+    let mut pred_epochs = Epochs::default();
+    pred_epochs.new_epoch(height);
+    let header = Header {
+        hash: Hash::sha256(b"header"),
+        ..Default::default()
+    };
+    let changed_key = Key::parse("changed").unwrap();
+    let changed_keys = ChangedKeysFilter::new([&changed_key].into_iter());
+
+    let mut batch = D::batch();
+    write_block(
+        db,
+        &mut batch,
+        &merkle_tree,
+        height,
+        epoch,
+        &pred_epochs,
+        &header,
+        &changed_keys,
+    )
+    .unwrap();
+    db.exec_batch(batch).unwrap();
+
+    let state = db
+        .read_last_block()
+        .unwrap()
+        .expect("The last block must have been written");
+    assert_eq!(state.height, height);
+    assert_eq!(state.epoch, epoch);
+    assert_eq!(state.pred_epochs, pred_epochs);
+    assert_eq!(
+        state.merkle_tree_stores.get_root(StoreType::Base),
+        *merkle_tree.stores().root(&StoreType::Base)
+    );
+
+    let read_header = db
+        .read_block_header(height)
+        .unwrap()
+        .expect("The block header must have been written");
+    assert_eq!(read_header.hash, header.hash);
+    assert!(db
+        .read_block_header(height.next_height())
+        .unwrap()
+        .is_none());
+
+    let read_changed_keys = db
+        .read_changed_keys_filter(height)
+        .unwrap()
+        .expect("The filter of the changed keys must have been written");
+    assert_eq!(read_changed_keys, changed_keys);
+    assert!(read_changed_keys.may_contain(&changed_key));
+    assert!(db
+        .read_changed_keys_filter(height.next_height())
+        .unwrap()
+        .is_none());
+
+    assert!(db
+        .read_merkle_tree_stores(epoch, height, None)
+        .unwrap()
+        .is_some());
+    let mut batch = D::batch();
+    db.prune_merkle_tree_store(&mut batch, &StoreType::Account, epoch)
+        .unwrap();
+    db.exec_batch(batch).unwrap();
+    assert!(
+        db.read_merkle_tree_stores(epoch, height, Some(StoreType::Account))
+            .unwrap()
+            .is_none(),
+        "A pruned Merkle tree store mustn't be readable"
+    );
+    assert!(db
+        .read_merkle_tree_stores(epoch, height, Some(StoreType::Base))
+        .unwrap()
+        .is_some());
+}
+
+/// Check the direct and batched writes and deletes of subspace values and
+/// their reported size diffs.
+pub fn check_subspace_read_write<D: DB>(db: &mut D) {
+    let key = Key::parse("direct").unwrap();
+    let batch_key = Key::parse("batch").unwrap();
+    let height = BlockHeight(1);
+
+    assert_eq!(db.read_subspace_val(&key).unwrap(), None);
+
+    let size_diff = db.write_subspace_val(height, &key, [1_u8; 4], true);
+    assert_eq!(size_diff.unwrap(), 4);
+    let size_diff = db.write_subspace_val(height, &key, [2_u8; 2], true);
+    assert_eq!(size_diff.unwrap(), -2);
+    assert_eq!(db.read_subspace_val(&key).unwrap(), Some(vec![2_u8; 2]));
+
+    let mut batch = D::batch();
+    let size_diff = db.batch_write_subspace_val(
+        &mut batch, height, &batch_key, [3_u8; 3], true,
+    );
+    assert_eq!(size_diff.unwrap(), 3);
+    db.exec_batch(batch).unwrap();
+    assert_eq!(
+        db.read_subspace_val(&batch_key).unwrap(),
+        Some(vec![3_u8; 3])
+    );
+
+    let size_diff = db.delete_subspace_val(height, &key, true);
+    assert_eq!(size_diff.unwrap(), 2);
+    assert_eq!(db.read_subspace_val(&key).unwrap(), None);
+    // Deleting a key that's not present is a no-op
+    let size_diff = db.delete_subspace_val(height, &key, true);
+    assert_eq!(size_diff.unwrap(), 0);
+
+    let mut batch = D::batch();
+    let size_diff =
+        db.batch_delete_subspace_val(&mut batch, height, &batch_key, true);
+    assert_eq!(size_diff.unwrap(), 3);
+    db.exec_batch(batch).unwrap();
+    assert_eq!(db.read_subspace_val(&batch_key).unwrap(), None);
+}
+
+/// Check that the subspace values can be read at past heights.
+pub fn check_subspace_val_with_height<D: DB>(db: &mut D) {
+    let key = Key::parse("historic").unwrap();
+    let untouched_key = Key::parse("untouched").unwrap();
+    let height_0 = BlockHeight(100);
+    let height_1 = BlockHeight(111);
+    let height_2 = BlockHeight(222);
+
+    db.write_subspace_val(height_0, &key, [0_u8], true).unwrap();
+    db.write_subspace_val(height_0, &untouched_key, [0_u8], true)
+        .unwrap();
+    db.write_subspace_val(height_1, &key, [1_u8], true).unwrap();
+    db.delete_subspace_val(height_2, &key, true).unwrap();
+
+    let read = |key: &Key, height: BlockHeight| {
+        db.read_subspace_val_with_height(key, height, height_2)
+            .unwrap()
+    };
+    assert_eq!(read(&key, height_0), Some(vec![0_u8]));
+    assert_eq!(read(&key, BlockHeight(105)), Some(vec![0_u8]));
+    assert_eq!(read(&key, height_1), Some(vec![1_u8]));
+    assert_eq!(read(&key, height_2), None);
+    assert_eq!(read(&untouched_key, height_1), Some(vec![0_u8]));
+    assert_eq!(read(&untouched_key, height_2), Some(vec![0_u8]));
+}
+
+/// Check that the subspace prefix iterator yields the matching keys in order.
+pub fn check_prefix_iter<D>(db: &mut D)
+where
+    D: DB + for<'iter> DBIter<'iter>,
+{
+    let prefix_0 = Key::parse("0").unwrap();
+    let prefix_1 = Key::parse("1").unwrap();
+    let prefix_01 = Key::parse("01").unwrap();
+    let keys_0: Vec<Key> = ["a", "b", "c"]
+        .iter()
+        .map(|seg| prefix_0.push(&seg.to_string()).unwrap())
+        .collect();
+    let keys_1: Vec<Key> = ["a", "b", "c"]
+        .iter()
+        .map(|seg| prefix_1.push(&seg.to_string()).unwrap())
+        .collect();
+    let keys_01 = vec![prefix_01.push(&"a".to_string()).unwrap()];
+    let all_keys = [keys_0.clone(), keys_01, keys_1.clone()].concat();
+
+    // Write the keys in reverse order
+    let mut batch = D::batch();
+    for key in all_keys.iter().rev() {
+        db.batch_write_subspace_val(
+            &mut batch,
+            BlockHeight(1),
+            key,
+            key.to_string(),
+            true,
+        )
+        .unwrap();
+    }
+    db.exec_batch(batch).unwrap();
+
+    let iter_keys = |prefix: Option<&Key>| -> Vec<Key> {
+        db.iter_prefix(prefix)
+            .map(|(key, val, _gas)| {
+                assert_eq!(key.as_bytes(), &val[..]);
+                Key::parse(key).unwrap()
+            })
+            .collect()
+    };
+    // Prefix "0" shouldn't match prefix "01"
+    assert_eq!(iter_keys(Some(&prefix_0)), keys_0);
+    assert_eq!(iter_keys(Some(&prefix_1)), keys_1);
+    assert_eq!(iter_keys(None), all_keys);
+    assert_eq!(iter_keys(Some(&Key::default())), all_keys);
+}
+
+/// Check that the subspace diffs are written and pruned.
+pub fn check_diffs<D>(db: &mut D)
+where
+    D: DB + for<'iter> DBIter<'iter>,
+{
+    let key_with_diffs = Key::parse("with_diffs").unwrap();
+    let key_without_diffs = Key::parse("without_diffs").unwrap();
+    let height_0 = BlockHeight::first();
+    let height_1 = height_0 + 10;
+
+    for height in [height_0, height_1] {
+        let val = [height.0 as u8];
+        db.write_subspace_val(height, &key_with_diffs, val, true)
+            .unwrap();
+        db.write_subspace_val(height, &key_without_diffs, val, false)
+            .unwrap();
+    }
+
+    // The diffs of the key with diffs are persisted
+    assert_eq!(
+        db.read_diffs_val(&key_with_diffs, height_0, true).unwrap(),
+        None
+    );
+    assert_eq!(
+        db.read_diffs_val(&key_with_diffs, height_0, false).unwrap(),
+        Some(vec![height_0.0 as u8])
+    );
+    assert_eq!(
+        db.read_diffs_val(&key_with_diffs, height_1, true).unwrap(),
+        Some(vec![height_0.0 as u8])
+    );
+    assert_eq!(
+        db.read_diffs_val(&key_with_diffs, height_1, false).unwrap(),
+        Some(vec![height_1.0 as u8])
+    );
+
+    // Only the last diffs of the key without diffs are kept
+    assert_eq!(
+        db.read_diffs_val(&key_without_diffs, height_0, false)
+            .unwrap(),
+        None
+    );
+    assert_eq!(
+        db.read_diffs_val(&key_without_diffs, height_1, true)
+            .unwrap(),
+        Some(vec![height_0.0 as u8])
+    );
+
+    assert_eq!(
+        iter_keys(db.iter_old_diffs(height_1, None)),
+        vec![key_with_diffs.to_string(), key_without_diffs.to_string()]
+    );
+    assert_eq!(
+        iter_keys(db.iter_new_diffs(height_0, None)),
+        vec![key_with_diffs.to_string()]
+    );
+    assert!(
+        iter_keys(db.iter_new_diffs(height_1, Some(&key_without_diffs)))
+            .is_empty(),
+        "The prefix must only match the sub-keys"
+    );
+}
+
+/// Check that pruning a block removes its results, diffs and base Merkle tree
+/// store, but keeps its header and the data of the other blocks.
+pub fn check_prune_block<D>(db: &mut D)
+where
+    D: DB + for<'iter> DBIter<'iter>,
+{
+    let merkle_tree = MerkleTree::<Sha256Hasher>::default();
+    let key = Key::parse("key").unwrap();
+    let epoch = Epoch(1);
+    let mut pred_epochs = Epochs::default();
+    pred_epochs.new_epoch(BlockHeight::first());
+    let header = Header::default();
+    let pruned_height = BlockHeight::first();
+    let kept_height = pruned_height.next_height();
+
+    for height in [pruned_height, kept_height] {
+        db.write_subspace_val(height, &key, [height.0 as u8], true)
+            .unwrap();
+        let mut batch = D::batch();
+        write_block(
+            db,
+            &mut batch,
+            &merkle_tree,
+            height,
+            epoch,
+            &pred_epochs,
+            &header,
+            &ChangedKeysFilter::default(),
+        )
+        .unwrap();
+        db.exec_batch(batch).unwrap();
+    }
+
+    let mut batch = D::batch();
+    db.prune_block(&mut batch, pruned_height).unwrap();
+    db.exec_batch(batch).unwrap();
+
+    assert_eq!(iter_keys(db.iter_results()), vec![kept_height.raw()]);
+    assert!(iter_keys(db.iter_new_diffs(pruned_height, None)).is_empty());
+    assert_eq!(
+        iter_keys(db.iter_new_diffs(kept_height, None)),
+        vec![key.to_string()]
+    );
+    assert_eq!(
+        iter_keys(db.iter_old_diffs(kept_height, None)),
+        vec![key.to_string()]
+    );
+    assert!(db
+        .read_merkle_tree_stores(epoch, pruned_height, Some(StoreType::Base))
+        .unwrap()
+        .is_none());
+    assert!(db
+        .read_merkle_tree_stores(epoch, kept_height, Some(StoreType::Base))
+        .unwrap()
+        .is_some());
+    assert!(db.read_block_header(pruned_height).unwrap().is_some());
+    assert_eq!(
+        db.read_subspace_val(&key).unwrap(),
+        Some(vec![kept_height.0 as u8])
+    );
+}
+
+/// Check that moving a block to a cold DB moves its results and Merkle tree
+/// stores, but keeps its header, its diffs and the data of the other blocks.
+pub fn check_move_block_to_cold<D>(db: &mut D, cold: &mut D)
+where
+    D: DB + for<'iter> DBIter<'iter>,
+{
+    let merkle_tree = MerkleTree::<Sha256Hasher>::default();
+    let key = Key::parse("key").unwrap();
+    let epoch = Epoch(1);
+    let mut pred_epochs = Epochs::default();
+    pred_epochs.new_epoch(BlockHeight::first());
+    let header = Header::default();
+    let cold_height = BlockHeight::first();
+    let hot_height = cold_height.next_height();
+
+    for height in [cold_height, hot_height] {
+        db.write_subspace_val(height, &key, [height.0 as u8], true)
+            .unwrap();
+        let mut batch = D::batch();
+        write_block(
+            db,
+            &mut batch,
+            &merkle_tree,
+            height,
+            epoch,
+            &pred_epochs,
+            &header,
+            &ChangedKeysFilter::default(),
+        )
+        .unwrap();
+        db.exec_batch(batch).unwrap();
+    }
+
+    let mut batch = D::batch();
+    db.move_block_to_cold(&mut batch, cold, cold_height, Some(epoch))
+        .unwrap();
+    cold.sync().unwrap();
+    db.exec_batch(batch).unwrap();
+
+    assert_eq!(iter_keys(db.iter_results()), vec![hot_height.raw()]);
+    assert_eq!(iter_keys(cold.iter_results()), vec![cold_height.raw()]);
+    for st in StoreType::iter() {
+        assert!(db
+            .read_merkle_tree_stores(epoch, cold_height, Some(*st))
+            .unwrap()
+            .is_none());
+        assert!(cold
+            .read_merkle_tree_stores(epoch, cold_height, Some(*st))
+            .unwrap()
+            .is_some());
+    }
+    assert!(db
+        .read_merkle_tree_stores(epoch, hot_height, Some(StoreType::Base))
+        .unwrap()
+        .is_some());
+    assert!(db.read_block_header(cold_height).unwrap().is_some());
+    assert_eq!(
+        iter_keys(db.iter_new_diffs(cold_height, None)),
+        vec![key.to_string()]
+    );
+}
+
+/// Check that the state diff of a block read from the DB transforms the
+/// subspace of the preceding block into the subspace of the block.
+pub fn check_state_diff<D>(db: &mut D)
+where
+    D: DB + for<'iter> DBIter<'iter>,
+{
+    let created = Key::parse("created").unwrap();
+    let modified = Key::parse("modified").unwrap();
+    let deleted = Key::parse("deleted").unwrap();
+    let height_0 = BlockHeight::first();
+    let height_1 = height_0.next_height();
+
+    db.write_subspace_val(height_0, &modified, [0], true)
+        .unwrap();
+    db.write_subspace_val(height_0, &deleted, [0], true)
+        .unwrap();
+    let mut subspace = iter_subspace(db);
+
+    db.write_subspace_val(height_1, &created, [1], true)
+        .unwrap();
+    db.write_subspace_val(height_1, &modified, [1], true)
+        .unwrap();
+    db.delete_subspace_val(height_1, &deleted, true).unwrap();
+
+    let diff = StateDiff::read(db, height_1);
+    assert_eq!(diff.height, height_1);
+    assert_eq!(
+        diff.changes,
+        BTreeMap::from([
+            (created.to_string(), KeyDiff::Created { value: vec![1] }),
+            (deleted.to_string(), KeyDiff::Deleted { old: vec![0] }),
+            (
+                modified.to_string(),
+                KeyDiff::Modified {
+                    old: vec![0],
+                    new: vec![1]
+                }
+            ),
+        ])
+    );
+
+    diff.apply(&mut subspace).unwrap();
+    assert_eq!(subspace, iter_subspace(db));
+    assert!(
+        diff.apply(&mut subspace).is_err(),
+        "A diff mustn't apply onto a subspace that doesn't precede it"
+    );
+}
+
+/// Check the replay protection entries.
+pub fn check_replay_protection<D>(db: &mut D)
+where
+    D: DB + for<'iter> DBIter<'iter>,
+{
+    let hash = Hash::sha256(b"tx");
+    let other_hash = Hash::sha256(b"other tx");
+    assert!(!db.has_replay_protection_entry(&hash).unwrap());
+
+    let mut batch = D::batch();
+    db.write_replay_protection_entry(
+        &mut batch,
+        &replay_protection::last_key(&hash),
+    )
+    .unwrap();
+    db.write_replay_protection_entry(
+        &mut batch,
+        &replay_protection::all_key(&other_hash),
+    )
+    .unwrap();
+    db.exec_batch(batch).unwrap();
+
+    assert!(db.has_replay_protection_entry(&hash).unwrap());
+    assert!(db.has_replay_protection_entry(&other_hash).unwrap());
+    assert_eq!(
+        iter_keys(db.iter_replay_protection()),
+        vec![hash.to_string()]
+    );
+
+    let mut batch = D::batch();
+    db.delete_replay_protection_entry(
+        &mut batch,
+        &replay_protection::last_key(&hash),
+    )
+    .unwrap();
+    db.exec_batch(batch).unwrap();
+    assert!(!db.has_replay_protection_entry(&hash).unwrap());
+    assert_eq!(db.iter_replay_protection().count(), 0);
+}
+
+/// Check that the data of a block whose commit has been interrupted after its
+/// journal was written can be discarded.
+pub fn check_block_journal<D>(db: &mut D)
+where
+    D: DB + for<'iter> DBIter<'iter>,
+{
+    let merkle_tree = MerkleTree::<Sha256Hasher>::default();
+    let key = Key::parse("journaled").unwrap();
+    let new_key = Key::parse("new").unwrap();
+    let tx_hash = Hash::sha256(b"tx");
+    let height_0 = BlockHeight(1);
+    let height_1 = BlockHeight(2);
+    let mut pred_epochs = Epochs::default();
+    pred_epochs.new_epoch(height_0);
+    let header = Header::default();
+    assert_eq!(db.read_block_journal().unwrap(), None);
+
+    // A committed block
+    let mut batch = D::batch();
+    db.batch_write_subspace_val(&mut batch, height_0, &key, [0_u8], true)
+        .unwrap();
+    write_block(
+        db,
+        &mut batch,
+        &merkle_tree,
+        height_0,
+        Epoch(0),
+        &pred_epochs,
+        &header,
+        &ChangedKeysFilter::default(),
+    )
+    .unwrap();
+    db.exec_batch(batch).unwrap();
+
+    // The commit of the next block is interrupted before its batch is
+    // executed
+    let journal = BlockJournal {
+        height: height_1,
+        hash: BlockHash::default(),
+    };
+    db.write_block_journal(&journal).unwrap();
+    assert_eq!(db.read_block_journal().unwrap(), Some(journal));
+    let mut batch = D::batch();
+    db.batch_write_subspace_val(&mut batch, height_1, &key, [1_u8], true)
+        .unwrap();
+    db.batch_write_subspace_val(&mut batch, height_1, &new_key, [1_u8], true)
+        .unwrap();
+    db.write_replay_protection_entry(
+        &mut batch,
+        &replay_protection::last_key(&tx_hash),
+    )
+    .unwrap();
+    db.write_replay_protection_entry(
+        &mut batch,
+        &replay_protection::block_key(height_1, &tx_hash),
+    )
+    .unwrap();
+    write_block(
+        db,
+        &mut batch,
+        &merkle_tree,
+        height_1,
+        Epoch(0),
+        &pred_epochs,
+        &header,
+        &ChangedKeysFilter::default(),
+    )
+    .unwrap();
+    drop(batch);
+
+    db.discard_block(height_1).unwrap();
+    db.delete_block_journal().unwrap();
+    assert_eq!(db.read_block_journal().unwrap(), None);
+    let state = db
+        .read_last_block()
+        .unwrap()
+        .expect("The last committed block must be kept");
+    assert_eq!(state.height, height_0);
+    assert_eq!(db.read_subspace_val(&key).unwrap(), Some(vec![0_u8]));
+    assert_eq!(db.read_subspace_val(&new_key).unwrap(), None);
+    assert_eq!(db.read_diffs_val(&key, height_1, false).unwrap(), None);
+    assert!(!db.has_replay_protection_entry(&tx_hash).unwrap());
+}
+
+/// A [`KvStore`] that can crash after a given number of writes, after which
+/// all its writes fail until it's restarted. The writes of a [`KvDB`] not
+/// being atomic, this emulates a crash in the middle of a commit.
+#[derive(Debug)]
+pub struct CrashingStore<S> {
+    store: S,
+    writes_left: Cell<Option<usize>>,
+}
+
+impl<S> CrashingStore<S> {
+    /// Wrap the given store
+    pub fn new(store: S) -> Self {
+        Self {
+            store,
+            writes_left: Cell::new(None),
+        }
+    }
+
+    /// Crash after the given number of writes
+    pub fn crash_after(&self, writes: usize) {
+        self.writes_left.set(Some(writes));
+    }
+
+    /// Restart after a crash
+    pub fn restart(&self) {
+        self.writes_left.set(None);
+    }
+
+    fn write(&self) -> Result<()> {
+        match self.writes_left.get() {
+            Some(0) => Err(Error::DBError("The store has crashed".to_owned())),
+            Some(writes) => {
+                self.writes_left.set(Some(writes - 1));
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+impl<S: KvStore> KvStore for CrashingStore<S> {
+    type Cache = S::Cache;
+    type Iter = S::Iter;
+
+    fn open(path: &Path, cache: Option<&Self::Cache>) -> Result<Self> {
+        S::open(path, cache).map(Self::new)
+    }
+
+    fn flush(&self, wait: bool) -> Result<()> {
+        self.store.flush(wait)
+    }
+
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        self.store.get(key)
+    }
+
+    fn insert(&self, key: String, value: Vec<u8>) -> Result<Option<Vec<u8>>> {
+        self.write()?;
+        self.store.insert(key, value)
+    }
+
+    fn remove(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        self.write()?;
+        self.store.remove(key)
+    }
+
+    fn iter_prefix(&self, prefix: &str) -> Self::Iter {
+        self.store.iter_prefix(prefix)
+    }
+
+    fn checkpoint(&self) -> Result<Self> {
+        self.store.checkpoint().map(Self::new)
+    }
+}
+
+/// Check that the subspace values of a block whose commit to a [`KvDB`] has
+/// crashed after any of the writes of a value or of its diffs are restored
+/// when the block is discarded, extending [`check_block_journal`] to the
+/// non-atomic writes.
+pub fn check_block_journal_crash<S: KvStore>(db: &mut KvDB<CrashingStore<S>>) {
+    let merkle_tree = MerkleTree::<Sha256Hasher>::default();
+    let key = Key::parse("journaled").unwrap();
+    let deleted_key = Key::parse("deleted").unwrap();
+    let height_0 = BlockHeight(1);
+    let height_1 = BlockHeight(2);
+    let mut pred_epochs = Epochs::default();
+    pred_epochs.new_epoch(height_0);
+    let header = Header::default();
+
+    // A committed block
+    let mut batch = KvDB::<CrashingStore<S>>::batch();
+    for key in [&key, &deleted_key] {
+        db.batch_write_subspace_val(&mut batch, height_0, key, [0_u8], true)
+            .unwrap();
+    }
+    write_block(
+        db,
+        &mut batch,
+        &merkle_tree,
+        height_0,
+        Epoch(0),
+        &pred_epochs,
+        &header,
+        &ChangedKeysFilter::default(),
+    )
+    .unwrap();
+    db.exec_batch(batch).unwrap();
+
+    // The commit of the next block crashes after each of the writes of the
+    // value and its diffs and of the deletion and its diff
+    let journal = BlockJournal {
+        height: height_1,
+        hash: BlockHash::default(),
+    };
+    for writes in 0..5 {
+        db.write_block_journal(&journal).unwrap();
+        db.store().crash_after(writes);
+        let mut batch = KvDB::<CrashingStore<S>>::batch();
+        let written = db
+            .batch_write_subspace_val(&mut batch, height_1, &key, [1_u8], true)
+            .and_then(|_| {
+                db.batch_delete_subspace_val(
+                    &mut batch,
+                    height_1,
+                    &deleted_key,
+                    true,
+                )
+            });
+        assert!(written.is_err(), "The commit must crash");
+
+        db.store().restart();
+        assert_eq!(db.read_block_journal().unwrap(), Some(journal.clone()));
+        db.discard_block(height_1).unwrap();
+        db.delete_block_journal().unwrap();
+        assert_eq!(db.read_subspace_val(&key).unwrap(), Some(vec![0_u8]));
+        assert_eq!(
+            db.read_subspace_val(&deleted_key).unwrap(),
+            Some(vec![0_u8])
+        );
+        assert_eq!(db.read_diffs_val(&key, height_1, false).unwrap(), None);
+        assert_eq!(
+            db.read_diffs_val(&deleted_key, height_1, true).unwrap(),
+            None
+        );
+    }
+}
+
+/// Check that the storage usage of the addresses is written and that a zero
+/// usage is deleted.
+pub fn check_storage_usage<D>(db: &mut D)
+where
+    D: DB + for<'iter> DBIter<'iter>,
+{
+    let owner = established_address_1();
+    let other = established_address_2();
+    assert_eq!(db.iter_storage_usage().count(), 0);
+
+    let mut batch = D::batch();
+    db.batch_write_storage_usage(&mut batch, &owner, 10)
+        .unwrap();
+    db.batch_write_storage_usage(&mut batch, &other, 20)
+        .unwrap();
+    db.exec_batch(batch).unwrap();
+    let usage = |db: &D| {
+        db.iter_storage_usage()
+            .map(|(address, bytes, _gas)| {
+                (address, u64::try_from_slice(&bytes).unwrap())
+            })
+            .collect::<BTreeMap<_, _>>()
+    };
+    assert_eq!(
+        usage(db),
+        BTreeMap::from([(owner.to_string(), 10), (other.to_string(), 20)])
+    );
+
+    let mut batch = D::batch();
+    db.batch_write_storage_usage(&mut batch, &owner, 0).unwrap();
+    db.exec_batch(batch).unwrap();
+    assert_eq!(usage(db), BTreeMap::from([(other.to_string(), 20)]));
+}
+
+/// Check that the balances of the balance index are written, iterated by the
+/// token and that a zero balance is deleted.
+pub fn check_balance_index<D>(db: &mut D)
+where
+    D: DB + for<'iter> DBIter<'iter>,
+{
+    let token = established_address_1();
+    let other_token = established_address_2();
+    let owner = established_address_3();
+    assert_eq!(db.read_indexed_balance(&token, &owner).unwrap(), None);
+    assert_eq!(db.iter_indexed_balances(None).count(), 0);
+
+    let mut batch = D::batch();
+    for (token, owner, amount) in [
+        (&token, &owner, 10_u64),
+        (&token, &other_token, 20),
+        (&other_token, &owner, 30),
+    ] {
+        db.batch_write_indexed_balance(&mut batch, token, owner, amount.into())
+            .unwrap();
+    }
+    db.exec_batch(batch).unwrap();
+    assert_eq!(
+        db.read_indexed_balance(&token, &owner).unwrap(),
+        Some(Amount::from(10_u64))
+    );
+    let balances = |db: &D, token: Option<&Address>| {
+        db.iter_indexed_balances(token)
+            .map(|(key, amount, _gas)| {
+                (key, Amount::try_from_slice(&amount).unwrap())
+            })
+            .collect::<BTreeMap<_, _>>()
+    };
+    assert_eq!(
+        balances(db, Some(&token)),
+        BTreeMap::from([
+            (owner.to_string(), Amount::from(10_u64)),
+            (other_token.to_string(), Amount::from(20_u64)),
+        ])
+    );
+    assert_eq!(balances(db, None).len(), 3);
+
+    let mut batch = D::batch();
+    db.batch_write_indexed_balance(&mut batch, &token, &owner, Amount::zero())
+        .unwrap();
+    db.exec_batch(batch).unwrap();
+    assert_eq!(db.read_indexed_balance(&token, &owner).unwrap(), None);
+    assert_eq!(
+        balances(db, Some(&token)),
+        BTreeMap::from([(other_token.to_string(), Amount::from(20_u64))])
+    );
+}
+
+/// Check that the txs of the account history are iterated by the address and
+/// ordered by their heights and indices, regardless of the number of digits.
+pub fn check_account_history<D>(db: &mut D)
+where
+    D: DB + for<'iter> DBIter<'iter>,
+{
+    let owner = established_address_1();
+    let other = established_address_2();
+    assert_eq!(db.iter_account_history(&owner).count(), 0);
+
+    let mut batch = D::batch();
+    for (address, height, index) in [
+        (&owner, 10, 0),
+        (&owner, 9, 12),
+        (&owner, 9, 2),
+        (&other, 11, 0),
+    ] {
+        let hash = Hash::sha256([height as u8, index as u8]);
+        db.batch_write_account_tx(
+            &mut batch,
+            address,
+            BlockHeight(height),
+            TxIndex(index),
+            &hash,
+        )
+        .unwrap();
+    }
+    db.exec_batch(batch).unwrap();
+    let hashes = |db: &D, address: &Address| {
+        db.iter_account_history(address)
+            .map(|(_key, hash, _gas)| Hash::try_from_slice(&hash).unwrap())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        hashes(db, &owner),
+        vec![
+            Hash::sha256([9, 2]),
+            Hash::sha256([9, 12]),
+            Hash::sha256([10, 0]),
+        ]
+    );
+    assert_eq!(hashes(db, &other), vec![Hash::sha256([11, 0])]);
+}
+
+/// Check that the checksums of the subspace values are written and deleted
+/// and that a repaired value overwrites the subspace value only.
+pub fn check_value_checksum<D: DB>(db: &mut D) {
+    let key = Key::parse("checksummed").unwrap();
+    let height = BlockHeight(1);
+    assert_eq!(db.read_value_checksum(&key).unwrap(), None);
+
+    let mut batch = D::batch();
+    db.batch_write_subspace_val(&mut batch, height, &key, b"value", true)
+        .unwrap();
+    let checksum = ValueChecksum::new(height, b"value");
+    db.batch_write_value_checksum(&mut batch, &key, Some(&checksum))
+        .unwrap();
+    db.exec_batch(batch).unwrap();
+    assert_eq!(db.read_value_checksum(&key).unwrap(), Some(checksum));
+    assert!(checksum.matches(b"value"));
+    assert!(!checksum.matches(b"other"));
+
+    db.repair_subspace_val(&key, b"repaired").unwrap();
+    assert_eq!(
+        db.read_subspace_val(&key).unwrap(),
+        Some(b"repaired".to_vec())
+    );
+    assert_eq!(
+        db.read_diffs_val(&key, height, false).unwrap(),
+        Some(b"value".to_vec())
+    );
+    assert_eq!(db.read_value_checksum(&key).unwrap(), Some(checksum));
+
+    let mut batch = D::batch();
+    db.batch_write_value_checksum(&mut batch, &key, None)
+        .unwrap();
+    db.exec_batch(batch).unwrap();
+    assert_eq!(db.read_value_checksum(&key).unwrap(), None);
+}
+
+/// Check that the signed nonce of the bridge pool is read from the current
+/// and past values of its root proof.
+pub fn check_bridge_pool_signed_nonce<D: DB>(db: &mut D) {
+    let key = Key {
+        segments: vec![
+            DbKeySeg::AddressSeg(BRIDGE_POOL_ADDRESS),
+            DbKeySeg::StringSeg(Segments::VALUES.signed_root.into()),
+        ],
+    };
+    // The encoding of a root proof without any signatures
+    let root_proof = |nonce: u64| {
+        let signatures: HashMap<u8, u8> = HashMap::new();
+        namada_core::types::encode(&(
+            signatures,
+            (KeccakHash::default(), Uint::from(nonce)),
+        ))
+    };
+    let height_0 = BlockHeight(1);
+    let height_1 = BlockHeight(2);
+
+    assert_eq!(
+        db.read_bridge_pool_signed_nonce(height_0, height_0)
+            .unwrap(),
+        None
+    );
+    db.write_subspace_val(height_0, &key, root_proof(1), true)
+        .unwrap();
+    db.write_subspace_val(height_1, &key, root_proof(2), true)
+        .unwrap();
+    assert_eq!(
+        db.read_bridge_pool_signed_nonce(height_1, height_1)
+            .unwrap(),
+        Some(Uint::from(2))
+    );
+    assert_eq!(
+        db.read_bridge_pool_signed_nonce(height_0, height_1)
+            .unwrap(),
+        Some(Uint::from(1))
+    );
+}
+
+/// Collect the keys yielded by a prefix iterator
+fn iter_keys(
+    iter: impl Iterator<Item = (String, Vec<u8>, u64)>,
+) -> Vec<String> {
+    iter.map(|(key, _val, _gas)| key).collect()
+}
+
+fn iter_subspace<D>(db: &D) -> BTreeMap<String, Vec<u8>>
+where
+    D: DB + for<'iter> DBIter<'iter>,
+{
+    db.iter_prefix(None)
+        .map(|(key, val, _gas)| (key, val))
+        .collect()
+}
+
+/// A helper to write a block
+fn write_block<D: DB>(
+    db: &D,
+    batch: &mut D::WriteBatch,
+    merkle_tree: &MerkleTree<Sha256Hasher>,
+    height: BlockHeight,
+    epoch: Epoch,
+    pred_epochs: &Epochs,
+    header: &Header,
+    changed_keys: &ChangedKeysFilter,
+) -> Result<()> {
+    let block = BlockStateWrite {
+        merkle_tree_stores: merkle_tree.stores(),
+        header: Some(header),
+        hash: &BlockHash::default(),
+        height,
+        time: DateTimeUtc::now(),
+        epoch,
+        pred_epochs,
+        next_epoch_min_start_height: BlockHeight::default(),
+        next_epoch_min_start_time: DateTimeUtc::now(),
+        update_epoch_blocks_delay: None,
+        address_gen: &EstablishedAddressGen::new("whatever"),
+        results: &BlockResults::default(),
+        conversion_state: &ConversionState::default(),
+        tx_queue: &TxQueue::default(),
+        ethereum_height: None,
+        eth_events_queue: &EthEventsQueue::default(),
+        changed_keys,
+    };
+    db.add_block_to_batch(block, batch, true)
+}
@@ -1,13 +1,14 @@
 use std::fmt::Debug;
 
-use namada_core::types::address::EstablishedAddressGen;
+use namada_core::borsh::{BorshDeserialize, BorshSerialize};
+use namada_core::types::address::{Address, EstablishedAddressGen};
 use namada_core::types::hash::{Error as HashError, Hash};
 use namada_core::types::storage::{
     BlockHash, BlockHeight, BlockResults, Epoch, Epochs, EthEventsQueue,
-    Header, Key,
+    Header, Key, TxIndex,
 };
 use namada_core::types::time::DateTimeUtc;
-use namada_core::types::token::ConversionState;
+use namada_core::types::token::{Amount, ConversionState};
 use namada_core::types::{ethereum_events, ethereum_structs};
 use namada_merkle_tree::{
     Error as MerkleTreeError, MerkleTreeStoresRead, MerkleTreeStoresWrite,
@@ -15,7 +16,9 @@ use namada_merkle_tree::{
 };
 use thiserror::Error;
 
+use crate::changed_keys::ChangedKeysFilter;
 use crate::tx_queue::TxQueue;
+use crate::value_checksum::ValueChecksum;
 
 #[allow(missing_docs)]
 #[derive(Error, Debug)]
@@ -38,11 +41,33 @@ pub enum Error {
     NoMerkleTree { height: BlockHeight },
     #[error("Code hash error: {0}")]
     InvalidCodeHash(HashError),
+    #[error(
+        "The value of the key {key} doesn't match its checksum written at the \
+         height {height} and it cannot be repaired"
+    )]
+    CorruptedValue { key: Key, height: BlockHeight },
 }
 
 /// A result of a function that may fail
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// The maximum number of the most recent blocks that can be rolled back. The
+/// data that is only kept to roll the blocks back is pruned once they are
+/// older.
+pub const MAX_ROLLBACK_BLOCKS: u64 = 100;
+
+/// The journal of a block whose commit to the DB has started. It's written
+/// before any data of the block and it's deleted with the last write of the
+/// commit, so if it's found in the DB, the commit has been interrupted, e.g.
+/// by a crash.
+#[derive(Clone, Debug, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct BlockJournal {
+    /// Height of the block
+    pub height: BlockHeight,
+    /// Hash of the block
+    pub hash: BlockHash,
+}
+
 /// The block's state as stored in the database.
 pub struct BlockStateRead {
     /// Merkle tree stores
@@ -113,6 +138,8 @@ pub struct BlockStateWrite<'a> {
     pub ethereum_height: Option<&'a ethereum_structs::BlockHeight>,
     /// The queue of Ethereum events to be processed in order.
     pub eth_events_queue: &'a EthEventsQueue,
+    /// The filter of the subspace keys changed in the block
+    pub changed_keys: &'a ChangedKeysFilter,
 }
 
 /// A database backend.
@@ -121,6 +148,8 @@ pub trait DB: Debug {
     type Cache;
     /// A handle for batch writes
     type WriteBatch: DBWriteBatch;
+    /// A read-only point-in-time copy of the DB
+    type Checkpoint: DBCheckpoint;
 
     /// Open the database from provided path
     fn open(
@@ -131,9 +160,40 @@ pub trait DB: Debug {
     /// Flush data on the memory to persistent them
     fn flush(&self, wait: bool) -> Result<()>;
 
+    /// Sync the writes executed so far to the disk, so that they survive a
+    /// crash of the machine
+    fn sync(&self) -> Result<()>;
+
+    /// Take a read-only point-in-time copy of the DB, which can be read from
+    /// another thread while the blocks keep being committed
+    fn checkpoint(&self) -> Result<Self::Checkpoint>;
+
     /// Read the last committed block's metadata
     fn read_last_block(&self) -> Result<Option<BlockStateRead>>;
 
+    /// Read the journal of the block whose commit has been interrupted, if
+    /// any
+    fn read_block_journal(&self) -> Result<Option<BlockJournal>>;
+
+    /// Write the journal of a block whose commit is starting. It's written
+    /// directly, before any data of the block.
+    fn write_block_journal(&mut self, journal: &BlockJournal) -> Result<()>;
+
+    /// Delete the block journal with the batch, which completes the commit of
+    /// the block. It must be the last write of the commit.
+    fn batch_delete_block_journal(
+        &self,
+        batch: &mut Self::WriteBatch,
+    ) -> Result<()>;
+
+    /// Delete the block journal directly
+    fn delete_block_journal(&mut self) -> Result<()>;
+
+    /// Discard the data of the block at the given height whose commit has
+    /// been interrupted, so that the DB is back at the state of the last
+    /// committed block.
+    fn discard_block(&mut self, height: BlockHeight) -> Result<()>;
+
     /// Write block's metadata. Merkle tree sub-stores are committed only when
     /// `is_full_commit` is `true` (typically on a beginning of a new epoch).
     fn add_block_to_batch(
@@ -146,6 +206,13 @@ pub trait DB: Debug {
     /// Read the block header with the given height from the DB
     fn read_block_header(&self, height: BlockHeight) -> Result<Option<Header>>;
 
+    /// Read the filter of the subspace keys changed in the block with the
+    /// given height, if any
+    fn read_changed_keys_filter(
+        &self,
+        height: BlockHeight,
+    ) -> Result<Option<ChangedKeysFilter>>;
+
     /// Read the merkle tree stores with the given epoch. If a store_type is
     /// given, it reads only the the specified tree. Otherwise, it reads all
     /// trees.
@@ -240,6 +307,29 @@ pub trait DB: Debug {
         pruned_epoch: Epoch,
     ) -> Result<()>;
 
+    /// Prune the data of the block at the given height that is only needed
+    /// to serve queries and proofs at that height: the block results, the
+    /// subspace diffs and the base Merkle tree store. The rest of the block
+    /// state, e.g. the header, is kept.
+    fn prune_block(
+        &mut self,
+        batch: &mut Self::WriteBatch,
+        height: BlockHeight,
+    ) -> Result<()>;
+
+    /// Move the block results and the base Merkle tree store of the block at
+    /// the given height and, if an epoch is given, the Merkle subtree stores
+    /// of the epoch to the `cold` DB. The data is written to the `cold` DB
+    /// right away, but it's only deleted from this DB with the batch, so the
+    /// `cold` DB should be synced before the batch is executed.
+    fn move_block_to_cold(
+        &mut self,
+        batch: &mut Self::WriteBatch,
+        cold: &mut Self,
+        height: BlockHeight,
+        epoch: Option<Epoch>,
+    ) -> Result<()>;
+
     /// Read the signed nonce of Bridge Pool
     fn read_bridge_pool_signed_nonce(
         &self,
@@ -260,6 +350,71 @@ pub trait DB: Debug {
         batch: &mut Self::WriteBatch,
         key: &Key,
     ) -> Result<()>;
+
+    /// Write the storage usage of the address, i.e. the number of bytes of
+    /// the subspace values under its keys. A zero usage is deleted.
+    fn batch_write_storage_usage(
+        &mut self,
+        batch: &mut Self::WriteBatch,
+        address: &Address,
+        bytes: u64,
+    ) -> Result<()>;
+
+    /// Read the balance of the owner of the token from the balance index, if
+    /// any
+    fn read_indexed_balance(
+        &self,
+        token: &Address,
+        owner: &Address,
+    ) -> Result<Option<Amount>>;
+
+    /// Write the balance of the owner of the token to the balance index. A
+    /// zero balance is deleted.
+    fn batch_write_indexed_balance(
+        &mut self,
+        batch: &mut Self::WriteBatch,
+        token: &Address,
+        owner: &Address,
+        amount: Amount,
+    ) -> Result<()>;
+
+    /// Write a tx in which the address appeared to the account history index
+    fn batch_write_account_tx(
+        &mut self,
+        batch: &mut Self::WriteBatch,
+        address: &Address,
+        height: BlockHeight,
+        index: TxIndex,
+        hash: &Hash,
+    ) -> Result<()>;
+
+    /// Read the checksum of the subspace value of the key, if any
+    fn read_value_checksum(&self, key: &Key) -> Result<Option<ValueChecksum>>;
+
+    /// Write the checksum of the subspace value of the key or, if `None`,
+    /// delete it
+    fn batch_write_value_checksum(
+        &self,
+        batch: &mut Self::WriteBatch,
+        key: &Key,
+        checksum: Option<&ValueChecksum>,
+    ) -> Result<()>;
+
+    /// Overwrite a corrupted subspace value of the key with its repaired
+    /// value. The diffs and the checksum of the value are left as they are.
+    fn repair_subspace_val(&self, key: &Key, value: &[u8]) -> Result<()>;
+}
+
+/// A read-only point-in-time copy of a database, e.g. to take a state snapshot
+/// in the background.
+pub trait DBCheckpoint: Send + 'static {
+    /// Read the metadata of the last block committed before the checkpoint
+    fn read_last_block(&self) -> Result<Option<BlockStateRead>>;
+
+    /// Iterate over the key-vals of the storage subspace, ordered by the
+    /// storage keys
+    fn iter_subspace(&self)
+        -> Box<dyn Iterator<Item = (String, Vec<u8>)> + '_>;
 }
 
 /// A database prefix iterator.
@@ -294,6 +449,23 @@ pub trait DBIter<'iter> {
 
     /// Read replay protection storage from the last block
     fn iter_replay_protection(&'iter self) -> Self::PrefixIter;
+
+    /// Read the storage usage of the addresses, keyed by the addresses
+    fn iter_storage_usage(&'iter self) -> Self::PrefixIter;
+
+    /// Read the balances of the balance index of the token, keyed by the
+    /// owners, or, if `None`, of all the tokens, keyed by the tokens and the
+    /// owners
+    fn iter_indexed_balances(
+        &'iter self,
+        token: Option<&Address>,
+    ) -> Self::PrefixIter;
+
+    /// Read the hashes of the txs of the account history index of the
+    /// address, keyed by the heights and the indices of the txs and ordered
+    /// from the oldest
+    fn iter_account_history(&'iter self, address: &Address)
+        -> Self::PrefixIter;
 }
 
 /// Atomic batch write.
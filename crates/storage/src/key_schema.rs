@@ -0,0 +1,442 @@
+//! Storage key schemas. A schema describes a family of storage keys: their
+//! segments, the type of their values and the module that owns them.
+//!
+//! The schemas are declared with the [`key_schemas!`](crate::key_schemas)
+//! macro, which derives for each schema the functions to build a key, to
+//! match a key and to read and write its typed value, so that the keys don't
+//! have to be built and parsed by hand.
+
+use std::fmt;
+
+use namada_core::types::address::Address;
+use namada_core::types::storage::{BlockHeight, Epoch, KeySeg};
+pub use namada_core::types::storage::{DbKeySeg, Key};
+#[doc(hidden)]
+pub use paste::paste as __paste;
+
+/// The schema of a family of storage keys
+#[derive(Clone, Copy)]
+pub struct KeySchema {
+    /// The name of the schema, unique within its owner module
+    pub name: &'static str,
+    /// The path of the module that owns the keys
+    pub owner: &'static str,
+    /// The segments of the keys
+    pub segments: &'static [SegmentSchema],
+    /// The names and types of the variable segments
+    pub vars: &'static [(&'static str, &'static str)],
+    /// The type of the Borsh encoded values, if all the keys have the same
+    /// type of values
+    pub value_type: Option<&'static str>,
+    /// Check if the given key matches the schema
+    pub matches: fn(&Key) -> bool,
+}
+
+impl fmt::Debug for KeySchema {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeySchema")
+            .field("name", &self.name)
+            .field("owner", &self.owner)
+            .field("segments", &self.segments)
+            .field("vars", &self.vars)
+            .field("value_type", &self.value_type)
+            .finish_non_exhaustive()
+    }
+}
+
+impl fmt::Display for KeySchema {
+    /// Formats the segments of the schema, e.g. `#Multitoken/{token}/balance`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (ix, segment) in self.segments.iter().enumerate() {
+            if ix > 0 {
+                f.write_str("/")?;
+            }
+            match segment {
+                SegmentSchema::Fixed(fixed) => f.write_str(fixed)?,
+                SegmentSchema::Var(name) => write!(f, "{{{name}}}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A segment of a [`KeySchema`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SegmentSchema {
+    /// A fixed segment. A string literal is given by its value and any other
+    /// expression as written in the schema.
+    Fixed(&'static str),
+    /// A variable segment with the given name
+    Var(&'static str),
+}
+
+/// The type of a variable segment of a [`KeySchema`]
+pub trait SegmentType {
+    /// The value matched from a key segment, borrowed from the key when the
+    /// segment holds the value as is
+    type Matched<'a>;
+
+    /// Convert the value into a key segment
+    fn to_seg(&self) -> DbKeySeg;
+
+    /// Match the value of a key segment
+    fn match_seg(seg: &DbKeySeg) -> Option<Self::Matched<'_>>;
+}
+
+impl SegmentType for Address {
+    type Matched<'a> = &'a Address;
+
+    fn to_seg(&self) -> DbKeySeg {
+        DbKeySeg::AddressSeg(self.clone())
+    }
+
+    fn match_seg(seg: &DbKeySeg) -> Option<&Address> {
+        match seg {
+            DbKeySeg::AddressSeg(addr) => Some(addr),
+            DbKeySeg::StringSeg(_) => None,
+        }
+    }
+}
+
+impl SegmentType for String {
+    type Matched<'a> = &'a String;
+
+    fn to_seg(&self) -> DbKeySeg {
+        DbKeySeg::StringSeg(self.clone())
+    }
+
+    fn match_seg(seg: &DbKeySeg) -> Option<&String> {
+        match seg {
+            DbKeySeg::StringSeg(string) => Some(string),
+            DbKeySeg::AddressSeg(_) => None,
+        }
+    }
+}
+
+/// Implement [`SegmentType`] for the types that are encoded in a string
+/// segment with [`KeySeg`]. The matched values are parsed from the segment.
+macro_rules! impl_parsed_segment_type {
+    ($($ty:ty),*) => {
+        $(
+            impl SegmentType for $ty {
+                type Matched<'a> = $ty;
+
+                fn to_seg(&self) -> DbKeySeg {
+                    self.to_db_key()
+                }
+
+                fn match_seg(seg: &DbKeySeg) -> Option<$ty> {
+                    match seg {
+                        DbKeySeg::StringSeg(string) => {
+                            <$ty as KeySeg>::parse(string.clone()).ok()
+                        }
+                        DbKeySeg::AddressSeg(_) => None,
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_parsed_segment_type!(u64, Epoch, BlockHeight);
+
+/// The value of a fixed segment of a [`KeySchema`]
+pub trait FixedSegment {
+    /// Convert the value into a key segment
+    fn to_seg(&self) -> DbKeySeg;
+
+    /// Check if a key segment has the value
+    fn matches_seg(&self, seg: &DbKeySeg) -> bool;
+}
+
+impl FixedSegment for &str {
+    fn to_seg(&self) -> DbKeySeg {
+        DbKeySeg::StringSeg(self.to_string())
+    }
+
+    fn matches_seg(&self, seg: &DbKeySeg) -> bool {
+        matches!(seg, DbKeySeg::StringSeg(string) if string == self)
+    }
+}
+
+impl FixedSegment for Address {
+    fn to_seg(&self) -> DbKeySeg {
+        DbKeySeg::AddressSeg(self.clone())
+    }
+
+    fn matches_seg(&self, seg: &DbKeySeg) -> bool {
+        matches!(seg, DbKeySeg::AddressSeg(addr) if addr == self)
+    }
+}
+
+/// Declare the schemas of the storage keys of a module.
+///
+/// Each schema is declared with its name, its variable segments with their
+/// [`SegmentType`]s, optionally the type of its values and its segments. A
+/// segment is either a string literal, a [`FixedSegment`] expression in braces
+/// or the name of a variable segment:
+///
+/// ```rust,ignore
+/// namada_storage::key_schemas! {
+///     /// Obtain a storage key for user's balance.
+///     balance(token: Address, owner: Address) -> Amount =
+///         [{ MULTITOKEN }, token, "balance", owner];
+/// }
+/// ```
+///
+/// For each schema `name`, the macro generates:
+///
+/// - `name_key(..)` to build a key from the variable segments,
+/// - `is_any_name_key(key)` to match a key, returning the variable segments, or
+///   the only variable segment, if it matches,
+/// - `read_name(storage, ..)` and `write_name(storage, .., value)` to read and
+///   write the typed value of a key, if the schema has a value type.
+///
+/// The macro also generates a `KEY_SCHEMAS` constant with the [`KeySchema`]s
+/// of the module, so it must be invoked at most once per module.
+#[macro_export]
+macro_rules! key_schemas {
+    ($(
+        $(#[$attr:meta])*
+        $name:ident ( $($var:ident : $ty:ty),* $(,)? ) $(-> $val:ty)? =
+            [ $($seg:tt),+ $(,)? ];
+    )*) => {
+        $(
+            $crate::__key_schema! {
+                $(#[$attr])*
+                $name ( $($var : $ty),* ) [ $($seg),+ ]
+            }
+            $crate::__key_schema_values! {
+                $name ( $($var : $ty),* ) $($val)?
+            }
+        )*
+
+        $crate::key_schema::__paste! {
+            /// The schemas of the storage keys of this module
+            pub const KEY_SCHEMAS: &[$crate::key_schema::KeySchema] = &[$(
+                $crate::key_schema::KeySchema {
+                    name: stringify!($name),
+                    owner: module_path!(),
+                    segments: &[$($crate::__key_schema_seg!(schema $seg)),+],
+                    vars: &[$((stringify!($var), stringify!($ty))),*],
+                    value_type: $crate::__key_schema_value_type!($($val)?),
+                    matches: |key| [<is_any_ $name _key>](key).is_some(),
+                }
+            ),*];
+        }
+    };
+}
+
+/// Generate the functions to build and match the keys of a schema
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __key_schema {
+    (
+        $(#[$attr:meta])*
+        $name:ident ( $($var:ident : $ty:ty),* ) [ $($seg:tt),+ ]
+    ) => {
+        $crate::key_schema::__paste! {
+            $(#[$attr])*
+            pub fn [<$name _key>]($($var: &$ty),*) -> $crate::key_schema::Key {
+                $crate::key_schema::Key {
+                    segments: vec![$($crate::__key_schema_seg!(to_seg $seg)),+],
+                }
+            }
+
+            #[doc = "Check if the given storage key is a [`" $name "_key`]. If \
+                     it is, return its variable segments."]
+            pub fn [<is_any_ $name _key>](
+                key: &$crate::key_schema::Key,
+            ) -> Option<$crate::__key_schema_matched!(type $($ty),*)> {
+                $(
+                    let $var =
+                        <$ty as $crate::key_schema::SegmentType>::match_seg;
+                )*
+                let mut segments = key.segments.iter();
+                $($crate::__key_schema_seg!(match_seg segments $seg);)+
+                if segments.next().is_some() {
+                    return None;
+                }
+                Some($crate::__key_schema_matched!(value $($var),*))
+            }
+        }
+    };
+}
+
+/// Generate the functions to read and write the values of a schema's keys, if
+/// it has a value type
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __key_schema_values {
+    ($name:ident ( $($var:ident : $ty:ty),* )) => {};
+    ($name:ident ( $($var:ident : $ty:ty),* ) $val:ty) => {
+        $crate::key_schema::__paste! {
+            #[doc = "Read the value of a [`" $name "_key`]."]
+            pub fn [<read_ $name>]<S>(
+                storage: &S,
+                $($var: &$ty,)*
+            ) -> $crate::Result<Option<$val>>
+            where
+                S: $crate::StorageRead,
+            {
+                storage.read(&[<$name _key>]($($var),*))
+            }
+
+            #[doc = "Write the value of a [`" $name "_key`]."]
+            pub fn [<write_ $name>]<S>(
+                storage: &mut S,
+                $($var: &$ty,)*
+                value: $val,
+            ) -> $crate::Result<()>
+            where
+                S: $crate::StorageWrite,
+            {
+                storage.write(&[<$name _key>]($($var),*), value)
+            }
+        }
+    };
+}
+
+/// Handle a segment of a schema
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __key_schema_seg {
+    (to_seg $fixed:literal) => {
+        $crate::key_schema::FixedSegment::to_seg(&$fixed)
+    };
+    (to_seg { $fixed:expr }) => {
+        $crate::key_schema::FixedSegment::to_seg(&$fixed)
+    };
+    (to_seg $var:ident) => {
+        $crate::key_schema::SegmentType::to_seg($var)
+    };
+    (match_seg $segments:ident $fixed:literal) => {
+        if !$crate::key_schema::FixedSegment::matches_seg(
+            &$fixed,
+            $segments.next()?,
+        ) {
+            return None;
+        }
+    };
+    (match_seg $segments:ident { $fixed:expr }) => {
+        if !$crate::key_schema::FixedSegment::matches_seg(
+            &$fixed,
+            $segments.next()?,
+        ) {
+            return None;
+        }
+    };
+    (match_seg $segments:ident $var:ident) => {
+        // Shadows the matching function of the variable segment
+        let $var = $var($segments.next()?)?;
+    };
+    (schema $fixed:literal) => {
+        $crate::key_schema::SegmentSchema::Fixed($fixed)
+    };
+    (schema { $fixed:expr }) => {
+        $crate::key_schema::SegmentSchema::Fixed(stringify!($fixed))
+    };
+    (schema $var:ident) => {
+        $crate::key_schema::SegmentSchema::Var(stringify!($var))
+    };
+}
+
+/// The type or the value matched from the variable segments of a key. A
+/// single variable segment is not wrapped in a tuple.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __key_schema_matched {
+    (type $ty:ty) => {
+        <$ty as $crate::key_schema::SegmentType>::Matched<'_>
+    };
+    (type $($ty:ty),*) => {
+        ($(<$ty as $crate::key_schema::SegmentType>::Matched<'_>),*)
+    };
+    (value $var:ident) => {
+        $var
+    };
+    (value $($var:ident),*) => {
+        ($($var),*)
+    };
+}
+
+/// The value type of a schema, if any
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __key_schema_value_type {
+    () => {
+        None
+    };
+    ($val:ty) => {
+        Some(stringify!($val))
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use namada_core::types::address::testing::{
+        established_address_1, established_address_2,
+    };
+    use namada_core::types::address::MULTITOKEN;
+
+    use super::*;
+    use crate::testing::TestStorage;
+
+    crate::key_schemas! {
+        /// A counter of an address
+        counter(addr: Address) -> u64 = [{ MULTITOKEN }, addr, "counter"];
+        /// A value of an address at an epoch
+        at_epoch(addr: Address, epoch: Epoch, name: String) =
+            [{ MULTITOKEN }, addr, "at_epoch", epoch, name];
+        /// A constant
+        constant() = ["constant"];
+    }
+
+    #[test]
+    fn test_key_schemas() {
+        let addr = established_address_1();
+        let epoch = Epoch(3);
+        let name = "name".to_string();
+
+        let key = counter_key(&addr);
+        assert_eq!(
+            key,
+            Key::from(MULTITOKEN.to_db_key())
+                .with_segment(addr.clone())
+                .with_segment("counter".to_string())
+        );
+        assert_eq!(is_any_counter_key(&key), Some(&addr));
+        assert_eq!(
+            is_any_counter_key(&key.push(&"more".to_string()).unwrap()),
+            None
+        );
+        assert_eq!(is_any_at_epoch_key(&key), None);
+
+        let key = at_epoch_key(&addr, &epoch, &name);
+        assert_eq!(is_any_at_epoch_key(&key), Some((&addr, epoch, &name)));
+        assert_eq!(is_any_counter_key(&key), None);
+        assert_eq!(is_any_constant_key(&constant_key()), Some(()));
+
+        let mut storage = TestStorage::default();
+        let other = established_address_2();
+        write_counter(&mut storage, &addr, 1).unwrap();
+        assert_eq!(read_counter(&storage, &addr).unwrap(), Some(1));
+        assert_eq!(read_counter(&storage, &other).unwrap(), None);
+
+        let names: Vec<_> =
+            KEY_SCHEMAS.iter().map(|schema| schema.name).collect();
+        assert_eq!(names, ["counter", "at_epoch", "constant"]);
+        let schema = &KEY_SCHEMAS[1];
+        assert!((schema.matches)(&key));
+        assert_eq!(schema.value_type, None);
+        assert_eq!(
+            schema.vars,
+            [("addr", "Address"), ("epoch", "Epoch"), ("name", "String")]
+        );
+        assert_eq!(
+            schema.to_string(),
+            "MULTITOKEN/{addr}/at_epoch/{epoch}/{name}"
+        );
+        assert_eq!(KEY_SCHEMAS[0].value_type, Some("u64"));
+    }
+}
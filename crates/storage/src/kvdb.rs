@@ -0,0 +1,1395 @@
+//! A [`DB`] implementation generic over an ordered key-value store. It backs
+//! the in-memory [`MockDB`](crate::mockdb::MockDB) used for testing and it
+//! can be used with alternative persistent key-value stores.
+//!
+//! All the data is kept in a single key space. The current storage tree is:
+//! - `height`, `tx_queue`, ...: the latest ledger state
+//! - `subspace/{dyn}`: accounts sub-spaces
+//! - `results/{h}`: block results at height `h`
+//! - `{h}`: for each block at height `h`:
+//!   - `tree`: merkle tree
+//!   - `hash`, `time`, `epoch`, `pred_epochs`, `address_gen`, `header`: block
+//!     state
+//!   - `new/{dyn}`: subspace value set in block height `h`
+//!   - `old/{dyn}`: subspace value from predecessor block height
+//!   - `changed_keys`: the filter of the subspace keys changed in the block
+//! - `tree/{st}/{epoch}`: merkle subtrees
+//! - `replay_protection`: hashes of processed tx
+//! - `storage_usage/{address}`: the number of bytes of the subspace values of
+//!   the address
+//! - `balance_index/{token}/{owner}`: the latest balances of the token owners
+//! - `account_history/{address}/{h}/{index}`: the hashes of the txs in which
+//!   the address appeared, if indexed
+//! - `checksum/{dyn}`: the checksums of the subspace values, if enabled
+//! - `block_journal`: the journal of a block whose commit has started
+//!   - `{key}`: the latest ledger state before the block
+//!
+//! The writes are applied directly to the store, the write batches are only
+//! here to satisfy the storage interface. Because the commit of a block isn't
+//! atomic, the latest ledger state is saved with the block journal and the
+//! diffs of a subspace value are written before the value itself, to be able
+//! to discard the block if its commit is interrupted. The storage usage
+//! and the balance index written by such a block are discarded altogether to
+//! be computed again.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::path::Path;
+use std::str::FromStr;
+
+use itertools::Either;
+use namada_core::borsh::{BorshDeserialize, BorshSerializeExt};
+use namada_core::ledger::replay_protection;
+use namada_core::types;
+use namada_core::types::address::Address;
+use namada_core::types::eth_bridge_pool::{Segments, BRIDGE_POOL_ADDRESS};
+use namada_core::types::hash::Hash;
+use namada_core::types::storage::{
+    BlockHeight, BlockResults, DbKeySeg, Epoch, EthEventsQueue, Header, Key,
+    KeySeg, TxIndex, KEY_SEGMENT_SEPARATOR,
+};
+use namada_core::types::time::DateTimeUtc;
+use namada_core::types::token::{Amount, ConversionState};
+use namada_core::types::{ethereum_events, ethereum_structs};
+use namada_merkle_tree::{
+    base_tree_key_prefix, subtree_key_prefix, MerkleTreeStoresRead, StoreType,
+};
+
+use crate::changed_keys::ChangedKeysFilter;
+use crate::db::{
+    BlockJournal, BlockStateRead, BlockStateWrite, DBCheckpoint, DBIter,
+    DBWriteBatch, Error, Result, DB, MAX_ROLLBACK_BLOCKS,
+};
+use crate::tx_queue::TxQueue;
+use crate::types::{KVBytes, PrefixIterator};
+use crate::value_checksum::ValueChecksum;
+
+const SUBSPACE_PREFIX: &str = "subspace";
+const REPLAY_PROTECTION_PREFIX: &str = "replay_protection";
+const BLOCK_JOURNAL_KEY: &str = "block_journal";
+const STORAGE_USAGE_PREFIX: &str = "storage_usage";
+const BALANCE_INDEX_PREFIX: &str = "balance_index";
+const ACCOUNT_HISTORY_PREFIX: &str = "account_history";
+const VALUE_CHECKSUM_PREFIX: &str = "checksum";
+
+/// The keys of the latest ledger state, which are overwritten by the commit
+/// of every block
+const STATE_KEYS: [&str; 8] = [
+    "height",
+    "next_epoch_min_start_height",
+    "next_epoch_min_start_time",
+    "update_epoch_blocks_delay",
+    "conversion_state",
+    "tx_queue",
+    "ethereum_height",
+    "eth_events_queue",
+];
+
+const OLD_DIFF_PREFIX: &str = "old";
+const NEW_DIFF_PREFIX: &str = "new";
+
+/// An ordered key-value store that can back a [`KvDB`].
+pub trait KvStore: Debug + Sized + Send + 'static {
+    /// Cache that can be shared with the store
+    type Cache;
+    /// The iterator over the key-vals of the store, ordered by the keys
+    type Iter: Debug + Iterator<Item = Result<KVBytes>>;
+
+    /// Open the store at the given path or create it if it doesn't exist
+    fn open(path: &Path, cache: Option<&Self::Cache>) -> Result<Self>;
+
+    /// Flush the writes to the underlying storage. When `wait` is `true`, this
+    /// blocks until the data is persisted.
+    fn flush(&self, wait: bool) -> Result<()>;
+
+    /// Read the value of the given key
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>>;
+
+    /// Write the value of the given key. Returns the previous value, if any.
+    fn insert(&self, key: String, value: Vec<u8>) -> Result<Option<Vec<u8>>>;
+
+    /// Delete the given key. Returns the previous value, if any.
+    fn remove(&self, key: &str) -> Result<Option<Vec<u8>>>;
+
+    /// Iterate over the key-vals whose key starts with the given prefix
+    fn iter_prefix(&self, prefix: &str) -> Self::Iter;
+
+    /// Copy the store at its current state into a new store that isn't
+    /// affected by the subsequent writes
+    fn checkpoint(&self) -> Result<Self>;
+}
+
+/// A DB backed by a [`KvStore`].
+#[derive(Debug, Default)]
+pub struct KvDB<S>(S);
+
+/// A [`KvDB`] write batch is not needed as the values are written directly to
+/// the store. It's here to satisfy the storage interface.
+#[derive(Debug, Default)]
+pub struct KvDBWriteBatch;
+
+/// A prefix iterator for the [`KvDB`].
+pub type KvPrefixIterator<S> = PrefixIterator<<S as KvStore>::Iter>;
+
+impl<S: KvStore> KvDB<S> {
+    /// Wrap the given store
+    pub fn new(store: S) -> Self {
+        Self(store)
+    }
+
+    /// Get the underlying store
+    pub fn store(&self) -> &S {
+        &self.0
+    }
+
+    /// Persist the diff of an account subspace key-val under the height where
+    /// it was changed.
+    fn write_subspace_diff(
+        &self,
+        height: BlockHeight,
+        key: &Key,
+        old_value: Option<&[u8]>,
+        new_value: Option<&[u8]>,
+        persist_diffs: bool,
+    ) -> Result<()> {
+        let (old_val_key, new_val_key) = old_and_new_diff_key(key, height)?;
+
+        if let Some(old_value) = old_value {
+            self.0.insert(old_val_key, old_value.to_vec())?;
+        }
+
+        if let Some(new_value) = new_value {
+            self.0.insert(new_val_key, new_value.to_vec())?;
+        }
+
+        // If not persisting the diffs, remove the last diffs.
+        if !persist_diffs && height > BlockHeight::first() {
+            let mut height = height.prev_height();
+            while height >= BlockHeight::first() {
+                let (old_diff_key, new_diff_key) =
+                    old_and_new_diff_key(key, height)?;
+                let has_old_diff = self.0.remove(&old_diff_key)?.is_some();
+                let has_new_diff = self.0.remove(&new_diff_key)?.is_some();
+                if has_old_diff || has_new_diff {
+                    break;
+                }
+                height = height.prev_height();
+            }
+        }
+        Ok(())
+    }
+
+    fn prefix_iter(
+        &self,
+        prefix: &str,
+        stripped_prefix: String,
+    ) -> KvPrefixIterator<S> {
+        PrefixIterator::new(self.0.iter_prefix(prefix), stripped_prefix)
+    }
+
+    /// Remove all the keys with the given prefix
+    fn remove_prefix(&self, prefix: &str) -> Result<()> {
+        let keys = self
+            .0
+            .iter_prefix(prefix)
+            .map(|result| result.map(|(key, _val)| key))
+            .collect::<Result<Vec<_>>>()?;
+        for key in keys {
+            let key = String::from_utf8(key.to_vec())
+                .map_err(|e| Error::DBError(e.to_string()))?;
+            self.0.remove(&key)?;
+        }
+        Ok(())
+    }
+
+    /// The prefix of the hashes of the txs included in the block at the given
+    /// height
+    fn replay_protection_block_prefix(height: BlockHeight) -> String {
+        format!(
+            "{REPLAY_PROTECTION_PREFIX}/{}/",
+            replay_protection::block_prefix(height)
+        )
+    }
+}
+
+impl<S: KvStore> DB for KvDB<S> {
+    type Cache = S::Cache;
+    type Checkpoint = Self;
+    type WriteBatch = KvDBWriteBatch;
+
+    fn open(db_path: impl AsRef<Path>, cache: Option<&Self::Cache>) -> Self {
+        Self(S::open(db_path.as_ref(), cache).expect("cannot open the DB"))
+    }
+
+    fn flush(&self, wait: bool) -> Result<()> {
+        self.0.flush(wait)
+    }
+
+    fn sync(&self) -> Result<()> {
+        self.0.flush(true)
+    }
+
+    fn checkpoint(&self) -> Result<Self> {
+        self.0.checkpoint().map(Self)
+    }
+
+    fn read_last_block(&self) -> Result<Option<BlockStateRead>> {
+        // Block height
+        let height: BlockHeight = match self.0.get("height")? {
+            Some(bytes) => types::decode(bytes).map_err(Error::CodingError)?,
+            None => return Ok(None),
+        };
+        // Block results
+        let results_path = format!("results/{}", height.raw());
+        let results: BlockResults = match self.0.get(&results_path)? {
+            Some(bytes) => types::decode(bytes).map_err(Error::CodingError)?,
+            None => return Ok(None),
+        };
+
+        // Epoch start height and time
+        let next_epoch_min_start_height: BlockHeight = match self
+            .0
+            .get("next_epoch_min_start_height")?
+        {
+            Some(bytes) => types::decode(bytes).map_err(Error::CodingError)?,
+            None => return Ok(None),
+        };
+        let next_epoch_min_start_time: DateTimeUtc = match self
+            .0
+            .get("next_epoch_min_start_time")?
+        {
+            Some(bytes) => types::decode(bytes).map_err(Error::CodingError)?,
+            None => return Ok(None),
+        };
+        let update_epoch_blocks_delay: Option<u32> = match self
+            .0
+            .get("update_epoch_blocks_delay")?
+        {
+            Some(bytes) => types::decode(bytes).map_err(Error::CodingError)?,
+            None => return Ok(None),
+        };
+        let conversion_state: ConversionState = match self
+            .0
+            .get("conversion_state")?
+        {
+            Some(bytes) => types::decode(bytes).map_err(Error::CodingError)?,
+            None => return Ok(None),
+        };
+        let tx_queue: TxQueue = match self.0.get("tx_queue")? {
+            Some(bytes) => types::decode(bytes).map_err(Error::CodingError)?,
+            None => return Ok(None),
+        };
+
+        let ethereum_height: Option<ethereum_structs::BlockHeight> = match self
+            .0
+            .get("ethereum_height")?
+        {
+            Some(bytes) => types::decode(bytes).map_err(Error::CodingError)?,
+            None => return Ok(None),
+        };
+
+        let eth_events_queue: EthEventsQueue = match self
+            .0
+            .get("eth_events_queue")?
+        {
+            Some(bytes) => types::decode(bytes).map_err(Error::CodingError)?,
+            None => return Ok(None),
+        };
+
+        // Load data at the height
+        let prefix = format!("{}/", height.raw());
+        let mut merkle_tree_stores = MerkleTreeStoresRead::default();
+        let mut hash = None;
+        let mut time = None;
+        let mut epoch: Option<Epoch> = None;
+        let mut pred_epochs = None;
+        let mut address_gen = None;
+        for entry in self.0.iter_prefix(&prefix) {
+            let (path, bytes) = entry?;
+            let path = String::from_utf8(path.to_vec())
+                .map_err(|e| Error::DBError(e.to_string()))?;
+            let segments: Vec<&str> =
+                path.split(KEY_SEGMENT_SEPARATOR).collect();
+            match segments.get(1) {
+                Some(prefix) => match *prefix {
+                    "tree" => match segments.get(2) {
+                        Some(s) => {
+                            let st = StoreType::from_str(s)?;
+                            match segments.get(3) {
+                                Some(&"root") => merkle_tree_stores.set_root(
+                                    &st,
+                                    types::decode(bytes)
+                                        .map_err(Error::CodingError)?,
+                                ),
+                                Some(&"store") => merkle_tree_stores
+                                    .set_store(st.decode_store(bytes)?),
+                                _ => unknown_key_error(&path)?,
+                            }
+                        }
+                        None => unknown_key_error(&path)?,
+                    },
+                    "header" => {
+                        // the block header doesn't have to be restored
+                    }
+                    OLD_DIFF_PREFIX | NEW_DIFF_PREFIX => {
+                        // the subspace diffs don't have to be restored
+                    }
+                    "changed_keys" => {
+                        // the filter of the changed keys is only read by
+                        // queries
+                    }
+                    "hash" => {
+                        hash = Some(
+                            types::decode(bytes).map_err(Error::CodingError)?,
+                        )
+                    }
+                    "time" => {
+                        time = Some(
+                            types::decode(bytes).map_err(Error::CodingError)?,
+                        )
+                    }
+                    "epoch" => {
+                        epoch = Some(
+                            types::decode(bytes).map_err(Error::CodingError)?,
+                        )
+                    }
+                    "pred_epochs" => {
+                        pred_epochs = Some(
+                            types::decode(bytes).map_err(Error::CodingError)?,
+                        )
+                    }
+                    "address_gen" => {
+                        address_gen = Some(
+                            types::decode(bytes).map_err(Error::CodingError)?,
+                        );
+                    }
+                    _ => unknown_key_error(&path)?,
+                },
+                None => unknown_key_error(&path)?,
+            }
+        }
+        // Restore subtrees of Merkle tree
+        if let Some(epoch) = epoch {
+            for st in StoreType::iter_subtrees() {
+                let prefix_key = subtree_key_prefix(st, epoch);
+                let root_key =
+                    prefix_key.clone().with_segment("root".to_owned());
+                if let Some(bytes) = self.0.get(&root_key.to_string())? {
+                    merkle_tree_stores.set_root(
+                        st,
+                        types::decode(bytes).map_err(Error::CodingError)?,
+                    );
+                }
+                let store_key = prefix_key.with_segment("store".to_owned());
+                if let Some(bytes) = self.0.get(&store_key.to_string())? {
+                    merkle_tree_stores.set_store(st.decode_store(bytes)?);
+                }
+            }
+        }
+        match (hash, time, epoch, pred_epochs, address_gen) {
+            (
+                Some(hash),
+                Some(time),
+                Some(epoch),
+                Some(pred_epochs),
+                Some(address_gen),
+            ) => Ok(Some(BlockStateRead {
+                merkle_tree_stores,
+                hash,
+                height,
+                time,
+                epoch,
+                pred_epochs,
+                next_epoch_min_start_height,
+                next_epoch_min_start_time,
+                update_epoch_blocks_delay,
+                address_gen,
+                results,
+                conversion_state,
+                tx_queue,
+                ethereum_height,
+                eth_events_queue,
+            })),
+            _ => Err(Error::Temporary {
+                error: "Essential data couldn't be read from the DB"
+                    .to_string(),
+            }),
+        }
+    }
+
+    fn add_block_to_batch(
+        &self,
+        state: BlockStateWrite,
+        _batch: &mut Self::WriteBatch,
+        is_full_commit: bool,
+    ) -> Result<()> {
+        let BlockStateWrite {
+            merkle_tree_stores,
+            header,
+            hash,
+            time,
+            height,
+            epoch,
+            pred_epochs,
+            next_epoch_min_start_height,
+            next_epoch_min_start_time,
+            update_epoch_blocks_delay,
+            address_gen,
+            results,
+            conversion_state,
+            ethereum_height,
+            eth_events_queue,
+            tx_queue,
+            changed_keys,
+        }: BlockStateWrite = state;
+
+        // Epoch start height and time
+        self.0.insert(
+            "next_epoch_min_start_height".into(),
+            types::encode(&next_epoch_min_start_height),
+        )?;
+        self.0.insert(
+            "next_epoch_min_start_time".into(),
+            types::encode(&next_epoch_min_start_time),
+        )?;
+        self.0.insert(
+            "update_epoch_blocks_delay".into(),
+            types::encode(&update_epoch_blocks_delay),
+        )?;
+        self.0.insert(
+            "ethereum_height".into(),
+            types::encode(&ethereum_height),
+        )?;
+        self.0.insert(
+            "eth_events_queue".into(),
+            types::encode(&eth_events_queue),
+        )?;
+        self.0.insert("tx_queue".into(), types::encode(&tx_queue))?;
+        self.0.insert(
+            "conversion_state".into(),
+            types::encode(conversion_state),
+        )?;
+
+        let prefix_key = Key::from(height.to_db_key());
+        // Merkle tree
+        {
+            for st in StoreType::iter() {
+                if *st == StoreType::Base || is_full_commit {
+                    let key_prefix = if *st == StoreType::Base {
+                        base_tree_key_prefix(height)
+                    } else {
+                        subtree_key_prefix(st, epoch)
+                    };
+                    let root_key =
+                        key_prefix.clone().with_segment("root".to_owned());
+                    self.0.insert(
+                        root_key.to_string(),
+                        types::encode(merkle_tree_stores.root(st)),
+                    )?;
+                    let store_key = key_prefix.with_segment("store".to_owned());
+                    self.0.insert(
+                        store_key.to_string(),
+                        merkle_tree_stores.store(st).encode(),
+                    )?;
+                }
+            }
+        }
+        // Block header
+        {
+            if let Some(h) = header {
+                let key = prefix_key
+                    .push(&"header".to_owned())
+                    .map_err(Error::KeyError)?;
+                self.0.insert(key.to_string(), h.serialize_to_vec())?;
+            }
+        }
+        // Block hash
+        {
+            let key = prefix_key
+                .push(&"hash".to_owned())
+                .map_err(Error::KeyError)?;
+            self.0.insert(key.to_string(), types::encode(&hash))?;
+        }
+        // Block time
+        {
+            let key = prefix_key
+                .push(&"time".to_owned())
+                .map_err(Error::KeyError)?;
+            self.0.insert(key.to_string(), types::encode(&time))?;
+        }
+        // Block epoch
+        {
+            let key = prefix_key
+                .push(&"epoch".to_owned())
+                .map_err(Error::KeyError)?;
+            self.0.insert(key.to_string(), types::encode(&epoch))?;
+        }
+        // Predecessor block epochs
+        {
+            let key = prefix_key
+                .push(&"pred_epochs".to_owned())
+                .map_err(Error::KeyError)?;
+            self.0
+                .insert(key.to_string(), types::encode(&pred_epochs))?;
+        }
+        // Address gen
+        {
+            let key = prefix_key
+                .push(&"address_gen".to_owned())
+                .map_err(Error::KeyError)?;
+            let value = &address_gen;
+            self.0.insert(key.to_string(), types::encode(value))?;
+        }
+        // Changed keys
+        {
+            let key = prefix_key
+                .push(&"changed_keys".to_owned())
+                .map_err(Error::KeyError)?;
+            self.0
+                .insert(key.to_string(), types::encode(changed_keys))?;
+        }
+        self.0.insert("height".to_owned(), types::encode(&height))?;
+        // Block results
+        {
+            let results_path = format!("results/{}", height.raw());
+            self.0.insert(results_path, types::encode(&results))?;
+        }
+        // The tx hashes of the block that is too old to be rolled back
+        if let Some(pruned_height) = height
+            .0
+            .checked_sub(MAX_ROLLBACK_BLOCKS)
+            .filter(|height| *height > 0)
+        {
+            self.remove_prefix(&Self::replay_protection_block_prefix(
+                BlockHeight(pruned_height),
+            ))?;
+        }
+        Ok(())
+    }
+
+    fn read_block_journal(&self) -> Result<Option<BlockJournal>> {
+        match self.0.get(BLOCK_JOURNAL_KEY)? {
+            Some(bytes) => {
+                Ok(Some(types::decode(bytes).map_err(Error::CodingError)?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn write_block_journal(&mut self, journal: &BlockJournal) -> Result<()> {
+        // Save the latest ledger state, which is overwritten by the commit
+        for key in STATE_KEYS {
+            let journal_key = format!("{BLOCK_JOURNAL_KEY}/{key}");
+            match self.0.get(key)? {
+                Some(value) => self.0.insert(journal_key, value)?,
+                None => self.0.remove(&journal_key)?,
+            };
+        }
+        self.0
+            .insert(BLOCK_JOURNAL_KEY.to_owned(), types::encode(journal))?;
+        Ok(())
+    }
+
+    fn batch_delete_block_journal(
+        &self,
+        _batch: &mut Self::WriteBatch,
+    ) -> Result<()> {
+        self.0.remove(BLOCK_JOURNAL_KEY)?;
+        Ok(())
+    }
+
+    fn delete_block_journal(&mut self) -> Result<()> {
+        self.0.remove(BLOCK_JOURNAL_KEY)?;
+        Ok(())
+    }
+
+    fn discard_block(&mut self, height: BlockHeight) -> Result<()> {
+        // Restore the subspace values changed in the block from its diffs
+        let mut restored = BTreeMap::new();
+        for (key, _val, _gas) in self.iter_new_diffs(height, None) {
+            restored.insert(key, None);
+        }
+        for (key, val, _gas) in self.iter_old_diffs(height, None) {
+            restored.insert(key, Some(val));
+        }
+        for (key, val) in restored {
+            let key = Key::parse(key).map_err(Error::KeyError)?;
+            // The checksum of a restored value isn't known
+            self.0.remove(&value_checksum_key(&key))?;
+            let key = subspace_key(&key)?;
+            match val {
+                Some(val) => self.0.insert(key, val)?,
+                None => self.0.remove(&key)?,
+            };
+        }
+        // Remove the diffs and the state of the block
+        self.remove_prefix(&format!("{}/", height.raw()))?;
+        self.0.remove(&format!("results/{}", height.raw()))?;
+        // Restore the latest ledger state saved with the journal
+        for key in STATE_KEYS {
+            match self.0.get(&format!("{BLOCK_JOURNAL_KEY}/{key}"))? {
+                Some(value) => self.0.insert(key.to_owned(), value)?,
+                None => self.0.remove(key)?,
+            };
+        }
+        // Remove the tx hashes included in the block
+        let prefix = Self::replay_protection_block_prefix(height);
+        let hashes = self
+            .prefix_iter(&prefix, prefix.clone())
+            .map(|(hash, _val, _gas)| hash)
+            .collect::<Vec<_>>();
+        for hash in hashes {
+            self.0.remove(&format!(
+                "{REPLAY_PROTECTION_PREFIX}/{}/{hash}",
+                replay_protection::last_prefix()
+            ))?;
+        }
+        self.remove_prefix(&prefix)?;
+        // Remove the txs of the block from the account history
+        let suffix = format!("/{:020}/", height.0);
+        let txs = self
+            .prefix_iter(&format!("{ACCOUNT_HISTORY_PREFIX}/"), String::new())
+            .filter_map(|(key, _val, _gas)| {
+                key.contains(&suffix).then_some(key)
+            })
+            .collect::<Vec<_>>();
+        for key in txs {
+            self.0.remove(&key)?;
+        }
+        // The storage usage and the balance index are computed again from
+        // the restored subspace
+        self.remove_prefix(&format!("{STORAGE_USAGE_PREFIX}/"))?;
+        self.remove_prefix(&format!("{BALANCE_INDEX_PREFIX}/"))
+    }
+
+    fn read_block_header(&self, height: BlockHeight) -> Result<Option<Header>> {
+        let prefix_key = Key::from(height.to_db_key());
+        let key = prefix_key
+            .push(&"header".to_owned())
+            .map_err(Error::KeyError)?;
+        match self.0.get(&key.to_string())? {
+            Some(v) => Ok(Some(
+                BorshDeserialize::try_from_slice(&v[..])
+                    .map_err(Error::BorshCodingError)?,
+            )),
+            None => Ok(None),
+        }
+    }
+
+    fn read_changed_keys_filter(
+        &self,
+        height: BlockHeight,
+    ) -> Result<Option<ChangedKeysFilter>> {
+        let key = Key::from(height.to_db_key())
+            .push(&"changed_keys".to_owned())
+            .map_err(Error::KeyError)?;
+        match self.0.get(&key.to_string())? {
+            Some(bytes) => {
+                Ok(Some(types::decode(bytes).map_err(Error::CodingError)?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn read_merkle_tree_stores(
+        &self,
+        epoch: Epoch,
+        base_height: BlockHeight,
+        store_type: Option<StoreType>,
+    ) -> Result<Option<MerkleTreeStoresRead>> {
+        let mut merkle_tree_stores = MerkleTreeStoresRead::default();
+        let store_types = store_type
+            .as_ref()
+            .map(|st| Either::Left(std::iter::once(st)))
+            .unwrap_or_else(|| Either::Right(StoreType::iter()));
+        for st in store_types {
+            let key_prefix = if *st == StoreType::Base {
+                base_tree_key_prefix(base_height)
+            } else {
+                subtree_key_prefix(st, epoch)
+            };
+            let root_key = key_prefix.clone().with_segment("root".to_owned());
+            match self.0.get(&root_key.to_string())? {
+                Some(b) => {
+                    let root = types::decode(b).map_err(Error::CodingError)?;
+                    merkle_tree_stores.set_root(st, root);
+                }
+                None => return Ok(None),
+            }
+
+            let store_key = key_prefix.with_segment("store".to_owned());
+            match self.0.get(&store_key.to_string())? {
+                Some(b) => {
+                    merkle_tree_stores.set_store(st.decode_store(b)?);
+                }
+                None => return Ok(None),
+            }
+        }
+        Ok(Some(merkle_tree_stores))
+    }
+
+    fn has_replay_protection_entry(&self, hash: &Hash) -> Result<bool> {
+        let prefix_key =
+            Key::parse(REPLAY_PROTECTION_PREFIX).map_err(Error::KeyError)?;
+        for subkey in [
+            replay_protection::last_key(hash),
+            replay_protection::all_key(hash),
+        ] {
+            let key = prefix_key.join(&subkey);
+            if self.0.get(&key.to_string())?.is_some() {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
+    fn read_diffs_val(
+        &self,
+        key: &Key,
+        height: BlockHeight,
+        is_old: bool,
+    ) -> Result<Option<Vec<u8>>> {
+        let (old_val_key, new_val_key) = old_and_new_diff_key(key, height)?;
+        self.0.get(if is_old { &old_val_key } else { &new_val_key })
+    }
+
+    fn read_subspace_val(&self, key: &Key) -> Result<Option<Vec<u8>>> {
+        self.0.get(&subspace_key(key)?)
+    }
+
+    fn read_subspace_val_with_height(
+        &self,
+        key: &Key,
+        height: BlockHeight,
+        last_height: BlockHeight,
+    ) -> Result<Option<Vec<u8>>> {
+        // Check if the value changed at this height
+        let (old_val_key, new_val_key) = old_and_new_diff_key(key, height)?;
+
+        // If it has a "new" val, it was written at this height
+        if let Some(new_val) = self.0.get(&new_val_key)? {
+            return Ok(Some(new_val));
+        }
+        // If it has an "old" val, it was deleted at this height
+        if self.0.get(&old_val_key)?.is_some() {
+            return Ok(None);
+        }
+
+        // If the value didn't change at the given height, we try to look for it
+        // at successor heights, up to the `last_height`
+        let mut raw_height = height.0 + 1;
+        loop {
+            // Try to find the next diff on this key
+            let (old_val_key, new_val_key) =
+                old_and_new_diff_key(key, BlockHeight(raw_height))?;
+            // If it has an "old" val, it's the one we're looking for
+            if let Some(bytes) = self.0.get(&old_val_key)? {
+                return Ok(Some(bytes));
+            }
+            // Check if the value was created at this height instead, which
+            // would mean that it wasn't present before
+            if self.0.get(&new_val_key)?.is_some() {
+                return Ok(None);
+            }
+
+            if raw_height >= last_height.0 {
+                // Read from latest height
+                return self.read_subspace_val(key);
+            } else {
+                raw_height += 1
+            }
+        }
+    }
+
+    fn write_subspace_val(
+        &mut self,
+        height: BlockHeight,
+        key: &Key,
+        value: impl AsRef<[u8]>,
+        persist_diffs: bool,
+    ) -> Result<i64> {
+        // batch_write are directly committed
+        self.batch_write_subspace_val(
+            &mut KvDBWriteBatch,
+            height,
+            key,
+            value,
+            persist_diffs,
+        )
+    }
+
+    fn delete_subspace_val(
+        &mut self,
+        height: BlockHeight,
+        key: &Key,
+        persist_diffs: bool,
+    ) -> Result<i64> {
+        // batch_delete are directly committed
+        self.batch_delete_subspace_val(
+            &mut KvDBWriteBatch,
+            height,
+            key,
+            persist_diffs,
+        )
+    }
+
+    fn batch() -> Self::WriteBatch {
+        KvDBWriteBatch
+    }
+
+    fn exec_batch(&mut self, _batch: Self::WriteBatch) -> Result<()> {
+        // Nothing to do - in KvDB, batch writes are committed directly from
+        // `batch_write_subspace_val` and `batch_delete_subspace_val`.
+        Ok(())
+    }
+
+    fn batch_write_subspace_val(
+        &self,
+        _batch: &mut Self::WriteBatch,
+        height: BlockHeight,
+        key: &Key,
+        value: impl AsRef<[u8]>,
+        persist_diffs: bool,
+    ) -> Result<i64> {
+        let value = value.as_ref();
+        let subspace_key = subspace_key(key)?;
+        let prev_value = self.0.get(&subspace_key)?;
+        // The diff is written before the value, so that the previous value
+        // can be restored from it if the commit is interrupted in between
+        self.write_subspace_diff(
+            height,
+            key,
+            prev_value.as_deref(),
+            Some(value),
+            persist_diffs,
+        )?;
+        self.0.insert(subspace_key, value.to_vec())?;
+
+        let size_diff = match prev_value {
+            Some(prev_value) => value.len() as i64 - prev_value.len() as i64,
+            None => value.len() as i64,
+        };
+        Ok(size_diff)
+    }
+
+    fn batch_delete_subspace_val(
+        &self,
+        _batch: &mut Self::WriteBatch,
+        height: BlockHeight,
+        key: &Key,
+        persist_diffs: bool,
+    ) -> Result<i64> {
+        let subspace_key = subspace_key(key)?;
+        let Some(prev_value) = self.0.get(&subspace_key)? else {
+            return Ok(0);
+        };
+        // Persist the previous value before the deletion, so that it can be
+        // restored if the commit is interrupted in between
+        self.write_subspace_diff(
+            height,
+            key,
+            Some(&prev_value),
+            None,
+            persist_diffs,
+        )?;
+        self.0.remove(&subspace_key)?;
+
+        Ok(prev_value.len() as i64)
+    }
+
+    fn prune_merkle_tree_store(
+        &mut self,
+        _batch: &mut Self::WriteBatch,
+        store_type: &StoreType,
+        epoch: Epoch,
+    ) -> Result<()> {
+        let prefix_key = subtree_key_prefix(store_type, epoch);
+        let root_key = prefix_key
+            .push(&"root".to_owned())
+            .map_err(Error::KeyError)?;
+        self.0.remove(&root_key.to_string())?;
+        let store_key = prefix_key
+            .push(&"store".to_owned())
+            .map_err(Error::KeyError)?;
+        self.0.remove(&store_key.to_string())?;
+        Ok(())
+    }
+
+    fn prune_block(
+        &mut self,
+        _batch: &mut Self::WriteBatch,
+        height: BlockHeight,
+    ) -> Result<()> {
+        // Block results
+        self.0.remove(&format!("results/{}", height.raw()))?;
+        // Base Merkle tree
+        let key_prefix = base_tree_key_prefix(height);
+        let root_key = key_prefix.clone().with_segment("root".to_owned());
+        self.0.remove(&root_key.to_string())?;
+        let store_key = key_prefix.with_segment("store".to_owned());
+        self.0.remove(&store_key.to_string())?;
+        // Subspace diffs
+        for diff_prefix in [OLD_DIFF_PREFIX, NEW_DIFF_PREFIX] {
+            self.remove_prefix(&format!("{}/{diff_prefix}/", height.raw()))?;
+        }
+        Ok(())
+    }
+
+    fn move_block_to_cold(
+        &mut self,
+        _batch: &mut Self::WriteBatch,
+        cold: &mut Self,
+        height: BlockHeight,
+        epoch: Option<Epoch>,
+    ) -> Result<()> {
+        for key in cold_block_keys(height, epoch) {
+            // Removed only once it's in the cold DB, so that the data isn't
+            // lost if the move is interrupted
+            if let Some(value) = self.0.get(&key)? {
+                cold.0.insert(key.clone(), value)?;
+                self.0.remove(&key)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn read_bridge_pool_signed_nonce(
+        &self,
+        height: BlockHeight,
+        last_height: BlockHeight,
+    ) -> Result<Option<ethereum_events::Uint>> {
+        let nonce_key = Key {
+            segments: vec![
+                DbKeySeg::AddressSeg(BRIDGE_POOL_ADDRESS),
+                DbKeySeg::StringSeg(Segments::VALUES.signed_root.into()),
+            ],
+        };
+        let bytes = if height == BlockHeight(0) || height >= last_height {
+            self.read_subspace_val(&nonce_key)?
+        } else {
+            self.read_subspace_val_with_height(&nonce_key, height, last_height)?
+        };
+        // The value is a Borsh encoded bridge pool root proof, whose last
+        // field is the signed `(root, nonce)` pair. The nonce is therefore
+        // encoded in the trailing bytes.
+        const NONCE_LEN: usize = std::mem::size_of::<ethereum_events::Uint>();
+        match bytes {
+            Some(bytes) => {
+                let nonce_bytes = bytes
+                    .len()
+                    .checked_sub(NONCE_LEN)
+                    .map(|start| &bytes[start..])
+                    .ok_or_else(|| {
+                        Error::DBError(
+                            "Invalid bridge pool signed root".to_string(),
+                        )
+                    })?;
+                Ok(Some(
+                    ethereum_events::Uint::try_from_slice(nonce_bytes)
+                        .map_err(Error::BorshCodingError)?,
+                ))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn write_replay_protection_entry(
+        &mut self,
+        _batch: &mut Self::WriteBatch,
+        key: &Key,
+    ) -> Result<()> {
+        let key = Key::parse(REPLAY_PROTECTION_PREFIX)
+            .map_err(Error::KeyError)?
+            .join(key);
+
+        match self.0.insert(key.to_string(), vec![])? {
+            Some(_) => Err(Error::DBError(format!(
+                "Replay protection key {key} already in storage"
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    fn delete_replay_protection_entry(
+        &mut self,
+        _batch: &mut Self::WriteBatch,
+        key: &Key,
+    ) -> Result<()> {
+        let key = Key::parse(REPLAY_PROTECTION_PREFIX)
+            .map_err(Error::KeyError)?
+            .join(key);
+
+        self.0.remove(&key.to_string())?;
+
+        Ok(())
+    }
+
+    fn batch_write_storage_usage(
+        &mut self,
+        _batch: &mut Self::WriteBatch,
+        address: &Address,
+        bytes: u64,
+    ) -> Result<()> {
+        let key = format!("{STORAGE_USAGE_PREFIX}/{address}");
+        if bytes == 0 {
+            self.0.remove(&key)?;
+        } else {
+            self.0.insert(key, types::encode(&bytes))?;
+        }
+        Ok(())
+    }
+
+    fn read_indexed_balance(
+        &self,
+        token: &Address,
+        owner: &Address,
+    ) -> Result<Option<Amount>> {
+        match self
+            .0
+            .get(&format!("{BALANCE_INDEX_PREFIX}/{token}/{owner}"))?
+        {
+            Some(bytes) => {
+                Ok(Some(types::decode(bytes).map_err(Error::CodingError)?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn batch_write_indexed_balance(
+        &mut self,
+        _batch: &mut Self::WriteBatch,
+        token: &Address,
+        owner: &Address,
+        amount: Amount,
+    ) -> Result<()> {
+        let key = format!("{BALANCE_INDEX_PREFIX}/{token}/{owner}");
+        if amount.is_zero() {
+            self.0.remove(&key)?;
+        } else {
+            self.0.insert(key, types::encode(&amount))?;
+        }
+        Ok(())
+    }
+
+    fn batch_write_account_tx(
+        &mut self,
+        _batch: &mut Self::WriteBatch,
+        address: &Address,
+        height: BlockHeight,
+        index: TxIndex,
+        hash: &Hash,
+    ) -> Result<()> {
+        self.0.insert(
+            account_history_key(address, height, index),
+            types::encode(hash),
+        )?;
+        Ok(())
+    }
+
+    fn read_value_checksum(&self, key: &Key) -> Result<Option<ValueChecksum>> {
+        match self.0.get(&value_checksum_key(key))? {
+            Some(bytes) => {
+                Ok(Some(types::decode(bytes).map_err(Error::CodingError)?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn batch_write_value_checksum(
+        &self,
+        _batch: &mut Self::WriteBatch,
+        key: &Key,
+        checksum: Option<&ValueChecksum>,
+    ) -> Result<()> {
+        let key = value_checksum_key(key);
+        match checksum {
+            Some(checksum) => self.0.insert(key, types::encode(checksum))?,
+            None => self.0.remove(&key)?,
+        };
+        Ok(())
+    }
+
+    fn repair_subspace_val(&self, key: &Key, value: &[u8]) -> Result<()> {
+        self.0.insert(subspace_key(key)?, value.to_vec())?;
+        Ok(())
+    }
+}
+
+impl<S: KvStore> DBCheckpoint for KvDB<S> {
+    fn read_last_block(&self) -> Result<Option<BlockStateRead>> {
+        DB::read_last_block(self)
+    }
+
+    fn iter_subspace(
+        &self,
+    ) -> Box<dyn Iterator<Item = (String, Vec<u8>)> + '_> {
+        Box::new(self.iter_prefix(None).map(|(key, val, _gas)| (key, val)))
+    }
+}
+
+impl<'iter, S: KvStore> DBIter<'iter> for KvDB<S> {
+    type PrefixIter = KvPrefixIterator<S>;
+
+    fn iter_prefix(&'iter self, prefix: Option<&Key>) -> Self::PrefixIter {
+        let stripped_prefix = format!("{SUBSPACE_PREFIX}/");
+        let prefix = format!(
+            "{}{}",
+            stripped_prefix,
+            match prefix {
+                Some(prefix) => {
+                    if prefix == &Key::default() {
+                        prefix.to_string()
+                    } else {
+                        format!("{prefix}/")
+                    }
+                }
+                None => "".to_string(),
+            }
+        );
+        self.prefix_iter(&prefix, stripped_prefix)
+    }
+
+    fn iter_results(&'iter self) -> Self::PrefixIter {
+        let stripped_prefix = "results/".to_owned();
+        self.prefix_iter(&stripped_prefix.clone(), stripped_prefix)
+    }
+
+    fn iter_old_diffs(
+        &self,
+        height: BlockHeight,
+        prefix: Option<&Key>,
+    ) -> Self::PrefixIter {
+        let stripped_prefix = format!("{}/{OLD_DIFF_PREFIX}/", height.raw());
+        let prefix = diffs_prefix(&stripped_prefix, prefix);
+        self.prefix_iter(&prefix, stripped_prefix)
+    }
+
+    fn iter_new_diffs(
+        &self,
+        height: BlockHeight,
+        prefix: Option<&Key>,
+    ) -> Self::PrefixIter {
+        let stripped_prefix = format!("{}/{NEW_DIFF_PREFIX}/", height.raw());
+        let prefix = diffs_prefix(&stripped_prefix, prefix);
+        self.prefix_iter(&prefix, stripped_prefix)
+    }
+
+    fn iter_replay_protection(&'iter self) -> Self::PrefixIter {
+        let stripped_prefix = format!(
+            "{REPLAY_PROTECTION_PREFIX}/{}/",
+            replay_protection::last_prefix()
+        );
+        self.prefix_iter(&stripped_prefix.clone(), stripped_prefix)
+    }
+
+    fn iter_storage_usage(&'iter self) -> Self::PrefixIter {
+        let stripped_prefix = format!("{STORAGE_USAGE_PREFIX}/");
+        self.prefix_iter(&stripped_prefix.clone(), stripped_prefix)
+    }
+
+    fn iter_indexed_balances(
+        &'iter self,
+        token: Option<&Address>,
+    ) -> Self::PrefixIter {
+        let stripped_prefix = match token {
+            Some(token) => format!("{BALANCE_INDEX_PREFIX}/{token}/"),
+            None => format!("{BALANCE_INDEX_PREFIX}/"),
+        };
+        self.prefix_iter(&stripped_prefix.clone(), stripped_prefix)
+    }
+
+    fn iter_account_history(
+        &'iter self,
+        address: &Address,
+    ) -> Self::PrefixIter {
+        let stripped_prefix = format!("{ACCOUNT_HISTORY_PREFIX}/{address}/");
+        self.prefix_iter(&stripped_prefix.clone(), stripped_prefix)
+    }
+}
+
+impl<I> Iterator for PrefixIterator<I>
+where
+    I: Iterator<Item = Result<KVBytes>>,
+{
+    type Item = (String, Vec<u8>, u64);
+
+    /// Returns the next pair and the gas cost
+    fn next(&mut self) -> Option<(String, Vec<u8>, u64)> {
+        match self.iter.next() {
+            Some(result) => {
+                let (key, val) =
+                    result.expect("Prefix iterator shouldn't fail");
+                let key = String::from_utf8(key.to_vec())
+                    .expect("Cannot convert from bytes to key string");
+                match key.strip_prefix(&self.stripped_prefix) {
+                    Some(k) => {
+                        let gas = k.len() + val.len();
+                        Some((k.to_owned(), val.to_vec(), gas as _))
+                    }
+                    None => self.next(),
+                }
+            }
+            None => None,
+        }
+    }
+}
+
+impl DBWriteBatch for KvDBWriteBatch {}
+
+/// An in-memory [`KvStore`].
+#[derive(Debug, Default)]
+pub struct InMemoryStore(
+    // The state is wrapped in `RefCell` to allow modifying it directly from
+    // batch write method (which requires immutable self ref).
+    RefCell<BTreeMap<String, Vec<u8>>>,
+);
+
+// The `InMemoryStore` is not `Sync`, but we're sharing it across threads for
+// reading only (for parallelized VP runs). In a different context, this may
+// not be safe.
+unsafe impl Sync for InMemoryStore {}
+
+/// An iterator over a snapshot of the key-vals of the [`InMemoryStore`].
+#[derive(Debug)]
+pub struct InMemoryIter(std::vec::IntoIter<(String, Vec<u8>)>);
+
+impl KvStore for InMemoryStore {
+    /// There is no cache for the in-memory store
+    type Cache = ();
+    type Iter = InMemoryIter;
+
+    fn open(_path: &Path, _cache: Option<&Self::Cache>) -> Result<Self> {
+        Ok(Self::default())
+    }
+
+    fn flush(&self, _wait: bool) -> Result<()> {
+        Ok(())
+    }
+
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        Ok(self.0.borrow().get(key).cloned())
+    }
+
+    fn insert(&self, key: String, value: Vec<u8>) -> Result<Option<Vec<u8>>> {
+        Ok(self.0.borrow_mut().insert(key, value))
+    }
+
+    fn remove(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        Ok(self.0.borrow_mut().remove(key))
+    }
+
+    fn iter_prefix(&self, prefix: &str) -> Self::Iter {
+        let kvs: Vec<_> = self
+            .0
+            .borrow()
+            .range(prefix.to_owned()..)
+            .take_while(|(key, _)| key.starts_with(prefix))
+            .map(|(key, val)| (key.clone(), val.clone()))
+            .collect();
+        InMemoryIter(kvs.into_iter())
+    }
+
+    fn checkpoint(&self) -> Result<Self> {
+        Ok(Self(RefCell::new(self.0.borrow().clone())))
+    }
+}
+
+impl Iterator for InMemoryIter {
+    type Item = Result<KVBytes>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(key, val)| {
+            Ok((Box::from(key.as_bytes()), Box::from(val.as_slice())))
+        })
+    }
+}
+
+fn subspace_key(key: &Key) -> Result<String> {
+    Ok(Key::parse(SUBSPACE_PREFIX)
+        .map_err(Error::KeyError)?
+        .join(key)
+        .to_string())
+}
+
+fn value_checksum_key(key: &Key) -> String {
+    format!("{VALUE_CHECKSUM_PREFIX}/{key}")
+}
+
+fn old_and_new_diff_key(
+    key: &Key,
+    height: BlockHeight,
+) -> Result<(String, String)> {
+    let key_prefix = Key::from(height.to_db_key());
+    let old = key_prefix
+        .push(&OLD_DIFF_PREFIX.to_owned())
+        .map_err(Error::KeyError)?
+        .join(key);
+    let new = key_prefix
+        .push(&NEW_DIFF_PREFIX.to_owned())
+        .map_err(Error::KeyError)?
+        .join(key);
+    Ok((old.to_string(), new.to_string()))
+}
+
+fn diffs_prefix(stripped_prefix: &str, prefix: Option<&Key>) -> String {
+    match prefix {
+        Some(k) if k != &Key::default() => format!("{stripped_prefix}{k}/"),
+        _ => stripped_prefix.to_owned(),
+    }
+}
+
+/// The keys of the data of a block that is moved to a cold DB
+fn cold_block_keys(height: BlockHeight, epoch: Option<Epoch>) -> Vec<String> {
+    let mut key_prefixes = vec![base_tree_key_prefix(height)];
+    if let Some(epoch) = epoch {
+        key_prefixes.extend(
+            StoreType::iter_subtrees().map(|st| subtree_key_prefix(st, epoch)),
+        );
+    }
+    let mut keys = vec![format!("results/{}", height.raw())];
+    for key_prefix in key_prefixes {
+        let root_key = key_prefix.clone().with_segment("root".to_owned());
+        let store_key = key_prefix.with_segment("store".to_owned());
+        keys.extend([root_key.to_string(), store_key.to_string()]);
+    }
+    keys
+}
+
+/// The key of a tx in the account history. The height and the index are
+/// padded for the txs to be ordered by the keys.
+fn account_history_key(
+    address: &Address,
+    height: BlockHeight,
+    index: TxIndex,
+) -> String {
+    format!(
+        "{ACCOUNT_HISTORY_PREFIX}/{address}/{:020}/{:010}",
+        height.0, index.0
+    )
+}
+
+fn unknown_key_error(key: &str) -> Result<()> {
+    Err(Error::UnknownKey {
+        key: key.to_owned(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conformance;
+
+    #[test]
+    fn test_in_memory_db_conformance() {
+        conformance::check_all(KvDB::<InMemoryStore>::default);
+    }
+
+    #[test]
+    fn test_in_memory_db_block_journal_crash() {
+        conformance::check_block_journal_crash(&mut KvDB::new(
+            conformance::CrashingStore::new(InMemoryStore::default()),
+        ));
+    }
+}
@@ -1,12 +1,19 @@
 //! The common storage read trait is implemented in the storage, client RPC, tx
 //! and VPs (both native and WASM).
 
+pub mod changed_keys;
 pub mod collections;
+#[cfg(any(test, feature = "testing"))]
+pub mod conformance;
 mod db;
 mod error;
+pub mod key_schema;
+pub mod kvdb;
 pub mod mockdb;
+pub mod state_diff;
 pub mod tx_queue;
 pub mod types;
+pub mod value_checksum;
 
 pub use db::{Error as DbError, Result as DbResult, *};
 pub use error::{CustomError, Error, OptionExt, Result, ResultExt};
@@ -0,0 +1,123 @@
+//! The diff of the storage subspace of a committed block, built from the
+//! subspace diffs persisted in the [`DB`].
+//!
+//! A [`StateDiff`] is Borsh encoded into a canonical file, which can be used
+//! to back up the state incrementally and to replay a series of blocks onto a
+//! base state, e.g. to find the block at which the states of two nodes
+//! diverged.
+//!
+//! The diffs of the keys that are not merklized are only kept in the DB until
+//! the key is written again, so only the diff of the last committed block is
+//! complete, unless the state persists the diffs of all the keys.
+
+use std::collections::BTreeMap;
+
+use namada_core::borsh::{BorshDeserialize, BorshSerialize};
+use namada_core::types::storage::BlockHeight;
+use thiserror::Error;
+
+use crate::db::{DBIter, DB};
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error(
+        "The value of the key {key} doesn't match the diff at height {height}"
+    )]
+    ValueMismatch { key: String, height: BlockHeight },
+}
+
+/// A result of a function that may fail
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// The change of a key's value in a block
+#[derive(Clone, Debug, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub enum KeyDiff {
+    /// The key didn't have a value before the block
+    Created {
+        /// The value written in the block
+        value: Vec<u8>,
+    },
+    /// The key's value was overwritten in the block
+    Modified {
+        /// The value before the block
+        old: Vec<u8>,
+        /// The value written in the block
+        new: Vec<u8>,
+    },
+    /// The key was deleted in the block
+    Deleted {
+        /// The value before the block
+        old: Vec<u8>,
+    },
+}
+
+/// The diff of the storage subspace of a committed block
+#[derive(Clone, Debug, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct StateDiff {
+    /// The height of the block
+    pub height: BlockHeight,
+    /// The changes of the keys, ordered by the keys
+    pub changes: BTreeMap<String, KeyDiff>,
+}
+
+impl StateDiff {
+    /// Read the diff of the block at the given height from the DB
+    pub fn read<D>(db: &D, height: BlockHeight) -> Self
+    where
+        D: DB + for<'iter> DBIter<'iter>,
+    {
+        let mut old_vals: BTreeMap<String, Vec<u8>> = db
+            .iter_old_diffs(height, None)
+            .map(|(key, value, _gas)| (key, value))
+            .collect();
+        let mut changes = BTreeMap::new();
+        for (key, new, _gas) in db.iter_new_diffs(height, None) {
+            let diff = match old_vals.remove(&key) {
+                Some(old) => KeyDiff::Modified { old, new },
+                None => KeyDiff::Created { value: new },
+            };
+            changes.insert(key, diff);
+        }
+        // The keys with an old value, but without a new one were deleted
+        changes.extend(
+            old_vals
+                .into_iter()
+                .map(|(key, old)| (key, KeyDiff::Deleted { old })),
+        );
+        Self { height, changes }
+    }
+
+    /// Apply the diff onto the given key-vals of the subspace at the
+    /// preceding height. Fails if the values before the block don't match the
+    /// given ones.
+    pub fn apply(
+        &self,
+        subspace: &mut BTreeMap<String, Vec<u8>>,
+    ) -> Result<()> {
+        for (key, diff) in &self.changes {
+            let expected_old = match diff {
+                KeyDiff::Created { .. } => None,
+                KeyDiff::Modified { old, .. } | KeyDiff::Deleted { old } => {
+                    Some(old)
+                }
+            };
+            if subspace.get(key) != expected_old {
+                return Err(Error::ValueMismatch {
+                    key: key.clone(),
+                    height: self.height,
+                });
+            }
+            match diff {
+                KeyDiff::Created { value: new }
+                | KeyDiff::Modified { new, .. } => {
+                    subspace.insert(key.clone(), new.clone());
+                }
+                KeyDiff::Deleted { .. } => {
+                    subspace.remove(key);
+                }
+            }
+        }
+        Ok(())
+    }
+}
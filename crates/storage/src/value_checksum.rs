@@ -0,0 +1,45 @@
+//! The checksums of the subspace values.
+//!
+//! When they're enabled, the checksum of every subspace value is written with
+//! the value, together with the height of the block that wrote it, and it's
+//! verified when the value is read from the DB. A corrupted value can then be
+//! repaired from the subspace diff written at that height, if it's still
+//! kept. The values written before the checksums were enabled aren't
+//! verified.
+
+use namada_core::borsh::{BorshDeserialize, BorshSerialize};
+use namada_core::types::hash::Hash;
+use namada_core::types::storage::BlockHeight;
+
+/// The checksum of a subspace value
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, BorshSerialize, BorshDeserialize,
+)]
+pub struct ValueChecksum {
+    /// The height of the block that wrote the value
+    pub height: BlockHeight,
+    /// The first 8 bytes of the SHA-256 hash of the value
+    pub checksum: u64,
+}
+
+impl ValueChecksum {
+    /// Compute the checksum of the value written at the given height
+    pub fn new(height: BlockHeight, value: &[u8]) -> Self {
+        Self {
+            height,
+            checksum: checksum(value),
+        }
+    }
+
+    /// Check if the value matches the checksum
+    pub fn matches(&self, value: &[u8]) -> bool {
+        self.checksum == checksum(value)
+    }
+}
+
+fn checksum(value: &[u8]) -> u64 {
+    let hash = Hash::sha256(value);
+    let mut bytes = [0; 8];
+    bytes.copy_from_slice(&hash.0[..8]);
+    u64::from_le_bytes(bytes)
+}
@@ -2137,6 +2137,7 @@ fn pgf_governance_proposal() -> Result<()> {
     let pgf_stewards = StewardsUpdate {
         add: Some(albert.clone()),
         remove: vec![],
+        spending_cap: None,
     };
 
     let valid_proposal_json_path =
@@ -2326,6 +2327,8 @@ fn pgf_governance_proposal() -> Result<()> {
             amount: token::Amount::from_u64(5),
             target: christel,
         })],
+        streams: vec![],
+        grants: vec![],
     };
 
     let valid_proposal_json_path =
@@ -23,7 +23,7 @@ mod tests {
 
     use borsh_ext::BorshSerializeExt;
     use itertools::Itertools;
-    use namada::account::pks_handle;
+    use namada::account::{self, pks_handle};
     use namada::ibc::context::transfer_mod::testing::DummyTransferModule;
     use namada::ibc::primitives::Msg;
     use namada::ibc::Error as IbcActionError;
@@ -33,10 +33,13 @@ mod tests {
     };
     use namada::ledger::tx_env::TxEnv;
     use namada::token::{self, Amount};
+    use namada::tx::data::{TxEvent, MAX_TX_EVENTS};
     use namada::tx::Tx;
     use namada::types::hash::Hash;
     use namada::types::key::*;
-    use namada::types::storage::{self, BlockHash, BlockHeight, Key, KeySeg};
+    use namada::types::storage::{
+        self, BlockHash, BlockHeight, Key, KeySeg, TxIndex,
+    };
     use namada::types::time::DateTimeUtc;
     use namada::types::{address, key};
     use namada_test_utils::TestWasms;
@@ -207,6 +210,55 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_tx_emit_event() {
+        // The environment must be initialized first
+        tx_host_env::init();
+
+        let events: Vec<TxEvent> = (0..3)
+            .map(|i| TxEvent {
+                event_type: "order_filled".to_string(),
+                attributes: [("order".to_string(), i.to_string())].into(),
+            })
+            .collect();
+        for event in &events {
+            tx::ctx().emit_event(event).unwrap();
+        }
+
+        let emitted = tx_host_env::with(|env| {
+            env.wl_storage.write_log.get_tx_events().to_vec()
+        });
+        assert_eq!(emitted, events);
+    }
+
+    #[test]
+    #[should_panic = "InvalidTxEvent"]
+    fn test_tx_emit_event_with_reserved_attribute() {
+        // The environment must be initialized first
+        tx_host_env::init();
+
+        let event = TxEvent {
+            event_type: "order_filled".to_string(),
+            attributes: [("hash".to_string(), "0".to_string())].into(),
+        };
+        tx::ctx().emit_event(&event).unwrap();
+    }
+
+    #[test]
+    #[should_panic = "InvalidTxEvent"]
+    fn test_tx_emit_too_many_events() {
+        // The environment must be initialized first
+        tx_host_env::init();
+
+        let event = TxEvent {
+            event_type: "order_filled".to_string(),
+            attributes: Default::default(),
+        };
+        for _ in 0..=MAX_TX_EVENTS {
+            tx::ctx().emit_event(&event).unwrap();
+        }
+    }
+
     #[test]
     #[should_panic]
     fn test_tx_init_account_with_invalid_vp() {
@@ -300,6 +352,114 @@ mod tests {
         tx::ctx().write_bytes(&vp_key, vp_hash).unwrap();
     }
 
+    /// Test that a tx updating the validity predicate of an account with a VP
+    /// update timelock only records a pending update.
+    #[test]
+    fn test_tx_update_vp_with_timelock() {
+        // Initialize a tx environment
+        tx_host_env::init();
+
+        let vp_owner = address::testing::established_address_1();
+        let keypair = key::testing::keypair_1();
+        let public_key = keypair.ref_to();
+        let vp_code = TestWasms::VpAlwaysTrue.read_bytes();
+        let vp_hash = sha256(&vp_code);
+
+        tx_host_env::with(|tx_env| {
+            tx_env.init_parameters(None, None, None, None);
+            tx_env.store_wasm_code(vp_code);
+
+            // Spawn the accounts to be able to modify their storage
+            tx_env.spawn_accounts([&vp_owner]);
+            tx_env.init_account_storage(&vp_owner, vec![public_key.clone()], 1);
+            account::set_vp_update_timelock(
+                &mut tx_env.wl_storage,
+                &vp_owner,
+                2,
+            )
+            .unwrap();
+        });
+
+        let vp_key = Key::validity_predicate(&vp_owner);
+        let vp_pre = tx::ctx().read_bytes(&vp_key).unwrap();
+
+        tx::ctx()
+            .update_validity_predicate(&vp_owner, vp_hash, &None)
+            .unwrap();
+
+        // The VP doesn't change until the timelock has passed
+        assert_eq!(tx::ctx().read_bytes(&vp_key).unwrap(), vp_pre);
+        let epoch = tx::ctx().get_block_epoch().unwrap();
+        assert_eq!(
+            account::pending_vp_update(tx::ctx(), &vp_owner).unwrap(),
+            Some(account::PendingVpUpdate {
+                vp_code_hash: vp_hash,
+                activation_epoch: epoch + 2_u64,
+            })
+        );
+    }
+
+    /// Test that a tx updating validity predicate to a code using the
+    /// non-deterministic SIMD instructions fails.
+    #[test]
+    #[should_panic = "InvalidVpCode"]
+    fn test_tx_update_vp_with_simd_rejected() {
+        // Initialize a tx environment
+        tx_host_env::init();
+
+        let vp_owner = address::testing::established_address_1();
+        let keypair = key::testing::keypair_1();
+        let public_key = keypair.ref_to();
+        // A wasm module with a single function type returning a `v128`
+        let vp_code = vec![
+            0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x01, 0x05, 0x01,
+            0x60, 0x00, 0x01, 0x7b,
+        ];
+        let vp_hash = sha256(&vp_code);
+
+        tx_host_env::with(|tx_env| {
+            tx_env.init_parameters(None, None, None, None);
+            tx_env.store_wasm_code(vp_code);
+
+            // Spawn the accounts to be able to modify their storage
+            tx_env.spawn_accounts([&vp_owner]);
+            tx_env.init_account_storage(&vp_owner, vec![public_key.clone()], 1);
+        });
+
+        // Update VP in a transaction.
+        // Panics only due to unwrap in `native_host_fn!` test macro
+        tx::ctx()
+            .update_validity_predicate(&vp_owner, vp_hash, &None)
+            .unwrap()
+    }
+
+    /// Test that a tx cannot write a pending validity predicate update
+    /// directly to storage
+    #[test]
+    #[should_panic = "CannotWritePendingVp"]
+    fn test_tx_write_pending_vp_rejected() {
+        // Initialize a tx environment
+        tx_host_env::init();
+
+        let vp_owner = address::testing::established_address_1();
+        let vp_code = TestWasms::VpAlwaysTrue.read_bytes();
+        let vp_hash = sha256(&vp_code);
+
+        tx_host_env::with(|tx_env| {
+            // Spawn the accounts to be able to modify their storage
+            tx_env.spawn_accounts([&vp_owner]);
+        });
+
+        // Writing the pending VP to storage directly should fail
+        let pending = account::PendingVpUpdate {
+            vp_code_hash: vp_hash,
+            activation_epoch: storage::Epoch::default(),
+        };
+        tx::ctx()
+            .write(&account::pending_vp_key(&vp_owner), pending)
+            .unwrap();
+    }
+
     /// Test that a tx initializing a new account with validity predicate that
     /// is not in the allowlist fails
     #[test]
@@ -483,6 +643,35 @@ mod tests {
         );
     }
 
+    /// Test that a VP can read the prior and posterior storage state of
+    /// other accounts.
+    #[test]
+    fn test_vp_read_other_account() {
+        let mut tx_env = TestTxEnv::default();
+
+        let addr = address::testing::established_address_1();
+        let other = address::testing::established_address_2();
+        let other_key = storage::Key::from(other.to_db_key())
+            .join(&Key::parse("state").unwrap());
+
+        // Write some value to the storage of the other account ...
+        tx_env.spawn_accounts([&addr, &other]);
+        tx_env.wl_storage.write(&other_key, "active").unwrap();
+        // ... and commit it
+        tx_env.wl_storage.commit_tx();
+
+        // Initialize the VP environment via a transaction that changes the
+        // other account's storage
+        vp_host_env::init_from_tx(addr, tx_env, |_addr| {
+            tx::ctx().write(&other_key, "jailed").unwrap();
+        });
+
+        let pre: Option<String> = vp::CTX.read_pre(&other_key).unwrap();
+        assert_eq!(pre.as_deref(), Some("active"));
+        let post: Option<String> = vp::CTX.read_post(&other_key).unwrap();
+        assert_eq!(post.as_deref(), Some("jailed"));
+    }
+
     #[test]
     fn test_vp_iter_prefix() {
         let mut tx_env = TestTxEnv::default();
@@ -612,6 +801,123 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_verify_signature() {
+        tx_host_env::init();
+        vp_host_env::init();
+
+        let msg = b"an off-chain order".as_slice();
+        // ed25519 and secp256k1 keys
+        for keypair in [key::testing::keypair_1(), key::testing::keypair_3()] {
+            let pk = keypair.ref_to();
+            let sig = common::SigScheme::sign(&keypair, msg);
+            assert!(namada_tx_prelude::verify_signature(&pk, msg, &sig));
+            assert!(namada_vp_prelude::verify_signature(&pk, msg, &sig));
+
+            // A signature of another message or by another key is rejected
+            let other_msg = b"another off-chain order".as_slice();
+            assert!(!namada_tx_prelude::verify_signature(&pk, other_msg, &sig));
+            assert!(!namada_vp_prelude::verify_signature(&pk, other_msg, &sig));
+            let other_pk = key::testing::keypair_2().ref_to();
+            assert!(!namada_tx_prelude::verify_signature(&other_pk, msg, &sig));
+            assert!(!namada_vp_prelude::verify_signature(&other_pk, msg, &sig));
+        }
+    }
+
+    #[test]
+    fn test_block_entropy() {
+        tx_host_env::init();
+        vp_host_env::init();
+
+        let entropy = namada_tx_prelude::get_block_entropy();
+        assert_eq!(
+            entropy,
+            tx_host_env::with(|env| env
+                .wl_storage
+                .storage
+                .get_block_hash()
+                .0
+                .entropy(&env.tx_index))
+        );
+        assert_eq!(
+            namada_vp_prelude::get_block_entropy(),
+            vp_host_env::with(|env| env
+                .wl_storage
+                .storage
+                .get_block_hash()
+                .0
+                .entropy(&env.tx_index))
+        );
+
+        // Another tx in the same block gets a different entropy
+        tx_host_env::with(|env| env.tx_index = TxIndex(1));
+        assert_ne!(entropy, namada_tx_prelude::get_block_entropy());
+    }
+
+    #[test]
+    fn test_recover_secp256k1_public_key() {
+        tx_host_env::init();
+        vp_host_env::init();
+
+        let keypair = key::testing::keypair_3();
+        let pk = keypair.ref_to();
+        let msg_hash = sha256(b"an Ethereum signed message");
+        let sig = match common::SigScheme::sign(&keypair, &msg_hash) {
+            common::Signature::Secp256k1(sig) => sig,
+            _ => unreachable!("keypair_3 is a secp256k1 key"),
+        };
+        let (r, s, v) = sig.into_eth_rsv();
+        let mut eth_sig = [0u8; 65];
+        eth_sig[..32].copy_from_slice(&r);
+        eth_sig[32..64].copy_from_slice(&s);
+        eth_sig[64] = v;
+
+        assert_eq!(
+            namada_tx_prelude::recover_secp256k1_public_key(
+                &msg_hash.0,
+                &eth_sig
+            ),
+            Some(pk.clone())
+        );
+        assert_eq!(
+            namada_vp_prelude::recover_secp256k1_public_key(
+                &msg_hash.0,
+                &eth_sig
+            ),
+            Some(pk.clone())
+        );
+
+        // A signature of another message recovers another key
+        let other_hash = sha256(b"another Ethereum signed message");
+        assert_ne!(
+            namada_tx_prelude::recover_secp256k1_public_key(
+                &other_hash.0,
+                &eth_sig
+            ),
+            Some(pk.clone())
+        );
+        assert_ne!(
+            namada_vp_prelude::recover_secp256k1_public_key(
+                &other_hash.0,
+                &eth_sig
+            ),
+            Some(pk)
+        );
+
+        // An invalid recovery byte is rejected
+        eth_sig[64] = 42;
+        assert!(namada_tx_prelude::recover_secp256k1_public_key(
+            &msg_hash.0,
+            &eth_sig
+        )
+        .is_none());
+        assert!(namada_vp_prelude::recover_secp256k1_public_key(
+            &msg_hash.0,
+            &eth_sig
+        )
+        .is_none());
+    }
+
     #[test]
     fn test_vp_get_metadata() {
         // The environment must be initialized first
@@ -719,6 +1025,78 @@ mod tests {
         assert!(!result);
     }
 
+    /// Test that a VP can simulate a sub-transaction on the storage state
+    /// written by the validated tx, without modifying it
+    #[test]
+    fn test_vp_simulate_sub_tx() {
+        // The environment must be initialized first
+        vp_host_env::init();
+
+        let code = TestWasms::TxWriteStorageKey.read_bytes();
+        let code_hash = Hash::sha256(&code);
+        let code_len = (code.len() as u64).serialize_to_vec();
+        let owner = address::testing::gen_implicit_address();
+        let parent_key = Key::from(owner.to_db_key())
+            .push(&"parent".to_string())
+            .unwrap();
+        let sub_tx_key = Key::from(owner.to_db_key())
+            .push(&"sub_tx".to_string())
+            .unwrap();
+        let data = namada_test_utils::tx_data::TxWriteData {
+            key: sub_tx_key.clone(),
+            value: vec![2],
+        };
+
+        // a code that isn't allowlisted can't be simulated
+        vp_host_env::with(|env| {
+            env.wl_storage
+                .storage
+                .write(&Key::wasm_code(&code_hash), code.clone())
+                .unwrap();
+            env.wl_storage
+                .storage
+                .write(&Key::wasm_code_len(&code_hash), code_len.clone())
+                .unwrap();
+            // a modification of the validated tx
+            env.wl_storage
+                .write_log
+                .write(&parent_key, vec![1])
+                .unwrap();
+        });
+        assert!(namada_vp_prelude::simulate_sub_tx(&code_hash, &data).is_none());
+
+        let allowlist_key =
+            namada::parameters::storage::get_sub_tx_allowlist_storage_key();
+        vp_host_env::with(|env| {
+            env.wl_storage
+                .storage
+                .write(
+                    &allowlist_key,
+                    vec![code_hash.to_string().to_lowercase()]
+                        .serialize_to_vec(),
+                )
+                .unwrap();
+        });
+        let simulated = namada_vp_prelude::simulate_sub_tx(&code_hash, &data)
+            .expect("Expected a successful simulation");
+        assert_eq!(
+            simulated.changed_keys,
+            BTreeSet::from([sub_tx_key.clone()])
+        );
+
+        vp_host_env::with(|env| {
+            assert_eq!(
+                env.wl_storage.write_log.get_keys(),
+                BTreeSet::from([parent_key.clone()])
+            );
+            assert!(env.gas_meter.get_vp_consumed_gas() > 0.into());
+        });
+
+        // the VPs evaluated from native VPs can't simulate sub-transactions
+        vp_host_env::with(|env| env.tx_wasm_cache = None);
+        assert!(namada_vp_prelude::simulate_sub_tx(&code_hash, &data).is_none());
+    }
+
     #[test]
     fn test_ibc_client() {
         // The environment must be initialized first
@@ -499,16 +499,19 @@ mod native_tx_host_env {
         result_ptr: u64
     ));
     native_host_fn!(tx_emit_ibc_event(event_ptr: u64, event_len: u64));
+    native_host_fn!(tx_emit_event(event_ptr: u64, event_len: u64));
     native_host_fn!(tx_get_ibc_events(event_type_ptr: u64, event_type_len: u64) -> i64);
     native_host_fn!(tx_get_chain_id(result_ptr: u64));
     native_host_fn!(tx_get_block_height() -> u64);
     native_host_fn!(tx_get_tx_index() -> u32);
     native_host_fn!(tx_get_block_header(height: u64) -> i64);
     native_host_fn!(tx_get_block_hash(result_ptr: u64));
+    native_host_fn!(tx_get_block_entropy(result_ptr: u64));
     native_host_fn!(tx_get_block_epoch() -> u64);
     native_host_fn!(tx_get_pred_epochs() -> i64);
     native_host_fn!(tx_get_native_token(result_ptr: u64));
     native_host_fn!(tx_log_string(str_ptr: u64, str_len: u64));
+    native_host_fn!(tx_abort(msg_ptr: u64, msg_len: u64));
     native_host_fn!(tx_charge_gas(used_gas: u64));
     native_host_fn!("non-result", tx_set_commitment_sentinel());
     native_host_fn!(tx_verify_tx_section_signature(
@@ -520,6 +523,32 @@ mod native_tx_host_env {
         max_signatures_ptr: u64,
         max_signatures_len: u64,
     ) -> i64);
+    native_host_fn!(tx_verify_signature(
+        pk_ptr: u64,
+        pk_len: u64,
+        msg_ptr: u64,
+        msg_len: u64,
+        sig_ptr: u64,
+        sig_len: u64,
+    ) -> i64);
+    native_host_fn!(tx_recover_secp256k1_public_key(
+        hash_ptr: u64,
+        hash_len: u64,
+        sig_ptr: u64,
+        sig_len: u64,
+    ) -> i64);
+    native_host_fn!(tx_invoke_sub_tx(
+        code_hash_ptr: u64,
+        code_hash_len: u64,
+        data_ptr: u64,
+        data_len: u64,
+    ) -> i64);
+    native_host_fn!(tx_simulate_sub_tx(
+        code_hash_ptr: u64,
+        code_hash_len: u64,
+        data_ptr: u64,
+        data_len: u64,
+    ) -> i64);
 }
 
 #[cfg(test)]
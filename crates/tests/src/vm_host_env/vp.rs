@@ -11,7 +11,7 @@ use namada::tx::Tx;
 use namada::types::address::{self, Address};
 use namada::types::storage::{self, Key, TxIndex};
 use namada::vm::prefix_iter::PrefixIterators;
-use namada::vm::wasm::{self, VpCache};
+use namada::vm::wasm::{self, TxCache, VpCache};
 use namada::vm::{self, WasmCacheRwAccess};
 use namada_tx_prelude::validity_predicate::VpSentinel;
 use namada_vp_prelude::Ctx;
@@ -54,6 +54,8 @@ pub struct TestVpEnv {
     pub result_buffer: Option<Vec<u8>>,
     pub vp_wasm_cache: VpCache<WasmCacheRwAccess>,
     pub vp_cache_dir: TempDir,
+    pub tx_wasm_cache: Option<TxCache<WasmCacheRwAccess>>,
+    pub tx_cache_dir: TempDir,
 }
 
 impl Default for TestVpEnv {
@@ -65,6 +67,8 @@ impl Default for TestVpEnv {
 
         let (vp_wasm_cache, vp_cache_dir) =
             wasm::compilation_cache::common::testing::cache();
+        let (tx_wasm_cache, tx_cache_dir) =
+            wasm::compilation_cache::common::testing::cache();
 
         let wl_storage = WlStorage {
             storage: TestStorage::default(),
@@ -88,6 +92,8 @@ impl Default for TestVpEnv {
             result_buffer: None,
             vp_wasm_cache,
             vp_cache_dir,
+            tx_wasm_cache: Some(tx_wasm_cache),
+            tx_cache_dir,
         }
     }
 }
@@ -270,6 +276,8 @@ mod native_vp_host_env {
                                 result_buffer,
                                 vp_wasm_cache,
                                 vp_cache_dir: _,
+                                tx_wasm_cache,
+                                tx_cache_dir: _,
                             }: &mut TestVpEnv| {
 
                             let env = vm::host_env::testing::vp_env(
@@ -286,6 +294,7 @@ mod native_vp_host_env {
                                 keys_changed,
                                 eval_runner,
                                 vp_wasm_cache,
+                                tx_wasm_cache,
                             );
 
                             // Call the `host_env` function and unwrap any
@@ -315,6 +324,8 @@ mod native_vp_host_env {
                                 result_buffer,
                                 vp_wasm_cache,
                                 vp_cache_dir: _,
+                                tx_wasm_cache,
+                                tx_cache_dir: _,
                             }: &mut TestVpEnv| {
 
                             let env = vm::host_env::testing::vp_env(
@@ -331,6 +342,7 @@ mod native_vp_host_env {
                                 keys_changed,
                                 eval_runner,
                                 vp_wasm_cache,
+                                tx_wasm_cache,
                             );
 
                             // Call the `host_env` function and unwrap any
@@ -358,6 +370,7 @@ mod native_vp_host_env {
     native_host_fn!(vp_get_tx_index() -> u32);
     native_host_fn!(vp_get_block_header(height: u64) -> i64);
     native_host_fn!(vp_get_block_hash(result_ptr: u64));
+    native_host_fn!(vp_get_block_entropy(result_ptr: u64));
     native_host_fn!(vp_get_tx_code_hash(result_ptr: u64));
     native_host_fn!(vp_get_block_epoch() -> u64);
     native_host_fn!(vp_get_pred_epochs() -> i64);
@@ -368,7 +381,14 @@ mod native_vp_host_env {
             input_data_ptr: u64,
             input_data_len: u64,
         ) -> i64);
+    native_host_fn!(vp_simulate_sub_tx(
+            code_hash_ptr: u64,
+            code_hash_len: u64,
+            data_ptr: u64,
+            data_len: u64,
+        ) -> i64);
     native_host_fn!(vp_log_string(str_ptr: u64, str_len: u64));
+    native_host_fn!(vp_abort(msg_ptr: u64, msg_len: u64));
     native_host_fn!(vp_verify_tx_section_signature(
         hash_list_ptr: u64,
         hash_list_len: u64,
@@ -380,5 +400,19 @@ mod native_vp_host_env {
         max_signatures_ptr: u64,
         max_signatures_len: u64,
     ) -> i64);
+    native_host_fn!(vp_verify_signature(
+        pk_ptr: u64,
+        pk_len: u64,
+        msg_ptr: u64,
+        msg_len: u64,
+        sig_ptr: u64,
+        sig_len: u64,
+    ) -> i64);
+    native_host_fn!(vp_recover_secp256k1_public_key(
+        hash_ptr: u64,
+        hash_len: u64,
+        sig_ptr: u64,
+        sig_len: u64,
+    ) -> i64);
     native_host_fn!(vp_charge_gas(used_gas: u64));
 }
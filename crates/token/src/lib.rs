@@ -6,7 +6,17 @@ pub use namada_trans_token::*;
 
 pub mod storage_key {
     pub use namada_shielded_token::storage_key::*;
+    use namada_storage::key_schema::KeySchema;
     pub use namada_trans_token::storage_key::*;
+
+    /// The schemas of the storage keys of the transparent and shielded tokens.
+    /// The schemas of the shielded pool's parameters come before the schema of
+    /// any token parameter, which also matches their keys.
+    pub fn key_schemas() -> impl Iterator<Item = &'static KeySchema> {
+        namada_shielded_token::storage_key::KEY_SCHEMAS
+            .iter()
+            .chain(namada_trans_token::storage_key::KEY_SCHEMAS)
+    }
 }
 
 use namada_core::types::address::Address;
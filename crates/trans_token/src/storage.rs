@@ -27,9 +27,8 @@ pub fn read_balance<S>(
 where
     S: StorageRead,
 {
-    let key = balance_key(token, owner);
-    let balance = storage.read::<token::Amount>(&key)?.unwrap_or_default();
-    Ok(balance)
+    let balance = crate::storage_key::read_balance(storage, token, owner)?;
+    Ok(balance.unwrap_or_default())
 }
 
 /// Read the total network supply of a given token.
@@ -40,9 +39,8 @@ pub fn read_total_supply<S>(
 where
     S: StorageRead,
 {
-    let key = minted_balance_key(token);
-    let balance = storage.read::<token::Amount>(&key)?.unwrap_or_default();
-    Ok(balance)
+    let balance = read_minted_balance(storage, token)?;
+    Ok(balance.unwrap_or_default())
 }
 
 /// Read the denomination of a given token, if any. Note that native
@@ -93,8 +91,7 @@ pub fn write_denom<S>(
 where
     S: StorageRead + StorageWrite,
 {
-    let key = denom_key(token);
-    storage.write(&key, denom)
+    crate::storage_key::write_denom(storage, token, denom)
 }
 
 /// Transfer `token` from `src` to `dest`. Returns an `Err` if `src` has
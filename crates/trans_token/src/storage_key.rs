@@ -1,7 +1,9 @@
 //! Transparent token storage keys
 
-use namada_core::types::address::{Address, InternalAddress};
-use namada_core::types::storage::{self, DbKeySeg, KeySeg};
+use namada_core::types::address::{Address, IBC, MULTITOKEN};
+use namada_core::types::ibc::IbcDenomTrace;
+use namada_core::types::storage::{self, KeySeg};
+use namada_core::types::token::{Amount, Denomination};
 
 /// Key segment for a balance key
 pub const BALANCE_STORAGE_KEY: &str = "balance";
@@ -13,6 +15,8 @@ pub const MINTER_STORAGE_KEY: &str = "minter";
 pub const MINTED_STORAGE_KEY: &str = "minted";
 /// Key segment for token parameters
 pub const PARAMETERS_STORAGE_KEY: &str = "parameters";
+/// Key segment for the display metadata of a token
+pub const METADATA_STORAGE_KEY: &str = "metadata";
 
 /// Gets the key for the given token address, error with the given
 /// message to expect if the key is not in the address
@@ -26,50 +30,47 @@ pub fn key_of_token(
         .expect(expect_message)
 }
 
-/// Obtain a storage key for user's balance.
-pub fn balance_key(token_addr: &Address, owner: &Address) -> storage::Key {
-    balance_prefix(token_addr)
-        .push(&owner.to_db_key())
-        .expect("Cannot obtain a storage key")
+namada_storage::key_schemas! {
+    /// Obtain a storage key for user's balance.
+    balance(token: Address, owner: Address) -> Amount =
+        [{ MULTITOKEN }, token, { BALANCE_STORAGE_KEY }, owner];
+    /// Obtain a storage key for the minted multitoken balance.
+    minted_balance(token: Address) -> Amount = [
+        { MULTITOKEN },
+        token,
+        { BALANCE_STORAGE_KEY },
+        { MINTED_STORAGE_KEY },
+    ];
+    /// Obtain a storage key for the multitoken minter.
+    minter(token: Address) -> Address =
+        [{ MULTITOKEN }, token, { MINTER_STORAGE_KEY }];
+    /// Obtain a storage key for a token parameter. The types of the
+    /// parameters' values differ.
+    token_parameter(token: Address, name: String) =
+        [{ MULTITOKEN }, token, { PARAMETERS_STORAGE_KEY }, name];
+    /// Obtain a storage key denomination of a token.
+    denom(token: Address) -> Denomination = [token, { DENOM_STORAGE_KEY }];
+    /// Obtain a storage key for the display metadata of a token. The
+    /// metadata of an IBC token is derived from its denom trace.
+    metadata(token: Address) -> IbcDenomTrace =
+        [{ MULTITOKEN }, token, { METADATA_STORAGE_KEY }];
 }
 
 /// Obtain a storage key prefix for all users' balances.
 pub fn balance_prefix(token_addr: &Address) -> storage::Key {
-    storage::Key::from(
-        Address::Internal(InternalAddress::Multitoken).to_db_key(),
-    )
-    .push(&token_addr.to_db_key())
-    .expect("Cannot obtain a storage key")
-    .push(&BALANCE_STORAGE_KEY.to_owned())
-    .expect("Cannot obtain a storage key")
+    storage::Key::from(MULTITOKEN.to_db_key())
+        .push(&token_addr.to_db_key())
+        .expect("Cannot obtain a storage key")
+        .push(&BALANCE_STORAGE_KEY.to_owned())
+        .expect("Cannot obtain a storage key")
 }
 
-/// Obtain a storage key prefix for all users' balances.
+/// Obtain a storage key prefix for all the parameters of a token.
 pub fn parameter_prefix(token_addr: &Address) -> storage::Key {
-    storage::Key::from(
-        Address::Internal(InternalAddress::Multitoken).to_db_key(),
-    )
-    .push(&token_addr.to_db_key())
-    .expect("Cannot obtain a storage key")
-    .push(&PARAMETERS_STORAGE_KEY.to_owned())
-    .expect("Cannot obtain a storage key")
-}
-
-/// Obtain a storage key for the multitoken minter.
-pub fn minter_key(token_addr: &Address) -> storage::Key {
-    storage::Key::from(
-        Address::Internal(InternalAddress::Multitoken).to_db_key(),
-    )
-    .push(&token_addr.to_db_key())
-    .expect("Cannot obtain a storage key")
-    .push(&MINTER_STORAGE_KEY.to_owned())
-    .expect("Cannot obtain a storage key")
-}
-
-/// Obtain a storage key for the minted multitoken balance.
-pub fn minted_balance_key(token_addr: &Address) -> storage::Key {
-    balance_prefix(token_addr)
-        .push(&MINTED_STORAGE_KEY.to_owned())
+    storage::Key::from(MULTITOKEN.to_db_key())
+        .push(&token_addr.to_db_key())
+        .expect("Cannot obtain a storage key")
+        .push(&PARAMETERS_STORAGE_KEY.to_owned())
         .expect("Cannot obtain a storage key")
 }
 
@@ -80,109 +81,13 @@ pub fn is_balance_key<'a>(
     token_addr: &Address,
     key: &'a storage::Key,
 ) -> Option<&'a Address> {
-    match &key.segments[..] {
-        [
-            DbKeySeg::AddressSeg(addr),
-            DbKeySeg::AddressSeg(token),
-            DbKeySeg::StringSeg(balance),
-            DbKeySeg::AddressSeg(owner),
-        ] if *addr == Address::Internal(InternalAddress::Multitoken)
-            && token == token_addr
-            && balance == BALANCE_STORAGE_KEY =>
-        {
-            Some(owner)
-        }
-        _ => None,
-    }
-}
-
-/// Check if the given storage key is a parameter key for an unspecified token.
-/// If it is, return the token address.
-pub fn is_any_token_parameter_key(key: &storage::Key) -> Option<&Address> {
-    match &key.segments[..] {
-        [
-            DbKeySeg::AddressSeg(addr),
-            DbKeySeg::AddressSeg(token),
-            DbKeySeg::StringSeg(parameter),
-            DbKeySeg::StringSeg(_parameter_name),
-        ] if *addr == Address::Internal(InternalAddress::Multitoken)
-            && parameter == PARAMETERS_STORAGE_KEY =>
-        {
-            Some(token)
-        }
-        _ => None,
-    }
-}
-
-/// Check if the given storage key is a balance key for an unspecified token. If
-/// it is, return the token and owner address.
-pub fn is_any_token_balance_key(key: &storage::Key) -> Option<[&Address; 2]> {
-    match &key.segments[..] {
-        [
-            DbKeySeg::AddressSeg(addr),
-            DbKeySeg::AddressSeg(token),
-            DbKeySeg::StringSeg(balance),
-            DbKeySeg::AddressSeg(owner),
-        ] if *addr == Address::Internal(InternalAddress::Multitoken)
-            && balance == BALANCE_STORAGE_KEY =>
-        {
-            Some([token, owner])
-        }
-        _ => None,
-    }
-}
-
-/// Obtain a storage key denomination of a token.
-pub fn denom_key(token_addr: &Address) -> storage::Key {
-    storage::Key::from(token_addr.to_db_key())
-        .push(&DENOM_STORAGE_KEY.to_owned())
-        .expect("Cannot obtain a storage key")
+    is_any_balance_key(key)
+        .and_then(|(token, owner)| (token == token_addr).then_some(owner))
 }
 
 /// Check if the given storage key is a denomination key for the given token.
 pub fn is_denom_key(token_addr: &Address, key: &storage::Key) -> bool {
-    matches!(&key.segments[..],
-        [
-            DbKeySeg::AddressSeg(addr),
-            ..,
-            DbKeySeg::StringSeg(key),
-        ] if key == DENOM_STORAGE_KEY && addr == token_addr)
-}
-
-/// Check if the given storage key is for a minter of a unspecified token.
-/// If it is, returns the token.
-pub fn is_any_minter_key(key: &storage::Key) -> Option<&Address> {
-    match &key.segments[..] {
-        [
-            DbKeySeg::AddressSeg(addr),
-            DbKeySeg::AddressSeg(token),
-            DbKeySeg::StringSeg(minter),
-        ] if *addr == Address::Internal(InternalAddress::Multitoken)
-            && minter == MINTER_STORAGE_KEY =>
-        {
-            Some(token)
-        }
-        _ => None,
-    }
-}
-
-/// Check if the given storage key is for total supply of a unspecified token.
-/// If it is, returns the token.
-pub fn is_any_minted_balance_key(key: &storage::Key) -> Option<&Address> {
-    match &key.segments[..] {
-        [
-            DbKeySeg::AddressSeg(addr),
-            DbKeySeg::AddressSeg(token),
-            DbKeySeg::StringSeg(balance),
-            DbKeySeg::StringSeg(owner),
-        ] if *addr == Address::Internal(InternalAddress::Multitoken)
-            && balance == BALANCE_STORAGE_KEY
-            && owner == MINTED_STORAGE_KEY =>
-        {
-            Some(token)
-        }
-        _ => None,
-    }
+    is_any_denom_key(key) == Some(token_addr)
 }
 
 /// Check if the given storage key is a balance key for a shielded action. If it
@@ -190,17 +95,8 @@ pub fn is_any_minted_balance_key(key: &storage::Key) -> Option<&Address> {
 pub fn is_any_shielded_action_balance_key(
     key: &storage::Key,
 ) -> Option<[&Address; 2]> {
-    is_any_token_balance_key(key).map_or_else(
-        || {
-            is_any_minted_balance_key(key).map(|token| {
-                [
-                    token,
-                    &Address::Internal(
-                        namada_core::types::address::InternalAddress::Ibc,
-                    ),
-                ]
-            })
-        },
-        Some,
+    is_any_balance_key(key).map_or_else(
+        || is_any_minted_balance_key(key).map(|token| [token, &IBC]),
+        |(token, owner)| Some([token, owner]),
     )
 }
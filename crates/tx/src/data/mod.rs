@@ -13,7 +13,7 @@ pub mod protocol;
 /// wrapper txs with encrypted payloads
 pub mod wrapper;
 
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt::{self, Display};
 use std::str::FromStr;
 
@@ -185,6 +185,8 @@ pub struct TxResult {
     pub ibc_events: BTreeSet<IbcEvent>,
     /// Ethereum bridge events emitted by the transaction
     pub eth_bridge_events: BTreeSet<EthBridgeEvent>,
+    /// Application-level events emitted by the transaction, in order
+    pub tx_events: Vec<TxEvent>,
 }
 
 impl TxResult {
@@ -194,6 +196,235 @@ impl TxResult {
     }
 }
 
+/// The result of a dry run of a transaction, with the would-be effects of its
+/// wrapper, if any, and of its inner transaction. None of them are committed.
+#[derive(
+    Clone,
+    Debug,
+    Default,
+    BorshSerialize,
+    BorshDeserialize,
+    Serialize,
+    Deserialize,
+)]
+pub struct DryRunResult {
+    /// The result of the inner transaction. Its gas includes the gas used by
+    /// the wrapper.
+    pub tx_result: TxResult,
+    /// Gas used by the wrapper transaction, if the dry run included one
+    pub wrapper_gas_used: Option<Gas>,
+    /// Gas used by the inner transaction, including its VPs
+    pub inner_gas_used: Gas,
+    /// Storage keys touched by the wrapper transaction, e.g. by the fee
+    /// payment
+    pub wrapper_changed_keys: BTreeSet<storage::Key>,
+}
+
+impl DryRunResult {
+    /// Check if the inner tx would be accepted by all the VPs
+    pub fn is_accepted(&self) -> bool {
+        self.tx_result.is_accepted()
+    }
+
+    /// Total gas that would be used by the wrapper and inner transactions
+    pub fn gas_used(&self) -> Gas {
+        self.tx_result.gas_used
+    }
+
+    /// All the storage keys that would be touched by the wrapper and inner
+    /// transactions
+    pub fn changed_keys(&self) -> BTreeSet<&storage::Key> {
+        self.wrapper_changed_keys
+            .iter()
+            .chain(self.tx_result.changed_keys.iter())
+            .collect()
+    }
+
+    /// The number of events that would be emitted by the inner transaction
+    pub fn events_count(&self) -> usize {
+        self.tx_result.ibc_events.len()
+            + self.tx_result.eth_bridge_events.len()
+            + self.tx_result.tx_events.len()
+    }
+}
+
+/// The would-be effects of a transaction simulated on a fork of the write log,
+/// of which none are committed
+#[derive(
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    Serialize,
+    Deserialize,
+)]
+pub struct SimulatedTx {
+    /// Storage keys that would be touched by the transaction
+    pub changed_keys: BTreeSet<storage::Key>,
+    /// Addresses of the validity predicates that would be triggered by the
+    /// transaction
+    pub verifiers: BTreeSet<Address>,
+    /// IBC events that would be emitted by the transaction
+    pub ibc_events: BTreeSet<IbcEvent>,
+    /// Application-level events that would be emitted by the transaction, in
+    /// order
+    pub tx_events: Vec<TxEvent>,
+}
+
+/// The maximum number of events that a transaction can emit
+pub const MAX_TX_EVENTS: usize = 32;
+/// The maximum size of an event emitted by a transaction, in bytes
+pub const MAX_TX_EVENT_BYTES: usize = 4_096;
+
+/// An application-level event emitted by a transaction
+#[derive(
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Serialize,
+    Deserialize,
+)]
+pub struct TxEvent {
+    /// The type of the event
+    pub event_type: String,
+    /// The attributes of the event
+    pub attributes: BTreeMap<String, String>,
+}
+
+impl TxEvent {
+    /// The attributes set by the ledger when the event is published
+    pub const RESERVED_ATTRIBUTES: [&'static str; 3] =
+        ["kind", "hash", "height"];
+
+    /// The size of the event type and attributes, in bytes
+    pub fn size(&self) -> usize {
+        self.attributes
+            .iter()
+            .fold(self.event_type.len(), |acc, (k, v)| acc + k.len() + v.len())
+    }
+
+    /// Check that the event has a type, that it doesn't exceed the maximum
+    /// size and that it doesn't set any reserved attribute
+    pub fn is_valid(&self) -> bool {
+        !self.event_type.is_empty()
+            && self.size() <= MAX_TX_EVENT_BYTES
+            && Self::RESERVED_ATTRIBUTES
+                .iter()
+                .all(|attr| !self.attributes.contains_key(*attr))
+    }
+}
+
+/// The maximum size of an error message of a tx or VP execution reported in
+/// the tx result, in bytes
+pub const MAX_WASM_ERROR_MSG_LEN: usize = 512;
+
+/// Protocol-defined codes of the errors of tx and VP executions reported in
+/// the tx results.
+/// The codes must not change with versions, only new ones may be added.
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    FromPrimitive,
+    ToPrimitive,
+    PartialEq,
+    Eq,
+    BorshSerialize,
+    BorshDeserialize,
+    Serialize,
+    Deserialize,
+)]
+pub enum WasmErrorCode {
+    // WARN: These codes shouldn't be changed between version!
+    // =========================================================================
+    /// The wasm code aborted the execution with an error message, e.g. on a
+    /// panic or when the tx or VP returned an error
+    Aborted = 1,
+    /// The wasm code trapped
+    Trap = 2,
+    /// The execution exceeded the gas limit
+    OutOfGas = 3,
+    /// The tx signature is invalid
+    InvalidSig = 4,
+    /// Any other error, e.g. of a native VP
+    Other = 5,
+    // =========================================================================
+    // WARN: These codes shouldn't be changed between version!
+}
+
+impl WasmErrorCode {
+    /// Convert to `u32`.
+    pub fn to_u32(&self) -> u32 {
+        ToPrimitive::to_u32(self).unwrap()
+    }
+
+    /// Convert from `u32`.
+    pub fn from_u32(raw: u32) -> Option<Self> {
+        FromPrimitive::from_u32(raw)
+    }
+}
+
+impl Display for WasmErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_u32())
+    }
+}
+
+/// An error of a tx or VP execution reported in the tx result
+#[derive(
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    BorshSerialize,
+    BorshDeserialize,
+    Serialize,
+    Deserialize,
+)]
+pub struct WasmError {
+    /// The code of the error
+    pub code: WasmErrorCode,
+    /// The message of the error, truncated to [`MAX_WASM_ERROR_MSG_LEN`]
+    /// bytes
+    pub message: String,
+}
+
+impl WasmError {
+    /// Create a new error, truncating the message to
+    /// [`MAX_WASM_ERROR_MSG_LEN`] bytes
+    pub fn new(code: WasmErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: truncate_error_message(message.into()),
+        }
+    }
+}
+
+impl Display for WasmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (error code {})", self.message, self.code)
+    }
+}
+
+/// Truncate an error message of a tx or VP execution to
+/// [`MAX_WASM_ERROR_MSG_LEN`] bytes, on a UTF-8 character boundary
+pub fn truncate_error_message(mut message: String) -> String {
+    if message.len() > MAX_WASM_ERROR_MSG_LEN {
+        let mut len = MAX_WASM_ERROR_MSG_LEN;
+        while !message.is_char_boundary(len) {
+            len -= 1;
+        }
+        message.truncate(len);
+    }
+    message
+}
+
 /// Result of checking a transaction with validity predicates
 // TODO derive BorshSchema after <https://github.com/near/borsh-rs/issues/82>
 #[derive(
@@ -213,7 +444,7 @@ pub struct VpsResult {
     /// The total gas used by all the VPs
     pub gas_used: VpsGas,
     /// Errors occurred in any of the VPs, if any
-    pub errors: Vec<(Address, String)>,
+    pub errors: Vec<(Address, WasmError)>,
     /// Sentinel to signal an invalid transaction signature
     pub invalid_sig: bool,
 }
@@ -548,3 +779,32 @@ fn test_process_tx_decrypted_signed() {
         _ => panic!("Test failed"),
     }
 }
+
+#[cfg(test)]
+mod test_wasm_error {
+    use super::*;
+
+    /// Test that the error messages are truncated on a character boundary
+    #[test]
+    fn test_wasm_error_truncation() {
+        let short = WasmError::new(WasmErrorCode::Aborted, "insufficient");
+        assert_eq!(short.message, "insufficient");
+        assert_eq!(
+            short.to_string(),
+            format!("insufficient (error code {})", WasmErrorCode::Aborted)
+        );
+
+        // The max length falls in the middle of a 2-byte character, which
+        // must not be split
+        let long = format!("a{}", "é".repeat(MAX_WASM_ERROR_MSG_LEN));
+        let err = WasmError::new(WasmErrorCode::Trap, long);
+        assert_eq!(err.message.len(), MAX_WASM_ERROR_MSG_LEN - 1);
+        assert!(err.message.chars().skip(1).all(|c| c == 'é'));
+
+        assert_eq!(
+            WasmErrorCode::from_u32(WasmErrorCode::OutOfGas.to_u32()),
+            Some(WasmErrorCode::OutOfGas)
+        );
+        assert_eq!(WasmErrorCode::from_u32(0), None);
+    }
+}
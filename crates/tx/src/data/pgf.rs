@@ -30,14 +30,34 @@ pub struct UpdateStewardCommission {
     pub commission: HashMap<Address, Dec>,
 }
 
+/// A tx data type to attest the completion of a pgf grant milestone
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    Serialize,
+    Deserialize,
+)]
+pub struct MilestoneAttestation {
+    /// The pgf steward address
+    pub steward: Address,
+    /// The grant id
+    pub grant_id: u64,
+    /// The index of the attested milestone
+    pub milestone: u64,
+}
+
 #[cfg(any(test, feature = "testing"))]
 /// Tests and strategies for PGF
 pub mod tests {
     use namada_core::types::address::testing::arb_non_internal_address;
     use namada_core::types::dec::testing::arb_dec;
+    use proptest::prelude::any;
     use proptest::{collection, prop_compose};
 
-    use super::UpdateStewardCommission;
+    use super::{MilestoneAttestation, UpdateStewardCommission};
 
     prop_compose! {
         /// Generate an arbitraary steward commission update
@@ -51,4 +71,19 @@ pub mod tests {
             }
         }
     }
+
+    prop_compose! {
+        /// Generate an arbitrary pgf milestone attestation
+        pub fn arb_milestone_attestation()(
+            steward in arb_non_internal_address(),
+            grant_id in any::<u64>(),
+            milestone in 0..10u64,
+        ) -> MilestoneAttestation {
+            MilestoneAttestation {
+                steward,
+                grant_id,
+                milestone,
+            }
+        }
+    }
 }
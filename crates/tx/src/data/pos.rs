@@ -187,6 +187,8 @@ pub struct MetaDataChange {
     pub discord_handle: Option<String>,
     /// Validator's avatar url
     pub avatar: Option<String>,
+    /// Validator's name
+    pub name: Option<String>,
     /// Validator's commission rate
     pub commission_rate: Option<Dec>,
 }
@@ -272,6 +274,7 @@ pub mod tests {
             website in option::of("[a-zA-Z0-9_]*"),
             discord_handle in option::of("[a-zA-Z0-9_]*"),
             avatar in option::of("[a-zA-Z0-9_]*"),
+            name in option::of("[a-zA-Z0-9_]*"),
             commission_rate in option::of(arb_dec()),
         ) -> MetaDataChange {
             MetaDataChange {
@@ -281,6 +284,7 @@ pub mod tests {
                 website,
                 discord_handle,
                 avatar,
+                name,
                 commission_rate,
             }
         }
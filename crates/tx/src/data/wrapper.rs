@@ -240,7 +240,11 @@ pub mod wrapper_tx {
         }
 
         /// Performs validation on the optional fee unshielding data carried by
-        /// the wrapper and generates the tx for execution.
+        /// the wrapper and generates the tx for execution. The unshielding
+        /// can accompany any kind of inner tx: it must only spend shielded
+        /// notes and the generated transfer moves exactly the fee of the
+        /// wrapper to the fee payer, which the MASP VP validates against the
+        /// unshielding transaction.
         pub fn check_and_generate_fee_unshielding(
             &self,
             transfer_code_hash: Hash,
@@ -282,6 +286,7 @@ pub mod wrapper_tx {
                         .to_string(),
                 ));
             }
+
             self.generate_fee_unshielding(
                 transfer_code_hash,
                 transfer_code_tag,
@@ -289,13 +294,25 @@ pub mod wrapper_tx {
             )
         }
 
-        /// Generates the fee unshielding tx for execution.
+        /// Generates the fee unshielding tx for execution. Fails if the
+        /// unshielding spends any transparent input.
         pub fn generate_fee_unshielding(
             &self,
             transfer_code_hash: Hash,
             transfer_code_tag: Option<String>,
             unshield: Transaction,
         ) -> Result<Tx, WrapperTxErr> {
+            // The unshielded funds must only come from the shielded pool so
+            // that the unshielding is bounded to the fee
+            if unshield
+                .transparent_bundle()
+                .map_or(false, |bundle| !bundle.vin.is_empty())
+            {
+                return Err(WrapperTxErr::InvalidUnshield(
+                    "Transparent inputs are not allowed".to_string(),
+                ));
+            }
+
             let mut tx =
                 Tx::from_type(TxType::Decrypted(DecryptedTx::Decrypted));
             let masp_section = tx.add_section(Section::MaspTx(unshield));
@@ -402,6 +419,136 @@ pub mod wrapper_tx {
             assert_eq!(refund, Amount::default());
         }
     }
+
+    #[cfg(test)]
+    mod test_fee_unshielding {
+        use masp_primitives::asset_type::AssetType;
+        use masp_primitives::consensus::{BlockHeight, TestNetwork};
+        use masp_primitives::convert::AllowedConversion;
+        use masp_primitives::merkle_tree::MerklePath;
+        use masp_primitives::sapling::prover::TxProver;
+        use masp_primitives::sapling::redjubjub::{PublicKey, Signature};
+        use masp_primitives::sapling::{
+            Diversifier, Node, PaymentAddress, ProofGenerationKey, Rseed,
+        };
+        use masp_primitives::transaction::builder::Builder;
+        use masp_primitives::transaction::components::{
+            I128Sum, TxOut, U64Sum, GROTH_PROOF_SIZE,
+        };
+        use masp_primitives::transaction::fees::fixed::FeeRule;
+        use masp_primitives::transaction::TransparentAddress;
+        use masp_primitives::{bls12_381, jubjub};
+        use namada_core::types::address::nam;
+        use namada_core::types::key::testing::common_sk_from_simple_seed;
+
+        use super::*;
+
+        /// A prover of the transactions without any sapling description,
+        /// which never need a proof
+        struct TransparentTxProver;
+
+        impl TxProver for TransparentTxProver {
+            type SaplingProvingContext = ();
+
+            fn new_sapling_proving_context(&self) {}
+
+            fn spend_proof(
+                &self,
+                _ctx: &mut Self::SaplingProvingContext,
+                _proof_generation_key: ProofGenerationKey,
+                _diversifier: Diversifier,
+                _rseed: Rseed,
+                _ar: jubjub::Fr,
+                _asset_type: AssetType,
+                _value: u64,
+                _anchor: bls12_381::Scalar,
+                _merkle_path: MerklePath<Node>,
+            ) -> Result<
+                ([u8; GROTH_PROOF_SIZE], jubjub::ExtendedPoint, PublicKey),
+                (),
+            > {
+                unreachable!("The tx has no shielded spend")
+            }
+
+            fn output_proof(
+                &self,
+                _ctx: &mut Self::SaplingProvingContext,
+                _esk: jubjub::Fr,
+                _payment_address: PaymentAddress,
+                _rcm: jubjub::Fr,
+                _asset_type: AssetType,
+                _value: u64,
+            ) -> ([u8; GROTH_PROOF_SIZE], jubjub::ExtendedPoint) {
+                unreachable!("The tx has no shielded output")
+            }
+
+            fn convert_proof(
+                &self,
+                _ctx: &mut Self::SaplingProvingContext,
+                _allowed_conversion: AllowedConversion,
+                _value: u64,
+                _anchor: bls12_381::Scalar,
+                _merkle_path: MerklePath<Node>,
+            ) -> Result<([u8; GROTH_PROOF_SIZE], jubjub::ExtendedPoint), ()>
+            {
+                unreachable!("The tx has no shielded convert")
+            }
+
+            fn binding_sig(
+                &self,
+                _ctx: &mut Self::SaplingProvingContext,
+                _assets_and_values: &I128Sum,
+                _sighash: &[u8; 32],
+            ) -> Result<Signature, ()> {
+                unreachable!("The tx has no sapling bundle")
+            }
+        }
+
+        /// Test that the fee unshielding of a wrapper tx can't spend
+        /// transparent inputs, also when it's generated without the
+        /// mempool checks
+        #[test]
+        fn test_fee_unshielding_transparent_inputs() {
+            let asset_type = AssetType::new(b"fee").unwrap();
+            let address = TransparentAddress([0; 20]);
+            let mut builder = Builder::<TestNetwork, _>::new_with_rng(
+                TestNetwork,
+                BlockHeight::from_u32(1_000),
+                rand::thread_rng(),
+            );
+            builder
+                .add_transparent_input(TxOut {
+                    asset_type,
+                    value: 10,
+                    address,
+                })
+                .unwrap();
+            builder
+                .add_transparent_output(&address, asset_type, 10)
+                .unwrap();
+            let (unshield, _metadata) = builder
+                .build(
+                    &TransparentTxProver,
+                    &FeeRule::non_standard(U64Sum::zero()),
+                )
+                .unwrap();
+            assert!(!unshield.transparent_bundle().unwrap().vin.is_empty());
+
+            let wrapper = WrapperTx::new(
+                Fee {
+                    amount_per_gas_unit: DenominatedAmount::native(1.into()),
+                    token: nam(),
+                },
+                common_sk_from_simple_seed(0).ref_to(),
+                Epoch(0),
+                100_000.into(),
+                None,
+            );
+            let res =
+                wrapper.generate_fee_unshielding(Hash::zero(), None, unshield);
+            assert!(matches!(res, Err(WrapperTxErr::InvalidUnshield(_))));
+        }
+    }
 }
 
 pub use wrapper_tx::*;
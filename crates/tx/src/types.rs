@@ -1231,7 +1231,9 @@ impl Tx {
                         witnesses.push(signatures);
                     }
                     // Short-circuit these checks if the threshold is exceeded
-                    if verified_pks.len() >= threshold.into() {
+                    if public_keys_index_map.total_weight(&verified_pks)
+                        >= threshold.into()
+                    {
                         return Ok(witnesses);
                     }
                 }
@@ -1239,7 +1241,7 @@ impl Tx {
         }
         Err(VerifySigError::InvalidSectionSignature(format!(
             "signature threshold not met: ({} < {})",
-            verified_pks.len(),
+            public_keys_index_map.total_weight(&verified_pks),
             threshold
         )))
     }
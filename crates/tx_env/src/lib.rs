@@ -6,6 +6,7 @@ use namada_core::types::address::Address;
 use namada_core::types::ibc::IbcEvent;
 use namada_core::types::storage;
 use namada_storage::{Result, StorageRead, StorageWrite};
+use namada_tx::data::TxEvent;
 
 /// Transaction host functions
 pub trait TxEnv: StorageRead + StorageWrite {
@@ -51,6 +52,11 @@ pub trait TxEnv: StorageRead + StorageWrite {
     /// Emit an IBC event. On multiple calls, these emitted event will be added.
     fn emit_ibc_event(&mut self, event: &IbcEvent) -> Result<()>;
 
+    /// Emit an application-level event. On multiple calls, the events are
+    /// added in order. The transaction fails if the event is invalid or if
+    /// too many events are emitted.
+    fn emit_event(&mut self, event: &TxEvent) -> Result<()>;
+
     /// Request to charge the provided amount of gas for the current transaction
     fn charge_gas(&mut self, used_gas: u64) -> Result<()>;
 
@@ -7,12 +7,35 @@ pub fn init_account(
     owner: &Address,
     data: InitAccount,
 ) -> EnvResult<Address> {
+    namada_account::validate_weights(
+        data.public_keys.len(),
+        &data.weights,
+        data.threshold,
+    )?;
     namada_account::init_account_storage(
         ctx,
         owner,
         &data.public_keys,
         data.threshold,
     )?;
+    namada_account::set_weights(ctx, owner, &data.weights)?;
 
     Ok(owner.to_owned())
 }
+
+/// Replace the public keys of an account with the given weighted keys and
+/// signature threshold
+pub fn rotate_keys(ctx: &mut Ctx, data: &RotateAccountKeys) -> TxResult {
+    namada_account::validate_weights(
+        data.public_keys.len(),
+        &data.weights,
+        data.threshold,
+    )?;
+    let owner = &data.addr;
+    namada_account::clear_public_keys(ctx, owner)?;
+    for (index, public_key) in data.public_keys.iter().enumerate() {
+        namada_account::set_public_key_at(ctx, owner, public_key, index as u8)?;
+    }
+    namada_account::set_weights(ctx, owner, &data.weights)?;
+    ctx.write(&namada_account::threshold_key(owner), data.threshold)
+}
@@ -53,6 +53,16 @@ pub fn log_string<T: AsRef<str>>(msg: T) {
     }
 }
 
+/// Abort the transaction with an error message, which is reported in the tx
+/// result. The message is truncated to a protocol-defined maximum size.
+pub fn abort<T: AsRef<str>>(msg: T) -> ! {
+    let msg = msg.as_ref();
+    unsafe {
+        namada_tx_abort(msg.as_ptr() as _, msg.len() as _);
+    }
+    unreachable!("The transaction must be aborted by the host")
+}
+
 /// Format and log a string in a debug build.
 ///
 /// In WASM target debug build, the message will be printed at the
@@ -351,6 +361,15 @@ impl TxEnv for Ctx {
         Ok(())
     }
 
+    fn emit_event(
+        &mut self,
+        event: &transaction::TxEvent,
+    ) -> Result<(), Error> {
+        let event = borsh::to_vec(event).unwrap();
+        unsafe { namada_tx_emit_event(event.as_ptr() as _, event.len() as _) };
+        Ok(())
+    }
+
     fn charge_gas(&mut self, used_gas: u64) -> Result<(), Error> {
         unsafe { namada_tx_charge_gas(used_gas) };
         Ok(())
@@ -418,6 +437,101 @@ pub fn verify_signatures_of_pks(
     Ok(HostEnvResult::is_success(valid))
 }
 
+/// Verify the signature of an arbitrary message with the given public key
+pub fn verify_signature(
+    pk: &common::PublicKey,
+    msg: &[u8],
+    sig: &common::Signature,
+) -> bool {
+    let pk = pk.serialize_to_vec();
+    let sig = sig.serialize_to_vec();
+
+    let valid = unsafe {
+        namada_tx_verify_signature(
+            pk.as_ptr() as _,
+            pk.len() as _,
+            msg.as_ptr() as _,
+            msg.len() as _,
+            sig.as_ptr() as _,
+            sig.len() as _,
+        )
+    };
+
+    HostEnvResult::is_success(valid)
+}
+
+/// Get the entropy derived from the hash of the current block and the index of
+/// the current transaction in it.
+///
+/// __WARNING__: The block proposer may bias this value, it must not be used
+/// where the proposer has a stake in the outcome.
+pub fn get_block_entropy() -> hash::Hash {
+    let mut result = [0u8; hash::HASH_LENGTH];
+    unsafe {
+        namada_tx_get_block_entropy(result.as_mut_ptr() as _);
+    }
+    hash::Hash(result)
+}
+
+/// Recover the secp256k1 public key of the signer of a 32 bytes message hash
+/// from an Ethereum encoded `r || s || v` signature, as produced by Ethereum
+/// wallets. Returns `None` if the key cannot be recovered.
+pub fn recover_secp256k1_public_key(
+    msg_hash: &[u8; 32],
+    sig: &[u8; 65],
+) -> Option<common::PublicKey> {
+    let read_result = unsafe {
+        namada_tx_recover_secp256k1_public_key(
+            msg_hash.as_ptr() as _,
+            msg_hash.len() as _,
+            sig.as_ptr() as _,
+            sig.len() as _,
+        )
+    };
+    read_from_buffer(read_result, namada_tx_result_buffer)
+        .and_then(|pk| common::PublicKey::try_from_slice(&pk[..]).ok())
+}
+
+/// Invoke the tx code with the given hash as a sub-transaction with the given
+/// data. The code hash must be in the sub-tx allowlist parameter. The
+/// sub-transaction shares the write log and the gas meter of this tx and its
+/// failure aborts this tx.
+pub fn invoke_sub_tx(code_hash: &hash::Hash, data: &impl BorshSerialize) {
+    let data = data.serialize_to_vec();
+    unsafe {
+        namada_tx_invoke_sub_tx(
+            code_hash.0.as_ptr() as _,
+            code_hash.0.len() as _,
+            data.as_ptr() as _,
+            data.len() as _,
+        );
+    }
+}
+
+/// Simulate the tx code with the given hash as a sub-transaction with the given
+/// data, without committing any of its storage modifications. The code hash
+/// must be in the sub-tx allowlist parameter. The gas of the simulation is
+/// charged to this tx. Returns the would-be effects of the sub-transaction,
+/// including the addresses of the validity predicates it would trigger, or
+/// `None` if it fails.
+pub fn simulate_sub_tx(
+    code_hash: &hash::Hash,
+    data: &impl BorshSerialize,
+) -> Option<transaction::SimulatedTx> {
+    let data = data.serialize_to_vec();
+    let read_result = unsafe {
+        namada_tx_simulate_sub_tx(
+            code_hash.0.as_ptr() as _,
+            code_hash.0.len() as _,
+            data.as_ptr() as _,
+            data.len() as _,
+        )
+    };
+    read_from_buffer(read_result, namada_tx_result_buffer).and_then(
+        |simulated| transaction::SimulatedTx::try_from_slice(&simulated).ok(),
+    )
+}
+
 /// Update the masp note commitment tree in storage with the new notes
 pub fn update_masp_note_commitment_tree(
     transaction: &Transaction,
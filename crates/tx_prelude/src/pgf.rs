@@ -1,4 +1,4 @@
-use namada_tx::data::pgf::UpdateStewardCommission;
+use namada_tx::data::pgf::{MilestoneAttestation, UpdateStewardCommission};
 
 use super::*;
 
@@ -20,3 +20,19 @@ pub fn remove_steward(ctx: &mut Ctx, data: &Address) -> EnvResult<()> {
 
     Ok(())
 }
+
+pub fn attest_pgf_milestone(
+    ctx: &mut Ctx,
+    data: MilestoneAttestation,
+) -> EnvResult<()> {
+    // The steward's VP checks that it signed the attestation
+    ctx.insert_verifier(&data.steward)?;
+    namada_governance::pgf::storage::attest_milestone(
+        ctx,
+        data.steward,
+        data.grant_id,
+        data.milestone,
+    )?;
+
+    Ok(())
+}
@@ -28,7 +28,14 @@ impl Ctx {
         amount: token::Amount,
     ) -> TxResult {
         let current_epoch = self.get_block_epoch()?;
-        bond_tokens(self, source, validator, amount, current_epoch, None)
+        bond_tokens(self, source, validator, amount, current_epoch, None)?;
+        let native_token = self.get_native_token()?;
+        namada_account::record_spending(
+            self,
+            source.unwrap_or(validator),
+            &native_token,
+            amount,
+        )
     }
 
     /// Unbond self-bonded tokens from a validator when `source` is `None`
@@ -153,6 +160,7 @@ impl Ctx {
                     website,
                     discord_handle,
                     avatar,
+                    name: None,
                 },
                 offset_opt: None,
             },
@@ -183,6 +191,7 @@ impl Ctx {
         website: Option<String>,
         discord_handle: Option<String>,
         avatar: Option<String>,
+        name: Option<String>,
         commission_rate: Option<Dec>,
     ) -> TxResult {
         let current_epoch = self.get_block_epoch()?;
@@ -194,6 +203,7 @@ impl Ctx {
             website,
             discord_handle,
             avatar,
+            name,
             commission_rate,
             current_epoch,
         )
@@ -29,6 +29,7 @@ pub fn transfer(
         dest_bal.receive(&amount).into_storage_result()?;
         ctx.write(&src_key, src_bal)?;
         ctx.write(&dest_key, dest_bal)?;
+        namada_account::record_spending(ctx, src, token, amount)?;
     }
     Ok(())
 }
@@ -53,6 +54,7 @@ pub fn undenominated_transfer(
         dest_bal.receive(&amount).into_storage_result()?;
         ctx.write(&src_key, src_bal)?;
         ctx.write(&dest_key, dest_bal)?;
+        namada_account::record_spending(ctx, src, token, amount)?;
     }
     Ok(())
 }
@@ -82,6 +82,9 @@ pub mod tx {
         // Emit an IBC event
         pub fn namada_tx_emit_ibc_event(event_ptr: u64, event_len: u64);
 
+        // Emit an application-level event
+        pub fn namada_tx_emit_event(event_ptr: u64, event_len: u64);
+
         // Get IBC events
         pub fn namada_tx_get_ibc_events(
             event_type_ptr: u64,
@@ -100,6 +103,9 @@ pub mod tx {
         // Get the current block hash
         pub fn namada_tx_get_block_hash(result_ptr: u64);
 
+        // Get the entropy derived from the current block hash and tx index
+        pub fn namada_tx_get_block_entropy(result_ptr: u64);
+
         // Get the current block epoch
         pub fn namada_tx_get_block_epoch() -> u64;
 
@@ -115,6 +121,9 @@ pub mod tx {
         // Requires a node running with "Info" log level
         pub fn namada_tx_log_string(str_ptr: u64, str_len: u64);
 
+        /// Abort the tx with an error message reported in the tx result
+        pub fn namada_tx_abort(msg_ptr: u64, msg_len: u64);
+
         /// Charge the provided amount of gas for the current tx
         pub fn namada_tx_charge_gas(used_gas: u64);
 
@@ -136,6 +145,41 @@ pub mod tx {
             max_signatures_len: u64,
         ) -> i64;
 
+        // Verify the signature of an arbitrary message
+        pub fn namada_tx_verify_signature(
+            pk_ptr: u64,
+            pk_len: u64,
+            msg_ptr: u64,
+            msg_len: u64,
+            sig_ptr: u64,
+            sig_len: u64,
+        ) -> i64;
+
+        // Recover the secp256k1 public key of the signer of a message hash
+        pub fn namada_tx_recover_secp256k1_public_key(
+            hash_ptr: u64,
+            hash_len: u64,
+            sig_ptr: u64,
+            sig_len: u64,
+        ) -> i64;
+
+        // Invoke an allowlisted tx code as a sub-transaction
+        pub fn namada_tx_invoke_sub_tx(
+            code_hash_ptr: u64,
+            code_hash_len: u64,
+            data_ptr: u64,
+            data_len: u64,
+        ) -> i64;
+
+        // Simulate an allowlisted tx code as a sub-transaction, without
+        // committing any of its storage modifications
+        pub fn namada_tx_simulate_sub_tx(
+            code_hash_ptr: u64,
+            code_hash_len: u64,
+            data_ptr: u64,
+            data_len: u64,
+        ) -> i64;
+
         /// Update the masp note commitment tree with the new notes
         pub fn namada_tx_update_masp_note_commitment_tree(
             transaction_ptr: u64,
@@ -212,6 +256,9 @@ pub mod vp {
         // Get the current block hash
         pub fn namada_vp_get_block_hash(result_ptr: u64);
 
+        // Get the entropy derived from the current block hash and tx index
+        pub fn namada_vp_get_block_entropy(result_ptr: u64);
+
         // Get the current tx hash
         pub fn namada_vp_get_tx_code_hash(result_ptr: u64);
 
@@ -236,6 +283,10 @@ pub mod vp {
         // Requires a node running with "Info" log level
         pub fn namada_vp_log_string(str_ptr: u64, str_len: u64);
 
+        /// Abort the VP, rejecting the tx, with an error message reported in
+        /// the tx result
+        pub fn namada_vp_abort(msg_ptr: u64, msg_len: u64);
+
         // Verify the signatures of a tx
         pub fn namada_vp_verify_tx_section_signature(
             hash_list_ptr: u64,
@@ -249,6 +300,24 @@ pub mod vp {
             max_signatures_len: u64,
         ) -> i64;
 
+        // Verify the signature of an arbitrary message
+        pub fn namada_vp_verify_signature(
+            pk_ptr: u64,
+            pk_len: u64,
+            msg_ptr: u64,
+            msg_len: u64,
+            sig_ptr: u64,
+            sig_len: u64,
+        ) -> i64;
+
+        // Recover the secp256k1 public key of the signer of a message hash
+        pub fn namada_vp_recover_secp256k1_public_key(
+            hash_ptr: u64,
+            hash_len: u64,
+            sig_ptr: u64,
+            sig_len: u64,
+        ) -> i64;
+
         pub fn namada_vp_eval(
             vp_code_hash_ptr: u64,
             vp_code_hash_len: u64,
@@ -256,6 +325,15 @@ pub mod vp {
             input_data_len: u64,
         ) -> i64;
 
+        // Simulate an allowlisted tx code as a sub-transaction of the
+        // validated tx, on its would-be storage state
+        pub fn namada_vp_simulate_sub_tx(
+            code_hash_ptr: u64,
+            code_hash_len: u64,
+            data_ptr: u64,
+            data_len: u64,
+        ) -> i64;
+
         /// Charge the provided amount of gas for the current vp
         pub fn namada_vp_charge_gas(used_gas: u64);
     }
@@ -14,7 +14,7 @@ use namada_core::types::ibc::{
 use namada_core::types::storage::{
     BlockHash, BlockHeight, Epoch, Epochs, Header, Key, TxIndex,
 };
-use namada_core::types::token::Transfer;
+use namada_core::types::token::{ShieldingSweep, Transfer};
 use namada_storage::{OptionExt, ResultExt, StorageRead};
 use namada_tx::Tx;
 
@@ -34,10 +34,14 @@ where
     /// Type to read storage state after the transaction execution
     type Post: StorageRead<PrefixIter<'view> = Self::PrefixIter<'view>>;
 
-    /// Read storage state before the transaction execution
+    /// Read storage state before the transaction execution. The storage of
+    /// any account can be read, not only the one of the VP's owner, and the
+    /// reads are charged gas per byte of the key and the value.
     fn pre(&'view self) -> Self::Pre;
 
-    /// Read storage state after the transaction execution
+    /// Read storage state after the transaction execution. The storage of any
+    /// account can be read, not only the one of the VP's owner, and the reads
+    /// are charged gas per byte of the key and the value.
     fn post(&'view self) -> Self::Post;
 
     /// Storage read temporary state Borsh encoded value (after tx execution).
@@ -131,6 +135,14 @@ where
             return Ok(masp_tx);
         }
 
+        if let Ok(sweep) = ShieldingSweep::try_from_slice(&data) {
+            let masp_tx = signed
+                .get_section(&sweep.shielded)
+                .and_then(|x| x.as_ref().masp_tx())
+                .ok_or_err_msg("unable to find shielded section")?;
+            return Ok(masp_tx);
+        }
+
         if let Ok(message) = MsgShieldedTransfer::try_from_slice(&data) {
             return Ok(message.shielded_transfer.masp_tx);
         }
@@ -58,6 +58,17 @@ pub fn log_string<T: AsRef<str>>(msg: T) {
     }
 }
 
+/// Abort the validity predicate, rejecting the transaction, with an error
+/// message, which is reported in the tx result. The message is truncated to a
+/// protocol-defined maximum size.
+pub fn abort<T: AsRef<str>>(msg: T) -> ! {
+    let msg = msg.as_ref();
+    unsafe {
+        namada_vp_abort(msg.as_ptr() as _, msg.len() as _);
+    }
+    unreachable!("The validity predicate must be aborted by the host")
+}
+
 /// Checks if a proposal id is being executed
 pub fn is_proposal_accepted(ctx: &Ctx, proposal_id: u64) -> VpResult {
     let proposal_execution_key =
@@ -75,6 +86,57 @@ pub fn verify_signatures(ctx: &Ctx, tx: &Tx, owner: &Address) -> VpResult {
         account::public_keys_index_map(&ctx.pre(), owner)?;
     let threshold = account::threshold(&ctx.pre(), owner)?.unwrap_or(1);
 
+    verify_tx_signatures(
+        tx,
+        owner,
+        &public_keys_index_map,
+        threshold,
+        max_signatures_per_transaction,
+    )
+}
+
+/// Verify that the tx has been signed by one of the owner's session keys
+/// that is allowed to sign the tx code at the current block height
+pub fn verify_session_signatures(
+    ctx: &Ctx,
+    tx: &Tx,
+    owner: &Address,
+) -> VpResult {
+    let Some(policy) = account::policy(&ctx.pre(), owner)? else {
+        return reject();
+    };
+    let Some(code_hash) = ctx.get_tx_code_hash()? else {
+        return reject();
+    };
+    let height = ctx.get_block_height()?;
+    let session_keys: Vec<_> = policy
+        .session_keys
+        .into_iter()
+        .filter(|session_key| session_key.is_allowed(&code_hash, height))
+        .map(|session_key| session_key.public_key)
+        .collect();
+    if session_keys.is_empty() {
+        return reject();
+    }
+    let max_signatures_per_transaction =
+        parameters::max_signatures_per_transaction(&ctx.pre())?;
+
+    verify_tx_signatures(
+        tx,
+        owner,
+        &account::AccountPublicKeysMap::from_iter(session_keys),
+        1,
+        max_signatures_per_transaction,
+    )
+}
+
+fn verify_tx_signatures(
+    tx: &Tx,
+    owner: &Address,
+    public_keys_index_map: &account::AccountPublicKeysMap,
+    threshold: u8,
+    max_signatures_per_transaction: Option<u8>,
+) -> VpResult {
     // Serialize parameters
     let max_signatures = max_signatures_per_transaction.serialize_to_vec();
     let public_keys_map = public_keys_index_map.serialize_to_vec();
@@ -98,6 +160,121 @@ pub fn verify_signatures(ctx: &Ctx, tx: &Tx, owner: &Address) -> VpResult {
     Ok(HostEnvResult::is_success(valid))
 }
 
+/// Verify the signature of an arbitrary message with the given public key
+pub fn verify_signature(
+    pk: &key::common::PublicKey,
+    msg: &[u8],
+    sig: &key::common::Signature,
+) -> bool {
+    let pk = pk.serialize_to_vec();
+    let sig = sig.serialize_to_vec();
+
+    let valid = unsafe {
+        namada_vp_verify_signature(
+            pk.as_ptr() as _,
+            pk.len() as _,
+            msg.as_ptr() as _,
+            msg.len() as _,
+            sig.as_ptr() as _,
+            sig.len() as _,
+        )
+    };
+
+    HostEnvResult::is_success(valid)
+}
+
+/// Get the entropy derived from the hash of the current block and the index of
+/// the current transaction in it.
+///
+/// __WARNING__: The block proposer may bias this value, it must not be used
+/// where the proposer has a stake in the outcome.
+pub fn get_block_entropy() -> Hash {
+    let mut result = [0u8; HASH_LENGTH];
+    unsafe {
+        namada_vp_get_block_entropy(result.as_mut_ptr() as _);
+    }
+    Hash(result)
+}
+
+/// Recover the secp256k1 public key of the signer of a 32 bytes message hash
+/// from an Ethereum encoded `r || s || v` signature, as produced by Ethereum
+/// wallets. Returns `None` if the key cannot be recovered.
+pub fn recover_secp256k1_public_key(
+    msg_hash: &[u8; 32],
+    sig: &[u8; 65],
+) -> Option<key::common::PublicKey> {
+    let read_result = unsafe {
+        namada_vp_recover_secp256k1_public_key(
+            msg_hash.as_ptr() as _,
+            msg_hash.len() as _,
+            sig.as_ptr() as _,
+            sig.len() as _,
+        )
+    };
+    read_from_buffer(read_result, namada_vp_result_buffer)
+        .and_then(|pk| key::common::PublicKey::try_from_slice(&pk[..]).ok())
+}
+
+/// Simulate the tx code with the given hash as a sub-transaction of the
+/// validated tx with the given data, on the storage state written by the
+/// validated tx, without committing any of its storage modifications. The code
+/// hash must be in the sub-tx allowlist parameter. The gas of the simulation is
+/// charged to this VP. Returns the would-be effects of the sub-transaction, or
+/// `None` if it fails.
+pub fn simulate_sub_tx(
+    code_hash: &Hash,
+    data: &impl BorshSerialize,
+) -> Option<namada_tx::data::SimulatedTx> {
+    let data = data.serialize_to_vec();
+    let read_result = unsafe {
+        namada_vp_simulate_sub_tx(
+            code_hash.0.as_ptr() as _,
+            code_hash.0.len() as _,
+            data.as_ptr() as _,
+            data.len() as _,
+        )
+    };
+    read_from_buffer(read_result, namada_vp_result_buffer).and_then(
+        |simulated| {
+            namada_tx::data::SimulatedTx::try_from_slice(&simulated).ok()
+        },
+    )
+}
+
+/// Check that a governance vote of the owner is written from an off-chain
+/// ballot signed by the owner and submitted in a batch by another account
+pub fn is_valid_signed_ballot(
+    ctx: &Ctx,
+    tx: &Tx,
+    owner: &Address,
+    vote_key: &storage::Key,
+) -> VpResult {
+    let batch = match tx.data().and_then(|data| {
+        gov_storage::proposal::VoteProposalBatchData::try_from_slice(&data).ok()
+    }) {
+        Some(batch) => batch,
+        None => return Ok(false),
+    };
+    let ballot = match batch.find_ballot(vote_key) {
+        Some(ballot) if &ballot.data.voter == owner => ballot,
+        _ => return Ok(false),
+    };
+
+    let public_keys_map = account::public_keys_index_map(&ctx.pre(), owner)?;
+    let threshold = account::threshold(&ctx.pre(), owner)?.unwrap_or(1);
+    let chain_id = chain::ChainId(ctx.get_chain_id()?);
+
+    // The ballot must not have been submitted before
+    let consumed_ballot_key = gov_storage::keys::get_consumed_ballot_key(
+        &ballot.compute_hash(&chain_id),
+    );
+    if ctx.has_key_pre(&consumed_ballot_key)? {
+        return Ok(false);
+    }
+
+    Ok(ballot.check_signature(&public_keys_map, threshold, &chain_id))
+}
+
 /// Format and log a string in a debug build.
 ///
 /// In WASM target debug build, the message will be printed at the
@@ -0,0 +1,70 @@
+//! SDK for writing Namada transactions and validity predicates (VPs) compiled
+//! to WASM.
+//!
+//! The host environment bindings are split by the kind of WASM code being
+//! written, each behind its own feature:
+//!
+//! - `tx`: the `tx` module exposes the host functions, the `#[transaction]`
+//!   entrypoint macro and the transaction context.
+//! - `vp`: the `vp` module exposes the host functions, the
+//!   `#[validity_predicate]` entrypoint macro and the VP context.
+//! - `testing`: the `testing` module exposes a harness mocking the host
+//!   environment, so that the WASM code can be tested natively.
+//!
+//! The [`storage`] module, available regardless of the features, provides
+//! typed storage bindings and storage key builders that can be used in both
+//! transactions and VPs.
+//!
+//! The entrypoint macros expect the `wee_alloc` crate to be a dependency of
+//! the WASM crate, which must be built with `crate-type = ["cdylib"]`. A
+//! minimal VP looks like:
+//!
+//! ```ignore
+//! use namada_wasm_sdk::storage::StorageValue;
+//! use namada_wasm_sdk::vp::*;
+//!
+//! #[validity_predicate(gas = 1000)]
+//! fn validate_tx(
+//!     ctx: &Ctx,
+//!     tx_data: Tx,
+//!     addr: Address,
+//!     keys_changed: BTreeSet<storage::Key>,
+//!     verifiers: BTreeSet<Address>,
+//! ) -> VpResult {
+//!     let counter: StorageValue<u64> = StorageValue::new(
+//!         namada_wasm_sdk::storage::account_key(&addr, &["counter"])?,
+//!     );
+//!     let (pre, post) = counter.read_pre_post(ctx)?;
+//!     Ok(post.unwrap_or_default() >= pre.unwrap_or_default())
+//! }
+//! ```
+
+#![doc(html_favicon_url = "https://dev.namada.net/master/favicon.png")]
+#![doc(html_logo_url = "https://dev.namada.net/master/rustdoc-logo.png")]
+#![deny(rustdoc::broken_intra_doc_links)]
+#![deny(rustdoc::private_intra_doc_links)]
+
+pub mod storage;
+
+/// Bindings for writing transactions
+#[cfg(feature = "tx")]
+pub mod tx {
+    // The entrypoint macro expands to paths prefixed by the prelude crate
+    // name, which are resolved from this glob re-export in the user crate.
+    pub use namada_tx_prelude::{self, *};
+}
+
+/// Bindings for writing validity predicates
+#[cfg(feature = "vp")]
+pub mod vp {
+    // The entrypoint macro expands to paths prefixed by the prelude crate
+    // name, which are resolved from this glob re-export in the user crate.
+    pub use namada_vp_prelude::{self, *};
+}
+
+/// Harness mocking the host environment of transactions and validity
+/// predicates, for native tests of WASM code
+#[cfg(feature = "testing")]
+pub mod testing {
+    pub use namada_tests::{tx, vp};
+}
@@ -0,0 +1,149 @@
+//! Typed storage bindings and storage key builders.
+
+use std::marker::PhantomData;
+
+use namada_core::borsh::{BorshDeserialize, BorshSerialize};
+use namada_core::types::address::Address;
+use namada_core::types::storage::{DbKeySeg, Key, KeySeg};
+use namada_storage::{Result, StorageRead, StorageWrite};
+#[cfg(feature = "vp")]
+use namada_vp_env::VpEnv;
+
+/// Build a storage key in the sub-space of the given account from the given
+/// string segments.
+pub fn account_key(owner: &Address, segments: &[&str]) -> Result<Key> {
+    segments
+        .iter()
+        .try_fold(Key::from(owner.to_db_key()), |key, segment| {
+            key.push(&segment.to_string())
+        })
+        .map_err(namada_storage::Error::new)
+}
+
+/// Check if the given storage key is in the sub-space of the given account.
+pub fn is_account_key(key: &Key, owner: &Address) -> bool {
+    matches!(key.first(), Some(DbKeySeg::AddressSeg(addr)) if addr == owner)
+}
+
+/// A handle to a Borsh encoded value of type `T` in storage
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StorageValue<T> {
+    key: Key,
+    phantom: PhantomData<T>,
+}
+
+impl<T> StorageValue<T>
+where
+    T: BorshSerialize + BorshDeserialize,
+{
+    /// Create a handle to the value stored at the given key
+    pub fn new(key: Key) -> Self {
+        Self {
+            key,
+            phantom: PhantomData,
+        }
+    }
+
+    /// The storage key of the value
+    pub fn key(&self) -> &Key {
+        &self.key
+    }
+
+    /// Read the value, if any
+    pub fn read<S>(&self, storage: &S) -> Result<Option<T>>
+    where
+        S: StorageRead,
+    {
+        storage.read(&self.key)
+    }
+
+    /// Read the value or its default, if there is none
+    pub fn read_or_default<S>(&self, storage: &S) -> Result<T>
+    where
+        S: StorageRead,
+        T: Default,
+    {
+        Ok(self.read(storage)?.unwrap_or_default())
+    }
+
+    /// Check if there is a value
+    pub fn is_present<S>(&self, storage: &S) -> Result<bool>
+    where
+        S: StorageRead,
+    {
+        storage.has_key(&self.key)
+    }
+
+    /// Write the value
+    pub fn write<S>(&self, storage: &mut S, value: &T) -> Result<()>
+    where
+        S: StorageWrite,
+    {
+        storage.write(&self.key, value)
+    }
+
+    /// Delete the value
+    pub fn delete<S>(&self, storage: &mut S) -> Result<()>
+    where
+        S: StorageWrite,
+    {
+        storage.delete(&self.key)
+    }
+
+    /// Read the value before and after the execution of the transaction
+    /// being validated by a validity predicate
+    #[cfg(feature = "vp")]
+    pub fn read_pre_post<'view, CTX>(
+        &self,
+        ctx: &'view CTX,
+    ) -> Result<(Option<T>, Option<T>)>
+    where
+        CTX: VpEnv<'view>,
+    {
+        Ok((self.read(&ctx.pre())?, self.read(&ctx.post())?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use namada_core::types::address::testing::{
+        established_address_1, established_address_2,
+    };
+    use namada_storage::testing::TestStorage;
+
+    use super::*;
+
+    #[test]
+    fn test_account_key() {
+        let owner = established_address_1();
+        let key = account_key(&owner, &["counter", "total"]).unwrap();
+        assert_eq!(
+            key.to_string(),
+            format!("#{}/counter/total", owner.encode())
+        );
+        assert!(is_account_key(&key, &owner));
+        assert!(!is_account_key(&key, &established_address_2()));
+
+        // A segment cannot be parsed as a reserved address segment
+        assert!(account_key(&owner, &["#not-an-address"]).is_err());
+    }
+
+    #[test]
+    fn test_storage_value() {
+        let mut storage = TestStorage::default();
+        let owner = established_address_1();
+        let counter: StorageValue<u64> =
+            StorageValue::new(account_key(&owner, &["counter"]).unwrap());
+
+        assert_eq!(counter.read(&storage).unwrap(), None);
+        assert_eq!(counter.read_or_default(&storage).unwrap(), 0);
+        assert!(!counter.is_present(&storage).unwrap());
+
+        counter.write(&mut storage, &42).unwrap();
+        assert_eq!(counter.read(&storage).unwrap(), Some(42));
+        assert!(counter.is_present(&storage).unwrap());
+
+        counter.delete(&mut storage).unwrap();
+        assert_eq!(counter.read(&storage).unwrap(), None);
+    }
+}
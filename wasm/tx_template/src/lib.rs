@@ -1,4 +1,4 @@
-use namada_tx_prelude::*;
+use namada_wasm_sdk::tx::*;
 
 #[transaction(gas = 1000)]
 fn apply_tx(_ctx: &mut Ctx, tx_data: Tx) -> TxResult {
@@ -8,7 +8,7 @@ fn apply_tx(_ctx: &mut Ctx, tx_data: Tx) -> TxResult {
 
 #[cfg(test)]
 mod tests {
-    use namada_tests::tx::*;
+    use namada_wasm_sdk::testing::tx::*;
 
     use super::*;
 
@@ -1,4 +1,4 @@
-use namada_vp_prelude::*;
+use namada_wasm_sdk::vp::*;
 
 #[validity_predicate(gas = 1000)]
 fn validate_tx(
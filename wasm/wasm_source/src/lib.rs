@@ -1,3 +1,5 @@
+#[cfg(feature = "tx_attest_pgf_milestone")]
+pub mod tx_attest_pgf_milestone;
 #[cfg(feature = "tx_become_validator")]
 pub mod tx_become_validator;
 #[cfg(feature = "tx_bond")]
@@ -14,8 +16,12 @@ pub mod tx_change_validator_metadata;
 pub mod tx_claim_rewards;
 #[cfg(feature = "tx_deactivate_validator")]
 pub mod tx_deactivate_validator;
+#[cfg(feature = "tx_delegate_vote")]
+pub mod tx_delegate_vote;
 #[cfg(feature = "tx_ibc")]
 pub mod tx_ibc;
+#[cfg(feature = "tx_ibc_misbehaviour")]
+pub mod tx_ibc_misbehaviour;
 #[cfg(feature = "tx_init_account")]
 pub mod tx_init_account;
 #[cfg(feature = "tx_init_proposal")]
@@ -28,6 +34,10 @@ pub mod tx_redelegate;
 pub mod tx_resign_steward;
 #[cfg(feature = "tx_reveal_pk")]
 pub mod tx_reveal_pk;
+#[cfg(feature = "tx_rotate_account_keys")]
+pub mod tx_rotate_account_keys;
+#[cfg(feature = "tx_shielding_sweep")]
+pub mod tx_shielding_sweep;
 #[cfg(feature = "tx_transfer")]
 pub mod tx_transfer;
 #[cfg(feature = "tx_unbond")]
@@ -36,12 +46,18 @@ pub mod tx_unbond;
 pub mod tx_unjail_validator;
 #[cfg(feature = "tx_update_account")]
 pub mod tx_update_account;
+#[cfg(feature = "tx_update_account_policy")]
+pub mod tx_update_account_policy;
 #[cfg(feature = "tx_update_steward_commission")]
 pub mod tx_update_steward_commission;
 #[cfg(feature = "tx_vote_proposal")]
 pub mod tx_vote_proposal;
+#[cfg(feature = "tx_vote_proposal_batch")]
+pub mod tx_vote_proposal_batch;
 #[cfg(feature = "tx_withdraw")]
 pub mod tx_withdraw;
+#[cfg(feature = "tx_withdraw_vote")]
+pub mod tx_withdraw_vote;
 
 #[cfg(feature = "vp_implicit")]
 pub mod vp_implicit;
@@ -0,0 +1,19 @@
+//! A tx for a steward to attest the completion of a pgf grant milestone
+
+use namada_tx_prelude::transaction::pgf::MilestoneAttestation;
+use namada_tx_prelude::*;
+
+#[transaction(gas = 1222239)]
+fn apply_tx(ctx: &mut Ctx, tx_data: Tx) -> TxResult {
+    let signed = tx_data;
+    let data = signed.data().ok_or_err_msg("Missing data").map_err(|err| {
+        ctx.set_commitment_sentinel();
+        err
+    })?;
+    let attestation = MilestoneAttestation::try_from_slice(&data[..])
+        .wrap_err("failed to decode a MilestoneAttestation")?;
+
+    pgf::attest_pgf_milestone(ctx, attestation)?;
+
+    Ok(())
+}
@@ -1,5 +1,7 @@
-//! A tx to initialize a new validator account with a given public keys and a
-//! validity predicates.
+//! A tx to convert an existing established account into a validator with the
+//! given consensus, protocol and Ethereum bridge keys, commission rate and
+//! metadata. The account keeps its address and its validity predicate, which
+//! authorizes the validator's actions with the account's signature(s).
 
 use namada_tx_prelude::transaction::pos::BecomeValidator;
 use namada_tx_prelude::*;
@@ -12,8 +14,17 @@ fn apply_tx(ctx: &mut Ctx, tx_data: Tx) -> TxResult {
         err
     })?;
     let become_validator = BecomeValidator::try_from_slice(&data[..])
-        .wrap_err("failed to decode InitValidator")?;
-    debug_log!("apply_tx called to init a new validator account");
+        .wrap_err("failed to decode BecomeValidator")?;
+    debug_log!(
+        "apply_tx called to turn account {} into a validator",
+        become_validator.address
+    );
+
+    // Only an existing account can become a validator
+    if !account::exists(ctx, &become_validator.address)? {
+        debug_log!("Account {} doesn't exist", become_validator.address);
+        panic!()
+    }
 
     // Check that the tx has been signed with all the keys to be used for the
     // validator account
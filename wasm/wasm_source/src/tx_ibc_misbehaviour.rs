@@ -0,0 +1,23 @@
+//! A tx to submit a misbehaviour of a counterparty light client.
+//! This tx freezes the client with the given misbehaviour evidence. The tx data
+//! should be an IBC `MsgSubmitMisbehaviour` encoded as protobuf `Any`.
+
+use namada_tx_prelude::*;
+
+#[transaction(gas = 585022)]
+fn apply_tx(ctx: &mut Ctx, tx_data: Tx) -> TxResult {
+    let signed = tx_data;
+    let data = signed.data().ok_or_err_msg("Missing data").map_err(|err| {
+        ctx.set_commitment_sentinel();
+        err
+    })?;
+    if !ibc::is_misbehaviour_message(&data) {
+        return Err(Error::new_const(
+            "The tx data should be a misbehaviour submission",
+        ));
+    }
+
+    // Temp. workaround for <https://github.com/anoma/namada/issues/1831>
+    tx_ibc_execute();
+    Ok(())
+}
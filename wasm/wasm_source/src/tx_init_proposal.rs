@@ -46,6 +46,18 @@ fn apply_tx(ctx: &mut Ctx, tx: Tx) -> TxResult {
         None => None,
     };
 
+    // The MASP VP validates the new rewards parameters and the PGF VP the
+    // spending caps of the stewards
+    match tx_data.r#type {
+        governance::storage::proposal::ProposalType::MaspRewards(_) => {
+            ctx.insert_verifier(&address::MASP)?;
+        }
+        governance::storage::proposal::ProposalType::PGFPayment(_) => {
+            ctx.insert_verifier(&governance::pgf::ADDRESS)?;
+        }
+        _ => {}
+    }
+
     log_string("apply_tx called to create a new governance proposal");
 
     governance::init_proposal(ctx, tx_data, content, code)
@@ -0,0 +1,19 @@
+//! A tx for rotating the weighted public keys and the signature threshold of
+//! an account.
+
+use namada_tx_prelude::*;
+
+#[transaction(gas = 968137)]
+fn apply_tx(ctx: &mut Ctx, tx: Tx) -> TxResult {
+    let signed = tx;
+    let data = signed.data().ok_or_err_msg("Missing data").map_err(|err| {
+        ctx.set_commitment_sentinel();
+        err
+    })?;
+    let tx_data = account::RotateAccountKeys::try_from_slice(&data[..])
+        .wrap_err("failed to decode RotateAccountKeys")?;
+
+    debug_log!("rotate keys for: {:#?}", tx_data.addr);
+
+    account::rotate_keys(ctx, &tx_data)
+}
@@ -0,0 +1,33 @@
+//! A tx shielding the transparent balances of several tokens of an account.
+//! This tx uses `token::ShieldingSweep` wrapped inside `SignedTxData`
+//! as its input as declared in `namada` crate.
+
+use namada_tx_prelude::*;
+
+#[transaction(gas = 1703358)]
+fn apply_tx(ctx: &mut Ctx, tx_data: Tx) -> TxResult {
+    let signed = tx_data;
+    let data = signed.data().ok_or_err_msg("Missing data").map_err(|err| {
+        ctx.set_commitment_sentinel();
+        err
+    })?;
+    let sweep = token::ShieldingSweep::try_from_slice(&data[..])
+        .wrap_err("failed to decode token::ShieldingSweep")?;
+    debug_log!("apply_tx called with shielding sweep: {:#?}", sweep);
+
+    for (token, amount) in &sweep.tokens {
+        token::transfer(ctx, &sweep.source, &address::MASP, token, *amount)?;
+    }
+
+    let shielded = signed
+        .get_section(&sweep.shielded)
+        .and_then(|x| x.as_ref().masp_tx())
+        .ok_or_err_msg("unable to find shielded section")
+        .map_err(|err| {
+            ctx.set_commitment_sentinel();
+            err
+        })?;
+    token::utils::handle_masp_tx(ctx, &shielded, sweep.key.as_deref())?;
+    update_masp_note_commitment_tree(&shielded)?;
+    Ok(())
+}
@@ -17,6 +17,12 @@ fn apply_tx(ctx: &mut Ctx, tx: Tx) -> TxResult {
     let owner = &tx_data.addr;
     debug_log!("update VP for: {:#?}", tx_data.addr);
 
+    if tx_data.cancel_pending_vp_update {
+        account::cancel_pending_vp_update(ctx, owner)?;
+    }
+
+    // When the account has a VP update timelock, the new VP is only recorded
+    // as pending by the host and takes effect after the timelock
     if let Some(hash) = tx_data.vp_code_hash {
         let vp_code_sec = signed
             .get_section(&hash)
@@ -39,6 +45,10 @@ fn apply_tx(ctx: &mut Ctx, tx: Tx) -> TxResult {
         )?;
     }
 
+    if let Some(timelock) = tx_data.vp_update_timelock {
+        account::set_vp_update_timelock(ctx, owner, timelock)?;
+    }
+
     if let Some(threshold) = tx_data.threshold {
         let threshold_key = account::threshold_key(owner);
         ctx.write(&threshold_key, threshold)?;
@@ -46,6 +56,8 @@ fn apply_tx(ctx: &mut Ctx, tx: Tx) -> TxResult {
 
     if !tx_data.public_keys.is_empty() {
         account::clear_public_keys(ctx, owner)?;
+        // The weights of the replaced keys don't apply to the new ones
+        account::set_weights(ctx, owner, &[])?;
         for (index, public_key) in tx_data.public_keys.iter().enumerate() {
             let index = index as u8;
             account::pks_handle(owner).insert(
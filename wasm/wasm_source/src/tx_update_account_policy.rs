@@ -0,0 +1,19 @@
+//! A tx for updating an account's policy, i.e. its daily spending limits and
+//! session keys enforced by the user VP.
+
+use namada_tx_prelude::*;
+
+#[transaction(gas = 968137)]
+fn apply_tx(ctx: &mut Ctx, tx: Tx) -> TxResult {
+    let signed = tx;
+    let data = signed.data().ok_or_err_msg("Missing data").map_err(|err| {
+        ctx.set_commitment_sentinel();
+        err
+    })?;
+    let tx_data = account::UpdateAccountPolicy::try_from_slice(&data[..])
+        .wrap_err("failed to decode UpdateAccountPolicy")?;
+
+    debug_log!("update policy for: {:#?}", tx_data.addr);
+
+    account::set_policy(ctx, &tx_data.addr, &tx_data.policy)
+}
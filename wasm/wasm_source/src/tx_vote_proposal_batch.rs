@@ -0,0 +1,18 @@
+//! A tx to submit a batch of off-chain signed votes on proposals
+
+use namada_tx_prelude::*;
+
+#[transaction(gas = 840866)]
+fn apply_tx(ctx: &mut Ctx, tx_data: Tx) -> TxResult {
+    let signed = tx_data;
+    let data = signed.data().ok_or_err_msg("Missing data").map_err(|err| {
+        ctx.set_commitment_sentinel();
+        err
+    })?;
+    let tx_data = governance::VoteProposalBatchData::try_from_slice(&data[..])
+        .wrap_err("failed to decode VoteProposalBatchData")?;
+
+    debug_log!("apply_tx called to submit a batch of governance votes");
+
+    governance::vote_proposal_batch(ctx, tx_data)
+}
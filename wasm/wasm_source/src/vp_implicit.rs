@@ -36,8 +36,8 @@ impl<'a> From<&'a storage::Key> for KeyType<'a> {
     fn from(key: &'a storage::Key) -> KeyType<'a> {
         if let Some(address) = account::is_pks_key(key) {
             Self::Pk(address)
-        } else if let Some([_, owner]) =
-            token::storage_key::is_any_token_balance_key(key)
+        } else if let Some((_, owner)) =
+            token::storage_key::is_any_balance_key(key)
         {
             Self::TokenBalance { owner }
         } else if token::storage_key::is_any_minted_balance_key(key).is_some() {
@@ -149,7 +149,11 @@ fn validate_tx(
             KeyType::TokenMinter(minter) => minter != &addr || *valid_sig,
             KeyType::PoS => validate_pos_changes(ctx, &addr, key, &valid_sig)?,
             KeyType::PgfSteward(address) => address != &addr || *valid_sig,
-            KeyType::GovernanceVote(voter) => voter != &addr || *valid_sig,
+            KeyType::GovernanceVote(voter) => {
+                voter != &addr
+                    || *valid_sig
+                    || is_valid_signed_ballot(ctx, &tx_data, &addr, key)?
+            }
             KeyType::Masp | KeyType::Ibc => true,
             KeyType::Unknown => {
                 // Unknown changes require a valid signature
@@ -3,12 +3,29 @@
 //! This VP currently provides a signature verification against a public key for
 //! sending tokens (receiving tokens is permissive).
 //!
+//! An account can have multiple public keys, each with an optional weight (1 by
+//! default), in which case a signature is valid when the total weight of the
+//! keys that signed the tx reaches the account's threshold.
+//!
 //! It allows to bond, unbond and withdraw tokens to and from PoS system with a
 //! valid signature(s).
 //!
 //! For validator a tx to change a validator's commission rate or metadata
 //! requires a valid signature(s) only from the validator.
 //!
+//! An account can optionally set a policy with a daily spending limit for
+//! some tokens, in which case every debit of such a token must be recorded
+//! against the limit, and with session keys, which are secondary keys allowed
+//! to sign only txs with specific code hashes until an expiry height. A valid
+//! signature of a session key authorizes the same changes as a signature of
+//! the account's keys, except for the changes of the account's keys,
+//! threshold, VP and policy.
+//!
+//! An account can also set a VP update timelock, in which case an update of its
+//! VP only takes effect after the given number of epochs and can be cancelled
+//! in the meantime with a valid signature of the account's keys. The timelock
+//! can only be increased.
+//!
 //! Any other storage key changes are allowed only with a valid signature.
 
 use core::ops::Deref;
@@ -31,7 +48,13 @@ use proof_of_stake::storage_key::{
 };
 
 enum KeyType<'a> {
-    TokenBalance { owner: &'a Address },
+    TokenBalance {
+        token: &'a Address,
+        owner: &'a Address,
+    },
+    DailySpending(&'a Address),
+    Auth(&'a Address),
+    VpUpdateTimelock(&'a Address),
     TokenMinted,
     TokenMinter(&'a Address),
     PoS,
@@ -45,10 +68,23 @@ enum KeyType<'a> {
 
 impl<'a> From<&'a storage::Key> for KeyType<'a> {
     fn from(key: &'a storage::Key) -> KeyType<'a> {
-        if let Some([_, owner]) =
-            token::storage_key::is_any_token_balance_key(key)
+        if let Some((token, owner)) =
+            token::storage_key::is_any_balance_key(key)
+        {
+            Self::TokenBalance { token, owner }
+        } else if let Some((owner, _token)) =
+            account::is_daily_spending_key(key)
         {
-            Self::TokenBalance { owner }
+            Self::DailySpending(owner)
+        } else if let Some(owner) = account::is_pks_key(key)
+            .or_else(|| account::is_weights_key(key))
+            .or_else(|| account::is_threshold_key(key))
+            .or_else(|| account::is_policy_key(key))
+            .or_else(|| account::is_pending_vp_key(key))
+        {
+            Self::Auth(owner)
+        } else if let Some(owner) = account::is_vp_update_timelock_key(key) {
+            Self::VpUpdateTimelock(owner)
         } else if token::storage_key::is_any_minted_balance_key(key).is_some() {
             Self::TokenMinted
         } else if let Some(minter) = token::storage_key::is_any_minter_key(key)
@@ -92,22 +128,37 @@ fn validate_tx(
         verifiers
     );
 
-    let valid_sig = Lazy::new(|| {
+    let valid_account_sig = Lazy::new(|| {
         matches!(verify_signatures(ctx, &tx_data, &addr), Ok(true))
     });
+    let valid_sig = Lazy::new(|| {
+        *valid_account_sig
+            || matches!(
+                verify_session_signatures(ctx, &tx_data, &addr),
+                Ok(true)
+            )
+    });
 
     for key in keys_changed.iter() {
         let key_type: KeyType = key.into();
         let is_valid = match key_type {
-            KeyType::TokenBalance { owner, .. } => {
+            KeyType::TokenBalance { token, owner } => {
                 if owner == &addr {
                     let pre: token::Amount =
                         ctx.read_pre(key)?.unwrap_or_default();
                     let post: token::Amount =
                         ctx.read_post(key)?.unwrap_or_default();
                     let change = post.change() - pre.change();
-                    // debit has to signed, credit doesn't
-                    let valid = change.non_negative() || *valid_sig;
+                    // debit has to signed and within the spending limit,
+                    // credit doesn't
+                    let valid = change.non_negative()
+                        || (*valid_sig
+                            && is_within_spending_limit(
+                                ctx,
+                                &addr,
+                                token,
+                                pre.checked_sub(post).unwrap_or_default(),
+                            )?);
                     debug_log!(
                         "token key: {}, change: {:?}, valid_sig: {}, valid \
                          modification: {}",
@@ -129,15 +180,29 @@ fn validate_tx(
                     true
                 }
             }
+            KeyType::DailySpending(owner) => {
+                owner != &addr
+                    || (*valid_sig && is_valid_daily_spending_change(ctx, key)?)
+            }
+            KeyType::Auth(owner) => owner != &addr || *valid_account_sig,
+            KeyType::VpUpdateTimelock(owner) => {
+                owner != &addr
+                    || (*valid_account_sig
+                        && is_valid_vp_update_timelock_change(ctx, key)?)
+            }
             KeyType::TokenMinted => verifiers.contains(&address::MULTITOKEN),
             KeyType::TokenMinter(minter) => minter != &addr || *valid_sig,
             KeyType::PoS => validate_pos_changes(ctx, &addr, key, &valid_sig)?,
             KeyType::PgfSteward(address) => address != &addr || *valid_sig,
-            KeyType::GovernanceVote(voter) => voter != &addr || *valid_sig,
+            KeyType::GovernanceVote(voter) => {
+                voter != &addr
+                    || *valid_sig
+                    || is_valid_signed_ballot(ctx, &tx_data, &addr, key)?
+            }
             KeyType::Vp(owner) => {
                 let has_post: bool = ctx.has_key_post(key)?;
                 if owner == &addr {
-                    has_post && *valid_sig
+                    has_post && *valid_account_sig
                 } else {
                     true
                 }
@@ -157,6 +222,58 @@ fn validate_tx(
     accept()
 }
 
+/// Check that a debit of a token from the owner is recorded against the
+/// owner's daily spending limit for the token, if any, and that the limit is
+/// not exceeded
+fn is_within_spending_limit(
+    ctx: &Ctx,
+    owner: &Address,
+    token: &Address,
+    debit: token::Amount,
+) -> VpResult {
+    let limit = account::policy(&ctx.pre(), owner)?
+        .and_then(|policy| policy.spending_limits.get(token).copied());
+    let Some(limit) = limit else {
+        return accept();
+    };
+    let day = account::current_day(&ctx.pre())?;
+    let spent_pre = account::spent_on_day(&ctx.pre(), owner, token, day)?;
+    let spent_post = account::spent_on_day(&ctx.post(), owner, token, day)?;
+    let is_recorded = spent_pre
+        .checked_add(debit)
+        .map(|spent| spent <= spent_post)
+        .unwrap_or_default();
+    Ok(is_recorded && spent_post <= limit)
+}
+
+/// Check that the amount debited during a day can only grow during the
+/// current day
+fn is_valid_daily_spending_change(ctx: &Ctx, key: &storage::Key) -> VpResult {
+    let pre: Option<account::DailySpending> = ctx.read_pre(key)?;
+    let post: Option<account::DailySpending> = ctx.read_post(key)?;
+    let Some(post) = post else {
+        return reject();
+    };
+    if post.day != account::current_day(&ctx.pre())? {
+        return reject();
+    }
+    Ok(match pre {
+        Some(pre) if pre.day == post.day => post.amount >= pre.amount,
+        _ => true,
+    })
+}
+
+fn is_valid_vp_update_timelock_change(
+    ctx: &Ctx,
+    key: &storage::Key,
+) -> VpResult {
+    let pre: u64 = ctx.read_pre(key)?.unwrap_or_default();
+    let post: Option<u64> = ctx.read_post(key)?;
+    // The timelock cannot be removed or shortened, so that a compromised key
+    // cannot use it to swap the VP without a delay
+    Ok(matches!(post, Some(post) if post >= pre))
+}
+
 fn validate_pos_changes(
     ctx: &Ctx,
     owner: &Address,
@@ -336,7 +453,8 @@ mod tests {
     use namada::tx::data::{self, TxType};
     use namada::tx::{Code, Data, Signature};
     use namada::types::dec::Dec;
-    use namada::types::storage::Epoch;
+    use namada::types::hash::Hash;
+    use namada::types::storage::{BlockHeight, Epoch};
     use namada_test_utils::TestWasms;
     // Use this as `#[test]` annotation to enable logging
     use namada_tests::log::test;
@@ -548,6 +666,270 @@ mod tests {
         );
     }
 
+    /// Run a debit transfer from an account with the given policy, signed by
+    /// the given keypair (the account's own key when `signer` is `Some`, or
+    /// a session key otherwise) for a tx with the given code hash, and
+    /// return the result of the VP.
+    fn validate_debit_with_policy(
+        policy: account::AccountPolicy,
+        keypair: key::common::SecretKey,
+        signer: Option<Address>,
+        code_hash: Hash,
+        debit: token::Amount,
+    ) -> bool {
+        // Initialize a tx environment
+        let mut tx_env = TestTxEnv::default();
+
+        let vp_owner = address::testing::established_address_1();
+        let public_key = key::testing::keypair_1().ref_to();
+        let target = address::testing::established_address_2();
+        let token = address::nam();
+        let amount = token::Amount::from_uint(10_098_123, 0).unwrap();
+
+        // Spawn the accounts to be able to modify their storage
+        tx_env.spawn_accounts([&vp_owner, &target, &token]);
+        tx_env.init_account_storage(&vp_owner, vec![public_key], 1);
+        account::set_policy(&mut tx_env.wl_storage, &vp_owner, &policy)
+            .unwrap();
+        // The account setup is not part of the tx being validated
+        tx_env.wl_storage.commit_tx();
+        // The spending limits are tracked by the day of the block time and
+        // the session keys expire at a block height
+        tx_env.wl_storage.storage.block.height = BlockHeight(10);
+        tx_env.wl_storage.storage.header = Some(storage::Header {
+            hash: Hash::default(),
+            time: time::DateTimeUtc::now(),
+            next_validators_hash: Hash::default(),
+        });
+
+        // Credit the tokens to the VP owner before running the transaction to
+        // be able to transfer from it
+        tx_env.credit_tokens(&vp_owner, &token, amount);
+        // write the denomination of NAM into storage
+        token::write_denom(
+            &mut tx_env.wl_storage,
+            &token,
+            token::NATIVE_MAX_DECIMAL_PLACES.into(),
+        )
+        .unwrap();
+
+        let debit = token::DenominatedAmount::new(
+            debit,
+            token::NATIVE_MAX_DECIMAL_PLACES.into(),
+        );
+
+        // Initialize VP environment from a transaction
+        vp_host_env::init_from_tx(vp_owner.clone(), tx_env, |address| {
+            // Apply transfer in a transaction
+            tx_host_env::token::transfer(
+                tx::ctx(),
+                address,
+                &target,
+                &token,
+                debit,
+            )
+            .unwrap();
+        });
+
+        let mut vp_env = vp_host_env::take();
+        let mut tx = vp_env.tx.clone();
+        tx.set_data(Data::new(vec![]));
+        tx.set_code(Code::from_hash(code_hash, None));
+        tx.add_section(Section::Signature(Signature::new(
+            vec![tx.raw_header_hash()],
+            [(0, keypair)].into_iter().collect(),
+            signer,
+        )));
+        let signed_tx = tx.clone();
+        vp_env.tx = signed_tx.clone();
+        let keys_changed: BTreeSet<storage::Key> =
+            vp_env.all_touched_storage_keys();
+        let verifiers: BTreeSet<Address> = BTreeSet::default();
+        vp_host_env::set(vp_env);
+        validate_tx(&CTX, signed_tx, vp_owner, keys_changed, verifiers).unwrap()
+    }
+
+    /// Test that a signed debit transfer is accepted only within the daily
+    /// spending limit.
+    #[test]
+    fn test_debit_transfer_spending_limit() {
+        let owner = address::testing::established_address_1();
+        let limit = token::Amount::from_uint(1_000, 0).unwrap();
+        let policy = account::AccountPolicy {
+            spending_limits: [(address::nam(), limit)].into_iter().collect(),
+            session_keys: vec![],
+        };
+
+        assert!(validate_debit_with_policy(
+            policy.clone(),
+            key::testing::keypair_1(),
+            Some(owner.clone()),
+            Hash::default(),
+            limit,
+        ));
+        assert!(!validate_debit_with_policy(
+            policy,
+            key::testing::keypair_1(),
+            Some(owner),
+            Hash::default(),
+            limit + token::Amount::from_uint(1, 0).unwrap(),
+        ));
+    }
+
+    /// Test that a debit transfer signed by a session key is accepted only for
+    /// an allowed tx code before the key's expiry.
+    #[test]
+    fn test_debit_transfer_signed_by_session_key() {
+        let code_hash = Hash::sha256(b"tx_transfer");
+        let session_key = account::SessionKey {
+            public_key: key::testing::keypair_2().ref_to(),
+            allowed_code_hashes: vec![code_hash],
+            expiry: BlockHeight(100),
+        };
+        let policy = account::AccountPolicy {
+            spending_limits: Default::default(),
+            session_keys: vec![session_key.clone()],
+        };
+        let debit = token::Amount::from_uint(1_000, 0).unwrap();
+
+        assert!(validate_debit_with_policy(
+            policy.clone(),
+            key::testing::keypair_2(),
+            None,
+            code_hash,
+            debit,
+        ));
+        // A tx code that's not allowed
+        assert!(!validate_debit_with_policy(
+            policy,
+            key::testing::keypair_2(),
+            None,
+            Hash::sha256(b"tx_bond"),
+            debit,
+        ));
+        // An expired session key
+        let policy = account::AccountPolicy {
+            spending_limits: Default::default(),
+            session_keys: vec![account::SessionKey {
+                expiry: BlockHeight(9),
+                ..session_key
+            }],
+        };
+        assert!(!validate_debit_with_policy(
+            policy,
+            key::testing::keypair_2(),
+            None,
+            code_hash,
+            debit,
+        ));
+    }
+
+    /// Run a debit transfer from an account with weighted public keys, signed
+    /// by the given keypairs, and return the result of the VP.
+    fn validate_weighted_debit(
+        keypairs: Vec<key::common::SecretKey>,
+        weights: Vec<u8>,
+        threshold: u8,
+    ) -> bool {
+        // Initialize a tx environment
+        let mut tx_env = TestTxEnv::default();
+
+        let vp_owner = address::testing::established_address_1();
+        let public_keys = vec![
+            key::testing::keypair_1().ref_to(),
+            key::testing::keypair_2().ref_to(),
+            key::testing::keypair_3().ref_to(),
+        ];
+        let target = address::testing::established_address_2();
+        let token = address::nam();
+        let amount = token::Amount::from_uint(10_098_123, 0).unwrap();
+
+        // Spawn the accounts to be able to modify their storage
+        tx_env.spawn_accounts([&vp_owner, &target, &token]);
+        tx_env.init_account_storage(&vp_owner, public_keys.clone(), threshold);
+        account::set_weights(&mut tx_env.wl_storage, &vp_owner, &weights)
+            .unwrap();
+
+        // Credit the tokens to the VP owner before running the transaction to
+        // be able to transfer from it
+        tx_env.credit_tokens(&vp_owner, &token, amount);
+        // write the denomination of NAM into storage
+        token::write_denom(
+            &mut tx_env.wl_storage,
+            &token,
+            token::NATIVE_MAX_DECIMAL_PLACES.into(),
+        )
+        .unwrap();
+
+        let amount = token::DenominatedAmount::new(
+            amount,
+            token::NATIVE_MAX_DECIMAL_PLACES.into(),
+        );
+
+        // Initialize VP environment from a transaction
+        vp_host_env::init_from_tx(vp_owner.clone(), tx_env, |address| {
+            // Apply transfer in a transaction
+            tx_host_env::token::transfer(
+                tx::ctx(),
+                address,
+                &target,
+                &token,
+                amount,
+            )
+            .unwrap();
+        });
+
+        let pks_map =
+            AccountPublicKeysMap::from_iter(public_keys).with_weights(weights);
+
+        let mut vp_env = vp_host_env::take();
+        let mut tx = vp_env.tx.clone();
+        tx.set_data(Data::new(vec![]));
+        tx.set_code(Code::new(vec![], None));
+        tx.add_section(Section::Signature(Signature::new(
+            vec![tx.raw_header_hash()],
+            pks_map.index_secret_keys(keypairs),
+            Some(vp_owner.clone()),
+        )));
+        let signed_tx = tx.clone();
+        vp_env.tx = signed_tx.clone();
+        let keys_changed: BTreeSet<storage::Key> =
+            vp_env.all_touched_storage_keys();
+        let verifiers: BTreeSet<Address> = BTreeSet::default();
+        vp_host_env::set(vp_env);
+        validate_tx(&CTX, signed_tx, vp_owner, keys_changed, verifiers).unwrap()
+    }
+
+    /// Test that a debit transfer is accepted only when the total weight of
+    /// the signing keys reaches the threshold.
+    #[test]
+    fn test_weighted_multisig_debit_transfer() {
+        let weights = vec![2, 1, 1];
+        let threshold = 2;
+
+        assert!(validate_weighted_debit(
+            vec![key::testing::keypair_1()],
+            weights.clone(),
+            threshold,
+        ));
+        assert!(!validate_weighted_debit(
+            vec![key::testing::keypair_2()],
+            weights.clone(),
+            threshold,
+        ));
+        assert!(validate_weighted_debit(
+            vec![key::testing::keypair_2(), key::testing::keypair_3()],
+            weights.clone(),
+            threshold,
+        ));
+        // Without weights, every key counts for 1
+        assert!(!validate_weighted_debit(
+            vec![key::testing::keypair_1()],
+            vec![],
+            threshold,
+        ));
+    }
+
     /// Test that a non-validator PoS action that must be authorized is rejected
     /// without a valid signature.
     #[test]
@@ -786,6 +1168,7 @@ mod tests {
                     Some("website".to_owned()),
                     Some("discord".to_owned()),
                     Some("avatar".to_owned()),
+                    Some("name".to_owned()),
                     Some(Dec::new(6, 2).unwrap()),
                 )
                 .unwrap();
@@ -1072,6 +1455,7 @@ mod tests {
                     Some("website".to_owned()),
                     Some("discord".to_owned()),
                     Some("avatar".to_owned()),
+                    Some("name".to_owned()),
                     Some(Dec::new(6, 2).unwrap()),
                 )
                 .unwrap();
@@ -1346,6 +1730,59 @@ mod tests {
         );
     }
 
+    /// Run a tx signed by the account's key that changes the account's VP
+    /// update timelock from `pre` to `post` and return the result of the VP.
+    fn validate_vp_update_timelock_change(pre: u64, post: u64) -> bool {
+        // Initialize a tx environment
+        let mut tx_env = TestTxEnv::default();
+
+        let vp_owner = address::testing::established_address_1();
+        let keypair = key::testing::keypair_1();
+        let public_key = keypair.ref_to();
+
+        // Spawn the accounts to be able to modify their storage
+        tx_env.spawn_accounts([&vp_owner]);
+        tx_env.init_account_storage(&vp_owner, vec![public_key.clone()], 1);
+        account::set_vp_update_timelock(&mut tx_env.wl_storage, &vp_owner, pre)
+            .unwrap();
+        // The account setup is not part of the tx being validated
+        tx_env.wl_storage.commit_tx();
+
+        // Initialize VP environment from a transaction
+        vp_host_env::init_from_tx(vp_owner.clone(), tx_env, |address| {
+            // Update the timelock in a transaction
+            account::set_vp_update_timelock(tx::ctx(), address, post).unwrap();
+        });
+
+        let pks_map = AccountPublicKeysMap::from_iter(vec![public_key]);
+
+        let mut vp_env = vp_host_env::take();
+        let mut tx = vp_env.tx.clone();
+        tx.set_data(Data::new(vec![]));
+        tx.set_code(Code::new(vec![], None));
+        tx.add_section(Section::Signature(Signature::new(
+            vec![tx.raw_header_hash()],
+            pks_map.index_secret_keys(vec![keypair]),
+            None,
+        )));
+        let signed_tx = tx.clone();
+        vp_env.tx = signed_tx.clone();
+        let keys_changed: BTreeSet<storage::Key> =
+            vp_env.all_touched_storage_keys();
+        let verifiers: BTreeSet<Address> = BTreeSet::default();
+        vp_host_env::set(vp_env);
+        validate_tx(&CTX, signed_tx, vp_owner, keys_changed, verifiers).unwrap()
+    }
+
+    /// Test that a VP update timelock can be increased, but not shortened.
+    #[test]
+    fn test_vp_update_timelock_change() {
+        assert!(validate_vp_update_timelock_change(0, 2));
+        assert!(validate_vp_update_timelock_change(2, 5));
+        assert!(!validate_vp_update_timelock_change(5, 2));
+        assert!(!validate_vp_update_timelock_change(5, 0));
+    }
+
     /// Test that a validity predicate update is accepted if allowed
     #[test]
     fn test_signed_vp_update_allowed_accepted() {